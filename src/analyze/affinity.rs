@@ -0,0 +1,381 @@
+//! Session affinity and response caching
+//!
+//! An upstream with session affinity (`ip_hash`, `hash $cookie_... `,
+//! `sticky`) pins each client to one server, usually because the server
+//! holds the client's session. A location that also caches the responses
+//! (`proxy_cache`, `fastcgi_cache`) serves them to every client without
+//! reaching the pinned server: one client's session-specific page is
+//! returned to the next, and a cached `Set-Cookie` from `sticky cookie`
+//! pins everyone to the same server.
+//!
+//! [`affinity_audit`] lists the upstreams with session affinity and the
+//! locations using them with their effective cache settings, and reports:
+//!
+//! - `affinity_cache`: a location caches responses from an upstream with
+//!   session affinity, and the cache key does not include the variable the
+//!   affinity is derived from
+
+use super::keepalive::target_upstream;
+use crate::ast::{Config, Directive, Value};
+use crate::extract;
+use crate::lint::{walk, Finding, Severity};
+use crate::types::{Server, SessionAffinity, Upstream};
+
+/// Cache key nginx uses when `proxy_cache_key` is not set
+const DEFAULT_PROXY_CACHE_KEY: &str = "$scheme$proxy_host$request_uri";
+
+/// How one location uses an upstream with session affinity
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AffinityUse {
+    /// First `server_name` of the enclosing server (`_` if none)
+    pub server_name: String,
+
+    /// Location path/pattern
+    pub location: String,
+
+    /// Line of the `proxy_pass` or `fastcgi_pass` directive
+    pub line: usize,
+
+    /// Upstream name
+    pub upstream: String,
+
+    /// Session affinity of the upstream
+    pub affinity: SessionAffinity,
+
+    /// Effective cache zone (`proxy_cache` or `fastcgi_cache`), `None`
+    /// when responses are not cached
+    pub cache_zone: Option<String>,
+
+    /// Effective cache key, when responses are cached
+    pub cache_key: Option<String>,
+
+    /// Whether responses with `Set-Cookie` are cached
+    /// (`proxy_ignore_headers Set-Cookie`)
+    pub caches_set_cookie: bool,
+}
+
+impl AffinityUse {
+    /// Whether cached responses are shared between clients pinned to
+    /// different servers: the cache key does not include the affinity
+    /// variable
+    #[must_use]
+    pub fn shares_cache(&self) -> bool {
+        let Some(key) = &self.cache_key else {
+            return false;
+        };
+        self.affinity
+            .variable()
+            .map_or(true, |variable| !key.contains(&variable))
+    }
+}
+
+/// Result of [`affinity_audit`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AffinityReport {
+    /// `http` upstreams with session affinity
+    pub upstreams: Vec<Upstream>,
+
+    /// Every location proxying to one of them
+    pub uses: Vec<AffinityUse>,
+
+    /// Locations caching responses of a pinned upstream (`affinity_cache`)
+    pub findings: Vec<Finding>,
+}
+
+/// Find the upstreams with session affinity and check that the locations
+/// using them do not cache their responses.
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::{analyze::affinity_audit, parse};
+///
+/// let config = parse(r"
+/// http {
+///     proxy_cache_path /var/cache/nginx keys_zone=pages:10m;
+///     upstream app {
+///         hash $cookie_sessionid consistent;
+///         server 10.0.0.1:8080;
+///         server 10.0.0.2:8080;
+///     }
+///     server {
+///         location / { proxy_pass http://app; proxy_cache pages; }
+///         location /api/ { proxy_pass http://app; }
+///     }
+/// }
+/// ")?;
+/// let report = affinity_audit(&config);
+///
+/// assert_eq!(report.upstreams[0].name, "app");
+/// assert_eq!(report.uses[0].cache_zone.as_deref(), Some("pages"));
+/// assert_eq!(report.uses[1].cache_zone, None);
+/// assert_eq!(report.findings[0].rule, "affinity_cache");
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+#[must_use]
+pub fn affinity_audit(config: &Config) -> AffinityReport {
+    let mut report = AffinityReport {
+        upstreams: extract::upstreams(config)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|u| !u.stream && u.affinity.is_some())
+            .collect(),
+        ..AffinityReport::default()
+    };
+
+    walk(config, &mut |directive, parents| {
+        let Some(name) = target_upstream(directive) else {
+            return;
+        };
+        let Some(upstream) = report.upstreams.iter().find(|u| u.name == name) else {
+            return;
+        };
+        let Some(affinity) = upstream.affinity.clone() else {
+            return;
+        };
+
+        let usage = affinity_use(directive, parents, &upstream.name, affinity);
+        if usage.shares_cache() {
+            report.findings.push(check_use(directive, &usage));
+        }
+        report.uses.push(usage);
+    });
+
+    report
+}
+
+fn affinity_use(
+    directive: &Directive,
+    parents: &[&Directive],
+    upstream: &str,
+    affinity: SessionAffinity,
+) -> AffinityUse {
+    // Innermost block first
+    let chain: Vec<&Directive> = parents.iter().rev().copied().collect();
+    let effective = |name: &str| -> Option<Vec<String>> {
+        chain
+            .iter()
+            .find_map(|block| block.find_children(name).last().copied())
+            .map(|d| d.args().iter().map(Value::unquoted).collect())
+    };
+    let prefix = if directive.name() == "fastcgi_pass" {
+        "fastcgi"
+    } else {
+        "proxy"
+    };
+
+    let cache_zone = effective(&format!("{prefix}_cache"))
+        .and_then(|args| args.into_iter().next())
+        .filter(|zone| zone != "off");
+    let cache_key = cache_zone.as_ref().map(|_| {
+        effective(&format!("{prefix}_cache_key")).map_or_else(
+            // fastcgi_cache_key has no default; nginx refuses to start
+            // without one
+            || DEFAULT_PROXY_CACHE_KEY.to_string(),
+            |args| args.join(" "),
+        )
+    });
+    let caches_set_cookie = effective(&format!("{prefix}_ignore_headers"))
+        .is_some_and(|headers| headers.iter().any(|h| h.eq_ignore_ascii_case("set-cookie")));
+
+    AffinityUse {
+        server_name: Server::primary_name_of(chain.iter().find(|p| p.name() == "server").copied()),
+        location: chain
+            .iter()
+            .find(|p| p.name() == "location")
+            .map(|l| l.args_as_strings().join(" "))
+            .unwrap_or_default(),
+        line: directive.span.line,
+        upstream: upstream.to_string(),
+        affinity,
+        cache_zone,
+        cache_key,
+        caches_set_cookie,
+    }
+}
+
+fn check_use(directive: &Directive, usage: &AffinityUse) -> Finding {
+    let mut message = format!(
+        "location \"{}\" in server \"{}\" caches responses (zone {}) from upstream \"{}\", \
+         which pins clients with {}; cached responses reach every client without the pinned \
+         server",
+        usage.location,
+        usage.server_name,
+        usage.cache_zone.as_deref().unwrap_or_default(),
+        usage.upstream,
+        usage.affinity
+    );
+    if usage.caches_set_cookie {
+        message.push_str(", including Set-Cookie responses");
+    }
+    let help = match usage.affinity.variable() {
+        Some(variable) => format!(
+            "Turn caching off for this location, or add {variable} to the cache key so each \
+             client gets its own entries"
+        ),
+        None => "Turn caching off for this location".to_string(),
+    };
+    Finding::new("affinity_cache", Severity::Warning, message)
+        .at(directive)
+        .with_help(help)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+    use crate::types::AffinityKind;
+
+    #[test]
+    fn test_affinity_audit() {
+        let config = parse(
+            r"
+http {
+    upstream app { sticky cookie srv_id expires=1h; server 10.0.0.1:8080; }
+    upstream legacy { ip_hash; server 10.0.0.9:80; }
+    upstream php { hash $cookie_PHPSESSID; server unix:/run/php.sock; }
+    upstream static { hash $request_uri consistent; server 10.0.0.5:80; }
+    proxy_cache pages;
+    server {
+        server_name example.com;
+        location / { proxy_pass http://app; }
+        location /account/ {
+            proxy_cache_key $scheme$host$request_uri$cookie_srv_id;
+            proxy_pass http://app;
+        }
+        location /old/ {
+            proxy_ignore_headers Set-Cookie;
+            proxy_pass http://legacy;
+        }
+        location ~ \.php$ { fastcgi_pass php; }
+        location /assets/ { proxy_pass http://static; }
+        location /live/ { proxy_cache off; proxy_pass http://app; }
+    }
+}
+",
+        )
+        .unwrap();
+        let report = affinity_audit(&config);
+
+        let kinds: Vec<_> = report
+            .upstreams
+            .iter()
+            .map(|u| u.affinity.as_ref().unwrap().kind)
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                AffinityKind::StickyCookie,
+                AffinityKind::IpHash,
+                AffinityKind::Hash
+            ]
+        );
+        let uses: Vec<_> = report
+            .uses
+            .iter()
+            .map(|u| {
+                (
+                    u.location.as_str(),
+                    u.cache_zone.as_deref(),
+                    u.shares_cache(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            uses,
+            vec![
+                ("/", Some("pages"), true),
+                // The cache key includes the route cookie
+                ("/account/", Some("pages"), false),
+                ("/old/", Some("pages"), true),
+                ("~ \\.php$", None, false),
+                ("/live/", None, false),
+            ]
+        );
+
+        let lines: Vec<_> = report.findings.iter().map(Finding::line).collect();
+        assert_eq!(lines, vec![Some(10), Some(17)]);
+        assert!(report.findings[0]
+            .message
+            .contains("which pins clients with sticky cookie srv_id"));
+        assert!(report.findings[1]
+            .message
+            .ends_with("including Set-Cookie responses"));
+    }
+
+    #[test]
+    fn test_affinity_audit_edge_cases() {
+        let config = parse(
+            r"
+stream {
+    upstream db { hash $remote_addr; server 10.0.0.3:5432; }
+    server { listen 5432; proxy_pass db; }
+}
+http {
+    upstream app { sticky cookie; server 10.0.0.1:8080; }
+    upstream php { ip_hash; server unix:/run/php.sock; }
+    server {
+        proxy_cache pages;
+        location / { proxy_pass http://app/; }
+        location /dynamic/ { proxy_pass http://$backend; }
+        location /other/ { proxy_pass http://db; }
+        location ~ \.php$ {
+            fastcgi_cache php;
+            fastcgi_cache_key $scheme$request_uri;
+            fastcgi_ignore_headers Set-Cookie Expires;
+            fastcgi_pass php;
+        }
+    }
+}
+",
+        )
+        .unwrap();
+        let report = affinity_audit(&config);
+
+        // Stream upstreams and targets that are not pinned upstreams are
+        // ignored
+        let names: Vec<_> = report.upstreams.iter().map(|u| u.name.as_str()).collect();
+        assert_eq!(names, vec!["app", "php"]);
+        let locations: Vec<_> = report.uses.iter().map(|u| u.location.as_str()).collect();
+        assert_eq!(locations, vec!["/", "~ \\.php$"]);
+
+        // No cookie name: no variable to add to the cache key
+        let root = &report.uses[0];
+        assert_eq!(root.server_name, "_");
+        assert_eq!(root.cache_key.as_deref(), Some(DEFAULT_PROXY_CACHE_KEY));
+        assert!(root.shares_cache());
+        assert_eq!(
+            report.findings[0].help.as_deref(),
+            Some("Turn caching off for this location")
+        );
+
+        // The FastCGI directives apply to `fastcgi_pass`
+        let php = &report.uses[1];
+        assert_eq!(php.cache_zone.as_deref(), Some("php"));
+        assert_eq!(php.cache_key.as_deref(), Some("$scheme$request_uri"));
+        assert!(php.caches_set_cookie);
+        assert!(report.findings[1]
+            .help
+            .as_deref()
+            .unwrap()
+            .contains("add $remote_addr to the cache key"));
+        assert_eq!(report.findings.len(), 2);
+    }
+
+    #[test]
+    fn test_affinity_audit_without_affinity() {
+        let config = parse(
+            r"
+http {
+    upstream app { least_conn; server 10.0.0.1:8080; }
+    server { proxy_cache pages; location / { proxy_pass http://app; } }
+}
+",
+        )
+        .unwrap();
+        assert_eq!(affinity_audit(&config), AffinityReport::default());
+        assert_eq!(affinity_audit(&Config::new()), AffinityReport::default());
+    }
+}
@@ -0,0 +1,583 @@
+//! Subrequest authentication (`auth_request`)
+//!
+//! `auth_request /auth;` sends a subrequest to `/auth` before serving each
+//! request and lets it through only on a 2xx answer. The URI is resolved
+//! like any other: the location handling it must exist, should be
+//! `internal` so clients cannot call the authentication endpoint directly,
+//! and must not require authentication itself. `auth_request` is
+//! inherited, so a server-wide `auth_request` also applies to the auth
+//! location unless it turns it `off`. The same holds for the page an
+//! `error_page 401` sends unauthenticated clients to.
+//!
+//! [`auth_request_audit`] lists every `auth_request` with the location
+//! answering it, the `auth_request_set` variables and the `error_page`
+//! handling of 401 and 403, and reports:
+//!
+//! - `auth_request_missing`: no location handles the auth URI
+//! - `auth_request_internal`: the auth location is not `internal`
+//! - `auth_request_loop`: the auth location requires authentication itself
+//! - `auth_request_error_page`: the `error_page` for 401 or 403 sends
+//!   clients to a page that requires authentication
+
+use crate::ast::{Config, ConfigIndex, ConfigPath, Directive, Value};
+use crate::lint::{walk, Finding, Severity};
+use crate::route::find_location;
+use crate::types::Server;
+
+/// An `error_page` for a status the auth subrequest produces
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AuthErrorPage {
+    /// 401 or 403
+    pub status: u16,
+
+    /// Target URI, named location or URL
+    pub target: String,
+
+    /// Line of the `error_page` directive
+    pub line: usize,
+}
+
+/// One `auth_request` directive, resolved in one server
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AuthRequest {
+    /// First `server_name` of the server (`_` if none)
+    pub server_name: String,
+
+    /// Path of the `auth_request` directive, in `http`, a `server` or a
+    /// `location`
+    pub path: ConfigPath,
+
+    /// Line of the `auth_request` directive
+    pub line: usize,
+
+    /// Auth subrequest URI
+    pub uri: String,
+
+    /// Pattern of the location handling the URI, `None` when none does or
+    /// the URI contains variables
+    pub auth_location: Option<String>,
+
+    /// Line of that location
+    pub auth_location_line: Option<usize>,
+
+    /// Whether the auth location is `internal`
+    pub internal: bool,
+
+    /// Effective `auth_request` of the auth location, if it requires
+    /// authentication itself
+    pub auth_location_requires: Option<String>,
+
+    /// Variables set from the subrequest (`auth_request_set`)
+    pub variables: Vec<String>,
+
+    /// Effective `error_page`s for 401 and 403
+    pub error_pages: Vec<AuthErrorPage>,
+}
+
+/// Result of [`auth_request_audit`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AuthRequestReport {
+    /// Every `auth_request`, per server it applies to
+    pub requests: Vec<AuthRequest>,
+
+    /// Problems found (`auth_request_missing`, `auth_request_internal`,
+    /// `auth_request_loop`, `auth_request_error_page`)
+    pub findings: Vec<Finding>,
+}
+
+/// Resolve every `auth_request` to its auth location and check the
+/// subrequest flow.
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::{analyze::auth_request_audit, parse};
+///
+/// let config = parse(r"
+/// http {
+///     server {
+///         server_name example.com;
+///         auth_request /auth;
+///         location / { root /var/www; }
+///         location = /auth { proxy_pass http://127.0.0.1:9000; }
+///     }
+/// }
+/// ")?;
+/// let report = auth_request_audit(&config);
+///
+/// assert_eq!(report.requests[0].auth_location.as_deref(), Some("= /auth"));
+///
+/// let rules: Vec<_> = report.findings.iter().map(|f| f.rule.as_str()).collect();
+/// assert_eq!(rules, vec!["auth_request_internal", "auth_request_loop"]);
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+#[must_use]
+pub fn auth_request_audit(config: &Config) -> AuthRequestReport {
+    let index = config.index();
+    let mut report = AuthRequestReport::default();
+
+    walk(config, &mut |directive, parents| {
+        if directive.name() != "auth_request" {
+            return;
+        }
+        let Some(uri) = directive.args().first().map(Value::unquoted) else {
+            return;
+        };
+        if uri == "off" {
+            return;
+        }
+        let Some(block) = parents.last().copied() else {
+            return;
+        };
+
+        // An http-level auth_request applies to every server
+        let servers: Vec<(&Directive, Vec<&Directive>)> =
+            match parents.iter().rposition(|p| p.name() == "server") {
+                Some(i) => vec![(parents[i], parents[..i].to_vec())],
+                None if block.name() == "http" => block
+                    .find_children("server")
+                    .into_iter()
+                    .map(|server| (server, parents.to_vec()))
+                    .collect(),
+                None => Vec::new(),
+            };
+
+        let path = index
+            .id_of(directive)
+            .map(|id| index.config_path(id))
+            .unwrap_or_default();
+        for (server, outer) in servers {
+            let request = auth_request(directive, &path, block, parents, server, &outer, &uri);
+            report.findings.extend(check_request(
+                &index, directive, block, &request, server, &outer,
+            ));
+            report.requests.push(request);
+        }
+    });
+
+    report
+}
+
+fn auth_request(
+    directive: &Directive,
+    path: &ConfigPath,
+    block: &Directive,
+    parents: &[&Directive],
+    server: &Directive,
+    outer: &[&Directive],
+    uri: &str,
+) -> AuthRequest {
+    let auth_chain = if uri.contains('$') {
+        Vec::new()
+    } else {
+        find_location(server, uri)
+    };
+    let auth_location = auth_chain.last().copied();
+
+    // error_page is inherited only by blocks that define none
+    let error_pages = parents
+        .iter()
+        .rev()
+        .map(|b| b.find_children("error_page"))
+        .find(|pages| !pages.is_empty())
+        .unwrap_or_default()
+        .into_iter()
+        .flat_map(|page| {
+            let args: Vec<String> = page.args().iter().map(Value::unquoted).collect();
+            let target = args.last().cloned().unwrap_or_default();
+            args.iter()
+                .filter_map(|arg| arg.parse::<u16>().ok())
+                .filter(|status| matches!(status, 401 | 403))
+                .map(|status| AuthErrorPage {
+                    status,
+                    target: target.clone(),
+                    line: page.span.line,
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect();
+
+    AuthRequest {
+        server_name: Server::primary_name_of(server),
+        path: path.clone(),
+        line: directive.span.line,
+        uri: uri.to_string(),
+        auth_location: auth_location.map(|l| l.args_as_strings().join(" ")),
+        auth_location_line: auth_location.map(|l| l.span.line),
+        internal: auth_location.is_some_and(|l| !l.find_children("internal").is_empty()),
+        auth_location_requires: auth_location
+            .and_then(|_| required_auth(&auth_chain, server, outer)),
+        variables: block
+            .find_children("auth_request_set")
+            .iter()
+            .filter_map(|d| d.args().first().map(Value::unquoted))
+            .collect(),
+        error_pages,
+    }
+}
+
+/// Effective `auth_request` of a location chain (outermost first) in
+/// `server`, `None` when it is off or not set
+fn required_auth(
+    locations: &[&Directive],
+    server: &Directive,
+    outer: &[&Directive],
+) -> Option<String> {
+    locations
+        .iter()
+        .rev()
+        .copied()
+        .chain(std::iter::once(server))
+        .chain(outer.iter().rev().copied())
+        .find_map(|block| block.find_children("auth_request").last().copied())
+        .and_then(|d| d.args().first().map(Value::unquoted))
+        .filter(|uri| uri != "off")
+}
+
+/// The block as written: `http`, `server` or `location <pattern>`
+fn label(block: &Directive) -> String {
+    match block.name() {
+        "location" => format!("location {}", block.args_as_strings().join(" ")),
+        name => name.to_string(),
+    }
+}
+
+fn check_request(
+    index: &ConfigIndex<'_>,
+    directive: &Directive,
+    block: &Directive,
+    request: &AuthRequest,
+    server: &Directive,
+    outer: &[&Directive],
+) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let at = if block.name() == "server" {
+        format!(
+            "auth_request {} in server \"{}\"",
+            request.uri, request.server_name
+        )
+    } else {
+        format!(
+            "auth_request {} in {} of server \"{}\"",
+            request.uri,
+            label(block),
+            request.server_name
+        )
+    };
+
+    let Some(location) = &request.auth_location else {
+        if !request.uri.contains('$') {
+            findings.push(
+                Finding::new(
+                    "auth_request_missing",
+                    Severity::Error,
+                    format!("{at}: no location handles {}", request.uri),
+                )
+                .at_indexed(index, directive)
+                .with_help(format!(
+                    "Add \"location = {} {{ internal; ... }}\" answering the subrequest",
+                    request.uri
+                )),
+            );
+        }
+        return findings;
+    };
+
+    if !request.internal {
+        findings.push(
+            Finding::new(
+                "auth_request_internal",
+                Severity::Warning,
+                format!(
+                    "{at}: the auth location {location} (line {}) is not internal, so clients \
+                     can call the authentication endpoint directly",
+                    request.auth_location_line.unwrap_or_default()
+                ),
+            )
+            .at_indexed(index, directive)
+            .with_help("Add \"internal;\" to the auth location"),
+        );
+    }
+
+    if let Some(required) = &request.auth_location_requires {
+        findings.push(
+            Finding::new(
+                "auth_request_loop",
+                Severity::Error,
+                format!(
+                    "{at}: the auth location {location} (line {}) requires auth_request \
+                     {required} itself, so the subrequest never succeeds",
+                    request.auth_location_line.unwrap_or_default()
+                ),
+            )
+            .at_indexed(index, directive)
+            .with_help("Add \"auth_request off;\" to the auth location"),
+        );
+    }
+
+    for page in &request.error_pages {
+        let chain = if let Some(name) = page.target.strip_prefix('@') {
+            server
+                .find_children("location")
+                .into_iter()
+                .filter(|l| l.first_arg().as_deref() == Some(&format!("@{name}")))
+                .collect()
+        } else if page.target.starts_with('/') {
+            let path = page.target.split('?').next().unwrap_or_default();
+            find_location(server, path)
+        } else {
+            // A redirect to a URL leaves the server
+            continue;
+        };
+        if chain.is_empty() || required_auth(&chain, server, outer).is_none() {
+            continue;
+        }
+        findings.push(
+            Finding::new(
+                "auth_request_error_page",
+                Severity::Warning,
+                format!(
+                    "{at}: error_page {} sends clients to {} (line {}), which requires \
+                     authentication itself",
+                    page.status, page.target, page.line
+                ),
+            )
+            .at_indexed(index, directive)
+            .with_help(format!(
+                "Add \"auth_request off;\" to the location serving {}",
+                page.target
+            )),
+        );
+    }
+
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_auth_request_audit() {
+        let config = parse(
+            r"
+http {
+    server {
+        server_name app.example.com;
+        auth_request /_auth;
+        auth_request_set $user $upstream_http_x_user;
+        error_page 401 = @login;
+        error_page 403 /denied.html;
+        location / { proxy_pass http://app; }
+        location = /_auth {
+            internal;
+            auth_request off;
+            proxy_pass http://auth;
+        }
+        location @login { return 302 /login; }
+        location = /denied.html { auth_request off; }
+    }
+    server {
+        server_name admin.example.com;
+        location /admin/ { auth_request /verify; }
+        location /api/ { auth_request /check$uri; }
+    }
+}
+",
+        )
+        .unwrap();
+        let report = auth_request_audit(&config);
+
+        let requests: Vec<_> = report
+            .requests
+            .iter()
+            .map(|r| {
+                (
+                    r.server_name.as_str(),
+                    r.path.to_string(),
+                    r.auth_location.as_deref(),
+                    r.internal,
+                )
+            })
+            .collect();
+        assert_eq!(
+            requests,
+            vec![
+                (
+                    "app.example.com",
+                    "http/server/auth_request".to_string(),
+                    Some("= /_auth"),
+                    true
+                ),
+                (
+                    "admin.example.com",
+                    "http/server[2]/location/auth_request".to_string(),
+                    None,
+                    false
+                ),
+                (
+                    "admin.example.com",
+                    "http/server[2]/location[2]/auth_request".to_string(),
+                    None,
+                    false
+                ),
+            ]
+        );
+        assert_eq!(report.requests[0].variables, ["$user"]);
+        let pages: Vec<_> = report.requests[0]
+            .error_pages
+            .iter()
+            .map(|p| (p.status, p.target.as_str()))
+            .collect();
+        assert_eq!(pages, vec![(401, "@login"), (403, "/denied.html")]);
+
+        // The @login location inherits the server's auth_request; variables
+        // in the URI are not resolved
+        let findings: Vec<_> = report
+            .findings
+            .iter()
+            .map(|f| (f.rule.as_str(), f.line()))
+            .collect();
+        assert_eq!(
+            findings,
+            vec![
+                ("auth_request_error_page", Some(5)),
+                ("auth_request_missing", Some(20)),
+            ]
+        );
+        assert_eq!(
+            report.findings[1].path.as_ref().unwrap().to_string(),
+            "http/server[2]/location/auth_request"
+        );
+    }
+
+    #[test]
+    fn test_http_level_auth_request() {
+        let config = parse(
+            r"
+http {
+    auth_request /auth;
+    server {
+        server_name a.example.com;
+        location / { auth_request off; }
+        location = /auth { internal; auth_request off; proxy_pass http://auth; }
+    }
+    server {
+        server_name b.example.com;
+        location /auth { internal; proxy_pass http://auth; }
+    }
+    server {
+        server_name c.example.com;
+        auth_request;
+    }
+}
+",
+        )
+        .unwrap();
+        let report = auth_request_audit(&config);
+
+        // One request per server; `off` and a missing URI are not requests
+        let requests: Vec<_> = report
+            .requests
+            .iter()
+            .map(|r| {
+                (
+                    r.server_name.as_str(),
+                    r.path.to_string(),
+                    r.auth_location_requires.as_deref(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            requests,
+            vec![
+                ("a.example.com", "http/auth_request".to_string(), None),
+                (
+                    "b.example.com",
+                    "http/auth_request".to_string(),
+                    Some("/auth")
+                ),
+                ("c.example.com", "http/auth_request".to_string(), None),
+            ]
+        );
+
+        let findings: Vec<_> = report
+            .findings
+            .iter()
+            .map(|f| (f.rule.as_str(), f.line()))
+            .collect();
+        assert_eq!(
+            findings,
+            vec![
+                ("auth_request_loop", Some(3)),
+                ("auth_request_missing", Some(3)),
+            ]
+        );
+        assert!(report.findings[0]
+            .message
+            .contains("server \"b.example.com\""));
+    }
+
+    #[test]
+    fn test_auth_error_pages() {
+        let config = parse(
+            r"
+http {
+    server {
+        auth_request /auth;
+        error_page 401 403 /login;
+        location = /auth { internal; auth_request off; }
+        location /login { }
+        location /admin/ {
+            error_page 401 https://sso.example.com/;
+            auth_request /auth;
+        }
+    }
+}
+",
+        )
+        .unwrap();
+        let report = auth_request_audit(&config);
+
+        let pages: Vec<_> = report
+            .requests
+            .iter()
+            .map(|r| {
+                r.error_pages
+                    .iter()
+                    .map(|p| (p.status, p.target.as_str(), p.line))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        assert_eq!(
+            pages,
+            vec![
+                vec![(401, "/login", 5), (403, "/login", 5)],
+                // The location's own error_page replaces the server's
+                vec![(401, "https://sso.example.com/", 9)],
+            ]
+        );
+
+        // /login inherits the server's auth_request for both statuses; a
+        // redirect to a URL is not followed
+        let findings: Vec<_> = report
+            .findings
+            .iter()
+            .map(|f| (f.rule.as_str(), f.line()))
+            .collect();
+        assert_eq!(
+            findings,
+            vec![
+                ("auth_request_error_page", Some(4)),
+                ("auth_request_error_page", Some(4)),
+            ]
+        );
+        assert!(report.findings[1]
+            .message
+            .contains("error_page 403 sends clients to /login (line 5)"));
+    }
+}
@@ -0,0 +1,437 @@
+//! Bot and user-agent blocking
+//!
+//! nginx has no bot filter of its own; configurations block crawlers and
+//! scripts by matching `$http_user_agent`, either directly in an `if`
+//! (`if ($http_user_agent ~* (curl|wget)) { return 403; }`) or through a
+//! `map` whose variable an `if` tests (`if ($bad_bot) { return 444; }`).
+//! `if` is only valid in `server` and `location`, so each virtual host
+//! needs its own copy, and a server left out serves every bot.
+//!
+//! [`bot_blocking`] lists, per server, the user-agent checks that reject
+//! requests and the mechanism each one uses, and reports:
+//!
+//! - `bot_filter_gap`: a server without any bot filtering while other
+//!   servers have some
+//! - `bot_map_unused`: a `map` on the user agent whose variable nothing
+//!   references, so it blocks nothing
+
+use crate::ast::{Config, Directive, Value};
+use crate::extract;
+use crate::lint::{walk, Finding, Severity};
+use crate::types::{IfCondition, Map, Server};
+
+/// How a server recognises bots
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum BotMechanism {
+    /// An `if` matching `$http_user_agent` itself
+    If,
+    /// An `if` testing the variable of a `map` on the user agent
+    Map,
+}
+
+impl std::fmt::Display for BotMechanism {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::If => "if",
+            Self::Map => "map",
+        })
+    }
+}
+
+/// One user-agent check that rejects requests
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BotRule {
+    /// How the check recognises bots
+    pub mechanism: BotMechanism,
+
+    /// Line of the `if` directive
+    pub line: usize,
+
+    /// Location pattern of the `if`, `None` when it applies to the whole
+    /// server
+    pub location: Option<String>,
+
+    /// Variable the `map` defines, for [`BotMechanism::Map`]
+    pub map: Option<String>,
+
+    /// User-agent patterns the check rejects, as written; empty when they
+    /// cannot be told (maps of maps)
+    pub patterns: Vec<String>,
+
+    /// What the `if` does: `return 403`, `deny all`
+    pub action: String,
+}
+
+/// Bot filtering of one server
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BotFilter {
+    /// First `server_name` of the server (`_` if none)
+    pub server_name: String,
+
+    /// Line of the `server` block
+    pub line: usize,
+
+    /// User-agent checks, in file order
+    pub rules: Vec<BotRule>,
+}
+
+impl BotFilter {
+    /// Whether the server filters bots anywhere
+    #[must_use]
+    pub fn is_filtered(&self) -> bool {
+        !self.rules.is_empty()
+    }
+
+    /// Whether a check applies to every request of the server, not only
+    /// to some locations
+    #[must_use]
+    pub fn is_server_wide(&self) -> bool {
+        self.rules.iter().any(|r| r.location.is_none())
+    }
+
+    /// Mechanisms the server uses, without duplicates
+    #[must_use]
+    pub fn mechanisms(&self) -> Vec<BotMechanism> {
+        let mut mechanisms = Vec::new();
+        for rule in &self.rules {
+            if !mechanisms.contains(&rule.mechanism) {
+                mechanisms.push(rule.mechanism);
+            }
+        }
+        mechanisms
+    }
+}
+
+/// Result of [`bot_blocking`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BotReport {
+    /// Every `http` server, in file order
+    pub servers: Vec<BotFilter>,
+
+    /// `map` blocks whose variable depends on the user agent
+    pub maps: Vec<Map>,
+
+    /// Problems found (`bot_filter_gap`, `bot_map_unused`)
+    pub findings: Vec<Finding>,
+}
+
+impl BotReport {
+    /// Servers that do not filter bots
+    pub fn unfiltered(&self) -> impl Iterator<Item = &BotFilter> {
+        self.servers.iter().filter(|s| !s.is_filtered())
+    }
+}
+
+/// Find the user-agent checks of every server and the servers without
+/// any.
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::{analyze::{bot_blocking, BotMechanism}, parse};
+///
+/// let config = parse(r"
+/// http {
+///     map $http_user_agent $bad_bot { default 0; ~*(ahrefs|semrush) 1; }
+///     server {
+///         server_name www.example.com;
+///         if ($bad_bot) { return 444; }
+///     }
+///     server { server_name api.example.com; }
+/// }
+/// ")?;
+/// let report = bot_blocking(&config);
+///
+/// assert_eq!(report.servers[0].mechanisms(), vec![BotMechanism::Map]);
+/// assert_eq!(report.servers[0].rules[0].patterns, vec!["~*(ahrefs|semrush)"]);
+/// assert_eq!(report.unfiltered().next().unwrap().server_name, "api.example.com");
+/// assert_eq!(report.findings[0].rule, "bot_filter_gap");
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+#[must_use]
+pub fn bot_blocking(config: &Config) -> BotReport {
+    let maps: Vec<Map> = extract::maps(config)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|m| !m.stream)
+        .collect();
+    let agent_maps = agent_maps(&maps);
+    let mut report = BotReport::default();
+    let mut referenced: Vec<String> = Vec::new();
+    let mut blocks: Vec<&Directive> = Vec::new();
+
+    walk(config, &mut |directive, parents| {
+        let defines_map = directive.name() == "map";
+        for (i, arg) in directive.args().iter().enumerate() {
+            // The variable a map defines is not a use of it
+            if !(defines_map && i == 1) {
+                referenced.extend(arg.variables().into_iter().map(str::to_string));
+            }
+        }
+
+        match directive.name() {
+            "map" => blocks.push(directive),
+            "server" if !parents.iter().any(|p| p.name() == "stream") => {
+                blocks.push(directive);
+                report.servers.push(BotFilter {
+                    server_name: Server::primary_name_of(directive),
+                    line: directive.span.line,
+                    rules: Vec::new(),
+                });
+            }
+            "if" => {
+                let Some(rule) = bot_rule(directive, parents, &agent_maps) else {
+                    return;
+                };
+                if let Some(server) = report.servers.last_mut() {
+                    server.rules.push(rule);
+                }
+            }
+            _ => {}
+        }
+    });
+
+    let block_at = |name: &str, line: usize| {
+        blocks
+            .iter()
+            .find(|b| b.name() == name && b.span.line == line)
+            .copied()
+    };
+    let filtered = report.servers.iter().filter(|s| s.is_filtered()).count();
+    if filtered > 0 {
+        let mut gaps = Vec::new();
+        for server in report.unfiltered() {
+            let Some(block) = block_at("server", server.line) else {
+                continue;
+            };
+            gaps.push(
+                Finding::new(
+                    "bot_filter_gap",
+                    Severity::Info,
+                    format!(
+                        "server \"{}\" does not filter bots, unlike {filtered} other server{}",
+                        server.server_name,
+                        if filtered == 1 { "" } else { "s" }
+                    ),
+                )
+                .at(block)
+                .with_help("Add the same user-agent check, e.g. through a shared include"),
+            );
+        }
+        report.findings.extend(gaps);
+    }
+
+    for map in agent_maps.iter().map(|(map, _)| *map) {
+        let Some(block) = block_at("map", map.line) else {
+            continue;
+        };
+        if referenced.contains(&map.variable) {
+            continue;
+        }
+        report.findings.push(
+            Finding::new(
+                "bot_map_unused",
+                Severity::Warning,
+                format!(
+                    "map {} ${} is never used, so it blocks nothing",
+                    map.source, map.variable
+                ),
+            )
+            .at(block)
+            .with_help(format!(
+                "Add \"if (${}) {{ return 403; }}\" to the servers to protect",
+                map.variable
+            )),
+        );
+    }
+
+    report.maps = agent_maps.into_iter().map(|(map, _)| map.clone()).collect();
+    report
+}
+
+/// Maps depending on the user agent, with whether they match it directly
+fn agent_maps(maps: &[Map]) -> Vec<(&Map, bool)> {
+    let mut agent: Vec<(&Map, bool)> = maps
+        .iter()
+        .filter(|m| m.source_variables().iter().any(|v| v == "http_user_agent"))
+        .map(|m| (m, true))
+        .collect();
+
+    // Maps of those maps, until nothing changes
+    loop {
+        let derived: Vec<&Map> = maps
+            .iter()
+            .filter(|m| !agent.iter().any(|(a, _)| std::ptr::eq(*a, *m)))
+            .filter(|m| {
+                m.source_variables()
+                    .iter()
+                    .any(|v| agent.iter().any(|(a, _)| a.variable == *v))
+            })
+            .collect();
+        if derived.is_empty() {
+            return agent;
+        }
+        agent.extend(derived.into_iter().map(|m| (m, false)));
+    }
+}
+
+fn bot_rule(
+    directive: &Directive,
+    parents: &[&Directive],
+    agent_maps: &[(&Map, bool)],
+) -> Option<BotRule> {
+    let args: Vec<String> = directive.args().iter().map(Value::unquoted).collect();
+    let condition = IfCondition::from_args(&args)?;
+    let variable = condition.variable()?;
+    let action = action(directive)?;
+
+    let (mechanism, map, patterns) = if variable == "http_user_agent" {
+        // A negated match lets the listed agents through and blocks the rest
+        let patterns = if condition.is_negated() {
+            Vec::new()
+        } else {
+            condition.operand.iter().cloned().collect()
+        };
+        (BotMechanism::If, None, patterns)
+    } else {
+        let (map, direct) = agent_maps.iter().find(|(m, _)| m.variable == variable)?;
+        let patterns = if *direct && !condition.is_negated() && !condition.is_regex() {
+            let blocked = |value: &str| match &condition.operand {
+                Some(operand) => value == operand,
+                None => !matches!(value, "" | "0"),
+            };
+            map.entries
+                .iter()
+                .filter(|e| blocked(&e.value))
+                .map(|e| e.key.clone())
+                .collect()
+        } else {
+            Vec::new()
+        };
+        (BotMechanism::Map, Some(map.variable.clone()), patterns)
+    };
+
+    Some(BotRule {
+        mechanism,
+        line: directive.span.line,
+        location: parents
+            .iter()
+            .rev()
+            .find(|p| p.name() == "location")
+            .map(|l| l.args_as_strings().join(" ")),
+        map,
+        patterns,
+        action,
+    })
+}
+
+/// The rejecting directive of an `if` body, `None` when it only sets
+/// variables or rewrites
+fn action(directive: &Directive) -> Option<String> {
+    directive
+        .children()
+        .unwrap_or_default()
+        .iter()
+        .find(|child| matches!(child.name(), "return" | "deny"))
+        .map(|child| format!("{} {}", child.name(), child.args_as_strings().join(" ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_bot_blocking() {
+        let config = parse(
+            r#"
+http {
+    map $http_user_agent $bad_bot {
+        default 0;
+        ~*(AhrefsBot|SemrushBot) 1;
+        "~*python-requests" 2;
+        "" 1;
+    }
+    map $bad_bot $block_reason { default none; 1 crawler; }
+    map $http_user_agent $is_mobile { default 0; ~*iphone 1; }
+    server {
+        server_name www.example.com;
+        if ($bad_bot = 1) { return 444; }
+        if ($http_user_agent ~* "(curl|wget)") { return 403; }
+        location /api/ {
+            if ($http_user_agent !~ ^Mozilla) { return 403; }
+        }
+        if ($http_user_agent ~* bingbot) { set $crawler 1; }
+    }
+    server {
+        server_name shop.example.com;
+        location /checkout/ {
+            if ($block_reason != none) { return 429; }
+        }
+    }
+    server { server_name api.example.com; }
+}
+"#,
+        )
+        .unwrap();
+        let report = bot_blocking(&config);
+
+        let www = &report.servers[0];
+        let rules: Vec<_> = www
+            .rules
+            .iter()
+            .map(|r| {
+                (
+                    r.mechanism,
+                    r.location.as_deref(),
+                    r.patterns.clone(),
+                    r.action.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            rules,
+            vec![
+                (
+                    BotMechanism::Map,
+                    None,
+                    vec!["~*(AhrefsBot|SemrushBot)".to_string(), String::new()],
+                    "return 444"
+                ),
+                (
+                    BotMechanism::If,
+                    None,
+                    vec!["(curl|wget)".to_string()],
+                    "return 403"
+                ),
+                (BotMechanism::If, Some("/api/"), vec![], "return 403"),
+            ]
+        );
+        assert_eq!(www.mechanisms(), vec![BotMechanism::Map, BotMechanism::If]);
+        assert!(www.is_server_wide());
+
+        let shop = &report.servers[1];
+        assert_eq!(shop.rules[0].map.as_deref(), Some("block_reason"));
+        assert!(shop.rules[0].patterns.is_empty());
+        assert!(!shop.is_server_wide());
+
+        assert_eq!(report.maps.len(), 3);
+        let findings: Vec<_> = report
+            .findings
+            .iter()
+            .map(|f| (f.rule.as_str(), f.line()))
+            .collect();
+        assert_eq!(
+            findings,
+            vec![("bot_filter_gap", Some(26)), ("bot_map_unused", Some(10))]
+        );
+        assert!(report.findings[0]
+            .message
+            .ends_with("unlike 2 other servers"));
+    }
+}
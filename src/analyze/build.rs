@@ -0,0 +1,493 @@
+//! nginx build information
+//!
+//! `nginx -V` prints the nginx version, the TLS library nginx was built
+//! with and the configure arguments. [`BuildInfo`] parses all three.
+//!
+//! The TLS library decides what the `ssl_*` directives can do. OpenSSL
+//! before 1.1.1 has no TLSv1.3. `BoringSSL` has no OCSP stapling and no
+//! DHE cipher suites. Neither `BoringSSL` nor `LibreSSL` implements
+//! `SSL_CONF_cmd`, which `ssl_conf_command` needs. [`TlsLibrary::check`]
+//! reports the directives and parameters the detected library does not
+//! support under the `tls_library_unsupported` rule.
+
+use crate::analyze::{CompiledModules, NginxVersion};
+use crate::ast::{Config, Directive};
+use crate::lint::{walk, Finding, Severity};
+use std::fmt;
+
+/// Rule of the findings of [`TlsLibrary::check`]
+const RULE: &str = "tls_library_unsupported";
+
+/// What `nginx -V` reports about a build
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BuildInfo {
+    /// nginx version
+    pub version: Option<NginxVersion>,
+
+    /// TLS library nginx runs with
+    pub tls_library: Option<TlsLibrary>,
+
+    /// Modules compiled in
+    pub modules: CompiledModules,
+}
+
+impl BuildInfo {
+    /// Parse `nginx -V` output
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nginx_discovery::analyze::{BuildInfo, NginxVersion, TlsLibraryKind};
+    ///
+    /// let info = BuildInfo::from_build_output(
+    ///     "nginx version: nginx/1.24.0\n\
+    ///      built with OpenSSL 3.0.2 15 Mar 2022\n\
+    ///      TLS SNI support enabled\n\
+    ///      configure arguments: --with-http_ssl_module",
+    /// );
+    ///
+    /// assert_eq!(info.version, Some(NginxVersion::new(1, 24, 0)));
+    /// let tls = info.tls_library.unwrap();
+    /// assert_eq!(tls.kind, TlsLibraryKind::OpenSsl);
+    /// assert_eq!(tls.version.as_deref(), Some("3.0.2"));
+    /// ```
+    #[must_use]
+    pub fn from_build_output(output: &str) -> Self {
+        Self {
+            version: output
+                .lines()
+                .find(|line| line.trim_start().starts_with("nginx version:"))
+                .and_then(NginxVersion::from_version_output),
+            tls_library: TlsLibrary::from_build_output(output),
+            modules: CompiledModules::from_build_output(output),
+        }
+    }
+}
+
+/// A TLS library family
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum TlsLibraryKind {
+    /// OpenSSL, including quictls
+    OpenSsl,
+    /// Google's OpenSSL fork
+    BoringSsl,
+    /// OpenBSD's OpenSSL fork
+    LibreSsl,
+}
+
+impl fmt::Display for TlsLibraryKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::OpenSsl => "OpenSSL",
+            Self::BoringSsl => "BoringSSL",
+            Self::LibreSsl => "LibreSSL",
+        })
+    }
+}
+
+/// The TLS library nginx runs with
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TlsLibrary {
+    /// Library family
+    pub kind: TlsLibraryKind,
+
+    /// Version as reported (`3.0.2`, `1.1.1k`); `BoringSSL` has none
+    pub version: Option<String>,
+}
+
+impl fmt::Display for TlsLibrary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.version {
+            Some(version) => write!(f, "{} {version}", self.kind),
+            None => write!(f, "{}", self.kind),
+        }
+    }
+}
+
+impl TlsLibrary {
+    /// Find the library in the `built with` line of `nginx -V` output
+    ///
+    /// When the library loaded at run time differs from the one nginx was
+    /// compiled against, nginx appends `(running with ...)`; that one is
+    /// returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nginx_discovery::analyze::{TlsLibrary, TlsLibraryKind};
+    ///
+    /// let tls = TlsLibrary::from_build_output(
+    ///     "built with OpenSSL 1.1.1 (compatible; BoringSSL) (running with BoringSSL)",
+    /// )
+    /// .unwrap();
+    /// assert_eq!(tls.kind, TlsLibraryKind::BoringSsl);
+    /// assert_eq!(tls.to_string(), "BoringSSL");
+    /// ```
+    #[must_use]
+    pub fn from_build_output(output: &str) -> Option<Self> {
+        let built_with = output
+            .lines()
+            .find_map(|line| line.trim().strip_prefix("built with "))?;
+        let text = built_with
+            .split_once("(running with ")
+            .map_or(built_with, |(_, running)| running);
+
+        let version_after = |name: &str| {
+            let (_, rest) = text.split_once(name)?;
+            rest.split_whitespace()
+                .next()
+                .filter(|version| version.starts_with(|c: char| c.is_ascii_digit()))
+                .map(|version| version.trim_end_matches(')').to_string())
+        };
+        if text.contains("BoringSSL") {
+            Some(Self {
+                kind: TlsLibraryKind::BoringSsl,
+                version: None,
+            })
+        } else if text.contains("LibreSSL") {
+            Some(Self {
+                kind: TlsLibraryKind::LibreSsl,
+                version: version_after("LibreSSL"),
+            })
+        } else if text.contains("OpenSSL") {
+            Some(Self {
+                kind: TlsLibraryKind::OpenSsl,
+                version: version_after("OpenSSL"),
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Whether the library negotiates TLSv1.3: OpenSSL 1.1.1, `LibreSSL`
+    /// 3.2.0 and `BoringSSL`
+    #[must_use]
+    pub fn supports_tls13(&self) -> bool {
+        match self.kind {
+            TlsLibraryKind::OpenSsl => self.at_least(1, 1, 1),
+            TlsLibraryKind::LibreSsl => self.at_least(3, 2, 0),
+            TlsLibraryKind::BoringSsl => true,
+        }
+    }
+
+    /// Whether `ssl_ecdh_curve` accepts `curve`, or `None` for a curve
+    /// this crate does not know
+    #[must_use]
+    pub fn supports_curve(&self, curve: &str) -> Option<bool> {
+        match curve.to_ascii_lowercase().as_str() {
+            "auto" | "prime256v1" | "secp384r1" | "secp521r1" | "p-256" | "p-384" | "p-521" => {
+                Some(true)
+            }
+            "x25519" => Some(match self.kind {
+                TlsLibraryKind::OpenSsl => self.at_least(1, 1, 0),
+                TlsLibraryKind::LibreSsl => self.at_least(2, 5, 0),
+                TlsLibraryKind::BoringSsl => true,
+            }),
+            "x448" => Some(self.kind == TlsLibraryKind::OpenSsl && self.at_least(1, 1, 1)),
+            _ => None,
+        }
+    }
+
+    /// Whether `ssl_ecdh_curve` accepts a colon-separated list; OpenSSL
+    /// before 1.0.2 takes a single curve
+    #[must_use]
+    pub fn supports_curve_list(&self) -> bool {
+        self.kind != TlsLibraryKind::OpenSsl || self.at_least(1, 0, 2)
+    }
+
+    /// Whether `ssl_stapling` works; `BoringSSL` lacks the OCSP callbacks
+    #[must_use]
+    pub fn supports_ocsp_stapling(&self) -> bool {
+        self.kind != TlsLibraryKind::BoringSsl
+    }
+
+    /// Whether `ssl_early_data` works: OpenSSL 1.1.1 and `BoringSSL`
+    #[must_use]
+    pub fn supports_early_data(&self) -> bool {
+        match self.kind {
+            TlsLibraryKind::OpenSsl => self.at_least(1, 1, 1),
+            TlsLibraryKind::LibreSsl => false,
+            TlsLibraryKind::BoringSsl => true,
+        }
+    }
+
+    /// Whether `ssl_conf_command` works: OpenSSL 1.0.2 and later
+    #[must_use]
+    pub fn supports_conf_command(&self) -> bool {
+        self.kind == TlsLibraryKind::OpenSsl && self.at_least(1, 0, 2)
+    }
+
+    /// Whether DHE cipher suites, and so `ssl_dhparam`, are available
+    #[must_use]
+    pub fn supports_dhe(&self) -> bool {
+        self.kind != TlsLibraryKind::BoringSsl
+    }
+
+    /// Whether the version is at least `major.minor.patch`; an unknown
+    /// version is assumed recent
+    fn at_least(&self, major: u32, minor: u32, patch: u32) -> bool {
+        let Some(version) = &self.version else {
+            return true;
+        };
+        let mut parts = version.split('.').map(|part| {
+            let digits = part
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(part.len());
+            part[..digits].parse::<u32>().unwrap_or(0)
+        });
+        let mut next = || parts.next().unwrap_or(0);
+        (next(), next(), next()) >= (major, minor, patch)
+    }
+
+    /// Directives and parameters of `config` this library does not
+    /// support, as `tls_library_unsupported` findings
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nginx_discovery::{analyze::TlsLibrary, parse};
+    ///
+    /// let config = parse("server {\n  ssl_protocols TLSv1.2 TLSv1.3;\n  ssl_stapling on;\n}\n")?;
+    /// let tls = TlsLibrary::from_build_output("built with OpenSSL 1.0.2k-fips  26 Jan 2017").unwrap();
+    ///
+    /// let findings = tls.check(&config);
+    /// assert_eq!(findings.len(), 1);
+    /// assert_eq!(findings[0].line(), Some(2));
+    /// # Ok::<(), nginx_discovery::Error>(())
+    /// ```
+    #[must_use]
+    pub fn check(&self, config: &Config) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        walk(config, &mut |directive, _| {
+            findings.extend(self.check_directive(directive));
+        });
+        findings
+    }
+
+    fn check_directive(&self, directive: &Directive) -> Option<Finding> {
+        let args = directive.args_as_strings();
+        let on = directive.first_arg().as_deref() == Some("on");
+        let (severity, message, help) = match directive.name() {
+            name if name.ends_with("ssl_protocols")
+                && args.iter().any(|arg| arg == "TLSv1.3")
+                && !self.supports_tls13() =>
+            {
+                (
+                    Severity::Warning,
+                    format!("{self} does not support TLSv1.3; \"{name}\" stops at TLSv1.2"),
+                    "Rebuild nginx against OpenSSL 1.1.1 or later".to_string(),
+                )
+            }
+            "listen" if args.iter().any(|arg| arg == "quic") && !self.supports_tls13() => (
+                Severity::Error,
+                format!("QUIC needs TLSv1.3, which {self} does not support"),
+                "Rebuild nginx against OpenSSL 1.1.1 or later, or remove the quic listener"
+                    .to_string(),
+            ),
+            "ssl_ecdh_curve" => {
+                let curves: Vec<&str> = args.first()?.split(':').collect();
+                if curves.len() > 1 && !self.supports_curve_list() {
+                    (
+                        Severity::Error,
+                        format!("{self} accepts a single curve in \"ssl_ecdh_curve\""),
+                        "Use \"ssl_ecdh_curve auto;\" or one curve such as prime256v1".to_string(),
+                    )
+                } else {
+                    let unsupported: Vec<&str> = curves
+                        .into_iter()
+                        .filter(|curve| self.supports_curve(curve) == Some(false))
+                        .collect();
+                    if unsupported.is_empty() {
+                        return None;
+                    }
+                    (
+                        Severity::Error,
+                        format!(
+                            "{self} does not support the curve {}, so nginx fails to start",
+                            unsupported.join(", ")
+                        ),
+                        "Remove the curve from \"ssl_ecdh_curve\"".to_string(),
+                    )
+                }
+            }
+            "ssl_stapling" if on && !self.supports_ocsp_stapling() => (
+                Severity::Warning,
+                format!("{self} does not support OCSP stapling; nginx ignores \"ssl_stapling\""),
+                "Remove \"ssl_stapling\" and \"ssl_stapling_verify\"".to_string(),
+            ),
+            "ssl_early_data" if on && !self.supports_early_data() => (
+                Severity::Warning,
+                format!(
+                    "{self} does not support 0-RTT early data; nginx ignores \"ssl_early_data\""
+                ),
+                "Remove \"ssl_early_data\"".to_string(),
+            ),
+            name if name.ends_with("ssl_conf_command") && !self.supports_conf_command() => (
+                Severity::Error,
+                format!("{self} has no SSL_CONF_cmd, so nginx rejects \"{name}\""),
+                format!("Remove \"{name}\" and use the dedicated ssl_* directives"),
+            ),
+            "ssl_dhparam" if !self.supports_dhe() => (
+                Severity::Info,
+                format!("{self} has no DHE cipher suites, so \"ssl_dhparam\" has no effect"),
+                "Remove \"ssl_dhparam\"".to_string(),
+            ),
+            _ => return None,
+        };
+        Some(
+            Finding::new(RULE, severity, message)
+                .at(directive)
+                .with_help(help),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_tls_library_check() {
+        let tls = TlsLibrary::from_build_output(
+            "built with OpenSSL 1.1.1f  31 Mar 2020 (running with OpenSSL 1.1.1k  25 Mar 2021)",
+        )
+        .unwrap();
+        assert_eq!(tls.version.as_deref(), Some("1.1.1k"));
+        assert!(tls.supports_tls13());
+        let libressl = TlsLibrary::from_build_output("built with LibreSSL 3.1.4").unwrap();
+        assert!(!libressl.supports_tls13());
+        assert_eq!(TlsLibrary::from_build_output("built by gcc 12.2.0"), None);
+
+        let config = parse(
+            "http {
+  ssl_ecdh_curve X25519:X448:prime256v1;
+  ssl_conf_command Options PrioritizeChaCha;
+  server {
+    listen 443 ssl;
+    ssl_protocols TLSv1.2 TLSv1.3;
+    ssl_stapling on;
+    ssl_early_data on;
+    ssl_dhparam /etc/nginx/dhparam.pem;
+  }
+}",
+        )
+        .unwrap();
+        let found = |build: &str| -> Vec<_> {
+            TlsLibrary::from_build_output(build)
+                .unwrap()
+                .check(&config)
+                .iter()
+                .map(|f| (f.line().unwrap_or_default(), f.severity))
+                .collect()
+        };
+
+        assert!(found("built with OpenSSL 3.0.2 15 Mar 2022").is_empty());
+        assert_eq!(
+            found("built with OpenSSL 1.1.1 (compatible; BoringSSL) (running with BoringSSL)"),
+            vec![
+                (2, Severity::Error),
+                (3, Severity::Error),
+                (7, Severity::Warning),
+                (9, Severity::Info)
+            ]
+        );
+        assert_eq!(
+            found("built with LibreSSL 3.8.2"),
+            vec![
+                (2, Severity::Error),
+                (3, Severity::Error),
+                (8, Severity::Warning)
+            ]
+        );
+    }
+
+    fn tls(build: &str) -> TlsLibrary {
+        TlsLibrary::from_build_output(build).unwrap()
+    }
+
+    #[test]
+    fn test_tls_library_detection_edge_cases() {
+        assert_eq!(BuildInfo::from_build_output(""), BuildInfo::default());
+
+        // Versions with suffixes, without patch or missing altogether
+        let openssl = tls("built with OpenSSL 1.0.2k-fips  26 Jan 2017");
+        assert_eq!(openssl.to_string(), "OpenSSL 1.0.2k-fips");
+        assert!(openssl.supports_curve_list() && openssl.supports_conf_command());
+        assert!(!openssl.supports_tls13());
+        assert!(tls("built with OpenSSL 3").supports_tls13());
+        let unknown = tls("built with OpenSSL");
+        assert_eq!(unknown.version, None);
+        assert!(unknown.supports_tls13() && unknown.supports_early_data());
+        assert_eq!(
+            tls("built with OpenSSL 1.1.1 (running with OpenSSL 3.0.13)")
+                .version
+                .as_deref(),
+            Some("3.0.13")
+        );
+        assert_eq!(
+            tls("  built with LibreSSL 3.9.0\n").kind,
+            TlsLibraryKind::LibreSsl
+        );
+
+        // Curves by library and version, matched without case
+        let old = tls("built with OpenSSL 1.0.1e-fips 11 Feb 2013");
+        assert_eq!(old.supports_curve("X25519"), Some(false));
+        assert_eq!(old.supports_curve("P-384"), Some(true));
+        assert_eq!(old.supports_curve("brainpoolP256r1"), None);
+        assert!(!old.supports_curve_list());
+        let libressl = tls("built with LibreSSL 2.4.5");
+        assert_eq!(libressl.supports_curve("x25519"), Some(false));
+        assert_eq!(libressl.supports_curve("x448"), Some(false));
+        assert!(!libressl.supports_early_data() && !libressl.supports_conf_command());
+        assert!(libressl.supports_ocsp_stapling() && libressl.supports_dhe());
+    }
+
+    #[test]
+    fn test_tls_library_check_edge_cases() {
+        let config = parse(
+            "http {
+  ssl_ecdh_curve X25519:prime256v1;
+  ssl_ecdh_curve brainpoolP256r1;
+  ssl_ecdh_curve;
+  ssl_stapling off;
+  proxy_ssl_protocols TLSv1.3;
+  proxy_ssl_conf_command Options -SessionTicket;
+  server { listen 443 quic; }
+}
+stream { server { ssl_protocols TLSv1.2; } }",
+        )
+        .unwrap();
+        let found = |build: &str| -> Vec<_> {
+            tls(build)
+                .check(&config)
+                .iter()
+                .map(|f| (f.line().unwrap_or_default(), f.severity, f.rule.clone()))
+                .collect()
+        };
+        let at = |line, severity| (line, severity, RULE.to_string());
+
+        // A list is rejected before its curves are looked at; unknown
+        // curves and a missing argument are left alone
+        assert_eq!(
+            found("built with OpenSSL 1.0.1e-fips 11 Feb 2013"),
+            vec![
+                at(2, Severity::Error),
+                at(6, Severity::Warning),
+                at(7, Severity::Error),
+                at(8, Severity::Error)
+            ]
+        );
+        let findings = tls("built with OpenSSL 1.0.1e").check(&config);
+        assert!(findings[0].message.contains("single curve"));
+        assert!(findings[1].message.contains("\"proxy_ssl_protocols\""));
+
+        // `off` is never reported; prefixed conf commands are
+        assert_eq!(found("built with BoringSSL"), vec![at(7, Severity::Error)]);
+        assert!(found("built with OpenSSL 3.0.13 30 Jan 2024").is_empty());
+    }
+}
@@ -0,0 +1,402 @@
+//! Static asset caching audit
+//!
+//! Collects the effective `open_file_cache`, `expires`, `etag` and
+//! `add_header Cache-Control` settings of every static location, following
+//! nginx inheritance (a location without `add_header` inherits all of its
+//! parent's headers, one with any `add_header` inherits none), and flags:
+//!
+//! - `caching_missing`: a static location that sends neither `expires` nor
+//!   `Cache-Control`
+//! - `caching_conflict`: `expires` and `add_header Cache-Control` disagree,
+//!   e.g. `expires 30d` with `Cache-Control: no-store`, or two different
+//!   `max-age` values
+//!
+//! A location is static when it has no content handler (`proxy_pass`,
+//! `fastcgi_pass`, `return`, ...) and either sets `root`/`alias` itself or
+//! matches asset file extensions (`\.(css|js|png)$`).
+
+use crate::ast::{parse_duration, Config, Directive};
+use crate::lint::{walk, Finding, Severity};
+use crate::types::Server;
+
+/// Directives that hand the request to something other than the file system
+const HANDLERS: &[&str] = &[
+    "proxy_pass",
+    "fastcgi_pass",
+    "uwsgi_pass",
+    "scgi_pass",
+    "grpc_pass",
+    "memcached_pass",
+    "return",
+];
+
+/// File extensions that identify asset locations
+const ASSET_EXTENSIONS: &[&str] = &[
+    "css", "js", "mjs", "map", "png", "jpg", "jpeg", "gif", "svg", "ico", "webp", "avif", "woff",
+    "woff2", "ttf", "otf", "eot", "mp4", "webm",
+];
+
+/// `expires max` in seconds (10 years)
+const EXPIRES_MAX: u64 = 315_360_000;
+
+/// Effective caching settings of one location
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LocationCaching {
+    /// First `server_name` of the enclosing server (`_` if none)
+    pub server_name: String,
+
+    /// Location path/pattern
+    pub location: String,
+
+    /// Line of the `location` directive
+    pub line: usize,
+
+    /// Whether the location serves files from disk
+    pub is_static: bool,
+
+    /// `open_file_cache` arguments (e.g. `max=1000 inactive=20s`)
+    pub open_file_cache: Option<String>,
+
+    /// `expires` arguments (e.g. `30d`, `max`, `off`)
+    pub expires: Option<String>,
+
+    /// `etag` setting; `None` means the default (`on`)
+    pub etag: Option<bool>,
+
+    /// Value of an `add_header Cache-Control` that applies to the location
+    pub cache_control: Option<String>,
+}
+
+impl LocationCaching {
+    /// Whether any caching header is sent (`expires` other than `off`, or
+    /// `Cache-Control`)
+    #[must_use]
+    pub fn has_cache_headers(&self) -> bool {
+        self.cache_control.is_some() || self.expires.as_deref().is_some_and(|e| e != "off")
+    }
+}
+
+/// Result of [`caching_audit`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CachingReport {
+    /// Every location with its effective caching settings
+    pub locations: Vec<LocationCaching>,
+
+    /// Problems found in static locations
+    pub findings: Vec<Finding>,
+}
+
+impl CachingReport {
+    /// Static locations only
+    pub fn static_locations(&self) -> impl Iterator<Item = &LocationCaching> {
+        self.locations.iter().filter(|l| l.is_static)
+    }
+}
+
+/// Audit caching of static locations.
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::{analyze::caching_audit, parse};
+///
+/// let config = parse(r"
+/// http {
+///     server {
+///         server_name example.com;
+///         location /assets/ { root /srv/www; }
+///         location ~* \.(css|js)$ { root /srv/www; expires 7d; }
+///     }
+/// }
+/// ")?;
+///
+/// let report = caching_audit(&config);
+/// assert_eq!(report.static_locations().count(), 2);
+/// assert_eq!(report.findings.len(), 1);
+/// assert_eq!(report.findings[0].rule, "caching_missing");
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+#[must_use]
+pub fn caching_audit(config: &Config) -> CachingReport {
+    let mut report = CachingReport::default();
+
+    walk(config, &mut |directive, parents| {
+        if directive.name() != "location" || directive.children().is_none() {
+            return;
+        }
+
+        let caching = location_caching(directive, parents);
+        if caching.is_static {
+            check_location(directive, &caching, &mut report.findings);
+        }
+        report.locations.push(caching);
+    });
+
+    report
+}
+
+fn location_caching(location: &Directive, parents: &[&Directive]) -> LocationCaching {
+    // Innermost block first
+    let chain: Vec<&Directive> = std::iter::once(location)
+        .chain(parents.iter().rev().copied())
+        .collect();
+
+    let server_name =
+        Server::primary_name_of(parents.iter().rev().find(|p| p.name() == "server").copied());
+
+    let children = location.children().unwrap_or_default();
+    let has_handler = children.iter().any(|c| HANDLERS.contains(&c.name()));
+    let serves_files = children
+        .iter()
+        .any(|c| c.name() == "root" || c.name() == "alias");
+    let path = location_path(location);
+
+    LocationCaching {
+        server_name,
+        is_static: !path.starts_with('@') && !has_handler && (serves_files || is_asset_path(&path)),
+        location: path,
+        line: location.span.line,
+        open_file_cache: effective(&chain, "open_file_cache")
+            .map(|d| d.args_as_strings().join(" ")),
+        expires: effective(&chain, "expires").map(|d| d.args_as_strings().join(" ")),
+        etag: effective(&chain, "etag").map(|d| d.first_arg().as_deref() != Some("off")),
+        cache_control: cache_control_header(&chain),
+    }
+}
+
+/// The directive `name` from the innermost block that sets it
+fn effective<'a>(chain: &[&'a Directive], name: &str) -> Option<&'a Directive> {
+    chain
+        .iter()
+        .find_map(|block| block.find_children(name).last().copied())
+}
+
+/// `add_header` is inherited only by blocks that define none of their own
+fn cache_control_header(chain: &[&Directive]) -> Option<String> {
+    let headers = chain
+        .iter()
+        .map(|block| block.find_children("add_header"))
+        .find(|headers| !headers.is_empty())?;
+
+    headers.iter().find_map(|header| {
+        let args = header.args_as_strings();
+        match args.as_slice() {
+            [name, value, ..] if name.eq_ignore_ascii_case("cache-control") => Some(value.clone()),
+            _ => None,
+        }
+    })
+}
+
+fn location_path(location: &Directive) -> String {
+    let args = location.args_as_strings();
+    match args.as_slice() {
+        [modifier, path, ..] if matches!(modifier.as_str(), "=" | "~" | "~*" | "^~") => {
+            path.clone()
+        }
+        [path, ..] => path.clone(),
+        [] => "/".to_string(),
+    }
+}
+
+fn is_asset_path(path: &str) -> bool {
+    path.split(|c: char| !c.is_ascii_alphanumeric())
+        .any(|token| ASSET_EXTENSIONS.contains(&token.to_ascii_lowercase().as_str()))
+}
+
+fn check_location(location: &Directive, caching: &LocationCaching, findings: &mut Vec<Finding>) {
+    let name = format!(
+        "location \"{}\" in server \"{}\"",
+        caching.location, caching.server_name
+    );
+
+    if !caching.has_cache_headers() {
+        findings.push(
+            Finding::new(
+                "caching_missing",
+                Severity::Warning,
+                format!("static {name} sends no expires or Cache-Control header"),
+            )
+            .at(location)
+            .with_help("Add `expires 30d;` (or `add_header Cache-Control \"public, max-age=2592000\";`) for fingerprinted assets"),
+        );
+        return;
+    }
+
+    let (Some(expires), Some(cache_control)) = (&caching.expires, &caching.cache_control) else {
+        return;
+    };
+
+    let conflict = match (expires_intent(expires), cache_control_intent(cache_control)) {
+        (Intent::NoCache, Intent::Cache(_)) | (Intent::Cache(_), Intent::NoCache) => true,
+        (Intent::Cache(Some(a)), Intent::Cache(Some(b))) => a != b,
+        _ => false,
+    };
+
+    if conflict {
+        findings.push(
+            Finding::new(
+                "caching_conflict",
+                Severity::Warning,
+                format!(
+                    "{name}: \"expires {expires}\" conflicts with \"Cache-Control: {cache_control}\""
+                ),
+            )
+            .at(location)
+            .with_help("Both emit a Cache-Control header; use either expires or add_header Cache-Control"),
+        );
+    }
+}
+
+/// What a caching header asks clients to do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Intent {
+    /// No header is sent
+    None,
+    /// Do not cache (`no-cache`, `no-store`, `max-age=0`, `expires epoch`)
+    NoCache,
+    /// Cache, for this many seconds if known
+    Cache(Option<u64>),
+    /// Cannot be determined statically (variables, unusual values)
+    Unknown,
+}
+
+fn expires_intent(expires: &str) -> Intent {
+    let value = expires.strip_prefix("modified ").unwrap_or(expires).trim();
+    match value {
+        "off" => Intent::None,
+        "epoch" => Intent::NoCache,
+        "max" => Intent::Cache(Some(EXPIRES_MAX)),
+        _ if value.starts_with('$') => Intent::Unknown,
+        // `@15h30m` is a time of day, the max-age depends on the request time
+        _ if value.starts_with('@') => Intent::Cache(None),
+        _ if value.starts_with('-') => Intent::NoCache,
+        _ => match parse_seconds(value) {
+            Some(0) => Intent::NoCache,
+            Some(seconds) => Intent::Cache(Some(seconds)),
+            None => Intent::Unknown,
+        },
+    }
+}
+
+fn cache_control_intent(value: &str) -> Intent {
+    let value = value.to_ascii_lowercase();
+    let directives: Vec<&str> = value.split(',').map(str::trim).collect();
+
+    if directives
+        .iter()
+        .any(|d| *d == "no-store" || *d == "no-cache")
+    {
+        return Intent::NoCache;
+    }
+
+    let max_age = directives
+        .iter()
+        .find_map(|d| d.strip_prefix("max-age="))
+        .map(str::parse::<u64>);
+    match max_age {
+        Some(Ok(0)) => Intent::NoCache,
+        Some(Ok(seconds)) => Intent::Cache(Some(seconds)),
+        None if directives
+            .iter()
+            .any(|d| *d == "public" || *d == "immutable") =>
+        {
+            Intent::Cache(None)
+        }
+        Some(Err(_)) | None => Intent::Unknown,
+    }
+}
+
+/// Parse an nginx time value (`30d`, `1h30m`, `3600`) into seconds
+pub(crate) fn parse_seconds(value: &str) -> Option<u64> {
+    parse_duration(value).map(|d| d.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_effective_settings_and_inheritance() {
+        let config = parse(
+            r#"
+http {
+    open_file_cache max=1000 inactive=20s;
+    add_header Cache-Control "public, max-age=3600";
+    server {
+        server_name example.com;
+        etag off;
+        location /static/ { root /srv; }
+        location /img/ { root /srv; add_header X-Frame-Options DENY; }
+        location /api/ { proxy_pass http://backend; }
+    }
+}
+"#,
+        )
+        .unwrap();
+
+        let report = caching_audit(&config);
+        assert_eq!(report.locations.len(), 3);
+
+        let static_dir = &report.locations[0];
+        assert!(static_dir.is_static);
+        assert_eq!(
+            static_dir.open_file_cache.as_deref(),
+            Some("max=1000 inactive=20s")
+        );
+        assert_eq!(
+            static_dir.cache_control.as_deref(),
+            Some("public, max-age=3600")
+        );
+        assert_eq!(static_dir.etag, Some(false));
+
+        // Its own add_header drops the inherited Cache-Control
+        assert_eq!(report.locations[1].cache_control, None);
+        assert!(!report.locations[2].is_static);
+
+        assert_eq!(report.findings.len(), 1);
+        assert_eq!(report.findings[0].rule, "caching_missing");
+        assert_eq!(report.findings[0].line(), Some(9));
+    }
+
+    #[test]
+    fn test_conflicting_headers() {
+        let config = parse(
+            r#"
+server {
+    location ~* \.(png|jpg)$ { expires 30d; add_header Cache-Control "no-store"; }
+    location /a/ { root /srv; expires 1h; add_header Cache-Control "max-age=3600"; }
+    location /b/ { root /srv; expires 1d; add_header Cache-Control "max-age=3600"; }
+}
+"#,
+        )
+        .unwrap();
+
+        let report = caching_audit(&config);
+        let lines: Vec<_> = report
+            .findings
+            .iter()
+            .map(|f| (f.rule.as_str(), f.line()))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![("caching_conflict", Some(3)), ("caching_conflict", Some(5))]
+        );
+    }
+
+    #[test]
+    fn test_parse_seconds() {
+        assert_eq!(parse_seconds("30d"), Some(2_592_000));
+        assert_eq!(parse_seconds("1h30m"), Some(5400));
+        assert_eq!(parse_seconds("3600"), Some(3600));
+        assert_eq!(parse_seconds("10x"), None);
+        assert_eq!(expires_intent("off"), Intent::None);
+        assert_eq!(expires_intent("epoch"), Intent::NoCache);
+        assert_eq!(
+            cache_control_intent("public, immutable"),
+            Intent::Cache(None)
+        );
+    }
+}
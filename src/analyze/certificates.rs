@@ -0,0 +1,586 @@
+//! Server names and the certificates that cover them
+//!
+//! [`certificate_coverage`] reads the `subjectAltName` of every certificate
+//! a TLS server uses and tells, for each of its `server_name`s, whether an
+//! exact name or a wildcard covers it. A wildcard stands for exactly one
+//! label: `*.example.com` covers `www.example.com` but neither
+//! `example.com` nor `a.b.example.com`, which is easy to miss when the
+//! apex is added to a server that already uses the wildcard certificate.
+//!
+//! - `wildcard_apex`: a certificate has a wildcard for the name's
+//!   subdomains but not the name itself
+//! - `certificate_name_mismatch`: a certificate does not cover a server
+//!   name at all
+//!
+//! A server with an RSA and an ECDSA certificate hands out either one, so
+//! every certificate of the server has to cover every name. Regular
+//! expression names and names ending in `*` cannot be checked and are
+//! skipped; `.example.com` stands for `example.com` and `*.example.com`.
+
+use crate::ast::{Config, Directive};
+use crate::extract;
+use crate::lint::{walk, Finding, Severity};
+use crate::pem;
+use crate::types::is_runtime_path;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// How a server name is covered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum NameCoverage {
+    /// Every certificate lists the name itself
+    Exact,
+    /// Every certificate covers the name, at least one through a wildcard
+    Wildcard,
+    /// Some certificate does not cover the name
+    Uncovered,
+    /// No certificate of the server could be read
+    Unknown,
+}
+
+impl fmt::Display for NameCoverage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Exact => "exact",
+            Self::Wildcard => "wildcard",
+            Self::Uncovered => "not covered",
+            Self::Unknown => "unknown",
+        })
+    }
+}
+
+/// A certificate file used by at least one TLS server
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CertificateNames {
+    /// Path as written in `ssl_certificate`
+    pub path: String,
+
+    /// Path resolved against the base directory
+    pub resolved: PathBuf,
+
+    /// Line of the first `ssl_certificate` naming it
+    pub line: usize,
+
+    /// DNS names of the `subjectAltName`, `None` when the file could not
+    /// be read or decoded
+    pub names: Option<Vec<String>>,
+
+    /// Server names served with it
+    pub server_names: Vec<String>,
+}
+
+impl CertificateNames {
+    /// Whether the certificate has a wildcard name
+    #[must_use]
+    pub fn is_wildcard(&self) -> bool {
+        self.names
+            .iter()
+            .flatten()
+            .any(|name| name.starts_with("*."))
+    }
+}
+
+/// A server name and the certificates it is served with
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ServerNameCoverage {
+    /// Name from `server_name`
+    pub name: String,
+
+    /// Line of the server block
+    pub line: usize,
+
+    /// How the certificates cover it
+    pub coverage: NameCoverage,
+
+    /// Certificate paths of the server
+    pub certificates: Vec<String>,
+}
+
+/// Result of [`certificate_coverage`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CertificateCoverageReport {
+    /// Every certificate, in the order first used
+    pub certificates: Vec<CertificateNames>,
+
+    /// Every checkable server name of a TLS server, in file order
+    pub names: Vec<ServerNameCoverage>,
+
+    /// Problems found (`wildcard_apex`, `certificate_name_mismatch`)
+    pub findings: Vec<Finding>,
+}
+
+/// Check which server names the certificates of each TLS server cover.
+///
+/// Relative certificate paths are resolved against `base_dir`.
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::{analyze::{certificate_coverage, NameCoverage}, parse};
+///
+/// let config = parse(r"
+/// server {
+///     listen 443 ssl;
+///     server_name example.com;
+///     ssl_certificate /no/such/cert.pem;
+///     ssl_certificate_key /no/such/key.pem;
+/// }
+/// ")?;
+/// let report = certificate_coverage(&config, "/etc/nginx");
+///
+/// assert_eq!(report.certificates[0].names, None);
+/// assert_eq!(report.names[0].coverage, NameCoverage::Unknown);
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+#[must_use]
+pub fn certificate_coverage(
+    config: &Config,
+    base_dir: impl AsRef<Path>,
+) -> CertificateCoverageReport {
+    let base_dir = base_dir.as_ref();
+    let mut report = CertificateCoverageReport::default();
+
+    walk(config, &mut |directive, parents| {
+        if directive.name() != "server" || !directive.is_block() || !is_tls(directive) {
+            return;
+        }
+        let certificates = std::iter::once(directive)
+            .chain(parents.iter().rev().copied())
+            .map(|block| block.find_children("ssl_certificate"))
+            .find(|found| !found.is_empty())
+            .unwrap_or_default();
+        if certificates.is_empty() {
+            return;
+        }
+
+        let names = server_names(directive);
+        let used: Vec<(usize, &Directive)> = certificates
+            .iter()
+            .filter_map(|&certificate| {
+                certificate_index(&mut report, certificate, base_dir).map(|i| (i, certificate))
+            })
+            .collect();
+        for &(index, _) in &used {
+            let server_names = &mut report.certificates[index].server_names;
+            for name in &names {
+                if !server_names.contains(name) {
+                    server_names.push(name.clone());
+                }
+            }
+        }
+
+        for name in names {
+            let coverage = check_name(&mut report, &name, &used);
+            report.names.push(ServerNameCoverage {
+                name,
+                line: directive.span.line,
+                coverage,
+                certificates: used
+                    .iter()
+                    .map(|&(i, _)| report.certificates[i].path.clone())
+                    .collect(),
+            });
+        }
+    });
+
+    report
+}
+
+fn is_tls(server: &Directive) -> bool {
+    extract::listens(server)
+        .iter()
+        .any(|listen| listen.ssl || listen.is_quic())
+}
+
+/// Host names a server answers for that a certificate can be checked
+/// against
+fn server_names(server: &Directive) -> Vec<String> {
+    let mut names = Vec::new();
+    for name in server
+        .find_children("server_name")
+        .iter()
+        .flat_map(|d| d.args_as_strings())
+    {
+        let name = name.to_ascii_lowercase();
+        if name.is_empty() || name == "_" || name.starts_with('~') || name.ends_with('*') {
+            continue;
+        }
+        let expanded = match name.strip_prefix('.') {
+            Some(domain) => vec![domain.to_string(), format!("*.{domain}")],
+            None => vec![name],
+        };
+        for name in expanded {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+    }
+    names
+}
+
+/// Index of the certificate in the report, adding it on first use;
+/// `None` for paths only known at runtime
+fn certificate_index(
+    report: &mut CertificateCoverageReport,
+    directive: &Directive,
+    base_dir: &Path,
+) -> Option<usize> {
+    let path = directive.first_arg()?;
+    if is_runtime_path(&path) {
+        return None;
+    }
+    let resolved = crate::paths::resolve(base_dir, &path);
+    if let Some(index) = report
+        .certificates
+        .iter()
+        .position(|c| c.resolved == resolved)
+    {
+        return Some(index);
+    }
+
+    let names = std::fs::read(&resolved)
+        .ok()
+        .and_then(|contents| pem::certificate_names(&String::from_utf8_lossy(&contents)));
+    report.certificates.push(CertificateNames {
+        path,
+        resolved,
+        line: directive.span.line,
+        names,
+        server_names: Vec::new(),
+    });
+    Some(report.certificates.len() - 1)
+}
+
+/// Coverage of `name` by the certificates at `used`, reporting each
+/// certificate that does not cover it on its `ssl_certificate`
+fn check_name(
+    report: &mut CertificateCoverageReport,
+    name: &str,
+    used: &[(usize, &Directive)],
+) -> NameCoverage {
+    let mut coverage = NameCoverage::Unknown;
+    for &(index, directive) in used {
+        let certificate = &report.certificates[index];
+        let Some(names) = &certificate.names else {
+            continue;
+        };
+
+        let found = if names.iter().any(|n| n.eq_ignore_ascii_case(name)) {
+            NameCoverage::Exact
+        } else if names.iter().any(|n| wildcard_matches(n, name)) {
+            NameCoverage::Wildcard
+        } else {
+            NameCoverage::Uncovered
+        };
+        coverage = match (coverage, found) {
+            (NameCoverage::Uncovered, _) | (_, NameCoverage::Uncovered) => NameCoverage::Uncovered,
+            (NameCoverage::Wildcard, _) | (_, NameCoverage::Wildcard) => NameCoverage::Wildcard,
+            _ => NameCoverage::Exact,
+        };
+        if found != NameCoverage::Uncovered {
+            continue;
+        }
+
+        let apex = format!("*.{name}");
+        let finding = if names.iter().any(|n| n.eq_ignore_ascii_case(&apex)) {
+            Finding::new(
+                "wildcard_apex",
+                Severity::Warning,
+                format!(
+                    "{name} is served with wildcard certificate {} ({apex}), which does not \
+                     cover the apex domain",
+                    certificate.path
+                ),
+            )
+            .with_help(format!(
+                "Add {name} to the certificate's subjectAltName or serve it with another \
+                 certificate"
+            ))
+        } else {
+            Finding::new(
+                "certificate_name_mismatch",
+                Severity::Warning,
+                format!("certificate {} does not cover {name}", certificate.path),
+            )
+            .with_help(if names.is_empty() {
+                "The certificate has no DNS names in its subjectAltName".to_string()
+            } else {
+                format!(
+                    "The certificate covers {}; a wildcard matches one label only",
+                    names.join(", ")
+                )
+            })
+        };
+
+        // Certificates inherited from http are reported once
+        let finding = finding.at(directive);
+        if !report
+            .findings
+            .iter()
+            .any(|f| f.span == finding.span && f.message == finding.message)
+        {
+            report.findings.push(finding);
+        }
+    }
+    coverage
+}
+
+/// Whether the certificate name `pattern` is a wildcard covering `host`
+fn wildcard_matches(pattern: &str, host: &str) -> bool {
+    let Some(domain) = pattern.strip_prefix("*.") else {
+        return false;
+    };
+    host.split_once('.').is_some_and(|(label, rest)| {
+        !label.is_empty() && label != "*" && rest.eq_ignore_ascii_case(domain)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+    use std::fs;
+
+    const WILDCARD_CERT: &str = "-----BEGIN CERTIFICATE-----
+MIIBnDCCAUOgAwIBAgIUJoGjr9qWe2DEpB45SgjzkxjoCIowCgYIKoZIzj0EAwIw
+FjEUMBIGA1UEAwwLZXhhbXBsZS5jb20wIBcNMjYxMDE1MTQwNTAyWhgPMjEyNjA5
+MjExNDA1MDJaMBYxFDASBgNVBAMMC2V4YW1wbGUuY29tMFkwEwYHKoZIzj0CAQYI
+KoZIzj0DAQcDQgAEt4v/jKk/ltvnuz56otHZSp3yBolwB9jLRV8mix+dOfrCU50j
+MQtqan7QYwG7iW7ou2U5eyG7Z9jLrrXPvn6MWKNtMGswHQYDVR0OBBYEFPxgvShG
+M/XmKhQc3RtXAIdEMOpuMB8GA1UdIwQYMBaAFPxgvShGM/XmKhQc3RtXAIdEMOpu
+MA8GA1UdEwEB/wQFMAMBAf8wGAYDVR0RBBEwD4INKi5leGFtcGxlLmNvbTAKBggq
+hkjOPQQDAgNHADBEAiBRMhHZ9M77MtXq1AV1C/W4TsZxp64xquXYALkAsanomgIg
+FuNY1PrhddTlB7oaWH/hOI91Hs384uaWSwQmkZZkS2Y=
+-----END CERTIFICATE-----
+";
+
+    const EXACT_CERT: &str = "-----BEGIN CERTIFICATE-----
+MIIBqzCCAVKgAwIBAgIUWFgtFdbil3mxg69Qe0X6MzirI5owCgYIKoZIzj0EAwIw
+FjEUMBIGA1UEAwwLZXhhbXBsZS5vcmcwIBcNMjYxMDE1MTQwNTAyWhgPMjEyNjA5
+MjExNDA1MDJaMBYxFDASBgNVBAMMC2V4YW1wbGUub3JnMFkwEwYHKoZIzj0CAQYI
+KoZIzj0DAQcDQgAEDroYjqplwvh7WtDrmlTzRP1R22x5GcLSt64dYBltQod+X5Ao
+2HWSyElPERsU/h0QiT807Up44CriPW4iJcqBfqN8MHowHQYDVR0OBBYEFDtdw6kn
+g83UtlS7oy66Lr0bTzxUMB8GA1UdIwQYMBaAFDtdw6kng83UtlS7oy66Lr0bTzxU
+MA8GA1UdEwEB/wQFMAMBAf8wJwYDVR0RBCAwHoILZXhhbXBsZS5vcmeCD3d3dy5l
+eGFtcGxlLm9yZzAKBggqhkjOPQQDAgNHADBEAiBa9S04qBEDiYm/kOmEBxA0svU+
+dlVkiY8lVT+3/JYtgQIgEE3P22QlycVTSTXAb0fuYwtoHEJg/rt/HJTsA3dcuuQ=
+-----END CERTIFICATE-----
+";
+
+    #[test]
+    fn test_certificate_coverage() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("wildcard.crt"), WILDCARD_CERT).unwrap();
+        fs::write(dir.path().join("exact.crt"), EXACT_CERT).unwrap();
+
+        let config = parse(
+            "http {
+               ssl_certificate wildcard.crt;
+               server {
+                 listen 443 ssl;
+                 server_name www.example.com example.com a.b.example.com;
+               }
+               server {
+                 listen 443 ssl;
+                 server_name .example.org;
+                 ssl_certificate exact.crt;
+               }
+               server { listen 80; server_name plain.example.net; }
+             }",
+        )
+        .unwrap();
+        let report = certificate_coverage(&config, dir.path());
+
+        assert_eq!(report.certificates.len(), 2);
+        assert!(report.certificates[0].is_wildcard());
+        assert_eq!(
+            report.certificates[1].names.as_deref(),
+            Some(&["example.org".to_string(), "www.example.org".to_string()][..])
+        );
+
+        let names: Vec<_> = report
+            .names
+            .iter()
+            .map(|n| (n.name.as_str(), n.coverage))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("www.example.com", NameCoverage::Wildcard),
+                ("example.com", NameCoverage::Uncovered),
+                ("a.b.example.com", NameCoverage::Uncovered),
+                ("example.org", NameCoverage::Exact),
+                ("*.example.org", NameCoverage::Uncovered),
+            ]
+        );
+
+        let findings: Vec<_> = report
+            .findings
+            .iter()
+            .map(|f| (f.rule.as_str(), f.line().unwrap()))
+            .collect();
+        assert_eq!(
+            findings,
+            vec![
+                ("wildcard_apex", 2),
+                ("certificate_name_mismatch", 2),
+                ("certificate_name_mismatch", 10),
+            ]
+        );
+    }
+
+    /// Certificate for CN=e without a `subjectAltName`
+    const NO_SAN_CERT: &str = "-----BEGIN CERTIFICATE-----
+MIIBLjCB4aADAgECAhRsOJhrWdcQcQmtW3++Utp/3MP3XTAFBgMrZXAwDDEKMAgG
+A1UEAwwBZTAgFw0yNjEwMTUxNzQwMTlaGA8yMTI2MDkyMTE3NDAxOVowDDEKMAgG
+A1UEAwwBZTAqMAUGAytlcAMhACLelLGEEwamS2AEGuEEvZHx14ssMjgu2RpecDwQ
+XNd1o1MwUTAdBgNVHQ4EFgQUITRwf/9j1HOKR5Nb4EZxGNzL27EwHwYDVR0jBBgw
+FoAUITRwf/9j1HOKR5Nb4EZxGNzL27EwDwYDVR0TAQH/BAUwAwEB/zAFBgMrZXAD
+QQBgAYN0sAQ8lUmb2z1QRGkRWYXH0TZ4zq5splbfiRkpnHcBgY1Eokft0qnsAQGg
+/BJN4aV3guQWxQaiou6xFOQE
+-----END CERTIFICATE-----
+";
+
+    #[test]
+    fn test_wildcard_matches() {
+        assert!(wildcard_matches("*.Example.COM", "WWW.example.com"));
+        assert!(!wildcard_matches("*.example.com", ".example.com"));
+        assert!(!wildcard_matches("*.example.com", "*.example.com"));
+        assert!(!wildcard_matches("*.example.com", "example.com"));
+        assert!(!wildcard_matches("*.*.example.com", "a.b.example.com"));
+        assert!(!wildcard_matches("www.example.com", "www.example.com"));
+        assert!(!wildcard_matches("*", "localhost"));
+    }
+
+    fn edge_case_report() -> CertificateCoverageReport {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("wildcard.crt"), WILDCARD_CERT).unwrap();
+        fs::write(dir.path().join("exact.crt"), EXACT_CERT).unwrap();
+        fs::write(dir.path().join("no_san.crt"), NO_SAN_CERT).unwrap();
+        fs::write(dir.path().join("garbage.crt"), "not a certificate").unwrap();
+
+        let config = parse(
+            "http {
+               server {
+                 listen 443 quic;
+                 server_name _ ~^www\\d+\\.example\\.com$ www.* \"\" WWW.Example.com www.example.com;
+                 ssl_certificate wildcard.crt;
+                 ssl_certificate ./exact.crt;
+               }
+               server {
+                 listen 443 ssl;
+                 server_name www.example.org;
+                 ssl_certificate exact.crt;
+                 ssl_certificate garbage.crt;
+                 ssl_certificate /etc/ssl/$ssl_server_name.crt;
+               }
+               server {
+                 listen 443 ssl;
+                 server_name e;
+                 ssl_certificate no_san.crt;
+               }
+               server { listen 443 ssl; server_name dynamic.example.com; ssl_certificate data:$cert; }
+               server { listen 443 ssl; server_name nocert.example.com; }
+             }",
+        )
+        .unwrap();
+        certificate_coverage(&config, dir.path())
+    }
+
+    #[test]
+    fn test_certificate_coverage_edge_cases() {
+        let report = edge_case_report();
+
+        // `./exact.crt` and `exact.crt` are one certificate; runtime paths
+        // are not listed
+        let certificates: Vec<_> = report
+            .certificates
+            .iter()
+            .map(|c| {
+                (
+                    c.path.as_str(),
+                    c.line,
+                    c.names.is_some(),
+                    c.server_names.clone(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            certificates,
+            vec![
+                ("wildcard.crt", 5, true, vec!["www.example.com".to_string()]),
+                (
+                    "./exact.crt",
+                    6,
+                    true,
+                    vec!["www.example.com".to_string(), "www.example.org".to_string()]
+                ),
+                (
+                    "garbage.crt",
+                    12,
+                    false,
+                    vec!["www.example.org".to_string()]
+                ),
+                ("no_san.crt", 18, true, vec!["e".to_string()]),
+            ]
+        );
+        assert!(!report.certificates[1].is_wildcard());
+        assert!(!report.certificates[2].is_wildcard());
+
+        // Only names that can be checked, lower-cased and once; a name is
+        // uncovered when one certificate misses it, and an unreadable
+        // certificate is left out
+        let names: Vec<_> = report
+            .names
+            .iter()
+            .map(|n| (n.name.as_str(), n.coverage, n.certificates.len()))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("www.example.com", NameCoverage::Uncovered, 2),
+                ("www.example.org", NameCoverage::Exact, 2),
+                ("e", NameCoverage::Uncovered, 1),
+                ("dynamic.example.com", NameCoverage::Unknown, 0),
+            ]
+        );
+        assert_eq!(NameCoverage::Uncovered.to_string(), "not covered");
+    }
+
+    #[test]
+    fn test_certificate_coverage_findings_edge_cases() {
+        let report = edge_case_report();
+        let findings: Vec<_> = report
+            .findings
+            .iter()
+            .map(|f| {
+                (
+                    f.line().unwrap(),
+                    f.message.as_str(),
+                    f.help.as_deref().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            findings,
+            vec![
+                (
+                    6,
+                    "certificate ./exact.crt does not cover www.example.com",
+                    "The certificate covers example.org, www.example.org; a wildcard matches one \
+                     label only"
+                ),
+                (
+                    18,
+                    "certificate no_san.crt does not cover e",
+                    "The certificate has no DNS names in its subjectAltName"
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,372 @@
+//! Routing coverage of a set of test requests
+//!
+//! [`coverage`] routes every request with the [routing
+//! simulator](crate::route) and counts, for each `http` server and each of
+//! its locations, how many requests reached it. A location counts as
+//! exercised when a request ends up in it or passes through it before a
+//! `rewrite ... last`; nested locations count for their enclosing ones
+//! too. What no request reaches is routing nobody tests:
+//!
+//! - `untested_server`: no request is handled by the server
+//! - `untested_location`: no request reaches the location
+//!
+//! Named locations (`@fallback`) are only entered through `try_files`,
+//! `error_page` and the like, which the simulator does not follow, so they
+//! are listed but not reported.
+
+use crate::ast::{Config, Directive};
+use crate::lint::{walk, Finding, Severity};
+use crate::route::{self, Outcome, SimulatedRequest};
+use crate::types::Server;
+
+/// A server and the requests it handled
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ServerCoverage {
+    /// First `server_name` (`_` if none)
+    pub name: String,
+
+    /// Line of the server block
+    pub line: usize,
+
+    /// Requests handled by the server
+    pub requests: usize,
+}
+
+/// A location and the requests that reached it
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LocationCoverage {
+    /// Modifier and path, as written
+    pub pattern: String,
+
+    /// Line of the location block
+    pub line: usize,
+
+    /// Name of the enclosing server
+    pub server: String,
+
+    /// Line of the enclosing server block
+    pub server_line: usize,
+
+    /// Requests that reached it
+    pub requests: usize,
+}
+
+impl LocationCoverage {
+    /// Whether this is a named location (`@name`)
+    #[must_use]
+    pub fn is_named(&self) -> bool {
+        self.pattern.starts_with('@')
+    }
+}
+
+/// A request that no server handles
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UnroutedRequest {
+    /// The request as a URL
+    pub url: String,
+
+    /// Why it was not routed
+    pub reason: String,
+}
+
+/// Result of [`coverage`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CoverageReport {
+    /// Number of requests routed
+    pub requests: usize,
+
+    /// Every `http` server, in file order
+    pub servers: Vec<ServerCoverage>,
+
+    /// Every location, in file order
+    pub locations: Vec<LocationCoverage>,
+
+    /// Requests no server handles, or that are not valid URLs
+    pub unrouted: Vec<UnroutedRequest>,
+
+    /// Untested servers and locations (`untested_server`,
+    /// `untested_location`)
+    pub findings: Vec<Finding>,
+}
+
+impl CoverageReport {
+    /// Share of servers and non-named locations reached by at least one
+    /// request, from 0.0 to 1.0 (1.0 when there are none)
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn ratio(&self) -> f64 {
+        let counts = self.servers.iter().map(|s| s.requests).chain(
+            self.locations
+                .iter()
+                .filter(|l| !l.is_named())
+                .map(|l| l.requests),
+        );
+        let (total, covered) = counts.fold((0_usize, 0_usize), |(total, covered), requests| {
+            (total + 1, covered + usize::from(requests > 0))
+        });
+        if total == 0 {
+            1.0
+        } else {
+            covered as f64 / total as f64
+        }
+    }
+}
+
+/// Route `requests` and report which servers and locations they exercise.
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::{analyze::coverage, parse, route::SimulatedRequest};
+///
+/// let config = parse(r"
+/// http {
+///     server {
+///         listen 80;
+///         server_name example.com;
+///         location /api/ { proxy_pass http://127.0.0.1:8080; }
+///         location /admin/ { deny all; }
+///     }
+/// }
+/// ")?;
+/// let requests = [SimulatedRequest::new("example.com", "/api/users")];
+/// let report = coverage(&config, &requests);
+///
+/// assert_eq!(report.locations[0].requests, 1);
+/// assert_eq!(report.findings.len(), 1);
+/// assert!(report.findings[0].message.contains("/admin/"));
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+#[must_use]
+pub fn coverage(config: &Config, requests: &[SimulatedRequest]) -> CoverageReport {
+    let mut report = CoverageReport {
+        requests: requests.len(),
+        ..CoverageReport::default()
+    };
+    let mut server_blocks: Vec<&Directive> = Vec::new();
+    let mut location_blocks: Vec<&Directive> = Vec::new();
+
+    walk(config, &mut |directive, parents| {
+        if !directive.is_block() {
+            return;
+        }
+        if directive.name() == "server" && parents.last().map(|p| p.name()) == Some("http") {
+            report.servers.push(ServerCoverage {
+                name: Server::primary_name_of(directive),
+                line: directive.span.line,
+                requests: 0,
+            });
+            server_blocks.push(directive);
+        } else if directive.name() == "location" {
+            let Some(server) = parents.iter().rev().find(|p| p.name() == "server") else {
+                return;
+            };
+            report.locations.push(LocationCoverage {
+                pattern: directive.args_as_strings().join(" "),
+                line: directive.span.line,
+                server: Server::primary_name_of(*server),
+                server_line: server.span.line,
+                requests: 0,
+            });
+            location_blocks.push(directive);
+        }
+    });
+
+    for request in requests {
+        let route = match route::simulate(config, request) {
+            Ok(route) => route,
+            Err(e) => {
+                report.unrouted.push(UnroutedRequest {
+                    url: request.url(),
+                    reason: e.to_string(),
+                });
+                continue;
+            }
+        };
+        let Some(server) = &route.server else {
+            let reason = match &route.outcome {
+                Outcome::NoServer => format!("no server listens on port {}", route.url.port),
+                other => other.to_string(),
+            };
+            report.unrouted.push(UnroutedRequest {
+                url: request.url(),
+                reason,
+            });
+            continue;
+        };
+
+        if let Some(entry) = report.servers.iter_mut().find(|s| s.line == server.line) {
+            entry.requests += 1;
+        }
+
+        // Locations left through `rewrite ... last` appear in the steps
+        let mut reached: Vec<usize> = route.locations.iter().map(|l| l.line).collect();
+        reached.extend(
+            route
+                .steps
+                .iter()
+                .filter(|step| step.message.starts_with("location "))
+                .filter_map(|step| step.line),
+        );
+        for location in &mut report.locations {
+            if location.server_line == server.line && reached.contains(&location.line) {
+                location.requests += 1;
+            }
+        }
+    }
+
+    for (entry, block) in report.servers.iter().zip(&server_blocks) {
+        if entry.requests == 0 {
+            report.findings.push(
+                Finding::new(
+                    "untested_server",
+                    Severity::Info,
+                    format!("no test request is handled by server \"{}\"", entry.name),
+                )
+                .at(block)
+                .with_help("Add a request for one of its names and ports"),
+            );
+        }
+    }
+    for (entry, block) in report.locations.iter().zip(&location_blocks) {
+        if entry.requests == 0 && !entry.is_named() {
+            report.findings.push(
+                Finding::new(
+                    "untested_location",
+                    Severity::Info,
+                    format!(
+                        "no test request reaches location {} in server \"{}\"",
+                        entry.pattern, entry.server
+                    ),
+                )
+                .at(block)
+                .with_help("Add a request matching it, or remove it if it is unreachable"),
+            );
+        }
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_coverage() {
+        let config = parse(
+            r"http {
+                server {
+                  listen 80;
+                  server_name example.com;
+                  location /old/ { rewrite ^/old/(.*)$ /new/$1 last; }
+                  location /new/ {
+                    location ~ \.png$ { expires 1d; }
+                  }
+                  location = /health { return 200; }
+                  location @fallback { return 404; }
+                }
+                server { listen 80; server_name other.example.com; }
+              }",
+        )
+        .unwrap();
+        let requests: Vec<SimulatedRequest> = [
+            "example.com/old/page",
+            "example.com/new/logo.png",
+            "http://example.com:8080/",
+        ]
+        .iter()
+        .map(|r| r.parse().unwrap())
+        .collect();
+
+        let report = coverage(&config, &requests);
+
+        let counts: Vec<_> = report
+            .locations
+            .iter()
+            .map(|l| (l.pattern.as_str(), l.requests))
+            .collect();
+        assert_eq!(
+            counts,
+            vec![
+                ("/old/", 1),
+                ("/new/", 2),
+                (r"~ \.png$", 1),
+                ("= /health", 0),
+                ("@fallback", 0),
+            ]
+        );
+        assert_eq!(report.servers[0].requests, 2);
+        assert_eq!(report.unrouted.len(), 1);
+        assert_eq!(report.unrouted[0].reason, "no server listens on port 8080");
+
+        let lines: Vec<_> = report
+            .findings
+            .iter()
+            .map(|f| (f.rule.as_str(), f.line().unwrap()))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![("untested_server", 12), ("untested_location", 9)]
+        );
+        assert!((report.ratio() - 4.0 / 6.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_coverage_edge_cases() {
+        let report = coverage(&parse("").unwrap(), &[]);
+        assert_eq!(report, CoverageReport::default());
+        assert!((report.ratio() - 1.0).abs() < f64::EPSILON);
+
+        // Stream servers are not listed
+        let config = parse(
+            "stream { server { listen 53; } }
+http {
+  server {
+    listen 80;
+    location / { }
+    location @named { }
+  }
+  server { listen 80; server_name example.com; }
+}",
+        )
+        .unwrap();
+        let untested = coverage(&config, &[]);
+        assert_eq!(untested.servers.len(), 2);
+        assert_eq!(untested.servers[0].name, "_");
+        assert_eq!(untested.locations.len(), 2);
+        assert_eq!(untested.locations[1].server, "_");
+        assert_eq!(untested.findings.len(), 3);
+        assert!(untested.ratio().abs() < f64::EPSILON);
+
+        // Requests count for the server that handles them, not by name
+        let requests = [
+            SimulatedRequest::new("example.com", "/"),
+            SimulatedRequest::new("unknown.example", "/a"),
+            SimulatedRequest::new("example.com:http", "/"),
+            SimulatedRequest::new("example.com:8080", "/"),
+        ];
+        let report = coverage(&config, &requests);
+        assert_eq!(report.requests, 4);
+        let counts: Vec<_> = report.servers.iter().map(|s| s.requests).collect();
+        assert_eq!(counts, vec![1, 1]);
+        assert_eq!(report.locations[0].requests, 1);
+
+        let unrouted: Vec<_> = report.unrouted.iter().map(|u| u.url.as_str()).collect();
+        assert_eq!(
+            unrouted,
+            vec!["http://example.com:http/", "http://example.com:8080/"]
+        );
+        assert!(report.unrouted[0].reason.contains("invalid port"));
+        assert_eq!(report.unrouted[1].reason, "no server listens on port 8080");
+
+        // The named location is listed but neither reported nor counted
+        assert!(report.findings.is_empty());
+        assert!((report.ratio() - 1.0).abs() < f64::EPSILON);
+    }
+}
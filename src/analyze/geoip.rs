@@ -0,0 +1,506 @@
+//! `GeoIP` databases and the variables they define
+//!
+//! `geoip2 /path/db.mmdb { $var <lookup path>; }` (the third-party
+//! `ngx_http_geoip2_module`) loads a `MaxMind` database at startup and
+//! defines one variable per lookup. The legacy `geoip_country`,
+//! `geoip_city` and `geoip_org` directives load a `GeoIP` database and
+//! define a fixed set of `$geoip_*` variables. A missing database stops
+//! nginx from starting, and a variable no database defines fails
+//! `nginx -t` with "unknown variable" — typically in the `map` or `if`
+//! that blocks or allows countries.
+//!
+//! [`geoip_audit`] lists every database with its variables and reports:
+//!
+//! - `geoip_database_missing`: the database file does not exist
+//! - `geoip_undefined_variable`: a `$geoip_*` or `$geoip2_*` variable is
+//!   referenced but no loaded database defines it
+//! - `geoip_unused_variable`: a `geoip2` variable is never referenced, so
+//!   the lookup is configured for nothing
+
+use crate::ast::{Config, ConfigIndex, ConfigPath, Directive, Value};
+use crate::lint::{walk, Finding, Severity};
+use std::path::{Path, PathBuf};
+
+/// Variables defined by `geoip_country`
+const COUNTRY_VARIABLES: &[&str] = &[
+    "geoip_country_code",
+    "geoip_country_code3",
+    "geoip_country_name",
+];
+
+/// Variables defined by `geoip_city`
+const CITY_VARIABLES: &[&str] = &[
+    "geoip_area_code",
+    "geoip_city_continent_code",
+    "geoip_city_country_code",
+    "geoip_city_country_code3",
+    "geoip_city_country_name",
+    "geoip_dma_code",
+    "geoip_latitude",
+    "geoip_longitude",
+    "geoip_region",
+    "geoip_region_name",
+    "geoip_city",
+    "geoip_postal_code",
+];
+
+/// Variables defined by `geoip_org`
+const ORG_VARIABLES: &[&str] = &["geoip_org"];
+
+/// A variable a `GeoIP` database defines
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GeoipVariable {
+    /// Variable name, with its `$`
+    pub name: String,
+
+    /// Line defining it (the database directive for legacy variables)
+    pub line: usize,
+
+    /// Lookup path in the database (`country iso_code`), empty for legacy
+    /// variables
+    pub lookup: String,
+
+    /// `default=` value, if any
+    pub default: Option<String>,
+
+    /// Whether any directive references the variable
+    pub used: bool,
+}
+
+/// A `geoip2`, `geoip_country`, `geoip_city` or `geoip_org` database
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GeoipDatabase {
+    /// Directive loading it
+    pub directive: String,
+
+    /// Path of the directive, in `http` or `stream`
+    pub config_path: ConfigPath,
+
+    /// Database path as written in the configuration
+    pub path: String,
+
+    /// Path resolved against the base directory
+    pub resolved: PathBuf,
+
+    /// Whether the file exists, `None` for paths built from variables
+    pub exists: Option<bool>,
+
+    /// Line of the directive
+    pub line: usize,
+
+    /// Variables the database defines
+    pub variables: Vec<GeoipVariable>,
+}
+
+/// Result of [`geoip_audit`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GeoipReport {
+    /// Every database, in file order
+    pub databases: Vec<GeoipDatabase>,
+
+    /// Problems found (`geoip_database_missing`, `geoip_undefined_variable`,
+    /// `geoip_unused_variable`)
+    pub findings: Vec<Finding>,
+}
+
+/// List the `GeoIP` databases, check that they exist and that the variables
+/// they define match the ones the configuration uses.
+///
+/// Relative database paths are resolved against `base_dir`.
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::{analyze::geoip_audit, parse};
+///
+/// let config = parse(r"
+/// http {
+///     geoip2 /no/such/GeoLite2-Country.mmdb {
+///         $geoip2_country_code country iso_code;
+///     }
+///     map $geoip2_country_code $blocked { default 0; CN 1; }
+///     server { if ($geoip2_city_name = Paris) { return 403; } }
+/// }
+/// ")?;
+/// let report = geoip_audit(&config, "/etc/nginx");
+///
+/// assert_eq!(report.databases[0].exists, Some(false));
+/// assert!(report.databases[0].variables[0].used);
+///
+/// let rules: Vec<_> = report.findings.iter().map(|f| f.rule.as_str()).collect();
+/// assert_eq!(rules, vec!["geoip_database_missing", "geoip_undefined_variable"]);
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+#[must_use]
+pub fn geoip_audit(config: &Config, base_dir: impl AsRef<Path>) -> GeoipReport {
+    let base_dir = base_dir.as_ref();
+    let index = config.index();
+    let mut report = GeoipReport::default();
+    let mut defined: Vec<String> = Vec::new();
+    let mut references: Vec<(String, &Directive)> = Vec::new();
+    let mut lookups: Vec<&Directive> = Vec::new();
+
+    walk(config, &mut |directive, _| {
+        if let Some(database) = database(&index, directive, base_dir) {
+            if database.exists == Some(false) {
+                report.findings.push(
+                    Finding::new(
+                        "geoip_database_missing",
+                        Severity::Error,
+                        format!(
+                            "{} database {} does not exist",
+                            database.directive, database.path
+                        ),
+                    )
+                    .at_indexed(&index, directive)
+                    .with_help("Fix the path or download the database (e.g. with geoipupdate)"),
+                );
+            }
+            report.databases.push(database);
+        }
+        if directive.name() == "geoip2" {
+            lookups.extend(
+                directive
+                    .children()
+                    .unwrap_or_default()
+                    .iter()
+                    .filter(|child| child.name().starts_with('$')),
+            );
+        }
+
+        let (definitions, uses) = variables(directive);
+        defined.extend(definitions);
+        references.extend(uses.into_iter().map(|name| (name, directive)));
+    });
+
+    for variable in report
+        .databases
+        .iter_mut()
+        .flat_map(|database| &mut database.variables)
+    {
+        let name = &variable.name[1..];
+        variable.used = references.iter().any(|(used, _)| used == name);
+    }
+
+    let known = |name: &str| {
+        defined.iter().any(|d| d == name)
+            || report
+                .databases
+                .iter()
+                .flat_map(|database| &database.variables)
+                .any(|variable| &variable.name[1..] == name)
+    };
+    let mut reported: Vec<&str> = Vec::new();
+    for (name, directive) in &references {
+        let geoip = name.starts_with("geoip_") || name.starts_with("geoip2_");
+        if !geoip || known(name) || reported.contains(&name.as_str()) {
+            continue;
+        }
+        reported.push(name);
+        report.findings.push(
+            Finding::new(
+                "geoip_undefined_variable",
+                Severity::Error,
+                format!(
+                    "{} references ${name}, which no loaded GeoIP database defines",
+                    directive.name()
+                ),
+            )
+            .at_indexed(&index, directive)
+            .with_help(format!(
+                "Define ${name} in a geoip2 block, or load the database providing it"
+            )),
+        );
+    }
+
+    for lookup in lookups {
+        let Some((database, variable)) = report.databases.iter().find_map(|database| {
+            database
+                .variables
+                .iter()
+                .find(|v| v.line == lookup.span.line && v.name == lookup.name())
+                .map(|variable| (database, variable))
+        }) else {
+            continue;
+        };
+        if variable.used {
+            continue;
+        }
+        report.findings.push(
+            Finding::new(
+                "geoip_unused_variable",
+                Severity::Info,
+                format!("{} from {} is never used", variable.name, database.path),
+            )
+            .at_indexed(&index, lookup)
+            .with_help("Remove the lookup, or use the variable in a map or if"),
+        );
+    }
+
+    report
+}
+
+fn database(
+    index: &ConfigIndex<'_>,
+    directive: &Directive,
+    base_dir: &Path,
+) -> Option<GeoipDatabase> {
+    let legacy = match directive.name() {
+        "geoip2" => None,
+        "geoip_country" => Some(COUNTRY_VARIABLES),
+        "geoip_city" => Some(CITY_VARIABLES),
+        "geoip_org" => Some(ORG_VARIABLES),
+        _ => return None,
+    };
+    let path = directive.args().first()?.unquoted();
+    let resolved = base_dir.join(&path);
+    let line = directive.span.line;
+
+    let variables = match legacy {
+        Some(names) => names
+            .iter()
+            .map(|name| GeoipVariable {
+                name: format!("${name}"),
+                line,
+                lookup: String::new(),
+                default: None,
+                used: false,
+            })
+            .collect(),
+        None => directive
+            .children()
+            .unwrap_or_default()
+            .iter()
+            .filter(|child| child.name().starts_with('$'))
+            .map(|child| {
+                let args: Vec<String> = child.args().iter().map(Value::unquoted).collect();
+                GeoipVariable {
+                    name: child.name().to_string(),
+                    line: child.span.line,
+                    lookup: args
+                        .iter()
+                        .filter(|arg| !arg.starts_with("default=") && !arg.starts_with("source="))
+                        .cloned()
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                    default: args
+                        .iter()
+                        .find_map(|arg| arg.strip_prefix("default="))
+                        .map(str::to_string),
+                    used: false,
+                }
+            })
+            .collect(),
+    };
+
+    Some(GeoipDatabase {
+        directive: directive.name().to_string(),
+        config_path: index
+            .id_of(directive)
+            .map(|id| index.config_path(id))
+            .unwrap_or_default(),
+        exists: (!path.contains('$')).then(|| resolved.is_file()),
+        path,
+        resolved,
+        line,
+        variables,
+    })
+}
+
+/// Variables `directive` defines and the ones it references, without `$`
+fn variables(directive: &Directive) -> (Vec<String>, Vec<String>) {
+    let args = directive.args();
+    // Argument defining a variable, for directives that take one
+    let definition = match directive.name() {
+        "set" | "auth_request_set" | "perl_set" | "js_set" => Some(0),
+        "map" => Some(1),
+        "geo" | "split_clients" => args.len().checked_sub(1),
+        _ => None,
+    };
+
+    let mut defined = Vec::new();
+    let mut used = Vec::new();
+    for (i, arg) in args.iter().enumerate() {
+        let names = arg.variables().into_iter().map(str::to_string);
+        if Some(i) == definition {
+            defined.extend(names);
+        } else {
+            used.extend(names);
+        }
+    }
+    (defined, used)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_geoip_audit() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("GeoLite2-City.mmdb"), b"").unwrap();
+        let config = parse(
+            r"
+http {
+    geoip2 GeoLite2-City.mmdb {
+        auto_reload 60m;
+        $geoip2_country_code default=US source=$remote_addr country iso_code;
+        $geoip2_city_name city names en;
+    }
+    geoip_country GeoIP.dat;
+    map $geoip2_country_code $allowed_country {
+        default yes;
+        CN no;
+    }
+    server {
+        if ($allowed_country = no) { return 403; }
+        if ($geoip2_continent = EU) { return 451; }
+        add_header X-Country $geoip_country_code;
+        add_header X-Region $geoip_region;
+    }
+}
+",
+        )
+        .unwrap();
+        let report = geoip_audit(&config, dir.path());
+
+        let databases: Vec<_> = report
+            .databases
+            .iter()
+            .map(|d| (d.directive.as_str(), d.exists, d.variables.len()))
+            .collect();
+        assert_eq!(
+            databases,
+            vec![("geoip2", Some(true), 2), ("geoip_country", Some(false), 3)]
+        );
+        let country = &report.databases[0].variables[0];
+        assert_eq!(country.name, "$geoip2_country_code");
+        assert_eq!(country.lookup, "country iso_code");
+        assert_eq!(country.default.as_deref(), Some("US"));
+        assert!(country.used);
+
+        // Unused legacy variables come with the directive and are not
+        // reported
+        let findings: Vec<_> = report
+            .findings
+            .iter()
+            .map(|f| {
+                (
+                    f.rule.as_str(),
+                    f.line(),
+                    f.path.as_ref().unwrap().to_string(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            findings,
+            vec![
+                (
+                    "geoip_database_missing",
+                    Some(8),
+                    "http/geoip_country".to_string()
+                ),
+                (
+                    "geoip_undefined_variable",
+                    Some(15),
+                    "http/server/if[2]".to_string()
+                ),
+                (
+                    "geoip_undefined_variable",
+                    Some(17),
+                    "http/server/add_header[2]".to_string()
+                ),
+                (
+                    "geoip_unused_variable",
+                    Some(6),
+                    "http/geoip2/$geoip2_city_name".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_geoip_audit_edge_cases() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("GeoIPCity.dat")).unwrap();
+        let org = dir.path().join("GeoIPOrg.dat");
+        std::fs::write(&org, b"").unwrap();
+        let config = parse(&format!(
+            r"
+stream {{
+    geoip2 /var/lib/$db.mmdb {{ $geoip2_asn autonomous_system_number; }}
+    server {{ listen 53; proxy_pass $geoip2_asn; }}
+}}
+http {{
+    geoip_city GeoIPCity.dat;
+    geoip_org {};
+    set $geoip_override 1;
+    server {{
+        add_header X-Override $geoip_override;
+        add_header X-Org $geoip_org;
+        add_header X-City $geoip_city;
+        add_header X-Asn $geoip2_isp;
+        add_header X-Asn-Again $geoip2_isp;
+    }}
+}}
+",
+            org.display()
+        ))
+        .unwrap();
+        let report = geoip_audit(&config, dir.path());
+
+        // A path built from a variable is not checked; a directory is not a
+        // database; absolute paths ignore the base directory
+        let databases: Vec<_> = report
+            .databases
+            .iter()
+            .map(|d| (d.directive.as_str(), d.config_path.to_string(), d.exists))
+            .collect();
+        assert_eq!(
+            databases,
+            vec![
+                ("geoip2", "stream/geoip2".to_string(), None),
+                ("geoip_city", "http/geoip_city".to_string(), Some(false)),
+                ("geoip_org", "http/geoip_org".to_string(), Some(true)),
+            ]
+        );
+        assert_eq!(report.databases[2].resolved, org);
+        assert!(report.databases[0].variables[0].used);
+
+        // Variables set by the configuration are not GeoIP lookups; an
+        // undefined variable is reported once
+        let findings: Vec<_> = report
+            .findings
+            .iter()
+            .map(|f| (f.rule.as_str(), f.line()))
+            .collect();
+        assert_eq!(
+            findings,
+            vec![
+                ("geoip_database_missing", Some(7)),
+                ("geoip_undefined_variable", Some(14)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_geoip_audit_without_databases() {
+        let config =
+            parse("http { server { add_header X-Country $geoip_country_code; } }").unwrap();
+        let report = geoip_audit(&config, "/etc/nginx");
+        assert!(report.databases.is_empty());
+        assert_eq!(report.findings.len(), 1);
+        assert!(report.findings[0]
+            .message
+            .contains("references $geoip_country_code, which no loaded GeoIP database defines"));
+
+        assert_eq!(
+            geoip_audit(&parse("http { geoip_country; }").unwrap(), "/"),
+            GeoipReport::default()
+        );
+    }
+}
@@ -0,0 +1,476 @@
+//! gRPC proxying
+//!
+//! `grpc_pass` forwards gRPC calls, which only travel over HTTP/2: a client
+//! reaching a server without HTTP/2 on its listener gets an HTTP/1.1 error
+//! instead of a gRPC status. The backend connection is configured apart
+//! from the client one: `grpc://` speaks cleartext HTTP/2 (h2c) and
+//! `grpcs://` TLS, and the `grpc_ssl_*` directives only apply to the
+//! latter. Streaming calls are cut off when the backend sends nothing for
+//! `grpc_read_timeout` (60 seconds by default).
+//!
+//! [`grpc_audit`] lists every `grpc_pass` location with its effective
+//! settings and reports:
+//!
+//! - `grpc_http2`: the server has no HTTP/2 listener (`listen ... http2` or
+//!   `http2 on`)
+//! - `grpc_read_timeout`: `grpc_read_timeout` is left at its 60 second
+//!   default, or set shorter
+//! - `grpc_tls`: the backend scheme disagrees with the rest of the backend
+//!   configuration: `grpc://` with `grpc_ssl_*` directives or to port 443,
+//!   `grpcs://` to port 80
+
+use crate::ast::{parse_duration, Config, Directive, Value};
+use crate::extract;
+use crate::lint::{walk, Finding, Severity};
+use crate::types::{Server, Upstream};
+use std::time::Duration;
+
+/// `grpc_read_timeout` when not set
+const DEFAULT_READ_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// One `grpc_pass` location
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GrpcLocation {
+    /// First `server_name` of the enclosing server (`_` if none)
+    pub server_name: String,
+
+    /// Location path/pattern
+    pub location: String,
+
+    /// Line of the `grpc_pass` directive
+    pub line: usize,
+
+    /// `grpc_pass` target as written
+    pub target: String,
+
+    /// Whether the backend is reached over TLS (`grpcs://`)
+    pub tls: bool,
+
+    /// Upstream the target names, if any
+    pub upstream: Option<String>,
+
+    /// Backend ports: the target's, or those of the upstream servers
+    pub backend_ports: Vec<u16>,
+
+    /// Whether the server accepts HTTP/2 from clients
+    pub http2: bool,
+
+    /// Effective `grpc_read_timeout` as written, `None` for the default
+    pub read_timeout: Option<String>,
+
+    /// Effective `grpc_ssl_*` directives
+    pub ssl_directives: Vec<String>,
+}
+
+impl GrpcLocation {
+    /// Effective `grpc_read_timeout`, `None` when the value cannot be parsed
+    #[must_use]
+    pub fn read_timeout(&self) -> Option<Duration> {
+        self.read_timeout
+            .as_deref()
+            .map_or(Some(DEFAULT_READ_TIMEOUT), parse_duration)
+    }
+}
+
+/// Result of [`grpc_audit`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GrpcReport {
+    /// Every `grpc_pass` location, in file order
+    pub locations: Vec<GrpcLocation>,
+
+    /// Problems found (`grpc_http2`, `grpc_read_timeout`, `grpc_tls`)
+    pub findings: Vec<Finding>,
+}
+
+/// Check every `grpc_pass` location for HTTP/2, timeout and backend TLS
+/// problems.
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::{analyze::grpc_audit, parse};
+///
+/// let config = parse(r"
+/// http {
+///     upstream orders { server 10.0.0.1:443; }
+///     server {
+///         listen 443 ssl;
+///         location /orders.Orders/ { grpc_pass grpc://orders; }
+///     }
+/// }
+/// ")?;
+/// let report = grpc_audit(&config);
+///
+/// assert_eq!(report.locations[0].backend_ports, vec![443]);
+/// assert!(!report.locations[0].http2);
+///
+/// let rules: Vec<_> = report.findings.iter().map(|f| f.rule.as_str()).collect();
+/// assert_eq!(rules, vec!["grpc_http2", "grpc_read_timeout", "grpc_tls"]);
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+#[must_use]
+pub fn grpc_audit(config: &Config) -> GrpcReport {
+    let upstreams: Vec<Upstream> = extract::upstreams(config)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|u| !u.stream)
+        .collect();
+    let mut report = GrpcReport::default();
+
+    walk(config, &mut |directive, parents| {
+        if directive.name() != "grpc_pass" {
+            return;
+        }
+        let Some(location) = grpc_location(directive, parents, &upstreams) else {
+            return;
+        };
+        report.findings.extend(check_location(directive, &location));
+        report.locations.push(location);
+    });
+
+    report
+}
+
+fn grpc_location(
+    directive: &Directive,
+    parents: &[&Directive],
+    upstreams: &[Upstream],
+) -> Option<GrpcLocation> {
+    let target = directive.args().first()?.unquoted();
+    // Innermost block first
+    let chain: Vec<&Directive> = parents.iter().rev().copied().collect();
+    let effective = |name: &str| -> Option<String> {
+        chain
+            .iter()
+            .find_map(|block| block.find_children(name).last().copied())
+            .and_then(|d| d.args().first().map(Value::unquoted))
+    };
+    let server = chain.iter().find(|p| p.name() == "server");
+
+    let (tls, address) = if let Some(rest) = target.strip_prefix("grpcs://") {
+        (true, rest)
+    } else {
+        (false, target.strip_prefix("grpc://").unwrap_or(&target))
+    };
+    let upstream = upstreams.iter().find(|u| u.name == address);
+    let backend_ports = match upstream {
+        Some(upstream) => upstream
+            .servers
+            .iter()
+            .map(|s| port(&s.address, tls))
+            .collect(),
+        None if address.starts_with("unix:") || address.contains('$') => Vec::new(),
+        None => vec![port(address, tls)],
+    };
+
+    let http2 = server
+        .is_some_and(|server| extract::listens(server).iter().any(|listen| listen.http2))
+        || effective("http2").as_deref() == Some("on");
+
+    let mut ssl_directives: Vec<String> = Vec::new();
+    for block in &chain {
+        for child in block.children().unwrap_or_default() {
+            let name = child.name();
+            if name.starts_with("grpc_ssl_") && !ssl_directives.iter().any(|d| d == name) {
+                ssl_directives.push(name.to_string());
+            }
+        }
+    }
+
+    Some(GrpcLocation {
+        server_name: Server::primary_name_of(server.copied()),
+        location: chain
+            .iter()
+            .find(|p| p.name() == "location")
+            .map(|l| l.args_as_strings().join(" "))
+            .unwrap_or_default(),
+        line: directive.span.line,
+        tls,
+        upstream: upstream.map(|u| u.name.clone()),
+        backend_ports,
+        http2,
+        read_timeout: effective("grpc_read_timeout"),
+        ssl_directives,
+        target,
+    })
+}
+
+/// Port of a `host[:port]` address, defaulting to the scheme's
+fn port(address: &str, tls: bool) -> u16 {
+    address
+        .rsplit_once(':')
+        .filter(|(host, _)| !host.ends_with(':'))
+        .and_then(|(_, port)| port.parse().ok())
+        .unwrap_or(if tls { 443 } else { 80 })
+}
+
+fn check_location(directive: &Directive, location: &GrpcLocation) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let at = format!(
+        "location \"{}\" in server \"{}\"",
+        location.location, location.server_name
+    );
+
+    if !location.http2 {
+        findings.push(
+            Finding::new(
+                "grpc_http2",
+                Severity::Error,
+                format!("{at} passes to gRPC, but the server does not accept HTTP/2"),
+            )
+            .at(directive)
+            .with_help(
+                "Add http2 to the listen directive, or \"http2 on;\" in the server (nginx \
+                 1.25.1+)",
+            ),
+        );
+    }
+
+    match (&location.read_timeout, location.read_timeout()) {
+        (None, _) => findings.push(
+            Finding::new(
+                "grpc_read_timeout",
+                Severity::Info,
+                format!(
+                    "{at} uses the default grpc_read_timeout of 60s: streaming calls idle \
+                     for longer are closed"
+                ),
+            )
+            .at(directive)
+            .with_help("Set grpc_read_timeout to the longest idle period of a stream"),
+        ),
+        (Some(value), Some(timeout)) if timeout < DEFAULT_READ_TIMEOUT => findings.push(
+            Finding::new(
+                "grpc_read_timeout",
+                Severity::Warning,
+                format!(
+                    "{at} sets grpc_read_timeout {value}, shorter than the 60s default: \
+                     streaming calls idle for longer are closed"
+                ),
+            )
+            .at(directive)
+            .with_help("Set grpc_read_timeout to the longest idle period of a stream"),
+        ),
+        _ => {}
+    }
+
+    if location.tls {
+        if location.backend_ports.contains(&80) {
+            findings.push(
+                Finding::new(
+                    "grpc_tls",
+                    Severity::Warning,
+                    format!(
+                        "{at} connects with TLS (grpcs://) to port 80 of {}",
+                        location.target
+                    ),
+                )
+                .at(directive)
+                .with_help("Use grpc:// for a cleartext backend, or the backend's TLS port"),
+            );
+        }
+    } else {
+        let mut reasons = Vec::new();
+        if !location.ssl_directives.is_empty() {
+            reasons.push(format!(
+                "{} only apply to grpcs://",
+                location.ssl_directives.join(", ")
+            ));
+        }
+        if location.backend_ports.contains(&443) {
+            reasons.push(format!("{} listens on port 443", location.target));
+        }
+        if !reasons.is_empty() {
+            findings.push(
+                Finding::new(
+                    "grpc_tls",
+                    Severity::Warning,
+                    format!(
+                        "{at} connects without TLS (grpc://), but {}",
+                        reasons.join(" and ")
+                    ),
+                )
+                .at(directive)
+                .with_help("Use grpcs:// for a TLS backend"),
+            );
+        }
+    }
+
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_grpc_audit() {
+        let config = parse(
+            r"
+http {
+    grpc_read_timeout 1h;
+    upstream users { server 10.0.0.2:50051; server 10.0.0.3:50051; }
+    server {
+        listen 443 ssl http2;
+        server_name api.example.com;
+        location /users.Users/ { grpc_pass grpc://users; }
+        location /billing.Billing/ {
+            grpc_ssl_verify on;
+            grpc_pass billing.internal:443;
+        }
+        location /stream.Stream/ {
+            grpc_read_timeout 30s;
+            grpc_pass grpcs://stream.internal:80;
+        }
+    }
+    server {
+        listen 8080;
+        http2 on;
+        location / { grpc_pass unix:/run/app.sock; }
+    }
+}
+",
+        )
+        .unwrap();
+        let report = grpc_audit(&config);
+
+        let locations: Vec<_> = report
+            .locations
+            .iter()
+            .map(|l| {
+                (
+                    l.location.as_str(),
+                    l.tls,
+                    l.upstream.as_deref(),
+                    l.backend_ports.clone(),
+                    l.http2,
+                )
+            })
+            .collect();
+        assert_eq!(
+            locations,
+            vec![
+                (
+                    "/users.Users/",
+                    false,
+                    Some("users"),
+                    vec![50051, 50051],
+                    true
+                ),
+                ("/billing.Billing/", false, None, vec![443], true),
+                ("/stream.Stream/", true, None, vec![80], true),
+                ("/", false, None, vec![], true),
+            ]
+        );
+        assert_eq!(
+            report.locations[2].read_timeout(),
+            Some(Duration::from_secs(30))
+        );
+
+        let findings: Vec<_> = report
+            .findings
+            .iter()
+            .map(|f| (f.rule.as_str(), f.line()))
+            .collect();
+        assert_eq!(
+            findings,
+            vec![
+                ("grpc_tls", Some(11)),
+                ("grpc_read_timeout", Some(15)),
+                ("grpc_tls", Some(15)),
+            ]
+        );
+        assert!(report.findings[0].message.ends_with(
+            "connects without TLS (grpc://), but grpc_ssl_verify only apply to grpcs:// and \
+             billing.internal:443 listens on port 443"
+        ));
+    }
+
+    #[test]
+    fn test_grpc_audit_edge_cases() {
+        let config = parse(
+            r"
+stream {
+    upstream users { server 10.0.0.2:443; }
+}
+http {
+    server {
+        listen 80;
+        grpc_read_timeout forever;
+        location /users.Users/ { grpc_pass grpc://users; }
+        location /v6/ { grpc_pass grpcs://[::1]:50051; }
+        location /v6-default/ { grpc_pass grpcs://[::1]; }
+        location /dynamic/ { grpc_pass grpc://$backend; }
+        location /empty/ { grpc_pass; }
+    }
+}
+",
+        )
+        .unwrap();
+        let report = grpc_audit(&config);
+
+        // A `grpc_pass` without a target is skipped; stream upstreams are not
+        // gRPC backends, so `users` is a host name with the default port
+        let locations: Vec<_> = report
+            .locations
+            .iter()
+            .map(|l| {
+                (
+                    l.location.as_str(),
+                    l.upstream.as_deref(),
+                    l.backend_ports.clone(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            locations,
+            vec![
+                ("/users.Users/", None, vec![80]),
+                ("/v6/", None, vec![50051]),
+                ("/v6-default/", None, vec![443]),
+                ("/dynamic/", None, vec![]),
+            ]
+        );
+
+        // An unparseable timeout is not reported as short; without HTTP/2
+        // every location is an error
+        assert_eq!(report.locations[0].read_timeout(), None);
+        assert!(report
+            .findings
+            .iter()
+            .all(|f| f.rule == "grpc_http2" && f.severity == Severity::Error));
+        assert_eq!(report.findings.len(), 4);
+    }
+
+    #[test]
+    fn test_grpc_read_timeout_severity() {
+        let config = parse(
+            r"
+http {
+    server {
+        listen 443 ssl http2;
+        location /default/ { grpc_pass grpcs://a:443; }
+        location /short/ { grpc_read_timeout 10s; grpc_pass grpcs://a:443; }
+        location /same/ { grpc_read_timeout 1m; grpc_pass grpcs://a:443; }
+    }
+}
+",
+        )
+        .unwrap();
+        let findings: Vec<_> = grpc_audit(&config)
+            .findings
+            .iter()
+            .map(|f| (f.rule.clone(), f.severity, f.line()))
+            .collect();
+        assert_eq!(
+            findings,
+            vec![
+                ("grpc_read_timeout".to_string(), Severity::Info, Some(5)),
+                ("grpc_read_timeout".to_string(), Severity::Warning, Some(6)),
+            ]
+        );
+    }
+}
@@ -0,0 +1,392 @@
+//! `add_header` inheritance
+//!
+//! nginx inherits `add_header` from the enclosing block only when a block
+//! defines none of its own: a single `add_header` in a location drops every
+//! header set in its server and in `http`. The configuration reads as if the
+//! location added one header, which is why the dropped ones go unnoticed.
+//!
+//! [`header_inheritance`] follows the headers into every server, location
+//! and `if` block, lists the blocks that drop inherited headers and reports:
+//!
+//! - `header_inheritance`: a block drops an inherited security header
+//!   (`Strict-Transport-Security`, `Content-Security-Policy`,
+//!   `X-Frame-Options`, ...) by declaring its own `add_header` without
+//!   repeating it
+
+use crate::ast::{Config, ConfigIndex, ConfigPath, Directive, Value};
+use crate::lint::{walk, Finding, Severity};
+use crate::types::Server;
+
+/// Response headers whose loss weakens the site's security, lowercase
+pub const SECURITY_HEADERS: &[&str] = &[
+    "strict-transport-security",
+    "content-security-policy",
+    "content-security-policy-report-only",
+    "x-frame-options",
+    "x-content-type-options",
+    "referrer-policy",
+    "permissions-policy",
+    "cross-origin-opener-policy",
+    "cross-origin-embedder-policy",
+    "cross-origin-resource-policy",
+    "x-xss-protection",
+];
+
+/// A block whose own `add_header` drops inherited headers
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HeaderOverride {
+    /// First `server_name` of the enclosing server (`_` if none)
+    pub server_name: String,
+
+    /// Path of the block: a `server`, `location` or `if`
+    pub path: ConfigPath,
+
+    /// Line of the block
+    pub line: usize,
+
+    /// Path of the block the dropped headers are set in
+    pub inherited_from: ConfigPath,
+
+    /// Line of that block
+    pub inherited_line: usize,
+
+    /// Headers the block adds itself
+    pub own: Vec<String>,
+
+    /// Inherited headers it does not repeat, as written
+    pub dropped: Vec<String>,
+}
+
+impl HeaderOverride {
+    /// Dropped headers that are security headers
+    pub fn dropped_security(&self) -> impl Iterator<Item = &String> {
+        self.dropped
+            .iter()
+            .filter(|name| SECURITY_HEADERS.contains(&name.to_ascii_lowercase().as_str()))
+    }
+}
+
+/// Result of [`header_inheritance`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HeaderReport {
+    /// Blocks that drop inherited headers, in file order
+    pub overrides: Vec<HeaderOverride>,
+
+    /// Problems found (`header_inheritance`)
+    pub findings: Vec<Finding>,
+}
+
+/// Find blocks whose own `add_header` silently drops the headers of the
+/// blocks around them.
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::{analyze::header_inheritance, parse};
+///
+/// let config = parse(r#"
+/// server {
+///     server_name example.com;
+///     add_header Strict-Transport-Security "max-age=63072000" always;
+///     add_header X-Frame-Options DENY;
+///     location /api/ {
+///         add_header Cache-Control no-store;
+///     }
+///     location /static/ {
+///         add_header Cache-Control public;
+///         add_header Strict-Transport-Security "max-age=63072000" always;
+///         add_header X-Frame-Options DENY;
+///     }
+/// }
+/// "#)?;
+/// let report = header_inheritance(&config);
+///
+/// assert_eq!(report.overrides.len(), 1);
+/// assert_eq!(report.overrides[0].path.to_string(), "server/location");
+/// assert_eq!(
+///     report.overrides[0].dropped,
+///     ["Strict-Transport-Security", "X-Frame-Options"]
+/// );
+/// assert_eq!(report.findings[0].rule, "header_inheritance");
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+#[must_use]
+pub fn header_inheritance(config: &Config) -> HeaderReport {
+    let index = config.index();
+    let path = |directive: &Directive| {
+        index
+            .id_of(directive)
+            .map(|id| index.config_path(id))
+            .unwrap_or_default()
+    };
+    let mut report = HeaderReport::default();
+
+    walk(config, &mut |directive, parents| {
+        let is_block = matches!(directive.name(), "server" | "location" | "if");
+        let http = !parents
+            .iter()
+            .any(|p| matches!(p.name(), "stream" | "mail"));
+        if !is_block || !directive.is_block() || !http {
+            return;
+        }
+        let own = header_names(directive);
+        if own.is_empty() {
+            return;
+        }
+        // The innermost enclosing block with headers of its own is what
+        // the block would otherwise inherit
+        let Some(outer) = parents
+            .iter()
+            .rev()
+            .find(|p| !p.find_children("add_header").is_empty())
+        else {
+            return;
+        };
+
+        let own_lower: Vec<String> = own.iter().map(|n| n.to_ascii_lowercase()).collect();
+        let dropped: Vec<String> = header_names(outer)
+            .into_iter()
+            .filter(|name| !own_lower.contains(&name.to_ascii_lowercase()))
+            .collect();
+        if dropped.is_empty() {
+            return;
+        }
+
+        let server = std::iter::once(directive)
+            .chain(parents.iter().rev().copied())
+            .find(|d| d.name() == "server");
+        let header_override = HeaderOverride {
+            server_name: Server::primary_name_of(server),
+            path: path(directive),
+            line: directive.span.line,
+            inherited_from: path(outer),
+            inherited_line: outer.span.line,
+            own,
+            dropped,
+        };
+        if let Some(finding) = check_override(&index, directive, outer, &header_override) {
+            report.findings.push(finding);
+        }
+        report.overrides.push(header_override);
+    });
+
+    report
+}
+
+/// Names of the headers a block adds, without duplicates
+fn header_names(block: &Directive) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for header in block.find_children("add_header") {
+        if let Some(name) = header.args().first().map(Value::unquoted) {
+            if !names.iter().any(|n| n.eq_ignore_ascii_case(&name)) {
+                names.push(name);
+            }
+        }
+    }
+    names
+}
+
+/// The block as written: `server`, `location <pattern>` or
+/// `if (<condition>)`
+fn label(block: &Directive) -> String {
+    let args: Vec<String> = block.args().iter().map(Value::unquoted).collect();
+    match block.name() {
+        "location" => format!("location {}", args.join(" ")),
+        "if" => format!("if {}", args.join(" ")),
+        name => name.to_string(),
+    }
+}
+
+fn check_override(
+    index: &ConfigIndex<'_>,
+    directive: &Directive,
+    outer: &Directive,
+    header_override: &HeaderOverride,
+) -> Option<Finding> {
+    let security: Vec<&str> = header_override
+        .dropped_security()
+        .map(String::as_str)
+        .collect();
+    if security.is_empty() {
+        return None;
+    }
+    let block = if directive.name() == "server" {
+        format!("server \"{}\"", header_override.server_name)
+    } else {
+        format!(
+            "{} in server \"{}\"",
+            label(directive),
+            header_override.server_name
+        )
+    };
+    Some(
+        Finding::new(
+            "header_inheritance",
+            Severity::Warning,
+            format!(
+                "{block} drops {} set in {} (line {}) by declaring its own add_header",
+                security.join(", "),
+                label(outer),
+                header_override.inherited_line
+            ),
+        )
+        .at_indexed(index, directive)
+        .with_help(format!(
+            "Repeat {} in this block, or keep the headers in a file included by every block \
+             that adds headers",
+            security.join(", ")
+        )),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_header_inheritance() {
+        let config = parse(
+            r"
+http {
+    add_header X-Content-Type-Options nosniff;
+    server {
+        server_name example.com;
+        add_header X-Request-Id $request_id;
+        location / {
+            if ($arg_debug) { add_header X-Debug 1; }
+        }
+    }
+    server {
+        server_name other.example.com;
+        location /api/ { add_header X-Content-Type-Options nosniff; }
+    }
+}
+",
+        )
+        .unwrap();
+        let report = header_inheritance(&config);
+
+        let blocks: Vec<_> = report
+            .overrides
+            .iter()
+            .map(|o| {
+                (
+                    o.path.to_string(),
+                    o.inherited_from.to_string(),
+                    o.dropped.clone(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            blocks,
+            vec![
+                (
+                    "http/server".to_string(),
+                    "http".to_string(),
+                    vec!["X-Content-Type-Options".to_string()]
+                ),
+                (
+                    "http/server/location/if".to_string(),
+                    "http/server".to_string(),
+                    vec!["X-Request-Id".to_string()]
+                ),
+            ]
+        );
+        // Only the loss of a security header is a finding
+        assert_eq!(report.findings.len(), 1);
+        assert_eq!(
+            report.findings[0].message,
+            "server \"example.com\" drops X-Content-Type-Options set in http (line 2) by \
+             declaring its own add_header"
+        );
+        assert_eq!(
+            report.findings[0].path.as_ref().unwrap().to_string(),
+            "http/server"
+        );
+    }
+
+    #[test]
+    fn test_headers_compare_without_case() {
+        let config = parse(
+            r#"
+server {
+    add_header Strict-Transport-Security "max-age=63072000" always;
+    add_header X-Frame-Options DENY;
+    location / {
+        add_header strict-transport-security "max-age=63072000" always;
+        add_header x-frame-options DENY;
+        add_header X-FRAME-OPTIONS DENY;
+    }
+    location /api/ {
+        add_header Cache-Control no-store;
+        add_header cache-control no-store;
+        add_header X-Frame-Options DENY;
+    }
+}
+"#,
+        )
+        .unwrap();
+        let report = header_inheritance(&config);
+
+        assert_eq!(report.overrides.len(), 1);
+        let api = &report.overrides[0];
+        assert_eq!(api.path.to_string(), "server/location[2]");
+        assert_eq!(api.own, ["Cache-Control", "X-Frame-Options"]);
+        assert_eq!(api.dropped, ["Strict-Transport-Security"]);
+        assert_eq!(api.server_name, "_");
+        assert_eq!(report.findings.len(), 1);
+        assert!(report.findings[0]
+            .message
+            .starts_with("location /api/ in server \"_\" drops Strict-Transport-Security"));
+    }
+
+    #[test]
+    fn test_inherits_from_nearest_block_with_headers() {
+        let config = parse(
+            r"
+http {
+    add_header X-Frame-Options DENY;
+    server {
+        server_name example.com;
+        location / {
+            location /nested/ { add_header X-Debug 1; }
+        }
+    }
+}
+stream {
+    server { add_header X-Frame-Options DENY; }
+}
+",
+        )
+        .unwrap();
+        let report = header_inheritance(&config);
+
+        // The server and the outer location add nothing and inherit;
+        // the nested location drops what http sets
+        assert_eq!(report.overrides.len(), 1);
+        let nested = &report.overrides[0];
+        assert_eq!(nested.path.to_string(), "http/server/location/location");
+        assert_eq!(
+            (nested.inherited_from.to_string(), nested.inherited_line),
+            ("http".to_string(), 2)
+        );
+        assert_eq!(nested.server_name, "example.com");
+        assert_eq!(report.findings[0].line(), Some(7));
+    }
+
+    #[test]
+    fn test_blocks_without_inherited_headers() {
+        for text in [
+            "server { location / { add_header X-Debug 1; } }",
+            "server { add_header X-Debug 1; location / { add_header X-Debug 2; } }",
+            "server { add_header X-Debug 1; location / { add_header; } }",
+            "",
+        ] {
+            let report = header_inheritance(&parse(text).unwrap());
+            assert_eq!(report, HeaderReport::default(), "{text}");
+        }
+    }
+}
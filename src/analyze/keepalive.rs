@@ -0,0 +1,331 @@
+//! Upstream keepalive audit
+//!
+//! `keepalive N` in an upstream block only keeps connections open when the
+//! locations using the upstream allow it:
+//!
+//! - `proxy_pass` needs `proxy_http_version 1.1` (the default is 1.0) and
+//!   a cleared `Connection` header (`proxy_set_header Connection "";`),
+//!   since nginx sends `Connection: close` by default
+//! - `fastcgi_pass` needs `fastcgi_keep_conn on`
+//!
+//! Otherwise every request opens a new connection to the backend and the
+//! `keepalive` pool stays empty. [`keepalive_audit`] follows nginx
+//! inheritance (`proxy_set_header` is inherited only by blocks that define
+//! none) and reports each such location as `upstream_keepalive`.
+
+use crate::ast::{Config, Directive, Value};
+use crate::extract;
+use crate::lint::{walk, Finding, Severity};
+use crate::types::{Server, Upstream};
+
+/// How one location uses an upstream
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UpstreamUse {
+    /// First `server_name` of the enclosing server (`_` if none)
+    pub server_name: String,
+
+    /// Location path/pattern
+    pub location: String,
+
+    /// Line of the `proxy_pass` or `fastcgi_pass` directive
+    pub line: usize,
+
+    /// Upstream name
+    pub upstream: String,
+
+    /// `proxy_pass` or `fastcgi_pass`
+    pub directive: String,
+
+    /// Effective `proxy_http_version` (`1.0` when not set), `None` for
+    /// `fastcgi_pass`
+    pub http_version: Option<String>,
+
+    /// Effective `Connection` header value, `None` when nginx sends its
+    /// default (`close`) or for `fastcgi_pass`
+    pub connection_header: Option<String>,
+
+    /// Whether `fastcgi_keep_conn on` applies, `None` for `proxy_pass`
+    pub keep_conn: Option<bool>,
+
+    /// Whether connections to the upstream are reused
+    pub reuses_connections: bool,
+}
+
+/// Result of [`keepalive_audit`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KeepaliveReport {
+    /// Every `http` upstream
+    pub upstreams: Vec<Upstream>,
+
+    /// Every location proxying to one of the upstreams
+    pub uses: Vec<UpstreamUse>,
+
+    /// Locations that prevent reuse of a keepalive upstream
+    pub findings: Vec<Finding>,
+}
+
+impl KeepaliveReport {
+    /// Upstreams configured with `keepalive`
+    pub fn keepalive_upstreams(&self) -> impl Iterator<Item = &Upstream> {
+        self.upstreams.iter().filter(|u| u.has_keepalive())
+    }
+}
+
+/// Check that locations using keepalive upstreams let nginx reuse the
+/// connections.
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::{analyze::keepalive_audit, parse};
+///
+/// let config = parse(r#"
+/// http {
+///     upstream app { server 10.0.0.1:8080; keepalive 32; }
+///     server {
+///         location /api {
+///             proxy_pass http://app;
+///             proxy_http_version 1.1;
+///             proxy_set_header Connection "";
+///         }
+///         location / { proxy_pass http://app; }
+///     }
+/// }
+/// "#)?;
+/// let report = keepalive_audit(&config);
+///
+/// assert!(report.uses[0].reuses_connections);
+/// assert!(!report.uses[1].reuses_connections);
+/// assert_eq!(report.findings.len(), 2);
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+#[must_use]
+pub fn keepalive_audit(config: &Config) -> KeepaliveReport {
+    let mut report = KeepaliveReport {
+        upstreams: extract::upstreams(config)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|u| !u.stream)
+            .collect(),
+        ..KeepaliveReport::default()
+    };
+
+    walk(config, &mut |directive, parents| {
+        let Some(name) = target_upstream(directive) else {
+            return;
+        };
+        let Some(upstream) = report.upstreams.iter().find(|u| u.name == name) else {
+            return;
+        };
+
+        let usage = upstream_use(directive, parents, upstream);
+        if upstream.has_keepalive() {
+            report.findings.extend(check_use(directive, &usage));
+        }
+        report.uses.push(usage);
+    });
+
+    report
+}
+
+/// Upstream name a `proxy_pass` or `fastcgi_pass` points at
+pub(super) fn target_upstream(directive: &Directive) -> Option<String> {
+    let target = directive.args().first()?.unquoted();
+    let host = match directive.name() {
+        "proxy_pass" => target
+            .strip_prefix("http://")
+            .or_else(|| target.strip_prefix("https://"))?,
+        "fastcgi_pass" => target.as_str(),
+        _ => return None,
+    };
+    let host = host.split(['/', ':']).next()?;
+    (!host.is_empty() && !host.contains('$')).then(|| host.to_string())
+}
+
+fn upstream_use(directive: &Directive, parents: &[&Directive], upstream: &Upstream) -> UpstreamUse {
+    // Innermost block first
+    let chain: Vec<&Directive> = parents.iter().rev().copied().collect();
+    let effective = |name: &str| -> Option<String> {
+        chain
+            .iter()
+            .find_map(|block| block.find_children(name).last().copied())
+            .and_then(|d| d.args().first().map(Value::unquoted))
+    };
+
+    let server_name = Server::primary_name_of(chain.iter().find(|p| p.name() == "server").copied());
+    let location = chain
+        .iter()
+        .find(|p| p.name() == "location")
+        .map(|l| l.args_as_strings().join(" "))
+        .unwrap_or_default();
+
+    let (http_version, connection_header, keep_conn) = if directive.name() == "proxy_pass" {
+        (
+            Some(effective("proxy_http_version").unwrap_or_else(|| "1.0".to_string())),
+            connection_header(&chain),
+            None,
+        )
+    } else {
+        (
+            None,
+            None,
+            Some(effective("fastcgi_keep_conn").as_deref() == Some("on")),
+        )
+    };
+
+    let reuses_connections = upstream.has_keepalive()
+        && match keep_conn {
+            Some(keep_conn) => keep_conn,
+            None => {
+                http_version.as_deref() == Some("1.1") && keeps_open(connection_header.as_deref())
+            }
+        };
+
+    UpstreamUse {
+        server_name,
+        location,
+        line: directive.span.line,
+        upstream: upstream.name.clone(),
+        directive: directive.name().to_string(),
+        http_version,
+        connection_header,
+        keep_conn,
+        reuses_connections,
+    }
+}
+
+/// `proxy_set_header` is inherited only by blocks that define none of their
+/// own
+fn connection_header(chain: &[&Directive]) -> Option<String> {
+    let headers = chain
+        .iter()
+        .map(|block| block.find_children("proxy_set_header"))
+        .find(|headers| !headers.is_empty())?;
+
+    headers.iter().rev().find_map(|header| match header.args() {
+        [name, value, ..] if name.as_str().eq_ignore_ascii_case("connection") => {
+            Some(value.unquoted())
+        }
+        _ => None,
+    })
+}
+
+/// Whether a `Connection` header value lets nginx keep the connection:
+/// set, and not `close`
+fn keeps_open(connection_header: Option<&str>) -> bool {
+    connection_header.is_some_and(|value| !value.eq_ignore_ascii_case("close"))
+}
+
+fn check_use(directive: &Directive, usage: &UpstreamUse) -> Vec<Finding> {
+    let place = format!(
+        "location \"{}\" in server \"{}\"",
+        usage.location, usage.server_name
+    );
+    let finding = |message: String, help: &str| {
+        Finding::new("upstream_keepalive", Severity::Warning, message)
+            .at(directive)
+            .with_help(help)
+    };
+    let mut findings = Vec::new();
+
+    if usage.keep_conn == Some(false) {
+        findings.push(finding(
+            format!(
+                "{place} uses keepalive upstream \"{}\" without fastcgi_keep_conn, so \
+                 connections are closed after every request",
+                usage.upstream
+            ),
+            "Add \"fastcgi_keep_conn on;\"",
+        ));
+    }
+    if let Some(version) = usage.http_version.as_deref().filter(|v| *v != "1.1") {
+        findings.push(finding(
+            format!(
+                "{place} proxies to keepalive upstream \"{}\" over HTTP/{version}, so \
+                 connections are closed after every request",
+                usage.upstream
+            ),
+            "Add \"proxy_http_version 1.1;\"",
+        ));
+    }
+    if usage.directive == "proxy_pass" && !keeps_open(usage.connection_header.as_deref()) {
+        findings.push(finding(
+            format!(
+                "{place} proxies to keepalive upstream \"{}\" without clearing the Connection \
+                 header, so nginx sends \"Connection: close\"",
+                usage.upstream
+            ),
+            "Add 'proxy_set_header Connection \"\";' (proxy_set_header in a location replaces \
+             all inherited ones)",
+        ));
+    }
+
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_keepalive_audit() {
+        let config = parse(
+            r#"
+http {
+    upstream app { server 10.0.0.1:8080; keepalive 16; }
+    upstream php { server unix:/run/php.sock; keepalive 8; }
+    upstream legacy { server 10.0.0.9:80; }
+    proxy_http_version 1.1;
+    proxy_set_header Connection "";
+    server {
+        server_name example.com;
+        location / { proxy_pass http://app/; }
+        location /headers {
+            proxy_set_header Host $host;
+            proxy_pass http://app;
+        }
+        location ~ \.php$ { fastcgi_pass php; }
+        location /old { proxy_pass http://legacy; }
+        location /dyn { proxy_pass http://$backend; }
+    }
+}
+"#,
+        )
+        .unwrap();
+        let report = keepalive_audit(&config);
+
+        assert_eq!(report.upstreams.len(), 3);
+        assert_eq!(report.keepalive_upstreams().count(), 2);
+
+        let uses: Vec<_> = report
+            .uses
+            .iter()
+            .map(|u| {
+                (
+                    u.location.as_str(),
+                    u.upstream.as_str(),
+                    u.reuses_connections,
+                )
+            })
+            .collect();
+        assert_eq!(
+            uses,
+            vec![
+                ("/", "app", true),
+                ("/headers", "app", false),
+                ("~ \\.php$", "php", false),
+                ("/old", "legacy", false),
+            ]
+        );
+        assert_eq!(report.uses[1].http_version.as_deref(), Some("1.1"));
+        assert_eq!(report.uses[1].connection_header, None);
+
+        let lines: Vec<_> = report.findings.iter().map(Finding::line).collect();
+        assert_eq!(lines, vec![Some(13), Some(15)]);
+        assert!(report.findings[0].message.contains("Connection"));
+        assert!(report.findings[1].message.contains("fastcgi_keep_conn"));
+    }
+}
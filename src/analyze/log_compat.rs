@@ -0,0 +1,398 @@
+//! Log format compatibility with downstream parsers
+//!
+//! Log shippers, analyzers and dashboards expect access log lines in a
+//! known shape: the `combined` or `common` line, or a JSON object with a
+//! given set of keys. [`log_format_compat`] checks each `log_format`
+//! against the [`CONSUMERS`] and reports:
+//!
+//! - `log_format_undefined`: an `access_log` names a format defined
+//!   nowhere in the configuration. nginx rejects it, and tools reading the
+//!   configuration assume `combined`
+//! - `log_format_compat`: a format in use that no known consumer reads, or
+//!   a JSON format lacking keys dashboards chart
+//! - `log_format_escape`: a JSON format without `escape=json`, so a quote
+//!   in a header breaks the line for JSON parsers
+
+use crate::ast::{Config, Directive};
+use crate::lint::{walk, Finding, Severity};
+use crate::logs::COMBINED;
+
+/// Common Log Format, `combined` without referer and user agent
+pub const COMMON: &str =
+    "$remote_addr - $remote_user [$time_local] \"$request\" $status $body_bytes_sent";
+
+/// What a downstream consumer expects of a log line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Expectation {
+    /// Lines in this format; fields appended after it are accepted
+    Line(&'static str),
+
+    /// A JSON object with at least these keys
+    JsonKeys(&'static [&'static str]),
+}
+
+/// A kind of log consumer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Consumer {
+    /// Short name
+    pub name: &'static str,
+
+    /// Who reads logs this way
+    pub description: &'static str,
+
+    /// Expected line shape
+    pub expects: Expectation,
+}
+
+/// Well-known consumer expectations
+pub const CONSUMERS: &[Consumer] = &[
+    Consumer {
+        name: "combined",
+        description: "GoAccess, AWStats, fail2ban and the default nginx parsers of log shippers",
+        expects: Expectation::Line(COMBINED),
+    },
+    Consumer {
+        name: "common",
+        description: "Common Log Format readers",
+        expects: Expectation::Line(COMMON),
+    },
+    Consumer {
+        name: "json-dashboard",
+        description: "JSON dashboards charting traffic, status codes and latency",
+        expects: Expectation::JsonKeys(&[
+            "remote_addr",
+            "request_method",
+            "request_uri",
+            "status",
+            "body_bytes_sent",
+            "request_time",
+            "http_referer",
+            "http_user_agent",
+        ]),
+    },
+    Consumer {
+        name: "json-upstream",
+        description: "JSON dashboards breaking latency down per backend",
+        expects: Expectation::JsonKeys(&[
+            "status",
+            "request_time",
+            "upstream_addr",
+            "upstream_status",
+            "upstream_response_time",
+        ]),
+    },
+];
+
+/// Compatibility of one `log_format`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FormatCompat {
+    /// Format name
+    pub name: String,
+
+    /// Line of the `log_format` directive
+    pub line: usize,
+
+    /// `escape=` parameter, if any
+    pub escape: Option<String>,
+
+    /// Keys of a JSON format, `None` for a line format
+    pub json_keys: Option<Vec<String>>,
+
+    /// Names of the [`CONSUMERS`] that read the format
+    pub compatible: Vec<String>,
+
+    /// Number of `access_log` directives using the format
+    pub uses: usize,
+}
+
+impl FormatCompat {
+    /// Whether the format produces JSON objects
+    #[must_use]
+    pub fn is_json(&self) -> bool {
+        self.json_keys.is_some()
+    }
+}
+
+/// Result of [`log_format_compat`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LogCompatReport {
+    /// Every `log_format`, in configuration order
+    pub formats: Vec<FormatCompat>,
+
+    /// Format names used by `access_log` but defined nowhere
+    pub undefined: Vec<String>,
+
+    /// Problems found
+    pub findings: Vec<Finding>,
+}
+
+/// Check `log_format` definitions against what downstream parsers expect.
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::{analyze::log_format_compat, parse};
+///
+/// let config = parse(r#"
+/// http {
+///     log_format timed '$remote_addr - $remote_user [$time_local] "$request" '
+///                      '$status $body_bytes_sent "$http_referer" "$http_user_agent" $request_time';
+///     access_log /var/log/nginx/access.log timed;
+///     server { access_log /var/log/nginx/api.log mian; }
+/// }
+/// "#)?;
+/// let report = log_format_compat(&config);
+///
+/// assert_eq!(report.formats[0].compatible, vec!["combined", "common"]);
+/// assert_eq!(report.undefined, vec!["mian"]);
+/// assert_eq!(report.findings[0].rule, "log_format_undefined");
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+#[must_use]
+pub fn log_format_compat(config: &Config) -> LogCompatReport {
+    let mut definitions: Vec<&Directive> = Vec::new();
+    let mut access_logs: Vec<(&Directive, String)> = Vec::new();
+    walk(config, &mut |directive, _| match directive.name() {
+        "log_format" if directive.args().len() >= 2 => definitions.push(directive),
+        "access_log" => {
+            let args = directive.args_as_strings();
+            // Either `off`, or a path followed by the format name
+            if let Some(format) = args.get(1).filter(|a| !a.contains('=')) {
+                access_logs.push((directive, format.clone()));
+            } else if args.first().is_some_and(|a| a != "off") {
+                access_logs.push((directive, "combined".to_string()));
+            }
+        }
+        _ => {}
+    });
+
+    let mut report = LogCompatReport::default();
+    for directive in definitions {
+        let args = directive.args_as_strings();
+        let uses = access_logs.iter().filter(|(_, f)| *f == args[0]).count();
+        let format = format_compat(&args, directive.span.line, uses);
+        report.findings.extend(check_format(directive, &format));
+        report.formats.push(format);
+    }
+
+    for (directive, name) in &access_logs {
+        if name == "combined" || report.formats.iter().any(|f| f.name == *name) {
+            continue;
+        }
+        if !report.undefined.contains(name) {
+            report.undefined.push(name.clone());
+        }
+        report.findings.push(
+            Finding::new(
+                "log_format_undefined",
+                Severity::Warning,
+                format!("access_log uses log format \"{name}\", which is not defined"),
+            )
+            .at(directive)
+            .with_help(format!(
+                "Define `log_format {name} ...` in the http block, or fix the name; nginx rejects unknown formats and tools reading this configuration assume combined"
+            )),
+        );
+    }
+
+    report
+}
+
+fn format_compat(args: &[String], line: usize, uses: usize) -> FormatCompat {
+    let (escape, pattern) = match args[1].strip_prefix("escape=") {
+        Some(escape) => (Some(escape.to_string()), args[2..].join(" ")),
+        None => (None, args[1..].join(" ")),
+    };
+    let normalized = normalize(&pattern);
+    let json_keys =
+        (normalized.starts_with('{') && normalized.ends_with('}')).then(|| json_keys(&normalized));
+
+    let compatible = CONSUMERS
+        .iter()
+        .filter(|consumer| match (consumer.expects, &json_keys) {
+            (Expectation::Line(expected), None) => {
+                let expected = normalize(expected);
+                normalized == expected || normalized.starts_with(&format!("{expected} "))
+            }
+            (Expectation::JsonKeys(required), Some(keys)) => {
+                required.iter().all(|key| keys.iter().any(|k| k == key))
+            }
+            _ => false,
+        })
+        .map(|consumer| consumer.name.to_string())
+        .collect();
+
+    FormatCompat {
+        name: args[0].clone(),
+        line,
+        escape,
+        json_keys,
+        compatible,
+        uses,
+    }
+}
+
+fn check_format(directive: &Directive, format: &FormatCompat) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let Some(keys) = &format.json_keys else {
+        if format.uses > 0 && format.compatible.is_empty() {
+            findings.push(
+                Finding::new(
+                    "log_format_compat",
+                    Severity::Info,
+                    format!(
+                        "log format \"{}\" is neither combined nor common; log parsers need a custom pattern for it",
+                        format.name
+                    ),
+                )
+                .at(directive)
+                .with_help("Append extra fields after the combined format, or log JSON with escape=json"),
+            );
+        }
+        return findings;
+    };
+
+    if format.escape.as_deref() != Some("json") {
+        findings.push(
+            Finding::new(
+                "log_format_escape",
+                Severity::Warning,
+                format!(
+                    "JSON log format \"{}\" does not set escape=json; quotes in headers produce invalid JSON",
+                    format.name
+                ),
+            )
+            .at(directive)
+            .with_help(format!("log_format {} escape=json '{{...}}';", format.name)),
+        );
+    }
+
+    if format.uses > 0 && format.compatible.is_empty() {
+        // Report the dashboard profile the format comes closest to
+        let closest = CONSUMERS
+            .iter()
+            .filter_map(|consumer| match consumer.expects {
+                Expectation::JsonKeys(required) => Some(
+                    required
+                        .iter()
+                        .filter(|key| !keys.iter().any(|k| k == *key))
+                        .copied()
+                        .collect::<Vec<_>>(),
+                )
+                .map(|missing| (consumer, missing)),
+                Expectation::Line(_) => None,
+            })
+            .min_by_key(|(_, missing)| missing.len());
+        if let Some((consumer, missing)) = closest {
+            findings.push(
+                Finding::new(
+                    "log_format_compat",
+                    Severity::Info,
+                    format!(
+                        "JSON log format \"{}\" lacks keys {} used by {}",
+                        format.name,
+                        missing.join(", "),
+                        consumer.name
+                    ),
+                )
+                .at(directive)
+                .with_help(format!("Add \"{}\": \"${}\"", missing[0], missing[0])),
+            );
+        }
+    }
+    findings
+}
+
+/// Collapse whitespace runs, as format strings split over several
+/// arguments are joined with a space
+fn normalize(pattern: &str) -> String {
+    pattern.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Keys of a JSON object pattern: quoted strings followed by `:`
+fn json_keys(pattern: &str) -> Vec<String> {
+    let mut keys = Vec::new();
+    let mut rest = pattern;
+    while let Some(start) = rest.find('"') {
+        let after = &rest[start + 1..];
+        let Some(end) = after.find('"') else {
+            break;
+        };
+        let tail = after[end + 1..].trim_start();
+        if let Some(next) = tail.strip_prefix(':') {
+            keys.push(after[..end].to_string());
+            rest = next;
+        } else {
+            rest = &after[end + 1..];
+        }
+    }
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_line_formats() {
+        let config = parse(
+            r#"
+http {
+    log_format common_copy '$remote_addr - $remote_user [$time_local] "$request" $status $body_bytes_sent';
+    log_format short '$remote_addr $status';
+    log_format unused '$status';
+    access_log /var/log/a.log common_copy;
+    access_log /var/log/b.log short;
+    access_log /var/log/c.log;
+    access_log off;
+}
+"#,
+        )
+        .unwrap();
+        let report = log_format_compat(&config);
+
+        assert_eq!(report.formats[0].compatible, vec!["common"]);
+        assert!(report.formats[1].compatible.is_empty());
+        assert_eq!(report.formats[1].uses, 1);
+        assert!(report.undefined.is_empty());
+
+        let rules: Vec<_> = report.findings.iter().map(|f| f.rule.as_str()).collect();
+        assert_eq!(rules, vec!["log_format_compat"]);
+        assert_eq!(report.findings[0].line(), Some(4));
+    }
+
+    #[test]
+    fn test_json_formats() {
+        let config = parse(
+            r#"
+http {
+    log_format dash escape=json '{"remote_addr":"$remote_addr","request_method":"$request_method",'
+        '"request_uri":"$request_uri","status":$status,"body_bytes_sent":$body_bytes_sent,'
+        '"request_time":$request_time,"http_referer":"$http_referer","http_user_agent":"$http_user_agent"}';
+    log_format raw '{"status": $status, "upstream_addr": "$upstream_addr"}';
+    access_log /var/log/dash.log dash;
+    access_log /var/log/raw.log raw;
+}
+"#,
+        )
+        .unwrap();
+        let report = log_format_compat(&config);
+
+        assert_eq!(report.formats[0].escape.as_deref(), Some("json"));
+        assert_eq!(report.formats[0].compatible, vec!["json-dashboard"]);
+        assert_eq!(
+            report.formats[1].json_keys,
+            Some(vec!["status".to_string(), "upstream_addr".to_string()])
+        );
+
+        let rules: Vec<_> = report.findings.iter().map(|f| f.rule.as_str()).collect();
+        assert_eq!(rules, vec!["log_format_escape", "log_format_compat"]);
+        assert_eq!(
+            report.findings[1].message,
+            "JSON log format \"raw\" lacks keys request_time, upstream_status, upstream_response_time used by json-upstream"
+        );
+    }
+}
@@ -0,0 +1,536 @@
+//! Access log errors by location
+//!
+//! A spike of 502s in the access log says something is broken, not where.
+//! [`log_error_correlation`] reads the last lines of each access log with
+//! its own format, follows every logged request through the configuration
+//! with [`route`], and counts requests, 4xx and 5xx
+//! responses per matched location and per upstream, and reports:
+//!
+//! - `location_error_rate`: a location with enough requests whose share
+//!   of 5xx responses reaches the threshold
+//!
+//! The request's host comes from `$host`, `$http_host` or `$server_name`
+//! when the format logs one, and otherwise from the server the
+//! `access_log` is defined in; its port from `$server_port`, defaulting
+//! to 80 and then 443. Logging `$host` therefore makes the mapping exact
+//! for logs shared by several servers.
+
+use crate::ast::{Config, Directive};
+use crate::extract;
+use crate::lint::{walk, Finding, Severity};
+use crate::logs::{read_tail, LogParser, LogRecord};
+use crate::route::{route, Outcome};
+use std::collections::HashMap;
+
+/// Options for [`log_error_correlation`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LogErrorOptions {
+    /// Lines read from the end of each log
+    pub lines: usize,
+
+    /// Only count lines logged at or after this time, in seconds since the
+    /// Unix epoch
+    pub since: Option<f64>,
+
+    /// Requests a location needs before `location_error_rate` applies
+    pub min_requests: usize,
+
+    /// Share of 5xx responses at which `location_error_rate` is reported
+    pub error_rate: f64,
+}
+
+impl Default for LogErrorOptions {
+    fn default() -> Self {
+        Self {
+            lines: 10_000,
+            since: None,
+            min_requests: 20,
+            error_rate: 0.05,
+        }
+    }
+}
+
+/// Requests served by one location
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LocationErrors {
+    /// First `server_name` of the server
+    pub server: String,
+
+    /// Innermost matched location as written, `(server)` when no location
+    /// matched
+    pub location: String,
+
+    /// Line of the location, or of the server when no location matched
+    pub line: usize,
+
+    /// Upstream block, or `proxy_pass` target, the location passes to
+    pub upstream: Option<String>,
+
+    /// Logged requests
+    pub requests: usize,
+
+    /// Requests answered with a 4xx status
+    pub client_errors: usize,
+
+    /// Requests answered with a 5xx status
+    pub server_errors: usize,
+}
+
+impl LocationErrors {
+    /// Share of requests answered with a 5xx status
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn server_error_rate(&self) -> f64 {
+        if self.requests == 0 {
+            return 0.0;
+        }
+        self.server_errors as f64 / self.requests as f64
+    }
+
+    /// Share of requests answered with a 4xx status
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn client_error_rate(&self) -> f64 {
+        if self.requests == 0 {
+            return 0.0;
+        }
+        self.client_errors as f64 / self.requests as f64
+    }
+}
+
+/// Requests passed to one upstream, over all its locations
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UpstreamErrors {
+    /// Upstream block, or `proxy_pass` target
+    pub name: String,
+
+    /// Logged requests
+    pub requests: usize,
+
+    /// Requests answered with a 4xx status
+    pub client_errors: usize,
+
+    /// Requests answered with a 5xx status
+    pub server_errors: usize,
+}
+
+/// Result of [`log_error_correlation`]
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LogErrorReport {
+    /// Locations that served logged requests, most 5xx first
+    pub locations: Vec<LocationErrors>,
+
+    /// Upstreams of those locations, most 5xx first
+    pub upstreams: Vec<UpstreamErrors>,
+
+    /// Lines mapped to a location or server
+    pub routed_lines: usize,
+
+    /// Lines that matched their format but could not be routed (no path or
+    /// status logged, or no server for the host and port)
+    pub unrouted_lines: usize,
+
+    /// Locations with a high error rate (`location_error_rate`)
+    pub findings: Vec<Finding>,
+}
+
+/// Where a request was routed, used as the aggregation key
+type RouteKey = (String, String, usize, Option<String>);
+
+/// Count logged requests and their errors per location and upstream.
+///
+/// # Examples
+///
+/// ```no_run
+/// use nginx_discovery::analyze::log_errors::{log_error_correlation, LogErrorOptions};
+///
+/// let config = nginx_discovery::parse(r"
+/// http {
+///     access_log /var/log/nginx/access.log;
+///     server {
+///         listen 80;
+///         server_name example.com;
+///         location /api/ { proxy_pass http://10.0.0.1:8080; }
+///     }
+/// }
+/// ")?;
+/// let report = log_error_correlation(&config, &LogErrorOptions::default());
+/// for location in &report.locations {
+///     println!("{} {}: {} 5xx of {}", location.server, location.location,
+///         location.server_errors, location.requests);
+/// }
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+#[must_use]
+pub fn log_error_correlation(config: &Config, options: &LogErrorOptions) -> LogErrorReport {
+    let formats = extract::log_formats(config).unwrap_or_default();
+    let mut report = LogErrorReport::default();
+    let mut counts: HashMap<RouteKey, (usize, usize, usize)> = HashMap::new();
+    let mut routes: HashMap<(String, u16, String), Option<RouteKey>> = HashMap::new();
+    let mut seen = Vec::new();
+
+    for log in extract::access_logs(config).unwrap_or_default() {
+        let parser = LogParser::for_log(&log, &formats);
+        let default_host = log
+            .server_names
+            .iter()
+            .find(|name| *name != "_")
+            .cloned()
+            .unwrap_or_else(|| "_".to_string());
+
+        for path in log.expanded_paths() {
+            if seen.contains(&path) {
+                continue;
+            }
+            let lines = read_tail(&path, options.lines).unwrap_or_default();
+            seen.push(path);
+
+            for record in lines.iter().filter_map(|line| parser.parse(line)) {
+                if let Some(since) = options.since {
+                    if record.timestamp().map_or(true, |t| t < since) {
+                        continue;
+                    }
+                }
+                let request = logged_request(&record, &default_host);
+                let Some((host, port, uri, status)) = request else {
+                    report.unrouted_lines += 1;
+                    continue;
+                };
+                let key = routes
+                    .entry((host.clone(), port, uri.clone()))
+                    .or_insert_with(|| route_key(config, &host, port, &uri))
+                    .clone();
+                let Some(key) = key else {
+                    report.unrouted_lines += 1;
+                    continue;
+                };
+
+                report.routed_lines += 1;
+                let entry = counts.entry(key).or_default();
+                entry.0 += 1;
+                entry.1 += usize::from((400..500).contains(&status));
+                entry.2 += usize::from(status >= 500);
+            }
+        }
+    }
+
+    let mut upstreams: HashMap<String, UpstreamErrors> = HashMap::new();
+    for ((server, location, line, upstream), (requests, client_errors, server_errors)) in counts {
+        if let Some(name) = &upstream {
+            let entry = upstreams
+                .entry(name.clone())
+                .or_insert_with(|| UpstreamErrors {
+                    name: name.clone(),
+                    requests: 0,
+                    client_errors: 0,
+                    server_errors: 0,
+                });
+            entry.requests += requests;
+            entry.client_errors += client_errors;
+            entry.server_errors += server_errors;
+        }
+        report.locations.push(LocationErrors {
+            server,
+            location,
+            line,
+            upstream,
+            requests,
+            client_errors,
+            server_errors,
+        });
+    }
+    report.locations.sort_by(|a, b| {
+        b.server_errors
+            .cmp(&a.server_errors)
+            .then_with(|| b.requests.cmp(&a.requests))
+            .then_with(|| a.line.cmp(&b.line))
+    });
+    report.upstreams = upstreams.into_values().collect();
+    report.upstreams.sort_by(|a, b| {
+        b.server_errors
+            .cmp(&a.server_errors)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+
+    report.findings = report
+        .locations
+        .iter()
+        .filter(|l| {
+            l.requests >= options.min_requests && l.server_error_rate() >= options.error_rate
+        })
+        .map(|l| error_rate_finding(config, l))
+        .collect();
+    report
+}
+
+/// Host, port, path and status of a logged request
+fn logged_request(record: &LogRecord, default_host: &str) -> Option<(String, u16, String, u16)> {
+    let status = record.get("status")?.parse().ok()?;
+    let uri = crate::logs::request_path(record)?.to_string();
+    let host = ["host", "http_host", "server_name"]
+        .iter()
+        .filter_map(|name| record.get(name))
+        .find(|host| !host.is_empty() && *host != "-")
+        .map_or(default_host, |host| host.split(':').next().unwrap_or(host));
+    let port = record
+        .get("server_port")
+        .and_then(|port| port.parse().ok())
+        .unwrap_or(0);
+    Some((host.to_ascii_lowercase(), port, uri, status))
+}
+
+/// Route a request, trying ports 80 and 443 when the port is unknown
+fn route_key(config: &Config, host: &str, port: u16, uri: &str) -> Option<RouteKey> {
+    let attempts: &[(&str, u16)] = if port == 0 {
+        &[("http", 80), ("https", 443)]
+    } else {
+        &[("http", port)]
+    };
+    attempts.iter().find_map(|(scheme, port)| {
+        let route = route(config, &format!("{scheme}://{host}:{port}{uri}")).ok()?;
+        let server = route.server?;
+        let upstream = match route.outcome {
+            Outcome::Proxy {
+                upstream, target, ..
+            } => Some(upstream.unwrap_or(target)),
+            _ => None,
+        };
+        Some(match route.locations.last() {
+            Some(location) => (
+                server.name,
+                location.pattern.clone(),
+                location.line,
+                upstream,
+            ),
+            None => (server.name, "(server)".to_string(), server.line, upstream),
+        })
+    })
+}
+
+fn error_rate_finding(config: &Config, location: &LocationErrors) -> Finding {
+    let mut finding = Finding::new(
+        "location_error_rate",
+        Severity::Warning,
+        format!(
+            "location {} in server \"{}\" answered {} of {} logged requests ({:.0}%) with a 5xx \
+             status",
+            location.location,
+            location.server,
+            location.server_errors,
+            location.requests,
+            location.server_error_rate() * 100.0
+        ),
+    )
+    .with_help(match &location.upstream {
+        Some(upstream) => format!("Check the health and error logs of {upstream}"),
+        None => "Check the error log for this location".to_string(),
+    });
+
+    let mut directive: Option<&Directive> = None;
+    walk(config, &mut |d, _| {
+        if directive.is_none()
+            && d.span.line == location.line
+            && matches!(d.name(), "location" | "server")
+        {
+            directive = Some(d);
+        }
+    });
+    if let Some(directive) = directive {
+        finding = finding.at(directive);
+    }
+    finding
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+    use std::io::Write;
+
+    #[test]
+    fn test_log_error_correlation() {
+        let dir = tempfile::tempdir().unwrap();
+        let access = dir.path().join("access.log");
+        let mut file = std::fs::File::create(&access).unwrap();
+        for i in 0..40 {
+            let (host, path, status) = match i % 4 {
+                0 => ("api.example.com", "/v1/users?id=7", 502),
+                1 => ("api.example.com", "/v1/users", 200),
+                2 => ("www.example.com", "/missing.html", 404),
+                _ => ("unknown.example.net", "/", 200),
+            };
+            writeln!(file, "{host} \"GET {path} HTTP/1.1\" {status}").unwrap();
+        }
+        writeln!(file, "www.example.com \"-\" 400").unwrap();
+
+        let config = parse(&format!(
+            "http {{\n\
+             log_format vhost '$host \"$request\" $status';\n\
+             access_log {} vhost;\n\
+             upstream api {{ server 10.0.0.1:8080; }}\n\
+             server {{\n\
+                 listen 80;\n\
+                 server_name api.example.com;\n\
+                 location /v1/ {{ proxy_pass http://api; }}\n\
+             }}\n\
+             server {{\n\
+                 listen 80 default_server;\n\
+                 server_name www.example.com;\n\
+                 root /var/www;\n\
+             }}\n\
+             }}",
+            access.display()
+        ))
+        .unwrap();
+        let report = log_error_correlation(&config, &LogErrorOptions::default());
+
+        assert_eq!((report.routed_lines, report.unrouted_lines), (40, 1));
+        let api = &report.locations[0];
+        assert_eq!(
+            (api.server.as_str(), api.location.as_str()),
+            ("api.example.com", "/v1/")
+        );
+        assert_eq!((api.requests, api.server_errors), (20, 10));
+        assert_eq!(api.upstream.as_deref(), Some("api"));
+        assert!((api.server_error_rate() - 0.5).abs() < f64::EPSILON);
+
+        // Unknown hosts fall back to the default server
+        let www = &report.locations[1];
+        assert_eq!(
+            (www.location.as_str(), www.requests, www.client_errors),
+            ("(server)", 20, 10)
+        );
+
+        assert_eq!(report.upstreams.len(), 1);
+        assert_eq!(report.upstreams[0].server_errors, 10);
+        assert_eq!(report.findings.len(), 1);
+        assert_eq!(report.findings[0].rule, "location_error_rate");
+        assert_eq!(report.findings[0].line(), Some(8));
+    }
+
+    #[test]
+    fn test_logged_request() {
+        let parser = LogParser::new("$host $http_host $server_port \"$request\" $status");
+        let request = |line: &str| logged_request(&parser.parse(line).unwrap(), "default.example");
+
+        assert_eq!(
+            request("API.Example.com:8443 - 8443 \"GET /v1?x=1 HTTP/1.1\" 503"),
+            Some(("api.example.com".to_string(), 8443, "/v1".to_string(), 503))
+        );
+        // An empty or `-` host falls through to the next variable, then to
+        // the server of the access_log
+        assert_eq!(
+            request("- www.example.com:80 - \"GET / HTTP/1.1\" 200").map(|r| (r.0, r.1)),
+            Some(("www.example.com".to_string(), 0))
+        );
+        assert_eq!(
+            request("- - 80 \"GET / HTTP/1.1\" 200").map(|r| r.0),
+            Some("default.example".to_string())
+        );
+        // Without a status or a path the request cannot be counted
+        assert_eq!(request("a.example - 80 \"GET / HTTP/1.1\" -"), None);
+        assert_eq!(request("a.example - 80 \"-\" 400"), None);
+    }
+
+    #[test]
+    fn test_log_error_thresholds_and_routing() {
+        let dir = tempfile::tempdir().unwrap();
+        let access = dir.path().join("access.log");
+        let mut file = std::fs::File::create(&access).unwrap();
+        // 19 requests to /a/, two of them 5xx; 21 to /b/, one of them 5xx;
+        // 5 to a port nothing listens on
+        for i in 0..19 {
+            let status = if i < 2 { 500 } else { 200 };
+            writeln!(file, "443 \"GET /a/ HTTP/1.1\" {status}").unwrap();
+        }
+        for i in 0..21 {
+            let status = if i == 0 { 504 } else { 200 };
+            writeln!(file, "- \"GET /b/ HTTP/1.1\" {status}").unwrap();
+        }
+        for _ in 0..5 {
+            writeln!(file, "8080 \"GET /a/ HTTP/1.1\" 200").unwrap();
+        }
+
+        let config = parse(&format!(
+            "http {{\n\
+             log_format ports '$server_port \"$request\" $status';\n\
+             server {{\n\
+                 listen 443 ssl;\n\
+                 server_name secure.example.com;\n\
+                 access_log {} ports;\n\
+                 location /a/ {{ return 200; }}\n\
+                 location /b/ {{ return 200; }}\n\
+             }}\n\
+             }}",
+            access.display()
+        ))
+        .unwrap();
+
+        // The host comes from the server of the access_log; an unknown port
+        // is tried as 80, then 443
+        let report = log_error_correlation(&config, &LogErrorOptions::default());
+        assert_eq!((report.routed_lines, report.unrouted_lines), (40, 5));
+        let locations: Vec<_> = report
+            .locations
+            .iter()
+            .map(|l| {
+                (
+                    l.server.as_str(),
+                    l.location.as_str(),
+                    l.requests,
+                    l.server_errors,
+                )
+            })
+            .collect();
+        assert_eq!(
+            locations,
+            vec![
+                ("secure.example.com", "/a/", 19, 2),
+                ("secure.example.com", "/b/", 21, 1),
+            ]
+        );
+        assert!(report.upstreams.is_empty());
+
+        // /a/ has too few requests, /b/ too few errors
+        assert!(report.findings.is_empty());
+        let options = LogErrorOptions {
+            min_requests: 10,
+            ..Default::default()
+        };
+        let report = log_error_correlation(&config, &options);
+        assert_eq!(report.findings.len(), 1);
+        assert_eq!(report.findings[0].line(), Some(7));
+        assert_eq!(
+            report.findings[0].help.as_deref(),
+            Some("Check the error log for this location")
+        );
+
+        // Lines without a timestamp are outside any time window
+        let options = LogErrorOptions {
+            since: Some(0.0),
+            ..Default::default()
+        };
+        let report = log_error_correlation(&config, &options);
+        assert_eq!((report.routed_lines, report.unrouted_lines), (0, 0));
+        assert!(report.locations.is_empty());
+    }
+
+    #[test]
+    fn test_error_rates_without_requests() {
+        let location = LocationErrors {
+            server: "_".to_string(),
+            location: "/".to_string(),
+            line: 1,
+            upstream: None,
+            requests: 0,
+            client_errors: 0,
+            server_errors: 0,
+        };
+        assert!(location.server_error_rate().abs() < f64::EPSILON);
+        assert!(location.client_error_rate().abs() < f64::EPSILON);
+    }
+}
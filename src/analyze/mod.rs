@@ -0,0 +1,89 @@
+//! Configuration analyzers
+//!
+//! Analyzers look at a parsed configuration as a whole and produce a typed
+//! report together with lint [`Finding`](crate::lint::Finding)s for the
+//! problems they detect.
+//!
+//! | Analyzer | Report | Finding rules |
+//! |----------|--------|---------------|
+//! | [`affinity::affinity_audit`] | [`affinity::AffinityReport`] | `affinity_cache` |
+//! | [`auth_request::auth_request_audit`] | [`auth_request::AuthRequestReport`] | `auth_request_missing`, `auth_request_internal`, `auth_request_loop`, `auth_request_error_page` |
+//! | [`bots::bot_blocking`] | [`bots::BotReport`] | `bot_filter_gap`, `bot_map_unused` |
+//! | [`build::TlsLibrary::check`] | — | `tls_library_unsupported` |
+//! | [`certificates::certificate_coverage`] | [`certificates::CertificateCoverageReport`] | `wildcard_apex`, `certificate_name_mismatch` |
+//! | [`coverage::coverage`] | [`coverage::CoverageReport`] | `untested_server`, `untested_location` |
+//! | [`caching::caching_audit`] | [`caching::CachingReport`] | `caching_missing`, `caching_conflict` |
+//! | [`version::required_version`] | [`version::RequiredVersion`] | `required_version` |
+//! | [`secrets::find_secrets`] | [`secrets::SecretReport`] | `embedded_secret` |
+//! | [`paths::check_paths`] | [`paths::PathReport`] | `missing_path` |
+//! | [`keepalive::keepalive_audit`] | [`keepalive::KeepaliveReport`] | `upstream_keepalive` |
+//! | [`modules::module_inventory`] | [`modules::ModuleInventory`] | `missing_module` |
+//! | [`waf::waf_coverage`] | [`waf::WafReport`] | `waf_rules_file`, `waf_no_rules` |
+//! | [`performance::performance`] | [`performance::PerformanceReport`] | `performance` |
+//! | [`real_ip::real_ip_audit`] | [`real_ip::RealIpReport`] | `real_ip_missing`, `real_ip_header`, `real_ip_trust_all` |
+//! | [`log_compat::log_format_compat`] | [`log_compat::LogCompatReport`] | `log_format_undefined`, `log_format_compat`, `log_format_escape` |
+//! | [`quic::quic_audit`] | [`quic::QuicReport`] | `quic_no_fallback`, `quic_alt_svc`, `quic_early_data` |
+//! | [`tls::tls_audit`] | [`tls::TlsReport`] | `tls_protocol`, `tls_cipher`, `tls_dhparam`, `tls_session_cache`, `tls_session_ticket` |
+//! | [`headers::header_inheritance`] | [`headers::HeaderReport`] | `header_inheritance` |
+//! | [`grpc::grpc_audit`] | [`grpc::GrpcReport`] | `grpc_http2`, `grpc_read_timeout`, `grpc_tls` |
+//! | [`geoip::geoip_audit`] | [`geoip::GeoipReport`] | `geoip_database_missing`, `geoip_undefined_variable`, `geoip_unused_variable` |
+//! | [`offload::xaccel_offload`] | [`offload::OffloadReport`] | `xaccel_unreachable` |
+//! | [`log_errors::log_error_correlation`] | [`log_errors::LogErrorReport`] | `location_error_rate` |
+
+pub mod affinity;
+pub mod auth_request;
+pub mod bots;
+pub mod build;
+pub mod caching;
+pub mod certificates;
+pub mod coverage;
+pub mod geoip;
+pub mod grpc;
+pub mod headers;
+pub mod keepalive;
+pub mod log_compat;
+pub mod log_errors;
+pub mod modules;
+pub mod offload;
+pub mod paths;
+pub mod performance;
+pub mod quic;
+pub mod real_ip;
+pub mod secrets;
+pub mod tls;
+pub mod version;
+pub mod waf;
+
+pub use affinity::{affinity_audit, AffinityReport, AffinityUse};
+pub use auth_request::{auth_request_audit, AuthErrorPage, AuthRequest, AuthRequestReport};
+pub use bots::{bot_blocking, BotFilter, BotMechanism, BotReport, BotRule};
+pub use build::{BuildInfo, TlsLibrary, TlsLibraryKind};
+pub use caching::{caching_audit, CachingReport, LocationCaching};
+pub use certificates::{
+    certificate_coverage, CertificateCoverageReport, CertificateNames, NameCoverage,
+    ServerNameCoverage,
+};
+pub use coverage::{coverage, CoverageReport, LocationCoverage, ServerCoverage, UnroutedRequest};
+pub use geoip::{geoip_audit, GeoipDatabase, GeoipReport, GeoipVariable};
+pub use grpc::{grpc_audit, GrpcLocation, GrpcReport};
+pub use headers::{header_inheritance, HeaderOverride, HeaderReport, SECURITY_HEADERS};
+pub use keepalive::{keepalive_audit, KeepaliveReport, UpstreamUse};
+pub use log_compat::{
+    log_format_compat, Consumer, Expectation, FormatCompat, LogCompatReport, CONSUMERS,
+};
+pub use log_errors::{
+    log_error_correlation, LocationErrors, LogErrorOptions, LogErrorReport, UpstreamErrors,
+};
+pub use modules::{module_inventory, module_of, CompiledModules, ModuleInventory, ModuleUsage};
+pub use offload::{xaccel_offload, OffloadReport, OffloadSource, OffloadTarget};
+pub use paths::{check_paths, PathKind, PathReport, PathStatus, ReferencedPath};
+pub use performance::{performance, PerformanceReport, TuningCheck};
+pub use quic::{quic_audit, QuicReport, QuicServer};
+pub use real_ip::{real_ip_audit, RealIpReport, RealIpServer};
+pub use secrets::{find_secrets, EmbeddedSecret, SecretKind, SecretReport};
+pub use tls::{
+    tls_audit, tls_audit_with, CipherProfile, TlsAuditOptions, TlsReport, TlsServer,
+    INTERMEDIATE_CIPHERS,
+};
+pub use version::{required_version, NginxVersion, RequiredVersion, VersionRequirement};
+pub use waf::{waf_coverage, RulesFile, WafContext, WafReport};
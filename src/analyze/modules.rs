@@ -0,0 +1,414 @@
+//! Third-party module inventory
+//!
+//! Directives of modules that are not part of stock nginx (lua, njs,
+//! headers-more, vts, geoip2, `ModSecurity`, ...) parse like any other
+//! directive, so a configuration can depend on them without anything
+//! saying so. [`module_inventory`] recognizes them by name, reports which
+//! modules the configuration relies on and whether a `load_module` line
+//! loads them. [`ModuleInventory::check`] cross-checks the result against
+//! the modules compiled into an nginx binary, as listed by `nginx -V`.
+
+use crate::ast::{Config, Span};
+use crate::lint::{walk, Finding, Severity};
+
+/// A module recognized by its directive names
+struct ModuleSpec {
+    /// Short module name
+    name: &'static str,
+    /// Directive names; a trailing `*` matches a prefix, a leading `*` a
+    /// substring
+    directives: &'static [&'static str],
+    /// Lowercase fragments identifying the module in `load_module` paths
+    /// and `nginx -V` configure arguments
+    markers: &'static [&'static str],
+}
+
+const MODULES: &[ModuleSpec] = &[
+    ModuleSpec {
+        name: "lua",
+        directives: &["lua_*", "*_by_lua*"],
+        markers: &["lua-nginx-module", "ngx_lua", "lua_module"],
+    },
+    ModuleSpec {
+        name: "njs",
+        directives: &["js_*"],
+        markers: &["njs", "_js_module"],
+    },
+    ModuleSpec {
+        name: "perl",
+        directives: &["perl", "perl_*"],
+        markers: &["perl_module"],
+    },
+    ModuleSpec {
+        name: "headers-more",
+        directives: &[
+            "more_set_headers",
+            "more_clear_headers",
+            "more_set_input_headers",
+            "more_clear_input_headers",
+        ],
+        markers: &["headers-more", "headers_more"],
+    },
+    ModuleSpec {
+        name: "vts",
+        directives: &["vhost_traffic_status*"],
+        markers: &["module-vts", "traffic_status"],
+    },
+    ModuleSpec {
+        name: "geoip2",
+        directives: &["geoip2*"],
+        markers: &["geoip2"],
+    },
+    ModuleSpec {
+        name: "modsecurity",
+        directives: &["modsecurity*"],
+        markers: &["modsecurity"],
+    },
+    ModuleSpec {
+        name: "brotli",
+        directives: &["brotli*"],
+        markers: &["brotli"],
+    },
+];
+
+impl ModuleSpec {
+    fn owns(&self, directive: &str) -> bool {
+        self.directives.iter().any(|pattern| {
+            if let Some(infix) = pattern.strip_prefix('*') {
+                directive.contains(infix.trim_end_matches('*'))
+            } else if let Some(prefix) = pattern.strip_suffix('*') {
+                directive.starts_with(prefix)
+            } else {
+                directive == *pattern
+            }
+        })
+    }
+
+    fn matches(&self, text: &str) -> bool {
+        let text = text.to_ascii_lowercase();
+        self.markers.iter().any(|marker| text.contains(marker))
+    }
+}
+
+/// Module a directive belongs to, for the third-party modules this crate
+/// knows about
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::analyze::modules::module_of;
+///
+/// assert_eq!(module_of("content_by_lua_block"), Some("lua"));
+/// assert_eq!(module_of("js_import"), Some("njs"));
+/// assert_eq!(module_of("proxy_pass"), None);
+/// ```
+#[must_use]
+pub fn module_of(directive: &str) -> Option<&'static str> {
+    spec_of(directive).map(|spec| spec.name)
+}
+
+fn spec_of(directive: &str) -> Option<&'static ModuleSpec> {
+    MODULES.iter().find(|spec| spec.owns(directive))
+}
+
+/// How one module is used by the configuration
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ModuleUsage {
+    /// Module name (`lua`, `njs`, `headers-more`, ...)
+    pub module: String,
+
+    /// Distinct directives of the module, in order of first use
+    pub directives: Vec<String>,
+
+    /// Number of directives using the module
+    pub uses: usize,
+
+    /// Location of the first use
+    pub span: Span,
+
+    /// Whether a `load_module` line loads the module
+    pub loaded: bool,
+}
+
+/// Modules compiled into an nginx binary, from `nginx -V`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CompiledModules {
+    /// Module-related configure arguments: `--with-http_perl_module`,
+    /// `--add-module=...`, `--add-dynamic-module=...`
+    pub arguments: Vec<String>,
+}
+
+impl CompiledModules {
+    /// Extract the module arguments from `nginx -V` output
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nginx_discovery::analyze::CompiledModules;
+    ///
+    /// let compiled = CompiledModules::from_build_output(
+    ///     "nginx version: nginx/1.25.3\n\
+    ///      configure arguments: --prefix=/etc/nginx --with-http_ssl_module \
+    ///      --add-dynamic-module=/build/njs/nginx",
+    /// );
+    ///
+    /// assert!(compiled.has("njs"));
+    /// assert!(!compiled.has("lua"));
+    /// ```
+    #[must_use]
+    pub fn from_build_output(output: &str) -> Self {
+        let arguments = output
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("configure arguments:"))
+            .flat_map(str::split_whitespace)
+            .filter(|arg| {
+                arg.starts_with("--add-module=")
+                    || arg.starts_with("--add-dynamic-module=")
+                    || (arg.starts_with("--with-") && arg.contains("_module"))
+            })
+            .map(str::to_string)
+            .collect();
+        Self { arguments }
+    }
+
+    /// Whether the build includes `module`, by its inventory name, either
+    /// statically or as a dynamic module
+    #[must_use]
+    pub fn has(&self, module: &str) -> bool {
+        self.arguments_for(module).next().is_some()
+    }
+
+    /// Whether `module` is only built as a dynamic module, which has to be
+    /// loaded with `load_module` before its directives can be used
+    #[must_use]
+    pub fn is_dynamic(&self, module: &str) -> bool {
+        self.has(module)
+            && self
+                .arguments_for(module)
+                .all(|arg| arg.starts_with("--add-dynamic-module=") || arg.ends_with("=dynamic"))
+    }
+
+    fn arguments_for<'a>(&'a self, module: &str) -> impl Iterator<Item = &'a String> + 'a {
+        let spec = MODULES.iter().find(|spec| spec.name == module);
+        self.arguments
+            .iter()
+            .filter(move |arg| spec.is_some_and(|spec| spec.matches(arg)))
+    }
+}
+
+/// Result of [`module_inventory`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ModuleInventory {
+    /// Third-party modules the configuration uses, in order of first use
+    pub modules: Vec<ModuleUsage>,
+
+    /// Arguments of every `load_module` directive
+    pub loaded: Vec<String>,
+}
+
+impl ModuleInventory {
+    /// Usage of `module`, if the configuration uses it
+    #[must_use]
+    pub fn module(&self, module: &str) -> Option<&ModuleUsage> {
+        self.modules.iter().find(|m| m.module == module)
+    }
+
+    /// Names of the modules used
+    #[must_use]
+    pub fn names(&self) -> Vec<&str> {
+        self.modules.iter().map(|m| m.module.as_str()).collect()
+    }
+
+    /// A `missing_module` error for every used module that is not
+    /// compiled into the binary, or only built as a dynamic module that no
+    /// `load_module` line loads
+    #[must_use]
+    pub fn check(&self, compiled: &CompiledModules) -> Vec<Finding> {
+        self.modules
+            .iter()
+            .filter(|m| !m.loaded && (!compiled.has(&m.module) || compiled.is_dynamic(&m.module)))
+            .map(|m| {
+                let directive = m.directives.first().map_or("", String::as_str);
+                let (message, help) = if compiled.has(&m.module) {
+                    (
+                        format!(
+                            "\"{directive}\" needs the {} module, which is built as a dynamic \
+                             module but never loaded",
+                            m.module
+                        ),
+                        "Add a load_module line for the module at the top of nginx.conf"
+                            .to_string(),
+                    )
+                } else {
+                    (
+                        format!(
+                            "\"{directive}\" needs the {} module, which this nginx build does \
+                             not include",
+                            m.module
+                        ),
+                        format!(
+                            "Install the {} module and load it with load_module, or remove its \
+                             directives",
+                            m.module
+                        ),
+                    )
+                };
+                Finding::new("missing_module", Severity::Error, message)
+                    .with_span(m.span)
+                    .with_help(help)
+            })
+            .collect()
+    }
+}
+
+/// List the third-party modules `config` relies on.
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::{analyze::module_inventory, parse};
+///
+/// let config = parse(r#"
+/// load_module modules/ngx_http_js_module.so;
+/// http {
+///     js_import main from app.js;
+///     server {
+///         more_set_headers "Server: edge";
+///         location / { js_content main.handler; }
+///     }
+/// }
+/// "#)?;
+/// let inventory = module_inventory(&config);
+///
+/// assert_eq!(inventory.names(), vec!["njs", "headers-more"]);
+/// assert_eq!(inventory.modules[0].uses, 2);
+/// assert!(inventory.modules[0].loaded);
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+#[must_use]
+pub fn module_inventory(config: &Config) -> ModuleInventory {
+    let mut inventory = ModuleInventory::default();
+
+    walk(config, &mut |directive, _| {
+        let name = directive.name();
+        if name == "load_module" {
+            inventory
+                .loaded
+                .extend(directive.args().iter().map(|a| a.as_str().to_string()));
+            return;
+        }
+        let Some(spec) = spec_of(name) else {
+            return;
+        };
+
+        match inventory.modules.iter_mut().find(|m| m.module == spec.name) {
+            Some(usage) => {
+                usage.uses += 1;
+                if !usage.directives.iter().any(|d| d == name) {
+                    usage.directives.push(name.to_string());
+                }
+            }
+            None => inventory.modules.push(ModuleUsage {
+                module: spec.name.to_string(),
+                directives: vec![name.to_string()],
+                uses: 1,
+                span: directive.span,
+                loaded: false,
+            }),
+        }
+    });
+
+    for usage in &mut inventory.modules {
+        usage.loaded = MODULES
+            .iter()
+            .find(|spec| spec.name == usage.module)
+            .is_some_and(|spec| inventory.loaded.iter().any(|path| spec.matches(path)));
+    }
+
+    inventory
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_module_of() {
+        assert_eq!(module_of("lua_shared_dict"), Some("lua"));
+        assert_eq!(module_of("init_worker_by_lua_file"), Some("lua"));
+        assert_eq!(module_of("perl"), Some("perl"));
+        assert_eq!(module_of("perl_set"), Some("perl"));
+        assert_eq!(module_of("vhost_traffic_status_zone"), Some("vts"));
+        assert_eq!(module_of("geoip2"), Some("geoip2"));
+        assert_eq!(module_of("geoip_country"), None);
+        assert_eq!(module_of("modsecurity_rules_file"), Some("modsecurity"));
+        assert_eq!(module_of("more_clear_headers"), Some("headers-more"));
+        assert_eq!(module_of("add_header"), None);
+    }
+
+    #[test]
+    fn test_module_inventory() {
+        let config = parse(
+            r"
+load_module /usr/lib/nginx/modules/ngx_http_modsecurity_module.so;
+http {
+    lua_shared_dict cache 10m;
+    geoip2 /etc/GeoLite2-Country.mmdb { $country country iso_code; }
+    server {
+        modsecurity on;
+        location / {
+            access_by_lua_block { ngx.exit(403) }
+            lua_code_cache on;
+        }
+    }
+}
+",
+        )
+        .unwrap();
+        let inventory = module_inventory(&config);
+
+        assert_eq!(inventory.names(), vec!["lua", "geoip2", "modsecurity"]);
+        let lua = inventory.module("lua").unwrap();
+        assert_eq!(lua.uses, 3);
+        assert_eq!(
+            lua.directives,
+            vec!["lua_shared_dict", "access_by_lua_block", "lua_code_cache"]
+        );
+        assert_eq!(lua.span.line, 4);
+        assert!(!lua.loaded);
+        assert!(inventory.module("modsecurity").unwrap().loaded);
+        assert_eq!(inventory.loaded.len(), 1);
+
+        let compiled = CompiledModules::from_build_output(
+            "nginx version: openresty/1.21.4.1\n\
+             built with OpenSSL 3.0.2\n\
+             configure arguments: --prefix=/usr/local/openresty/nginx --with-cc-opt=-O2 \
+             --add-module=../ngx_lua-0.10.26 --with-http_ssl_module",
+        );
+        assert_eq!(compiled.arguments.len(), 2);
+        assert!(compiled.has("lua"));
+
+        let findings = inventory.check(&compiled);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].rule, "missing_module");
+        assert!(findings[0].message.contains("geoip2"));
+        assert_eq!(findings[0].line(), Some(5));
+
+        assert_eq!(inventory.check(&CompiledModules::default()).len(), 2);
+
+        let dynamic = CompiledModules::from_build_output(
+            "configure arguments: --add-dynamic-module=/src/lua-nginx-module \
+             --with-http_perl_module=dynamic --add-module=/src/ngx_http_geoip2_module",
+        );
+        assert!(dynamic.is_dynamic("lua"));
+        assert!(dynamic.is_dynamic("perl"));
+        assert!(!dynamic.is_dynamic("geoip2"));
+        let findings = inventory.check(&dynamic);
+        assert_eq!(findings.len(), 1);
+        assert!(findings[0].message.contains("never loaded"));
+    }
+}
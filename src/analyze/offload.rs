@@ -0,0 +1,419 @@
+//! `X-Accel-Redirect` offload
+//!
+//! An application behind `proxy_pass` (or `fastcgi_pass`, `uwsgi_pass`,
+//! `scgi_pass`) can answer with an `X-Accel-Redirect: /protected/report.pdf`
+//! header: nginx drops the response body and serves the URI from an
+//! `internal` location instead, sending the file itself. The application
+//! only decides whether a client may download the file; where it is stored
+//! is the internal location's `alias` or `root`. Moving the files therefore
+//! means changing those locations, and every application able to redirect
+//! to them has to keep producing valid URIs.
+//!
+//! [`xaccel_offload`] lists every internal location serving files, the
+//! directory its files come from, and the proxied locations of the same
+//! server that can trigger it (those not ignoring the header with
+//! `proxy_ignore_headers X-Accel-Redirect`), and reports:
+//!
+//! - `xaccel_unreachable`: an internal file location no application can
+//!   redirect to and no `error_page`, `try_files` or `rewrite` reaches
+
+use crate::ast::{Config, Directive, Value};
+use crate::lint::{walk, Finding, Severity};
+use crate::route::find_location;
+use crate::types::{LocationModifier, Server, TryFiles, TryFilesFallback};
+use std::path::{Path, PathBuf};
+
+/// Directives handing the request to an application
+const PASS_DIRECTIVES: &[&str] = &["proxy_pass", "fastcgi_pass", "uwsgi_pass", "scgi_pass"];
+
+/// A proxied location whose responses can trigger an offload
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OffloadSource {
+    /// Location path/pattern
+    pub location: String,
+
+    /// Line of the pass directive
+    pub line: usize,
+
+    /// `proxy_pass`, `fastcgi_pass`, `uwsgi_pass` or `scgi_pass`
+    pub directive: String,
+
+    /// Application the location passes to, as written
+    pub backend: String,
+}
+
+/// An internal location serving files
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OffloadTarget {
+    /// First `server_name` of the enclosing server (`_` if none)
+    pub server_name: String,
+
+    /// Location path/pattern
+    pub location: String,
+
+    /// Line of the location
+    pub line: usize,
+
+    /// Directory the files are served from: the `alias`, or the effective
+    /// `root` the request URI is appended to
+    pub storage: PathBuf,
+
+    /// Whether `storage` is an `alias`
+    pub alias: bool,
+
+    /// Proxied locations of the server that can redirect to it
+    pub triggered_by: Vec<OffloadSource>,
+
+    /// Whether an `error_page`, `try_files` or `rewrite` of the server
+    /// redirects to it
+    pub redirected_to: bool,
+}
+
+impl OffloadTarget {
+    /// File an `X-Accel-Redirect` URI is served from, `None` when the URI
+    /// does not belong to the location or the location is a regex
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nginx_discovery::{analyze::xaccel_offload, parse};
+    /// use std::path::Path;
+    ///
+    /// let config = parse(r"
+    /// server {
+    ///     location /protected/ { internal; alias /srv/files/; }
+    ///     location / { proxy_pass http://127.0.0.1:8000; }
+    /// }
+    /// ")?;
+    /// let target = &xaccel_offload(&config).targets[0];
+    ///
+    /// assert_eq!(
+    ///     target.file_for("/protected/2024/report.pdf").as_deref(),
+    ///     Some(Path::new("/srv/files/2024/report.pdf"))
+    /// );
+    /// # Ok::<(), nginx_discovery::Error>(())
+    /// ```
+    #[must_use]
+    pub fn file_for(&self, uri: &str) -> Option<PathBuf> {
+        let (modifier, path) = LocationModifier::from_args(
+            &self
+                .location
+                .split_whitespace()
+                .map(String::from)
+                .collect::<Vec<_>>(),
+        );
+        match modifier {
+            LocationModifier::Exact if uri != path => return None,
+            LocationModifier::Regex | LocationModifier::RegexCaseInsensitive => return None,
+            _ if !uri.starts_with(&path) => return None,
+            _ => {}
+        }
+        if self.alias {
+            Some(join(&self.storage, &uri[path.len()..]))
+        } else {
+            Some(join(&self.storage, uri))
+        }
+    }
+}
+
+/// Result of [`xaccel_offload`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OffloadReport {
+    /// Internal locations serving files, in file order
+    pub targets: Vec<OffloadTarget>,
+
+    /// Unreachable offload locations (`xaccel_unreachable`)
+    pub findings: Vec<Finding>,
+}
+
+/// Map the internal file locations to the applications that can offload
+/// downloads to them.
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::{analyze::xaccel_offload, parse};
+///
+/// let config = parse(r"
+/// http {
+///     server {
+///         server_name files.example.com;
+///         root /var/www;
+///         location /media/ { internal; }
+///         location /app/ { proxy_pass http://127.0.0.1:8000; }
+///         location /static/ {
+///             proxy_ignore_headers X-Accel-Redirect;
+///             proxy_pass http://127.0.0.1:9000;
+///         }
+///     }
+/// }
+/// ")?;
+/// let report = xaccel_offload(&config);
+///
+/// assert_eq!(report.targets[0].storage.to_str(), Some("/var/www"));
+/// assert_eq!(report.targets[0].triggered_by[0].location, "/app/");
+/// assert_eq!(report.targets[0].triggered_by.len(), 1);
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+#[must_use]
+pub fn xaccel_offload(config: &Config) -> OffloadReport {
+    let mut report = OffloadReport::default();
+
+    walk(config, &mut |directive, parents| {
+        let is_server = directive.name() == "server"
+            && directive.is_block()
+            && !parents
+                .iter()
+                .any(|p| matches!(p.name(), "stream" | "mail"));
+        if !is_server {
+            return;
+        }
+
+        let mut locations = Vec::new();
+        collect_locations(directive, &mut Vec::new(), &mut locations);
+        // Innermost block last
+        let outer: Vec<&Directive> = parents.iter().copied().chain([directive]).collect();
+
+        let sources: Vec<OffloadSource> = locations
+            .iter()
+            .filter_map(|(location, chain)| offload_source(location, chain, &outer))
+            .collect();
+        let redirected = redirect_targets(directive);
+
+        for (location, chain) in &locations {
+            let Some((storage, alias)) = storage(location, chain, &outer) else {
+                continue;
+            };
+            let target = OffloadTarget {
+                server_name: Server::primary_name_of(directive),
+                location: location.args_as_strings().join(" "),
+                line: location.span.line,
+                storage,
+                alias,
+                triggered_by: sources.clone(),
+                redirected_to: redirected.iter().any(|r| std::ptr::eq(*r, *location)),
+            };
+            if target.triggered_by.is_empty() && !target.redirected_to {
+                report.findings.push(unreachable(location, &target));
+            }
+            report.targets.push(target);
+        }
+    });
+
+    report
+}
+
+/// Every location in a block with the locations enclosing it
+fn collect_locations<'a>(
+    block: &'a Directive,
+    chain: &mut Vec<&'a Directive>,
+    out: &mut Vec<(&'a Directive, Vec<&'a Directive>)>,
+) {
+    for location in block.find_children("location") {
+        out.push((location, chain.clone()));
+        chain.push(location);
+        collect_locations(location, chain, out);
+        chain.pop();
+    }
+}
+
+/// Innermost value of a directive in a location, its enclosing locations
+/// and the blocks outside them
+fn effective<'a>(
+    name: &str,
+    location: &'a Directive,
+    chain: &[&'a Directive],
+    outer: &[&'a Directive],
+) -> Option<&'a Directive> {
+    std::iter::once(location)
+        .chain(chain.iter().rev().copied())
+        .chain(outer.iter().rev().copied())
+        .find_map(|block| block.find_children(name).last().copied())
+}
+
+fn offload_source(
+    location: &Directive,
+    chain: &[&Directive],
+    outer: &[&Directive],
+) -> Option<OffloadSource> {
+    let pass = location
+        .children()
+        .unwrap_or_default()
+        .iter()
+        .find(|d| PASS_DIRECTIVES.contains(&d.name()))?;
+    let prefix = pass.name().trim_end_matches("_pass");
+    let ignored = effective(&format!("{prefix}_ignore_headers"), location, chain, outer)
+        .is_some_and(|d| {
+            d.args()
+                .iter()
+                .any(|h| h.as_str().eq_ignore_ascii_case("x-accel-redirect"))
+        });
+    (!ignored).then(|| OffloadSource {
+        location: location.args_as_strings().join(" "),
+        line: pass.span.line,
+        directive: pass.name().to_string(),
+        backend: pass.args().first().map(Value::unquoted).unwrap_or_default(),
+    })
+}
+
+/// Where an internal location serving files takes them from
+fn storage(
+    location: &Directive,
+    chain: &[&Directive],
+    outer: &[&Directive],
+) -> Option<(PathBuf, bool)> {
+    let named = location
+        .args()
+        .first()
+        .is_some_and(|a| a.as_str().starts_with('@'));
+    let serves_files = PASS_DIRECTIVES
+        .iter()
+        .chain(&["return"])
+        .all(|name| location.find_children(name).is_empty());
+    if location.find_children("internal").is_empty() || named || !serves_files {
+        return None;
+    }
+    if let Some(alias) = location.find_children("alias").last() {
+        return Some((PathBuf::from(alias.args().first()?.unquoted()), true));
+    }
+    let root = effective("root", location, chain, outer)
+        .and_then(|d| d.args().first().map(Value::unquoted))
+        .unwrap_or_else(|| "html".to_string());
+    Some((PathBuf::from(root), false))
+}
+
+/// Locations the server's `error_page`, `try_files` and `rewrite`
+/// directives redirect to
+fn redirect_targets(server: &Directive) -> Vec<&Directive> {
+    let mut uris = Vec::new();
+    for directive in server
+        .find_recursive("error_page")
+        .into_iter()
+        .chain(server.find_recursive("try_files"))
+        .chain(server.find_recursive("rewrite"))
+    {
+        let args: Vec<String> = directive.args().iter().map(Value::unquoted).collect();
+        let uri = match directive.name() {
+            "try_files" => match TryFiles::from_args(&args).map(|t| t.fallback) {
+                Some(TryFilesFallback::Uri(uri)) => Some(uri),
+                _ => None,
+            },
+            "rewrite" => args.get(1).cloned(),
+            _ => args.last().cloned(),
+        };
+        if let Some(uri) = uri.filter(|uri| uri.starts_with('/')) {
+            let path = uri.split(['?', '$']).next().unwrap_or_default().to_string();
+            uris.push(path);
+        }
+    }
+    uris.iter()
+        .filter_map(|uri| find_location(server, uri).last().copied())
+        .collect()
+}
+
+fn join(base: &Path, uri: &str) -> PathBuf {
+    base.join(uri.trim_start_matches('/'))
+}
+
+fn unreachable(location: &Directive, target: &OffloadTarget) -> Finding {
+    Finding::new(
+        "xaccel_unreachable",
+        Severity::Info,
+        format!(
+            "internal location {} in server \"{}\" serves {}, but no proxied application in the \
+             server can redirect to it and no error_page, try_files or rewrite does",
+            target.location,
+            target.server_name,
+            target.storage.display()
+        ),
+    )
+    .at(location)
+    .with_help("Remove the location, or check the application it was meant for")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_xaccel_offload() {
+        let config = parse(
+            r"
+http {
+    proxy_ignore_headers X-Accel-Redirect;
+    server {
+        server_name app.example.com;
+        root /var/www;
+        error_page 404 /errors/404.html;
+        location /errors/ { internal; }
+        location /downloads/ { internal; alias /mnt/storage/; }
+        location = /protected/index.html { internal; root /srv; }
+        location /app/ {
+            proxy_ignore_headers Expires;
+            proxy_pass http://django;
+        }
+        location ~ \.php$ { fastcgi_pass unix:/run/php.sock; }
+        location /legacy/ { proxy_pass http://legacy; }
+    }
+    server {
+        server_name static.example.com;
+        location /_files/ { internal; root /data; }
+        location @fallback { internal; }
+    }
+}
+",
+        )
+        .unwrap();
+        let report = xaccel_offload(&config);
+
+        let targets: Vec<_> = report
+            .targets
+            .iter()
+            .map(|t| {
+                (
+                    t.location.as_str(),
+                    t.storage.to_str().unwrap(),
+                    t.alias,
+                    t.redirected_to,
+                )
+            })
+            .collect();
+        assert_eq!(
+            targets,
+            vec![
+                ("/errors/", "/var/www", false, true),
+                ("/downloads/", "/mnt/storage/", true, false),
+                ("= /protected/index.html", "/srv", false, false),
+                ("/_files/", "/data", false, false),
+            ]
+        );
+
+        // /app/ overrides the http-level ignore; /legacy/ inherits it
+        let sources: Vec<_> = report.targets[1]
+            .triggered_by
+            .iter()
+            .map(|s| (s.location.as_str(), s.directive.as_str()))
+            .collect();
+        assert_eq!(
+            sources,
+            vec![("/app/", "proxy_pass"), ("~ \\.php$", "fastcgi_pass")]
+        );
+
+        assert_eq!(
+            report.targets[1].file_for("/downloads/a/b.zip"),
+            Some(PathBuf::from("/mnt/storage/a/b.zip"))
+        );
+        assert_eq!(
+            report.targets[2].file_for("/protected/index.html"),
+            Some(PathBuf::from("/srv/protected/index.html"))
+        );
+        assert_eq!(report.targets[2].file_for("/protected/other.html"), None);
+
+        let lines: Vec<_> = report.findings.iter().map(Finding::line).collect();
+        assert_eq!(lines, vec![Some(20)]);
+    }
+}
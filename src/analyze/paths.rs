@@ -0,0 +1,386 @@
+//! Referenced path existence check
+//!
+//! Verifies that files and directories the configuration points at exist on
+//! disk: certificates and keys, `auth_basic_user_file`, `include` targets,
+//! `load_module` objects, `ModSecurity` rules files and `root`/`alias`
+//! document roots. Missing files nginx loads at startup are errors
+//! (`nginx -t` fails); missing document roots are warnings (requests get
+//! 404).
+//!
+//! Relative paths are resolved against a base directory, usually the
+//! directory of the main configuration file. Paths built from variables and
+//! inline `data:` values can only be resolved at runtime and are reported as
+//! unchecked.
+
+use crate::ast::{Config, Directive, Span};
+use crate::lint::{walk, Finding, Severity};
+use std::path::{Path, PathBuf};
+
+/// Directives naming a file nginx reads at startup
+const FILE_DIRECTIVES: &[&str] = &[
+    "ssl_certificate",
+    "ssl_certificate_key",
+    "ssl_trusted_certificate",
+    "ssl_client_certificate",
+    "ssl_crl",
+    "ssl_dhparam",
+    "ssl_password_file",
+    "ssl_stapling_file",
+    "ssl_session_ticket_key",
+    "proxy_ssl_certificate",
+    "proxy_ssl_certificate_key",
+    "proxy_ssl_trusted_certificate",
+    "proxy_ssl_crl",
+    "proxy_ssl_password_file",
+    "grpc_ssl_certificate",
+    "grpc_ssl_certificate_key",
+    "grpc_ssl_trusted_certificate",
+    "auth_basic_user_file",
+    "load_module",
+    "modsecurity_rules_file",
+    "geoip_country",
+    "geoip_city",
+    "geoip_org",
+    "geoip2",
+];
+
+/// What a referenced path should be
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum PathKind {
+    /// A regular file
+    File,
+    /// A directory (`root`)
+    Directory,
+    /// A file or a directory (`alias`)
+    Any,
+    /// An `include` target, possibly a glob
+    Include,
+}
+
+/// Outcome of checking one path
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum PathStatus {
+    /// The path exists and has the expected type
+    Exists,
+    /// Nothing exists at the path
+    Missing,
+    /// The path exists but is a file where a directory is expected or the
+    /// other way round
+    WrongType,
+    /// An `include` glob that matches no file
+    NoMatches,
+    /// Built from variables or inline data, only known at runtime
+    Unchecked,
+}
+
+/// A path referenced by a directive
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReferencedPath {
+    /// Directive name
+    pub directive: String,
+
+    /// Path as written in the configuration
+    pub path: String,
+
+    /// Path resolved against the base directory
+    pub resolved: PathBuf,
+
+    /// Expected type
+    pub kind: PathKind,
+
+    /// Result of the check
+    pub status: PathStatus,
+
+    /// Location of the directive
+    pub span: Span,
+}
+
+/// Result of [`check_paths`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PathReport {
+    /// Every referenced path, in file order
+    pub paths: Vec<ReferencedPath>,
+
+    /// Problems found (`missing_path`)
+    pub findings: Vec<Finding>,
+}
+
+impl PathReport {
+    /// Paths that are missing, have the wrong type or match nothing
+    pub fn problems(&self) -> impl Iterator<Item = &ReferencedPath> {
+        self.paths
+            .iter()
+            .filter(|p| !matches!(p.status, PathStatus::Exists | PathStatus::Unchecked))
+    }
+}
+
+/// Check that the paths referenced by the configuration exist.
+///
+/// Relative paths are resolved against `base_dir`.
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::{analyze::{check_paths, PathStatus}, parse};
+///
+/// let config = parse("http { server { ssl_certificate /no/such/cert.pem; root /; } }")?;
+/// let report = check_paths(&config, "/etc/nginx");
+///
+/// assert_eq!(report.paths[0].status, PathStatus::Missing);
+/// assert_eq!(report.paths[1].status, PathStatus::Exists);
+/// assert_eq!(report.findings.len(), 1);
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+#[must_use]
+pub fn check_paths(config: &Config, base_dir: impl AsRef<Path>) -> PathReport {
+    let base_dir = base_dir.as_ref();
+    let mut report = PathReport::default();
+
+    walk(config, &mut |directive, _| {
+        let Some(kind) = kind_of(directive.name()) else {
+            return;
+        };
+        let Some(raw) = directive.args().first().map(|v| {
+            if v.is_variable() {
+                v.to_config_string()
+            } else {
+                v.as_str().to_string()
+            }
+        }) else {
+            return;
+        };
+
+        let resolved = base_dir.join(&raw);
+        let status = if raw.contains('$') || raw.starts_with("data:") || raw.starts_with("engine:")
+        {
+            PathStatus::Unchecked
+        } else {
+            status(&resolved, kind)
+        };
+
+        if let Some(finding) = finding(directive, &raw, kind, status) {
+            report.findings.push(finding);
+        }
+        report.paths.push(ReferencedPath {
+            directive: directive.name().to_string(),
+            path: raw,
+            resolved,
+            kind,
+            status,
+            span: directive.span,
+        });
+    });
+
+    report
+}
+
+fn kind_of(directive: &str) -> Option<PathKind> {
+    match directive {
+        "root" => Some(PathKind::Directory),
+        "alias" => Some(PathKind::Any),
+        "include" => Some(PathKind::Include),
+        name if FILE_DIRECTIVES.contains(&name) => Some(PathKind::File),
+        _ => None,
+    }
+}
+
+fn status(path: &Path, kind: PathKind) -> PathStatus {
+    if kind == PathKind::Include && is_glob(&path.to_string_lossy()) {
+        return glob_status(path);
+    }
+
+    let Ok(metadata) = std::fs::metadata(path) else {
+        return PathStatus::Missing;
+    };
+    let expected = match kind {
+        PathKind::File | PathKind::Include => metadata.is_file(),
+        PathKind::Directory => metadata.is_dir(),
+        PathKind::Any => true,
+    };
+    if expected {
+        PathStatus::Exists
+    } else {
+        PathStatus::WrongType
+    }
+}
+
+pub(crate) fn is_glob(path: &str) -> bool {
+    path.contains(['*', '?', '['])
+}
+
+/// nginx accepts a glob that matches nothing; only wildcards in the last
+/// component are expanded here
+fn glob_status(path: &Path) -> PathStatus {
+    let (Some(dir), Some(pattern)) = (path.parent(), path.file_name()) else {
+        return PathStatus::Unchecked;
+    };
+    let pattern = pattern.to_string_lossy();
+    if is_glob(&dir.to_string_lossy()) || pattern.contains('[') {
+        return PathStatus::Unchecked;
+    }
+
+    let matched = std::fs::read_dir(dir).is_ok_and(|entries| {
+        entries
+            .filter_map(std::result::Result::ok)
+            .any(|entry| wildcard_match(&pattern, &entry.file_name().to_string_lossy()))
+    });
+    if matched {
+        PathStatus::Exists
+    } else {
+        PathStatus::NoMatches
+    }
+}
+
+/// Match `name` against a pattern with `*` and `?` wildcards
+pub(crate) fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    n = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+fn finding(
+    directive: &Directive,
+    raw: &str,
+    kind: PathKind,
+    status: PathStatus,
+) -> Option<Finding> {
+    let name = directive.name();
+    let severity = match kind {
+        PathKind::File | PathKind::Include => Severity::Error,
+        PathKind::Directory | PathKind::Any => Severity::Warning,
+    };
+
+    let (severity, message, help) = match status {
+        PathStatus::Exists | PathStatus::Unchecked => return None,
+        PathStatus::Missing => (
+            severity,
+            format!("\"{name}\" refers to {raw}, which does not exist"),
+            format!("Create {raw} or fix the path"),
+        ),
+        PathStatus::WrongType => {
+            let expected = if kind == PathKind::Directory {
+                "a directory"
+            } else {
+                "a file"
+            };
+            (
+                severity,
+                format!("\"{name}\" refers to {raw}, which is not {expected}"),
+                format!("Point \"{name}\" at {expected}"),
+            )
+        }
+        PathStatus::NoMatches => (
+            Severity::Info,
+            format!("\"include {raw}\" matches no files"),
+            "Remove the include or add the files it should load".to_string(),
+        ),
+    };
+
+    Some(
+        Finding::new("missing_path", severity, message)
+            .at(directive)
+            .with_help(help),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_check_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("conf.d")).unwrap();
+        std::fs::create_dir(dir.path().join("html")).unwrap();
+        std::fs::write(dir.path().join("conf.d/app.conf"), "").unwrap();
+        std::fs::write(dir.path().join("cert.pem"), "").unwrap();
+
+        let config = parse(
+            r"
+include conf.d/*.conf;
+include sites-enabled/*;
+include missing.conf;
+http {
+    server {
+        root html;
+        ssl_certificate cert.pem;
+        ssl_certificate_key /no/such/key.pem;
+        location /x { root cert.pem; }
+        location /y { alias /srv/$host/; }
+    }
+}
+",
+        )
+        .unwrap();
+        let report = check_paths(&config, dir.path());
+
+        let statuses: Vec<_> = report.paths.iter().map(|p| p.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                PathStatus::Exists,
+                PathStatus::NoMatches,
+                PathStatus::Missing,
+                PathStatus::Exists,
+                PathStatus::Exists,
+                PathStatus::Missing,
+                PathStatus::WrongType,
+                PathStatus::Unchecked,
+            ]
+        );
+        assert_eq!(report.problems().count(), 4);
+
+        let severities: Vec<_> = report.findings.iter().map(|f| f.severity).collect();
+        assert_eq!(
+            severities,
+            vec![
+                Severity::Info,
+                Severity::Error,
+                Severity::Error,
+                Severity::Warning
+            ]
+        );
+        assert_eq!(report.findings[2].line(), Some(9));
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("*.conf", "app.conf"));
+        assert!(wildcard_match("*", "anything"));
+        assert!(wildcard_match("site-?.conf", "site-a.conf"));
+        assert!(wildcard_match("*-*.conf", "a-b-c.conf"));
+        assert!(!wildcard_match("*.conf", "app.conf.bak"));
+        assert!(!wildcard_match("site-?.conf", "site-ab.conf"));
+    }
+}
@@ -0,0 +1,412 @@
+//! Connection and global performance tuning report
+//!
+//! Checks the global settings that decide how much traffic one nginx
+//! instance can serve: worker processes and connections, file descriptor
+//! limits, `sendfile`/`tcp_nopush`/`tcp_nodelay`, `keepalive_timeout`,
+//! `gzip` and `open_file_cache`. Each setting is compared with a
+//! recommendation and weighted; the weights of the settings that are fine
+//! add up to a 0-100 tuning score.
+//!
+//! Only the `main`, `events` and `http` levels are looked at; a snippet
+//! without an `http` block is treated as its content. Settings left at a
+//! poor default are reported as `performance` infos, explicitly configured
+//! poor values as warnings.
+
+use crate::analyze::caching::parse_seconds;
+use crate::ast::{Config, Directive, Span, Value};
+use crate::lint::{Finding, Severity};
+use std::fmt::Write as _;
+
+/// One tuned setting
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TuningCheck {
+    /// Directive name
+    pub setting: String,
+
+    /// Value as configured, `None` when the default applies
+    pub configured: Option<String>,
+
+    /// Value in effect
+    pub effective: String,
+
+    /// Recommended value
+    pub recommended: String,
+
+    /// Whether the effective value is fine
+    pub ok: bool,
+
+    /// Share of the score this setting is worth
+    pub weight: u32,
+
+    /// Location of the directive, when configured
+    pub span: Option<Span>,
+}
+
+/// Result of [`performance`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PerformanceReport {
+    /// Every setting checked
+    pub checks: Vec<TuningCheck>,
+
+    /// Tuning score from 0 to 100
+    pub score: u32,
+
+    /// One `performance` finding per setting that is not fine
+    pub findings: Vec<Finding>,
+}
+
+impl PerformanceReport {
+    fn add(&mut self, check: Check) {
+        let (check, finding) = check.evaluate();
+        self.checks.push(check);
+        self.findings.extend(finding);
+    }
+
+    /// Settings that are not fine
+    pub fn failed(&self) -> impl Iterator<Item = &TuningCheck> {
+        self.checks.iter().filter(|c| !c.ok)
+    }
+
+    /// Letter grade of the score: A (90+), B (75+), C (50+), D
+    #[must_use]
+    pub fn grade(&self) -> char {
+        match self.score {
+            90.. => 'A',
+            75..=89 => 'B',
+            50..=74 => 'C',
+            _ => 'D',
+        }
+    }
+
+    /// Render the report as a Markdown section
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nginx_discovery::{analyze::performance, parse};
+    ///
+    /// let config = parse("http { sendfile on; }")?;
+    /// let markdown = performance(&config).to_markdown();
+    ///
+    /// assert!(markdown.starts_with("## Performance tuning"));
+    /// assert!(markdown.contains("| sendfile | on | on | ✓ |"));
+    /// # Ok::<(), nginx_discovery::Error>(())
+    /// ```
+    #[must_use]
+    pub fn to_markdown(&self) -> String {
+        let mut out = format!(
+            "## Performance tuning\n\nScore: **{}/100** (grade {})\n\n",
+            self.score,
+            self.grade()
+        );
+        out.push_str("| Setting | Value | Recommended | OK |\n");
+        out.push_str("|---------|-------|-------------|----|\n");
+        for check in &self.checks {
+            let value = match &check.configured {
+                Some(_) => check.effective.clone(),
+                None => format!("{} (default)", check.effective),
+            };
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} | {} |",
+                check.setting,
+                value.replace('|', "\\|"),
+                check.recommended,
+                if check.ok { "✓" } else { "✗" }
+            );
+        }
+
+        if !self.findings.is_empty() {
+            out.push_str("\n### Recommendations\n\n");
+            for finding in &self.findings {
+                let _ = write!(out, "- {}", finding.message);
+                if let Some(help) = &finding.help {
+                    let _ = write!(out, ": {help}");
+                }
+                out.push('\n');
+            }
+        }
+        out
+    }
+}
+
+/// Summarize the global performance settings of `config`.
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::{analyze::performance, parse};
+///
+/// let config = parse(r"
+/// worker_processes auto;
+/// events { worker_connections 4096; }
+/// http {
+///     sendfile on;
+///     tcp_nopush on;
+///     keepalive_timeout 0;
+/// }
+/// ")?;
+/// let report = performance(&config);
+///
+/// let failed: Vec<_> = report.failed().map(|c| c.setting.as_str()).collect();
+/// assert_eq!(
+///     failed,
+///     vec!["worker_rlimit_nofile", "keepalive_timeout", "gzip", "gzip_types", "open_file_cache"]
+/// );
+/// assert!(report.score < 75);
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+#[must_use]
+pub fn performance(config: &Config) -> PerformanceReport {
+    let main: Vec<&Directive> = config.directives.iter().collect();
+    let events: Vec<&Directive> = config
+        .find_directives("events")
+        .iter()
+        .flat_map(|e| e.children().unwrap_or_default())
+        .collect();
+    let http: Vec<&Directive> = match config.find_directives("http").first() {
+        Some(http) => http.children().unwrap_or_default().iter().collect(),
+        None => main.clone(),
+    };
+
+    let mut report = PerformanceReport::default();
+    worker_checks(&mut report, &main, &events);
+    http_checks(&mut report, &http);
+
+    let total: u32 = report.checks.iter().map(|c| c.weight).sum();
+    let passed: u32 = report
+        .checks
+        .iter()
+        .filter(|c| c.ok)
+        .map(|c| c.weight)
+        .sum();
+    report.score = (passed * 100 + total / 2) / total;
+    report
+}
+
+fn worker_checks(report: &mut PerformanceReport, main: &[&Directive], events: &[&Directive]) {
+    let worker_connections = setting(events, "worker_connections")
+        .and_then(|d| first(d).parse::<u64>().ok())
+        .unwrap_or(512);
+
+    report.add(Check {
+        setting: "worker_processes",
+        directive: setting(main, "worker_processes"),
+        default: "1",
+        recommended: "auto".to_string(),
+        weight: 15,
+        ok: &|v| v == "auto" || v.parse::<u32>().is_ok_and(|n| n > 1),
+        help: "Use \"worker_processes auto;\" to run one worker per CPU core",
+    });
+    report.add(Check {
+        setting: "worker_connections",
+        directive: setting(events, "worker_connections"),
+        default: "512",
+        recommended: "1024 or more".to_string(),
+        weight: 10,
+        ok: &|v| v.parse::<u64>().is_ok_and(|n| n >= 1024),
+        help: "Raise worker_connections in the events block, e.g. 4096",
+    });
+    report.add(Check {
+        setting: "worker_rlimit_nofile",
+        directive: setting(main, "worker_rlimit_nofile"),
+        default: "system limit",
+        recommended: format!("{} or more", worker_connections * 2),
+        weight: 5,
+        ok: &|v| v.parse::<u64>().is_ok_and(|n| n >= worker_connections * 2),
+        help: "Set worker_rlimit_nofile to at least twice worker_connections: proxied \
+               requests use two descriptors",
+    });
+}
+
+fn http_checks(report: &mut PerformanceReport, http: &[&Directive]) {
+    report.add(Check {
+        setting: "sendfile",
+        directive: setting(http, "sendfile"),
+        default: "off",
+        recommended: "on".to_string(),
+        weight: 15,
+        ok: &|v| v == "on",
+        help: "Add \"sendfile on;\" so the kernel copies static files directly to the socket",
+    });
+    report.add(Check {
+        setting: "tcp_nopush",
+        directive: setting(http, "tcp_nopush"),
+        default: "off",
+        recommended: "on".to_string(),
+        weight: 5,
+        ok: &|v| v == "on",
+        help: "Add \"tcp_nopush on;\" to send headers and the start of a file in one packet",
+    });
+    report.add(Check {
+        setting: "tcp_nodelay",
+        directive: setting(http, "tcp_nodelay"),
+        default: "on",
+        recommended: "on".to_string(),
+        weight: 5,
+        ok: &|v| v == "on",
+        help: "Remove \"tcp_nodelay off;\": small responses on keepalive connections get delayed",
+    });
+    report.add(Check {
+        setting: "keepalive_timeout",
+        directive: setting(http, "keepalive_timeout"),
+        default: "75s",
+        recommended: "15s-75s".to_string(),
+        weight: 10,
+        ok: &|v| parse_seconds(v).is_some_and(|s| (1..=75).contains(&s)),
+        help: "Use a keepalive_timeout between 15s and 75s: 0 closes every connection, long \
+               timeouts tie up worker connections",
+    });
+    report.add(Check {
+        setting: "gzip",
+        directive: setting(http, "gzip"),
+        default: "off",
+        recommended: "on".to_string(),
+        weight: 15,
+        ok: &|v| v == "on",
+        help: "Add \"gzip on;\" to compress text responses",
+    });
+    report.add(Check {
+        setting: "gzip_types",
+        directive: setting(http, "gzip_types"),
+        default: "text/html",
+        recommended: "text/css application/javascript application/json ...".to_string(),
+        weight: 5,
+        ok: &|v| v.split_whitespace().any(|t| t != "text/html"),
+        help: "List CSS, JavaScript, JSON and SVG in gzip_types; only text/html is compressed \
+               by default",
+    });
+    report.add(Check {
+        setting: "open_file_cache",
+        directive: setting(http, "open_file_cache"),
+        default: "off",
+        recommended: "max=10000 inactive=30s".to_string(),
+        weight: 10,
+        ok: &|v| v != "off",
+        help: "Add \"open_file_cache max=10000 inactive=30s;\" to cache file descriptors of \
+               static files",
+    });
+}
+
+/// Last directive named `name` among `directives`
+fn setting<'a>(directives: &[&'a Directive], name: &str) -> Option<&'a Directive> {
+    directives.iter().rev().find(|d| d.name() == name).copied()
+}
+
+/// Arguments of `directive` joined by spaces
+fn first(directive: &Directive) -> String {
+    directive
+        .args()
+        .iter()
+        .map(Value::unquoted)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// A setting to check
+struct Check<'a> {
+    setting: &'static str,
+    directive: Option<&'a Directive>,
+    default: &'static str,
+    recommended: String,
+    weight: u32,
+    ok: &'a dyn Fn(&str) -> bool,
+    help: &'static str,
+}
+
+impl Check<'_> {
+    fn evaluate(self) -> (TuningCheck, Option<Finding>) {
+        let configured = self.directive.map(first);
+        let effective = configured
+            .clone()
+            .unwrap_or_else(|| self.default.to_string());
+        let ok = (self.ok)(&effective);
+
+        let finding = (!ok).then(|| match self.directive {
+            Some(directive) => Finding::new(
+                "performance",
+                Severity::Warning,
+                format!(
+                    "{} {effective} is not recommended (recommended: {})",
+                    self.setting, self.recommended
+                ),
+            )
+            .at(directive),
+            None => Finding::new(
+                "performance",
+                Severity::Info,
+                format!(
+                    "{} is not set and defaults to {effective} (recommended: {})",
+                    self.setting, self.recommended
+                ),
+            ),
+        });
+
+        let check = TuningCheck {
+            setting: self.setting.to_string(),
+            configured,
+            effective,
+            recommended: self.recommended,
+            ok,
+            weight: self.weight,
+            span: self.directive.map(|d| d.span),
+        };
+        (check, finding.map(|f| f.with_help(self.help)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_performance_tuned() {
+        let config = parse(
+            r"
+worker_processes auto;
+worker_rlimit_nofile 65535;
+events { worker_connections 8192; }
+http {
+    sendfile on;
+    tcp_nopush on;
+    keepalive_timeout 65;
+    gzip on;
+    gzip_types text/css application/json;
+    open_file_cache max=10000 inactive=30s;
+}
+",
+        )
+        .unwrap();
+        let report = performance(&config);
+
+        assert_eq!(report.score, 100);
+        assert_eq!(report.grade(), 'A');
+        assert!(report.findings.is_empty());
+        let nodelay = &report.checks[5];
+        assert_eq!(nodelay.setting, "tcp_nodelay");
+        assert_eq!(nodelay.configured, None);
+        assert_eq!(nodelay.effective, "on");
+    }
+
+    #[test]
+    fn test_performance_defaults() {
+        // A snippet without http is checked as the http level
+        let config = parse("sendfile on; tcp_nodelay off; keepalive_timeout 300s;").unwrap();
+        let report = performance(&config);
+
+        assert_eq!(report.score, 16);
+        assert_eq!(report.grade(), 'D');
+        let found: Vec<_> = report
+            .findings
+            .iter()
+            .map(|f| (f.severity, f.line()))
+            .collect();
+        assert_eq!(found[0], (Severity::Info, None));
+        assert!(found.contains(&(Severity::Warning, Some(1))));
+        assert_eq!(report.findings.len(), 9);
+        assert!(report.findings[1].message.contains("1024 or more"));
+    }
+}
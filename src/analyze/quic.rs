@@ -0,0 +1,381 @@
+//! HTTP/3 (QUIC) configuration
+//!
+//! `listen 443 quic` opens a UDP socket for HTTP/3. Browsers never start
+//! with HTTP/3: they connect over TCP first and only switch after the
+//! response advertises the QUIC endpoint in an `Alt-Svc` header
+//! (`add_header Alt-Svc 'h3=":443"; ma=86400';`). A QUIC server therefore
+//! needs a TCP listener on the same port as a fallback and the header.
+//!
+//! [`quic_audit`] lists every server with a QUIC listener or an HTTP/3
+//! advertisement, with its effective `http3`, `ssl_early_data`,
+//! `quic_retry` and `Alt-Svc` settings, and reports:
+//!
+//! - `quic_no_fallback`: a QUIC port without a TCP listener on the same
+//!   port in the server
+//! - `quic_alt_svc`: a QUIC listener not advertised in `Alt-Svc`, or an
+//!   `Alt-Svc` advertising a port the server does not listen on with QUIC
+//! - `quic_early_data`: `ssl_early_data on`, whose 0-RTT requests can be
+//!   replayed
+//!
+//! Whether the UDP port is reachable is a live check of the `network`
+//! module, behind the `network` feature.
+
+use crate::ast::{Config, Directive};
+use crate::extract;
+use crate::lint::{walk, Finding, Severity};
+use crate::types::{ListenDirective, Server};
+
+/// HTTP/3 settings of a server
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct QuicServer {
+    /// First `server_name` of the server (`_` if none)
+    pub server_name: String,
+
+    /// Line of the `server` directive
+    pub line: usize,
+
+    /// UDP listeners (`listen ... quic`)
+    pub quic_listeners: Vec<ListenDirective>,
+
+    /// TCP listeners
+    pub tcp_listeners: Vec<ListenDirective>,
+
+    /// Effective `http3` (default `on`)
+    pub http3: bool,
+
+    /// Effective `ssl_early_data` (default `off`)
+    pub early_data: bool,
+
+    /// Effective `quic_retry` (default `off`)
+    pub quic_retry: bool,
+
+    /// Effective `Alt-Svc` header value
+    pub alt_svc: Option<String>,
+}
+
+impl QuicServer {
+    /// Ports advertised for HTTP/3 (`h3=":443"`) in the `Alt-Svc` header
+    #[must_use]
+    pub fn advertised_ports(&self) -> Vec<u16> {
+        self.alt_svc
+            .as_deref()
+            .map(alt_svc_h3_ports)
+            .unwrap_or_default()
+    }
+
+    /// QUIC ports without a TCP listener on the same port
+    #[must_use]
+    pub fn missing_fallback(&self) -> Vec<u16> {
+        let mut ports: Vec<u16> = self
+            .quic_listeners
+            .iter()
+            .map(|l| l.port)
+            .filter(|port| !self.tcp_listeners.iter().any(|l| l.port == *port))
+            .collect();
+        ports.dedup();
+        ports
+    }
+
+    /// Whether HTTP/3 is served: a QUIC listener with `http3` on
+    #[must_use]
+    pub fn serves_http3(&self) -> bool {
+        self.http3 && !self.quic_listeners.is_empty()
+    }
+}
+
+/// Result of [`quic_audit`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct QuicReport {
+    /// Servers with a QUIC listener or an HTTP/3 advertisement, in file
+    /// order
+    pub servers: Vec<QuicServer>,
+
+    /// Problems found (`quic_no_fallback`, `quic_alt_svc`,
+    /// `quic_early_data`)
+    pub findings: Vec<Finding>,
+}
+
+impl QuicReport {
+    /// Whether the configuration serves HTTP/3 at all
+    #[must_use]
+    pub fn is_used(&self) -> bool {
+        self.servers.iter().any(QuicServer::serves_http3)
+    }
+}
+
+/// Check the HTTP/3 setup of every server.
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::{analyze::quic_audit, parse};
+///
+/// let config = parse(r#"
+/// http {
+///     server {
+///         listen 443 ssl;
+///         listen 443 quic reuseport;
+///         add_header Alt-Svc 'h3=":443"; ma=86400';
+///     }
+///     server {
+///         listen 8443 quic;
+///     }
+/// }
+/// "#)?;
+/// let report = quic_audit(&config);
+///
+/// assert_eq!(report.servers[0].advertised_ports(), vec![443]);
+/// assert_eq!(report.servers[1].missing_fallback(), vec![8443]);
+///
+/// let rules: Vec<_> = report.findings.iter().map(|f| f.rule.as_str()).collect();
+/// assert_eq!(rules, vec!["quic_no_fallback", "quic_alt_svc"]);
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+#[must_use]
+pub fn quic_audit(config: &Config) -> QuicReport {
+    let mut report = QuicReport::default();
+
+    walk(config, &mut |directive, parents| {
+        let is_http_server = directive.name() == "server"
+            && directive.is_block()
+            && parents.last().map(|p| p.name()) == Some("http");
+        if !is_http_server {
+            return;
+        }
+
+        let server = quic_server(directive, parents);
+        if server.quic_listeners.is_empty() && server.advertised_ports().is_empty() {
+            return;
+        }
+        report.findings.extend(check_server(directive, &server));
+        report.servers.push(server);
+    });
+
+    report
+}
+
+fn quic_server(server: &Directive, parents: &[&Directive]) -> QuicServer {
+    // Innermost block first
+    let chain: Vec<&Directive> = std::iter::once(server)
+        .chain(parents.iter().rev().copied())
+        .collect();
+    let flag = |name: &str, default: bool| -> bool {
+        chain
+            .iter()
+            .find_map(|block| block.find_children(name).last().copied())
+            .and_then(Directive::first_arg)
+            .map_or(default, |value| value == "on")
+    };
+
+    let (quic_listeners, tcp_listeners) = extract::listens(server)
+        .into_iter()
+        .partition(ListenDirective::is_quic);
+
+    QuicServer {
+        server_name: Server::primary_name_of(server),
+        line: server.span.line,
+        quic_listeners,
+        tcp_listeners,
+        http3: flag("http3", true),
+        early_data: flag("ssl_early_data", false),
+        quic_retry: flag("quic_retry", false),
+        alt_svc: alt_svc(&chain),
+    }
+}
+
+/// `add_header` is inherited only by blocks that define none of their own
+fn alt_svc(chain: &[&Directive]) -> Option<String> {
+    let headers = chain
+        .iter()
+        .map(|block| block.find_children("add_header"))
+        .find(|headers| !headers.is_empty())?;
+
+    headers.iter().rev().find_map(|header| match header.args() {
+        [name, value, ..] if name.as_str().eq_ignore_ascii_case("alt-svc") => {
+            Some(value.unquoted())
+        }
+        _ => None,
+    })
+}
+
+/// Ports of the `h3` (and draft `h3-NN`) entries of an `Alt-Svc` value
+fn alt_svc_h3_ports(value: &str) -> Vec<u16> {
+    value
+        .split(',')
+        .filter_map(|entry| {
+            let (protocol, authority) = entry.split(';').next()?.split_once('=')?;
+            if !protocol.trim().starts_with("h3") {
+                return None;
+            }
+            let authority = authority.trim().trim_matches('"');
+            authority.rsplit(':').next()?.parse().ok()
+        })
+        .collect()
+}
+
+fn check_server(directive: &Directive, server: &QuicServer) -> Vec<Finding> {
+    let name = &server.server_name;
+    let mut findings = Vec::new();
+    let quic_ports: Vec<u16> = server.quic_listeners.iter().map(|l| l.port).collect();
+
+    for port in server.missing_fallback() {
+        findings.push(
+            Finding::new(
+                "quic_no_fallback",
+                Severity::Warning,
+                format!(
+                    "server \"{name}\" listens for QUIC on port {port} without a TCP listener \
+                     on the same port; clients connect over TCP first and never reach HTTP/3"
+                ),
+            )
+            .at(directive)
+            .with_help(format!(
+                "Add \"listen {port} ssl;\" next to the quic listener"
+            )),
+        );
+    }
+
+    let advertised = server.advertised_ports();
+    if server.serves_http3() {
+        let mut missing: Vec<u16> = quic_ports
+            .iter()
+            .copied()
+            .filter(|port| !advertised.contains(port))
+            .collect();
+        missing.dedup();
+        for port in missing {
+            findings.push(
+                Finding::new(
+                    "quic_alt_svc",
+                    Severity::Warning,
+                    format!(
+                        "server \"{name}\" listens for QUIC on port {port} but does not \
+                         advertise it in Alt-Svc, so browsers stay on TCP"
+                    ),
+                )
+                .at(directive)
+                .with_help(format!(
+                    "Add: add_header Alt-Svc 'h3=\":{port}\"; ma=86400'; (add_header in a \
+                     location replaces all inherited ones)"
+                )),
+            );
+        }
+    }
+    for port in advertised {
+        if !server.http3 || !quic_ports.contains(&port) {
+            findings.push(
+                Finding::new(
+                    "quic_alt_svc",
+                    Severity::Warning,
+                    format!(
+                        "server \"{name}\" advertises HTTP/3 on port {port} in Alt-Svc but \
+                         does not serve it there"
+                    ),
+                )
+                .at(directive)
+                .with_help(format!(
+                    "Add \"listen {port} quic;\" or remove the h3 entry from Alt-Svc"
+                )),
+            );
+        }
+    }
+
+    if server.early_data && server.serves_http3() {
+        findings.push(
+            Finding::new(
+                "quic_early_data",
+                Severity::Info,
+                format!("server \"{name}\" accepts 0-RTT early data, which an attacker can replay"),
+            )
+            .at(directive)
+            .with_help(
+                "Pass \"proxy_set_header Early-Data $ssl_early_data;\" so backends can reject \
+                 non-idempotent early requests (HTTP 425)",
+            ),
+        );
+    }
+
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_alt_svc_h3_ports() {
+        assert_eq!(
+            alt_svc_h3_ports(r#"h3=":443"; ma=86400, h3-29=":8443", h2=":443""#),
+            vec![443, 8443]
+        );
+        assert_eq!(alt_svc_h3_ports(r#"h3="cdn.example.com:443""#), vec![443]);
+        assert!(alt_svc_h3_ports("clear").is_empty());
+    }
+
+    #[test]
+    fn test_quic_audit() {
+        let config = parse(
+            r#"
+http {
+    ssl_early_data on;
+    add_header Alt-Svc 'h3=":443"; ma=86400';
+    server {
+        server_name good.example.com;
+        listen 443 ssl;
+        listen [::]:443 quic reuseport;
+        quic_retry on;
+    }
+    server {
+        server_name plain.example.com;
+        listen 80;
+    }
+    server {
+        server_name off.example.com;
+        listen 443 ssl;
+        listen 443 quic;
+        http3 off;
+        add_header X-Frame-Options DENY;
+    }
+}
+"#,
+        )
+        .unwrap();
+        let report = quic_audit(&config);
+
+        // plain.example.com inherits the advertisement from http
+        let names: Vec<_> = report
+            .servers
+            .iter()
+            .map(|s| s.server_name.as_str())
+            .collect();
+        assert_eq!(
+            names,
+            vec!["good.example.com", "plain.example.com", "off.example.com"]
+        );
+
+        let good = &report.servers[0];
+        assert!(good.serves_http3());
+        assert!(good.quic_retry);
+        assert!(good.early_data);
+        assert_eq!(good.quic_listeners[0].address, "::");
+        assert!(good.missing_fallback().is_empty());
+
+        let off = &report.servers[2];
+        assert!(!off.serves_http3());
+        assert_eq!(off.alt_svc, None);
+        assert!(report.is_used());
+
+        let found: Vec<_> = report
+            .findings
+            .iter()
+            .map(|f| (f.rule.as_str(), f.line()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![("quic_early_data", Some(5)), ("quic_alt_svc", Some(11))]
+        );
+    }
+}
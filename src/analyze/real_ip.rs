@@ -0,0 +1,344 @@
+//! PROXY protocol and real client IP
+//!
+//! Behind a load balancer `$remote_addr` is the balancer's address unless
+//! the realip module replaces it. With `listen ... proxy_protocol` the
+//! client address arrives in the PROXY protocol header, and nginx uses it
+//! when the connection comes from a trusted `set_real_ip_from` address and
+//! (in `http`) `real_ip_header proxy_protocol` is set; the default
+//! `real_ip_header` is `X-Real-IP`.
+//!
+//! [`real_ip_audit`] follows `set_real_ip_from` (inherited only by blocks
+//! that define none) and `real_ip_header` from `http`/`stream` into every
+//! server and reports:
+//!
+//! - `real_ip_missing`: a `proxy_protocol` listener without
+//!   `set_real_ip_from`, so `$remote_addr` is the load balancer
+//! - `real_ip_header`: `real_ip_header proxy_protocol` in a server without
+//!   a `proxy_protocol` listener (the address is never replaced), or a
+//!   `proxy_protocol` server reading the client address from a header
+//! - `real_ip_trust_all`: `set_real_ip_from 0.0.0.0/0` or `::/0`, which
+//!   lets any client choose its address
+
+use crate::ast::{Config, Directive, Span};
+use crate::extract;
+use crate::lint::{walk, Finding, Severity};
+use crate::types::{ListenDirective, Server};
+
+/// Real client IP settings of a server
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RealIpServer {
+    /// First `server_name` of the server (`_` if none)
+    pub server_name: String,
+
+    /// Line of the `server` directive
+    pub line: usize,
+
+    /// Whether the server is inside `stream`
+    pub stream: bool,
+
+    /// Ports of the `proxy_protocol` listeners
+    pub proxy_protocol_ports: Vec<u16>,
+
+    /// Ports of the other listeners
+    pub direct_ports: Vec<u16>,
+
+    /// Effective trusted addresses (`set_real_ip_from`)
+    pub trusted: Vec<String>,
+
+    /// Effective `real_ip_header` (`X-Real-IP` when not set), `None` in
+    /// `stream`, which always uses the PROXY protocol
+    pub real_ip_header: Option<String>,
+}
+
+impl RealIpServer {
+    /// Whether the server has a `proxy_protocol` listener
+    #[must_use]
+    pub fn uses_proxy_protocol(&self) -> bool {
+        !self.proxy_protocol_ports.is_empty()
+    }
+
+    /// Whether `$remote_addr` is replaced with the address from the PROXY
+    /// protocol header
+    #[must_use]
+    pub fn resolves_client_ip(&self) -> bool {
+        self.uses_proxy_protocol()
+            && !self.trusted.is_empty()
+            && self
+                .real_ip_header
+                .as_deref()
+                .map_or(true, |header| header == "proxy_protocol")
+    }
+}
+
+/// Result of [`real_ip_audit`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RealIpReport {
+    /// Servers using `proxy_protocol` or the realip module, in file order
+    pub servers: Vec<RealIpServer>,
+
+    /// Problems found (`real_ip_missing`, `real_ip_header`,
+    /// `real_ip_trust_all`)
+    pub findings: Vec<Finding>,
+}
+
+/// Check that servers behind a PROXY protocol load balancer see the real
+/// client address.
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::{analyze::real_ip_audit, parse};
+///
+/// let config = parse(r"
+/// http {
+///     server {
+///         listen 80 proxy_protocol;
+///         set_real_ip_from 10.0.0.0/8;
+///         real_ip_header proxy_protocol;
+///     }
+///     server {
+///         listen 8080 proxy_protocol;
+///     }
+/// }
+/// ")?;
+/// let report = real_ip_audit(&config);
+///
+/// assert!(report.servers[0].resolves_client_ip());
+/// assert!(!report.servers[1].resolves_client_ip());
+/// assert_eq!(report.findings[0].rule, "real_ip_missing");
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+#[must_use]
+pub fn real_ip_audit(config: &Config) -> RealIpReport {
+    let mut report = RealIpReport::default();
+    let mut reported: Vec<Span> = Vec::new();
+
+    walk(config, &mut |directive, parents| {
+        let in_context = parents
+            .last()
+            .is_some_and(|p| matches!(p.name(), "http" | "stream"));
+        if directive.name() != "server" || !directive.is_block() || !in_context {
+            return;
+        }
+
+        let (server, trusted) = real_ip_server(directive, parents);
+        let realip_configured = directive.find_children("set_real_ip_from").len()
+            + directive.find_children("real_ip_header").len()
+            > 0;
+        if !server.uses_proxy_protocol() && !realip_configured {
+            return;
+        }
+        report.findings.extend(check_server(directive, &server));
+
+        // Reported once for blocks shared by several servers
+        for from in trusted {
+            let trusts_all = from
+                .first_arg()
+                .is_some_and(|a| a == "0.0.0.0/0" || a == "::/0");
+            if trusts_all && !reported.contains(&from.span) {
+                reported.push(from.span);
+                report.findings.push(trust_all_finding(from));
+            }
+        }
+        report.servers.push(server);
+    });
+
+    report
+}
+
+/// Effective settings of `server`, with the `set_real_ip_from`
+/// directives that apply
+fn real_ip_server<'a>(
+    server: &'a Directive,
+    parents: &[&'a Directive],
+) -> (RealIpServer, Vec<&'a Directive>) {
+    // Innermost block first
+    let chain: Vec<&Directive> = std::iter::once(server)
+        .chain(parents.iter().rev().copied())
+        .collect();
+    let stream = parents.iter().any(|p| p.name() == "stream");
+
+    let (proxy_protocol, direct): (Vec<ListenDirective>, Vec<ListenDirective>) =
+        extract::listens(server)
+            .into_iter()
+            .partition(|listen| listen.proxy_protocol);
+
+    let trusted = chain
+        .iter()
+        .map(|block| block.find_children("set_real_ip_from"))
+        .find(|from| !from.is_empty())
+        .unwrap_or_default();
+    let real_ip_header = (!stream).then(|| {
+        chain
+            .iter()
+            .find_map(|block| block.find_children("real_ip_header").last().copied())
+            .and_then(Directive::first_arg)
+            .unwrap_or_else(|| "X-Real-IP".to_string())
+    });
+
+    let settings = RealIpServer {
+        server_name: Server::primary_name_of(server),
+        line: server.span.line,
+        stream,
+        proxy_protocol_ports: proxy_protocol.iter().map(|l| l.port).collect(),
+        direct_ports: direct.iter().map(|l| l.port).collect(),
+        trusted: trusted.iter().filter_map(|d| d.first_arg()).collect(),
+        real_ip_header,
+    };
+    (settings, trusted)
+}
+
+fn check_server(directive: &Directive, server: &RealIpServer) -> Vec<Finding> {
+    let name = &server.server_name;
+    let header = server.real_ip_header.as_deref();
+    let mut findings = Vec::new();
+
+    if server.uses_proxy_protocol() && server.trusted.is_empty() {
+        findings.push(
+            Finding::new(
+                "real_ip_missing",
+                Severity::Warning,
+                format!(
+                    "server \"{name}\" accepts the PROXY protocol but has no set_real_ip_from, \
+                     so $remote_addr is the load balancer's address"
+                ),
+            )
+            .at(directive)
+            .with_help(if server.stream {
+                "Add \"set_real_ip_from <load balancer CIDR>;\""
+            } else {
+                "Add \"set_real_ip_from <load balancer CIDR>;\" and \
+                 \"real_ip_header proxy_protocol;\""
+            }),
+        );
+    } else if server.uses_proxy_protocol() && header.is_some_and(|h| h != "proxy_protocol") {
+        findings.push(
+            Finding::new(
+                "real_ip_header",
+                Severity::Warning,
+                format!(
+                    "server \"{name}\" accepts the PROXY protocol but takes the client address \
+                     from the {} header",
+                    header.unwrap_or_default()
+                ),
+            )
+            .at(directive)
+            .with_help("Add \"real_ip_header proxy_protocol;\""),
+        );
+    }
+
+    if !server.uses_proxy_protocol() && header == Some("proxy_protocol") {
+        findings.push(
+            Finding::new(
+                "real_ip_header",
+                Severity::Warning,
+                format!(
+                    "server \"{name}\" uses real_ip_header proxy_protocol without a \
+                     proxy_protocol listener, so $remote_addr is never replaced"
+                ),
+            )
+            .at(directive)
+            .with_help("Add the proxy_protocol parameter to the listen directives"),
+        );
+    }
+
+    findings
+}
+
+fn trust_all_finding(from: &Directive) -> Finding {
+    Finding::new(
+        "real_ip_trust_all",
+        Severity::Warning,
+        format!(
+            "set_real_ip_from {} trusts every address, so any client can choose its \
+             $remote_addr",
+            from.first_arg().unwrap_or_default()
+        ),
+    )
+    .at(from)
+    .with_help("Trust only the load balancer addresses")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_real_ip_audit() {
+        let config = parse(
+            r"
+http {
+    set_real_ip_from 10.0.0.0/8;
+    server {
+        server_name lb.example.com;
+        listen 80 proxy_protocol;
+        real_ip_header proxy_protocol;
+    }
+    server {
+        server_name header.example.com;
+        listen 443 ssl proxy_protocol;
+        listen 8443 ssl;
+    }
+    server {
+        server_name direct.example.com;
+        listen 80;
+        real_ip_header proxy_protocol;
+    }
+    server {
+        server_name plain.example.com;
+        listen 80;
+    }
+}
+stream {
+    server {
+        listen 5432 proxy_protocol;
+        set_real_ip_from ::/0;
+        proxy_pass db:5432;
+    }
+}
+",
+        )
+        .unwrap();
+        let report = real_ip_audit(&config);
+
+        let summary: Vec<_> = report
+            .servers
+            .iter()
+            .map(|s| (s.server_name.as_str(), s.resolves_client_ip()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("lb.example.com", true),
+                ("header.example.com", false),
+                ("direct.example.com", false),
+                ("_", true),
+            ]
+        );
+
+        let header = &report.servers[1];
+        assert_eq!(header.trusted, vec!["10.0.0.0/8"]);
+        assert_eq!(header.real_ip_header.as_deref(), Some("X-Real-IP"));
+        assert_eq!(header.proxy_protocol_ports, vec![443]);
+        assert_eq!(header.direct_ports, vec![8443]);
+        assert!(report.servers[3].stream);
+        assert_eq!(report.servers[3].real_ip_header, None);
+
+        let found: Vec<_> = report
+            .findings
+            .iter()
+            .map(|f| (f.rule.as_str(), f.line()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("real_ip_header", Some(9)),
+                ("real_ip_header", Some(14)),
+                ("real_ip_trust_all", Some(27)),
+            ]
+        );
+    }
+}
@@ -0,0 +1,1273 @@
+//! TLS protocols, ciphers and key exchange
+//!
+//! [`tls_audit`] lists every server with a TLS listener together with its
+//! effective `ssl_protocols`, `ssl_ciphers`, `ssl_ecdh_curve` and
+//! `ssl_dhparam`, reads the key size of the dhparam file, and grades the
+//! server against Mozilla's server side TLS profiles:
+//!
+//! - modern: TLSv1.3 only
+//! - intermediate: TLSv1.2 and TLSv1.3, AEAD ciphers with forward secrecy,
+//!   DH parameters of at least 2048 bits
+//!
+//! Anything weaker is [`CipherProfile::Legacy`]. The findings say what to
+//! change:
+//!
+//! - `tls_protocol`: `SSLv3`, `TLSv1` or `TLSv1.1` enabled
+//! - `tls_cipher`: RC4, 3DES, export, anonymous or NULL ciphers, static
+//!   RSA key exchange (`TLS_RSA_*`), CBC ciphers, curves below 128-bit
+//!   security
+//! - `tls_dhparam`: DH parameters smaller than 2048 bits
+//!
+//! Session resumption is checked too, against the number of sessions
+//! expected within `ssl_session_timeout` and the number of nginx
+//! instances serving the same names ([`TlsAuditOptions`]):
+//!
+//! - `tls_session_cache`: a shared cache too small for the expected
+//!   sessions, a per-worker `builtin` cache, one zone declared with two
+//!   sizes, no resumption at all, a timeout longer than a day
+//! - `tls_session_ticket`: tickets whose key is generated at startup,
+//!   which differs between instances and is never rotated, or a single
+//!   `ssl_session_ticket_key` that cannot be rotated without invalidating
+//!   every ticket
+//!
+//! Unset directives take the defaults of current nginx: `TLSv1.2 TLSv1.3`
+//! and `HIGH:!aNULL:!MD5`. The cipher string is not expanded the way
+//! OpenSSL does; explicit cipher names, the common class keywords and
+//! `+` combinations such as `ECDHE+AESGCM` are recognized.
+
+use crate::ast::{parse_duration, parse_size, Config, Directive, Span};
+use crate::extract;
+use crate::lint::{walk, Finding, Severity};
+use crate::pem::{der_element, pem_body};
+use crate::types::Server;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::Path;
+use std::time::Duration;
+
+/// `ssl_protocols` of nginx 1.23.4 and later when not set
+const DEFAULT_PROTOCOLS: &[&str] = &["TLSv1.2", "TLSv1.3"];
+
+/// `ssl_ciphers` when not set
+const DEFAULT_CIPHERS: &str = "HIGH:!aNULL:!MD5";
+
+/// Cipher list of Mozilla's intermediate profile, in its order
+pub const INTERMEDIATE_CIPHERS: &[&str] = &[
+    "ECDHE-ECDSA-AES128-GCM-SHA256",
+    "ECDHE-RSA-AES128-GCM-SHA256",
+    "ECDHE-ECDSA-AES256-GCM-SHA384",
+    "ECDHE-RSA-AES256-GCM-SHA384",
+    "ECDHE-ECDSA-CHACHA20-POLY1305",
+    "ECDHE-RSA-CHACHA20-POLY1305",
+    "DHE-RSA-AES128-GCM-SHA256",
+    "DHE-RSA-AES256-GCM-SHA384",
+    "DHE-RSA-CHACHA20-POLY1305",
+];
+
+/// Smallest DH parameters the intermediate profile accepts
+const MIN_DHPARAM_BITS: u32 = 2048;
+
+/// `ssl_session_timeout` when not set
+const DEFAULT_SESSION_TIMEOUT: &str = "5m";
+
+/// Sessions one megabyte of shared cache holds, per the nginx documentation
+const SESSIONS_PER_MEGABYTE: u64 = 4000;
+
+/// Longest `ssl_session_timeout` Mozilla recommends
+const MAX_SESSION_TIMEOUT: Duration = Duration::from_secs(86_400);
+
+/// Key exchange prefixes of cipher names with forward secrecy, or without
+/// RSA key exchange at all
+const KEY_EXCHANGE_PREFIXES: &[&str] = &[
+    "ECDHE-", "DHE-", "EDH-", "ECDH-", "ADH-", "AECDH-", "PSK-", "RSA-PSK-", "SRP-", "TLS_",
+];
+
+/// Cipher classes that include static RSA and CBC ciphers
+const CLASS_KEYWORDS: &[&str] = &[
+    "ALL", "DEFAULT", "HIGH", "AES", "AES128", "AES256", "CAMELLIA", "SHA", "SHA1", "SHA256",
+    "SHA384", "kRSA", "RSA",
+];
+
+/// Key exchange classes with forward secrecy
+const FORWARD_SECRET_CLASSES: &[&str] = &[
+    "ECDHE", "EECDH", "kECDHE", "kEECDH", "DHE", "EDH", "kDHE", "kEDH",
+];
+
+/// Cipher classes of AEAD ciphers in Mozilla's intermediate profile
+const AEAD_CLASSES: &[&str] = &["AESGCM", "CHACHA20"];
+
+/// Insecure cipher classes: `(label, OpenSSL keyword, matcher)`
+#[allow(clippy::type_complexity)]
+const INSECURE: &[(&str, &str, fn(&str) -> bool)] = &[
+    ("RC4", "RC4", |c| c.contains("RC4")),
+    ("3DES", "3DES", |c| c.contains("DES")),
+    ("MD5", "MD5", |c| c.contains("MD5")),
+    ("export", "EXPORT", |c| c.starts_with("EXP")),
+    ("anonymous", "aNULL", |c| {
+        c == "aNULL" || c.starts_with("ADH") || c.starts_with("AECDH")
+    }),
+    ("NULL", "eNULL", |c| {
+        c == "eNULL" || c == "NULL" || c.contains("-NULL-")
+    }),
+    ("low-strength", "LOW", |c| c == "LOW"),
+    ("medium-strength", "MEDIUM", |c| c == "MEDIUM"),
+];
+
+/// Curves below 128-bit security
+const WEAK_CURVES: &[&str] = &[
+    "secp160k1",
+    "secp160r1",
+    "secp160r2",
+    "secp192k1",
+    "prime192v1",
+    "secp192r1",
+    "secp224k1",
+    "secp224r1",
+    "P-192",
+    "P-224",
+];
+
+/// How a server compares to Mozilla's TLS profiles
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum CipherProfile {
+    /// Below the intermediate profile
+    Legacy,
+    /// Mozilla's intermediate profile
+    Intermediate,
+    /// Mozilla's modern profile
+    Modern,
+}
+
+impl fmt::Display for CipherProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Legacy => "legacy",
+            Self::Intermediate => "intermediate",
+            Self::Modern => "modern",
+        })
+    }
+}
+
+/// TLS settings of a server
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TlsServer {
+    /// First `server_name` of the server (`_` if none)
+    pub server_name: String,
+
+    /// Line of the `server` directive
+    pub line: usize,
+
+    /// Effective `ssl_protocols`
+    pub protocols: Vec<String>,
+
+    /// Effective `ssl_ciphers`
+    pub ciphers: String,
+
+    /// Effective `ssl_ecdh_curve`
+    pub ecdh_curve: Option<String>,
+
+    /// Effective `ssl_dhparam`
+    pub dhparam: Option<String>,
+
+    /// Size of the prime in the dhparam file, when it could be read
+    pub dhparam_bits: Option<u32>,
+
+    /// Effective `ssl_session_cache` (`shared:SSL:10m`, `off`)
+    pub session_cache: Option<String>,
+
+    /// Effective `ssl_session_timeout`
+    pub session_timeout: String,
+
+    /// Effective `ssl_session_tickets` (default `on`)
+    pub session_tickets: bool,
+
+    /// Effective `ssl_session_ticket_key` files; the first encrypts
+    pub session_ticket_keys: Vec<String>,
+}
+
+impl TlsServer {
+    /// The strongest Mozilla profile the server meets
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nginx_discovery::analyze::{tls_audit, CipherProfile};
+    /// use nginx_discovery::parse;
+    ///
+    /// let config = parse(
+    ///     "http { server { listen 443 ssl; ssl_protocols TLSv1.3; } }",
+    /// )?;
+    /// let report = tls_audit(&config, "/etc/nginx");
+    /// assert_eq!(report.servers[0].profile(), CipherProfile::Modern);
+    /// # Ok::<(), nginx_discovery::Error>(())
+    /// ```
+    #[must_use]
+    pub fn profile(&self) -> CipherProfile {
+        if self.protocols.iter().all(|p| p == "TLSv1.3") {
+            return CipherProfile::Modern;
+        }
+        let protocols_ok = self
+            .protocols
+            .iter()
+            .all(|p| DEFAULT_PROTOCOLS.contains(&p.as_str()));
+        let ciphers_ok = enabled(&self.ciphers).all(|c| {
+            c.starts_with("TLS_")
+                || INTERMEDIATE_CIPHERS.contains(&c)
+                || (combines(c, FORWARD_SECRET_CLASSES) && combines(c, AEAD_CLASSES))
+        });
+        let dhparam_ok = !self
+            .dhparam_bits
+            .is_some_and(|bits| bits < MIN_DHPARAM_BITS);
+        if protocols_ok && ciphers_ok && dhparam_ok {
+            CipherProfile::Intermediate
+        } else {
+            CipherProfile::Legacy
+        }
+    }
+
+    /// Shared cache zones as `(name, size in bytes)`
+    #[must_use]
+    pub fn session_cache_zones(&self) -> Vec<(&str, u64)> {
+        self.session_cache
+            .iter()
+            .flat_map(|cache| cache.split_whitespace())
+            .filter_map(|part| {
+                let (name, size) = part.strip_prefix("shared:")?.split_once(':')?;
+                Some((name, parse_size(size)?))
+            })
+            .collect()
+    }
+
+    /// Sessions the shared cache holds, about 4000 per megabyte
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nginx_discovery::{analyze::tls_audit, parse};
+    ///
+    /// let config = parse(
+    ///     "http { server { listen 443 ssl; ssl_session_cache shared:SSL:10m; } }",
+    /// )?;
+    /// let report = tls_audit(&config, "/etc/nginx");
+    /// assert_eq!(report.servers[0].session_cache_capacity(), Some(40_000));
+    /// # Ok::<(), nginx_discovery::Error>(())
+    /// ```
+    #[must_use]
+    pub fn session_cache_capacity(&self) -> Option<u64> {
+        let zones = self.session_cache_zones();
+        if zones.is_empty() {
+            return None;
+        }
+        Some(
+            zones
+                .iter()
+                .map(|(_, size)| size * SESSIONS_PER_MEGABYTE / (1024 * 1024))
+                .sum(),
+        )
+    }
+
+    /// Whether clients can resume sessions, through the cache or tickets
+    #[must_use]
+    pub fn resumes_sessions(&self) -> bool {
+        self.session_tickets
+            || self
+                .session_cache
+                .as_deref()
+                .is_some_and(|cache| !matches!(cache, "off" | "none"))
+    }
+}
+
+/// Options for [`tls_audit_with`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TlsAuditOptions {
+    /// TLS sessions expected within `ssl_session_timeout`, across all
+    /// servers; `None` estimates `worker_processes` × `worker_connections`
+    /// (`worker_processes auto` counts as one)
+    pub expected_sessions: Option<u64>,
+
+    /// nginx instances serving the same names, such as the nodes behind a
+    /// load balancer
+    pub instances: usize,
+}
+
+impl Default for TlsAuditOptions {
+    fn default() -> Self {
+        Self {
+            expected_sessions: None,
+            instances: 1,
+        }
+    }
+}
+
+/// Result of [`tls_audit`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TlsReport {
+    /// Servers with a TLS listener, in file order
+    pub servers: Vec<TlsServer>,
+
+    /// TLS sessions the session cache was checked against
+    pub expected_sessions: u64,
+
+    /// Problems found (`tls_protocol`, `tls_cipher`, `tls_dhparam`,
+    /// `tls_session_cache`, `tls_session_ticket`), once per directive
+    pub findings: Vec<Finding>,
+}
+
+/// Grade the TLS settings of every server.
+///
+/// Relative `ssl_dhparam` paths are resolved against `base_dir`.
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::analyze::{tls_audit, CipherProfile};
+/// use nginx_discovery::parse;
+///
+/// let config = parse(r#"
+/// http {
+///     ssl_protocols TLSv1 TLSv1.1 TLSv1.2;
+///     ssl_ciphers ECDHE-RSA-AES128-GCM-SHA256:AES128-SHA;
+///     server {
+///         listen 443 ssl;
+///     }
+/// }
+/// "#)?;
+/// let report = tls_audit(&config, "/etc/nginx");
+///
+/// assert_eq!(report.servers[0].profile(), CipherProfile::Legacy);
+/// let rules: Vec<_> = report.findings.iter().map(|f| f.rule.as_str()).collect();
+/// assert_eq!(
+///     rules,
+///     vec!["tls_protocol", "tls_cipher", "tls_cipher", "tls_session_ticket"]
+/// );
+/// assert!(report.findings[1].message.contains("TLS_RSA_*"));
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+#[must_use]
+pub fn tls_audit(config: &Config, base_dir: impl AsRef<Path>) -> TlsReport {
+    tls_audit_with(config, base_dir, &TlsAuditOptions::default())
+}
+
+/// Grade the TLS settings of every server, checking session resumption
+/// against `options`.
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::analyze::{tls_audit_with, TlsAuditOptions};
+/// use nginx_discovery::parse;
+///
+/// let config = parse("
+/// http {
+///     ssl_session_cache shared:SSL:1m;
+///     server { listen 443 ssl; ssl_protocols TLSv1.3; }
+/// }
+/// ")?;
+/// let options = TlsAuditOptions { expected_sessions: Some(20_000), instances: 2 };
+/// let report = tls_audit_with(&config, "/etc/nginx", &options);
+///
+/// let rules: Vec<_> = report.findings.iter().map(|f| f.rule.as_str()).collect();
+/// assert_eq!(rules, vec!["tls_session_cache", "tls_session_ticket"]);
+/// assert!(report.findings[0].message.contains("about 4000 sessions"));
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+#[must_use]
+pub fn tls_audit_with(
+    config: &Config,
+    base_dir: impl AsRef<Path>,
+    options: &TlsAuditOptions,
+) -> TlsReport {
+    let base_dir = base_dir.as_ref();
+    let mut report = TlsReport {
+        expected_sessions: options
+            .expected_sessions
+            .unwrap_or_else(|| estimated_sessions(config)),
+        ..TlsReport::default()
+    };
+    let mut reported: HashSet<(String, Span)> = HashSet::new();
+    let mut zones: HashMap<String, u64> = HashMap::new();
+
+    walk(config, &mut |directive, parents| {
+        let is_http_server = directive.name() == "server"
+            && directive.is_block()
+            && parents.last().map(|p| p.name()) == Some("http");
+        if !is_http_server {
+            return;
+        }
+
+        // Innermost block first
+        let chain: Vec<&Directive> = std::iter::once(directive)
+            .chain(parents.iter().rev().copied())
+            .collect();
+        let Some(server) = tls_server(&chain, base_dir) else {
+            return;
+        };
+        let mut findings = check_server(&chain, &server);
+        findings.extend(check_sessions(
+            &chain,
+            &server,
+            report.expected_sessions,
+            options.instances,
+            &mut zones,
+        ));
+        for (source, finding) in findings {
+            let span = source.unwrap_or(directive).span;
+            if reported.insert((finding.message.clone(), span)) {
+                report.findings.push(finding.with_span(span));
+            }
+        }
+        report.servers.push(server);
+    });
+
+    report
+}
+
+/// Innermost definition of `name`
+fn effective<'a>(chain: &[&'a Directive], name: &str) -> Option<&'a Directive> {
+    effective_all(chain, name).last().copied()
+}
+
+/// Definitions of `name` in the innermost block that has any
+fn effective_all<'a>(chain: &[&'a Directive], name: &str) -> Vec<&'a Directive> {
+    chain
+        .iter()
+        .map(|block| block.find_children(name))
+        .find(|found| !found.is_empty())
+        .unwrap_or_default()
+}
+
+/// `worker_processes` × `worker_connections`
+fn estimated_sessions(config: &Config) -> u64 {
+    let workers = config
+        .find_directives("worker_processes")
+        .first()
+        .and_then(|d| d.first_arg())
+        .and_then(|n| n.parse::<u64>().ok())
+        .unwrap_or(1);
+    let connections = config
+        .find_directives("events")
+        .iter()
+        .flat_map(|events| events.find_children("worker_connections"))
+        .next()
+        .and_then(Directive::first_arg)
+        .and_then(|n| n.parse::<u64>().ok())
+        .unwrap_or(512);
+    workers * connections
+}
+
+fn tls_server(chain: &[&Directive], base_dir: &Path) -> Option<TlsServer> {
+    let server = chain[0];
+    let has_tls = extract::listens(server)
+        .iter()
+        .any(|listen| listen.ssl || listen.is_quic())
+        || effective(chain, "ssl")
+            .and_then(Directive::first_arg)
+            .as_deref()
+            == Some("on");
+    if !has_tls {
+        return None;
+    }
+
+    let dhparam = effective(chain, "ssl_dhparam").and_then(Directive::first_arg);
+    Some(TlsServer {
+        server_name: Server::primary_name_of(server),
+        line: server.span.line,
+        protocols: effective(chain, "ssl_protocols").map_or_else(
+            || DEFAULT_PROTOCOLS.iter().map(ToString::to_string).collect(),
+            Directive::args_as_strings,
+        ),
+        ciphers: effective(chain, "ssl_ciphers")
+            .and_then(Directive::first_arg)
+            .unwrap_or_else(|| DEFAULT_CIPHERS.to_string()),
+        ecdh_curve: effective(chain, "ssl_ecdh_curve").and_then(Directive::first_arg),
+        dhparam_bits: dhparam
+            .as_deref()
+            .and_then(|path| dhparam_bits(&crate::paths::resolve(base_dir, path))),
+        dhparam,
+        session_cache: effective(chain, "ssl_session_cache").map(|d| d.args_as_strings().join(" ")),
+        session_timeout: effective(chain, "ssl_session_timeout")
+            .and_then(Directive::first_arg)
+            .unwrap_or_else(|| DEFAULT_SESSION_TIMEOUT.to_string()),
+        session_tickets: effective(chain, "ssl_session_tickets")
+            .and_then(Directive::first_arg)
+            .map_or(true, |value| value == "on"),
+        session_ticket_keys: effective_all(chain, "ssl_session_ticket_key")
+            .iter()
+            .filter_map(|d| d.first_arg())
+            .collect(),
+    })
+}
+
+/// Findings with the directive they are about; `None` for a default
+fn check_server<'a>(
+    chain: &[&'a Directive],
+    server: &TlsServer,
+) -> Vec<(Option<&'a Directive>, Finding)> {
+    let mut findings = Vec::new();
+
+    let old: Vec<&str> = server
+        .protocols
+        .iter()
+        .map(String::as_str)
+        .filter(|p| matches!(*p, "SSLv2" | "SSLv3" | "TLSv1" | "TLSv1.1"))
+        .collect();
+    if !old.is_empty() {
+        findings.push((
+            effective(chain, "ssl_protocols"),
+            Finding::new(
+                "tls_protocol",
+                Severity::Warning,
+                format!(
+                    "ssl_protocols enables {}, which are deprecated (RFC 8996)",
+                    old.join(" and ")
+                ),
+            )
+            .with_help(format!(
+                "Remove {}: ssl_protocols TLSv1.2 TLSv1.3;",
+                old.join(" and ")
+            )),
+        ));
+    }
+
+    // ssl_ciphers only applies up to TLSv1.2
+    if server.profile() != CipherProfile::Modern {
+        let source = effective(chain, "ssl_ciphers");
+        let subject = match source {
+            Some(_) => "ssl_ciphers".to_string(),
+            None => format!("the default ssl_ciphers ({DEFAULT_CIPHERS})"),
+        };
+        findings.extend(
+            cipher_findings(&server.ciphers, &subject)
+                .into_iter()
+                .map(|finding| (source, finding)),
+        );
+    }
+
+    if let Some(curves) = &server.ecdh_curve {
+        let weak: Vec<&str> = curves
+            .split(':')
+            .filter(|curve| WEAK_CURVES.iter().any(|w| w.eq_ignore_ascii_case(curve)))
+            .collect();
+        if !weak.is_empty() {
+            findings.push((
+                effective(chain, "ssl_ecdh_curve"),
+                Finding::new(
+                    "tls_cipher",
+                    Severity::Warning,
+                    format!(
+                        "ssl_ecdh_curve enables {}, below 128-bit security",
+                        weak.join(", ")
+                    ),
+                )
+                .with_help("Use: ssl_ecdh_curve X25519:prime256v1:secp384r1;"),
+            ));
+        }
+    }
+
+    if let Some(bits) = server.dhparam_bits.filter(|bits| *bits < MIN_DHPARAM_BITS) {
+        findings.push((
+            effective(chain, "ssl_dhparam"),
+            Finding::new(
+                "tls_dhparam",
+                Severity::Warning,
+                format!("dhparam is {bits}-bit; DHE key exchange needs at least {MIN_DHPARAM_BITS} bits"),
+            )
+            .with_help(
+                "Use the ffdhe2048 group of RFC 7919, or generate new parameters: \
+                 openssl dhparam -out dhparam.pem 2048",
+            ),
+        ));
+    }
+
+    findings
+}
+
+/// Session cache and ticket findings; `zones` collects the size of each
+/// shared zone to catch conflicting declarations
+fn check_sessions<'a>(
+    chain: &[&'a Directive],
+    server: &TlsServer,
+    expected_sessions: u64,
+    instances: usize,
+    zones: &mut HashMap<String, u64>,
+) -> Vec<(Option<&'a Directive>, Finding)> {
+    let mut findings = Vec::new();
+    let cache = effective(chain, "ssl_session_cache");
+
+    for (name, size) in server.session_cache_zones() {
+        let declared = *zones.entry(name.to_string()).or_insert(size);
+        if declared != size {
+            findings.push((
+                cache,
+                Finding::new(
+                    "tls_session_cache",
+                    Severity::Error,
+                    format!(
+                        "shared zone \"{name}\" is declared with {size} bytes here and {declared} \
+                         bytes elsewhere, so nginx refuses to start"
+                    ),
+                )
+                .with_help(format!("Declare \"{name}\" with the same size everywhere")),
+            ));
+        }
+    }
+
+    if let Some(capacity) = server.session_cache_capacity() {
+        if capacity < expected_sessions {
+            let megabytes = (expected_sessions + SESSIONS_PER_MEGABYTE - 1) / SESSIONS_PER_MEGABYTE;
+            let name = server
+                .session_cache_zones()
+                .first()
+                .map_or("SSL", |(name, _)| *name)
+                .to_string();
+            findings.push((
+                cache,
+                Finding::new(
+                    "tls_session_cache",
+                    Severity::Warning,
+                    format!(
+                        "the shared session cache holds about {capacity} sessions, fewer than \
+                         the {expected_sessions} expected; evicted clients do a full handshake"
+                    ),
+                )
+                .with_help(format!(
+                    "Use: ssl_session_cache shared:{name}:{megabytes}m; (about 4000 sessions per \
+                     megabyte)"
+                )),
+            ));
+        }
+    }
+
+    if server
+        .session_cache
+        .as_deref()
+        .is_some_and(|cache| cache.split_whitespace().any(|p| p.starts_with("builtin")))
+    {
+        findings.push((
+            cache,
+            Finding::new(
+                "tls_session_cache",
+                Severity::Info,
+                "the builtin session cache is per worker, so a client resumes only on the \
+                 worker that served it, and it fragments memory",
+            )
+            .with_help("Use only a shared cache: ssl_session_cache shared:SSL:10m;"),
+        ));
+    }
+
+    if !server.resumes_sessions() {
+        findings.push((
+            cache,
+            Finding::new(
+                "tls_session_cache",
+                Severity::Info,
+                "neither a session cache nor session tickets are enabled; every connection \
+                 does a full handshake",
+            )
+            .with_help("Add: ssl_session_cache shared:SSL:10m;"),
+        ));
+    }
+
+    if let Some(timeout) = parse_duration(&server.session_timeout)
+        .filter(|timeout| *timeout > MAX_SESSION_TIMEOUT && server.resumes_sessions())
+    {
+        findings.push((
+            effective(chain, "ssl_session_timeout"),
+            Finding::new(
+                "tls_session_cache",
+                Severity::Info,
+                format!(
+                    "ssl_session_timeout {} keeps session keys usable for {} hours, longer \
+                     than the day Mozilla recommends",
+                    server.session_timeout,
+                    timeout.as_secs() / 3600
+                ),
+            )
+            .with_help("Use: ssl_session_timeout 1d;"),
+        ));
+    }
+
+    findings.extend(check_tickets(chain, server, instances));
+    findings
+}
+
+/// Session ticket findings
+fn check_tickets<'a>(
+    chain: &[&'a Directive],
+    server: &TlsServer,
+    instances: usize,
+) -> Option<(Option<&'a Directive>, Finding)> {
+    if !server.session_tickets {
+        return None;
+    }
+    let tickets = effective(chain, "ssl_session_tickets");
+    match server.session_ticket_keys.len() {
+        0 if instances > 1 => Some((
+            tickets,
+            Finding::new(
+                "tls_session_ticket",
+                Severity::Warning,
+                format!(
+                    "session tickets are on without ssl_session_ticket_key; each of the \
+                     {instances} nginx instances encrypts tickets with its own random key, \
+                     so the others reject them"
+                ),
+            )
+            .with_help(
+                "Deploy the same ssl_session_ticket_key files to every instance and rotate \
+                 them together, or set ssl_session_tickets off",
+            ),
+        )),
+        0 => Some((
+            tickets,
+            Finding::new(
+                "tls_session_ticket",
+                Severity::Info,
+                "session tickets are encrypted with a key generated at startup, which only \
+                 changes when nginx restarts; a long-lived key weakens forward secrecy",
+            )
+            .with_help(
+                "Rotate keys with ssl_session_ticket_key files, or set ssl_session_tickets off",
+            ),
+        )),
+        1 => Some((
+            effective(chain, "ssl_session_ticket_key"),
+            Finding::new(
+                "tls_session_ticket",
+                Severity::Info,
+                "a single ssl_session_ticket_key cannot be rotated without invalidating \
+                 every issued ticket",
+            )
+            .with_help("On rotation, list the new key first and keep the previous key after it"),
+        )),
+        _ => None,
+    }
+}
+
+/// Entries of a cipher string that add ciphers
+fn enabled(ciphers: &str) -> impl Iterator<Item = &str> {
+    ciphers
+        .split([':', ',', ' '])
+        .filter(|c| !c.is_empty() && !c.starts_with(['!', '-', '+', '@']))
+}
+
+/// Whether `entry` is a `+` combination of classes that includes one of
+/// `classes`; the combination only selects ciphers in every class
+fn combines(entry: &str, classes: &[&str]) -> bool {
+    entry.contains('+') && entry.split('+').any(|class| classes.contains(&class))
+}
+
+fn cipher_findings(ciphers: &str, subject: &str) -> Vec<Finding> {
+    let removed: Vec<&str> = ciphers
+        .split([':', ',', ' '])
+        .filter_map(|c| c.strip_prefix('!').or_else(|| c.strip_prefix('-')))
+        .collect();
+    let mut findings = Vec::new();
+
+    for (label, keyword, matches) in INSECURE {
+        if removed.contains(keyword) {
+            continue;
+        }
+        let found: Vec<&str> = enabled(ciphers).filter(|c| matches(c)).collect();
+        if !found.is_empty() {
+            findings.push(
+                Finding::new(
+                    "tls_cipher",
+                    Severity::Warning,
+                    format!("{subject} enables {label} ciphers ({})", found.join(", ")),
+                )
+                .with_help(format!("Remove {label} ciphers: add !{keyword}")),
+            );
+        }
+    }
+
+    let static_rsa: Vec<&str> = enabled(ciphers)
+        .filter(|c| {
+            if CLASS_KEYWORDS.contains(c) || combines(c, &["kRSA", "RSA"]) {
+                return true;
+            }
+            c.contains('-')
+                && !KEY_EXCHANGE_PREFIXES.iter().any(|p| c.starts_with(p))
+                && !INSECURE.iter().any(|(_, _, matches)| matches(c))
+        })
+        .collect();
+    if !static_rsa.is_empty() && !["kRSA", "RSA"].iter().any(|k| removed.contains(k)) {
+        findings.push(
+            Finding::new(
+                "tls_cipher",
+                Severity::Warning,
+                format!(
+                    "{subject} enables TLS_RSA_* ciphers ({}); static RSA key exchange has no \
+                     forward secrecy",
+                    static_rsa.join(", ")
+                ),
+            )
+            .with_help("Remove TLS_RSA_* ciphers: add !kRSA, or use the Mozilla intermediate list"),
+        );
+    }
+
+    let cbc: Vec<&str> = enabled(ciphers)
+        .filter(|c| {
+            CLASS_KEYWORDS.contains(c)
+                || (c.contains('+') && !combines(c, AEAD_CLASSES))
+                || (c.contains('-')
+                    && !c.starts_with("TLS_")
+                    && !["GCM", "CHACHA20", "CCM"]
+                        .iter()
+                        .any(|aead| c.contains(aead))
+                    && !INSECURE.iter().any(|(_, _, matches)| matches(c)))
+        })
+        .collect();
+    if !cbc.is_empty() {
+        findings.push(
+            Finding::new(
+                "tls_cipher",
+                Severity::Info,
+                format!(
+                    "{subject} enables CBC ciphers ({}), which Mozilla's intermediate profile drops",
+                    cbc.join(", ")
+                ),
+            )
+            .with_help(format!("Use: ssl_ciphers {};", INTERMEDIATE_CIPHERS.join(":"))),
+        );
+    }
+
+    findings
+}
+
+/// Size in bits of the prime in a PEM dhparam file
+fn dhparam_bits(path: &Path) -> Option<u32> {
+    let text = std::fs::read_to_string(path).ok()?;
+    let der = pem_body(&text, "DH PARAMETERS")?;
+
+    // DHParameter ::= SEQUENCE { prime INTEGER, base INTEGER, ... }
+    let (params, _) = der_element(&der, 0x30)?;
+    let (prime, _) = der_element(params, 0x02)?;
+    let start = prime.iter().position(|b| *b != 0)?;
+    let bits = u32::try_from(prime.len() - start).ok()? * 8;
+    Some(bits - prime[start].leading_zeros())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    const DH_1024: &str = "-----BEGIN X9.42 DH PARAMETERS-----
+MIIBJgKBgQDRBvGFDW/kocNpppttd0AqlMH7sYreQa6Clhp0kosAXBt4rRKtCLpS
+Lv6gq/PBpoTDPeC8tyydCe7/NTY+vy0Y2AEe0J6OETrJTOLvRCW9O5gu7l9XKZc8
+sTJojnVX32TrD76TO7UZEeuK6dqXOOfrCrjdy7IQ3bU2VVe4gvlipQKBgAe/K2Gj
+RkDkCrjg2mm5zIu64ci4sNlb1nvEgBRCQjz7LYeoSQN1BVi9xnq9nSQXeEdjwpYd
+otl80c3qzAPFbEGX9kOLrsKSlNDYOfZmi4ePqPKxZV4LvnUauTxxDNLm4drWe+qS
+kWLug51ZnS1qRk2vTRcw8mZ0giu9NwdpwleyAh0AgZreJ3/1rm1RNQ2MWVipTOcm
+4ufMuO0udJiakw==
+-----END X9.42 DH PARAMETERS-----
+";
+
+    #[test]
+    fn test_tls_audit() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("dhparam.pem"), DH_1024).unwrap();
+        assert_eq!(dhparam_bits(&dir.path().join("dhparam.pem")), Some(1024));
+
+        let intermediate = INTERMEDIATE_CIPHERS.join(":");
+        let config = parse(&format!(
+            "http {{
+  ssl_ciphers {intermediate};
+  ssl_dhparam dhparam.pem;
+  ssl_session_cache shared:SSL:10m;
+  ssl_session_tickets off;
+  server {{
+    server_name a.example.com;
+    listen 443 ssl;
+  }}
+  server {{
+    server_name b.example.com;
+    listen 443 ssl;
+    ssl_protocols TLSv1.2;
+    ssl_ciphers ECDHE-RSA-AES128-GCM-SHA256:DES-CBC3-SHA:!aNULL;
+    ssl_dhparam /no/such/dhparam.pem;
+    ssl_ecdh_curve secp224r1:prime256v1;
+  }}
+  server {{
+    server_name c.example.com;
+    listen 443 ssl;
+    ssl_protocols TLSv1.3;
+  }}
+  server {{
+    listen 80;
+  }}
+}}"
+        ))
+        .unwrap();
+        let report = tls_audit(&config, dir.path());
+
+        let profiles: Vec<_> = report
+            .servers
+            .iter()
+            .map(|s| (s.server_name.as_str(), s.profile()))
+            .collect();
+        assert_eq!(
+            profiles,
+            vec![
+                ("a.example.com", CipherProfile::Legacy),
+                ("b.example.com", CipherProfile::Legacy),
+                ("c.example.com", CipherProfile::Modern),
+            ]
+        );
+        assert_eq!(report.servers[0].dhparam_bits, Some(1024));
+        assert_eq!(report.servers[1].dhparam_bits, None);
+
+        let found: Vec<_> = report
+            .findings
+            .iter()
+            .map(|f| (f.rule.as_str(), f.line().unwrap_or_default()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![("tls_dhparam", 3), ("tls_cipher", 14), ("tls_cipher", 16)]
+        );
+        assert!(report.findings[0]
+            .message
+            .starts_with("dhparam is 1024-bit"));
+        assert!(report.findings[1].message.contains("3DES"));
+    }
+
+    #[test]
+    fn test_session_checks() {
+        let config = parse(
+            "worker_processes 4;
+events { worker_connections 4096; }
+http {
+  ssl_session_cache builtin:1000 shared:SSL:2m;
+  ssl_session_timeout 7d;
+  server { server_name a; listen 443 ssl; ssl_session_ticket_key a.key; }
+  server { server_name b; listen 443 ssl; ssl_session_cache shared:SSL:20m; ssl_session_tickets off; }
+}",
+        )
+        .unwrap();
+        let report = tls_audit(&config, "/etc/nginx");
+        assert_eq!(report.expected_sessions, 16_384);
+        assert_eq!(report.servers[0].session_cache_capacity(), Some(8000));
+        assert_eq!(report.servers[0].session_ticket_keys, vec!["a.key"]);
+        assert!(!report.servers[1].session_tickets);
+
+        let found: Vec<_> = report
+            .findings
+            .iter()
+            .filter(|f| f.rule.starts_with("tls_session"))
+            .map(|f| (f.rule.as_str(), f.severity, f.line().unwrap_or_default()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("tls_session_cache", Severity::Warning, 4),
+                ("tls_session_cache", Severity::Info, 4),
+                ("tls_session_cache", Severity::Info, 5),
+                ("tls_session_ticket", Severity::Info, 6),
+                ("tls_session_cache", Severity::Error, 7),
+            ]
+        );
+        let undersized = report
+            .findings
+            .iter()
+            .find(|f| f.rule == "tls_session_cache")
+            .unwrap();
+        assert!(undersized
+            .help
+            .as_deref()
+            .is_some_and(|help| help.contains("shared:SSL:5m")));
+    }
+
+    /// A PEM `DH PARAMETERS` block with `prime` and generator 2
+    fn dh_pem(prime: &[u8]) -> String {
+        const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+        let mut integer = vec![0x02, 0x82];
+        integer.extend(u16::try_from(prime.len()).unwrap().to_be_bytes());
+        integer.extend(prime);
+        let mut der = vec![0x30, 0x82];
+        der.extend(u16::try_from(integer.len() + 3).unwrap().to_be_bytes());
+        der.extend(integer);
+        der.extend([0x02, 0x01, 0x02]);
+
+        let mut body = String::new();
+        for chunk in der.chunks(3) {
+            let n = chunk
+                .iter()
+                .enumerate()
+                .fold(0u32, |n, (i, b)| n | u32::from(*b) << (16 - 8 * i));
+            for i in 0..=chunk.len() {
+                body.push(char::from(ALPHABET[(n >> (18 - 6 * i) & 63) as usize]));
+            }
+            body.push_str(&"=".repeat(3 - chunk.len()));
+        }
+        format!("-----BEGIN DH PARAMETERS-----\n{body}\n-----END DH PARAMETERS-----\n")
+    }
+
+    #[test]
+    fn test_dhparam_bits_edge_cases() {
+        let dir = tempfile::tempdir().unwrap();
+        let bits = |contents: &str| {
+            let path = dir.path().join("dhparam.pem");
+            std::fs::write(&path, contents).unwrap();
+            dhparam_bits(&path)
+        };
+
+        // A leading zero byte keeps the prime positive and is not counted
+        let mut prime = vec![0x00, 0xC0];
+        prime.extend([0x00; 255]);
+        assert_eq!(bits(&dh_pem(&prime)), Some(2048));
+        assert_eq!(bits(&dh_pem(&[0x01, 0x00])), Some(9));
+        assert_eq!(bits(&dh_pem(&[0x00, 0x00])), None);
+
+        assert_eq!(bits(""), None);
+        assert_eq!(
+            bits("-----BEGIN DH PARAMETERS-----\n*\n-----END DH PARAMETERS-----\n"),
+            None
+        );
+        assert_eq!(bits(&DH_1024.replace("X9.42 DH", "EC")), None);
+        assert_eq!(dhparam_bits(&dir.path().join("missing.pem")), None);
+        assert_eq!(dhparam_bits(dir.path()), None);
+    }
+
+    #[test]
+    fn test_tls_audit_edge_cases() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut prime = vec![0x00, 0xC0];
+        prime.extend([0x00; 255]);
+        std::fs::write(dir.path().join("ffdhe2048.pem"), dh_pem(&prime)).unwrap();
+
+        let intermediate = INTERMEDIATE_CIPHERS.join(":");
+        let config = parse(&format!(
+            "http {{
+  ssl_session_tickets off;
+  ssl_session_cache shared:SSL:10m;
+  server {{ server_name default; listen 443 ssl; }}
+  server {{
+    server_name strong;
+    listen 443 ssl;
+    ssl_ciphers {intermediate}:TLS_AES_128_GCM_SHA256;
+    ssl_dhparam ffdhe2048.pem;
+  }}
+  server {{ server_name modern; listen 443 quic; ssl_protocols TLSv1.3; ssl_ciphers RC4-SHA; }}
+  server {{ server_name export; ssl on; ssl_ciphers EXP-RC4-MD5:!MD5:ALL:!kRSA; }}
+  server {{ server_name curves; listen 443 ssl; ssl_ciphers ECDHE-RSA-AES128-GCM-SHA256; ssl_ecdh_curve P-192:X25519; }}
+  server {{ listen 443; }}
+}}
+stream {{ server {{ listen 443 ssl; ssl_ciphers RC4-SHA; }} }}"
+        ))
+        .unwrap();
+        let report = tls_audit(&config, dir.path());
+
+        // `ssl on`, QUIC and TLS listeners count; stream servers do not
+        let servers: Vec<_> = report
+            .servers
+            .iter()
+            .map(|s| (s.server_name.as_str(), s.profile()))
+            .collect();
+        assert_eq!(
+            servers,
+            vec![
+                ("default", CipherProfile::Legacy),
+                ("strong", CipherProfile::Intermediate),
+                ("modern", CipherProfile::Modern),
+                ("export", CipherProfile::Legacy),
+                ("curves", CipherProfile::Intermediate),
+            ]
+        );
+        assert_eq!(report.servers[1].dhparam_bits, Some(2048));
+
+        let found: Vec<_> = report
+            .findings
+            .iter()
+            .map(|f| (f.line().unwrap_or_default(), f.severity, f.message.as_str()))
+            .collect();
+        // The default cipher string is reported at its server; removed
+        // classes are not reported, class keywords are
+        assert_eq!(
+            found,
+            vec![
+                (
+                    4,
+                    Severity::Warning,
+                    "the default ssl_ciphers (HIGH:!aNULL:!MD5) enables TLS_RSA_* ciphers (HIGH); \
+                     static RSA key exchange has no forward secrecy"
+                ),
+                (
+                    4,
+                    Severity::Info,
+                    "the default ssl_ciphers (HIGH:!aNULL:!MD5) enables CBC ciphers (HIGH), which \
+                     Mozilla's intermediate profile drops"
+                ),
+                (12, Severity::Warning, "ssl_ciphers enables RC4 ciphers (EXP-RC4-MD5)"),
+                (12, Severity::Warning, "ssl_ciphers enables export ciphers (EXP-RC4-MD5)"),
+                (
+                    12,
+                    Severity::Info,
+                    "ssl_ciphers enables CBC ciphers (ALL), which Mozilla's intermediate profile drops"
+                ),
+                (
+                    13,
+                    Severity::Warning,
+                    "ssl_ecdh_curve enables P-192, below 128-bit security"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cipher_class_combinations() {
+        let config = parse(
+            "http {
+  server { server_name aead; listen 443 ssl; ssl_ciphers ECDHE+AESGCM:EDH+CHACHA20:!aNULL; }
+  server { server_name cbc; listen 443 ssl; ssl_ciphers EECDH+AES:!aNULL; }
+  server { server_name rsa; listen 443 ssl; ssl_ciphers kRSA+AESGCM; }
+}",
+        )
+        .unwrap();
+        let report = tls_audit(&config, "/etc/nginx");
+
+        let profiles: Vec<_> = report
+            .servers
+            .iter()
+            .map(|s| (s.server_name.as_str(), s.profile()))
+            .collect();
+        assert_eq!(
+            profiles,
+            vec![
+                ("aead", CipherProfile::Intermediate),
+                ("cbc", CipherProfile::Legacy),
+                ("rsa", CipherProfile::Legacy),
+            ]
+        );
+
+        // Each legacy grade comes with the finding that explains it
+        let found: Vec<_> = report
+            .findings
+            .iter()
+            .filter(|f| f.rule == "tls_cipher")
+            .map(|f| (f.line().unwrap_or_default(), f.message.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (
+                    3,
+                    "ssl_ciphers enables CBC ciphers (EECDH+AES), which Mozilla's intermediate \
+                     profile drops"
+                ),
+                (
+                    4,
+                    "ssl_ciphers enables TLS_RSA_* ciphers (kRSA+AESGCM); static RSA key exchange \
+                     has no forward secrecy"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_session_checks_edge_cases() {
+        let config = parse(
+            "worker_processes auto;
+http {
+  ssl_session_cache shared:A:1m shared:B:1m shared:C shared:D:10x;
+  ssl_session_timeout forever;
+  server { server_name a; listen 443 ssl; }
+  server { server_name b; listen 443 ssl; }
+  server { server_name c; listen 443 ssl; ssl_session_cache off; ssl_session_tickets off; ssl_session_timeout 2d; }
+  server { server_name d; listen 443 ssl; ssl_session_cache none; ssl_session_tickets off; }
+  server {
+    server_name e;
+    listen 443 ssl;
+    ssl_session_ticket_key current.key;
+    ssl_session_ticket_key previous.key;
+  }
+}",
+        )
+        .unwrap();
+
+        // No events block: one worker with 512 connections
+        let report = tls_audit(&config, "/etc/nginx");
+        assert_eq!(report.expected_sessions, 512);
+        // Zones without a valid size are left out
+        assert_eq!(
+            report.servers[0].session_cache_zones(),
+            vec![("A", 1024 * 1024), ("B", 1024 * 1024)]
+        );
+        assert_eq!(report.servers[0].session_cache_capacity(), Some(8000));
+        assert_eq!(report.servers[2].session_cache_capacity(), None);
+        assert!(!report.servers[2].resumes_sessions());
+        assert!(!report.servers[3].resumes_sessions());
+
+        let found = |report: &TlsReport| -> Vec<_> {
+            report
+                .findings
+                .iter()
+                .filter(|f| f.rule.starts_with("tls_session"))
+                .map(|f| (f.rule.clone(), f.severity, f.line().unwrap_or_default()))
+                .collect()
+        };
+        let at = |rule: &str, severity, line| (rule.to_string(), severity, line);
+        // The inherited ticket finding is reported once per server; a
+        // timeout without resumption or that does not parse is not reported
+        assert_eq!(
+            found(&report),
+            vec![
+                at("tls_session_ticket", Severity::Info, 5),
+                at("tls_session_ticket", Severity::Info, 6),
+                at("tls_session_cache", Severity::Info, 7),
+                at("tls_session_cache", Severity::Info, 8),
+            ]
+        );
+
+        // A larger expected load finds the shared cache too small once,
+        // and tickets without shared keys break across instances
+        let options = TlsAuditOptions {
+            expected_sessions: Some(10_000),
+            instances: 3,
+        };
+        let report = tls_audit_with(&config, "/etc/nginx", &options);
+        assert_eq!(report.expected_sessions, 10_000);
+        assert_eq!(
+            found(&report),
+            vec![
+                at("tls_session_cache", Severity::Warning, 3),
+                at("tls_session_ticket", Severity::Warning, 5),
+                at("tls_session_ticket", Severity::Warning, 6),
+                at("tls_session_cache", Severity::Info, 7),
+                at("tls_session_cache", Severity::Info, 8),
+            ]
+        );
+        let session = |rule: &str| {
+            report
+                .findings
+                .iter()
+                .find(|f| f.rule == rule && f.severity == Severity::Warning)
+                .unwrap()
+        };
+        let help = session("tls_session_cache").help.as_deref().unwrap();
+        assert!(help.contains("shared:A:3m"), "{help}");
+        assert!(session("tls_session_ticket")
+            .message
+            .contains("each of the 3 nginx instances"));
+
+        // Neither cache nor tickets is reported at the server when nothing
+        // is set
+        let config = parse("http { server { listen 443 ssl; ssl_session_tickets off; } }").unwrap();
+        let report = tls_audit(&config, "/etc/nginx");
+        assert_eq!(
+            found(&report),
+            vec![at("tls_session_cache", Severity::Info, 1)]
+        );
+        assert!(report
+            .findings
+            .iter()
+            .any(|f| f.message.starts_with("neither a session cache")));
+    }
+}
@@ -0,0 +1,381 @@
+//! Minimum nginx version inference
+//!
+//! Every directive in the [`docs`] database records the nginx
+//! version that introduced it; parameters added later (`listen ... quic`,
+//! `ssl_protocols TLSv1.3`, `add_header ... always`) are tracked in a
+//! separate table. [`required_version`] walks the configuration and reports
+//! the highest version needed, with the directive responsible for each
+//! requirement so older servers can be diagnosed before deployment.
+
+use crate::ast::{Config, Directive, Span};
+use crate::docs::{self, DirectiveDoc};
+use crate::lint::{walk, Finding, Severity};
+use std::fmt;
+use std::str::FromStr;
+
+/// Parameters introduced after their directive: `(directive, parameter,
+/// version)`. A parameter ending in `=` or `:` matches as a prefix.
+const PARAMETERS: &[(&str, &str, &str)] = &[
+    ("listen", "fastopen=", "1.5.8"),
+    ("listen", "proxy_protocol", "1.5.12"),
+    ("listen", "reuseport", "1.9.1"),
+    ("listen", "http2", "1.9.5"),
+    ("listen", "udp", "1.9.13"),
+    ("listen", "quic", "1.25.0"),
+    ("ssl_protocols", "TLSv1.3", "1.13.0"),
+    ("proxy_ssl_protocols", "TLSv1.3", "1.13.0"),
+    ("add_header", "always", "1.7.5"),
+    ("access_log", "if=", "1.7.0"),
+    ("access_log", "syslog:", "1.7.1"),
+    ("error_log", "syslog:", "1.7.1"),
+    ("error_log", "memory:", "1.7.11"),
+    ("log_format", "escape=", "1.11.8"),
+    ("limit_req", "delay=", "1.15.7"),
+    ("proxy_cache_path", "use_temp_path=", "1.7.10"),
+    ("proxy_cache_path", "min_free=", "1.19.1"),
+    ("proxy_next_upstream", "non_idempotent", "1.9.13"),
+    ("proxy_next_upstream", "http_429", "1.11.13"),
+    ("proxy_cache_use_stale", "http_429", "1.11.13"),
+    ("server", "max_conns=", "1.11.5"),
+    ("server", "resolve", "1.27.3"),
+];
+
+/// An nginx release number
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NginxVersion {
+    /// Major version
+    pub major: u32,
+    /// Minor version
+    pub minor: u32,
+    /// Patch version
+    pub patch: u32,
+}
+
+impl NginxVersion {
+    /// Create a version
+    #[must_use]
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// Extract the version from `nginx -v` output
+    /// (`nginx version: nginx/1.25.3`, `nginx version: openresty/1.21.4.1`)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nginx_discovery::analyze::NginxVersion;
+    ///
+    /// let version = NginxVersion::from_version_output("nginx version: nginx/1.25.3").unwrap();
+    /// assert_eq!(version, NginxVersion::new(1, 25, 3));
+    /// ```
+    #[must_use]
+    pub fn from_version_output(output: &str) -> Option<Self> {
+        let (_, rest) = output.split_once('/')?;
+        let version = rest.split_whitespace().next()?;
+
+        // OpenResty appends a fourth component
+        let mut parts = version.splitn(4, '.');
+        let major = parts.next()?.parse().ok()?;
+        let minor = parts.next()?.parse().ok()?;
+        let patch = parts.next()?.parse().ok()?;
+        Some(Self::new(major, minor, patch))
+    }
+}
+
+impl fmt::Display for NginxVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+impl FromStr for NginxVersion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid nginx version '{s}': expected MAJOR.MINOR[.PATCH]");
+        let mut parts = s.trim().split('.');
+
+        let mut next = |required: bool| -> Result<u32, String> {
+            match parts.next() {
+                Some(part) => part.parse().map_err(|_| invalid()),
+                None if required => Err(invalid()),
+                None => Ok(0),
+            }
+        };
+        let version = Self::new(next(true)?, next(true)?, next(false)?);
+
+        if parts.next().is_some() {
+            return Err(invalid());
+        }
+        Ok(version)
+    }
+}
+
+/// One directive or parameter that needs a minimum nginx version
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VersionRequirement {
+    /// Directive name
+    pub directive: String,
+
+    /// Parameter that raises the requirement, `None` for the directive
+    /// itself
+    pub parameter: Option<String>,
+
+    /// First nginx version supporting it
+    pub version: NginxVersion,
+
+    /// Location of the directive
+    pub span: Span,
+}
+
+impl VersionRequirement {
+    /// `directive` or `directive parameter`
+    #[must_use]
+    pub fn feature(&self) -> String {
+        match &self.parameter {
+            Some(parameter) => format!("{} {}", self.directive, parameter),
+            None => self.directive.clone(),
+        }
+    }
+}
+
+/// Result of [`required_version`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RequiredVersion {
+    /// Highest version required, `None` when nothing used postdates 1.0
+    pub minimum: Option<NginxVersion>,
+
+    /// Every requirement, highest version first
+    pub requirements: Vec<VersionRequirement>,
+}
+
+impl RequiredVersion {
+    /// Requirements not met by `installed`
+    pub fn unsupported(
+        &self,
+        installed: NginxVersion,
+    ) -> impl Iterator<Item = &VersionRequirement> {
+        self.requirements
+            .iter()
+            .filter(move |r| r.version > installed)
+    }
+
+    /// Whether `installed` supports everything the configuration uses
+    #[must_use]
+    pub fn is_satisfied_by(&self, installed: NginxVersion) -> bool {
+        self.minimum.map_or(true, |minimum| minimum <= installed)
+    }
+
+    /// A `required_version` warning for every requirement `installed`
+    /// does not meet
+    #[must_use]
+    pub fn check(&self, installed: NginxVersion) -> Vec<Finding> {
+        self.unsupported(installed)
+            .map(|r| {
+                Finding::new(
+                    "required_version",
+                    Severity::Warning,
+                    format!(
+                        "\"{}\" requires nginx {} but the installed version is {installed}",
+                        r.feature(),
+                        r.version
+                    ),
+                )
+                .with_span(r.span)
+                .with_help(format!(
+                    "Upgrade nginx to {} or later, or remove \"{}\"",
+                    r.version,
+                    r.feature()
+                ))
+            })
+            .collect()
+    }
+}
+
+/// Compute the minimum nginx version needed for the directives and
+/// parameters used in `config`.
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::{analyze::{required_version, NginxVersion}, parse};
+///
+/// let config = parse("http { server { listen 443 quic reuseport; http2 on; } }")?;
+/// let required = required_version(&config);
+///
+/// assert_eq!(required.minimum, Some(NginxVersion::new(1, 25, 1)));
+/// assert_eq!(required.requirements[0].feature(), "http2");
+/// assert!(!required.is_satisfied_by(NginxVersion::new(1, 24, 0)));
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+#[must_use]
+pub fn required_version(config: &Config) -> RequiredVersion {
+    let mut requirements = Vec::new();
+
+    walk(config, &mut |directive, parents| {
+        let name = directive.name();
+
+        if let Some(version) = directive_doc(name, parents)
+            .and_then(|doc| doc.since)
+            .and_then(|since| since.parse::<NginxVersion>().ok())
+        {
+            requirements.push(VersionRequirement {
+                directive: name.to_string(),
+                parameter: None,
+                version,
+                span: directive.span,
+            });
+        }
+
+        requirements.extend(parameter_requirements(directive));
+    });
+
+    // Highest first, then in file order
+    requirements.sort_by(|a, b| {
+        b.version
+            .cmp(&a.version)
+            .then((a.span.line, a.span.col).cmp(&(b.span.line, b.span.col)))
+    });
+
+    RequiredVersion {
+        minimum: requirements.first().map(|r| r.version),
+        requirements,
+    }
+}
+
+/// The database entry for `name` in the module family (http, stream, mail)
+/// of its enclosing blocks, preferring one allowed in the direct parent
+fn directive_doc(name: &str, parents: &[&Directive]) -> Option<&'static DirectiveDoc> {
+    let family = if parents.iter().any(|p| p.name() == "stream") {
+        "ngx_stream_"
+    } else if parents.iter().any(|p| p.name() == "mail") {
+        "ngx_mail_"
+    } else {
+        ""
+    };
+    let context = parents.last().map_or("main", |p| p.name());
+
+    let mut candidates: Vec<&DirectiveDoc> = docs::lookup_all(name)
+        .filter(|doc| {
+            if family.is_empty() {
+                !doc.module.starts_with("ngx_stream_") && !doc.module.starts_with("ngx_mail_")
+            } else {
+                doc.module.starts_with(family)
+            }
+        })
+        .collect();
+    if candidates.is_empty() {
+        // `stream` and `mail` themselves sit in the main context
+        candidates = docs::lookup_all(name).collect();
+    }
+
+    candidates
+        .iter()
+        .find(|doc| doc.allowed_in(context))
+        .or_else(|| candidates.first())
+        .copied()
+}
+
+fn parameter_requirements(directive: &Directive) -> Vec<VersionRequirement> {
+    let args = directive.args_as_strings();
+
+    PARAMETERS
+        .iter()
+        .filter(|(name, _, _)| *name == directive.name())
+        .filter(|(_, parameter, _)| {
+            args.iter().any(|arg| {
+                if parameter.ends_with('=') || parameter.ends_with(':') {
+                    arg.starts_with(parameter)
+                } else {
+                    arg == parameter
+                }
+            })
+        })
+        .filter_map(|(name, parameter, version)| {
+            Some(VersionRequirement {
+                directive: (*name).to_string(),
+                parameter: Some(parameter.trim_end_matches(['=', ':']).to_string()),
+                version: version.parse().ok()?,
+                span: directive.span,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_version_parsing() {
+        assert_eq!("1.25".parse(), Ok(NginxVersion::new(1, 25, 0)));
+        assert_eq!("1.25.3".parse(), Ok(NginxVersion::new(1, 25, 3)));
+        assert!("1.x".parse::<NginxVersion>().is_err());
+        assert!("1.2.3.4".parse::<NginxVersion>().is_err());
+        assert_eq!(
+            NginxVersion::from_version_output("nginx version: openresty/1.21.4.1"),
+            Some(NginxVersion::new(1, 21, 4))
+        );
+        assert!(NginxVersion::new(1, 9, 5) < NginxVersion::new(1, 25, 0));
+    }
+
+    #[test]
+    fn test_parameters_and_context() {
+        let config = parse(
+            "http {
+  upstream app { server 10.0.0.1 max_conns=10; keepalive 16; keepalive_requests 100; }
+  server { listen 443 ssl http2; ssl_protocols TLSv1.2 TLSv1.3; }
+}
+stream { server { listen 53 udp; proxy_pass dns; } }",
+        )
+        .unwrap();
+
+        let required = required_version(&config);
+        let features: Vec<_> = required
+            .requirements
+            .iter()
+            .map(|r| (r.feature(), r.version.to_string()))
+            .collect();
+
+        assert_eq!(
+            features,
+            vec![
+                ("keepalive_requests".to_string(), "1.15.3".to_string()),
+                ("ssl_protocols TLSv1.3".to_string(), "1.13.0".to_string()),
+                ("server max_conns".to_string(), "1.11.5".to_string()),
+                ("listen udp".to_string(), "1.9.13".to_string()),
+                ("listen http2".to_string(), "1.9.5".to_string()),
+                ("stream".to_string(), "1.9.0".to_string()),
+                ("server".to_string(), "1.9.0".to_string()),
+                ("listen".to_string(), "1.9.0".to_string()),
+                ("proxy_pass".to_string(), "1.9.0".to_string()),
+                ("keepalive".to_string(), "1.1.4".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_against_installed() {
+        let config = parse("server {\n  listen 443 quic;\n}\n").unwrap();
+        let required = required_version(&config);
+
+        assert!(required.check(NginxVersion::new(1, 25, 0)).is_empty());
+
+        let findings = required.check(NginxVersion::new(1, 24, 0));
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].rule, "required_version");
+        assert_eq!(findings[0].line(), Some(2));
+        assert!(findings[0]
+            .message
+            .contains("\"listen quic\" requires nginx 1.25.0"));
+    }
+}
@@ -0,0 +1,324 @@
+//! `ModSecurity` WAF coverage
+//!
+//! Maps every server and location to its effective `ModSecurity` settings.
+//! `modsecurity on|off` is inherited from the enclosing block (the default
+//! is `off`); rules from `modsecurity_rules_file`, `modsecurity_rules` and
+//! `modsecurity_rules_remote` add up, a location running the rules of its
+//! server and `http` block plus its own. A context is protected when the
+//! WAF is on and at least one rule set applies.
+//!
+//! Findings:
+//!
+//! - `waf_rules_file`: a `modsecurity_rules_file` that does not exist
+//!   (nginx fails to start)
+//! - `waf_no_rules`: `modsecurity on` where no rules are loaded, so
+//!   nothing is inspected
+
+use crate::ast::{Config, Directive, Span, Value};
+use crate::lint::{walk, Finding, Severity};
+use crate::types::Server;
+use std::path::{Path, PathBuf};
+
+/// Effective `ModSecurity` settings of a server or location
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WafContext {
+    /// First `server_name` of the server (`_` if none)
+    pub server_name: String,
+
+    /// Location arguments (`~ \.php$`), `None` for the server block itself
+    pub location: Option<String>,
+
+    /// Line of the `server` or `location` directive
+    pub line: usize,
+
+    /// Whether `modsecurity` is on
+    pub enabled: bool,
+
+    /// Rules files that apply, outermost block first
+    pub rules_files: Vec<String>,
+
+    /// Number of inline `modsecurity_rules` that apply
+    pub inline_rules: usize,
+
+    /// URLs of `modsecurity_rules_remote` that apply
+    pub remote_rules: Vec<String>,
+}
+
+impl WafContext {
+    /// Whether any rule set applies
+    #[must_use]
+    pub fn has_rules(&self) -> bool {
+        !self.rules_files.is_empty() || self.inline_rules > 0 || !self.remote_rules.is_empty()
+    }
+
+    /// Whether requests are inspected: the WAF is on and has rules
+    #[must_use]
+    pub fn is_protected(&self) -> bool {
+        self.enabled && self.has_rules()
+    }
+}
+
+/// A `modsecurity_rules_file` reference
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RulesFile {
+    /// Path as written in the configuration
+    pub path: String,
+
+    /// Path resolved against the base directory
+    pub resolved: PathBuf,
+
+    /// Whether the file exists, `None` for paths built from variables
+    pub exists: Option<bool>,
+
+    /// Location of the directive
+    pub span: Span,
+}
+
+/// Result of [`waf_coverage`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WafReport {
+    /// Every server and location, in file order
+    pub contexts: Vec<WafContext>,
+
+    /// Every `modsecurity_rules_file`, in file order
+    pub rules_files: Vec<RulesFile>,
+
+    /// Problems found (`waf_rules_file`, `waf_no_rules`)
+    pub findings: Vec<Finding>,
+}
+
+impl WafReport {
+    /// Servers and locations whose requests are inspected
+    pub fn protected(&self) -> impl Iterator<Item = &WafContext> {
+        self.contexts.iter().filter(|c| c.is_protected())
+    }
+
+    /// Servers and locations whose requests are not inspected
+    pub fn unprotected(&self) -> impl Iterator<Item = &WafContext> {
+        self.contexts.iter().filter(|c| !c.is_protected())
+    }
+
+    /// Whether the configuration uses `ModSecurity` at all
+    #[must_use]
+    pub fn is_used(&self) -> bool {
+        self.contexts.iter().any(|c| c.enabled || c.has_rules())
+    }
+}
+
+/// Map servers and locations to their `ModSecurity` settings and check
+/// the rules files.
+///
+/// Relative rules file paths are resolved against `base_dir`.
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::{analyze::waf_coverage, parse};
+///
+/// let config = parse(r"
+/// http {
+///     modsecurity_rules_file /no/such/main.conf;
+///     server {
+///         server_name shop.example.com;
+///         modsecurity on;
+///         location /static/ { modsecurity off; }
+///     }
+/// }
+/// ")?;
+/// let report = waf_coverage(&config, "/etc/nginx");
+///
+/// assert_eq!(report.protected().count(), 1);
+/// assert_eq!(report.unprotected().next().unwrap().location.as_deref(), Some("/static/"));
+/// assert_eq!(report.findings[0].rule, "waf_rules_file");
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+#[must_use]
+pub fn waf_coverage(config: &Config, base_dir: impl AsRef<Path>) -> WafReport {
+    let base_dir = base_dir.as_ref();
+    let mut report = WafReport::default();
+    let mut reported: Vec<Span> = Vec::new();
+
+    walk(config, &mut |directive, parents| match directive.name() {
+        "server" | "location" if directive.children().is_some() => {
+            let (context, switch) = waf_context(directive, parents);
+            if let Some(switch) = switch.filter(|_| context.enabled && !context.has_rules()) {
+                if !reported.contains(&switch.span) {
+                    reported.push(switch.span);
+                    report.findings.push(no_rules_finding(switch));
+                }
+            }
+            report.contexts.push(context);
+        }
+        "modsecurity_rules_file" => {
+            let Some(value) = directive.args().first() else {
+                return;
+            };
+            let path = value.unquoted();
+            let resolved = base_dir.join(&path);
+            let exists = (!path.contains('$')).then(|| resolved.is_file());
+            if exists == Some(false) {
+                report.findings.push(
+                    Finding::new(
+                        "waf_rules_file",
+                        Severity::Error,
+                        format!("ModSecurity rules file {path} does not exist"),
+                    )
+                    .at(directive)
+                    .with_help("Fix the path or install the rule set (e.g. the OWASP CRS)"),
+                );
+            }
+            report.rules_files.push(RulesFile {
+                path,
+                resolved,
+                exists,
+                span: directive.span,
+            });
+        }
+        _ => {}
+    });
+
+    report
+}
+
+/// Effective settings of `block`, with the `modsecurity` directive that
+/// decides whether the WAF is on
+fn waf_context<'a>(
+    block: &'a Directive,
+    parents: &[&'a Directive],
+) -> (WafContext, Option<&'a Directive>) {
+    // Outermost block first
+    let chain: Vec<&Directive> = parents
+        .iter()
+        .copied()
+        .chain(std::iter::once(block))
+        .collect();
+
+    let switch = chain
+        .iter()
+        .rev()
+        .find_map(|b| b.find_children("modsecurity").last().copied());
+
+    let server = chain.iter().rev().find(|b| b.name() == "server");
+    let server_name = Server::primary_name_of(server.copied());
+
+    let all = |name: &str| -> Vec<&Directive> {
+        chain.iter().flat_map(|b| b.find_children(name)).collect()
+    };
+
+    let context = WafContext {
+        server_name,
+        location: (block.name() == "location").then(|| block.args_as_strings().join(" ")),
+        line: block.span.line,
+        enabled: switch.is_some_and(|d| d.first_arg().as_deref() == Some("on")),
+        rules_files: all("modsecurity_rules_file")
+            .iter()
+            .filter_map(|d| d.args().first().map(Value::unquoted))
+            .collect(),
+        inline_rules: all("modsecurity_rules").len(),
+        remote_rules: all("modsecurity_rules_remote")
+            .iter()
+            .filter_map(|d| d.args().get(1).map(Value::unquoted))
+            .collect(),
+    };
+    (context, switch)
+}
+
+fn no_rules_finding(switch: &Directive) -> Finding {
+    Finding::new(
+        "waf_no_rules",
+        Severity::Warning,
+        "modsecurity is on but no rules are loaded, so requests are not inspected",
+    )
+    .at(switch)
+    .with_help("Load a rule set with modsecurity_rules_file, e.g. /etc/nginx/modsec/main.conf")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_waf_coverage() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("main.conf"), "SecRuleEngine On\n").unwrap();
+
+        let config = parse(
+            r#"
+http {
+    modsecurity on;
+    server {
+        server_name shop.example.com;
+        modsecurity_rules_file main.conf;
+        location /api {
+            modsecurity_rules 'SecRule ARGS "@rx x" "id:1,deny"';
+            modsecurity_rules_file missing.conf;
+        }
+        location /health { modsecurity off; }
+    }
+    server {
+        server_name plain.example.com;
+        location / { }
+    }
+}
+"#,
+        )
+        .unwrap();
+        let report = waf_coverage(&config, dir.path());
+
+        let summary: Vec<_> = report
+            .contexts
+            .iter()
+            .map(|c| {
+                (
+                    c.server_name.as_str(),
+                    c.location.as_deref(),
+                    c.is_protected(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("shop.example.com", None, true),
+                ("shop.example.com", Some("/api"), true),
+                ("shop.example.com", Some("/health"), false),
+                ("plain.example.com", None, false),
+                ("plain.example.com", Some("/"), false),
+            ]
+        );
+        assert_eq!(
+            report.contexts[1].rules_files,
+            vec!["main.conf", "missing.conf"]
+        );
+        assert_eq!(report.contexts[1].inline_rules, 1);
+        assert!(report.is_used());
+
+        assert_eq!(report.rules_files.len(), 2);
+        assert_eq!(report.rules_files[0].exists, Some(true));
+
+        // The missing file, and `modsecurity on` without rules for the
+        // second server, reported once
+        let found: Vec<_> = report
+            .findings
+            .iter()
+            .map(|f| (f.rule.as_str(), f.line()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![("waf_rules_file", Some(9)), ("waf_no_rules", Some(3))]
+        );
+    }
+
+    #[test]
+    fn test_waf_unused() {
+        let config = parse("http { server { location / { } } }").unwrap();
+        let report = waf_coverage(&config, "/");
+        assert!(!report.is_used());
+        assert!(report.findings.is_empty());
+        assert_eq!(report.unprotected().count(), 2);
+    }
+}
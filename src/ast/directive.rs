@@ -3,7 +3,7 @@
 use super::{Span, Spanned, Value};
 
 /// A directive in the NGINX configuration
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Directive {
     /// The directive content (simple or block)
@@ -13,7 +13,7 @@ pub struct Directive {
 }
 
 /// Directive content - either simple or block
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum DirectiveItem {
     /// Simple directive: `name arg1 arg2 ...;`
@@ -0,0 +1,334 @@
+//! Node identifiers and upward navigation
+//!
+//! The AST only links blocks to their children. [`ConfigIndex`] numbers
+//! every directive of a [`Config`] and records its parent, so callers can
+//! ask which `server` a `location` belongs to without walking the tree
+//! again. It also maps directive names to their nodes, so a service holding
+//! a parsed configuration can answer repeated lookups without scanning the
+//! whole tree each time.
+
+use super::{Config, Directive, Span};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Identifier of a directive within a [`ConfigIndex`]
+///
+/// Ids are assigned in document order (a block before its children) and
+/// are stable for a given configuration, so they can be stored in findings
+/// and resolved later against an index of the same configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct NodeId(pub usize);
+
+impl fmt::Display for NodeId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Node<'a> {
+    directive: &'a Directive,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+    depth: usize,
+}
+
+/// Index of every directive of a configuration with parent links and a
+/// name lookup table
+///
+/// Building the index walks the configuration once; afterwards
+/// [`find`](Self::find) and [`find_directives`](Self::find_directives)
+/// are hash lookups.
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::{ast::ConfigIndex, parse};
+///
+/// let config = parse(r"
+/// http {
+///     server {
+///         server_name example.com;
+///         location /api { proxy_pass http://api; }
+///     }
+/// }
+/// ")?;
+/// let index = ConfigIndex::new(&config);
+///
+/// let location = index.find("location")[0];
+/// let server = index.enclosing(location, "server").unwrap();
+/// assert_eq!(index.get(server).find_children("server_name").len(), 1);
+/// assert_eq!(index.path(location), vec!["http", "server", "location"]);
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct ConfigIndex<'a> {
+    nodes: Vec<Node<'a>>,
+    roots: Vec<NodeId>,
+    by_name: HashMap<&'a str, Vec<NodeId>>,
+}
+
+impl<'a> ConfigIndex<'a> {
+    /// Index every directive of `config`
+    #[must_use]
+    pub fn new(config: &'a Config) -> Self {
+        let mut index = Self {
+            nodes: Vec::new(),
+            roots: Vec::new(),
+            by_name: HashMap::new(),
+        };
+        index.roots = index.add_all(&config.directives, None, 0);
+        index
+    }
+
+    /// Index every directive of `config` (same as [`new`](Self::new))
+    #[must_use]
+    pub fn build(config: &'a Config) -> Self {
+        Self::new(config)
+    }
+
+    fn add_all(
+        &mut self,
+        directives: &'a [Directive],
+        parent: Option<NodeId>,
+        depth: usize,
+    ) -> Vec<NodeId> {
+        let mut ids = Vec::with_capacity(directives.len());
+        for directive in directives {
+            let id = NodeId(self.nodes.len());
+            self.nodes.push(Node {
+                directive,
+                parent,
+                children: Vec::new(),
+                depth,
+            });
+            self.by_name.entry(directive.name()).or_default().push(id);
+            if let Some(children) = directive.children() {
+                let children = self.add_all(children, Some(id), depth + 1);
+                self.nodes[id.0].children = children;
+            }
+            ids.push(id);
+        }
+        ids
+    }
+
+    /// Number of directives
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Check if the configuration has no directives
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Directive with the given id
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` does not belong to this index.
+    #[must_use]
+    pub fn get(&self, id: NodeId) -> &'a Directive {
+        self.nodes[id.0].directive
+    }
+
+    /// Directive with the given id, `None` if it does not belong to this
+    /// index
+    #[must_use]
+    pub fn try_get(&self, id: NodeId) -> Option<&'a Directive> {
+        self.nodes.get(id.0).map(|node| node.directive)
+    }
+
+    /// All ids in document order
+    pub fn ids(&self) -> impl Iterator<Item = NodeId> + '_ {
+        (0..self.nodes.len()).map(NodeId)
+    }
+
+    /// Top-level directives
+    #[must_use]
+    pub fn roots(&self) -> &[NodeId] {
+        &self.roots
+    }
+
+    /// Enclosing block of a directive, `None` at the top level
+    #[must_use]
+    pub fn parent(&self, id: NodeId) -> Option<NodeId> {
+        self.nodes.get(id.0).and_then(|node| node.parent)
+    }
+
+    /// Direct children of a block
+    #[must_use]
+    pub fn children(&self, id: NodeId) -> &[NodeId] {
+        self.nodes
+            .get(id.0)
+            .map_or(&[], |node| node.children.as_slice())
+    }
+
+    /// Nesting depth (0 for top-level directives)
+    #[must_use]
+    pub fn depth(&self, id: NodeId) -> usize {
+        self.nodes.get(id.0).map_or(0, |node| node.depth)
+    }
+
+    /// Enclosing blocks, innermost first
+    pub fn ancestors(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        std::iter::successors(self.parent(id), move |&parent| self.parent(parent))
+    }
+
+    /// Nearest enclosing block named `name`
+    #[must_use]
+    pub fn enclosing(&self, id: NodeId, name: &str) -> Option<NodeId> {
+        self.ancestors(id)
+            .find(|&parent| self.get(parent).name() == name)
+    }
+
+    /// Names of the enclosing blocks and the directive itself, outermost
+    /// first
+    #[must_use]
+    pub fn path(&self, id: NodeId) -> Vec<&'a str> {
+        let mut path: Vec<&str> = self.ancestors(id).map(|p| self.get(p).name()).collect();
+        path.reverse();
+        path.push(self.get(id).name());
+        path
+    }
+
+    /// Ids of all directives named `name`, in document order
+    #[must_use]
+    pub fn find(&self, name: &str) -> Vec<NodeId> {
+        self.by_name.get(name).cloned().unwrap_or_default()
+    }
+
+    /// All directives named `name` at any depth, in document order
+    ///
+    /// Same result as [`Config::find_directives_recursive`] without
+    /// walking the tree.
+    #[must_use]
+    pub fn find_directives(&self, name: &str) -> Vec<&'a Directive> {
+        self.by_name
+            .get(name)
+            .map(|ids| ids.iter().map(|&id| self.get(id)).collect())
+            .unwrap_or_default()
+    }
+
+    /// Number of directives named `name`
+    #[must_use]
+    pub fn count(&self, name: &str) -> usize {
+        self.by_name.get(name).map_or(0, Vec::len)
+    }
+
+    /// Check if any directive is named `name`
+    #[must_use]
+    pub fn contains(&self, name: &str) -> bool {
+        self.by_name.contains_key(name)
+    }
+
+    /// Distinct directive names, in no particular order
+    pub fn names(&self) -> impl Iterator<Item = &'a str> + '_ {
+        self.by_name.keys().copied()
+    }
+
+    /// Id of a directive borrowed from the indexed configuration
+    #[must_use]
+    pub fn id_of(&self, directive: &Directive) -> Option<NodeId> {
+        self.by_name
+            .get(directive.name())?
+            .iter()
+            .copied()
+            .find(|&id| std::ptr::eq(self.get(id), directive))
+    }
+
+    /// Id of the directive starting at `span`, e.g. to resolve a
+    /// [`Finding`](crate::lint::Finding) location
+    #[must_use]
+    pub fn at_span(&self, span: Span) -> Option<NodeId> {
+        self.ids().find(|&id| self.get(id).span == span)
+    }
+}
+
+impl Config {
+    /// Build a [`ConfigIndex`] of the configuration
+    #[must_use]
+    pub fn index(&self) -> ConfigIndex<'_> {
+        ConfigIndex::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_config_index() {
+        let config = parse(
+            r"
+user nginx;
+http {
+    upstream api { server 10.0.0.1; }
+    server {
+        listen 80;
+        location / {
+            location /nested { return 204; }
+        }
+    }
+}
+",
+        )
+        .unwrap();
+        let index = config.index();
+
+        assert_eq!(index.len(), 9);
+        assert_eq!(index.roots(), &[NodeId(0), NodeId(1)]);
+        assert_eq!(index.children(NodeId(1)), &[NodeId(2), NodeId(4)]);
+
+        let nested = index.find("return")[0];
+        assert_eq!(index.depth(nested), 4);
+        assert_eq!(
+            index.ancestors(nested).collect::<Vec<_>>(),
+            vec![NodeId(7), NodeId(6), NodeId(4), NodeId(1)]
+        );
+        assert_eq!(index.enclosing(nested, "server"), Some(NodeId(4)));
+        assert_eq!(index.enclosing(nested, "stream"), None);
+        assert_eq!(index.parent(NodeId(0)), None);
+
+        let server = index.get(NodeId(4));
+        assert_eq!(index.id_of(server), Some(NodeId(4)));
+        assert_eq!(index.at_span(server.span), Some(NodeId(4)));
+        assert_eq!(index.try_get(NodeId(9)), None);
+        assert_eq!(NodeId(4).to_string(), "#4");
+    }
+
+    #[test]
+    fn test_name_lookup() {
+        let config = parse(
+            r"
+http {
+    access_log /var/log/nginx/main.log;
+    server {
+        access_log /var/log/nginx/a.log;
+        location / { access_log off; }
+    }
+}
+",
+        )
+        .unwrap();
+        let index = ConfigIndex::build(&config);
+
+        assert_eq!(
+            index.find_directives("access_log"),
+            config.find_directives_recursive("access_log")
+        );
+        assert_eq!(index.count("access_log"), 3);
+        assert_eq!(
+            index.find("access_log"),
+            vec![NodeId(1), NodeId(3), NodeId(5)]
+        );
+        assert!(index.contains("location"));
+        assert!(index.find_directives("proxy_pass").is_empty());
+        assert_eq!(index.names().count(), 4);
+    }
+}
@@ -0,0 +1,462 @@
+//! Merging an overlay configuration into a base
+//!
+//! The rules are documented on [`Config::merge`].
+
+use super::{Config, Directive, DirectiveItem, Value};
+use crate::types::ListenDirective;
+use std::collections::BTreeSet;
+
+/// Directives nginx accepts several times in one block
+const REPEATABLE: &[&str] = &[
+    "access_log",
+    "allow",
+    "auth_request_set",
+    "deny",
+    "env",
+    "error_log",
+    "error_page",
+    "fastcgi_cache_valid",
+    "include",
+    "limit_conn",
+    "limit_req",
+    "listen",
+    "load_module",
+    "mirror",
+    "proxy_cache_valid",
+    "proxy_hide_header",
+    "proxy_pass_header",
+    "rewrite",
+    "server",
+    "server_name",
+    "set_real_ip_from",
+    "ssl_certificate",
+    "ssl_certificate_key",
+    "ssl_conf_command",
+    "sub_filter",
+];
+
+/// Repeatable directives whose first argument identifies them
+const KEYED: &[&str] = &[
+    "add_header",
+    "add_trailer",
+    "fastcgi_param",
+    "grpc_set_header",
+    "proxy_set_header",
+    "scgi_param",
+    "set",
+    "uwsgi_param",
+];
+
+/// Directives defining something by name, and the parameter holding it
+/// (`None` for the first argument)
+const DEFINITIONS: &[(&str, Option<&str>)] = &[
+    ("fastcgi_cache_path", Some("keys_zone=")),
+    ("limit_conn_zone", Some("zone=")),
+    ("limit_req_zone", Some("zone=")),
+    ("log_format", None),
+    ("proxy_cache_path", Some("keys_zone=")),
+    ("scgi_cache_path", Some("keys_zone=")),
+    ("uwsgi_cache_path", Some("keys_zone=")),
+];
+
+/// Blocks that exist once per parent, matched by name alone
+const SINGLETON_BLOCKS: &[&str] = &["events", "http", "mail", "stream", "types"];
+
+/// Blocks whose children are entries keyed by their first word
+const DATA_BLOCKS: &[&str] = &["charset_map", "geo", "map", "split_clients", "types"];
+
+impl Config {
+    /// Merge an overlay configuration into this one
+    ///
+    /// Appending the directives of one file to another rarely gives a valid
+    /// configuration: `worker_processes` or `root` would be duplicate, a
+    /// second `http` block is rejected, and a second `server` for the same
+    /// name is ignored. This follows what each directive means instead:
+    ///
+    /// - singleton directives (`root`, `proxy_pass`, `worker_processes`, ...)
+    ///   replace the base value in place
+    /// - repeatable directives (`listen`, `allow`, `include`, ...) are added
+    ///   unless the block already has an identical one
+    /// - keyed directives (`proxy_set_header Host`, `add_header
+    ///   X-Frame-Options`, `set $var`, ...) replace the base directive with
+    ///   the same key
+    /// - definitions (`log_format main`, `limit_req_zone ... zone=one:10m`,
+    ///   `proxy_cache_path ... keys_zone=cache:10m`, ...) replace the base
+    ///   definition of the same name, which nginx would reject as a duplicate
+    /// - blocks are merged into the matching base block: `http` and `events`
+    ///   by name, `server` by `server_name` and listen addresses (by
+    ///   `server_name` alone when the overlay has no `listen`), `location`,
+    ///   `upstream` and `map` by their arguments
+    /// - entries of data blocks (`map`, `geo`, `types`, ...) replace the base
+    ///   entry with the same key
+    ///
+    /// New simple directives are placed before the nested blocks of their
+    /// block, and `load_module` before everything else at the top level.
+    /// Spans are kept, so merged directives still point at the file they
+    /// came from.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use nginx_discovery::parse;
+    ///
+    /// let mut base = parse(r"
+    /// worker_processes 2;
+    /// http {
+    ///     server {
+    ///         listen 80;
+    ///         server_name shop.example.com;
+    ///         location / { proxy_pass http://127.0.0.1:8080; proxy_set_header Host $host; }
+    ///     }
+    /// }
+    /// ")?;
+    /// let overlay = parse(r"
+    /// worker_processes auto;
+    /// http {
+    ///     server {
+    ///         listen 80;
+    ///         server_name shop.example.com;
+    ///         location / { proxy_pass http://10.0.0.5:8080; proxy_set_header Host shop.internal; }
+    ///     }
+    /// }
+    /// ")?;
+    ///
+    /// base.merge(overlay);
+    /// assert_eq!(
+    ///     base.to_config_string(),
+    ///     "worker_processes auto;
+    /// http {
+    ///     server {
+    ///         listen 80;
+    ///         server_name shop.example.com;
+    ///         location / {
+    ///             proxy_pass http://10.0.0.5:8080;
+    ///             proxy_set_header Host shop.internal;
+    ///         }
+    ///     }
+    /// }
+    /// "
+    /// );
+    /// # Ok::<(), nginx_discovery::Error>(())
+    /// ```
+    pub fn merge(&mut self, overlay: Config) {
+        merge_block(&mut self.directives, overlay.directives, None);
+    }
+}
+
+/// Merge `overlay` into the children of a block named `parent`
+/// (`None` at the top level)
+fn merge_block(base: &mut Vec<Directive>, overlay: Vec<Directive>, parent: Option<&str>) {
+    let data = parent.is_some_and(|p| DATA_BLOCKS.contains(&p));
+
+    for directive in overlay {
+        let Some(i) = base.iter().position(|b| matches(b, &directive, data)) else {
+            let at = insert_position(base, &directive, parent, data);
+            base.insert(at, directive);
+            continue;
+        };
+
+        if !data && base[i].is_block() {
+            let name = directive.name().to_string();
+            if let (DirectiveItem::Block { children, .. }, Some(base_children)) =
+                (directive.item, base[i].children_mut())
+            {
+                merge_block(base_children, children, Some(&name));
+            }
+            continue;
+        }
+
+        // Later base directives the overlay one replaces as well
+        let mut j = i + 1;
+        while j < base.len() {
+            if matches(&base[j], &directive, data) {
+                base.remove(j);
+            } else {
+                j += 1;
+            }
+        }
+        base[i] = directive;
+    }
+}
+
+/// Whether the overlay directive replaces or merges into `base`
+fn matches(base: &Directive, overlay: &Directive, data: bool) -> bool {
+    let name = overlay.name();
+    if base.name() != name || base.is_block() != overlay.is_block() {
+        return false;
+    }
+    if data {
+        return true;
+    }
+
+    if overlay.is_block() {
+        return match name {
+            _ if SINGLETON_BLOCKS.contains(&name) => true,
+            "server" => server_matches(base, overlay),
+            _ => args(base) == args(overlay),
+        };
+    }
+    if let Some((_, parameter)) = DEFINITIONS.iter().find(|(n, _)| *n == name) {
+        return match (
+            defined_name(base, *parameter),
+            defined_name(overlay, *parameter),
+        ) {
+            (Some(a), Some(b)) => a == b,
+            _ => args(base) == args(overlay),
+        };
+    }
+    if KEYED.contains(&name) {
+        let key = |d: &Directive| d.args().first().map(Value::unquoted);
+        return match (key(base), key(overlay)) {
+            (Some(a), Some(b)) => a.eq_ignore_ascii_case(&b),
+            _ => false,
+        };
+    }
+    !REPEATABLE.contains(&name) || args(base) == args(overlay)
+}
+
+fn args(directive: &Directive) -> Vec<String> {
+    directive.args().iter().map(Value::unquoted).collect()
+}
+
+/// Name a definition gives, from its first argument or from the value of
+/// `parameter` up to the size (`zone=one:10m` defines `one`)
+fn defined_name(directive: &Directive, parameter: Option<&str>) -> Option<String> {
+    let args = args(directive);
+    match parameter {
+        None => args.into_iter().next(),
+        Some(parameter) => args.iter().find_map(|arg| {
+            let value = arg.strip_prefix(parameter)?;
+            Some(value.split(':').next().unwrap_or_default().to_string())
+        }),
+    }
+}
+
+/// Whether an overlay server is the base server: same names and same
+/// listen addresses, or same names when the overlay has no `listen`
+///
+/// Matching on names alone would merge an overlay for the `443 ssl` server
+/// of a name into its port 80 redirect server.
+fn server_matches(base: &Directive, overlay: &Directive) -> bool {
+    server_names(base) == server_names(overlay)
+        && (overlay.find_children("listen").is_empty()
+            || listen_addresses(base) == listen_addresses(overlay))
+}
+
+fn server_names(server: &Directive) -> Vec<String> {
+    let mut names: Vec<String> = server
+        .find_children("server_name")
+        .into_iter()
+        .flat_map(args)
+        .collect();
+    names.sort();
+    names
+}
+
+/// `address:port` of each listener of a server (`*` for any IPv4
+/// address), `*:80` when it has none
+fn listen_addresses(server: &Directive) -> BTreeSet<String> {
+    let listens = server.find_children("listen");
+    if listens.is_empty() {
+        return BTreeSet::from(["*:80".to_string()]);
+    }
+    listens
+        .into_iter()
+        .filter_map(|listen| listen.args().first().map(Value::unquoted))
+        .map(
+            |address| match ListenDirective::from_args(std::slice::from_ref(&address)) {
+                Ok(listen) if listen.address == "0.0.0.0" => format!("*:{}", listen.port),
+                Ok(listen) => format!("{}:{}", listen.address, listen.port),
+                Err(_) => address,
+            },
+        )
+        .collect()
+}
+
+/// Where a directive new to the block goes
+fn insert_position(
+    base: &[Directive],
+    directive: &Directive,
+    parent: Option<&str>,
+    data: bool,
+) -> usize {
+    if parent.is_none() && directive.name() == "load_module" {
+        return base
+            .iter()
+            .rposition(|b| b.name() == "load_module")
+            .map_or(0, |i| i + 1);
+    }
+    if data || directive.is_block() {
+        return base.len();
+    }
+    base.iter()
+        .position(Directive::is_block)
+        .unwrap_or(base.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse;
+
+    fn merged(base: &str, overlay: &str) -> String {
+        let mut config = parse(base).unwrap();
+        config.merge(parse(overlay).unwrap());
+        config.to_config_string()
+    }
+
+    #[test]
+    fn test_merge_directives() {
+        let out = merged(
+            "user nginx;\nevents { worker_connections 512; }\nhttp { gzip on; gzip off; include mime.types; }",
+            "load_module modules/ngx_http_geoip2_module.so;\n\
+             events { worker_connections 4096; }\n\
+             http { gzip_types text/css; gzip on; include mime.types; include conf.d/*.conf; }",
+        );
+        assert_eq!(
+            out,
+            "load_module modules/ngx_http_geoip2_module.so;
+user nginx;
+events {
+    worker_connections 4096;
+}
+http {
+    gzip on;
+    include mime.types;
+    gzip_types text/css;
+    include conf.d/*.conf;
+}
+"
+        );
+    }
+
+    #[test]
+    fn test_merge_blocks() {
+        let out = merged(
+            r"http {
+    map $host $pool { default a; b.example b; }
+    upstream app { server 10.0.0.1; }
+    server { listen 80; location / { add_header X-A 1; } }
+    server { server_name b.example; }
+}",
+            r"http {
+    map $host $pool { b.example c; c.example c; }
+    upstream app { server 10.0.0.2; }
+    server { listen 80; location / { add_header x-a 2; add_header X-B 3; } location /new { return 204; } }
+    server { server_name c.example; }
+}",
+        );
+        assert_eq!(
+            out,
+            "http {
+    map $host $pool {
+        default a;
+        b.example c;
+        c.example c;
+    }
+    upstream app {
+        server 10.0.0.1;
+        server 10.0.0.2;
+    }
+    server {
+        listen 80;
+        location / {
+            add_header x-a 2;
+            add_header X-B 3;
+        }
+        location /new {
+            return 204;
+        }
+    }
+    server {
+        server_name b.example;
+    }
+    server {
+        server_name c.example;
+    }
+}
+"
+        );
+    }
+
+    #[test]
+    fn test_merge_servers_by_listen() {
+        let base = r"http {
+    server { listen 80; server_name a.com; return 301 https://$host$request_uri; }
+    server { listen 443 ssl; server_name a.com; root /old; }
+}";
+        let out = merged(
+            base,
+            "http { server { listen 443 ssl; server_name a.com; root /new; } }",
+        );
+        assert_eq!(
+            out,
+            "http {
+    server {
+        listen 80;
+        server_name a.com;
+        return 301 https://$host$request_uri;
+    }
+    server {
+        listen 443 ssl;
+        server_name a.com;
+        root /new;
+    }
+}
+"
+        );
+
+        // `*:443` and `0.0.0.0:443` are the same socket
+        let out = merged(
+            base,
+            "http { server { listen 0.0.0.0:443 ssl; server_name a.com; root /new; } }",
+        );
+        assert_eq!(out.matches("server {").count(), 2);
+        assert!(!out.contains("/old"));
+
+        // Same names on another port is another server
+        let out = merged(
+            base,
+            "http { server { listen 8443 ssl; server_name a.com; } }",
+        );
+        assert_eq!(out.matches("server {").count(), 3);
+
+        // Without a listen, the first server of the name
+        let out = merged(
+            base,
+            "http { server { server_name a.com; access_log off; } }",
+        );
+        assert!(out.contains("return 301 https://$host$request_uri;\n        access_log off;"));
+    }
+
+    #[test]
+    fn test_merge_definitions() {
+        let out = merged(
+            r"http {
+    log_format main '$remote_addr $request';
+    log_format json escape=json '{}';
+    limit_req_zone $binary_remote_addr zone=one:10m rate=1r/s;
+    limit_conn_zone $binary_remote_addr zone=addr:10m;
+    proxy_cache_path /var/cache/a levels=1:2 keys_zone=cache:10m;
+}",
+            r"http {
+    log_format main '$remote_addr $status';
+    limit_req_zone $binary_remote_addr zone=one:20m rate=5r/s;
+    limit_req_zone $server_name zone=two:10m rate=50r/s;
+    limit_conn_zone $server_name zone=addr:10m;
+    proxy_cache_path /var/cache/b keys_zone=cache:50m;
+}",
+        );
+        assert_eq!(
+            out,
+            "http {
+    log_format main '$remote_addr $status';
+    log_format json escape=json '{}';
+    limit_req_zone $binary_remote_addr zone=one:20m rate=5r/s;
+    limit_conn_zone $server_name zone=addr:10m;
+    proxy_cache_path /var/cache/b keys_zone=cache:50m;
+    limit_req_zone $server_name zone=two:10m rate=50r/s;
+}
+"
+        );
+    }
+}
@@ -25,17 +25,26 @@
 //! ```
 
 mod directive;
+mod index;
+mod merge;
+mod path;
+mod render;
+mod scrub;
 mod span;
 mod value;
 
 pub use directive::{Directive, DirectiveItem};
+pub use index::{ConfigIndex, NodeId};
+pub use path::{ConfigPath, PathSegment};
+pub use scrub::ScrubOptions;
 pub use span::{Span, Spanned};
-pub use value::Value;
+pub(crate) use value::{parse_duration, parse_size};
+pub use value::{split_variables, Segment, Value};
 
 /// Root configuration node
 ///
 /// Represents a complete NGINX configuration file or a logical section.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Config {
     /// Top-level directives
@@ -193,6 +202,33 @@ impl Config {
         count
     }
 
+    /// Copy of the configuration with identifying values redacted
+    ///
+    /// Server names, addresses, upstream hosts and auth and certificate file
+    /// paths are replaced by placeholders (`host-1.example`, `10.0.0.1`,
+    /// `/redacted/cert-1.pem`) and embedded credentials by `REDACTED`. The
+    /// same value always gets the same placeholder, so the scrubbed
+    /// configuration behaves like the original and can be shared in bug
+    /// reports.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use nginx_discovery::{ast::ScrubOptions, parse};
+    ///
+    /// let config = parse("server { server_name shop.example.com; return 301 https://shop.example.com/; }")?;
+    /// let scrubbed = config.scrubbed(ScrubOptions::default());
+    ///
+    /// let server = &scrubbed.directives[0];
+    /// assert_eq!(server.find_children("server_name")[0].args()[0].as_str(), "host-1.example");
+    /// assert_eq!(server.find_children("return")[0].args()[1].as_str(), "https://host-1.example/");
+    /// # Ok::<(), nginx_discovery::Error>(())
+    /// ```
+    #[must_use]
+    pub fn scrubbed(&self, options: ScrubOptions) -> Config {
+        scrub::scrub(self, options)
+    }
+
     /// Check if the configuration is empty
     #[must_use]
     pub fn is_empty(&self) -> bool {
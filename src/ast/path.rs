@@ -0,0 +1,357 @@
+//! Context paths addressing directives
+//!
+//! A [`ConfigPath`] names a directive by the blocks leading to it, each
+//! with its position among the siblings of the same name:
+//! `http/server[2]/location[3]` is the third `location` of the second
+//! `server` in `http`. Positions start at 1 and `[1]` may be left out, so
+//! `http/server` is the first server.
+//!
+//! Unlike a [`NodeId`] a path is readable and stays valid when the same
+//! configuration is parsed again; unlike a line number it does not change
+//! with formatting. Lint findings, semantic diffs and query matches carry
+//! one, and [`Query::under`](crate::query::Query::under) filters by one.
+
+use super::{Config, ConfigIndex, Directive, NodeId};
+use crate::{Error, Result};
+use std::fmt;
+use std::str::FromStr;
+
+/// One step of a [`ConfigPath`]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PathSegment {
+    /// Directive name
+    pub name: String,
+
+    /// Position among the siblings with the same name, starting at 1
+    pub position: usize,
+}
+
+impl fmt::Display for PathSegment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.position == 1 {
+            write!(f, "{}", self.name)
+        } else {
+            write!(f, "{}[{}]", self.name, self.position)
+        }
+    }
+}
+
+/// Address of a directive within a configuration
+///
+/// The empty path addresses the configuration itself and displays as `/`.
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::{ast::ConfigPath, parse};
+///
+/// let config = parse(r"
+/// http {
+///     server { server_name a.example; }
+///     server {
+///         server_name b.example;
+///         location / { root /srv; }
+///         location /api { proxy_pass http://api; }
+///     }
+/// }
+/// ")?;
+///
+/// let path: ConfigPath = "http/server[2]/location[2]".parse()?;
+/// let location = config.resolve(&path).unwrap();
+/// assert_eq!(location.first_arg().as_deref(), Some("/api"));
+///
+/// let index = config.index();
+/// let proxy_pass = index.find("proxy_pass")[0];
+/// assert_eq!(
+///     index.config_path(proxy_pass).to_string(),
+///     "http/server[2]/location[2]/proxy_pass"
+/// );
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "String", try_from = "String")
+)]
+pub struct ConfigPath {
+    segments: Vec<PathSegment>,
+}
+
+impl ConfigPath {
+    /// The path of the configuration itself
+    #[must_use]
+    pub fn root() -> Self {
+        Self::default()
+    }
+
+    /// Parse a path such as `http/server[2]/location[3]`
+    ///
+    /// A leading or trailing `/` is ignored; `""` and `/` give the root.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidInput`] for empty segments, names containing
+    /// whitespace or brackets, and positions that are not positive numbers.
+    pub fn parse(path: &str) -> Result<Self> {
+        let trimmed = path.trim().trim_matches('/');
+        if trimmed.is_empty() {
+            return Ok(Self::root());
+        }
+        let invalid =
+            |reason: &str| Error::InvalidInput(format!("invalid path '{path}': {reason}"));
+
+        let mut segments = Vec::new();
+        for part in trimmed.split('/') {
+            let (name, position) = match part.strip_suffix(']') {
+                Some(rest) => {
+                    let (name, position) = rest
+                        .split_once('[')
+                        .ok_or_else(|| invalid("unbalanced ']'"))?;
+                    let position: usize = position
+                        .parse()
+                        .map_err(|_| invalid(&format!("position '{position}' must be a number")))?;
+                    if position == 0 {
+                        return Err(invalid("positions start at 1"));
+                    }
+                    (name, position)
+                }
+                None => (part, 1),
+            };
+            if name.is_empty() {
+                return Err(invalid("empty segment"));
+            }
+            if name.contains(|c: char| c.is_whitespace() || c == '[' || c == ']') {
+                return Err(invalid("names cannot contain whitespace or brackets"));
+            }
+            segments.push(PathSegment {
+                name: name.to_string(),
+                position,
+            });
+        }
+        Ok(Self { segments })
+    }
+
+    /// Path of an indexed directive (same as [`ConfigIndex::config_path`])
+    #[must_use]
+    pub fn of(index: &ConfigIndex<'_>, id: NodeId) -> Self {
+        let mut chain: Vec<NodeId> = index.ancestors(id).collect();
+        chain.reverse();
+        chain.push(id);
+
+        let segments = chain
+            .into_iter()
+            .map(|node| {
+                let name = index.get(node).name();
+                let siblings = match index.parent(node) {
+                    Some(parent) => index.children(parent),
+                    None => index.roots(),
+                };
+                let position = siblings
+                    .iter()
+                    .take_while(|&&sibling| sibling != node)
+                    .filter(|&&sibling| index.get(sibling).name() == name)
+                    .count()
+                    + 1;
+                PathSegment {
+                    name: name.to_string(),
+                    position,
+                }
+            })
+            .collect();
+        Self { segments }
+    }
+
+    /// Extend the path with a child
+    #[must_use]
+    pub fn child(mut self, name: impl Into<String>, position: usize) -> Self {
+        self.segments.push(PathSegment {
+            name: name.into(),
+            position,
+        });
+        self
+    }
+
+    /// Steps from the outermost block to the directive
+    #[must_use]
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.segments
+    }
+
+    /// Number of steps
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.segments.len()
+    }
+
+    /// Check if this is the root path
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Name of the addressed directive, `None` for the root
+    #[must_use]
+    pub fn name(&self) -> Option<&str> {
+        self.segments.last().map(|s| s.name.as_str())
+    }
+
+    /// Path of the enclosing block, `None` for the root
+    #[must_use]
+    pub fn parent(&self) -> Option<Self> {
+        let (_, parent) = self.segments.split_last()?;
+        Some(Self {
+            segments: parent.to_vec(),
+        })
+    }
+
+    /// Check if this path is `other` or lies inside it
+    #[must_use]
+    pub fn starts_with(&self, other: &ConfigPath) -> bool {
+        self.segments.starts_with(&other.segments)
+    }
+}
+
+impl fmt::Display for ConfigPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.segments.is_empty() {
+            return write!(f, "/");
+        }
+        for (i, segment) in self.segments.iter().enumerate() {
+            if i > 0 {
+                write!(f, "/")?;
+            }
+            write!(f, "{segment}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for ConfigPath {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s)
+    }
+}
+
+impl From<ConfigPath> for String {
+    fn from(path: ConfigPath) -> Self {
+        path.to_string()
+    }
+}
+
+impl TryFrom<String> for ConfigPath {
+    type Error = Error;
+
+    fn try_from(path: String) -> Result<Self> {
+        Self::parse(&path)
+    }
+}
+
+impl ConfigIndex<'_> {
+    /// Context path of a directive
+    #[must_use]
+    pub fn config_path(&self, id: NodeId) -> ConfigPath {
+        ConfigPath::of(self, id)
+    }
+
+    /// Id of the directive a path addresses, `None` for the root and for
+    /// paths leading nowhere
+    #[must_use]
+    pub fn resolve(&self, path: &ConfigPath) -> Option<NodeId> {
+        let mut siblings = self.roots();
+        let mut found = None;
+        for segment in path.segments() {
+            let id = siblings
+                .iter()
+                .copied()
+                .filter(|&id| self.get(id).name() == segment.name)
+                .nth(segment.position - 1)?;
+            siblings = self.children(id);
+            found = Some(id);
+        }
+        found
+    }
+}
+
+impl Config {
+    /// Directive a path addresses, `None` for the root and for paths
+    /// leading nowhere
+    #[must_use]
+    pub fn resolve(&self, path: &ConfigPath) -> Option<&Directive> {
+        let mut siblings = self.directives.as_slice();
+        let mut found = None;
+        for segment in path.segments() {
+            let directive = siblings
+                .iter()
+                .filter(|d| d.name() == segment.name)
+                .nth(segment.position - 1)?;
+            siblings = directive.children().unwrap_or_default();
+            found = Some(directive);
+        }
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_parse_and_display() {
+        let path = ConfigPath::parse("/http/server[2]/location[1]/").unwrap();
+        assert_eq!(path.to_string(), "http/server[2]/location");
+        assert_eq!(path.len(), 3);
+        assert_eq!(path.name(), Some("location"));
+        assert_eq!(path.parent().unwrap().to_string(), "http/server[2]");
+        assert!(path.starts_with(&"http/server[2]".parse().unwrap()));
+        assert!(!path.starts_with(&"http/server".parse().unwrap()));
+        assert_eq!(
+            ConfigPath::root().child("stream", 1).child("server", 3),
+            "stream/server[3]".parse().unwrap()
+        );
+        assert_eq!(ConfigPath::parse("/").unwrap().to_string(), "/");
+
+        for invalid in ["http//server", "server[0]", "server[x]", "server]", "a b"] {
+            assert!(ConfigPath::parse(invalid).is_err(), "{invalid}");
+        }
+        let err = ConfigPath::parse("server[x]").unwrap_err().to_string();
+        assert!(err.contains("position 'x' must be a number"), "{err}");
+        let err = ConfigPath::parse("server[0]").unwrap_err().to_string();
+        assert!(err.contains("positions start at 1"), "{err}");
+    }
+
+    #[test]
+    fn test_path_round_trip() {
+        let config = parse(
+            r"
+user nginx;
+http {
+    server { listen 80; }
+    server {
+        listen 443;
+        location / { return 204; }
+        location /a { location /a/b { return 200; } }
+    }
+}
+",
+        )
+        .unwrap();
+        let index = config.index();
+
+        for id in index.ids() {
+            let path = index.config_path(id);
+            assert_eq!(index.resolve(&path), Some(id), "{path}");
+            assert!(std::ptr::eq(config.resolve(&path).unwrap(), index.get(id)));
+        }
+        let nested = index.find("return")[1];
+        assert_eq!(
+            index.config_path(nested).to_string(),
+            "http/server[2]/location[2]/location/return"
+        );
+        assert_eq!(index.resolve(&"http/server[3]".parse().unwrap()), None);
+        assert_eq!(config.resolve(&ConfigPath::root()), None);
+    }
+}
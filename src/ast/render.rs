@@ -0,0 +1,121 @@
+//! Rendering the AST back to configuration text
+//!
+//! The output is normalized (four-space indentation, one directive per
+//! line, comments dropped) and parses back to the same AST, spans aside.
+
+use super::{Config, Directive, Value};
+
+const INDENT: &str = "    ";
+
+impl Config {
+    /// Render the configuration as nginx configuration text
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use nginx_discovery::parse;
+    ///
+    /// let config = parse("http{server{listen 80;return 200 'ok';}}")?;
+    /// assert_eq!(
+    ///     config.to_config_string(),
+    ///     "http {\n    server {\n        listen 80;\n        return 200 'ok';\n    }\n}\n"
+    /// );
+    /// # Ok::<(), nginx_discovery::Error>(())
+    /// ```
+    #[must_use]
+    pub fn to_config_string(&self) -> String {
+        let mut out = String::new();
+        for directive in &self.directives {
+            render(directive, 0, &mut out);
+        }
+        out
+    }
+}
+
+impl Directive {
+    /// Render the directive, and its block if any, as configuration text
+    #[must_use]
+    pub fn to_config_string(&self) -> String {
+        let mut out = String::new();
+        render(self, 0, &mut out);
+        out
+    }
+}
+
+fn render(directive: &Directive, depth: usize, out: &mut String) {
+    let indent = INDENT.repeat(depth);
+    let args = directive.args();
+    out.push_str(&indent);
+    // Quoted names (`'' '';` in a `map`) keep their quotes
+    out.push_str(&Value::literal(directive.name()).to_config_string());
+
+    // A Lua block keeps its code as the last argument of an empty block
+    let lua = directive.name().ends_with("_by_lua_block")
+        && directive.children().is_some_and(<[Directive]>::is_empty);
+    let (args, code) = match args.split_last() {
+        Some((code, rest)) if lua => (rest, Some(code.as_str())),
+        _ => (args, None),
+    };
+    for arg in args {
+        out.push(' ');
+        out.push_str(&arg.to_config_string());
+    }
+
+    match (directive.children(), code) {
+        (_, Some(code)) if code.contains('\n') => {
+            out.push_str(" {\n");
+            out.push_str(code);
+            out.push('\n');
+            out.push_str(&indent);
+            out.push_str("}\n");
+        }
+        (_, Some(code)) => {
+            out.push_str(" { ");
+            out.push_str(code);
+            out.push_str(" }\n");
+        }
+        (Some([]), None) => out.push_str(" {}\n"),
+        (Some(children), None) => {
+            out.push_str(" {\n");
+            for child in children {
+                render(child, depth + 1, out);
+            }
+            out.push_str(&indent);
+            out.push_str("}\n");
+        }
+        (None, None) => out.push_str(";\n"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse;
+
+    #[test]
+    fn test_render_round_trip() {
+        let text = r#"
+# comment
+events {}
+http {
+    log_format main '$remote_addr "$request"';
+    server {
+        listen [::]:443 ssl;
+        server_name "~^(?<sub>.+)\.example\.com$";
+        location ~* \.(png|jpg)$ { expires 30d; }
+        content_by_lua_block {
+            ngx.say("}")
+        }
+        access_by_lua_block { ngx.exit(403) }
+    }
+}
+"#;
+        let config = parse(text).unwrap();
+        let rendered = config.to_config_string();
+        let reparsed = parse(&rendered).unwrap();
+
+        assert_eq!(reparsed.to_config_string(), rendered);
+        assert_eq!(reparsed.count_directives(), config.count_directives());
+        assert!(rendered.starts_with("events {}\nhttp {\n"));
+        assert!(rendered.contains("        access_by_lua_block { ngx.exit(403) }\n"));
+    }
+}
@@ -1,6 +1,139 @@
 //! Value types for NGINX directive arguments
+//!
+//! A [`Value`] keeps the argument as written. The typed accessors
+//! ([`Value::as_bool`], [`Value::as_number`], [`Value::as_size`],
+//! [`Value::as_duration`] and [`Value::segments`]) interpret it on demand
+//! with nginx's own rules, since the same text can mean different things to
+//! different directives (`10m` is ten minutes to `proxy_read_timeout` and
+//! ten megabytes to `client_max_body_size`).
 
 use std::fmt;
+use std::time::Duration;
+
+/// A piece of an argument: literal text or a variable reference
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Segment<'a> {
+    /// Literal text
+    Literal(&'a str),
+
+    /// Variable name of a `$name` or `${name}` reference
+    Variable(&'a str),
+}
+
+/// Split text into literal parts and `$name` / `${name}` references.
+///
+/// A `$` not followed by a name is literal text.
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::ast::{split_variables, Segment};
+///
+/// assert_eq!(
+///     split_variables("/var/log/${host}_$status.log"),
+///     vec![
+///         Segment::Literal("/var/log/"),
+///         Segment::Variable("host"),
+///         Segment::Literal("_"),
+///         Segment::Variable("status"),
+///         Segment::Literal(".log"),
+///     ]
+/// );
+/// ```
+#[must_use]
+pub fn split_variables(text: &str) -> Vec<Segment<'_>> {
+    let mut segments = Vec::new();
+    // Start of the pending literal text
+    let mut literal = 0;
+    let mut pos = 0;
+
+    while let Some(offset) = text[pos..].find('$') {
+        let dollar = pos + offset;
+        let after = &text[dollar + 1..];
+        let (name, consumed) = if let Some(braced) = after.strip_prefix('{') {
+            match braced.find('}') {
+                Some(end) => (&braced[..end], end + 2),
+                None => ("", 0),
+            }
+        } else {
+            let end = after
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(after.len());
+            (&after[..end], end)
+        };
+        pos = dollar + 1 + consumed;
+
+        // A lone `$` stays part of the literal text
+        if name.is_empty() {
+            continue;
+        }
+        if dollar > literal {
+            segments.push(Segment::Literal(&text[literal..dollar]));
+        }
+        segments.push(Segment::Variable(name));
+        literal = pos;
+    }
+
+    if literal < text.len() {
+        segments.push(Segment::Literal(&text[literal..]));
+    }
+    segments
+}
+
+/// Parse an nginx time (`30s`, `1h 30m`, `500ms`; a bare number is
+/// seconds)
+pub(crate) fn parse_duration(text: &str) -> Option<Duration> {
+    let mut total = Duration::ZERO;
+    let mut rest = text.trim();
+    if rest.is_empty() {
+        return None;
+    }
+
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        if digits == 0 {
+            return None;
+        }
+        let n: u64 = rest[..digits].parse().ok()?;
+        rest = &rest[digits..];
+
+        let (unit, len) = if rest.starts_with("ms") {
+            (Duration::from_millis(1), 2)
+        } else {
+            let seconds = match rest.chars().next() {
+                None | Some(' ' | 's') => 1,
+                Some('m') => 60,
+                Some('h') => 3600,
+                Some('d') => 86_400,
+                Some('w') => 604_800,
+                Some('M') => 2_592_000,
+                Some('y') => 31_536_000,
+                Some(_) => return None,
+            };
+            let len = usize::from(!rest.is_empty() && !rest.starts_with(' '));
+            (Duration::from_secs(seconds), len)
+        };
+        total = total.checked_add(unit.checked_mul(u32::try_from(n).ok()?)?)?;
+        rest = rest[len..].trim_start();
+    }
+    Some(total)
+}
+
+/// Parse an nginx size (`512`, `8k`, `10m`, `1g`) into bytes
+pub(crate) fn parse_size(text: &str) -> Option<u64> {
+    let (number, multiplier) = match text.chars().last()? {
+        'k' | 'K' => (&text[..text.len() - 1], 1024),
+        'm' | 'M' => (&text[..text.len() - 1], 1024 * 1024),
+        'g' | 'G' => (&text[..text.len() - 1], 1024 * 1024 * 1024),
+        _ => (text, 1),
+    };
+    if number.is_empty() || !number.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    number.parse::<u64>().ok()?.checked_mul(multiplier)
+}
 
 /// Represents a value in an NGINX directive
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -63,18 +196,176 @@ impl Value {
         matches!(self, Self::SingleQuoted(_) | Self::DoubleQuoted(_))
     }
 
+    /// Get the value as nginx sees it: quotes removed, variables keep
+    /// their `$`
+    #[must_use]
+    pub fn unquoted(&self) -> String {
+        match self {
+            Self::Variable(s) => format!("${s}"),
+            other => other.as_str().to_string(),
+        }
+    }
+
+    /// Split the value into literal text and variable references
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nginx_discovery::ast::{Segment, Value};
+    ///
+    /// let value = Value::literal("/var/log/$host.log");
+    /// assert!(value.is_interpolated());
+    /// assert_eq!(value.segments()[1], Segment::Variable("host"));
+    /// ```
+    #[must_use]
+    pub fn segments(&self) -> Vec<Segment<'_>> {
+        match self {
+            Self::Variable(name) => vec![Segment::Variable(name)],
+            other => split_variables(other.as_str()),
+        }
+    }
+
+    /// Names of the variables the value references
+    #[must_use]
+    pub fn variables(&self) -> Vec<&str> {
+        self.segments()
+            .into_iter()
+            .filter_map(|segment| match segment {
+                Segment::Variable(name) => Some(name),
+                Segment::Literal(_) => None,
+            })
+            .collect()
+    }
+
+    /// Check if the value mixes variables with other text or variables,
+    /// e.g. `/var/log/$host.log`
+    #[must_use]
+    pub fn is_interpolated(&self) -> bool {
+        let segments = self.segments();
+        segments.len() > 1 && segments.iter().any(|s| matches!(s, Segment::Variable(_)))
+    }
+
+    /// Interpret the value as a flag (`on`/`off`, any case)
+    #[must_use]
+    pub fn as_bool(&self) -> Option<bool> {
+        if self.is_variable() {
+            return None;
+        }
+        match self.as_str() {
+            s if s.eq_ignore_ascii_case("on") => Some(true),
+            s if s.eq_ignore_ascii_case("off") => Some(false),
+            _ => None,
+        }
+    }
+
+    /// Interpret the value as a non-negative integer
+    #[must_use]
+    pub fn as_number(&self) -> Option<u64> {
+        let s = self.as_str();
+        if self.is_variable() || s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        s.parse().ok()
+    }
+
+    /// Interpret the value as a size in bytes (`512`, `8k`, `10m`, `1g`)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nginx_discovery::ast::Value;
+    ///
+    /// assert_eq!(Value::literal("8k").as_size(), Some(8192));
+    /// assert_eq!(Value::literal("1h").as_size(), None);
+    /// ```
+    #[must_use]
+    pub fn as_size(&self) -> Option<u64> {
+        if self.is_variable() {
+            return None;
+        }
+        parse_size(self.as_str())
+    }
+
+    /// Interpret the value as a time (`500ms`, `30s`, `1h 30m`, `7d`; a
+    /// bare number is seconds)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nginx_discovery::ast::Value;
+    /// use std::time::Duration;
+    ///
+    /// assert_eq!(Value::literal("1h30m").as_duration(), Some(Duration::from_secs(5400)));
+    /// assert_eq!(Value::literal("60").as_duration(), Some(Duration::from_secs(60)));
+    /// ```
+    #[must_use]
+    pub fn as_duration(&self) -> Option<Duration> {
+        if self.is_variable() {
+            return None;
+        }
+        parse_duration(self.as_str())
+    }
+
     /// Get the value as it would appear in the config file
+    ///
+    /// Quoted values keep their quote style unless the text contains that
+    /// quote unescaped; literals that would not lex as a single word
+    /// (spaces, `;`, braces) are double-quoted.
     #[must_use]
     pub fn to_config_string(&self) -> String {
         match self {
+            Self::Literal(s) if needs_quotes(s) => quote(s, '"'),
             Self::Literal(s) => s.clone(),
-            Self::SingleQuoted(s) => format!("'{s}'"),
-            Self::DoubleQuoted(s) => format!("\"{s}\""),
+            Self::SingleQuoted(s) => quote(s, '\''),
+            Self::DoubleQuoted(s) => quote(s, '"'),
             Self::Variable(s) => format!("${s}"),
         }
     }
 }
 
+/// Whether a bare word would be lexed differently from `text`
+fn needs_quotes(text: &str) -> bool {
+    text.is_empty()
+        || text.starts_with(['"', '\'', '#'])
+        || text
+            .chars()
+            .any(|c| c.is_whitespace() || matches!(c, ';' | '{' | '}'))
+}
+
+/// Whether `text` contains `quote` not preceded by a backslash
+fn has_unescaped(text: &str, quote: char) -> bool {
+    let mut escaped = false;
+    for c in text.chars() {
+        if c == quote && !escaped {
+            return true;
+        }
+        escaped = c == '\\' && !escaped;
+    }
+    false
+}
+
+/// Quote raw string content, preferring `preferred` and escaping only when
+/// both quote characters occur
+fn quote(text: &str, preferred: char) -> String {
+    let other = if preferred == '"' { '\'' } else { '"' };
+    if !has_unescaped(text, preferred) {
+        format!("{preferred}{text}{preferred}")
+    } else if !has_unescaped(text, other) {
+        format!("{other}{text}{other}")
+    } else {
+        let mut escaped = String::with_capacity(text.len() + 2);
+        let mut backslash = false;
+        for c in text.chars() {
+            if c == preferred && !backslash {
+                escaped.push('\\');
+            }
+            backslash = c == '\\' && !backslash;
+            escaped.push(c);
+        }
+        format!("{preferred}{escaped}{preferred}")
+    }
+}
+
 impl fmt::Display for Value {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.as_str())
@@ -135,6 +426,71 @@ mod tests {
         assert!(val.is_variable());
         assert!(!val.is_quoted());
         assert_eq!(val.to_config_string(), "$remote_addr");
+        assert_eq!(val.unquoted(), "$remote_addr");
+        assert_eq!(Value::double_quoted("a b").unquoted(), "a b");
+    }
+
+    #[test]
+    fn test_to_config_string_quoting() {
+        assert_eq!(Value::literal("a b").to_config_string(), "\"a b\"");
+        assert_eq!(Value::literal("").to_config_string(), "\"\"");
+        assert_eq!(Value::single_quoted("it's").to_config_string(), "\"it's\"");
+        assert_eq!(
+            Value::single_quoted(r"it\'s").to_config_string(),
+            r"'it\'s'"
+        );
+        assert_eq!(
+            Value::double_quoted(r#"'a' "b""#).to_config_string(),
+            r#""'a' \"b\"""#
+        );
+    }
+
+    #[test]
+    fn test_segments() {
+        assert_eq!(
+            Value::variable("host").segments(),
+            vec![Segment::Variable("host")]
+        );
+        assert!(!Value::variable("host").is_interpolated());
+        assert!(Value::double_quoted("$scheme://$host").is_interpolated());
+        assert_eq!(
+            Value::double_quoted("$scheme://$host").variables(),
+            vec!["scheme", "host"]
+        );
+        assert_eq!(
+            split_variables("a$ b${x"),
+            vec![Segment::Literal("a$ b${x")]
+        );
+    }
+
+    #[test]
+    fn test_typed_accessors() {
+        assert_eq!(Value::literal("ON").as_bool(), Some(true));
+        assert_eq!(Value::literal("off").as_bool(), Some(false));
+        assert_eq!(Value::literal("yes").as_bool(), None);
+
+        assert_eq!(Value::literal("1024").as_number(), Some(1024));
+        assert_eq!(Value::literal("-1").as_number(), None);
+        assert_eq!(Value::variable("1").as_number(), None);
+
+        assert_eq!(Value::literal("512").as_size(), Some(512));
+        assert_eq!(Value::literal("10M").as_size(), Some(10 * 1024 * 1024));
+        assert_eq!(Value::literal("1g").as_size(), Some(1 << 30));
+        assert_eq!(Value::literal("k").as_size(), None);
+
+        let secs = |s: u64| Some(Duration::from_secs(s));
+        assert_eq!(Value::literal("30d").as_duration(), secs(2_592_000));
+        assert_eq!(Value::double_quoted("1h 30m").as_duration(), secs(5400));
+        assert_eq!(
+            Value::literal("1s500ms").as_duration(),
+            Some(Duration::from_millis(1500))
+        );
+        assert_eq!(Value::literal("10x").as_duration(), None);
+        assert_eq!(Value::literal("").as_duration(), None);
+        assert_eq!(Value::literal("9999999999999d").as_duration(), None);
+        // Each part fits, their sum does not
+        let overflowing = "4294967295y".repeat(200);
+        assert_eq!(Value::literal(overflowing).as_duration(), None);
     }
 
     #[test]
@@ -1,6 +1,7 @@
 //! Command-line argument definitions
 
 use clap::{Args, Parser, Subcommand, ValueEnum};
+use nginx_discovery::parser::SourceEncoding;
 use std::path::PathBuf;
 
 /// NGINX configuration discovery and analysis tool
@@ -33,6 +34,10 @@ pub struct GlobalOpts {
     /// When to use colored output
     #[arg(long, value_enum, default_value = "auto", global = true)]
     pub color: ColorChoice,
+
+    /// Encoding of the configuration files (utf-8, latin1, windows-1252)
+    #[arg(long, default_value = "utf-8", global = true)]
+    pub encoding: SourceEncoding,
 }
 
 #[derive(Debug, Clone, ValueEnum)]
@@ -60,6 +65,56 @@ pub enum Commands {
     /// Run diagnostics and health checks
     Doctor(DoctorArgs),
 
+    /// Validate configuration before deployment (parse, lint, conflicts)
+    Ci(CiArgs),
+
+    /// Show reference documentation for a directive
+    Explain(ExplainArgs),
+
+    /// Compare two configurations by servers, locations and upstreams
+    Diff(DiffArgs),
+
+    /// Route recorded requests (HAR file or access log) and show how each
+    /// is handled, or which are routed differently by another configuration
+    Replay(ReplayArgs),
+
+    /// Find directives by name, arguments, enclosing blocks and children
+    Query(QueryArgs),
+
+    /// Show which file includes which, with cycles and missing files
+    Includes(IncludesArgs),
+
+    /// Parse every configuration file below a directory, without a master
+    /// nginx.conf
+    Scan(ScanArgs),
+
+    /// Summarise the access logs the configuration writes
+    Logs(LogsArgs),
+
+    /// Capture the running configuration (nginx -T) into a tar archive
+    Backup(BackupArgs),
+
+    /// Compare a backup archive with the files on disk and restore it
+    Restore(RestoreArgs),
+
+    /// Run live network checks against the configuration
+    #[cfg(feature = "network")]
+    Network(Box<NetworkArgs>),
+
+    /// Run network checks on an interval and serve the results as
+    /// Prometheus metrics
+    #[cfg(feature = "network")]
+    Daemon(DaemonArgs),
+
+    /// Translate the configuration into a Caddyfile, HAProxy or nginx Unit
+    /// configuration
+    #[cfg(feature = "migrate")]
+    Migrate(MigrateArgs),
+
+    /// Run a Language Server Protocol server over stdio
+    #[cfg(feature = "lsp")]
+    Lsp,
+
     /// Interactive mode - guided configuration analysis
     Interactive,
 }
@@ -161,6 +216,60 @@ pub enum ExtractTarget {
         #[arg(short, long)]
         output: Option<PathBuf>,
     },
+
+    /// Extract split_clients blocks (A/B tests and traffic splits)
+    SplitClients {
+        /// Output format
+        #[arg(short, long, value_enum)]
+        format: Option<OutputFormat>,
+
+        /// Output file
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Extract upstream blocks with their servers and keepalive settings
+    Upstreams {
+        /// Output format
+        #[arg(short, long, value_enum)]
+        format: Option<OutputFormat>,
+
+        /// Output file
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Extract URLs for external monitoring (server_name x listen)
+    Monitoring {
+        /// Target list layout for json/yaml output
+        #[arg(long, value_enum, default_value = "blackbox")]
+        style: MonitoringStyle,
+
+        /// blackbox-exporter prober module
+        #[arg(long, default_value = "http_2xx")]
+        module: String,
+
+        /// Uptime Kuma check interval in seconds
+        #[arg(long, default_value = "60")]
+        interval: u32,
+
+        /// Output format
+        #[arg(short, long, value_enum)]
+        format: Option<OutputFormat>,
+
+        /// Output file
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+/// Target list layouts for `extract monitoring`
+#[derive(Debug, Clone, ValueEnum)]
+pub enum MonitoringStyle {
+    /// Prometheus file_sd target groups for blackbox-exporter
+    Blackbox,
+    /// Uptime Kuma import document
+    UptimeKuma,
 }
 
 #[derive(Debug, Clone, ValueEnum)]
@@ -174,9 +283,9 @@ pub enum OutputFormat {
 /// Arguments for the export command
 #[derive(Args, Debug)]
 pub struct ExportArgs {
-    /// Export format
-    #[arg(value_enum)]
-    pub format: ExportFormat,
+    /// Export format: json, yaml, or any other registered exporter (toml
+    /// and markdown when built with their features)
+    pub format: String,
 
     /// Output file (stdout if not specified)
     #[arg(short, long)]
@@ -185,12 +294,26 @@ pub struct ExportArgs {
     /// Pretty-print output (for JSON/YAML)
     #[arg(long)]
     pub pretty: bool,
-}
 
-#[derive(Debug, Clone, ValueEnum)]
-pub enum ExportFormat {
-    Json,
-    Yaml,
+    /// Redact server names, addresses, upstream hosts and auth/certificate
+    /// paths so the output can be shared
+    #[arg(long)]
+    pub scrub: bool,
+
+    /// Annotate every directive with the file and line it is defined at
+    #[arg(long)]
+    pub source: bool,
+
+    /// Byte-identical output for identical configurations, for committing
+    /// exports to version control
+    #[arg(long)]
+    pub deterministic: bool,
+
+    /// Export the settings in effect in every server and location after
+    /// inheritance (root, headers, timeouts, logging) instead of the
+    /// directives as written
+    #[arg(long)]
+    pub effective: bool,
 }
 
 /// Arguments for the doctor command
@@ -203,6 +326,566 @@ pub struct DoctorArgs {
     /// Attempt to fix issues automatically
     #[arg(long)]
     pub fix: bool,
+
+    /// Output format (table prints the colored report)
+    #[arg(short, long, value_enum, default_value = "table")]
+    pub format: OutputFormat,
+}
+
+/// Arguments for the ci command
+#[derive(Args, Debug)]
+pub struct CiArgs {
+    /// Configuration file to validate (defaults to --config or auto-detection)
+    pub file: Option<PathBuf>,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value = "text")]
+    pub format: CiFormat,
+
+    /// Fail when a finding at or above this level is found (info, warning, error)
+    #[arg(long, default_value = "error")]
+    pub fail_on: String,
+
+    /// Also check that referenced certificate and key files are valid
+    #[arg(long)]
+    pub check_certs: bool,
+
+    /// Also check that the document root of every location exists
+    #[arg(long)]
+    pub check_roots: bool,
+
+    /// Skip these lint rules (comma-separated or repeated)
+    #[arg(long, value_delimiter = ',')]
+    pub skip: Vec<String>,
+
+    /// nginx version the configuration is deployed to; limits deprecation
+    /// findings to that release and reports removed directives as errors
+    #[arg(long, value_name = "VERSION")]
+    pub nginx_version: Option<String>,
+
+    /// Also check these policy packs (mozilla-intermediate-tls, cis-nginx-benchmark-subset,
+    /// performance-baseline, or mozilla, cis, performance; comma-separated or repeated)
+    #[arg(long, value_delimiter = ',')]
+    pub policy: Vec<String>,
+
+    /// List lint rules and exit
+    #[arg(long)]
+    pub list_rules: bool,
+}
+
+/// Output formats for the ci command
+#[derive(Debug, Clone, ValueEnum)]
+pub enum CiFormat {
+    /// Human-readable findings
+    Text,
+    /// JSON report
+    Json,
+    /// GitHub Actions workflow commands (`::error file=...,line=...`)
+    Github,
+    /// reviewdog Diagnostic JSON (rdjson)
+    Rdjson,
+}
+
+/// Arguments for the explain command
+#[derive(Args, Debug)]
+pub struct ExplainArgs {
+    /// Directive name (e.g. proxy_pass)
+    #[arg(required_unless_present = "url")]
+    pub directive: Option<String>,
+
+    /// Show how the configuration handles a request to this URL instead
+    /// (server, location, rewrites, effective directives and backend)
+    #[arg(long, conflicts_with = "directive")]
+    pub url: Option<String>,
+
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// Arguments for the diff command
+#[derive(Args, Debug)]
+pub struct DiffArgs {
+    /// Current configuration (e.g. blue)
+    pub old: PathBuf,
+
+    /// Configuration to compare against it (e.g. green or canary)
+    pub new: PathBuf,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value = "table")]
+    pub format: OutputFormat,
+
+    /// Exit with status 1 when the configurations differ
+    #[arg(long)]
+    pub exit_code: bool,
+
+    /// Print a review report grouped by category, including TLS and
+    /// timeout settings, instead of the change list
+    #[arg(long, value_enum)]
+    pub report: Option<ReportFormat>,
+
+    /// Server the old configuration is for, to compare across web servers
+    #[cfg(feature = "import")]
+    #[arg(long, value_enum, default_value = "nginx")]
+    pub old_format: ConfigFormat,
+
+    /// Server the new configuration is for
+    #[cfg(feature = "import")]
+    #[arg(long, value_enum, default_value = "nginx")]
+    pub new_format: ConfigFormat,
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+pub enum ReportFormat {
+    Markdown,
+    Html,
+}
+
+#[cfg(feature = "import")]
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum ConfigFormat {
+    Nginx,
+    Apache,
+    Caddy,
+}
+
+/// Arguments for the replay command
+#[derive(Args, Debug)]
+#[command(group(clap::ArgGroup::new("input").required(true).args(["har", "log"])))]
+pub struct ReplayArgs {
+    /// HAR file exported from a browser
+    #[arg(long, value_name = "FILE")]
+    pub har: Option<PathBuf>,
+
+    /// Access log file
+    #[arg(long, value_name = "FILE")]
+    pub log: Option<PathBuf>,
+
+    /// Lines read from the end of the access log
+    #[arg(long, default_value = "10000", requires = "log")]
+    pub lines: usize,
+
+    /// log_format of the access log (default: the format the configuration
+    /// writes it with, else combined)
+    #[arg(long, value_name = "NAME", requires = "log")]
+    pub log_format: Option<String>,
+
+    /// Host for log lines without $host, $http_host or $server_name
+    #[arg(long, default_value = "localhost")]
+    pub host: String,
+
+    /// Show only the requests this configuration routes differently
+    #[arg(long, value_name = "CONFIG")]
+    pub against: Option<PathBuf>,
+
+    /// Exit with status 1 when routing changes (with --against)
+    #[arg(long, requires = "against")]
+    pub exit_code: bool,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value = "table")]
+    pub format: OutputFormat,
+
+    /// Output file (stdout if not specified)
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+}
+
+/// Arguments for the includes command
+#[derive(Args, Debug)]
+pub struct IncludesArgs {
+    /// Graph format
+    #[arg(short, long, value_enum, default_value = "dot")]
+    pub format: GraphFormat,
+
+    /// Exit with status 1 when a cycle or a missing include is found
+    #[arg(long)]
+    pub strict: bool,
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+pub enum GraphFormat {
+    Dot,
+    Json,
+}
+
+/// Arguments for the logs command
+#[derive(Args, Debug)]
+pub struct LogsArgs {
+    #[command(subcommand)]
+    pub command: LogsCommand,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum LogsCommand {
+    /// Top status codes, paths and clients, error rates and request time
+    /// percentiles over the last lines of each access log
+    Stats {
+        /// Lines read from the end of each log
+        #[arg(long, default_value = "10000")]
+        lines: usize,
+
+        /// Only count lines logged in the last N minutes
+        #[arg(long, value_name = "N")]
+        minutes: Option<u64>,
+
+        /// Only this access log file
+        #[arg(long, value_name = "FILE")]
+        log: Option<PathBuf>,
+
+        /// Entries in each top list
+        #[arg(long, default_value = "10")]
+        top: usize,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "table")]
+        format: OutputFormat,
+
+        /// Output file (stdout if not specified)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Traffic share and error rates per backend logged in
+    /// `$upstream_addr`, flagging backends the configuration no longer
+    /// lists
+    Backends {
+        /// Lines read from the end of each log
+        #[arg(long, default_value = "10000")]
+        lines: usize,
+
+        /// Only count lines logged in the last N minutes
+        #[arg(long, value_name = "N")]
+        minutes: Option<u64>,
+
+        /// Do not resolve configured hostnames to match logged addresses
+        #[arg(long)]
+        no_resolve: bool,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "table")]
+        format: OutputFormat,
+
+        /// Output file (stdout if not specified)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+/// Arguments for the backup command
+#[derive(Args, Debug)]
+pub struct BackupArgs {
+    /// Directory the archive is written to
+    pub dest: PathBuf,
+
+    /// Read nginx -T output from this file instead of running nginx
+    #[arg(long)]
+    pub dump: Option<PathBuf>,
+
+    /// Store referenced certificates, keys and password files, not only
+    /// their paths
+    #[arg(long)]
+    pub include_contents: bool,
+}
+
+/// Arguments for the restore command
+#[derive(Args, Debug)]
+pub struct RestoreArgs {
+    /// Archive written by the backup command
+    pub archive: PathBuf,
+
+    /// Write changed files; without it only the differences are shown
+    #[arg(long)]
+    pub apply: bool,
+
+    /// Directory the archived absolute paths are restored below
+    #[arg(long, default_value = "/")]
+    pub root: PathBuf,
+}
+
+/// Arguments for the scan command
+#[derive(Args, Debug)]
+pub struct ScanArgs {
+    /// Directory to scan (e.g. /etc/nginx/conf.d or a repository of vhosts)
+    pub dir: PathBuf,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value = "table")]
+    pub format: OutputFormat,
+
+    /// File extensions to read
+    #[arg(long = "ext", value_delimiter = ',', default_value = "conf")]
+    pub extensions: Vec<String>,
+
+    /// Only scan the directory itself, not its subdirectories
+    #[arg(long)]
+    pub no_recursive: bool,
+
+    /// Exit with status 1 when a file fails to parse
+    #[arg(long)]
+    pub strict: bool,
+}
+
+/// Arguments for the query command
+#[derive(Args, Debug)]
+pub struct QueryArgs {
+    /// Directive name (any directive if omitted)
+    pub name: Option<String>,
+
+    /// Require an argument matching this regular expression
+    #[arg(long)]
+    pub arg: Option<String>,
+
+    /// Require these enclosing blocks, outermost first (e.g. http,server)
+    #[arg(long, value_delimiter = ',')]
+    pub inside: Vec<String>,
+
+    /// Only search at or below this context path (e.g. `http/server[2]`)
+    #[arg(long)]
+    pub under: Option<String>,
+
+    /// Require a direct child directive with this name
+    #[arg(long)]
+    pub has_child: Vec<String>,
+
+    /// Exclude directives with a direct child with this name
+    #[arg(long)]
+    pub without_child: Vec<String>,
+
+    /// Output as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// Arguments for the migrate command
+#[cfg(feature = "migrate")]
+#[derive(Args, Debug)]
+pub struct MigrateArgs {
+    /// Target server
+    #[arg(long, value_enum)]
+    pub to: MigrateTarget,
+
+    /// Output file (stdout if not specified)
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// Exit with status 1 when something could not be converted
+    #[arg(long)]
+    pub strict: bool,
+}
+
+#[cfg(feature = "migrate")]
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum MigrateTarget {
+    Caddy,
+    Haproxy,
+    Unit,
+}
+
+/// Arguments for the network command
+#[cfg(feature = "network")]
+#[derive(Args, Debug)]
+pub struct NetworkArgs {
+    #[cfg(feature = "network-history")]
+    #[command(subcommand)]
+    pub command: Option<NetworkCommand>,
+
+    /// List available checks and exit
+    #[arg(long)]
+    pub list_checks: bool,
+
+    /// Run only these checks (comma-separated or repeated)
+    #[arg(long, value_delimiter = ',')]
+    pub only: Vec<String>,
+
+    /// Skip these checks (comma-separated or repeated)
+    #[arg(long, value_delimiter = ',')]
+    pub skip: Vec<String>,
+
+    /// Per-check timeout in seconds
+    #[arg(long, default_value = "5")]
+    pub timeout: u64,
+
+    /// Probe each listener with every server_name as the Host header
+    #[arg(long)]
+    pub host_routing: bool,
+
+    /// Response header identifying the server block that answered
+    #[arg(long)]
+    pub marker_header: Option<String>,
+
+    /// Send host routing probes to this address instead of the listen address
+    #[arg(long)]
+    pub target: Option<std::net::IpAddr>,
+
+    /// Override result severity: `CHECK[:TARGET]=LEVEL` (e.g. `dns:*.internal=info`)
+    #[arg(long, value_name = "RULE")]
+    pub severity: Vec<String>,
+
+    /// Exit non-zero when a problem at or above this level is found
+    #[arg(long, default_value = "error")]
+    pub fail_on: String,
+
+    /// Check every target of an inventory file ("NAME SOURCE" per line,
+    /// SOURCE a config file or a host name) instead of one configuration
+    #[arg(long, value_name = "FILE")]
+    pub inventory: Option<PathBuf>,
+
+    /// Maximum number of inventory targets checked at the same time
+    #[arg(long, default_value = "4", requires = "inventory")]
+    pub concurrency: usize,
+
+    /// Reuse recent DNS, SSL and port results from the on-disk cache
+    #[arg(long)]
+    pub cache: bool,
+
+    /// Cache file (implies --cache; default in the user cache directory)
+    #[arg(long, value_name = "FILE")]
+    pub cache_file: Option<PathBuf>,
+
+    /// Keep cached results this many seconds instead of the per-check
+    /// defaults (dns 300, ssl 3600, port 60)
+    #[arg(long, value_name = "SECS")]
+    pub cache_ttl: Option<u64>,
+
+    /// Resolve server names with these name servers (`IP[:PORT]`, comma-separated
+    /// or repeated) instead of the system resolver
+    #[arg(long, value_name = "ADDR", value_delimiter = ',')]
+    pub dns_server: Vec<String>,
+
+    /// Resolve server names with the name servers of the configuration's
+    /// `resolver` directive
+    #[arg(long)]
+    pub dns_from_config: bool,
+
+    /// Report successful results slower than a budget as degraded:
+    /// dns|tcp|tls=MILLIS or =off (defaults dns=100, tcp=50, tls=300)
+    #[arg(long, value_name = "NAME=MS")]
+    pub latency_budget: Vec<String>,
+
+    /// Connect port, upstream and SSL probes through this proxy
+    /// (`http://[USER:PASS@]HOST:PORT` or `socks5://[USER:PASS@]HOST:PORT`)
+    #[arg(long, value_name = "URL")]
+    pub proxy: Option<String>,
+
+    /// POST new failures and recoveries to this webhook (repeatable)
+    #[arg(long, value_name = "URL")]
+    pub webhook: Vec<String>,
+
+    /// Webhook payload format (generic, slack or teams)
+    #[arg(long, default_value = "generic", requires = "webhook")]
+    pub webhook_format: String,
+
+    /// Lowest severity that is alerted
+    #[arg(
+        long,
+        value_name = "LEVEL",
+        default_value = "error",
+        requires = "webhook"
+    )]
+    pub alert_on: String,
+
+    /// File remembering alerted failures between runs (default in the user
+    /// cache directory)
+    #[arg(long, value_name = "FILE", requires = "webhook")]
+    pub alert_state: Option<PathBuf>,
+
+    /// Append the results to the check history
+    #[cfg(feature = "network-history")]
+    #[arg(long)]
+    pub history: bool,
+
+    /// History file (implies --history; default in the user cache directory)
+    #[cfg(feature = "network-history")]
+    #[arg(long, value_name = "FILE")]
+    pub history_file: Option<PathBuf>,
+
+    /// Output format (table or json)
+    #[arg(short, long, value_enum, default_value = "table")]
+    pub format: OutputFormat,
+
+    /// Output file (stdout if not specified)
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+}
+
+#[cfg(feature = "network-history")]
+#[derive(Subcommand, Debug)]
+pub enum NetworkCommand {
+    /// Show status changes and latency trend of a target from the check history
+    History {
+        /// Target as checked (host, HOST:PORT or upstream address); a host
+        /// also matches its HOST:PORT targets
+        target: String,
+
+        /// History file (default in the user cache directory)
+        #[arg(long, value_name = "FILE")]
+        file: Option<PathBuf>,
+
+        /// Show at most this many recent status changes per check
+        #[arg(long, default_value = "20")]
+        last: usize,
+
+        /// Output format (table or json)
+        #[arg(short, long, value_enum, default_value = "table")]
+        format: OutputFormat,
+
+        /// Output file (stdout if not specified)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+/// Arguments for the daemon command
+#[cfg(feature = "network")]
+#[derive(Args, Debug)]
+pub struct DaemonArgs {
+    /// Seconds between check runs
+    #[arg(long, default_value = "60")]
+    pub interval: u64,
+
+    /// Address serving /metrics, /results and /healthz
+    #[arg(long, value_name = "ADDR", default_value = "127.0.0.1:9273")]
+    pub listen: std::net::SocketAddr,
+
+    /// Run only these checks (comma-separated or repeated)
+    #[arg(long, value_delimiter = ',')]
+    pub only: Vec<String>,
+
+    /// Skip these checks (comma-separated or repeated)
+    #[arg(long, value_delimiter = ',')]
+    pub skip: Vec<String>,
+
+    /// Per-check timeout in seconds
+    #[arg(long, default_value = "5")]
+    pub timeout: u64,
+
+    /// Probe each listener with every server_name as the Host header
+    #[arg(long)]
+    pub host_routing: bool,
+
+    /// POST new failures and recoveries to this webhook (repeatable)
+    #[arg(long, value_name = "URL")]
+    pub webhook: Vec<String>,
+
+    /// Webhook payload format (generic, slack or teams)
+    #[arg(long, default_value = "generic", requires = "webhook")]
+    pub webhook_format: String,
+
+    /// Lowest severity that is alerted
+    #[arg(
+        long,
+        value_name = "LEVEL",
+        default_value = "error",
+        requires = "webhook"
+    )]
+    pub alert_on: String,
+
+    /// File remembering alerted failures across restarts (default in the
+    /// user cache directory)
+    #[arg(long, value_name = "FILE", requires = "webhook")]
+    pub alert_state: Option<PathBuf>,
 }
 
 /// Arguments for the analyze command
@@ -224,6 +907,19 @@ pub enum AnalyzeTarget {
         #[arg(long)]
         check_certs: bool,
 
+        /// Read `nginx -V` output from this file instead of running nginx
+        #[arg(long, value_name = "FILE")]
+        build_info: Option<PathBuf>,
+
+        /// TLS sessions expected within ssl_session_timeout
+        /// (default: worker_processes × worker_connections)
+        #[arg(long, value_name = "N")]
+        expected_sessions: Option<u64>,
+
+        /// nginx instances serving the same names behind a load balancer
+        #[arg(long, value_name = "N", default_value = "1")]
+        instances: usize,
+
         /// Output format
         #[arg(short, long, value_enum, default_value = "table")]
         format: OutputFormat,
@@ -251,4 +947,278 @@ pub enum AnalyzeTarget {
         #[arg(short, long)]
         output: Option<PathBuf>,
     },
+
+    /// Compute the minimum nginx version the configuration needs
+    Version {
+        /// Compare against this version instead of the installed nginx
+        #[arg(long, value_name = "VERSION")]
+        nginx_version: Option<String>,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "table")]
+        format: OutputFormat,
+
+        /// Output file (stdout if not specified)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Audit expires/Cache-Control, etag and open_file_cache on static locations
+    Caching {
+        /// Show only locations with findings
+        #[arg(long)]
+        warnings_only: bool,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "table")]
+        format: OutputFormat,
+
+        /// Output file (stdout if not specified)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Find credentials embedded in directive arguments
+    Secrets {
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "table")]
+        format: OutputFormat,
+
+        /// Output file (stdout if not specified)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// List third-party modules (lua, njs, headers-more, ...) the configuration uses
+    Modules {
+        /// Read `nginx -V` output from this file instead of running nginx
+        #[arg(long, value_name = "FILE")]
+        build_info: Option<PathBuf>,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "table")]
+        format: OutputFormat,
+
+        /// Output file (stdout if not specified)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Check that locations using keepalive upstreams can reuse connections
+    Keepalive {
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "table")]
+        format: OutputFormat,
+
+        /// Output file (stdout if not specified)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Score worker, connection, sendfile, gzip and file cache tuning
+    Performance {
+        /// Print a Markdown report instead of the selected format
+        #[arg(long)]
+        markdown: bool,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "table")]
+        format: OutputFormat,
+
+        /// Output file (stdout if not specified)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Check log formats against what log parsers and dashboards expect
+    LogFormats {
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "table")]
+        format: OutputFormat,
+
+        /// Output file (stdout if not specified)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Check HTTP/3 (QUIC) listeners for a TCP fallback and Alt-Svc
+    Quic {
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "table")]
+        format: OutputFormat,
+
+        /// Output file (stdout if not specified)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Find locations whose own add_header drops inherited security headers
+    Headers {
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "table")]
+        format: OutputFormat,
+
+        /// Output file (stdout if not specified)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Find locations caching responses of upstreams with session affinity
+    Affinity {
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "table")]
+        format: OutputFormat,
+
+        /// Output file (stdout if not specified)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Show which servers block bots by user agent, and how
+    Bots {
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "table")]
+        format: OutputFormat,
+
+        /// Output file (stdout if not specified)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Check gRPC locations for HTTP/2, timeouts and backend TLS
+    Grpc {
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "table")]
+        format: OutputFormat,
+
+        /// Output file (stdout if not specified)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Map X-Accel-Redirect locations to the applications that trigger them
+    Offload {
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "table")]
+        format: OutputFormat,
+
+        /// Output file (stdout if not specified)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Map access log requests to the locations and upstreams that served
+    /// them and report their error rates
+    LogErrors {
+        /// Lines read from the end of each access log
+        #[arg(long, default_value = "10000")]
+        lines: usize,
+
+        /// Only count lines logged in the last N minutes
+        #[arg(long, value_name = "N")]
+        minutes: Option<u64>,
+
+        /// Requests a location needs before its error rate is reported
+        #[arg(long, default_value = "20")]
+        min_requests: usize,
+
+        /// Share of 5xx responses to report, in percent
+        #[arg(long, default_value = "5")]
+        error_rate: f64,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "table")]
+        format: OutputFormat,
+
+        /// Output file (stdout if not specified)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Show which servers and locations a list of test requests exercises
+    Coverage {
+        /// File with one request per line, as a URL or host/path; blank
+        /// lines and lines starting with # are skipped
+        #[arg(long, value_name = "FILE")]
+        requests: PathBuf,
+
+        /// Show only servers and locations no request reaches
+        #[arg(long)]
+        untested_only: bool,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "table")]
+        format: OutputFormat,
+
+        /// Output file (stdout if not specified)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Show which server names wildcard and exact certificates cover
+    Certificates {
+        /// Show only names that are not covered
+        #[arg(long)]
+        uncovered_only: bool,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "table")]
+        format: OutputFormat,
+
+        /// Output file (stdout if not specified)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Check GeoIP databases and the variables they define
+    Geoip {
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "table")]
+        format: OutputFormat,
+
+        /// Output file (stdout if not specified)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Check that PROXY protocol servers see the real client address
+    RealIp {
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "table")]
+        format: OutputFormat,
+
+        /// Output file (stdout if not specified)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Map servers and locations to their ModSecurity WAF settings
+    Waf {
+        /// Show only servers and locations that are not protected
+        #[arg(long)]
+        unprotected_only: bool,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "table")]
+        format: OutputFormat,
+
+        /// Output file (stdout if not specified)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Check that referenced certificates, includes and document roots exist
+    Paths {
+        /// Show only missing paths
+        #[arg(long)]
+        problems_only: bool,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "table")]
+        format: OutputFormat,
+
+        /// Output file (stdout if not specified)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
 }
@@ -1,11 +1,19 @@
 //! Analyze command implementation
 
 use crate::cli::args::{AnalyzeArgs, AnalyzeTarget, GlobalOpts, OutputFormat};
+use crate::cli::output::table;
 use crate::cli::utils;
 use anyhow::{Context, Result};
 use colored::Colorize;
+use nginx_discovery::analyze::{
+    BuildInfo, CipherProfile, CompiledModules, LogErrorOptions, NameCoverage, NginxVersion,
+    TlsAuditOptions, TlsLibrary, TlsReport, INTERMEDIATE_CIPHERS,
+};
+use nginx_discovery::lint;
+use nginx_discovery::route::SimulatedRequest;
 use nginx_discovery::NginxDiscovery;
 use std::fs;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 pub fn run(args: AnalyzeArgs, global: &GlobalOpts) -> Result<()> {
     utils::setup_colors(global.color.clone());
@@ -13,17 +21,31 @@ pub fn run(args: AnalyzeArgs, global: &GlobalOpts) -> Result<()> {
     // Load configuration
     let config_path = utils::find_config(global)?;
     let discovery =
-        NginxDiscovery::from_config_file(&config_path).context("Failed to parse configuration")?;
+        utils::load_config(global, &config_path).context("Failed to parse configuration")?;
 
     // Analyze based on target
     let (output, output_path) = match args.target {
         AnalyzeTarget::Ssl {
             warnings_only,
             check_certs,
+            build_info,
+            expected_sessions,
+            instances,
             format,
             output,
         } => {
-            let result = analyze_ssl(&discovery, &format, warnings_only, check_certs)?;
+            let options = TlsAuditOptions {
+                expected_sessions,
+                instances,
+            };
+            let result = analyze_ssl(
+                &discovery,
+                &format,
+                warnings_only,
+                check_certs,
+                build_info.as_deref(),
+                &options,
+            )?;
             (result, output)
         }
         AnalyzeTarget::Security {
@@ -35,6 +57,140 @@ pub fn run(args: AnalyzeArgs, global: &GlobalOpts) -> Result<()> {
             let result = analyze_security(&discovery, &format, &level, fix)?;
             (result, output)
         }
+        AnalyzeTarget::Version {
+            nginx_version,
+            format,
+            output,
+        } => {
+            let result = analyze_version(&discovery, &format, nginx_version.as_deref())?;
+            (result, output)
+        }
+        AnalyzeTarget::Caching {
+            warnings_only,
+            format,
+            output,
+        } => {
+            let result = analyze_caching(&discovery, &format, warnings_only)?;
+            (result, output)
+        }
+        AnalyzeTarget::Secrets { format, output } => {
+            let result = analyze_secrets(&discovery, &format)?;
+            (result, output)
+        }
+        AnalyzeTarget::Modules {
+            build_info,
+            format,
+            output,
+        } => {
+            let result = analyze_modules(&discovery, &format, build_info.as_deref())?;
+            (result, output)
+        }
+        AnalyzeTarget::Keepalive { format, output } => {
+            let result = analyze_keepalive(&discovery, &format)?;
+            (result, output)
+        }
+        AnalyzeTarget::Performance {
+            markdown,
+            format,
+            output,
+        } => {
+            let result = analyze_performance(&discovery, &format, markdown)?;
+            (result, output)
+        }
+        AnalyzeTarget::LogFormats { format, output } => {
+            let result = analyze_log_formats(&discovery, &format)?;
+            (result, output)
+        }
+        AnalyzeTarget::Quic { format, output } => {
+            let result = analyze_quic(&discovery, &format)?;
+            (result, output)
+        }
+        AnalyzeTarget::Headers { format, output } => {
+            let result = analyze_headers(&discovery, &format)?;
+            (result, output)
+        }
+        AnalyzeTarget::Affinity { format, output } => {
+            let result = analyze_affinity(&discovery, &format)?;
+            (result, output)
+        }
+        AnalyzeTarget::Bots { format, output } => {
+            let result = analyze_bots(&discovery, &format)?;
+            (result, output)
+        }
+        AnalyzeTarget::Grpc { format, output } => {
+            let result = analyze_grpc(&discovery, &format)?;
+            (result, output)
+        }
+        AnalyzeTarget::Offload { format, output } => {
+            let result = analyze_offload(&discovery, &format)?;
+            (result, output)
+        }
+        AnalyzeTarget::LogErrors {
+            lines,
+            minutes,
+            min_requests,
+            error_rate,
+            format,
+            output,
+        } => {
+            #[allow(clippy::cast_precision_loss)]
+            let since = minutes.map(|minutes| {
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default();
+                now.saturating_sub(Duration::from_secs(minutes * 60))
+                    .as_secs_f64()
+            });
+            let options = LogErrorOptions {
+                lines,
+                since,
+                min_requests,
+                error_rate: error_rate / 100.0,
+            };
+            let result = analyze_log_errors(&discovery, &options, &format)?;
+            (result, output)
+        }
+        AnalyzeTarget::Coverage {
+            requests,
+            untested_only,
+            format,
+            output,
+        } => {
+            let result = analyze_coverage(&discovery, &requests, &format, untested_only)?;
+            (result, output)
+        }
+        AnalyzeTarget::Certificates {
+            uncovered_only,
+            format,
+            output,
+        } => {
+            let result = analyze_certificates(&discovery, &format, uncovered_only)?;
+            (result, output)
+        }
+        AnalyzeTarget::Geoip { format, output } => {
+            let result = analyze_geoip(&discovery, &format)?;
+            (result, output)
+        }
+        AnalyzeTarget::RealIp { format, output } => {
+            let result = analyze_real_ip(&discovery, &format)?;
+            (result, output)
+        }
+        AnalyzeTarget::Waf {
+            unprotected_only,
+            format,
+            output,
+        } => {
+            let result = analyze_waf(&discovery, &format, unprotected_only)?;
+            (result, output)
+        }
+        AnalyzeTarget::Paths {
+            problems_only,
+            format,
+            output,
+        } => {
+            let result = analyze_paths(&discovery, &format, problems_only)?;
+            (result, output)
+        }
     };
 
     // Write output
@@ -72,6 +228,8 @@ fn analyze_ssl(
     format: &OutputFormat,
     warnings_only: bool,
     check_certs: bool,
+    build_info: Option<&std::path::Path>,
+    options: &TlsAuditOptions,
 ) -> Result<String> {
     let ssl_servers = discovery.ssl_servers();
 
@@ -79,14 +237,31 @@ fn analyze_ssl(
         return Ok("No SSL/TLS configuration found.".to_string());
     }
 
+    let tls_library = match build_info {
+        Some(path) => {
+            BuildInfo::from_build_output(
+                &fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read {}", path.display()))?,
+            )
+            .tls_library
+        }
+        None => nginx_discovery::system::nginx_build_info()
+            .ok()
+            .and_then(|info| info.tls_library),
+    };
+
     let mut issues = Vec::new();
 
+    // Check 1: Protocols, ciphers, DH parameters and session resumption
+    check_tls_profiles(
+        &discovery.tls_audit_with(options),
+        tls_library.as_ref(),
+        &mut issues,
+    );
+
     for server in &ssl_servers {
         let server_name = server.primary_name().unwrap_or("_").to_string();
 
-        // Check 1: SSL protocols
-        check_ssl_protocols(&server_name, &mut issues);
-
         // Check 2: HTTP/2 support
         check_http2_support(server, &server_name, &mut issues);
 
@@ -102,24 +277,77 @@ fn analyze_ssl(
         check_mixed_content(server, &server_name, &mut issues);
     }
 
+    // Check 6: Directives the TLS library does not support
+    if let Some(tls) = &tls_library {
+        issues.extend(tls.check(discovery.config()).into_iter().map(finding_issue));
+    }
+
     // Filter by severity
     if warnings_only {
         issues.retain(|i| i.severity != Severity::Info);
     }
 
     // Format output
-    format_ssl_analysis(&ssl_servers, &issues, format)
+    format_ssl_analysis(&ssl_servers, tls_library.as_ref(), &issues, format)
 }
 
-fn check_ssl_protocols(server_name: &str, issues: &mut Vec<SslIssue>) {
-    // This is a placeholder - in real implementation, we'd parse ssl_protocols directive
-    // For now, we provide general guidance
-    issues.push(SslIssue {
-        severity: Severity::Info,
-        server: server_name.to_string(),
-        issue: "SSL/TLS protocol configuration not explicitly checked".to_string(),
-        recommendation: "Ensure ssl_protocols directive uses TLSv1.2 and TLSv1.3 only".to_string(),
-    });
+fn check_tls_profiles(
+    report: &TlsReport,
+    tls_library: Option<&TlsLibrary>,
+    issues: &mut Vec<SslIssue>,
+) {
+    let has_tls13 = tls_library.map_or(true, TlsLibrary::supports_tls13);
+    for server in &report.servers {
+        let profile = server.profile();
+        let (severity, recommendation) = match profile {
+            CipherProfile::Modern => (Severity::Info, "No change needed".to_string()),
+            CipherProfile::Intermediate if has_tls13 => (
+                Severity::Info,
+                "If every client supports TLSv1.3, use ssl_protocols TLSv1.3 (modern profile)"
+                    .to_string(),
+            ),
+            CipherProfile::Intermediate => (
+                Severity::Info,
+                "Rebuild nginx against OpenSSL 1.1.1 or later to offer TLSv1.3".to_string(),
+            ),
+            CipherProfile::Legacy => (
+                Severity::Warning,
+                format!(
+                    "Use ssl_protocols {}; ssl_ciphers {};",
+                    if has_tls13 {
+                        "TLSv1.2 TLSv1.3"
+                    } else {
+                        "TLSv1.2"
+                    },
+                    INTERMEDIATE_CIPHERS.join(":")
+                ),
+            ),
+        };
+        issues.push(SslIssue {
+            severity,
+            server: server.server_name.clone(),
+            issue: format!(
+                "TLS configuration matches the {profile} profile ({})",
+                server.protocols.join(" ")
+            ),
+            recommendation,
+        });
+    }
+    issues.extend(report.findings.iter().cloned().map(finding_issue));
+}
+
+/// An issue for a finding, labelled with its line
+fn finding_issue(finding: lint::Finding) -> SslIssue {
+    SslIssue {
+        severity: match finding.severity {
+            lint::Severity::Error => Severity::Critical,
+            lint::Severity::Warning => Severity::Warning,
+            lint::Severity::Info => Severity::Info,
+        },
+        server: format!("line {}", finding.line().unwrap_or_default()),
+        issue: finding.message,
+        recommendation: finding.help.unwrap_or_default(),
+    }
 }
 
 fn check_http2_support(
@@ -181,6 +409,7 @@ fn check_mixed_content(
 
 fn format_ssl_analysis(
     servers: &[nginx_discovery::types::Server],
+    tls_library: Option<&TlsLibrary>,
     issues: &[SslIssue],
     format: &OutputFormat,
 ) -> Result<String> {
@@ -189,7 +418,14 @@ fn format_ssl_analysis(
             let mut output = String::new();
 
             output.push_str(&format!("{}\n\n", "=== SSL/TLS Analysis ===".bold()));
-            output.push_str(&format!("SSL-enabled servers: {}\n\n", servers.len()));
+            output.push_str(&format!("SSL-enabled servers: {}\n", servers.len()));
+            match tls_library {
+                Some(tls) => output.push_str(&format!("TLS library: {tls}\n\n")),
+                None => output.push_str(&format!(
+                    "{}\n\n",
+                    "nginx not found: TLS library not checked (use --build-info)".dimmed()
+                )),
+            }
 
             if issues.is_empty() {
                 output.push_str(&format!("{}\n", "✓ No issues found".green()));
@@ -249,6 +485,7 @@ fn format_ssl_analysis(
         OutputFormat::Json => {
             let data = serde_json::json!({
                 "ssl_servers_count": servers.len(),
+                "tls_library": tls_library.map(ToString::to_string),
                 "issues": issues.iter().map(|i| {
                     serde_json::json!({
                         "severity": format!("{:?}", i.severity),
@@ -328,10 +565,43 @@ fn analyze_security(
             }
         }
 
-        // Check 3: Server tokens
+        // Check 3: Admin paths open to every method without authentication
+        for location in &server.locations {
+            if is_admin_path(&location.path) && location.allows_all_methods() {
+                let issue = if location.limit_except.is_some() {
+                    format!(
+                        "limit_except on admin path '{}' restricts nothing",
+                        location.path
+                    )
+                } else {
+                    format!(
+                        "Admin path '{}' accepts all HTTP methods without authentication",
+                        location.path
+                    )
+                };
+                issues.push(SecurityIssue {
+                    severity: Severity::Warning,
+                    server: server_name.clone(),
+                    category: "Access Control".to_string(),
+                    issue,
+                    risk: "Anyone can send POST, PUT or DELETE requests to the admin interface"
+                        .to_string(),
+                    fix: "Add auth_basic/auth_request, or limit_except GET { allow <admin network>; deny all; }"
+                        .to_string(),
+                });
+            }
+        }
+
+        // Check 4: Server tokens
         check_server_tokens(&server_name, &mut issues);
     }
 
+    // Check 5: auth_request subrequest flow
+    check_auth_requests(discovery, &mut issues);
+
+    // Check 6: Password file hashes
+    check_password_files(discovery, &mut issues);
+
     // Filter by severity level
     let min_severity = match level.to_lowercase().as_str() {
         "critical" => Severity::Critical,
@@ -367,6 +637,77 @@ fn is_sensitive_path(path: &str) -> bool {
     sensitive.iter().any(|p| path.starts_with(p))
 }
 
+fn is_admin_path(path: &str) -> bool {
+    let admin = [
+        "/manage",
+        "/dashboard",
+        "/console",
+        "/actuator",
+        "/internal",
+    ];
+    path.contains("admin") || admin.iter().any(|p| path.starts_with(p))
+}
+
+fn check_auth_requests(discovery: &NginxDiscovery, issues: &mut Vec<SecurityIssue>) {
+    let report = discovery.auth_request_audit();
+    for finding in &report.findings {
+        let server = report
+            .requests
+            .iter()
+            .find(|r| {
+                Some(r.line) == finding.line()
+                    && finding
+                        .message
+                        .contains(&format!("server \"{}\"", r.server_name))
+            })
+            .map_or("_", |r| r.server_name.as_str());
+        let risk = match finding.rule.as_str() {
+            "auth_request_missing" | "auth_request_loop" => {
+                "Every authenticated request fails, or falls through to an unintended location"
+            }
+            "auth_request_internal" => {
+                "Clients can call the authentication endpoint directly and probe it"
+            }
+            _ => "Unauthenticated clients cannot reach the login or error page",
+        };
+        issues.push(SecurityIssue {
+            severity: match finding.severity {
+                lint::Severity::Error => Severity::Critical,
+                lint::Severity::Warning => Severity::Warning,
+                lint::Severity::Info => Severity::Info,
+            },
+            server: server.to_string(),
+            category: "Authentication".to_string(),
+            issue: finding.message.clone(),
+            risk: risk.to_string(),
+            fix: finding.help.clone().unwrap_or_default(),
+        });
+    }
+}
+
+fn check_password_files(discovery: &NginxDiscovery, issues: &mut Vec<SecurityIssue>) {
+    for finding in discovery.audit_htpasswd().findings {
+        let weak = finding.rule == "htpasswd_weak_hash";
+        issues.push(SecurityIssue {
+            severity: if weak {
+                Severity::Warning
+            } else {
+                Severity::Critical
+            },
+            server: "-".to_string(),
+            category: "Password Files".to_string(),
+            issue: finding.message,
+            risk: if weak {
+                "A leaked password file can be cracked offline"
+            } else {
+                "Nobody can log in to the locations protected by the file"
+            }
+            .to_string(),
+            fix: finding.help.unwrap_or_default(),
+        });
+    }
+}
+
 fn check_server_tokens(server_name: &str, issues: &mut Vec<SecurityIssue>) {
     // Placeholder - would need to check server_tokens directive
     issues.push(SecurityIssue {
@@ -475,3 +816,1396 @@ fn format_security_issue(output: &mut String, issue: &SecurityIssue, show_fix: b
         output.push_str(&format!("    Fix: {}\n", issue.fix.green()));
     }
 }
+
+fn analyze_caching(
+    discovery: &NginxDiscovery,
+    format: &OutputFormat,
+    warnings_only: bool,
+) -> Result<String> {
+    let mut report = discovery.caching_audit();
+    report.locations.retain(|l| l.is_static);
+    if warnings_only {
+        let flagged: Vec<usize> = report.findings.iter().filter_map(|f| f.line()).collect();
+        report.locations.retain(|l| flagged.contains(&l.line));
+    }
+
+    match format {
+        OutputFormat::Table => {
+            let mut output = String::new();
+            output.push_str(&format!("{}\n\n", "=== Static Asset Caching ===".bold()));
+
+            let locations: Vec<_> = report.locations.iter().collect();
+            output.push_str(&table::format_location_caching(&locations));
+            output.push('\n');
+
+            if report.findings.is_empty() {
+                output.push_str(&format!("\n{}\n", "✓ No caching issues found".green()));
+            } else {
+                output.push_str(&format!("\n{}\n", "WARNINGS:".yellow().bold()));
+                for finding in &report.findings {
+                    output.push_str(&format!(
+                        "\n{} line {}: {}\n",
+                        "⚠".yellow(),
+                        finding.line().unwrap_or_default(),
+                        finding.message
+                    ));
+                    if let Some(help) = &finding.help {
+                        output.push_str(&format!("  Fix: {}\n", help.dimmed()));
+                    }
+                }
+                output.push_str(&format!(
+                    "\n{}\n  {} static locations, {} warnings\n",
+                    "Summary:".bold(),
+                    report.locations.len(),
+                    report.findings.len()
+                ));
+            }
+
+            Ok(output)
+        }
+        OutputFormat::Json => serde_json::to_string_pretty(&report).context("Failed to serialize"),
+        OutputFormat::Yaml => serde_yaml::to_string(&report).context("Failed to serialize"),
+        OutputFormat::Csv => {
+            let locations: Vec<_> = report.locations.iter().collect();
+            Ok(table::format_location_caching_csv(&locations))
+        }
+    }
+}
+
+fn analyze_version(
+    discovery: &NginxDiscovery,
+    format: &OutputFormat,
+    nginx_version: Option<&str>,
+) -> Result<String> {
+    let required = discovery.required_version();
+    let installed = match nginx_version {
+        Some(version) => Some(
+            version
+                .parse::<NginxVersion>()
+                .map_err(anyhow::Error::msg)?,
+        ),
+        None => nginx_discovery::system::installed_version().ok(),
+    };
+
+    match format {
+        OutputFormat::Table => {
+            let mut output = String::new();
+            output.push_str(&format!("{}\n\n", "=== Required NGINX Version ===".bold()));
+
+            match required.minimum {
+                Some(minimum) => output.push_str(&format!(
+                    "Minimum version: {}\n",
+                    minimum.to_string().bold()
+                )),
+                None => output.push_str("Minimum version: any (nothing newer than 1.0 is used)\n"),
+            }
+            if let Some(installed) = installed {
+                output.push_str(&format!("Installed/target: {installed}\n"));
+            }
+
+            if !required.requirements.is_empty() {
+                output.push('\n');
+                output.push_str(&table::format_version_requirements(&required.requirements));
+                output.push('\n');
+            }
+
+            if let Some(installed) = installed {
+                let findings = required.check(installed);
+                if findings.is_empty() {
+                    output.push_str(&format!(
+                        "\n{}\n",
+                        format!("✓ nginx {installed} supports this configuration").green()
+                    ));
+                } else {
+                    output.push_str(&format!("\n{}\n", "WARNINGS:".yellow().bold()));
+                    for finding in &findings {
+                        output.push_str(&format!(
+                            "\n{} line {}: {}\n",
+                            "⚠".yellow(),
+                            finding.line().unwrap_or_default(),
+                            finding.message
+                        ));
+                    }
+                }
+            }
+
+            Ok(output)
+        }
+        OutputFormat::Json | OutputFormat::Yaml => {
+            let data = serde_json::json!({
+                "minimum": required.minimum.map(|v| v.to_string()),
+                "installed": installed.map(|v| v.to_string()),
+                "supported": installed.map(|v| required.is_satisfied_by(v)),
+                "requirements": required.requirements,
+            });
+            if matches!(format, OutputFormat::Json) {
+                serde_json::to_string_pretty(&data).context("Failed to serialize")
+            } else {
+                serde_yaml::to_string(&data).context("Failed to serialize")
+            }
+        }
+        OutputFormat::Csv => {
+            let mut output = String::from("Version,Feature,Line\n");
+            for r in &required.requirements {
+                output.push_str(&format!("{},{},{}\n", r.version, r.feature(), r.span.line));
+            }
+            Ok(output)
+        }
+    }
+}
+
+fn analyze_secrets(discovery: &NginxDiscovery, format: &OutputFormat) -> Result<String> {
+    let report = discovery.find_secrets();
+
+    match format {
+        OutputFormat::Table => {
+            let mut output = String::new();
+            output.push_str(&format!("{}\n\n", "=== Embedded Secrets ===".bold()));
+
+            if report.secrets.is_empty() {
+                output.push_str(&format!("{}\n", "✓ No embedded credentials found".green()));
+                return Ok(output);
+            }
+
+            output.push_str(&table::format_secrets(&report.secrets));
+            output.push('\n');
+            output.push_str(&format!("\n{}\n", "WARNINGS:".yellow().bold()));
+            for finding in &report.findings {
+                output.push_str(&format!(
+                    "\n{} line {}: {}\n",
+                    "⚠".yellow(),
+                    finding.line().unwrap_or_default(),
+                    finding.message
+                ));
+                if let Some(help) = &finding.help {
+                    output.push_str(&format!("  Fix: {}\n", help.dimmed()));
+                }
+            }
+
+            Ok(output)
+        }
+        OutputFormat::Json => serde_json::to_string_pretty(&report).context("Failed to serialize"),
+        OutputFormat::Yaml => serde_yaml::to_string(&report).context("Failed to serialize"),
+        OutputFormat::Csv => {
+            let mut output = String::from("Line,Kind,Directive,Value\n");
+            for s in &report.secrets {
+                output.push_str(&format!(
+                    "{},{},{},\"{}\"\n",
+                    s.span.line,
+                    s.kind,
+                    s.directive,
+                    s.masked.replace('"', "\"\"")
+                ));
+            }
+            Ok(output)
+        }
+    }
+}
+
+fn analyze_modules(
+    discovery: &NginxDiscovery,
+    format: &OutputFormat,
+    build_info: Option<&std::path::Path>,
+) -> Result<String> {
+    let inventory = discovery.module_inventory();
+    let compiled = match build_info {
+        Some(path) => Some(CompiledModules::from_build_output(
+            &fs::read_to_string(path)
+                .with_context(|| format!("Failed to read {}", path.display()))?,
+        )),
+        None => nginx_discovery::system::compiled_modules().ok(),
+    };
+    let findings = compiled
+        .as_ref()
+        .map(|c| inventory.check(c))
+        .unwrap_or_default();
+
+    match format {
+        OutputFormat::Table => {
+            let mut output = String::new();
+            output.push_str(&format!("{}\n\n", "=== Third-Party Modules ===".bold()));
+
+            if inventory.modules.is_empty() {
+                output.push_str(&format!(
+                    "{}\n",
+                    "✓ Only stock nginx modules are used".green()
+                ));
+                return Ok(output);
+            }
+
+            output.push_str(&table::format_modules(
+                &inventory.modules,
+                compiled.as_ref(),
+            ));
+            output.push('\n');
+
+            if compiled.is_none() {
+                output.push_str(&format!(
+                    "\n{}\n",
+                    "nginx not found: compiled modules not checked (use --build-info)".dimmed()
+                ));
+            } else if findings.is_empty() {
+                output.push_str(&format!(
+                    "\n{}\n",
+                    "✓ Every module is compiled in or loaded".green()
+                ));
+            } else {
+                output.push_str(&format!("\n{}\n", "ERRORS:".red().bold()));
+                for finding in &findings {
+                    output.push_str(&format!(
+                        "\n{} line {}: {}\n",
+                        "✗".red(),
+                        finding.line().unwrap_or_default(),
+                        finding.message
+                    ));
+                    if let Some(help) = &finding.help {
+                        output.push_str(&format!("  Fix: {}\n", help.dimmed()));
+                    }
+                }
+            }
+
+            Ok(output)
+        }
+        OutputFormat::Json | OutputFormat::Yaml => {
+            let data = serde_json::json!({
+                "modules": inventory.modules,
+                "loaded": inventory.loaded,
+                "compiled": compiled.map(|c| c.arguments),
+                "findings": findings,
+            });
+            if matches!(format, OutputFormat::Json) {
+                serde_json::to_string_pretty(&data).context("Failed to serialize")
+            } else {
+                serde_yaml::to_string(&data).context("Failed to serialize")
+            }
+        }
+        OutputFormat::Csv => {
+            let mut output = String::from("Module,Uses,Line,Directives,Loaded\n");
+            for m in &inventory.modules {
+                output.push_str(&format!(
+                    "{},{},{},\"{}\",{}\n",
+                    m.module,
+                    m.uses,
+                    m.span.line,
+                    m.directives.join(" "),
+                    m.loaded
+                ));
+            }
+            Ok(output)
+        }
+    }
+}
+
+fn analyze_keepalive(discovery: &NginxDiscovery, format: &OutputFormat) -> Result<String> {
+    let report = discovery.keepalive_audit();
+
+    match format {
+        OutputFormat::Table => {
+            let mut output = String::new();
+            output.push_str(&format!("{}\n\n", "=== Upstream Keepalive ===".bold()));
+
+            if report.keepalive_upstreams().next().is_none() {
+                output.push_str(&format!(
+                    "{}\n",
+                    "No upstream uses keepalive: every request opens a new backend connection"
+                        .yellow()
+                ));
+                return Ok(output);
+            }
+
+            output.push_str(&table::format_upstream_uses(&report.uses));
+            output.push('\n');
+
+            if report.findings.is_empty() {
+                output.push_str(&format!(
+                    "\n{}\n",
+                    "✓ Every location can reuse keepalive connections".green()
+                ));
+            } else {
+                output.push_str(&format!("\n{}\n", "WARNINGS:".yellow().bold()));
+                for finding in &report.findings {
+                    output.push_str(&format!(
+                        "\n{} line {}: {}\n",
+                        "⚠".yellow(),
+                        finding.line().unwrap_or_default(),
+                        finding.message
+                    ));
+                    if let Some(help) = &finding.help {
+                        output.push_str(&format!("  Fix: {}\n", help.dimmed()));
+                    }
+                }
+            }
+
+            Ok(output)
+        }
+        OutputFormat::Json => serde_json::to_string_pretty(&report).context("Failed to serialize"),
+        OutputFormat::Yaml => serde_yaml::to_string(&report).context("Failed to serialize"),
+        OutputFormat::Csv => {
+            let mut output =
+                String::from("Line,Server,Location,Upstream,HTTP Version,Connection,Reuse\n");
+            for u in &report.uses {
+                output.push_str(&format!(
+                    "{},{},\"{}\",{},{},\"{}\",{}\n",
+                    u.line,
+                    u.server_name,
+                    u.location.replace('"', "\"\""),
+                    u.upstream,
+                    u.http_version.as_deref().unwrap_or(""),
+                    u.connection_header
+                        .as_deref()
+                        .unwrap_or("")
+                        .replace('"', "\"\""),
+                    u.reuses_connections
+                ));
+            }
+            Ok(output)
+        }
+    }
+}
+
+fn analyze_performance(
+    discovery: &NginxDiscovery,
+    format: &OutputFormat,
+    markdown: bool,
+) -> Result<String> {
+    let report = discovery.performance();
+    if markdown {
+        return Ok(report.to_markdown());
+    }
+
+    match format {
+        OutputFormat::Table => {
+            let mut output = String::new();
+            output.push_str(&format!("{}\n\n", "=== Performance Tuning ===".bold()));
+            output.push_str(&table::format_tuning_checks(&report.checks));
+            output.push('\n');
+
+            let score = format!("Score: {}/100 (grade {})", report.score, report.grade());
+            output.push_str(&format!(
+                "\n{}\n",
+                match report.grade() {
+                    'A' => score.green().bold(),
+                    'B' | 'C' => score.yellow().bold(),
+                    _ => score.red().bold(),
+                }
+            ));
+
+            if !report.findings.is_empty() {
+                output.push_str(&format!("\n{}\n", "RECOMMENDATIONS:".yellow().bold()));
+                for finding in &report.findings {
+                    let icon = match finding.severity {
+                        lint::Severity::Warning => "⚠".yellow(),
+                        _ => "ℹ".blue(),
+                    };
+                    output.push_str(&format!("\n{} {}\n", icon, finding.message));
+                    if let Some(help) = &finding.help {
+                        output.push_str(&format!("  Fix: {}\n", help.dimmed()));
+                    }
+                }
+            }
+
+            Ok(output)
+        }
+        OutputFormat::Json => serde_json::to_string_pretty(&report).context("Failed to serialize"),
+        OutputFormat::Yaml => serde_yaml::to_string(&report).context("Failed to serialize"),
+        OutputFormat::Csv => {
+            let mut output = String::from("Setting,Value,Default,Recommended,Weight,OK\n");
+            for c in &report.checks {
+                output.push_str(&format!(
+                    "{},\"{}\",{},\"{}\",{},{}\n",
+                    c.setting,
+                    c.effective.replace('"', "\"\""),
+                    c.configured.is_none(),
+                    c.recommended,
+                    c.weight,
+                    c.ok
+                ));
+            }
+            Ok(output)
+        }
+    }
+}
+
+fn analyze_log_formats(discovery: &NginxDiscovery, format: &OutputFormat) -> Result<String> {
+    let report = discovery.log_format_compat();
+
+    match format {
+        OutputFormat::Table => {
+            let mut output = String::new();
+            output.push_str(&format!("{}\n\n", "=== Log Formats ===".bold()));
+
+            if report.formats.is_empty() {
+                output.push_str(&format!(
+                    "{}\n",
+                    "No log_format defined: access logs use combined".yellow()
+                ));
+            } else {
+                output.push_str(&table::format_log_compat(&report.formats));
+                output.push('\n');
+            }
+
+            if report.findings.is_empty() {
+                output.push_str(&format!(
+                    "\n{}\n",
+                    "✓ Every log format in use is readable by common log parsers".green()
+                ));
+            } else {
+                output.push_str(&format!("\n{}\n", "WARNINGS:".yellow().bold()));
+                for finding in &report.findings {
+                    output.push_str(&format!(
+                        "\n{} line {}: {}\n",
+                        "⚠".yellow(),
+                        finding.line().unwrap_or_default(),
+                        finding.message
+                    ));
+                    if let Some(help) = &finding.help {
+                        output.push_str(&format!("  Fix: {}\n", help.dimmed()));
+                    }
+                }
+            }
+
+            Ok(output)
+        }
+        OutputFormat::Json => serde_json::to_string_pretty(&report).context("Failed to serialize"),
+        OutputFormat::Yaml => serde_yaml::to_string(&report).context("Failed to serialize"),
+        OutputFormat::Csv => {
+            let mut output = String::from("Line,Name,Kind,Escape,Uses,Compatible\n");
+            for f in &report.formats {
+                output.push_str(&format!(
+                    "{},{},{},{},{},{}\n",
+                    f.line,
+                    f.name,
+                    if f.is_json() { "json" } else { "line" },
+                    f.escape.as_deref().unwrap_or(""),
+                    f.uses,
+                    f.compatible.join(" ")
+                ));
+            }
+            Ok(output)
+        }
+    }
+}
+
+fn analyze_quic(discovery: &NginxDiscovery, format: &OutputFormat) -> Result<String> {
+    let report = discovery.quic_audit();
+
+    match format {
+        OutputFormat::Table => {
+            let mut output = String::new();
+            output.push_str(&format!("{}\n\n", "=== HTTP/3 (QUIC) ===".bold()));
+
+            if report.servers.is_empty() {
+                output.push_str(&format!(
+                    "{}\n",
+                    "No server listens with quic: HTTP/3 is not served".yellow()
+                ));
+                return Ok(output);
+            }
+
+            output.push_str(&table::format_quic_servers(&report.servers));
+            output.push('\n');
+
+            if report.findings.is_empty() {
+                output.push_str(&format!(
+                    "\n{}\n",
+                    "✓ Every QUIC listener has a TCP fallback and is advertised".green()
+                ));
+            } else {
+                output.push_str(&format!("\n{}\n", "ISSUES:".yellow().bold()));
+                for finding in &report.findings {
+                    let icon = match finding.severity {
+                        lint::Severity::Error => "✗".red(),
+                        lint::Severity::Warning => "⚠".yellow(),
+                        lint::Severity::Info => "ℹ".blue(),
+                    };
+                    output.push_str(&format!(
+                        "\n{} line {}: {}\n",
+                        icon,
+                        finding.line().unwrap_or_default(),
+                        finding.message
+                    ));
+                    if let Some(help) = &finding.help {
+                        output.push_str(&format!("  Fix: {}\n", help.dimmed()));
+                    }
+                }
+            }
+
+            Ok(output)
+        }
+        OutputFormat::Json => serde_json::to_string_pretty(&report).context("Failed to serialize"),
+        OutputFormat::Yaml => serde_yaml::to_string(&report).context("Failed to serialize"),
+        OutputFormat::Csv => {
+            let mut output =
+                String::from("Line,Server,QUIC Ports,TCP Ports,HTTP3,Early Data,Retry,Alt-Svc\n");
+            for s in &report.servers {
+                let ports = |listeners: &[nginx_discovery::types::ListenDirective]| {
+                    listeners
+                        .iter()
+                        .map(|l| l.port.to_string())
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                };
+                output.push_str(&format!(
+                    "{},{},{},{},{},{},{},\"{}\"\n",
+                    s.line,
+                    s.server_name,
+                    ports(&s.quic_listeners),
+                    ports(&s.tcp_listeners),
+                    s.http3,
+                    s.early_data,
+                    s.quic_retry,
+                    s.alt_svc.as_deref().unwrap_or("").replace('"', "\"\"")
+                ));
+            }
+            Ok(output)
+        }
+    }
+}
+
+fn analyze_headers(discovery: &NginxDiscovery, format: &OutputFormat) -> Result<String> {
+    let report = discovery.header_inheritance();
+
+    match format {
+        OutputFormat::Table => {
+            let mut output = String::new();
+            output.push_str(&format!("{}\n\n", "=== add_header Inheritance ===".bold()));
+
+            if report.overrides.is_empty() {
+                output.push_str(&format!(
+                    "{}\n",
+                    "✓ No block drops the headers it would inherit".green()
+                ));
+                return Ok(output);
+            }
+
+            output.push_str(&table::format_header_overrides(&report.overrides));
+            output.push('\n');
+
+            if report.findings.is_empty() {
+                output.push_str(&format!(
+                    "\n{}\n",
+                    "✓ No security header is dropped".green()
+                ));
+            } else {
+                output.push_str(&format!("\n{}\n", "WARNINGS:".yellow().bold()));
+                for finding in &report.findings {
+                    output.push_str(&format!(
+                        "\n{} line {}: {}\n",
+                        "⚠".yellow(),
+                        finding.line().unwrap_or_default(),
+                        finding.message
+                    ));
+                    if let Some(help) = &finding.help {
+                        output.push_str(&format!("  Fix: {}\n", help.dimmed()));
+                    }
+                }
+            }
+
+            Ok(output)
+        }
+        OutputFormat::Json => serde_json::to_string_pretty(&report).context("Failed to serialize"),
+        OutputFormat::Yaml => serde_yaml::to_string(&report).context("Failed to serialize"),
+        OutputFormat::Csv => {
+            let mut output = String::from("Line,Server,Block,Inherited From,Own,Dropped\n");
+            for o in &report.overrides {
+                output.push_str(&format!(
+                    "{},{},\"{}\",{},\"{}\",\"{}\"\n",
+                    o.line,
+                    o.server_name,
+                    o.path.to_string().replace('"', "\"\""),
+                    o.inherited_from.to_string().replace('"', "\"\""),
+                    o.own.join(" "),
+                    o.dropped.join(" ")
+                ));
+            }
+            Ok(output)
+        }
+    }
+}
+
+fn analyze_affinity(discovery: &NginxDiscovery, format: &OutputFormat) -> Result<String> {
+    let report = discovery.affinity_audit();
+
+    match format {
+        OutputFormat::Table => {
+            let mut output = String::new();
+            output.push_str(&format!("{}\n\n", "=== Session Affinity ===".bold()));
+
+            if report.upstreams.is_empty() {
+                output.push_str(&format!(
+                    "{}\n",
+                    "No upstream uses session affinity".dimmed()
+                ));
+                return Ok(output);
+            }
+
+            output.push_str(&table::format_affinity_uses(&report.uses));
+            output.push('\n');
+
+            if report.findings.is_empty() {
+                output.push_str(&format!(
+                    "\n{}\n",
+                    "✓ No location caches responses of a pinned upstream".green()
+                ));
+            } else {
+                output.push_str(&format!("\n{}\n", "WARNINGS:".yellow().bold()));
+                for finding in &report.findings {
+                    output.push_str(&format!(
+                        "\n{} line {}: {}\n",
+                        "⚠".yellow(),
+                        finding.line().unwrap_or_default(),
+                        finding.message
+                    ));
+                    if let Some(help) = &finding.help {
+                        output.push_str(&format!("  Fix: {}\n", help.dimmed()));
+                    }
+                }
+            }
+
+            Ok(output)
+        }
+        OutputFormat::Json => serde_json::to_string_pretty(&report).context("Failed to serialize"),
+        OutputFormat::Yaml => serde_yaml::to_string(&report).context("Failed to serialize"),
+        OutputFormat::Csv => {
+            let mut output = String::from(
+                "Line,Server,Location,Upstream,Affinity,Cache Zone,Cache Key,Shared\n",
+            );
+            for u in &report.uses {
+                output.push_str(&format!(
+                    "{},{},\"{}\",{},\"{}\",{},\"{}\",{}\n",
+                    u.line,
+                    u.server_name,
+                    u.location.replace('"', "\"\""),
+                    u.upstream,
+                    u.affinity,
+                    u.cache_zone.as_deref().unwrap_or(""),
+                    u.cache_key.as_deref().unwrap_or(""),
+                    u.shares_cache()
+                ));
+            }
+            Ok(output)
+        }
+    }
+}
+
+fn analyze_bots(discovery: &NginxDiscovery, format: &OutputFormat) -> Result<String> {
+    let report = discovery.bot_blocking();
+
+    match format {
+        OutputFormat::Table => {
+            let mut output = String::new();
+            output.push_str(&format!("{}\n\n", "=== Bot Blocking ===".bold()));
+
+            if report.servers.is_empty() {
+                output.push_str(&format!("{}\n", "No servers found".dimmed()));
+                return Ok(output);
+            }
+
+            output.push_str(&table::format_bot_filters(&report.servers));
+            output.push('\n');
+
+            let filtered = report.servers.iter().filter(|s| s.is_filtered()).count();
+            output.push_str(&format!(
+                "\n{} of {} servers filter bots by user agent\n",
+                filtered,
+                report.servers.len()
+            ));
+
+            if !report.findings.is_empty() {
+                output.push_str(&format!("\n{}\n", "FINDINGS:".yellow().bold()));
+                for finding in &report.findings {
+                    let marker = match finding.severity {
+                        lint::Severity::Error => "✗".red(),
+                        lint::Severity::Warning => "⚠".yellow(),
+                        lint::Severity::Info => "ℹ".blue(),
+                    };
+                    output.push_str(&format!(
+                        "\n{} line {}: {}\n",
+                        marker,
+                        finding.line().unwrap_or_default(),
+                        finding.message
+                    ));
+                    if let Some(help) = &finding.help {
+                        output.push_str(&format!("  Fix: {}\n", help.dimmed()));
+                    }
+                }
+            }
+
+            Ok(output)
+        }
+        OutputFormat::Json => serde_json::to_string_pretty(&report).context("Failed to serialize"),
+        OutputFormat::Yaml => serde_yaml::to_string(&report).context("Failed to serialize"),
+        OutputFormat::Csv => {
+            // One row per check; servers without any get an empty one
+            let mut output =
+                String::from("Server,Server Line,Mechanism,Line,Location,Map,Patterns,Action\n");
+            for s in &report.servers {
+                if s.rules.is_empty() {
+                    output.push_str(&format!("{},{},,,,,,\n", s.server_name, s.line));
+                }
+                for r in &s.rules {
+                    output.push_str(&format!(
+                        "{},{},{},{},\"{}\",{},\"{}\",{}\n",
+                        s.server_name,
+                        s.line,
+                        r.mechanism,
+                        r.line,
+                        r.location
+                            .as_deref()
+                            .unwrap_or_default()
+                            .replace('"', "\"\""),
+                        r.map.as_deref().unwrap_or_default(),
+                        r.patterns.join(" ").replace('"', "\"\""),
+                        r.action
+                    ));
+                }
+            }
+            Ok(output)
+        }
+    }
+}
+
+fn analyze_grpc(discovery: &NginxDiscovery, format: &OutputFormat) -> Result<String> {
+    let report = discovery.grpc_audit();
+
+    match format {
+        OutputFormat::Table => {
+            let mut output = String::new();
+            output.push_str(&format!("{}\n\n", "=== gRPC Proxying ===".bold()));
+
+            if report.locations.is_empty() {
+                output.push_str(&format!("{}\n", "No location uses grpc_pass".dimmed()));
+                return Ok(output);
+            }
+
+            output.push_str(&table::format_grpc_locations(&report.locations));
+            output.push('\n');
+
+            if report.findings.is_empty() {
+                output.push_str(&format!(
+                    "\n{}\n",
+                    "✓ gRPC locations are configured consistently".green()
+                ));
+            } else {
+                output.push_str(&format!("\n{}\n", "FINDINGS:".yellow().bold()));
+                for finding in &report.findings {
+                    let marker = match finding.severity {
+                        lint::Severity::Error => "✗".red(),
+                        lint::Severity::Warning => "⚠".yellow(),
+                        lint::Severity::Info => "ℹ".blue(),
+                    };
+                    output.push_str(&format!(
+                        "\n{} line {}: {}\n",
+                        marker,
+                        finding.line().unwrap_or_default(),
+                        finding.message
+                    ));
+                    if let Some(help) = &finding.help {
+                        output.push_str(&format!("  Fix: {}\n", help.dimmed()));
+                    }
+                }
+            }
+
+            Ok(output)
+        }
+        OutputFormat::Json => serde_json::to_string_pretty(&report).context("Failed to serialize"),
+        OutputFormat::Yaml => serde_yaml::to_string(&report).context("Failed to serialize"),
+        OutputFormat::Csv => {
+            let mut output =
+                String::from("Line,Server,Location,Target,TLS,Backend Ports,HTTP2,Read Timeout\n");
+            for l in &report.locations {
+                output.push_str(&format!(
+                    "{},{},\"{}\",{},{},\"{}\",{},{}\n",
+                    l.line,
+                    l.server_name,
+                    l.location.replace('"', "\"\""),
+                    l.target,
+                    l.tls,
+                    l.backend_ports
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                    l.http2,
+                    l.read_timeout.as_deref().unwrap_or("")
+                ));
+            }
+            Ok(output)
+        }
+    }
+}
+
+fn analyze_offload(discovery: &NginxDiscovery, format: &OutputFormat) -> Result<String> {
+    let report = discovery.xaccel_offload();
+
+    match format {
+        OutputFormat::Table => {
+            let mut output = String::new();
+            output.push_str(&format!(
+                "{}\n\n",
+                "=== X-Accel-Redirect Offload ===".bold()
+            ));
+
+            if report.targets.is_empty() {
+                output.push_str(&format!(
+                    "{}\n",
+                    "No internal location serves files".dimmed()
+                ));
+                return Ok(output);
+            }
+
+            output.push_str(&table::format_offload_targets(&report.targets));
+            output.push('\n');
+
+            if !report.findings.is_empty() {
+                output.push_str(&format!("\n{}\n", "NOTES:".blue().bold()));
+                for finding in &report.findings {
+                    output.push_str(&format!(
+                        "\n{} line {}: {}\n",
+                        "ℹ".blue(),
+                        finding.line().unwrap_or_default(),
+                        finding.message
+                    ));
+                }
+            }
+
+            Ok(output)
+        }
+        OutputFormat::Json => serde_json::to_string_pretty(&report).context("Failed to serialize"),
+        OutputFormat::Yaml => serde_yaml::to_string(&report).context("Failed to serialize"),
+        OutputFormat::Csv => {
+            // One row per target and application that can trigger it
+            let mut output = String::from(
+                "Server,Location,Line,Storage,Alias,Source Location,Source Line,Backend\n",
+            );
+            for t in &report.targets {
+                let prefix = format!(
+                    "{},\"{}\",{},\"{}\",{}",
+                    t.server_name,
+                    t.location.replace('"', "\"\""),
+                    t.line,
+                    t.storage.display(),
+                    t.alias
+                );
+                if t.triggered_by.is_empty() {
+                    output.push_str(&format!("{prefix},,,\n"));
+                }
+                for s in &t.triggered_by {
+                    output.push_str(&format!(
+                        "{prefix},\"{}\",{},{}\n",
+                        s.location.replace('"', "\"\""),
+                        s.line,
+                        s.backend
+                    ));
+                }
+            }
+            Ok(output)
+        }
+    }
+}
+
+fn analyze_log_errors(
+    discovery: &NginxDiscovery,
+    options: &LogErrorOptions,
+    format: &OutputFormat,
+) -> Result<String> {
+    let report = discovery.log_error_correlation(options);
+
+    match format {
+        OutputFormat::Table => {
+            let mut output = String::new();
+            output.push_str(&format!("{}\n\n", "=== Access Log Errors ===".bold()));
+
+            if report.locations.is_empty() {
+                output.push_str(&format!(
+                    "{}\n",
+                    "No access log line could be mapped to a location".dimmed()
+                ));
+                return Ok(output);
+            }
+
+            output.push_str(&table::format_location_errors(&report.locations));
+            output.push('\n');
+            if !report.upstreams.is_empty() {
+                output.push_str(&format!("\n{}\n", "Upstreams:".bold()));
+                for u in &report.upstreams {
+                    output.push_str(&format!(
+                        "  {}: {} requests, {} 4xx, {} 5xx\n",
+                        u.name, u.requests, u.client_errors, u.server_errors
+                    ));
+                }
+            }
+            output.push_str(&format!(
+                "\n{} lines mapped, {} not routable\n",
+                report.routed_lines, report.unrouted_lines
+            ));
+
+            if report.findings.is_empty() {
+                output.push_str(&format!(
+                    "\n{}\n",
+                    "✓ No location has a high 5xx rate".green()
+                ));
+            } else {
+                output.push_str(&format!("\n{}\n", "FINDINGS:".yellow().bold()));
+                for finding in &report.findings {
+                    output.push_str(&format!(
+                        "\n{} line {}: {}\n",
+                        "⚠".yellow(),
+                        finding.line().unwrap_or_default(),
+                        finding.message
+                    ));
+                    if let Some(help) = &finding.help {
+                        output.push_str(&format!("  Fix: {}\n", help.dimmed()));
+                    }
+                }
+            }
+
+            Ok(output)
+        }
+        OutputFormat::Json => serde_json::to_string_pretty(&report).context("Failed to serialize"),
+        OutputFormat::Yaml => serde_yaml::to_string(&report).context("Failed to serialize"),
+        OutputFormat::Csv => {
+            let mut output = String::from("Server,Location,Line,Upstream,Requests,4xx,5xx\n");
+            for l in &report.locations {
+                output.push_str(&format!(
+                    "{},\"{}\",{},{},{},{},{}\n",
+                    l.server,
+                    l.location.replace('"', "\"\""),
+                    l.line,
+                    l.upstream.as_deref().unwrap_or(""),
+                    l.requests,
+                    l.client_errors,
+                    l.server_errors
+                ));
+            }
+            Ok(output)
+        }
+    }
+}
+
+fn analyze_coverage(
+    discovery: &NginxDiscovery,
+    path: &std::path::Path,
+    format: &OutputFormat,
+    untested_only: bool,
+) -> Result<String> {
+    let text =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let requests = text
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(number, line)| {
+            line.parse::<SimulatedRequest>()
+                .with_context(|| format!("{}:{number}: invalid request", path.display()))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut report = discovery.coverage(&requests);
+    let ratio = report.ratio();
+    if untested_only {
+        report.servers.retain(|s| s.requests == 0);
+        report
+            .locations
+            .retain(|l| l.requests == 0 && !l.is_named());
+    }
+
+    match format {
+        OutputFormat::Table => {
+            let mut output = String::new();
+            output.push_str(&format!("{}\n\n", "=== Routing Coverage ===".bold()));
+            output.push_str(&format!(
+                "Requests: {}   Coverage: {:.0}%\n\n",
+                report.requests,
+                ratio * 100.0
+            ));
+            output.push_str(&table::format_coverage(&report.servers, &report.locations));
+            output.push('\n');
+
+            if !report.unrouted.is_empty() {
+                output.push_str(&format!("\n{}\n", "NOT ROUTED:".yellow().bold()));
+                for request in &report.unrouted {
+                    output.push_str(&format!(
+                        "  {} {}: {}\n",
+                        "⚠".yellow(),
+                        request.url,
+                        request.reason
+                    ));
+                }
+            }
+            if report.findings.is_empty() {
+                output.push_str(&format!(
+                    "\n{}\n",
+                    "✓ Every server and location is reached by a request".green()
+                ));
+            } else {
+                output.push_str(&format!(
+                    "\n{} {} servers or locations are not tested\n",
+                    "ℹ".blue(),
+                    report.findings.len()
+                ));
+            }
+
+            Ok(output)
+        }
+        OutputFormat::Json => serde_json::to_string_pretty(&report).context("Failed to serialize"),
+        OutputFormat::Yaml => serde_yaml::to_string(&report).context("Failed to serialize"),
+        OutputFormat::Csv => {
+            let mut output = String::from("Line,Server,Location,Requests\n");
+            for s in &report.servers {
+                output.push_str(&format!("{},{},,{}\n", s.line, s.name, s.requests));
+            }
+            for l in &report.locations {
+                output.push_str(&format!(
+                    "{},{},\"{}\",{}\n",
+                    l.line, l.server, l.pattern, l.requests
+                ));
+            }
+            Ok(output)
+        }
+    }
+}
+
+fn analyze_certificates(
+    discovery: &NginxDiscovery,
+    format: &OutputFormat,
+    uncovered_only: bool,
+) -> Result<String> {
+    let mut report = discovery.certificate_coverage();
+    if uncovered_only {
+        report
+            .names
+            .retain(|n| n.coverage == NameCoverage::Uncovered);
+    }
+
+    match format {
+        OutputFormat::Table => {
+            let mut output = String::new();
+            output.push_str(&format!("{}\n\n", "=== Certificate Coverage ===".bold()));
+
+            if report.certificates.is_empty() {
+                output.push_str(&format!(
+                    "{}\n",
+                    "No TLS server has a certificate file".dimmed()
+                ));
+                return Ok(output);
+            }
+
+            let wildcard = report
+                .certificates
+                .iter()
+                .filter(|c| c.is_wildcard())
+                .count();
+            output.push_str(&format!(
+                "Certificates: {} ({} wildcard)\n\n",
+                report.certificates.len(),
+                wildcard
+            ));
+            output.push_str(&table::format_certificate_coverage(&report.names));
+            output.push('\n');
+
+            if report.findings.is_empty() {
+                output.push_str(&format!(
+                    "\n{}\n",
+                    "✓ Every server name is covered by its certificates".green()
+                ));
+            } else {
+                output.push_str(&format!("\n{}\n", "FINDINGS:".yellow().bold()));
+                for finding in &report.findings {
+                    output.push_str(&format!(
+                        "\n{} line {}: {}\n",
+                        "⚠".yellow(),
+                        finding.line().unwrap_or_default(),
+                        finding.message
+                    ));
+                    if let Some(help) = &finding.help {
+                        output.push_str(&format!("  Fix: {}\n", help.dimmed()));
+                    }
+                }
+            }
+
+            Ok(output)
+        }
+        OutputFormat::Json => serde_json::to_string_pretty(&report).context("Failed to serialize"),
+        OutputFormat::Yaml => serde_yaml::to_string(&report).context("Failed to serialize"),
+        OutputFormat::Csv => {
+            let mut output = String::from("Line,Server Name,Coverage,Certificates\n");
+            for n in &report.names {
+                output.push_str(&format!(
+                    "{},{},{},\"{}\"\n",
+                    n.line,
+                    n.name,
+                    n.coverage,
+                    n.certificates.join(" ")
+                ));
+            }
+            Ok(output)
+        }
+    }
+}
+
+fn analyze_geoip(discovery: &NginxDiscovery, format: &OutputFormat) -> Result<String> {
+    let report = discovery.geoip_audit();
+
+    match format {
+        OutputFormat::Table => {
+            let mut output = String::new();
+            output.push_str(&format!("{}\n\n", "=== GeoIP Databases ===".bold()));
+
+            if report.databases.is_empty() {
+                output.push_str(&format!("{}\n", "No GeoIP database is loaded".dimmed()));
+                return Ok(output);
+            }
+
+            output.push_str(&table::format_geoip_databases(&report.databases));
+            output.push('\n');
+
+            if report.findings.is_empty() {
+                output.push_str(&format!(
+                    "\n{}\n",
+                    "✓ GeoIP databases exist and their variables are used".green()
+                ));
+            } else {
+                output.push_str(&format!("\n{}\n", "FINDINGS:".yellow().bold()));
+                for finding in &report.findings {
+                    let marker = match finding.severity {
+                        lint::Severity::Error => "✗".red(),
+                        lint::Severity::Warning => "⚠".yellow(),
+                        lint::Severity::Info => "ℹ".blue(),
+                    };
+                    output.push_str(&format!(
+                        "\n{} line {}: {}\n",
+                        marker,
+                        finding.line().unwrap_or_default(),
+                        finding.message
+                    ));
+                    if let Some(help) = &finding.help {
+                        output.push_str(&format!("  Fix: {}\n", help.dimmed()));
+                    }
+                }
+            }
+
+            Ok(output)
+        }
+        OutputFormat::Json => serde_json::to_string_pretty(&report).context("Failed to serialize"),
+        OutputFormat::Yaml => serde_yaml::to_string(&report).context("Failed to serialize"),
+        OutputFormat::Csv => {
+            // One row per database and variable it defines
+            let mut output = String::from("Line,Directive,Database,Exists,Variable,Lookup,Used\n");
+            for d in &report.databases {
+                let exists = d.exists.map_or_else(String::new, |e| e.to_string());
+                for v in &d.variables {
+                    output.push_str(&format!(
+                        "{},{},\"{}\",{},{},\"{}\",{}\n",
+                        v.line, d.directive, d.path, exists, v.name, v.lookup, v.used
+                    ));
+                }
+            }
+            Ok(output)
+        }
+    }
+}
+
+fn analyze_real_ip(discovery: &NginxDiscovery, format: &OutputFormat) -> Result<String> {
+    let report = discovery.real_ip_audit();
+
+    match format {
+        OutputFormat::Table => {
+            let mut output = String::new();
+            output.push_str(&format!("{}\n\n", "=== Real Client IP ===".bold()));
+
+            if report.servers.is_empty() {
+                output.push_str(&format!(
+                    "{}\n",
+                    "No server uses proxy_protocol or the realip module".yellow()
+                ));
+                return Ok(output);
+            }
+
+            output.push_str(&table::format_real_ip_servers(&report.servers));
+            output.push('\n');
+
+            if report.findings.is_empty() {
+                output.push_str(&format!(
+                    "\n{}\n",
+                    "✓ Every PROXY protocol server sees the real client address".green()
+                ));
+            } else {
+                output.push_str(&format!("\n{}\n", "WARNINGS:".yellow().bold()));
+                for finding in &report.findings {
+                    output.push_str(&format!(
+                        "\n{} line {}: {}\n",
+                        "⚠".yellow(),
+                        finding.line().unwrap_or_default(),
+                        finding.message
+                    ));
+                    if let Some(help) = &finding.help {
+                        output.push_str(&format!("  Fix: {}\n", help.dimmed()));
+                    }
+                }
+            }
+
+            Ok(output)
+        }
+        OutputFormat::Json => serde_json::to_string_pretty(&report).context("Failed to serialize"),
+        OutputFormat::Yaml => serde_yaml::to_string(&report).context("Failed to serialize"),
+        OutputFormat::Csv => {
+            let mut output = String::from(
+                "Line,Server,Context,Proxy Protocol Ports,Trusted,Real IP Header,Client IP\n",
+            );
+            for s in &report.servers {
+                output.push_str(&format!(
+                    "{},{},{},{},\"{}\",{},{}\n",
+                    s.line,
+                    s.server_name,
+                    if s.stream { "stream" } else { "http" },
+                    s.proxy_protocol_ports
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                    s.trusted.join(" "),
+                    s.real_ip_header.as_deref().unwrap_or(""),
+                    s.resolves_client_ip()
+                ));
+            }
+            Ok(output)
+        }
+    }
+}
+
+fn analyze_waf(
+    discovery: &NginxDiscovery,
+    format: &OutputFormat,
+    unprotected_only: bool,
+) -> Result<String> {
+    let mut report = discovery.waf_coverage();
+    if unprotected_only {
+        report.contexts = report.unprotected().cloned().collect();
+    }
+
+    match format {
+        OutputFormat::Table => {
+            let mut output = String::new();
+            output.push_str(&format!(
+                "{}\n\n",
+                "=== ModSecurity WAF Coverage ===".bold()
+            ));
+
+            if !report.is_used() && report.rules_files.is_empty() && !unprotected_only {
+                output.push_str(&format!(
+                    "{}\n",
+                    "ModSecurity is not configured: no server or location is protected".yellow()
+                ));
+                return Ok(output);
+            }
+
+            let contexts: Vec<_> = report.contexts.iter().collect();
+            output.push_str(&table::format_waf_contexts(&contexts));
+            output.push('\n');
+            output.push_str(&format!(
+                "\nProtected: {}  Unprotected: {}\n",
+                report.protected().count().to_string().green(),
+                report.unprotected().count().to_string().yellow()
+            ));
+
+            if !report.findings.is_empty() {
+                output.push_str(&format!("\n{}\n", "ISSUES:".yellow().bold()));
+                for finding in &report.findings {
+                    let icon = match finding.severity {
+                        lint::Severity::Error => "✗".red(),
+                        lint::Severity::Warning => "⚠".yellow(),
+                        lint::Severity::Info => "ℹ".blue(),
+                    };
+                    output.push_str(&format!(
+                        "\n{} line {}: {}\n",
+                        icon,
+                        finding.line().unwrap_or_default(),
+                        finding.message
+                    ));
+                    if let Some(help) = &finding.help {
+                        output.push_str(&format!("  Fix: {}\n", help.dimmed()));
+                    }
+                }
+            }
+
+            Ok(output)
+        }
+        OutputFormat::Json => serde_json::to_string_pretty(&report).context("Failed to serialize"),
+        OutputFormat::Yaml => serde_yaml::to_string(&report).context("Failed to serialize"),
+        OutputFormat::Csv => {
+            let mut output = String::from("Server,Location,Line,Enabled,Rules Files,Protected\n");
+            for c in &report.contexts {
+                output.push_str(&format!(
+                    "{},\"{}\",{},{},\"{}\",{}\n",
+                    c.server_name,
+                    c.location.as_deref().unwrap_or("").replace('"', "\"\""),
+                    c.line,
+                    c.enabled,
+                    c.rules_files.join(" "),
+                    c.is_protected()
+                ));
+            }
+            Ok(output)
+        }
+    }
+}
+
+fn analyze_paths(
+    discovery: &NginxDiscovery,
+    format: &OutputFormat,
+    problems_only: bool,
+) -> Result<String> {
+    let mut report = discovery.check_paths();
+    if problems_only {
+        report.paths = report.problems().cloned().collect();
+    }
+
+    match format {
+        OutputFormat::Table => {
+            let mut output = String::new();
+            output.push_str(&format!("{}\n\n", "=== Referenced Paths ===".bold()));
+
+            let paths: Vec<_> = report.paths.iter().collect();
+            output.push_str(&table::format_paths(&paths));
+            output.push('\n');
+
+            if report.findings.is_empty() {
+                output.push_str(&format!("\n{}\n", "✓ All referenced paths exist".green()));
+            } else {
+                output.push_str(&format!("\n{}\n", "ISSUES:".yellow().bold()));
+                for finding in &report.findings {
+                    let icon = match finding.severity {
+                        lint::Severity::Error => "✗".red(),
+                        lint::Severity::Warning => "⚠".yellow(),
+                        lint::Severity::Info => "ℹ".blue(),
+                    };
+                    output.push_str(&format!(
+                        "\n{icon} line {}: {}\n",
+                        finding.line().unwrap_or_default(),
+                        finding.message
+                    ));
+                }
+            }
+
+            Ok(output)
+        }
+        OutputFormat::Json => serde_json::to_string_pretty(&report).context("Failed to serialize"),
+        OutputFormat::Yaml => serde_yaml::to_string(&report).context("Failed to serialize"),
+        OutputFormat::Csv => {
+            let mut output = String::from("Line,Directive,Path,Resolved,Status\n");
+            for p in &report.paths {
+                output.push_str(&format!(
+                    "{},{},{},{},{:?}\n",
+                    p.span.line,
+                    p.directive,
+                    p.path,
+                    p.resolved.display(),
+                    p.status
+                ));
+            }
+            Ok(output)
+        }
+    }
+}
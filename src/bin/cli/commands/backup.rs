@@ -0,0 +1,33 @@
+//! Backup command implementation
+
+use crate::cli::args::{BackupArgs, GlobalOpts};
+use crate::cli::utils;
+use anyhow::{Context, Result};
+use colored::Colorize;
+use nginx_discovery::system::{self, BackupOptions};
+
+pub fn run(args: &BackupArgs, global: &GlobalOpts) -> Result<()> {
+    utils::setup_colors(global.color.clone());
+
+    let options = BackupOptions {
+        include_contents: args.include_contents,
+    };
+    let backup = match &args.dump {
+        Some(path) => {
+            let dump = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            system::backup_from_dump(&dump, &args.dest, &options)
+        }
+        None => system::backup_config(&args.dest, &options),
+    }
+    .context("Backup failed")?;
+
+    println!("{} {}", "✓".green(), backup.path.display());
+    println!("  {} configuration file(s)", backup.config_files.len());
+    println!(
+        "  {} referenced file(s), {} stored",
+        backup.referenced.len(),
+        backup.copied.len()
+    );
+    Ok(())
+}
@@ -0,0 +1,129 @@
+//! CI command implementation
+
+use crate::cli::args::{CiArgs, CiFormat, GlobalOpts};
+use crate::cli::output::annotations;
+use crate::cli::utils;
+use anyhow::{bail, Context, Result};
+use colored::Colorize;
+use nginx_discovery::ci::{self, CiOptions, CiReport};
+use nginx_discovery::lint::rules::{CertificateFiles, DocumentRoots};
+use nginx_discovery::lint::{Linter, Policy, Severity};
+use std::path::Path;
+
+pub fn run(args: CiArgs, global: &GlobalOpts) -> Result<()> {
+    utils::setup_colors(global.color.clone());
+
+    let linter = Policy::ALL.into_iter().fold(
+        Linter::with_default_rules()
+            .with_rule(CertificateFiles::new())
+            .with_rule(DocumentRoots::new()),
+        Linter::with_policy,
+    );
+
+    if args.list_rules {
+        for rule in linter.iter() {
+            println!("{:<28} {}", rule.id().bold(), rule.description());
+        }
+        return Ok(());
+    }
+
+    for id in &args.skip {
+        if !linter.contains(id) {
+            bail!(
+                "Unknown rule '{}' (available: {})",
+                id,
+                linter.names().join(", ")
+            );
+        }
+    }
+
+    let policies = args
+        .policy
+        .iter()
+        .map(|name| name.parse::<Policy>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(anyhow::Error::msg)?;
+    let fail_on: Severity = args.fail_on.parse().map_err(anyhow::Error::msg)?;
+    let path = match args.file {
+        Some(path) => path,
+        None => utils::find_config(global)?,
+    };
+
+    let mut options = CiOptions::default()
+        .with_check_certs(args.check_certs)
+        .with_check_roots(args.check_roots)
+        .with_fail_on(fail_on)
+        .with_skip_rules(args.skip)
+        .with_policies(policies);
+    if let Some(version) = &args.nginx_version {
+        options = options.with_nginx_version(version.parse().map_err(anyhow::Error::msg)?);
+    }
+    let mut report = ci::validate(&path, &options);
+
+    // Review tools expect paths relative to the checked out repository
+    if let Some(workspace) = std::env::var_os("GITHUB_WORKSPACE") {
+        annotations::relativize(&mut report.findings, Path::new(&workspace));
+    }
+
+    match args.format {
+        CiFormat::Text => print_text(&report, global.quiet),
+        CiFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&report).context("Failed to serialize to JSON")?
+        ),
+        CiFormat::Github => {
+            if !report.findings.is_empty() {
+                println!("{}", annotations::format_github(&report.findings));
+            }
+        }
+        CiFormat::Rdjson => println!(
+            "{}",
+            annotations::format_rdjson(&report.findings)
+                .context("Failed to serialize to rdjson")?
+        ),
+    }
+
+    if !report.passed {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+fn print_text(report: &CiReport, quiet: bool) {
+    for finding in &report.findings {
+        let line = finding.to_string();
+        let line = match finding.severity {
+            Severity::Error => line.red(),
+            Severity::Warning => line.yellow(),
+            Severity::Info => line.normal(),
+        };
+        println!("{}", line);
+
+        if let Some(help) = &finding.help {
+            println!("  {}", help.dimmed());
+        }
+        if let Some(reference) = &finding.reference {
+            println!("  {}", reference.dimmed());
+        }
+        if let Some(url) = &finding.doc_url {
+            println!("  {}", format!("see {url}").dimmed());
+        }
+    }
+
+    if quiet {
+        return;
+    }
+
+    let verdict = if report.passed {
+        "PASSED".green().bold()
+    } else {
+        "FAILED".red().bold()
+    };
+    println!(
+        "\n{}: {} errors, {} warnings",
+        verdict,
+        report.errors(),
+        report.warnings()
+    );
+}
@@ -0,0 +1,572 @@
+//! Daemon command implementation
+//!
+//! Runs the selected network checks on an interval and serves the latest
+//! results over HTTP: `/metrics` in the Prometheus text format, `/results`
+//! as JSON and `/healthz`. The configuration and the files it includes are
+//! re-parsed when one of them changes; a configuration that no longer
+//! parses keeps the previous one in use.
+
+use crate::cli::args::{DaemonArgs, GlobalOpts};
+use crate::cli::commands::network::{alerter, select_checks};
+use crate::cli::utils;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use colored::Colorize;
+use nginx_discovery::includes::IncludeGraph;
+use nginx_discovery::network::{
+    prometheus_metrics, CheckRegistry, NetworkCheckOptions, NetworkCheckResult, NetworkCheckSummary,
+};
+use nginx_discovery::NginxDiscovery;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant, SystemTime};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Largest request head read from a client
+const MAX_REQUEST: usize = 8192;
+
+/// Latest results and run statistics shared with the HTTP server
+#[derive(Default)]
+struct DaemonState {
+    results: Vec<NetworkCheckResult>,
+    last_run: Option<DateTime<Utc>>,
+    run_duration: Duration,
+    runs: u64,
+    reloads: u64,
+    config_errors: u64,
+    config_error: Option<String>,
+    check_error: Option<String>,
+}
+
+impl DaemonState {
+    /// Why the daemon is not healthy: a broken configuration or a failed
+    /// check run
+    fn error(&self) -> Option<&str> {
+        self.config_error.as_deref().or(self.check_error.as_deref())
+    }
+}
+
+type SharedState = Arc<Mutex<DaemonState>>;
+
+/// Parsed configuration with the modification times it was parsed at
+type Loaded = (Vec<(PathBuf, Option<SystemTime>)>, NginxDiscovery);
+
+fn lock(state: &SharedState) -> std::sync::MutexGuard<'_, DaemonState> {
+    state.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+pub fn run(args: &DaemonArgs, global: &GlobalOpts) -> Result<()> {
+    utils::setup_colors(global.color.clone());
+
+    let registry = select_checks(CheckRegistry::with_builtin_checks(), &args.only, &args.skip)?;
+    let options = NetworkCheckOptions {
+        check_host_routing: args.host_routing || !args.only.is_empty(),
+        timeout: Duration::from_secs(args.timeout),
+        alerts: alerter(
+            &args.webhook,
+            &args.webhook_format,
+            &args.alert_on,
+            args.alert_state.clone(),
+        )?,
+        ..Default::default()
+    };
+    let config_path = utils::find_config(global)?;
+
+    let runtime = tokio::runtime::Runtime::new().context("Failed to start async runtime")?;
+    runtime.block_on(async {
+        let listener = TcpListener::bind(args.listen)
+            .await
+            .with_context(|| format!("Failed to listen on {}", args.listen))?;
+        if !global.quiet {
+            eprintln!(
+                "{} Checking {} every {}s, serving http://{}/metrics",
+                "▶".green(),
+                config_path.display(),
+                args.interval,
+                args.listen
+            );
+        }
+
+        let state = SharedState::default();
+        tokio::spawn(serve(listener, Arc::clone(&state)));
+
+        let mut interval = tokio::time::interval(Duration::from_secs(args.interval.max(1)));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        let mut loaded: Option<Loaded> = None;
+        loop {
+            interval.tick().await;
+            loaded = reload(&config_path, loaded, &state, global);
+            if let Some((_, discovery)) = &loaded {
+                check(discovery, &registry, &options, &state, global).await;
+            }
+        }
+    })
+}
+
+/// Re-parse the configuration if it or an included file changed
+fn reload(
+    config_path: &Path,
+    loaded: Option<Loaded>,
+    state: &SharedState,
+    global: &GlobalOpts,
+) -> Option<Loaded> {
+    let fingerprint = fingerprint(config_path);
+    if let Some((previous, _)) = &loaded {
+        if *previous == fingerprint {
+            return loaded;
+        }
+    }
+
+    match utils::load_config(global, config_path) {
+        Ok(discovery) => {
+            let mut state = lock(state);
+            if loaded.is_some() {
+                state.reloads += 1;
+                if !global.quiet {
+                    eprintln!("{} Reloaded {}", "↻".cyan(), config_path.display());
+                }
+            }
+            state.config_error = None;
+            Some((fingerprint, discovery))
+        }
+        Err(e) => {
+            let message = format!("Failed to parse {}: {e}", config_path.display());
+            if !global.quiet {
+                eprintln!("{} {}", "⚠".yellow(), message);
+            }
+            let mut state = lock(state);
+            state.config_errors += 1;
+            state.config_error = Some(message);
+            // Keep checking the last good configuration, but remember the
+            // broken one so the error is reported once per change
+            loaded.map(|(_, discovery)| (fingerprint, discovery))
+        }
+    }
+}
+
+/// Modification times of the configuration and every file it includes
+fn fingerprint(config_path: &Path) -> Vec<(PathBuf, Option<SystemTime>)> {
+    let files = IncludeGraph::build(config_path).map_or_else(
+        |_| vec![config_path.to_path_buf()],
+        |graph| graph.files.into_iter().map(|f| f.path).collect(),
+    );
+    files
+        .into_iter()
+        .map(|path| {
+            let modified = fs::metadata(&path).and_then(|m| m.modified()).ok();
+            (path, modified)
+        })
+        .collect()
+}
+
+async fn check(
+    discovery: &NginxDiscovery,
+    registry: &CheckRegistry,
+    options: &NetworkCheckOptions,
+    state: &SharedState,
+    global: &GlobalOpts,
+) {
+    let started = Instant::now();
+    let outcome = registry.run(discovery.config(), options).await;
+    let mut state = lock(state);
+    state.runs += 1;
+    state.last_run = Some(Utc::now());
+    state.run_duration = started.elapsed();
+    match outcome {
+        Ok(results) => {
+            if global.verbose {
+                eprintln!(
+                    "{} {}",
+                    Utc::now().format("%H:%M:%S"),
+                    NetworkCheckSummary::from(results.as_slice())
+                );
+            }
+            state.results = results;
+            state.check_error = None;
+        }
+        Err(e) => {
+            if !global.quiet {
+                eprintln!("{} Network checks failed: {}", "✗".red(), e);
+            }
+            state.check_error = Some(e.to_string());
+        }
+    }
+}
+
+async fn serve(listener: TcpListener, state: SharedState) {
+    loop {
+        let Ok((stream, _)) = listener.accept().await else {
+            continue;
+        };
+        tokio::spawn(respond(stream, Arc::clone(&state)));
+    }
+}
+
+async fn respond(mut stream: TcpStream, state: SharedState) {
+    let mut request = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") && request.len() < MAX_REQUEST {
+        match tokio::time::timeout(Duration::from_secs(5), stream.read(&mut buf)).await {
+            Ok(Ok(n)) if n > 0 => request.extend_from_slice(&buf[..n]),
+            _ => return,
+        }
+    }
+
+    let head = String::from_utf8_lossy(&request);
+    let mut parts = head.split_whitespace();
+    let (method, target) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    let path = target.split(['?', '#']).next().unwrap_or("");
+    let (status, content_type, body) = if method == "GET" {
+        route(path, &lock(&state))
+    } else {
+        (
+            "405 Method Not Allowed",
+            "text/plain",
+            "Method not allowed\n".to_string(),
+        )
+    };
+
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    let _ = stream.write_all(response.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
+
+fn route(path: &str, state: &DaemonState) -> (&'static str, &'static str, String) {
+    match path {
+        "/metrics" => ("200 OK", "text/plain; version=0.0.4", daemon_metrics(state)),
+        "/results" => {
+            let data = serde_json::json!({
+                "last_run": state.last_run,
+                "error": state.error(),
+                "results": state.results,
+                "summary": NetworkCheckSummary::from(state.results.as_slice()),
+            });
+            (
+                "200 OK",
+                "application/json",
+                serde_json::to_string_pretty(&data).unwrap_or_default(),
+            )
+        }
+        "/healthz" => match (state.error(), state.runs) {
+            (Some(error), _) => (
+                "503 Service Unavailable",
+                "text/plain",
+                format!("{error}\n"),
+            ),
+            (None, 0) => (
+                "503 Service Unavailable",
+                "text/plain",
+                "No checks run yet\n".to_string(),
+            ),
+            (None, _) => ("200 OK", "text/plain", "ok\n".to_string()),
+        },
+        _ => ("404 Not Found", "text/plain", "Not found\n".to_string()),
+    }
+}
+
+/// Check metrics followed by the daemon's own
+fn daemon_metrics(state: &DaemonState) -> String {
+    let mut out = prometheus_metrics(&state.results);
+    let metrics = [
+        (
+            "daemon_runs_total",
+            "counter",
+            "Check runs since the daemon started",
+            state.runs.to_string(),
+        ),
+        (
+            "daemon_config_reloads_total",
+            "counter",
+            "Configuration reloads after a change",
+            state.reloads.to_string(),
+        ),
+        (
+            "daemon_config_errors_total",
+            "counter",
+            "Configuration changes that failed to parse",
+            state.config_errors.to_string(),
+        ),
+        (
+            "daemon_last_run_timestamp_seconds",
+            "gauge",
+            "Unix time the last check run finished",
+            state.last_run.map_or(0, |t| t.timestamp()).to_string(),
+        ),
+        (
+            "daemon_run_duration_seconds",
+            "gauge",
+            "Time the last check run took",
+            state.run_duration.as_secs_f64().to_string(),
+        ),
+    ];
+    for (name, kind, help, value) in metrics {
+        let _ = writeln!(out, "# HELP nginx_discovery_{name} {help}");
+        let _ = writeln!(out, "# TYPE nginx_discovery_{name} {kind}");
+        let _ = writeln!(out, "nginx_discovery_{name} {value}");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::args::ColorChoice;
+    use futures::future::BoxFuture;
+    use nginx_discovery::ast::Config;
+    use nginx_discovery::network::{Check, CheckSeverity, HealthStatus};
+    use nginx_discovery::parser::SourceEncoding;
+
+    fn global() -> GlobalOpts {
+        GlobalOpts {
+            config: None,
+            verbose: false,
+            quiet: true,
+            color: ColorChoice::Never,
+            encoding: SourceEncoding::Utf8,
+        }
+    }
+
+    fn server_names(loaded: Option<&Loaded>) -> Vec<String> {
+        loaded.map_or_else(Vec::new, |(_, discovery)| {
+            discovery
+                .servers()
+                .iter()
+                .flat_map(|s| s.server_names.clone())
+                .collect()
+        })
+    }
+
+    #[test]
+    fn test_reload_on_change() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nginx.conf");
+        let site = dir.path().join("site.conf");
+        fs::write(&path, "http { server { server_name a.example.com; } }").unwrap();
+        let state = SharedState::default();
+
+        let loaded = reload(&path, None, &state, &global());
+        assert_eq!(server_names(loaded.as_ref()), ["a.example.com"]);
+
+        // Unchanged: not parsed again
+        let loaded = reload(&path, loaded, &state, &global());
+        assert_eq!(lock(&state).reloads, 0);
+
+        // A newly included file is a change
+        fs::write(&site, "").unwrap();
+        fs::write(
+            &path,
+            format!(
+                "http {{ include {}; server {{ server_name b.example.com; }} }}",
+                site.display()
+            ),
+        )
+        .unwrap();
+        let loaded = reload(&path, loaded, &state, &global());
+        assert_eq!(lock(&state).reloads, 1);
+        assert_eq!(server_names(loaded.as_ref()), ["b.example.com"]);
+
+        // So is a change to an included file alone
+        let (mut fingerprint, discovery) = loaded.unwrap();
+        let included = fingerprint
+            .iter_mut()
+            .find(|(file, _)| file.ends_with("site.conf"))
+            .unwrap();
+        included.1 = None;
+        let loaded = reload(&path, Some((fingerprint, discovery)), &state, &global());
+        assert_eq!(lock(&state).reloads, 2);
+
+        // A broken configuration keeps the last good one and is reported
+        // once per change
+        fs::write(&path, "http { server {").unwrap();
+        let loaded = reload(&path, loaded, &state, &global());
+        let loaded = reload(&path, loaded, &state, &global());
+        assert_eq!(server_names(loaded.as_ref()), ["b.example.com"]);
+        {
+            let state = lock(&state);
+            assert_eq!((state.reloads, state.config_errors), (2, 1));
+            assert!(state.error().unwrap().starts_with("Failed to parse"));
+            assert_eq!(route("/healthz", &state).0, "503 Service Unavailable");
+        }
+
+        // Fixing it reloads and clears the error
+        fs::write(&path, "http { server { server_name c.example.com; } }").unwrap();
+        let loaded = reload(&path, loaded, &state, &global());
+        assert_eq!(server_names(loaded.as_ref()), ["c.example.com"]);
+        let state = lock(&state);
+        assert_eq!((state.reloads, state.config_errors), (3, 1));
+        assert_eq!(state.error(), None);
+    }
+
+    #[test]
+    fn test_broken_configuration_at_start() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nginx.conf");
+        fs::write(&path, "events {").unwrap();
+        let state = SharedState::default();
+
+        assert!(reload(&path, None, &state, &global()).is_none());
+        assert!(reload(&dir.path().join("missing.conf"), None, &state, &global()).is_none());
+        assert_eq!(lock(&state).config_errors, 2);
+        assert_eq!(lock(&state).reloads, 0);
+    }
+
+    /// Check reporting one target down
+    struct Down;
+
+    impl Check for Down {
+        fn name(&self) -> &'static str {
+            "port"
+        }
+
+        fn description(&self) -> &'static str {
+            "Always down"
+        }
+
+        fn run<'a>(
+            &'a self,
+            _config: &'a Config,
+            _options: &'a NetworkCheckOptions,
+        ) -> BoxFuture<'a, nginx_discovery::Result<Vec<NetworkCheckResult>>> {
+            Box::pin(async {
+                Ok(vec![NetworkCheckResult {
+                    check_type: "port".to_string(),
+                    target: "10.0.0.1:80".to_string(),
+                    status: HealthStatus::Unhealthy,
+                    message: "Connection refused".to_string(),
+                    severity: CheckSeverity::Error,
+                    details: None,
+                    latency: None,
+                    cached: None,
+                }])
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_check_sends_alerts() {
+        // Webhook answering 204 and counting the requests it gets
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let hook = tokio::spawn(async move {
+            let mut bodies = Vec::new();
+            while let Ok(Ok((mut stream, _))) =
+                tokio::time::timeout(Duration::from_secs(1), listener.accept()).await
+            {
+                let mut request = Vec::new();
+                let mut buf = [0u8; 4096];
+                loop {
+                    let n = stream.read(&mut buf).await.unwrap();
+                    request.extend_from_slice(&buf[..n]);
+                    let text = String::from_utf8_lossy(&request);
+                    let Some((head, body)) = text.split_once("\r\n\r\n") else {
+                        continue;
+                    };
+                    let length = head
+                        .lines()
+                        .find_map(|l| {
+                            l.to_ascii_lowercase()
+                                .strip_prefix("content-length: ")
+                                .and_then(|l| l.trim().parse::<usize>().ok())
+                        })
+                        .unwrap_or(0);
+                    if n == 0 || body.len() >= length {
+                        break;
+                    }
+                }
+                stream
+                    .write_all(b"HTTP/1.1 204 No Content\r\ncontent-length: 0\r\n\r\n")
+                    .await
+                    .unwrap();
+                bodies.push(String::from_utf8(request).unwrap());
+            }
+            bodies
+        });
+
+        let dir = tempfile::tempdir().unwrap();
+        let discovery = NginxDiscovery::from_config_text("server { listen 80; }").unwrap();
+        let registry = CheckRegistry::new().with_check(Down);
+        let options = NetworkCheckOptions {
+            alerts: alerter(
+                &[url],
+                "generic",
+                "error",
+                Some(dir.path().join("alerts.json")),
+            )
+            .unwrap(),
+            ..Default::default()
+        };
+        let state = SharedState::default();
+
+        // The failure is sent once, not on every run
+        check(&discovery, &registry, &options, &state, &global()).await;
+        check(&discovery, &registry, &options, &state, &global()).await;
+        let bodies = hook.await.unwrap();
+        assert_eq!(bodies.len(), 1);
+        assert!(bodies[0].contains("\"title\":\"nginx-discovery: 1 new failure\""));
+        {
+            let state = lock(&state);
+            assert_eq!(state.runs, 2);
+            assert_eq!(state.results.len(), 1);
+            assert_eq!(state.error(), None);
+        }
+
+        // An undeliverable alert is recorded as a result; the daemon keeps
+        // serving the run
+        let unreachable = NetworkCheckOptions {
+            alerts: alerter(
+                &["http://127.0.0.1:1/hook".to_string()],
+                "slack",
+                "warning",
+                Some(dir.path().join("other.json")),
+            )
+            .unwrap(),
+            ..Default::default()
+        };
+        check(&discovery, &registry, &unreachable, &state, &global()).await;
+        let state = lock(&state);
+        let alert = state.results.last().unwrap();
+        assert_eq!(
+            (alert.check_type.as_str(), alert.status),
+            ("alert", HealthStatus::Error)
+        );
+        assert!(alert.message.contains("Webhook http://127.0.0.1:1/hook"));
+        assert_eq!(state.error(), None);
+        assert!(route("/metrics", &state)
+            .2
+            .contains("nginx_discovery_check_results{status=\"error\"} 1\n"));
+        assert!(route("/metrics", &state)
+            .2
+            .contains("nginx_discovery_daemon_runs_total 3\n"));
+    }
+
+    #[test]
+    fn test_route() {
+        let mut state = DaemonState::default();
+        assert_eq!(
+            route("/healthz", &state),
+            (
+                "503 Service Unavailable",
+                "text/plain",
+                "No checks run yet\n".to_string()
+            )
+        );
+        state.runs = 1;
+        assert_eq!(route("/healthz", &state).0, "200 OK");
+        assert_eq!(route("/nope", &state).0, "404 Not Found");
+
+        let results: serde_json::Value =
+            serde_json::from_str(&route("/results", &state).2).unwrap();
+        assert_eq!(results["error"], serde_json::Value::Null);
+        assert_eq!(results["results"], serde_json::json!([]));
+
+        let metrics = route("/metrics", &state).2;
+        assert!(metrics.contains("# TYPE nginx_discovery_daemon_config_reloads_total counter\n"));
+        assert!(metrics.contains("nginx_discovery_daemon_last_run_timestamp_seconds 0\n"));
+    }
+}
@@ -0,0 +1,147 @@
+//! Diff command implementation
+
+use crate::cli::args::{DiffArgs, GlobalOpts, OutputFormat, ReportFormat};
+use crate::cli::utils;
+use anyhow::{Context, Result};
+use colored::Colorize;
+use nginx_discovery::diff::{self, ChangeKind, SemanticDiff};
+use nginx_discovery::parse;
+use std::fs;
+
+pub fn run(args: &DiffArgs, global: &GlobalOpts) -> Result<()> {
+    utils::setup_colors(global.color.clone());
+
+    if let Some(format) = &args.report {
+        return run_report(args, format, global);
+    }
+
+    let old = parse_file(args, false, global.quiet)?;
+    let new = parse_file(args, true, global.quiet)?;
+    let changes = diff::semantic_compare(&old, &new);
+
+    let output = match args.format {
+        OutputFormat::Table => format_text(&changes, args),
+        OutputFormat::Json => {
+            serde_json::to_string_pretty(&changes).context("Failed to serialize")?
+        }
+        OutputFormat::Yaml => serde_yaml::to_string(&changes).context("Failed to serialize")?,
+        OutputFormat::Csv => {
+            let mut output = String::from("Kind,Subject,Message\n");
+            for change in &changes.changes {
+                output.push_str(&format!(
+                    "{},\"{}\",\"{}\"\n",
+                    change.kind,
+                    change.subject.replace('"', "\"\""),
+                    change.message.replace('"', "\"\"")
+                ));
+            }
+            output
+        }
+    };
+    print!("{output}");
+
+    if args.exit_code && !changes.is_empty() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn run_report(args: &DiffArgs, format: &ReportFormat, global: &GlobalOpts) -> Result<()> {
+    let load = |new: bool| {
+        let path = if new { &args.new } else { &args.old };
+        #[cfg(feature = "import")]
+        if let Some(format) = import_format(args, new) {
+            let text = fs::read_to_string(path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            return nginx_discovery::NginxDiscovery::from_imported(&text, format)
+                .with_context(|| format!("Failed to import {}", path.display()));
+        }
+        utils::load_config(global, path)
+            .with_context(|| format!("Failed to parse {}", path.display()))
+    };
+    let report = diff::compare_environments(&load(false)?, &load(true)?);
+    match format {
+        ReportFormat::Markdown => print!("{}", report.to_markdown()),
+        ReportFormat::Html => print!("{}", report.to_html()),
+    }
+
+    if args.exit_code && !report.is_empty() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Parse the old or the new configuration, importing it when it is for
+/// another web server
+#[cfg_attr(not(feature = "import"), allow(unused_variables))]
+fn parse_file(args: &DiffArgs, new: bool, quiet: bool) -> Result<nginx_discovery::ast::Config> {
+    let path = if new { &args.new } else { &args.old };
+    let text =
+        fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    #[cfg(feature = "import")]
+    if let Some(format) = import_format(args, new) {
+        let imported = nginx_discovery::import::import(&text, format)
+            .with_context(|| format!("Failed to import {}", path.display()))?;
+        if !quiet {
+            for warning in &imported.warnings {
+                eprintln!("{}: {}: {warning}", "warning".yellow(), path.display());
+            }
+        }
+        return Ok(imported.config);
+    }
+    parse(&text).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+#[cfg(feature = "import")]
+fn import_format(args: &DiffArgs, new: bool) -> Option<nginx_discovery::import::ImportFormat> {
+    use crate::cli::args::ConfigFormat;
+    use nginx_discovery::import::ImportFormat;
+
+    match if new {
+        args.new_format
+    } else {
+        args.old_format
+    } {
+        ConfigFormat::Nginx => None,
+        ConfigFormat::Apache => Some(ImportFormat::Apache),
+        ConfigFormat::Caddy => Some(ImportFormat::Caddy),
+    }
+}
+
+fn format_text(changes: &SemanticDiff, args: &DiffArgs) -> String {
+    let mut output = format!(
+        "{}\n{} {}\n{} {}\n\n",
+        "=== Configuration Diff ===".bold(),
+        "---".red(),
+        args.old.display(),
+        "+++".green(),
+        args.new.display()
+    );
+
+    if changes.is_empty() {
+        output.push_str(&format!(
+            "{}\n",
+            "✓ No differences in servers, locations or upstreams".green()
+        ));
+        return output;
+    }
+
+    for change in &changes.changes {
+        let line = change.to_string();
+        output.push_str(&format!(
+            "{}\n",
+            match change.kind {
+                ChangeKind::Added => line.green(),
+                ChangeKind::Removed => line.red(),
+                ChangeKind::Modified => line.yellow(),
+            }
+        ));
+    }
+    output.push_str(&format!(
+        "\n{} added, {} removed, {} modified\n",
+        changes.of_kind(ChangeKind::Added).count(),
+        changes.of_kind(ChangeKind::Removed).count(),
+        changes.of_kind(ChangeKind::Modified).count()
+    ));
+    output
+}
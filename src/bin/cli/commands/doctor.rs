@@ -1,239 +1,121 @@
 //! Doctor command implementation
 
-use crate::cli::args::{DoctorArgs, GlobalOpts};
+use crate::cli::args::{DoctorArgs, GlobalOpts, OutputFormat};
 use crate::cli::utils;
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
 use colored::Colorize;
-use nginx_discovery::{system, NginxDiscovery};
+use nginx_discovery::doctor::{self, DoctorCheck, DoctorOptions, DoctorReport, DoctorStatus};
+use nginx_discovery::lint::Linter;
 use std::path::Path;
 
 pub fn run(args: DoctorArgs, global: &GlobalOpts) -> Result<()> {
     utils::setup_colors(global.color.clone());
 
-    println!("{}\n", "Running diagnostics...".bold());
-
-    let mut passed = 0;
-    let mut warnings = 0;
-    let mut errors = 0;
-
-    // Check 1: NGINX binary
-    match check_nginx_binary() {
-        CheckResult::Pass(msg) => {
-            println!("{} {}", "✓".green(), msg);
-            passed += 1;
-        }
-        CheckResult::Warning(msg) => {
-            println!("{} {}", "⚠".yellow(), msg);
-            warnings += 1;
-        }
-        CheckResult::Error(msg) => {
-            println!("{} {}", "✗".red(), msg);
-            errors += 1;
-        }
-    }
-
-    // Check 2: Configuration file
-    let config_path = match utils::find_config(global) {
-        Ok(path) => path,
-        Err(e) => {
-            println!("{} Configuration file: {}", "✗".red(), e);
-            errors += 1;
-            return print_summary(passed, warnings, errors);
-        }
-    };
-
-    match check_config_file(&config_path) {
-        CheckResult::Pass(msg) => {
-            println!("{} {}", "✓".green(), msg);
-            passed += 1;
-        }
-        CheckResult::Warning(msg) => {
-            println!("{} {}", "⚠".yellow(), msg);
-            warnings += 1;
-        }
-        CheckResult::Error(msg) => {
-            println!("{} {}", "✗".red(), msg);
-            errors += 1;
-        }
+    let pretty = matches!(args.format, OutputFormat::Table);
+    if pretty {
+        println!("{}\n", "Running diagnostics...".bold());
     }
 
-    // Check 3: Configuration syntax
-    match check_config_syntax(&config_path) {
-        CheckResult::Pass(msg) => {
-            println!("{} {}", "✓".green(), msg);
-            passed += 1;
-        }
-        CheckResult::Warning(msg) => {
-            println!("{} {}", "⚠".yellow(), msg);
-            warnings += 1;
-        }
-        CheckResult::Error(msg) => {
-            println!("{} {}", "✗".red(), msg);
-            errors += 1;
-        }
-    }
-
-    // Check 4: Parse with nginx-discovery
-    let discovery = match NginxDiscovery::from_config_file(&config_path) {
-        Ok(d) => {
-            println!("{} Configuration parsed successfully", "✓".green());
-            passed += 1;
-            Some(d)
-        }
+    let config_path = utils::find_config(global);
+    let report = match &config_path {
+        Ok(path) => doctor::run_file(path, DoctorOptions::default()),
         Err(e) => {
-            println!("{} Configuration parsing failed: {}", "✗".red(), e);
-            errors += 1;
-            None
+            let mut report = DoctorReport::new();
+            report.push(
+                DoctorCheck::error("config_file", format!("Configuration file: {}", e))
+                    .with_remediation("Pass the path to nginx.conf with --config"),
+            );
+            report
         }
     };
 
-    // Check 5: Log files
-    if let Some(ref discovery) = discovery {
-        match check_log_files(discovery) {
-            CheckResult::Pass(msg) => {
-                println!("{} {}", "✓".green(), msg);
-                passed += 1;
-            }
-            CheckResult::Warning(msg) => {
-                println!("{} {}", "⚠".yellow(), msg);
-                warnings += 1;
-            }
-            CheckResult::Error(msg) => {
-                println!("{} {}", "✗".red(), msg);
-                errors += 1;
-            }
-        }
-    }
-
-    // Check 6: SSL certificates
-    if let Some(ref discovery) = discovery {
-        match check_ssl_certificates(discovery) {
-            CheckResult::Pass(msg) => {
-                println!("{} {}", "✓".green(), msg);
-                passed += 1;
-            }
-            CheckResult::Warning(msg) => {
-                println!("{} {}", "⚠".yellow(), msg);
-                warnings += 1;
-            }
-            CheckResult::Error(msg) => {
-                println!("{} {}", "✗".red(), msg);
-                errors += 1;
-            }
-        }
+    match args.format {
+        OutputFormat::Table => print_report(&report),
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&report).context("Failed to serialize to JSON")?
+        ),
+        OutputFormat::Yaml => print!(
+            "{}",
+            serde_yaml::to_string(&report).context("Failed to serialize to YAML")?
+        ),
+        OutputFormat::Csv => bail!("Doctor reports support table, json and yaml output"),
     }
 
-    print_summary(passed, warnings, errors)?;
-
     if args.fix {
-        println!("\n{}", "Automatic fixes not yet implemented.".dimmed());
-        println!("{}", "Please resolve issues manually.".dimmed());
+        if let Ok(path) = &config_path {
+            fix_config(path, pretty)?;
+        }
     }
 
     // Exit with error code if there are errors
-    if errors > 0 {
+    if !report.is_ok() {
         std::process::exit(1);
     }
 
     Ok(())
 }
 
-enum CheckResult {
-    Pass(String),
-    Warning(String),
-    Error(String),
-}
-
-fn check_nginx_binary() -> CheckResult {
-    match system::find_nginx() {
-        Ok(path) => match system::nginx_version() {
-            Ok(version) => CheckResult::Pass(format!(
-                "NGINX binary found: {} ({})",
-                path.display(),
-                version
-            )),
-            Err(_) => CheckResult::Pass(format!("NGINX binary found: {}", path.display())),
-        },
-        Err(_) => CheckResult::Error("NGINX binary not found in PATH".to_string()),
-    }
-}
-
-fn check_config_file(path: &Path) -> CheckResult {
-    if !path.exists() {
-        return CheckResult::Error(format!("Configuration file not found: {}", path.display()));
-    }
+fn print_report(report: &DoctorReport) {
+    for check in &report.checks {
+        let icon = match check.status {
+            DoctorStatus::Pass => "✓".green(),
+            DoctorStatus::Warning => "⚠".yellow(),
+            DoctorStatus::Error => "✗".red(),
+        };
+        println!("{} {}", icon, check.message);
 
-    match std::fs::metadata(path) {
-        Ok(metadata) => {
-            if metadata.is_file() {
-                CheckResult::Pass(format!("Configuration file: {}", path.display()))
-            } else {
-                CheckResult::Error(format!("Path is not a file: {}", path.display()))
-            }
+        if let Some(remediation) = &check.remediation {
+            println!("  {}", remediation.dimmed());
         }
-        Err(e) => CheckResult::Error(format!("Cannot access config file: {}", e)),
     }
-}
 
-fn check_config_syntax(_path: &Path) -> CheckResult {
-    match system::test_config() {
-        Ok(_) => CheckResult::Pass("Configuration syntax: valid".to_string()),
-        Err(e) => CheckResult::Error(format!("Configuration syntax error: {}", e)),
-    }
+    print_summary(report.passed(), report.warnings(), report.errors());
 }
 
-fn check_log_files(discovery: &NginxDiscovery) -> CheckResult {
-    let logs = discovery.all_log_files();
-
-    if logs.is_empty() {
-        return CheckResult::Warning("No log files configured".to_string());
+/// Apply the fixes of the default lint rules and write the result next to
+/// the configuration as `<file>.fixed`, leaving the original untouched
+fn fix_config(path: &Path, pretty: bool) -> Result<()> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let mut config = nginx_discovery::parse(&text).context("Failed to parse configuration")?;
+    let findings = Linter::with_default_rules().check(&config);
+
+    // Applied one at a time to list the fixes that took effect
+    let applied: Vec<&str> = findings
+        .iter()
+        .filter_map(|f| f.fix.as_ref())
+        .filter(|fix| fix.apply(&mut config))
+        .map(|fix| fix.description.as_str())
+        .collect();
+    if applied.is_empty() {
+        if pretty {
+            println!("\n{}", "No automatic fixes available.".dimmed());
+        }
+        return Ok(());
+    }
+
+    let mut fixed = path.as_os_str().to_owned();
+    fixed.push(".fixed");
+    std::fs::write(&fixed, config.to_config_string())
+        .with_context(|| format!("Failed to write {}", Path::new(&fixed).display()))?;
+
+    if pretty {
+        println!("\n{}", "=== Fixes ===".bold());
+        println!();
+        for description in &applied {
+            println!("  {} {}", "✓".green(), description);
+        }
+        println!(
+            "\n{} fix(es) written to {} (comments are not preserved); review and copy it over the original",
+            applied.len(),
+            Path::new(&fixed).display()
+        );
     }
-
-    let mut warnings: Vec<String> = Vec::new();
-
-    for log_path in &logs {
-        if let Some(parent) = log_path.parent() {
-            if !parent.exists() {
-                warnings.push(format!(
-                    "Log directory does not exist: {}",
-                    parent.display()
-                ));
-            } else if let Ok(metadata) = std::fs::metadata(parent) {
-                // Check if directory is writable (Unix-specific check would be better)
-                if metadata.permissions().readonly() {
-                    warnings.push(format!("Log directory not writable: {}", parent.display()));
-                }
-            }
-        }
-    }
-
-    if !warnings.is_empty() {
-        CheckResult::Warning(format!(
-            "Log files: {} warnings ({})",
-            warnings.len(),
-            warnings[0]
-        ))
-    } else {
-        CheckResult::Pass(format!(
-            "Log files: {} configured, all directories accessible",
-            logs.len()
-        ))
-    }
-}
-
-fn check_ssl_certificates(discovery: &NginxDiscovery) -> CheckResult {
-    let ssl_servers = discovery.ssl_servers();
-
-    if ssl_servers.is_empty() {
-        return CheckResult::Pass("No SSL configuration found".to_string());
-    }
-
-    // This is a basic check - in a real implementation, you'd parse
-    // ssl_certificate directives and check if files exist
-    CheckResult::Pass(format!("SSL servers: {} configured", ssl_servers.len()))
+    Ok(())
 }
 
-fn print_summary(passed: usize, warnings: usize, errors: usize) -> Result<()> {
+fn print_summary(passed: usize, warnings: usize, errors: usize) {
     println!("\n{}", "=== Summary ===".bold());
     println!();
     println!("  {} checks passed", passed.to_string().green());
@@ -253,6 +135,4 @@ fn print_summary(passed: usize, warnings: usize, errors: usize) -> Result<()> {
     } else {
         println!("\n{}", "Some checks failed".red());
     }
-
-    Ok(())
 }
@@ -0,0 +1,167 @@
+//! Explain command implementation
+
+use crate::cli::args::{ExplainArgs, GlobalOpts};
+use crate::cli::utils;
+use anyhow::{bail, Context, Result};
+use colored::Colorize;
+use nginx_discovery::docs;
+use nginx_discovery::route::{self, Outcome, Route};
+use std::path::Path;
+
+pub fn run(args: &ExplainArgs, global: &GlobalOpts) -> Result<()> {
+    utils::setup_colors(global.color.clone());
+
+    if let Some(url) = &args.url {
+        return explain_url(url, args.json, global);
+    }
+    let directive = args.directive.as_deref().unwrap_or_default();
+
+    let entries: Vec<_> = docs::lookup_all(directive).collect();
+    if entries.is_empty() {
+        let suggestions = docs::suggest(directive);
+        if suggestions.is_empty() {
+            bail!("Unknown directive '{}'", directive);
+        }
+        bail!(
+            "Unknown directive '{}'. Did you mean: {}?",
+            directive,
+            suggestions.join(", ")
+        );
+    }
+
+    if args.json {
+        let data: Vec<_> = entries
+            .iter()
+            .map(|doc| {
+                let mut value = serde_json::to_value(doc)?;
+                value["url"] = serde_json::json!(doc.url());
+                Ok(value)
+            })
+            .collect::<Result<_, serde_json::Error>>()
+            .context("Failed to serialize")?;
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&data).context("Failed to serialize")?
+        );
+        return Ok(());
+    }
+
+    for (i, doc) in entries.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        println!("{}", doc.name.bold());
+        println!("{doc}");
+        println!("{}", doc.url().dimmed());
+    }
+
+    Ok(())
+}
+
+fn explain_url(url: &str, json: bool, global: &GlobalOpts) -> Result<()> {
+    let path = utils::find_config(global)?;
+    let text = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let config = nginx_discovery::parse(&text)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+    let route = route::route(&config, url)?;
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&route).context("Failed to serialize")?
+        );
+        return Ok(());
+    }
+
+    print_route(&route, &path);
+    Ok(())
+}
+
+fn print_route(route: &Route, path: &Path) {
+    let at = |line: usize| format!("{}:{line}", path.display()).dimmed();
+
+    println!("{}", format!("=== {} ===", route.url).bold());
+    match &route.server {
+        Some(server) => println!(
+            "Server:    {} ({}) {}",
+            server.name.cyan(),
+            server.matched_by,
+            at(server.line)
+        ),
+        None => println!("Server:    {}", "none".red()),
+    }
+    for location in &route.locations {
+        println!(
+            "Location:  {} {}",
+            location.pattern.cyan(),
+            at(location.line)
+        );
+    }
+    if route.uri != route.url.path {
+        println!("URI:       {}", route.uri);
+    }
+
+    println!("\n{}", "Steps:".bold());
+    for step in &route.steps {
+        match step.line {
+            Some(line) => println!("  {} {}", at(line), step.message),
+            None => println!("  {}", step.message),
+        }
+    }
+
+    if !route.directives.is_empty() {
+        println!("\n{}", "Directives in effect:".bold());
+        for directive in &route.directives {
+            println!(
+                "  {} {:<20} {} {}",
+                at(directive.line),
+                directive
+                    .path
+                    .parent()
+                    .unwrap_or_default()
+                    .to_string()
+                    .dimmed(),
+                directive.name.bold(),
+                directive.args.join(" ")
+            );
+        }
+    }
+
+    println!("\n{}", "Result:".bold());
+    let result = route.outcome.to_string();
+    match &route.outcome {
+        Outcome::NoServer | Outcome::Unresolved { .. } => println!("  {}", result.red()),
+        _ => println!("  {}", result.green()),
+    }
+    match &route.outcome {
+        Outcome::Proxy {
+            upstream: Some(upstream),
+            servers,
+            ..
+        } => println!("  upstream {upstream}: {}", servers.join(", ")),
+        Outcome::Static {
+            try_files: Some(try_files),
+            ..
+        } => println!(
+            "  {}",
+            format!(
+                "try_files {} (depends on the filesystem)",
+                try_files.join(" ")
+            )
+            .yellow()
+        ),
+        _ => {}
+    }
+    for (label, headers) in [
+        ("Response headers", route.response_headers()),
+        ("Upstream request headers", route.upstream_headers()),
+    ] {
+        if !headers.is_empty() {
+            println!("\n{}", format!("{label}:").bold());
+            for (name, value) in headers {
+                println!("  {name}: {value}");
+            }
+        }
+    }
+}
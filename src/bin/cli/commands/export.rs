@@ -1,10 +1,12 @@
 //! Export command implementation
 
-use crate::cli::args::{ExportArgs, ExportFormat, GlobalOpts};
+use crate::cli::args::{ExportArgs, GlobalOpts};
 use crate::cli::utils;
-use anyhow::{Context, Result};
-use nginx_discovery::NginxDiscovery;
+use anyhow::{bail, Context, Result};
+use nginx_discovery::ast::ScrubOptions;
+use nginx_discovery::export;
 use std::fs;
+use std::io::{IsTerminal, Write};
 
 pub fn run(args: ExportArgs, global: &GlobalOpts) -> Result<()> {
     utils::setup_colors(global.color.clone());
@@ -12,30 +14,63 @@ pub fn run(args: ExportArgs, global: &GlobalOpts) -> Result<()> {
     // Load configuration
     let config_path = utils::find_config(global)?;
     let discovery =
-        NginxDiscovery::from_config_file(&config_path).context("Failed to parse configuration")?;
+        utils::load_config(global, &config_path).context("Failed to parse configuration")?;
 
-    // Export based on format
-    let output = match args.format {
-        ExportFormat::Json => {
-            if args.pretty {
-                discovery.to_json().context("Failed to export to JSON")?
-            } else {
-                serde_json::to_string(discovery.config()).context("Failed to export to JSON")?
-            }
-        }
-        ExportFormat::Yaml => discovery.to_yaml().context("Failed to export to YAML")?,
+    let config = if args.scrub {
+        discovery.config().scrubbed(ScrubOptions::default())
+    } else {
+        discovery.config().clone()
     };
 
+    // Export based on format
+    let mut options = export::ExportOptions::builder()
+        .pretty(args.pretty)
+        .include_source(args.source)
+        .deterministic(args.deterministic);
+    if args.source {
+        options = options.source_file(&config_path);
+    }
+    let mut buffer = Vec::new();
+    if args.effective {
+        let format = args
+            .format
+            .parse::<export::ExportFormat>()
+            .map_err(anyhow::Error::msg)?;
+        export::effective_config(&config)
+            .write(&mut buffer, format, args.pretty)
+            .context("Failed to export")?;
+    } else {
+        export::ExporterRegistry::with_builtin_exporters(&options.build())
+            .export(&args.format, &config, &mut buffer)
+            .context("Failed to export")?;
+    }
+
     // Write output
     if let Some(output_path) = &args.output {
-        fs::write(output_path, &output)
+        fs::write(output_path, &buffer)
             .with_context(|| format!("Failed to write to {}", output_path.display()))?;
 
         if !global.quiet {
             eprintln!("Configuration exported to: {}", output_path.display());
         }
+    } else if !args
+        .format
+        .parse::<export::ExportFormat>()
+        .is_ok_and(|format| format.is_binary())
+    {
+        println!("{}", String::from_utf8_lossy(&buffer));
     } else {
-        println!("{}", output);
+        // Binary formats go to stdout as is, but not into a terminal
+        let mut stdout = std::io::stdout().lock();
+        if stdout.is_terminal() {
+            bail!(
+                "{} output is binary; redirect it or use -o FILE",
+                args.format
+            );
+        }
+        stdout
+            .write_all(&buffer)
+            .context("Failed to write output")?;
     }
 
     Ok(())
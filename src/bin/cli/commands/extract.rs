@@ -1,9 +1,10 @@
 //! Extract command implementation
 
-use crate::cli::args::{ExtractArgs, ExtractTarget, GlobalOpts, OutputFormat};
+use crate::cli::args::{ExtractArgs, ExtractTarget, GlobalOpts, MonitoringStyle, OutputFormat};
 use crate::cli::output::table;
 use crate::cli::utils;
 use anyhow::{Context, Result};
+use nginx_discovery::types::{blackbox_targets, uptime_kuma_monitors};
 use nginx_discovery::NginxDiscovery;
 use std::fs;
 
@@ -13,7 +14,7 @@ pub fn run(args: ExtractArgs, global: &GlobalOpts) -> Result<()> {
     // Load configuration
     let config_path = utils::find_config(global)?;
     let discovery =
-        NginxDiscovery::from_config_file(&config_path).context("Failed to parse configuration")?;
+        utils::load_config(global, &config_path).context("Failed to parse configuration")?;
 
     // Extract based on target
     let (output, _format_arg, output_arg) = match args.target {
@@ -61,6 +62,31 @@ pub fn run(args: ExtractArgs, global: &GlobalOpts) -> Result<()> {
                 out,
             )
         }
+        ExtractTarget::SplitClients { format, output } => {
+            let fmt = format.unwrap_or(args.format);
+            let out = output.or(args.output);
+            (extract_split_clients(&discovery, &fmt)?, fmt, out)
+        }
+        ExtractTarget::Upstreams { format, output } => {
+            let fmt = format.unwrap_or(args.format);
+            let out = output.or(args.output);
+            (extract_upstreams(&discovery, &fmt)?, fmt, out)
+        }
+        ExtractTarget::Monitoring {
+            style,
+            module,
+            interval,
+            format,
+            output,
+        } => {
+            let fmt = format.unwrap_or(args.format);
+            let out = output.or(args.output);
+            (
+                extract_monitoring(&discovery, &fmt, &style, &module, interval)?,
+                fmt,
+                out,
+            )
+        }
     };
 
     // Write output
@@ -116,6 +142,34 @@ fn extract_servers(
     }
 }
 
+fn extract_split_clients(discovery: &NginxDiscovery, format: &OutputFormat) -> Result<String> {
+    let splits = discovery.split_clients();
+
+    match format {
+        OutputFormat::Table => Ok(table::format_split_clients(&splits)),
+        OutputFormat::Json => {
+            serde_json::to_string_pretty(&splits).context("Failed to serialize to JSON")
+        }
+        OutputFormat::Yaml => serde_yaml::to_string(&splits).context("Failed to serialize to YAML"),
+        OutputFormat::Csv => Ok(table::format_split_clients_csv(&splits)),
+    }
+}
+
+fn extract_upstreams(discovery: &NginxDiscovery, format: &OutputFormat) -> Result<String> {
+    let upstreams = discovery.upstreams();
+
+    match format {
+        OutputFormat::Table => Ok(table::format_upstreams(&upstreams)),
+        OutputFormat::Json => {
+            serde_json::to_string_pretty(&upstreams).context("Failed to serialize to JSON")
+        }
+        OutputFormat::Yaml => {
+            serde_yaml::to_string(&upstreams).context("Failed to serialize to YAML")
+        }
+        OutputFormat::Csv => Ok(table::format_upstreams_csv(&upstreams)),
+    }
+}
+
 fn extract_logs(
     discovery: &NginxDiscovery,
     format: &OutputFormat,
@@ -225,6 +279,37 @@ fn extract_locations(
     }
 }
 
+fn extract_monitoring(
+    discovery: &NginxDiscovery,
+    format: &OutputFormat,
+    style: &MonitoringStyle,
+    module: &str,
+    interval: u32,
+) -> Result<String> {
+    let targets = discovery.monitoring_targets();
+
+    match (format, style) {
+        (OutputFormat::Table, _) => Ok(table::format_monitoring_targets(&targets)),
+        (OutputFormat::Csv, _) => Ok(table::format_monitoring_targets_csv(&targets)),
+        (OutputFormat::Json, MonitoringStyle::Blackbox) => {
+            serde_json::to_string_pretty(&blackbox_targets(&targets, module))
+                .context("Failed to serialize to JSON")
+        }
+        (OutputFormat::Json, MonitoringStyle::UptimeKuma) => {
+            serde_json::to_string_pretty(&uptime_kuma_monitors(&targets, interval))
+                .context("Failed to serialize to JSON")
+        }
+        (OutputFormat::Yaml, MonitoringStyle::Blackbox) => {
+            serde_yaml::to_string(&blackbox_targets(&targets, module))
+                .context("Failed to serialize to YAML")
+        }
+        (OutputFormat::Yaml, MonitoringStyle::UptimeKuma) => {
+            serde_yaml::to_string(&uptime_kuma_monitors(&targets, interval))
+                .context("Failed to serialize to YAML")
+        }
+    }
+}
+
 fn wildcard_match(pattern: &str, text: &str) -> bool {
     if pattern == "*" {
         return true;
@@ -0,0 +1,45 @@
+//! Includes command implementation
+
+use crate::cli::args::{GlobalOpts, GraphFormat, IncludesArgs};
+use crate::cli::utils;
+use anyhow::{Context, Result};
+use colored::Colorize;
+use nginx_discovery::includes::IncludeGraph;
+use nginx_discovery::lint::Severity;
+
+pub fn run(args: &IncludesArgs, global: &GlobalOpts) -> Result<()> {
+    utils::setup_colors(global.color.clone());
+
+    let path = utils::find_config(global)?;
+    let graph =
+        IncludeGraph::build(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+
+    match args.format {
+        GraphFormat::Dot => print!("{}", graph.to_dot()),
+        GraphFormat::Json => println!("{}", graph.to_json().context("Failed to serialize")?),
+    }
+
+    // Findings go to stderr so the graph can be piped into `dot`
+    let findings = graph.findings();
+    if !global.quiet {
+        for finding in &findings {
+            let line = finding.line().map(|l| format!(":{l}")).unwrap_or_default();
+            let file = finding
+                .file
+                .as_ref()
+                .map(|f| format!("{}{line}: ", f.display()))
+                .unwrap_or_default();
+            let severity = match finding.severity {
+                Severity::Error => "error".red(),
+                Severity::Warning => "warning".yellow(),
+                Severity::Info => "info".blue(),
+            };
+            eprintln!("{file}{severity}: {}", finding.message);
+        }
+    }
+
+    if args.strict && findings.iter().any(|f| f.severity == Severity::Error) {
+        std::process::exit(1);
+    }
+    Ok(())
+}
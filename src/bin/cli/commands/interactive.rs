@@ -23,7 +23,7 @@ pub fn run(global: &GlobalOpts) -> Result<()> {
     println!("\n{} {}", "Loading:".dimmed(), config_path.display());
 
     let discovery =
-        NginxDiscovery::from_config_file(&config_path).context("Failed to parse configuration")?;
+        utils::load_config(global, &config_path).context("Failed to parse configuration")?;
 
     println!("{}", "✓ Configuration loaded successfully".green());
 
@@ -58,10 +58,10 @@ pub fn run(global: &GlobalOpts) -> Result<()> {
             7 => run_health_check(&config_path)?,
             8 => {
                 // Reload
-                let new_discovery = NginxDiscovery::from_config_file(&config_path)
+                let new_discovery = utils::load_config(global, &config_path)
                     .context("Failed to reload configuration")?;
                 println!("{}", "✓ Configuration reloaded".green());
-                return run_with_discovery(new_discovery, &config_path);
+                return run_with_discovery(new_discovery, &config_path, global);
             }
             9 => {
                 println!("\n{}", "Goodbye! 👋".bold());
@@ -85,7 +85,11 @@ pub fn run(global: &GlobalOpts) -> Result<()> {
     Ok(())
 }
 
-fn run_with_discovery(discovery: NginxDiscovery, config_path: &PathBuf) -> Result<()> {
+fn run_with_discovery(
+    discovery: NginxDiscovery,
+    config_path: &PathBuf,
+    global: &GlobalOpts,
+) -> Result<()> {
     loop {
         println!();
         let action = Select::with_theme(&ColorfulTheme::default())
@@ -115,9 +119,9 @@ fn run_with_discovery(discovery: NginxDiscovery, config_path: &PathBuf) -> Resul
             6 => export_interactive(&discovery)?,
             7 => run_health_check(config_path)?,
             8 => {
-                let new_discovery = NginxDiscovery::from_config_file(config_path)?;
+                let new_discovery = utils::load_config(global, config_path)?;
                 println!("{}", "✓ Configuration reloaded".green());
-                return run_with_discovery(new_discovery, config_path);
+                return run_with_discovery(new_discovery, config_path, global);
             }
             9 => {
                 println!("\n{}", "Goodbye! 👋".bold());
@@ -353,7 +357,7 @@ fn analyze_ssl_interactive(discovery: &NginxDiscovery) -> Result<()> {
                 if listen.http2 {
                     features.push("HTTP/2".to_string());
                 }
-                if listen.http3 {
+                if listen.is_quic() {
                     features.push("HTTP/3".to_string());
                 }
                 println!("    {}", features.join(", "));
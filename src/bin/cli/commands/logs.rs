@@ -0,0 +1,325 @@
+//! Logs command implementation
+
+use crate::cli::args::{GlobalOpts, LogsArgs, LogsCommand, OutputFormat};
+use crate::cli::utils;
+use anyhow::{bail, Context, Result};
+use colored::Colorize;
+use nginx_discovery::logs::{
+    BackendOptions, BackendReport, BackendState, LogStats, StatsOptions, StatsReport,
+};
+use std::fs;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tabled::{settings::Style, Table, Tabled};
+
+pub fn run(args: &LogsArgs, global: &GlobalOpts) -> Result<()> {
+    utils::setup_colors(global.color.clone());
+
+    let config_path = utils::find_config(global)?;
+    let discovery =
+        utils::load_config(global, &config_path).context("Failed to parse configuration")?;
+
+    let (text, output) = match &args.command {
+        LogsCommand::Stats {
+            lines,
+            minutes,
+            log,
+            top,
+            format,
+            output,
+        } => {
+            let options = StatsOptions {
+                lines: *lines,
+                since: since(*minutes),
+                top: *top,
+            };
+            let mut report = discovery.log_stats(&options);
+            if let Some(log) = log {
+                report.logs.retain(|stats| &stats.path == log);
+                if report.logs.is_empty() {
+                    bail!(
+                        "{} is not an access log of {}",
+                        log.display(),
+                        config_path.display()
+                    );
+                }
+            }
+
+            let text = match format {
+                OutputFormat::Table => format_table(&report),
+                OutputFormat::Json => {
+                    serde_json::to_string_pretty(&report).context("Failed to serialize to JSON")?
+                }
+                OutputFormat::Yaml => {
+                    serde_yaml::to_string(&report).context("Failed to serialize")?
+                }
+                OutputFormat::Csv => bail!("Log statistics support table, json and yaml output"),
+            };
+            (text, output)
+        }
+        LogsCommand::Backends {
+            lines,
+            minutes,
+            no_resolve,
+            format,
+            output,
+        } => {
+            let options = BackendOptions {
+                lines: *lines,
+                since: since(*minutes),
+                resolve: !no_resolve,
+            };
+            let report = discovery.backend_stats(&options);
+            let text = match format {
+                OutputFormat::Table => format_backends(&report),
+                OutputFormat::Json => {
+                    serde_json::to_string_pretty(&report).context("Failed to serialize to JSON")?
+                }
+                OutputFormat::Yaml => {
+                    serde_yaml::to_string(&report).context("Failed to serialize")?
+                }
+                OutputFormat::Csv => {
+                    let mut text = String::from("Backend,State,Upstreams,Requests,Share,4xx,5xx\n");
+                    for b in &report.backends {
+                        text.push_str(&format!(
+                            "{},{},{},{},{:.4},{},{}\n",
+                            b.address,
+                            b.state,
+                            b.upstreams.join(" "),
+                            b.requests,
+                            b.share,
+                            b.client_errors.map_or(String::new(), |c| c.to_string()),
+                            b.server_errors.map_or(String::new(), |c| c.to_string())
+                        ));
+                    }
+                    text
+                }
+            };
+            (text, output)
+        }
+    };
+
+    if let Some(path) = output {
+        fs::write(path, &text).with_context(|| format!("Failed to write to {}", path.display()))?;
+        if !global.quiet {
+            eprintln!("Results written to: {}", path.display());
+        }
+    } else {
+        println!("{text}");
+    }
+    Ok(())
+}
+
+/// Start of the last `minutes` minutes, in seconds since the Unix epoch
+fn since(minutes: Option<u64>) -> Option<f64> {
+    minutes.map(|minutes| {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        now.saturating_sub(Duration::from_secs(minutes * 60))
+            .as_secs_f64()
+    })
+}
+
+#[derive(Tabled)]
+struct BackendRow {
+    #[tabled(rename = "Backend")]
+    address: String,
+    #[tabled(rename = "Upstream")]
+    upstreams: String,
+    #[tabled(rename = "State")]
+    state: String,
+    #[tabled(rename = "Requests")]
+    requests: String,
+    #[tabled(rename = "4xx")]
+    client_errors: String,
+    #[tabled(rename = "5xx")]
+    server_errors: String,
+}
+
+fn format_backends(report: &BackendReport) -> String {
+    if report.logs.is_empty() {
+        return "No access log format records $upstream_addr.".to_string();
+    }
+    if report.backends.is_empty() {
+        return format!(
+            "No proxied requests in {} ({} lines served locally).",
+            report
+                .logs
+                .iter()
+                .map(|p| p.display().to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+            report.local_lines
+        );
+    }
+
+    let rows: Vec<BackendRow> = report
+        .backends
+        .iter()
+        .map(|b| {
+            let state = match b.state {
+                BackendState::Configured => b.state.to_string().green().to_string(),
+                BackendState::NotConfigured => b.state.to_string().red().bold().to_string(),
+                BackendState::Unverified => b.state.to_string().yellow().to_string(),
+            };
+            let errors = |count: Option<usize>| {
+                count.map_or_else(
+                    || "-".to_string(),
+                    |count| format!("{count} ({:.1}%)", rate(count, b.requests)),
+                )
+            };
+            BackendRow {
+                address: b.address.clone(),
+                upstreams: if b.upstreams.is_empty() {
+                    "-".to_string()
+                } else {
+                    b.upstreams.join(", ")
+                },
+                state,
+                requests: format!("{} ({:.1}%)", b.requests, b.share * 100.0),
+                client_errors: errors(b.client_errors),
+                server_errors: errors(b.server_errors),
+            }
+        })
+        .collect();
+    let mut table = Table::new(rows);
+    table.with(Style::rounded());
+
+    let mut output = table.to_string();
+    output.push_str(&format!(
+        "\n\n{} proxied lines, {} served locally\n",
+        report.proxied_lines, report.local_lines
+    ));
+    for finding in &report.findings {
+        output.push_str(&format!("\n{} {}\n", "⚠".yellow(), finding.message));
+        if let Some(help) = &finding.help {
+            output.push_str(&format!("  Fix: {}\n", help.dimmed()));
+        }
+    }
+    output
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn rate(count: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        count as f64 * 100.0 / total as f64
+    }
+}
+
+#[derive(Tabled)]
+struct TopRow {
+    #[tabled(rename = "Status")]
+    status: String,
+    #[tabled(rename = "Count")]
+    status_count: String,
+    #[tabled(rename = "Path")]
+    path: String,
+    #[tabled(rename = "Count")]
+    path_count: String,
+    #[tabled(rename = "Client")]
+    client: String,
+    #[tabled(rename = "Count")]
+    client_count: String,
+}
+
+fn format_table(report: &StatsReport) -> String {
+    if report.logs.is_empty() {
+        return "The configuration writes no access log files.".to_string();
+    }
+    report
+        .logs
+        .iter()
+        .map(format_log)
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+fn format_log(stats: &LogStats) -> String {
+    let mut output = format!(
+        "{} ({})\n",
+        stats.path.display().to_string().bold(),
+        stats.format
+    );
+
+    if stats.sampled_lines == 0 {
+        output.push_str(&format!("  {}\n", "No lines read".dimmed()));
+        return output;
+    }
+
+    output.push_str(&format!("  {} lines", stats.counted_lines));
+    if let (Some(first), Some(last)) = (stats.first, stats.last) {
+        output.push_str(&format!(" over {}", format_span(last - first)));
+    }
+    if stats.unparsed_lines > 0 {
+        output.push_str(&format!(
+            ", {} not matching the format",
+            stats.unparsed_lines.to_string().yellow()
+        ));
+    }
+    output.push('\n');
+
+    if let (Some(client), Some(server)) = (stats.client_error_rate, stats.server_error_rate) {
+        let server = format!("{:.1}%", server * 100.0);
+        let server = if server != "0.0%" {
+            server.red().bold().to_string()
+        } else {
+            server
+        };
+        output.push_str(&format!(
+            "  Errors: 4xx {:.1}%, 5xx {server}\n",
+            client * 100.0
+        ));
+    }
+    if let Some(times) = &stats.request_time {
+        output.push_str(&format!(
+            "  Request time: p50 {:.3}s, p95 {:.3}s, p99 {:.3}s, max {:.3}s\n",
+            times.p50, times.p95, times.p99, times.max
+        ));
+    }
+
+    let rows = stats
+        .statuses
+        .len()
+        .max(stats.paths.len())
+        .max(stats.clients.len());
+    if rows > 0 {
+        let cell = |entries: &[nginx_discovery::logs::TopEntry], i: usize| {
+            entries.get(i).map_or_else(
+                || (String::new(), String::new()),
+                |e| (e.value.clone(), e.count.to_string()),
+            )
+        };
+        let rows: Vec<TopRow> = (0..rows)
+            .map(|i| {
+                let (status, status_count) = cell(&stats.statuses, i);
+                let (path, path_count) = cell(&stats.paths, i);
+                let (client, client_count) = cell(&stats.clients, i);
+                TopRow {
+                    status,
+                    status_count,
+                    path,
+                    path_count,
+                    client,
+                    client_count,
+                }
+            })
+            .collect();
+        let mut table = Table::new(rows);
+        table.with(Style::rounded());
+        output.push_str(&table.to_string());
+    }
+    output
+}
+
+/// `1h 02m`, `16m 39s` or `42s`
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn format_span(secs: f64) -> String {
+    let secs = secs.max(0.0).round() as u64;
+    match (secs / 3600, secs % 3600 / 60, secs % 60) {
+        (0, 0, s) => format!("{s}s"),
+        (0, m, s) => format!("{m}m {s:02}s"),
+        (h, m, _) => format!("{h}h {m:02}m"),
+    }
+}
@@ -0,0 +1,8 @@
+//! LSP command implementation
+
+use anyhow::Result;
+
+pub fn run() -> Result<()> {
+    nginx_discovery::lsp::run_stdio()?;
+    Ok(())
+}
@@ -0,0 +1,50 @@
+//! Migrate command implementation
+
+use crate::cli::args::{GlobalOpts, MigrateArgs, MigrateTarget};
+use crate::cli::utils;
+use anyhow::{Context, Result};
+use colored::Colorize;
+use nginx_discovery::migrate::MigrationTarget;
+
+pub fn run(args: &MigrateArgs, global: &GlobalOpts) -> Result<()> {
+    utils::setup_colors(global.color.clone());
+
+    let config_path = utils::find_config(global)?;
+    let discovery =
+        utils::load_config(global, &config_path).context("Failed to parse configuration")?;
+
+    let target = match args.to {
+        MigrateTarget::Caddy => MigrationTarget::Caddy,
+        MigrateTarget::Haproxy => MigrationTarget::Haproxy,
+        MigrateTarget::Unit => MigrationTarget::Unit,
+    };
+    let migration = discovery.migrate(target);
+
+    if let Some(path) = &args.output {
+        std::fs::write(path, &migration.output)
+            .with_context(|| format!("Failed to write to {}", path.display()))?;
+        if !global.quiet {
+            eprintln!("{target} configuration written to: {}", path.display());
+        }
+    } else {
+        print!("{}", migration.output);
+    }
+
+    // Warnings go to stderr so the output can be redirected
+    if !global.quiet {
+        for warning in &migration.warnings {
+            eprintln!("{}: {warning}", "warning".yellow());
+        }
+        if !migration.warnings.is_empty() {
+            eprintln!(
+                "\n{} construct(s) not converted or approximated",
+                migration.warnings.len()
+            );
+        }
+    }
+
+    if args.strict && !migration.warnings.is_empty() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
@@ -1,8 +1,26 @@
 //! Command implementations
 
 pub mod analyze;
+pub mod backup;
+pub mod ci;
+#[cfg(feature = "network")]
+pub mod daemon;
+pub mod diff;
 pub mod doctor;
+pub mod explain;
 pub mod export;
 pub mod extract;
+pub mod includes;
 pub mod interactive;
+pub mod logs;
+#[cfg(feature = "lsp")]
+pub mod lsp;
+#[cfg(feature = "migrate")]
+pub mod migrate;
+#[cfg(feature = "network")]
+pub mod network;
 pub mod parse;
+pub mod query;
+pub mod replay;
+pub mod restore;
+pub mod scan;
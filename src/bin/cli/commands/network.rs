@@ -0,0 +1,522 @@
+//! Network command implementation
+
+#[cfg(feature = "network-history")]
+use crate::cli::args::NetworkCommand;
+use crate::cli::args::{GlobalOpts, NetworkArgs, OutputFormat};
+use crate::cli::utils;
+use anyhow::{bail, Context, Result};
+use colored::Colorize;
+use nginx_discovery::network::{
+    check_inventory, exit_code, parse_nameserver, Alerter, BatchOptions, BatchReport, CacheTtls,
+    CheckRegistry, CheckSeverity, DnsResolverOptions, HostRoutingOptions, Inventory,
+    LatencyBudgets, NetworkCheckOptions, NetworkCheckResult, NetworkCheckSummary, Proxy,
+    ResultCache, SeverityOverride, Webhook, WebhookFormat,
+};
+#[cfg(feature = "network-history")]
+use nginx_discovery::network::{CheckHistory, TargetHistory};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tabled::{settings::Style, Table, Tabled};
+
+pub fn run(args: NetworkArgs, global: &GlobalOpts) -> Result<()> {
+    utils::setup_colors(global.color.clone());
+
+    #[cfg(feature = "network-history")]
+    if let Some(NetworkCommand::History {
+        target,
+        file,
+        last,
+        format,
+        output,
+    }) = &args.command
+    {
+        let history = CheckHistory::new(file.clone().unwrap_or_else(CheckHistory::default_path));
+        let targets = history
+            .target(target)
+            .with_context(|| format!("Failed to read history {}", history.path().display()))?;
+        let output_text = match format {
+            OutputFormat::Table => format_history(target, &targets, *last),
+            OutputFormat::Json => {
+                serde_json::to_string_pretty(&targets).context("Failed to serialize to JSON")?
+            }
+            OutputFormat::Yaml | OutputFormat::Csv => {
+                bail!("Network history supports table and json output")
+            }
+        };
+        return write_output(&output_text, output.as_deref(), global);
+    }
+
+    let registry = CheckRegistry::with_builtin_checks();
+
+    if args.list_checks {
+        for check in registry.iter() {
+            println!("{:<14} {}", check.name().bold(), check.description());
+        }
+        return Ok(());
+    }
+
+    let registry = select_checks(registry, &args.only, &args.skip)?;
+
+    let severity_overrides = args
+        .severity
+        .iter()
+        .map(|rule| rule.parse::<SeverityOverride>())
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(anyhow::Error::msg)?;
+    let fail_on: CheckSeverity = args.fail_on.parse().map_err(anyhow::Error::msg)?;
+
+    let mut host_routing = HostRoutingOptions::default();
+    if let Some(header) = args.marker_header {
+        host_routing = host_routing.with_marker_header(header);
+    }
+    if let Some(target) = args.target {
+        host_routing = host_routing.with_target(target);
+    }
+
+    let proxy = args
+        .proxy
+        .as_deref()
+        .map(str::parse::<Proxy>)
+        .transpose()
+        .map_err(anyhow::Error::msg)?;
+
+    let mut dns = DnsResolverOptions {
+        use_config_resolver: args.dns_from_config,
+        ..Default::default()
+    };
+    for server in &args.dns_server {
+        let Some(addr) = parse_nameserver(server) else {
+            bail!("Invalid name server '{}' (expected IP or IP:PORT)", server);
+        };
+        dns = dns.with_nameserver(addr);
+    }
+
+    let mut latency_budgets = LatencyBudgets::default();
+    for rule in &args.latency_budget {
+        let Some((name, value)) = rule.split_once('=') else {
+            bail!("Invalid latency budget '{}': expected NAME=MILLIS", rule);
+        };
+        let budget = match value.trim() {
+            "off" => None,
+            ms => Some(Duration::from_millis(ms.parse().with_context(|| {
+                format!("Invalid latency budget '{rule}': expected milliseconds or off")
+            })?)),
+        };
+        latency_budgets
+            .set(name.trim(), budget)
+            .map_err(anyhow::Error::msg)?;
+    }
+
+    let cache = (args.cache || args.cache_file.is_some()).then(|| {
+        let path = args
+            .cache_file
+            .clone()
+            .unwrap_or_else(ResultCache::default_path);
+        Arc::new(ResultCache::load(path))
+    });
+    let cache_ttls = args.cache_ttl.map_or_else(CacheTtls::default, |secs| {
+        CacheTtls::uniform(Duration::from_secs(secs))
+    });
+
+    let alerts = alerter(
+        &args.webhook,
+        &args.webhook_format,
+        &args.alert_on,
+        args.alert_state.clone(),
+    )?;
+
+    // Checks named with --only run even if they are off by default
+    let selected = !args.only.is_empty();
+    let options = NetworkCheckOptions {
+        check_upstreams: true,
+        check_ssl: true,
+        check_ports: true,
+        check_dns: true,
+        check_host_routing: args.host_routing || selected,
+        host_routing,
+        timeout: Duration::from_secs(args.timeout),
+        severity_overrides,
+        fail_on,
+        cache: cache.clone(),
+        cache_ttls,
+        dns,
+        latency_budgets,
+        proxy,
+        alerts,
+        ..Default::default()
+    };
+
+    #[cfg(feature = "network-history")]
+    let history = (args.history || args.history_file.is_some()).then(|| {
+        CheckHistory::new(
+            args.history_file
+                .clone()
+                .unwrap_or_else(CheckHistory::default_path),
+        )
+    });
+
+    let runtime = tokio::runtime::Runtime::new().context("Failed to start async runtime")?;
+
+    if let Some(path) = &args.inventory {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read inventory {}", path.display()))?;
+        let inventory: Inventory = text.parse().map_err(anyhow::Error::msg)?;
+        let batch = BatchOptions {
+            concurrency: args.concurrency,
+            checks: NetworkCheckOptions {
+                continue_on_error: true,
+                ..options
+            },
+        };
+        let report = runtime.block_on(check_inventory(&inventory, &registry, &batch));
+        save_cache(cache.as_deref(), global);
+        #[cfg(feature = "network-history")]
+        {
+            let results: Vec<_> = report
+                .hosts
+                .iter()
+                .flat_map(|h| h.results.clone())
+                .collect();
+            record_history(history.as_ref(), &results, global);
+        }
+
+        let output = match args.format {
+            OutputFormat::Table => format_batch_table(&report),
+            OutputFormat::Json => {
+                serde_json::to_string_pretty(&report).context("Failed to serialize to JSON")?
+            }
+            OutputFormat::Yaml | OutputFormat::Csv => {
+                bail!("Network results support table and json output")
+            }
+        };
+        write_output(&output, args.output.as_deref(), global)?;
+
+        let code = report.exit_code(batch.checks.fail_on);
+        if code != 0 {
+            std::process::exit(code);
+        }
+        return Ok(());
+    }
+
+    let config_path = utils::find_config(global)?;
+    let discovery =
+        utils::load_config(global, &config_path).context("Failed to parse configuration")?;
+    let results = runtime
+        .block_on(registry.run(discovery.config(), &options))
+        .context("Network checks failed")?;
+    save_cache(cache.as_deref(), global);
+    #[cfg(feature = "network-history")]
+    record_history(history.as_ref(), &results, global);
+
+    let output = match args.format {
+        OutputFormat::Table => format_table(&results),
+        OutputFormat::Json => format_json(&results)?,
+        OutputFormat::Yaml | OutputFormat::Csv => {
+            bail!("Network results support table and json output")
+        }
+    };
+
+    write_output(&output, args.output.as_deref(), global)?;
+
+    let code = exit_code(&results, options.fail_on);
+    if code != 0 {
+        std::process::exit(code);
+    }
+
+    Ok(())
+}
+
+/// Restrict the registry to the checks named with `--only` and `--skip`
+pub(crate) fn select_checks(
+    registry: CheckRegistry,
+    only: &[String],
+    skip: &[String],
+) -> Result<CheckRegistry> {
+    for name in only.iter().chain(skip) {
+        if !registry.contains(name) {
+            bail!(
+                "Unknown check '{}' (available: {})",
+                name,
+                registry.names().join(", ")
+            );
+        }
+    }
+
+    let registry = if only.is_empty() {
+        registry
+    } else {
+        registry.only(only)
+    };
+    Ok(registry.skip(skip))
+}
+
+/// Alerter posting to every `--webhook`, `None` without webhooks
+pub(crate) fn alerter(
+    webhooks: &[String],
+    format: &str,
+    alert_on: &str,
+    state: Option<PathBuf>,
+) -> Result<Option<Arc<Alerter>>> {
+    if webhooks.is_empty() {
+        return Ok(None);
+    }
+    let format: WebhookFormat = format.parse().map_err(anyhow::Error::msg)?;
+    let min_severity: CheckSeverity = alert_on.parse().map_err(anyhow::Error::msg)?;
+    let alerter = webhooks
+        .iter()
+        .fold(Alerter::new(min_severity), |alerter, url| {
+            alerter.with_sink(Webhook::new(url.clone()).with_format(format))
+        })
+        .with_state_file(state.unwrap_or_else(Alerter::default_state_path));
+    Ok(Some(Arc::new(alerter)))
+}
+
+fn write_output(output: &str, path: Option<&Path>, global: &GlobalOpts) -> Result<()> {
+    if let Some(path) = path {
+        fs::write(path, output)
+            .with_context(|| format!("Failed to write to {}", path.display()))?;
+
+        if !global.quiet {
+            eprintln!("Results written to: {}", path.display());
+        }
+    } else {
+        println!("{}", output);
+    }
+    Ok(())
+}
+
+/// Persist the result cache; a failure only costs the next run its hits
+fn save_cache(cache: Option<&ResultCache>, global: &GlobalOpts) {
+    if let Some(cache) = cache {
+        if let Err(e) = cache.save() {
+            if !global.quiet {
+                eprintln!(
+                    "{} Failed to save cache {}: {}",
+                    "⚠".yellow(),
+                    cache.path().display(),
+                    e
+                );
+            }
+        }
+    }
+}
+
+/// Append results to the check history; a failure only leaves a gap in it
+#[cfg(feature = "network-history")]
+fn record_history(
+    history: Option<&CheckHistory>,
+    results: &[NetworkCheckResult],
+    global: &GlobalOpts,
+) {
+    if let Some(history) = history {
+        if let Err(e) = history.record(results) {
+            if !global.quiet {
+                eprintln!(
+                    "{} Failed to record history {}: {}",
+                    "⚠".yellow(),
+                    history.path().display(),
+                    e
+                );
+            }
+        }
+    }
+}
+
+#[derive(Tabled)]
+struct ResultRow {
+    #[tabled(rename = "Check")]
+    check: String,
+    #[tabled(rename = "Target")]
+    target: String,
+    #[tabled(rename = "Status")]
+    status: String,
+    #[tabled(rename = "Message")]
+    message: String,
+}
+
+fn format_table(results: &[NetworkCheckResult]) -> String {
+    if results.is_empty() {
+        return "No applicable network checks.".to_string();
+    }
+
+    let rows: Vec<ResultRow> = results
+        .iter()
+        .map(|r| ResultRow {
+            check: r.check_type.clone(),
+            target: r.target.clone(),
+            status: match r.cached {
+                Some(age) => format!("{} (cached {}s)", r.status, age.as_secs()),
+                None => r.status.to_string(),
+            },
+            message: r.message.clone(),
+        })
+        .collect();
+
+    let mut table = Table::new(rows);
+    table.with(Style::rounded());
+
+    format!("{}\n\n{}", table, NetworkCheckSummary::from(results))
+}
+
+fn format_json(results: &[NetworkCheckResult]) -> Result<String> {
+    let data = serde_json::json!({
+        "results": results,
+        "summary": NetworkCheckSummary::from(results),
+    });
+
+    serde_json::to_string_pretty(&data).context("Failed to serialize to JSON")
+}
+
+#[derive(Tabled)]
+struct HostRow {
+    #[tabled(rename = "Host")]
+    host: String,
+    #[tabled(rename = "Source")]
+    source: String,
+    #[tabled(rename = "Results")]
+    results: String,
+    #[tabled(rename = "Time")]
+    time: String,
+}
+
+#[derive(Tabled)]
+struct FailureRow {
+    #[tabled(rename = "Check")]
+    check: String,
+    #[tabled(rename = "Status")]
+    status: String,
+    #[tabled(rename = "Message")]
+    message: String,
+    #[tabled(rename = "Hosts")]
+    hosts: String,
+}
+
+fn format_batch_table(report: &BatchReport) -> String {
+    if report.hosts.is_empty() {
+        return "The inventory has no targets.".to_string();
+    }
+
+    let rows: Vec<HostRow> = report
+        .hosts
+        .iter()
+        .map(|h| HostRow {
+            host: h.name.clone(),
+            source: h.source.clone(),
+            results: match &h.error {
+                Some(error) => format!("{} {}", "✗".red(), error),
+                None => h.summary.by_status.to_string(),
+            },
+            time: format!("{:.1}s", h.duration.as_secs_f64()),
+        })
+        .collect();
+    let mut hosts = Table::new(rows);
+    hosts.with(Style::rounded());
+
+    let mut output = format!(
+        "{}\n\n{} of {} hosts with problems, {:.1}s\n",
+        hosts,
+        report.failed_hosts().count(),
+        report.hosts.len(),
+        report.duration.as_secs_f64()
+    );
+
+    if !report.failures.is_empty() {
+        let rows: Vec<FailureRow> = report
+            .failures
+            .iter()
+            .map(|g| FailureRow {
+                check: g.check_type.clone(),
+                status: g.status.to_string(),
+                message: g.message.clone(),
+                hosts: format!("{} ({})", g.hosts.len(), g.hosts.join(", ")),
+            })
+            .collect();
+        let mut failures = Table::new(rows);
+        failures.with(Style::rounded());
+        output.push_str(&format!(
+            "\n{}\n{}\n",
+            "FAILURES BY CAUSE:".yellow().bold(),
+            failures
+        ));
+    }
+
+    output
+}
+
+#[cfg(feature = "network-history")]
+#[derive(Tabled)]
+struct ChangeRow {
+    #[tabled(rename = "When")]
+    at: String,
+    #[tabled(rename = "From")]
+    from: String,
+    #[tabled(rename = "To")]
+    to: String,
+    #[tabled(rename = "Message")]
+    message: String,
+}
+
+#[cfg(feature = "network-history")]
+fn format_history(target: &str, targets: &[TargetHistory], last: usize) -> String {
+    if targets.is_empty() {
+        return format!("No recorded checks for {target}.");
+    }
+
+    let mut output = String::new();
+    for history in targets {
+        let Some(current) = history.current() else {
+            continue;
+        };
+        output.push_str(&format!(
+            "{} {}: {} since {}, {} samples, {} status changes{}\n",
+            history.check_type.bold(),
+            history.target,
+            current.result.status,
+            history
+                .changes
+                .last()
+                .map_or(history.samples[0].timestamp, |c| c.at)
+                .format("%Y-%m-%d %H:%M:%S UTC"),
+            history.samples.len(),
+            history.changes.len(),
+            if history.is_flapping() {
+                format!(" {}", "(flapping)".red().bold())
+            } else {
+                String::new()
+            }
+        ));
+
+        if let Some(latency) = history.latency() {
+            output.push_str(&format!(
+                "  latency: min {:?}, mean {:?}, max {:?}; {:?} -> {:?} ({:+.0}%)\n",
+                latency.min,
+                latency.mean,
+                latency.max,
+                latency.earlier,
+                latency.recent,
+                latency.change_percent()
+            ));
+        }
+
+        if !history.changes.is_empty() {
+            let skip = history.changes.len().saturating_sub(last);
+            let rows: Vec<ChangeRow> = history.changes[skip..]
+                .iter()
+                .map(|c| ChangeRow {
+                    at: c.at.format("%Y-%m-%d %H:%M:%S").to_string(),
+                    from: c.from.to_string(),
+                    to: c.to.to_string(),
+                    message: c.message.clone(),
+                })
+                .collect();
+            let mut table = Table::new(rows);
+            table.with(Style::rounded());
+            output.push_str(&format!("{table}\n"));
+        }
+        output.push('\n');
+    }
+
+    output.trim_end().to_string()
+}
@@ -17,7 +17,7 @@ pub fn run(args: ParseArgs, global: &GlobalOpts) -> Result<()> {
     }
 
     let discovery = if config_path.exists() {
-        NginxDiscovery::from_config_file(&config_path).context("Failed to parse configuration")?
+        utils::load_config(global, &config_path).context("Failed to parse configuration")?
     } else {
         anyhow::bail!("Configuration file not found: {}", config_path.display());
     };
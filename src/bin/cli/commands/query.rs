@@ -0,0 +1,79 @@
+//! Query command implementation
+
+use crate::cli::args::{GlobalOpts, QueryArgs};
+use crate::cli::utils;
+use anyhow::{Context, Result};
+use colored::Colorize;
+use nginx_discovery::query::Query;
+
+pub fn run(args: &QueryArgs, global: &GlobalOpts) -> Result<()> {
+    utils::setup_colors(global.color.clone());
+
+    let path = utils::find_config(global)?;
+    let text = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let config = nginx_discovery::parse(&text)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+
+    let query = build_query(args)?;
+    let matches = config.query(&query);
+
+    if args.json {
+        let data: Vec<_> = matches
+            .iter()
+            .map(|found| {
+                serde_json::json!({
+                    "line": found.line(),
+                    "name": found.directive.name(),
+                    "args": found.directive.args().iter().map(|a| a.unquoted()).collect::<Vec<_>>(),
+                    "context": found.context,
+                    "path": found.path.to_string(),
+                })
+            })
+            .collect();
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&data).context("Failed to serialize")?
+        );
+        return Ok(());
+    }
+
+    for found in &matches {
+        println!(
+            "{} {}",
+            format!("{}:{}", path.display(), found.line()).dimmed(),
+            found
+        );
+    }
+    if !global.quiet {
+        println!(
+            "\n{} match(es) for {}",
+            matches.len(),
+            query.to_string().cyan()
+        );
+    }
+    Ok(())
+}
+
+fn build_query(args: &QueryArgs) -> Result<Query> {
+    let mut query = Query::new();
+    if let Some(name) = &args.name {
+        query = query.with_name(name);
+    }
+    if let Some(pattern) = &args.arg {
+        query = query.arg_regex(pattern)?;
+    }
+    if !args.inside.is_empty() {
+        query = query.inside(&args.inside);
+    }
+    if let Some(path) = &args.under {
+        query = query.under(path.parse()?);
+    }
+    for child in &args.has_child {
+        query = query.has_child(Query::name(child));
+    }
+    for child in &args.without_child {
+        query = query.without(Query::new().has_child(Query::name(child)));
+    }
+    Ok(query)
+}
@@ -0,0 +1,215 @@
+//! Replay command implementation
+
+use crate::cli::args::{GlobalOpts, OutputFormat, ReplayArgs};
+use crate::cli::utils;
+use anyhow::{bail, Context, Result};
+use colored::Colorize;
+use nginx_discovery::diff::{self, RoutingChange};
+use nginx_discovery::logs::{self, LogParser};
+use nginx_discovery::route::{self, RouteDecision, SimulatedRequest};
+use nginx_discovery::NginxDiscovery;
+use std::fs;
+use tabled::{settings::Style, Table, Tabled};
+
+pub fn run(args: &ReplayArgs, global: &GlobalOpts) -> Result<()> {
+    utils::setup_colors(global.color.clone());
+
+    let config_path = utils::find_config(global)?;
+    let discovery =
+        utils::load_config(global, &config_path).context("Failed to parse configuration")?;
+    let requests = read_requests(args, &discovery)?;
+    if requests.is_empty() {
+        bail!("No requests found in the input");
+    }
+
+    let mut changed = false;
+    let text = if let Some(against) = &args.against {
+        let new = utils::load_config(global, against)
+            .with_context(|| format!("Failed to parse {}", against.display()))?;
+        let changes = diff::routing_changes(discovery.config(), new.config(), &requests);
+        changed = !changes.is_empty();
+        format_changes(&changes, requests.len(), &args.format)?
+    } else {
+        let decisions = route::replay(discovery.config(), &requests);
+        format_decisions(&decisions, &args.format)?
+    };
+
+    if let Some(path) = &args.output {
+        fs::write(path, &text).with_context(|| format!("Failed to write to {}", path.display()))?;
+        if !global.quiet {
+            eprintln!("Results written to: {}", path.display());
+        }
+    } else {
+        println!("{text}");
+    }
+
+    if args.exit_code && changed {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn read_requests(args: &ReplayArgs, discovery: &NginxDiscovery) -> Result<Vec<SimulatedRequest>> {
+    if let Some(path) = &args.har {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        return route::requests_from_har(&text)
+            .with_context(|| format!("Failed to read {}", path.display()));
+    }
+    let Some(path) = &args.log else {
+        bail!("Either --har or --log is required");
+    };
+
+    let formats = discovery.log_formats();
+    let parser = match &args.log_format {
+        Some(name) if name == "combined" => LogParser::combined(),
+        Some(name) => match formats.iter().find(|f| &f.name == name) {
+            Some(format) => LogParser::from_format(format),
+            None => bail!("log_format '{name}' is not defined in the configuration"),
+        },
+        None => discovery
+            .access_logs()
+            .iter()
+            .find(|log| log.expanded_paths().contains(path))
+            .map_or_else(LogParser::combined, |log| LogParser::for_log(log, &formats)),
+    };
+    let lines = logs::read_tail(path, args.lines)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(route::requests_from_log(
+        lines.iter().map(String::as_str),
+        &parser,
+        &args.host,
+    ))
+}
+
+#[derive(Tabled)]
+struct DecisionRow {
+    #[tabled(rename = "Request")]
+    request: String,
+    #[tabled(rename = "Count")]
+    count: usize,
+    #[tabled(rename = "Server")]
+    server: String,
+    #[tabled(rename = "Location")]
+    location: String,
+    #[tabled(rename = "Handled by")]
+    outcome: String,
+}
+
+#[derive(Tabled)]
+struct ChangeRow {
+    #[tabled(rename = "Request")]
+    request: String,
+    #[tabled(rename = "Count")]
+    count: usize,
+    #[tabled(rename = "Before")]
+    old: String,
+    #[tabled(rename = "After")]
+    new: String,
+}
+
+fn or_dash(value: Option<&String>) -> String {
+    value.map_or_else(|| "-".to_string(), Clone::clone)
+}
+
+fn csv_field(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "\"\""))
+}
+
+fn format_decisions(decisions: &[RouteDecision], format: &OutputFormat) -> Result<String> {
+    match format {
+        OutputFormat::Table => {
+            let mut output = format!("{}\n\n", "=== Routing Decisions ===".bold());
+            let total: usize = decisions.iter().map(|d| d.count).sum();
+            output.push_str(&format!(
+                "Requests: {total} ({} distinct)\n\n",
+                decisions.len()
+            ));
+            let rows = decisions.iter().map(|d| DecisionRow {
+                request: d.request.clone(),
+                count: d.count,
+                server: or_dash(d.server.as_ref()),
+                location: or_dash(d.location.as_ref()),
+                outcome: d.outcome.clone(),
+            });
+            let mut table = Table::new(rows);
+            table.with(Style::rounded());
+            output.push_str(&table.to_string());
+            Ok(output)
+        }
+        OutputFormat::Json => {
+            serde_json::to_string_pretty(decisions).context("Failed to serialize")
+        }
+        OutputFormat::Yaml => serde_yaml::to_string(decisions).context("Failed to serialize"),
+        OutputFormat::Csv => {
+            let mut output = String::from("Request,Count,Server,Location,Outcome\n");
+            for d in decisions {
+                output.push_str(&format!(
+                    "{},{},{},{},{}\n",
+                    csv_field(&d.request),
+                    d.count,
+                    csv_field(d.server.as_deref().unwrap_or_default()),
+                    csv_field(d.location.as_deref().unwrap_or_default()),
+                    csv_field(&d.outcome)
+                ));
+            }
+            Ok(output)
+        }
+    }
+}
+
+fn format_changes(
+    changes: &[RoutingChange],
+    requests: usize,
+    format: &OutputFormat,
+) -> Result<String> {
+    match format {
+        OutputFormat::Table => {
+            let mut output = format!("{}\n\n", "=== Routing Changes ===".bold());
+            if changes.is_empty() {
+                output.push_str(&format!(
+                    "{}",
+                    format!("✓ All {requests} requests are routed the same way").green()
+                ));
+                return Ok(output);
+            }
+            let affected: usize = changes.iter().map(|c| c.old.count).sum();
+            output.push_str(&format!(
+                "{} of {requests} requests are routed differently\n\n",
+                affected.to_string().yellow().bold()
+            ));
+            let describe = |d: &RouteDecision| {
+                format!(
+                    "{} {}\n{}",
+                    or_dash(d.server.as_ref()),
+                    or_dash(d.location.as_ref()),
+                    d.outcome
+                )
+            };
+            let rows = changes.iter().map(|c| ChangeRow {
+                request: c.old.request.clone(),
+                count: c.old.count,
+                old: describe(&c.old),
+                new: describe(&c.new),
+            });
+            let mut table = Table::new(rows);
+            table.with(Style::rounded());
+            output.push_str(&table.to_string());
+            Ok(output)
+        }
+        OutputFormat::Json => serde_json::to_string_pretty(changes).context("Failed to serialize"),
+        OutputFormat::Yaml => serde_yaml::to_string(changes).context("Failed to serialize"),
+        OutputFormat::Csv => {
+            let mut output = String::from("Request,Count,Change\n");
+            for c in changes {
+                output.push_str(&format!(
+                    "{},{},{}\n",
+                    csv_field(&c.old.request),
+                    c.old.count,
+                    csv_field(&c.summary())
+                ));
+            }
+            Ok(output)
+        }
+    }
+}
@@ -0,0 +1,47 @@
+//! Restore command implementation
+
+use crate::cli::args::{GlobalOpts, RestoreArgs};
+use crate::cli::utils;
+use anyhow::{Context, Result};
+use colored::Colorize;
+use nginx_discovery::system::{self, FileChange, RestoreOptions};
+
+pub fn run(args: &RestoreArgs, global: &GlobalOpts) -> Result<()> {
+    utils::setup_colors(global.color.clone());
+
+    let options = RestoreOptions {
+        root: args.root.clone(),
+        dry_run: !args.apply,
+    };
+    let report = system::restore(&args.archive, &options)
+        .with_context(|| format!("Failed to restore {}", args.archive.display()))?;
+
+    for file in report.changed() {
+        let path = file.path.display();
+        match file.change {
+            FileChange::Created => println!("{} {path}", "+".green()),
+            FileChange::Modified => {
+                println!("{} {path}", "~".yellow());
+                for line in file.diff.lines() {
+                    let line = if line.starts_with('-') {
+                        line.red()
+                    } else {
+                        line.green()
+                    };
+                    println!("    {line}");
+                }
+            }
+            FileChange::Unchanged => {}
+        }
+    }
+
+    let changed = report.changed().count();
+    if changed == 0 {
+        println!("{}", "✓ Files on disk match the archive".green());
+    } else if report.applied {
+        println!("\n{changed} file(s) restored");
+    } else {
+        println!("\n{changed} file(s) differ; run with --apply to restore them");
+    }
+    Ok(())
+}
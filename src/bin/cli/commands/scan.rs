@@ -0,0 +1,101 @@
+//! Scan command implementation
+
+use crate::cli::args::{GlobalOpts, OutputFormat, ScanArgs};
+use crate::cli::utils;
+use anyhow::{Context, Result};
+use colored::Colorize;
+use nginx_discovery::scan::{self, ScanOptions, ScanReport};
+
+pub fn run(args: &ScanArgs, global: &GlobalOpts) -> Result<()> {
+    utils::setup_colors(global.color.clone());
+
+    let options = ScanOptions {
+        extensions: args.extensions.clone(),
+        recursive: !args.no_recursive,
+        ..ScanOptions::default()
+    };
+    let report = scan::directory(&args.dir, &options)
+        .with_context(|| format!("Failed to scan {}", args.dir.display()))?;
+
+    let output = match args.format {
+        OutputFormat::Table => format_text(&report),
+        OutputFormat::Json => {
+            serde_json::to_string_pretty(&report).context("Failed to serialize")? + "\n"
+        }
+        OutputFormat::Yaml => serde_yaml::to_string(&report).context("Failed to serialize")?,
+        OutputFormat::Csv => {
+            let mut output = String::from("File,Servers,Ports,Error\n");
+            for file in &report.files {
+                let mut ports: Vec<u16> = file
+                    .servers
+                    .iter()
+                    .flat_map(|s| s.listen.iter().map(|l| l.port))
+                    .collect();
+                ports.sort_unstable();
+                ports.dedup();
+                output.push_str(&format!(
+                    "\"{}\",{},\"{}\",\"{}\"\n",
+                    file.path.display().to_string().replace('"', "\"\""),
+                    file.servers.len(),
+                    ports
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                    file.error
+                        .as_deref()
+                        .unwrap_or_default()
+                        .replace('"', "\"\"")
+                ));
+            }
+            output
+        }
+    };
+    print!("{output}");
+
+    if args.strict && report.failed().next().is_some() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn format_text(report: &ScanReport) -> String {
+    let mut output = format!("{} {}\n\n", "=== Scan ===".bold(), report.root.display());
+
+    for file in &report.files {
+        let path = file
+            .path
+            .strip_prefix(&report.root)
+            .unwrap_or(&file.path)
+            .display();
+        match &file.error {
+            Some(error) => output.push_str(&format!("{} {path}: {error}\n", "✗".red())),
+            None => output.push_str(&format!(
+                "{} {path} ({} server(s))\n",
+                "✓".green(),
+                file.servers.len()
+            )),
+        }
+    }
+
+    let failed = report.failed().count();
+    output.push_str(&format!(
+        "\n{} file(s), {} failed, {} server(s)\n",
+        report.files.len(),
+        failed,
+        report.servers().count()
+    ));
+    let ports: Vec<String> = report.ports.iter().map(ToString::to_string).collect();
+    output.push_str(&format!("Ports: {}\n", ports.join(", ")));
+    output.push_str(&format!(
+        "Server names: {}\n",
+        report.server_names.join(", ")
+    ));
+    if !report.log_files.is_empty() {
+        output.push_str("Log files:\n");
+        for log in &report.log_files {
+            output.push_str(&format!("  {}\n", log.display()));
+        }
+    }
+    output
+}
@@ -0,0 +1,172 @@
+//! Inline code review formats for lint findings
+//!
+//! - GitHub Actions workflow commands (`::error file=...,line=...::message`)
+//! - reviewdog's Diagnostic JSON (rdjson)
+
+use nginx_discovery::lint::{Finding, Severity};
+use serde_json::{json, Value};
+use std::path::Path;
+
+/// Name reported as the diagnostic source.
+const SOURCE: &str = "nginx-discover";
+
+/// Format findings as GitHub Actions workflow commands, one per line.
+pub fn format_github(findings: &[Finding]) -> String {
+    findings
+        .iter()
+        .map(github_annotation)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Format findings as a reviewdog rdjson document.
+pub fn format_rdjson(findings: &[Finding]) -> Result<String, serde_json::Error> {
+    let diagnostics: Vec<Value> = findings.iter().map(rdjson_diagnostic).collect();
+
+    serde_json::to_string_pretty(&json!({
+        "source": { "name": SOURCE },
+        "diagnostics": diagnostics,
+    }))
+}
+
+/// Rewrite finding paths relative to `base` (e.g. `$GITHUB_WORKSPACE`) so
+/// review tools can map them to files in the repository.
+pub fn relativize(findings: &mut [Finding], base: &Path) {
+    for finding in findings {
+        if let Some(relative) = finding
+            .file
+            .as_deref()
+            .and_then(|file| file.strip_prefix(base).ok())
+        {
+            finding.file = Some(relative.to_path_buf());
+        }
+    }
+}
+
+fn github_annotation(finding: &Finding) -> String {
+    let level = match finding.severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Info => "notice",
+    };
+
+    let mut properties = Vec::new();
+    if let Some(file) = &finding.file {
+        properties.push(format!(
+            "file={}",
+            escape_property(&file.display().to_string())
+        ));
+    }
+    if let Some(span) = finding.span {
+        properties.push(format!("line={}", span.line));
+        properties.push(format!("col={}", span.col));
+    }
+    properties.push(format!("title={}", escape_property(&finding.rule)));
+
+    format!(
+        "::{} {}::{}",
+        level,
+        properties.join(","),
+        escape_data(&message_with_help(finding))
+    )
+}
+
+fn rdjson_diagnostic(finding: &Finding) -> Value {
+    let severity = match finding.severity {
+        Severity::Error => "ERROR",
+        Severity::Warning => "WARNING",
+        Severity::Info => "INFO",
+    };
+
+    let mut location = json!({
+        "path": finding
+            .file
+            .as_ref()
+            .map(|f| f.display().to_string())
+            .unwrap_or_default(),
+    });
+    if let Some(span) = finding.span {
+        location["range"] = json!({
+            "start": { "line": span.line, "column": span.col },
+        });
+    }
+
+    let mut code = json!({ "value": finding.rule });
+    if let Some(url) = &finding.doc_url {
+        code["url"] = json!(url);
+    }
+
+    json!({
+        "message": message_with_help(finding),
+        "location": location,
+        "severity": severity,
+        "source": { "name": SOURCE },
+        "code": code,
+    })
+}
+
+fn message_with_help(finding: &Finding) -> String {
+    match &finding.help {
+        Some(help) => format!("{}\n{}", finding.message, help),
+        None => finding.message.clone(),
+    }
+}
+
+/// Escape the message part of a workflow command.
+fn escape_data(value: &str) -> String {
+    value
+        .replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escape a `key=value` property of a workflow command.
+fn escape_property(value: &str) -> String {
+    escape_data(value).replace(':', "%3A").replace(',', "%2C")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nginx_discovery::ast::Span;
+
+    fn finding() -> Finding {
+        Finding::new(
+            "duplicate_directive",
+            Severity::Error,
+            "100% \"root\" is duplicate",
+        )
+        .with_span(Span::new(10, 20, 4, 5))
+        .with_file("/work/repo/conf/a,b.conf")
+        .with_help("Remove one")
+        .with_doc_url("https://nginx.org/en/docs/http/ngx_http_core_module.html#root")
+    }
+
+    #[test]
+    fn test_github_annotation() {
+        let out = format_github(&[finding()]);
+        assert_eq!(
+            out,
+            "::error file=/work/repo/conf/a%2Cb.conf,line=4,col=5,title=duplicate_directive::100%25 \"root\" is duplicate%0ARemove one"
+        );
+    }
+
+    #[test]
+    fn test_rdjson() {
+        let mut findings = vec![finding()];
+        relativize(&mut findings, Path::new("/work/repo"));
+
+        let doc: Value = serde_json::from_str(&format_rdjson(&findings).unwrap()).unwrap();
+        let diagnostic = &doc["diagnostics"][0];
+
+        assert_eq!(doc["source"]["name"], SOURCE);
+        assert_eq!(diagnostic["severity"], "ERROR");
+        assert_eq!(diagnostic["location"]["path"], "conf/a,b.conf");
+        assert_eq!(diagnostic["location"]["range"]["start"]["line"], 4);
+        assert_eq!(diagnostic["code"]["value"], "duplicate_directive");
+        assert_eq!(
+            diagnostic["code"]["url"],
+            "https://nginx.org/en/docs/http/ngx_http_core_module.html#root"
+        );
+    }
+}
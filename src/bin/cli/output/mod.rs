@@ -1,4 +1,5 @@
 //! Output formatting modules
 
+pub mod annotations;
 pub mod table;
 pub mod tree;
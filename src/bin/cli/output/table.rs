@@ -1,6 +1,15 @@
 //! Table formatting for CLI output
 
-use nginx_discovery::types::{AccessLog, Location, LogFormat, Server};
+use nginx_discovery::analyze::{
+    AffinityUse, BotFilter, CompiledModules, EmbeddedSecret, FormatCompat, GeoipDatabase,
+    GrpcLocation, HeaderOverride, LocationCaching, LocationCoverage, LocationErrors, ModuleUsage,
+    NameCoverage, OffloadTarget, QuicServer, RealIpServer, ReferencedPath, ServerCoverage,
+    ServerNameCoverage, TuningCheck, UpstreamUse, VersionRequirement, WafContext,
+};
+use nginx_discovery::types::{
+    AccessLog, ListenDirective, Location, LogFormat, MonitoringTarget, Server, SplitClients,
+    Upstream,
+};
 use tabled::{settings::Style, Table, Tabled};
 
 #[derive(Tabled)]
@@ -155,6 +164,27 @@ struct LocationRow {
     location_type: String,
     #[tabled(rename = "Target")]
     target: String,
+    #[tabled(rename = "Methods")]
+    methods: String,
+}
+
+/// Which methods reach a location: `all`, `all (auth)`, `GET; others denied`
+fn location_methods(loc: &Location) -> String {
+    let Some(limit) = &loc.limit_except else {
+        return if loc.requires_auth {
+            "all (auth)".to_string()
+        } else {
+            "all".to_string()
+        };
+    };
+    let others = if limit.denies_all() {
+        "denied"
+    } else if limit.is_restricted() {
+        "restricted"
+    } else {
+        "unrestricted"
+    };
+    format!("{}; others {others}", limit.methods.join(" "))
 }
 
 pub fn format_locations(locations: &[(String, Location)]) -> String {
@@ -187,6 +217,7 @@ pub fn format_locations(locations: &[(String, Location)]) -> String {
                 modifier: format!("{:?}", loc.modifier),
                 location_type: loc_type,
                 target,
+                methods: location_methods(loc),
             }
         })
         .collect();
@@ -198,7 +229,7 @@ pub fn format_locations(locations: &[(String, Location)]) -> String {
 }
 
 pub fn format_locations_csv(locations: &[(String, Location)]) -> String {
-    let mut output = String::from("Server,Path,Modifier,Type,Target\n");
+    let mut output = String::from("Server,Path,Modifier,Type,Target,Methods\n");
 
     for (server, loc) in locations {
         let (loc_type, target) = if loc.is_proxy() {
@@ -218,10 +249,1041 @@ pub fn format_locations_csv(locations: &[(String, Location)]) -> String {
         };
 
         output.push_str(&format!(
-            "{},{},{:?},{},{}\n",
-            server, loc.path, loc.modifier, loc_type, target
+            "{},{},{:?},{},{},{}\n",
+            server,
+            loc.path,
+            loc.modifier,
+            loc_type,
+            target,
+            location_methods(loc)
+        ));
+    }
+
+    output
+}
+
+#[derive(Tabled)]
+struct SplitRow {
+    #[tabled(rename = "Variable")]
+    variable: String,
+    #[tabled(rename = "Source")]
+    source: String,
+    #[tabled(rename = "Share")]
+    share: String,
+    #[tabled(rename = "Value")]
+    value: String,
+}
+
+pub fn format_split_clients(splits: &[SplitClients]) -> String {
+    if splits.is_empty() {
+        return "No split_clients blocks found.".to_string();
+    }
+
+    let rows: Vec<SplitRow> = splits
+        .iter()
+        .flat_map(|split| {
+            split.shares().into_iter().map(|(value, share)| SplitRow {
+                variable: format!("${}", split.variable),
+                source: split.source.clone(),
+                share: format!("{share}%"),
+                value: format!("\"{value}\""),
+            })
+        })
+        .collect();
+
+    let mut table = Table::new(rows);
+    table.with(Style::rounded());
+
+    let mut output = table.to_string();
+    for split in splits.iter().filter(|s| s.is_over_allocated()) {
+        output.push_str(&format!(
+            "\n\nWarning: ${} allocates {}%, nginx rejects more than 100%",
+            split.variable,
+            split.total_percent()
         ));
     }
+    output
+}
+
+pub fn format_split_clients_csv(splits: &[SplitClients]) -> String {
+    let mut output = String::from("Variable,Source,Share,Value\n");
+
+    for split in splits {
+        for (value, share) in split.shares() {
+            output.push_str(&format!(
+                "${},{},{},{}\n",
+                split.variable, split.source, share, value
+            ));
+        }
+    }
 
     output
 }
+
+#[derive(Tabled)]
+struct UpstreamRow {
+    #[tabled(rename = "Upstream")]
+    name: String,
+    #[tabled(rename = "Context")]
+    context: String,
+    #[tabled(rename = "Method")]
+    method: String,
+    #[tabled(rename = "Servers")]
+    servers: String,
+    #[tabled(rename = "Keepalive")]
+    keepalive: String,
+}
+
+pub fn format_upstreams(upstreams: &[Upstream]) -> String {
+    if upstreams.is_empty() {
+        return "No upstream blocks found.".to_string();
+    }
+
+    let rows: Vec<UpstreamRow> = upstreams
+        .iter()
+        .map(|u| UpstreamRow {
+            name: u.name.clone(),
+            context: if u.stream { "stream" } else { "http" }.to_string(),
+            method: u
+                .method
+                .clone()
+                .unwrap_or_else(|| "round-robin".to_string()),
+            servers: u
+                .servers
+                .iter()
+                .map(|s| {
+                    let mut server = s.address.clone();
+                    if s.backup {
+                        server.push_str(" (backup)");
+                    }
+                    if s.down {
+                        server.push_str(" (down)");
+                    }
+                    server
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+            keepalive: u
+                .keepalive
+                .map_or_else(|| "-".to_string(), |n| n.to_string()),
+        })
+        .collect();
+
+    let mut table = Table::new(rows);
+    table.with(Style::rounded());
+    table.to_string()
+}
+
+pub fn format_upstreams_csv(upstreams: &[Upstream]) -> String {
+    let mut output = String::from("Upstream,Context,Method,Server,Backup,Down,Keepalive\n");
+
+    for u in upstreams {
+        for s in &u.servers {
+            output.push_str(&format!(
+                "{},{},\"{}\",{},{},{},{}\n",
+                u.name,
+                if u.stream { "stream" } else { "http" },
+                u.method.as_deref().unwrap_or(""),
+                s.address,
+                s.backup,
+                s.down,
+                u.keepalive.map(|n| n.to_string()).unwrap_or_default()
+            ));
+        }
+    }
+
+    output
+}
+
+#[derive(Tabled)]
+struct MonitoringRow {
+    #[tabled(rename = "URL")]
+    url: String,
+    #[tabled(rename = "Server Name")]
+    server_name: String,
+    #[tabled(rename = "Scheme")]
+    scheme: String,
+    #[tabled(rename = "Port")]
+    port: u16,
+}
+
+pub fn format_monitoring_targets(targets: &[MonitoringTarget]) -> String {
+    if targets.is_empty() {
+        return "No monitoring targets found.".to_string();
+    }
+
+    let rows: Vec<MonitoringRow> = targets
+        .iter()
+        .map(|t| MonitoringRow {
+            url: t.url.clone(),
+            server_name: t.server_name.clone(),
+            scheme: t.scheme.clone(),
+            port: t.port,
+        })
+        .collect();
+
+    let mut table = Table::new(rows);
+    table.with(Style::rounded());
+    table.to_string()
+}
+
+pub fn format_monitoring_targets_csv(targets: &[MonitoringTarget]) -> String {
+    let mut output = String::from("URL,Server Name,Scheme,Port\n");
+
+    for target in targets {
+        output.push_str(&format!(
+            "{},{},{},{}\n",
+            target.url, target.server_name, target.scheme, target.port
+        ));
+    }
+
+    output
+}
+
+#[derive(Tabled)]
+struct CachingRow {
+    #[tabled(rename = "Server")]
+    server: String,
+    #[tabled(rename = "Location")]
+    location: String,
+    #[tabled(rename = "Expires")]
+    expires: String,
+    #[tabled(rename = "Cache-Control")]
+    cache_control: String,
+    #[tabled(rename = "ETag")]
+    etag: String,
+    #[tabled(rename = "open_file_cache")]
+    open_file_cache: String,
+}
+
+fn or_dash(value: Option<&str>) -> String {
+    value.unwrap_or("-").to_string()
+}
+
+pub fn format_location_caching(locations: &[&LocationCaching]) -> String {
+    if locations.is_empty() {
+        return "No static locations found.".to_string();
+    }
+
+    let rows: Vec<CachingRow> = locations
+        .iter()
+        .map(|l| CachingRow {
+            server: l.server_name.clone(),
+            location: l.location.clone(),
+            expires: or_dash(l.expires.as_deref()),
+            cache_control: or_dash(l.cache_control.as_deref()),
+            etag: if l.etag == Some(false) { "off" } else { "on" }.to_string(),
+            open_file_cache: or_dash(l.open_file_cache.as_deref()),
+        })
+        .collect();
+
+    let mut table = Table::new(rows);
+    table.with(Style::rounded());
+    table.to_string()
+}
+
+pub fn format_location_caching_csv(locations: &[&LocationCaching]) -> String {
+    let mut output = String::from("Server,Location,Expires,Cache-Control,ETag,open_file_cache\n");
+
+    for l in locations {
+        output.push_str(&format!(
+            "{},{},{},\"{}\",{},{}\n",
+            l.server_name,
+            l.location,
+            l.expires.as_deref().unwrap_or(""),
+            l.cache_control
+                .as_deref()
+                .unwrap_or("")
+                .replace('"', "\"\""),
+            if l.etag == Some(false) { "off" } else { "on" },
+            l.open_file_cache.as_deref().unwrap_or("")
+        ));
+    }
+
+    output
+}
+
+#[derive(Tabled)]
+struct VersionRow {
+    #[tabled(rename = "Since")]
+    version: String,
+    #[tabled(rename = "Feature")]
+    feature: String,
+    #[tabled(rename = "Line")]
+    line: usize,
+}
+
+pub fn format_version_requirements(requirements: &[VersionRequirement]) -> String {
+    let rows: Vec<VersionRow> = requirements
+        .iter()
+        .map(|r| VersionRow {
+            version: r.version.to_string(),
+            feature: r.feature(),
+            line: r.span.line,
+        })
+        .collect();
+
+    let mut table = Table::new(rows);
+    table.with(Style::rounded());
+    table.to_string()
+}
+
+#[derive(Tabled)]
+struct SecretRow {
+    #[tabled(rename = "Line")]
+    line: usize,
+    #[tabled(rename = "Kind")]
+    kind: String,
+    #[tabled(rename = "Directive")]
+    directive: String,
+    #[tabled(rename = "Value")]
+    masked: String,
+}
+
+pub fn format_secrets(secrets: &[EmbeddedSecret]) -> String {
+    let rows: Vec<SecretRow> = secrets
+        .iter()
+        .map(|s| SecretRow {
+            line: s.span.line,
+            kind: s.kind.to_string(),
+            directive: s.directive.clone(),
+            masked: s.masked.clone(),
+        })
+        .collect();
+
+    let mut table = Table::new(rows);
+    table.with(Style::rounded());
+    table.to_string()
+}
+
+#[derive(Tabled)]
+struct ModuleRow {
+    #[tabled(rename = "Module")]
+    module: String,
+    #[tabled(rename = "Uses")]
+    uses: usize,
+    #[tabled(rename = "First Line")]
+    line: usize,
+    #[tabled(rename = "Directives")]
+    directives: String,
+    #[tabled(rename = "load_module")]
+    loaded: String,
+    #[tabled(rename = "Compiled")]
+    compiled: String,
+}
+
+pub fn format_modules(modules: &[ModuleUsage], compiled: Option<&CompiledModules>) -> String {
+    let yes_no = |b: bool| if b { "yes" } else { "no" }.to_string();
+    let rows: Vec<ModuleRow> = modules
+        .iter()
+        .map(|m| ModuleRow {
+            module: m.module.clone(),
+            uses: m.uses,
+            line: m.span.line,
+            directives: m.directives.join(", "),
+            loaded: yes_no(m.loaded),
+            compiled: compiled.map_or_else(|| "?".to_string(), |c| yes_no(c.has(&m.module))),
+        })
+        .collect();
+
+    let mut table = Table::new(rows);
+    table.with(Style::rounded());
+    table.to_string()
+}
+
+#[derive(Tabled)]
+struct UpstreamUseRow {
+    #[tabled(rename = "Line")]
+    line: usize,
+    #[tabled(rename = "Server")]
+    server: String,
+    #[tabled(rename = "Location")]
+    location: String,
+    #[tabled(rename = "Upstream")]
+    upstream: String,
+    #[tabled(rename = "HTTP")]
+    http_version: String,
+    #[tabled(rename = "Connection")]
+    connection: String,
+    #[tabled(rename = "Reuse")]
+    reuse: String,
+}
+
+pub fn format_upstream_uses(uses: &[UpstreamUse]) -> String {
+    let rows: Vec<UpstreamUseRow> = uses
+        .iter()
+        .map(|u| UpstreamUseRow {
+            line: u.line,
+            server: u.server_name.clone(),
+            location: u.location.clone(),
+            upstream: u.upstream.clone(),
+            http_version: u
+                .http_version
+                .clone()
+                .unwrap_or_else(|| "fastcgi".to_string()),
+            connection: match (&u.connection_header, u.keep_conn) {
+                (_, Some(true)) => "keep_conn".to_string(),
+                (_, Some(false)) => "-".to_string(),
+                (Some(value), None) => format!("\"{value}\""),
+                (None, None) => "close (default)".to_string(),
+            },
+            reuse: if u.reuses_connections { "✓" } else { "✗" }.to_string(),
+        })
+        .collect();
+
+    let mut table = Table::new(rows);
+    table.with(Style::rounded());
+    table.to_string()
+}
+
+#[derive(Tabled)]
+struct WafRow {
+    #[tabled(rename = "Server")]
+    server: String,
+    #[tabled(rename = "Location")]
+    location: String,
+    #[tabled(rename = "Line")]
+    line: usize,
+    #[tabled(rename = "WAF")]
+    enabled: String,
+    #[tabled(rename = "Rules")]
+    rules: String,
+    #[tabled(rename = "Protected")]
+    protected: String,
+}
+
+pub fn format_waf_contexts(contexts: &[&WafContext]) -> String {
+    let rows: Vec<WafRow> = contexts
+        .iter()
+        .map(|c| {
+            let mut rules = c.rules_files.clone();
+            if c.inline_rules > 0 {
+                rules.push(format!("{} inline", c.inline_rules));
+            }
+            rules.extend(c.remote_rules.iter().cloned());
+            WafRow {
+                server: c.server_name.clone(),
+                location: c.location.clone().unwrap_or_else(|| "(server)".to_string()),
+                line: c.line,
+                enabled: if c.enabled { "on" } else { "off" }.to_string(),
+                rules: if rules.is_empty() {
+                    "-".to_string()
+                } else {
+                    rules.join(", ")
+                },
+                protected: if c.is_protected() { "✓" } else { "✗" }.to_string(),
+            }
+        })
+        .collect();
+
+    let mut table = Table::new(rows);
+    table.with(Style::rounded());
+    table.to_string()
+}
+
+#[derive(Tabled)]
+struct PathRow {
+    #[tabled(rename = "Line")]
+    line: usize,
+    #[tabled(rename = "Directive")]
+    directive: String,
+    #[tabled(rename = "Path")]
+    path: String,
+    #[tabled(rename = "Status")]
+    status: String,
+}
+
+pub fn format_paths(paths: &[&ReferencedPath]) -> String {
+    if paths.is_empty() {
+        return "No referenced paths found.".to_string();
+    }
+
+    let rows: Vec<PathRow> = paths
+        .iter()
+        .map(|p| PathRow {
+            line: p.span.line,
+            directive: p.directive.clone(),
+            path: p.path.clone(),
+            status: format!("{:?}", p.status),
+        })
+        .collect();
+
+    let mut table = Table::new(rows);
+    table.with(Style::rounded());
+    table.to_string()
+}
+
+#[derive(Tabled)]
+struct TuningRow {
+    #[tabled(rename = "Setting")]
+    setting: String,
+    #[tabled(rename = "Value")]
+    value: String,
+    #[tabled(rename = "Recommended")]
+    recommended: String,
+    #[tabled(rename = "Weight")]
+    weight: u32,
+    #[tabled(rename = "OK")]
+    ok: String,
+}
+
+pub fn format_tuning_checks(checks: &[TuningCheck]) -> String {
+    let rows: Vec<TuningRow> = checks
+        .iter()
+        .map(|c| TuningRow {
+            setting: c.setting.clone(),
+            value: match &c.configured {
+                Some(value) => value.clone(),
+                None => format!("{} (default)", c.effective),
+            },
+            recommended: c.recommended.clone(),
+            weight: c.weight,
+            ok: if c.ok { "✓" } else { "✗" }.to_string(),
+        })
+        .collect();
+
+    let mut table = Table::new(rows);
+    table.with(Style::rounded());
+    table.to_string()
+}
+
+#[derive(Tabled)]
+struct QuicServerRow {
+    #[tabled(rename = "Line")]
+    line: usize,
+    #[tabled(rename = "Server")]
+    server: String,
+    #[tabled(rename = "QUIC")]
+    quic: String,
+    #[tabled(rename = "TCP")]
+    tcp: String,
+    #[tabled(rename = "HTTP/3")]
+    http3: String,
+    #[tabled(rename = "0-RTT")]
+    early_data: String,
+    #[tabled(rename = "Retry")]
+    retry: String,
+    #[tabled(rename = "Alt-Svc")]
+    alt_svc: String,
+}
+
+pub fn format_quic_servers(servers: &[QuicServer]) -> String {
+    let ports = |listeners: &[ListenDirective]| {
+        if listeners.is_empty() {
+            return "-".to_string();
+        }
+        listeners
+            .iter()
+            .map(|l| l.port.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let mark = |on: bool| if on { "✓" } else { "✗" }.to_string();
+
+    let rows: Vec<QuicServerRow> = servers
+        .iter()
+        .map(|s| QuicServerRow {
+            line: s.line,
+            server: s.server_name.clone(),
+            quic: ports(&s.quic_listeners),
+            tcp: ports(&s.tcp_listeners),
+            http3: mark(s.serves_http3()),
+            early_data: mark(s.early_data),
+            retry: mark(s.quic_retry),
+            alt_svc: s.alt_svc.clone().unwrap_or_else(|| "-".to_string()),
+        })
+        .collect();
+
+    let mut table = Table::new(rows);
+    table.with(Style::rounded());
+    table.to_string()
+}
+
+#[derive(Tabled)]
+struct RealIpRow {
+    #[tabled(rename = "Line")]
+    line: usize,
+    #[tabled(rename = "Server")]
+    server: String,
+    #[tabled(rename = "PROXY Ports")]
+    proxy_protocol: String,
+    #[tabled(rename = "Trusted")]
+    trusted: String,
+    #[tabled(rename = "Header")]
+    header: String,
+    #[tabled(rename = "Client IP")]
+    client_ip: String,
+}
+
+pub fn format_real_ip_servers(servers: &[RealIpServer]) -> String {
+    let rows: Vec<RealIpRow> = servers
+        .iter()
+        .map(|s| RealIpRow {
+            line: s.line,
+            server: if s.stream {
+                format!("{} (stream)", s.server_name)
+            } else {
+                s.server_name.clone()
+            },
+            proxy_protocol: if s.uses_proxy_protocol() {
+                s.proxy_protocol_ports
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            } else {
+                "-".to_string()
+            },
+            trusted: if s.trusted.is_empty() {
+                "-".to_string()
+            } else {
+                s.trusted.join(", ")
+            },
+            header: s.real_ip_header.clone().unwrap_or_else(|| "-".to_string()),
+            client_ip: if s.resolves_client_ip() { "✓" } else { "✗" }.to_string(),
+        })
+        .collect();
+
+    let mut table = Table::new(rows);
+    table.with(Style::rounded());
+    table.to_string()
+}
+
+#[derive(Tabled)]
+struct AffinityUseRow {
+    #[tabled(rename = "Line")]
+    line: usize,
+    #[tabled(rename = "Server")]
+    server: String,
+    #[tabled(rename = "Location")]
+    location: String,
+    #[tabled(rename = "Upstream")]
+    upstream: String,
+    #[tabled(rename = "Affinity")]
+    affinity: String,
+    #[tabled(rename = "Cache")]
+    cache: String,
+}
+
+/// Format locations using upstreams with session affinity as a table
+pub fn format_affinity_uses(uses: &[AffinityUse]) -> String {
+    let rows: Vec<AffinityUseRow> = uses
+        .iter()
+        .map(|u| AffinityUseRow {
+            line: u.line,
+            server: u.server_name.clone(),
+            location: u.location.clone(),
+            upstream: u.upstream.clone(),
+            affinity: u.affinity.to_string(),
+            cache: match &u.cache_zone {
+                None => "-".to_string(),
+                Some(zone) if u.shares_cache() => format!("{zone} ⚠"),
+                Some(zone) => format!("{zone} (keyed)"),
+            },
+        })
+        .collect();
+
+    let mut table = Table::new(rows);
+    table.with(Style::rounded());
+    table.to_string()
+}
+
+#[derive(Tabled)]
+struct GrpcLocationRow {
+    #[tabled(rename = "Line")]
+    line: usize,
+    #[tabled(rename = "Server")]
+    server: String,
+    #[tabled(rename = "Location")]
+    location: String,
+    #[tabled(rename = "Backend")]
+    target: String,
+    #[tabled(rename = "HTTP/2")]
+    http2: String,
+    #[tabled(rename = "Read Timeout")]
+    read_timeout: String,
+}
+
+/// Format `grpc_pass` locations as a table
+pub fn format_grpc_locations(locations: &[GrpcLocation]) -> String {
+    let rows: Vec<GrpcLocationRow> = locations
+        .iter()
+        .map(|l| GrpcLocationRow {
+            line: l.line,
+            server: l.server_name.clone(),
+            location: l.location.clone(),
+            target: l.target.clone(),
+            http2: if l.http2 { "✓" } else { "✗" }.to_string(),
+            read_timeout: l
+                .read_timeout
+                .clone()
+                .unwrap_or_else(|| "60s (default)".to_string()),
+        })
+        .collect();
+
+    let mut table = Table::new(rows);
+    table.with(Style::rounded());
+    table.to_string()
+}
+
+#[derive(Tabled)]
+struct OffloadTargetRow {
+    #[tabled(rename = "Line")]
+    line: usize,
+    #[tabled(rename = "Server")]
+    server: String,
+    #[tabled(rename = "Location")]
+    location: String,
+    #[tabled(rename = "Storage")]
+    storage: String,
+    #[tabled(rename = "Triggered By")]
+    triggered_by: String,
+}
+
+/// Format `X-Accel-Redirect` offload locations as a table
+pub fn format_offload_targets(targets: &[OffloadTarget]) -> String {
+    let rows: Vec<OffloadTargetRow> = targets
+        .iter()
+        .map(|t| OffloadTargetRow {
+            line: t.line,
+            server: t.server_name.clone(),
+            location: t.location.clone(),
+            storage: format!(
+                "{} ({})",
+                t.storage.display(),
+                if t.alias { "alias" } else { "root" }
+            ),
+            triggered_by: if t.triggered_by.is_empty() {
+                "-".to_string()
+            } else {
+                t.triggered_by
+                    .iter()
+                    .map(|s| format!("{} → {}", s.location, s.backend))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            },
+        })
+        .collect();
+
+    let mut table = Table::new(rows);
+    table.with(Style::rounded());
+    table.to_string()
+}
+
+#[derive(Tabled)]
+struct LocationErrorsRow {
+    #[tabled(rename = "Line")]
+    line: usize,
+    #[tabled(rename = "Server")]
+    server: String,
+    #[tabled(rename = "Location")]
+    location: String,
+    #[tabled(rename = "Upstream")]
+    upstream: String,
+    #[tabled(rename = "Requests")]
+    requests: usize,
+    #[tabled(rename = "4xx")]
+    client_errors: String,
+    #[tabled(rename = "5xx")]
+    server_errors: String,
+}
+
+/// Format per-location request and error counts as a table
+pub fn format_location_errors(locations: &[LocationErrors]) -> String {
+    let rows: Vec<LocationErrorsRow> = locations
+        .iter()
+        .map(|l| LocationErrorsRow {
+            line: l.line,
+            server: l.server.clone(),
+            location: l.location.clone(),
+            upstream: l.upstream.clone().unwrap_or_else(|| "-".to_string()),
+            requests: l.requests,
+            client_errors: format!(
+                "{} ({:.1}%)",
+                l.client_errors,
+                l.client_error_rate() * 100.0
+            ),
+            server_errors: format!(
+                "{} ({:.1}%)",
+                l.server_errors,
+                l.server_error_rate() * 100.0
+            ),
+        })
+        .collect();
+
+    let mut table = Table::new(rows);
+    table.with(Style::rounded());
+    table.to_string()
+}
+
+#[derive(Tabled)]
+struct CoverageRow {
+    #[tabled(rename = "Line")]
+    line: usize,
+    #[tabled(rename = "Server")]
+    server: String,
+    #[tabled(rename = "Location")]
+    location: String,
+    #[tabled(rename = "Requests")]
+    requests: String,
+}
+
+/// Format servers and locations with the number of test requests reaching
+/// them, each server followed by its locations
+pub fn format_coverage(servers: &[ServerCoverage], locations: &[LocationCoverage]) -> String {
+    let count = |requests: usize, named: bool| match requests {
+        0 if named => "- (named)".to_string(),
+        0 => "✗ 0".to_string(),
+        n => n.to_string(),
+    };
+    let mut rows: Vec<CoverageRow> = servers
+        .iter()
+        .map(|s| CoverageRow {
+            line: s.line,
+            server: s.name.clone(),
+            location: String::new(),
+            requests: count(s.requests, false),
+        })
+        .collect();
+    rows.extend(locations.iter().map(|l| CoverageRow {
+        line: l.line,
+        server: l.server.clone(),
+        location: l.pattern.clone(),
+        requests: count(l.requests, l.is_named()),
+    }));
+    rows.sort_by_key(|row| row.line);
+
+    let mut table = Table::new(rows);
+    table.with(Style::rounded());
+    table.to_string()
+}
+
+#[derive(Tabled)]
+struct CertificateCoverageRow {
+    #[tabled(rename = "Line")]
+    line: usize,
+    #[tabled(rename = "Server Name")]
+    name: String,
+    #[tabled(rename = "Coverage")]
+    coverage: String,
+    #[tabled(rename = "Certificates")]
+    certificates: String,
+}
+
+/// Format server names and how their certificates cover them as a table
+pub fn format_certificate_coverage(names: &[ServerNameCoverage]) -> String {
+    let rows: Vec<CertificateCoverageRow> = names
+        .iter()
+        .map(|n| CertificateCoverageRow {
+            line: n.line,
+            name: n.name.clone(),
+            coverage: match n.coverage {
+                NameCoverage::Uncovered => format!("✗ {}", n.coverage),
+                _ => n.coverage.to_string(),
+            },
+            certificates: n.certificates.join("\n"),
+        })
+        .collect();
+
+    let mut table = Table::new(rows);
+    table.with(Style::rounded());
+    table.to_string()
+}
+
+#[derive(Tabled)]
+struct GeoipDatabaseRow {
+    #[tabled(rename = "Line")]
+    line: usize,
+    #[tabled(rename = "Database")]
+    database: String,
+    #[tabled(rename = "Exists")]
+    exists: String,
+    #[tabled(rename = "Variables")]
+    variables: String,
+}
+
+/// Format GeoIP databases and their variables as a table
+pub fn format_geoip_databases(databases: &[GeoipDatabase]) -> String {
+    let rows: Vec<GeoipDatabaseRow> = databases
+        .iter()
+        .map(|d| GeoipDatabaseRow {
+            line: d.line,
+            database: format!("{} ({})", d.path, d.directive),
+            exists: match d.exists {
+                Some(true) => "✓",
+                Some(false) => "✗",
+                None => "?",
+            }
+            .to_string(),
+            variables: d
+                .variables
+                .iter()
+                .map(|v| {
+                    // Legacy databases define their variables whether used or not
+                    if v.used || d.directive != "geoip2" {
+                        v.name.clone()
+                    } else {
+                        format!("{} (unused)", v.name)
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+        })
+        .collect();
+
+    let mut table = Table::new(rows);
+    table.with(Style::rounded());
+    table.to_string()
+}
+
+#[derive(Tabled)]
+struct BotFilterRow {
+    #[tabled(rename = "Line")]
+    line: usize,
+    #[tabled(rename = "Server")]
+    server: String,
+    #[tabled(rename = "Mechanism")]
+    mechanism: String,
+    #[tabled(rename = "Scope")]
+    scope: String,
+    #[tabled(rename = "Checks")]
+    checks: String,
+}
+
+/// Format the bot filtering of each server as a table
+pub fn format_bot_filters(servers: &[BotFilter]) -> String {
+    let rows: Vec<BotFilterRow> = servers
+        .iter()
+        .map(|s| BotFilterRow {
+            line: s.line,
+            server: s.server_name.clone(),
+            mechanism: if s.is_filtered() {
+                s.mechanisms()
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            } else {
+                "✗ none".to_string()
+            },
+            scope: if s.is_server_wide() {
+                "server".to_string()
+            } else if s.is_filtered() {
+                s.rules
+                    .iter()
+                    .filter_map(|r| r.location.clone())
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            } else {
+                "-".to_string()
+            },
+            checks: s
+                .rules
+                .iter()
+                .map(|r| {
+                    let test = match &r.map {
+                        Some(map) => format!("${map}"),
+                        None if r.patterns.is_empty() => "$http_user_agent".to_string(),
+                        None => r
+                            .patterns
+                            .iter()
+                            .map(|p| if p.is_empty() { "\"\"" } else { p.as_str() })
+                            .collect::<Vec<_>>()
+                            .join(" "),
+                    };
+                    format!("{test} → {}", r.action)
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+        })
+        .collect();
+
+    let mut table = Table::new(rows);
+    table.with(Style::rounded());
+    table.to_string()
+}
+
+#[derive(Tabled)]
+struct HeaderOverrideRow {
+    #[tabled(rename = "Line")]
+    line: usize,
+    #[tabled(rename = "Server")]
+    server: String,
+    #[tabled(rename = "Block")]
+    block: String,
+    #[tabled(rename = "Inherits From")]
+    inherited_from: String,
+    #[tabled(rename = "Dropped")]
+    dropped: String,
+}
+
+/// Format blocks that drop inherited `add_header`s as a table
+pub fn format_header_overrides(overrides: &[HeaderOverride]) -> String {
+    let rows: Vec<HeaderOverrideRow> = overrides
+        .iter()
+        .map(|o| {
+            let security: Vec<&String> = o.dropped_security().collect();
+            HeaderOverrideRow {
+                line: o.line,
+                server: o.server_name.clone(),
+                block: o.path.to_string(),
+                inherited_from: format!("{} (line {})", o.inherited_from, o.inherited_line),
+                dropped: o
+                    .dropped
+                    .iter()
+                    .map(|name| {
+                        if security.contains(&name) {
+                            format!("{name} ⚠")
+                        } else {
+                            name.clone()
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            }
+        })
+        .collect();
+
+    let mut table = Table::new(rows);
+    table.with(Style::rounded());
+    table.to_string()
+}
+
+#[derive(Tabled)]
+struct LogCompatRow {
+    #[tabled(rename = "Line")]
+    line: usize,
+    #[tabled(rename = "Format")]
+    name: String,
+    #[tabled(rename = "Kind")]
+    kind: String,
+    #[tabled(rename = "Uses")]
+    uses: usize,
+    #[tabled(rename = "Readable by")]
+    compatible: String,
+}
+
+pub fn format_log_compat(formats: &[FormatCompat]) -> String {
+    let rows: Vec<LogCompatRow> = formats
+        .iter()
+        .map(|f| LogCompatRow {
+            line: f.line,
+            name: f.name.clone(),
+            kind: match (&f.json_keys, f.escape.as_deref()) {
+                (Some(_), Some(escape)) => format!("json (escape={escape})"),
+                (Some(_), None) => "json".to_string(),
+                (None, _) => "line".to_string(),
+            },
+            uses: f.uses,
+            compatible: if f.compatible.is_empty() {
+                "-".to_string()
+            } else {
+                f.compatible.join(", ")
+            },
+        })
+        .collect();
+
+    let mut table = Table::new(rows);
+    table.with(Style::rounded());
+    table.to_string()
+}
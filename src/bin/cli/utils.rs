@@ -3,8 +3,9 @@
 use crate::cli::args::{ColorChoice, GlobalOpts};
 use anyhow::Result;
 use colored::control;
-use nginx_discovery::system;
-use std::path::PathBuf;
+use colored::Colorize;
+use nginx_discovery::{system, NginxDiscovery};
+use std::path::{Path, PathBuf};
 
 /// Setup color output based on user preference
 pub fn setup_colors(choice: ColorChoice) {
@@ -22,6 +23,18 @@ pub fn setup_colors(choice: ColorChoice) {
     }
 }
 
+/// Load a configuration file in the `--encoding` encoding, reporting
+/// bytes that did not decode
+pub fn load_config(global: &GlobalOpts, path: &Path) -> nginx_discovery::Result<NginxDiscovery> {
+    let discovery = NginxDiscovery::from_config_file_with_encoding(path, global.encoding)?;
+    if !global.quiet {
+        for warning in discovery.encoding_warnings() {
+            eprintln!("{} {}: {warning}", "warning:".yellow(), path.display());
+        }
+    }
+    Ok(discovery)
+}
+
 /// Find NGINX configuration file
 pub fn find_config(global: &GlobalOpts) -> Result<PathBuf> {
     if let Some(ref path) = global.config {
@@ -40,6 +53,8 @@ pub fn find_config(global: &GlobalOpts) -> Result<PathBuf> {
         "/usr/local/nginx/conf/nginx.conf",
         "/usr/local/etc/nginx/nginx.conf",
         "nginx.conf",
+        #[cfg(windows)]
+        r"C:\nginx\conf\nginx.conf",
     ];
 
     for path in &common_paths {
@@ -52,17 +67,13 @@ pub fn find_config(global: &GlobalOpts) -> Result<PathBuf> {
         }
     }
 
-    // Try using nginx -V to find config
-    if let Ok(_nginx_path) = system::find_nginx() {
-        if global.verbose {
-            eprintln!("Trying to detect config from nginx binary...");
-        }
-
-        // Parse nginx -V output to find --conf-path
-        // This is a simplified version - full implementation would parse the output
-        let default_path = PathBuf::from("/etc/nginx/nginx.conf");
-        if default_path.exists() {
-            return Ok(default_path);
+    // Ask the nginx binary where its configuration is
+    if global.verbose {
+        eprintln!("Trying to detect config from nginx binary...");
+    }
+    if let Ok(install) = system::nginx_install() {
+        if install.conf_path.exists() {
+            return Ok(install.conf_path);
         }
     }
 
@@ -20,6 +20,24 @@ fn main() -> Result<()> {
         Commands::Analyze(args) => cli::commands::analyze::run(args, &cli.global)?,
         Commands::Export(args) => cli::commands::export::run(args, &cli.global)?,
         Commands::Doctor(args) => cli::commands::doctor::run(args, &cli.global)?,
+        Commands::Ci(args) => cli::commands::ci::run(args, &cli.global)?,
+        Commands::Explain(args) => cli::commands::explain::run(&args, &cli.global)?,
+        Commands::Diff(args) => cli::commands::diff::run(&args, &cli.global)?,
+        Commands::Replay(args) => cli::commands::replay::run(&args, &cli.global)?,
+        Commands::Query(args) => cli::commands::query::run(&args, &cli.global)?,
+        Commands::Includes(args) => cli::commands::includes::run(&args, &cli.global)?,
+        Commands::Scan(args) => cli::commands::scan::run(&args, &cli.global)?,
+        Commands::Logs(args) => cli::commands::logs::run(&args, &cli.global)?,
+        Commands::Backup(args) => cli::commands::backup::run(&args, &cli.global)?,
+        Commands::Restore(args) => cli::commands::restore::run(&args, &cli.global)?,
+        #[cfg(feature = "network")]
+        Commands::Network(args) => cli::commands::network::run(*args, &cli.global)?,
+        #[cfg(feature = "network")]
+        Commands::Daemon(args) => cli::commands::daemon::run(&args, &cli.global)?,
+        #[cfg(feature = "migrate")]
+        Commands::Migrate(args) => cli::commands::migrate::run(&args, &cli.global)?,
+        #[cfg(feature = "lsp")]
+        Commands::Lsp => cli::commands::lsp::run()?,
         Commands::Interactive => cli::commands::interactive::run(&cli.global)?,
     }
 
@@ -0,0 +1,459 @@
+//! Pre-deployment validation for CI pipelines
+//!
+//! [`validate`] runs everything that can be checked without a running
+//! nginx — parsing, directive context validation, lint rules, conflict
+//! detection and, optionally, offline certificate and document root checks
+//! — and returns a single [`CiReport`] with a pass/fail verdict and the
+//! findings.
+//!
+//! # Examples
+//!
+//! ```
+//! use nginx_discovery::ci::{self, CiInput, CiOptions};
+//!
+//! let report = ci::validate(
+//!     CiInput::text("http { server { listen 80; root /a; root /b; } }"),
+//!     &CiOptions::default(),
+//! );
+//!
+//! assert!(!report.passed);
+//! assert_eq!(report.findings[0].rule, "duplicate_directive");
+//! ```
+
+use crate::analyze::NginxVersion;
+use crate::ast::Span;
+use crate::lint::rules::{CertificateFiles, DeprecatedDirective, DocumentRoots};
+use crate::lint::{Finding, Linter, Policy, Severity};
+use crate::parser::{read_source, EncodingWarning, SourceEncoding};
+use std::path::{Path, PathBuf};
+
+/// Configuration to validate: a file on disk or configuration text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CiInput {
+    /// Read the configuration from a file.
+    Path(PathBuf),
+    /// Validate configuration text directly.
+    Text(String),
+}
+
+impl CiInput {
+    /// Validate configuration text.
+    #[must_use]
+    pub fn text(text: impl Into<String>) -> Self {
+        Self::Text(text.into())
+    }
+}
+
+impl From<PathBuf> for CiInput {
+    fn from(path: PathBuf) -> Self {
+        Self::Path(path)
+    }
+}
+
+impl From<&Path> for CiInput {
+    fn from(path: &Path) -> Self {
+        Self::Path(path.to_path_buf())
+    }
+}
+
+impl From<&PathBuf> for CiInput {
+    fn from(path: &PathBuf) -> Self {
+        Self::Path(path.clone())
+    }
+}
+
+/// Options for [`validate`].
+#[derive(Debug, Clone)]
+pub struct CiOptions {
+    /// Run the default lint rules (default: true).
+    pub lint: bool,
+
+    /// Check that referenced certificate and key files exist and are
+    /// valid PEM (default: false).
+    pub check_certs: bool,
+
+    /// Check that the document root of every location exists
+    /// (default: false).
+    pub check_roots: bool,
+
+    /// Findings at or above this severity fail the run (default: error).
+    pub fail_on: Severity,
+
+    /// Lint rule ids to skip.
+    pub skip_rules: Vec<String>,
+
+    /// nginx release the configuration is deployed to; deprecation
+    /// findings are limited to that release (default: report all).
+    pub nginx_version: Option<NginxVersion>,
+
+    /// Policy packs to check in addition to the lint rules.
+    pub policies: Vec<Policy>,
+}
+
+impl Default for CiOptions {
+    fn default() -> Self {
+        Self {
+            lint: true,
+            check_certs: false,
+            check_roots: false,
+            fail_on: Severity::Error,
+            skip_rules: Vec::new(),
+            nginx_version: None,
+            policies: Vec::new(),
+        }
+    }
+}
+
+impl CiOptions {
+    /// Enable or disable the offline certificate checks.
+    #[must_use]
+    pub fn with_check_certs(mut self, check_certs: bool) -> Self {
+        self.check_certs = check_certs;
+        self
+    }
+
+    /// Enable or disable the document root existence check.
+    #[must_use]
+    pub fn with_check_roots(mut self, check_roots: bool) -> Self {
+        self.check_roots = check_roots;
+        self
+    }
+
+    /// Set the severity that fails the run.
+    #[must_use]
+    pub fn with_fail_on(mut self, fail_on: Severity) -> Self {
+        self.fail_on = fail_on;
+        self
+    }
+
+    /// Skip the given lint rules.
+    #[must_use]
+    pub fn with_skip_rules<S: Into<String>>(mut self, rules: impl IntoIterator<Item = S>) -> Self {
+        self.skip_rules.extend(rules.into_iter().map(Into::into));
+        self
+    }
+
+    /// Check deprecations against a specific nginx release.
+    #[must_use]
+    pub fn with_nginx_version(mut self, version: NginxVersion) -> Self {
+        self.nginx_version = Some(version);
+        self
+    }
+
+    /// Also check the given policy packs.
+    #[must_use]
+    pub fn with_policies(mut self, policies: impl IntoIterator<Item = Policy>) -> Self {
+        self.policies.extend(policies);
+        self
+    }
+}
+
+/// Consolidated result of a CI validation run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CiReport {
+    /// File that was validated, `None` for text input.
+    pub file: Option<PathBuf>,
+
+    /// Whether no finding reached [`CiReport::fail_on`].
+    pub passed: bool,
+
+    /// Severity threshold the verdict was computed with.
+    pub fail_on: Severity,
+
+    /// All findings, ordered by location.
+    pub findings: Vec<Finding>,
+}
+
+impl CiReport {
+    /// Number of findings with the given severity.
+    #[must_use]
+    pub fn count(&self, severity: Severity) -> usize {
+        self.findings
+            .iter()
+            .filter(|f| f.severity == severity)
+            .count()
+    }
+
+    /// Number of errors.
+    #[must_use]
+    pub fn errors(&self) -> usize {
+        self.count(Severity::Error)
+    }
+
+    /// Number of warnings.
+    #[must_use]
+    pub fn warnings(&self) -> usize {
+        self.count(Severity::Warning)
+    }
+
+    /// Findings that caused the run to fail.
+    pub fn failures(&self) -> impl Iterator<Item = &Finding> {
+        self.findings.iter().filter(|f| f.severity >= self.fail_on)
+    }
+}
+
+/// Validate a configuration for deployment.
+///
+/// Read and parse errors are reported as findings (rules `config_file` and
+/// `parse`), so this never fails; check [`CiReport::passed`]. Bytes of a
+/// file that are not valid UTF-8 are replaced and reported as `encoding`
+/// warnings.
+#[must_use]
+pub fn validate(input: impl Into<CiInput>, options: &CiOptions) -> CiReport {
+    let mut encoding = Vec::new();
+    let (file, text) = match input.into() {
+        CiInput::Path(path) => match read_source(&path, SourceEncoding::Utf8) {
+            Ok(source) => {
+                if !options.skip_rules.iter().any(|id| id == "encoding") {
+                    encoding = source.warnings.iter().map(encoding_finding).collect();
+                }
+                (Some(path), source.text)
+            }
+            Err(e) => {
+                let finding = Finding::new(
+                    "config_file",
+                    Severity::Error,
+                    format!("cannot read {}: {e}", path.display()),
+                );
+                return report(Some(path), vec![finding], options.fail_on);
+            }
+        },
+        CiInput::Text(text) => (None, text),
+    };
+
+    let mut findings = match crate::parse(&text) {
+        Ok(config) => {
+            let mut linter = if options.lint {
+                Linter::with_default_rules().skip(&options.skip_rules)
+            } else {
+                Linter::new()
+            };
+
+            if let Some(version) = options.nginx_version {
+                if linter.contains("deprecated_directive") {
+                    linter.register(Box::new(DeprecatedDirective::for_version(version)));
+                }
+            }
+
+            let base_dir = file
+                .as_deref()
+                .and_then(Path::parent)
+                .map(Path::to_path_buf)
+                .unwrap_or_default();
+            let policies: Vec<Policy> = options
+                .policies
+                .iter()
+                .copied()
+                .filter(|policy| !options.skip_rules.iter().any(|id| id == policy.id()))
+                .collect();
+            if options.check_certs {
+                linter = linter.with_rule(
+                    CertificateFiles::new()
+                        .with_base_dir(&base_dir)
+                        .require_ecdsa(policies.iter().any(|p| p.requires_ecdsa())),
+                );
+            }
+            if options.check_roots {
+                linter = linter.with_rule(DocumentRoots::new().with_base_dir(base_dir));
+            }
+            for policy in policies {
+                linter = linter.with_policy(policy);
+            }
+
+            linter.check(&config)
+        }
+        Err(e) => {
+            let mut finding = Finding::new("parse", Severity::Error, e.message());
+            if let Some((line, col)) = e.location() {
+                finding = finding.with_span(Span::at(0, line, col));
+            }
+            vec![finding]
+        }
+    };
+    findings.splice(0..0, encoding);
+
+    report(file, findings, options.fail_on)
+}
+
+/// Bytes that are not valid UTF-8, replaced before parsing
+fn encoding_finding(warning: &EncodingWarning) -> Finding {
+    Finding::new("encoding", Severity::Warning, warning.message.clone()).with_span(Span::at(
+        0,
+        warning.line,
+        1,
+    ))
+}
+
+fn report(file: Option<PathBuf>, mut findings: Vec<Finding>, fail_on: Severity) -> CiReport {
+    if let Some(file) = &file {
+        for finding in &mut findings {
+            finding.file = Some(file.clone());
+        }
+    }
+
+    CiReport {
+        passed: findings.iter().all(|f| f.severity < fail_on),
+        file,
+        fail_on,
+        findings,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_config_passes() {
+        let report = validate(
+            CiInput::text("http { server { listen 80; server_name example.com; } }"),
+            &CiOptions::default(),
+        );
+
+        assert!(report.passed);
+        assert!(report.findings.is_empty());
+        assert_eq!(report.file, None);
+    }
+
+    #[test]
+    fn test_parse_error_is_a_finding() {
+        let report = validate(CiInput::text("http {\n  server {\n"), &CiOptions::default());
+
+        assert!(!report.passed);
+        assert_eq!(report.findings.len(), 1);
+        assert_eq!(report.findings[0].rule, "parse");
+        assert!(report.findings[0].span.is_some());
+    }
+
+    #[test]
+    fn test_fail_on_threshold() {
+        let text = "http {
+          server { listen 80; server_name a.example; }
+          server { listen 80; server_name a.example; }
+        }";
+
+        let report = validate(CiInput::text(text), &CiOptions::default());
+        assert!(report.passed);
+        assert_eq!(report.warnings(), 1);
+
+        let report = validate(
+            CiInput::text(text),
+            &CiOptions::default().with_fail_on(Severity::Warning),
+        );
+        assert!(!report.passed);
+        assert_eq!(report.failures().count(), 1);
+
+        let report = validate(
+            CiInput::text(text),
+            &CiOptions::default().with_skip_rules(["conflicts"]),
+        );
+        assert!(report.findings.is_empty());
+    }
+
+    #[test]
+    fn test_nginx_version_limits_deprecations() {
+        let text = "http { server { listen 443 ssl http2; ssl on; } }";
+
+        let report = validate(CiInput::text(text), &CiOptions::default());
+        assert_eq!(report.warnings(), 2);
+
+        let options = CiOptions::default().with_nginx_version(NginxVersion::new(1, 26, 0));
+        let report = validate(CiInput::text(text), &options);
+        assert_eq!(report.warnings(), 1);
+        assert_eq!(report.errors(), 1);
+        assert!(!report.passed);
+
+        let options = options.with_skip_rules(["deprecated_directive"]);
+        assert!(validate(CiInput::text(text), &options).findings.is_empty());
+    }
+
+    #[test]
+    fn test_policies() {
+        let text = "http { server { listen 443 ssl; ssl_protocols TLSv1.1 TLSv1.2; } }";
+        assert!(validate(CiInput::text(text), &CiOptions::default())
+            .findings
+            .is_empty());
+
+        let options = CiOptions::default()
+            .with_policies([Policy::MozillaIntermediateTls])
+            .with_fail_on(Severity::Warning);
+        let report = validate(CiInput::text(text), &options);
+        assert!(!report.passed);
+        assert!(report
+            .findings
+            .iter()
+            .all(|f| f.rule == "mozilla-intermediate-tls"));
+
+        let options = options.with_skip_rules(["mozilla-intermediate-tls"]);
+        assert!(validate(CiInput::text(text), &options).findings.is_empty());
+    }
+
+    #[test]
+    fn test_file_input_and_certs() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nginx.conf");
+        std::fs::write(
+            &path,
+            "http {\n  server {\n    listen 443 ssl;\n    ssl_certificate certs/site.pem;\n  }\n}\n",
+        )
+        .unwrap();
+
+        let report = validate(&path, &CiOptions::default());
+        assert!(report.passed);
+
+        let report = validate(&path, &CiOptions::default().with_check_certs(true));
+        assert!(!report.passed);
+        assert_eq!(report.findings[0].rule, "certificate_files");
+        assert_eq!(report.findings[0].file.as_deref(), Some(path.as_path()));
+        assert_eq!(report.findings[0].line(), Some(4));
+
+        let report = validate(dir.path().join("missing.conf"), &CiOptions::default());
+        assert_eq!(report.findings[0].rule, "config_file");
+    }
+
+    #[test]
+    fn test_check_roots() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nginx.conf");
+        std::fs::write(
+            &path,
+            "http {\n  server {\n    root html;\n    location / { }\n  }\n}\n",
+        )
+        .unwrap();
+
+        let options = CiOptions::default()
+            .with_check_roots(true)
+            .with_fail_on(Severity::Warning);
+        let report = validate(&path, &options);
+        assert_eq!(report.findings[0].rule, "missing_document_root");
+        assert_eq!(report.findings[0].line(), Some(4));
+
+        std::fs::create_dir(dir.path().join("html")).unwrap();
+        assert!(validate(&path, &options).passed);
+    }
+
+    #[test]
+    fn test_invalid_utf8_is_an_encoding_warning() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nginx.conf");
+        std::fs::write(
+            &path,
+            b"http {\n  # caf\xE9\n  server { listen 80; server_name example.com; }\n}\n",
+        )
+        .unwrap();
+
+        let report = validate(&path, &CiOptions::default());
+        assert!(report.passed);
+        assert_eq!(report.findings.len(), 1);
+        assert_eq!(report.findings[0].rule, "encoding");
+        assert_eq!(report.findings[0].severity, Severity::Warning);
+        assert_eq!(report.findings[0].line(), Some(2));
+        assert_eq!(report.findings[0].file.as_deref(), Some(path.as_path()));
+
+        let options = CiOptions::default().with_fail_on(Severity::Warning);
+        assert!(!validate(&path, &options).passed);
+        let options = options.with_skip_rules(["encoding"]);
+        assert!(validate(&path, &options).findings.is_empty());
+    }
+}
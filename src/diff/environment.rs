@@ -0,0 +1,582 @@
+//! Comparison report for two environments
+//!
+//! [`compare_environments`] groups the differences between, say, staging
+//! and production by category so that a change review can go through
+//! servers, routing, TLS, upstreams and timeouts one at a time, and renders
+//! them as Markdown or HTML.
+
+use super::{semantic_compare, Change, ChangeKind};
+use crate::ast::{Config, Directive};
+use crate::extract;
+use crate::NginxDiscovery;
+use std::fmt::{self, Write as _};
+
+/// Section of an [`EnvironmentReport`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Category {
+    /// Servers added or removed, listen addresses, roots and index files
+    Servers,
+    /// Locations added or removed and where they send requests
+    Routing,
+    /// `ssl_*` directives
+    Tls,
+    /// Upstream blocks and their servers
+    Upstreams,
+    /// `*_timeout` directives
+    Timeouts,
+}
+
+impl Category {
+    /// Every category, in report order
+    pub const ALL: [Category; 5] = [
+        Self::Servers,
+        Self::Routing,
+        Self::Tls,
+        Self::Upstreams,
+        Self::Timeouts,
+    ];
+
+    /// Heading of the category's section
+    #[must_use]
+    pub fn title(self) -> &'static str {
+        match self {
+            Self::Servers => "Servers",
+            Self::Routing => "Routing",
+            Self::Tls => "TLS",
+            Self::Upstreams => "Upstreams",
+            Self::Timeouts => "Timeouts",
+        }
+    }
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.title())
+    }
+}
+
+/// Changes of one [`Category`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Section {
+    /// What the changes are about
+    pub category: Category,
+
+    /// The changes, never empty
+    pub changes: Vec<Change>,
+}
+
+/// Result of [`compare_environments`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EnvironmentReport {
+    /// Name of the first environment
+    pub from: String,
+
+    /// Name of the second environment
+    pub to: String,
+
+    /// Sections with at least one change, in [`Category::ALL`] order
+    pub sections: Vec<Section>,
+}
+
+impl EnvironmentReport {
+    /// Name the environments in headings, e.g. `staging` and `production`
+    #[must_use]
+    pub fn with_names(mut self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        self.from = from.into();
+        self.to = to.into();
+        self
+    }
+
+    /// Whether the environments are equivalent
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.sections.is_empty()
+    }
+
+    /// Number of changes across sections
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.sections.iter().map(|s| s.changes.len()).sum()
+    }
+
+    /// Changes of one category, empty if there are none
+    #[must_use]
+    pub fn changes(&self, category: Category) -> &[Change] {
+        self.sections
+            .iter()
+            .find(|s| s.category == category)
+            .map_or(&[], |s| s.changes.as_slice())
+    }
+
+    /// The report as Markdown, one `##` section per category
+    #[must_use]
+    pub fn to_markdown(&self) -> String {
+        let mut out = format!("# {} → {}\n\n", self.from, self.to);
+        if self.is_empty() {
+            out.push_str("No differences.\n");
+            return out;
+        }
+
+        let _ = writeln!(out, "{}\n", self.totals());
+        for section in &self.sections {
+            let _ = writeln!(out, "## {} ({})\n", section.category, section.changes.len());
+            for change in &section.changes {
+                let _ = writeln!(out, "- **{}** {}", change.kind, change.message);
+            }
+            out.push('\n');
+        }
+        out.pop();
+        out
+    }
+
+    /// The report as a standalone HTML page
+    ///
+    /// Each change is a list item with class `added`, `removed` or
+    /// `modified`.
+    #[must_use]
+    pub fn to_html(&self) -> String {
+        let title = format!("{} → {}", escape_html(&self.from), escape_html(&self.to));
+        let mut out = format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n\
+             <style>\n\
+             body {{ font-family: sans-serif; margin: 2em; }}\n\
+             li.added {{ color: #1a7f37; }}\n\
+             li.removed {{ color: #cf222e; }}\n\
+             li.modified {{ color: #9a6700; }}\n\
+             </style>\n</head>\n<body>\n<h1>{title}</h1>\n"
+        );
+        if self.is_empty() {
+            out.push_str("<p>No differences.</p>\n");
+        } else {
+            let _ = writeln!(out, "<p>{}</p>", self.totals());
+        }
+        for section in &self.sections {
+            let _ = writeln!(
+                out,
+                "<h2>{} ({})</h2>\n<ul>",
+                section.category,
+                section.changes.len()
+            );
+            for change in &section.changes {
+                let _ = writeln!(
+                    out,
+                    "<li class=\"{}\"><strong>{}</strong> {}</li>",
+                    change.kind,
+                    change.kind,
+                    escape_html(&change.message)
+                );
+            }
+            out.push_str("</ul>\n");
+        }
+        out.push_str("</body>\n</html>\n");
+        out
+    }
+
+    fn totals(&self) -> String {
+        let count = |kind| {
+            self.sections
+                .iter()
+                .flat_map(|s| &s.changes)
+                .filter(|c| c.kind == kind)
+                .count()
+        };
+        format!(
+            "{} change(s): {} added, {} removed, {} modified",
+            self.len(),
+            count(ChangeKind::Added),
+            count(ChangeKind::Removed),
+            count(ChangeKind::Modified)
+        )
+    }
+}
+
+/// Compare two environments category by category
+///
+/// Servers, routing and upstreams come from [`semantic_compare`]. TLS and
+/// timeout directives are compared in the block where they are written —
+/// `main`, `http`, a server or a location — so a protocol change in the
+/// `http` block is reported once rather than for every server inheriting
+/// it.
+///
+/// Environments are named after their configuration files, or `a` and `b`
+/// for configurations parsed from text; see
+/// [`EnvironmentReport::with_names`].
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::diff::{compare_environments, Category};
+/// use nginx_discovery::NginxDiscovery;
+///
+/// let staging = NginxDiscovery::from_config_text(r"
+/// http {
+///     ssl_protocols TLSv1.2 TLSv1.3;
+///     server { listen 443 ssl; server_name example.com; }
+/// }
+/// ")?;
+/// let production = NginxDiscovery::from_config_text(r"
+/// http {
+///     ssl_protocols TLSv1.2;
+///     proxy_read_timeout 120s;
+///     server { listen 443 ssl; server_name example.com; }
+/// }
+/// ")?;
+///
+/// let report = compare_environments(&staging, &production).with_names("staging", "production");
+/// assert_eq!(
+///     report.changes(Category::Tls)[0].message,
+///     "http now sets ssl_protocols TLSv1.2 instead of TLSv1.2 TLSv1.3"
+/// );
+/// assert_eq!(report.changes(Category::Timeouts).len(), 1);
+/// assert!(report.to_markdown().starts_with("# staging → production\n"));
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+#[must_use]
+pub fn compare_environments(a: &NginxDiscovery, b: &NginxDiscovery) -> EnvironmentReport {
+    let name = |discovery: &NginxDiscovery, fallback: &str| {
+        discovery
+            .config_path()
+            .map_or_else(|| fallback.to_string(), |p| p.display().to_string())
+    };
+    let (old, new) = (a.config(), b.config());
+
+    let mut sections: Vec<Section> = Category::ALL
+        .iter()
+        .map(|&category| Section {
+            category,
+            changes: Vec::new(),
+        })
+        .collect();
+    let mut push = |category: Category, change: Change| {
+        sections[category as usize].changes.push(change);
+    };
+
+    for change in semantic_compare(old, new).changes {
+        let category = if change.subject.starts_with("upstream ") {
+            Category::Upstreams
+        } else if change.subject.starts_with("location ") {
+            Category::Routing
+        } else {
+            Category::Servers
+        };
+        push(category, change);
+    }
+
+    let labels = ServerLabels::new(old, new);
+    for (category, matches) in [
+        (Category::Tls, is_tls as fn(&str) -> bool),
+        (Category::Timeouts, is_timeout),
+    ] {
+        let before = settings(old, &labels, matches);
+        let after = settings(new, &labels, matches);
+        for change in compare_settings(&before, &after) {
+            push(category, change);
+        }
+    }
+
+    sections.retain(|s| !s.changes.is_empty());
+    EnvironmentReport {
+        from: name(a, "a"),
+        to: name(b, "b"),
+        sections,
+    }
+}
+
+fn is_tls(name: &str) -> bool {
+    name.starts_with("ssl_")
+}
+
+fn is_timeout(name: &str) -> bool {
+    name.ends_with("_timeout")
+}
+
+/// Server labels matching those of [`semantic_compare`]: the first port is
+/// added when servers in either configuration share their names
+struct ServerLabels {
+    names: Vec<String>,
+}
+
+impl ServerLabels {
+    fn new(old: &Config, new: &Config) -> Self {
+        let mut names = Vec::new();
+        for config in [old, new] {
+            let mut seen: Vec<String> = Vec::new();
+            for server in config.find_directives_recursive("server") {
+                if server.is_block() {
+                    let key = server_names(server).join(" ");
+                    if seen.contains(&key) && !names.contains(&key) {
+                        names.push(key.clone());
+                    }
+                    seen.push(key);
+                }
+            }
+        }
+        Self { names }
+    }
+
+    fn label(&self, server: &Directive) -> String {
+        let names = server_names(server);
+        let primary = names.first().map_or("_", String::as_str);
+        let port = server
+            .find_children("listen")
+            .first()
+            .and_then(|l| extract::listen(l))
+            .map(|l| l.port);
+        match port {
+            Some(port) if self.names.contains(&names.join(" ")) => {
+                format!("server {primary}:{port}")
+            }
+            _ => format!("server {primary}"),
+        }
+    }
+}
+
+fn server_names(server: &Directive) -> Vec<String> {
+    server
+        .find_children("server_name")
+        .iter()
+        .flat_map(|d| d.args_as_strings())
+        .collect()
+}
+
+/// One directive as written in one block, with every value it is given
+/// there
+struct Setting {
+    scope: String,
+    name: String,
+    values: Vec<String>,
+}
+
+impl Setting {
+    fn value(&self) -> String {
+        self.values.join(", ")
+    }
+}
+
+fn settings(config: &Config, labels: &ServerLabels, matches: fn(&str) -> bool) -> Vec<Setting> {
+    let mut settings = Vec::new();
+    collect_settings(&config.directives, "main", labels, matches, &mut settings);
+    settings
+}
+
+fn collect_settings(
+    directives: &[Directive],
+    scope: &str,
+    labels: &ServerLabels,
+    matches: fn(&str) -> bool,
+    settings: &mut Vec<Setting>,
+) {
+    let server = scope
+        .find(" in ")
+        .map_or(scope, |i| &scope[i + " in ".len()..]);
+    for directive in directives {
+        if let Some(children) = directive.children() {
+            let inner = match directive.name() {
+                "http" | "stream" | "mail" => directive.name().to_string(),
+                "server" => labels.label(directive),
+                "location" => format!(
+                    "location {} in {server}",
+                    directive.args_as_strings().join(" ")
+                ),
+                "upstream" => format!("upstream {}", directive.first_arg().unwrap_or_default()),
+                _ => scope.to_string(),
+            };
+            collect_settings(children, &inner, labels, matches, settings);
+        } else if matches(directive.name()) {
+            let value = directive.args_as_strings().join(" ");
+            match settings
+                .iter_mut()
+                .find(|s| s.scope == scope && s.name == directive.name())
+            {
+                Some(setting) => setting.values.push(value),
+                None => settings.push(Setting {
+                    scope: scope.to_string(),
+                    name: directive.name().to_string(),
+                    values: vec![value],
+                }),
+            }
+        }
+    }
+}
+
+fn compare_settings(old: &[Setting], new: &[Setting]) -> Vec<Change> {
+    let find = |settings: &'_ [Setting], setting: &Setting| {
+        settings
+            .iter()
+            .position(|s| s.scope == setting.scope && s.name == setting.name)
+    };
+    let mut changes = Vec::new();
+
+    for setting in old {
+        if find(new, setting).is_none() {
+            changes.push(Change::new(
+                ChangeKind::Removed,
+                &setting.scope,
+                format!(
+                    "{} no longer sets {} (was {})",
+                    setting.scope,
+                    setting.name,
+                    setting.value()
+                ),
+            ));
+        }
+    }
+    for setting in new {
+        match find(old, setting).map(|i| &old[i]) {
+            Some(before) if before.values != setting.values => changes.push(Change::new(
+                ChangeKind::Modified,
+                &setting.scope,
+                format!(
+                    "{} now sets {} {} instead of {}",
+                    setting.scope,
+                    setting.name,
+                    setting.value(),
+                    before.value()
+                ),
+            )),
+            Some(_) => {}
+            None => changes.push(Change::new(
+                ChangeKind::Added,
+                &setting.scope,
+                format!(
+                    "{} now sets {} {}",
+                    setting.scope,
+                    setting.name,
+                    setting.value()
+                ),
+            )),
+        }
+    }
+    changes
+}
+
+fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STAGING: &str = r"
+http {
+    ssl_protocols TLSv1.2 TLSv1.3;
+    proxy_read_timeout 30s;
+    upstream app { server 10.0.0.1:8080; }
+    server {
+        listen 443 ssl;
+        server_name example.com;
+        ssl_certificate /etc/ssl/staging.pem;
+        location /api { proxy_pass http://app; proxy_connect_timeout 5s; }
+    }
+    server { listen 80; server_name old.example.com; }
+}
+";
+
+    const PRODUCTION: &str = r"
+http {
+    ssl_protocols TLSv1.2 TLSv1.3;
+    proxy_read_timeout 60s;
+    upstream app { server 10.0.0.1:8080; server 10.0.0.2:8080; }
+    server {
+        listen 443 ssl;
+        server_name example.com;
+        ssl_certificate /etc/ssl/prod-rsa.pem;
+        ssl_certificate /etc/ssl/prod-ecdsa.pem;
+        ssl_stapling on;
+        location /api { proxy_pass http://app; }
+        location /<admin> { return 403; }
+    }
+    server { listen 443 ssl; server_name new.example.com; }
+}
+";
+
+    fn report() -> EnvironmentReport {
+        compare_environments(
+            &NginxDiscovery::from_config_text(STAGING).unwrap(),
+            &NginxDiscovery::from_config_text(PRODUCTION).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_compare_environments() {
+        let report = report();
+        assert_eq!((report.from.as_str(), report.to.as_str()), ("a", "b"));
+        let messages = |category| {
+            report
+                .changes(category)
+                .iter()
+                .map(|c| c.message.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            messages(Category::Servers),
+            vec![
+                "server old.example.com removed",
+                "server new.example.com added, listening on 443 ssl",
+            ]
+        );
+        assert_eq!(
+            messages(Category::Routing),
+            vec!["location /<admin> in server example.com added"]
+        );
+        assert_eq!(
+            messages(Category::Tls),
+            vec![
+                "server example.com now sets ssl_certificate /etc/ssl/prod-rsa.pem, \
+                 /etc/ssl/prod-ecdsa.pem instead of /etc/ssl/staging.pem",
+                "server example.com now sets ssl_stapling on",
+            ]
+        );
+        assert_eq!(
+            messages(Category::Upstreams),
+            vec!["upstream app now sends traffic to 10.0.0.2:8080"]
+        );
+        assert_eq!(
+            messages(Category::Timeouts),
+            vec![
+                "location /api in server example.com no longer sets proxy_connect_timeout (was 5s)",
+                "http now sets proxy_read_timeout 60s instead of 30s",
+            ]
+        );
+        assert_eq!(report.len(), 8);
+
+        let same = NginxDiscovery::from_config_text(STAGING).unwrap();
+        let unchanged = compare_environments(&same, &same);
+        assert!(unchanged.is_empty());
+        assert_eq!(unchanged.to_markdown(), "# a → b\n\nNo differences.\n");
+    }
+
+    #[test]
+    fn test_environment_report_output() {
+        let report = report().with_names("staging", "production");
+        let markdown = report.to_markdown();
+        assert!(markdown.starts_with(
+            "# staging → production\n\n8 change(s): 3 added, 2 removed, 3 modified\n\n## Servers (2)\n\n- **removed** server old.example.com removed\n"
+        ));
+        assert!(markdown.contains("\n## Timeouts (2)\n"));
+        assert!(markdown.ends_with("instead of 30s\n"));
+
+        let html = report.to_html();
+        assert!(html.contains("<h1>staging → production</h1>"));
+        assert!(html.contains("<h2>TLS (2)</h2>"));
+        assert!(html.contains(
+            "<li class=\"added\"><strong>added</strong> location /&lt;admin&gt; in server example.com added</li>"
+        ));
+        assert!(html.ends_with("</html>\n"));
+    }
+}
@@ -0,0 +1,847 @@
+//! Semantic comparison of two configurations
+//!
+//! [`semantic_compare`] compares the extracted model — servers, locations
+//! and upstreams — instead of raw directives, so a release review reads
+//! "location /api in server example.com:443 now proxies to upstream
+//! v2-backend instead of upstream v1-backend" rather than a textual diff.
+//! Reordering directives, comments and formatting produce no changes.
+//!
+//! Servers are matched by their names and listen ports; a server whose
+//! names stay the same while its ports change is reported as modified.
+//! Locations are matched by modifier and path, upstreams by name. Each
+//! change carries the [`ConfigPath`] of the block it is about, in the new
+//! configuration or, for removals, the old one.
+//!
+//! [`compare_environments`] builds on it to group the changes between two
+//! environments by category, adds TLS and timeout settings, and renders a
+//! Markdown or HTML report for change review.
+//!
+//! [`routing_changes`] replays requests, e.g. yesterday's traffic, through
+//! both configurations and lists those routed differently.
+//!
+//! # Examples
+//!
+//! ```
+//! use nginx_discovery::{diff, parse};
+//!
+//! let blue = parse(r"
+//! http {
+//!     upstream v1-backend { server 10.0.0.1:8080; }
+//!     upstream v2-backend { server 10.0.0.2:8080; }
+//!     server {
+//!         server_name example.com;
+//!         location /api { proxy_pass http://v1-backend; }
+//!     }
+//! }
+//! ")?;
+//! let green = parse(r"
+//! http {
+//!     upstream v1-backend { server 10.0.0.1:8080; }
+//!     upstream v2-backend { server 10.0.0.2:8080; }
+//!     server {
+//!         server_name example.com;
+//!         location /api { proxy_pass http://v2-backend; }
+//!     }
+//! }
+//! ")?;
+//!
+//! let changes = diff::semantic_compare(&blue, &green);
+//! assert_eq!(changes.len(), 1);
+//! assert_eq!(
+//!     changes.changes[0].message,
+//!     "location /api in server example.com now proxies to upstream v2-backend \
+//!      instead of upstream v1-backend"
+//! );
+//! # Ok::<(), nginx_discovery::Error>(())
+//! ```
+
+mod environment;
+mod routes;
+
+pub use environment::{compare_environments, Category, EnvironmentReport, Section};
+pub use routes::{routing_changes, RoutingChange};
+
+use crate::ast::{Config, ConfigIndex, ConfigPath, NodeId};
+use crate::extract;
+use crate::types::{ListenDirective, Location, LocationModifier, Server, Upstream, UpstreamServer};
+use std::fmt::{self, Write as _};
+
+/// Kind of a [`Change`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum ChangeKind {
+    /// Only in the new configuration
+    Added,
+    /// Only in the old configuration
+    Removed,
+    /// In both, with different settings
+    Modified,
+}
+
+impl fmt::Display for ChangeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Added => "added",
+            Self::Removed => "removed",
+            Self::Modified => "modified",
+        })
+    }
+}
+
+/// One difference between two configurations
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Change {
+    /// Added, removed or modified
+    pub kind: ChangeKind,
+
+    /// What changed: `server example.com`, `location /api in server
+    /// example.com`, `upstream backend`
+    pub subject: String,
+
+    /// The change as a sentence
+    pub message: String,
+
+    /// Context path of the server, location or upstream block
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub path: Option<ConfigPath>,
+}
+
+impl Change {
+    fn new(kind: ChangeKind, subject: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            subject: subject.into(),
+            message: message.into(),
+            path: None,
+        }
+    }
+}
+
+/// Set the path of the changes that have none yet
+fn locate(changes: &mut [Change], path: Option<&ConfigPath>) {
+    for change in changes.iter_mut().filter(|c| c.path.is_none()) {
+        change.path = path.cloned();
+    }
+}
+
+/// Finds the blocks behind the extracted model of one configuration
+struct Locator<'a> {
+    index: ConfigIndex<'a>,
+    /// `server` blocks, in the order [`extract::servers`] returns them
+    servers: Vec<NodeId>,
+}
+
+/// A locator with the block it is looking at
+type Located<'l, 'c> = (&'l Locator<'c>, Option<NodeId>);
+
+impl<'a> Locator<'a> {
+    fn new(config: &'a Config) -> Self {
+        let index = config.index();
+        let servers = index
+            .find("server")
+            .into_iter()
+            .filter(|&id| index.get(id).is_block())
+            .collect();
+        Self { index, servers }
+    }
+
+    fn path(&self, id: Option<NodeId>) -> Option<ConfigPath> {
+        id.map(|id| self.index.config_path(id))
+    }
+
+    /// Block of `server`, one of `servers`
+    fn server(&self, servers: &[Server], server: &Server) -> Option<NodeId> {
+        let i = servers.iter().position(|s| std::ptr::eq(s, server))?;
+        self.servers.get(i).copied()
+    }
+
+    fn location(&self, server: Option<NodeId>, location: &Location) -> Option<ConfigPath> {
+        let label = location_label(location);
+        let id = self.index.children(server?).iter().copied().find(|&id| {
+            let directive = self.index.get(id);
+            directive.name() == "location" && directive.args_as_strings().join(" ") == label
+        });
+        self.path(id)
+    }
+
+    fn upstream(&self, name: &str) -> Option<ConfigPath> {
+        let id = self.index.find("upstream").into_iter().find(|&id| {
+            let directive = self.index.get(id);
+            directive.is_block() && directive.first_arg().as_deref() == Some(name)
+        });
+        self.path(id)
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = match self.kind {
+            ChangeKind::Added => '+',
+            ChangeKind::Removed => '-',
+            ChangeKind::Modified => '~',
+        };
+        write!(f, "{sign} {}", self.message)
+    }
+}
+
+/// Result of [`semantic_compare`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SemanticDiff {
+    /// Changes: servers and their locations first, then upstreams
+    pub changes: Vec<Change>,
+}
+
+impl SemanticDiff {
+    /// Whether the configurations are equivalent
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// Number of changes
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    /// Changes of one kind
+    pub fn of_kind(&self, kind: ChangeKind) -> impl Iterator<Item = &Change> {
+        self.changes.iter().filter(move |c| c.kind == kind)
+    }
+}
+
+impl fmt::Display for SemanticDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for change in &self.changes {
+            writeln!(f, "{change}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Compare two configurations at the level of servers, locations and
+/// upstreams.
+///
+/// See the [module documentation](self) for an example.
+#[must_use]
+pub fn semantic_compare(old: &Config, new: &Config) -> SemanticDiff {
+    let old_upstreams = extract::upstreams(old).unwrap_or_default();
+    let new_upstreams = extract::upstreams(new).unwrap_or_default();
+    let locators = (Locator::new(old), Locator::new(new));
+    let mut diff = SemanticDiff::default();
+
+    compare_servers(
+        &extract::servers(old).unwrap_or_default(),
+        &extract::servers(new).unwrap_or_default(),
+        (&old_upstreams, &new_upstreams),
+        (&locators.0, &locators.1),
+        &mut diff.changes,
+    );
+    let start = diff.changes.len();
+    compare_upstreams(&old_upstreams, &new_upstreams, &mut diff.changes);
+    for change in &mut diff.changes[start..] {
+        let locator = match change.kind {
+            ChangeKind::Removed => &locators.0,
+            _ => &locators.1,
+        };
+        change.path = change
+            .subject
+            .strip_prefix("upstream ")
+            .and_then(|name| locator.upstream(name));
+    }
+
+    diff
+}
+
+/// Names of a server, `_` without `server_name`
+fn names(server: &Server) -> String {
+    if server.server_names.is_empty() {
+        "_".to_string()
+    } else {
+        server.server_names.join(" ")
+    }
+}
+
+fn listens(server: &Server) -> Vec<String> {
+    let mut listens: Vec<String> = server.listen.iter().map(describe_listen).collect();
+    listens.sort();
+    listens
+}
+
+fn describe_listen(listen: &ListenDirective) -> String {
+    let mut out = if listen.address == "*" {
+        listen.port.to_string()
+    } else if listen.address.contains(':') {
+        format!("[{}]:{}", listen.address, listen.port)
+    } else {
+        format!("{}:{}", listen.address, listen.port)
+    };
+    let flags = [
+        (listen.ssl, "ssl"),
+        (listen.http2, "http2"),
+        (listen.is_quic(), "quic"),
+        (listen.udp, "udp"),
+        (listen.proxy_protocol, "proxy_protocol"),
+        (listen.default_server, "default_server"),
+    ];
+    for (_, flag) in flags.iter().filter(|(on, _)| *on) {
+        out.push(' ');
+        out.push_str(flag);
+    }
+    out
+}
+
+/// `server example.com`, with the first port when several servers may
+/// share the name
+fn server_label(server: &Server, ambiguous: bool) -> String {
+    let name = server.primary_name().unwrap_or("_");
+    match server.listen.first() {
+        Some(listen) if ambiguous => format!("server {name}:{}", listen.port),
+        _ => format!("server {name}"),
+    }
+}
+
+fn compare_servers(
+    old: &[Server],
+    new: &[Server],
+    upstreams: (&[Upstream], &[Upstream]),
+    (old_locator, new_locator): (&Locator<'_>, &Locator<'_>),
+    changes: &mut Vec<Change>,
+) {
+    let count =
+        |servers: &[Server], name: &str| servers.iter().filter(|s| names(s) == name).count();
+    let ambiguous = |server: &Server| {
+        let name = names(server);
+        count(old, &name) > 1 || count(new, &name) > 1
+    };
+
+    // Pair servers: same names and listens first, then same names
+    let mut unmatched_new: Vec<&Server> = new.iter().collect();
+    let mut pairs: Vec<(&Server, &Server)> = Vec::new();
+    let mut removed: Vec<&Server> = Vec::new();
+    for server in old {
+        if let Some(i) = unmatched_new
+            .iter()
+            .position(|n| names(n) == names(server) && listens(n) == listens(server))
+        {
+            pairs.push((server, unmatched_new.remove(i)));
+        } else {
+            removed.push(server);
+        }
+    }
+    removed.retain(
+        |server| match unmatched_new.iter().position(|n| names(n) == names(server)) {
+            Some(i) => {
+                pairs.push((server, unmatched_new.remove(i)));
+                false
+            }
+            None => true,
+        },
+    );
+
+    for server in removed {
+        let label = server_label(server, ambiguous(server));
+        changes.push(Change::new(
+            ChangeKind::Removed,
+            &label,
+            format!("{label} removed"),
+        ));
+        let last = changes.len() - 1;
+        locate(
+            &mut changes[last..],
+            old_locator.path(old_locator.server(old, server)).as_ref(),
+        );
+    }
+    for (old_server, new_server) in pairs {
+        let start = changes.len();
+        let nodes = (
+            old_locator.server(old, old_server),
+            new_locator.server(new, new_server),
+        );
+        compare_server(
+            old_server,
+            new_server,
+            &server_label(new_server, ambiguous(new_server)),
+            upstreams,
+            ((old_locator, nodes.0), (new_locator, nodes.1)),
+            changes,
+        );
+        locate(&mut changes[start..], new_locator.path(nodes.1).as_ref());
+    }
+    for server in unmatched_new {
+        let label = server_label(server, ambiguous(server));
+        let listen = listens(server);
+        let message = if listen.is_empty() {
+            format!("{label} added")
+        } else {
+            format!("{label} added, listening on {}", listen.join(", "))
+        };
+        changes.push(Change::new(ChangeKind::Added, &label, message));
+        let last = changes.len() - 1;
+        locate(
+            &mut changes[last..],
+            new_locator.path(new_locator.server(new, server)).as_ref(),
+        );
+    }
+}
+
+fn compare_server(
+    old: &Server,
+    new: &Server,
+    label: &str,
+    upstreams: (&[Upstream], &[Upstream]),
+    ((old_locator, old_node), (new_locator, new_node)): (Located<'_, '_>, Located<'_, '_>),
+    changes: &mut Vec<Change>,
+) {
+    let mut modified = |message: String| {
+        changes.push(Change::new(ChangeKind::Modified, label, message));
+    };
+
+    let (old_listen, new_listen) = (listens(old), listens(new));
+    if old_listen != new_listen {
+        modified(format!(
+            "{label} now listens on {} instead of {}",
+            or_none(&new_listen.join(", ")),
+            or_none(&old_listen.join(", "))
+        ));
+    }
+    if old.root != new.root {
+        modified(format!(
+            "{label} now serves files from {} instead of {}",
+            path_or_none(new.root.as_deref()),
+            path_or_none(old.root.as_deref())
+        ));
+    }
+    if old.index != new.index {
+        modified(format!(
+            "{label} now uses index {} instead of {}",
+            or_none(&new.index.join(" ")),
+            or_none(&old.index.join(" "))
+        ));
+    }
+
+    for location in &old.locations {
+        if !new.locations.iter().any(|l| same_location(l, location)) {
+            let subject = format!("location {} in {label}", location_label(location));
+            changes.push(Change::new(
+                ChangeKind::Removed,
+                &subject,
+                format!("{subject} removed"),
+            ));
+            let last = changes.len() - 1;
+            locate(
+                &mut changes[last..],
+                old_locator.location(old_node, location).as_ref(),
+            );
+        }
+    }
+    for location in &new.locations {
+        let subject = format!("location {} in {label}", location_label(location));
+        let start = changes.len();
+        if let Some(old_location) = old.locations.iter().find(|l| same_location(l, location)) {
+            compare_location(old_location, location, &subject, upstreams, changes);
+        } else {
+            let message = match &location.proxy_pass {
+                Some(target) => format!(
+                    "{subject} added, proxying to {}",
+                    describe_target(target, upstreams.1)
+                ),
+                None => format!("{subject} added"),
+            };
+            changes.push(Change::new(ChangeKind::Added, &subject, message));
+        }
+        locate(
+            &mut changes[start..],
+            new_locator.location(new_node, location).as_ref(),
+        );
+    }
+}
+
+fn same_location(a: &Location, b: &Location) -> bool {
+    a.modifier == b.modifier && a.path == b.path
+}
+
+fn location_label(location: &Location) -> String {
+    let modifier = match location.modifier {
+        LocationModifier::None => "",
+        LocationModifier::Exact => "= ",
+        LocationModifier::PrefixPriority => "^~ ",
+        LocationModifier::Regex => "~ ",
+        LocationModifier::RegexCaseInsensitive => "~* ",
+    };
+    format!("{modifier}{}", location.path)
+}
+
+fn compare_location(
+    old: &Location,
+    new: &Location,
+    subject: &str,
+    (old_upstreams, new_upstreams): (&[Upstream], &[Upstream]),
+    changes: &mut Vec<Change>,
+) {
+    let mut modified = |message: String| {
+        changes.push(Change::new(ChangeKind::Modified, subject, message));
+    };
+
+    match (&old.proxy_pass, &new.proxy_pass) {
+        (Some(before), Some(after)) if before != after => modified(format!(
+            "{subject} now proxies to {} instead of {}",
+            describe_target(after, new_upstreams),
+            describe_target(before, old_upstreams)
+        )),
+        (None, Some(after)) => modified(format!(
+            "{subject} now proxies to {}",
+            describe_target(after, new_upstreams)
+        )),
+        (Some(before), None) => modified(format!(
+            "{subject} no longer proxies to {}",
+            describe_target(before, old_upstreams)
+        )),
+        _ => {}
+    }
+    if old.root != new.root {
+        modified(format!(
+            "{subject} now serves files from {} instead of {}",
+            path_or_none(new.root.as_deref()),
+            path_or_none(old.root.as_deref())
+        ));
+    }
+    if old.alias != new.alias {
+        modified(format!(
+            "{subject} now aliases {} instead of {}",
+            path_or_none(new.alias.as_deref()),
+            path_or_none(old.alias.as_deref())
+        ));
+    }
+    if old.internal != new.internal {
+        modified(if new.internal {
+            format!("{subject} is now internal")
+        } else {
+            format!("{subject} is no longer internal: clients can request it directly")
+        });
+    }
+    if old.try_files != new.try_files {
+        let describe = |location: &Location| {
+            location.try_files.as_ref().map_or_else(
+                || "none".to_string(),
+                |t| format!("{} {}", t.files.join(" "), t.fallback),
+            )
+        };
+        modified(format!(
+            "{subject} now uses try_files {} instead of {}",
+            describe(new),
+            describe(old)
+        ));
+    }
+    if old.requires_auth != new.requires_auth {
+        modified(if new.requires_auth {
+            format!("{subject} now requires authentication")
+        } else {
+            format!("{subject} no longer requires authentication")
+        });
+    }
+    if old.limit_except != new.limit_except {
+        let methods = |location: &Location| {
+            location
+                .limit_except
+                .as_ref()
+                .map_or_else(|| "all".to_string(), |l| l.methods.join(" "))
+        };
+        modified(format!(
+            "{subject} now allows {} methods instead of {}",
+            methods(new),
+            methods(old)
+        ));
+    }
+}
+
+/// `upstream name` when `proxy_pass` points at an upstream block,
+/// otherwise the URL
+fn describe_target(target: &str, upstreams: &[Upstream]) -> String {
+    let host = target
+        .split_once("://")
+        .map_or(target, |(_, rest)| rest)
+        .split(['/', ':'])
+        .next()
+        .unwrap_or_default();
+    if upstreams.iter().any(|u| u.name == host) {
+        format!("upstream {host}")
+    } else {
+        target.to_string()
+    }
+}
+
+fn compare_upstreams(old: &[Upstream], new: &[Upstream], changes: &mut Vec<Change>) {
+    for upstream in old {
+        if !new.iter().any(|u| u.name == upstream.name) {
+            let subject = format!("upstream {}", upstream.name);
+            changes.push(Change::new(
+                ChangeKind::Removed,
+                &subject,
+                format!("{subject} removed"),
+            ));
+        }
+    }
+    for upstream in new {
+        let subject = format!("upstream {}", upstream.name);
+        if let Some(old_upstream) = old.iter().find(|u| u.name == upstream.name) {
+            compare_upstream(old_upstream, upstream, &subject, changes);
+        } else {
+            let servers: Vec<&str> = upstream
+                .servers
+                .iter()
+                .map(|s| s.address.as_str())
+                .collect();
+            changes.push(Change::new(
+                ChangeKind::Added,
+                &subject,
+                format!(
+                    "{subject} added with servers {}",
+                    or_none(&servers.join(", "))
+                ),
+            ));
+        }
+    }
+}
+
+fn compare_upstream(old: &Upstream, new: &Upstream, subject: &str, changes: &mut Vec<Change>) {
+    let mut push = |kind, message: String| changes.push(Change::new(kind, subject, message));
+
+    for server in &old.servers {
+        if !new.servers.iter().any(|s| s.address == server.address) {
+            push(
+                ChangeKind::Modified,
+                format!("{subject} no longer sends traffic to {}", server.address),
+            );
+        }
+    }
+    for server in &new.servers {
+        match old.servers.iter().find(|s| s.address == server.address) {
+            Some(before) => {
+                if let Some(message) = describe_server_change(before, server) {
+                    push(ChangeKind::Modified, format!("{subject}: {message}"));
+                }
+            }
+            None => push(
+                ChangeKind::Modified,
+                format!(
+                    "{subject} now sends traffic to {}{}",
+                    server.address,
+                    server_role(server)
+                ),
+            ),
+        }
+    }
+
+    if old.method != new.method {
+        push(
+            ChangeKind::Modified,
+            format!(
+                "{subject} now balances with {} instead of {}",
+                new.method.as_deref().unwrap_or("round robin"),
+                old.method.as_deref().unwrap_or("round robin")
+            ),
+        );
+    }
+    if old.keepalive != new.keepalive {
+        let describe = |keepalive: Option<u32>| {
+            keepalive.map_or_else(|| "none".to_string(), |n| n.to_string())
+        };
+        push(
+            ChangeKind::Modified,
+            format!(
+                "{subject} now keeps {} idle connections instead of {}",
+                describe(new.keepalive),
+                describe(old.keepalive)
+            ),
+        );
+    }
+}
+
+fn server_role(server: &UpstreamServer) -> String {
+    let mut role = String::new();
+    if let Some(weight) = server.weight {
+        let _ = write!(role, " (weight {weight})");
+    }
+    if server.backup {
+        role.push_str(" as backup");
+    }
+    if server.down {
+        role.push_str(" marked down");
+    }
+    role
+}
+
+fn describe_server_change(old: &UpstreamServer, new: &UpstreamServer) -> Option<String> {
+    let address = &new.address;
+    let mut parts = Vec::new();
+    let (old_weight, new_weight) = (old.weight.unwrap_or(1), new.weight.unwrap_or(1));
+    if old_weight != new_weight {
+        parts.push(format!(
+            "weight of {address} changed from {old_weight} to {new_weight}"
+        ));
+    }
+    if old.backup != new.backup {
+        parts.push(if new.backup {
+            format!("{address} is now a backup server")
+        } else {
+            format!("{address} is no longer a backup server")
+        });
+    }
+    if old.down != new.down {
+        parts.push(if new.down {
+            format!("{address} is now marked down")
+        } else {
+            format!("{address} is no longer marked down")
+        });
+    }
+    if old.max_fails != new.max_fails || old.fail_timeout != new.fail_timeout {
+        parts.push(format!("failure settings of {address} changed"));
+    }
+    (!parts.is_empty()).then(|| parts.join(", "))
+}
+
+fn or_none(value: &str) -> &str {
+    if value.is_empty() {
+        "none"
+    } else {
+        value
+    }
+}
+
+fn path_or_none(path: Option<&std::path::Path>) -> String {
+    path.map_or_else(|| "none".to_string(), |p| p.display().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    const BLUE: &str = r"
+http {
+    upstream app {
+        server 10.0.0.1:8080;
+        server 10.0.0.2:8080;
+    }
+    server {
+        listen 80;
+        server_name example.com;
+        return 301 https://$host$request_uri;
+    }
+    server {
+        listen 443 ssl;
+        server_name example.com;
+        root /srv/blue;
+        location /api { proxy_pass http://app; }
+        location /old { proxy_pass http://10.0.0.9; }
+        location = /health { return 200; }
+    }
+}
+";
+
+    #[test]
+    fn test_semantic_compare_identical() {
+        // Reordering and formatting are not changes
+        let config = parse(BLUE).unwrap();
+        let reordered = parse(&BLUE.replace(
+            "listen 443 ssl;\n        server_name example.com;",
+            "server_name example.com; listen 443 ssl;",
+        ))
+        .unwrap();
+        assert!(semantic_compare(&config, &reordered).is_empty());
+    }
+
+    #[test]
+    fn test_semantic_compare_canary() {
+        let green = r"
+http {
+    upstream app {
+        least_conn;
+        server 10.0.0.1:8080 weight=9;
+        server 10.0.0.3:8080;
+        keepalive 16;
+    }
+    server {
+        listen 80;
+        server_name example.com;
+        return 301 https://$host$request_uri;
+    }
+    server {
+        listen 443 ssl http2;
+        server_name example.com;
+        root /srv/green;
+        location /api { proxy_pass http://app; auth_basic on; }
+        location = /health { return 200; }
+        location /new { proxy_pass http://app/v2/; }
+    }
+    upstream canary { server 10.0.1.1:8080; }
+}
+";
+        let diff = semantic_compare(&parse(BLUE).unwrap(), &parse(green).unwrap());
+        let messages: Vec<_> = diff.changes.iter().map(|c| c.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "server example.com:443 now listens on 443 ssl http2 instead of 443 ssl",
+                "server example.com:443 now serves files from /srv/green instead of /srv/blue",
+                "location /old in server example.com:443 removed",
+                "location /api in server example.com:443 now requires authentication",
+                "location /new in server example.com:443 added, proxying to upstream app",
+                "upstream app no longer sends traffic to 10.0.0.2:8080",
+                "upstream app: weight of 10.0.0.1:8080 changed from 1 to 9",
+                "upstream app now sends traffic to 10.0.0.3:8080",
+                "upstream app now balances with least_conn instead of round robin",
+                "upstream app now keeps 16 idle connections instead of none",
+                "upstream canary added with servers 10.0.1.1:8080",
+            ]
+        );
+        assert_eq!(diff.of_kind(ChangeKind::Added).count(), 2);
+        assert_eq!(diff.of_kind(ChangeKind::Removed).count(), 1);
+        assert_eq!(
+            diff.changes[2].subject,
+            "location /old in server example.com:443"
+        );
+        let paths: Vec<_> = diff
+            .changes
+            .iter()
+            .map(|c| c.path.as_ref().map(ToString::to_string).unwrap_or_default())
+            .collect();
+        assert_eq!(
+            paths[..5],
+            [
+                "http/server[2]",
+                "http/server[2]",
+                "http/server[2]/location[2]",
+                "http/server[2]/location",
+                "http/server[2]/location[3]",
+            ]
+        );
+        assert_eq!(paths[5], "http/upstream");
+        assert_eq!(paths[10], "http/upstream[2]");
+        assert!(diff
+            .to_string()
+            .starts_with("~ server example.com:443 now listens"));
+    }
+
+    #[test]
+    fn test_semantic_compare_servers() {
+        let old =
+            parse("http { server { server_name a.com; } server { server_name b.com; } }").unwrap();
+        let new = parse("http { server { server_name a.com; listen 8080; } server { server_name c.com; listen 80; } }")
+            .unwrap();
+        let diff = semantic_compare(&old, &new);
+        let messages: Vec<_> = diff.changes.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "- server b.com removed",
+                "~ server a.com now listens on 8080 instead of none",
+                "+ server c.com added, listening on 80",
+            ]
+        );
+    }
+}
@@ -0,0 +1,167 @@
+//! Requests whose routing differs between two configurations
+
+use crate::ast::Config;
+use crate::route::{replay, RouteDecision, SimulatedRequest};
+
+/// A request routed differently by the new configuration
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RoutingChange {
+    /// Routing by the old configuration
+    pub old: RouteDecision,
+
+    /// Routing by the new configuration
+    pub new: RouteDecision,
+}
+
+impl RoutingChange {
+    /// What changed, e.g. `location /api/ -> /v2/`, separated by `; `
+    #[must_use]
+    pub fn summary(&self) -> String {
+        let show = |value: &Option<String>| value.as_deref().unwrap_or("none").to_string();
+        let mut parts = Vec::new();
+        if self.old.server != self.new.server {
+            parts.push(format!(
+                "server {} -> {}",
+                show(&self.old.server),
+                show(&self.new.server)
+            ));
+        }
+        if self.old.location != self.new.location {
+            parts.push(format!(
+                "location {} -> {}",
+                show(&self.old.location),
+                show(&self.new.location)
+            ));
+        }
+        if self.old.outcome != self.new.outcome {
+            parts.push(format!("{} -> {}", self.old.outcome, self.new.outcome));
+        }
+        parts.join("; ")
+    }
+}
+
+/// Route `requests` through both configurations and keep those that pick
+/// another server or location or are handled differently
+///
+/// Identical requests are merged as in [`replay`].
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::{diff, parse, route::SimulatedRequest};
+///
+/// let old = parse(r"
+/// http {
+///     server {
+///         server_name example.com;
+///         location /api/ { proxy_pass http://10.0.0.1:8080; }
+///     }
+/// }
+/// ")?;
+/// let new = parse(r"
+/// http {
+///     server {
+///         server_name example.com;
+///         location /api/ { proxy_pass http://10.0.0.1:8080; }
+///         location /api/v2/ { proxy_pass http://10.0.0.2:8080; }
+///     }
+/// }
+/// ")?;
+/// let requests = [
+///     SimulatedRequest::new("example.com", "/api/users"),
+///     SimulatedRequest::new("example.com", "/api/v2/users"),
+/// ];
+///
+/// let changes = diff::routing_changes(&old, &new, &requests);
+/// assert_eq!(changes.len(), 1);
+/// assert_eq!(changes[0].new.location.as_deref(), Some("/api/v2/"));
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+#[must_use]
+pub fn routing_changes(
+    old: &Config,
+    new: &Config,
+    requests: &[SimulatedRequest],
+) -> Vec<RoutingChange> {
+    replay(old, requests)
+        .into_iter()
+        .zip(replay(new, requests))
+        .filter(|(old, new)| !old.same_route(new))
+        .map(|(old, new)| RoutingChange { old, new })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_routing_changes() {
+        let old = parse(
+            r"http {
+                server {
+                  listen 80;
+                  server_name example.com;
+                  location / { root /var/www; }
+                  location /old/ { return 301 /new/; }
+                }
+              }",
+        )
+        .unwrap();
+        let new = parse(
+            r"http {
+                server {
+                  listen 80;
+                  server_name example.com;
+                  location / { root /var/www; }
+                }
+              }",
+        )
+        .unwrap();
+        let requests = [
+            SimulatedRequest::new("example.com", "/index.html"),
+            SimulatedRequest::new("example.com", "/old/page"),
+        ];
+
+        let changes = routing_changes(&old, &new, &requests);
+
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].old.request, "http://example.com/old/page");
+        assert_eq!(
+            changes[0].summary(),
+            "location /old/ -> /; redirect 301 to /new/ -> file /var/www/old/page"
+        );
+    }
+
+    #[test]
+    fn test_routing_changes_edge_cases() {
+        let old = parse(
+            "http { server { listen 80; server_name a.example; location / { return 200; } } }",
+        )
+        .unwrap();
+        let requests = [
+            SimulatedRequest::new("a.example", "/"),
+            SimulatedRequest::new("a.example:http", "/"),
+        ];
+        assert!(routing_changes(&old, &old, &requests).is_empty());
+        assert!(routing_changes(&old, &old, &[]).is_empty());
+
+        let renamed = parse(
+            "http { server { listen 80; server_name b.example; location / { return 200; } } }",
+        )
+        .unwrap();
+        let changes = routing_changes(&old, &renamed, &requests);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].summary(), "server a.example -> b.example");
+
+        let moved = parse("http { server { listen 8080; server_name a.example; } }").unwrap();
+        let changes = routing_changes(&old, &moved, &requests[..1]);
+        assert_eq!(
+            changes[0].summary(),
+            "server a.example -> none; location / -> none; \
+             return 200 -> no server listens on the port"
+        );
+    }
+}
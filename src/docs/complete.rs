@@ -0,0 +1,156 @@
+//! Completion data for editors
+
+use super::{data, DirectiveDoc};
+use crate::parser::{context_at, CursorContext};
+
+/// What can be written at a cursor position
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Completion {
+    /// Where the cursor is
+    pub context: CursorContext,
+
+    /// Directives allowed in the enclosing block whose name starts with
+    /// the prefix, sorted by name; empty while writing arguments
+    pub directives: Vec<&'static DirectiveDoc>,
+
+    /// Documentation of the directive whose arguments are being written,
+    /// for its syntax as an argument hint
+    pub signature: Option<&'static DirectiveDoc>,
+}
+
+/// Completions at byte `offset` of a configuration being edited
+///
+/// The text does not have to parse: the enclosing blocks are tracked from
+/// the tokens before the cursor. Directives are matched to the block with
+/// the context names of the nginx documentation, so `if` inside a
+/// `location` offers what is allowed in `if in location`, and `server`
+/// inside `stream` offers stream directives. Blocks nginx does not
+/// document directives for (`map`, `types`, ...) offer only directives
+/// allowed anywhere, like `include`.
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::docs;
+///
+/// let text = "http { server { location / { proxy_pass http://app; proxy_se";
+/// let completion = docs::complete(text, text.len());
+/// let names: Vec<&str> = completion.directives.iter().map(|d| d.name).collect();
+/// assert!(names.contains(&"proxy_set_header"));
+///
+/// let text = "events { worker_connections ";
+/// let completion = docs::complete(text, text.len());
+/// assert_eq!(completion.signature.unwrap().syntax, "worker_connections number;");
+/// ```
+#[must_use]
+pub fn complete(text: &str, offset: usize) -> Completion {
+    let context = context_at(text, offset);
+    let (family, block) = doc_context(&context.blocks);
+
+    let mut directives = Vec::new();
+    let mut signature = None;
+    if context.in_comment {
+        // Nothing to offer
+    } else if let Some(name) = &context.directive {
+        signature = in_family(family, name)
+            .find(|doc| doc.allowed_in(block))
+            .or_else(|| in_family(family, name).next())
+            .or_else(|| super::lookup(name));
+    } else {
+        directives = data::DIRECTIVES
+            .iter()
+            .filter(|doc| doc.name.starts_with(&context.prefix))
+            .filter(|doc| doc.allowed_in(block))
+            .filter(|doc| block == "main" || matches_family(family, doc))
+            .collect();
+        directives.sort_by_key(|doc| doc.name);
+        directives.dedup_by_key(|doc| doc.name);
+    }
+
+    Completion {
+        context,
+        directives,
+        signature,
+    }
+}
+
+/// Module family prefix (`ngx_stream_`, `ngx_mail_`, or empty for http and
+/// core) and documentation context name of the innermost block
+fn doc_context(blocks: &[String]) -> (&'static str, &str) {
+    let family = if blocks.iter().any(|b| b == "stream") {
+        "ngx_stream_"
+    } else if blocks.iter().any(|b| b == "mail") {
+        "ngx_mail_"
+    } else {
+        ""
+    };
+    let block = match blocks {
+        [] => "main",
+        [.., outer, inner] if inner == "if" && outer == "location" => "if in location",
+        [.., inner] => inner,
+    };
+    (family, block)
+}
+
+fn matches_family(family: &str, doc: &DirectiveDoc) -> bool {
+    if family.is_empty() {
+        !doc.module.starts_with("ngx_stream_") && !doc.module.starts_with("ngx_mail_")
+    } else {
+        doc.module.starts_with(family)
+    }
+}
+
+fn in_family<'a>(
+    family: &'a str,
+    name: &'a str,
+) -> impl Iterator<Item = &'static DirectiveDoc> + 'a {
+    super::lookup_all(name).filter(move |doc| matches_family(family, doc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(text: &str) -> Vec<&'static str> {
+        complete(text, text.len())
+            .directives
+            .iter()
+            .map(|d| d.name)
+            .collect()
+    }
+
+    #[test]
+    fn test_complete_by_context() {
+        let main = names("");
+        assert!(main.contains(&"http"));
+        assert!(main.contains(&"stream"));
+        assert!(main.contains(&"worker_processes"));
+        assert!(!main.contains(&"listen"));
+
+        assert_eq!(names("events { worker_c"), vec!["worker_connections"]);
+
+        let location_if = names("http { server { location / { if ($x) { ");
+        assert!(location_if.contains(&"proxy_pass"));
+        assert!(!location_if.contains(&"listen"));
+
+        let stream = names("stream { server { proxy_");
+        assert!(stream.contains(&"proxy_pass"));
+        assert!(!stream.contains(&"proxy_set_header"));
+
+        assert_eq!(names("http { map $a $b { inc"), vec!["include"]);
+        assert!(names("http { # a comment").is_empty());
+    }
+
+    #[test]
+    fn test_complete_signature() {
+        let text = "stream { server { listen 12345 ";
+        let completion = complete(text, text.len());
+        assert!(completion.directives.is_empty());
+        assert_eq!(completion.context.arg_index, 1);
+        assert_eq!(
+            completion.signature.unwrap().module,
+            "ngx_stream_core_module"
+        );
+    }
+}
@@ -0,0 +1,362 @@
+//! Directive database
+//!
+//! Transcribed from the nginx reference documentation. Entries are grouped
+//! by module; within the http modules the core module comes first so that
+//! [`super::lookup`] prefers it for names shared with other modules.
+
+use super::DirectiveDoc;
+
+// Modules
+const CORE: &str = "ngx_core_module";
+const HTTP_CORE: &str = "ngx_http_core_module";
+const ACCESS: &str = "ngx_http_access_module";
+const AUTH_BASIC: &str = "ngx_http_auth_basic_module";
+const AUTH_REQUEST: &str = "ngx_http_auth_request_module";
+const AUTOINDEX: &str = "ngx_http_autoindex_module";
+const CHARSET: &str = "ngx_http_charset_module";
+const FASTCGI: &str = "ngx_http_fastcgi_module";
+const GEO: &str = "ngx_http_geo_module";
+const GRPC: &str = "ngx_http_grpc_module";
+const GZIP: &str = "ngx_http_gzip_module";
+const GZIP_STATIC: &str = "ngx_http_gzip_static_module";
+const HEADERS: &str = "ngx_http_headers_module";
+const INDEX: &str = "ngx_http_index_module";
+const LIMIT_CONN: &str = "ngx_http_limit_conn_module";
+const LIMIT_REQ: &str = "ngx_http_limit_req_module";
+const LOG: &str = "ngx_http_log_module";
+const MAP: &str = "ngx_http_map_module";
+const MEMCACHED: &str = "ngx_http_memcached_module";
+const MIRROR: &str = "ngx_http_mirror_module";
+const PROXY: &str = "ngx_http_proxy_module";
+const REALIP: &str = "ngx_http_realip_module";
+const REFERER: &str = "ngx_http_referer_module";
+const REWRITE: &str = "ngx_http_rewrite_module";
+const SCGI: &str = "ngx_http_scgi_module";
+const SLICE: &str = "ngx_http_slice_module";
+const SPLIT_CLIENTS: &str = "ngx_http_split_clients_module";
+const SSI: &str = "ngx_http_ssi_module";
+const SSL: &str = "ngx_http_ssl_module";
+const STUB_STATUS: &str = "ngx_http_stub_status_module";
+const SUB: &str = "ngx_http_sub_module";
+const UPSTREAM: &str = "ngx_http_upstream_module";
+const UWSGI: &str = "ngx_http_uwsgi_module";
+const V2: &str = "ngx_http_v2_module";
+const V3: &str = "ngx_http_v3_module";
+const STREAM_CORE: &str = "ngx_stream_core_module";
+const STREAM_PROXY: &str = "ngx_stream_proxy_module";
+const STREAM_SSL_PREREAD: &str = "ngx_stream_ssl_preread_module";
+const STREAM_UPSTREAM: &str = "ngx_stream_upstream_module";
+const MAIL_CORE: &str = "ngx_mail_core_module";
+
+// Context sets
+const ANY: &[&str] = &["any"];
+const MAIN: &[&str] = &["main"];
+const EVENTS: &[&str] = &["events"];
+const HTTP: &[&str] = &["http"];
+const HS: &[&str] = &["http", "server"];
+const HSL: &[&str] = &["http", "server", "location"];
+const HSLI: &[&str] = &["http", "server", "location", "if in location"];
+const HSLLE: &[&str] = &["http", "server", "location", "limit_except"];
+const SERVER: &[&str] = &["server"];
+const SL: &[&str] = &["server", "location"];
+const SLI: &[&str] = &["server", "location", "if"];
+const LOC: &[&str] = &["location"];
+const LI: &[&str] = &["location", "if in location"];
+const LILE: &[&str] = &["location", "if in location", "limit_except"];
+const UPS: &[&str] = &["upstream"];
+const STREAM_SERVER: &[&str] = &["stream", "server"];
+
+const fn d(
+    name: &'static str,
+    module: &'static str,
+    syntax: &'static str,
+    default: Option<&'static str>,
+    contexts: &'static [&'static str],
+    since: Option<&'static str>,
+) -> DirectiveDoc {
+    DirectiveDoc {
+        name,
+        module,
+        syntax,
+        default,
+        contexts,
+        since,
+    }
+}
+
+#[rustfmt::skip]
+pub(super) static DIRECTIVES: &[DirectiveDoc] = &[
+    // ngx_core_module
+    d("accept_mutex", CORE, "accept_mutex on | off;", Some("accept_mutex off;"), EVENTS, None),
+    d("daemon", CORE, "daemon on | off;", Some("daemon on;"), MAIN, None),
+    d("debug_connection", CORE, "debug_connection address | CIDR | unix:;", None, EVENTS, None),
+    d("env", CORE, "env variable[=value];", Some("env TZ;"), MAIN, None),
+    d("error_log", CORE, "error_log file [level];", Some("error_log logs/error.log error;"), &["main", "http", "mail", "stream", "server", "location"], None),
+    d("events", CORE, "events { ... }", None, MAIN, None),
+    d("include", CORE, "include file | mask;", None, ANY, None),
+    d("load_module", CORE, "load_module file;", None, MAIN, Some("1.9.11")),
+    d("master_process", CORE, "master_process on | off;", Some("master_process on;"), MAIN, None),
+    d("multi_accept", CORE, "multi_accept on | off;", Some("multi_accept off;"), EVENTS, None),
+    d("pcre_jit", CORE, "pcre_jit on | off;", Some("pcre_jit off;"), MAIN, Some("1.1.12")),
+    d("pid", CORE, "pid file;", Some("pid logs/nginx.pid;"), MAIN, None),
+    d("ssl_engine", CORE, "ssl_engine device;", None, MAIN, None),
+    d("thread_pool", CORE, "thread_pool name threads=number [max_queue=number];", Some("thread_pool default threads=32 max_queue=65536;"), MAIN, Some("1.7.11")),
+    d("timer_resolution", CORE, "timer_resolution interval;", None, MAIN, None),
+    d("use", CORE, "use method;", None, EVENTS, None),
+    d("user", CORE, "user user [group];", Some("user nobody nobody;"), MAIN, None),
+    d("worker_connections", CORE, "worker_connections number;", Some("worker_connections 512;"), EVENTS, None),
+    d("worker_cpu_affinity", CORE, "worker_cpu_affinity cpumask ...;\nworker_cpu_affinity auto [cpumask];", None, MAIN, None),
+    d("worker_priority", CORE, "worker_priority number;", Some("worker_priority 0;"), MAIN, None),
+    d("worker_processes", CORE, "worker_processes number | auto;", Some("worker_processes 1;"), MAIN, None),
+    d("worker_rlimit_nofile", CORE, "worker_rlimit_nofile number;", None, MAIN, None),
+    d("worker_shutdown_timeout", CORE, "worker_shutdown_timeout time;", None, MAIN, Some("1.11.11")),
+    d("working_directory", CORE, "working_directory directory;", None, MAIN, None),
+    // ngx_http_core_module
+    d("absolute_redirect", HTTP_CORE, "absolute_redirect on | off;", Some("absolute_redirect on;"), HSL, Some("1.11.8")),
+    d("aio", HTTP_CORE, "aio on | off | threads[=pool];", Some("aio off;"), HSL, None),
+    d("alias", HTTP_CORE, "alias path;", None, LOC, None),
+    d("chunked_transfer_encoding", HTTP_CORE, "chunked_transfer_encoding on | off;", Some("chunked_transfer_encoding on;"), HSL, None),
+    d("client_body_buffer_size", HTTP_CORE, "client_body_buffer_size size;", Some("client_body_buffer_size 8k|16k;"), HSL, None),
+    d("client_body_temp_path", HTTP_CORE, "client_body_temp_path path [level1 [level2 [level3]]];", Some("client_body_temp_path client_body_temp;"), HSL, None),
+    d("client_body_timeout", HTTP_CORE, "client_body_timeout time;", Some("client_body_timeout 60s;"), HSL, None),
+    d("client_header_buffer_size", HTTP_CORE, "client_header_buffer_size size;", Some("client_header_buffer_size 1k;"), HS, None),
+    d("client_header_timeout", HTTP_CORE, "client_header_timeout time;", Some("client_header_timeout 60s;"), HS, None),
+    d("client_max_body_size", HTTP_CORE, "client_max_body_size size;", Some("client_max_body_size 1m;"), HSL, None),
+    d("default_type", HTTP_CORE, "default_type mime-type;", Some("default_type text/plain;"), HSL, None),
+    d("directio", HTTP_CORE, "directio size | off;", Some("directio off;"), HSL, None),
+    d("error_page", HTTP_CORE, "error_page code ... [=[response]] uri;", None, HSLI, None),
+    d("etag", HTTP_CORE, "etag on | off;", Some("etag on;"), HSL, Some("1.3.3")),
+    d("http", HTTP_CORE, "http { ... }", None, MAIN, None),
+    d("if_modified_since", HTTP_CORE, "if_modified_since off | exact | before;", Some("if_modified_since exact;"), HSL, None),
+    d("ignore_invalid_headers", HTTP_CORE, "ignore_invalid_headers on | off;", Some("ignore_invalid_headers on;"), HS, None),
+    d("internal", HTTP_CORE, "internal;", None, LOC, None),
+    d("keepalive_requests", HTTP_CORE, "keepalive_requests number;", Some("keepalive_requests 1000;"), HSL, None),
+    d("keepalive_timeout", HTTP_CORE, "keepalive_timeout timeout [header_timeout];", Some("keepalive_timeout 75s;"), HSL, None),
+    d("large_client_header_buffers", HTTP_CORE, "large_client_header_buffers number size;", Some("large_client_header_buffers 4 8k;"), HS, None),
+    d("limit_except", HTTP_CORE, "limit_except method ... { ... }", None, LOC, None),
+    d("limit_rate", HTTP_CORE, "limit_rate rate;", Some("limit_rate 0;"), HSLI, None),
+    d("lingering_close", HTTP_CORE, "lingering_close off | on | always;", Some("lingering_close on;"), HSL, Some("1.1.0")),
+    d("listen", HTTP_CORE, "listen address[:port] [default_server] [ssl] [http2 | quic] [proxy_protocol] [setfib=number] [fastopen=number] [backlog=number] [rcvbuf=size] [sndbuf=size] [accept_filter=filter] [deferred] [bind] [ipv6only=on|off] [reuseport] [so_keepalive=on|off|[keepidle]:[keepintvl]:[keepcnt]];\nlisten port [default_server] [ssl] [http2 | quic] [proxy_protocol] [...];\nlisten unix:path [default_server] [ssl] [http2] [proxy_protocol] [...];", Some("listen *:80 | *:8000;"), SERVER, None),
+    d("location", HTTP_CORE, "location [ = | ~ | ~* | ^~ ] uri { ... }\nlocation @name { ... }", None, SL, None),
+    d("log_not_found", HTTP_CORE, "log_not_found on | off;", Some("log_not_found on;"), HSL, None),
+    d("merge_slashes", HTTP_CORE, "merge_slashes on | off;", Some("merge_slashes on;"), HS, None),
+    d("open_file_cache", HTTP_CORE, "open_file_cache off;\nopen_file_cache max=N [inactive=time];", Some("open_file_cache off;"), HSL, None),
+    d("open_file_cache_errors", HTTP_CORE, "open_file_cache_errors on | off;", Some("open_file_cache_errors off;"), HSL, None),
+    d("open_file_cache_min_uses", HTTP_CORE, "open_file_cache_min_uses number;", Some("open_file_cache_min_uses 1;"), HSL, None),
+    d("open_file_cache_valid", HTTP_CORE, "open_file_cache_valid time;", Some("open_file_cache_valid 60s;"), HSL, None),
+    d("output_buffers", HTTP_CORE, "output_buffers number size;", Some("output_buffers 2 32k;"), HSL, None),
+    d("port_in_redirect", HTTP_CORE, "port_in_redirect on | off;", Some("port_in_redirect on;"), HSL, None),
+    d("postpone_output", HTTP_CORE, "postpone_output size;", Some("postpone_output 1460;"), HSL, None),
+    d("reset_timedout_connection", HTTP_CORE, "reset_timedout_connection on | off;", Some("reset_timedout_connection off;"), HSL, None),
+    d("resolver", HTTP_CORE, "resolver address ... [valid=time] [ipv4=on|off] [ipv6=on|off] [status_zone=zone];", None, HSL, None),
+    d("resolver_timeout", HTTP_CORE, "resolver_timeout time;", Some("resolver_timeout 30s;"), HSL, None),
+    d("root", HTTP_CORE, "root path;", Some("root html;"), HSLI, None),
+    d("satisfy", HTTP_CORE, "satisfy all | any;", Some("satisfy all;"), HSL, None),
+    d("send_timeout", HTTP_CORE, "send_timeout time;", Some("send_timeout 60s;"), HSL, None),
+    d("sendfile", HTTP_CORE, "sendfile on | off;", Some("sendfile off;"), HSLI, None),
+    d("server", HTTP_CORE, "server { ... }", None, HTTP, None),
+    d("server_name", HTTP_CORE, "server_name name ...;", Some("server_name \"\";"), SERVER, None),
+    d("server_name_in_redirect", HTTP_CORE, "server_name_in_redirect on | off;", Some("server_name_in_redirect off;"), HSL, None),
+    d("server_names_hash_bucket_size", HTTP_CORE, "server_names_hash_bucket_size size;", Some("server_names_hash_bucket_size 32|64|128;"), HTTP, None),
+    d("server_names_hash_max_size", HTTP_CORE, "server_names_hash_max_size size;", Some("server_names_hash_max_size 512;"), HTTP, None),
+    d("server_tokens", HTTP_CORE, "server_tokens on | off | build | string;", Some("server_tokens on;"), HSL, None),
+    d("tcp_nodelay", HTTP_CORE, "tcp_nodelay on | off;", Some("tcp_nodelay on;"), HSL, None),
+    d("tcp_nopush", HTTP_CORE, "tcp_nopush on | off;", Some("tcp_nopush off;"), HSL, None),
+    d("try_files", HTTP_CORE, "try_files file ... uri;\ntry_files file ... =code;", None, SL, None),
+    d("types", HTTP_CORE, "types { ... }", Some("types { text/html html; image/gif gif; image/jpeg jpg; }"), HSL, None),
+    d("types_hash_max_size", HTTP_CORE, "types_hash_max_size size;", Some("types_hash_max_size 1024;"), HSL, None),
+    d("underscores_in_headers", HTTP_CORE, "underscores_in_headers on | off;", Some("underscores_in_headers off;"), HS, None),
+    // ngx_http_access_module
+    d("allow", ACCESS, "allow address | CIDR | unix: | all;", None, HSLLE, None),
+    d("deny", ACCESS, "deny address | CIDR | unix: | all;", None, HSLLE, None),
+    // ngx_http_auth_basic_module
+    d("auth_basic", AUTH_BASIC, "auth_basic string | off;", Some("auth_basic off;"), HSLLE, None),
+    d("auth_basic_user_file", AUTH_BASIC, "auth_basic_user_file file;", None, HSLLE, None),
+    // ngx_http_auth_request_module
+    d("auth_request", AUTH_REQUEST, "auth_request uri | off;", Some("auth_request off;"), HSL, Some("1.5.4")),
+    d("auth_request_set", AUTH_REQUEST, "auth_request_set $variable value;", None, HSL, Some("1.5.4")),
+    // ngx_http_autoindex_module
+    d("autoindex", AUTOINDEX, "autoindex on | off;", Some("autoindex off;"), HSL, None),
+    d("autoindex_format", AUTOINDEX, "autoindex_format html | xml | json | jsonp;", Some("autoindex_format html;"), HSL, Some("1.7.9")),
+    // ngx_http_charset_module
+    d("charset", CHARSET, "charset charset | off;", Some("charset off;"), HSLI, None),
+    d("charset_map", CHARSET, "charset_map charset1 charset2 { ... }", None, HTTP, None),
+    d("source_charset", CHARSET, "source_charset charset;", None, HSLI, None),
+    // ngx_http_fastcgi_module
+    d("fastcgi_buffers", FASTCGI, "fastcgi_buffers number size;", Some("fastcgi_buffers 8 4k|8k;"), HSL, None),
+    d("fastcgi_cache", FASTCGI, "fastcgi_cache zone | off;", Some("fastcgi_cache off;"), HSL, None),
+    d("fastcgi_cache_path", FASTCGI, "fastcgi_cache_path path [levels=levels] keys_zone=name:size [inactive=time] [max_size=size] [...];", None, HTTP, None),
+    d("fastcgi_index", FASTCGI, "fastcgi_index name;", None, HSL, None),
+    d("fastcgi_intercept_errors", FASTCGI, "fastcgi_intercept_errors on | off;", Some("fastcgi_intercept_errors off;"), HSL, None),
+    d("fastcgi_param", FASTCGI, "fastcgi_param parameter value [if_not_empty];", None, HSL, None),
+    d("fastcgi_pass", FASTCGI, "fastcgi_pass address;", None, LI, None),
+    d("fastcgi_read_timeout", FASTCGI, "fastcgi_read_timeout time;", Some("fastcgi_read_timeout 60s;"), HSL, None),
+    d("fastcgi_split_path_info", FASTCGI, "fastcgi_split_path_info regex;", None, LOC, None),
+    // ngx_http_geo_module
+    d("geo", GEO, "geo [$address] $variable { ... }", None, HTTP, None),
+    // ngx_http_grpc_module
+    d("grpc_pass", GRPC, "grpc_pass address;", None, LI, Some("1.13.10")),
+    d("grpc_read_timeout", GRPC, "grpc_read_timeout time;", Some("grpc_read_timeout 60s;"), HSL, Some("1.13.10")),
+    d("grpc_set_header", GRPC, "grpc_set_header field value;", Some("grpc_set_header Content-Length \"\";"), HSL, Some("1.13.10")),
+    // ngx_http_gzip_module
+    d("gzip", GZIP, "gzip on | off;", Some("gzip off;"), HSLI, None),
+    d("gzip_comp_level", GZIP, "gzip_comp_level level;", Some("gzip_comp_level 1;"), HSL, None),
+    d("gzip_disable", GZIP, "gzip_disable regex ...;", None, HSL, None),
+    d("gzip_min_length", GZIP, "gzip_min_length length;", Some("gzip_min_length 20;"), HSL, None),
+    d("gzip_proxied", GZIP, "gzip_proxied off | expired | no-cache | no-store | private | no_last_modified | no_etag | auth | any ...;", Some("gzip_proxied off;"), HSL, None),
+    d("gzip_types", GZIP, "gzip_types mime-type ...;", Some("gzip_types text/html;"), HSL, None),
+    d("gzip_vary", GZIP, "gzip_vary on | off;", Some("gzip_vary off;"), HSL, None),
+    d("gzip_static", GZIP_STATIC, "gzip_static on | off | always;", Some("gzip_static off;"), HSL, None),
+    // ngx_http_headers_module
+    d("add_header", HEADERS, "add_header name value [always];", None, HSLI, None),
+    d("add_trailer", HEADERS, "add_trailer name value [always];", None, HSLI, Some("1.13.2")),
+    d("expires", HEADERS, "expires [modified] time;\nexpires epoch | max | off;", Some("expires off;"), HSLI, None),
+    // ngx_http_index_module
+    d("index", INDEX, "index file ...;", Some("index index.html;"), HSL, None),
+    // ngx_http_limit_conn_module
+    d("limit_conn", LIMIT_CONN, "limit_conn zone number;", None, HSL, None),
+    d("limit_conn_status", LIMIT_CONN, "limit_conn_status code;", Some("limit_conn_status 503;"), HSL, Some("1.3.15")),
+    d("limit_conn_zone", LIMIT_CONN, "limit_conn_zone key zone=name:size;", None, HTTP, Some("1.1.8")),
+    // ngx_http_limit_req_module
+    d("limit_req", LIMIT_REQ, "limit_req zone=name [burst=number] [nodelay | delay=number];", None, HSL, None),
+    d("limit_req_log_level", LIMIT_REQ, "limit_req_log_level info | notice | warn | error;", Some("limit_req_log_level error;"), HSL, None),
+    d("limit_req_status", LIMIT_REQ, "limit_req_status code;", Some("limit_req_status 503;"), HSL, Some("1.3.15")),
+    d("limit_req_zone", LIMIT_REQ, "limit_req_zone key zone=name:size rate=rate [sync];", None, HTTP, None),
+    // ngx_http_log_module
+    d("access_log", LOG, "access_log path [format [buffer=size] [gzip[=level]] [flush=time] [if=condition]];\naccess_log off;", Some("access_log logs/access.log combined;"), &["http", "server", "location", "if in location", "limit_except"], None),
+    d("log_format", LOG, "log_format name [escape=default|json|none] string ...;", Some("log_format combined \"...\";"), HTTP, None),
+    d("open_log_file_cache", LOG, "open_log_file_cache max=N [inactive=time] [min_uses=N] [valid=time];\nopen_log_file_cache off;", Some("open_log_file_cache off;"), HSL, None),
+    // ngx_http_map_module
+    d("map", MAP, "map string $variable { ... }", None, HTTP, None),
+    d("map_hash_bucket_size", MAP, "map_hash_bucket_size size;", Some("map_hash_bucket_size 32|64|128;"), HTTP, None),
+    d("map_hash_max_size", MAP, "map_hash_max_size size;", Some("map_hash_max_size 2048;"), HTTP, None),
+    // ngx_http_memcached_module
+    d("memcached_pass", MEMCACHED, "memcached_pass address;", None, LI, None),
+    // ngx_http_mirror_module
+    d("mirror", MIRROR, "mirror uri | off;", Some("mirror off;"), HSL, Some("1.13.4")),
+    // ngx_http_proxy_module
+    d("proxy_buffer_size", PROXY, "proxy_buffer_size size;", Some("proxy_buffer_size 4k|8k;"), HSL, None),
+    d("proxy_buffering", PROXY, "proxy_buffering on | off;", Some("proxy_buffering on;"), HSL, None),
+    d("proxy_buffers", PROXY, "proxy_buffers number size;", Some("proxy_buffers 8 4k|8k;"), HSL, None),
+    d("proxy_busy_buffers_size", PROXY, "proxy_busy_buffers_size size;", Some("proxy_busy_buffers_size 8k|16k;"), HSL, None),
+    d("proxy_cache", PROXY, "proxy_cache zone | off;", Some("proxy_cache off;"), HSL, None),
+    d("proxy_cache_bypass", PROXY, "proxy_cache_bypass string ...;", None, HSL, None),
+    d("proxy_cache_key", PROXY, "proxy_cache_key string;", Some("proxy_cache_key $scheme$proxy_host$request_uri;"), HSL, None),
+    d("proxy_cache_lock", PROXY, "proxy_cache_lock on | off;", Some("proxy_cache_lock off;"), HSL, Some("1.1.12")),
+    d("proxy_cache_path", PROXY, "proxy_cache_path path [levels=levels] [use_temp_path=on|off] keys_zone=name:size [inactive=time] [max_size=size] [min_free=size] [...];", None, HTTP, None),
+    d("proxy_cache_use_stale", PROXY, "proxy_cache_use_stale error | timeout | invalid_header | updating | http_500 | http_502 | http_503 | http_504 | http_403 | http_404 | http_429 | off ...;", Some("proxy_cache_use_stale off;"), HSL, None),
+    d("proxy_cache_valid", PROXY, "proxy_cache_valid [code ...] time;", None, HSL, None),
+    d("proxy_connect_timeout", PROXY, "proxy_connect_timeout time;", Some("proxy_connect_timeout 60s;"), HSL, None),
+    d("proxy_cookie_domain", PROXY, "proxy_cookie_domain off;\nproxy_cookie_domain domain replacement;", Some("proxy_cookie_domain off;"), HSL, Some("1.1.15")),
+    d("proxy_cookie_path", PROXY, "proxy_cookie_path off;\nproxy_cookie_path path replacement;", Some("proxy_cookie_path off;"), HSL, Some("1.1.15")),
+    d("proxy_hide_header", PROXY, "proxy_hide_header field;", None, HSL, None),
+    d("proxy_http_version", PROXY, "proxy_http_version 1.0 | 1.1;", Some("proxy_http_version 1.0;"), HSL, Some("1.1.4")),
+    d("proxy_ignore_headers", PROXY, "proxy_ignore_headers field ...;", None, HSL, None),
+    d("proxy_intercept_errors", PROXY, "proxy_intercept_errors on | off;", Some("proxy_intercept_errors off;"), HSL, None),
+    d("proxy_max_temp_file_size", PROXY, "proxy_max_temp_file_size size;", Some("proxy_max_temp_file_size 1024m;"), HSL, None),
+    d("proxy_next_upstream", PROXY, "proxy_next_upstream error | timeout | invalid_header | http_500 | http_502 | http_503 | http_504 | http_403 | http_404 | http_429 | non_idempotent | off ...;", Some("proxy_next_upstream error timeout;"), HSL, None),
+    d("proxy_no_cache", PROXY, "proxy_no_cache string ...;", None, HSL, None),
+    d("proxy_pass", PROXY, "proxy_pass URL;", None, LILE, None),
+    d("proxy_pass_header", PROXY, "proxy_pass_header field;", None, HSL, None),
+    d("proxy_read_timeout", PROXY, "proxy_read_timeout time;", Some("proxy_read_timeout 60s;"), HSL, None),
+    d("proxy_redirect", PROXY, "proxy_redirect default;\nproxy_redirect off;\nproxy_redirect redirect replacement;", Some("proxy_redirect default;"), HSL, None),
+    d("proxy_request_buffering", PROXY, "proxy_request_buffering on | off;", Some("proxy_request_buffering on;"), HSL, Some("1.7.11")),
+    d("proxy_send_timeout", PROXY, "proxy_send_timeout time;", Some("proxy_send_timeout 60s;"), HSL, None),
+    d("proxy_set_header", PROXY, "proxy_set_header field value;", Some("proxy_set_header Host $proxy_host;\nproxy_set_header Connection close;"), HSL, None),
+    d("proxy_ssl_name", PROXY, "proxy_ssl_name name;", Some("proxy_ssl_name $proxy_host;"), HSL, Some("1.7.0")),
+    d("proxy_ssl_protocols", PROXY, "proxy_ssl_protocols [SSLv2] [SSLv3] [TLSv1] [TLSv1.1] [TLSv1.2] [TLSv1.3];", Some("proxy_ssl_protocols TLSv1.2 TLSv1.3;"), HSL, Some("1.5.6")),
+    d("proxy_ssl_server_name", PROXY, "proxy_ssl_server_name on | off;", Some("proxy_ssl_server_name off;"), HSL, Some("1.7.0")),
+    d("proxy_ssl_trusted_certificate", PROXY, "proxy_ssl_trusted_certificate file;", None, HSL, Some("1.7.0")),
+    d("proxy_ssl_verify", PROXY, "proxy_ssl_verify on | off;", Some("proxy_ssl_verify off;"), HSL, Some("1.7.0")),
+    // ngx_http_realip_module
+    d("real_ip_header", REALIP, "real_ip_header field | X-Real-IP | X-Forwarded-For | proxy_protocol;", Some("real_ip_header X-Real-IP;"), HSL, None),
+    d("real_ip_recursive", REALIP, "real_ip_recursive on | off;", Some("real_ip_recursive off;"), HSL, Some("1.3.0")),
+    d("set_real_ip_from", REALIP, "set_real_ip_from address | CIDR | unix:;", None, HSL, None),
+    // ngx_http_referer_module
+    d("valid_referers", REFERER, "valid_referers none | blocked | server_names | string ...;", None, SL, None),
+    // ngx_http_rewrite_module
+    d("break", REWRITE, "break;", None, SLI, None),
+    d("if", REWRITE, "if (condition) { ... }", None, SL, None),
+    d("return", REWRITE, "return code [text];\nreturn code URL;\nreturn URL;", None, SLI, None),
+    d("rewrite", REWRITE, "rewrite regex replacement [flag];", None, SLI, None),
+    d("rewrite_log", REWRITE, "rewrite_log on | off;", Some("rewrite_log off;"), &["http", "server", "location", "if"], None),
+    d("set", REWRITE, "set $variable value;", None, SLI, None),
+    d("uninitialized_variable_warn", REWRITE, "uninitialized_variable_warn on | off;", Some("uninitialized_variable_warn on;"), &["http", "server", "location", "if"], None),
+    // ngx_http_scgi_module
+    d("scgi_pass", SCGI, "scgi_pass address;", None, LI, None),
+    // ngx_http_slice_module
+    d("slice", SLICE, "slice size;", Some("slice 0;"), HSL, Some("1.9.8")),
+    // ngx_http_split_clients_module
+    d("split_clients", SPLIT_CLIENTS, "split_clients string $variable { ... }", None, HTTP, None),
+    // ngx_http_ssi_module
+    d("ssi", SSI, "ssi on | off;", Some("ssi off;"), HSLI, None),
+    // ngx_http_ssl_module
+    d("ssl", SSL, "ssl on | off;", Some("ssl off;"), HS, None),
+    d("ssl_buffer_size", SSL, "ssl_buffer_size size;", Some("ssl_buffer_size 16k;"), HS, Some("1.5.9")),
+    d("ssl_certificate", SSL, "ssl_certificate file;", None, HS, None),
+    d("ssl_certificate_key", SSL, "ssl_certificate_key file;", None, HS, None),
+    d("ssl_ciphers", SSL, "ssl_ciphers ciphers;", Some("ssl_ciphers HIGH:!aNULL:!MD5;"), HS, None),
+    d("ssl_client_certificate", SSL, "ssl_client_certificate file;", None, HS, None),
+    d("ssl_conf_command", SSL, "ssl_conf_command name value;", None, HS, Some("1.19.4")),
+    d("ssl_dhparam", SSL, "ssl_dhparam file;", None, HS, None),
+    d("ssl_early_data", SSL, "ssl_early_data on | off;", Some("ssl_early_data off;"), HS, Some("1.15.3")),
+    d("ssl_ecdh_curve", SSL, "ssl_ecdh_curve curve;", Some("ssl_ecdh_curve auto;"), HS, Some("1.1.0")),
+    d("ssl_password_file", SSL, "ssl_password_file file;", None, HS, Some("1.7.3")),
+    d("ssl_prefer_server_ciphers", SSL, "ssl_prefer_server_ciphers on | off;", Some("ssl_prefer_server_ciphers off;"), HS, None),
+    d("ssl_protocols", SSL, "ssl_protocols [SSLv2] [SSLv3] [TLSv1] [TLSv1.1] [TLSv1.2] [TLSv1.3];", Some("ssl_protocols TLSv1.2 TLSv1.3;"), HS, None),
+    d("ssl_reject_handshake", SSL, "ssl_reject_handshake on | off;", Some("ssl_reject_handshake off;"), HS, Some("1.19.4")),
+    d("ssl_session_cache", SSL, "ssl_session_cache off | none | [builtin[:size]] [shared:name:size];", Some("ssl_session_cache none;"), HS, None),
+    d("ssl_session_tickets", SSL, "ssl_session_tickets on | off;", Some("ssl_session_tickets on;"), HS, Some("1.5.9")),
+    d("ssl_session_timeout", SSL, "ssl_session_timeout time;", Some("ssl_session_timeout 5m;"), HS, None),
+    d("ssl_stapling", SSL, "ssl_stapling on | off;", Some("ssl_stapling off;"), HS, Some("1.3.7")),
+    d("ssl_stapling_verify", SSL, "ssl_stapling_verify on | off;", Some("ssl_stapling_verify off;"), HS, Some("1.3.7")),
+    d("ssl_trusted_certificate", SSL, "ssl_trusted_certificate file;", None, HS, Some("1.3.7")),
+    d("ssl_verify_client", SSL, "ssl_verify_client on | off | optional | optional_no_ca;", Some("ssl_verify_client off;"), HS, None),
+    d("ssl_verify_depth", SSL, "ssl_verify_depth number;", Some("ssl_verify_depth 1;"), HS, None),
+    // ngx_http_stub_status_module
+    d("stub_status", STUB_STATUS, "stub_status;", None, SL, None),
+    // ngx_http_sub_module
+    d("sub_filter", SUB, "sub_filter string replacement;", None, HSL, None),
+    d("sub_filter_once", SUB, "sub_filter_once on | off;", Some("sub_filter_once on;"), HSL, None),
+    // ngx_http_upstream_module
+    d("hash", UPSTREAM, "hash key [consistent];", None, UPS, Some("1.7.2")),
+    d("ip_hash", UPSTREAM, "ip_hash;", None, UPS, None),
+    d("keepalive", UPSTREAM, "keepalive connections;", None, UPS, Some("1.1.4")),
+    d("keepalive_requests", UPSTREAM, "keepalive_requests number;", Some("keepalive_requests 1000;"), UPS, Some("1.15.3")),
+    d("keepalive_timeout", UPSTREAM, "keepalive_timeout timeout;", Some("keepalive_timeout 60s;"), UPS, Some("1.15.3")),
+    d("least_conn", UPSTREAM, "least_conn;", None, UPS, Some("1.3.1")),
+    d("random", UPSTREAM, "random [two [method]];", None, UPS, Some("1.15.1")),
+    d("server", UPSTREAM, "server address [parameters];", None, UPS, None),
+    d("upstream", UPSTREAM, "upstream name { ... }", None, HTTP, None),
+    d("zone", UPSTREAM, "zone name [size];", None, UPS, Some("1.9.0")),
+    // ngx_http_uwsgi_module
+    d("uwsgi_param", UWSGI, "uwsgi_param parameter value [if_not_empty];", None, HSL, None),
+    d("uwsgi_pass", UWSGI, "uwsgi_pass [protocol://]address;", None, LI, None),
+    // ngx_http_v2_module
+    d("http2", V2, "http2 on | off;", Some("http2 off;"), HS, Some("1.25.1")),
+    d("http2_max_concurrent_streams", V2, "http2_max_concurrent_streams number;", Some("http2_max_concurrent_streams 128;"), HS, None),
+    // ngx_http_v3_module
+    d("http3", V3, "http3 on | off;", Some("http3 on;"), HS, Some("1.25.0")),
+    d("http3_hq", V3, "http3_hq on | off;", Some("http3_hq off;"), HS, Some("1.25.0")),
+    d("quic_gso", V3, "quic_gso on | off;", Some("quic_gso off;"), HS, Some("1.25.0")),
+    d("quic_host_key", V3, "quic_host_key file;", None, HS, Some("1.25.0")),
+    d("quic_retry", V3, "quic_retry on | off;", Some("quic_retry off;"), HS, Some("1.25.0")),
+    // ngx_stream_core_module
+    d("listen", STREAM_CORE, "listen address:port [ssl] [udp] [proxy_protocol] [fastopen=number] [backlog=number] [rcvbuf=size] [sndbuf=size] [bind] [ipv6only=on|off] [reuseport] [so_keepalive=on|off|[keepidle]:[keepintvl]:[keepcnt]];", None, SERVER, Some("1.9.0")),
+    d("server", STREAM_CORE, "server { ... }", None, &["stream"], Some("1.9.0")),
+    d("stream", STREAM_CORE, "stream { ... }", None, MAIN, Some("1.9.0")),
+    // ngx_stream_proxy_module
+    d("proxy_connect_timeout", STREAM_PROXY, "proxy_connect_timeout time;", Some("proxy_connect_timeout 60s;"), STREAM_SERVER, Some("1.9.0")),
+    d("proxy_pass", STREAM_PROXY, "proxy_pass address;", None, SERVER, Some("1.9.0")),
+    d("proxy_timeout", STREAM_PROXY, "proxy_timeout timeout;", Some("proxy_timeout 10m;"), STREAM_SERVER, Some("1.9.0")),
+    // ngx_stream_ssl_preread_module
+    d("ssl_preread", STREAM_SSL_PREREAD, "ssl_preread on | off;", Some("ssl_preread off;"), STREAM_SERVER, Some("1.11.5")),
+    // ngx_stream_upstream_module
+    d("server", STREAM_UPSTREAM, "server address [parameters];", None, UPS, Some("1.9.0")),
+    d("upstream", STREAM_UPSTREAM, "upstream name { ... }", None, &["stream"], Some("1.9.0")),
+    // ngx_mail_core_module
+    d("listen", MAIL_CORE, "listen address:port [ssl] [proxy_protocol] [backlog=number] [rcvbuf=size] [sndbuf=size] [bind] [ipv6only=on|off] [so_keepalive=on|off|[keepidle]:[keepintvl]:[keepcnt]];", None, SERVER, None),
+    d("mail", MAIL_CORE, "mail { ... }", None, MAIN, None),
+    d("protocol", MAIL_CORE, "protocol imap | pop3 | smtp;", None, SERVER, None),
+    d("server", MAIL_CORE, "server { ... }", None, &["mail"], None),
+];
@@ -0,0 +1,245 @@
+//! nginx directive reference
+//!
+//! A compact, embedded database of directive metadata — syntax, default
+//! value, allowed contexts, the nginx version that introduced the directive
+//! and a link to the official documentation. It backs the
+//! `nginx-discover explain` command, adds documentation links to lint
+//! findings, and provides editor completions through [`complete`].
+//!
+//! # Examples
+//!
+//! ```
+//! use nginx_discovery::docs;
+//!
+//! let doc = docs::lookup("proxy_pass").unwrap();
+//! assert_eq!(doc.module, "ngx_http_proxy_module");
+//! assert_eq!(doc.syntax, "proxy_pass URL;");
+//! assert!(doc.contexts.contains(&"location"));
+//! assert_eq!(
+//!     doc.url(),
+//!     "https://nginx.org/en/docs/http/ngx_http_proxy_module.html#proxy_pass"
+//! );
+//! ```
+
+mod complete;
+mod data;
+
+pub use complete::{complete, Completion};
+
+use std::fmt;
+
+/// Base URL of the official nginx documentation
+const DOCS_BASE: &str = "https://nginx.org/en/docs";
+
+/// Reference documentation of one directive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct DirectiveDoc {
+    /// Directive name
+    pub name: &'static str,
+
+    /// nginx module providing the directive (e.g. `ngx_http_proxy_module`)
+    pub module: &'static str,
+
+    /// Syntax as shown in the nginx documentation; alternative forms are
+    /// separated by newlines
+    pub syntax: &'static str,
+
+    /// Default value, `None` when the directive has no default
+    pub default: Option<&'static str>,
+
+    /// Contexts the directive is allowed in (`main`, `http`, `server`,
+    /// `location`, `if in location`, ...)
+    pub contexts: &'static [&'static str],
+
+    /// nginx version that introduced the directive, `None` for directives
+    /// that predate 1.0
+    pub since: Option<&'static str>,
+}
+
+impl DirectiveDoc {
+    /// Link to the directive in the official documentation
+    #[must_use]
+    pub fn url(&self) -> String {
+        let section = if self.module.starts_with("ngx_http_") {
+            "http/"
+        } else if self.module.starts_with("ngx_stream_") {
+            "stream/"
+        } else if self.module.starts_with("ngx_mail_") {
+            "mail/"
+        } else {
+            ""
+        };
+        format!("{DOCS_BASE}/{section}{}.html#{}", self.module, self.name)
+    }
+
+    /// Whether the directive is allowed in `context`
+    #[must_use]
+    pub fn allowed_in(&self, context: &str) -> bool {
+        self.contexts.contains(&"any") || self.contexts.contains(&context)
+    }
+}
+
+impl fmt::Display for DirectiveDoc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Syntax:  {}", self.syntax.replace('\n', "\n         "))?;
+        writeln!(f, "Default: {}", self.default.unwrap_or("—"))?;
+        writeln!(f, "Context: {}", self.contexts.join(", "))?;
+        if let Some(since) = self.since {
+            writeln!(f, "Since:   {since}")?;
+        }
+        write!(f, "Module:  {}", self.module)
+    }
+}
+
+/// Look up a directive by name
+///
+/// Names shared by several modules (`server`, `listen`, `proxy_pass` exist
+/// in both http and stream) resolve to the http entry; use [`lookup_all`]
+/// to get every variant.
+#[must_use]
+pub fn lookup(name: &str) -> Option<&'static DirectiveDoc> {
+    data::DIRECTIVES.iter().find(|doc| doc.name == name)
+}
+
+/// Every module's documentation for a directive name
+pub fn lookup_all(name: &str) -> impl Iterator<Item = &'static DirectiveDoc> + '_ {
+    data::DIRECTIVES.iter().filter(move |doc| doc.name == name)
+}
+
+/// All known directives
+#[must_use]
+pub fn all() -> &'static [DirectiveDoc] {
+    data::DIRECTIVES
+}
+
+/// Names of known directives that start with `prefix`, sorted and
+/// deduplicated
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::docs;
+///
+/// assert!(docs::names_with_prefix("gzip_").contains(&"gzip_types"));
+/// ```
+#[must_use]
+pub fn names_with_prefix(prefix: &str) -> Vec<&'static str> {
+    let mut names: Vec<&'static str> = data::DIRECTIVES
+        .iter()
+        .map(|doc| doc.name)
+        .filter(|name| name.starts_with(prefix))
+        .collect();
+    names.sort_unstable();
+    names.dedup();
+    names
+}
+
+/// Known directive names closest to a misspelled `name`, best first
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::docs;
+///
+/// assert_eq!(docs::suggest("proxy_pas").first(), Some(&"proxy_pass"));
+/// ```
+#[must_use]
+pub fn suggest(name: &str) -> Vec<&'static str> {
+    let max_distance = (name.len() / 3).max(1);
+    let mut candidates: Vec<(usize, &'static str)> = data::DIRECTIVES
+        .iter()
+        .map(|doc| (edit_distance(name, doc.name), doc.name))
+        .filter(|&(distance, _)| distance <= max_distance)
+        .collect();
+    candidates.sort_unstable();
+    candidates.dedup_by_key(|&mut (_, name)| name);
+    candidates
+        .into_iter()
+        .map(|(_, name)| name)
+        .take(5)
+        .collect()
+}
+
+/// Levenshtein distance between two ASCII-ish strings
+pub(crate) fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, &cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup() {
+        let doc = lookup("worker_processes").unwrap();
+        assert_eq!(doc.default, Some("worker_processes 1;"));
+        assert_eq!(
+            doc.url(),
+            "https://nginx.org/en/docs/ngx_core_module.html#worker_processes"
+        );
+
+        assert_eq!(lookup("http2").unwrap().since, Some("1.25.1"));
+        assert!(lookup("not_a_directive").is_none());
+    }
+
+    #[test]
+    fn test_lookup_all() {
+        let modules: Vec<_> = lookup_all("server").map(|d| d.module).collect();
+        assert_eq!(
+            modules,
+            vec![
+                "ngx_http_core_module",
+                "ngx_http_upstream_module",
+                "ngx_stream_core_module",
+                "ngx_stream_upstream_module",
+                "ngx_mail_core_module",
+            ]
+        );
+        assert!(lookup("server").unwrap().allowed_in("http"));
+        assert!(lookup("include").unwrap().allowed_in("upstream"));
+    }
+
+    #[test]
+    fn test_database_is_well_formed() {
+        for doc in all() {
+            assert!(!doc.contexts.is_empty(), "{} has no context", doc.name);
+            assert!(
+                doc.syntax.starts_with(doc.name),
+                "{} syntax does not start with its name",
+                doc.name
+            );
+            assert!(
+                all()
+                    .iter()
+                    .filter(|other| other.name == doc.name && other.module == doc.module)
+                    .count()
+                    == 1,
+                "{} is listed twice for {}",
+                doc.name,
+                doc.module
+            );
+        }
+    }
+
+    #[test]
+    fn test_suggest() {
+        assert_eq!(
+            suggest("worker_proceses").first(),
+            Some(&"worker_processes")
+        );
+        assert!(suggest("completely_unknown_thing").is_empty());
+    }
+}
@@ -0,0 +1,856 @@
+//! Installation and configuration diagnostics
+//!
+//! The doctor runs a fixed set of sanity checks (nginx binary, config file,
+//! `nginx -t`, log directories, SSL, file permissions, `SELinux` and
+//! `AppArmor`, listen ports held by other processes) and collects the outcome in a typed
+//! [`DoctorReport`] that can be rendered as text or serialized for tooling.
+//!
+//! # Examples
+//!
+//! ```
+//! use nginx_discovery::{doctor, NginxDiscovery};
+//!
+//! let discovery = NginxDiscovery::from_config_text("http { access_log /tmp/access.log; }")?;
+//! let options = doctor::DoctorOptions {
+//!     check_binary: false,
+//!     check_syntax: false,
+//!     ..Default::default()
+//! };
+//!
+//! let report = doctor::run(&discovery, options);
+//! assert!(report.get("config_parse").is_some());
+//! # Ok::<(), nginx_discovery::Error>(())
+//! ```
+
+use crate::analyze::{NginxVersion, RequiredVersion};
+use crate::types::LogPathMode;
+use crate::NginxDiscovery;
+use std::fmt;
+use std::path::Path;
+
+/// Outcome of a single doctor check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum DoctorStatus {
+    /// Check passed.
+    Pass,
+    /// Check passed with something worth looking at.
+    Warning,
+    /// Check failed.
+    Error,
+}
+
+impl fmt::Display for DoctorStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::Pass => "pass",
+            Self::Warning => "warning",
+            Self::Error => "error",
+        };
+        write!(f, "{s}")
+    }
+}
+
+/// A single doctor check result.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DoctorCheck {
+    /// Stable identifier (e.g. `nginx_binary`, `log_files`).
+    pub id: String,
+
+    /// Outcome of the check.
+    pub status: DoctorStatus,
+
+    /// Human-readable result.
+    pub message: String,
+
+    /// Suggested fix when the check did not pass.
+    pub remediation: Option<String>,
+}
+
+impl DoctorCheck {
+    /// Create a passing check.
+    #[must_use]
+    pub fn pass(id: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            status: DoctorStatus::Pass,
+            message: message.into(),
+            remediation: None,
+        }
+    }
+
+    /// Create a warning.
+    #[must_use]
+    pub fn warning(id: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            status: DoctorStatus::Warning,
+            message: message.into(),
+            remediation: None,
+        }
+    }
+
+    /// Create a failed check.
+    #[must_use]
+    pub fn error(id: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            status: DoctorStatus::Error,
+            message: message.into(),
+            remediation: None,
+        }
+    }
+
+    /// Attach a suggested fix.
+    #[must_use]
+    pub fn with_remediation(mut self, remediation: impl Into<String>) -> Self {
+        self.remediation = Some(remediation.into());
+        self
+    }
+}
+
+/// Ordered results of a doctor run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DoctorReport {
+    /// Checks in the order they ran.
+    pub checks: Vec<DoctorCheck>,
+}
+
+impl DoctorReport {
+    /// Create an empty report.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a check result.
+    pub fn push(&mut self, check: DoctorCheck) {
+        self.checks.push(check);
+    }
+
+    /// Look up a check by id.
+    #[must_use]
+    pub fn get(&self, id: &str) -> Option<&DoctorCheck> {
+        self.checks.iter().find(|c| c.id == id)
+    }
+
+    /// Number of passed checks.
+    #[must_use]
+    pub fn passed(&self) -> usize {
+        self.count(DoctorStatus::Pass)
+    }
+
+    /// Number of warnings.
+    #[must_use]
+    pub fn warnings(&self) -> usize {
+        self.count(DoctorStatus::Warning)
+    }
+
+    /// Number of failed checks.
+    #[must_use]
+    pub fn errors(&self) -> usize {
+        self.count(DoctorStatus::Error)
+    }
+
+    /// Whether no check failed.
+    #[must_use]
+    pub fn is_ok(&self) -> bool {
+        self.errors() == 0
+    }
+
+    fn count(&self, status: DoctorStatus) -> usize {
+        self.checks.iter().filter(|c| c.status == status).count()
+    }
+}
+
+/// Selects which doctor checks run.
+#[derive(Debug, Clone, Copy)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct DoctorOptions {
+    /// Look for the nginx binary and its version (needs the `system` feature).
+    pub check_binary: bool,
+
+    /// Run `nginx -t` (needs the `system` feature).
+    pub check_syntax: bool,
+
+    /// Check that log directories exist and are writable.
+    pub check_logs: bool,
+
+    /// Check SSL configuration.
+    pub check_ssl: bool,
+
+    /// Check permissions of keys, password files and log directories
+    /// (needs the `system` feature and a configuration loaded from a file).
+    pub check_permissions: bool,
+
+    /// Flag paths and ports an enforcing `SELinux` or `AppArmor` policy
+    /// likely blocks (needs the `system` feature and a configuration loaded
+    /// from a file).
+    pub check_security_modules: bool,
+
+    /// Check whether configured listen ports are free, held by nginx or
+    /// taken by another process (needs the `system` feature and a
+    /// configuration loaded from a file).
+    pub check_sockets: bool,
+
+    /// Cross-check the nginx systemd unit against the configuration
+    /// (needs the `system` feature and a configuration loaded from a file).
+    pub check_systemd: bool,
+}
+
+impl Default for DoctorOptions {
+    fn default() -> Self {
+        Self {
+            check_binary: true,
+            check_syntax: true,
+            check_logs: true,
+            check_ssl: true,
+            check_permissions: true,
+            check_security_modules: true,
+            check_sockets: true,
+            check_systemd: true,
+        }
+    }
+}
+
+/// Run the doctor against an already parsed configuration.
+///
+/// The config file and `nginx -t` checks only run when the discovery was
+/// loaded from a file.
+#[must_use]
+pub fn run(discovery: &NginxDiscovery, options: DoctorOptions) -> DoctorReport {
+    let mut report = DoctorReport::new();
+
+    preamble(&mut report, discovery.config_path(), options);
+    report.push(DoctorCheck::pass(
+        "config_parse",
+        "Configuration parsed successfully",
+    ));
+
+    #[cfg(feature = "system")]
+    if options.check_binary {
+        if let Some(check) = check_required_version(discovery) {
+            report.push(check);
+        }
+    }
+
+    if options.check_logs {
+        report.push(check_log_files(discovery));
+    }
+
+    if options.check_ssl {
+        report.push(check_ssl_certificates(discovery));
+    }
+
+    #[cfg(feature = "system")]
+    if options.check_permissions && discovery.config_path().is_some() {
+        report.push(permission_check(&discovery.audit_permissions()));
+    }
+
+    #[cfg(feature = "system")]
+    if options.check_security_modules && discovery.config_path().is_some() {
+        report.push(security_module_check(&discovery.audit_mac()));
+    }
+
+    #[cfg(feature = "system")]
+    if options.check_sockets && discovery.config_path().is_some() {
+        report.push(socket_check(&discovery.listen_sockets()));
+    }
+
+    #[cfg(feature = "system")]
+    if options.check_systemd && discovery.config_path().is_some() {
+        report.push(systemd_check(&discovery.audit_systemd()));
+    }
+
+    report
+}
+
+/// Parse the configuration at `path` and run the doctor against it.
+///
+/// A parse failure is reported as a failed `config_parse` check rather
+/// than an error, and the checks that need a parsed config are skipped.
+#[must_use]
+pub fn run_file(path: &Path, options: DoctorOptions) -> DoctorReport {
+    match NginxDiscovery::from_config_file(path) {
+        Ok(discovery) => run(&discovery, options),
+        Err(e) => {
+            let mut report = DoctorReport::new();
+            preamble(&mut report, Some(path), options);
+            report.push(
+                DoctorCheck::error("config_parse", format!("Configuration parsing failed: {e}"))
+                    .with_remediation("Fix the reported syntax error and run the doctor again"),
+            );
+            report
+        }
+    }
+}
+
+/// Checks that do not need a parsed configuration.
+#[cfg_attr(not(feature = "system"), allow(unused_variables))]
+fn preamble(report: &mut DoctorReport, path: Option<&Path>, options: DoctorOptions) {
+    #[cfg(feature = "system")]
+    if options.check_binary {
+        report.push(check_nginx_binary());
+    }
+
+    if let Some(path) = path {
+        report.push(check_config_file(path));
+
+        #[cfg(feature = "system")]
+        if options.check_syntax {
+            report.push(check_config_syntax());
+        }
+    }
+}
+
+#[cfg(feature = "system")]
+fn check_nginx_binary() -> DoctorCheck {
+    use crate::system;
+
+    match system::find_nginx() {
+        Ok(path) => match system::nginx_version() {
+            Ok(version) => DoctorCheck::pass(
+                "nginx_binary",
+                format!("NGINX binary found: {} ({})", path.display(), version),
+            ),
+            Err(_) => DoctorCheck::pass(
+                "nginx_binary",
+                format!("NGINX binary found: {}", path.display()),
+            ),
+        },
+        Err(_) => DoctorCheck::error("nginx_binary", "NGINX binary not found in PATH")
+            .with_remediation("Install nginx or add its sbin directory to PATH"),
+    }
+}
+
+/// Compare the version the configuration needs with the installed binary.
+/// Skipped when nginx is not installed; `nginx_binary` reports that.
+#[cfg(feature = "system")]
+fn check_required_version(discovery: &NginxDiscovery) -> Option<DoctorCheck> {
+    let installed = crate::system::installed_version().ok()?;
+    Some(version_check(&discovery.required_version(), installed))
+}
+
+#[cfg_attr(not(feature = "system"), allow(dead_code))]
+fn version_check(required: &RequiredVersion, installed: NginxVersion) -> DoctorCheck {
+    let unsupported: Vec<_> = required.unsupported(installed).collect();
+
+    match unsupported.first() {
+        None => DoctorCheck::pass(
+            "nginx_version",
+            match required.minimum {
+                Some(minimum) => format!(
+                    "NGINX version: {installed} (configuration needs {minimum} or later)"
+                ),
+                None => format!("NGINX version: {installed}"),
+            },
+        ),
+        Some(first) => DoctorCheck::warning(
+            "nginx_version",
+            format!(
+                "Configuration needs nginx {} but {installed} is installed ({} unsupported, e.g. \"{}\" on line {})",
+                first.version,
+                unsupported.len(),
+                first.feature(),
+                first.span.line
+            ),
+        )
+        .with_remediation(format!("Upgrade nginx to {} or later", first.version)),
+    }
+}
+
+fn check_config_file(path: &Path) -> DoctorCheck {
+    if !path.exists() {
+        return DoctorCheck::error(
+            "config_file",
+            format!("Configuration file not found: {}", path.display()),
+        )
+        .with_remediation("Pass the correct path with --config");
+    }
+
+    match std::fs::metadata(path) {
+        Ok(metadata) if metadata.is_file() => DoctorCheck::pass(
+            "config_file",
+            format!("Configuration file: {}", path.display()),
+        ),
+        Ok(_) => DoctorCheck::error(
+            "config_file",
+            format!("Path is not a file: {}", path.display()),
+        )
+        .with_remediation("Point --config at nginx.conf, not its directory"),
+        Err(e) => DoctorCheck::error("config_file", format!("Cannot access config file: {e}"))
+            .with_remediation("Check file permissions or run with sudo"),
+    }
+}
+
+#[cfg(feature = "system")]
+fn check_config_syntax() -> DoctorCheck {
+    match crate::system::test_config() {
+        Ok(_) => DoctorCheck::pass("config_syntax", "Configuration syntax: valid"),
+        Err(e) => DoctorCheck::error("config_syntax", format!("Configuration syntax error: {e}"))
+            .with_remediation("Run `nginx -t` to see the full error"),
+    }
+}
+
+fn check_log_files(discovery: &NginxDiscovery) -> DoctorCheck {
+    // Templated paths ($host, ...) are checked per server name; paths using
+    // request variables only exist at runtime, and syslog/stderr targets
+    // have no file, so both are skipped.
+    let logs = discovery.all_log_files_with(LogPathMode::Expand);
+    let access_logs = discovery.access_logs();
+    let unresolved = access_logs
+        .iter()
+        .filter(|log| log.is_templated() && log.expanded_paths().is_empty())
+        .count();
+    let non_file = access_logs.iter().filter(|log| !log.is_file()).count();
+
+    if logs.is_empty() && unresolved == 0 && non_file == 0 {
+        return DoctorCheck::warning("log_files", "No log files configured")
+            .with_remediation("Add access_log and error_log directives");
+    }
+
+    let mut warnings: Vec<String> = Vec::new();
+
+    for log_path in &logs {
+        if let Some(parent) = log_path.parent() {
+            if !parent.exists() {
+                warnings.push(format!(
+                    "Log directory does not exist: {}",
+                    parent.display()
+                ));
+            } else if let Ok(metadata) = std::fs::metadata(parent) {
+                // Check if directory is writable (Unix-specific check would be better)
+                if metadata.permissions().readonly() {
+                    warnings.push(format!("Log directory not writable: {}", parent.display()));
+                }
+            }
+        }
+    }
+
+    if warnings.is_empty() {
+        let mut notes = Vec::new();
+        if unresolved > 0 {
+            notes.push(format!("{unresolved} templated paths not checked"));
+        }
+        if non_file > 0 {
+            notes.push(format!("{non_file} syslog/stderr targets"));
+        }
+        let skipped = if notes.is_empty() {
+            String::new()
+        } else {
+            format!(" ({})", notes.join(", "))
+        };
+        DoctorCheck::pass(
+            "log_files",
+            format!(
+                "Log files: {} configured, all directories accessible{skipped}",
+                logs.len()
+            ),
+        )
+    } else {
+        DoctorCheck::warning(
+            "log_files",
+            format!("Log files: {} warnings ({})", warnings.len(), warnings[0]),
+        )
+        .with_remediation("Create the missing log directories and make them writable by nginx")
+    }
+}
+
+#[cfg(feature = "system")]
+fn permission_check(report: &crate::system::PermissionReport) -> DoctorCheck {
+    let Some(first) = report.findings.iter().max_by_key(|f| f.severity) else {
+        let note = if report.supported {
+            ""
+        } else {
+            " (ownership checks not supported on this platform)"
+        };
+        return DoctorCheck::pass(
+            "file_permissions",
+            format!("File permissions: {} paths checked{note}", report.checked),
+        );
+    };
+
+    let message = format!(
+        "File permissions: {} issues ({})",
+        report.findings.len(),
+        first.message
+    );
+    let check = if first.severity == crate::lint::Severity::Error {
+        DoctorCheck::error("file_permissions", message)
+    } else {
+        DoctorCheck::warning("file_permissions", message)
+    };
+    match &first.help {
+        Some(help) => check.with_remediation(help.clone()),
+        None => check,
+    }
+}
+
+#[cfg(feature = "system")]
+fn security_module_check(report: &crate::system::MacReport) -> DoctorCheck {
+    let status = report.status;
+    let Some(module) = status.module else {
+        return DoctorCheck::pass("security_modules", "No SELinux or AppArmor confinement");
+    };
+    if !status.enforcing {
+        return DoctorCheck::pass(
+            "security_modules",
+            format!("{module}: not enforcing, denials are only logged"),
+        );
+    }
+    let Some(first) = report.findings.first() else {
+        return DoctorCheck::pass(
+            "security_modules",
+            format!(
+                "{module} enforcing: {} paths and ports in standard locations",
+                report.checked
+            ),
+        );
+    };
+
+    let check = DoctorCheck::warning(
+        "security_modules",
+        format!(
+            "{module} enforcing: {} likely denials ({})",
+            report.findings.len(),
+            first.message
+        ),
+    );
+    match &first.help {
+        Some(help) => check.with_remediation(help.clone()),
+        None => check,
+    }
+}
+
+#[cfg(feature = "system")]
+fn socket_check(report: &crate::system::SocketReport) -> DoctorCheck {
+    if !report.supported {
+        return DoctorCheck::pass(
+            "listen_sockets",
+            "Listen sockets: not checked (needs /proc on Linux)",
+        );
+    }
+    if report.ports.is_empty() {
+        return DoctorCheck::pass("listen_sockets", "Listen sockets: no TCP or UDP listeners");
+    }
+
+    let port = |p: &crate::system::PortUse| format!("{}/{}", p.port, p.protocol);
+    let conflicts: Vec<_> = report.conflicts().collect();
+    if let Some(first) = conflicts.first() {
+        let crate::system::PortState::Other(owner) = &first.state else {
+            unreachable!("conflicts are held by other processes");
+        };
+        return DoctorCheck::error(
+            "listen_sockets",
+            format!(
+                "Listen sockets: {} port(s) taken by other processes (port {} is held by {owner})",
+                conflicts.len(),
+                port(first)
+            ),
+        )
+        .with_remediation(format!(
+            "Stop {} or move one of them to another port",
+            owner.name
+        ));
+    }
+
+    let free: Vec<String> = report.free().map(port).collect();
+    if !report.nginx_running() && free.len() == report.ports.len() {
+        return DoctorCheck::warning(
+            "listen_sockets",
+            format!(
+                "Listen sockets: nginx is not running (none of {} ports is bound)",
+                free.len()
+            ),
+        )
+        .with_remediation("Start nginx, e.g. `systemctl start nginx`");
+    }
+    if !free.is_empty() {
+        return DoctorCheck::warning(
+            "listen_sockets",
+            format!(
+                "Listen sockets: nothing is listening on {}",
+                free.join(", ")
+            ),
+        )
+        .with_remediation("Reload nginx to apply the configuration: `nginx -s reload`");
+    }
+
+    let unknown = report
+        .ports
+        .iter()
+        .filter(|p| p.state == crate::system::PortState::Unknown)
+        .count();
+    let note = if unknown == 0 {
+        String::new()
+    } else {
+        format!(" ({unknown} held by processes not visible without root)")
+    };
+    DoctorCheck::pass(
+        "listen_sockets",
+        format!("Listen sockets: {} ports bound{note}", report.ports.len()),
+    )
+}
+
+#[cfg(feature = "system")]
+fn systemd_check(report: &crate::system::SystemdReport) -> DoctorCheck {
+    if report.unit.is_none() {
+        return DoctorCheck::pass("systemd_unit", "No nginx systemd unit found");
+    }
+    let Some(first) = report.findings.first() else {
+        return DoctorCheck::pass(
+            "systemd_unit",
+            "systemd unit: limits, restart policy and config test in place",
+        );
+    };
+
+    let message = format!(
+        "systemd unit: {} issue(s) ({})",
+        report.findings.len(),
+        first.message
+    );
+    let check = if first.severity == crate::lint::Severity::Info {
+        DoctorCheck::pass("systemd_unit", message)
+    } else {
+        DoctorCheck::warning("systemd_unit", message)
+    };
+    match &first.help {
+        Some(help) => check.with_remediation(help.clone()),
+        None => check,
+    }
+}
+
+fn check_ssl_certificates(discovery: &NginxDiscovery) -> DoctorCheck {
+    let ssl_servers = discovery.ssl_servers();
+
+    if ssl_servers.is_empty() {
+        return DoctorCheck::pass("ssl_certificates", "No SSL configuration found");
+    }
+
+    // This is a basic check - in a real implementation, you'd parse
+    // ssl_certificate directives and check if files exist
+    DoctorCheck::pass(
+        "ssl_certificates",
+        format!("SSL servers: {} configured", ssl_servers.len()),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn offline() -> DoctorOptions {
+        DoctorOptions {
+            check_binary: false,
+            check_syntax: false,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_run_from_text() {
+        let discovery = NginxDiscovery::from_config_text(
+            "http { server { listen 443 ssl; server_name example.com; } }",
+        )
+        .unwrap();
+
+        let report = run(&discovery, offline());
+
+        let ids: Vec<_> = report.checks.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, vec!["config_parse", "log_files", "ssl_certificates"]);
+        assert_eq!(
+            report.get("log_files").unwrap().status,
+            DoctorStatus::Warning
+        );
+        assert!(report.get("log_files").unwrap().remediation.is_some());
+        assert_eq!(report.warnings(), 1);
+        assert!(report.is_ok());
+    }
+
+    #[test]
+    fn test_templated_log_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = format!(
+            "server {{ server_name a.example; access_log {0}/$host.log; access_log {0}/$remote_addr.log; }}",
+            dir.path().display()
+        );
+        let discovery = NginxDiscovery::from_config_text(&config).unwrap();
+
+        let check = check_log_files(&discovery);
+        assert_eq!(check.status, DoctorStatus::Pass);
+        assert!(check.message.contains("1 configured"));
+        assert!(check.message.contains("1 templated paths not checked"));
+    }
+
+    #[test]
+    fn test_non_file_log_targets() {
+        let discovery = NginxDiscovery::from_config_text(
+            "http { access_log syslog:server=10.0.0.1:514,tag=nginx main; access_log stderr; }",
+        )
+        .unwrap();
+
+        let check = check_log_files(&discovery);
+        assert_eq!(check.status, DoctorStatus::Pass);
+        assert!(check.message.contains("0 configured"));
+        assert!(check.message.contains("2 syslog/stderr targets"));
+    }
+
+    #[test]
+    fn test_version_check() {
+        let discovery =
+            NginxDiscovery::from_config_text("server {\n  listen 443 quic;\n}").unwrap();
+        let required = discovery.required_version();
+
+        let check = version_check(&required, NginxVersion::new(1, 25, 3));
+        assert_eq!(check.status, DoctorStatus::Pass);
+
+        let check = version_check(&required, NginxVersion::new(1, 18, 0));
+        assert_eq!(check.status, DoctorStatus::Warning);
+        assert!(check.message.contains("\"listen quic\" on line 2"));
+    }
+
+    #[cfg(feature = "system")]
+    #[test]
+    fn test_permission_check() {
+        let discovery = NginxDiscovery::from_config_text(
+            "server { root /srv/www; auth_basic_user_file /srv/www/.htpasswd; }",
+        )
+        .unwrap();
+        let report = crate::system::audit_permissions(discovery.config(), "/etc/nginx");
+
+        let check = permission_check(&report);
+        assert_eq!(check.status, DoctorStatus::Error);
+        assert!(check.message.contains("document root /srv/www"));
+        assert!(check.remediation.is_some());
+
+        let empty = crate::system::audit_permissions(discovery.config(), "/etc/nginx");
+        let check = permission_check(&crate::system::PermissionReport {
+            findings: Vec::new(),
+            ..empty
+        });
+        assert_eq!(check.status, DoctorStatus::Pass);
+    }
+
+    #[cfg(feature = "system")]
+    #[test]
+    fn test_security_module_check() {
+        use crate::system::{audit_mac_with, MacStatus};
+
+        let discovery =
+            NginxDiscovery::from_config_text("server { listen 80; root /home/alice/www; }")
+                .unwrap();
+        let audit = |status| audit_mac_with(discovery.config(), "/etc/nginx", status);
+
+        let check = security_module_check(&audit(MacStatus::from_files(Some("1"), None)));
+        assert_eq!(check.status, DoctorStatus::Warning);
+        assert!(check
+            .message
+            .starts_with("SELinux enforcing: 1 likely denials (document root /home/alice/www"));
+        assert!(check.remediation.unwrap().contains("httpd_enable_homedirs"));
+
+        let check = security_module_check(&audit(MacStatus::from_files(Some("0"), None)));
+        assert_eq!(check.status, DoctorStatus::Pass);
+        assert!(check.message.contains("not enforcing"));
+        assert_eq!(
+            security_module_check(&audit(MacStatus::default())).status,
+            DoctorStatus::Pass
+        );
+    }
+
+    #[cfg(feature = "system")]
+    #[test]
+    fn test_socket_check() {
+        use crate::system::{listen_socket_report, ListeningSocket, Protocol, SocketOwner};
+
+        let config = crate::parse("server { listen 80; listen 8080; }").unwrap();
+        let socket = |port, name: &str| ListeningSocket {
+            protocol: Protocol::Tcp,
+            address: "0.0.0.0".parse().unwrap(),
+            port,
+            inode: 0,
+            owner: Some(SocketOwner {
+                pid: 42,
+                name: name.to_string(),
+            }),
+        };
+        let check =
+            |sockets: &[ListeningSocket]| socket_check(&listen_socket_report(&config, sockets));
+
+        let stolen = check(&[socket(80, "nginx"), socket(8080, "node")]);
+        assert_eq!(stolen.status, DoctorStatus::Error);
+        assert!(stolen
+            .message
+            .contains("port 8080/tcp is held by node (pid 42)"));
+
+        let stopped = check(&[]);
+        assert_eq!(stopped.status, DoctorStatus::Warning);
+        assert!(stopped.message.contains("nginx is not running"));
+
+        let stale = check(&[socket(80, "nginx")]);
+        assert_eq!(stale.status, DoctorStatus::Warning);
+        assert!(stale.message.ends_with("nothing is listening on 8080/tcp"));
+
+        let running = check(&[socket(80, "nginx"), socket(8080, "nginx")]);
+        assert_eq!(running.status, DoctorStatus::Pass);
+    }
+
+    #[cfg(feature = "system")]
+    #[test]
+    fn test_systemd_check() {
+        use crate::system::{audit_systemd_with, SystemdUnit};
+
+        let config = crate::parse("events { worker_connections 4096; }").unwrap();
+        let check = |unit: &str| {
+            systemd_check(&audit_systemd_with(&config, Some(SystemdUnit::parse(unit))))
+        };
+
+        let low = check("[Service]\nExecStartPre=/usr/sbin/nginx -t\nRestart=always\n");
+        assert_eq!(low.status, DoctorStatus::Warning);
+        assert!(low
+            .message
+            .starts_with("systemd unit: 1 issue(s) (LimitNOFILE"));
+        assert!(low.remediation.unwrap().contains("LimitNOFILE=8192"));
+
+        let good = check(
+            "[Service]\nExecStartPre=/usr/sbin/nginx -t\nRestart=always\nLimitNOFILE=65536\n",
+        );
+        assert_eq!(good.status, DoctorStatus::Pass);
+        assert_eq!(
+            systemd_check(&audit_systemd_with(&config, None)).message,
+            "No nginx systemd unit found"
+        );
+    }
+
+    #[test]
+    fn test_run_file_missing() {
+        let report = run_file(Path::new("/no/such/nginx.conf"), offline());
+
+        assert_eq!(
+            report.get("config_file").unwrap().status,
+            DoctorStatus::Error
+        );
+        assert_eq!(
+            report.get("config_parse").unwrap().status,
+            DoctorStatus::Error
+        );
+        assert_eq!(report.errors(), 2);
+        assert!(!report.is_ok());
+    }
+
+    #[test]
+    fn test_options_skip_checks() {
+        let discovery = NginxDiscovery::from_config_text("user nginx;").unwrap();
+        let options = DoctorOptions {
+            check_logs: false,
+            check_ssl: false,
+            ..offline()
+        };
+
+        let report = run(&discovery, options);
+        assert_eq!(report.checks.len(), 1);
+        assert_eq!(report.passed(), 1);
+    }
+}
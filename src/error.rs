@@ -224,6 +224,16 @@ impl Error {
         }
     }
 
+    /// Get the source location (line, column) of the error, if known
+    #[must_use]
+    pub fn location(&self) -> Option<(usize, usize)> {
+        match self {
+            Self::Parse { line, col, .. } | Self::Syntax { line, col, .. } => Some((*line, *col)),
+            Self::UnexpectedEof { line, .. } => Some((*line, 1)),
+            _ => None,
+        }
+    }
+
     /// Get detailed error information with formatted output
     ///
     /// This provides a beautiful, colorized error display with:
@@ -398,6 +408,13 @@ mod tests {
         assert!(detailed.contains("Expected: closing brace '}'"));
     }
 
+    #[test]
+    fn test_error_location() {
+        assert_eq!(Error::parse("bad", 3, 7).location(), Some((3, 7)));
+        assert_eq!(Error::unexpected_eof("'}'", 9).location(), Some((9, 1)));
+        assert_eq!(Error::custom("no position").location(), None);
+    }
+
     #[test]
     fn test_invalid_directive() {
         let err = Error::invalid_directive(
@@ -0,0 +1,197 @@
+//! Source-annotated and deterministic serialization of a configuration
+//!
+//! [`Annotated`] serializes a [`Config`] with the keys of every object in
+//! sorted order and, with sources included, a `source` entry
+//! (`{"file": ..., "line": ...}`) next to the `span` of every directive. It
+//! writes straight into the serializer, so the annotated and deterministic
+//! exports stream like the default one instead of building the document in
+//! memory first.
+
+use crate::ast::{Config, Directive, DirectiveItem, Span, Value};
+use serde::ser::{Serialize, SerializeMap, Serializer};
+
+/// A configuration serialized with sorted keys and optional source entries
+pub(crate) struct Annotated<'a> {
+    config: &'a Config,
+    source: Option<Source<'a>>,
+}
+
+/// File named in `source` entries
+#[derive(Clone, Copy)]
+struct Source<'a> {
+    file: Option<&'a str>,
+}
+
+impl<'a> Annotated<'a> {
+    /// `config` with sorted keys only
+    pub(crate) fn new(config: &'a Config) -> Self {
+        Self {
+            config,
+            source: None,
+        }
+    }
+
+    /// Add a `source` entry to every directive, naming `file` if given
+    pub(crate) fn with_source(mut self, file: Option<&'a str>) -> Self {
+        self.source = Some(Source { file });
+        self
+    }
+}
+
+impl Serialize for Annotated<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(1))?;
+        map.serialize_entry(
+            "directives",
+            &Directives(&self.config.directives, self.source),
+        )?;
+        map.end()
+    }
+}
+
+struct Directives<'a>(&'a [Directive], Option<Source<'a>>);
+
+impl Serialize for Directives<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.iter().map(|d| AnnotatedDirective(d, self.1)))
+    }
+}
+
+struct AnnotatedDirective<'a>(&'a Directive, Option<Source<'a>>);
+
+impl Serialize for AnnotatedDirective<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let AnnotatedDirective(directive, source) = *self;
+        let mut map = serializer.serialize_map(Some(if source.is_some() { 3 } else { 2 }))?;
+        map.serialize_entry("item", &Item(&directive.item, source))?;
+        if let Some(source) = source {
+            map.serialize_entry("source", &SourceEntry(source, directive.span.line))?;
+        }
+        map.serialize_entry("span", &SortedSpan(directive.span))?;
+        map.end()
+    }
+}
+
+struct Item<'a>(&'a DirectiveItem, Option<Source<'a>>);
+
+impl Serialize for Item<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(1))?;
+        match self.0 {
+            DirectiveItem::Simple { name, args } => {
+                map.serialize_entry("Simple", &Body(name, args, None))?;
+            }
+            DirectiveItem::Block {
+                name,
+                args,
+                children,
+            } => {
+                let children = Directives(children, self.1);
+                map.serialize_entry("Block", &Body(name, args, Some(children)))?;
+            }
+        }
+        map.end()
+    }
+}
+
+struct Body<'a>(&'a str, &'a [Value], Option<Directives<'a>>);
+
+impl Serialize for Body<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let Body(name, args, children) = self;
+        let mut map = serializer.serialize_map(Some(if children.is_some() { 3 } else { 2 }))?;
+        map.serialize_entry("args", args)?;
+        if let Some(children) = children {
+            map.serialize_entry("children", children)?;
+        }
+        map.serialize_entry("name", name)?;
+        map.end()
+    }
+}
+
+struct SourceEntry<'a>(Source<'a>, usize);
+
+impl Serialize for SourceEntry<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let SourceEntry(source, line) = self;
+        let mut map = serializer.serialize_map(Some(if source.file.is_some() { 2 } else { 1 }))?;
+        if let Some(file) = source.file {
+            map.serialize_entry("file", file)?;
+        }
+        map.serialize_entry("line", line)?;
+        map.end()
+    }
+}
+
+struct SortedSpan(Span);
+
+impl Serialize for SortedSpan {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let span = self.0;
+        let mut map = serializer.serialize_map(Some(4))?;
+        map.serialize_entry("col", &span.col)?;
+        map.serialize_entry("end", &span.end)?;
+        map.serialize_entry("line", &span.line)?;
+        map.serialize_entry("start", &span.start)?;
+        map.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The previous implementation: annotate and sort a `serde_json::Value`
+    fn through_value(config: &Config, file: Option<&str>) -> serde_json::Value {
+        fn annotate(value: &mut serde_json::Value, file: Option<&str>) {
+            match value {
+                serde_json::Value::Object(map) => {
+                    if map.contains_key("item") {
+                        let line = map["span"]["line"].clone();
+                        let mut source = serde_json::Map::new();
+                        if let Some(file) = file {
+                            source.insert("file".to_string(), file.into());
+                        }
+                        source.insert("line".to_string(), line);
+                        map.insert("source".to_string(), source.into());
+                    }
+                    for child in map.values_mut() {
+                        annotate(child, file);
+                    }
+                }
+                serde_json::Value::Array(items) => {
+                    for item in items {
+                        annotate(item, file);
+                    }
+                }
+                _ => {}
+            }
+        }
+        let mut value = serde_json::to_value(config).unwrap();
+        annotate(&mut value, file);
+        value
+    }
+
+    #[test]
+    fn test_matches_annotated_value() {
+        let config = crate::parse(
+            "http {\n    server { listen 80; location / { return 200 \"ok\"; } }\n}\nevents { }\n",
+        )
+        .unwrap();
+
+        let streamed =
+            serde_json::to_string(&Annotated::new(&config).with_source(Some("a.conf"))).unwrap();
+        let expected = serde_json::to_string(&through_value(&config, Some("a.conf"))).unwrap();
+        assert_eq!(streamed, expected);
+
+        // Sorted keys without sources: only the key order differs from the
+        // derived serialization
+        let sorted: serde_json::Value =
+            serde_json::from_str(&serde_json::to_string(&Annotated::new(&config)).unwrap())
+                .unwrap();
+        assert_eq!(sorted, serde_json::to_value(&config).unwrap());
+        assert!(serde_json::to_string(&Annotated::new(&config))
+            .unwrap()
+            .starts_with(r#"{"directives":[{"item":{"Block":{"args":[],"children":"#));
+    }
+}
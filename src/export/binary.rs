@@ -0,0 +1,253 @@
+// src/export/binary.rs
+//! `MessagePack` and CBOR export formats.
+//!
+//! Requires the `export-binary` feature. Both formats encode the same
+//! document as the JSON export, value for value: structs and objects become
+//! maps with string keys, sequences become arrays, and enums become
+//! single-entry maps keyed by the variant name. Source annotations and
+//! deterministic key order apply as they do for JSON.
+//!
+//! Encoding is done by [`rmp_serde`] and [`ciborium`], which serialize
+//! straight into the writer. The documents are about a quarter smaller than
+//! compact JSON and are loaded back without text parsing by
+//! [`from_msgpack`] and [`from_cbor`].
+//!
+//! # Examples
+//!
+//! ```
+//! use nginx_discovery::export::{binary, export, ExportFormat, ExportOptions};
+//!
+//! let config = nginx_discovery::parse("server { listen 80; }")?;
+//! let options = ExportOptions::builder().format(ExportFormat::Cbor).build();
+//! let mut bytes = Vec::new();
+//! export(&config, &mut bytes, &options)?;
+//!
+//! assert_eq!(binary::from_cbor(&bytes)?, config);
+//! # Ok::<(), nginx_discovery::Error>(())
+//! ```
+
+use crate::ast::Config;
+use crate::{Error, Result};
+use serde::Serialize;
+use std::io::Write;
+
+/// Write `value` as `MessagePack`, with structs as maps keyed by field name
+pub(crate) fn write_msgpack<W: Write, T: Serialize + ?Sized>(
+    value: &T,
+    writer: &mut W,
+) -> Result<()> {
+    super::with_io_errors(writer, |writer| {
+        rmp_serde::encode::write_named(writer, value)
+            .map_err(|e| Error::Serialization(format!("MessagePack encoding failed: {e}")))
+    })
+}
+
+/// Write `value` as CBOR (RFC 8949)
+pub(crate) fn write_cbor<W: Write, T: Serialize + ?Sized>(value: &T, writer: &mut W) -> Result<()> {
+    ciborium::ser::into_writer(value, writer).map_err(|e| match e {
+        ciborium::ser::Error::Io(e) => Error::Io(e),
+        ciborium::ser::Error::Value(e) => {
+            Error::Serialization(format!("CBOR encoding failed: {e}"))
+        }
+    })
+}
+
+/// Load a configuration exported as `MessagePack`.
+///
+/// # Errors
+///
+/// Returns [`Error::Serialization`] if `bytes` is not a `MessagePack`
+/// export of a configuration.
+pub fn from_msgpack(bytes: &[u8]) -> Result<Config> {
+    let mut rest = bytes;
+    let config = rmp_serde::decode::from_read(&mut rest)
+        .map_err(|e| Error::Serialization(format!("Invalid MessagePack: {e}")))?;
+    finish("MessagePack", bytes, rest)?;
+    Ok(config)
+}
+
+/// Load a configuration exported as CBOR.
+///
+/// # Errors
+///
+/// Returns [`Error::Serialization`] if `bytes` is not a CBOR export of a
+/// configuration.
+pub fn from_cbor(bytes: &[u8]) -> Result<Config> {
+    let mut rest = bytes;
+    let config = ciborium::de::from_reader(&mut rest)
+        .map_err(|e| Error::Serialization(format!("Invalid CBOR: {e}")))?;
+    finish("CBOR", bytes, rest)?;
+    Ok(config)
+}
+
+/// Reject bytes left over after the document
+fn finish(format: &str, bytes: &[u8], rest: &[u8]) -> Result<()> {
+    if rest.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::Serialization(format!(
+            "Invalid {format}: trailing data at byte {}",
+            bytes.len() - rest.len()
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{export, ExportFormat, ExportOptions};
+
+    fn export_as(config: &Config, options: &ExportOptions) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        export(config, &mut bytes, options).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_binary_round_trip() {
+        let config = crate::parse(
+            "http {\n    server { listen 80; server_name \"a b\"; location / { return 200 $uri; } }\n}\n",
+        )
+        .unwrap();
+
+        for source in [false, true] {
+            let options = |format| {
+                ExportOptions::builder()
+                    .format(format)
+                    .include_source(source)
+                    .source_file("nginx.conf")
+                    .build()
+            };
+            let msgpack = export_as(&config, &options(ExportFormat::MessagePack));
+            assert_eq!(from_msgpack(&msgpack).unwrap(), config);
+            let cbor = export_as(&config, &options(ExportFormat::Cbor));
+            assert_eq!(from_cbor(&cbor).unwrap(), config);
+        }
+    }
+
+    #[test]
+    fn test_binary_matches_json_document() {
+        let config = crate::parse(
+            "events { }\nhttp {\n    upstream app { server 10.0.0.1:8080 weight=5; }\n    server { listen 443 ssl; location ~ ^/api { proxy_pass http://app; } }\n}\n",
+        )
+        .unwrap();
+
+        for (source, deterministic) in [(false, false), (true, false), (false, true)] {
+            let options = |format| {
+                ExportOptions::builder()
+                    .format(format)
+                    .include_source(source)
+                    .deterministic(deterministic)
+                    .build()
+            };
+            let json: serde_json::Value =
+                serde_json::from_slice(&export_as(&config, &options(ExportFormat::Json))).unwrap();
+
+            // Decoded into a generic value, without the configuration types
+            let msgpack = export_as(&config, &options(ExportFormat::MessagePack));
+            let decoded: serde_json::Value = rmp_serde::from_slice(&msgpack).unwrap();
+            assert_eq!(decoded, json);
+
+            let cbor = export_as(&config, &options(ExportFormat::Cbor));
+            let decoded: ciborium::value::Value = ciborium::de::from_reader(&cbor[..]).unwrap();
+            let decoded: serde_json::Value = decoded.deserialized().unwrap();
+            assert_eq!(decoded, json);
+        }
+    }
+
+    #[test]
+    fn test_binary_encoding() {
+        #[derive(Serialize)]
+        struct Doc {
+            a: Vec<i64>,
+        }
+        let doc = Doc { a: vec![1, -1] };
+
+        // Checked against the specifications
+        let mut bytes = Vec::new();
+        write_cbor(&doc, &mut bytes).unwrap();
+        assert_eq!(bytes, [0xa1, 0x61, b'a', 0x82, 0x01, 0x20]);
+        let mut bytes = Vec::new();
+        write_msgpack(&doc, &mut bytes).unwrap();
+        assert_eq!(bytes, [0x81, 0xa1, b'a', 0x92, 0x01, 0xff]);
+    }
+
+    #[test]
+    fn test_invalid_binary_input() {
+        let config = crate::parse("server { listen 80; }").unwrap();
+        let cbor = export_as(
+            &config,
+            &ExportOptions::builder().format(ExportFormat::Cbor).build(),
+        );
+        let msgpack = export_as(
+            &config,
+            &ExportOptions::builder()
+                .format(ExportFormat::MessagePack)
+                .build(),
+        );
+
+        // Truncated input
+        assert!(from_cbor(&cbor[..cbor.len() - 1]).is_err());
+        assert!(from_msgpack(&msgpack[..msgpack.len() - 1]).is_err());
+
+        // Trailing data
+        let mut extra = cbor.clone();
+        extra.push(0);
+        let err = from_cbor(&extra).unwrap_err().to_string();
+        assert!(err.contains("trailing data"), "{err}");
+        let mut extra = msgpack.clone();
+        extra.push(0);
+        assert!(from_msgpack(&extra).is_err());
+
+        // Forged lengths, the wrong format and documents of another shape
+        assert!(from_msgpack(&[0xdd, 0xff, 0xff, 0xff, 0xff]).is_err());
+        assert!(from_msgpack(&[0xdb, 0xff, 0xff, 0xff, 0xff]).is_err());
+        assert!(from_cbor(&[0x9a, 0xff, 0xff, 0xff, 0xff]).is_err());
+        assert!(from_cbor(&msgpack).is_err());
+        assert!(from_msgpack(&[0x90]).is_err());
+        assert!(from_cbor(&[]).is_err());
+    }
+
+    #[test]
+    fn test_binary_edge_cases() {
+        // An empty configuration round-trips
+        let empty = crate::parse("").unwrap();
+        for format in [ExportFormat::MessagePack, ExportFormat::Cbor] {
+            let bytes = export_as(&empty, &ExportOptions::builder().format(format).build());
+            assert!(!bytes.is_empty());
+            let decoded = match format {
+                ExportFormat::Cbor => from_cbor(&bytes),
+                _ => from_msgpack(&bytes),
+            };
+            assert_eq!(decoded.unwrap(), empty);
+        }
+
+        // Pretty printing does not change the bytes
+        let config = crate::parse("server { listen 80; }").unwrap();
+        for format in [ExportFormat::MessagePack, ExportFormat::Cbor] {
+            let options = |pretty| {
+                ExportOptions::builder()
+                    .format(format)
+                    .pretty(pretty)
+                    .build()
+            };
+            assert_eq!(
+                export_as(&config, &options(true)),
+                export_as(&config, &options(false))
+            );
+        }
+
+        // Errors name the format
+        let err = from_msgpack(&[]).unwrap_err();
+        assert!(
+            matches!(&err, Error::Serialization(msg) if msg.starts_with("Invalid MessagePack"))
+        );
+        let err = from_cbor(&[0xff]).unwrap_err();
+        assert!(matches!(&err, Error::Serialization(msg) if msg.starts_with("Invalid CBOR")));
+        let err = finish("CBOR", &[1, 2, 3], &[3]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            Error::Serialization("Invalid CBOR: trailing data at byte 2".to_string()).to_string()
+        );
+    }
+}
@@ -0,0 +1,528 @@
+//! Export the effective configuration of every location
+//!
+//! A directive dump shows what each block says, not what applies in it:
+//! a location without `root` serves from the server's root, and one
+//! `add_header` in a location drops every header set in the server.
+//! [`effective_config`] resolves the inheritance and lists, for every
+//! server and location of the `http` block, the settings that actually
+//! apply together with the block they come from:
+//!
+//! - `root` from the innermost block that sets it, or the location's own
+//!   `alias` (which is not inherited)
+//! - `index`, `add_header`, `access_log` and `error_log` from the
+//!   innermost block that has at least one: a block setting any of them
+//!   replaces all inherited ones
+//! - every `*_timeout` directive, each from the innermost block setting it
+//!
+//! Locations keep their nesting, so the export is a tree of servers and
+//! locations.
+//!
+//! # Examples
+//!
+//! ```
+//! use nginx_discovery::export::effective::effective_config;
+//! use nginx_discovery::parse;
+//!
+//! let config = parse(r#"
+//! http {
+//!     proxy_read_timeout 30s;
+//!     server {
+//!         root /srv/www;
+//!         add_header X-Frame-Options DENY;
+//!         location /api { add_header Cache-Control no-store; }
+//!     }
+//! }
+//! "#)?;
+//! let effective = effective_config(&config);
+//! let api = &effective.servers[0].locations[0];
+//!
+//! let root = api.settings.root.as_ref().unwrap();
+//! assert_eq!(root.args, vec!["/srv/www"]);
+//! assert!(root.inherited);
+//! assert_eq!(api.settings.headers.len(), 1);
+//! assert_eq!(api.settings.timeouts[0].from, "http");
+//! # Ok::<(), nginx_discovery::Error>(())
+//! ```
+
+use super::ExportFormat;
+use crate::ast::{Config, Directive};
+use crate::lint::walk;
+use crate::Result;
+use std::io::Write;
+
+/// List directives whose inherited values are replaced as a whole
+const LIST_DIRECTIVES: [&str; 4] = ["index", "add_header", "access_log", "error_log"];
+
+/// A directive in effect in a block
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct EffectiveDirective {
+    /// Directive name
+    pub name: String,
+
+    /// Arguments as written
+    pub args: Vec<String>,
+
+    /// Line of the directive
+    pub line: usize,
+
+    /// Block it is written in: `http`, `server` or `location /path`
+    pub from: String,
+
+    /// Whether it comes from an enclosing block
+    pub inherited: bool,
+}
+
+/// Settings in effect in a server or location
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct EffectiveSettings {
+    /// `root`, or the location's own `alias`
+    pub root: Option<EffectiveDirective>,
+
+    /// `index` directives
+    pub index: Vec<EffectiveDirective>,
+
+    /// `add_header` directives
+    pub headers: Vec<EffectiveDirective>,
+
+    /// `*_timeout` directives, by name
+    pub timeouts: Vec<EffectiveDirective>,
+
+    /// `access_log` directives
+    pub access_logs: Vec<EffectiveDirective>,
+
+    /// `error_log` directives
+    pub error_logs: Vec<EffectiveDirective>,
+}
+
+/// A location with its effective settings and nested locations
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct EffectiveLocation {
+    /// Modifier and path, as written (`= /exact`, `/api`)
+    pub location: String,
+
+    /// Line of the location block
+    pub line: usize,
+
+    /// Settings in effect
+    #[serde(flatten)]
+    pub settings: EffectiveSettings,
+
+    /// Nested locations
+    pub locations: Vec<EffectiveLocation>,
+}
+
+/// A server with its effective settings and locations
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct EffectiveServer {
+    /// Names from `server_name`
+    pub server_names: Vec<String>,
+
+    /// Arguments of each `listen` directive
+    pub listen: Vec<String>,
+
+    /// Line of the server block
+    pub line: usize,
+
+    /// Settings in effect for requests no location matches
+    #[serde(flatten)]
+    pub settings: EffectiveSettings,
+
+    /// Top-level locations
+    pub locations: Vec<EffectiveLocation>,
+}
+
+/// Effective configuration of every `http` server, in file order
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct EffectiveConfig {
+    /// Servers of the `http` blocks
+    pub servers: Vec<EffectiveServer>,
+}
+
+impl EffectiveConfig {
+    /// Write the effective configuration in `format`
+    ///
+    /// Markdown renders each server as a section with its locations as a
+    /// nested list.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if serialization or writing fails, and for XML,
+    /// whose schema only describes the configuration as written.
+    pub fn write<W: Write>(
+        &self,
+        writer: &mut W,
+        format: ExportFormat,
+        pretty: bool,
+    ) -> Result<()> {
+        let text = match format {
+            ExportFormat::Json if pretty => serde_json::to_string_pretty(self)?,
+            ExportFormat::Json => serde_json::to_string(self)?,
+            ExportFormat::Yaml => serde_yaml::to_string(self)?,
+            #[cfg(feature = "export-toml")]
+            ExportFormat::Toml => toml::to_string_pretty(self)?,
+            #[cfg(feature = "export-markdown")]
+            ExportFormat::Markdown => markdown(self),
+            #[cfg(feature = "export-xml")]
+            ExportFormat::Xml => {
+                return Err(crate::Error::NotImplemented(
+                    "XML export of the effective configuration".to_string(),
+                ))
+            }
+            #[cfg(feature = "export-binary")]
+            ExportFormat::MessagePack => return super::binary::write_msgpack(self, writer),
+            #[cfg(feature = "export-binary")]
+            ExportFormat::Cbor => return super::binary::write_cbor(self, writer),
+        };
+        writer.write_all(text.as_bytes())?;
+        Ok(())
+    }
+}
+
+/// Resolve the settings in effect in every server and location of the
+/// `http` blocks
+#[must_use]
+pub fn effective_config(config: &Config) -> EffectiveConfig {
+    let mut servers = Vec::new();
+    walk(config, &mut |directive, parents| {
+        if directive.name() != "server"
+            || !directive.is_block()
+            || parents.last().map(|p| p.name()) != Some("http")
+        {
+            return;
+        }
+        let chain: Vec<&Directive> = std::iter::once(directive)
+            .chain(parents.iter().rev().copied())
+            .collect();
+        servers.push(EffectiveServer {
+            server_names: directive
+                .find_children("server_name")
+                .iter()
+                .flat_map(|d| d.args_as_strings())
+                .collect(),
+            listen: directive
+                .find_children("listen")
+                .iter()
+                .map(|d| d.args_as_strings().join(" "))
+                .collect(),
+            line: directive.span.line,
+            settings: settings(&chain),
+            locations: locations(&chain),
+        });
+    });
+    EffectiveConfig { servers }
+}
+
+/// Locations directly inside `chain[0]`
+fn locations(chain: &[&Directive]) -> Vec<EffectiveLocation> {
+    chain[0]
+        .find_children("location")
+        .into_iter()
+        .filter(|location| location.is_block())
+        .map(|location| {
+            let chain: Vec<&Directive> = std::iter::once(location)
+                .chain(chain.iter().copied())
+                .collect();
+            EffectiveLocation {
+                location: location.args_as_strings().join(" "),
+                line: location.span.line,
+                settings: settings(&chain),
+                locations: locations(&chain),
+            }
+        })
+        .collect()
+}
+
+/// Settings in effect in `chain[0]`, given its enclosing blocks
+fn settings(chain: &[&Directive]) -> EffectiveSettings {
+    let alias = chain[0]
+        .find_children("alias")
+        .last()
+        .map(|alias| entry(alias, chain[0], false));
+
+    let mut timeouts: Vec<&str> = chain
+        .iter()
+        .flat_map(|block| block.children().unwrap_or_default())
+        .map(Directive::name)
+        .filter(|name| name.ends_with("_timeout"))
+        .collect();
+    timeouts.sort_unstable();
+    timeouts.dedup();
+
+    let [index, headers, access_logs, error_logs] = LIST_DIRECTIVES.map(|name| list(chain, name));
+    EffectiveSettings {
+        root: alias.or_else(|| single(chain, "root")),
+        index,
+        headers,
+        timeouts: timeouts
+            .into_iter()
+            .filter_map(|name| single(chain, name))
+            .collect(),
+        access_logs,
+        error_logs,
+    }
+}
+
+/// Last `name` in the innermost block that sets it
+fn single(chain: &[&Directive], name: &str) -> Option<EffectiveDirective> {
+    chain.iter().enumerate().find_map(|(depth, block)| {
+        block
+            .find_children(name)
+            .last()
+            .map(|directive| entry(directive, block, depth > 0))
+    })
+}
+
+/// Every `name` in the innermost block that has any
+fn list(chain: &[&Directive], name: &str) -> Vec<EffectiveDirective> {
+    chain
+        .iter()
+        .enumerate()
+        .find_map(|(depth, block)| {
+            let found = block.find_children(name);
+            (!found.is_empty()).then(|| {
+                found
+                    .into_iter()
+                    .map(|directive| entry(directive, block, depth > 0))
+                    .collect()
+            })
+        })
+        .unwrap_or_default()
+}
+
+fn entry(directive: &Directive, block: &Directive, inherited: bool) -> EffectiveDirective {
+    let from = match block.name() {
+        "location" => format!("location {}", block.args_as_strings().join(" ")),
+        name => name.to_string(),
+    };
+    EffectiveDirective {
+        name: directive.name().to_string(),
+        args: directive.args_as_strings(),
+        line: directive.span.line,
+        from,
+        inherited,
+    }
+}
+
+#[cfg(feature = "export-markdown")]
+fn markdown(config: &EffectiveConfig) -> String {
+    use std::fmt::Write as FmtWrite;
+
+    fn write_settings(md: &mut String, settings: &EffectiveSettings, indent: &str) {
+        let all = settings
+            .root
+            .iter()
+            .chain(&settings.index)
+            .chain(&settings.headers)
+            .chain(&settings.timeouts)
+            .chain(&settings.access_logs)
+            .chain(&settings.error_logs);
+        for d in all {
+            let origin = if d.inherited {
+                format!(" (from {}, line {})", d.from, d.line)
+            } else {
+                String::new()
+            };
+            let _ = writeln!(md, "{indent}- `{} {}`{origin}", d.name, d.args.join(" "));
+        }
+    }
+
+    fn write_locations(md: &mut String, locations: &[EffectiveLocation], indent: &str) {
+        for location in locations {
+            let _ = writeln!(
+                md,
+                "{indent}- **location {}** (line {})",
+                location.location, location.line
+            );
+            let nested = format!("{indent}  ");
+            write_settings(md, &location.settings, &nested);
+            write_locations(md, &location.locations, &nested);
+        }
+    }
+
+    let mut md = String::from("# Effective Configuration\n");
+    for server in &config.servers {
+        let names = if server.server_names.is_empty() {
+            "_".to_string()
+        } else {
+            server.server_names.join(", ")
+        };
+        let _ = writeln!(md, "\n## Server {names} (line {})\n", server.line);
+        write_settings(&mut md, &server.settings, "");
+        write_locations(&mut md, &server.locations, "");
+    }
+    md
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_effective_config() {
+        let config = parse(
+            "http {
+               access_log /var/log/nginx/access.log;
+               proxy_read_timeout 30s;
+               server {
+                 listen 80;
+                 root /srv/www;
+                 add_header X-Frame-Options DENY;
+                 location /static {
+                   alias /srv/static/;
+                   access_log off;
+                   location /static/img { proxy_read_timeout 5s; }
+                 }
+               }
+             }",
+        )
+        .unwrap();
+        let effective = effective_config(&config);
+        let server = &effective.servers[0];
+
+        assert_eq!(server.listen, vec!["80"]);
+        assert!(!server.settings.root.as_ref().unwrap().inherited);
+        assert_eq!(server.settings.access_logs[0].from, "http");
+
+        let statics = &server.locations[0];
+        assert_eq!(statics.settings.root.as_ref().unwrap().name, "alias");
+        assert_eq!(statics.settings.headers[0].line, 7);
+        assert_eq!(statics.settings.access_logs[0].args, vec!["off"]);
+
+        // alias is not inherited; the server's root is
+        let img = &statics.locations[0];
+        let root = img.settings.root.as_ref().unwrap();
+        assert_eq!((root.name.as_str(), root.from.as_str()), ("root", "server"));
+        assert_eq!(img.settings.access_logs[0].from, "location /static");
+        assert_eq!(img.settings.timeouts[0].args, vec!["5s"]);
+        assert!(!img.settings.timeouts[0].inherited);
+
+        let mut out = Vec::new();
+        effective
+            .write(&mut out, ExportFormat::Json, false)
+            .unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(value["servers"][0]["locations"][0]["location"], "/static");
+    }
+
+    #[test]
+    fn test_effective_config_edge_cases() {
+        assert_eq!(
+            effective_config(&parse("").unwrap()),
+            EffectiveConfig::default()
+        );
+
+        let config = parse(
+            "stream { server { listen 53; proxy_timeout 1s; } }
+http {
+  index index.html;
+  send_timeout 10s;
+  add_header A 1;
+  add_header B 2;
+  server {
+    root /a;
+    root /b;
+    client_body_timeout 5s;
+    location = /exact {
+      index home.html;
+      send_timeout 1s;
+      location /nested { add_header C 3; }
+    }
+  }
+}",
+        )
+        .unwrap();
+        let effective = effective_config(&config);
+
+        // Only http servers; the last root of a block wins
+        assert_eq!(effective.servers.len(), 1);
+        let server = &effective.servers[0];
+        assert!(server.server_names.is_empty() && server.listen.is_empty());
+        assert_eq!(server.settings.root.as_ref().unwrap().args, vec!["/b"]);
+        assert_eq!(server.settings.headers.len(), 2);
+        assert!(server.settings.headers.iter().all(|h| h.inherited));
+
+        // Timeouts by name, each from the innermost block setting it
+        let exact = &server.locations[0];
+        assert_eq!(exact.location, "= /exact");
+        let timeouts: Vec<_> = exact
+            .settings
+            .timeouts
+            .iter()
+            .map(|t| (t.name.as_str(), t.from.as_str(), t.inherited))
+            .collect();
+        assert_eq!(
+            timeouts,
+            vec![
+                ("client_body_timeout", "server", true),
+                ("send_timeout", "location = /exact", false)
+            ]
+        );
+        assert_eq!(exact.settings.index[0].args, vec!["home.html"]);
+
+        // One add_header in a location drops the inherited ones
+        let nested = &exact.locations[0];
+        assert_eq!(nested.settings.headers.len(), 1);
+        assert_eq!(nested.settings.headers[0].args, vec!["C", "3"]);
+        assert_eq!(nested.settings.index[0].from, "location = /exact");
+        assert!(nested.settings.index[0].inherited);
+        assert!(nested.locations.is_empty());
+    }
+
+    #[test]
+    fn test_effective_config_write_edge_cases() {
+        struct Closed;
+
+        impl Write for Closed {
+            fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+                Err(std::io::ErrorKind::BrokenPipe.into())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let config = parse("http { send_timeout 10s; server { location / { } } }").unwrap();
+        let effective = effective_config(&config);
+
+        let mut out = Vec::new();
+        effective.write(&mut out, ExportFormat::Yaml, true).unwrap();
+        let yaml = String::from_utf8(out).unwrap();
+        assert!(yaml.contains("from: http"), "{yaml}");
+
+        #[cfg(feature = "export-markdown")]
+        {
+            let mut out = Vec::new();
+            effective
+                .write(&mut out, ExportFormat::Markdown, false)
+                .unwrap();
+            let md = String::from_utf8(out).unwrap();
+            assert!(
+                md.contains("## Server _ (line 1)\n\n- `send_timeout 10s` (from http, line 1)\n")
+            );
+            assert!(md.contains(
+                "- **location /** (line 1)\n  - `send_timeout 10s` (from http, line 1)\n"
+            ));
+        }
+
+        #[cfg(feature = "export-xml")]
+        assert!(matches!(
+            effective.write(&mut Vec::new(), ExportFormat::Xml, false),
+            Err(crate::Error::NotImplemented(_))
+        ));
+
+        // Writer failures are I/O errors, for text and binary formats
+        #[allow(unused_mut)]
+        let mut formats = vec![ExportFormat::Json, ExportFormat::Yaml];
+        #[cfg(feature = "export-binary")]
+        formats.extend([ExportFormat::MessagePack, ExportFormat::Cbor]);
+        for format in formats {
+            let err = effective.write(&mut Closed, format, false).unwrap_err();
+            assert!(
+                matches!(&err, crate::Error::Io(e) if e.kind() == std::io::ErrorKind::BrokenPipe),
+                "{format}: {err:?}"
+            );
+        }
+    }
+}
@@ -3,9 +3,11 @@
 //!
 //! This module provides filtering capabilities to selectively export
 //! parts of NGINX configurations based on various criteria such as
-//! server name, port, SSL status, or directive name.
+//! server name, port, SSL status, directive name, or the
+//! [`ConfigPath`] of a block.
 
-use crate::{ast::Config, Error, Result};
+use crate::ast::{Config, ConfigPath, Directive, DirectiveItem, PathSegment};
+use crate::{Error, Result};
 
 /// Filter for selecting specific configuration elements.
 ///
@@ -56,6 +58,10 @@ pub enum FilterType {
 
     /// Filter by directive name
     Directive,
+
+    /// Filter to the directive a [`ConfigPath`] addresses, such as
+    /// `http/server[2]/location[3]`, with the blocks leading to it
+    Path,
 }
 
 impl Filter {
@@ -124,6 +130,9 @@ impl Filter {
             FilterType::Directive => {
                 Self::filter_by_directive(&self.pattern, &mut filtered);
             }
+            FilterType::Path => {
+                Self::filter_by_path(&self.pattern, &mut filtered)?;
+            }
             FilterType::Upstream | FilterType::Location => {
                 return Err(Error::NotImplemented(format!(
                     "Filter type {:?} not yet implemented",
@@ -176,6 +185,47 @@ impl Filter {
         // Keep only directives matching the specified name
         config.directives.retain(|d| d.name() == directive_name);
     }
+
+    /// Filters configuration to the directive a path addresses and the
+    /// blocks leading to it; nothing is left if the path leads nowhere.
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::InvalidInput` if the pattern is not a valid path.
+    fn filter_by_path(pattern: &str, config: &mut Config) -> Result<()> {
+        let path = ConfigPath::parse(pattern)?;
+        let Some((last, blocks)) = path.segments().split_last() else {
+            return Ok(());
+        };
+        if config.resolve(&path).is_none() {
+            config.directives.clear();
+            return Ok(());
+        }
+
+        // The path resolves, so every directive on the way is a block
+        let mut directives = &mut config.directives;
+        for segment in blocks {
+            keep_only(directives, segment);
+            let DirectiveItem::Block { children, .. } = &mut directives[0].item else {
+                return Ok(());
+            };
+            directives = children;
+        }
+        keep_only(directives, last);
+        Ok(())
+    }
+}
+
+/// Drop every directive but the one `segment` addresses, which exists
+fn keep_only(directives: &mut Vec<Directive>, segment: &PathSegment) {
+    let mut same_name = 0;
+    directives.retain(|d| {
+        if d.name() != segment.name {
+            return false;
+        }
+        same_name += 1;
+        same_name == segment.position
+    });
 }
 
 impl std::str::FromStr for Filter {
@@ -186,7 +236,7 @@ impl std::str::FromStr for Filter {
     /// # Format
     ///
     /// Filters are specified as `type=pattern`, where:
-    /// - `type` is one of: `server_name`, `server`, `port`, `upstream`, `location`, `ssl`, `ssl_only`, `directive`
+    /// - `type` is one of: `server_name`, `server`, `port`, `upstream`, `location`, `ssl`, `ssl_only`, `directive`, `path`
     /// - `pattern` is the value to match (can include wildcards for server names)
     ///
     /// # Examples
@@ -198,6 +248,7 @@ impl std::str::FromStr for Filter {
     /// let filter: Filter = "port=443".parse().unwrap();
     /// let filter: Filter = "ssl_only=true".parse().unwrap();
     /// let filter: Filter = "directive=proxy_pass".parse().unwrap();
+    /// let filter: Filter = "path=http/server[2]/location".parse().unwrap();
     /// ```
     ///
     /// # Errors
@@ -205,6 +256,7 @@ impl std::str::FromStr for Filter {
     /// Returns an error if:
     /// - The string is not in `type=pattern` format
     /// - The filter type is not recognized
+    /// - A `path` pattern is not a valid [`ConfigPath`]
     fn from_str(s: &str) -> Result<Self> {
         // Parse filter string like "server_name=*.example.com" or "port=443"
         let parts: Vec<&str> = s.splitn(2, '=').collect();
@@ -222,6 +274,10 @@ impl std::str::FromStr for Filter {
             "location" => FilterType::Location,
             "ssl" | "ssl_only" => FilterType::SslOnly,
             "directive" => FilterType::Directive,
+            "path" => {
+                ConfigPath::parse(parts[1])?;
+                FilterType::Path
+            }
             other => {
                 return Err(Error::InvalidInput(format!("Unknown filter type: {other}")));
             }
@@ -288,4 +344,45 @@ mod tests {
         let result = filter.apply(&config);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_path_filter() {
+        let config = crate::parse(
+            "user nginx;\nhttp {\n    gzip on;\n    server { listen 80; }\n    server {\n        listen 443;\n        location / { return 204; }\n        location /api { proxy_pass http://api; }\n    }\n}\n",
+        )
+        .unwrap();
+
+        let filter: Filter = "path=http/server[2]/location[2]".parse().unwrap();
+        assert_eq!(filter.filter_type, FilterType::Path);
+        let filtered = filter.apply(&config).unwrap();
+        assert_eq!(
+            filtered.to_config_string(),
+            crate::parse("http { server { location /api { proxy_pass http://api; } } }")
+                .unwrap()
+                .to_config_string()
+        );
+
+        // A simple directive, the root, and paths leading nowhere
+        let filtered = Filter::new(FilterType::Path, "http/gzip")
+            .apply(&config)
+            .unwrap();
+        assert_eq!(filtered.count_directives(), 2);
+        let filtered = Filter::new(FilterType::Path, "/").apply(&config).unwrap();
+        assert_eq!(filtered, config);
+        for nowhere in ["http/server[3]", "user/worker_processes", "events"] {
+            let filtered = Filter::new(FilterType::Path, nowhere)
+                .apply(&config)
+                .unwrap();
+            assert!(filtered.directives.is_empty(), "{nowhere}");
+        }
+    }
+
+    #[test]
+    fn test_invalid_path_filter() {
+        assert!("path=http//server".parse::<Filter>().is_err());
+        let err = "path=server[x]".parse::<Filter>().unwrap_err().to_string();
+        assert!(err.contains("must be a number"), "{err}");
+        let filter = Filter::new(FilterType::Path, "server[0]");
+        assert!(filter.apply(&Config::default()).is_err());
+    }
 }
@@ -0,0 +1,365 @@
+//! Export findings together with the configuration they were found in
+//!
+//! [`FindingsExport`] gathers findings from analyzers, lint rules and
+//! network checks, tags each with its [`FindingCategory`], and writes them
+//! with the configuration into one document, so a single artifact records
+//! what the configuration was and what was found. Categories and a minimum
+//! severity can be selected at export time.
+//!
+//! # Examples
+//!
+//! ```
+//! use nginx_discovery::export::findings::{FindingCategory, FindingsExport};
+//! use nginx_discovery::export::ExportFormat;
+//! use nginx_discovery::lint::{Linter, Severity};
+//! use nginx_discovery::parse;
+//!
+//! let config = parse("http { server { listen 80; root /a; root /b; } }")?;
+//! let export = FindingsExport::new()
+//!     .with_lint(Linter::with_default_rules().check(&config))
+//!     .with_analyzers(&config)
+//!     .categories([FindingCategory::Lint])
+//!     .min_severity(Severity::Warning);
+//!
+//! let document = export.document(&config);
+//! assert_eq!(document.findings[0].finding.rule, "duplicate_directive");
+//!
+//! let mut out = Vec::new();
+//! export.write(&config, &mut out, ExportFormat::Json, true)?;
+//! # Ok::<(), nginx_discovery::Error>(())
+//! ```
+
+use super::ExportFormat;
+use crate::analyze;
+use crate::ast::Config;
+use crate::lint::{Finding, Severity};
+use crate::Result;
+use std::fmt;
+use std::io::Write;
+
+/// Where a finding comes from
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum FindingCategory {
+    /// A configuration analyzer ([`analyze`])
+    Analyzer,
+    /// A lint rule ([`Linter`](crate::lint::Linter))
+    Lint,
+    /// A network check
+    Network,
+}
+
+impl fmt::Display for FindingCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::Analyzer => "analyzer",
+            Self::Lint => "lint",
+            Self::Network => "network",
+        };
+        write!(f, "{s}")
+    }
+}
+
+impl std::str::FromStr for FindingCategory {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "analyzer" | "analyze" => Ok(Self::Analyzer),
+            "lint" => Ok(Self::Lint),
+            "network" => Ok(Self::Network),
+            _ => Err(format!(
+                "Invalid finding category '{s}'. Valid categories: analyzer, lint, network"
+            )),
+        }
+    }
+}
+
+/// A finding tagged with its category
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct CategorizedFinding {
+    /// Where the finding comes from
+    pub category: FindingCategory,
+
+    /// The finding itself
+    #[serde(flatten)]
+    pub finding: Finding,
+}
+
+/// The exported document: the configuration and the selected findings
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct FindingsDocument {
+    /// The configuration the findings refer to
+    pub config: Config,
+
+    /// Selected findings, by category and then in the order they were added
+    pub findings: Vec<CategorizedFinding>,
+}
+
+/// Collects findings and exports the selected ones with the configuration
+#[derive(Debug, Clone, Default)]
+pub struct FindingsExport {
+    findings: Vec<CategorizedFinding>,
+    categories: Option<Vec<FindingCategory>>,
+    min_severity: Option<Severity>,
+}
+
+impl FindingsExport {
+    /// Create an empty export including every category and severity
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add findings of the given category
+    #[must_use]
+    pub fn with_findings(
+        mut self,
+        category: FindingCategory,
+        findings: impl IntoIterator<Item = Finding>,
+    ) -> Self {
+        self.findings.extend(
+            findings
+                .into_iter()
+                .map(|finding| CategorizedFinding { category, finding }),
+        );
+        self
+    }
+
+    /// Add lint findings
+    #[must_use]
+    pub fn with_lint(self, findings: impl IntoIterator<Item = Finding>) -> Self {
+        self.with_findings(FindingCategory::Lint, findings)
+    }
+
+    /// Run the analyzers that need nothing but the configuration (session
+    /// affinity, `auth_request`, bot blocking, caching, gRPC, header inheritance,
+    /// keepalive, log formats, `X-Accel-Redirect` offload, performance,
+    /// QUIC, real IP, secrets) and add their findings
+    #[must_use]
+    pub fn with_analyzers(self, config: &Config) -> Self {
+        let findings = [
+            analyze::affinity_audit(config).findings,
+            analyze::auth_request_audit(config).findings,
+            analyze::bot_blocking(config).findings,
+            analyze::caching_audit(config).findings,
+            analyze::grpc_audit(config).findings,
+            analyze::header_inheritance(config).findings,
+            analyze::keepalive_audit(config).findings,
+            analyze::log_format_compat(config).findings,
+            analyze::xaccel_offload(config).findings,
+            analyze::performance(config).findings,
+            analyze::quic_audit(config).findings,
+            analyze::real_ip_audit(config).findings,
+            analyze::find_secrets(config).findings,
+        ];
+        self.with_findings(FindingCategory::Analyzer, findings.into_iter().flatten())
+    }
+
+    /// Add the problems reported by network checks
+    ///
+    /// Healthy and not applicable results are left out. The rule id is
+    /// `network_<check type>`; critical results become errors.
+    #[cfg(feature = "network")]
+    #[must_use]
+    pub fn with_network(self, results: &[crate::network::NetworkCheckResult]) -> Self {
+        use crate::network::{CheckSeverity, HealthStatus};
+
+        let findings = results
+            .iter()
+            .filter(|r| {
+                !matches!(
+                    r.status,
+                    HealthStatus::Healthy | HealthStatus::NotApplicable
+                )
+            })
+            .map(|r| {
+                let severity = match r.severity {
+                    CheckSeverity::Info => Severity::Info,
+                    CheckSeverity::Warning => Severity::Warning,
+                    CheckSeverity::Error | CheckSeverity::Critical => Severity::Error,
+                };
+                let mut finding = Finding::new(
+                    format!("network_{}", r.check_type),
+                    severity,
+                    format!("{}: {}", r.target, r.message),
+                );
+                finding.help.clone_from(&r.details);
+                finding
+            });
+        self.with_findings(FindingCategory::Network, findings)
+    }
+
+    /// Export only these categories
+    #[must_use]
+    pub fn categories(mut self, categories: impl IntoIterator<Item = FindingCategory>) -> Self {
+        self.categories = Some(categories.into_iter().collect());
+        self
+    }
+
+    /// Export only findings at least this severe
+    #[must_use]
+    pub fn min_severity(mut self, severity: Severity) -> Self {
+        self.min_severity = Some(severity);
+        self
+    }
+
+    /// Findings passing the category and severity selection
+    pub fn selected(&self) -> impl Iterator<Item = &CategorizedFinding> {
+        self.findings.iter().filter(|f| {
+            self.categories
+                .as_ref()
+                .map_or(true, |categories| categories.contains(&f.category))
+                && self
+                    .min_severity
+                    .map_or(true, |min| f.finding.severity >= min)
+        })
+    }
+
+    /// Build the document for `config`
+    #[must_use]
+    pub fn document(&self, config: &Config) -> FindingsDocument {
+        let mut findings: Vec<CategorizedFinding> = self.selected().cloned().collect();
+        findings.sort_by_key(|f| f.category);
+        FindingsDocument {
+            config: config.clone(),
+            findings,
+        }
+    }
+
+    /// Write the document for `config` in `format`
+    ///
+    /// Markdown lists the findings and the configuration as text.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if serialization or writing fails.
+    pub fn write<W: Write>(
+        &self,
+        config: &Config,
+        writer: &mut W,
+        format: ExportFormat,
+        pretty: bool,
+    ) -> Result<()> {
+        let document = self.document(config);
+        let text = match format {
+            ExportFormat::Json if pretty => serde_json::to_string_pretty(&document)?,
+            ExportFormat::Json => serde_json::to_string(&document)?,
+            ExportFormat::Yaml => serde_yaml::to_string(&document)?,
+            #[cfg(feature = "export-toml")]
+            ExportFormat::Toml => toml::to_string_pretty(&document)?,
+            #[cfg(feature = "export-markdown")]
+            ExportFormat::Markdown => markdown(&document),
+            #[cfg(feature = "export-xml")]
+            ExportFormat::Xml => return super::xml::write_findings(&document, writer, pretty),
+            #[cfg(feature = "export-binary")]
+            ExportFormat::MessagePack => return super::binary::write_msgpack(&document, writer),
+            #[cfg(feature = "export-binary")]
+            ExportFormat::Cbor => return super::binary::write_cbor(&document, writer),
+        };
+        writer.write_all(text.as_bytes())?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "export-markdown")]
+fn markdown(document: &FindingsDocument) -> String {
+    use std::fmt::Write as FmtWrite;
+
+    let mut md = String::from("# Findings\n\n");
+    if document.findings.is_empty() {
+        md.push_str("No findings.\n");
+    } else {
+        md.push_str("| Category | Severity | Rule | Line | Message |\n");
+        md.push_str("|----------|----------|------|------|---------|\n");
+        for f in &document.findings {
+            let line = f.finding.line().map(|l| l.to_string()).unwrap_or_default();
+            let _ = writeln!(
+                md,
+                "| {} | {} | {} | {line} | {} |",
+                f.category,
+                f.finding.severity,
+                f.finding.rule,
+                f.finding.message.replace('|', "\\|")
+            );
+        }
+    }
+    let _ = write!(
+        md,
+        "\n# Configuration\n\n```nginx\n{}```\n",
+        document.config.to_config_string()
+    );
+    md
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    fn sample() -> (Config, FindingsExport) {
+        let config = parse("http { server { listen 80; } }").unwrap();
+        let export = FindingsExport::new()
+            .with_lint([
+                Finding::new("lint_warning", Severity::Warning, "w"),
+                Finding::new("lint_info", Severity::Info, "i"),
+            ])
+            .with_findings(
+                FindingCategory::Analyzer,
+                [Finding::new("analyzer_error", Severity::Error, "e")],
+            );
+        (config, export)
+    }
+
+    fn rules(document: &FindingsDocument) -> Vec<&str> {
+        document
+            .findings
+            .iter()
+            .map(|f| f.finding.rule.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_select_by_category_and_severity() {
+        let (config, export) = sample();
+
+        assert_eq!(
+            rules(&export.document(&config)),
+            vec!["analyzer_error", "lint_warning", "lint_info"]
+        );
+
+        let export = export.min_severity(Severity::Warning);
+        assert_eq!(
+            rules(&export.document(&config)),
+            vec!["analyzer_error", "lint_warning"]
+        );
+
+        let export = export.categories([FindingCategory::Lint]);
+        assert_eq!(rules(&export.document(&config)), vec!["lint_warning"]);
+        assert_eq!("Analyze".parse(), Ok(FindingCategory::Analyzer));
+        assert!("dns".parse::<FindingCategory>().is_err());
+    }
+
+    #[test]
+    fn test_write_json() {
+        let (config, export) = sample();
+        let mut out = Vec::new();
+        export
+            .categories([FindingCategory::Analyzer])
+            .write(&config, &mut out, ExportFormat::Json, false)
+            .unwrap();
+
+        let value: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(value["findings"][0]["category"], "analyzer");
+        assert_eq!(value["findings"][0]["rule"], "analyzer_error");
+        assert_eq!(
+            value["config"]["directives"][0]["item"]["Block"]["name"],
+            "http"
+        );
+
+        let document: FindingsDocument = serde_json::from_slice(&out).unwrap();
+        assert_eq!(document.config, config);
+    }
+}
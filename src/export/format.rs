@@ -12,6 +12,8 @@ use std::fmt;
 /// - `json` and `yaml` are always available when the `serde` feature is enabled
 /// - `toml` requires the `export-toml` feature
 /// - `markdown` requires the `export-markdown` feature
+/// - `xml` requires the `export-xml` feature
+/// - `msgpack` and `cbor` require the `export-binary` feature
 ///
 /// # Examples
 ///
@@ -47,6 +49,20 @@ pub enum ExportFormat {
     /// Generates human-readable documentation with sections and formatting.
     #[cfg(feature = "export-markdown")]
     Markdown,
+
+    /// XML format (requires `export-xml` feature).
+    ///
+    /// Follows the JSON model; see [`xml`](super::xml) for the schema.
+    #[cfg(feature = "export-xml")]
+    Xml,
+
+    /// `MessagePack` binary format (requires `export-binary` feature).
+    #[cfg(feature = "export-binary")]
+    MessagePack,
+
+    /// CBOR binary format (requires `export-binary` feature).
+    #[cfg(feature = "export-binary")]
+    Cbor,
 }
 
 impl ExportFormat {
@@ -69,6 +85,12 @@ impl ExportFormat {
             Self::Toml => "toml",
             #[cfg(feature = "export-markdown")]
             Self::Markdown => "md",
+            #[cfg(feature = "export-xml")]
+            Self::Xml => "xml",
+            #[cfg(feature = "export-binary")]
+            Self::MessagePack => "msgpack",
+            #[cfg(feature = "export-binary")]
+            Self::Cbor => "cbor",
         }
     }
 
@@ -94,6 +116,12 @@ impl ExportFormat {
             Self::Toml => "application/toml",
             #[cfg(feature = "export-markdown")]
             Self::Markdown => "text/markdown",
+            #[cfg(feature = "export-xml")]
+            Self::Xml => "application/xml",
+            #[cfg(feature = "export-binary")]
+            Self::MessagePack => "application/vnd.msgpack",
+            #[cfg(feature = "export-binary")]
+            Self::Cbor => "application/cbor",
         }
     }
 
@@ -119,9 +147,33 @@ impl ExportFormat {
             Self::Toml => true,
             #[cfg(feature = "export-markdown")]
             Self::Markdown => false, // Markdown is always formatted
+            #[cfg(feature = "export-xml")]
+            Self::Xml => true,
+            #[cfg(feature = "export-binary")]
+            Self::MessagePack => false, // Binary formats have no layout
+            #[cfg(feature = "export-binary")]
+            Self::Cbor => false,
         }
     }
 
+    /// Returns whether this format produces binary rather than text output.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nginx_discovery::export::ExportFormat;
+    ///
+    /// assert!(!ExportFormat::Json.is_binary());
+    /// ```
+    #[must_use]
+    pub fn is_binary(&self) -> bool {
+        #[cfg(feature = "export-binary")]
+        if matches!(self, Self::MessagePack | Self::Cbor) {
+            return true;
+        }
+        false
+    }
+
     /// Returns all available export formats.
     ///
     /// The returned formats depend on which features are enabled at compile time.
@@ -144,6 +196,12 @@ impl ExportFormat {
             Self::Toml,
             #[cfg(feature = "export-markdown")]
             Self::Markdown,
+            #[cfg(feature = "export-xml")]
+            Self::Xml,
+            #[cfg(feature = "export-binary")]
+            Self::MessagePack,
+            #[cfg(feature = "export-binary")]
+            Self::Cbor,
         ]
     }
 }
@@ -157,6 +215,12 @@ impl fmt::Display for ExportFormat {
             Self::Toml => write!(f, "toml"),
             #[cfg(feature = "export-markdown")]
             Self::Markdown => write!(f, "markdown"),
+            #[cfg(feature = "export-xml")]
+            Self::Xml => write!(f, "xml"),
+            #[cfg(feature = "export-binary")]
+            Self::MessagePack => write!(f, "msgpack"),
+            #[cfg(feature = "export-binary")]
+            Self::Cbor => write!(f, "cbor"),
         }
     }
 }
@@ -172,6 +236,9 @@ impl std::str::FromStr for ExportFormat {
     /// - `"yaml"` or `"yml"` → `ExportFormat::Yaml`
     /// - `"toml"` → `ExportFormat::Toml` (if `export-toml` feature enabled)
     /// - `"markdown"` or `"md"` → `ExportFormat::Markdown` (if `export-markdown` feature enabled)
+    /// - `"xml"` → `ExportFormat::Xml` (if `export-xml` feature enabled)
+    /// - `"msgpack"` or `"messagepack"` → `ExportFormat::MessagePack` (if `export-binary` feature enabled)
+    /// - `"cbor"` → `ExportFormat::Cbor` (if `export-binary` feature enabled)
     ///
     /// Matching is case-insensitive.
     ///
@@ -199,6 +266,12 @@ impl std::str::FromStr for ExportFormat {
             "toml" => Ok(Self::Toml),
             #[cfg(feature = "export-markdown")]
             "markdown" | "md" => Ok(Self::Markdown),
+            #[cfg(feature = "export-xml")]
+            "xml" => Ok(Self::Xml),
+            #[cfg(feature = "export-binary")]
+            "msgpack" | "messagepack" => Ok(Self::MessagePack),
+            #[cfg(feature = "export-binary")]
+            "cbor" => Ok(Self::Cbor),
             _ => Err(format!(
                 "Unknown format: {s}. Available: {}",
                 Self::all()
@@ -357,4 +430,31 @@ mod tests {
         assert_eq!(ExportFormat::Markdown.mime_type(), "text/markdown");
         assert!(!ExportFormat::Markdown.supports_pretty());
     }
+
+    #[cfg(feature = "export-binary")]
+    #[test]
+    fn test_binary_formats() {
+        for (name, format) in [
+            ("MessagePack", ExportFormat::MessagePack),
+            ("msgpack", ExportFormat::MessagePack),
+            ("CBOR", ExportFormat::Cbor),
+        ] {
+            assert_eq!(name.parse::<ExportFormat>().unwrap(), format);
+            assert!(format.is_binary());
+            assert!(!format.supports_pretty());
+        }
+        assert_eq!(ExportFormat::MessagePack.to_string(), "msgpack");
+        assert_eq!(ExportFormat::MessagePack.extension(), "msgpack");
+        assert_eq!(
+            ExportFormat::MessagePack.mime_type(),
+            "application/vnd.msgpack"
+        );
+        assert_eq!(ExportFormat::Cbor.extension(), "cbor");
+        assert_eq!(ExportFormat::Cbor.mime_type(), "application/cbor");
+        assert!("msg-pack".parse::<ExportFormat>().is_err());
+        assert!(!ExportFormat::all()
+            .into_iter()
+            .filter(|f| !matches!(f, ExportFormat::MessagePack | ExportFormat::Cbor))
+            .any(|f| f.is_binary()));
+    }
 }
@@ -2,15 +2,17 @@
 //! Enhanced export functionality with multiple format support.
 //!
 //! This module provides comprehensive export capabilities for NGINX configurations,
-//! supporting JSON, YAML, TOML, and Markdown formats with filtering and customization.
+//! supporting JSON, YAML, TOML, Markdown, XML, `MessagePack`, and CBOR formats with filtering and customization.
 //!
 //! # Features
 //!
-//! - Multiple export formats (JSON, YAML, TOML, Markdown)
+//! - Multiple export formats (JSON, YAML, TOML, Markdown, XML, `MessagePack`, CBOR)
 //! - Pretty printing and compact modes
 //! - Export filtering (by server name, port, SSL status, etc.)
 //! - Metadata inclusion
 //! - Builder pattern for flexible options
+//! - Custom formats through the [`Exporter`] trait and [`ExporterRegistry`]
+//! - The [`effective`] settings of every location after inheritance
 //!
 //! # Examples
 //!
@@ -47,15 +49,29 @@
 //! # Ok::<(), nginx_discovery::Error>(())
 //! ```
 
+#[cfg(feature = "serde")]
+mod annotated;
+#[cfg(feature = "export-binary")]
+pub mod binary;
+pub mod effective;
 pub mod filter;
+pub mod findings;
 pub mod format;
 pub mod options;
+pub mod registry;
+#[cfg(feature = "export-xml")]
+pub mod xml;
 
+pub use effective::{effective_config, EffectiveConfig};
 pub use filter::{Filter, FilterType};
+pub use findings::{CategorizedFinding, FindingCategory, FindingsDocument, FindingsExport};
 pub use format::{ExportFormat, Exporter};
-pub use options::{ExportOptions, ExportOptionsBuilder};
+pub use options::{ExportOptions, ExportOptionsBuilder, Timestamps};
+pub use registry::{ExporterRegistry, FormatExporter};
 
 use crate::{ast::Config, Result};
+#[cfg(feature = "serde")]
+use annotated::Annotated;
 use std::io::Write;
 
 /// Exports an NGINX configuration with the specified options.
@@ -103,31 +119,58 @@ use std::io::Write;
 /// # Ok::<(), Box<dyn std::error::Error>>(())
 /// ```
 pub fn export<W: Write>(config: &Config, writer: &mut W, options: &ExportOptions) -> Result<()> {
-    // Apply filters if specified
+    // Apply filters if specified, without copying an unfiltered config
+    let filtered;
     let filtered_config = if let Some(filter) = &options.filter {
-        filter.apply(config)?
+        filtered = filter.apply(config)?;
+        &filtered
     } else {
-        config.clone()
+        config
     };
 
+    // Exporters write as they go; buffer their many small writes
+    let mut writer = std::io::BufWriter::new(writer);
+    let writer = &mut writer;
+
     // Export using specified format
     match options.format {
         ExportFormat::Json => {
-            export_json(&filtered_config, writer, options)?;
+            export_json(filtered_config, writer, options)?;
         }
         ExportFormat::Yaml => {
-            export_yaml(&filtered_config, writer, options)?;
+            export_yaml(filtered_config, writer, options)?;
         }
         #[cfg(feature = "export-toml")]
         ExportFormat::Toml => {
-            export_toml(&filtered_config, writer, options)?;
+            export_toml(filtered_config, writer, options)?;
         }
         #[cfg(feature = "export-markdown")]
         ExportFormat::Markdown => {
-            export_markdown(&filtered_config, writer, options)?;
+            export_markdown(filtered_config, writer, options)?;
+        }
+        #[cfg(feature = "export-xml")]
+        ExportFormat::Xml => {
+            export_xml(filtered_config, writer, options)?;
+        }
+        #[cfg(feature = "export-binary")]
+        ExportFormat::MessagePack => {
+            let file = source_name(options);
+            match annotated(filtered_config, options, file.as_deref()) {
+                Some(annotated) => binary::write_msgpack(&annotated, writer)?,
+                None => binary::write_msgpack(filtered_config, writer)?,
+            }
+        }
+        #[cfg(feature = "export-binary")]
+        ExportFormat::Cbor => {
+            let file = source_name(options);
+            match annotated(filtered_config, options, file.as_deref()) {
+                Some(annotated) => binary::write_cbor(&annotated, writer)?,
+                None => binary::write_cbor(filtered_config, writer)?,
+            }
         }
     }
 
+    writer.flush()?;
     Ok(())
 }
 
@@ -137,13 +180,16 @@ pub fn export<W: Write>(config: &Config, writer: &mut W, options: &ExportOptions
 fn export_json<W: Write>(config: &Config, writer: &mut W, options: &ExportOptions) -> Result<()> {
     #[cfg(feature = "serde")]
     {
-        let json = if options.pretty {
-            serde_json::to_string_pretty(config)?
-        } else {
-            serde_json::to_string(config)?
-        };
-        writer.write_all(json.as_bytes())?;
-        Ok(())
+        let file = source_name(options);
+        with_io_errors(writer, |writer| {
+            match (annotated(config, options, file.as_deref()), options.pretty) {
+                (Some(annotated), true) => serde_json::to_writer_pretty(writer, &annotated)?,
+                (Some(annotated), false) => serde_json::to_writer(writer, &annotated)?,
+                (None, true) => serde_json::to_writer_pretty(writer, config)?,
+                (None, false) => serde_json::to_writer(writer, config)?,
+            }
+            Ok(())
+        })
     }
     #[cfg(not(feature = "serde"))]
     {
@@ -155,30 +201,167 @@ fn export_json<W: Write>(config: &Config, writer: &mut W, options: &ExportOption
 /// Exports configuration to YAML format.
 ///
 /// YAML is always pretty-printed by default.
-fn export_yaml<W: Write>(config: &Config, writer: &mut W, _options: &ExportOptions) -> Result<()> {
+fn export_yaml<W: Write>(config: &Config, writer: &mut W, options: &ExportOptions) -> Result<()> {
     #[cfg(feature = "serde")]
     {
-        let yaml = serde_yaml::to_string(config)?;
-        writer.write_all(yaml.as_bytes())?;
-        Ok(())
+        let file = source_name(options);
+        with_io_errors(writer, |writer| {
+            match annotated(config, options, file.as_deref()) {
+                Some(annotated) => serde_yaml::to_writer(writer, &annotated)?,
+                None => serde_yaml::to_writer(writer, config)?,
+            }
+            Ok(())
+        })
     }
     #[cfg(not(feature = "serde"))]
     {
-        let _ = config;
+        let _ = (config, options);
         Err(crate::Error::FeatureNotEnabled("serde".to_string()))
     }
 }
 
+/// Writer keeping the I/O error it failed with
+#[cfg(feature = "serde")]
+struct IoCapture<W> {
+    inner: W,
+    error: Option<std::io::Error>,
+}
+
+#[cfg(feature = "serde")]
+impl<W: Write> IoCapture<W> {
+    fn keep<T>(&mut self, result: std::io::Result<T>) -> std::io::Result<T> {
+        result.map_err(|e| {
+            let passed = std::io::Error::new(e.kind(), e.to_string());
+            self.error = Some(e);
+            passed
+        })
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<W: Write> Write for IoCapture<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let result = self.inner.write(buf);
+        self.keep(result)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        let result = self.inner.flush();
+        self.keep(result)
+    }
+}
+
+/// Run a serializer on `writer`, reporting a failed write as
+/// [`Error::Io`](crate::Error::Io) rather than the serializer's own error
+#[cfg(feature = "serde")]
+fn with_io_errors<W: Write>(
+    writer: &mut W,
+    write: impl FnOnce(&mut IoCapture<&mut W>) -> Result<()>,
+) -> Result<()> {
+    let mut capture = IoCapture {
+        inner: writer,
+        error: None,
+    };
+    let result = write(&mut capture);
+    match (result, capture.error) {
+        (Err(_), Some(e)) => Err(crate::Error::Io(e)),
+        (result, _) => result,
+    }
+}
+
+/// The configuration with a `source` entry next to the `span` of every
+/// directive when sources are included and with object keys sorted in
+/// deterministic mode; `None` when neither applies and the derived
+/// serialization is used
+#[cfg(feature = "serde")]
+fn annotated<'a>(
+    config: &'a Config,
+    options: &ExportOptions,
+    file: Option<&'a str>,
+) -> Option<Annotated<'a>> {
+    if !options.include_source && !options.deterministic {
+        return None;
+    }
+    let annotated = Annotated::new(config);
+    Some(if options.include_source {
+        annotated.with_source(file)
+    } else {
+        annotated
+    })
+}
+
+/// File named in source annotations; only its file name in deterministic
+/// mode, so the export does not depend on where the configuration lives
+#[cfg(feature = "serde")]
+fn source_name(options: &ExportOptions) -> Option<String> {
+    let file = options.source_file.as_ref()?;
+    let file = if options.deterministic {
+        file.file_name()
+            .map_or(file.as_path(), std::path::Path::new)
+    } else {
+        file
+    };
+    Some(file.display().to_string())
+}
+
+/// RFC 3339 rendering of `now` in the requested timezone
+#[cfg(feature = "export-markdown")]
+fn timestamp(timestamps: Timestamps, now: chrono::DateTime<chrono::Utc>) -> Option<String> {
+    use chrono::SecondsFormat;
+
+    match timestamps {
+        Timestamps::Local => Some(
+            now.with_timezone(&chrono::Local)
+                .to_rfc3339_opts(SecondsFormat::Secs, true),
+        ),
+        Timestamps::Utc => Some(now.to_rfc3339_opts(SecondsFormat::Secs, true)),
+        Timestamps::Offset(seconds) => chrono::FixedOffset::east_opt(seconds).map(|offset| {
+            now.with_timezone(&offset)
+                .to_rfc3339_opts(SecondsFormat::Secs, true)
+        }),
+        Timestamps::None => None,
+    }
+}
+
+/// `file:line` of a directive for source annotations in Markdown
+#[cfg(feature = "export-markdown")]
+fn source_of(directive: &crate::ast::Directive, options: &ExportOptions) -> String {
+    match source_name(options) {
+        Some(file) => format!("{file}:{}", directive.span.line),
+        None => format!("line {}", directive.span.line),
+    }
+}
+
+/// Exports configuration to XML format.
+///
+/// Requires the `export-xml` feature. See [`xml`] for the schema.
+#[cfg(feature = "export-xml")]
+fn export_xml<W: Write>(config: &Config, writer: &mut W, options: &ExportOptions) -> Result<()> {
+    let source = options.include_source.then(|| xml::Source {
+        file: source_name(options),
+    });
+    xml::write_config(
+        config,
+        writer,
+        options.pretty && !options.compact,
+        source.as_ref(),
+    )
+}
+
 /// Exports configuration to TOML format.
 ///
 /// Requires the `export-toml` feature.
-/// Supports both pretty-printed and compact output.
+/// Supports both pretty-printed and compact output. The `toml` crate
+/// only serializes to a string, so this is the one format built in memory
+/// before it is written.
 #[cfg(feature = "export-toml")]
 fn export_toml<W: Write>(config: &Config, writer: &mut W, options: &ExportOptions) -> Result<()> {
-    let toml_str = if options.pretty {
-        toml::to_string_pretty(config)?
-    } else {
-        toml::to_string(config)?
+    let file = source_name(options);
+    let toml_str = match (annotated(config, options, file.as_deref()), options.pretty) {
+        (Some(annotated), true) => toml::to_string_pretty(&annotated)?,
+        (Some(annotated), false) => toml::to_string(&annotated)?,
+        (None, true) => toml::to_string_pretty(config)?,
+        (None, false) => toml::to_string(config)?,
     };
     writer.write_all(toml_str.as_bytes())?;
     Ok(())
@@ -194,48 +377,59 @@ fn export_markdown<W: Write>(
     writer: &mut W,
     options: &ExportOptions,
 ) -> Result<()> {
-    use std::fmt::Write as FmtWrite;
-
-    let mut md = String::new();
-
     // Title
-    writeln!(md, "# NGINX Configuration Report")?;
-    writeln!(md)?;
+    writeln!(writer, "# NGINX Configuration Report")?;
+    writeln!(writer)?;
 
     // Metadata
     if options.include_metadata {
-        writeln!(md, "## Metadata")?;
-        writeln!(md)?;
-        writeln!(
-            md,
-            "- **Generated**: {}",
-            chrono::Local::now().format("%Y-%m-%d %H:%M:%S")
-        )?;
-        writeln!(md, "- **Directives**: {}", config.directives.len())?;
-        writeln!(md)?;
+        writeln!(writer, "## Metadata")?;
+        writeln!(writer)?;
+        let timestamps = if options.deterministic {
+            Timestamps::None
+        } else {
+            options.timestamps
+        };
+        if let Some(generated) = timestamp(timestamps, chrono::Utc::now()) {
+            writeln!(writer, "- **Generated**: {generated}")?;
+        }
+        writeln!(writer, "- **Directives**: {}", config.directives.len())?;
+        writeln!(writer)?;
     }
 
     // HTTP Configuration - extract servers directly
-    writeln!(md, "## HTTP Configuration")?;
-    writeln!(md)?;
+    writeln!(writer, "## HTTP Configuration")?;
+    writeln!(writer)?;
 
-    // Servers
-    let servers = crate::extract::servers(config)?;
-    if !servers.is_empty() {
-        writeln!(md, "### Servers ({} total)", servers.len())?;
-        writeln!(md)?;
+    // Servers, extracted one block at a time so their locations are not
+    // all held in memory
+    let server_blocks: Vec<_> = config
+        .find_directives_recursive("server")
+        .into_iter()
+        .filter(|d| d.children().is_some())
+        .collect();
+    if !server_blocks.is_empty() {
+        writeln!(writer, "### Servers ({} total)", server_blocks.len())?;
+        writeln!(writer)?;
 
-        for (i, server) in servers.iter().enumerate() {
-            writeln!(md, "#### Server {}", i + 1)?;
-            writeln!(md)?;
+        for (i, block) in server_blocks.iter().enumerate() {
+            let Some(server) = crate::extract::server(block) else {
+                continue;
+            };
+            writeln!(writer, "#### Server {}", i + 1)?;
+            writeln!(writer)?;
 
             if !server.server_names.is_empty() {
-                writeln!(md, "- **Server Names**: {}", server.server_names.join(", "))?;
+                writeln!(
+                    writer,
+                    "- **Server Names**: {}",
+                    server.server_names.join(", ")
+                )?;
             }
 
             if !server.listen.is_empty() {
                 writeln!(
-                    md,
+                    writer,
                     "- **Listen**: {}",
                     server
                         .listen
@@ -247,27 +441,44 @@ fn export_markdown<W: Write>(
             }
 
             if let Some(root) = &server.root {
-                writeln!(md, "- **Root**: {}", root.display())?;
+                writeln!(writer, "- **Root**: {}", root.display())?;
+            }
+
+            if options.include_source {
+                writeln!(writer, "- **Source**: {}", source_of(block, options))?;
+                let locations = block.find_recursive("location");
+                if !locations.is_empty() {
+                    writeln!(writer, "- **Locations**:")?;
+                    for location in locations {
+                        writeln!(
+                            writer,
+                            "  - `{}` ({})",
+                            location.args_as_strings().join(" "),
+                            source_of(location, options)
+                        )?;
+                    }
+                }
             }
 
-            writeln!(md)?;
+            writeln!(writer)?;
         }
     }
 
     // Upstreams
-    writeln!(md, "### Upstreams")?;
-    writeln!(md)?;
-    writeln!(md, "_Upstream extraction coming in next version_")?;
-    writeln!(md)?;
+    writeln!(writer, "### Upstreams")?;
+    writeln!(writer)?;
+    writeln!(writer, "_Upstream extraction coming in next version_")?;
+    writeln!(writer)?;
 
     // Stream block
-    writeln!(md, "## Stream Configuration")?;
-    writeln!(md)?;
-    writeln!(md, "_Stream configuration support coming in next version_")?;
-    writeln!(md)?;
+    writeln!(writer, "## Stream Configuration")?;
+    writeln!(writer)?;
+    writeln!(
+        writer,
+        "_Stream configuration support coming in next version_"
+    )?;
+    writeln!(writer)?;
 
-    // Write to output
-    writer.write_all(md.as_bytes())?;
     Ok(())
 }
 
@@ -304,4 +515,247 @@ mod tests {
         export(&config, &mut output, &options).unwrap();
         assert!(!output.is_empty());
     }
+
+    #[test]
+    fn test_export_with_source() {
+        let config = crate::parse("server {\n    listen 80;\n    location /api/ { }\n}\n").unwrap();
+        let options = ExportOptions::builder()
+            .include_source(true)
+            .source_file("nginx.conf")
+            .build();
+        let mut output = Vec::new();
+        export(&config, &mut output, &options).unwrap();
+
+        let value: serde_json::Value = serde_json::from_slice(&output).unwrap();
+        let server = &value["directives"][0];
+        assert_eq!(server["source"]["file"], "nginx.conf");
+        assert_eq!(server["source"]["line"], 1);
+        let location = &server["item"]["Block"]["children"][1];
+        assert_eq!(location["source"]["line"], 3);
+
+        #[cfg(feature = "export-markdown")]
+        {
+            let options = ExportOptions::builder()
+                .format(ExportFormat::Markdown)
+                .include_source(true)
+                .source_file("nginx.conf")
+                .build();
+            let mut output = Vec::new();
+            export(&config, &mut output, &options).unwrap();
+            let md = String::from_utf8(output).unwrap();
+            assert!(md.contains("- **Source**: nginx.conf:1\n"));
+            assert!(md.contains("  - `/api/` (nginx.conf:3)\n"));
+        }
+    }
+
+    #[test]
+    fn test_export_source_edge_cases() {
+        let config = crate::parse("server {\n    listen 80;\n}\n").unwrap();
+        let export_with = |options: ExportOptions| {
+            let mut output = Vec::new();
+            export(&config, &mut output, &options).unwrap();
+            String::from_utf8(output).unwrap()
+        };
+
+        // Sources without a file name carry the line only
+        let json = export_with(ExportOptions::builder().include_source(true).build());
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            value["directives"][0]["source"],
+            serde_json::json!({ "line": 1 })
+        );
+
+        // A file name alone does not turn sources on
+        let plain = export_with(ExportOptions::default());
+        assert_eq!(
+            export_with(ExportOptions::builder().source_file("nginx.conf").build()),
+            plain
+        );
+        assert!(!plain.contains("\"source\""));
+
+        let yaml = export_with(
+            ExportOptions::builder()
+                .format(ExportFormat::Yaml)
+                .include_source(true)
+                .source_file("nginx.conf")
+                .build(),
+        );
+        let value: serde_yaml::Value = serde_yaml::from_str(&yaml).unwrap();
+        assert_eq!(value["directives"][0]["source"]["file"], "nginx.conf");
+        assert_eq!(
+            value["directives"][0]["item"]["Block"]["children"][0]["source"]["line"],
+            2
+        );
+
+        #[cfg(feature = "export-xml")]
+        {
+            let xml = export_with(
+                ExportOptions::builder()
+                    .format(ExportFormat::Xml)
+                    .include_source(true)
+                    .build(),
+            );
+            assert!(xml.contains("<source line=\"2\"/>"), "{xml}");
+            assert!(!xml.contains("file="));
+        }
+
+        #[cfg(feature = "export-markdown")]
+        {
+            let md = export_with(
+                ExportOptions::builder()
+                    .format(ExportFormat::Markdown)
+                    .include_source(true)
+                    .build(),
+            );
+            assert!(md.contains("- **Source**: line 1\n"));
+            // No locations to list
+            assert!(!md.contains("**Locations**"));
+        }
+    }
+
+    #[cfg(feature = "export-markdown")]
+    #[test]
+    fn test_markdown_timestamps() {
+        let now = chrono::DateTime::parse_from_rfc3339("2024-05-01T10:00:00Z")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+        assert_eq!(
+            timestamp(Timestamps::Utc, now).as_deref(),
+            Some("2024-05-01T10:00:00Z")
+        );
+        assert_eq!(
+            timestamp(Timestamps::Offset(19_800), now).as_deref(),
+            Some("2024-05-01T15:30:00+05:30")
+        );
+        assert_eq!(timestamp(Timestamps::None, now), None);
+
+        let options = ExportOptions::builder()
+            .format(ExportFormat::Markdown)
+            .timestamps(Timestamps::None)
+            .build();
+        let mut output = Vec::new();
+        export(&Config::default(), &mut output, &options).unwrap();
+        let md = String::from_utf8(output).unwrap();
+        assert!(md.contains("## Metadata"));
+        assert!(!md.contains("Generated"));
+    }
+
+    #[test]
+    fn test_export_deterministic() {
+        let config = crate::parse("http {\n    server { listen 80; }\n}\n").unwrap();
+        let options = ExportOptions::builder()
+            .deterministic(true)
+            .include_source(true)
+            .source_file("/etc/nginx/nginx.conf")
+            .build();
+
+        let mut first = Vec::new();
+        let mut second = Vec::new();
+        export(&config, &mut first, &options).unwrap();
+        export(&config.clone(), &mut second, &options).unwrap();
+        assert_eq!(first, second);
+
+        let json = String::from_utf8(first).unwrap();
+        assert!(json.contains("\"file\": \"nginx.conf\""));
+        // Keys sorted: "item" < "source" < "span"
+        let item = json.find("\"item\"").unwrap();
+        let source = json.find("\"source\"").unwrap();
+        let span = json.find("\"span\"").unwrap();
+        assert!(item < source && source < span);
+
+        #[cfg(feature = "export-markdown")]
+        {
+            let options = ExportOptions::builder()
+                .format(ExportFormat::Markdown)
+                .deterministic(true)
+                .timestamps(Timestamps::Utc)
+                .build();
+            let mut output = Vec::new();
+            export(&config, &mut output, &options).unwrap();
+            assert!(!String::from_utf8(output).unwrap().contains("Generated"));
+        }
+    }
+
+    #[test]
+    fn test_source_name() {
+        let name = |file: &str, deterministic| {
+            source_name(
+                &ExportOptions::builder()
+                    .source_file(file)
+                    .deterministic(deterministic)
+                    .build(),
+            )
+        };
+        assert_eq!(name("conf/nginx.conf", true).as_deref(), Some("nginx.conf"));
+        assert_eq!(
+            name("conf/nginx.conf", false).as_deref(),
+            Some("conf/nginx.conf")
+        );
+        // Nothing shorter to fall back to
+        assert_eq!(name("/", true).as_deref(), Some("/"));
+        assert_eq!(name("..", true).as_deref(), Some(".."));
+        assert_eq!(source_name(&ExportOptions::default()), None);
+    }
+
+    #[test]
+    fn test_export_deterministic_edge_cases() {
+        let config =
+            crate::parse("events { }\nhttp {\n    server { listen 80; server_name a; }\n}\n")
+                .unwrap();
+        let export_with = |options: &ExportOptions| {
+            let mut output = Vec::new();
+            export(&config, &mut output, options).unwrap();
+            output
+        };
+
+        // Sorted keys only: the same document as the default export, and no
+        // source entries
+        let options = ExportOptions::builder()
+            .deterministic(true)
+            .pretty(false)
+            .build();
+        let sorted = String::from_utf8(export_with(&options)).unwrap();
+        assert!(sorted.starts_with(r#"{"directives":[{"item":{"Block":{"args":[],"children":[]"#));
+        assert!(!sorted.contains("\"source\""));
+        let sorted: serde_json::Value = serde_json::from_str(&sorted).unwrap();
+        let plain: serde_json::Value =
+            serde_json::from_slice(&export_with(&ExportOptions::default())).unwrap();
+        assert_eq!(sorted, plain);
+
+        // Every streamed format is byte-identical between runs, wherever the
+        // configuration lives
+        #[allow(unused_mut)]
+        let mut formats = vec![ExportFormat::Json, ExportFormat::Yaml];
+        #[cfg(feature = "export-toml")]
+        formats.push(ExportFormat::Toml);
+        #[cfg(feature = "export-binary")]
+        formats.extend([ExportFormat::MessagePack, ExportFormat::Cbor]);
+        for format in formats {
+            let options = |file: &str| {
+                ExportOptions::builder()
+                    .format(format)
+                    .deterministic(true)
+                    .include_source(true)
+                    .source_file(file)
+                    .build()
+            };
+            assert_eq!(
+                export_with(&options("/etc/nginx/nginx.conf")),
+                export_with(&options("/srv/copy/nginx.conf")),
+                "{format:?}"
+            );
+        }
+
+        #[cfg(feature = "export-markdown")]
+        {
+            let options = ExportOptions::builder()
+                .format(ExportFormat::Markdown)
+                .deterministic(true)
+                .timestamps(Timestamps::Offset(3_600))
+                .build();
+            let md = String::from_utf8(export_with(&options)).unwrap();
+            assert!(!md.contains("Generated"));
+            assert!(md.contains("- **Directives**: 2\n"));
+        }
+    }
 }
@@ -22,6 +22,82 @@
 //! ```
 
 use super::{ExportFormat, Filter};
+use std::path::PathBuf;
+
+/// How timestamps in export metadata are written.
+///
+/// Timestamps are RFC 3339, e.g. `2024-05-01T12:00:00+02:00`.
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::export::Timestamps;
+///
+/// assert_eq!("utc".parse::<Timestamps>().unwrap(), Timestamps::Utc);
+/// assert_eq!("+05:30".parse::<Timestamps>().unwrap(), Timestamps::Offset(19_800));
+/// assert_eq!("none".parse::<Timestamps>().unwrap(), Timestamps::None);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Timestamps {
+    /// In the local timezone of the machine running the export.
+    #[default]
+    Local,
+
+    /// In UTC.
+    Utc,
+
+    /// At a fixed offset from UTC, in seconds east of UTC.
+    Offset(i32),
+
+    /// Left out, so identical inputs export identically.
+    None,
+}
+
+impl std::str::FromStr for Timestamps {
+    type Err = String;
+
+    /// Parses `local`, `utc` (or `z`), `none` (or `off`), or an offset
+    /// such as `+02:00`, `-0530` or `UTC+1`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error message for anything else, including IANA zone
+    /// names such as `Europe/Paris`, which are not supported.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.trim().to_ascii_lowercase();
+        match lower.as_str() {
+            "local" => return Ok(Self::Local),
+            "utc" | "z" | "gmt" => return Ok(Self::Utc),
+            "none" | "off" => return Ok(Self::None),
+            _ => {}
+        }
+
+        let offset = lower
+            .strip_prefix("utc")
+            .or_else(|| lower.strip_prefix("gmt"))
+            .unwrap_or(&lower);
+        let (sign, rest) = match offset.as_bytes().first() {
+            Some(b'+') => (1, &offset[1..]),
+            Some(b'-') => (-1, &offset[1..]),
+            _ => {
+                return Err(format!(
+                    "Unknown timezone: {s}. Use local, utc, none or an offset such as +02:00"
+                ))
+            }
+        };
+        let (hours, minutes) = match rest.split_once(':') {
+            Some((hours, minutes)) => (hours, minutes),
+            None if rest.len() > 2 => rest.split_at(rest.len() - 2),
+            None => (rest, "0"),
+        };
+        match (hours.parse::<i32>(), minutes.parse::<i32>()) {
+            (Ok(hours), Ok(minutes)) if hours <= 14 && minutes < 60 => {
+                Ok(Self::Offset(sign * (hours * 3600 + minutes * 60)))
+            }
+            _ => Err(format!("Invalid UTC offset: {s}")),
+        }
+    }
+}
 
 /// Export options for controlling output format and content.
 ///
@@ -31,7 +107,7 @@ use super::{ExportFormat, Filter};
 /// # Examples
 ///
 /// ```
-/// use nginx_discovery::export::{ExportOptions, ExportFormat};
+/// use nginx_discovery::export::{ExportOptions, ExportFormat, Timestamps};
 ///
 /// let options = ExportOptions {
 ///     format: ExportFormat::Json,
@@ -41,6 +117,10 @@ use super::{ExportFormat, Filter};
 ///     compact: false,
 ///     filter: None,
 ///     template: None,
+///     include_source: false,
+///     source_file: None,
+///     timestamps: Timestamps::Utc,
+///     deterministic: false,
 /// };
 /// ```
 #[derive(Debug, Clone)]
@@ -66,6 +146,22 @@ pub struct ExportOptions {
 
     /// Custom template for markdown/html exports
     pub template: Option<String>,
+
+    /// Annotate every directive, and every server and location in
+    /// Markdown, with `source: { file, line }`
+    pub include_source: bool,
+
+    /// File named in `source` annotations, usually the main configuration
+    /// file; omitted when `None`
+    pub source_file: Option<PathBuf>,
+
+    /// Timezone of metadata timestamps, or none at all
+    pub timestamps: Timestamps,
+
+    /// Byte-identical output for identical configurations: object keys
+    /// sorted, no timestamps whatever [`timestamps`](Self::timestamps)
+    /// says, and only the file name of [`source_file`](Self::source_file)
+    pub deterministic: bool,
 }
 
 impl Default for ExportOptions {
@@ -78,6 +174,10 @@ impl Default for ExportOptions {
             compact: false,
             filter: None,
             template: None,
+            include_source: false,
+            source_file: None,
+            timestamps: Timestamps::default(),
+            deterministic: false,
         }
     }
 }
@@ -131,6 +231,10 @@ pub struct ExportOptionsBuilder {
     compact: Option<bool>,
     filter: Option<Filter>,
     template: Option<String>,
+    include_source: Option<bool>,
+    source_file: Option<PathBuf>,
+    timestamps: Option<Timestamps>,
+    deterministic: Option<bool>,
 }
 
 impl ExportOptionsBuilder {
@@ -272,6 +376,72 @@ impl ExportOptionsBuilder {
         self
     }
 
+    /// Enables or disables source annotations.
+    ///
+    /// Each exported directive gets a `source` entry with the file and
+    /// line it is defined at, so a reader of the export can find it in
+    /// the configuration.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nginx_discovery::export::ExportOptions;
+    ///
+    /// let options = ExportOptions::builder()
+    ///     .include_source(true)
+    ///     .source_file("/etc/nginx/nginx.conf")
+    ///     .build();
+    /// ```
+    #[must_use]
+    pub fn include_source(mut self, include: bool) -> Self {
+        self.include_source = Some(include);
+        self
+    }
+
+    /// Sets the file named in source annotations.
+    #[must_use]
+    pub fn source_file(mut self, file: impl Into<PathBuf>) -> Self {
+        self.source_file = Some(file.into());
+        self
+    }
+
+    /// Sets how metadata timestamps are written.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nginx_discovery::export::{ExportOptions, Timestamps};
+    ///
+    /// let options = ExportOptions::builder()
+    ///     .timestamps(Timestamps::None)  // Reproducible output
+    ///     .build();
+    /// ```
+    #[must_use]
+    pub fn timestamps(mut self, timestamps: Timestamps) -> Self {
+        self.timestamps = Some(timestamps);
+        self
+    }
+
+    /// Enables or disables deterministic output.
+    ///
+    /// Deterministic exports of the same configuration are byte-identical,
+    /// so they can be committed and diffed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nginx_discovery::export::ExportOptions;
+    ///
+    /// let options = ExportOptions::builder()
+    ///     .deterministic(true)
+    ///     .build();
+    /// ```
+    #[must_use]
+    pub fn deterministic(mut self, deterministic: bool) -> Self {
+        self.deterministic = Some(deterministic);
+        self
+    }
+
     /// Builds the final `ExportOptions`.
     ///
     /// Any unset options will use their default values.
@@ -297,6 +467,10 @@ impl ExportOptionsBuilder {
             compact: self.compact.unwrap_or(defaults.compact),
             filter: self.filter.or(defaults.filter),
             template: self.template.or(defaults.template),
+            include_source: self.include_source.unwrap_or(defaults.include_source),
+            source_file: self.source_file.or(defaults.source_file),
+            timestamps: self.timestamps.unwrap_or(defaults.timestamps),
+            deterministic: self.deterministic.unwrap_or(defaults.deterministic),
         }
     }
 }
@@ -369,4 +543,38 @@ mod tests {
         assert_eq!(options.format, ExportFormat::Json);
         assert!(options.pretty);
     }
+
+    #[test]
+    fn test_source_options() {
+        let defaults = ExportOptions::default();
+        assert!(!defaults.include_source);
+        assert_eq!(defaults.source_file, None);
+
+        let options = ExportOptions::builder()
+            .source_file("/etc/nginx/nginx.conf")
+            .build();
+        assert!(!options.include_source);
+        assert_eq!(
+            options.source_file.as_deref(),
+            Some(std::path::Path::new("/etc/nginx/nginx.conf"))
+        );
+
+        let options = ExportOptions::builder()
+            .include_source(true)
+            .include_source(false)
+            .build();
+        assert!(!options.include_source);
+    }
+
+    #[test]
+    fn test_timestamps_from_str() {
+        assert_eq!("Local".parse(), Ok(Timestamps::Local));
+        assert_eq!("Z".parse(), Ok(Timestamps::Utc));
+        assert_eq!("off".parse(), Ok(Timestamps::None));
+        assert_eq!("-08:00".parse(), Ok(Timestamps::Offset(-28_800)));
+        assert_eq!("+0530".parse(), Ok(Timestamps::Offset(19_800)));
+        assert_eq!("UTC+1".parse(), Ok(Timestamps::Offset(3_600)));
+        assert!("Europe/Paris".parse::<Timestamps>().is_err());
+        assert!("+25:00".parse::<Timestamps>().is_err());
+    }
 }
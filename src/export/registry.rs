@@ -0,0 +1,336 @@
+// src/export/registry.rs
+//! Registry of exporters selectable by name.
+//!
+//! Every export format is an [`Exporter`] trait object with a name and a
+//! file extension. [`ExporterRegistry::with_builtin_exporters`] provides the
+//! formats behind [`export`]; downstream crates can register
+//! their own exporters alongside them and callers select one by name at
+//! runtime, for example from a `--format` command-line argument.
+//!
+//! # Examples
+//!
+//! ```
+//! use nginx_discovery::ast::Config;
+//! use nginx_discovery::export::{Exporter, ExporterRegistry, ExportOptions};
+//! use std::io::Write;
+//!
+//! struct CountExporter;
+//!
+//! impl Exporter for CountExporter {
+//!     fn export(&self, config: &Config, writer: &mut dyn Write) -> nginx_discovery::Result<()> {
+//!         writeln!(writer, "{} directives", config.directives.len())?;
+//!         Ok(())
+//!     }
+//!
+//!     fn format_name(&self) -> &str {
+//!         "count"
+//!     }
+//!
+//!     fn extension(&self) -> &str {
+//!         "txt"
+//!     }
+//! }
+//!
+//! let registry = ExporterRegistry::with_builtin_exporters(&ExportOptions::default())
+//!     .with_exporter(CountExporter);
+//!
+//! let config = nginx_discovery::parse("server { listen 80; }")?;
+//! let mut output = Vec::new();
+//! registry.export("count", &config, &mut output)?;
+//! assert_eq!(output, b"1 directives\n");
+//! # Ok::<(), nginx_discovery::Error>(())
+//! ```
+
+use super::{export, ExportFormat, ExportOptions, Exporter};
+use crate::ast::Config;
+use crate::{Error, Result};
+use std::io::Write;
+
+/// A built-in format as an [`Exporter`], exporting with fixed options.
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::export::{ExportFormat, ExportOptions, Exporter, FormatExporter};
+///
+/// let exporter = FormatExporter::new(ExportOptions::builder().format(ExportFormat::Yaml).build());
+/// assert_eq!(exporter.format_name(), "yaml");
+/// assert_eq!(exporter.extension(), "yaml");
+/// ```
+#[derive(Debug, Clone)]
+pub struct FormatExporter {
+    options: ExportOptions,
+    name: String,
+}
+
+impl FormatExporter {
+    /// Creates an exporter for `options.format` using `options`.
+    #[must_use]
+    pub fn new(options: ExportOptions) -> Self {
+        Self {
+            name: options.format.to_string(),
+            options,
+        }
+    }
+
+    /// Returns the options the exporter uses.
+    #[must_use]
+    pub fn options(&self) -> &ExportOptions {
+        &self.options
+    }
+}
+
+impl Exporter for FormatExporter {
+    fn export(&self, config: &Config, mut writer: &mut dyn Write) -> Result<()> {
+        export(config, &mut writer, &self.options)
+    }
+
+    fn format_name(&self) -> &str {
+        &self.name
+    }
+
+    fn extension(&self) -> &str {
+        self.options.format.extension()
+    }
+}
+
+/// Ordered collection of [`Exporter`]s, looked up by name.
+#[derive(Default)]
+pub struct ExporterRegistry {
+    exporters: Vec<Box<dyn Exporter>>,
+}
+
+impl std::fmt::Debug for ExporterRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ExporterRegistry")
+            .field("exporters", &self.names())
+            .finish()
+    }
+}
+
+impl ExporterRegistry {
+    /// Creates an empty registry.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a registry with every built-in format enabled at compile
+    /// time (see [`ExportFormat::all`]), each exporting with `options`
+    /// apart from the format.
+    #[must_use]
+    pub fn with_builtin_exporters(options: &ExportOptions) -> Self {
+        ExportFormat::all()
+            .into_iter()
+            .fold(Self::new(), |registry, format| {
+                registry.with_exporter(FormatExporter::new(ExportOptions {
+                    format,
+                    ..options.clone()
+                }))
+            })
+    }
+
+    /// Adds an exporter, returning the registry.
+    #[must_use]
+    pub fn with_exporter(mut self, exporter: impl Exporter + 'static) -> Self {
+        self.register(Box::new(exporter));
+        self
+    }
+
+    /// Adds an exporter.
+    ///
+    /// An exporter with the same name as an existing one replaces it in
+    /// place, so built-in formats can be overridden.
+    pub fn register(&mut self, exporter: Box<dyn Exporter>) {
+        if let Some(existing) = self
+            .exporters
+            .iter_mut()
+            .find(|e| e.format_name() == exporter.format_name())
+        {
+            *existing = exporter;
+        } else {
+            self.exporters.push(exporter);
+        }
+    }
+
+    /// Looks up an exporter by name, ignoring case.
+    ///
+    /// Built-in formats are also found by their aliases (`yml`, `md`).
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&dyn Exporter> {
+        let find = |name: &str| {
+            self.exporters
+                .iter()
+                .find(|e| e.format_name().eq_ignore_ascii_case(name))
+                .map(AsRef::as_ref)
+        };
+        find(name).or_else(|| {
+            let format: ExportFormat = name.parse().ok()?;
+            find(&format.to_string())
+        })
+    }
+
+    /// Returns whether an exporter with this name is registered.
+    #[must_use]
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// Returns the names of all registered exporters, in registration order.
+    #[must_use]
+    pub fn names(&self) -> Vec<&str> {
+        self.exporters.iter().map(|e| e.format_name()).collect()
+    }
+
+    /// Iterates over the registered exporters.
+    pub fn iter(&self) -> impl Iterator<Item = &dyn Exporter> {
+        self.exporters.iter().map(AsRef::as_ref)
+    }
+
+    /// Returns the number of registered exporters.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.exporters.len()
+    }
+
+    /// Returns whether no exporter is registered.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.exporters.is_empty()
+    }
+
+    /// Exports `config` with the exporter named `name`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidInput`] listing the available exporters if
+    /// none is named `name`, or the exporter's own error.
+    pub fn export(&self, name: &str, config: &Config, writer: &mut dyn Write) -> Result<()> {
+        let exporter = self.get(name).ok_or_else(|| {
+            Error::InvalidInput(format!(
+                "Unknown export format: {name}. Available: {}",
+                self.names().join(", ")
+            ))
+        })?;
+        exporter.export(config, writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Fixed(&'static str);
+
+    #[allow(clippy::unnecessary_literal_bound)]
+    impl Exporter for Fixed {
+        fn export(&self, _config: &Config, writer: &mut dyn Write) -> Result<()> {
+            writer.write_all(self.0.as_bytes())?;
+            Ok(())
+        }
+
+        fn format_name(&self) -> &str {
+            "fixed"
+        }
+
+        fn extension(&self) -> &str {
+            "txt"
+        }
+    }
+
+    #[test]
+    fn test_exporter_registry() {
+        let options = ExportOptions::builder().pretty(false).build();
+        let mut registry = ExporterRegistry::with_builtin_exporters(&options);
+        assert_eq!(registry.names()[..2], ["json", "yaml"]);
+        assert_eq!(registry.get("YML").unwrap().format_name(), "yaml");
+
+        let config = crate::parse("server { listen 80; }").unwrap();
+        let mut output = Vec::new();
+        registry.export("json", &config, &mut output).unwrap();
+        assert!(output.starts_with(b"{\"directives\":"));
+
+        registry.register(Box::new(Fixed("first")));
+        registry.register(Box::new(Fixed("second")));
+        assert_eq!(registry.len(), ExportFormat::all().len() + 1);
+        let mut output = Vec::new();
+        registry.export("Fixed", &config, &mut output).unwrap();
+        assert_eq!(output, b"second");
+
+        let err = registry
+            .export("csv", &config, &mut Vec::new())
+            .unwrap_err();
+        assert!(err.to_string().contains("Available: json, yaml"));
+    }
+
+    struct Broken;
+
+    #[allow(clippy::unnecessary_literal_bound)]
+    impl Exporter for Broken {
+        fn export(&self, _config: &Config, _writer: &mut dyn Write) -> Result<()> {
+            Err(Error::Serialization("broken".to_string()))
+        }
+
+        fn format_name(&self) -> &str {
+            "yaml"
+        }
+
+        fn extension(&self) -> &str {
+            "yml"
+        }
+    }
+
+    #[test]
+    fn test_empty_registry() {
+        let registry = ExporterRegistry::new();
+        assert!(registry.is_empty());
+        assert_eq!(registry.len(), 0);
+        assert!(registry.get("json").is_none());
+        assert!(!registry.contains("yml"));
+        assert_eq!(
+            format!("{registry:?}"),
+            "ExporterRegistry { exporters: [] }"
+        );
+
+        let config = crate::parse("").unwrap();
+        let err = registry
+            .export("json", &config, &mut Vec::new())
+            .unwrap_err();
+        assert!(matches!(err, Error::InvalidInput(_)));
+        assert!(err.to_string().ends_with("Available: "), "{err}");
+    }
+
+    #[test]
+    fn test_registry_overrides_and_errors() {
+        let options = ExportOptions::builder().format(ExportFormat::Yaml).build();
+        let exporter = FormatExporter::new(options);
+        assert_eq!(exporter.format_name(), "yaml");
+        assert_eq!(exporter.extension(), "yaml");
+        assert_eq!(exporter.options().format, ExportFormat::Yaml);
+
+        // An override keeps its place and is found by the built-in's alias
+        let mut registry = ExporterRegistry::new()
+            .with_exporter(FormatExporter::new(ExportOptions::default()))
+            .with_exporter(exporter)
+            .with_exporter(Fixed("text"));
+        registry.register(Box::new(Broken));
+        assert_eq!(registry.names(), ["json", "yaml", "fixed"]);
+        assert_eq!(registry.get("YML").unwrap().extension(), "yml");
+        assert_eq!(
+            registry.iter().map(Exporter::extension).collect::<Vec<_>>(),
+            ["json", "yml", "txt"]
+        );
+        assert!(format!("{registry:?}").contains(r#"["json", "yaml", "fixed"]"#));
+
+        // Aliases only resolve built-in formats
+        assert!(!registry.contains("md"));
+        assert!(!registry.contains("fix"));
+
+        // The exporter's own error is returned as is
+        let config = crate::parse("").unwrap();
+        let err = registry
+            .export("yml", &config, &mut Vec::new())
+            .unwrap_err();
+        assert!(matches!(err, Error::Serialization(ref msg) if msg == "broken"));
+    }
+}
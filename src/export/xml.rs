@@ -0,0 +1,571 @@
+// src/export/xml.rs
+//! XML export format.
+//!
+//! Requires the `export-xml` feature. The XML follows the JSON model of
+//! [`Config`] one to one, so either can be converted into the other
+//! without losing anything:
+//!
+//! - scalars of a directive (its name and span) are attributes
+//! - arguments are `<arg>` elements, in order, with the argument as text
+//!   and its JSON variant name (`Literal`, `SingleQuoted`, `DoubleQuoted`,
+//!   `Variable`) in the `kind` attribute
+//! - a block directive has a `<children>` element, empty for `name { }`;
+//!   a simple directive has none
+//! - with [`include_source`](super::ExportOptions::include_source), a
+//!   `<source>` element with `file` and `line` attributes comes first
+//! - XML 1.0 cannot hold U+0000–U+0008, U+000B, U+000C, U+000E–U+001F,
+//!   U+FFFE and U+FFFF, not even as character references. An argument
+//!   containing one gets `escaped="true"`: its backslashes are doubled and
+//!   each such character is written as `\uXXXX` (upper-case hex), so the
+//!   value can be restored. Elsewhere (names, messages, file names) they
+//!   are written as `\uXXXX` without the flag.
+//!
+//! ```xml
+//! <?xml version="1.0" encoding="UTF-8"?>
+//! <config xmlns="urn:nginx-discovery:config:1">
+//!   <directive name="server" start="0" end="21" line="1" col="1">
+//!     <children>
+//!       <directive name="listen" start="9" end="19" line="1" col="10">
+//!         <arg kind="Literal">80</arg>
+//!       </directive>
+//!     </children>
+//!   </directive>
+//! </config>
+//! ```
+//!
+//! [`XML_SCHEMA`] is the XML Schema of this format, for tools that
+//! validate their input.
+
+use super::findings::FindingsDocument;
+use crate::ast::{Config, Directive, Value};
+use crate::Result;
+use std::fmt::Write as _;
+use std::io::Write;
+
+/// Namespace of the root element
+pub const XML_NAMESPACE: &str = "urn:nginx-discovery:config:1";
+
+/// XML Schema (XSD) of the configuration export
+pub const XML_SCHEMA: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema"
+           xmlns="urn:nginx-discovery:config:1"
+           targetNamespace="urn:nginx-discovery:config:1"
+           elementFormDefault="qualified">
+
+  <!-- A parsed configuration: its top-level directives in order -->
+  <xs:element name="config" type="Directives"/>
+
+  <xs:complexType name="Directives">
+    <xs:sequence>
+      <xs:element name="directive" type="Directive" minOccurs="0" maxOccurs="unbounded"/>
+    </xs:sequence>
+  </xs:complexType>
+
+  <!-- A directive. Block directives have <children>, simple ones do not. -->
+  <xs:complexType name="Directive">
+    <xs:sequence>
+      <xs:element name="source" type="Source" minOccurs="0"/>
+      <xs:element name="arg" type="Arg" minOccurs="0" maxOccurs="unbounded"/>
+      <xs:element name="children" type="Directives" minOccurs="0"/>
+    </xs:sequence>
+    <xs:attribute name="name" type="xs:string" use="required"/>
+    <!-- Byte offsets of the directive in its file -->
+    <xs:attribute name="start" type="xs:nonNegativeInteger" use="required"/>
+    <xs:attribute name="end" type="xs:nonNegativeInteger" use="required"/>
+    <!-- 1-based line and column, 0 when unknown -->
+    <xs:attribute name="line" type="xs:nonNegativeInteger" use="required"/>
+    <xs:attribute name="col" type="xs:nonNegativeInteger" use="required"/>
+  </xs:complexType>
+
+  <!-- An argument; the text is the value of the JSON model, without
+       surrounding quotes or the leading $ of a variable -->
+  <xs:complexType name="Arg">
+    <xs:simpleContent>
+      <xs:extension base="xs:string">
+        <xs:attribute name="kind" type="ArgKind" use="required"/>
+        <!-- When true, \\ stands for a backslash and \uXXXX for a
+             character XML 1.0 does not allow (U+0000-U+0008, U+000B,
+             U+000C, U+000E-U+001F, U+FFFE, U+FFFF) -->
+        <xs:attribute name="escaped" type="xs:boolean" default="false"/>
+      </xs:extension>
+    </xs:simpleContent>
+  </xs:complexType>
+
+  <xs:simpleType name="ArgKind">
+    <xs:restriction base="xs:string">
+      <xs:enumeration value="Literal"/>
+      <xs:enumeration value="SingleQuoted"/>
+      <xs:enumeration value="DoubleQuoted"/>
+      <xs:enumeration value="Variable"/>
+    </xs:restriction>
+  </xs:simpleType>
+
+  <!-- Where the directive is defined, with include_source -->
+  <xs:complexType name="Source">
+    <xs:attribute name="file" type="xs:string"/>
+    <xs:attribute name="line" type="xs:nonNegativeInteger" use="required"/>
+  </xs:complexType>
+
+  <!-- Findings export: the configuration and the selected findings -->
+  <xs:element name="findings-document">
+    <xs:complexType>
+      <xs:sequence>
+        <xs:element name="config" type="Directives"/>
+        <xs:element name="findings">
+          <xs:complexType>
+            <xs:sequence>
+              <xs:element name="finding" type="Finding" minOccurs="0" maxOccurs="unbounded"/>
+            </xs:sequence>
+          </xs:complexType>
+        </xs:element>
+      </xs:sequence>
+    </xs:complexType>
+  </xs:element>
+
+  <xs:complexType name="Finding">
+    <xs:sequence>
+      <xs:element name="message" type="xs:string"/>
+      <xs:element name="help" type="xs:string" minOccurs="0"/>
+    </xs:sequence>
+    <xs:attribute name="category" type="xs:string" use="required"/>
+    <xs:attribute name="rule" type="xs:string" use="required"/>
+    <xs:attribute name="severity" type="xs:string" use="required"/>
+    <xs:attribute name="directive" type="xs:string"/>
+    <xs:attribute name="line" type="xs:nonNegativeInteger"/>
+    <xs:attribute name="file" type="xs:string"/>
+  </xs:complexType>
+</xs:schema>
+"#;
+
+/// Streaming XML writer with optional indentation
+struct XmlWriter<'a, W: Write> {
+    writer: &'a mut W,
+    pretty: bool,
+    depth: usize,
+}
+
+impl<'a, W: Write> XmlWriter<'a, W> {
+    fn new(writer: &'a mut W, pretty: bool) -> Result<Self> {
+        write!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        let mut xml = Self {
+            writer,
+            pretty,
+            depth: 0,
+        };
+        xml.newline()?;
+        Ok(xml)
+    }
+
+    fn newline(&mut self) -> Result<()> {
+        if self.pretty {
+            writeln!(self.writer)?;
+        }
+        Ok(())
+    }
+
+    fn start_tag(&mut self, name: &str, attrs: &[(&str, &str)]) -> Result<()> {
+        if self.pretty {
+            write!(self.writer, "{:width$}", "", width = self.depth * 2)?;
+        }
+        write!(self.writer, "<{name}")?;
+        for (key, value) in attrs {
+            write!(self.writer, " {key}=\"{}\"", escape(value, true))?;
+        }
+        Ok(())
+    }
+
+    fn open(&mut self, name: &str, attrs: &[(&str, &str)]) -> Result<()> {
+        self.start_tag(name, attrs)?;
+        write!(self.writer, ">")?;
+        self.depth += 1;
+        self.newline()
+    }
+
+    fn close(&mut self, name: &str) -> Result<()> {
+        self.depth -= 1;
+        if self.pretty {
+            write!(self.writer, "{:width$}", "", width = self.depth * 2)?;
+        }
+        write!(self.writer, "</{name}>")?;
+        self.newline()
+    }
+
+    fn empty(&mut self, name: &str, attrs: &[(&str, &str)]) -> Result<()> {
+        self.start_tag(name, attrs)?;
+        write!(self.writer, "/>")?;
+        self.newline()
+    }
+
+    fn text(&mut self, name: &str, attrs: &[(&str, &str)], text: &str) -> Result<()> {
+        self.start_tag(name, attrs)?;
+        write!(self.writer, ">{}</{name}>", escape(text, false))?;
+        self.newline()
+    }
+
+    fn directives(&mut self, directives: &[Directive], source: Option<&Source>) -> Result<()> {
+        for directive in directives {
+            self.directive(directive, source)?;
+        }
+        Ok(())
+    }
+
+    fn directive(&mut self, directive: &Directive, source: Option<&Source>) -> Result<()> {
+        let span = directive.span;
+        let (start, end, line, col) = (
+            span.start.to_string(),
+            span.end.to_string(),
+            span.line.to_string(),
+            span.col.to_string(),
+        );
+        let attrs = [
+            ("name", directive.name()),
+            ("start", start.as_str()),
+            ("end", end.as_str()),
+            ("line", line.as_str()),
+            ("col", col.as_str()),
+        ];
+        if source.is_none() && directive.args().is_empty() && directive.children().is_none() {
+            return self.empty("directive", &attrs);
+        }
+
+        self.open("directive", &attrs)?;
+        if let Some(source) = source {
+            match &source.file {
+                Some(file) => self.empty("source", &[("file", file), ("line", &line)])?,
+                None => self.empty("source", &[("line", &line)])?,
+            }
+        }
+        for arg in directive.args() {
+            let (kind, text) = match arg {
+                Value::Literal(s) => ("Literal", s),
+                Value::SingleQuoted(s) => ("SingleQuoted", s),
+                Value::DoubleQuoted(s) => ("DoubleQuoted", s),
+                Value::Variable(s) => ("Variable", s),
+            };
+            match escape_disallowed(text) {
+                Some(text) => self.text("arg", &[("kind", kind), ("escaped", "true")], &text)?,
+                None => self.text("arg", &[("kind", kind)], text)?,
+            }
+        }
+        match directive.children() {
+            Some([]) => self.empty("children", &[])?,
+            Some(children) => {
+                self.open("children", &[])?;
+                self.directives(children, source)?;
+                self.close("children")?;
+            }
+            None => {}
+        }
+        self.close("directive")
+    }
+}
+
+/// File named in `<source>` elements
+pub(crate) struct Source {
+    pub(crate) file: Option<String>,
+}
+
+/// Write `config` as a `<config>` document
+pub(crate) fn write_config<W: Write>(
+    config: &Config,
+    writer: &mut W,
+    pretty: bool,
+    source: Option<&Source>,
+) -> Result<()> {
+    let mut xml = XmlWriter::new(writer, pretty)?;
+    if config.directives.is_empty() {
+        return xml.empty("config", &[("xmlns", XML_NAMESPACE)]);
+    }
+    xml.open("config", &[("xmlns", XML_NAMESPACE)])?;
+    xml.directives(&config.directives, source)?;
+    xml.close("config")
+}
+
+/// Write a findings export as a `<findings-document>`
+pub(crate) fn write_findings<W: Write>(
+    document: &FindingsDocument,
+    writer: &mut W,
+    pretty: bool,
+) -> Result<()> {
+    let mut xml = XmlWriter::new(writer, pretty)?;
+    xml.open("findings-document", &[("xmlns", XML_NAMESPACE)])?;
+    xml.open("config", &[])?;
+    xml.directives(&document.config.directives, None)?;
+    xml.close("config")?;
+
+    xml.open("findings", &[])?;
+    for f in &document.findings {
+        let category = f.category.to_string();
+        let severity = f.finding.severity.to_string();
+        let line = f.finding.line().map(|l| l.to_string());
+        let file = f
+            .finding
+            .file
+            .as_ref()
+            .map(|file| file.display().to_string());
+        let mut attrs = vec![
+            ("category", category.as_str()),
+            ("rule", f.finding.rule.as_str()),
+            ("severity", severity.as_str()),
+        ];
+        if let Some(directive) = &f.finding.directive {
+            attrs.push(("directive", directive));
+        }
+        if let Some(line) = &line {
+            attrs.push(("line", line));
+        }
+        if let Some(file) = &file {
+            attrs.push(("file", file));
+        }
+        xml.open("finding", &attrs)?;
+        xml.text("message", &[], &f.finding.message)?;
+        if let Some(help) = &f.finding.help {
+            xml.text("help", &[], help)?;
+        }
+        xml.close("finding")?;
+    }
+    xml.close("findings")?;
+    xml.close("findings-document")
+}
+
+/// Whether XML 1.0 allows `c` in a document
+fn is_xml_char(c: char) -> bool {
+    !matches!(
+        c,
+        '\u{0}'..='\u{8}' | '\u{B}' | '\u{C}' | '\u{E}'..='\u{1F}' | '\u{FFFE}' | '\u{FFFF}'
+    )
+}
+
+/// Argument text with backslashes doubled and characters XML 1.0 does not
+/// allow written as `\uXXXX`; `None` when there are none of them
+fn escape_disallowed(value: &str) -> Option<String> {
+    if value.chars().all(is_xml_char) {
+        return None;
+    }
+    let mut escaped = String::with_capacity(value.len() + 8);
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            c if !is_xml_char(c) => push_code(&mut escaped, c),
+            c => escaped.push(c),
+        }
+    }
+    Some(escaped)
+}
+
+/// Append `c` as `\uXXXX`
+fn push_code(escaped: &mut String, c: char) {
+    let _ = write!(escaped, "\\u{:04X}", u32::from(c));
+}
+
+/// Escape text content, or an attribute value when `attribute` is set
+///
+/// Characters XML 1.0 does not allow are written as `\uXXXX`.
+fn escape(value: &str, attribute: bool) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' if attribute => escaped.push_str("&quot;"),
+            '\n' if attribute => escaped.push_str("&#10;"),
+            '\t' if attribute => escaped.push_str("&#9;"),
+            '\r' => escaped.push_str("&#13;"),
+            c if !is_xml_char(c) => push_code(&mut escaped, c),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_config_xml() {
+        let config = crate::parse(
+            "server {\n    listen 80;\n    location / { }\n    return 200 \"a<b & \\\"c\\\"\";\n    set $x $host;\n}\n",
+        )
+        .unwrap();
+        let mut output = Vec::new();
+        write_config(&config, &mut output, true, None).unwrap();
+        let xml = String::from_utf8(output).unwrap();
+
+        assert!(xml.starts_with(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<config xmlns=\"urn:nginx-discovery:config:1\">\n"
+        ));
+        assert!(xml.contains(
+            "      <directive name=\"listen\" start=\"13\" end=\"23\" line=\"2\" col=\"5\">\n        <arg kind=\"Literal\">80</arg>\n      </directive>\n"
+        ));
+        // An empty block keeps its <children>, telling it from a simple directive
+        assert!(xml.contains("<arg kind=\"Literal\">/</arg>\n        <children/>\n"));
+        assert!(xml.contains("<arg kind=\"DoubleQuoted\">a&lt;b &amp; \\\"c\\\"</arg>"));
+        assert!(xml.contains("<arg kind=\"Variable\">host</arg>"));
+
+        let mut compact = Vec::new();
+        let source = Source {
+            file: Some("nginx.conf".to_string()),
+        };
+        write_config(&config, &mut compact, false, Some(&source)).unwrap();
+        let compact = String::from_utf8(compact).unwrap();
+        assert!(!compact.contains('\n'));
+        assert!(compact.contains(
+            "<directive name=\"server\" start=\"0\" end=\"92\" line=\"1\" col=\"1\"><source file=\"nginx.conf\" line=\"1\"/><children>"
+        ));
+    }
+
+    /// Undo [`escape`] and [`escape_disallowed`] for the text of an
+    /// `escaped="true"` argument
+    fn unescape_arg(text: &str) -> String {
+        let text = text
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&#13;", "\r")
+            .replace("&amp;", "&");
+        let mut value = String::new();
+        let mut chars = text.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                value.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('u') => {
+                    let hex: String = chars.by_ref().take(4).collect();
+                    let code = u32::from_str_radix(&hex, 16).unwrap();
+                    value.push(char::from_u32(code).unwrap());
+                }
+                Some(other) => value.push(other),
+                None => panic!("dangling backslash"),
+            }
+        }
+        value
+    }
+
+    #[test]
+    fn test_control_characters_round_trip() {
+        let original = "a\u{1}b\\u0041\u{1f}\u{ffff}<c>\u{b}";
+        let config =
+            crate::parse(&format!("return 200 \"{original}\";\nset $x \"\u{c}\";\n")).unwrap();
+        let mut output = Vec::new();
+        write_config(&config, &mut output, false, None).unwrap();
+        let xml = String::from_utf8(output).unwrap();
+
+        assert!(xml.chars().all(is_xml_char));
+        assert!(xml.contains("<arg kind=\"Literal\">200</arg>"));
+        let args: Vec<String> = xml
+            .split("<arg kind=\"DoubleQuoted\" escaped=\"true\">")
+            .skip(1)
+            .map(|rest| unescape_arg(&rest[..rest.find("</arg>").unwrap()]))
+            .collect();
+        assert_eq!(args, vec![original.to_string(), "\u{c}".to_string()]);
+
+        // Outside arguments the characters are replaced, not flagged
+        assert_eq!(escape("a\u{0}b", true), "a\\u0000b");
+    }
+
+    #[test]
+    fn test_write_config_xml_edge_cases() {
+        let mut output = Vec::new();
+        write_config(&crate::parse("").unwrap(), &mut output, true, None).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<config xmlns=\"urn:nginx-discovery:config:1\"/>\n"
+        );
+
+        let config = crate::parse("daemon;\nreturn 'x\ty';\n").unwrap();
+        let mut output = Vec::new();
+        write_config(&config, &mut output, false, None).unwrap();
+        let xml = String::from_utf8(output).unwrap();
+        assert!(
+            xml.contains("<directive name=\"daemon\" start=\"0\" end=\"7\" line=\"1\" col=\"1\"/>")
+        );
+        // Tabs stay as they are in text, but not in attributes
+        assert!(xml.contains("<arg kind=\"SingleQuoted\">x\ty</arg>"));
+
+        // Without a file, the source has only a line; with one, the file
+        // name is escaped as an attribute
+        let mut output = Vec::new();
+        write_config(&config, &mut output, false, Some(&Source { file: None })).unwrap();
+        let xml = String::from_utf8(output).unwrap();
+        assert!(xml.contains("line=\"1\" col=\"1\"><source line=\"1\"/></directive>"));
+
+        let source = Source {
+            file: Some("a\"b&c\n\td.conf".to_string()),
+        };
+        let mut output = Vec::new();
+        write_config(&config, &mut output, false, Some(&source)).unwrap();
+        let xml = String::from_utf8(output).unwrap();
+        assert!(xml.contains("<source file=\"a&quot;b&amp;c&#10;&#9;d.conf\" line=\"2\"/>"));
+        assert_eq!(escape("a\r\"b\"\n", false), "a&#13;\"b\"\n");
+    }
+
+    #[test]
+    fn test_write_findings_xml() {
+        use crate::export::{CategorizedFinding, FindingCategory};
+        use crate::lint::{Finding, Severity};
+
+        let config = crate::parse("listen 80;").unwrap();
+        let document = FindingsDocument {
+            config: config.clone(),
+            findings: vec![
+                CategorizedFinding {
+                    category: FindingCategory::Lint,
+                    finding: Finding::new("plain", Severity::Info, "a < b").with_help("use \"c\""),
+                },
+                CategorizedFinding {
+                    category: FindingCategory::Network,
+                    finding: Finding::new("located", Severity::Error, "down")
+                        .at(&config.directives[0])
+                        .with_file("conf/nginx.conf"),
+                },
+            ],
+        };
+        let mut output = Vec::new();
+        write_findings(&document, &mut output, false).unwrap();
+        let xml = String::from_utf8(output).unwrap();
+        assert!(xml.contains("<config><directive name=\"listen\""));
+        assert!(xml.contains(
+            "<finding category=\"lint\" rule=\"plain\" severity=\"info\"><message>a &lt; b</message><help>use \"c\"</help></finding>"
+        ), "{xml}");
+        assert!(xml.contains(
+            "<finding category=\"network\" rule=\"located\" severity=\"error\" directive=\"listen\" line=\"1\" file=\"conf/nginx.conf\"><message>down</message></finding>"
+        ), "{xml}");
+
+        // No findings still gives a <findings> element
+        let empty = FindingsDocument {
+            config: crate::parse("").unwrap(),
+            findings: Vec::new(),
+        };
+        let mut output = Vec::new();
+        write_findings(&empty, &mut output, true).unwrap();
+        let xml = String::from_utf8(output).unwrap();
+        assert!(
+            xml.ends_with(
+                "  <config>\n  </config>\n  <findings>\n  </findings>\n</findings-document>\n"
+            ),
+            "{xml}"
+        );
+    }
+
+    #[test]
+    fn test_write_error_is_io() {
+        struct Full;
+
+        impl Write for Full {
+            fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+                Err(std::io::ErrorKind::WriteZero.into())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let config = crate::parse("listen 80;").unwrap();
+        let err = write_config(&config, &mut Full, true, None).unwrap_err();
+        assert!(matches!(err, crate::Error::Io(e) if e.kind() == std::io::ErrorKind::WriteZero));
+    }
+}
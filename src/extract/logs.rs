@@ -1,8 +1,9 @@
 //! Extract log-related directives from NGINX configuration
 
+use super::ExtractWarning;
 use crate::ast::{Config, Directive};
 use crate::error::Result;
-use crate::types::{AccessLog, LogContext, LogFormat};
+use crate::types::{AccessLog, LogContext, LogFormat, Server};
 use std::path::PathBuf;
 
 /// Extract all `log_format` directives
@@ -12,15 +13,22 @@ use std::path::PathBuf;
 /// This function currently does not return errors but returns `Result`
 /// for consistency with other extractors.
 pub fn log_formats(config: &Config) -> Result<Vec<LogFormat>> {
+    Ok(collect_log_formats(config, &mut Vec::new()))
+}
+
+pub(crate) fn collect_log_formats(
+    config: &Config,
+    warnings: &mut Vec<ExtractWarning>,
+) -> Vec<LogFormat> {
     let mut formats = Vec::new();
 
     for directive in config.find_directives_recursive("log_format") {
-        if let Some(format) = parse_log_format(directive) {
+        if let Some(format) = parse_log_format(directive, warnings) {
             formats.push(format);
         }
     }
 
-    Ok(formats)
+    formats
 }
 
 /// Extract all `access_log` directives
@@ -30,11 +38,18 @@ pub fn log_formats(config: &Config) -> Result<Vec<LogFormat>> {
 /// This function currently does not return errors but returns `Result`
 /// for consistency with other extractors.
 pub fn access_logs(config: &Config) -> Result<Vec<AccessLog>> {
+    Ok(collect_access_logs(config, &mut Vec::new()))
+}
+
+pub(crate) fn collect_access_logs(
+    config: &Config,
+    warnings: &mut Vec<ExtractWarning>,
+) -> Vec<AccessLog> {
     let mut logs = Vec::new();
 
     // Find in top-level and http context
     for directive in config.find_directives("access_log") {
-        if let Some(log) = parse_access_log(directive, LogContext::Main) {
+        if let Some(log) = parse_access_log(directive, LogContext::Main, warnings) {
             logs.push(log);
         }
     }
@@ -42,7 +57,7 @@ pub fn access_logs(config: &Config) -> Result<Vec<AccessLog>> {
     // Also check inside http blocks
     for http in config.find_directives("http") {
         for directive in http.find_children("access_log") {
-            if let Some(log) = parse_access_log(directive, LogContext::Main) {
+            if let Some(log) = parse_access_log(directive, LogContext::Main, warnings) {
                 logs.push(log);
             }
         }
@@ -50,12 +65,17 @@ pub fn access_logs(config: &Config) -> Result<Vec<AccessLog>> {
 
     // Find in server blocks
     for server in config.find_directives_recursive("server") {
-        let server_name = get_server_name(server);
+        let server_name = Server::primary_name_of(server);
+        let server_names: Vec<String> = server
+            .find_children("server_name")
+            .iter()
+            .flat_map(|d| d.args_as_strings())
+            .collect();
         let context = LogContext::Server(server_name);
 
         for directive in server.find_children("access_log") {
-            if let Some(log) = parse_access_log(directive, context.clone()) {
-                logs.push(log);
+            if let Some(log) = parse_access_log(directive, context.clone(), warnings) {
+                logs.push(log.with_server_names(server_names.clone()));
             }
         }
 
@@ -65,20 +85,27 @@ pub fn access_logs(config: &Config) -> Result<Vec<AccessLog>> {
             let context = LogContext::Location(location_path);
 
             for directive in location.find_children("access_log") {
-                if let Some(log) = parse_access_log(directive, context.clone()) {
-                    logs.push(log);
+                if let Some(log) = parse_access_log(directive, context.clone(), warnings) {
+                    logs.push(log.with_server_names(server_names.clone()));
                 }
             }
         }
     }
 
-    Ok(logs)
+    logs
 }
 
 /// Parse a `log_format` directive
-fn parse_log_format(directive: &Directive) -> Option<LogFormat> {
+fn parse_log_format(
+    directive: &Directive,
+    warnings: &mut Vec<ExtractWarning>,
+) -> Option<LogFormat> {
     let args = directive.args_as_strings();
     if args.len() < 2 {
+        warnings.push(ExtractWarning::new(
+            directive,
+            "log_format needs a name and a format string; skipped",
+        ));
         return None;
     }
 
@@ -89,9 +116,14 @@ fn parse_log_format(directive: &Directive) -> Option<LogFormat> {
 }
 
 /// Parse an `access_log` directive
-fn parse_access_log(directive: &Directive, context: LogContext) -> Option<AccessLog> {
+fn parse_access_log(
+    directive: &Directive,
+    context: LogContext,
+    warnings: &mut Vec<ExtractWarning>,
+) -> Option<AccessLog> {
     let args = directive.args_as_strings();
     if args.is_empty() {
+        warnings.push(missing_path(directive));
         return None;
     }
 
@@ -125,13 +157,11 @@ fn parse_access_log(directive: &Directive, context: LogContext) -> Option<Access
     Some(log)
 }
 
-/// Get `server_name` from a server directive
-fn get_server_name(server: &Directive) -> String {
-    server
-        .find_children("server_name")
-        .first()
-        .and_then(|d| d.first_arg())
-        .unwrap_or_else(|| "_".to_string())
+pub(crate) fn missing_path(directive: &Directive) -> ExtractWarning {
+    ExtractWarning::new(
+        directive,
+        format!("{} without a path; skipped", directive.name()),
+    )
 }
 
 #[cfg(test)]
@@ -188,6 +218,44 @@ server {
         assert!(matches!(logs[0].context, LogContext::Server(_)));
     }
 
+    #[test]
+    fn test_templated_log_keeps_server_names() {
+        let config = r"
+server {
+    server_name example.com www.example.com;
+    access_log /var/log/nginx/$host.access.log;
+}
+";
+
+        let parsed = parse(config).unwrap();
+        let logs = access_logs(&parsed).unwrap();
+
+        assert_eq!(
+            logs[0].path,
+            PathBuf::from("/var/log/nginx/$host.access.log")
+        );
+        assert!(logs[0].is_templated());
+        assert_eq!(logs[0].server_names, vec!["example.com", "www.example.com"]);
+        assert_eq!(logs[0].expanded_paths().len(), 2);
+    }
+
+    #[test]
+    fn test_syslog_access_log() {
+        let config = r"
+access_log syslog:server=10.0.0.1:514,tag=nginx main;
+";
+
+        let parsed = parse(config).unwrap();
+        let logs = access_logs(&parsed).unwrap();
+
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].format_name, Some("main".to_string()));
+        assert!(matches!(
+            &logs[0].target,
+            crate::types::LogTarget::Syslog { server, .. } if server == "10.0.0.1:514"
+        ));
+    }
+
     #[test]
     fn test_skip_disabled_logs() {
         let config = r"
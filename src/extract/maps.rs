@@ -0,0 +1,127 @@
+//! Extract `map` blocks from NGINX configuration
+
+use super::ExtractWarning;
+use crate::ast::{Config, Directive, Value};
+use crate::error::Result;
+use crate::lint::walk;
+use crate::types::Map;
+
+/// Extract all `map` blocks, from `http` and `stream`
+///
+/// # Errors
+///
+/// This function currently does not return errors but returns `Result`
+/// for consistency with other extractors.
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::{extract, parse};
+///
+/// let config = parse(r"
+/// http {
+///     map $http_upgrade $connection_upgrade {
+///         default upgrade;
+///         ''      close;
+///     }
+/// }
+/// ")?;
+///
+/// let maps = extract::maps(&config)?;
+/// assert_eq!(maps[0].variable, "connection_upgrade");
+/// assert_eq!(maps[0].value_for("websocket"), "upgrade");
+/// assert_eq!(maps[0].value_for(""), "close");
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+pub fn maps(config: &Config) -> Result<Vec<Map>> {
+    Ok(collect_maps(config, &mut Vec::new()))
+}
+
+pub(crate) fn collect_maps(config: &Config, warnings: &mut Vec<ExtractWarning>) -> Vec<Map> {
+    let mut maps = Vec::new();
+    walk(config, &mut |directive, parents| {
+        if directive.name() != "map" {
+            return;
+        }
+        if let Some(mut map) = parse_map(directive, warnings) {
+            map.stream = parents.iter().any(|p| p.name() == "stream");
+            maps.push(map);
+        }
+    });
+    maps
+}
+
+fn parse_map(directive: &Directive, warnings: &mut Vec<ExtractWarning>) -> Option<Map> {
+    let [source, variable] = directive.args() else {
+        warnings.push(ExtractWarning::new(
+            directive,
+            "map needs a source string and a variable; skipped",
+        ));
+        return None;
+    };
+    let mut map = Map::new(source.unquoted(), variable.unquoted());
+    map.line = directive.span.line;
+
+    for entry in directive.children()? {
+        let value = entry
+            .args()
+            .first()
+            .map(Value::unquoted)
+            .unwrap_or_default();
+        map = map.with_entry(entry.name(), value, entry.span.line);
+    }
+
+    Some(map)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_extract_maps() {
+        let config = parse(
+            r#"
+http {
+    map $http_user_agent $bad_bot {
+        default 0;
+        ~*(AhrefsBot|SemrushBot) 1;
+        "~*python-requests" 1;
+        "" 1;
+    }
+    map $uri {
+        default x;
+    }
+}
+stream {
+    map $ssl_preread_server_name $backend {
+        hostnames;
+        *.example.com app;
+    }
+}
+"#,
+        )
+        .unwrap();
+        let mut warnings = Vec::new();
+        let maps = collect_maps(&config, &mut warnings);
+
+        assert_eq!(maps.len(), 2);
+        assert_eq!(maps[0].source, "$http_user_agent");
+        assert_eq!(maps[0].line, 3);
+        let keys: Vec<_> = maps[0].entries.iter().map(|e| e.key.as_str()).collect();
+        assert_eq!(
+            keys,
+            vec!["~*(AhrefsBot|SemrushBot)", "~*python-requests", ""]
+        );
+        assert_eq!(maps[0].value_for("python-requests/2.31"), "1");
+        assert!(!maps[0].stream);
+
+        assert!(maps[1].stream);
+        assert!(maps[1].hostnames);
+        assert_eq!(maps[1].entries[0].value, "app");
+
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].span.line, 9);
+    }
+}
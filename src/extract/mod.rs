@@ -1,7 +1,96 @@
 //! High-level extractors for NGINX directives
+//!
+//! Extractors skip directives they cannot interpret (a `listen` with an
+//! unparseable address, an `access_log` without a path) and parameters
+//! they do not know (`listen 80 fast`). [`warnings`]
+//! reports what was skipped or defaulted, so callers can tell an empty
+//! result from dropped data.
 
 pub mod logs;
+pub mod maps;
 pub mod servers;
+pub mod split_clients;
+pub mod upstreams;
 
 pub use logs::{access_logs, log_formats};
+pub use maps::maps;
+#[cfg(feature = "export-markdown")]
+pub(crate) use servers::server;
 pub use servers::servers;
+pub(crate) use servers::{listen, listens, parse_listen};
+pub use split_clients::split_clients;
+pub use upstreams::upstreams;
+
+use crate::ast::{Config, Directive, Span};
+use std::fmt;
+
+/// A recoverable problem met during extraction
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExtractWarning {
+    /// Name of the directive
+    pub directive: String,
+
+    /// Location of the directive
+    pub span: Span,
+
+    /// What was skipped or assumed
+    pub message: String,
+}
+
+impl ExtractWarning {
+    pub(crate) fn new(directive: &Directive, message: impl Into<String>) -> Self {
+        Self {
+            directive: directive.name().to_string(),
+            span: directive.span,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ExtractWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.span.line, self.message)
+    }
+}
+
+/// Run every extractor and collect the problems they met, in file order
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::{extract, parse};
+///
+/// let config = parse("http { server { listen 80o; } }")?;
+///
+/// assert!(extract::servers(&config)?[0].listen.is_empty());
+/// let warnings = extract::warnings(&config);
+/// assert_eq!(
+///     warnings[0].message,
+///     "invalid port \"80o\" in listen 80o; the listener is skipped"
+/// );
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+#[must_use]
+pub fn warnings(config: &Config) -> Vec<ExtractWarning> {
+    let mut warnings = Vec::new();
+    logs::collect_log_formats(config, &mut warnings);
+    logs::collect_access_logs(config, &mut warnings);
+    maps::collect_maps(config, &mut warnings);
+    servers::collect_servers(config, &mut warnings);
+    split_clients::collect_split_clients(config, &mut warnings);
+    upstreams::collect_upstreams(config, &mut warnings);
+
+    // Access logs are read by several extractors
+    let mut seen = Vec::new();
+    warnings.retain(|w| {
+        let key = (w.span, w.message.clone());
+        let new = !seen.contains(&key);
+        if new {
+            seen.push(key);
+        }
+        new
+    });
+    warnings.sort_by_key(|w| (w.span.line, w.span.col));
+    warnings
+}
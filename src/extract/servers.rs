@@ -1,10 +1,12 @@
 //! Extract server-related directives from NGINX configuration
 
-use crate::ast::{Config, Directive};
-use crate::error::Result;
+use super::logs::missing_path;
+use super::ExtractWarning;
+use crate::ast::{Config, Directive, Value};
+use crate::error::{Error, Result};
 use crate::types::{
-    AccessLog, ErrorLog, ErrorLogLevel, ListenDirective, Location, LocationModifier, LogContext,
-    Server,
+    AccessLog, AccessRule, CertificatePair, ErrorLog, ErrorLogLevel, LimitExcept, ListenDirective,
+    Location, LocationModifier, LogContext, Server, TryFiles,
 };
 use std::path::PathBuf;
 
@@ -34,22 +36,88 @@ use std::path::PathBuf;
 /// # Ok::<(), nginx_discovery::Error>(())
 /// ```
 pub fn servers(config: &Config) -> Result<Vec<Server>> {
+    Ok(collect_servers(config, &mut Vec::new()))
+}
+
+pub(crate) fn collect_servers(config: &Config, warnings: &mut Vec<ExtractWarning>) -> Vec<Server> {
     let mut result = Vec::new();
 
     // Find all server blocks
     for server_directive in config.find_directives_recursive("server") {
-        if let Some(server) = parse_server(server_directive) {
+        if let Some(server) = parse_server(server_directive, warnings) {
             result.push(server);
         }
     }
 
-    Ok(result)
+    result
 }
 
 /// Parse a single server directive
-fn parse_server(directive: &Directive) -> Option<Server> {
+/// Listener of a `listen` directive
+///
+/// An unknown or malformed parameter is ignored with a warning and the rest
+/// of the listener kept; a missing or invalid address or port skips the
+/// listener with a warning.
+pub(crate) fn parse_listen(
+    directive: &Directive,
+    warnings: &mut Vec<ExtractWarning>,
+) -> Option<ListenDirective> {
+    let reason = |err: Error| match err {
+        Error::InvalidInput(reason) => reason,
+        other => other.to_string(),
+    };
+    let args = directive.args_as_strings();
+    let mut kept: Vec<String> = args.iter().take(1).cloned().collect();
+    if let Err(err) = ListenDirective::from_args(&kept) {
+        warnings.push(ExtractWarning::new(
+            directive,
+            format!("{}; the listener is skipped", reason(err)),
+        ));
+        return None;
+    }
+    for arg in args.iter().skip(1) {
+        kept.push(arg.clone());
+        if let Err(err) = ListenDirective::from_args(&kept) {
+            kept.pop();
+            warnings.push(ExtractWarning::new(
+                directive,
+                format!("{}; the parameter is ignored", reason(err)),
+            ));
+        }
+    }
+    ListenDirective::from_args(&kept).ok()
+}
+
+/// Listener of a `listen` directive, as [`parse_listen`] reads it; the
+/// warnings are reported by [`warnings`](super::warnings)
+pub(crate) fn listen(directive: &Directive) -> Option<ListenDirective> {
+    parse_listen(directive, &mut Vec::new())
+}
+
+/// Listeners of the `listen` directives of a server block
+pub(crate) fn listens(server: &Directive) -> Vec<ListenDirective> {
+    server
+        .find_children("listen")
+        .into_iter()
+        .filter_map(listen)
+        .collect()
+}
+
+/// Settings of one `server` block, or `None` when it has no body
+#[cfg(feature = "export-markdown")]
+pub(crate) fn server(directive: &Directive) -> Option<Server> {
+    parse_server(directive, &mut Vec::new())
+}
+
+fn parse_server(directive: &Directive, warnings: &mut Vec<ExtractWarning>) -> Option<Server> {
     let children = directive.children()?;
     let mut server = Server::new();
+    let server_auth = children
+        .iter()
+        .rev()
+        .find_map(auth_setting)
+        .unwrap_or(false);
+    let mut keys = Vec::new();
 
     for child in children {
         match child.name() {
@@ -59,7 +127,7 @@ fn parse_server(directive: &Directive) -> Option<Server> {
                 }
             }
             "listen" => {
-                if let Some(listen) = ListenDirective::from_args(&child.args_as_strings()) {
+                if let Some(listen) = parse_listen(child, warnings) {
                     server = server.with_listen(listen);
                 }
             }
@@ -74,34 +142,72 @@ fn parse_server(directive: &Directive) -> Option<Server> {
                 }
             }
             "access_log" => {
-                if let Some(log) = parse_access_log_in_server(child) {
+                if let Some(log) = parse_access_log_in_server(child, warnings) {
                     server.access_logs.push(log);
                 }
             }
             "error_log" => {
-                if let Some(log) = parse_error_log_in_server(child) {
+                if let Some(log) = parse_error_log_in_server(child, warnings) {
                     server.error_logs.push(log);
                 }
             }
             "location" => {
-                if let Some(location) = parse_location(child) {
+                if let Some(location) = parse_location(child, server_auth, warnings) {
                     server = server.with_location(location);
                 }
             }
+            "ssl_certificate" => {
+                if let Some(path) = child.first_arg() {
+                    server = server.with_certificate(CertificatePair::new(path));
+                }
+            }
+            "ssl_certificate_key" => keys.extend(child.first_arg()),
             _ => {} // Ignore other directives for now
         }
     }
 
+    // Keys pair with certificates by position, not by where they appear
+    for (pair, key) in server.certificates.iter_mut().zip(keys) {
+        pair.key = Some(key);
+    }
+
     Some(server)
 }
 
+/// Whether an `auth_basic` or `auth_request` directive turns
+/// authentication on or off
+fn auth_setting(directive: &Directive) -> Option<bool> {
+    match directive.name() {
+        "auth_basic" | "auth_request" => Some(directive.first_arg()? != "off"),
+        _ => None,
+    }
+}
+
+/// Parse a `limit_except` block
+fn parse_limit_except(directive: &Directive) -> LimitExcept {
+    let mut limit = LimitExcept::new(&directive.args_as_strings());
+    for child in directive.children().unwrap_or_default() {
+        if let Some(rule) = AccessRule::from_directive(child.name(), &child.args_as_strings()) {
+            limit.rules.push(rule);
+        } else if let Some(auth) = auth_setting(child) {
+            limit.auth = auth;
+        }
+    }
+    limit
+}
+
 /// Parse location block
-fn parse_location(directive: &Directive) -> Option<Location> {
+fn parse_location(
+    directive: &Directive,
+    inherited_auth: bool,
+    warnings: &mut Vec<ExtractWarning>,
+) -> Option<Location> {
     let args = directive.args_as_strings();
     let (modifier, path) = LocationModifier::from_args(&args);
 
     let children = directive.children()?;
     let mut location = Location::new(path, modifier);
+    location.requires_auth = inherited_auth;
 
     for child in children {
         match child.name() {
@@ -110,13 +216,30 @@ fn parse_location(directive: &Directive) -> Option<Location> {
                     location.root = Some(PathBuf::from(root));
                 }
             }
+            "alias" => {
+                if let Some(alias) = child.first_arg() {
+                    location.alias = Some(PathBuf::from(alias));
+                }
+            }
+            "internal" => location.internal = true,
             "proxy_pass" => {
                 if let Some(upstream) = child.first_arg() {
                     location.proxy_pass = Some(upstream);
                 }
             }
+            "limit_except" => {
+                location.limit_except = Some(parse_limit_except(child));
+            }
+            "auth_basic" | "auth_request" => {
+                location.requires_auth = auth_setting(child).unwrap_or(location.requires_auth);
+            }
+            "try_files" => {
+                location.try_files = TryFiles::from_args(
+                    &child.args().iter().map(Value::unquoted).collect::<Vec<_>>(),
+                );
+            }
             "access_log" => {
-                if let Some(log) = parse_access_log_in_location(child, &location.path) {
+                if let Some(log) = parse_access_log_in_location(child, &location.path, warnings) {
                     location.access_logs.push(log);
                 }
             }
@@ -128,9 +251,13 @@ fn parse_location(directive: &Directive) -> Option<Location> {
 }
 
 /// Parse `access_log` in server context
-fn parse_access_log_in_server(directive: &Directive) -> Option<AccessLog> {
+fn parse_access_log_in_server(
+    directive: &Directive,
+    warnings: &mut Vec<ExtractWarning>,
+) -> Option<AccessLog> {
     let args = directive.args_as_strings();
     if args.is_empty() {
+        warnings.push(missing_path(directive));
         return None;
     }
 
@@ -160,9 +287,14 @@ fn parse_access_log_in_server(directive: &Directive) -> Option<AccessLog> {
 }
 
 /// Parse `access_log` in location context
-fn parse_access_log_in_location(directive: &Directive, location_path: &str) -> Option<AccessLog> {
+fn parse_access_log_in_location(
+    directive: &Directive,
+    location_path: &str,
+    warnings: &mut Vec<ExtractWarning>,
+) -> Option<AccessLog> {
     let args = directive.args_as_strings();
     if args.is_empty() {
+        warnings.push(missing_path(directive));
         return None;
     }
 
@@ -192,14 +324,27 @@ fn parse_access_log_in_location(directive: &Directive, location_path: &str) -> O
 }
 
 /// Parse `error_log` in server context
-fn parse_error_log_in_server(directive: &Directive) -> Option<ErrorLog> {
+fn parse_error_log_in_server(
+    directive: &Directive,
+    warnings: &mut Vec<ExtractWarning>,
+) -> Option<ErrorLog> {
     let args = directive.args_as_strings();
     if args.is_empty() {
+        warnings.push(missing_path(directive));
         return None;
     }
 
     let path = PathBuf::from(&args[0]);
     let level = if args.len() > 1 {
+        let known = [
+            "debug", "info", "notice", "warn", "error", "crit", "alert", "emerg",
+        ];
+        if !known.contains(&args[1].as_str()) {
+            warnings.push(ExtractWarning::new(
+                directive,
+                format!("unknown error_log level {}; using error", args[1]),
+            ));
+        }
         args[1]
             .parse::<ErrorLogLevel>()
             .unwrap_or(ErrorLogLevel::Error)
@@ -218,6 +363,67 @@ fn parse_error_log_in_server(directive: &Directive) -> Option<ErrorLog> {
 mod tests {
     use super::*;
     use crate::parse;
+    use crate::types::TryFilesFallback;
+
+    #[test]
+    fn test_extract_warnings() {
+        let config = parse(
+            r"
+server {
+    listen 80o;
+    listen 443 ssl;
+    error_log /var/log/nginx/error.log loud;
+    location / {
+        access_log;
+    }
+}
+",
+        )
+        .unwrap();
+
+        let mut warnings = Vec::new();
+        let servers = collect_servers(&config, &mut warnings);
+        assert_eq!(servers[0].listen.len(), 1);
+        assert_eq!(servers[0].error_logs[0].level, ErrorLogLevel::Error);
+
+        let found: Vec<_> = warnings
+            .iter()
+            .map(|w| (w.directive.as_str(), w.span.line))
+            .collect();
+        assert_eq!(
+            found,
+            vec![("listen", 3), ("error_log", 5), ("access_log", 7)]
+        );
+        assert_eq!(
+            warnings[1].to_string(),
+            "line 5: unknown error_log level loud; using error"
+        );
+        // The access log is also read by the access log extractor
+        assert_eq!(crate::extract::warnings(&config), warnings);
+    }
+
+    #[test]
+    fn test_parse_listen() {
+        let config = parse("server { listen 443 ssl backlog=many fast http2; listen ; }").unwrap();
+        let listens = config.directives[0].find_children("listen");
+        let mut warnings = Vec::new();
+
+        let listen = parse_listen(listens[0], &mut warnings).unwrap();
+        assert_eq!(listen.port, 443);
+        assert!(listen.ssl && listen.http2);
+        assert_eq!(listen.backlog, None);
+        assert!(parse_listen(listens[1], &mut warnings).is_none());
+
+        let messages: Vec<_> = warnings.iter().map(|w| w.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "invalid listen parameter \"backlog=many\"; the parameter is ignored",
+                "invalid listen parameter \"fast\"; the parameter is ignored",
+                "listen needs an address or port; the listener is skipped",
+            ]
+        );
+    }
 
     #[test]
     fn test_extract_basic_server() {
@@ -294,6 +500,65 @@ mod tests {
         assert!(loc2.is_proxy());
     }
 
+    #[test]
+    fn test_extract_limit_except() {
+        let config = r#"
+        server {
+            auth_basic "staff";
+            location /admin {
+                auth_basic off;
+                limit_except GET {
+                    allow 10.0.0.0/8;
+                    deny all;
+                }
+            }
+            location /upload {
+                auth_basic off;
+                limit_except GET POST { }
+            }
+            location / { }
+        }
+        "#;
+
+        let parsed = parse(config).unwrap();
+        let locations = &servers(&parsed).unwrap()[0].locations;
+
+        let limit = locations[0].limit_except.as_ref().unwrap();
+        assert_eq!(limit.methods, vec!["GET"]);
+        assert_eq!(limit.rules.len(), 2);
+        assert!(!locations[0].requires_auth);
+        assert!(!locations[0].allows_all_methods());
+
+        assert!(locations[1].allows_all_methods());
+        assert!(locations[2].requires_auth);
+        assert!(!locations[2].allows_all_methods());
+    }
+
+    #[test]
+    fn test_extract_location_try_files() {
+        let parsed = parse("server { location / { try_files $uri $uri/ @app; } }").unwrap();
+        let servers_list = servers(&parsed).unwrap();
+
+        let try_files = servers_list[0].locations[0].try_files.as_ref().unwrap();
+        assert_eq!(try_files.files, vec!["$uri", "$uri/"]);
+        assert_eq!(
+            try_files.fallback,
+            TryFilesFallback::NamedLocation("app".to_string())
+        );
+    }
+
+    #[test]
+    fn test_extract_location_internal_alias() {
+        let parsed =
+            parse("server { location /protected/ { internal; alias /srv/files/; } }").unwrap();
+        let servers_list = servers(&parsed).unwrap();
+
+        let location = &servers_list[0].locations[0];
+        assert!(location.internal);
+        assert_eq!(location.alias, Some(PathBuf::from("/srv/files/")));
+        assert!(location.is_static());
+    }
+
     #[test]
     fn test_extract_server_with_logs() {
         let config = r"
@@ -346,4 +611,56 @@ mod tests {
             LocationModifier::Regex
         );
     }
+
+    #[test]
+    fn test_extract_certificate_pairs() {
+        let config = r"
+        server {
+            listen 443 ssl;
+            ssl_certificate rsa.crt;
+            ssl_certificate_key rsa.key;
+            ssl_certificate ecdsa.crt;
+        }
+        ";
+
+        let parsed = parse(config).unwrap();
+        let certificates = &servers(&parsed).unwrap()[0].certificates;
+
+        assert_eq!(
+            certificates,
+            &vec![
+                CertificatePair::new("rsa.crt").with_key("rsa.key"),
+                CertificatePair::new("ecdsa.crt"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extract_certificate_pairs_by_position() {
+        let config = r"
+        server {
+            ssl_certificate_key rsa.key;
+            ssl_certificate_key ecdsa.key;
+            ssl_certificate_key extra.key;
+            ssl_certificate rsa.crt;
+            ssl_certificate ecdsa.crt;
+        }
+        server {
+            ssl_certificate_key only.key;
+        }
+        ";
+
+        let parsed = parse(config).unwrap();
+        let servers = servers(&parsed).unwrap();
+
+        // Keys before their certificates still pair; extra keys are dropped
+        assert_eq!(
+            servers[0].certificates,
+            vec![
+                CertificatePair::new("rsa.crt").with_key("rsa.key"),
+                CertificatePair::new("ecdsa.crt").with_key("ecdsa.key"),
+            ]
+        );
+        assert!(servers[1].certificates.is_empty());
+    }
 }
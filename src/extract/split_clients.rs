@@ -0,0 +1,109 @@
+//! Extract `split_clients` blocks from NGINX configuration
+
+use super::ExtractWarning;
+use crate::ast::{Config, Directive, Value};
+use crate::error::Result;
+use crate::types::SplitClients;
+
+/// Extract all `split_clients` blocks, from `http` and `stream`
+///
+/// # Errors
+///
+/// This function currently does not return errors but returns `Result`
+/// for consistency with other extractors.
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::{extract, parse};
+///
+/// let config = parse(r#"
+/// http {
+///     split_clients "${remote_addr}AAA" $variant {
+///         20%  "new";
+///         *    "old";
+///     }
+/// }
+/// "#)?;
+///
+/// let splits = extract::split_clients(&config)?;
+/// assert_eq!(splits[0].variable, "variant");
+/// assert_eq!(splits[0].shares(), vec![("new", 20.0), ("old", 80.0)]);
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+pub fn split_clients(config: &Config) -> Result<Vec<SplitClients>> {
+    Ok(collect_split_clients(config, &mut Vec::new()))
+}
+
+pub(crate) fn collect_split_clients(
+    config: &Config,
+    warnings: &mut Vec<ExtractWarning>,
+) -> Vec<SplitClients> {
+    config
+        .find_directives_recursive("split_clients")
+        .into_iter()
+        .filter_map(|directive| parse_split_clients(directive, warnings))
+        .collect()
+}
+
+fn parse_split_clients(
+    directive: &Directive,
+    warnings: &mut Vec<ExtractWarning>,
+) -> Option<SplitClients> {
+    let [source, variable] = directive.args() else {
+        warnings.push(ExtractWarning::new(
+            directive,
+            "split_clients needs a source string and a variable; skipped",
+        ));
+        return None;
+    };
+    let mut split = SplitClients::new(source.unquoted(), variable.unquoted());
+
+    for bucket in directive.children()? {
+        let value = bucket
+            .args()
+            .first()
+            .map(Value::unquoted)
+            .unwrap_or_default();
+        split = split.with_bucket(bucket.name(), value);
+    }
+
+    Some(split)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_extract_split_clients() {
+        let config = parse(
+            r#"
+http {
+    split_clients "${remote_addr}AAA" $variant {
+        0.5%  .one;
+        2.0%  .two;
+        *     "";
+    }
+}
+stream {
+    split_clients $remote_addr $upstream {
+        50% backend_a;
+        *   backend_b;
+    }
+}
+"#,
+        )
+        .unwrap();
+        let splits = split_clients(&config).unwrap();
+
+        assert_eq!(splits.len(), 2);
+        assert_eq!(splits[0].source, "${remote_addr}AAA");
+        assert_eq!(splits[0].buckets[2].value, "");
+        assert_eq!(splits[0].buckets[2].percent, None);
+        assert_eq!(splits[1].source, "$remote_addr");
+        assert_eq!(splits[1].variable, "upstream");
+        assert_eq!(splits[1].shares()[1], ("backend_b", 50.0));
+    }
+}
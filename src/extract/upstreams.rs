@@ -0,0 +1,189 @@
+//! Extract `upstream` blocks from NGINX configuration
+
+use super::ExtractWarning;
+use crate::ast::{Config, Directive, Value};
+use crate::error::Result;
+use crate::types::{SessionAffinity, Upstream, UpstreamServer};
+
+/// Load-balancing method directives
+const METHODS: &[&str] = &["least_conn", "ip_hash", "hash", "random", "least_time"];
+
+/// Extract all `upstream` blocks, from `http` and `stream`
+///
+/// # Errors
+///
+/// This function currently does not return errors but returns `Result`
+/// for consistency with other extractors.
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::{extract, parse};
+///
+/// let config = parse(r"
+/// http {
+///     upstream backend {
+///         least_conn;
+///         server 10.0.0.1:8080;
+///         server 10.0.0.2:8080 backup;
+///         keepalive 32;
+///     }
+/// }
+/// ")?;
+///
+/// let upstreams = extract::upstreams(&config)?;
+/// assert_eq!(upstreams[0].name, "backend");
+/// assert_eq!(upstreams[0].keepalive, Some(32));
+/// assert_eq!(upstreams[0].method.as_deref(), Some("least_conn"));
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+pub fn upstreams(config: &Config) -> Result<Vec<Upstream>> {
+    Ok(collect_upstreams(config, &mut Vec::new()))
+}
+
+pub(crate) fn collect_upstreams(
+    config: &Config,
+    warnings: &mut Vec<ExtractWarning>,
+) -> Vec<Upstream> {
+    let mut upstreams = Vec::new();
+
+    for directive in &config.directives {
+        collect(directive, false, &mut upstreams, warnings);
+    }
+
+    upstreams
+}
+
+fn collect(
+    directive: &Directive,
+    stream: bool,
+    upstreams: &mut Vec<Upstream>,
+    warnings: &mut Vec<ExtractWarning>,
+) {
+    if directive.name() == "upstream" {
+        if let Some(upstream) = parse_upstream(directive, stream, warnings) {
+            upstreams.push(upstream);
+        }
+        return;
+    }
+
+    let stream = stream || directive.name() == "stream";
+    for child in directive.children().unwrap_or_default() {
+        collect(child, stream, upstreams, warnings);
+    }
+}
+
+fn parse_upstream(
+    directive: &Directive,
+    stream: bool,
+    warnings: &mut Vec<ExtractWarning>,
+) -> Option<Upstream> {
+    let Some(name) = directive.first_arg() else {
+        warnings.push(ExtractWarning::new(
+            directive,
+            "upstream without a name; skipped",
+        ));
+        return None;
+    };
+    let mut upstream = Upstream::new(name);
+    upstream.stream = stream;
+    upstream.line = directive.span.line;
+
+    for child in directive.children()? {
+        let args: Vec<String> = child.args().iter().map(Value::unquoted).collect();
+        match child.name() {
+            "server" => match UpstreamServer::from_args(&args) {
+                Some(server) => upstream.servers.push(server),
+                None => warnings.push(ExtractWarning::new(
+                    child,
+                    format!(
+                        "upstream {} server {} could not be parsed; skipped",
+                        upstream.name,
+                        args.join(" ")
+                    ),
+                )),
+            },
+            "keepalive" => upstream.keepalive = number(child, &args, warnings),
+            "keepalive_timeout" => upstream.keepalive_timeout = args.first().cloned(),
+            "keepalive_requests" => {
+                upstream.keepalive_requests = number(child, &args, warnings);
+            }
+            "sticky" => upstream.affinity = SessionAffinity::from_directive("sticky", &args),
+            name if METHODS.contains(&name) => {
+                upstream.affinity = SessionAffinity::from_directive(name, &args);
+                upstream.method = Some(
+                    std::iter::once(name.to_string())
+                        .chain(args)
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                );
+            }
+            _ => {}
+        }
+    }
+
+    Some(upstream)
+}
+
+/// First argument as a number, with a warning when it is not one
+fn number<T: std::str::FromStr>(
+    directive: &Directive,
+    args: &[String],
+    warnings: &mut Vec<ExtractWarning>,
+) -> Option<T> {
+    let value = args.first()?;
+    let number = value.parse().ok();
+    if number.is_none() {
+        warnings.push(ExtractWarning::new(
+            directive,
+            format!("{} {value} is not a number; ignored", directive.name()),
+        ));
+    }
+    number
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_extract_upstreams() {
+        let config = parse(
+            r"
+http {
+    upstream app {
+        hash $request_uri consistent;
+        server 10.0.0.1:8080 weight=2;
+        server unix:/run/app.sock;
+        keepalive 16;
+        keepalive_timeout 60s;
+        keepalive_requests 1000;
+    }
+    server { location / { proxy_pass http://app; } }
+}
+stream {
+    upstream dns { server 10.0.0.53:53; }
+}
+",
+        )
+        .unwrap();
+        let upstreams = upstreams(&config).unwrap();
+
+        assert_eq!(upstreams.len(), 2);
+        let app = &upstreams[0];
+        assert_eq!(app.method.as_deref(), Some("hash $request_uri consistent"));
+        // Hashing on the URI spreads the cache, it does not pin clients
+        assert!(app.affinity.is_none());
+        assert_eq!(app.servers.len(), 2);
+        assert_eq!(app.servers[0].weight, Some(2));
+        assert_eq!(app.keepalive_timeout.as_deref(), Some("60s"));
+        assert_eq!(app.keepalive_requests, Some(1000));
+        assert_eq!(app.line, 3);
+        assert!(!app.stream);
+
+        assert_eq!(upstreams[1].name, "dns");
+        assert!(upstreams[1].stream);
+        assert!(!upstreams[1].has_keepalive());
+    }
+}
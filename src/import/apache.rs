@@ -0,0 +1,576 @@
+//! Apache httpd configuration import
+
+use super::{block, simple, upstream_name, with_default_port, Backend, Builder};
+use crate::ast::Directive;
+use crate::{Error, Result};
+use std::collections::HashMap;
+
+/// A directive or a `<Section>` of an Apache configuration
+enum Item {
+    Directive {
+        name: String,
+        args: Vec<String>,
+        line: usize,
+    },
+    Section {
+        name: String,
+        args: Vec<String>,
+        line: usize,
+        body: Vec<Item>,
+    },
+}
+
+/// Conditional sections whose contents are imported as if unconditional
+const TRANSPARENT: &[&str] = &["ifdefine", "iffile", "ifmodule", "ifversion"];
+
+/// Proxy directives `ProxyPass` implies in nginx
+const PROXY_IMPLIED: &[&str] = &[
+    "proxypassreverse",
+    "proxypassreversecookiedomain",
+    "proxypassreversecookiepath",
+    "proxyrequests",
+    "sslproxyengine",
+];
+
+pub(super) fn import(source: &str, builder: &mut Builder) -> Result<()> {
+    let items = parse(source)?;
+    // Balancers may be defined after the virtual hosts using them
+    let mut balancers = HashMap::new();
+    collect_balancers(&items, &mut balancers, builder);
+    top_level(&items, &balancers, builder);
+    Ok(())
+}
+
+/// Split the source into logical lines, joining `\` continuations
+fn logical_lines(source: &str) -> Vec<(usize, String)> {
+    let mut lines = Vec::new();
+    let mut pending: Option<(usize, String)> = None;
+    for (i, raw) in source.lines().enumerate() {
+        let text = raw.trim();
+        let (start, mut joined) = pending.take().unwrap_or((i + 1, String::new()));
+        if let Some(head) = text.strip_suffix('\\') {
+            joined.push_str(head);
+            joined.push(' ');
+            pending = Some((start, joined));
+            continue;
+        }
+        joined.push_str(text);
+        let joined = joined.trim();
+        if !joined.is_empty() && !joined.starts_with('#') {
+            lines.push((start, joined.to_string()));
+        }
+    }
+    lines.extend(pending.map(|(line, text)| (line, text.trim().to_string())));
+    lines
+}
+
+/// Split a line into arguments, unquoting `"..."`
+fn words(text: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        let mut word = String::new();
+        if c == '"' {
+            chars.next();
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => word.extend(chars.next()),
+                    '"' => break,
+                    _ => word.push(c),
+                }
+            }
+        } else {
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                word.push(c);
+                chars.next();
+            }
+        }
+        words.push(word);
+    }
+    words
+}
+
+fn parse(source: &str) -> Result<Vec<Item>> {
+    // Open sections: name, arguments, line and the items read so far
+    let mut stack: Vec<(String, Vec<String>, usize, Vec<Item>)> = Vec::new();
+    let mut items = Vec::new();
+    for (line, text) in logical_lines(source) {
+        if let Some(close) = text.strip_prefix("</") {
+            let name = close.trim_end_matches('>').trim();
+            let Some((open, args, start, body)) = stack.pop() else {
+                return Err(Error::parse(
+                    format!("</{name}> without a matching <{name}>"),
+                    line,
+                    1,
+                ));
+            };
+            if !open.eq_ignore_ascii_case(name) {
+                return Err(Error::parse(
+                    format!("</{name}> closes <{open}> opened on line {start}"),
+                    line,
+                    1,
+                ));
+            }
+            let section = Item::Section {
+                name: open,
+                args,
+                line: start,
+                body,
+            };
+            stack
+                .last_mut()
+                .map_or(&mut items, |s| &mut s.3)
+                .push(section);
+        } else if let Some(open) = text.strip_prefix('<') {
+            let Some(inner) = open.strip_suffix('>') else {
+                return Err(Error::parse(
+                    "Section header without a closing '>'",
+                    line,
+                    1,
+                ));
+            };
+            let mut args = words(inner);
+            if args.is_empty() {
+                return Err(Error::parse("Empty section header", line, 1));
+            }
+            let name = args.remove(0);
+            stack.push((name, args, line, Vec::new()));
+        } else {
+            let mut args = words(&text);
+            let name = args.remove(0);
+            let directive = Item::Directive { name, args, line };
+            stack
+                .last_mut()
+                .map_or(&mut items, |s| &mut s.3)
+                .push(directive);
+        }
+    }
+    if let Some((name, _, line, _)) = stack.pop() {
+        return Err(Error::parse(format!("<{name}> is never closed"), line, 1));
+    }
+    Ok(items)
+}
+
+/// `<Proxy balancer://name>` sections as upstreams; the value tells whether
+/// the members are reached over HTTPS
+fn collect_balancers(items: &[Item], balancers: &mut HashMap<String, bool>, builder: &mut Builder) {
+    for item in items {
+        let Item::Section {
+            name,
+            args,
+            line,
+            body,
+        } = item
+        else {
+            continue;
+        };
+        let balancer = args.first().and_then(|url| url.strip_prefix("balancer://"));
+        match balancer {
+            Some(balancer) if name.eq_ignore_ascii_case("proxy") => {
+                let balancer = balancer.trim_end_matches('/');
+                let https = balancer_upstream(balancer, *line, body, builder);
+                balancers.insert(balancer.to_string(), https);
+            }
+            _ => collect_balancers(body, balancers, builder),
+        }
+    }
+}
+
+fn balancer_upstream(name: &str, line: usize, body: &[Item], builder: &mut Builder) -> bool {
+    let mut children = Vec::new();
+    let mut https = false;
+    for item in body {
+        let (directive, args, at) = match item {
+            Item::Directive { name, args, line } => (name.to_ascii_lowercase(), args, *line),
+            Item::Section { name, line, .. } => {
+                builder.warn(*line, format!("Not imported: <{name}>"));
+                continue;
+            }
+        };
+        match directive.as_str() {
+            "balancermember" => {
+                let Some(member) = args.first().and_then(|url| Backend::parse(url)) else {
+                    builder.warn(at, "BalancerMember without a URL is not imported");
+                    continue;
+                };
+                https |= member.scheme == "https";
+                let mut server = vec![with_default_port(
+                    member.authority,
+                    member.scheme == "https",
+                )];
+                for param in &args[1..] {
+                    match param.split_once('=') {
+                        Some(("loadfactor", factor)) => server.push(format!("weight={factor}")),
+                        Some(("status", status)) if status.contains('H') => {
+                            server.push("backup".to_string());
+                        }
+                        Some(("status", status)) if status.contains('D') => {
+                            server.push("down".to_string());
+                        }
+                        _ => builder.warn(at, format!("BalancerMember {param} is not imported")),
+                    }
+                }
+                children.push(simple("server", server, at));
+            }
+            "proxyset" => {
+                for param in args {
+                    match param.split_once('=') {
+                        Some(("lbmethod", "byrequests")) => {}
+                        Some(("lbmethod", "bybusyness")) => {
+                            children.insert(0, simple("least_conn", Vec::new(), at));
+                        }
+                        _ => builder.warn(at, format!("ProxySet {param} is not imported")),
+                    }
+                }
+            }
+            _ => builder.warn(at, format!("Not imported: {}", item_name(item))),
+        }
+    }
+    let name = upstream_name(name);
+    if !builder.has_upstream(&name) {
+        builder
+            .upstreams
+            .push(block("upstream", vec![name], children, line));
+    }
+    https
+}
+
+fn item_name(item: &Item) -> &str {
+    match item {
+        Item::Directive { name, .. } | Item::Section { name, .. } => name,
+    }
+}
+
+fn top_level(items: &[Item], balancers: &HashMap<String, bool>, builder: &mut Builder) {
+    for item in items {
+        match item {
+            Item::Section {
+                name,
+                args,
+                line,
+                body,
+            } => {
+                let lower = name.to_ascii_lowercase();
+                if lower == "virtualhost" {
+                    virtual_host(args, *line, body, balancers, builder);
+                } else if TRANSPARENT.contains(&lower.as_str()) {
+                    top_level(body, balancers, builder);
+                }
+            }
+            Item::Directive { name, line, .. } => {
+                if name.eq_ignore_ascii_case("include")
+                    || name.eq_ignore_ascii_case("includeoptional")
+                {
+                    builder.warn(
+                        *line,
+                        format!("{name} is not followed; import the included files separately"),
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// What a `<VirtualHost>` section translates to
+#[derive(Default)]
+struct VirtualHost {
+    names: Vec<String>,
+    ssl: bool,
+    preserve_host: bool,
+    /// `root`, `index` and certificate directives
+    settings: Vec<Directive>,
+    /// Line, location arguments and `proxy_pass` target
+    proxies: Vec<(usize, Vec<String>, String)>,
+}
+
+fn virtual_host(
+    addresses: &[String],
+    line: usize,
+    body: &[Item],
+    balancers: &HashMap<String, bool>,
+    builder: &mut Builder,
+) {
+    let mut host = VirtualHost::default();
+    host_items(body, &mut host, balancers, builder);
+
+    let mut children = Vec::new();
+    for address in addresses {
+        let (ip, port) = match address.rsplit_once(':') {
+            Some((ip, port)) if !port.contains(']') => (ip, port),
+            _ => (address.as_str(), "80"),
+        };
+        let mut listen = vec![if matches!(ip, "*" | "_default_") {
+            port.to_string()
+        } else {
+            format!("{ip}:{port}")
+        }];
+        if host.ssl {
+            listen.push("ssl".to_string());
+        }
+        children.push(simple("listen", listen, line));
+    }
+    if !host.names.is_empty() {
+        children.push(simple("server_name", host.names, line));
+    }
+    children.append(&mut host.settings);
+    for (at, args, target) in host.proxies {
+        let mut location = vec![simple("proxy_pass", vec![target], at)];
+        if host.preserve_host {
+            location.push(simple(
+                "proxy_set_header",
+                vec!["Host".to_string(), "$host".to_string()],
+                at,
+            ));
+        }
+        children.push(block("location", args, location, at));
+    }
+    builder
+        .servers
+        .push(block("server", Vec::new(), children, line));
+}
+
+fn host_items(
+    items: &[Item],
+    host: &mut VirtualHost,
+    balancers: &HashMap<String, bool>,
+    builder: &mut Builder,
+) {
+    for item in items {
+        match item {
+            Item::Directive { name, args, line } => {
+                host_directive(name, args, *line, host, balancers, builder);
+            }
+            Item::Section {
+                name,
+                args,
+                line,
+                body,
+            } => {
+                let lower = name.to_ascii_lowercase();
+                match lower.as_str() {
+                    "location" | "locationmatch" => {
+                        let mut location = args.clone();
+                        if lower == "locationmatch" {
+                            location.insert(0, "~".to_string());
+                        }
+                        location_section(&location, body, host, balancers, builder);
+                    }
+                    "proxy" if args.first().is_some_and(|a| a.starts_with("balancer://")) => {}
+                    _ if TRANSPARENT.contains(&lower.as_str()) => {
+                        host_items(body, host, balancers, builder);
+                    }
+                    _ => builder.warn(*line, format!("Not imported: <{name}>")),
+                }
+            }
+        }
+    }
+}
+
+fn host_directive(
+    name: &str,
+    args: &[String],
+    line: usize,
+    host: &mut VirtualHost,
+    balancers: &HashMap<String, bool>,
+    builder: &mut Builder,
+) {
+    let lower = name.to_ascii_lowercase();
+    let setting = match lower.as_str() {
+        "servername" => {
+            // ServerName may carry a scheme and port
+            let server_name = args.first().map(|n| {
+                let n = n.split_once("://").map_or(n.as_str(), |(_, n)| n);
+                n.split(':').next().unwrap_or(n).to_string()
+            });
+            host.names.splice(0..0, server_name);
+            return;
+        }
+        "serveralias" => {
+            host.names.extend(args.iter().cloned());
+            return;
+        }
+        "sslengine" => {
+            host.ssl = args.first().is_some_and(|a| a.eq_ignore_ascii_case("on"));
+            return;
+        }
+        "proxypreservehost" => {
+            host.preserve_host = args.first().is_some_and(|a| a.eq_ignore_ascii_case("on"));
+            return;
+        }
+        "proxypass" | "proxypassmatch" if args.len() >= 2 => {
+            let mut location = vec![args[0].clone()];
+            if lower == "proxypassmatch" {
+                location.insert(0, "~".to_string());
+            }
+            if args[1] == "!" {
+                builder.warn(
+                    line,
+                    format!("{name} exclusion of {} is not imported", args[0]),
+                );
+            } else if let Some(target) = proxy_target(&args[1], line, balancers, builder) {
+                host.proxies.push((line, location, target));
+                if args.len() > 2 {
+                    builder.warn(line, format!("{name} parameters are not imported"));
+                }
+            }
+            return;
+        }
+        _ if PROXY_IMPLIED.contains(&lower.as_str()) => return,
+        "documentroot" => "root",
+        "directoryindex" => "index",
+        "sslcertificatefile" => "ssl_certificate",
+        "sslcertificatekeyfile" => "ssl_certificate_key",
+        _ => {
+            builder.warn(line, format!("Not imported: {name}"));
+            return;
+        }
+    };
+    host.settings.push(simple(setting, args.to_vec(), line));
+}
+
+/// A `<Location>` section: only its `ProxyPass` is imported
+fn location_section(
+    location: &[String],
+    body: &[Item],
+    host: &mut VirtualHost,
+    balancers: &HashMap<String, bool>,
+    builder: &mut Builder,
+) {
+    for item in body {
+        match item {
+            Item::Directive { name, args, line } if name.eq_ignore_ascii_case("proxypass") => {
+                if let Some(target) = args
+                    .first()
+                    .and_then(|url| proxy_target(url, *line, balancers, builder))
+                {
+                    host.proxies.push((*line, location.to_vec(), target));
+                }
+            }
+            Item::Directive { name, .. }
+                if PROXY_IMPLIED.contains(&name.to_ascii_lowercase().as_str()) => {}
+            Item::Directive { name, line, .. } | Item::Section { name, line, .. } => {
+                builder.warn(*line, format!("Not imported: {name} in <Location>"));
+            }
+        }
+    }
+}
+
+/// The `proxy_pass` target of a `ProxyPass` URL
+fn proxy_target(
+    url: &str,
+    line: usize,
+    balancers: &HashMap<String, bool>,
+    builder: &mut Builder,
+) -> Option<String> {
+    let Some(backend) = Backend::parse(url) else {
+        builder.warn(line, format!("Proxy target {url} is not a URL"));
+        return None;
+    };
+    match backend.scheme {
+        "http" | "https" => Some(url.to_string()),
+        "balancer" => {
+            let Some(https) = balancers.get(backend.authority) else {
+                builder.warn(line, format!("Balancer {url} is not defined"));
+                return None;
+            };
+            let scheme = if *https { "https" } else { "http" };
+            Some(format!(
+                "{scheme}://{}{}",
+                upstream_name(backend.authority),
+                backend.path
+            ))
+        }
+        scheme => {
+            builder.warn(line, format!("Proxying over {scheme}:// is not imported"));
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::import::{import, ImportFormat};
+
+    #[test]
+    fn test_apache() {
+        let imported = import(
+            r#"
+ServerRoot "/etc/httpd"
+<Proxy "balancer://app">
+    BalancerMember http://10.0.0.1:8080 loadfactor=2
+    BalancerMember http://10.0.0.2:8080 status=+H
+    ProxySet lbmethod=bybusyness
+</Proxy>
+<IfModule mod_ssl.c>
+<VirtualHost 192.0.2.1:443 *:8443>
+    ServerName https://example.com:443
+    ServerAlias www.example.com
+    DocumentRoot "/var/www/example"
+    SSLEngine on
+    SSLCertificateFile /etc/ssl/example.pem
+    ProxyPreserveHost On
+    ProxyPass /api/ balancer://app/ \
+        timeout=5
+    ProxyPassReverse /api/ balancer://app/
+    <LocationMatch "^/ws/">
+        ProxyPass ws://127.0.0.1:9000/
+    </LocationMatch>
+    <Directory /var/www/example>
+        Require all granted
+    </Directory>
+</VirtualHost>
+</IfModule>
+"#,
+            ImportFormat::Apache,
+        )
+        .unwrap();
+
+        assert_eq!(
+            imported.config.to_config_string(),
+            "http {
+    upstream app {
+        least_conn;
+        server 10.0.0.1:8080 weight=2;
+        server 10.0.0.2:8080 backup;
+    }
+    server {
+        listen 192.0.2.1:443 ssl;
+        listen 8443 ssl;
+        server_name example.com www.example.com;
+        root /var/www/example;
+        ssl_certificate /etc/ssl/example.pem;
+        location /api/ {
+            proxy_pass http://app/;
+            proxy_set_header Host $host;
+        }
+    }
+}
+"
+        );
+        let messages: Vec<_> = imported.warnings.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "line 16: ProxyPass parameters are not imported",
+                "line 20: Proxying over ws:// is not imported",
+                "line 22: Not imported: <Directory>",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_apache_errors() {
+        let err = import("<VirtualHost *:80>\n</Location>\n", ImportFormat::Apache).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("closes <VirtualHost> opened on line 1"));
+        assert!(import("<VirtualHost *:80>\n", ImportFormat::Apache).is_err());
+    }
+}
@@ -0,0 +1,578 @@
+//! Caddyfile import
+
+use super::{block, simple, upstream_name, with_default_port, Builder};
+use crate::ast::Directive;
+use crate::{Error, Result};
+use std::collections::HashMap;
+
+/// A Caddyfile line: its arguments and the block it opens, if any
+struct Node {
+    args: Vec<String>,
+    line: usize,
+    block: Option<Vec<Node>>,
+}
+
+pub(super) fn import(source: &str, builder: &mut Builder) -> Result<()> {
+    let mut lines = lines(source).into_iter().peekable();
+    let mut nodes = nodes(&mut lines, None)?;
+
+    // Global options
+    if nodes
+        .first()
+        .is_some_and(|n| n.args.is_empty() && n.block.is_some())
+    {
+        nodes.remove(0);
+    }
+    // A single site may leave out the braces
+    if nodes.first().is_some_and(|n| n.block.is_none()) {
+        let first = nodes.remove(0);
+        site(&first.args, first.line, &nodes, builder);
+        return Ok(());
+    }
+    for node in &nodes {
+        let name = node.args.first().map_or("", String::as_str);
+        match &node.block {
+            _ if name.starts_with('(') => {
+                builder.warn(node.line, format!("Snippet {name} is not imported"));
+            }
+            Some(body) => site(&node.args, node.line, body, builder),
+            None => builder.warn(node.line, format!("Not imported: {name}")),
+        }
+    }
+    Ok(())
+}
+
+/// Split the source into lines of tokens, without comments
+fn lines(source: &str) -> Vec<(usize, Vec<String>)> {
+    let mut lines = Vec::new();
+    for (i, text) in source.lines().enumerate() {
+        let mut tokens = Vec::new();
+        let mut chars = text.chars().peekable();
+        while let Some(&c) = chars.peek() {
+            if c.is_whitespace() {
+                chars.next();
+                continue;
+            }
+            if c == '#' {
+                break;
+            }
+            let mut token = String::new();
+            if c == '"' || c == '`' {
+                chars.next();
+                while let Some(next) = chars.next() {
+                    match next {
+                        '\\' if c == '"' => token.extend(chars.next()),
+                        _ if next == c => break,
+                        _ => token.push(next),
+                    }
+                }
+            } else {
+                while let Some(&next) = chars.peek() {
+                    if next.is_whitespace() {
+                        break;
+                    }
+                    token.push(next);
+                    chars.next();
+                }
+            }
+            tokens.push(token);
+        }
+        if !tokens.is_empty() {
+            lines.push((i + 1, tokens));
+        }
+    }
+    lines
+}
+
+/// Read nodes up to the `}` closing the block opened on `open`, or to the
+/// end of the source at the top level
+fn nodes<I>(lines: &mut std::iter::Peekable<I>, open: Option<usize>) -> Result<Vec<Node>>
+where
+    I: Iterator<Item = (usize, Vec<String>)>,
+{
+    let mut nodes = Vec::new();
+    while let Some((line, mut args)) = lines.next() {
+        if args == ["}"] {
+            return if open.is_some() {
+                Ok(nodes)
+            } else {
+                Err(Error::parse("Unexpected '}'", line, 1))
+            };
+        }
+        let block = if args.last().is_some_and(|a| a == "{") {
+            args.pop();
+            Some(self::nodes(lines, Some(line))?)
+        } else {
+            None
+        };
+        nodes.push(Node { args, line, block });
+    }
+    match open {
+        Some(line) => Err(Error::parse("Block is never closed", line, 1)),
+        None => Ok(nodes),
+    }
+}
+
+/// What a site block translates to
+#[derive(Default)]
+struct Site {
+    /// Host names, or the listener port when there are none
+    label: String,
+    /// Named matchers as location arguments
+    matchers: HashMap<String, Vec<String>>,
+    /// `root`, `try_files` and certificate directives of the server
+    settings: Vec<Directive>,
+    /// Location arguments, line and directives
+    locations: Vec<(Vec<String>, usize, Vec<Directive>)>,
+}
+
+/// Where a directive applies
+#[derive(Clone)]
+enum Scope {
+    Server,
+    /// Location arguments, and whether the matched prefix is stripped
+    /// (`handle_path`)
+    Location(Vec<String>, bool),
+}
+
+impl Site {
+    fn location(&mut self, args: &[String], line: usize) -> &mut Vec<Directive> {
+        let i = if let Some(i) = self.locations.iter().position(|(a, _, _)| a == args) {
+            i
+        } else {
+            self.locations.push((args.to_vec(), line, Vec::new()));
+            self.locations.len() - 1
+        };
+        &mut self.locations[i].2
+    }
+
+    fn add(&mut self, scope: &Scope, directive: Directive, line: usize) {
+        match scope {
+            Scope::Server => self.settings.push(directive),
+            Scope::Location(args, _) => self.location(args, line).push(directive),
+        }
+    }
+}
+
+fn site(addresses: &[String], line: usize, body: &[Node], builder: &mut Builder) {
+    let mut listens: Vec<Vec<String>> = Vec::new();
+    let mut names: Vec<String> = Vec::new();
+    for address in addresses.iter().flat_map(|a| a.split(',')) {
+        if address.is_empty() {
+            continue;
+        }
+        let (scheme, rest) = address
+            .split_once("://")
+            .map_or(("", address), |(s, r)| (s, r));
+        let (authority, path) = rest.find('/').map_or((rest, ""), |i| rest.split_at(i));
+        if !path.is_empty() {
+            builder.warn(
+                line,
+                format!("The path of site address {address} is not imported"),
+            );
+        }
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) if !port.contains(']') => (host, port.parse::<u16>().ok()),
+            _ => (authority, None),
+        };
+        let port = port.unwrap_or(match scheme {
+            "http" => 80,
+            "https" => 443,
+            _ if host.is_empty() => 80,
+            _ => 443,
+        });
+        // Caddy serves HTTPS for host names unless told otherwise
+        let ssl = match scheme {
+            "http" => false,
+            "https" => true,
+            _ => port == 443 || (!host.is_empty() && port != 80),
+        };
+        let mut listen = vec![port.to_string()];
+        if ssl {
+            listen.push("ssl".to_string());
+        }
+        if !listens.contains(&listen) {
+            listens.push(listen);
+        }
+        if !host.is_empty() && !names.iter().any(|n| n == host) {
+            names.push(host.to_string());
+        }
+    }
+
+    let mut site = Site {
+        label: names
+            .first()
+            .cloned()
+            .unwrap_or_else(|| format!("port_{}", listens.first().map_or("80", |l| l[0].as_str()))),
+        ..Site::default()
+    };
+    for node in body {
+        if let Some(name) = node.args.first().filter(|a| a.starts_with('@')) {
+            named_matcher(name, node, &mut site, builder);
+        }
+    }
+    for node in body {
+        directive(node, &Scope::Server, &mut site, builder);
+    }
+
+    let mut children: Vec<Directive> = listens
+        .into_iter()
+        .map(|listen| simple("listen", listen, line))
+        .collect();
+    if !names.is_empty() {
+        children.push(simple("server_name", names, line));
+    }
+    children.append(&mut site.settings);
+    for (args, at, location) in site.locations {
+        children.push(block("location", args, location, at));
+    }
+    builder
+        .servers
+        .push(block("server", Vec::new(), children, line));
+}
+
+/// `@name path ...` and `@name path_regexp ...`, inline or as a block of
+/// one matcher
+fn named_matcher(name: &str, node: &Node, site: &mut Site, builder: &mut Builder) {
+    let (line, args) = match &node.block {
+        Some(block) if block.len() == 1 => (block[0].line, block[0].args.as_slice()),
+        Some(_) => (node.line, &[][..]),
+        None => (node.line, &node.args[1..]),
+    };
+    let location = match args {
+        [kind, regexp] | [kind, _, regexp] if kind == "path_regexp" => {
+            Some(vec!["~".to_string(), regexp.clone()])
+        }
+        [kind, path] if kind == "path" => path_location(path),
+        _ => None,
+    };
+    match location {
+        Some(location) => {
+            site.matchers.insert(name.to_string(), location);
+        }
+        None => builder.warn(line, format!("Matcher {name} is not imported")),
+    }
+}
+
+/// Location arguments for a path matcher
+fn path_location(path: &str) -> Option<Vec<String>> {
+    match path.strip_suffix('*') {
+        Some(prefix) if !prefix.contains('*') => Some(vec![prefix.to_string()]),
+        Some(_) => None,
+        None if path.contains('*') => None,
+        None => Some(vec!["=".to_string(), path.to_string()]),
+    }
+}
+
+/// The scope of a directive with its matcher, if any, consumed from `args`
+fn scoped<'a>(
+    args: &'a [String],
+    scope: &Scope,
+    line: usize,
+    site: &Site,
+    builder: &mut Builder,
+) -> Option<(Scope, &'a [String])> {
+    let Some(token) = args.first() else {
+        return Some((scope.clone(), args));
+    };
+    let location = if token == "*" {
+        return Some((scope.clone(), &args[1..]));
+    } else if token.starts_with('/') {
+        path_location(token)
+    } else if token.starts_with('@') {
+        site.matchers.get(token).cloned()
+    } else {
+        return Some((scope.clone(), args));
+    };
+    let Some(location) = location else {
+        builder.warn(line, format!("Matcher {token} is not imported"));
+        return None;
+    };
+    if let Scope::Location(..) = scope {
+        builder.warn(line, format!("Nested matcher {token} is not imported"));
+        return None;
+    }
+    Some((Scope::Location(location, false), &args[1..]))
+}
+
+fn directive(node: &Node, scope: &Scope, site: &mut Site, builder: &mut Builder) {
+    let Some((name, args)) = node.args.split_first() else {
+        return;
+    };
+    let line = node.line;
+    if name.starts_with('@') {
+        return;
+    }
+    if name == "tls" {
+        match (args, &node.block) {
+            ([cert, key], None) => {
+                site.settings
+                    .push(simple("ssl_certificate", vec![cert.clone()], line));
+                site.settings
+                    .push(simple("ssl_certificate_key", vec![key.clone()], line));
+            }
+            ([_], None) => {}
+            _ => builder.warn(line, "tls options are not imported"),
+        }
+        return;
+    }
+    let Some((scope, args)) = scoped(args, scope, line, site, builder) else {
+        return;
+    };
+    match name.as_str() {
+        "root" => {
+            site.add(&scope, simple("root", args.to_vec(), line), line);
+        }
+        "try_files" => {
+            let mut files: Vec<String> = args.iter().map(|a| variables(a)).collect();
+            if files.len() == 1 {
+                files.push("=404".to_string());
+            }
+            site.add(&scope, simple("try_files", files, line), line);
+        }
+        "file_server" => {
+            if args.iter().any(|a| a == "browse") || node.block.is_some() {
+                builder.warn(line, "file_server options are not imported");
+            }
+            if let Scope::Location(location, _) = &scope {
+                site.location(location, line);
+            }
+        }
+        "reverse_proxy" => reverse_proxy(node, args, &scope, site, builder),
+        "handle" | "route" | "handle_path" => {
+            let scope = match scope {
+                Scope::Server => Scope::Location(vec!["/".to_string()], false),
+                Scope::Location(location, _) => Scope::Location(location, name == "handle_path"),
+            };
+            for child in node.block.iter().flatten() {
+                directive(child, &scope, site, builder);
+            }
+        }
+        _ => builder.warn(line, format!("Not imported: {name}")),
+    }
+}
+
+fn reverse_proxy(
+    node: &Node,
+    args: &[String],
+    scope: &Scope,
+    site: &mut Site,
+    builder: &mut Builder,
+) {
+    let line = node.line;
+    let mut backends: Vec<&str> = args.iter().map(String::as_str).collect();
+    let mut method: Option<&[&str]> = None;
+    let mut headers = Vec::new();
+    for option in node.block.iter().flatten() {
+        match option.args.split_first() {
+            Some((to, rest)) if to == "to" => backends.extend(rest.iter().map(String::as_str)),
+            Some((policy, [value])) if policy == "lb_policy" => {
+                method = match value.as_str() {
+                    "round_robin" => None,
+                    "least_conn" => Some(&["least_conn"][..]),
+                    "ip_hash" | "client_ip_hash" => Some(&["ip_hash"][..]),
+                    "random" => Some(&["random"][..]),
+                    "uri_hash" => Some(&["hash", "$request_uri", "consistent"][..]),
+                    _ => {
+                        builder.warn(option.line, format!("lb_policy {value} is not imported"));
+                        None
+                    }
+                };
+            }
+            Some((header, [field, value])) if header == "header_up" => {
+                headers.push(simple(
+                    "proxy_set_header",
+                    vec![field.clone(), variables(value)],
+                    option.line,
+                ));
+            }
+            _ => builder.warn(
+                option.line,
+                format!("reverse_proxy {} is not imported", option.args.join(" ")),
+            ),
+        }
+    }
+
+    let mut https = false;
+    let mut servers = Vec::new();
+    for backend in backends {
+        let (scheme, authority) = backend.split_once("://").unwrap_or(("http", backend));
+        match scheme {
+            "http" | "https" => https |= scheme == "https",
+            _ => {
+                builder.warn(line, format!("Proxying over {scheme}:// is not imported"));
+                continue;
+            }
+        }
+        // `:8080` is a port on the local host
+        let authority = if authority.starts_with(':') {
+            format!("localhost{authority}")
+        } else {
+            authority.to_string()
+        };
+        servers.push(authority);
+    }
+    let scheme = if https { "https" } else { "http" };
+    let target = match servers.as_slice() {
+        [] => return,
+        [server] if method.is_none() => server.clone(),
+        _ => {
+            let mut name = upstream_name(&site.label);
+            let base = name.clone();
+            let mut n = 1;
+            while builder.has_upstream(&name) {
+                n += 1;
+                name = format!("{base}_{n}");
+            }
+            let mut upstream = Vec::new();
+            if let Some((method, params)) = method.and_then(<[&str]>::split_first) {
+                let params = params.iter().map(ToString::to_string).collect();
+                upstream.push(simple(method, params, line));
+            }
+            upstream.extend(
+                servers
+                    .iter()
+                    .map(|s| simple("server", vec![with_default_port(s, https)], line)),
+            );
+            builder
+                .upstreams
+                .push(block("upstream", vec![name.clone()], upstream, line));
+            name
+        }
+    };
+
+    let (location, strip) = match scope {
+        Scope::Server => (vec!["/".to_string()], false),
+        Scope::Location(location, strip) => (location.clone(), *strip),
+    };
+    let uri = if strip { "/" } else { "" };
+    let children = site.location(&location, line);
+    children.push(simple(
+        "proxy_pass",
+        vec![format!("{scheme}://{target}{uri}")],
+        line,
+    ));
+    children.append(&mut headers);
+}
+
+/// Caddy placeholders as nginx variables
+fn variables(value: &str) -> String {
+    value
+        .replace("{http.request.host}", "$host")
+        .replace("{host}", "$host")
+        .replace("{remote_host}", "$remote_addr")
+        .replace("{scheme}", "$scheme")
+        .replace("{uri}", "$request_uri")
+        .replace("{path}", "$uri")
+        .replace("{query}", "$args")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::import::{import, ImportFormat};
+
+    #[test]
+    fn test_caddyfile() {
+        let imported = import(
+            r"
+{
+    email admin@example.com
+}
+
+(common) {
+    encode gzip
+}
+
+example.com, www.example.com {
+    root * /srv/www
+    tls /etc/ssl/example.pem /etc/ssl/example.key
+    @legacy path_regexp ^/v[12]/
+    handle_path /api/* {
+        reverse_proxy 10.0.0.1:8080 10.0.0.2:8080 {
+            lb_policy least_conn
+            header_up Host {host}
+        }
+    }
+    reverse_proxy @legacy :9000
+    handle {
+        try_files {path} /index.html
+        file_server
+    }
+    encode zstd gzip
+}
+
+http://static.example.com:8080 {
+    root * /srv/static
+    file_server browse
+}
+",
+            ImportFormat::Caddy,
+        )
+        .unwrap();
+
+        assert_eq!(
+            imported.config.to_config_string(),
+            "http {
+    upstream example.com {
+        least_conn;
+        server 10.0.0.1:8080;
+        server 10.0.0.2:8080;
+    }
+    server {
+        listen 443 ssl;
+        server_name example.com www.example.com;
+        root /srv/www;
+        ssl_certificate /etc/ssl/example.pem;
+        ssl_certificate_key /etc/ssl/example.key;
+        location /api/ {
+            proxy_pass http://example.com/;
+            proxy_set_header Host $host;
+        }
+        location ~ ^/v[12]/ {
+            proxy_pass http://localhost:9000;
+        }
+        location / {
+            try_files $uri /index.html;
+        }
+    }
+    server {
+        listen 8080;
+        server_name static.example.com;
+        root /srv/static;
+    }
+}
+"
+        );
+        let messages: Vec<_> = imported.warnings.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "line 6: Snippet (common) is not imported",
+                "line 25: Not imported: encode",
+                "line 30: file_server options are not imported",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_caddyfile_without_braces() {
+        let imported = import(
+            ":8080\nreverse_proxy /api/* backend:3000\n",
+            ImportFormat::Caddy,
+        )
+        .unwrap();
+        assert_eq!(
+            imported.config.to_config_string(),
+            "http {
+    server {
+        listen 8080;
+        location /api/ {
+            proxy_pass http://backend:3000;
+        }
+    }
+}
+"
+        );
+        assert!(import("example.com {\n    root * /srv\n", ImportFormat::Caddy).is_err());
+    }
+}
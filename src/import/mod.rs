@@ -0,0 +1,254 @@
+//! Import from other web servers
+//!
+//! [`import`] reads an Apache httpd configuration or a Caddyfile and builds
+//! the nginx configuration it corresponds to, so the extraction, diff and
+//! report tooling can compare servers across web servers during a
+//! migration. The importers cover the concepts the model is built from:
+//!
+//! | Format | Imported |
+//! |--------|----------|
+//! | [`Apache`](ImportFormat::Apache) | `<VirtualHost>` addresses, `ServerName`/`ServerAlias`, `DocumentRoot`, `DirectoryIndex`, `SSLEngine` and certificates, `ProxyPass`/`ProxyPassMatch`, `<Location>` proxies, `<Proxy balancer://>` members |
+//! | [`Caddy`](ImportFormat::Caddy) | site addresses, `root`, `try_files`, `reverse_proxy` with `lb_policy`, `tls` certificates, `handle`/`handle_path`/`route` blocks, `path` and `path_regexp` matchers |
+//!
+//! Everything else is reported as an [`ImportWarning`] instead of being
+//! dropped silently.
+//!
+//! # Examples
+//!
+//! ```
+//! use nginx_discovery::{extract, import::{import, ImportFormat}};
+//!
+//! let imported = import(r"
+//! <VirtualHost *:80>
+//!     ServerName example.com
+//!     DocumentRoot /var/www/example
+//!     ProxyPass /api/ http://127.0.0.1:8080/
+//!     Redirect /old /new
+//! </VirtualHost>
+//! ", ImportFormat::Apache)?;
+//!
+//! let servers = extract::servers(&imported.config)?;
+//! assert_eq!(servers[0].server_names, ["example.com"]);
+//! assert_eq!(servers[0].listen[0].port, 80);
+//! assert_eq!(servers[0].locations[0].proxy_pass.as_deref(), Some("http://127.0.0.1:8080/"));
+//! assert_eq!(imported.warnings[0].to_string(), "line 6: Not imported: Redirect");
+//! # Ok::<(), nginx_discovery::Error>(())
+//! ```
+
+mod apache;
+mod caddy;
+
+use crate::ast::{Config, Directive, Span};
+use crate::{Error, Result};
+use std::fmt;
+use std::str::FromStr;
+
+/// Configuration format to import from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum ImportFormat {
+    /// Apache httpd configuration (`httpd.conf`, `sites-enabled/*.conf`)
+    Apache,
+    /// Caddyfile
+    Caddy,
+}
+
+impl ImportFormat {
+    /// Every format
+    pub const ALL: [Self; 2] = [Self::Apache, Self::Caddy];
+}
+
+impl fmt::Display for ImportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Apache => "apache",
+            Self::Caddy => "caddy",
+        })
+    }
+}
+
+impl FromStr for ImportFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "apache" | "httpd" => Ok(Self::Apache),
+            "caddy" | "caddyfile" => Ok(Self::Caddy),
+            _ => Err(Error::InvalidInput(format!(
+                "unknown import format '{s}': expected apache or caddy"
+            ))),
+        }
+    }
+}
+
+/// A construct of the source configuration that was not imported
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ImportWarning {
+    /// Line in the source configuration
+    pub line: usize,
+
+    /// What was not imported and why
+    pub message: String,
+}
+
+impl ImportWarning {
+    fn new(line: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ImportWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// Result of [`import`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Import {
+    /// Format of the source configuration
+    pub format: ImportFormat,
+
+    /// The equivalent nginx configuration: an `http` block with the
+    /// upstreams and servers. Directive spans carry the source lines.
+    pub config: Config,
+
+    /// Constructs that were not imported, in file order
+    pub warnings: Vec<ImportWarning>,
+}
+
+/// Build the nginx configuration equivalent to another server's
+/// configuration
+///
+/// See the [module documentation](self).
+///
+/// # Errors
+///
+/// Returns [`Error::Parse`] when the source is not well formed: an
+/// unclosed or unbalanced section or block.
+pub fn import(source: &str, format: ImportFormat) -> Result<Import> {
+    let mut builder = Builder::default();
+    match format {
+        ImportFormat::Apache => apache::import(source, &mut builder)?,
+        ImportFormat::Caddy => caddy::import(source, &mut builder)?,
+    }
+    Ok(builder.finish(format))
+}
+
+/// The nginx configuration being built
+#[derive(Default)]
+struct Builder {
+    upstreams: Vec<Directive>,
+    servers: Vec<Directive>,
+    warnings: Vec<ImportWarning>,
+}
+
+impl Builder {
+    fn warn(&mut self, line: usize, message: impl Into<String>) {
+        self.warnings.push(ImportWarning::new(line, message));
+    }
+
+    fn has_upstream(&self, name: &str) -> bool {
+        self.upstreams
+            .iter()
+            .any(|u| u.first_arg().as_deref() == Some(name))
+    }
+
+    fn finish(mut self, format: ImportFormat) -> Import {
+        self.warnings.sort_by_key(|w| w.line);
+        let mut http = self.upstreams;
+        http.append(&mut self.servers);
+        Import {
+            format,
+            config: Config::with_directives(vec![block("http", Vec::new(), http, 1)]),
+            warnings: self.warnings,
+        }
+    }
+}
+
+fn simple(name: &str, args: Vec<String>, line: usize) -> Directive {
+    Directive::simple_with_span(name, args, Span::at(0, line, 1))
+}
+
+fn block(name: &str, args: Vec<String>, children: Vec<Directive>, line: usize) -> Directive {
+    Directive::block_with_span(name, args, children, Span::at(0, line, 1))
+}
+
+/// A backend URL split into scheme, authority and path
+struct Backend<'a> {
+    scheme: &'a str,
+    authority: &'a str,
+    path: &'a str,
+}
+
+impl<'a> Backend<'a> {
+    fn parse(url: &'a str) -> Option<Self> {
+        let (scheme, rest) = url.split_once("://")?;
+        let (authority, path) = rest.find('/').map_or((rest, ""), |i| rest.split_at(i));
+        (!authority.is_empty()).then_some(Self {
+            scheme,
+            authority,
+            path,
+        })
+    }
+}
+
+/// `host:port`, adding the scheme's default port
+fn with_default_port(authority: &str, https: bool) -> String {
+    let port = authority
+        .rsplit_once(':')
+        .is_some_and(|(host, port)| !host.ends_with(':') && port.parse::<u16>().is_ok());
+    if port {
+        authority.to_string()
+    } else {
+        format!("{authority}:{}", if https { 443 } else { 80 })
+    }
+}
+
+/// An nginx upstream name for a host name, without the characters nginx
+/// would read as syntax
+fn upstream_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_and_helpers() {
+        assert_eq!(
+            "httpd".parse::<ImportFormat>().unwrap(),
+            ImportFormat::Apache
+        );
+        assert_eq!(
+            "Caddyfile".parse::<ImportFormat>().unwrap(),
+            ImportFormat::Caddy
+        );
+        assert!("iis".parse::<ImportFormat>().is_err());
+
+        let backend = Backend::parse("https://app.internal:8443/v1/").unwrap();
+        assert_eq!(
+            (backend.scheme, backend.authority, backend.path),
+            ("https", "app.internal:8443", "/v1/")
+        );
+        assert!(Backend::parse("/local/path").is_none());
+        assert_eq!(with_default_port("app", true), "app:443");
+        assert_eq!(with_default_port("[::1]:8080", false), "[::1]:8080");
+        assert_eq!(upstream_name("*.example.com"), "_.example.com");
+    }
+}
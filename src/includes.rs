@@ -0,0 +1,463 @@
+//! Include resolution and the include graph
+//!
+//! [`IncludeGraph::build`] starts at a main configuration file, follows
+//! its `include` directives through every file they load, and records
+//! which file includes which. Globs are expanded in their last path
+//! component, like `conf.d/*.conf`; patterns nginx would resolve that this
+//! crate cannot (variables, wildcards in directory names, character
+//! classes) are kept as unresolved edges.
+//!
+//! The graph exports to DOT ([`to_dot`](IncludeGraph::to_dot)) and, with
+//! the `serde` feature, to JSON. Include cycles and missing files are
+//! reported as lint [`Finding`]s by [`findings`](IncludeGraph::findings).
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use nginx_discovery::includes::IncludeGraph;
+//!
+//! let graph = IncludeGraph::build("/etc/nginx/nginx.conf")?;
+//! println!("{}", graph.to_dot());
+//! for finding in graph.findings() {
+//!     println!("{finding}");
+//! }
+//! # Ok::<(), nginx_discovery::Error>(())
+//! ```
+
+use crate::analyze::paths::{is_glob, wildcard_match};
+use crate::analyze::PathStatus;
+use crate::ast::{Span, Value};
+use crate::error::Result;
+use crate::lint::{walk, Finding, Severity};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+/// A file in the include graph
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IncludeFile {
+    /// Path of the file
+    pub path: PathBuf,
+
+    /// Why the file could not be read or parsed; its includes are then
+    /// unknown
+    pub error: Option<String>,
+}
+
+/// One `include` directive
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IncludeEdge {
+    /// File holding the directive
+    pub from: PathBuf,
+
+    /// Pattern as written
+    pub pattern: String,
+
+    /// Location of the directive in [`from`](Self::from)
+    pub span: Span,
+
+    /// Files the pattern resolves to, sorted
+    pub targets: Vec<PathBuf>,
+
+    /// [`Exists`](PathStatus::Exists) when resolved,
+    /// [`Missing`](PathStatus::Missing) for a plain path that does not
+    /// exist, [`NoMatches`](PathStatus::NoMatches) for a glob matching
+    /// nothing, [`Unchecked`](PathStatus::Unchecked) when the pattern could
+    /// not be resolved
+    pub status: PathStatus,
+}
+
+/// Which file includes which, starting at a main configuration file
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IncludeGraph {
+    /// The main configuration file
+    pub root: PathBuf,
+
+    /// Directory relative include paths are resolved against
+    pub base_dir: PathBuf,
+
+    /// Every file reached, root first, in breadth-first order
+    pub files: Vec<IncludeFile>,
+
+    /// Every `include` directive of those files
+    pub edges: Vec<IncludeEdge>,
+}
+
+impl IncludeGraph {
+    /// Build the graph of `root`, resolving relative includes against the
+    /// directory of `root` as nginx does for its default prefix
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `root` cannot be read. Other files that cannot
+    /// be read or parsed are recorded in [`IncludeFile::error`].
+    pub fn build(root: impl AsRef<Path>) -> Result<Self> {
+        let root = root.as_ref();
+        let base_dir = root.parent().unwrap_or(Path::new(".")).to_path_buf();
+        Self::build_with_base(root, base_dir)
+    }
+
+    /// Build the graph of `root`, resolving relative includes against
+    /// `base_dir`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `root` cannot be read.
+    pub fn build_with_base(root: impl AsRef<Path>, base_dir: impl Into<PathBuf>) -> Result<Self> {
+        let root = root.as_ref().to_path_buf();
+        std::fs::metadata(&root)?;
+
+        let mut graph = Self {
+            root: root.clone(),
+            base_dir: base_dir.into(),
+            files: Vec::new(),
+            edges: Vec::new(),
+        };
+        let mut seen = HashSet::from([root.clone()]);
+        let mut queue = VecDeque::from([root]);
+
+        while let Some(path) = queue.pop_front() {
+            let config = match crate::parser::parse_file(&path) {
+                Ok(config) => config,
+                Err(error) => {
+                    graph.files.push(IncludeFile {
+                        path,
+                        error: Some(error.to_string()),
+                    });
+                    continue;
+                }
+            };
+
+            walk(&config, &mut |directive, _| {
+                if directive.name() != "include" {
+                    return;
+                }
+                let Some(pattern) = directive.args().first().map(Value::to_config_string) else {
+                    return;
+                };
+                let (targets, status) = graph.resolve(&pattern);
+                for target in &targets {
+                    if seen.insert(target.clone()) {
+                        queue.push_back(target.clone());
+                    }
+                }
+                graph.edges.push(IncludeEdge {
+                    from: path.clone(),
+                    pattern,
+                    span: directive.span,
+                    targets,
+                    status,
+                });
+            });
+            graph.files.push(IncludeFile { path, error: None });
+        }
+
+        Ok(graph)
+    }
+
+    fn resolve(&self, pattern: &str) -> (Vec<PathBuf>, PathStatus) {
+        let unquoted = pattern.trim_matches(['"', '\'']);
+        if unquoted.contains('$') {
+            return (Vec::new(), PathStatus::Unchecked);
+        }
+        let path = crate::paths::resolve(&self.base_dir, unquoted);
+        if !is_glob(unquoted) {
+            return if path.is_file() {
+                (vec![path], PathStatus::Exists)
+            } else {
+                (Vec::new(), PathStatus::Missing)
+            };
+        }
+
+        let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+            return (Vec::new(), PathStatus::Unchecked);
+        };
+        let name = name.to_string_lossy();
+        if is_glob(&dir.to_string_lossy()) || name.contains('[') {
+            return (Vec::new(), PathStatus::Unchecked);
+        }
+
+        let mut targets: Vec<PathBuf> = std::fs::read_dir(dir)
+            .map(|entries| {
+                entries
+                    .filter_map(std::result::Result::ok)
+                    .filter(|entry| wildcard_match(&name, &entry.file_name().to_string_lossy()))
+                    .map(|entry| entry.path())
+                    .filter(|path| path.is_file())
+                    .collect()
+            })
+            .unwrap_or_default();
+        targets.sort();
+        let status = if targets.is_empty() {
+            PathStatus::NoMatches
+        } else {
+            PathStatus::Exists
+        };
+        (targets, status)
+    }
+
+    /// Include cycles, each as the files along it with the first file
+    /// repeated at the end
+    #[must_use]
+    pub fn cycles(&self) -> Vec<Vec<PathBuf>> {
+        let mut children: HashMap<&Path, Vec<&Path>> = HashMap::new();
+        for edge in &self.edges {
+            children
+                .entry(edge.from.as_path())
+                .or_default()
+                .extend(edge.targets.iter().map(PathBuf::as_path));
+        }
+
+        let mut cycles = Vec::new();
+        let mut done = HashSet::new();
+        let mut stack = Vec::new();
+        for file in &self.files {
+            find_cycles(&file.path, &children, &mut stack, &mut done, &mut cycles);
+        }
+        cycles
+    }
+
+    /// Cycles (`include_cycle`), missing files (`missing_include`), globs
+    /// matching nothing and unresolved patterns (`unresolved_include`), and
+    /// files that do not parse (`include_parse_error`)
+    #[must_use]
+    pub fn findings(&self) -> Vec<Finding> {
+        let mut findings = Vec::new();
+
+        for file in &self.files {
+            if let Some(error) = &file.error {
+                findings.push(
+                    Finding::new(
+                        "include_parse_error",
+                        Severity::Error,
+                        format!("{} cannot be loaded: {error}", file.path.display()),
+                    )
+                    .with_file(&file.path),
+                );
+            }
+        }
+
+        for edge in &self.edges {
+            let (rule, severity, message) = match edge.status {
+                PathStatus::Exists | PathStatus::WrongType => continue,
+                PathStatus::Missing => (
+                    "missing_include",
+                    Severity::Error,
+                    format!(
+                        "\"include {}\" refers to a file that does not exist",
+                        edge.pattern
+                    ),
+                ),
+                PathStatus::NoMatches => (
+                    "unresolved_include",
+                    Severity::Info,
+                    format!("\"include {}\" matches no files", edge.pattern),
+                ),
+                PathStatus::Unchecked => (
+                    "unresolved_include",
+                    Severity::Info,
+                    format!("\"include {}\" cannot be resolved statically", edge.pattern),
+                ),
+            };
+            let mut finding = Finding::new(rule, severity, message)
+                .with_span(edge.span)
+                .with_file(&edge.from);
+            finding.directive = Some("include".to_string());
+            findings.push(finding);
+        }
+
+        for cycle in self.cycles() {
+            let chain: Vec<String> = cycle.iter().map(|p| p.display().to_string()).collect();
+            findings.push(
+                Finding::new(
+                    "include_cycle",
+                    Severity::Error,
+                    format!("Include cycle: {}", chain.join(" -> ")),
+                )
+                .with_file(&cycle[0])
+                .with_help(
+                    "Remove one of the includes; nginx fails to start on recursive includes",
+                ),
+            );
+        }
+
+        findings
+    }
+
+    /// The graph in Graphviz DOT format
+    ///
+    /// Files are boxes, files that fail to load are red. Unresolved
+    /// patterns are dashed ellipses labeled with the pattern, red when the
+    /// file is missing.
+    #[must_use]
+    pub fn to_dot(&self) -> String {
+        let label = |path: &Path| {
+            path.strip_prefix(&self.base_dir)
+                .unwrap_or(path)
+                .display()
+                .to_string()
+                .replace('"', "\\\"")
+        };
+
+        let mut dot = String::from("digraph includes {\n    rankdir=LR;\n    node [shape=box];\n");
+        for file in &self.files {
+            let color = if file.error.is_some() {
+                ", color=red"
+            } else {
+                ""
+            };
+            let _ = writeln!(
+                dot,
+                "    \"{}\" [label=\"{}\"{color}];",
+                file.path.display(),
+                label(&file.path)
+            );
+        }
+        for (i, edge) in self.edges.iter().enumerate() {
+            let from = edge.from.display();
+            if edge.targets.is_empty() {
+                let color = if edge.status == PathStatus::Missing {
+                    ", color=red"
+                } else {
+                    ""
+                };
+                let _ = writeln!(
+                    dot,
+                    "    \"unresolved{i}\" [label=\"{}\", shape=ellipse, style=dashed{color}];",
+                    edge.pattern.replace('"', "\\\"")
+                );
+                let _ = writeln!(dot, "    \"{from}\" -> \"unresolved{i}\" [style=dashed];");
+            }
+            for target in &edge.targets {
+                let _ = writeln!(dot, "    \"{from}\" -> \"{}\";", target.display());
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// The graph as pretty-printed JSON
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if serialization fails.
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+fn find_cycles<'a>(
+    path: &'a Path,
+    children: &HashMap<&'a Path, Vec<&'a Path>>,
+    stack: &mut Vec<&'a Path>,
+    done: &mut HashSet<&'a Path>,
+    cycles: &mut Vec<Vec<PathBuf>>,
+) {
+    if let Some(start) = stack.iter().position(|&p| p == path) {
+        let mut cycle: Vec<PathBuf> = stack[start..].iter().map(|p| p.to_path_buf()).collect();
+        cycle.push(path.to_path_buf());
+        cycles.push(cycle);
+        return;
+    }
+    if !done.insert(path) {
+        return;
+    }
+
+    stack.push(path);
+    for &child in children.get(path).into_iter().flatten() {
+        find_cycles(child, children, stack, done, cycles);
+    }
+    stack.pop();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_include_graph() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir(root.join("conf.d")).unwrap();
+        fs::write(
+            root.join("nginx.conf"),
+            "events {}\nhttp {\n    include mime.types;\n    include conf.d/*.conf;\n    include missing.conf;\n    include sites/*.conf;\n    include $dir/x.conf;\n}\n",
+        )
+        .unwrap();
+        fs::write(root.join("mime.types"), "types { text/html html; }").unwrap();
+        fs::write(
+            root.join("conf.d/a.conf"),
+            "server { include snippets.conf; }",
+        )
+        .unwrap();
+        fs::write(root.join("conf.d/b.conf"), "server {").unwrap();
+        fs::write(root.join("snippets.conf"), "include conf.d/a.conf;").unwrap();
+
+        let graph = IncludeGraph::build(root.join("nginx.conf")).unwrap();
+        let files: Vec<_> = graph
+            .files
+            .iter()
+            .map(|f| f.path.strip_prefix(root).unwrap().to_path_buf())
+            .collect();
+        assert_eq!(
+            files,
+            vec![
+                PathBuf::from("nginx.conf"),
+                PathBuf::from("mime.types"),
+                PathBuf::from("conf.d/a.conf"),
+                PathBuf::from("conf.d/b.conf"),
+                PathBuf::from("snippets.conf"),
+            ]
+        );
+        assert!(graph.files[3].error.is_some());
+
+        let statuses: Vec<_> = graph.edges.iter().map(|e| e.status).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                PathStatus::Exists,
+                PathStatus::Exists,
+                PathStatus::Missing,
+                PathStatus::NoMatches,
+                PathStatus::Unchecked,
+                PathStatus::Exists,
+                PathStatus::Exists,
+            ]
+        );
+        assert_eq!(graph.edges[1].targets.len(), 2);
+        assert_eq!(graph.edges[2].span.line, 5);
+
+        let cycles = graph.cycles();
+        assert_eq!(cycles.len(), 1);
+        assert_eq!(cycles[0].len(), 3);
+        assert!(cycles[0][0].ends_with("conf.d/a.conf"));
+
+        let mut rules: Vec<_> = graph.findings().into_iter().map(|f| f.rule).collect();
+        rules.sort();
+        assert_eq!(
+            rules,
+            vec![
+                "include_cycle",
+                "include_parse_error",
+                "missing_include",
+                "unresolved_include",
+                "unresolved_include",
+            ]
+        );
+
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph includes {"));
+        assert!(dot.contains("[label=\"conf.d/b.conf\", color=red]"));
+        assert!(dot.contains("[label=\"missing.conf\", shape=ellipse, style=dashed, color=red]"));
+    }
+
+    #[test]
+    fn test_missing_root() {
+        assert!(IncludeGraph::build("/no/such/nginx.conf").is_err());
+    }
+}
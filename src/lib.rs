@@ -40,9 +40,14 @@
 //!
 //! - `system` (default): System interaction (detect nginx, run nginx -T)
 //! - `serde`: JSON/YAML serialization support
+//! - `export-xml`: XML export with an XSD schema
+//! - `export-binary`: `MessagePack` and CBOR export
 //! - `visitor`: Visitor pattern for AST traversal
-//! - `includes`: Include directive resolution
+//! - `includes`: Include directive resolution and the include graph
 //! - `cli`: Command-line interface (binary only)
+//! - `lsp`: Language Server Protocol mode
+//! - `migrate`: Migration to Caddy, `HAProxy` and nginx Unit configurations
+//! - `import`: Import of Apache httpd and Caddyfile configurations
 //!
 //! ## Examples
 //!
@@ -117,11 +122,27 @@
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
 // Public modules
+pub mod analyze;
 pub mod ast;
+pub mod ci;
+pub mod diff;
+pub mod docs;
+pub mod doctor;
 pub mod error;
 pub mod error_builder;
 pub mod extract;
+
+#[cfg(feature = "includes")]
+#[cfg_attr(docsrs, doc(cfg(feature = "includes")))]
+pub mod includes;
+
+pub mod lint;
+pub mod logs;
 pub mod parser;
+pub mod paths;
+pub mod query;
+pub mod route;
+pub mod scan;
 
 #[cfg(feature = "system")]
 #[cfg_attr(docsrs, doc(cfg(feature = "system")))]
@@ -139,13 +160,27 @@ pub mod export;
 #[cfg(feature = "network")]
 pub mod network;
 
+#[cfg(feature = "lsp")]
+pub mod lsp;
+
+#[cfg(feature = "import")]
+#[cfg_attr(docsrs, doc(cfg(feature = "import")))]
+pub mod import;
+
+#[cfg(feature = "migrate")]
+#[cfg_attr(docsrs, doc(cfg(feature = "migrate")))]
+pub mod migrate;
+
 // High-level API
 mod discovery;
+mod pem;
+mod shared;
 pub use discovery::NginxDiscovery;
+pub use shared::{ReloadEvent, SharedDiscovery};
 
 // Re-exports for convenience
 pub use error::{Error, Result};
-pub use parser::parse;
+pub use parser::{parse, parse_block_body, parse_directive, parse_file, parse_files};
 
 /// Commonly used imports for quick setup
 ///
@@ -0,0 +1,238 @@
+//! Machine-applicable fixes
+//!
+//! A rule can attach a [`Fix`] to its [`Finding`]: one or more edits that
+//! insert, replace or delete directives, each addressed by the span of the
+//! directive it targets. [`apply_fixes`] applies them to a parsed
+//! configuration, so tools can act on findings instead of only reporting
+//! them.
+//!
+//! # Examples
+//!
+//! ```
+//! use nginx_discovery::{lint::{apply_fixes, Linter}, parse};
+//!
+//! let mut config = parse("http { server { listen 80; root /a; root /b; } }")?;
+//! let findings = Linter::with_default_rules().check(&config);
+//!
+//! assert_eq!(apply_fixes(&mut config, &findings), 1);
+//! assert_eq!(
+//!     config.to_config_string(),
+//!     "http {\n    server {\n        listen 80;\n        root /a;\n    }\n}\n"
+//! );
+//! # Ok::<(), nginx_discovery::Error>(())
+//! ```
+
+use super::Finding;
+use crate::ast::{Config, Directive, Span};
+
+/// A single change to the configuration
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case", tag = "kind"))]
+pub enum FixEdit {
+    /// Append `directive` to the block starting at `parent`, or to the top
+    /// level when `parent` is `None`
+    Insert {
+        /// Span of the enclosing block
+        parent: Option<Span>,
+        /// Directive to add
+        directive: Directive,
+    },
+    /// Replace the directive starting at `span`
+    Replace {
+        /// Span of the directive to replace
+        span: Span,
+        /// Replacement directive
+        directive: Directive,
+    },
+    /// Remove the directive starting at `span`, with its block
+    Delete {
+        /// Span of the directive to remove
+        span: Span,
+    },
+}
+
+/// A machine-applicable fix for a finding
+///
+/// The edits are applied together or not at all.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Fix {
+    /// What the fix does, e.g. `Remove the duplicate "root"`
+    pub description: String,
+
+    /// Edits in the order they are applied
+    pub edits: Vec<FixEdit>,
+}
+
+impl Fix {
+    /// Create a fix without edits
+    #[must_use]
+    pub fn new(description: impl Into<String>) -> Self {
+        Self {
+            description: description.into(),
+            edits: Vec::new(),
+        }
+    }
+
+    /// Fix deleting one directive
+    #[must_use]
+    pub fn delete(description: impl Into<String>, directive: &Directive) -> Self {
+        Self::new(description).with_edit(FixEdit::Delete {
+            span: directive.span,
+        })
+    }
+
+    /// Fix replacing one directive
+    #[must_use]
+    pub fn replace(
+        description: impl Into<String>,
+        directive: &Directive,
+        replacement: Directive,
+    ) -> Self {
+        Self::new(description).with_edit(FixEdit::Replace {
+            span: directive.span,
+            directive: replacement,
+        })
+    }
+
+    /// Add an edit, builder style
+    #[must_use]
+    pub fn with_edit(mut self, edit: FixEdit) -> Self {
+        self.edits.push(edit);
+        self
+    }
+
+    /// Apply the edits to `config`
+    ///
+    /// Returns `false`, leaving `config` unchanged, when a target span no
+    /// longer matches a directive (another fix removed or replaced it).
+    pub fn apply(&self, config: &mut Config) -> bool {
+        let mut fixed = config.directives.clone();
+        for edit in &self.edits {
+            if !apply_edit(&mut fixed, edit) {
+                return false;
+            }
+        }
+        config.directives = fixed;
+        true
+    }
+}
+
+fn apply_edit(directives: &mut Vec<Directive>, edit: &FixEdit) -> bool {
+    match edit {
+        FixEdit::Insert {
+            parent: None,
+            directive,
+        } => {
+            directives.push(directive.clone());
+            true
+        }
+        FixEdit::Insert {
+            parent: Some(span),
+            directive,
+        } => with_directive(
+            directives,
+            *span,
+            &mut |siblings, i| match siblings[i].children_mut() {
+                Some(children) => {
+                    children.push(directive.clone());
+                    true
+                }
+                None => false,
+            },
+        ),
+        FixEdit::Replace { span, directive } => {
+            with_directive(directives, *span, &mut |siblings, i| {
+                siblings[i] = directive.clone();
+                true
+            })
+        }
+        FixEdit::Delete { span } => with_directive(directives, *span, &mut |siblings, i| {
+            siblings.remove(i);
+            true
+        }),
+    }
+}
+
+/// Find the directive starting at `span` and call `edit` with its sibling
+/// list and index
+fn with_directive(
+    directives: &mut Vec<Directive>,
+    span: Span,
+    edit: &mut dyn FnMut(&mut Vec<Directive>, usize) -> bool,
+) -> bool {
+    if let Some(i) = directives.iter().position(|d| d.span == span) {
+        return edit(directives, i);
+    }
+    directives.iter_mut().any(|directive| {
+        directive
+            .children_mut()
+            .is_some_and(|children| with_directive(children, span, edit))
+    })
+}
+
+/// Apply the fixes attached to `findings`, in order
+///
+/// A fix whose targets were already removed or replaced by an earlier fix
+/// is skipped. Returns the number of fixes applied.
+pub fn apply_fixes(config: &mut Config, findings: &[Finding]) -> usize {
+    findings
+        .iter()
+        .filter_map(|finding| finding.fix.as_ref())
+        .filter(|fix| fix.apply(config))
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_fix_edits() {
+        let mut config = parse("http {\n  server {\n    listen 80;\n    root /a;\n  }\n}").unwrap();
+        let server = config.directives[0].children().unwrap()[0].clone();
+        let root = server.children().unwrap()[1].clone();
+
+        let fix = Fix::replace(
+            "Use /srv",
+            &root,
+            Directive::simple("root", vec!["/srv".into()]),
+        )
+        .with_edit(FixEdit::Insert {
+            parent: Some(server.span),
+            directive: Directive::simple("index", vec!["index.html".into()]),
+        })
+        .with_edit(FixEdit::Insert {
+            parent: None,
+            directive: Directive::simple("pid", vec!["/run/nginx.pid".into()]),
+        });
+        assert!(fix.apply(&mut config));
+        assert_eq!(
+            config.to_config_string(),
+            "http {\n    server {\n        listen 80;\n        root /srv;\n        index index.html;\n    }\n}\npid /run/nginx.pid;\n"
+        );
+
+        // The replaced directive is gone, so a second fix on it is skipped
+        let before = config.clone();
+        let stale = Fix::delete("Remove root", &root).with_edit(FixEdit::Insert {
+            parent: None,
+            directive: Directive::simple("user", vec!["nginx".into()]),
+        });
+        assert!(!stale.apply(&mut config));
+        assert_eq!(config, before);
+    }
+
+    #[test]
+    fn test_apply_fixes() {
+        let mut config =
+            parse("worker_processes 1;\nworker_processes 2;\nworker_processes 3;").unwrap();
+        let findings = crate::lint::Linter::with_default_rules().check(&config);
+        assert_eq!(findings.len(), 2);
+
+        assert_eq!(apply_fixes(&mut config, &findings), 2);
+        assert_eq!(config.to_config_string(), "worker_processes 1;\n");
+        assert_eq!(apply_fixes(&mut config, &findings), 0);
+    }
+}
@@ -0,0 +1,505 @@
+//! Static analysis of parsed configurations
+//!
+//! A [`Linter`] runs a set of [`Rule`]s over a [`Config`] and collects
+//! [`Finding`]s. Every finding carries the rule that produced it, a
+//! [`Severity`], and the span of the offending directive so tools can point
+//! at the exact line.
+//!
+//! # Examples
+//!
+//! ```
+//! use nginx_discovery::{lint::{Linter, Severity}, parse};
+//!
+//! let config = parse("http { server { listen 80; root /a; root /b; } }")?;
+//! let findings = Linter::with_default_rules().check(&config);
+//!
+//! assert_eq!(findings.len(), 1);
+//! assert_eq!(findings[0].rule, "duplicate_directive");
+//! assert_eq!(findings[0].severity, Severity::Error);
+//! # Ok::<(), nginx_discovery::Error>(())
+//! ```
+
+mod fix;
+pub mod policy;
+mod query;
+pub mod rules;
+
+pub use fix::{apply_fixes, Fix, FixEdit};
+pub use policy::Policy;
+pub use query::QueryRule;
+
+use crate::ast::{Config, ConfigIndex, ConfigPath, Directive, NodeId, Span};
+use crate::docs;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+
+/// How serious a finding is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Severity {
+    /// Worth knowing, nothing to fix.
+    Info,
+    /// Likely a mistake, nginx still starts.
+    Warning,
+    /// nginx will refuse the configuration or misbehave.
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::Info => "info",
+            Self::Warning => "warning",
+            Self::Error => "error",
+        };
+        write!(f, "{s}")
+    }
+}
+
+impl std::str::FromStr for Severity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "info" => Ok(Self::Info),
+            "warning" | "warn" => Ok(Self::Warning),
+            "error" => Ok(Self::Error),
+            _ => Err(format!(
+                "Invalid severity '{s}': expected info, warning or error"
+            )),
+        }
+    }
+}
+
+/// A single problem reported by a lint rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Finding {
+    /// Id of the rule that produced the finding.
+    pub rule: String,
+
+    /// How serious the finding is.
+    pub severity: Severity,
+
+    /// Human-readable description.
+    pub message: String,
+
+    /// Name of the offending directive.
+    pub directive: Option<String>,
+
+    /// Location of the offending directive.
+    pub span: Option<Span>,
+
+    /// File the finding refers to, when known.
+    pub file: Option<PathBuf>,
+
+    /// Suggested fix.
+    pub help: Option<String>,
+
+    /// Link to the documentation of the offending directive.
+    pub doc_url: Option<String>,
+
+    /// Control of the baseline the finding comes from, for
+    /// [`Policy`] findings.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub reference: Option<String>,
+
+    /// Id of the offending directive in a [`ConfigIndex`] of the
+    /// configuration, when the producer had one.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub node: Option<NodeId>,
+
+    /// Context path of the offending directive, e.g.
+    /// `http/server[2]/location[3]`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub path: Option<ConfigPath>,
+
+    /// Machine-applicable fix, see [`apply_fixes`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub fix: Option<Fix>,
+}
+
+impl Finding {
+    /// Create a finding without a location.
+    #[must_use]
+    pub fn new(rule: impl Into<String>, severity: Severity, message: impl Into<String>) -> Self {
+        Self {
+            rule: rule.into(),
+            severity,
+            message: message.into(),
+            directive: None,
+            span: None,
+            file: None,
+            help: None,
+            doc_url: None,
+            reference: None,
+            node: None,
+            path: None,
+            fix: None,
+        }
+    }
+
+    /// Point the finding at a directive.
+    #[must_use]
+    pub fn at(mut self, directive: &Directive) -> Self {
+        self.directive = Some(directive.name().to_string());
+        self.span = Some(directive.span);
+        self
+    }
+
+    /// Point the finding at an indexed directive.
+    #[must_use]
+    pub fn at_node(self, index: &ConfigIndex<'_>, id: NodeId) -> Self {
+        let mut finding = self.at(index.get(id));
+        finding.node = Some(id);
+        finding.path = Some(index.config_path(id));
+        finding
+    }
+
+    /// Point the finding at a directive borrowed from an indexed
+    /// configuration, with its node and path when the index has it.
+    #[must_use]
+    pub fn at_indexed(self, index: &ConfigIndex<'_>, directive: &Directive) -> Self {
+        match index.id_of(directive) {
+            Some(id) => self.at_node(index, id),
+            None => self.at(directive),
+        }
+    }
+
+    /// Set the location explicitly.
+    #[must_use]
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span = Some(span);
+        self
+    }
+
+    /// Set the file the finding refers to.
+    #[must_use]
+    pub fn with_file(mut self, file: impl Into<PathBuf>) -> Self {
+        self.file = Some(file.into());
+        self
+    }
+
+    /// Attach a suggested fix.
+    #[must_use]
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+
+    /// Attach a documentation link.
+    #[must_use]
+    pub fn with_doc_url(mut self, url: impl Into<String>) -> Self {
+        self.doc_url = Some(url.into());
+        self
+    }
+
+    /// Attach a machine-applicable fix.
+    #[must_use]
+    pub fn with_fix(mut self, fix: Fix) -> Self {
+        self.fix = Some(fix);
+        self
+    }
+
+    /// Name the baseline control the finding comes from.
+    #[must_use]
+    pub fn with_reference(mut self, reference: impl Into<String>) -> Self {
+        self.reference = Some(reference.into());
+        self
+    }
+
+    /// Line of the finding, if known.
+    #[must_use]
+    pub fn line(&self) -> Option<usize> {
+        self.span.map(|s| s.line)
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}:", file.display())?;
+        }
+        if let Some(span) = self.span {
+            write!(f, "{}:{}: ", span.line, span.col)?;
+        } else if self.file.is_some() {
+            write!(f, " ")?;
+        }
+        write!(f, "{}[{}]: {}", self.severity, self.rule, self.message)
+    }
+}
+
+/// A static check over a parsed configuration.
+///
+/// Implement this to add custom rules to a [`Linter`].
+pub trait Rule: Send + Sync {
+    /// Stable identifier, used to select or skip the rule.
+    fn id(&self) -> &str;
+
+    /// One-line description shown in rule listings.
+    fn description(&self) -> &str;
+
+    /// Run the rule and return its findings.
+    fn check(&self, config: &Config) -> Vec<Finding>;
+}
+
+/// An ordered set of lint rules.
+#[derive(Default)]
+pub struct Linter {
+    rules: Vec<Box<dyn Rule>>,
+}
+
+impl fmt::Debug for Linter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Linter")
+            .field("rules", &self.names())
+            .finish()
+    }
+}
+
+impl Linter {
+    /// Create a linter with no rules.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a linter with the built-in rules that only look at the
+    /// configuration itself (no filesystem access).
+    #[must_use]
+    pub fn with_default_rules() -> Self {
+        let mut linter = Self::new();
+        for rule in rules::default_rules() {
+            linter.register(rule);
+        }
+        linter
+    }
+
+    /// Add a rule, builder style.
+    #[must_use]
+    pub fn with_rule(mut self, rule: impl Rule + 'static) -> Self {
+        self.register(Box::new(rule));
+        self
+    }
+
+    /// Add a policy pack, builder style.
+    #[must_use]
+    pub fn with_policy(self, policy: Policy) -> Self {
+        self.with_rule(policy)
+    }
+
+    /// Add a rule, replacing any rule with the same id.
+    pub fn register(&mut self, rule: Box<dyn Rule>) {
+        if let Some(existing) = self.rules.iter_mut().find(|r| r.id() == rule.id()) {
+            *existing = rule;
+        } else {
+            self.rules.push(rule);
+        }
+    }
+
+    /// Drop the rules with the given ids.
+    #[must_use]
+    pub fn skip<S: AsRef<str>>(mut self, ids: &[S]) -> Self {
+        self.rules
+            .retain(|rule| !ids.iter().any(|id| id.as_ref() == rule.id()));
+        self
+    }
+
+    /// Whether a rule with this id is registered.
+    #[must_use]
+    pub fn contains(&self, id: &str) -> bool {
+        self.rules.iter().any(|r| r.id() == id)
+    }
+
+    /// Ids of the registered rules, in order.
+    #[must_use]
+    pub fn names(&self) -> Vec<&str> {
+        self.rules.iter().map(|r| r.id()).collect()
+    }
+
+    /// Iterate over the registered rules.
+    pub fn iter(&self) -> impl Iterator<Item = &dyn Rule> {
+        self.rules.iter().map(AsRef::as_ref)
+    }
+
+    /// Number of registered rules.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    /// Whether no rules are registered.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Run every rule and return the findings ordered by location.
+    ///
+    /// Findings that name a known directive get a link to its
+    /// documentation (see [`docs`]) unless the rule set one.
+    #[must_use]
+    pub fn check(&self, config: &Config) -> Vec<Finding> {
+        let mut findings: Vec<Finding> = self
+            .rules
+            .iter()
+            .flat_map(|rule| rule.check(config))
+            .collect();
+
+        let index = config.index();
+        let mut by_span: HashMap<Span, Vec<NodeId>> = HashMap::new();
+        for id in index.ids() {
+            by_span.entry(index.get(id).span).or_default().push(id);
+        }
+
+        for finding in &mut findings {
+            if finding.path.is_none() {
+                // Spans of included files may repeat, the name tells them apart
+                let node =
+                    finding
+                        .node
+                        .filter(|&id| index.try_get(id).is_some())
+                        .or_else(|| {
+                            by_span.get(&finding.span?)?.iter().copied().find(|&id| {
+                                finding.directive.as_deref() == Some(index.get(id).name())
+                            })
+                        });
+                finding.path = node.map(|id| index.config_path(id));
+            }
+            if finding.doc_url.is_none() {
+                finding.doc_url = finding
+                    .directive
+                    .as_deref()
+                    .and_then(docs::lookup)
+                    .map(docs::DirectiveDoc::url);
+            }
+        }
+
+        findings.sort_by_key(|f| f.span.map(|s| (s.line, s.col)));
+        findings
+    }
+}
+
+/// Visit every directive together with its chain of enclosing blocks.
+///
+/// The slice passed to `visit` lists the parents from outermost to
+/// innermost; it is empty for top-level directives.
+pub(crate) fn walk<'a>(
+    config: &'a Config,
+    visit: &mut impl FnMut(&'a Directive, &[&'a Directive]),
+) {
+    fn walk_inner<'a>(
+        directives: &'a [Directive],
+        parents: &mut Vec<&'a Directive>,
+        visit: &mut impl FnMut(&'a Directive, &[&'a Directive]),
+    ) {
+        for directive in directives {
+            visit(directive, parents);
+            if let Some(children) = directive.children() {
+                parents.push(directive);
+                walk_inner(children, parents, visit);
+                parents.pop();
+            }
+        }
+    }
+
+    walk_inner(&config.directives, &mut Vec::new(), visit);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    struct Always;
+
+    impl Rule for Always {
+        fn id(&self) -> &'static str {
+            "always"
+        }
+
+        fn description(&self) -> &'static str {
+            "Reports every top-level directive"
+        }
+
+        fn check(&self, config: &Config) -> Vec<Finding> {
+            config
+                .directives
+                .iter()
+                .map(|d| Finding::new("always", Severity::Info, "seen").at(d))
+                .collect()
+        }
+    }
+
+    #[test]
+    fn test_linter_registry() {
+        let linter = Linter::with_default_rules().with_rule(Always);
+        assert!(linter.contains("always"));
+        assert!(linter.contains("duplicate_directive"));
+
+        let linter = linter.skip(&["always"]);
+        assert!(!linter.contains("always"));
+
+        let mut linter = Linter::new().with_rule(Always);
+        linter.register(Box::new(Always));
+        assert_eq!(linter.len(), 1);
+    }
+
+    #[test]
+    fn test_findings_sorted_by_location() {
+        let config = parse("user nginx;\nworker_processes 2;\n").unwrap();
+        let findings = Linter::new().with_rule(Always).check(&config);
+
+        assert_eq!(findings.len(), 2);
+        assert_eq!(findings[0].line(), Some(1));
+        assert_eq!(findings[1].line(), Some(2));
+        assert_eq!(findings[1].directive.as_deref(), Some("worker_processes"));
+        assert_eq!(
+            findings[1]
+                .path
+                .as_ref()
+                .map(ToString::to_string)
+                .as_deref(),
+            Some("worker_processes")
+        );
+        assert_eq!(
+            findings[1].doc_url.as_deref(),
+            Some("https://nginx.org/en/docs/ngx_core_module.html#worker_processes")
+        );
+    }
+
+    #[test]
+    fn test_finding_at_node() {
+        let config = parse("http {\n    server { listen 80; }\n}\n").unwrap();
+        let index = config.index();
+        let listen = index.find("listen")[0];
+        let finding = Finding::new("x", Severity::Info, "seen").at_node(&index, listen);
+
+        assert_eq!(finding.node, Some(listen));
+        assert_eq!(finding.line(), Some(2));
+        let server = index.enclosing(finding.node.unwrap(), "server");
+        assert_eq!(server, index.at_span(index.get(server.unwrap()).span));
+        assert_eq!(finding.path.unwrap().to_string(), "http/server/listen");
+    }
+
+    #[test]
+    fn test_finding_display() {
+        let finding = Finding::new("x", Severity::Warning, "bad thing")
+            .with_span(Span::new(0, 1, 3, 5))
+            .with_file("nginx.conf");
+        assert_eq!(finding.to_string(), "nginx.conf:3:5: warning[x]: bad thing");
+
+        let bare = Finding::new("x", Severity::Error, "oops");
+        assert_eq!(bare.to_string(), "error[x]: oops");
+    }
+
+    #[test]
+    fn test_severity_parse() {
+        assert_eq!("warn".parse::<Severity>(), Ok(Severity::Warning));
+        assert_eq!("ERROR".parse::<Severity>(), Ok(Severity::Error));
+        assert!("fatal".parse::<Severity>().is_err());
+        assert!(Severity::Error > Severity::Warning);
+    }
+}
@@ -0,0 +1,850 @@
+//! Policy packs: opinionated rule bundles
+//!
+//! A [`Policy`] is a [`Rule`] that bundles the checks of a published
+//! baseline. Its findings use the policy id as rule, name the control they
+//! come from in [`Finding::reference`] and link to the baseline in
+//! [`Finding::doc_url`].
+//!
+//! | Policy | Alias | Checks |
+//! |--------|-------|--------|
+//! | `mozilla-intermediate-tls` | `mozilla` | `ssl_protocols`, weak `ssl_ciphers`, `ssl_prefer_server_ciphers`, `ssl_session_tickets`, HSTS, ECDSA certificates (with `certificate_files`) |
+//! | `cis-nginx-benchmark-subset` | `cis` | Unknown host names, `server_tokens`, hidden files, `X-Powered-By`, access logging, TLS protocols, HSTS, client timeouts and body size, security headers |
+//! | `performance-baseline` | `performance` | The [`performance`](crate::analyze::performance()) tuning checks and upstream keepalive |
+//!
+//! Policies are not part of the default rules; add them with
+//! [`Linter::with_policy`](super::Linter::with_policy).
+//!
+//! # Examples
+//!
+//! ```
+//! use nginx_discovery::{lint::{Linter, Policy}, parse};
+//!
+//! let config = parse("http { server { listen 443 ssl; ssl_protocols TLSv1 TLSv1.2; } }")?;
+//! let policy: Policy = "mozilla".parse().unwrap();
+//! let findings = Linter::new().with_policy(policy).check(&config);
+//!
+//! let protocols = findings
+//!     .iter()
+//!     .find(|f| f.directive.as_deref() == Some("ssl_protocols"))
+//!     .unwrap();
+//! assert_eq!(protocols.rule, "mozilla-intermediate-tls");
+//! assert_eq!(protocols.reference.as_deref(), Some("Mozilla intermediate: protocols"));
+//! # Ok::<(), nginx_discovery::Error>(())
+//! ```
+
+use super::{walk, Finding, Rule, Severity};
+use crate::analyze::caching::parse_seconds;
+use crate::analyze::{keepalive_audit, performance};
+use crate::ast::{Config, Directive, Span, Value};
+use crate::docs;
+use crate::extract;
+use crate::types::{self, ListenDirective};
+use std::fmt;
+
+const MOZILLA_URL: &str = "https://ssl-config.mozilla.org/#server=nginx&config=intermediate";
+const CIS_URL: &str = "https://www.cisecurity.org/benchmark/nginx";
+
+/// Legacy protocols excluded by both TLS baselines
+const LEGACY_PROTOCOLS: &[&str] = &["SSLv2", "SSLv3", "TLSv1", "TLSv1.1"];
+
+/// Cipher string fragments naming broken or unauthenticated suites
+const WEAK_CIPHERS: &[&str] = &["RC4", "DES", "MD5", "NULL", "EXP", "ADH", "AECDH"];
+
+/// HSTS max-age used by the Mozilla configurations (two years)
+const HSTS_MAX_AGE: u64 = 63_072_000;
+
+/// An opinionated bundle of checks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum Policy {
+    /// TLS settings of the Mozilla "intermediate" server configuration
+    MozillaIntermediateTls,
+    /// Checks from the CIS NGINX Benchmark that can be decided from the
+    /// configuration alone
+    CisNginxBenchmarkSubset,
+    /// Worker, connection and upstream keepalive tuning
+    PerformanceBaseline,
+}
+
+impl Policy {
+    /// Every policy, in listing order
+    pub const ALL: [Self; 3] = [
+        Self::MozillaIntermediateTls,
+        Self::CisNginxBenchmarkSubset,
+        Self::PerformanceBaseline,
+    ];
+
+    /// Stable identifier, also the rule id of the findings
+    #[must_use]
+    pub fn id(self) -> &'static str {
+        match self {
+            Self::MozillaIntermediateTls => "mozilla-intermediate-tls",
+            Self::CisNginxBenchmarkSubset => "cis-nginx-benchmark-subset",
+            Self::PerformanceBaseline => "performance-baseline",
+        }
+    }
+
+    /// Short name accepted in place of the id
+    #[must_use]
+    pub fn alias(self) -> &'static str {
+        match self {
+            Self::MozillaIntermediateTls => "mozilla",
+            Self::CisNginxBenchmarkSubset => "cis",
+            Self::PerformanceBaseline => "performance",
+        }
+    }
+
+    /// Document the policy is derived from
+    #[must_use]
+    pub fn reference_url(self) -> &'static str {
+        match self {
+            Self::MozillaIntermediateTls => MOZILLA_URL,
+            Self::CisNginxBenchmarkSubset => CIS_URL,
+            Self::PerformanceBaseline => "https://nginx.org/en/docs/ngx_core_module.html",
+        }
+    }
+
+    /// Whether the baseline asks every TLS server for an ECDSA certificate
+    ///
+    /// Key types are only known from the certificate files, so this is
+    /// checked by
+    /// [`CertificateFiles::require_ecdsa`](super::rules::CertificateFiles::require_ecdsa)
+    /// rather than by the policy itself.
+    #[must_use]
+    pub fn requires_ecdsa(self) -> bool {
+        matches!(self, Self::MozillaIntermediateTls)
+    }
+}
+
+impl fmt::Display for Policy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.id())
+    }
+}
+
+impl std::str::FromStr for Policy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.to_ascii_lowercase();
+        Self::ALL
+            .into_iter()
+            .find(|policy| policy.id() == s || policy.alias() == s)
+            .ok_or_else(|| {
+                let names: Vec<_> = Self::ALL.into_iter().map(Self::id).collect();
+                format!("Invalid policy '{s}': expected {}", names.join(", "))
+            })
+    }
+}
+
+impl Rule for Policy {
+    fn id(&self) -> &str {
+        Policy::id(*self)
+    }
+
+    fn description(&self) -> &str {
+        match self {
+            Self::MozillaIntermediateTls => "Policy: Mozilla intermediate TLS configuration",
+            Self::CisNginxBenchmarkSubset => "Policy: subset of the CIS NGINX Benchmark",
+            Self::PerformanceBaseline => "Policy: worker, connection and keepalive tuning",
+        }
+    }
+
+    fn check(&self, config: &Config) -> Vec<Finding> {
+        match self {
+            Self::MozillaIntermediateTls => mozilla(config),
+            Self::CisNginxBenchmarkSubset => cis(config),
+            Self::PerformanceBaseline => performance_baseline(config),
+        }
+    }
+}
+
+/// An HTTP server with its enclosing blocks, innermost first
+struct Server<'a> {
+    directive: &'a Directive,
+    chain: Vec<&'a Directive>,
+}
+
+impl<'a> Server<'a> {
+    fn name(&self) -> String {
+        types::Server::primary_name_of(self.directive)
+    }
+
+    fn listens(&self) -> Vec<ListenDirective> {
+        extract::listens(self.directive)
+    }
+
+    fn is_tls(&self) -> bool {
+        self.listens().iter().any(|l| l.ssl || l.is_quic())
+    }
+
+    /// Last occurrence of `name` in the innermost block that sets it
+    fn effective(&self, name: &str) -> Option<&'a Directive> {
+        effective(&self.chain, name)
+    }
+
+    /// Effective `add_header` named `header`
+    fn header(&self, header: &str) -> Option<&'a Directive> {
+        self.chain
+            .iter()
+            .map(|block| block.find_children("add_header"))
+            .find(|headers| !headers.is_empty())?
+            .into_iter()
+            .rev()
+            .find(|h| {
+                h.first_arg()
+                    .is_some_and(|n| n.eq_ignore_ascii_case(header))
+            })
+    }
+}
+
+fn http_servers(config: &Config) -> Vec<Server<'_>> {
+    let mut servers = Vec::new();
+    walk(config, &mut |directive, parents| {
+        if directive.name() == "server"
+            && directive.is_block()
+            && parents.last().map(|p| p.name()) == Some("http")
+        {
+            servers.push(Server {
+                directive,
+                chain: std::iter::once(directive)
+                    .chain(parents.iter().rev().copied())
+                    .collect(),
+            });
+        }
+    });
+    servers
+}
+
+fn effective<'a>(chain: &[&'a Directive], name: &str) -> Option<&'a Directive> {
+    chain
+        .iter()
+        .find_map(|block| block.find_children(name).last().copied())
+}
+
+fn finding(policy: Policy, control: &str, severity: Severity, message: String) -> Finding {
+    let reference = match policy {
+        Policy::MozillaIntermediateTls => format!("Mozilla intermediate: {control}"),
+        Policy::CisNginxBenchmarkSubset => format!("CIS NGINX Benchmark {control}"),
+        Policy::PerformanceBaseline => format!("performance baseline: {control}"),
+    };
+    Finding::new(policy.id(), severity, message)
+        .with_reference(reference)
+        .with_doc_url(policy.reference_url())
+}
+
+/// Findings on a directive shared by several servers are reported once
+fn push_once(findings: &mut Vec<Finding>, reported: &mut Vec<(Span, String)>, finding: Finding) {
+    if let Some(span) = finding.span {
+        let key = (span, finding.reference.clone().unwrap_or_default());
+        if reported.contains(&key) {
+            return;
+        }
+        reported.push(key);
+    }
+    findings.push(finding);
+}
+
+fn mozilla(config: &Config) -> Vec<Finding> {
+    let policy = Policy::MozillaIntermediateTls;
+    let mut findings = Vec::new();
+    let mut reported = Vec::new();
+
+    for server in http_servers(config).iter().filter(|s| s.is_tls()) {
+        let name = server.name();
+        let mut checks = vec![
+            protocols(policy, "protocols", server),
+            hsts(policy, "HSTS", server),
+        ];
+
+        if let Some(ciphers) = server.effective("ssl_ciphers") {
+            let weak = weak_ciphers(&ciphers.first_arg().unwrap_or_default());
+            checks.push((!weak.is_empty()).then(|| {
+                finding(
+                    policy,
+                    "ciphers",
+                    Severity::Warning,
+                    format!("ssl_ciphers enables weak ciphers ({})", weak.join(", ")),
+                )
+                .at(ciphers)
+                .with_help("Use the cipher list of the Mozilla intermediate configuration")
+            }));
+        }
+
+        let prefer = server.effective("ssl_prefer_server_ciphers");
+        checks.push(prefer.filter(|d| is_on(d)).map(|d| {
+            finding(
+                policy,
+                "ssl_prefer_server_ciphers",
+                Severity::Info,
+                "ssl_prefer_server_ciphers is on; with only strong ciphers enabled the client \
+                 can pick the fastest one for its hardware"
+                    .to_string(),
+            )
+            .at(d)
+            .with_help("Set \"ssl_prefer_server_ciphers off;\"")
+        }));
+
+        let tickets = server.effective("ssl_session_tickets");
+        checks.push(match tickets {
+            Some(d) if is_on(d) => Some(
+                finding(
+                    policy,
+                    "ssl_session_tickets",
+                    Severity::Warning,
+                    "ssl_session_tickets is on; ticket keys that are never rotated break \
+                     forward secrecy"
+                        .to_string(),
+                )
+                .at(d)
+                .with_help("Set \"ssl_session_tickets off;\""),
+            ),
+            Some(_) => None,
+            None => Some(
+                finding(
+                    policy,
+                    "ssl_session_tickets",
+                    Severity::Info,
+                    format!(
+                        "server \"{name}\" does not set ssl_session_tickets, which defaults \
+                         to on; ticket keys that are never rotated break forward secrecy"
+                    ),
+                )
+                .at(server.directive)
+                .with_help("Set \"ssl_session_tickets off;\""),
+            ),
+        });
+
+        for check in checks.into_iter().flatten() {
+            push_once(&mut findings, &mut reported, check);
+        }
+    }
+
+    findings
+}
+
+fn cis(config: &Config) -> Vec<Finding> {
+    let policy = Policy::CisNginxBenchmarkSubset;
+    let servers = http_servers(config);
+    let mut findings = Vec::new();
+    let mut reported = Vec::new();
+
+    findings.extend(cis_http(config, &servers));
+
+    for server in &servers {
+        let name = server.name();
+        let mut checks = vec![hidden_files(server)];
+        if server.is_tls() {
+            checks.push(protocols(policy, "4.1.4", server));
+            checks.push(hsts(policy, "4.1.8", server));
+        }
+        for (control, header, value) in [
+            ("5.3.1", "X-Frame-Options", "SAMEORIGIN"),
+            ("5.3.2", "X-Content-Type-Options", "nosniff"),
+        ] {
+            checks.push(server.header(header).is_none().then(|| {
+                finding(
+                    policy,
+                    control,
+                    Severity::Info,
+                    format!("server \"{name}\" does not send {header}"),
+                )
+                .at(server.directive)
+                .with_help(format!(
+                    "Add \"add_header {header} {value} always;\" (add_header in a location \
+                     replaces all inherited ones)"
+                ))
+            }));
+        }
+        for check in checks.into_iter().flatten() {
+            push_once(&mut findings, &mut reported, check);
+        }
+    }
+
+    walk(config, &mut |directive, parents| {
+        if let Some(check) = cis_directive(directive, parents) {
+            push_once(&mut findings, &mut reported, check);
+        }
+    });
+
+    findings
+}
+
+/// Checks of the `http` block: unknown host names, `server_tokens` and
+/// client timeouts
+fn cis_http(config: &Config, servers: &[Server]) -> Vec<Finding> {
+    let policy = Policy::CisNginxBenchmarkSubset;
+    let mut findings = Vec::new();
+
+    for http in config.find_directives("http") {
+        let in_http: Vec<&Server> = servers
+            .iter()
+            .filter(|s| s.chain.last().is_some_and(|top| std::ptr::eq(*top, http)))
+            .collect();
+        findings.extend(default_server(http, &in_http));
+
+        let tokens = effective(&[http], "server_tokens");
+        if tokens.and_then(Directive::first_arg).as_deref() != Some("off") {
+            let mut check = finding(
+                policy,
+                "2.5.1",
+                Severity::Warning,
+                "server_tokens is not off, so responses and error pages disclose the nginx \
+                 version"
+                    .to_string(),
+            )
+            .with_help("Set \"server_tokens off;\" in http");
+            check = check.at(tokens.unwrap_or(http));
+            findings.push(check);
+        }
+
+        for name in ["client_header_timeout", "client_body_timeout"] {
+            let directive = effective(&[http], name);
+            let value = directive
+                .and_then(Directive::first_arg)
+                .unwrap_or_else(|| "60s".to_string());
+            if parse_seconds(&value).is_some_and(|seconds| seconds > 10) {
+                let severity = if directive.is_some() {
+                    Severity::Warning
+                } else {
+                    Severity::Info
+                };
+                findings.push(
+                    finding(
+                        policy,
+                        "5.2.1",
+                        severity,
+                        format!("{name} is {value}; slow clients can hold connections open"),
+                    )
+                    .at(directive.unwrap_or(http))
+                    .with_help(format!("Set \"{name} 10;\" in http")),
+                );
+            }
+        }
+    }
+
+    findings
+}
+
+/// Unknown host names must reach a default server that rejects them
+fn default_server(http: &Directive, servers: &[&Server]) -> Option<Finding> {
+    let policy = Policy::CisNginxBenchmarkSubset;
+    if servers.is_empty() {
+        return None;
+    }
+
+    let Some(default) = servers
+        .iter()
+        .find(|s| s.listens().iter().any(|l| l.default_server))
+    else {
+        return Some(
+            finding(
+                policy,
+                "2.4.2",
+                Severity::Warning,
+                "no server is marked default_server, so requests for unknown host names are \
+                 served by the first server"
+                    .to_string(),
+            )
+            .at(http)
+            .with_help(
+                "Add a catch-all server: listen 80 default_server; server_name _; return 444;",
+            ),
+        );
+    };
+
+    let rejects = default.directive.find_children("return").iter().any(|r| {
+        r.first_arg()
+            .and_then(|code| code.parse::<u16>().ok())
+            .is_some_and(|code| (400..500).contains(&code))
+    }) || default.effective("ssl_reject_handshake").is_some_and(is_on);
+    (!rejects).then(|| {
+        finding(
+            policy,
+            "2.4.2",
+            Severity::Warning,
+            format!(
+                "default server \"{}\" serves requests for unknown host names",
+                default.name()
+            ),
+        )
+        .at(default.directive)
+        .with_help("Reject them in the default server with \"return 444;\"")
+    })
+}
+
+fn hidden_files(server: &Server) -> Option<Finding> {
+    let mut denied = false;
+    walk_block(server.directive, &mut |directive| {
+        if directive.name() == "location" && directive.args_as_strings().join(" ").contains("/\\.")
+        {
+            denied = true;
+        }
+    });
+
+    (!denied).then(|| {
+        finding(
+            Policy::CisNginxBenchmarkSubset,
+            "2.5.3",
+            Severity::Warning,
+            format!(
+                "server \"{}\" has no location denying hidden files such as .git or .env",
+                server.name()
+            ),
+        )
+        .at(server.directive)
+        .with_help("Add: location ~ /\\. { deny all; return 404; }")
+    })
+}
+
+/// Checks of single directives: proxied `X-Powered-By`, disabled access
+/// logs and unlimited request bodies
+fn cis_directive(directive: &Directive, parents: &[&Directive]) -> Option<Finding> {
+    let policy = Policy::CisNginxBenchmarkSubset;
+    let parent = parents.last().map(|p| p.name());
+
+    match directive.name() {
+        "proxy_pass" if parent == Some("location") => {
+            // nginx already hides Server and Date from proxied responses
+            let chain: Vec<&Directive> = parents.iter().rev().copied().collect();
+            let hides = chain
+                .iter()
+                .map(|block| block.find_children("proxy_hide_header"))
+                .find(|headers| !headers.is_empty())
+                .unwrap_or_default()
+                .iter()
+                .any(|h| {
+                    h.first_arg()
+                        .is_some_and(|n| n.eq_ignore_ascii_case("X-Powered-By"))
+                });
+            (!hides).then(|| {
+                finding(
+                    policy,
+                    "2.5.4",
+                    Severity::Info,
+                    "the X-Powered-By header of the proxied application is passed to clients"
+                        .to_string(),
+                )
+                .at(directive)
+                .with_help("Add \"proxy_hide_header X-Powered-By;\"")
+            })
+        }
+        "access_log" if matches!(parent, Some("http" | "server")) => {
+            (directive.first_arg().as_deref() == Some("off")).then(|| {
+                finding(
+                    policy,
+                    "3.2",
+                    Severity::Warning,
+                    format!(
+                        "access logging is disabled in {}",
+                        parent.unwrap_or_default()
+                    ),
+                )
+                .at(directive)
+                .with_help("Disable access logs only in locations such as health checks")
+            })
+        }
+        "client_max_body_size" => (directive.first_arg().as_deref() == Some("0")).then(|| {
+            finding(
+                policy,
+                "5.2.2",
+                Severity::Warning,
+                "client_max_body_size 0 disables the request body size limit".to_string(),
+            )
+            .at(directive)
+            .with_help("Set the largest body the application accepts, e.g. 10m")
+        }),
+        _ => None,
+    }
+}
+
+/// `ssl_protocols` must not enable legacy protocols
+fn protocols(policy: Policy, control: &str, server: &Server) -> Option<Finding> {
+    let help = "Set \"ssl_protocols TLSv1.2 TLSv1.3;\"";
+    let Some(directive) = server.effective("ssl_protocols") else {
+        return Some(
+            finding(
+                policy,
+                control,
+                Severity::Info,
+                format!(
+                    "server \"{}\" does not set ssl_protocols; before nginx 1.23.4 the \
+                     default includes TLSv1 and TLSv1.1",
+                    server.name()
+                ),
+            )
+            .at(server.directive)
+            .with_help(help),
+        );
+    };
+
+    let legacy: Vec<String> = directive
+        .args_as_strings()
+        .into_iter()
+        .filter(|p| LEGACY_PROTOCOLS.contains(&p.as_str()))
+        .collect();
+    (!legacy.is_empty()).then(|| {
+        finding(
+            policy,
+            control,
+            Severity::Warning,
+            format!("ssl_protocols enables {}", legacy.join(", ")),
+        )
+        .at(directive)
+        .with_help(help)
+    })
+}
+
+/// HTTPS servers should send `Strict-Transport-Security`
+fn hsts(policy: Policy, control: &str, server: &Server) -> Option<Finding> {
+    let help = format!(
+        "Add \"add_header Strict-Transport-Security \\\"max-age={HSTS_MAX_AGE}\\\" always;\""
+    );
+    let Some(header) = server.header("Strict-Transport-Security") else {
+        return Some(
+            finding(
+                policy,
+                control,
+                Severity::Warning,
+                format!(
+                    "server \"{}\" does not send Strict-Transport-Security",
+                    server.name()
+                ),
+            )
+            .at(server.directive)
+            .with_help(help),
+        );
+    };
+
+    let value = header
+        .args()
+        .get(1)
+        .map(Value::unquoted)
+        .unwrap_or_default();
+    let max_age = value
+        .split(';')
+        .find_map(|part| part.trim().strip_prefix("max-age="))
+        .and_then(|age| age.trim_matches('"').parse::<u64>().ok())
+        .unwrap_or(0);
+    (max_age < HSTS_MAX_AGE).then(|| {
+        finding(
+            policy,
+            control,
+            Severity::Info,
+            format!("Strict-Transport-Security max-age is {max_age}, less than two years"),
+        )
+        .at(header)
+        .with_help(help)
+    })
+}
+
+/// Enabled cipher string entries naming weak suites
+fn weak_ciphers(ciphers: &str) -> Vec<String> {
+    ciphers
+        .split([':', ' ', ','])
+        .filter(|c| !c.is_empty() && !c.starts_with(['!', '-', '+']))
+        .filter(|c| {
+            let upper = c.to_ascii_uppercase();
+            WEAK_CIPHERS.iter().any(|weak| upper.contains(weak))
+        })
+        .map(str::to_string)
+        .collect()
+}
+
+fn is_on(directive: &Directive) -> bool {
+    directive.first_arg().as_deref() == Some("on")
+}
+
+fn walk_block<'a>(block: &'a Directive, visit: &mut impl FnMut(&'a Directive)) {
+    for child in block.children().unwrap_or_default() {
+        visit(child);
+        walk_block(child, visit);
+    }
+}
+
+fn performance_baseline(config: &Config) -> Vec<Finding> {
+    let policy = Policy::PerformanceBaseline;
+    let report = performance(config);
+
+    // Every failed check produced one finding, in the same order
+    let mut findings: Vec<Finding> = report
+        .failed()
+        .zip(&report.findings)
+        .map(|(check, found)| {
+            let mut tuned = found.clone();
+            tuned.rule = policy.id().to_string();
+            tuned.reference = Some(format!("performance baseline: {}", check.setting));
+            tuned.doc_url = docs::lookup(&check.setting).map(docs::DirectiveDoc::url);
+            tuned
+        })
+        .collect();
+
+    findings.extend(keepalive_audit(config).findings.into_iter().map(|found| {
+        let mut tuned = found;
+        tuned.rule = policy.id().to_string();
+        tuned.reference = Some("performance baseline: upstream keepalive".to_string());
+        tuned.doc_url = docs::lookup("keepalive").map(docs::DirectiveDoc::url);
+        tuned
+    }));
+
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lint::Linter;
+    use crate::parse;
+
+    fn found(policy: Policy, config: &str) -> Vec<(Option<String>, Option<usize>)> {
+        let config = parse(config).unwrap();
+        Linter::new()
+            .with_policy(policy)
+            .check(&config)
+            .into_iter()
+            .map(|f| {
+                let line = f.line();
+                (f.reference, line)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_policy_parse() {
+        assert_eq!("cis".parse(), Ok(Policy::CisNginxBenchmarkSubset));
+        assert_eq!(
+            "Mozilla-Intermediate-TLS".parse(),
+            Ok(Policy::MozillaIntermediateTls)
+        );
+        assert!("nist".parse::<Policy>().is_err());
+        assert_eq!(
+            Policy::PerformanceBaseline.to_string(),
+            "performance-baseline"
+        );
+    }
+
+    #[test]
+    fn test_weak_ciphers() {
+        assert_eq!(
+            weak_ciphers("ECDHE-RSA-AES128-GCM-SHA256:DES-CBC3-SHA:!aNULL:!MD5:RC4-SHA"),
+            vec!["DES-CBC3-SHA", "RC4-SHA"]
+        );
+        assert!(weak_ciphers("HIGH:!aNULL:!MD5").is_empty());
+    }
+
+    #[test]
+    fn test_mozilla_intermediate_tls() {
+        let findings = found(
+            Policy::MozillaIntermediateTls,
+            r#"
+http {
+    ssl_protocols TLSv1 TLSv1.2;
+    ssl_session_tickets off;
+    server {
+        listen 443 ssl;
+        ssl_ciphers HIGH:RC4-SHA:!aNULL;
+        add_header Strict-Transport-Security "max-age=31536000";
+    }
+    server {
+        listen 8443 ssl;
+        ssl_protocols TLSv1.2 TLSv1.3;
+        ssl_prefer_server_ciphers on;
+        add_header Strict-Transport-Security "max-age=63072000" always;
+    }
+    server {
+        listen 80;
+    }
+}
+"#,
+        );
+        let reference = |control: &str| Some(format!("Mozilla intermediate: {control}"));
+        assert_eq!(
+            findings,
+            vec![
+                (reference("protocols"), Some(3)),
+                (reference("ciphers"), Some(7)),
+                (reference("HSTS"), Some(8)),
+                (reference("ssl_prefer_server_ciphers"), Some(13)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cis_subset() {
+        let findings = found(
+            Policy::CisNginxBenchmarkSubset,
+            r"
+http {
+    server_tokens off;
+    client_header_timeout 10s;
+    client_body_timeout 10s;
+    add_header X-Frame-Options SAMEORIGIN;
+    add_header X-Content-Type-Options nosniff;
+    server {
+        listen 80 default_server;
+        return 444;
+        location ~ /\. { deny all; }
+    }
+    server {
+        listen 80;
+        server_name app.example.com;
+        access_log off;
+        client_max_body_size 0;
+        location / { proxy_pass http://app; }
+    }
+}
+",
+        );
+        let reference = |control: &str| Some(format!("CIS NGINX Benchmark {control}"));
+        assert_eq!(
+            findings,
+            vec![
+                (reference("2.5.3"), Some(13)),
+                (reference("3.2"), Some(16)),
+                (reference("5.2.2"), Some(17)),
+                (reference("2.5.4"), Some(18)),
+            ]
+        );
+
+        let findings = found(
+            Policy::CisNginxBenchmarkSubset,
+            "http { server { listen 80; location ~ /\\. { deny all; } } }",
+        );
+        let controls: Vec<_> = findings.into_iter().filter_map(|(r, _)| r).collect();
+        assert_eq!(
+            controls,
+            vec![
+                "CIS NGINX Benchmark 2.4.2",
+                "CIS NGINX Benchmark 2.5.1",
+                "CIS NGINX Benchmark 5.2.1",
+                "CIS NGINX Benchmark 5.2.1",
+                "CIS NGINX Benchmark 5.3.1",
+                "CIS NGINX Benchmark 5.3.2",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_performance_baseline() {
+        let config = parse(
+            "worker_processes 1;
+             http {
+                 upstream app { server 127.0.0.1:8080; keepalive 16; }
+                 server { location / { proxy_pass http://app; } }
+             }",
+        )
+        .unwrap();
+        let findings = Policy::PerformanceBaseline.check(&config);
+
+        assert!(findings
+            .iter()
+            .all(|f| f.rule == "performance-baseline" && f.reference.is_some()));
+        assert!(findings.iter().any(|f| f.reference.as_deref()
+            == Some("performance baseline: worker_processes")
+            && f.line() == Some(1)));
+        assert!(findings
+            .iter()
+            .any(|f| f.reference.as_deref() == Some("performance baseline: upstream keepalive")));
+    }
+}
@@ -0,0 +1,106 @@
+//! Lint rules defined by a [`Query`]
+
+use super::{Finding, Rule, Severity};
+use crate::ast::Config;
+use crate::query::Query;
+
+/// A custom rule reporting every directive matched by a [`Query`]
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::{lint::{Linter, QueryRule, Severity}, parse, query::Query};
+///
+/// let rule = QueryRule::new(
+///     "no_autoindex",
+///     Severity::Warning,
+///     "autoindex exposes directory listings",
+///     Query::name("autoindex").arg("on"),
+/// )
+/// .with_help("Remove \"autoindex on;\"");
+///
+/// let config = parse("http { server { location /files { autoindex on; } } }")?;
+/// let findings = Linter::new().with_rule(rule).check(&config);
+///
+/// assert_eq!(findings.len(), 1);
+/// assert_eq!(findings[0].rule, "no_autoindex");
+/// assert!(findings[0].node.is_some());
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct QueryRule {
+    id: String,
+    description: String,
+    severity: Severity,
+    message: String,
+    help: Option<String>,
+    query: Query,
+}
+
+impl QueryRule {
+    /// Create a rule reporting the directives matched by `query` with
+    /// `message`
+    #[must_use]
+    pub fn new(
+        id: impl Into<String>,
+        severity: Severity,
+        message: impl Into<String>,
+        query: Query,
+    ) -> Self {
+        let message = message.into();
+        Self {
+            id: id.into(),
+            description: message.clone(),
+            severity,
+            message,
+            help: None,
+            query,
+        }
+    }
+
+    /// Set the description shown in rule listings (the message by default)
+    #[must_use]
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = description.into();
+        self
+    }
+
+    /// Set the suggested fix attached to each finding
+    #[must_use]
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help = Some(help.into());
+        self
+    }
+
+    /// The query the rule runs
+    #[must_use]
+    pub fn query(&self) -> &Query {
+        &self.query
+    }
+}
+
+impl Rule for QueryRule {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn check(&self, config: &Config) -> Vec<Finding> {
+        config
+            .query(&self.query)
+            .into_iter()
+            .map(|found| {
+                let mut finding =
+                    Finding::new(&*self.id, self.severity, &*self.message).at(found.directive);
+                finding.node = Some(found.id);
+                if let Some(help) = &self.help {
+                    finding = finding.with_help(help.as_str());
+                }
+                finding
+            })
+            .collect()
+    }
+}
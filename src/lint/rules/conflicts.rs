@@ -0,0 +1,243 @@
+//! Conflicts between server and location blocks
+
+use crate::ast::{Config, Directive};
+use crate::extract;
+use crate::lint::{walk, Finding, Rule, Severity};
+use std::collections::HashMap;
+
+/// Reports configurations nginx rejects or silently resolves in an
+/// unexpected way:
+///
+/// - two `default_server` listeners on the same address and port
+/// - the same `server_name` in two servers listening on the same address
+///   and port (nginx ignores the second one)
+/// - two `location` blocks with the same modifier and path in one block
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Conflicts;
+
+/// One `listen` endpoint of an HTTP server.
+struct Endpoint<'a> {
+    key: String,
+    default_server: bool,
+    server: &'a Directive,
+    listen: Option<&'a Directive>,
+}
+
+impl Rule for Conflicts {
+    fn id(&self) -> &'static str {
+        "conflicts"
+    }
+
+    fn description(&self) -> &'static str {
+        "Duplicate default_server, conflicting server_name and duplicate location blocks"
+    }
+
+    fn check(&self, config: &Config) -> Vec<Finding> {
+        let mut servers = Vec::new();
+        let mut findings = Vec::new();
+
+        walk(config, &mut |directive, parents| {
+            let is_http_server = directive.name() == "server"
+                && directive.is_block()
+                && parents.last().map(|p| p.name()) == Some("http");
+            if is_http_server {
+                servers.push(directive);
+            }
+
+            if let Some(children) = directive.children() {
+                findings.extend(self.duplicate_locations(children));
+            }
+        });
+
+        findings.extend(self.server_conflicts(&servers));
+        findings
+    }
+}
+
+impl Conflicts {
+    fn server_conflicts(self, servers: &[&Directive]) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        let mut defaults: HashMap<String, &Endpoint> = HashMap::new();
+        let mut names: HashMap<(String, String), &Directive> = HashMap::new();
+
+        let endpoints: Vec<Endpoint> = servers.iter().flat_map(|s| endpoints(s)).collect();
+
+        for endpoint in &endpoints {
+            if endpoint.default_server {
+                if defaults.contains_key(&endpoint.key) {
+                    let mut finding = Finding::new(
+                        self.id(),
+                        Severity::Error,
+                        format!("a duplicate default server for {}", endpoint.key),
+                    )
+                    .with_help("Only one server per address and port may be default_server");
+                    if let Some(listen) = endpoint.listen {
+                        finding = finding.at(listen);
+                    }
+                    findings.push(finding);
+                } else {
+                    defaults.insert(endpoint.key.clone(), endpoint);
+                }
+            }
+
+            for server_name in endpoint.server.find_children("server_name") {
+                for name in server_name.args_as_strings() {
+                    if name.is_empty() {
+                        continue;
+                    }
+
+                    let key = (endpoint.key.clone(), name.to_ascii_lowercase());
+                    match names.get(&key) {
+                        Some(first) if !std::ptr::eq(*first, endpoint.server) => {
+                            findings.push(
+                                Finding::new(
+                                    self.id(),
+                                    Severity::Warning,
+                                    format!(
+                                        "conflicting server name \"{name}\" on {}, ignored (first defined on line {})",
+                                        endpoint.key, first.span.line
+                                    ),
+                                )
+                                .at(server_name),
+                            );
+                        }
+                        Some(_) => {}
+                        None => {
+                            names.insert(key, endpoint.server);
+                        }
+                    }
+                }
+            }
+        }
+
+        findings
+    }
+
+    fn duplicate_locations(self, children: &[Directive]) -> Vec<Finding> {
+        let mut seen: HashMap<String, &Directive> = HashMap::new();
+        let mut findings = Vec::new();
+
+        for location in children.iter().filter(|d| d.name() == "location") {
+            let key = location.args_as_strings().join(" ");
+            if let Some(first) = seen.get(&key) {
+                findings.push(
+                    Finding::new(
+                        self.id(),
+                        Severity::Error,
+                        format!(
+                            "duplicate location \"{key}\" (first defined on line {})",
+                            first.span.line
+                        ),
+                    )
+                    .at(location),
+                );
+            } else {
+                seen.insert(key, location);
+            }
+        }
+
+        findings
+    }
+}
+
+/// Endpoints a server listens on; a server without `listen` gets `*:80`.
+fn endpoints(server: &Directive) -> Vec<Endpoint<'_>> {
+    let listens = server.find_children("listen");
+    if listens.is_empty() {
+        return vec![Endpoint {
+            key: "*:80".to_string(),
+            default_server: false,
+            server,
+            listen: None,
+        }];
+    }
+
+    listens
+        .into_iter()
+        .filter_map(|listen| {
+            let parsed = extract::listen(listen)?;
+            let address = match parsed.address.as_str() {
+                "0.0.0.0" => "*".to_string(),
+                a if a.contains(':') => format!("[{a}]"),
+                a => a.to_string(),
+            };
+            // QUIC listeners are UDP sockets, separate from TCP ones
+            let protocol = if parsed.is_quic() { " (quic)" } else { "" };
+            Some(Endpoint {
+                key: format!("{address}:{}{protocol}", parsed.port),
+                default_server: parsed.default_server,
+                server,
+                listen: Some(listen),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_duplicate_default_server() {
+        let config = parse(
+            "http {
+               server { listen 80 default_server; }
+               server { listen 0.0.0.0:80 default_server; }
+               server { listen 8080 default_server; }
+             }",
+        )
+        .unwrap();
+        let findings = Conflicts.check(&config);
+
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].severity, Severity::Error);
+        assert_eq!(findings[0].line(), Some(3));
+        assert!(findings[0].message.contains("*:80"));
+    }
+
+    #[test]
+    fn test_quic_listener_is_separate() {
+        let config = parse(
+            "http {
+               server { listen 443 ssl default_server; }
+               server { listen 443 quic default_server; }
+             }",
+        )
+        .unwrap();
+        assert!(Conflicts.check(&config).is_empty());
+    }
+
+    #[test]
+    fn test_conflicting_server_name() {
+        let config = parse(
+            "http {
+               server { listen 80; server_name example.com www.example.com; }
+               server { listen 80; server_name EXAMPLE.com; }
+               server { listen 443 ssl; server_name example.com; }
+             }",
+        )
+        .unwrap();
+        let findings = Conflicts.check(&config);
+
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].severity, Severity::Warning);
+        assert!(findings[0].message.contains("\"EXAMPLE.com\""));
+    }
+
+    #[test]
+    fn test_duplicate_location() {
+        let config = parse(
+            "http { server {
+               location /a { }
+               location = /a { }
+               location /a { }
+             } }",
+        )
+        .unwrap();
+        let findings = Conflicts.check(&config);
+
+        assert_eq!(findings.len(), 1);
+        assert!(findings[0].message.contains("duplicate location \"/a\""));
+    }
+}
@@ -0,0 +1,335 @@
+//! Directive placement and shape validation
+
+use super::DATA_BLOCKS;
+use crate::ast::{Config, Directive};
+use crate::lint::{walk, Finding, Rule, Severity};
+
+/// Reports known directives used in a context nginx does not allow, and
+/// block directives written as simple ones (or the other way round).
+///
+/// Unknown directives are ignored so third-party modules do not produce
+/// noise.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DirectiveContext;
+
+const MAIN: u16 = 1;
+const EVENTS: u16 = 1 << 1;
+const HTTP: u16 = 1 << 2;
+const SERVER: u16 = 1 << 3;
+const LOCATION: u16 = 1 << 4;
+const IF: u16 = 1 << 5;
+const LIMIT_EXCEPT: u16 = 1 << 6;
+const UPSTREAM: u16 = 1 << 7;
+const STREAM: u16 = 1 << 8;
+const STREAM_SERVER: u16 = 1 << 9;
+const STREAM_UPSTREAM: u16 = 1 << 10;
+const MAIL: u16 = 1 << 11;
+const MAIL_SERVER: u16 = 1 << 12;
+
+const HTTP_ANY: u16 = HTTP | SERVER | LOCATION;
+const STREAM_ANY: u16 = STREAM | STREAM_SERVER;
+const MAIL_ANY: u16 = MAIL | MAIL_SERVER;
+const SSL: u16 = HTTP | SERVER | STREAM_ANY | MAIL_ANY;
+
+/// `(name, allowed contexts, is a block)`
+const DIRECTIVES: &[(&str, u16, bool)] = &[
+    // Core
+    ("user", MAIN, false),
+    ("worker_processes", MAIN, false),
+    ("worker_rlimit_nofile", MAIN, false),
+    ("worker_cpu_affinity", MAIN, false),
+    ("worker_priority", MAIN, false),
+    ("worker_shutdown_timeout", MAIN, false),
+    ("pid", MAIN, false),
+    ("daemon", MAIN, false),
+    ("master_process", MAIN, false),
+    ("load_module", MAIN, false),
+    ("env", MAIN, false),
+    ("pcre_jit", MAIN, false),
+    ("thread_pool", MAIN, false),
+    ("timer_resolution", MAIN, false),
+    ("working_directory", MAIN, false),
+    ("error_log", MAIN | HTTP_ANY | STREAM_ANY | MAIL_ANY, false),
+    ("events", MAIN, true),
+    ("http", MAIN, true),
+    ("stream", MAIN, true),
+    ("mail", MAIN, true),
+    // Events
+    ("worker_connections", EVENTS, false),
+    ("use", EVENTS, false),
+    ("multi_accept", EVENTS, false),
+    ("accept_mutex", EVENTS, false),
+    ("accept_mutex_delay", EVENTS, false),
+    // HTTP structure
+    ("server", HTTP | STREAM | MAIL, true),
+    ("location", SERVER | LOCATION, true),
+    ("if", SERVER | LOCATION, true),
+    ("limit_except", LOCATION, true),
+    ("upstream", HTTP | STREAM, true),
+    ("map", HTTP | STREAM, true),
+    ("geo", HTTP | STREAM, true),
+    ("split_clients", HTTP | STREAM, true),
+    ("types", HTTP_ANY, true),
+    ("log_format", HTTP | STREAM, false),
+    ("server_names_hash_bucket_size", HTTP, false),
+    ("server_names_hash_max_size", HTTP, false),
+    ("variables_hash_max_size", HTTP, false),
+    ("proxy_cache_path", HTTP, false),
+    ("limit_req_zone", HTTP, false),
+    ("limit_conn_zone", HTTP | STREAM, false),
+    // HTTP server
+    ("listen", SERVER | STREAM_SERVER | MAIL_SERVER, false),
+    ("server_name", SERVER | MAIL_ANY, false),
+    ("ssl", HTTP | SERVER, false),
+    ("http2", HTTP | SERVER, false),
+    ("underscores_in_headers", HTTP | SERVER, false),
+    ("client_header_buffer_size", HTTP | SERVER, false),
+    ("large_client_header_buffers", HTTP | SERVER, false),
+    ("ssl_stapling", HTTP | SERVER, false),
+    ("try_files", SERVER | LOCATION, false),
+    ("return", SERVER | LOCATION | IF, false),
+    ("rewrite", SERVER | LOCATION | IF, false),
+    ("set", SERVER | LOCATION | IF, false),
+    ("break", SERVER | LOCATION | IF, false),
+    // HTTP content
+    ("root", HTTP_ANY | IF, false),
+    ("alias", LOCATION, false),
+    ("internal", LOCATION, false),
+    ("index", HTTP_ANY, false),
+    ("autoindex", HTTP_ANY, false),
+    ("default_type", HTTP_ANY, false),
+    ("charset", HTTP_ANY | IF, false),
+    ("error_page", HTTP_ANY | IF, false),
+    (
+        "access_log",
+        HTTP_ANY | IF | LIMIT_EXCEPT | STREAM_ANY,
+        false,
+    ),
+    ("log_not_found", HTTP_ANY, false),
+    ("server_tokens", HTTP_ANY, false),
+    ("sendfile", HTTP_ANY | IF, false),
+    ("tcp_nopush", HTTP_ANY, false),
+    ("tcp_nodelay", HTTP_ANY | STREAM_ANY, false),
+    ("keepalive_timeout", HTTP_ANY | UPSTREAM, false),
+    ("send_timeout", HTTP_ANY, false),
+    ("client_max_body_size", HTTP_ANY, false),
+    ("client_body_buffer_size", HTTP_ANY, false),
+    ("gzip", HTTP_ANY | IF, false),
+    ("gzip_types", HTTP_ANY, false),
+    ("expires", HTTP_ANY | IF, false),
+    ("etag", HTTP_ANY, false),
+    ("add_header", HTTP_ANY | IF, false),
+    ("open_file_cache", HTTP_ANY, false),
+    ("sub_filter", HTTP_ANY, false),
+    ("satisfy", HTTP_ANY, false),
+    ("allow", HTTP_ANY | LIMIT_EXCEPT | STREAM_ANY, false),
+    ("deny", HTTP_ANY | LIMIT_EXCEPT | STREAM_ANY, false),
+    ("auth_basic", HTTP_ANY | LIMIT_EXCEPT, false),
+    ("auth_basic_user_file", HTTP_ANY | LIMIT_EXCEPT, false),
+    ("limit_req", HTTP_ANY, false),
+    ("limit_conn", HTTP_ANY | STREAM_ANY, false),
+    ("real_ip_header", HTTP_ANY, false),
+    ("set_real_ip_from", HTTP_ANY, false),
+    (
+        "resolver",
+        HTTP_ANY | UPSTREAM | STREAM_ANY | MAIL_ANY,
+        false,
+    ),
+    // Proxying
+    (
+        "proxy_pass",
+        LOCATION | IF | LIMIT_EXCEPT | STREAM_SERVER,
+        false,
+    ),
+    ("proxy_set_header", HTTP_ANY, false),
+    ("proxy_http_version", HTTP_ANY, false),
+    ("proxy_redirect", HTTP_ANY, false),
+    ("proxy_buffering", HTTP_ANY, false),
+    ("proxy_read_timeout", HTTP_ANY, false),
+    ("proxy_connect_timeout", HTTP_ANY | STREAM_ANY, false),
+    ("proxy_next_upstream", HTTP_ANY | STREAM_ANY, false),
+    ("proxy_cache", HTTP_ANY, false),
+    ("proxy_cache_valid", HTTP_ANY, false),
+    ("fastcgi_pass", LOCATION | IF, false),
+    ("fastcgi_param", HTTP_ANY, false),
+    ("uwsgi_pass", LOCATION | IF, false),
+    ("grpc_pass", LOCATION | IF, false),
+    // Upstream
+    ("keepalive", UPSTREAM, false),
+    ("least_conn", UPSTREAM | STREAM_UPSTREAM, false),
+    ("ip_hash", UPSTREAM, false),
+    ("hash", UPSTREAM | STREAM_UPSTREAM, false),
+    ("zone", UPSTREAM | STREAM_UPSTREAM, false),
+    // SSL
+    ("ssl_certificate", SSL, false),
+    ("ssl_certificate_key", SSL, false),
+    ("ssl_protocols", SSL, false),
+    ("ssl_ciphers", SSL, false),
+    ("ssl_prefer_server_ciphers", SSL, false),
+    ("ssl_session_cache", SSL, false),
+    ("ssl_session_timeout", SSL, false),
+    ("ssl_dhparam", SSL, false),
+];
+
+/// Context of the innermost enclosing block, `None` for blocks whose
+/// contents are not checked.
+fn context_of(parents: &[&Directive]) -> Option<u16> {
+    let Some((block, outer)) = parents.split_last() else {
+        return Some(MAIN);
+    };
+    let outer = outer.last().map(|d| d.name());
+
+    match block.name() {
+        "events" => Some(EVENTS),
+        "http" => Some(HTTP),
+        "stream" => Some(STREAM),
+        "mail" => Some(MAIL),
+        "server" => match outer {
+            Some("stream") => Some(STREAM_SERVER),
+            Some("mail") => Some(MAIL_SERVER),
+            _ => Some(SERVER),
+        },
+        "upstream" => match outer {
+            Some("stream") => Some(STREAM_UPSTREAM),
+            _ => Some(UPSTREAM),
+        },
+        "location" => Some(LOCATION),
+        "if" => Some(IF),
+        "limit_except" => Some(LIMIT_EXCEPT),
+        _ => None,
+    }
+}
+
+fn context_name(context: u16) -> &'static str {
+    match context {
+        MAIN => "main",
+        EVENTS => "events",
+        HTTP => "http",
+        SERVER => "server",
+        LOCATION => "location",
+        IF => "if",
+        LIMIT_EXCEPT => "limit_except",
+        UPSTREAM => "upstream",
+        STREAM => "stream",
+        STREAM_SERVER => "stream server",
+        STREAM_UPSTREAM => "stream upstream",
+        MAIL => "mail",
+        _ => "mail server",
+    }
+}
+
+impl Rule for DirectiveContext {
+    fn id(&self) -> &'static str {
+        "directive_context"
+    }
+
+    fn description(&self) -> &'static str {
+        "Directives used outside their allowed context or with the wrong shape"
+    }
+
+    fn check(&self, config: &Config) -> Vec<Finding> {
+        let mut findings = Vec::new();
+
+        walk(config, &mut |directive, parents| {
+            if parents.iter().any(|p| DATA_BLOCKS.contains(&p.name())) {
+                return;
+            }
+            let Some(context) = context_of(parents) else {
+                return;
+            };
+            let Some(&(name, allowed, is_block)) =
+                DIRECTIVES.iter().find(|(n, _, _)| *n == directive.name())
+            else {
+                return;
+            };
+
+            // `server` inside an upstream is a simple directive
+            let in_upstream = context & (UPSTREAM | STREAM_UPSTREAM) != 0;
+            if name == "server" && in_upstream {
+                return;
+            }
+
+            if allowed & context == 0 {
+                findings.push(
+                    Finding::new(
+                        self.id(),
+                        Severity::Error,
+                        format!(
+                            "\"{name}\" directive is not allowed in the {} context",
+                            context_name(context)
+                        ),
+                    )
+                    .at(directive),
+                );
+            } else if is_block && !directive.is_block() {
+                findings.push(
+                    Finding::new(
+                        self.id(),
+                        Severity::Error,
+                        format!("\"{name}\" directive requires a block"),
+                    )
+                    .at(directive),
+                );
+            } else if !is_block && directive.is_block() {
+                findings.push(
+                    Finding::new(
+                        self.id(),
+                        Severity::Error,
+                        format!("\"{name}\" directive does not take a block"),
+                    )
+                    .at(directive)
+                    .with_help("End the directive with ';' instead of '{ ... }'"),
+                );
+            }
+        });
+
+        findings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    fn check(input: &str) -> Vec<Finding> {
+        DirectiveContext.check(&parse(input).unwrap())
+    }
+
+    #[test]
+    fn test_valid_config_is_clean() {
+        let findings = check(
+            "user nginx;
+             events { worker_connections 1024; }
+             http {
+                 upstream app { server 127.0.0.1:8080; keepalive 16; }
+                 map $uri $x { default 0; listen 1; }
+                 server {
+                     listen 80;
+                     location / { proxy_pass http://app; if ($x) { return 403; } }
+                 }
+             }
+             stream { server { listen 53 udp; proxy_pass dns; } }",
+        );
+        assert!(findings.is_empty(), "{findings:?}");
+    }
+
+    #[test]
+    fn test_wrong_context() {
+        let findings = check("listen 80;\nhttp { server { worker_connections 10; } }");
+
+        assert_eq!(findings.len(), 2);
+        assert!(findings[0].message.contains("main context"));
+        assert_eq!(findings[1].directive.as_deref(), Some("worker_connections"));
+        assert_eq!(findings[1].line(), Some(2));
+    }
+
+    #[test]
+    fn test_wrong_shape() {
+        let findings = check("http { server { location /a; root /x { } } }");
+
+        assert_eq!(findings.len(), 2);
+        assert!(findings[0].message.contains("requires a block"));
+        assert!(findings[1].message.contains("does not take a block"));
+    }
+}
@@ -0,0 +1,216 @@
+//! Deprecated and removed directives and parameters
+
+use crate::analyze::NginxVersion;
+use crate::ast::{Config, Directive};
+use crate::lint::{walk, Finding, Rule, Severity};
+
+/// Reports directives and `listen` parameters that nginx has deprecated or
+/// removed, with the recommended replacement.
+///
+/// By default every deprecation is reported as a warning. With
+/// [`for_version`](Self::for_version) only deprecations that apply to that
+/// nginx release are reported, and directives it no longer accepts become
+/// errors.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DeprecatedDirective {
+    target: Option<NginxVersion>,
+}
+
+struct Deprecation {
+    directive: &'static str,
+    /// Parameter that is deprecated, `None` for the whole directive
+    parameter: Option<&'static str>,
+    deprecated: NginxVersion,
+    removed: Option<NginxVersion>,
+    replacement: &'static str,
+}
+
+const fn deprecation(
+    directive: &'static str,
+    parameter: Option<&'static str>,
+    deprecated: NginxVersion,
+    removed: Option<NginxVersion>,
+    replacement: &'static str,
+) -> Deprecation {
+    Deprecation {
+        directive,
+        parameter,
+        deprecated,
+        removed,
+        replacement,
+    }
+}
+
+const fn v(major: u32, minor: u32, patch: u32) -> NginxVersion {
+    NginxVersion::new(major, minor, patch)
+}
+
+#[rustfmt::skip]
+const DEPRECATIONS: &[Deprecation] = &[
+    deprecation("ssl", None, v(1, 15, 0), Some(v(1, 25, 1)), "Use the \"ssl\" parameter of the \"listen\" directive"),
+    deprecation("listen", Some("spdy"), v(1, 9, 5), Some(v(1, 9, 5)), "Use \"http2 on;\" (or the \"http2\" listen parameter before 1.25.1)"),
+    deprecation("listen", Some("http2"), v(1, 25, 1), None, "Remove the \"http2\" parameter and add \"http2 on;\" to the server block"),
+    deprecation("spdy_chunk_size", None, v(1, 9, 5), Some(v(1, 9, 5)), "Use \"http2_chunk_size\""),
+    deprecation("spdy_headers_comp", None, v(1, 9, 5), Some(v(1, 9, 5)), "Remove it; HTTP/2 header compression is not configurable"),
+    deprecation("limit_zone", None, v(1, 1, 8), Some(v(1, 7, 6)), "Use \"limit_conn_zone\""),
+    deprecation("http2_push", None, v(1, 25, 1), Some(v(1, 25, 1)), "Remove it; HTTP/2 server push is no longer supported"),
+    deprecation("http2_push_preload", None, v(1, 25, 1), Some(v(1, 25, 1)), "Remove it; HTTP/2 server push is no longer supported"),
+    deprecation("http2_max_field_size", None, v(1, 19, 7), None, "Use \"large_client_header_buffers\""),
+    deprecation("http2_max_header_size", None, v(1, 19, 7), None, "Use \"large_client_header_buffers\""),
+    deprecation("http2_idle_timeout", None, v(1, 19, 7), None, "Use \"keepalive_timeout\""),
+    deprecation("http2_recv_timeout", None, v(1, 19, 7), None, "Use \"client_header_timeout\""),
+    deprecation("http2_max_requests", None, v(1, 19, 7), None, "Use \"keepalive_requests\""),
+    deprecation("proxy_downstream_buffer", None, v(1, 9, 4), Some(v(1, 9, 4)), "Use \"proxy_buffer_size\""),
+    deprecation("proxy_upstream_buffer", None, v(1, 9, 4), Some(v(1, 9, 4)), "Use \"proxy_buffer_size\""),
+];
+
+impl DeprecatedDirective {
+    /// Only report deprecations that apply to `version`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nginx_discovery::{analyze::NginxVersion, lint::{rules::DeprecatedDirective, Rule}, parse};
+    ///
+    /// let config = parse("http { server { listen 443 ssl http2; } }")?;
+    ///
+    /// // "listen ... http2" is still the way to enable HTTP/2 before 1.25.1
+    /// let rule = DeprecatedDirective::for_version(NginxVersion::new(1, 24, 0));
+    /// assert!(rule.check(&config).is_empty());
+    ///
+    /// let rule = DeprecatedDirective::for_version(NginxVersion::new(1, 25, 3));
+    /// assert_eq!(rule.check(&config).len(), 1);
+    /// # Ok::<(), nginx_discovery::Error>(())
+    /// ```
+    #[must_use]
+    pub fn for_version(version: NginxVersion) -> Self {
+        Self {
+            target: Some(version),
+        }
+    }
+
+    fn finding(self, directive: &Directive, deprecation: &Deprecation) -> Finding {
+        let subject = match deprecation.parameter {
+            Some(parameter) => format!("the \"{parameter}\" parameter of \"{}\"", directive.name()),
+            None => format!("the \"{}\" directive", directive.name()),
+        };
+
+        let (severity, status) = match deprecation.removed {
+            Some(removed) if self.target.is_some_and(|target| target >= removed) => {
+                (Severity::Error, format!("was removed in nginx {removed}"))
+            }
+            Some(removed) if removed == deprecation.deprecated => {
+                (Severity::Warning, format!("was removed in nginx {removed}"))
+            }
+            Some(removed) => (
+                Severity::Warning,
+                format!(
+                    "is deprecated since nginx {} and was removed in {removed}",
+                    deprecation.deprecated
+                ),
+            ),
+            None => (
+                Severity::Warning,
+                format!("is deprecated since nginx {}", deprecation.deprecated),
+            ),
+        };
+
+        Finding::new(self.id(), severity, format!("{subject} {status}"))
+            .at(directive)
+            .with_help(deprecation.replacement)
+    }
+
+    fn applies(self, deprecation: &Deprecation) -> bool {
+        self.target
+            .map_or(true, |target| target >= deprecation.deprecated)
+    }
+}
+
+impl Rule for DeprecatedDirective {
+    fn id(&self) -> &'static str {
+        "deprecated_directive"
+    }
+
+    fn description(&self) -> &'static str {
+        "Deprecated or removed directives and listen parameters"
+    }
+
+    fn check(&self, config: &Config) -> Vec<Finding> {
+        let mut findings = Vec::new();
+
+        walk(config, &mut |directive, _| {
+            for deprecation in DEPRECATIONS {
+                if deprecation.directive != directive.name() || !self.applies(deprecation) {
+                    continue;
+                }
+                let matches = deprecation.parameter.map_or(true, |parameter| {
+                    directive.args().iter().any(|arg| arg.as_str() == parameter)
+                });
+                if matches {
+                    findings.push(self.finding(directive, deprecation));
+                }
+            }
+        });
+
+        findings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_deprecated_directives_and_parameters() {
+        let config = parse(
+            r"
+http {
+    server {
+        listen 443 ssl http2;
+        listen 80;
+        ssl on;
+    }
+    server {
+        listen 8443 spdy;
+    }
+}
+",
+        )
+        .unwrap();
+        let findings = DeprecatedDirective::default().check(&config);
+
+        assert_eq!(findings.len(), 3);
+        assert_eq!(findings[0].line(), Some(4));
+        assert_eq!(
+            findings[0].message,
+            "the \"http2\" parameter of \"listen\" is deprecated since nginx 1.25.1"
+        );
+        assert!(findings[0].help.as_deref().unwrap().contains("http2 on;"));
+        assert_eq!(findings[1].line(), Some(6));
+        assert_eq!(findings[1].directive.as_deref(), Some("ssl"));
+        assert_eq!(findings[2].line(), Some(9));
+        assert!(findings.iter().all(|f| f.severity == Severity::Warning));
+    }
+
+    #[test]
+    fn test_target_version() {
+        let config = parse("http { server { listen 443 ssl http2; ssl on; } }").unwrap();
+
+        let findings = DeprecatedDirective::for_version(NginxVersion::new(1, 14, 0)).check(&config);
+        assert!(findings.is_empty());
+
+        let findings = DeprecatedDirective::for_version(NginxVersion::new(1, 20, 0)).check(&config);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].severity, Severity::Warning);
+
+        let findings = DeprecatedDirective::for_version(NginxVersion::new(1, 26, 0)).check(&config);
+        assert_eq!(findings.len(), 2);
+        let ssl = findings
+            .iter()
+            .find(|f| f.directive.as_deref() == Some("ssl"))
+            .unwrap();
+        assert_eq!(ssl.severity, Severity::Error);
+        assert!(ssl.message.ends_with("was removed in nginx 1.25.1"));
+    }
+}
@@ -0,0 +1,402 @@
+//! `root` and `alias` checks for locations
+
+use crate::ast::{Config, Directive, Value};
+use crate::lint::{walk, Finding, Fix, Rule, Severity};
+use std::path::PathBuf;
+
+/// Directives that hand the request to another server, so the document
+/// root of the location is never used
+const HANDLERS: &[&str] = &[
+    "proxy_pass",
+    "fastcgi_pass",
+    "uwsgi_pass",
+    "scgi_pass",
+    "grpc_pass",
+    "memcached_pass",
+    "return",
+];
+
+/// The URI prefix of a prefix or exact-match location
+///
+/// Regex and named locations return `None`.
+fn location_prefix(location: &Directive) -> Option<(&str, bool)> {
+    match location.args() {
+        [uri] if !uri.as_str().starts_with('@') => Some((uri.as_str(), false)),
+        [modifier, uri] if modifier.as_str() == "^~" => Some((uri.as_str(), false)),
+        [modifier, uri] if modifier.as_str() == "=" => Some((uri.as_str(), true)),
+        _ => None,
+    }
+}
+
+fn child<'a>(block: &'a Directive, name: &str) -> Option<&'a Directive> {
+    block.children()?.iter().find(|d| d.name() == name)
+}
+
+/// Literal first argument, or `None` when it contains variables
+fn literal_arg(directive: &Directive) -> Option<String> {
+    directive.first_arg().filter(|arg| !arg.contains('$'))
+}
+
+/// Reports the `alias` path traversal: a prefix location without a trailing
+/// slash whose `alias` ends with one.
+///
+/// With `location /static { alias /srv/static/; }` a request for
+/// `/static../secret` maps to `/srv/static/../secret`, outside the aliased
+/// directory.
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::{lint::{rules::AliasTraversal, Linter}, parse};
+///
+/// let config = parse("http { server { location /files { alias /srv/files/; } } }")?;
+/// let findings = Linter::new().with_rule(AliasTraversal).check(&config);
+///
+/// assert_eq!(findings[0].rule, "alias_traversal");
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AliasTraversal;
+
+impl Rule for AliasTraversal {
+    fn id(&self) -> &'static str {
+        "alias_traversal"
+    }
+
+    fn description(&self) -> &'static str {
+        "Prefix location without trailing slash using an alias with one"
+    }
+
+    fn check(&self, config: &Config) -> Vec<Finding> {
+        let mut findings = Vec::new();
+
+        walk(config, &mut |directive, parents| {
+            if directive.name() != "alias" {
+                return;
+            }
+            let Some(location) = parents.last().filter(|p| p.name() == "location") else {
+                return;
+            };
+            let Some((uri, false)) = location_prefix(location) else {
+                return;
+            };
+            let Some(alias) = directive.first_arg() else {
+                return;
+            };
+
+            if !uri.ends_with('/') && alias.ends_with('/') {
+                findings.push(
+                    Finding::new(
+                        self.id(),
+                        Severity::Error,
+                        format!(
+                            "location {uri} has no trailing slash but alias {alias} does; \
+                             {uri}../ escapes the aliased directory"
+                        ),
+                    )
+                    .at(directive)
+                    .with_help(format!(
+                        "Use \"location {uri}/\" or remove the trailing slash from the alias"
+                    )),
+                );
+            }
+        });
+
+        findings
+    }
+}
+
+/// Reports a `root` inside a location that repeats the location path.
+///
+/// `root` is prepended to the full URI, so `location /static/ { root
+/// /srv/static; }` serves `/srv/static/static/...`. Either drop the
+/// duplicated part from `root` or use `alias`.
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::{lint::{rules::RootInLocation, Linter}, parse};
+///
+/// let config = parse("http { server { location /static/ { root /srv/static; } } }")?;
+/// let findings = Linter::new().with_rule(RootInLocation).check(&config);
+///
+/// assert_eq!(findings[0].rule, "root_in_location");
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RootInLocation;
+
+impl Rule for RootInLocation {
+    fn id(&self) -> &'static str {
+        "root_in_location"
+    }
+
+    fn description(&self) -> &'static str {
+        "root inside a location that repeats the location path"
+    }
+
+    fn check(&self, config: &Config) -> Vec<Finding> {
+        let mut findings = Vec::new();
+
+        walk(config, &mut |directive, parents| {
+            if directive.name() != "root" {
+                return;
+            }
+            let Some(location) = parents.last().filter(|p| p.name() == "location") else {
+                return;
+            };
+            let Some((uri, _)) = location_prefix(location) else {
+                return;
+            };
+            let Some(root) = directive.first_arg() else {
+                return;
+            };
+
+            let segment = uri.trim_end_matches('/');
+            let trimmed = root.trim_end_matches('/');
+            if segment.is_empty() || !trimmed.ends_with(segment) {
+                return;
+            }
+            let parent = match &trimmed[..trimmed.len() - segment.len()] {
+                "" => "/",
+                parent => parent,
+            };
+            findings.push(
+                Finding::new(
+                    self.id(),
+                    Severity::Warning,
+                    format!(
+                        "root {root} repeats the location path {uri}; files are served from \
+                         {trimmed}{segment}/"
+                    ),
+                )
+                .at(directive)
+                .with_help(format!("Use \"root {parent};\" or \"alias {trimmed}/;\""))
+                .with_fix(Fix::replace(
+                    format!("Set root to {parent}"),
+                    directive,
+                    Directive::simple_with_span("root", vec![parent.to_string()], directive.span),
+                )),
+            );
+        });
+
+        findings
+    }
+}
+
+/// Reports locations whose effective document root does not exist.
+///
+/// The document root is the location's own `root` or `alias`, or the `root`
+/// inherited from the enclosing location, server or `http` block.
+/// Locations that proxy or return are skipped, as are paths with variables
+/// and locations relying on the compiled-in default `html` root.
+///
+/// This rule reads the filesystem, so it is not part of
+/// [`Linter::with_default_rules`](crate::lint::Linter::with_default_rules).
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::{lint::{rules::DocumentRoots, Linter}, parse};
+///
+/// let config = parse("http { server { root /no/such/dir; location / { } } }")?;
+/// let findings = Linter::new()
+///     .with_rule(DocumentRoots::new().with_base_dir("/etc/nginx"))
+///     .check(&config);
+///
+/// assert_eq!(findings[0].rule, "missing_document_root");
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DocumentRoots {
+    base_dir: Option<PathBuf>,
+}
+
+impl DocumentRoots {
+    /// Create the rule, resolving relative paths against the current
+    /// directory.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Resolve relative paths against `dir`, usually the nginx prefix or
+    /// the directory of the main configuration file.
+    #[must_use]
+    pub fn with_base_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.base_dir = Some(dir.into());
+        self
+    }
+
+    fn resolve(&self, path: &str) -> PathBuf {
+        match &self.base_dir {
+            Some(base) => crate::paths::resolve(base, path),
+            None => PathBuf::from(path),
+        }
+    }
+}
+
+impl Rule for DocumentRoots {
+    fn id(&self) -> &'static str {
+        "missing_document_root"
+    }
+
+    fn description(&self) -> &'static str {
+        "Locations whose resolved document root does not exist (reads the filesystem)"
+    }
+
+    fn check(&self, config: &Config) -> Vec<Finding> {
+        let mut findings = Vec::new();
+
+        walk(config, &mut |location, parents| {
+            if location.name() != "location"
+                || location
+                    .args()
+                    .first()
+                    .is_some_and(|a| a.as_str().starts_with('@'))
+                || HANDLERS.iter().any(|h| child(location, h).is_some())
+            {
+                return;
+            }
+
+            let (source, path, is_alias) = if let Some(alias) = child(location, "alias") {
+                (alias, literal_arg(alias), true)
+            } else {
+                let inherited = std::iter::once(location)
+                    .chain(parents.iter().rev().copied())
+                    .find_map(|block| child(block, "root"));
+                let Some(root) = inherited else {
+                    return;
+                };
+                (root, literal_arg(root), false)
+            };
+            let Some(path) = path else {
+                return;
+            };
+
+            let resolved = self.resolve(&path);
+            let exists = if is_alias {
+                resolved.exists()
+            } else {
+                resolved.is_dir()
+            };
+            if exists {
+                return;
+            }
+
+            let uri = location
+                .args()
+                .iter()
+                .map(Value::as_str)
+                .collect::<Vec<_>>()
+                .join(" ");
+            let inherited = if parents_contain(location, source) {
+                String::new()
+            } else {
+                format!(" (inherited from line {})", source.span.line)
+            };
+            findings.push(
+                Finding::new(
+                    self.id(),
+                    Severity::Warning,
+                    format!(
+                        "document root {} of location {uri} does not exist{inherited}",
+                        resolved.display()
+                    ),
+                )
+                .at(location)
+                .with_help(format!(
+                    "Create {} or fix \"{}\"",
+                    resolved.display(),
+                    source.name()
+                )),
+            );
+        });
+
+        findings
+    }
+}
+
+/// Whether `directive` is a direct child of `block`
+fn parents_contain(block: &Directive, directive: &Directive) -> bool {
+    block
+        .children()
+        .is_some_and(|children| children.iter().any(|c| std::ptr::eq(c, directive)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_alias_traversal_and_root_in_location() {
+        let config = parse(
+            r"
+http {
+    server {
+        location /static { alias /srv/static/; }
+        location /ok/ { alias /srv/ok/; }
+        location ^~ /img { alias /srv/img/; }
+        location = /robots.txt { alias /srv/robots.txt; }
+        location ~ ^/x(.*)$ { alias /srv/x/$1; }
+        location /assets/ { root /srv/www/assets; }
+        location /media { root /srv/media-files; }
+        location / { root /srv/www; }
+    }
+}
+",
+        )
+        .unwrap();
+
+        let lines: Vec<_> = AliasTraversal
+            .check(&config)
+            .iter()
+            .map(Finding::line)
+            .collect();
+        assert_eq!(lines, vec![Some(4), Some(6)]);
+
+        let findings = RootInLocation.check(&config);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].line(), Some(9));
+        assert!(findings[0].message.contains("/srv/www/assets/assets/"));
+        assert_eq!(
+            findings[0].help.as_deref(),
+            Some("Use \"root /srv/www;\" or \"alias /srv/www/assets/;\"")
+        );
+    }
+
+    #[test]
+    fn test_document_roots() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("html")).unwrap();
+
+        let config = parse(
+            r"
+http {
+    root html;
+    server {
+        location / { }
+        location /api { proxy_pass http://backend; }
+        location /old { root gone; }
+        location /files { alias missing/; }
+        location /dyn { root /srv/$host; }
+    }
+    server {
+        root nothing;
+        location /a { location /a/b { } }
+    }
+}
+",
+        )
+        .unwrap();
+        let findings = DocumentRoots::new()
+            .with_base_dir(dir.path())
+            .check(&config);
+
+        let lines: Vec<_> = findings.iter().map(Finding::line).collect();
+        assert_eq!(lines, vec![Some(7), Some(8), Some(13), Some(13)]);
+        assert!(!findings[0].message.contains("inherited"));
+        assert!(findings[2].message.contains("inherited from line 12"));
+    }
+}
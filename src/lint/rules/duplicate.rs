@@ -0,0 +1,139 @@
+//! Directives that may appear only once per block
+
+use super::DATA_BLOCKS;
+use crate::ast::{Config, Directive};
+use crate::lint::{walk, Finding, Fix, Rule, Severity};
+use std::collections::HashMap;
+
+/// Reports directives repeated within the same block where nginx rejects
+/// the configuration with "directive is duplicate".
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DuplicateDirective;
+
+const NON_REPEATABLE: &[&str] = &[
+    "user",
+    "pid",
+    "worker_processes",
+    "worker_rlimit_nofile",
+    "worker_connections",
+    "events",
+    "http",
+    "stream",
+    "root",
+    "alias",
+    "proxy_pass",
+    "fastcgi_pass",
+    "uwsgi_pass",
+    "grpc_pass",
+    "try_files",
+    "client_max_body_size",
+    "keepalive_timeout",
+    "sendfile",
+    "gzip",
+    "default_type",
+    "server_tokens",
+    "autoindex",
+    "charset",
+    "expires",
+    "proxy_http_version",
+    "proxy_buffering",
+    "ssl_protocols",
+    "ssl_ciphers",
+    "ssl_prefer_server_ciphers",
+    "ssl_session_cache",
+    "ssl_session_timeout",
+    "types",
+];
+
+impl Rule for DuplicateDirective {
+    fn id(&self) -> &'static str {
+        "duplicate_directive"
+    }
+
+    fn description(&self) -> &'static str {
+        "Directives that may appear only once per block"
+    }
+
+    fn check(&self, config: &Config) -> Vec<Finding> {
+        let mut findings = self.check_block(&config.directives);
+
+        walk(config, &mut |directive, _| {
+            if DATA_BLOCKS.contains(&directive.name()) {
+                return;
+            }
+            if let Some(children) = directive.children() {
+                findings.extend(self.check_block(children));
+            }
+        });
+
+        findings
+    }
+}
+
+impl DuplicateDirective {
+    fn check_block(self, directives: &[Directive]) -> Vec<Finding> {
+        let mut first_seen: HashMap<&str, &Directive> = HashMap::new();
+        let mut findings = Vec::new();
+
+        for directive in directives {
+            let name = directive.name();
+            if !NON_REPEATABLE.contains(&name) {
+                continue;
+            }
+
+            if let Some(first) = first_seen.get(name) {
+                findings.push(
+                    Finding::new(
+                        self.id(),
+                        Severity::Error,
+                        format!(
+                            "\"{name}\" directive is duplicate (first defined on line {})",
+                            first.span.line
+                        ),
+                    )
+                    .at(directive)
+                    .with_help(format!("Remove one of the \"{name}\" directives"))
+                    .with_fix(Fix::delete(
+                        format!("Remove the \"{name}\" on line {}", directive.span.line),
+                        directive,
+                    )),
+                );
+            } else {
+                first_seen.insert(name, directive);
+            }
+        }
+
+        findings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_duplicate_in_same_block() {
+        let config = parse(
+            "http {\n  server {\n    root /a;\n    location / { root /b; }\n    root /c;\n  }\n}",
+        )
+        .unwrap();
+        let findings = DuplicateDirective.check(&config);
+
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].line(), Some(5));
+        assert!(findings[0].message.contains("first defined on line 3"));
+    }
+
+    #[test]
+    fn test_repeatable_and_data_blocks_ignored() {
+        let config = parse(
+            "http { server { listen 80; listen 443; } types { text/html html; text/html htm; } }",
+        )
+        .unwrap();
+        assert!(DuplicateDirective.check(&config).is_empty());
+
+        let config = parse("worker_processes 1; worker_processes 2;").unwrap();
+        assert_eq!(DuplicateDirective.check(&config).len(), 1);
+    }
+}
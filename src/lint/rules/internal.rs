@@ -0,0 +1,235 @@
+//! `internal` location exposure
+
+use crate::ast::{Config, Directive, Value};
+use crate::lint::{walk, Finding, Rule, Severity};
+use crate::route::find_location;
+use crate::types::{LocationModifier, TryFiles, TryFilesFallback};
+
+/// Path segments that name a location meant for `X-Accel-Redirect`
+/// offload rather than for clients
+const INTERNAL_SEGMENTS: &[&str] = &[
+    "protected",
+    "private",
+    "internal",
+    "secure",
+    "accel",
+    "x-accel",
+    "xaccel",
+];
+
+/// Directives that restrict who can reach a location
+const ACCESS_CONTROL: &[&str] = &["internal", "auth_basic", "auth_request", "deny", "allow"];
+
+/// Reports `internal` locations clients can reach anyway, and locations
+/// that look internal but are not marked so:
+///
+/// - a `rewrite` or `try_files` fallback in an external location that maps
+///   client-controlled URIs (`/private/$1`, `/files/$uri`) into an
+///   `internal` location: every file behind it is one rewrite away
+/// - a location serving files from `root` or `alias` under an internal-
+///   looking path (`/protected/`, `/private/`, `/_accel/`), without
+///   `internal` or any other access control: `X-Accel-Redirect` targets
+///   are meant to be reached through the application only
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::{lint::{rules::InternalLocations, Linter}, parse};
+///
+/// let config = parse(r"
+/// http { server {
+///     location /files/ { rewrite ^/files/(.*)$ /private/$1 last; }
+///     location /private/ { internal; alias /srv/private/; }
+/// } }
+/// ")?;
+/// let findings = Linter::new().with_rule(InternalLocations).check(&config);
+///
+/// assert!(findings[0].message.contains("internal location /private/"));
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct InternalLocations;
+
+impl Rule for InternalLocations {
+    fn id(&self) -> &'static str {
+        "internal_location"
+    }
+
+    fn description(&self) -> &'static str {
+        "internal locations reachable through rewrites and unprotected X-Accel-Redirect targets"
+    }
+
+    fn check(&self, config: &Config) -> Vec<Finding> {
+        let mut findings = Vec::new();
+
+        walk(config, &mut |directive, parents| {
+            let Some(server) = parents.iter().rev().find(|p| p.name() == "server") else {
+                return;
+            };
+            match directive.name() {
+                "rewrite" | "try_files" => {
+                    findings.extend(self.check_redirect(directive, parents, server));
+                }
+                "location" => findings.extend(self.check_target(directive)),
+                _ => {}
+            }
+        });
+
+        findings
+    }
+}
+
+impl InternalLocations {
+    fn check_redirect(
+        self,
+        directive: &Directive,
+        parents: &[&Directive],
+        server: &Directive,
+    ) -> Option<Finding> {
+        let source = parents.iter().rev().find(|p| p.name() == "location");
+        if source.is_some_and(|l| is_internal(l)) {
+            return None;
+        }
+        let target = redirect_target(directive)?;
+        // Only a client-controlled part makes every file reachable
+        let path = target.split('?').next().unwrap_or_default();
+        let prefix = &path[..path.find('$')?];
+
+        let location = find_location(server, prefix).last().copied()?;
+        if !is_internal(location) || source.is_some_and(|s| std::ptr::eq(*s, location)) {
+            return None;
+        }
+        let from = source.map_or_else(
+            || "the server".to_string(),
+            |s| format!("location {}", pattern(s)),
+        );
+        Some(
+            Finding::new(
+                self.id(),
+                Severity::Warning,
+                format!(
+                    "{} in {from} maps client URIs to {target} in internal location {} (line \
+                     {}): clients can reach every file behind it",
+                    directive.name(),
+                    pattern(location),
+                    location.span.line
+                ),
+            )
+            .at(directive)
+            .with_help(
+                "Redirect only the fixed URIs meant to be public, or protect the internal \
+                 location with auth_request or allow/deny",
+            ),
+        )
+    }
+
+    fn check_target(self, location: &Directive) -> Option<Finding> {
+        let (modifier, path) = LocationModifier::from_args(&location.args_as_strings());
+        let prefix = matches!(
+            modifier,
+            LocationModifier::None | LocationModifier::PrefixPriority | LocationModifier::Exact
+        );
+        let serves_files = ["alias", "root"]
+            .iter()
+            .any(|name| !location.find_children(name).is_empty());
+        let protected = ACCESS_CONTROL
+            .iter()
+            .any(|name| !location.find_children(name).is_empty());
+        if !prefix || !serves_files || protected || !looks_internal(&path) {
+            return None;
+        }
+        Some(
+            Finding::new(
+                self.id(),
+                Severity::Warning,
+                format!(
+                    "location {path} looks like an X-Accel-Redirect target but is not \
+                     internal: clients can download its files directly"
+                ),
+            )
+            .at(location)
+            .with_help("Add \"internal;\" to the location"),
+        )
+    }
+}
+
+/// URI an internal redirect goes to: a `rewrite` without a redirect flag
+/// or the URI fallback of `try_files`
+fn redirect_target(directive: &Directive) -> Option<String> {
+    let args: Vec<String> = directive.args().iter().map(Value::unquoted).collect();
+    let target = if directive.name() == "rewrite" {
+        let replacement = args.get(1)?;
+        if matches!(
+            args.get(2).map(String::as_str),
+            Some("redirect" | "permanent")
+        ) {
+            return None;
+        }
+        replacement.clone()
+    } else {
+        match TryFiles::from_args(&args)?.fallback {
+            TryFilesFallback::Uri(uri) => uri,
+            _ => return None,
+        }
+    };
+    target.starts_with('/').then_some(target)
+}
+
+fn is_internal(location: &Directive) -> bool {
+    !location.find_children("internal").is_empty()
+}
+
+fn pattern(location: &Directive) -> String {
+    location.args_as_strings().join(" ")
+}
+
+fn looks_internal(path: &str) -> bool {
+    path.split('/')
+        .map(|segment| segment.trim_start_matches('_').to_ascii_lowercase())
+        .any(|segment| INTERNAL_SEGMENTS.contains(&segment.as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_internal_locations() {
+        let config = parse(
+            r"
+http {
+    server {
+        rewrite ^/dl/(.*)$ /_accel/$1;
+        location / { try_files $uri /index.php?$args; }
+        location /files/ { try_files $uri /protected/$uri; }
+        location /go/ { rewrite ^/go/(.*)$ /protected/$1 redirect; }
+        location ~ \.php$ { internal; fastcgi_pass unix:/run/php.sock; }
+        location /protected/ {
+            internal;
+            alias /srv/protected/;
+        }
+        location /_accel/ {
+            internal;
+            rewrite ^/_accel/(.*)$ /protected/$1 last;
+        }
+        location /private/ { root /srv; }
+        location /private-api/ { proxy_pass http://api; }
+        location /secure/ { root /srv; auth_basic staff; }
+    }
+}
+",
+        )
+        .unwrap();
+        let findings = InternalLocations.check(&config);
+
+        // The fixed index.php fallback, the external redirect and rewrites
+        // inside internal locations are fine
+        let lines: Vec<_> = findings.iter().map(Finding::line).collect();
+        assert_eq!(lines, vec![Some(4), Some(6), Some(17)]);
+        assert!(findings[0]
+            .message
+            .starts_with("rewrite in the server maps client URIs to /_accel/$1"));
+        assert!(findings[2].message.contains("X-Accel-Redirect"));
+    }
+}
@@ -0,0 +1,76 @@
+//! Built-in lint rules
+//!
+//! | Rule | Checks |
+//! |------|--------|
+//! | `directive_context` | Known directives used outside their allowed context or with the wrong shape |
+//! | `duplicate_directive` | Directives that may appear only once per block |
+//! | `conflicts` | Duplicate `default_server`, conflicting `server_name`, duplicate `location` |
+//! | `deprecated_directive` | Deprecated or removed directives and parameters (`ssl on`, `listen ... spdy`, `listen ... http2`) |
+//! | `alias_traversal` | Prefix `location` without a trailing slash whose `alias` has one (`/static../` escapes the directory) |
+//! | `root_in_location` | `root` inside a `location` that repeats the location path |
+//! | `regex_pattern` | Regular expressions nginx cannot compile and patterns prone to catastrophic backtracking |
+//! | `if_is_evil` | `if` inside a `location` doing anything other than `return` or `rewrite` |
+//! | `proxy_pass_regex_uri` | `proxy_pass` with a URI part in regex or named locations, `if` or `limit_except` |
+//! | `root_in_if` | `root` set inside an `if` block |
+//! | `proxy_pass_dns_cache` | `proxy_pass` to a literal host name that nginx resolves only at startup |
+//! | `undefined_reference` | Log formats, upstreams, `limit_req`/`limit_conn` zones, cache zones and `error_page` named locations referenced but not defined |
+//! | `internal_location` | `internal` locations clients reach through rewrites or `try_files` with client-controlled URIs, and X-Accel-Redirect-looking locations not marked `internal` |
+//! | `try_files` | Invalid `try_files` fallbacks, undefined named locations, `try_files` with `proxy_pass` |
+//! | `certificate_files` | Referenced certificate and key files exist and look valid (reads the filesystem, not a default rule) |
+//! | `missing_document_root` | The `root` or `alias` a location resolves to exists (reads the filesystem, not a default rule) |
+
+mod certificates;
+mod conflicts;
+mod context;
+mod deprecated;
+mod document_root;
+mod duplicate;
+mod internal;
+mod patterns;
+mod pitfalls;
+mod references;
+mod try_files;
+
+pub use certificates::CertificateFiles;
+pub use conflicts::Conflicts;
+pub use context::DirectiveContext;
+pub use deprecated::DeprecatedDirective;
+pub use document_root::{AliasTraversal, DocumentRoots, RootInLocation};
+pub use duplicate::DuplicateDirective;
+pub use internal::InternalLocations;
+pub use patterns::RegexPatterns;
+pub use pitfalls::{IfIsEvil, ProxyPassDnsCache, ProxyPassRegexUri, RootInIf};
+pub use references::UndefinedReference;
+pub use try_files::TryFilesUsage;
+
+use super::Rule;
+
+/// Blocks whose children are data entries rather than directives.
+pub(crate) const DATA_BLOCKS: &[&str] = &[
+    "map",
+    "geo",
+    "types",
+    "split_clients",
+    "charset_map",
+    "match",
+];
+
+/// Rules that only look at the parsed configuration.
+pub(crate) fn default_rules() -> Vec<Box<dyn Rule>> {
+    vec![
+        Box::new(DirectiveContext),
+        Box::new(DuplicateDirective),
+        Box::new(Conflicts),
+        Box::new(DeprecatedDirective::default()),
+        Box::new(AliasTraversal),
+        Box::new(RootInLocation),
+        Box::new(RegexPatterns),
+        Box::new(TryFilesUsage),
+        Box::new(UndefinedReference),
+        Box::new(IfIsEvil),
+        Box::new(ProxyPassRegexUri),
+        Box::new(RootInIf),
+        Box::new(ProxyPassDnsCache),
+        Box::new(InternalLocations),
+    ]
+}
@@ -0,0 +1,621 @@
+//! Regular expression validation
+//!
+//! nginx compiles regular expressions with PCRE when it loads the
+//! configuration, so a typo in a `location ~` pattern only shows up as
+//! `pcre_compile() failed` from `nginx -t`. This module implements the
+//! subset of PCRE syntax checks needed to catch those errors offline:
+//! unbalanced groups and character classes, quantifiers with nothing to
+//! repeat, unknown escapes and group constructs, and out-of-order ranges.
+//! It does not implement every PCRE feature, so some patterns PCRE rejects
+//! (for example backreferences to groups that do not exist) are accepted.
+//!
+//! The checks are hand-written rather than delegated to the `regex` crate
+//! because that crate deliberately rejects the PCRE constructs nginx
+//! configurations rely on: lookahead and lookbehind, backreferences,
+//! atomic groups, possessive quantifiers, recursion and `\Q...\E` quoting.
+//! Compiling with it would report those valid patterns as errors, and an
+//! allowlist that strips them first would still need a parser for the same
+//! syntax. Walking the pattern once also gives the offsets and group
+//! nesting that the backtracking check needs.
+
+use crate::ast::{Config, Directive};
+use crate::lint::{walk, Finding, Rule, Severity};
+
+/// Letters PCRE accepts after a backslash
+const ESCAPE_LETTERS: &str = "aAbBcdDeEfgGhHkKnNpPQrRsStvVwWxXzZ";
+
+/// Names accepted in `[[:name:]]`
+const POSIX_CLASSES: &[&str] = &[
+    "alnum", "alpha", "ascii", "blank", "cntrl", "digit", "graph", "lower", "print", "punct",
+    "space", "upper", "word", "xdigit",
+];
+
+/// Largest repeat count PCRE accepts in `{n,m}`
+const MAX_REPEAT: u32 = 65_535;
+
+/// Reports regular expressions nginx cannot compile and patterns prone to
+/// catastrophic backtracking.
+///
+/// Checks `location ~` / `~*` patterns, `server_name ~...` names, `map`
+/// keys starting with `~` and the first argument of `rewrite`. Invalid
+/// patterns are errors; nested unbounded quantifiers such as `(a+)+`, which
+/// can take exponential time on non-matching input, are warnings.
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::{lint::{rules::RegexPatterns, Linter, Severity}, parse};
+///
+/// let config = parse(r"http { server { location ~ ^/(api|v1/ { } } }")?;
+/// let findings = Linter::new().with_rule(RegexPatterns).check(&config);
+///
+/// assert_eq!(findings[0].severity, Severity::Error);
+/// assert!(findings[0].message.contains("missing )"));
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RegexPatterns;
+
+impl Rule for RegexPatterns {
+    fn id(&self) -> &'static str {
+        "regex_pattern"
+    }
+
+    fn description(&self) -> &'static str {
+        "Invalid regular expressions and patterns prone to catastrophic backtracking"
+    }
+
+    fn check(&self, config: &Config) -> Vec<Finding> {
+        let mut findings = Vec::new();
+
+        walk(config, &mut |directive, parents| {
+            for pattern in patterns(directive, parents.last().copied()) {
+                findings.extend(self.check_pattern(directive, &pattern));
+            }
+        });
+
+        findings
+    }
+}
+
+impl RegexPatterns {
+    fn check_pattern(self, directive: &Directive, pattern: &str) -> Vec<Finding> {
+        match validate(pattern) {
+            Err((offset, error)) => vec![Finding::new(
+                self.id(),
+                Severity::Error,
+                format!("invalid regular expression \"{pattern}\": {error} at offset {offset}"),
+            )
+            .at(directive)
+            .with_help("Fix the pattern; nginx -t fails with pcre_compile() failed")],
+            Ok(warnings) => warnings
+                .into_iter()
+                .map(|(offset, warning)| {
+                    Finding::new(
+                        self.id(),
+                        Severity::Warning,
+                        format!("regular expression \"{pattern}\": {warning} at offset {offset}"),
+                    )
+                    .at(directive)
+                    .with_help(
+                        "Make the inner repetition possessive (a++) or atomic ((?>...)), or \
+                         rewrite the pattern so each character can only match one way",
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Regular expressions used by a directive
+fn patterns(directive: &Directive, parent: Option<&Directive>) -> Vec<String> {
+    let args = directive.args();
+    match directive.name() {
+        "location" => match args {
+            [modifier, pattern] if matches!(modifier.as_str(), "~" | "~*") => {
+                vec![pattern.as_str().to_string()]
+            }
+            _ => Vec::new(),
+        },
+        "server_name" => args
+            .iter()
+            .filter_map(|arg| arg.as_str().strip_prefix('~'))
+            .map(str::to_string)
+            .collect(),
+        "rewrite" => args
+            .first()
+            .map(|arg| vec![arg.as_str().to_string()])
+            .unwrap_or_default(),
+        key if parent.is_some_and(|p| p.name() == "map") => key
+            .strip_prefix('~')
+            .map(|pattern| vec![pattern.strip_prefix('*').unwrap_or(pattern).to_string()])
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    }
+}
+
+/// Result of checking a pattern: warnings, or the first error
+type Checked = Result<Vec<(usize, String)>, (usize, String)>;
+
+/// The item a quantifier would apply to
+#[derive(Clone, Copy)]
+enum Atom {
+    Simple,
+    Group {
+        start: usize,
+        unbounded: bool,
+        atomic: bool,
+    },
+}
+
+/// An open group
+struct Group {
+    start: usize,
+    atomic: bool,
+    /// Whether the group contains an unbounded quantifier
+    unbounded: bool,
+}
+
+/// How a `(` starts
+enum GroupStart {
+    /// A capturing or non-capturing group
+    Group { next: usize, atomic: bool },
+    /// A self-contained item such as a recursion `(?R)`
+    Atom(usize),
+    /// An option setting `(?i)`, a comment or a verb, which cannot be repeated
+    Nothing(usize),
+}
+
+/// Check a pattern, returning the offset (in characters) and description of
+/// the first syntax error, or of each backtracking hazard.
+fn validate(pattern: &str) -> Checked {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut groups: Vec<Group> = Vec::new();
+    let mut warnings = Vec::new();
+    let mut last: Option<Atom> = None;
+    let mut i = 0;
+
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => {
+                i = escape_end(&chars, i)?;
+                last = Some(Atom::Simple);
+            }
+            '[' => {
+                i = class_end(&chars, i)?;
+                last = Some(Atom::Simple);
+            }
+            '(' => match group_start(&chars, i)? {
+                GroupStart::Group { next, atomic } => {
+                    groups.push(Group {
+                        start: i,
+                        atomic,
+                        unbounded: false,
+                    });
+                    last = None;
+                    i = next;
+                }
+                GroupStart::Atom(next) => {
+                    last = Some(Atom::Simple);
+                    i = next;
+                }
+                GroupStart::Nothing(next) => {
+                    last = None;
+                    i = next;
+                }
+            },
+            ')' => {
+                let Some(group) = groups.pop() else {
+                    return Err((i, "unmatched )".to_string()));
+                };
+                if group.unbounded {
+                    mark_unbounded(&mut groups);
+                }
+                last = Some(Atom::Group {
+                    start: group.start,
+                    unbounded: group.unbounded,
+                    atomic: group.atomic,
+                });
+                i += 1;
+            }
+            '|' => {
+                last = None;
+                i += 1;
+            }
+            '{' if repeat_end(&chars, i)?.is_none() => {
+                // Not a valid {n,m}: PCRE treats the brace literally
+                last = Some(Atom::Simple);
+                i += 1;
+            }
+            '*' | '+' | '?' | '{' => {
+                let Some(atom) = last.take() else {
+                    return Err((
+                        i,
+                        "quantifier does not follow a repeatable item".to_string(),
+                    ));
+                };
+                i = quantifier(&chars, i, atom, &mut groups, &mut warnings)?;
+            }
+            _ => {
+                last = Some(Atom::Simple);
+                i += 1;
+            }
+        }
+    }
+
+    match groups.last() {
+        Some(group) => Err((group.start, "missing )".to_string())),
+        None => Ok(warnings),
+    }
+}
+
+/// Apply the quantifier at `i` to `atom`, returning the index after it
+fn quantifier(
+    chars: &[char],
+    i: usize,
+    atom: Atom,
+    groups: &mut [Group],
+    warnings: &mut Vec<(usize, String)>,
+) -> Result<usize, (usize, String)> {
+    let (mut next, unbounded) = if chars[i] == '{' {
+        repeat_end(chars, i)?.unwrap_or((i + 1, false))
+    } else {
+        (i + 1, chars[i] != '?')
+    };
+
+    let possessive = chars.get(next) == Some(&'+');
+    if matches!(chars.get(next), Some('+' | '?')) {
+        next += 1;
+    }
+
+    if unbounded {
+        if let Atom::Group {
+            start,
+            unbounded: true,
+            atomic: false,
+        } = atom
+        {
+            if !possessive {
+                warnings.push((
+                    start,
+                    "nested unbounded quantifiers may cause catastrophic backtracking".to_string(),
+                ));
+            }
+        }
+        mark_unbounded(groups);
+    }
+    Ok(next)
+}
+
+fn mark_unbounded(groups: &mut [Group]) {
+    if let Some(group) = groups.last_mut() {
+        group.unbounded = true;
+    }
+}
+
+/// Index after the escape sequence starting at `i`
+fn escape_end(chars: &[char], i: usize) -> Result<usize, (usize, String)> {
+    let Some(&c) = chars.get(i + 1) else {
+        return Err((i, "\\ at end of pattern".to_string()));
+    };
+
+    match c {
+        'x' | 'p' | 'P' | 'o' | 'g' | 'k' if matches!(chars.get(i + 2), Some('{' | '<' | '\'')) => {
+            let close = match chars[i + 2] {
+                '{' => '}',
+                '<' => '>',
+                _ => '\'',
+            };
+            find(chars, i + 3, close)
+                .map(|end| end + 1)
+                .ok_or((i, format!("missing {close} after \\{c}")))
+        }
+        'Q' => Ok((i + 2..chars.len().saturating_sub(1))
+            .find(|&j| chars[j] == '\\' && chars[j + 1] == 'E')
+            .map_or(chars.len(), |j| j + 2)),
+        'c' if chars.get(i + 2).is_none() => Err((i, "\\c at end of pattern".to_string())),
+        'c' => Ok(i + 3),
+        c if c.is_ascii_alphabetic() && !ESCAPE_LETTERS.contains(c) => {
+            Err((i, format!("unrecognized escape \\{c}")))
+        }
+        _ => Ok(i + 2),
+    }
+}
+
+/// Index after the character class starting at `i`
+fn class_end(chars: &[char], i: usize) -> Result<usize, (usize, String)> {
+    let unterminated = || (i, "missing terminating ] for character class".to_string());
+    let mut j = i + 1;
+    if chars.get(j) == Some(&'^') {
+        j += 1;
+    }
+    // A leading ] is a literal
+    if chars.get(j) == Some(&']') {
+        j += 1;
+    }
+
+    loop {
+        let Some(&c) = chars.get(j) else {
+            return Err(unterminated());
+        };
+        match c {
+            ']' => return Ok(j + 1),
+            '\\' => j = escape_end(chars, j)?,
+            '[' if matches!(chars.get(j + 1), Some(':' | '.' | '=')) => {
+                let delimiter = chars[j + 1];
+                let end = (j + 2..chars.len().saturating_sub(1))
+                    .find(|&k| chars[k] == delimiter && chars[k + 1] == ']')
+                    .ok_or_else(unterminated)?;
+                let name: String = chars[j + 2..end].iter().collect();
+                let name = name.strip_prefix('^').unwrap_or(&name);
+                if delimiter == ':' && !POSIX_CLASSES.contains(&name) {
+                    return Err((j, format!("unknown POSIX class name \"{name}\"")));
+                }
+                j = end + 2;
+            }
+            start => {
+                let end = chars.get(j + 2).copied();
+                match end {
+                    Some(end) if chars[j + 1] == '-' && !matches!(end, ']' | '\\' | '[') => {
+                        if start > end {
+                            return Err((j, "range out of order in character class".to_string()));
+                        }
+                        j += 3;
+                    }
+                    _ => j += 1,
+                }
+            }
+        }
+    }
+}
+
+/// How the group starting at `i` opens
+fn group_start(chars: &[char], i: usize) -> Result<GroupStart, (usize, String)> {
+    if chars.get(i + 1) == Some(&'*') {
+        // A verb such as (*UTF8) or (*SKIP)
+        return find(chars, i + 2, ')')
+            .map(|end| GroupStart::Nothing(end + 1))
+            .ok_or((i, "missing )".to_string()));
+    }
+    if chars.get(i + 1) != Some(&'?') {
+        return Ok(GroupStart::Group {
+            next: i + 1,
+            atomic: false,
+        });
+    }
+
+    let unknown = || (i, "unrecognized character after (?".to_string());
+    let missing = || (i, "missing )".to_string());
+    let group = |next| {
+        Ok(GroupStart::Group {
+            next,
+            atomic: false,
+        })
+    };
+
+    match chars.get(i + 2).copied() {
+        Some(':' | '=' | '!' | '|') => group(i + 3),
+        Some('>') => Ok(GroupStart::Group {
+            next: i + 3,
+            atomic: true,
+        }),
+        Some('#') => find(chars, i + 3, ')')
+            .map(|end| GroupStart::Nothing(end + 1))
+            .ok_or_else(missing),
+        Some('<') if matches!(chars.get(i + 3), Some('=' | '!')) => group(i + 4),
+        Some('<') => group_name(chars, i + 3, '>').and_then(group),
+        Some('\'') => group_name(chars, i + 3, '\'').and_then(group),
+        Some('P') => match chars.get(i + 3) {
+            Some('<') => group_name(chars, i + 4, '>').and_then(group),
+            Some('=' | '>') => find(chars, i + 4, ')')
+                .map(|end| GroupStart::Atom(end + 1))
+                .ok_or_else(missing),
+            _ => Err(unknown()),
+        },
+        Some(c) if c == 'R' || c == '&' || c == '+' || c.is_ascii_digit() => {
+            find(chars, i + 2, ')')
+                .map(|end| GroupStart::Atom(end + 1))
+                .ok_or_else(missing)
+        }
+        Some(c) if c == '-' || c == '^' || c.is_ascii_alphabetic() => {
+            let mut j = i + 2;
+            while chars
+                .get(j)
+                .is_some_and(|c| matches!(c, 'i' | 'm' | 'n' | 's' | 'x' | 'J' | 'U' | '-' | '^'))
+            {
+                j += 1;
+            }
+            match chars.get(j) {
+                Some(')') => Ok(GroupStart::Nothing(j + 1)),
+                Some(':') => group(j + 1),
+                _ => Err(unknown()),
+            }
+        }
+        _ => Err(unknown()),
+    }
+}
+
+/// Validate a group name starting at `start`, returning the index after the
+/// closing delimiter
+fn group_name(chars: &[char], start: usize, close: char) -> Result<usize, (usize, String)> {
+    let end =
+        find(chars, start, close).ok_or((start, format!("missing {close} after group name")))?;
+    let name = &chars[start..end];
+    let valid = name
+        .first()
+        .is_some_and(|c| c.is_ascii_alphabetic() || *c == '_')
+        && name.iter().all(|c| c.is_ascii_alphanumeric() || *c == '_');
+    if valid {
+        Ok(end + 1)
+    } else {
+        Err((start, "invalid group name".to_string()))
+    }
+}
+
+/// Parse `{n}`, `{n,}` or `{n,m}` at `i`: the index after it and whether the
+/// repeat is unbounded, or `None` when the brace is a literal
+fn repeat_end(chars: &[char], i: usize) -> Result<Option<(usize, bool)>, (usize, String)> {
+    let Some(end) = find(chars, i + 1, '}') else {
+        return Ok(None);
+    };
+    let body: String = chars[i + 1..end].iter().collect();
+    let (min, max) = match body.split_once(',') {
+        Some((min, max)) => (min, Some(max)),
+        None => (body.as_str(), None),
+    };
+
+    let number = |s: &str| {
+        if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+            None
+        } else {
+            Some(s.parse::<u32>().unwrap_or(u32::MAX))
+        }
+    };
+    let Some(min) = number(min) else {
+        return Ok(None);
+    };
+    let max = match max {
+        None => Some(min),
+        Some("") => None,
+        Some(max) => match number(max) {
+            Some(max) => Some(max),
+            None => return Ok(None),
+        },
+    };
+
+    if min > MAX_REPEAT || max.is_some_and(|max| max > MAX_REPEAT) {
+        return Err((i, "number too big in {} quantifier".to_string()));
+    }
+    if max.is_some_and(|max| max < min) {
+        return Err((i, "numbers out of order in {} quantifier".to_string()));
+    }
+    Ok(Some((end + 1, max.is_none())))
+}
+
+fn find(chars: &[char], from: usize, c: char) -> Option<usize> {
+    chars
+        .get(from..)?
+        .iter()
+        .position(|&x| x == c)
+        .map(|p| from + p)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_validate() {
+        for valid in [
+            r"^/api/(v1|v2)/",
+            r"\.(jpg|png|gif)$",
+            r"^/(?<user>[a-z_-]+)/?$",
+            r"^/(?P<id>\d{1,6})$",
+            r"(?i)^/admin",
+            r"[[:alpha:]]+\.example\.com$",
+            r"^www\.(.+)$",
+            r"[]a-z]",
+            r"a{2,}b{,3}",
+            r"\Q(literal\E",
+        ] {
+            assert!(validate(valid).is_ok(), "{valid}");
+        }
+
+        let error = |pattern: &str| validate(pattern).unwrap_err();
+        assert_eq!(error(r"^/(api"), (2, "missing )".to_string()));
+        assert_eq!(error(r"^/api)").1, "unmatched )");
+        assert_eq!(error(r"*.php$").0, 0);
+        assert_eq!(error(r"a**").0, 2);
+        assert_eq!(
+            error(r"[a-z").1,
+            "missing terminating ] for character class"
+        );
+        assert_eq!(error(r"[z-a]").1, "range out of order in character class");
+        assert_eq!(error(r"\i").1, "unrecognized escape \\i");
+        assert_eq!(error(r"(?<1x>a)").1, "invalid group name");
+        assert_eq!(error(r"a{3,2}").1, "numbers out of order in {} quantifier");
+        assert_eq!(error(r"[[:alfa:]]").0, 1);
+    }
+
+    #[test]
+    fn test_pcre_constructs() {
+        for valid in [
+            // Lookaround
+            r"^/(?=api/)",
+            r"^/(?!static/).+",
+            r"(?<=/v1)/users",
+            r"(?<!\.php)$",
+            // Backreferences
+            r"^/(\w+)/\1$",
+            r"^/(?<dir>\w+)/\k<dir>$",
+            r"^/(\w+)/\g{1}$",
+            r"^/(?P<dir>\w+)/(?P=dir)$",
+            // Atomic groups and possessive quantifiers
+            r"^(?>\d+)\.html$",
+            r"^/\w++/[^/]*+$",
+            // Recursion and subroutine calls
+            r"^(\((?1)*\))$",
+            r"^(?<p>a|\((?&p)\))$",
+            r"(?R)?",
+            r"(?P>name)",
+            // Options, comments and verbs
+            r"(?i:admin)|(?-i)Admin",
+            r"(?x)^/api (?#version) /v\d+",
+            r"(*UTF8)^/caf\x{e9}$",
+            // Unicode properties, escapes and lazy quantifiers
+            r"^/\p{L}+/\P{Lu}*?$",
+            r"^/\x41\cA\Q.*\E",
+        ] {
+            assert!(validate(valid).is_ok(), "{valid}");
+        }
+    }
+
+    #[test]
+    fn test_backtracking() {
+        assert_eq!(validate(r"^(a+)+$").unwrap().len(), 1);
+        assert_eq!(validate(r"^(\w+\s?)*$").unwrap()[0].0, 1);
+        assert_eq!(validate(r"^((ab)*c)+$").unwrap().len(), 1);
+        assert!(validate(r"^(a+)++$").unwrap().is_empty());
+        assert!(validate(r"^(?>a+)+$").unwrap().is_empty());
+        assert!(validate(r"^(a+)?$").unwrap().is_empty());
+        assert!(validate(r"^(a|b)+$").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_rule() {
+        let config = parse(
+            r#"
+http {
+    map $uri $legacy {
+        default 0;
+        ~^/old/(.*  1;
+        "~*^/(x+)+$" 1;
+    }
+    server {
+        server_name ~^(www\.)?(?<domain>.+)$ example.com;
+        location ~* \.(php|phtml$ { }
+        location /plain( { }
+        rewrite ^/user/([0-9]+ /u/$1;
+    }
+}
+"#,
+        )
+        .unwrap();
+        let findings = RegexPatterns.check(&config);
+
+        let found: Vec<_> = findings.iter().map(|f| (f.line(), f.severity)).collect();
+        assert_eq!(
+            found,
+            vec![
+                (Some(5), Severity::Error),
+                (Some(6), Severity::Warning),
+                (Some(10), Severity::Error),
+                (Some(12), Severity::Error),
+            ]
+        );
+        assert!(findings[0]
+            .message
+            .contains("\"^/old/(.*\": missing ) at offset 6"));
+    }
+}
@@ -0,0 +1,447 @@
+//! Well-known nginx anti-patterns
+//!
+//! Each finding links to an explanation of the pitfall and suggests a
+//! rewrite.
+
+use crate::ast::{Config, Directive};
+use crate::lint::{walk, Finding, Rule, Severity};
+use std::net::IpAddr;
+
+const IF_IS_EVIL_URL: &str = "https://www.nginx.com/resources/wiki/start/topics/depth/ifisevil/";
+const PITFALLS_URL: &str =
+    "https://www.nginx.com/resources/wiki/start/topics/tutorials/config_pitfalls/";
+const PROXY_PASS_URL: &str = "https://nginx.org/en/docs/http/ngx_http_proxy_module.html#proxy_pass";
+
+/// Directives that are safe inside `if` in a location
+const SAFE_IN_IF: &[&str] = &["return", "rewrite"];
+
+/// Whether a location matches by regular expression
+fn is_regex_location(location: &Directive) -> bool {
+    location.name() == "location"
+        && location
+            .args()
+            .first()
+            .is_some_and(|m| matches!(m.as_str(), "~" | "~*"))
+}
+
+/// Host and URI part of a literal `http://` or `https://` proxy target,
+/// `None` for other schemes, UNIX sockets and targets with variables
+pub(super) fn split_target(target: &str) -> Option<(&str, Option<&str>)> {
+    if target.contains('$') {
+        return None;
+    }
+    let rest = target
+        .strip_prefix("http://")
+        .or_else(|| target.strip_prefix("https://"))
+        .filter(|rest| !rest.starts_with("unix:"))?;
+    Some(match rest.find('/') {
+        Some(i) => (&rest[..i], Some(&rest[i..])),
+        None => (rest, None),
+    })
+}
+
+/// Host name of an authority, without port or IPv6 brackets
+pub(super) fn host_of(authority: &str) -> &str {
+    if let Some(rest) = authority.strip_prefix('[') {
+        return rest.split(']').next().unwrap_or(rest);
+    }
+    authority.split(':').next().unwrap_or(authority)
+}
+
+/// Reports `if` inside a location doing anything other than `return` or
+/// `rewrite`.
+///
+/// `if` in a location creates a nested location with its own configuration;
+/// other directives inside it behave in surprising ways (handlers lost,
+/// `try_files` ignored, crashes in older versions).
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::{lint::{rules::IfIsEvil, Linter}, parse};
+///
+/// let config = parse(r#"
+/// http { server { location / {
+///     if ($http_user_agent ~ "bot") { add_header X-Bot 1; }
+/// } } }
+/// "#)?;
+/// let findings = Linter::new().with_rule(IfIsEvil).check(&config);
+///
+/// assert_eq!(findings[0].rule, "if_is_evil");
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct IfIsEvil;
+
+impl Rule for IfIsEvil {
+    fn id(&self) -> &'static str {
+        "if_is_evil"
+    }
+
+    fn description(&self) -> &'static str {
+        "if inside a location used for anything other than return or rewrite"
+    }
+
+    fn check(&self, config: &Config) -> Vec<Finding> {
+        let mut findings = Vec::new();
+
+        walk(config, &mut |directive, parents| {
+            if directive.name() != "if" || !parents.last().is_some_and(|p| p.name() == "location") {
+                return;
+            }
+            let unsafe_children: Vec<&str> = directive
+                .children()
+                .unwrap_or_default()
+                .iter()
+                .map(Directive::name)
+                .filter(|name| !SAFE_IN_IF.contains(name))
+                .collect();
+            if unsafe_children.is_empty() {
+                return;
+            }
+
+            findings.push(
+                Finding::new(
+                    self.id(),
+                    Severity::Warning,
+                    format!(
+                        "if inside a location contains {}; only return and rewrite are safe \
+                         there",
+                        unsafe_children.join(", ")
+                    ),
+                )
+                .at(directive)
+                .with_help(
+                    "Compute the value with a map and use it outside the if, split the \
+                     condition into separate locations, or end the if with return",
+                )
+                .with_doc_url(IF_IS_EVIL_URL),
+            );
+        });
+
+        findings
+    }
+}
+
+/// Reports `proxy_pass` with a URI part where nginx cannot replace the
+/// matched location prefix: regex and named locations, `if` and
+/// `limit_except` blocks.
+///
+/// nginx refuses such a configuration ("`proxy_pass` cannot have URI part
+/// in location given by regular expression").
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::{lint::{rules::ProxyPassRegexUri, Linter}, parse};
+///
+/// let config = parse(r"
+/// http { server { location ~ ^/api/ { proxy_pass http://backend/v1/; } } }
+/// ")?;
+/// let findings = Linter::new().with_rule(ProxyPassRegexUri).check(&config);
+///
+/// assert_eq!(findings[0].rule, "proxy_pass_regex_uri");
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProxyPassRegexUri;
+
+impl Rule for ProxyPassRegexUri {
+    fn id(&self) -> &'static str {
+        "proxy_pass_regex_uri"
+    }
+
+    fn description(&self) -> &'static str {
+        "proxy_pass with a URI part inside a regex location, named location, if or limit_except"
+    }
+
+    fn check(&self, config: &Config) -> Vec<Finding> {
+        let mut findings = Vec::new();
+
+        walk(config, &mut |directive, parents| {
+            if directive.name() != "proxy_pass" {
+                return;
+            }
+            let Some(parent) = parents.last() else {
+                return;
+            };
+            let place = if is_regex_location(parent) {
+                "a regex location"
+            } else if parent.name() == "location"
+                && parent.first_arg().is_some_and(|uri| uri.starts_with('@'))
+            {
+                "a named location"
+            } else if parent.name() == "if" {
+                "an if block"
+            } else if parent.name() == "limit_except" {
+                "a limit_except block"
+            } else {
+                return;
+            };
+            let Some(target) = directive.first_arg() else {
+                return;
+            };
+            let Some((authority, Some(uri))) = split_target(&target) else {
+                return;
+            };
+            let scheme = target.split("://").next().unwrap_or("http");
+
+            findings.push(
+                Finding::new(
+                    self.id(),
+                    Severity::Error,
+                    format!(
+                        "proxy_pass {target} has a URI part ({uri}) inside {place}; nginx \
+                         rejects this configuration"
+                    ),
+                )
+                .at(directive)
+                .with_help(format!(
+                    "Use \"proxy_pass {scheme}://{authority};\" and rewrite the URI first, e.g. \
+                     \"rewrite ^/old/(.*)$ {}$1 break;\"",
+                    if uri.ends_with('/') {
+                        uri.to_string()
+                    } else {
+                        format!("{uri}/")
+                    }
+                ))
+                .with_doc_url(PROXY_PASS_URL),
+            );
+        });
+
+        findings
+    }
+}
+
+/// Reports `root` inside an `if` block.
+///
+/// The document root should not depend on an `if`; the nested location
+/// `if` creates does not inherit the handlers of its parent, and the
+/// intended fallback rarely works.
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::{lint::{rules::RootInIf, Linter}, parse};
+///
+/// let config = parse(r"
+/// http { server { location / { if ($host = a.example) { root /srv/a; } } } }
+/// ")?;
+/// let findings = Linter::new().with_rule(RootInIf).check(&config);
+///
+/// assert_eq!(findings[0].rule, "root_in_if");
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RootInIf;
+
+impl Rule for RootInIf {
+    fn id(&self) -> &'static str {
+        "root_in_if"
+    }
+
+    fn description(&self) -> &'static str {
+        "root set inside an if block"
+    }
+
+    fn check(&self, config: &Config) -> Vec<Finding> {
+        let mut findings = Vec::new();
+
+        walk(config, &mut |directive, parents| {
+            if directive.name() != "root" || !parents.last().is_some_and(|p| p.name() == "if") {
+                return;
+            }
+            findings.push(
+                Finding::new(
+                    self.id(),
+                    Severity::Warning,
+                    "root is set inside an if block",
+                )
+                .at(directive)
+                .with_help(
+                    "Set root once per server or location; to vary it, map the condition to \
+                     a variable (map $host $site_root { ... }) and use \"root $site_root;\"",
+                )
+                .with_doc_url(PITFALLS_URL),
+            );
+        });
+
+        findings
+    }
+}
+
+/// Reports `proxy_pass` to a literal host name that is not an `upstream`.
+///
+/// nginx resolves such names once at startup and keeps the addresses until
+/// the next reload, so a backend whose DNS record changes (cloud load
+/// balancers, containers) stops receiving traffic. Putting the name in a
+/// variable with a `resolver` in scope makes nginx re-resolve it.
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::{lint::{rules::ProxyPassDnsCache, Linter}, parse};
+///
+/// let config = parse(r"
+/// http { server { location / { proxy_pass http://api.internal.example:8080; } } }
+/// ")?;
+/// let findings = Linter::new().with_rule(ProxyPassDnsCache).check(&config);
+///
+/// assert_eq!(findings[0].rule, "proxy_pass_dns_cache");
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProxyPassDnsCache;
+
+impl Rule for ProxyPassDnsCache {
+    fn id(&self) -> &'static str {
+        "proxy_pass_dns_cache"
+    }
+
+    fn description(&self) -> &'static str {
+        "proxy_pass host name resolved only at startup"
+    }
+
+    fn check(&self, config: &Config) -> Vec<Finding> {
+        let upstreams: Vec<String> = config
+            .find_directives_recursive("upstream")
+            .iter()
+            .filter_map(|u| u.first_arg())
+            .collect();
+        let mut findings = Vec::new();
+
+        walk(config, &mut |directive, _| {
+            if directive.name() != "proxy_pass" {
+                return;
+            }
+            let Some(target) = directive.first_arg() else {
+                return;
+            };
+            let Some((authority, _)) = split_target(&target) else {
+                return;
+            };
+            let host = host_of(authority);
+            if host.parse::<IpAddr>().is_ok()
+                || host == "localhost"
+                || upstreams.iter().any(|u| u == host)
+            {
+                return;
+            }
+
+            let scheme = target.split("://").next().unwrap_or("http");
+            findings.push(
+                Finding::new(
+                    self.id(),
+                    Severity::Info,
+                    format!("{host} is resolved once at startup and cached until the next reload"),
+                )
+                .at(directive)
+                .with_help(format!(
+                    "If the address can change, define a resolver and use a variable: \
+                     \"resolver 127.0.0.53 valid=30s; set $backend {authority}; \
+                     proxy_pass {scheme}://$backend;\""
+                ))
+                .with_doc_url(PROXY_PASS_URL),
+            );
+        });
+
+        findings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    fn lines(rule: &dyn Rule, text: &str) -> Vec<Option<usize>> {
+        rule.check(&parse(text).unwrap())
+            .iter()
+            .map(Finding::line)
+            .collect()
+    }
+
+    #[test]
+    fn test_if_is_evil() {
+        let text = r#"
+http {
+    server {
+        if ($host = old.example) { add_header X-Old 1; }
+        location / {
+            if ($request_method = POST) { return 405; }
+            if ($uri ~ "^/old/") { rewrite ^/old/(.*)$ /new/$1 last; }
+            if ($http_user_agent ~ bot) { set $bot 1; proxy_pass http://bots; }
+        }
+    }
+}
+"#;
+        assert_eq!(lines(&IfIsEvil, text), vec![Some(8)]);
+        let findings = IfIsEvil.check(&parse(text).unwrap());
+        assert!(findings[0].message.contains("set, proxy_pass"));
+        assert_eq!(findings[0].doc_url.as_deref(), Some(IF_IS_EVIL_URL));
+    }
+
+    #[test]
+    fn test_proxy_pass_regex_uri() {
+        let text = r"
+http {
+    server {
+        location ~ ^/api/ { proxy_pass http://backend/v1/; }
+        location ~* \.php$ { proxy_pass http://php; }
+        location /plain { proxy_pass http://backend/v1/; }
+        location @fallback { proxy_pass http://backend/; }
+        location /cond { if ($arg_x) { proxy_pass https://[::1]:8443/x; } }
+        location ~ ^/var/ { proxy_pass http://backend$request_uri; }
+    }
+}
+";
+        assert_eq!(
+            lines(&ProxyPassRegexUri, text),
+            vec![Some(4), Some(7), Some(8)]
+        );
+        let findings = ProxyPassRegexUri.check(&parse(text).unwrap());
+        assert!(findings[0]
+            .help
+            .as_deref()
+            .unwrap()
+            .contains("http://backend;"));
+    }
+
+    #[test]
+    fn test_root_in_if() {
+        let text = r"
+http {
+    server {
+        root /srv/default;
+        if ($host = a.example) { root /srv/a; }
+    }
+}
+";
+        assert_eq!(lines(&RootInIf, text), vec![Some(5)]);
+    }
+
+    #[test]
+    fn test_proxy_pass_dns_cache() {
+        let text = r"
+http {
+    upstream app { server app.internal:8080; }
+    server {
+        location /a { proxy_pass http://app; }
+        location /b { proxy_pass http://127.0.0.1:8080; }
+        location /c { proxy_pass http://[::1]; }
+        location /d { proxy_pass https://api.example.com/v1/; }
+        location /e { set $backend api.example.com; proxy_pass http://$backend; }
+        location /f { proxy_pass http://localhost:3000; }
+        location /g { proxy_pass http://unix:/run/app.sock; }
+    }
+}
+";
+        assert_eq!(lines(&ProxyPassDnsCache, text), vec![Some(8)]);
+        let findings = ProxyPassDnsCache.check(&parse(text).unwrap());
+        assert!(findings[0]
+            .help
+            .as_deref()
+            .unwrap()
+            .contains("proxy_pass https://$backend;"));
+    }
+}
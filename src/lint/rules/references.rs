@@ -0,0 +1,326 @@
+//! Cross-references between named entities
+
+use super::pitfalls::{host_of, split_target};
+use crate::ast::{Config, Directive, Value};
+use crate::docs::edit_distance;
+use crate::lint::{walk, Finding, Rule, Severity};
+use std::net::IpAddr;
+
+/// Reports references to names defined nowhere in the configuration:
+///
+/// - `access_log` format names without a `log_format`
+/// - `proxy_pass http://name` without `upstream name` when `name` is not a
+///   qualified host name
+/// - `limit_req zone=` and `limit_conn` zones without `limit_req_zone` or
+///   `limit_conn_zone`
+/// - `proxy_cache` and `fastcgi_cache` zones without a matching
+///   `keys_zone=`
+/// - `error_page ... @name` without `location @name` in the server
+///
+/// The help names the closest definition and lists the defined ones.
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::{lint::{rules::UndefinedReference, Linter}, parse};
+///
+/// let config = parse(r"
+/// http {
+///     limit_req_zone $binary_remote_addr zone=api:10m rate=5r/s;
+///     server { location / { limit_req zone=apl burst=10; } }
+/// }
+/// ")?;
+/// let findings = Linter::new().with_rule(UndefinedReference).check(&config);
+///
+/// assert_eq!(findings[0].message, "limit_req zone \"apl\" is not defined");
+/// assert_eq!(findings[0].help.as_deref(), Some("Did you mean \"api\"? Defined: api"));
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UndefinedReference;
+
+/// Names defined across the configuration
+#[derive(Debug, Default)]
+struct Definitions {
+    log_formats: Vec<String>,
+    upstreams: Vec<String>,
+    stream_upstreams: Vec<String>,
+    req_zones: Vec<String>,
+    conn_zones: Vec<String>,
+    proxy_caches: Vec<String>,
+    fastcgi_caches: Vec<String>,
+}
+
+impl Definitions {
+    fn collect(config: &Config) -> Self {
+        let mut defs = Self {
+            log_formats: vec!["combined".to_string()],
+            ..Self::default()
+        };
+        walk(config, &mut |directive, parents| {
+            let list = match directive.name() {
+                "log_format" => &mut defs.log_formats,
+                "upstream" if parents.iter().any(|p| p.name() == "stream") => {
+                    &mut defs.stream_upstreams
+                }
+                "upstream" => &mut defs.upstreams,
+                "limit_req_zone" => &mut defs.req_zones,
+                "limit_conn_zone" => &mut defs.conn_zones,
+                "proxy_cache_path" => &mut defs.proxy_caches,
+                "fastcgi_cache_path" => &mut defs.fastcgi_caches,
+                _ => return,
+            };
+            let name = match directive.name() {
+                "log_format" | "upstream" => directive.first_arg(),
+                "proxy_cache_path" | "fastcgi_cache_path" => zone_param(directive, "keys_zone="),
+                _ => zone_param(directive, "zone="),
+            };
+            list.extend(name);
+        });
+        defs
+    }
+}
+
+/// Name of a `zone=NAME:SIZE` style parameter
+fn zone_param(directive: &Directive, prefix: &str) -> Option<String> {
+    directive.args().iter().find_map(|arg| {
+        let value = arg.unquoted();
+        let zone = value.strip_prefix(prefix)?;
+        Some(zone.split(':').next().unwrap_or(zone).to_string())
+    })
+}
+
+impl Rule for UndefinedReference {
+    fn id(&self) -> &'static str {
+        "undefined_reference"
+    }
+
+    fn description(&self) -> &'static str {
+        "References to undefined log formats, upstreams, zones and named locations"
+    }
+
+    fn check(&self, config: &Config) -> Vec<Finding> {
+        let defs = Definitions::collect(config);
+        let mut findings = Vec::new();
+
+        walk(config, &mut |directive, parents| {
+            let args: Vec<String> = directive.args().iter().map(Value::unquoted).collect();
+            let (what, name, defined): (&str, String, Vec<String>) = match directive.name() {
+                "access_log" => match args.get(1) {
+                    Some(format) if !format.contains('=') && args[0] != "off" => {
+                        ("log format", format.clone(), defs.log_formats.clone())
+                    }
+                    _ => return,
+                },
+                "proxy_pass" => {
+                    let stream = parents.iter().any(|p| p.name() == "stream");
+                    let Some(host) = proxy_host(&args, stream) else {
+                        return;
+                    };
+                    let upstreams = if stream {
+                        &defs.stream_upstreams
+                    } else {
+                        &defs.upstreams
+                    };
+                    if upstreams.contains(&host) || is_host_name(&host) {
+                        return;
+                    }
+                    let finding = self.undefined(directive, "upstream", &host, upstreams);
+                    // A bare name may still be a host name resolved by DNS
+                    // (a container or service name)
+                    let severity = if closest(&host, upstreams).is_some() {
+                        Severity::Warning
+                    } else {
+                        Severity::Info
+                    };
+                    findings.push(Finding {
+                        severity,
+                        ..finding
+                    });
+                    return;
+                }
+                "limit_req" => match zone_param(directive, "zone=") {
+                    Some(zone) => ("limit_req zone", zone, defs.req_zones.clone()),
+                    None => return,
+                },
+                "limit_conn" => match args.first() {
+                    Some(zone) if !zone.contains('$') => {
+                        ("limit_conn zone", zone.clone(), defs.conn_zones.clone())
+                    }
+                    _ => return,
+                },
+                "proxy_cache" => match cache_zone(&args) {
+                    Some(zone) => ("proxy_cache zone", zone, defs.proxy_caches.clone()),
+                    None => return,
+                },
+                "fastcgi_cache" => match cache_zone(&args) {
+                    Some(zone) => ("fastcgi_cache zone", zone, defs.fastcgi_caches.clone()),
+                    None => return,
+                },
+                "error_page" => {
+                    let Some(target) = args.last().and_then(|a| a.strip_prefix('@')) else {
+                        return;
+                    };
+                    let Some(server) = parents.iter().rev().find(|p| p.name() == "server") else {
+                        return;
+                    };
+                    (
+                        "named location",
+                        format!("@{target}"),
+                        named_locations(server),
+                    )
+                }
+                _ => return,
+            };
+            if !defined.contains(&name) {
+                findings.push(self.undefined(directive, what, &name, &defined));
+            }
+        });
+
+        findings
+    }
+}
+
+impl UndefinedReference {
+    fn undefined(
+        self,
+        directive: &Directive,
+        what: &str,
+        name: &str,
+        defined: &[String],
+    ) -> Finding {
+        let help = match closest(name, defined) {
+            Some(candidate) => format!(
+                "Did you mean \"{candidate}\"? Defined: {}",
+                defined.join(", ")
+            ),
+            None if defined.is_empty() => {
+                format!("No {what} is defined; define \"{name}\" or remove the reference")
+            }
+            None => format!("Define \"{name}\" or use one of: {}", defined.join(", ")),
+        };
+        Finding::new(
+            self.id(),
+            Severity::Error,
+            format!("{what} \"{name}\" is not defined"),
+        )
+        .at(directive)
+        .with_help(help)
+    }
+}
+
+/// Zone of a `proxy_cache` or `fastcgi_cache`, `None` for `off` and
+/// variables
+fn cache_zone(args: &[String]) -> Option<String> {
+    args.first()
+        .filter(|zone| *zone != "off" && !zone.contains('$'))
+        .cloned()
+}
+
+/// Host a `proxy_pass` points at, `None` for variables, sockets and IPs
+fn proxy_host(args: &[String], stream: bool) -> Option<String> {
+    let target = args.first()?;
+    let authority = if stream {
+        (!target.contains('$') && !target.starts_with("unix:")).then_some(target.as_str())?
+    } else {
+        split_target(target)?.0
+    };
+    let host = host_of(authority);
+    (host.parse::<IpAddr>().is_err() && !host.is_empty()).then(|| host.to_string())
+}
+
+/// Whether a host is a qualified name DNS resolves rather than an
+/// upstream name
+fn is_host_name(host: &str) -> bool {
+    host.contains('.') || host == "localhost"
+}
+
+/// Closest defined name within a few edits
+fn closest<'a>(name: &str, defined: &'a [String]) -> Option<&'a str> {
+    let max = (name.len() / 3).max(2);
+    defined
+        .iter()
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= max)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate.as_str())
+}
+
+/// `location @name` blocks anywhere in a server, with the `@`
+fn named_locations(server: &Directive) -> Vec<String> {
+    server
+        .find_recursive("location")
+        .into_iter()
+        .filter_map(|location| location.first_arg().filter(|name| name.starts_with('@')))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_undefined_references() {
+        let config = parse(
+            r"
+http {
+    log_format main '$remote_addr $status';
+    upstream backend { server 10.0.0.1:8080; }
+    limit_conn_zone $binary_remote_addr zone=addr:10m;
+    proxy_cache_path /var/cache/nginx keys_zone=static:10m;
+    access_log /var/log/a.log main;
+    access_log /var/log/b.log mian;
+    access_log /var/log/c.log combined buffer=32k;
+    server {
+        error_page 404 @notfound;
+        error_page 500 @oops;
+        location @notfound { return 404; }
+        location /a { proxy_pass http://backnd; limit_conn addr 10; }
+        location /b { proxy_pass http://api.example.com; proxy_cache statik; }
+        location /c { proxy_pass http://app:3000; limit_req zone=one; }
+        location /d { proxy_pass http://backend/; proxy_cache off; }
+    }
+}
+stream {
+    upstream dns { server 10.0.0.53:53; }
+    server { listen 53 udp; proxy_pass dns; }
+}
+",
+        )
+        .unwrap();
+        let findings = UndefinedReference.check(&config);
+        let summary: Vec<_> = findings
+            .iter()
+            .map(|f| (f.line().unwrap(), f.severity, f.message.as_str()))
+            .collect();
+
+        assert_eq!(
+            summary,
+            vec![
+                (8, Severity::Error, "log format \"mian\" is not defined"),
+                (
+                    12,
+                    Severity::Error,
+                    "named location \"@oops\" is not defined"
+                ),
+                (14, Severity::Warning, "upstream \"backnd\" is not defined"),
+                (
+                    15,
+                    Severity::Error,
+                    "proxy_cache zone \"statik\" is not defined"
+                ),
+                (16, Severity::Info, "upstream \"app\" is not defined"),
+                (16, Severity::Error, "limit_req zone \"one\" is not defined"),
+            ]
+        );
+        assert_eq!(
+            findings[0].help.as_deref(),
+            Some("Did you mean \"main\"? Defined: combined, main")
+        );
+        assert_eq!(
+            findings[5].help.as_deref(),
+            Some("No limit_req zone is defined; define \"one\" or remove the reference")
+        );
+    }
+}
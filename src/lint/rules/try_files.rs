@@ -0,0 +1,187 @@
+//! `try_files` validation
+
+use crate::ast::{Config, Directive, Value};
+use crate::lint::{walk, Finding, Rule, Severity};
+use crate::types::{TryFiles, TryFilesFallback};
+
+/// Reports `try_files` directives nginx rejects or that do not do what
+/// they appear to:
+///
+/// - fewer than two parameters, or a `=code` fallback that is not a status
+///   code
+/// - a named location fallback (`@app`) that no `location @app` in the
+///   server defines
+/// - a fallback URI that does not start with `/`
+/// - `try_files` next to `proxy_pass` in one location: the files are looked
+///   up under `root` first, and the request is proxied only when the last
+///   file check succeeds
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::{lint::{rules::TryFilesUsage, Linter}, parse};
+///
+/// let config = parse("http { server { location / { try_files $uri @app; } } }")?;
+/// let findings = Linter::new().with_rule(TryFilesUsage).check(&config);
+///
+/// assert!(findings[0].message.contains("@app"));
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TryFilesUsage;
+
+impl Rule for TryFilesUsage {
+    fn id(&self) -> &'static str {
+        "try_files"
+    }
+
+    fn description(&self) -> &'static str {
+        "try_files fallbacks, missing named locations and try_files with proxy_pass"
+    }
+
+    fn check(&self, config: &Config) -> Vec<Finding> {
+        let mut findings = Vec::new();
+
+        walk(config, &mut |directive, parents| {
+            if directive.name() != "try_files" {
+                return;
+            }
+            let args: Vec<String> = directive.args().iter().map(Value::unquoted).collect();
+            let Some(try_files) = TryFiles::from_args(&args) else {
+                findings.push(
+                    Finding::new(
+                        self.id(),
+                        Severity::Error,
+                        "try_files needs at least one file and a fallback",
+                    )
+                    .at(directive)
+                    .with_help("Add a fallback such as =404 or @backend as the last parameter"),
+                );
+                return;
+            };
+
+            let server = parents.iter().rev().find(|p| p.name() == "server");
+            findings.extend(self.check_fallback(directive, &try_files.fallback, server.copied()));
+
+            let block = parents.last().copied();
+            if block.is_some_and(|b| {
+                b.name() == "location" && !b.find_children("proxy_pass").is_empty()
+            }) {
+                findings.push(
+                    Finding::new(
+                        self.id(),
+                        Severity::Warning,
+                        "try_files and proxy_pass in the same location: files are looked up \
+                         under root before anything is proxied",
+                    )
+                    .at(directive)
+                    .with_help(
+                        "Move proxy_pass to a named location and use it as the fallback: \
+                         try_files $uri @backend;",
+                    ),
+                );
+            }
+        });
+
+        findings
+    }
+}
+
+impl TryFilesUsage {
+    fn check_fallback(
+        self,
+        directive: &Directive,
+        fallback: &TryFilesFallback,
+        server: Option<&Directive>,
+    ) -> Option<Finding> {
+        let (severity, message, help) = match fallback {
+            TryFilesFallback::Code(_) => return None,
+            TryFilesFallback::InvalidCode(code) => (
+                Severity::Error,
+                format!("invalid try_files status code {code}"),
+                "Use =code with a status code such as =404".to_string(),
+            ),
+            TryFilesFallback::NamedLocation(name) => {
+                let server = server?;
+                if named_locations(server).contains(&name.as_str()) {
+                    return None;
+                }
+                (
+                    Severity::Error,
+                    format!("try_files falls back to @{name}, but no \"location @{name}\" exists in this server"),
+                    format!("Define \"location @{name}\" in the server or fix the name"),
+                )
+            }
+            TryFilesFallback::Uri(uri) => {
+                if uri.starts_with('/') || uri.starts_with('$') {
+                    return None;
+                }
+                (
+                    Severity::Warning,
+                    format!("try_files fallback \"{uri}\" is not an absolute URI"),
+                    format!("Use \"/{uri}\", a named location or =404"),
+                )
+            }
+        };
+
+        Some(
+            Finding::new(self.id(), severity, message)
+                .at(directive)
+                .with_help(help),
+        )
+    }
+}
+
+/// Names of the `location @name` blocks anywhere in a server
+fn named_locations(server: &Directive) -> Vec<&str> {
+    server
+        .find_recursive("location")
+        .into_iter()
+        .filter_map(|location| location.args().first()?.as_str().strip_prefix('@'))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_try_files() {
+        let config = parse(
+            r"
+http {
+    server {
+        try_files $uri;
+        location / { try_files $uri $uri/ @app; }
+        location /x { try_files $uri @missing; }
+        location /y { try_files $uri =40x; }
+        location /z { try_files $uri index.html; }
+        location /api {
+            try_files $uri $uri/ =404;
+            proxy_pass http://backend;
+        }
+        location /ok { try_files $uri /index.php?$args; }
+        location @app { proxy_pass http://app; }
+    }
+}
+",
+        )
+        .unwrap();
+        let findings = TryFilesUsage.check(&config);
+
+        let found: Vec<_> = findings.iter().map(|f| (f.line(), f.severity)).collect();
+        assert_eq!(
+            found,
+            vec![
+                (Some(4), Severity::Error),
+                (Some(6), Severity::Error),
+                (Some(7), Severity::Error),
+                (Some(8), Severity::Warning),
+                (Some(10), Severity::Warning),
+            ]
+        );
+        assert!(findings[1].message.contains("@missing"));
+        assert!(findings[4].message.contains("proxy_pass"));
+    }
+}
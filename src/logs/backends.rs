@@ -0,0 +1,607 @@
+//! Upstream backends seen in access logs
+//!
+//! When a log format records `$upstream_addr`, and ideally
+//! `$upstream_status`, every line names the backends nginx tried for the
+//! request. Those are counted per backend and compared with the servers the
+//! configuration lists in `upstream` blocks and `*_pass` directives. A
+//! backend still receiving traffic without being configured usually means
+//! nginx runs an older configuration than the one on disk, or resolved a
+//! hostname once at startup and keeps using an address DNS no longer
+//! returns.
+//!
+//! Finding rule:
+//!
+//! - `stale_backend`: a logged backend is in no upstream block or pass
+//!   target of the configuration
+
+use super::reader::{read_tail, LogParser, LogRecord};
+use crate::ast::Config;
+use crate::extract;
+use crate::lint::{walk, Finding, Severity};
+use std::collections::HashMap;
+use std::net::{SocketAddr, ToSocketAddrs};
+
+/// Directives that pass requests to a backend
+const PASS_DIRECTIVES: &[&str] = &[
+    "proxy_pass",
+    "fastcgi_pass",
+    "grpc_pass",
+    "uwsgi_pass",
+    "scgi_pass",
+    "memcached_pass",
+];
+
+/// Options for [`backend_stats`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BackendOptions {
+    /// Lines read from the end of each log
+    pub lines: usize,
+
+    /// Only count lines logged at or after this time, in seconds since the
+    /// Unix epoch; lines without a timestamp are then skipped
+    pub since: Option<f64>,
+
+    /// Resolve configured hostnames, so backends logged by address can be
+    /// matched against them. Without it, backends that match no literal
+    /// address are [`Unverified`](BackendState::Unverified) when the
+    /// configuration names servers by hostname.
+    pub resolve: bool,
+}
+
+impl Default for BackendOptions {
+    fn default() -> Self {
+        Self {
+            lines: 10_000,
+            since: None,
+            resolve: false,
+        }
+    }
+}
+
+/// Whether a logged backend is part of the configuration
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum BackendState {
+    /// Listed in an upstream block or pass target
+    Configured,
+
+    /// Listed nowhere in the configuration
+    NotConfigured,
+
+    /// Not matched, but the configuration names backends by unresolved
+    /// hostname or through variables, so it may still be one of them
+    Unverified,
+}
+
+impl std::fmt::Display for BackendState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Configured => write!(f, "configured"),
+            Self::NotConfigured => write!(f, "not configured"),
+            Self::Unverified => write!(f, "unverified"),
+        }
+    }
+}
+
+/// Traffic of one backend address
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BackendStats {
+    /// Address as logged, `host:port` or `unix:/path`; an upstream name
+    /// when nginx found no live server in it
+    pub address: String,
+
+    /// Upstream blocks, or pass directives, that list the address
+    pub upstreams: Vec<String>,
+
+    /// Whether the address is part of the configuration
+    pub state: BackendState,
+
+    /// Requests passed to the backend, retries included
+    pub requests: usize,
+
+    /// Share of all logged backend requests
+    pub share: f64,
+
+    /// Requests the backend answered with a 4xx status, when the format
+    /// logs `$upstream_status`
+    pub client_errors: Option<usize>,
+
+    /// Requests the backend answered with a 5xx status, or that failed
+    /// with no response, when the format logs `$upstream_status`
+    pub server_errors: Option<usize>,
+}
+
+impl BackendStats {
+    /// Share of the backend's requests that got a 5xx or no response
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn server_error_rate(&self) -> Option<f64> {
+        let errors = self.server_errors?;
+        (self.requests > 0).then(|| errors as f64 / self.requests as f64)
+    }
+}
+
+/// Result of [`backend_stats`]
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BackendReport {
+    /// Backends by number of requests, busiest first
+    pub backends: Vec<BackendStats>,
+
+    /// Counted lines that passed the request to a backend
+    pub proxied_lines: usize,
+
+    /// Counted lines served without a backend (`$upstream_addr` is `-`)
+    pub local_lines: usize,
+
+    /// Access logs whose format records `$upstream_addr`
+    pub logs: Vec<std::path::PathBuf>,
+
+    /// Backends receiving traffic that are not configured
+    /// (`stale_backend`)
+    pub findings: Vec<Finding>,
+}
+
+/// Counts per backend before the configuration is consulted
+#[derive(Default)]
+struct Counts {
+    requests: usize,
+    client_errors: usize,
+    server_errors: usize,
+}
+
+/// Summarise the backends logged in `$upstream_addr` by every access log
+/// whose format records it
+///
+/// # Examples
+///
+/// ```no_run
+/// use nginx_discovery::logs::{backend_stats, BackendOptions, BackendState};
+///
+/// let config = nginx_discovery::parse(r#"
+/// http {
+///     log_format upstreams '$remote_addr "$request" $status "$upstream_addr" "$upstream_status"';
+///     access_log /var/log/nginx/access.log upstreams;
+///     upstream app { server 10.0.0.1:8080; server 10.0.0.2:8080; }
+///     server { listen 80; location / { proxy_pass http://app; } }
+/// }
+/// "#)?;
+/// let report = backend_stats(&config, &BackendOptions::default());
+/// for backend in &report.backends {
+///     if backend.state == BackendState::NotConfigured {
+///         println!("{} still gets {} requests", backend.address, backend.requests);
+///     }
+/// }
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+#[must_use]
+pub fn backend_stats(config: &Config, options: &BackendOptions) -> BackendReport {
+    let formats = extract::log_formats(config).unwrap_or_default();
+    let configured = Configured::from_config(config, options.resolve);
+    let mut report = BackendReport::default();
+    let mut counts: HashMap<String, Counts> = HashMap::new();
+    let mut with_status = false;
+
+    for log in extract::access_logs(config).unwrap_or_default() {
+        let parser = LogParser::for_log(&log, &formats);
+        if !parser.variables().any(|v| v == "upstream_addr") {
+            continue;
+        }
+        with_status |= parser.variables().any(|v| v == "upstream_status");
+
+        for path in log.expanded_paths() {
+            if report.logs.contains(&path) {
+                continue;
+            }
+            let lines = read_tail(&path, options.lines).unwrap_or_default();
+            report.logs.push(path);
+
+            for record in lines.iter().filter_map(|line| parser.parse(line)) {
+                if let Some(since) = options.since {
+                    if record.timestamp().map_or(true, |t| t < since) {
+                        continue;
+                    }
+                }
+                let attempts = attempts(&record);
+                if attempts.is_empty() {
+                    report.local_lines += 1;
+                    continue;
+                }
+                report.proxied_lines += 1;
+                for (address, status) in attempts {
+                    let key = if configured.upstream_names.iter().any(|n| n == address) {
+                        address.to_string()
+                    } else {
+                        normalize(address, 80)
+                    };
+                    let entry = counts.entry(key).or_default();
+                    entry.requests += 1;
+                    match status {
+                        Some(status) if status.starts_with('4') => entry.client_errors += 1,
+                        Some(status) if status.starts_with('5') => entry.server_errors += 1,
+                        // Logged when the backend could not be reached
+                        Some("-") => entry.server_errors += 1,
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+
+    let total: usize = counts.values().map(|c| c.requests).sum();
+    #[allow(clippy::cast_precision_loss)]
+    let mut backends: Vec<BackendStats> = counts
+        .into_iter()
+        .map(|(address, counts)| {
+            let upstreams = configured.sources(&address);
+            let state = if !upstreams.is_empty() {
+                BackendState::Configured
+            } else if configured.complete {
+                BackendState::NotConfigured
+            } else {
+                BackendState::Unverified
+            };
+            BackendStats {
+                address,
+                upstreams,
+                state,
+                requests: counts.requests,
+                share: counts.requests as f64 / total as f64,
+                client_errors: with_status.then_some(counts.client_errors),
+                server_errors: with_status.then_some(counts.server_errors),
+            }
+        })
+        .collect();
+    backends.sort_by(|a, b| {
+        b.requests
+            .cmp(&a.requests)
+            .then_with(|| a.address.cmp(&b.address))
+    });
+
+    report.findings = backends
+        .iter()
+        .filter(|b| b.state == BackendState::NotConfigured)
+        .map(|b| {
+            Finding::new(
+                "stale_backend",
+                Severity::Warning,
+                format!(
+                    "backend {} received {} logged requests but is in no upstream block or \
+                     pass target",
+                    b.address, b.requests
+                ),
+            )
+            .with_help(
+                "Reload nginx if the configuration changed; for backends named by hostname, \
+                 use a resolver with `resolve` or a variable in the pass target so DNS \
+                 changes are picked up",
+            )
+        })
+        .collect();
+    report.backends = backends;
+    report
+}
+
+/// Backends tried for a request with their status, in order
+///
+/// `$upstream_addr` separates servers tried in one upstream with `, ` and
+/// internal redirects to another with ` : `; `$upstream_status` follows
+/// the same layout.
+fn attempts<'a>(record: &'a LogRecord) -> Vec<(&'a str, Option<&'a str>)> {
+    let Some(addresses) = record.get("upstream_addr") else {
+        return Vec::new();
+    };
+    let split = |value: &'a str| -> Vec<&'a str> {
+        value
+            .split(" : ")
+            .flat_map(|group| group.split(','))
+            .map(str::trim)
+            .collect()
+    };
+    let statuses = record.get("upstream_status").map(split).unwrap_or_default();
+    split(addresses)
+        .into_iter()
+        .enumerate()
+        .filter(|(_, address)| !address.is_empty() && *address != "-")
+        .map(|(i, address)| (address, statuses.get(i).copied()))
+        .collect()
+}
+
+/// `host:port` with the default port added and IP addresses in canonical
+/// form; `unix:` paths unchanged
+fn normalize(address: &str, default_port: u16) -> String {
+    if address.starts_with("unix:") {
+        return address.to_string();
+    }
+    if let Ok(addr) = address.parse::<SocketAddr>() {
+        return addr.to_string();
+    }
+    // A bare IPv6 address also ends in `:<digits>`
+    let has_port = address.rsplit_once(':').is_some_and(|(host, port)| {
+        !host.is_empty() && !host.contains(':') && port.parse::<u16>().is_ok()
+    });
+    if has_port {
+        address.to_string()
+    } else if address.contains(':') && !address.starts_with('[') {
+        // Bare IPv6 address
+        format!("[{address}]:{default_port}")
+    } else {
+        format!("{address}:{default_port}")
+    }
+}
+
+/// Backend addresses the configuration lists
+struct Configured {
+    /// Normalized address to the upstreams or directives listing it
+    addresses: HashMap<String, Vec<String>>,
+
+    /// Upstream block names, logged when no server of a block is available
+    upstream_names: Vec<String>,
+
+    /// Whether every configured backend is known by address: no
+    /// unresolved hostnames and no pass target built from variables
+    complete: bool,
+}
+
+impl Configured {
+    fn from_config(config: &Config, resolve: bool) -> Self {
+        let mut configured = Self {
+            addresses: HashMap::new(),
+            upstream_names: Vec::new(),
+            complete: true,
+        };
+
+        let upstreams = extract::upstreams(config).unwrap_or_default();
+        for upstream in &upstreams {
+            configured.upstream_names.push(upstream.name.clone());
+            for server in &upstream.servers {
+                configured.add(&normalize(&server.address, 80), &upstream.name, resolve);
+            }
+        }
+
+        walk(config, &mut |directive, _| {
+            let name = directive.name();
+            if !PASS_DIRECTIVES.contains(&name) {
+                return;
+            }
+            let Some(target) = directive.first_arg() else {
+                return;
+            };
+            if target.contains('$') {
+                configured.complete = false;
+                return;
+            }
+            if let Some(address) = pass_address(&target) {
+                if !configured.upstream_names.contains(&address.0) {
+                    configured.add(&normalize(&address.0, address.1), name, resolve);
+                }
+            }
+        });
+        configured
+    }
+
+    fn add(&mut self, address: &str, source: &str, resolve: bool) {
+        let mut push = |address: String| {
+            let sources = self.addresses.entry(address).or_default();
+            if !sources.iter().any(|s| s == source) {
+                sources.push(source.to_string());
+            }
+        };
+
+        let literal = address.starts_with("unix:") || address.parse::<SocketAddr>().is_ok();
+        if !literal {
+            match resolve.then(|| address.to_socket_addrs()) {
+                Some(Ok(resolved)) => resolved.for_each(|addr| push(addr.to_string())),
+                _ => self.complete = false,
+            }
+        }
+        push(address.to_string());
+    }
+
+    fn sources(&self, address: &str) -> Vec<String> {
+        if self.upstream_names.iter().any(|name| name == address) {
+            return vec![address.to_string()];
+        }
+        self.addresses.get(address).cloned().unwrap_or_default()
+    }
+}
+
+/// Address and default port of a pass target: `http://10.0.0.1:8080/api`,
+/// `127.0.0.1:9000`, `http://unix:/run/app.sock:/`
+fn pass_address(target: &str) -> Option<(String, u16)> {
+    let (scheme, rest) = target.split_once("://").unwrap_or(("", target));
+    let port = if matches!(scheme, "https" | "grpcs") {
+        443
+    } else {
+        80
+    };
+    if let Some(path) = rest.strip_prefix("unix:") {
+        let path = path.split(':').next().unwrap_or(path);
+        return Some((format!("unix:{path}"), port));
+    }
+    let host = rest.split('/').next().filter(|h| !h.is_empty())?;
+    Some((host.to_string(), port))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+    use std::io::Write;
+
+    #[test]
+    fn test_backend_stats() {
+        let dir = tempfile::tempdir().unwrap();
+        let access = dir.path().join("access.log");
+        let mut file = std::fs::File::create(&access).unwrap();
+        for i in 0..10 {
+            let (addr, status) = match i % 5 {
+                0 => ("10.0.0.1:8080, 10.0.0.2:8080", "502, 200"),
+                1 | 2 => ("10.0.0.2:8080", "200"),
+                3 => ("10.0.0.9:8080", "200"),
+                _ => ("-", "-"),
+            };
+            writeln!(file, "\"GET / HTTP/1.1\" 200 \"{addr}\" \"{status}\"").unwrap();
+        }
+        writeln!(
+            file,
+            "\"GET /php HTTP/1.1\" 504 \"unix:/run/php.sock\" \"-\""
+        )
+        .unwrap();
+
+        let config = parse(&format!(
+            "http {{\n\
+             log_format upstreams '\"$request\" $status \"$upstream_addr\" \"$upstream_status\"';\n\
+             access_log {} upstreams;\n\
+             upstream app {{ server 10.0.0.1:8080; server 10.0.0.2:8080; }}\n\
+             server {{\n\
+                 listen 80;\n\
+                 location / {{ proxy_pass http://app; }}\n\
+                 location /php {{ fastcgi_pass unix:/run/php.sock; }}\n\
+             }}\n\
+             }}",
+            access.display()
+        ))
+        .unwrap();
+        let report = backend_stats(&config, &BackendOptions::default());
+
+        assert_eq!((report.proxied_lines, report.local_lines), (9, 2));
+        let addresses: Vec<_> = report.backends.iter().map(|b| b.address.as_str()).collect();
+        assert_eq!(
+            addresses,
+            [
+                "10.0.0.2:8080",
+                "10.0.0.1:8080",
+                "10.0.0.9:8080",
+                "unix:/run/php.sock"
+            ]
+        );
+
+        let second = &report.backends[1];
+        assert_eq!(second.upstreams, ["app"]);
+        assert_eq!((second.requests, second.server_errors), (2, Some(2)));
+        assert_eq!(second.server_error_rate(), Some(1.0));
+        assert_eq!(report.backends[0].requests, 6);
+        assert!((report.backends[0].share - 6.0 / 11.0).abs() < f64::EPSILON);
+
+        assert_eq!(report.backends[2].state, BackendState::NotConfigured);
+        assert_eq!(report.backends[3].upstreams, ["fastcgi_pass"]);
+        assert_eq!(report.backends[3].server_errors, Some(1));
+        assert_eq!(report.findings.len(), 1);
+        assert!(report.findings[0].message.contains("10.0.0.9:8080"));
+    }
+
+    #[test]
+    fn test_address_forms() {
+        assert_eq!(normalize("10.0.0.1", 80), "10.0.0.1:80");
+        assert_eq!(normalize("backend.internal", 443), "backend.internal:443");
+        assert_eq!(
+            normalize("backend.internal:8080", 80),
+            "backend.internal:8080"
+        );
+        assert_eq!(normalize("::1", 80), "[::1]:80");
+        assert_eq!(normalize("[0:0::1]:8080", 80), "[::1]:8080");
+        assert_eq!(normalize("unix:/run/app.sock", 80), "unix:/run/app.sock");
+
+        assert_eq!(
+            pass_address("https://api.internal/v1"),
+            Some(("api.internal".to_string(), 443))
+        );
+        assert_eq!(
+            pass_address("http://unix:/run/app.sock:/api"),
+            Some(("unix:/run/app.sock".to_string(), 80))
+        );
+        assert_eq!(
+            pass_address("127.0.0.1:9000"),
+            Some(("127.0.0.1:9000".to_string(), 80))
+        );
+        assert_eq!(pass_address("http:///"), None);
+    }
+
+    #[test]
+    fn test_attempts() {
+        let parser = LogParser::new("\"$upstream_addr\" \"$upstream_status\"");
+        let record = parser
+            .parse("\"10.0.0.1:80, 10.0.0.2:80 : app, -\" \"502, 504 : 200\"")
+            .unwrap();
+        // Retries, then an internal redirect; a status list shorter than
+        // the address list leaves the rest without status
+        assert_eq!(
+            attempts(&record),
+            vec![
+                ("10.0.0.1:80", Some("502")),
+                ("10.0.0.2:80", Some("504")),
+                ("app", Some("200")),
+            ]
+        );
+
+        let record = LogParser::new("$upstream_addr").parse("-").unwrap();
+        assert!(attempts(&record).is_empty());
+        let record = LogParser::new("$status").parse("200").unwrap();
+        assert!(attempts(&record).is_empty());
+    }
+
+    #[test]
+    fn test_unverified_backends() {
+        let dir = tempfile::tempdir().unwrap();
+        let access = dir.path().join("access.log");
+        std::fs::write(&access, "10.0.0.7:8080\napp\n10.0.0.7:8080\n").unwrap();
+        let plain = dir.path().join("plain.log");
+        std::fs::write(&plain, "10.0.0.8:8080\n").unwrap();
+
+        let config = parse(&format!(
+            "http {{\n\
+             log_format addr '$upstream_addr';\n\
+             access_log {} addr;\n\
+             access_log {};\n\
+             upstream app {{ server backend.internal:8080; }}\n\
+             server {{ location / {{ proxy_pass http://app; }} }}\n\
+             }}",
+            access.display(),
+            plain.display()
+        ))
+        .unwrap();
+        let report = backend_stats(&config, &BackendOptions::default());
+
+        // Logs whose format has no $upstream_addr are not read; without
+        // $upstream_status errors are unknown
+        assert_eq!(report.logs, vec![access]);
+        let backends: Vec<_> = report
+            .backends
+            .iter()
+            .map(|b| (b.address.as_str(), b.state, b.requests, b.server_errors))
+            .collect();
+        assert_eq!(
+            backends,
+            vec![
+                ("10.0.0.7:8080", BackendState::Unverified, 2, None),
+                // Logged when no server of the upstream was available
+                ("app", BackendState::Configured, 1, None),
+            ]
+        );
+        assert_eq!(report.backends[0].server_error_rate(), None);
+        assert_eq!(report.backends[0].state.to_string(), "unverified");
+
+        // A backend nginx may have resolved from a hostname is not reported
+        // as stale
+        assert!(report.findings.is_empty());
+    }
+
+    #[test]
+    fn test_variable_pass_target_is_incomplete() {
+        let config = parse(
+            "http {\n\
+             upstream app { server 10.0.0.1:80; }\n\
+             server { location / { proxy_pass http://$backend; } }\n\
+             }",
+        )
+        .unwrap();
+        let configured = Configured::from_config(&config, false);
+        assert!(!configured.complete);
+        assert_eq!(configured.sources("10.0.0.1:80"), ["app"]);
+        assert!(configured.sources("10.0.0.2:80").is_empty());
+    }
+}
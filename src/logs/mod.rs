@@ -0,0 +1,38 @@
+//! Reading the logs a configuration writes
+//!
+//! The configuration says where access logs go and what their lines look
+//! like. This module uses that to read them:
+//!
+//! - [`LogParser`] turns a `log_format` into a line parser, so fields are
+//!   looked up by variable name (`$status`, `$request_time`, ...) whatever
+//!   their order in the line
+//! - [`read_tail`] reads the last lines of a log without reading the whole
+//!   file
+//! - [`estimate_volume`] projects the daily volume of each access log
+//! - [`log_stats`] summarises the last lines of each access log: top
+//!   statuses, paths and clients, error rates and request time percentiles
+//! - [`backend_stats`] counts the backends logged in `$upstream_addr` and
+//!   flags those the configuration no longer lists
+//!
+//! ```
+//! use nginx_discovery::logs::LogParser;
+//!
+//! let parser = LogParser::new("$remote_addr [$time_local] \"$request\" $status $request_time");
+//! let record = parser
+//!     .parse("10.0.0.1 [10/Oct/2000:13:55:36 -0700] \"GET / HTTP/1.1\" 200 0.012")
+//!     .unwrap();
+//!
+//! assert_eq!(record.get("status"), Some("200"));
+//! assert_eq!(record.timestamp(), Some(971_211_336.0));
+//! ```
+
+mod backends;
+mod reader;
+mod stats;
+mod volume;
+
+pub use backends::{backend_stats, BackendOptions, BackendReport, BackendState, BackendStats};
+pub use reader::{read_tail, LogParser, LogRecord, COMBINED};
+pub(crate) use stats::request_path;
+pub use stats::{log_stats, LogStats, RequestTimes, StatsOptions, StatsReport, TopEntry};
+pub use volume::{estimate_line_bytes, estimate_volume, LogVolume, VolumeOptions, VolumeReport};
@@ -0,0 +1,375 @@
+//! Format-aware access log reader
+
+use crate::ast::{split_variables, Segment};
+use crate::error::Result;
+use crate::types::{AccessLog, LogFormat};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Pattern of the predefined `combined` format, used by `access_log`
+/// directives without a format name
+pub const COMBINED: &str = "$remote_addr - $remote_user [$time_local] \
+     \"$request\" $status $body_bytes_sent \"$http_referer\" \"$http_user_agent\"";
+
+/// Bytes read per step when looking for the last lines of a file
+const TAIL_CHUNK: u64 = 64 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Literal(String),
+    Variable(String),
+}
+
+/// Parser for the lines a `log_format` produces
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogParser {
+    parts: Vec<Part>,
+}
+
+impl LogParser {
+    /// Parser for a `log_format` pattern
+    ///
+    /// A leading `escape=` parameter is skipped. Format strings split over
+    /// several arguments are stored joined with a space, so whitespace next
+    /// to other literal text also matches none, and whitespace between two
+    /// variables matches any run of it.
+    #[must_use]
+    pub fn new(pattern: &str) -> Self {
+        let pattern = match pattern.strip_prefix("escape=") {
+            Some(rest) => rest.split_once(' ').map_or("", |(_, pattern)| pattern),
+            None => pattern,
+        };
+
+        let parts = split_variables(pattern)
+            .into_iter()
+            .map(|segment| match segment {
+                Segment::Literal(text) => {
+                    let mut collapsed = String::with_capacity(text.len());
+                    for c in text.chars() {
+                        if !c.is_whitespace() {
+                            collapsed.push(c);
+                        } else if !collapsed.ends_with(' ') {
+                            collapsed.push(' ');
+                        }
+                    }
+                    Part::Literal(collapsed)
+                }
+                Segment::Variable(name) => Part::Variable(name.to_string()),
+            })
+            .collect();
+        Self { parts }
+    }
+
+    /// Parser for the predefined `combined` format
+    #[must_use]
+    pub fn combined() -> Self {
+        Self::new(COMBINED)
+    }
+
+    /// Parser for a defined `log_format`
+    #[must_use]
+    pub fn from_format(format: &LogFormat) -> Self {
+        Self::new(&format.pattern)
+    }
+
+    /// Parser for the lines an `access_log` writes
+    ///
+    /// Logs without a format name, or naming a format missing from
+    /// `formats`, use `combined` like nginx does.
+    #[must_use]
+    pub fn for_log(log: &AccessLog, formats: &[LogFormat]) -> Self {
+        log.format_name
+            .as_deref()
+            .and_then(|name| formats.iter().find(|f| f.name == name))
+            .map_or_else(Self::combined, Self::from_format)
+    }
+
+    /// Variables of the format, in line order
+    pub fn variables(&self) -> impl Iterator<Item = &str> {
+        self.parts.iter().filter_map(|part| match part {
+            Part::Variable(name) => Some(name.as_str()),
+            Part::Literal(_) => None,
+        })
+    }
+
+    /// Literal text of the format, without the variables
+    #[must_use]
+    pub fn literal_len(&self) -> usize {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                Part::Literal(text) => text.len(),
+                Part::Variable(_) => 0,
+            })
+            .sum()
+    }
+
+    /// Split a log line into its fields
+    ///
+    /// A variable ends where the literal text after it starts, or at the
+    /// next space when another variable follows directly. Returns `None`
+    /// when the line does not match the format.
+    #[must_use]
+    pub fn parse(&self, line: &str) -> Option<LogRecord> {
+        let mut fields = Vec::new();
+        let mut rest = line.trim_end_matches(['\r', '\n']);
+
+        for (i, part) in self.parts.iter().enumerate() {
+            match part {
+                Part::Literal(text) => rest = &rest[match_literal(rest, text)?..],
+                Part::Variable(name) => {
+                    let end = match self.parts.get(i + 1) {
+                        Some(Part::Literal(next)) => find_literal(rest, next)?,
+                        Some(Part::Variable(_)) => rest.find(' ').unwrap_or(rest.len()),
+                        None => rest.len(),
+                    };
+                    fields.push((name.clone(), rest[..end].to_string()));
+                    rest = &rest[end..];
+                }
+            }
+        }
+
+        rest.is_empty().then_some(LogRecord { fields })
+    }
+}
+
+/// Fields of one log line
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LogRecord {
+    /// Variable names, without the `$`, and their values in line order
+    pub fields: Vec<(String, String)>,
+}
+
+impl LogRecord {
+    /// Value of a variable, `None` when the format does not log it
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Time of the request in seconds since the Unix epoch
+    ///
+    /// Read from `$msec`, `$time_iso8601` or `$time_local`, whichever the
+    /// format logs.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn timestamp(&self) -> Option<f64> {
+        if let Some(msec) = self.get("msec").and_then(|v| v.parse().ok()) {
+            return Some(msec);
+        }
+        if let Some(secs) = self.get("time_iso8601").and_then(parse_iso8601) {
+            return Some(secs as f64);
+        }
+        self.get("time_local")
+            .and_then(parse_time_local)
+            .map(|secs| secs as f64)
+    }
+}
+
+/// Read the last `lines` lines of a file
+///
+/// The file is read backwards in chunks, so this is cheap on large logs.
+/// Invalid UTF-8 is replaced.
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be opened or read.
+pub fn read_tail(path: impl AsRef<Path>, lines: usize) -> Result<Vec<String>> {
+    let mut file = std::fs::File::open(path)?;
+    let len = file.seek(SeekFrom::End(0))?;
+
+    let mut start = len;
+    let mut buf: Vec<u8> = Vec::new();
+    // One more newline than lines, as the last line ends with one
+    while start > 0 && count_newlines(&buf) <= lines {
+        let step = TAIL_CHUNK.min(start);
+        start -= step;
+        file.seek(SeekFrom::Start(start))?;
+        let mut chunk = vec![0; usize::try_from(step).unwrap_or(usize::MAX)];
+        file.read_exact(&mut chunk)?;
+        chunk.extend_from_slice(&buf);
+        buf = chunk;
+    }
+
+    let text = String::from_utf8_lossy(&buf);
+    let mut tail: Vec<String> = text.lines().map(str::to_string).collect();
+    // The first line may be cut off by the chunk boundary
+    if start > 0 && !tail.is_empty() {
+        tail.remove(0);
+    }
+    let skip = tail.len().saturating_sub(lines);
+    Ok(tail.split_off(skip))
+}
+
+/// Length of the literal at the start of `text`
+///
+/// A space in the literal matches any run of whitespace, an empty one
+/// only when the literal has other text.
+fn match_literal(text: &str, literal: &str) -> Option<usize> {
+    let blank = literal.trim().is_empty();
+    let mut pos = 0;
+    for c in literal.chars() {
+        if c == ' ' {
+            let skipped = text[pos..].len() - text[pos..].trim_start().len();
+            if blank && skipped == 0 {
+                return None;
+            }
+            pos += skipped;
+        } else if text[pos..].starts_with(c) {
+            pos += c.len_utf8();
+        } else {
+            return None;
+        }
+    }
+    Some(pos)
+}
+
+/// Where the literal first matches in `text`
+fn find_literal(text: &str, literal: &str) -> Option<usize> {
+    text.char_indices()
+        .map(|(i, _)| i)
+        .find(|&i| match_literal(&text[i..], literal).is_some())
+}
+
+#[allow(clippy::naive_bytecount)]
+fn count_newlines(buf: &[u8]) -> usize {
+    buf.iter().filter(|&&b| b == b'\n').count()
+}
+
+/// Parse `$time_local`, e.g. `10/Oct/2000:13:55:36 -0700`
+fn parse_time_local(value: &str) -> Option<i64> {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+
+    let (datetime, zone) = value.split_once(' ')?;
+    let mut parts = datetime.splitn(3, '/');
+    let day = parts.next()?.parse().ok()?;
+    let month = parts.next()?;
+    let month = MONTHS.iter().position(|m| *m == month)? + 1;
+    let (year, time) = parts.next()?.split_once(':')?;
+    let secs = civil_secs(year.parse().ok()?, month, day, time)?;
+    Some(secs - zone_offset(zone)?)
+}
+
+/// Parse `$time_iso8601`, e.g. `2000-10-10T13:55:36-07:00`
+fn parse_iso8601(value: &str) -> Option<i64> {
+    let (date, rest) = value.split_once('T')?;
+    let mut parts = date.splitn(3, '-');
+    let year = parts.next()?.parse().ok()?;
+    let month = parts.next()?.parse().ok()?;
+    let day = parts.next()?.parse().ok()?;
+
+    let (time, zone) = if let Some(time) = rest.strip_suffix('Z') {
+        (time, "+0000".to_string())
+    } else {
+        let at = rest.rfind(['+', '-'])?;
+        (&rest[..at], rest[at..].replace(':', ""))
+    };
+    Some(civil_secs(year, month, day, time)? - zone_offset(&zone)?)
+}
+
+/// Seconds since the epoch of a date and an `HH:MM:SS` time
+fn civil_secs(year: i64, month: usize, day: i64, time: &str) -> Option<i64> {
+    let mut hms = time.splitn(3, ':').map(|v| v.parse::<i64>().ok());
+    let (hour, minute, second) = (hms.next()??, hms.next()??, hms.next()??);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    // Days from civil date, after Howard Hinnant's `days_from_civil`
+    let month = i64::try_from(month).ok()?;
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+
+    Some(days * 86_400 + hour * 3600 + minute * 60 + second)
+}
+
+/// Offset in seconds of a `+HHMM` / `-HHMM` zone
+fn zone_offset(zone: &str) -> Option<i64> {
+    let (sign, digits) = match zone.as_bytes().first()? {
+        b'+' => (1, &zone[1..]),
+        b'-' => (-1, &zone[1..]),
+        _ => return None,
+    };
+    if digits.len() != 4 {
+        return None;
+    }
+    let hours: i64 = digits[..2].parse().ok()?;
+    let minutes: i64 = digits[2..].parse().ok()?;
+    Some(sign * (hours * 3600 + minutes * 60))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_parse_combined() {
+        let parser = LogParser::combined();
+        let record = parser
+            .parse(
+                "203.0.113.7 - - [15/Oct/2026:08:00:01 +0000] \"GET /a?b=1 HTTP/1.1\" 404 153 \"-\" \"curl/8.5.0\"\n",
+            )
+            .unwrap();
+
+        assert_eq!(record.get("remote_addr"), Some("203.0.113.7"));
+        assert_eq!(record.get("request"), Some("GET /a?b=1 HTTP/1.1"));
+        assert_eq!(record.get("status"), Some("404"));
+        assert_eq!(record.get("http_user_agent"), Some("curl/8.5.0"));
+        assert_eq!(record.get("request_time"), None);
+        assert_eq!(record.timestamp(), Some(1_792_051_201.0));
+
+        assert!(parser.parse("not a log line").is_none());
+    }
+
+    #[test]
+    fn test_parse_formats() {
+        // As extracted from
+        //   log_format json escape=json '{"t":"$time_iso8601",'
+        //                               '"s":$status,"m":$msec}';
+        let parser =
+            LogParser::new("escape=json {\"t\":\"$time_iso8601\", \"s\":$status,\"m\":$msec}");
+        assert_eq!(
+            parser.variables().collect::<Vec<_>>(),
+            vec!["time_iso8601", "status", "msec"]
+        );
+        let record = parser
+            .parse("{\"t\":\"2026-10-15T10:00:01+02:00\",\"s\":502,\"m\":1792051201.250}")
+            .unwrap();
+        assert_eq!(record.get("status"), Some("502"));
+        assert_eq!(record.timestamp(), Some(1_792_051_201.25));
+
+        assert_eq!(
+            parse_iso8601("2026-10-15T10:00:01+02:00"),
+            Some(1_792_051_201)
+        );
+        assert_eq!(parse_iso8601("2026-10-15T08:00:01Z"), Some(1_792_051_201));
+        assert_eq!(
+            parse_time_local("15/Oct/2026:01:00:01 -0700"),
+            Some(1_792_051_201)
+        );
+    }
+
+    #[test]
+    fn test_read_tail() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        for i in 0..20_000 {
+            writeln!(file, "line {i}").unwrap();
+        }
+        file.flush().unwrap();
+
+        let tail = read_tail(file.path(), 3).unwrap();
+        assert_eq!(tail, vec!["line 19997", "line 19998", "line 19999"]);
+        assert_eq!(read_tail(file.path(), 50_000).unwrap().len(), 20_000);
+    }
+}
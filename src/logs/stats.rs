@@ -0,0 +1,457 @@
+//! Access log quick statistics
+//!
+//! The last lines of each access log are parsed with the log's own format
+//! and summarised for incident triage: busiest status codes, paths and
+//! clients, the share of client and server errors, and `$request_time`
+//! percentiles when the format logs it.
+
+use super::reader::{read_tail, LogParser, LogRecord};
+use crate::ast::Config;
+use crate::extract;
+use crate::types::{AccessLog, LogFormat};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Options for [`log_stats`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StatsOptions {
+    /// Lines read from the end of each log
+    pub lines: usize,
+
+    /// Only count lines logged at or after this time, in seconds since the
+    /// Unix epoch; lines without a timestamp are then skipped
+    pub since: Option<f64>,
+
+    /// Entries kept in each top list
+    pub top: usize,
+}
+
+impl Default for StatsOptions {
+    fn default() -> Self {
+        Self {
+            lines: 10_000,
+            since: None,
+            top: 10,
+        }
+    }
+}
+
+/// A value and how many lines had it
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TopEntry {
+    /// Field value
+    pub value: String,
+
+    /// Lines with the value
+    pub count: usize,
+}
+
+/// Percentiles of `$request_time`, in seconds
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RequestTimes {
+    /// Median
+    pub p50: f64,
+
+    /// 95th percentile
+    pub p95: f64,
+
+    /// 99th percentile
+    pub p99: f64,
+
+    /// Slowest request
+    pub max: f64,
+}
+
+/// Statistics of one access log file
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LogStats {
+    /// Log file
+    pub path: PathBuf,
+
+    /// Format name, `combined` when the directive names none
+    pub format: String,
+
+    /// Lines read from the end of the file, 0 when it cannot be read
+    pub sampled_lines: usize,
+
+    /// Sampled lines that match the format and fall in the time window
+    pub counted_lines: usize,
+
+    /// Sampled lines that do not match the format
+    pub unparsed_lines: usize,
+
+    /// Time of the first and last counted line, in seconds since the Unix
+    /// epoch, when the format logs one
+    pub first: Option<f64>,
+
+    /// See [`first`](Self::first)
+    pub last: Option<f64>,
+
+    /// Most frequent `$status` values
+    pub statuses: Vec<TopEntry>,
+
+    /// Most frequent request paths, without query string
+    pub paths: Vec<TopEntry>,
+
+    /// Most frequent `$remote_addr` values
+    pub clients: Vec<TopEntry>,
+
+    /// Share of counted lines with a 4xx status, when the format logs
+    /// `$status`
+    pub client_error_rate: Option<f64>,
+
+    /// Share of counted lines with a 5xx status, when the format logs
+    /// `$status`
+    pub server_error_rate: Option<f64>,
+
+    /// `$request_time` percentiles, when the format logs it
+    pub request_time: Option<RequestTimes>,
+}
+
+impl LogStats {
+    /// Statistics of log lines written in the parser's format
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn from_lines(
+        path: impl Into<PathBuf>,
+        format: impl Into<String>,
+        parser: &LogParser,
+        lines: &[String],
+        options: &StatsOptions,
+    ) -> Self {
+        let mut stats = Self {
+            path: path.into(),
+            format: format.into(),
+            sampled_lines: lines.len(),
+            counted_lines: 0,
+            unparsed_lines: 0,
+            first: None,
+            last: None,
+            statuses: Vec::new(),
+            paths: Vec::new(),
+            clients: Vec::new(),
+            client_error_rate: None,
+            server_error_rate: None,
+            request_time: None,
+        };
+
+        let mut statuses = HashMap::new();
+        let mut paths = HashMap::new();
+        let mut clients = HashMap::new();
+        let mut times = Vec::new();
+        for line in lines {
+            let Some(record) = parser.parse(line) else {
+                stats.unparsed_lines += 1;
+                continue;
+            };
+            let timestamp = record.timestamp();
+            if let Some(since) = options.since {
+                if timestamp.map_or(true, |t| t < since) {
+                    continue;
+                }
+            }
+            stats.counted_lines += 1;
+            if let Some(t) = timestamp {
+                stats.first = Some(stats.first.map_or(t, |first: f64| first.min(t)));
+                stats.last = Some(stats.last.map_or(t, |last: f64| last.max(t)));
+            }
+
+            if let Some(status) = record.get("status") {
+                *statuses.entry(status.to_string()).or_insert(0) += 1;
+            }
+            if let Some(path) = request_path(&record) {
+                *paths.entry(path.to_string()).or_insert(0) += 1;
+            }
+            if let Some(client) = record.get("remote_addr") {
+                *clients.entry(client.to_string()).or_insert(0) += 1;
+            }
+            if let Some(time) = record
+                .get("request_time")
+                .and_then(|t| t.parse::<f64>().ok())
+            {
+                times.push(time);
+            }
+        }
+
+        if parser.variables().any(|v| v == "status") && stats.counted_lines > 0 {
+            let share = |class: char| {
+                let count: usize = statuses
+                    .iter()
+                    .filter(|(status, _)| status.starts_with(class))
+                    .map(|(_, count)| count)
+                    .sum();
+                count as f64 / stats.counted_lines as f64
+            };
+            stats.client_error_rate = Some(share('4'));
+            stats.server_error_rate = Some(share('5'));
+        }
+        stats.request_time = percentiles(times);
+        stats.statuses = top(statuses, options.top);
+        stats.paths = top(paths, options.top);
+        stats.clients = top(clients, options.top);
+        stats
+    }
+}
+
+/// Result of [`log_stats`]
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StatsReport {
+    /// One entry per access log file
+    pub logs: Vec<LogStats>,
+}
+
+/// Summarise the last lines of every access log file of a configuration
+///
+/// Paths are expanded and non-file targets left out as for
+/// [`estimate_volume`](super::estimate_volume).
+#[must_use]
+pub fn log_stats(config: &Config, options: &StatsOptions) -> StatsReport {
+    let formats = extract::log_formats(config).unwrap_or_default();
+    let mut report = StatsReport::default();
+
+    for log in extract::access_logs(config).unwrap_or_default() {
+        for path in log.expanded_paths() {
+            if report.logs.iter().any(|s| s.path == path) {
+                continue;
+            }
+            report.logs.push(sample(&log, path, &formats, options));
+        }
+    }
+    report
+}
+
+fn sample(
+    log: &AccessLog,
+    path: PathBuf,
+    formats: &[LogFormat],
+    options: &StatsOptions,
+) -> LogStats {
+    let parser = LogParser::for_log(log, formats);
+    let lines = read_tail(&path, options.lines).unwrap_or_default();
+    let format = log.format_name.as_deref().unwrap_or("combined");
+    LogStats::from_lines(path, format, &parser, &lines, options)
+}
+
+/// Path of the request, from `$request_uri`, `$uri` or `$request`
+pub(crate) fn request_path(record: &LogRecord) -> Option<&str> {
+    let uri = record
+        .get("request_uri")
+        .or_else(|| record.get("uri"))
+        .or_else(|| record.get("request")?.split(' ').nth(1))?;
+    uri.split(['?', '#']).next().filter(|p| !p.is_empty())
+}
+
+/// The `n` most frequent values, ties in value order
+fn top(counts: HashMap<String, usize>, n: usize) -> Vec<TopEntry> {
+    let mut entries: Vec<TopEntry> = counts
+        .into_iter()
+        .map(|(value, count)| TopEntry { value, count })
+        .collect();
+    entries.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+    entries.truncate(n);
+    entries
+}
+
+/// Nearest-rank percentiles
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss,
+    clippy::cast_sign_loss
+)]
+fn percentiles(mut times: Vec<f64>) -> Option<RequestTimes> {
+    if times.is_empty() {
+        return None;
+    }
+    times.sort_by(f64::total_cmp);
+    let rank = |p: f64| times[((p * times.len() as f64).ceil() as usize).clamp(1, times.len()) - 1];
+    Some(RequestTimes {
+        p50: rank(0.50),
+        p95: rank(0.95),
+        p99: rank(0.99),
+        max: times[times.len() - 1],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+    use std::io::Write;
+
+    #[test]
+    fn test_log_stats() {
+        let dir = tempfile::tempdir().unwrap();
+        let access = dir.path().join("access.log");
+        let mut file = std::fs::File::create(&access).unwrap();
+        // 100 requests a second apart, every tenth a 502 on /api
+        for i in 0..100 {
+            let (status, path) = match i % 10 {
+                0 => (502, "/api?id=1"),
+                1..=3 => (404, "/missing"),
+                _ => (200, "/"),
+            };
+            writeln!(
+                file,
+                "10.0.0.{} [15/Oct/2026:08:{:02}:{:02} +0000] \"GET {path} HTTP/1.1\" {status} {}.{:03}",
+                i % 3,
+                i / 60,
+                i % 60,
+                i / 100,
+                i + 1
+            )
+            .unwrap();
+        }
+        writeln!(file, "garbage").unwrap();
+
+        let config = parse(&format!(
+            "http {{\n\
+             log_format timed '$remote_addr [$time_local] \"$request\" $status $request_time';\n\
+             access_log {} timed;\n\
+             access_log syslog:server=10.0.0.2;\n\
+             }}",
+            access.display()
+        ))
+        .unwrap();
+        let report = log_stats(&config, &StatsOptions::default());
+        assert_eq!(report.logs.len(), 1);
+
+        let stats = &report.logs[0];
+        assert_eq!(stats.format, "timed");
+        assert_eq!(
+            (
+                stats.sampled_lines,
+                stats.counted_lines,
+                stats.unparsed_lines
+            ),
+            (101, 100, 1)
+        );
+        assert_eq!(
+            stats.statuses[0],
+            TopEntry {
+                value: "200".to_string(),
+                count: 60
+            }
+        );
+        assert_eq!(stats.paths.len(), 3);
+        assert_eq!(stats.paths[2].value, "/api");
+        assert_eq!(stats.clients[0].value, "10.0.0.0");
+        assert_eq!(stats.client_error_rate, Some(0.3));
+        assert_eq!(stats.server_error_rate, Some(0.1));
+        let times = stats.request_time.unwrap();
+        assert_eq!((times.p50, times.p95, times.max), (0.05, 0.095, 0.1));
+
+        // The last 10 seconds only
+        let since = stats.last.unwrap() - 9.0;
+        let options = StatsOptions {
+            since: Some(since),
+            top: 1,
+            ..Default::default()
+        };
+        let recent = &log_stats(&config, &options).logs[0];
+        assert_eq!(recent.counted_lines, 10);
+        assert_eq!(recent.first, Some(since));
+        assert_eq!(recent.statuses.len(), 1);
+    }
+
+    fn lines(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn test_stats_without_status_or_time() {
+        let parser = LogParser::new("$remote_addr \"$request\" $request_time");
+        let sample = lines(&[
+            "10.0.0.1 \"GET /b?x=1 HTTP/1.1\" -",
+            "10.0.0.2 \"GET /a HTTP/1.1\" 0.5",
+            "10.0.0.1 \"-\" 0.1",
+            "10.0.0.3 \"GET ?x=1 HTTP/1.1\" 0.2",
+        ]);
+        let stats =
+            LogStats::from_lines("a.log", "short", &parser, &sample, &StatsOptions::default());
+
+        // No $status: no error rates; unparseable request times and
+        // requests without a path are left out
+        assert_eq!(stats.counted_lines, 4);
+        assert_eq!(
+            (stats.client_error_rate, stats.server_error_rate),
+            (None, None)
+        );
+        assert!(stats.statuses.is_empty());
+        let paths: Vec<_> = stats
+            .paths
+            .iter()
+            .map(|p| (p.value.as_str(), p.count))
+            .collect();
+        assert_eq!(paths, vec![("/a", 1), ("/b", 1)]);
+        let times = stats.request_time.unwrap();
+        assert_eq!((times.p50, times.p99, times.max), (0.2, 0.5, 0.5));
+        assert_eq!((stats.first, stats.last), (None, None));
+
+        // A time window skips every line without a timestamp
+        let options = StatsOptions {
+            since: Some(0.0),
+            ..Default::default()
+        };
+        let windowed = LogStats::from_lines("a.log", "short", &parser, &sample, &options);
+        assert_eq!((windowed.sampled_lines, windowed.counted_lines), (4, 0));
+        assert_eq!(windowed.request_time, None);
+        assert!(windowed.clients.is_empty());
+    }
+
+    #[test]
+    fn test_stats_of_unreadable_and_empty_logs() {
+        let dir = tempfile::tempdir().unwrap();
+        let empty = dir.path().join("empty.log");
+        std::fs::write(&empty, "").unwrap();
+        let missing = dir.path().join("missing.log");
+        let config = parse(&format!(
+            "http {{\n\
+             access_log {missing};\n\
+             server {{ access_log {missing}; access_log {empty} combined; }}\n\
+             }}",
+            missing = missing.display(),
+            empty = empty.display()
+        ))
+        .unwrap();
+        let report = log_stats(&config, &StatsOptions::default());
+
+        // A file logged to twice is sampled once
+        let logs: Vec<_> = report
+            .logs
+            .iter()
+            .map(|l| (l.path.clone(), l.format.as_str(), l.sampled_lines))
+            .collect();
+        assert_eq!(logs, vec![(missing, "combined", 0), (empty, "combined", 0)]);
+        for stats in &report.logs {
+            assert_eq!(stats.counted_lines, 0);
+            assert_eq!(stats.client_error_rate, None);
+            assert_eq!(stats.request_time, None);
+        }
+    }
+
+    #[test]
+    fn test_top_ties_and_percentiles() {
+        let counts: HashMap<String, usize> = [("b", 2), ("a", 2), ("c", 5), ("d", 1)]
+            .into_iter()
+            .map(|(value, count)| (value.to_string(), count))
+            .collect();
+        let values: Vec<_> = top(counts.clone(), 3)
+            .into_iter()
+            .map(|e| e.value)
+            .collect();
+        assert_eq!(values, ["c", "a", "b"]);
+        assert!(top(counts, 0).is_empty());
+
+        assert_eq!(percentiles(Vec::new()), None);
+        let single = percentiles(vec![0.3]).unwrap();
+        assert_eq!(
+            (single.p50, single.p95, single.p99, single.max),
+            (0.3, 0.3, 0.3, 0.3)
+        );
+    }
+}
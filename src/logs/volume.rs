@@ -0,0 +1,273 @@
+//! Access log volume estimation
+//!
+//! Each access log file is sampled from its tail: the timestamps of the
+//! sampled lines give the write rate and their length the line size. Logs
+//! that do not exist yet get a line size estimated from their format and
+//! no rate.
+
+use super::reader::{read_tail, LogParser};
+use crate::ast::Config;
+use crate::extract;
+use crate::types::{AccessLog, LogFormat};
+use std::path::PathBuf;
+
+/// Seconds per day
+const DAY: f64 = 86_400.0;
+
+/// Typical width of variables commonly found in log formats
+const TYPICAL_WIDTHS: &[(&str, usize)] = &[
+    ("remote_addr", 14),
+    ("remote_user", 1),
+    ("time_local", 26),
+    ("time_iso8601", 25),
+    ("msec", 14),
+    ("request", 60),
+    ("request_method", 4),
+    ("request_uri", 45),
+    ("uri", 35),
+    ("args", 20),
+    ("status", 3),
+    ("body_bytes_sent", 5),
+    ("bytes_sent", 5),
+    ("request_length", 3),
+    ("request_time", 5),
+    ("http_referer", 40),
+    ("http_user_agent", 110),
+    ("http_x_forwarded_for", 15),
+    ("host", 20),
+    ("server_name", 20),
+    ("upstream_addr", 15),
+    ("upstream_status", 3),
+    ("upstream_response_time", 5),
+    ("upstream_connect_time", 5),
+    ("upstream_header_time", 5),
+];
+
+/// Width assumed for variables not in [`TYPICAL_WIDTHS`]
+const DEFAULT_WIDTH: usize = 10;
+
+/// Options for [`estimate_volume`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VolumeOptions {
+    /// Lines read from the end of each log
+    pub sample_lines: usize,
+}
+
+impl Default for VolumeOptions {
+    fn default() -> Self {
+        Self {
+            sample_lines: 10_000,
+        }
+    }
+}
+
+/// Projected volume of one access log file
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LogVolume {
+    /// Log file
+    pub path: PathBuf,
+
+    /// Format name, `combined` when the directive names none
+    pub format: String,
+
+    /// Lines read from the end of the file, 0 when it cannot be read
+    pub sampled_lines: usize,
+
+    /// Sampled lines matching the format and carrying a timestamp
+    pub timed_lines: usize,
+
+    /// Seconds between the first and last timed line
+    pub window_secs: Option<f64>,
+
+    /// Lines written per second over the window
+    pub lines_per_sec: Option<f64>,
+
+    /// Average line size in bytes, newline included
+    pub avg_line_bytes: f64,
+
+    /// Whether `avg_line_bytes` was measured on sampled lines rather than
+    /// estimated from the format
+    pub measured: bool,
+}
+
+impl LogVolume {
+    /// Projected lines per day
+    #[must_use]
+    pub fn daily_lines(&self) -> Option<f64> {
+        self.lines_per_sec.map(|rate| rate * DAY)
+    }
+
+    /// Projected bytes per day
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn daily_bytes(&self) -> Option<u64> {
+        self.daily_lines()
+            .map(|lines| (lines * self.avg_line_bytes).round() as u64)
+    }
+}
+
+/// Result of [`estimate_volume`]
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VolumeReport {
+    /// One entry per access log file
+    pub logs: Vec<LogVolume>,
+}
+
+impl VolumeReport {
+    /// Projected bytes per day over all logs with a known rate
+    #[must_use]
+    pub fn daily_bytes(&self) -> u64 {
+        self.logs.iter().filter_map(LogVolume::daily_bytes).sum()
+    }
+
+    /// Logs whose rate could not be measured
+    pub fn unknown(&self) -> impl Iterator<Item = &LogVolume> {
+        self.logs.iter().filter(|log| log.lines_per_sec.is_none())
+    }
+}
+
+/// Project the daily volume of every access log file of a configuration
+///
+/// Templated paths are expanded against server names like
+/// [`LogPathMode::Expand`](crate::types::LogPathMode::Expand); syslog and
+/// other non-file targets are left out. A rate needs at least two timed
+/// lines over a window of more than a second.
+#[must_use]
+pub fn estimate_volume(config: &Config, options: &VolumeOptions) -> VolumeReport {
+    let formats = extract::log_formats(config).unwrap_or_default();
+    let mut report = VolumeReport::default();
+
+    for log in extract::access_logs(config).unwrap_or_default() {
+        for path in log.expanded_paths() {
+            if report.logs.iter().any(|v| v.path == path) {
+                continue;
+            }
+            report
+                .logs
+                .push(sample(&log, path, &formats, options.sample_lines));
+        }
+    }
+    report
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn sample(log: &AccessLog, path: PathBuf, formats: &[LogFormat], sample_lines: usize) -> LogVolume {
+    let parser = LogParser::for_log(log, formats);
+    let lines = read_tail(&path, sample_lines).unwrap_or_default();
+
+    let timestamps: Vec<f64> = lines
+        .iter()
+        .filter_map(|line| parser.parse(line)?.timestamp())
+        .collect();
+    let first = timestamps.iter().copied().fold(f64::INFINITY, f64::min);
+    let last = timestamps.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let window_secs = (timestamps.len() > 1).then_some(last - first);
+    let lines_per_sec = window_secs
+        .filter(|window| *window > 1.0)
+        .map(|window| (timestamps.len() - 1) as f64 / window);
+
+    let measured = !lines.is_empty();
+    let avg_line_bytes = if measured {
+        let total: usize = lines.iter().map(|line| line.len() + 1).sum();
+        total as f64 / lines.len() as f64
+    } else {
+        estimate_line_bytes(&parser) as f64
+    };
+
+    LogVolume {
+        path,
+        format: log
+            .format_name
+            .clone()
+            .unwrap_or_else(|| "combined".to_string()),
+        sampled_lines: lines.len(),
+        timed_lines: timestamps.len(),
+        window_secs,
+        lines_per_sec,
+        avg_line_bytes,
+        measured,
+    }
+}
+
+/// Typical size in bytes of a line of the format, newline included
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::logs::{estimate_line_bytes, LogParser};
+///
+/// let parser = LogParser::new("$remote_addr $status");
+/// assert_eq!(estimate_line_bytes(&parser), 14 + 1 + 3 + 1);
+/// ```
+#[must_use]
+pub fn estimate_line_bytes(parser: &LogParser) -> usize {
+    let variables: usize = parser
+        .variables()
+        .map(|name| {
+            TYPICAL_WIDTHS
+                .iter()
+                .find(|(known, _)| *known == name)
+                .map_or(DEFAULT_WIDTH, |(_, width)| *width)
+        })
+        .sum();
+    parser.literal_len() + variables + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+    use std::io::Write;
+
+    #[test]
+    fn test_estimate_volume() {
+        let dir = tempfile::tempdir().unwrap();
+        let access = dir.path().join("access.log");
+        let mut file = std::fs::File::create(&access).unwrap();
+        // 121 lines over two minutes: one line per second
+        for i in 0..=120 {
+            writeln!(
+                file,
+                "10.0.0.1 [15/Oct/2026:08:{:02}:{:02} +0000] 200 {}",
+                i / 60,
+                i % 60,
+                if i % 2 == 0 { "ok" } else { "okay" }
+            )
+            .unwrap();
+        }
+
+        let config = parse(&format!(
+            "http {{\n\
+             log_format short '$remote_addr [$time_local] $status $x';\n\
+             access_log {} short;\n\
+             access_log {}/missing.log;\n\
+             access_log syslog:server=10.0.0.2;\n\
+             }}",
+            access.display(),
+            dir.path().display()
+        ))
+        .unwrap();
+        let report = estimate_volume(&config, &VolumeOptions::default());
+        assert_eq!(report.logs.len(), 2);
+
+        let sampled = &report.logs[0];
+        assert_eq!(sampled.format, "short");
+        assert_eq!((sampled.sampled_lines, sampled.timed_lines), (121, 121));
+        assert_eq!(sampled.window_secs, Some(120.0));
+        assert_eq!(sampled.lines_per_sec, Some(1.0));
+        assert!(sampled.measured);
+        // 45 and 47 bytes per line, newline included
+        assert!((sampled.avg_line_bytes - 5565.0 / 121.0).abs() < 1e-9);
+        assert_eq!(sampled.daily_bytes(), Some(3_973_686));
+
+        let missing = &report.logs[1];
+        assert_eq!(missing.format, "combined");
+        assert!(!missing.measured && missing.lines_per_sec.is_none());
+        assert!((missing.avg_line_bytes - 277.0).abs() < 1e-9);
+        assert_eq!(report.unknown().count(), 1);
+        assert_eq!(report.daily_bytes(), 3_973_686);
+    }
+}
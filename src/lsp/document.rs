@@ -0,0 +1,386 @@
+//! Language features for one open document
+//!
+//! A [`Document`] parses its text once and answers the editor's questions
+//! from the result: diagnostics from the parser and the default lint rules,
+//! hover documentation and completions from the [`docs`](crate::docs)
+//! database, definitions of upstreams and named locations, and the block
+//! outline.
+
+use super::protocol::{
+    symbol_kind, CompletionItem, Diagnostic, DocumentSymbol, Hover, LineIndex, Location,
+    MarkupContent, Position, Range,
+};
+use crate::ast::{Config, ConfigIndex, Directive, Span};
+use crate::lint::{Linter, Severity};
+use crate::{docs, Error};
+
+/// An open document and its parse result
+#[derive(Debug)]
+pub struct Document {
+    text: String,
+    parsed: std::result::Result<Config, Error>,
+}
+
+impl Document {
+    /// Parse `text`
+    #[must_use]
+    pub fn new(text: impl Into<String>) -> Self {
+        let text = text.into();
+        let parsed = crate::parse(&text);
+        Self { text, parsed }
+    }
+
+    /// The document text
+    #[must_use]
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// The parsed configuration, if the text parses
+    #[must_use]
+    pub fn config(&self) -> Option<&Config> {
+        self.parsed.as_ref().ok()
+    }
+
+    /// The parse error, or the findings of the default lint rules
+    #[must_use]
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        let index = LineIndex::new(&self.text);
+        let config = match &self.parsed {
+            Ok(config) => config,
+            Err(error) => {
+                let start = error
+                    .location()
+                    .map_or_else(Position::default, |(line, col)| index.line_col(line, col));
+                let end = index.position(index.offset(start) + 1);
+                return vec![Diagnostic {
+                    range: Range { start, end },
+                    severity: 1,
+                    code: "parse".to_string(),
+                    source: SOURCE.to_string(),
+                    message: error.message(),
+                }];
+            }
+        };
+
+        Linter::with_default_rules()
+            .check(config)
+            .into_iter()
+            .map(|finding| {
+                let range = finding.span.map_or_else(Range::default, |span| {
+                    let (start, end) = self.header(span);
+                    index.range(start, end)
+                });
+                let mut message = finding.message;
+                if let Some(help) = finding.help {
+                    message = format!("{message}\n{help}");
+                }
+                Diagnostic {
+                    range,
+                    severity: match finding.severity {
+                        Severity::Error => 1,
+                        Severity::Warning => 2,
+                        Severity::Info => 3,
+                    },
+                    code: finding.rule,
+                    source: SOURCE.to_string(),
+                    message,
+                }
+            })
+            .collect()
+    }
+
+    /// Documentation of the directive whose name is at `position`
+    #[must_use]
+    pub fn hover(&self, position: Position) -> Option<Hover> {
+        let config = self.config()?;
+        let index = LineIndex::new(&self.text);
+        let offset = index.offset(position);
+
+        let config_index = ConfigIndex::new(config);
+        let directive = config_index
+            .ids()
+            .map(|id| config_index.get(id))
+            .find(|d| {
+                let (start, end) = self.name_range(d.span, d.name());
+                (start..end).contains(&offset)
+            })?;
+        let doc = docs::lookup(directive.name())?;
+
+        let (start, end) = self.name_range(directive.span, directive.name());
+        Some(Hover {
+            contents: markdown(doc),
+            range: index.range(start, end),
+        })
+    }
+
+    /// Directives allowed at `position`, see [`docs::complete`]
+    ///
+    /// Works on text that does not parse. Nothing is offered while the
+    /// cursor is in arguments or a comment.
+    #[must_use]
+    pub fn completion(&self, position: Position) -> Vec<CompletionItem> {
+        let offset = LineIndex::new(&self.text).offset(position);
+        docs::complete(&self.text, offset)
+            .directives
+            .into_iter()
+            .map(|doc| CompletionItem {
+                label: doc.name.to_string(),
+                kind: 14,
+                detail: doc.syntax.to_string(),
+                documentation: markdown(doc),
+            })
+            .collect()
+    }
+
+    /// Where the upstream or named location referenced at `position` is
+    /// defined
+    ///
+    /// `@name` resolves to `location @name`. A word in a `*_pass`
+    /// directive resolves to `upstream <host>`, ignoring any scheme, port
+    /// and path.
+    #[must_use]
+    pub fn definition(&self, uri: &str, position: Position) -> Option<Location> {
+        let config = self.config()?;
+        let index = LineIndex::new(&self.text);
+        let offset = index.offset(position);
+
+        let config_index = ConfigIndex::new(config);
+        let referrer = config_index
+            .ids()
+            .map(|id| config_index.get(id))
+            .filter(|d| {
+                let (start, end) = self.header(d.span);
+                (start..=end).contains(&offset)
+            })
+            .last()?;
+        let word = word_at(&self.text, offset)?;
+
+        let target = if word.starts_with('@') {
+            find(&config.directives, &|d| {
+                d.name() == "location" && d.first_arg().as_deref() == Some(word)
+            })
+        } else if referrer.name().ends_with("_pass") {
+            let host = word.split_once("://").map_or(word, |(_, rest)| rest);
+            let host = host.split([':', '/']).next().unwrap_or(host);
+            find(&config.directives, &|d| {
+                d.name() == "upstream" && d.first_arg().as_deref() == Some(host)
+            })
+        } else {
+            None
+        }?;
+
+        let (start, end) = self.header(target.span);
+        Some(Location {
+            uri: uri.to_string(),
+            range: index.range(start, end),
+        })
+    }
+
+    /// Outline of the blocks in the document
+    #[must_use]
+    pub fn symbols(&self) -> Vec<DocumentSymbol> {
+        let index = LineIndex::new(&self.text);
+        self.config()
+            .map(|config| self.block_symbols(&index, &config.directives))
+            .unwrap_or_default()
+    }
+
+    fn block_symbols(
+        &self,
+        index: &LineIndex<'_>,
+        directives: &[Directive],
+    ) -> Vec<DocumentSymbol> {
+        directives
+            .iter()
+            .filter_map(|directive| {
+                let children = directive.children()?;
+                let mut name = directive.name().to_string();
+                let label = if directive.name() == "server" {
+                    directive
+                        .find_children("server_name")
+                        .first()
+                        .map(|d| d.args_as_strings())
+                        .unwrap_or_default()
+                } else {
+                    directive.args().iter().map(ToString::to_string).collect()
+                };
+                for part in label {
+                    name.push(' ');
+                    name.push_str(&part);
+                }
+                let (start, end) = self.header(directive.span);
+                Some(DocumentSymbol {
+                    name,
+                    kind: match directive.name() {
+                        "http" | "events" | "stream" | "mail" => symbol_kind::NAMESPACE,
+                        "server" => symbol_kind::CLASS,
+                        "location" => symbol_kind::METHOD,
+                        "upstream" => symbol_kind::STRUCT,
+                        _ => symbol_kind::OBJECT,
+                    },
+                    range: index.range(directive.span.start, directive.span.end),
+                    selection_range: index.range(start, end),
+                    children: self.block_symbols(index, children),
+                })
+            })
+            .collect()
+    }
+
+    /// Byte range of the directive header: its name and arguments, up to
+    /// the `;` or `{`
+    fn header(&self, span: Span) -> (usize, usize) {
+        let text = self.text.get(span.start..span.end).unwrap_or_default();
+        let len = text.find(['{', ';']).unwrap_or(text.len());
+        (span.start, span.start + text[..len].trim_end().len())
+    }
+
+    fn name_range(&self, span: Span, name: &str) -> (usize, usize) {
+        let (start, end) = self.header(span);
+        (start, end.min(start + name.len()))
+    }
+}
+
+const SOURCE: &str = "nginx-discovery";
+
+fn markdown(doc: &docs::DirectiveDoc) -> MarkupContent {
+    MarkupContent {
+        kind: "markdown".to_string(),
+        value: format!("**{}**\n\n```\n{doc}\n```\n\n{}", doc.name, doc.url()),
+    }
+}
+
+fn find<'a>(
+    directives: &'a [Directive],
+    matches: &dyn Fn(&Directive) -> bool,
+) -> Option<&'a Directive> {
+    directives.iter().find_map(|d| {
+        if matches(d) {
+            Some(d)
+        } else {
+            find(d.children().unwrap_or_default(), matches)
+        }
+    })
+}
+
+/// The whitespace-delimited argument around `offset`, without quotes
+fn word_at(text: &str, offset: usize) -> Option<&str> {
+    let is_boundary = |c: char| c.is_whitespace() || matches!(c, ';' | '{' | '}' | '"' | '\'');
+    let start = text[..offset].rfind(is_boundary).map_or(0, |i| i + 1);
+    let end = text[offset..]
+        .find(is_boundary)
+        .map_or(text.len(), |i| offset + i);
+    let word = &text[start..end];
+    (!word.is_empty()).then_some(word)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = "http {
+    upstream backend { server 10.0.0.1:8080; }
+    server {
+        server_name example.com;
+        location / {
+            proxy_pass http://backend/api;
+            error_page 502 = @fallback;
+        }
+        location @fallback { return 503; }
+    }
+}
+";
+
+    #[test]
+    fn test_diagnostics() {
+        let document = Document::new("http {\n    server {\n        listen 80;\n        root /a;\n        root /b;\n    }\n}\n");
+        let diagnostics = document.diagnostics();
+        let duplicate = diagnostics
+            .iter()
+            .find(|d| d.code == "duplicate_directive")
+            .unwrap();
+        assert_eq!(duplicate.severity, 1);
+        assert_eq!(
+            duplicate.range,
+            Range {
+                start: Position::new(4, 8),
+                end: Position::new(4, 15)
+            }
+        );
+
+        let document = Document::new("http {\n    server {\n");
+        let diagnostics = document.diagnostics();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, "parse");
+        assert_eq!(diagnostics[0].severity, 1);
+    }
+
+    #[test]
+    fn test_hover() {
+        let document = Document::new(CONFIG);
+        let hover = document.hover(Position::new(5, 15)).unwrap();
+        assert!(hover.contents.value.starts_with("**proxy_pass**"));
+        assert!(hover.contents.value.contains("Syntax:"));
+        assert_eq!(hover.range.start, Position::new(5, 12));
+        assert_eq!(hover.range.end, Position::new(5, 22));
+
+        // Arguments are not documented
+        assert!(document.hover(Position::new(5, 30)).is_none());
+    }
+
+    #[test]
+    fn test_completion() {
+        let document = Document::new("events {\n    worker_c\n}\n");
+        let items = document.completion(Position::new(1, 12));
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].label, "worker_connections");
+        assert_eq!(items[0].detail, "worker_connections number;");
+
+        let items = document.completion(Position::new(0, 3));
+        assert_eq!(items[0].label, "events");
+        // Arguments are not completed
+        assert!(document.completion(Position::new(0, 7)).is_empty());
+    }
+
+    #[test]
+    fn test_definition() {
+        let document = Document::new(CONFIG);
+        let upstream = document
+            .definition("file:///nginx.conf", Position::new(5, 32))
+            .unwrap();
+        assert_eq!(upstream.uri, "file:///nginx.conf");
+        assert_eq!(upstream.range.start, Position::new(1, 4));
+        assert_eq!(upstream.range.end, Position::new(1, 20));
+
+        let named = document
+            .definition("file:///nginx.conf", Position::new(6, 32))
+            .unwrap();
+        assert_eq!(named.range.start, Position::new(8, 8));
+
+        // `server_name` values are not references
+        assert!(document
+            .definition("file:///nginx.conf", Position::new(3, 22))
+            .is_none());
+    }
+
+    #[test]
+    fn test_symbols() {
+        let symbols = Document::new(CONFIG).symbols();
+        assert_eq!(symbols.len(), 1);
+        assert_eq!(symbols[0].kind, symbol_kind::NAMESPACE);
+
+        let names: Vec<&str> = symbols[0]
+            .children
+            .iter()
+            .map(|s| s.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["upstream backend", "server example.com"]);
+
+        let locations: Vec<&str> = symbols[0].children[1]
+            .children
+            .iter()
+            .map(|s| s.name.as_str())
+            .collect();
+        assert_eq!(locations, vec!["location /", "location @fallback"]);
+    }
+}
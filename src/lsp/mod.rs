@@ -0,0 +1,43 @@
+//! Language Server Protocol mode
+//!
+//! A minimal language server for NGINX configuration files, speaking
+//! JSON-RPC over stdio with `Content-Length` framing. It supports:
+//!
+//! - diagnostics on open and change, from the parser and the default
+//!   [lint rules](crate::lint::rules)
+//! - hover documentation for directive names from the [`docs`](crate::docs)
+//!   database
+//! - go-to-definition for upstreams referenced by `*_pass` directives and
+//!   for named locations (`@name`)
+//! - completion of the directives allowed in the enclosing block
+//! - document symbols for blocks
+//!
+//! Documents are synchronized in full on every change. Each document is
+//! analyzed on its own; `include`d files are not followed.
+//!
+//! # Examples
+//!
+//! ```
+//! use nginx_discovery::lsp::Server;
+//! use serde_json::json;
+//!
+//! let mut server = Server::new();
+//! let replies = server.handle(&json!({
+//!     "jsonrpc": "2.0",
+//!     "method": "textDocument/didOpen",
+//!     "params": { "textDocument": {
+//!         "uri": "file:///etc/nginx/nginx.conf",
+//!         "text": "user nginx;\nuser www-data;\n",
+//!     }},
+//! }));
+//!
+//! let diagnostics = &replies[0]["params"]["diagnostics"];
+//! assert_eq!(diagnostics[0]["code"], "duplicate_directive");
+//! ```
+
+mod document;
+pub mod protocol;
+mod server;
+
+pub use document::Document;
+pub use server::{read_message, run, run_stdio, write_message, Server};
@@ -0,0 +1,220 @@
+//! LSP data types and position conversion
+//!
+//! Only the parts of the protocol the server uses are modeled. Positions
+//! are zero-based lines and UTF-16 code unit offsets, as the protocol
+//! requires; [`LineIndex`] converts them from and to byte offsets.
+
+use serde::{Deserialize, Serialize};
+
+/// Position in a document (zero-based line, UTF-16 column)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
+pub struct Position {
+    /// Line, starting at 0
+    pub line: u32,
+    /// Column in UTF-16 code units, starting at 0
+    pub character: u32,
+}
+
+impl Position {
+    /// Create a position
+    #[must_use]
+    pub fn new(line: u32, character: u32) -> Self {
+        Self { line, character }
+    }
+}
+
+/// Range in a document, end exclusive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Range {
+    /// First position
+    pub start: Position,
+    /// Position after the last character
+    pub end: Position,
+}
+
+/// Diagnostic severity (`1` error, `2` warning, `3` information)
+pub type DiagnosticSeverity = u8;
+
+/// A problem reported for a document
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    /// Where the problem is
+    pub range: Range,
+    /// How serious it is
+    pub severity: DiagnosticSeverity,
+    /// Lint rule id, or `parse` for syntax errors
+    pub code: String,
+    /// Always `nginx-discovery`
+    pub source: String,
+    /// Description of the problem
+    pub message: String,
+}
+
+/// Markdown hover content
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Hover {
+    /// Rendered documentation
+    pub contents: MarkupContent,
+    /// Range of the hovered directive name
+    pub range: Range,
+}
+
+/// Text in a given markup language
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MarkupContent {
+    /// `markdown` or `plaintext`
+    pub kind: String,
+    /// The content
+    pub value: String,
+}
+
+/// A directive offered for completion
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompletionItem {
+    /// Directive name
+    pub label: String,
+    /// Always `14` (keyword)
+    pub kind: u8,
+    /// Directive syntax
+    pub detail: String,
+    /// Rendered documentation
+    pub documentation: MarkupContent,
+}
+
+/// A range in a document identified by its URI
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Location {
+    /// Document URI
+    pub uri: String,
+    /// Range in the document
+    pub range: Range,
+}
+
+/// Symbol kinds used for configuration blocks
+pub mod symbol_kind {
+    /// `http`, `events`, `stream`, `mail`
+    pub const NAMESPACE: u8 = 3;
+    /// `server`
+    pub const CLASS: u8 = 5;
+    /// `location`
+    pub const METHOD: u8 = 6;
+    /// `upstream`
+    pub const STRUCT: u8 = 23;
+    /// Any other block
+    pub const OBJECT: u8 = 19;
+}
+
+/// A block of the configuration in the document outline
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentSymbol {
+    /// Block name with its arguments (`location /api`)
+    pub name: String,
+    /// Symbol kind, see [`symbol_kind`]
+    pub kind: u8,
+    /// The whole block
+    pub range: Range,
+    /// The block header (name and arguments)
+    pub selection_range: Range,
+    /// Nested blocks
+    pub children: Vec<DocumentSymbol>,
+}
+
+/// Converts between byte offsets and LSP positions for one text
+#[derive(Debug, Clone)]
+pub struct LineIndex<'a> {
+    text: &'a str,
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    /// Index the line starts of `text`
+    #[must_use]
+    pub fn new(text: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self { text, line_starts }
+    }
+
+    /// Position of a byte offset (clamped to the text)
+    #[must_use]
+    pub fn position(&self, offset: usize) -> Position {
+        let offset = offset.min(self.text.len());
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let start = self.line_starts[line];
+        let character: usize = self
+            .text
+            .get(start..offset)
+            .unwrap_or_default()
+            .chars()
+            .map(char::len_utf16)
+            .sum();
+        Position::new(to_u32(line), to_u32(character))
+    }
+
+    /// Byte offset of a position (clamped to the line and the text)
+    #[must_use]
+    pub fn offset(&self, position: Position) -> usize {
+        let Some(&start) = self.line_starts.get(position.line as usize) else {
+            return self.text.len();
+        };
+        let line = &self.text[start..];
+        let line = &line[..line.find('\n').unwrap_or(line.len())];
+        let mut units = 0;
+        for (i, c) in line.char_indices() {
+            if units >= position.character as usize {
+                return start + i;
+            }
+            units += c.len_utf16();
+        }
+        start + line.len()
+    }
+
+    /// Range of a byte range
+    #[must_use]
+    pub fn range(&self, start: usize, end: usize) -> Range {
+        Range {
+            start: self.position(start),
+            end: self.position(end),
+        }
+    }
+
+    /// Position of a one-based line and column as reported in errors
+    #[must_use]
+    pub fn line_col(&self, line: usize, col: usize) -> Position {
+        let line = line.saturating_sub(1).min(self.line_starts.len() - 1);
+        let start = self.line_starts[line];
+        let offset = self.text[start..]
+            .char_indices()
+            .nth(col.saturating_sub(1))
+            .map_or(self.text.len(), |(i, _)| start + i);
+        self.position(offset)
+    }
+}
+
+fn to_u32(n: usize) -> u32 {
+    u32::try_from(n).unwrap_or(u32::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_index() {
+        let text = "user nginx;\n# é😀\nhttp {}\n";
+        let index = LineIndex::new(text);
+
+        assert_eq!(index.position(0), Position::new(0, 0));
+        assert_eq!(index.position(12), Position::new(1, 0));
+        // "é" is one UTF-16 unit, "😀" two
+        let http = text.find("http").unwrap();
+        assert_eq!(index.position(http - 1), Position::new(1, 5));
+        assert_eq!(index.position(http), Position::new(2, 0));
+        assert_eq!(index.offset(Position::new(2, 0)), http);
+        assert_eq!(index.offset(Position::new(1, 5)), http - 1);
+        assert_eq!(index.offset(Position::new(9, 0)), text.len());
+        assert_eq!(index.line_col(3, 1), Position::new(2, 0));
+    }
+}
@@ -0,0 +1,299 @@
+//! Message handling and the stdio transport
+
+use super::document::Document;
+use super::protocol::Position;
+use crate::{Error, Result};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+
+/// JSON-RPC error code for unknown methods
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// JSON-RPC error code for malformed parameters
+const INVALID_PARAMS: i64 = -32602;
+
+/// Language server state: the open documents
+///
+/// [`handle`](Self::handle) takes one decoded message and returns the
+/// messages to send back, so the server can be driven without any IO.
+#[derive(Debug, Default)]
+pub struct Server {
+    documents: HashMap<String, Document>,
+    exit: bool,
+}
+
+impl Server {
+    /// Create a server without open documents
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether the client sent `exit`
+    #[must_use]
+    pub fn is_exiting(&self) -> bool {
+        self.exit
+    }
+
+    /// The open document with this URI
+    #[must_use]
+    pub fn document(&self, uri: &str) -> Option<&Document> {
+        self.documents.get(uri)
+    }
+
+    /// Handle one request or notification
+    ///
+    /// Returns the response to a request, and the `publishDiagnostics`
+    /// notifications caused by opening, changing or closing a document.
+    pub fn handle(&mut self, message: &Value) -> Vec<Value> {
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = &message["params"];
+        let id = message.get("id").cloned();
+
+        let result = match method {
+            "initialize" => Ok(json!({
+                "capabilities": {
+                    "textDocumentSync": 1,
+                    "hoverProvider": true,
+                    "definitionProvider": true,
+                    "documentSymbolProvider": true,
+                    "completionProvider": {},
+                },
+                "serverInfo": {
+                    "name": "nginx-discovery",
+                    "version": env!("CARGO_PKG_VERSION"),
+                },
+            })),
+            "shutdown" => Ok(Value::Null),
+            "exit" => {
+                self.exit = true;
+                return Vec::new();
+            }
+            "textDocument/didOpen" => {
+                let document = &params["textDocument"];
+                return self.update(
+                    document["uri"].as_str(),
+                    document["text"].as_str().map(str::to_string),
+                );
+            }
+            "textDocument/didChange" => {
+                // Full sync: the last change holds the whole text
+                let text = params["contentChanges"]
+                    .as_array()
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change["text"].as_str())
+                    .map(str::to_string);
+                return self.update(params["textDocument"]["uri"].as_str(), text);
+            }
+            "textDocument/didClose" => {
+                let Some(uri) = params["textDocument"]["uri"].as_str() else {
+                    return Vec::new();
+                };
+                self.documents.remove(uri);
+                return vec![diagnostics_notification(uri, &[])];
+            }
+            "textDocument/hover" => self.at_position(params, |document, _, position| {
+                json!(document.hover(position))
+            }),
+            "textDocument/completion" => self.at_position(params, |document, _, position| {
+                json!(document.completion(position))
+            }),
+            "textDocument/definition" => self.at_position(params, |document, uri, position| {
+                json!(document.definition(uri, position))
+            }),
+            "textDocument/documentSymbol" => match params["textDocument"]["uri"].as_str() {
+                Some(uri) => Ok(self
+                    .documents
+                    .get(uri)
+                    .map_or(Value::Null, |document| json!(document.symbols()))),
+                None => Err((INVALID_PARAMS, "missing textDocument.uri".to_string())),
+            },
+            _ => Err((METHOD_NOT_FOUND, format!("unknown method '{method}'"))),
+        };
+
+        // Notifications get no response
+        let Some(id) = id else {
+            return Vec::new();
+        };
+        vec![match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": code, "message": message },
+            }),
+        }]
+    }
+
+    fn update(&mut self, uri: Option<&str>, text: Option<String>) -> Vec<Value> {
+        let (Some(uri), Some(text)) = (uri, text) else {
+            return Vec::new();
+        };
+        let document = Document::new(text);
+        let notification = diagnostics_notification(uri, &document.diagnostics());
+        self.documents.insert(uri.to_string(), document);
+        vec![notification]
+    }
+
+    fn at_position(
+        &self,
+        params: &Value,
+        answer: impl Fn(&Document, &str, Position) -> Value,
+    ) -> std::result::Result<Value, (i64, String)> {
+        let uri = params["textDocument"]["uri"].as_str();
+        let position = serde_json::from_value::<Position>(params["position"].clone()).ok();
+        let (Some(uri), Some(position)) = (uri, position) else {
+            return Err((
+                INVALID_PARAMS,
+                "expected textDocument.uri and position".to_string(),
+            ));
+        };
+        Ok(self
+            .documents
+            .get(uri)
+            .map_or(Value::Null, |document| answer(document, uri, position)))
+    }
+}
+
+fn diagnostics_notification(uri: &str, diagnostics: &[super::protocol::Diagnostic]) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/publishDiagnostics",
+        "params": { "uri": uri, "diagnostics": diagnostics },
+    })
+}
+
+/// Read one `Content-Length` framed message
+///
+/// Returns `None` at end of input.
+///
+/// # Errors
+///
+/// Returns an error if reading fails, the header has no valid
+/// `Content-Length`, or the body is not JSON.
+pub fn read_message<R: BufRead>(reader: &mut R) -> Result<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+    let length = length.ok_or_else(|| Error::Custom("missing Content-Length header".into()))?;
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    Ok(Some(serde_json::from_slice(&body)?))
+}
+
+/// Write one message with a `Content-Length` header
+///
+/// # Errors
+///
+/// Returns an error if serialization or writing fails.
+pub fn write_message<W: Write>(writer: &mut W, message: &Value) -> Result<()> {
+    let body = serde_json::to_string(message)?;
+    write!(writer, "Content-Length: {}\r\n\r\n{body}", body.len())?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Serve requests from `reader` until `exit` or end of input
+///
+/// # Errors
+///
+/// Returns an error if reading or writing a message fails.
+pub fn run<R: BufRead, W: Write>(mut reader: R, mut writer: W) -> Result<()> {
+    let mut server = Server::new();
+    while let Some(message) = read_message(&mut reader)? {
+        for reply in server.handle(&message) {
+            write_message(&mut writer, &reply)?;
+        }
+        if server.is_exiting() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Serve requests on stdin and stdout
+///
+/// # Errors
+///
+/// Returns an error if reading or writing a message fails.
+pub fn run_stdio() -> Result<()> {
+    let stdin = std::io::stdin();
+    let stdout = std::io::stdout();
+    run(stdin.lock(), stdout.lock())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(messages: &[Value]) -> Vec<u8> {
+        let mut out = Vec::new();
+        for message in messages {
+            write_message(&mut out, message).unwrap();
+        }
+        out
+    }
+
+    #[test]
+    fn test_session() {
+        let uri = "file:///etc/nginx/nginx.conf";
+        let input = frame(&[
+            json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {} }),
+            json!({ "jsonrpc": "2.0", "method": "initialized", "params": {} }),
+            json!({ "jsonrpc": "2.0", "method": "textDocument/didOpen", "params": {
+                "textDocument": { "uri": uri, "languageId": "nginx", "version": 1,
+                    "text": "worker_processes 1;\nworker_processes 2;\n" }
+            }}),
+            json!({ "jsonrpc": "2.0", "method": "textDocument/didChange", "params": {
+                "textDocument": { "uri": uri, "version": 2 },
+                "contentChanges": [{ "text": "worker_processes 1;\n" }]
+            }}),
+            json!({ "jsonrpc": "2.0", "id": 2, "method": "textDocument/hover", "params": {
+                "textDocument": { "uri": uri }, "position": { "line": 0, "character": 3 }
+            }}),
+            json!({ "jsonrpc": "2.0", "id": 3, "method": "workspace/symbol", "params": {} }),
+            json!({ "jsonrpc": "2.0", "id": 4, "method": "shutdown" }),
+            json!({ "jsonrpc": "2.0", "method": "exit" }),
+            json!({ "jsonrpc": "2.0", "id": 5, "method": "shutdown" }),
+        ]);
+
+        let mut output = Vec::new();
+        run(input.as_slice(), &mut output).unwrap();
+
+        let mut reader = output.as_slice();
+        let mut replies = Vec::new();
+        while let Some(message) = read_message(&mut reader).unwrap() {
+            replies.push(message);
+        }
+        assert_eq!(replies.len(), 6);
+        assert_eq!(replies[0]["result"]["capabilities"]["hoverProvider"], true);
+
+        let opened = &replies[1]["params"];
+        assert_eq!(opened["uri"], uri);
+        assert_eq!(opened["diagnostics"][0]["code"], "duplicate_directive");
+        assert_eq!(opened["diagnostics"][0]["range"]["start"]["line"], 1);
+        assert_eq!(replies[2]["params"]["diagnostics"], json!([]));
+
+        assert!(replies[3]["result"]["contents"]["value"]
+            .as_str()
+            .unwrap()
+            .contains("worker_processes"));
+        assert_eq!(replies[4]["error"]["code"], METHOD_NOT_FOUND);
+        // Nothing is answered after `exit`
+        assert_eq!(replies.last().unwrap()["id"], 4);
+    }
+}
@@ -0,0 +1,437 @@
+//! Caddyfile output
+
+use super::{
+    check_location, is_named, location_line, with_port, MigrationWarning, Plan, ProxyTarget, Site,
+};
+use crate::types::{Location, LocationModifier, TryFilesFallback, Upstream};
+use std::fmt::Write as _;
+
+pub(super) fn render(plan: &Plan<'_>, warnings: &mut Vec<MigrationWarning>) -> String {
+    let mut out = String::from(
+        "# Converted from an nginx configuration by nginx-discovery.\n\
+         # Review the migration warnings before use.\n",
+    );
+    for site in &plan.sites {
+        out.push('\n');
+        render_site(plan, site, &mut out, warnings);
+    }
+    out
+}
+
+/// Site addresses: every name on every listener, or the bare port
+fn addresses(site: &Site<'_>, warnings: &mut Vec<MigrationWarning>) -> Vec<String> {
+    let mut addresses = Vec::new();
+    for listen in site.listens() {
+        let (scheme, default_port) = if listen.ssl {
+            ("https", 443)
+        } else {
+            ("http", 80)
+        };
+        let port = if listen.port == default_port {
+            String::new()
+        } else {
+            format!(":{}", listen.port)
+        };
+        if site.names.is_empty() {
+            if listen.ssl {
+                warnings.push(MigrationWarning::new(
+                    &site.label,
+                    Some(site.line()),
+                    "Caddy needs a host name to manage the certificate of a TLS site",
+                ));
+            }
+            addresses.push(format!(":{}", listen.port));
+        } else {
+            addresses.extend(
+                site.names
+                    .iter()
+                    .map(|name| format!("{scheme}://{name}{port}")),
+            );
+        }
+    }
+    addresses.dedup();
+    addresses
+}
+
+fn render_site(
+    plan: &Plan<'_>,
+    site: &Site<'_>,
+    out: &mut String,
+    warnings: &mut Vec<MigrationWarning>,
+) {
+    let _ = writeln!(out, "{} {{", addresses(site, warnings).join(", "));
+    if site.server.has_ssl() {
+        let certificate = site
+            .certificate()
+            .map_or_else(String::new, |cert| format!(" ({cert})"));
+        warnings.push(MigrationWarning::new(
+            &site.label,
+            Some(site.line()),
+            format!(
+                "Caddy obtains certificates automatically; use the tls directive to keep \
+                 the current one{certificate}"
+            ),
+        ));
+    }
+    if let Some(root) = &site.server.root {
+        let _ = writeln!(out, "\troot * {}", root.display());
+    }
+
+    let locations = &site.server.locations;
+    let plain_prefix = locations
+        .iter()
+        .any(|l| l.modifier == LocationModifier::None && l.path != "/" && !is_named(l));
+    let mut regexes = 0;
+    for location in locations {
+        check_location(site, location, warnings);
+        if is_named(location) {
+            continue;
+        }
+        let matcher = match location.modifier {
+            LocationModifier::None if location.path == "/" => String::new(),
+            LocationModifier::None | LocationModifier::PrefixPriority => {
+                format!(" {}*", location.path)
+            }
+            LocationModifier::Exact => format!(" {}", location.path),
+            LocationModifier::Regex | LocationModifier::RegexCaseInsensitive => {
+                regexes += 1;
+                let flags = if location.modifier == LocationModifier::Regex {
+                    ""
+                } else {
+                    "(?i)"
+                };
+                let _ = writeln!(
+                    out,
+                    "\t@location{regexes} path_regexp {flags}{}",
+                    location.path
+                );
+                if plain_prefix {
+                    warnings.push(MigrationWarning::new(
+                        site.location_subject(location),
+                        location_line(site, location),
+                        "Caddy tries path matchers before regular expressions; check that \
+                         no prefix location now takes its requests",
+                    ));
+                }
+                format!(" @location{regexes}")
+            }
+        };
+
+        let mut body = String::new();
+        let strip = handle_location(plan, site, location, &mut body, warnings);
+        let handle = if strip { "handle_path" } else { "handle" };
+        let _ = writeln!(out, "\t{handle}{matcher} {{");
+        if body.is_empty() {
+            out.push_str("\t\t# nothing converted, see the migration warnings\n");
+        }
+        out.push_str(&body);
+        out.push_str("\t}\n");
+    }
+
+    if !site.has_catch_all() && site.server.root.is_some() {
+        out.push_str("\thandle {\n");
+        file_server(site, "", out);
+        out.push_str("\t}\n");
+    }
+    out.push_str("}\n");
+}
+
+/// Directives of the `handle` block of a location; `true` when the
+/// matched prefix must be stripped (`handle_path`)
+fn handle_location(
+    plan: &Plan<'_>,
+    site: &Site<'_>,
+    location: &Location,
+    out: &mut String,
+    warnings: &mut Vec<MigrationWarning>,
+) -> bool {
+    let subject = site.location_subject(location);
+    let line = location_line(site, location);
+
+    if let Some(proxy_pass) = &location.proxy_pass {
+        let Some(target) = plan.proxy_target(proxy_pass, &subject, line, warnings) else {
+            return false;
+        };
+        let mut strip = false;
+        if let Some(uri) = &target.uri {
+            let prefix = matches!(
+                location.modifier,
+                LocationModifier::None | LocationModifier::PrefixPriority
+            );
+            if prefix {
+                strip = true;
+                let base = uri.trim_end_matches('/');
+                if !base.is_empty() {
+                    let _ = writeln!(out, "\t\trewrite * {base}{{path}}");
+                }
+            } else {
+                warnings.push(MigrationWarning::new(
+                    &subject,
+                    line,
+                    format!("The URI part {uri} of proxy_pass is not converted"),
+                ));
+            }
+        }
+        reverse_proxy(&target, &subject, line, out, warnings);
+        return strip;
+    }
+
+    if let Some(root) = &location.root {
+        let _ = writeln!(out, "\t\troot * {}", root.display());
+    }
+    if site.root_of(location).is_some() || location.try_files.is_some() {
+        let mut try_files = String::new();
+        if let Some(files) = &location.try_files {
+            let mut args: Vec<String> = files.files.iter().map(|f| placeholders(f)).collect();
+            match &files.fallback {
+                TryFilesFallback::Uri(uri) => args.push(placeholders(uri)),
+                TryFilesFallback::Code(404) => {}
+                fallback => warnings.push(MigrationWarning::new(
+                    &subject,
+                    line,
+                    format!("try_files fallback {fallback} is not converted"),
+                )),
+            }
+            let _ = writeln!(try_files, "\t\ttry_files {}", args.join(" "));
+        }
+        file_server(site, &try_files, out);
+    }
+    false
+}
+
+/// `try_files` (if any) followed by `file_server`
+fn file_server(site: &Site<'_>, try_files: &str, out: &mut String) {
+    out.push_str(try_files);
+    if site.server.index.is_empty() {
+        out.push_str("\t\tfile_server\n");
+    } else {
+        let _ = writeln!(
+            out,
+            "\t\tfile_server {{\n\t\t\tindex {}\n\t\t}}",
+            site.server.index.join(" ")
+        );
+    }
+}
+
+fn reverse_proxy(
+    target: &ProxyTarget<'_>,
+    subject: &str,
+    line: Option<usize>,
+    out: &mut String,
+    warnings: &mut Vec<MigrationWarning>,
+) {
+    let scheme = if target.https { "https://" } else { "" };
+    let Some(upstream) = target.upstream else {
+        let _ = writeln!(out, "\t\treverse_proxy {scheme}{}", target.authority);
+        return;
+    };
+
+    let mut servers = Vec::new();
+    for server in upstream.servers.iter().filter(|s| !s.down) {
+        match with_port(&server.address, target.default_port()) {
+            Some(address) if !server.backup => servers.push(format!("{scheme}{address}")),
+            Some(_) => warnings.push(upstream_warning(
+                upstream,
+                format!("Backup server {} is not converted", server.address),
+            )),
+            None => warnings.push(upstream_warning(
+                upstream,
+                format!("UNIX socket server {} is not converted", server.address),
+            )),
+        }
+        if server.weight.is_some_and(|w| w != 1) {
+            warnings.push(upstream_warning(
+                upstream,
+                format!("The weight of {} is not converted", server.address),
+            ));
+        }
+    }
+    if servers.is_empty() {
+        warnings.push(MigrationWarning::new(
+            subject,
+            line,
+            format!(
+                "upstream {} has no server Caddy can proxy to",
+                upstream.name
+            ),
+        ));
+        return;
+    }
+
+    let policy = match upstream.method.as_deref() {
+        None => Some("round_robin"),
+        Some("least_conn") => Some("least_conn"),
+        Some("ip_hash") => Some("ip_hash"),
+        Some(method) if method.starts_with("random") => Some("random"),
+        Some(method) if method.starts_with("hash $request_uri") => Some("uri_hash"),
+        Some(method) => {
+            warnings.push(upstream_warning(
+                upstream,
+                format!("Load balancing method {method} is not converted"),
+            ));
+            None
+        }
+    };
+    let mut options = Vec::new();
+    if servers.len() > 1 {
+        options.extend(policy.map(|p| format!("lb_policy {p}")));
+    }
+    let primary = upstream.primary_servers().next();
+    if let Some(fail_timeout) = primary.and_then(|s| s.fail_timeout.as_deref()) {
+        options.push(format!("fail_duration {fail_timeout}"));
+    }
+    if let Some(max_fails) = primary.and_then(|s| s.max_fails) {
+        options.push(format!("max_fails {max_fails}"));
+    }
+
+    if options.is_empty() {
+        let _ = writeln!(out, "\t\treverse_proxy {}", servers.join(" "));
+    } else {
+        let _ = writeln!(out, "\t\treverse_proxy {} {{", servers.join(" "));
+        for option in options {
+            let _ = writeln!(out, "\t\t\t{option}");
+        }
+        out.push_str("\t\t}\n");
+    }
+}
+
+fn upstream_warning(upstream: &Upstream, message: String) -> MigrationWarning {
+    MigrationWarning::new(
+        format!("upstream {}", upstream.name),
+        Some(upstream.line),
+        message,
+    )
+}
+
+/// nginx variables of `try_files` as Caddy placeholders
+fn placeholders(value: &str) -> String {
+    value
+        .replace("$request_uri", "{uri}")
+        .replace("$query_string", "{query}")
+        .replace("$is_args", "?")
+        .replace("$args", "{query}")
+        .replace("$uri", "{path}")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::migrate::{migrate, MigrationTarget};
+    use crate::parse;
+
+    #[test]
+    fn test_caddyfile() {
+        let config = parse(
+            r"
+http {
+    upstream app {
+        server 10.0.0.1:8080 max_fails=3 fail_timeout=30s;
+        server 10.0.0.2:8080;
+        server 10.0.0.3:8080 backup;
+    }
+    server {
+        listen 80;
+        listen 443 ssl;
+        server_name example.com;
+        ssl_certificate /etc/ssl/example.pem;
+        root /srv/www;
+        index index.html;
+        location / { try_files $uri $uri/ /index.html; }
+        location /api/ { proxy_pass http://app/v1/; }
+        location ~* \.(png|jpg)$ { root /srv/images; }
+        location = /health { proxy_pass http://127.0.0.1:9000; }
+    }
+}
+",
+        )
+        .unwrap();
+        let migration = migrate(&config, MigrationTarget::Caddy);
+
+        assert_eq!(
+            migration
+                .output
+                .lines()
+                .skip(3)
+                .collect::<Vec<_>>()
+                .join("\n"),
+            r"http://example.com, https://example.com {
+	root * /srv/www
+	handle {
+		try_files {path} {path}/ /index.html
+		file_server {
+			index index.html
+		}
+	}
+	handle_path /api/* {
+		rewrite * /v1{path}
+		reverse_proxy 10.0.0.1:8080 10.0.0.2:8080 {
+			lb_policy round_robin
+			fail_duration 30s
+			max_fails 3
+		}
+	}
+	@location1 path_regexp (?i)\.(png|jpg)$
+	handle @location1 {
+		root * /srv/images
+		file_server {
+			index index.html
+		}
+	}
+	handle /health {
+		reverse_proxy 127.0.0.1:9000
+	}
+}"
+        );
+        let messages: Vec<_> = migration.warnings.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "line 3: upstream app: Backup server 10.0.0.3:8080 is not converted",
+                "line 8: server example.com: Caddy obtains certificates automatically; \
+                 use the tls directive to keep the current one (/etc/ssl/example.pem)",
+                "line 17: location ~* \\.(png|jpg)$ in server example.com: Caddy tries path \
+                 matchers before regular expressions; check that no prefix location now takes \
+                 its requests",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_server_root() {
+        let config = parse(
+            r"
+http {
+    server {
+        listen 80;
+        server_name example.com;
+        root /var/www/html;
+        index index.html;
+        location /api/ { proxy_pass http://10.0.0.1:8080; }
+    }
+}
+",
+        )
+        .unwrap();
+        let migration = migrate(&config, MigrationTarget::Caddy);
+
+        assert_eq!(
+            migration
+                .output
+                .lines()
+                .skip(3)
+                .collect::<Vec<_>>()
+                .join("\n"),
+            r"http://example.com {
+	root * /var/www/html
+	handle /api/* {
+		reverse_proxy 10.0.0.1:8080
+	}
+	handle {
+		file_server {
+			index index.html
+		}
+	}
+}"
+        );
+        assert!(migration.warnings.is_empty());
+    }
+}
@@ -0,0 +1,499 @@
+//! `HAProxy` configuration output
+
+use super::{
+    check_location, first_match_order, location_line, with_port, MigrationWarning, Plan,
+    ProxyTarget, Site,
+};
+use crate::types::{Location, LocationModifier, Upstream};
+use std::fmt::Write as _;
+
+const PREAMBLE: &str = "\
+# Converted from an nginx configuration by nginx-discovery.
+# Review the migration warnings before use.
+
+global
+\tlog stdout format raw local0
+
+defaults
+\tmode http
+\tlog global
+\toption httplog
+\ttimeout connect 5s
+\ttimeout client 60s
+\ttimeout server 60s
+";
+
+/// Directory `HAProxy` loads certificate bundles from in the output
+const CERT_DIR: &str = "/etc/haproxy/certs/";
+
+/// Sites sharing an address and port
+struct Frontend {
+    address: String,
+    port: u16,
+    ssl: bool,
+    http2: bool,
+    sites: Vec<usize>,
+    default: Option<usize>,
+}
+
+impl Frontend {
+    fn name(&self) -> String {
+        if self.address == "*" {
+            format!("http_{}", self.port)
+        } else {
+            format!("http_{}_{}", sanitize(&self.address), self.port)
+        }
+    }
+
+    fn bind(&self) -> String {
+        let address = match self.address.as_str() {
+            "*" => String::new(),
+            address if address.contains(':') => format!("[{address}]"),
+            address => address.to_string(),
+        };
+        let mut bind = format!("bind {address}:{}", self.port);
+        if self.ssl {
+            let _ = write!(bind, " ssl crt {CERT_DIR}");
+            if self.http2 {
+                bind.push_str(" alpn h2,http/1.1");
+            }
+        }
+        bind
+    }
+}
+
+/// A `use_backend` rule: path condition and backend
+type Rule = (Option<String>, String);
+
+pub(super) fn render(plan: &Plan<'_>, warnings: &mut Vec<MigrationWarning>) -> String {
+    let mut backends: Vec<(String, String)> = Vec::new();
+    let rules: Vec<Vec<Rule>> = plan
+        .sites
+        .iter()
+        .map(|site| site_rules(plan, site, &mut backends, warnings))
+        .collect();
+
+    let mut out = String::from(PREAMBLE);
+    for frontend in frontends(plan) {
+        let _ = writeln!(out, "\nfrontend {}", frontend.name());
+        let _ = writeln!(out, "\t{}", frontend.bind());
+
+        for &i in &frontend.sites {
+            for acl in host_acls(&plan.sites[i].names) {
+                let _ = writeln!(out, "\tacl host_{i} {acl}");
+            }
+        }
+        for &i in &frontend.sites {
+            if plan.sites[i].names.is_empty() {
+                continue;
+            }
+            for (path, backend) in &rules[i] {
+                match path {
+                    Some(path) => {
+                        let _ = writeln!(out, "\tuse_backend {backend} if host_{i} {path}");
+                    }
+                    None => {
+                        let _ = writeln!(out, "\tuse_backend {backend} if host_{i}");
+                    }
+                }
+            }
+        }
+        if let Some(i) = frontend.default {
+            for (path, backend) in &rules[i] {
+                match path {
+                    Some(path) => {
+                        let _ = writeln!(out, "\tuse_backend {backend} if {path}");
+                    }
+                    None => {
+                        let _ = writeln!(out, "\tdefault_backend {backend}");
+                    }
+                }
+            }
+        }
+    }
+    for (name, body) in backends {
+        let _ = write!(out, "\nbackend {name}\n{body}");
+    }
+    out
+}
+
+/// Group the listeners of every site by address and port
+fn frontends(plan: &Plan<'_>) -> Vec<Frontend> {
+    let mut frontends: Vec<Frontend> = Vec::new();
+    for (i, site) in plan.sites.iter().enumerate() {
+        for listen in site.listens() {
+            let position = frontends
+                .iter()
+                .position(|f| f.address == listen.address && f.port == listen.port);
+            let frontend = if let Some(position) = position {
+                &mut frontends[position]
+            } else {
+                frontends.push(Frontend {
+                    address: listen.address.clone(),
+                    port: listen.port,
+                    ssl: false,
+                    http2: false,
+                    sites: Vec::new(),
+                    default: None,
+                });
+                frontends.last_mut().expect("just pushed")
+            };
+            frontend.ssl |= listen.ssl;
+            frontend.http2 |= listen.http2;
+            if !frontend.sites.contains(&i) {
+                frontend.sites.push(i);
+            }
+            if listen.default_server || frontend.default.is_none() {
+                frontend.default = Some(i);
+            }
+        }
+    }
+    frontends
+}
+
+/// ACL criteria matching the host names of a site
+fn host_acls(names: &[String]) -> Vec<String> {
+    let host = "req.hdr(host),field(1,:)";
+    let mut exact = Vec::new();
+    let mut acls = Vec::new();
+    for name in names {
+        if let Some(suffix) = name.strip_prefix('*') {
+            acls.push(format!("{host} -m end -i {suffix}"));
+        } else if let Some(prefix) = name.strip_suffix('*') {
+            acls.push(format!("{host} -m beg -i {prefix}"));
+        } else {
+            exact.push(name.as_str());
+        }
+    }
+    if !exact.is_empty() {
+        acls.insert(0, format!("{host} -i {}", exact.join(" ")));
+    }
+    acls
+}
+
+/// Rules for the proxied locations of a site, in first-match order
+fn site_rules(
+    plan: &Plan<'_>,
+    site: &Site<'_>,
+    backends: &mut Vec<(String, String)>,
+    warnings: &mut Vec<MigrationWarning>,
+) -> Vec<Rule> {
+    if site.server.has_ssl() {
+        let certificate = site
+            .certificate()
+            .unwrap_or_else(|| "the certificate".to_string());
+        warnings.push(MigrationWarning::new(
+            &site.label,
+            Some(site.line()),
+            format!("Combine {certificate} and its key into one PEM file in {CERT_DIR}"),
+        ));
+    }
+    for location in &site.server.locations {
+        check_location(site, location, warnings);
+    }
+    if let Some(root) = site
+        .server
+        .root
+        .as_deref()
+        .filter(|_| !site.has_catch_all())
+    {
+        warnings.push(MigrationWarning::new(
+            &site.label,
+            Some(site.line()),
+            format!(
+                "HAProxy does not serve files; requests no location matches are served \
+                 from {} and are not converted",
+                root.display()
+            ),
+        ));
+    }
+
+    let mut rules = Vec::new();
+    for i in first_match_order(&site.server.locations) {
+        let location = &site.server.locations[i];
+        let subject = site.location_subject(location);
+        let line = location_line(site, location);
+        let Some(proxy_pass) = &location.proxy_pass else {
+            if site.root_of(location).is_some() || location.try_files.is_some() {
+                warnings.push(MigrationWarning::new(
+                    &subject,
+                    line,
+                    "HAProxy does not serve files; static content is not converted",
+                ));
+            }
+            continue;
+        };
+        let Some(target) = plan.proxy_target(proxy_pass, &subject, line, warnings) else {
+            continue;
+        };
+        if let Some(uri) = &target.uri {
+            warnings.push(MigrationWarning::new(
+                &subject,
+                line,
+                format!("The URI part {uri} of proxy_pass is not converted"),
+            ));
+        }
+        let name = backend(&target, backends, warnings);
+        rules.push((path_condition(location), name));
+    }
+    rules
+}
+
+/// Anonymous ACL for the location path, `None` for `location /`
+fn path_condition(location: &Location) -> Option<String> {
+    let path = &location.path;
+    Some(match location.modifier {
+        LocationModifier::None if path == "/" => return None,
+        LocationModifier::None | LocationModifier::PrefixPriority => {
+            format!("{{ path_beg {path} }}")
+        }
+        LocationModifier::Exact => format!("{{ path {path} }}"),
+        LocationModifier::Regex => format!("{{ path_reg {path} }}"),
+        LocationModifier::RegexCaseInsensitive => format!("{{ path_reg -i {path} }}"),
+    })
+}
+
+/// Name of the backend for a proxy target, adding it on first use
+fn backend(
+    target: &ProxyTarget<'_>,
+    backends: &mut Vec<(String, String)>,
+    warnings: &mut Vec<MigrationWarning>,
+) -> String {
+    let name = sanitize(target.upstream.map_or(&target.authority, |u| &u.name));
+    if backends.iter().any(|(existing, _)| *existing == name) {
+        return name;
+    }
+
+    let tls = if target.https { " ssl verify none" } else { "" };
+    let mut body = String::new();
+    if let Some(upstream) = target.upstream {
+        upstream_body(upstream, target, tls, &mut body, warnings);
+    } else {
+        let address = with_port(&target.authority, target.default_port())
+            .unwrap_or_else(|| target.authority.clone());
+        let _ = writeln!(body, "\tserver s1 {address}{tls}");
+    }
+    if target.https {
+        let (subject, line) = match target.upstream {
+            Some(upstream) => (format!("upstream {}", upstream.name), Some(upstream.line)),
+            None => (format!("backend {name}"), None),
+        };
+        warnings.push(MigrationWarning::new(
+            subject,
+            line,
+            "TLS to the backend is not verified; add ca-file and verify required",
+        ));
+    }
+    backends.push((name.clone(), body));
+    name
+}
+
+fn upstream_body(
+    upstream: &Upstream,
+    target: &ProxyTarget<'_>,
+    tls: &str,
+    body: &mut String,
+    warnings: &mut Vec<MigrationWarning>,
+) {
+    let mut warn = |message: String| {
+        warnings.push(MigrationWarning::new(
+            format!("upstream {}", upstream.name),
+            Some(upstream.line),
+            message,
+        ));
+    };
+
+    let balance = match upstream.method.as_deref() {
+        None => "roundrobin",
+        Some("least_conn") => "leastconn",
+        Some("ip_hash") => "source",
+        Some(method) if method.starts_with("hash $remote_addr") => "source",
+        Some(method) if method.starts_with("hash $request_uri") => "uri",
+        Some(method) if method.starts_with("random") => "random",
+        Some(method) => {
+            warn(format!("Load balancing method {method} is not converted"));
+            "roundrobin"
+        }
+    };
+    let _ = writeln!(body, "\tbalance {balance}");
+
+    for (i, server) in upstream.servers.iter().enumerate() {
+        let Some(address) = with_port(&server.address, target.default_port()) else {
+            warn(format!(
+                "UNIX socket server {} is not converted",
+                server.address
+            ));
+            continue;
+        };
+        let _ = write!(body, "\tserver s{} {address}{tls}", i + 1);
+        if let Some(weight) = server.weight {
+            let _ = write!(body, " weight {weight}");
+        }
+        if server.backup {
+            body.push_str(" backup");
+        }
+        if server.down {
+            body.push_str(" disabled");
+        }
+        body.push('\n');
+    }
+    if upstream
+        .servers
+        .iter()
+        .any(|s| s.max_fails.is_some() || s.fail_timeout.is_some())
+    {
+        warn(
+            "Passive health checks (max_fails, fail_timeout) are not converted; \
+             consider active checks with check"
+                .to_string(),
+        );
+    }
+}
+
+/// A name `HAProxy` accepts for a section
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::migrate::{migrate, MigrationTarget};
+    use crate::parse;
+
+    #[test]
+    fn test_haproxy() {
+        let config = parse(
+            r"
+http {
+    upstream app {
+        least_conn;
+        server 10.0.0.1:8080 weight=3;
+        server 10.0.0.2:8080 backup;
+    }
+    server {
+        listen 80 default_server;
+        server_name example.com *.example.org;
+        location / { proxy_pass http://app; }
+        location ~ ^/v\d+/ { proxy_pass http://app; }
+        location = /status { proxy_pass http://10.0.0.9; }
+        location /static/ { root /srv; }
+    }
+    server {
+        listen 80;
+        listen 443 ssl http2;
+        server_name api.example.com;
+        ssl_certificate /etc/ssl/api.pem;
+        location /v2/ { proxy_pass https://10.0.1.1:8443/; }
+    }
+}
+",
+        )
+        .unwrap();
+        let migration = migrate(&config, MigrationTarget::Haproxy);
+        let output = migration.output.split_once("\nfrontend ").unwrap().1;
+
+        assert_eq!(
+            output,
+            r"http_80
+	bind :80
+	acl host_0 req.hdr(host),field(1,:) -i example.com
+	acl host_0 req.hdr(host),field(1,:) -m end -i .example.org
+	acl host_1 req.hdr(host),field(1,:) -i api.example.com
+	use_backend 10.0.0.9 if host_0 { path /status }
+	use_backend app if host_0 { path_reg ^/v\d+/ }
+	use_backend app if host_0
+	use_backend 10.0.1.1:8443 if host_1 { path_beg /v2/ }
+	use_backend 10.0.0.9 if { path /status }
+	use_backend app if { path_reg ^/v\d+/ }
+	default_backend app
+
+frontend http_443
+	bind :443 ssl crt /etc/haproxy/certs/ alpn h2,http/1.1
+	acl host_1 req.hdr(host),field(1,:) -i api.example.com
+	use_backend 10.0.1.1:8443 if host_1 { path_beg /v2/ }
+	use_backend 10.0.1.1:8443 if { path_beg /v2/ }
+
+backend 10.0.0.9
+	server s1 10.0.0.9:80
+
+backend app
+	balance leastconn
+	server s1 10.0.0.1:8080 weight 3
+	server s2 10.0.0.2:8080 backup
+
+backend 10.0.1.1:8443
+	server s1 10.0.1.1:8443 ssl verify none
+"
+        );
+        let messages: Vec<_> = migration
+            .warnings
+            .iter()
+            .map(|w| w.message.as_str())
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                "HAProxy does not serve files; static content is not converted",
+                "Combine /etc/ssl/api.pem and its key into one PEM file in /etc/haproxy/certs/",
+                "The URI part / of proxy_pass is not converted",
+                "TLS to the backend is not verified; add ca-file and verify required",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_server_root() {
+        let config = parse(
+            r"
+http {
+    server {
+        listen 80;
+        server_name example.com;
+        root /var/www/html;
+        index index.html;
+        location /api/ { proxy_pass http://10.0.0.1:8080; }
+    }
+}
+",
+        )
+        .unwrap();
+        let migration = migrate(&config, MigrationTarget::Haproxy);
+
+        assert!(migration
+            .output
+            .contains("\tuse_backend 10.0.0.1:8080 if host_0 { path_beg /api/ }\n"));
+        assert!(!migration.output.contains("default_backend"));
+        let messages: Vec<_> = migration.warnings.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "line 3: server example.com: HAProxy does not serve files; requests no location \
+                 matches are served from /var/www/html and are not converted"
+            ]
+        );
+
+        // A location serving files from the inherited root warns as well
+        let config = parse(
+            "http { server { root /var/www; location / { proxy_pass http://10.0.0.1; } \
+             location /static/ {} } }",
+        )
+        .unwrap();
+        let migration = migrate(&config, MigrationTarget::Haproxy);
+        let messages: Vec<_> = migration.warnings.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "line 1: location /static/ in server _: HAProxy does not serve files; \
+                 static content is not converted"
+            ]
+        );
+    }
+}
@@ -0,0 +1,574 @@
+//! Migration to other web servers
+//!
+//! [`migrate`] translates the extracted model — servers, locations and
+//! upstreams — into a Caddyfile, an `HAProxy` configuration or an nginx Unit
+//! JSON configuration. The result is a starting point for a migration, not
+//! a drop-in replacement: whatever the target cannot express, and every
+//! directive the model does not carry (`return`, `rewrite`, `add_header`,
+//! ...), is reported as a [`MigrationWarning`] instead of being dropped
+//! silently.
+//!
+//! | Target | nginx concepts covered |
+//! |--------|------------------------|
+//! | [`Caddy`](MigrationTarget::Caddy) | sites, `handle` per location, `reverse_proxy` with load balancing, `root`/`try_files`/`file_server` |
+//! | [`Haproxy`](MigrationTarget::Haproxy) | one frontend per listener, host and path ACLs, one backend per upstream |
+//! | [`Unit`](MigrationTarget::Unit) | listeners, routes with host and URI matches, upstreams, `share` for static files |
+//!
+//! # Examples
+//!
+//! ```
+//! use nginx_discovery::{migrate::{migrate, MigrationTarget}, parse};
+//!
+//! let config = parse(r"
+//! http {
+//!     upstream app { least_conn; server 10.0.0.1:8080; server 10.0.0.2:8080; }
+//!     server {
+//!         listen 80;
+//!         server_name example.com;
+//!         location /api { proxy_pass http://app; }
+//!         location / { return 204; }
+//!     }
+//! }
+//! ")?;
+//! let migration = migrate(&config, MigrationTarget::Caddy);
+//!
+//! assert!(migration.output.contains("http://example.com {"));
+//! assert!(migration.output.contains("reverse_proxy 10.0.0.1:8080 10.0.0.2:8080"));
+//! assert_eq!(migration.warnings[0].message, "Not converted: return");
+//! # Ok::<(), nginx_discovery::Error>(())
+//! ```
+
+mod caddy;
+mod haproxy;
+mod unit;
+
+use crate::ast::{Config, Directive};
+use crate::extract;
+use crate::types::{ListenDirective, Location, LocationModifier, Server, Upstream};
+use crate::{Error, Result};
+use std::fmt;
+use std::str::FromStr;
+
+/// Server block directives the translation takes into account
+///
+/// Logging and certificates are set up per target and reported there.
+const SERVER_CONVERTED: &[&str] = &[
+    "access_log",
+    "error_log",
+    "index",
+    "listen",
+    "location",
+    "root",
+    "server_name",
+    "ssl_certificate",
+    "ssl_certificate_key",
+];
+
+/// Location block directives the translation takes into account
+const LOCATION_CONVERTED: &[&str] = &["access_log", "error_log", "proxy_pass", "root", "try_files"];
+
+/// Configuration format to migrate to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum MigrationTarget {
+    /// Caddyfile
+    Caddy,
+    /// `haproxy.cfg`
+    Haproxy,
+    /// nginx Unit JSON configuration
+    Unit,
+}
+
+impl MigrationTarget {
+    /// Every target
+    pub const ALL: [Self; 3] = [Self::Caddy, Self::Haproxy, Self::Unit];
+}
+
+impl fmt::Display for MigrationTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Caddy => "caddy",
+            Self::Haproxy => "haproxy",
+            Self::Unit => "unit",
+        })
+    }
+}
+
+impl FromStr for MigrationTarget {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "caddy" | "caddyfile" => Ok(Self::Caddy),
+            "haproxy" => Ok(Self::Haproxy),
+            "unit" | "nginx-unit" => Ok(Self::Unit),
+            _ => Err(Error::InvalidInput(format!(
+                "unknown migration target '{s}': expected caddy, haproxy or unit"
+            ))),
+        }
+    }
+}
+
+/// A construct the translation dropped or only approximated
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MigrationWarning {
+    /// What the warning is about: `server example.com`, `location /api in
+    /// server example.com`, `upstream app`
+    pub subject: String,
+
+    /// Line of the block in the nginx configuration, when known
+    pub line: Option<usize>,
+
+    /// What was not converted and why
+    pub message: String,
+}
+
+impl MigrationWarning {
+    fn new(subject: impl Into<String>, line: Option<usize>, message: impl Into<String>) -> Self {
+        Self {
+            subject: subject.into(),
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for MigrationWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(line) = self.line {
+            write!(f, "line {line}: ")?;
+        }
+        write!(f, "{}: {}", self.subject, self.message)
+    }
+}
+
+/// Result of [`migrate`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Migration {
+    /// Format of `output`
+    pub target: MigrationTarget,
+
+    /// The translated configuration
+    pub output: String,
+
+    /// Constructs that were dropped or approximated, in file order
+    pub warnings: Vec<MigrationWarning>,
+}
+
+/// Translate a configuration into another server's format
+///
+/// See the [module documentation](self).
+#[must_use]
+pub fn migrate(config: &Config, target: MigrationTarget) -> Migration {
+    let mut warnings = Vec::new();
+    let plan = Plan::new(config, &mut warnings);
+    let output = match target {
+        MigrationTarget::Caddy => caddy::render(&plan, &mut warnings),
+        MigrationTarget::Haproxy => haproxy::render(&plan, &mut warnings),
+        MigrationTarget::Unit => unit::render(&plan, &mut warnings),
+    };
+    // A warning about an upstream comes once per location using it
+    let mut seen = Vec::new();
+    warnings.retain(|w| {
+        let new = !seen.contains(w);
+        if new {
+            seen.push(w.clone());
+        }
+        new
+    });
+    warnings.sort_by_key(|w| w.line.unwrap_or(usize::MAX));
+    Migration {
+        target,
+        output,
+        warnings,
+    }
+}
+
+/// An `http` server with the block it was extracted from
+struct Site<'a> {
+    server: Server,
+    block: &'a Directive,
+    /// Host names to match, without `_` and with `.example.com` expanded
+    names: Vec<String>,
+    label: String,
+}
+
+impl Site<'_> {
+    fn line(&self) -> usize {
+        self.block.span.line
+    }
+
+    /// Block of the `i`th location of the server
+    fn location_block(&self, i: usize) -> Option<&Directive> {
+        self.block.find_children("location").get(i).copied()
+    }
+
+    fn location_subject(&self, location: &Location) -> String {
+        format!("location {} in {}", location_label(location), self.label)
+    }
+
+    /// Listeners, port 80 when the server has no `listen`
+    fn listens(&self) -> Vec<ListenDirective> {
+        if self.server.listen.is_empty() {
+            vec![ListenDirective::new("*", 80)]
+        } else {
+            self.server.listen.clone()
+        }
+    }
+
+    /// Certificate files of the server
+    fn certificate(&self) -> Option<String> {
+        self.block
+            .find_children("ssl_certificate")
+            .first()?
+            .first_arg()
+    }
+
+    /// Whether a `location /` takes the requests no other location matches;
+    /// without one nginx serves them from the server `root`
+    fn has_catch_all(&self) -> bool {
+        self.server
+            .locations
+            .iter()
+            .any(|l| l.modifier == LocationModifier::None && l.path == "/")
+    }
+
+    /// `root` of a location, inherited from the server
+    fn root_of<'l>(&'l self, location: &'l Location) -> Option<&'l std::path::Path> {
+        location.root.as_deref().or(self.server.root.as_deref())
+    }
+}
+
+/// The parts of a configuration every target translates
+struct Plan<'a> {
+    sites: Vec<Site<'a>>,
+    upstreams: Vec<Upstream>,
+}
+
+impl<'a> Plan<'a> {
+    fn new(config: &'a Config, warnings: &mut Vec<MigrationWarning>) -> Self {
+        let index = config.index();
+        let blocks = index
+            .find("server")
+            .into_iter()
+            .filter(|&id| index.get(id).is_block());
+        let servers = extract::servers(config).unwrap_or_default();
+
+        let mut sites = Vec::new();
+        for (id, server) in blocks.zip(servers) {
+            let block = index.get(id);
+            if index.enclosing(id, "stream").is_some() {
+                continue;
+            }
+            let label = format!("server {}", server.primary_name().unwrap_or("_"));
+            let mut names = Vec::new();
+            for name in &server.server_names {
+                if name.starts_with('~') {
+                    warnings.push(MigrationWarning::new(
+                        &label,
+                        Some(block.span.line),
+                        format!("Regular expression server name {name} is not converted"),
+                    ));
+                } else if let Some(domain) = name.strip_prefix('.') {
+                    names.push(domain.to_string());
+                    names.push(format!("*.{domain}"));
+                } else if !name.is_empty() && name != "_" {
+                    names.push(name.clone());
+                }
+            }
+            let site = Site {
+                server,
+                block,
+                names,
+                label,
+            };
+            site.check_converted(warnings);
+            sites.push(site);
+        }
+
+        for stream in config.find_directives_recursive("stream") {
+            warnings.push(MigrationWarning::new(
+                "stream",
+                Some(stream.span.line),
+                "TCP/UDP proxying in stream blocks is not converted",
+            ));
+        }
+
+        let upstreams = extract::upstreams(config)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|u| !u.stream)
+            .collect();
+        Plan { sites, upstreams }
+    }
+
+    fn upstream(&self, name: &str) -> Option<&Upstream> {
+        self.upstreams.iter().find(|u| u.name == name)
+    }
+
+    /// Where a `proxy_pass` points, `None` (with a warning) for variables,
+    /// sockets and other schemes
+    fn proxy_target(
+        &self,
+        proxy_pass: &str,
+        subject: &str,
+        line: Option<usize>,
+        warnings: &mut Vec<MigrationWarning>,
+    ) -> Option<ProxyTarget<'_>> {
+        let unsupported = |warnings: &mut Vec<MigrationWarning>, why: &str| {
+            warnings.push(MigrationWarning::new(
+                subject,
+                line,
+                format!("proxy_pass {proxy_pass} is not converted: {why}"),
+            ));
+        };
+        if proxy_pass.contains('$') {
+            unsupported(warnings, "it uses variables");
+            return None;
+        }
+        let (https, rest) = if let Some(rest) = proxy_pass.strip_prefix("http://") {
+            (false, rest)
+        } else if let Some(rest) = proxy_pass.strip_prefix("https://") {
+            (true, rest)
+        } else {
+            unsupported(warnings, "unsupported scheme");
+            return None;
+        };
+        if rest.starts_with("unix:") {
+            unsupported(warnings, "UNIX sockets are not supported");
+            return None;
+        }
+        let (authority, uri) = match rest.find('/') {
+            Some(i) => (&rest[..i], Some(rest[i..].to_string())),
+            None => (rest, None),
+        };
+        Some(ProxyTarget {
+            https,
+            upstream: self.upstream(authority),
+            authority: authority.to_string(),
+            uri,
+        })
+    }
+}
+
+impl Site<'_> {
+    /// Warn about the directives of the server and its locations the
+    /// translation ignores
+    fn check_converted(&self, warnings: &mut Vec<MigrationWarning>) {
+        let mut check = |block: &Directive, converted: &[&str], subject: String| {
+            let mut names: Vec<&str> = Vec::new();
+            for child in block.children().unwrap_or_default() {
+                if !converted.contains(&child.name()) && !names.contains(&child.name()) {
+                    names.push(child.name());
+                }
+            }
+            if !names.is_empty() {
+                warnings.push(MigrationWarning::new(
+                    subject,
+                    Some(block.span.line),
+                    format!("Not converted: {}", names.join(", ")),
+                ));
+            }
+        };
+        check(self.block, SERVER_CONVERTED, self.label.clone());
+        for (i, location) in self.server.locations.iter().enumerate() {
+            if let Some(block) = self.location_block(i) {
+                check(block, LOCATION_CONVERTED, self.location_subject(location));
+            }
+        }
+    }
+}
+
+/// Destination of a `proxy_pass`
+struct ProxyTarget<'a> {
+    https: bool,
+    /// Upstream block the host names, if any
+    upstream: Option<&'a Upstream>,
+    /// `host[:port]` as written
+    authority: String,
+    /// URI part, which replaces the matched location prefix in nginx
+    uri: Option<String>,
+}
+
+impl ProxyTarget<'_> {
+    fn default_port(&self) -> u16 {
+        if self.https {
+            443
+        } else {
+            80
+        }
+    }
+}
+
+/// `host:port`, adding `port` when the address has none; `None` for
+/// UNIX sockets
+fn with_port(address: &str, port: u16) -> Option<String> {
+    if address.starts_with("unix:") {
+        return None;
+    }
+    let has_port = match address.rfind(':') {
+        Some(i) => !address.starts_with('[') || address[..i].ends_with(']'),
+        None => false,
+    };
+    Some(if has_port {
+        address.to_string()
+    } else {
+        format!("{address}:{port}")
+    })
+}
+
+/// `= /health`, `~* \.php$`, `/api`
+fn location_label(location: &Location) -> String {
+    let modifier = match location.modifier {
+        LocationModifier::None => "",
+        LocationModifier::Exact => "= ",
+        LocationModifier::PrefixPriority => "^~ ",
+        LocationModifier::Regex => "~ ",
+        LocationModifier::RegexCaseInsensitive => "~* ",
+    };
+    format!("{modifier}{}", location.path)
+}
+
+fn is_named(location: &Location) -> bool {
+    location.path.starts_with('@')
+}
+
+fn is_regex(location: &Location) -> bool {
+    matches!(
+        location.modifier,
+        LocationModifier::Regex | LocationModifier::RegexCaseInsensitive
+    )
+}
+
+/// Indexes of the locations in the order a first-match router must try
+/// them: exact matches, `^~` prefixes longest first, regular expressions
+/// in file order, then the other prefixes longest first
+///
+/// This follows nginx except for a plain prefix longer than a matching
+/// `^~` one. Named locations are left out.
+fn first_match_order(locations: &[Location]) -> Vec<usize> {
+    let rank = |location: &Location| match location.modifier {
+        LocationModifier::Exact => 0,
+        LocationModifier::PrefixPriority => 1,
+        LocationModifier::Regex | LocationModifier::RegexCaseInsensitive => 2,
+        LocationModifier::None => 3,
+    };
+    let mut order: Vec<usize> = (0..locations.len())
+        .filter(|&i| !is_named(&locations[i]))
+        .collect();
+    order.sort_by_key(|&i| {
+        let location = &locations[i];
+        let length = if is_regex(location) {
+            0
+        } else {
+            usize::MAX - location.path.len()
+        };
+        (rank(location), length)
+    });
+    order
+}
+
+/// Warnings the three targets share for a location
+fn check_location(site: &Site<'_>, location: &Location, warnings: &mut Vec<MigrationWarning>) {
+    let line = location_line(site, location);
+    let subject = site.location_subject(location);
+    if is_named(location) {
+        warnings.push(MigrationWarning::new(
+            &subject,
+            line,
+            "Named locations are not converted",
+        ));
+    }
+    if location.limit_except.is_some() {
+        warnings.push(MigrationWarning::new(
+            &subject,
+            line,
+            "limit_except method restrictions are not converted",
+        ));
+    }
+    if location.requires_auth {
+        warnings.push(MigrationWarning::new(
+            &subject,
+            line,
+            "Authentication (auth_basic, auth_request) is not converted",
+        ));
+    }
+}
+
+/// Line of the block of a location, for warnings
+fn location_line(site: &Site<'_>, location: &Location) -> Option<usize> {
+    site.server
+        .locations
+        .iter()
+        .position(|l| std::ptr::eq(l, location))
+        .and_then(|i| site.location_block(i))
+        .map(|block| block.span.line)
+        .or(Some(site.line()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_plan_and_order() {
+        let config = parse(
+            r"
+http {
+    server {
+        server_name .example.com _ ~^www\d+$;
+        add_header X-A 1;
+        location / { root /srv; }
+        location /api { proxy_pass http://app; }
+        location ~ \.php$ { fastcgi_pass php; }
+        location = /health { return 200; }
+        location ^~ /static/ { root /srv; }
+        location @fallback { proxy_pass http://app; }
+    }
+}
+stream { server { listen 53 udp; proxy_pass 10.0.0.53:53; } }
+",
+        )
+        .unwrap();
+        let mut warnings = Vec::new();
+        let plan = Plan::new(&config, &mut warnings);
+
+        assert_eq!(plan.sites.len(), 1);
+        let site = &plan.sites[0];
+        assert_eq!(site.names, vec!["example.com", "*.example.com"]);
+        let order: Vec<_> = first_match_order(&site.server.locations)
+            .into_iter()
+            .map(|i| location_label(&site.server.locations[i]))
+            .collect();
+        assert_eq!(
+            order,
+            vec!["= /health", "^~ /static/", "~ \\.php$", "/api", "/"]
+        );
+
+        let messages: Vec<_> = warnings.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "line 3: server .example.com: Regular expression server name ~^www\\d+$ is not converted",
+                "line 3: server .example.com: Not converted: add_header",
+                "line 8: location ~ \\.php$ in server .example.com: Not converted: fastcgi_pass",
+                "line 9: location = /health in server .example.com: Not converted: return",
+                "line 14: stream: TCP/UDP proxying in stream blocks is not converted",
+            ]
+        );
+
+        assert_eq!(with_port("10.0.0.1", 80).as_deref(), Some("10.0.0.1:80"));
+        assert_eq!(with_port("[::1]", 443).as_deref(), Some("[::1]:443"));
+        assert_eq!(with_port("[::1]:8080", 80).as_deref(), Some("[::1]:8080"));
+        assert_eq!(with_port("unix:/run/app.sock", 80), None);
+        assert_eq!(
+            "Caddyfile".parse::<MigrationTarget>().unwrap(),
+            MigrationTarget::Caddy
+        );
+        assert!("apache".parse::<MigrationTarget>().is_err());
+    }
+}
@@ -0,0 +1,369 @@
+//! nginx Unit JSON configuration output
+
+use super::{
+    check_location, first_match_order, location_line, with_port, MigrationWarning, Plan,
+    ProxyTarget, Site,
+};
+use crate::types::{Location, LocationModifier, TryFilesFallback, Upstream};
+use serde_json::{json, Map, Value};
+use std::net::{IpAddr, SocketAddr};
+
+pub(super) fn render(plan: &Plan<'_>, warnings: &mut Vec<MigrationWarning>) -> String {
+    let mut listeners = Map::new();
+    let mut routes = Map::new();
+    let mut upstreams = Map::new();
+
+    let site_routes: Vec<Vec<Value>> = plan
+        .sites
+        .iter()
+        .map(|site| site_routes(plan, site, &mut upstreams, warnings))
+        .collect();
+
+    for (i, site) in plan.sites.iter().enumerate() {
+        for listen in site.listens() {
+            let address = if listen.address.contains(':') {
+                format!("[{}]:{}", listen.address, listen.port)
+            } else {
+                format!("{}:{}", listen.address, listen.port)
+            };
+            let route = format!("port_{}", listen.port);
+            let listener = listeners
+                .entry(address)
+                .or_insert_with(|| json!({ "pass": format!("routes/{route}") }));
+            if listen.ssl {
+                let bundle = site.names.first().map_or("default", String::as_str);
+                listener["tls"] = json!({ "certificate": bundle });
+                let certificate = site
+                    .certificate()
+                    .unwrap_or_else(|| "the certificate".to_string());
+                warnings.push(MigrationWarning::new(
+                    &site.label,
+                    Some(site.line()),
+                    format!("Upload {certificate} and its key to Unit as the bundle \"{bundle}\""),
+                ));
+            }
+            let steps = routes
+                .entry(route)
+                .or_insert_with(|| Value::Array(Vec::new()));
+            if let Value::Array(steps) = steps {
+                for step in &site_routes[i] {
+                    if !steps.contains(step) {
+                        steps.push(step.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    let mut config = json!({ "listeners": listeners, "routes": routes });
+    if !upstreams.is_empty() {
+        config["upstreams"] = Value::Object(upstreams);
+    }
+    let mut out = serde_json::to_string_pretty(&config).unwrap_or_default();
+    out.push('\n');
+    out
+}
+
+/// Route steps for the locations of a site, in first-match order
+fn site_routes(
+    plan: &Plan<'_>,
+    site: &Site<'_>,
+    upstreams: &mut Map<String, Value>,
+    warnings: &mut Vec<MigrationWarning>,
+) -> Vec<Value> {
+    for location in &site.server.locations {
+        check_location(site, location, warnings);
+    }
+
+    let mut steps = Vec::new();
+    for i in first_match_order(&site.server.locations) {
+        let location = &site.server.locations[i];
+        let Some(action) = action(plan, site, location, upstreams, warnings) else {
+            continue;
+        };
+        steps.push(step(site, uri_pattern(location), action));
+    }
+    if !site.has_catch_all() {
+        if let Some(root) = &site.server.root {
+            steps.push(step(site, None, share(site, &root.display().to_string())));
+        }
+    }
+    steps
+}
+
+/// Route step for the host names of a site and an optional `uri` pattern
+fn step(site: &Site<'_>, uri: Option<String>, action: Value) -> Value {
+    let mut matches = Map::new();
+    if !site.names.is_empty() {
+        matches.insert("host".to_string(), json!(site.names));
+    }
+    if let Some(uri) = uri {
+        matches.insert("uri".to_string(), json!(uri));
+    }
+    let mut step = Map::new();
+    if !matches.is_empty() {
+        step.insert("match".to_string(), Value::Object(matches));
+    }
+    step.insert("action".to_string(), action);
+    Value::Object(step)
+}
+
+/// Action serving the request URI from `root`
+fn share(site: &Site<'_>, root: &str) -> Value {
+    let mut action = Map::new();
+    let root = root.trim_end_matches('/');
+    action.insert("share".to_string(), json!(format!("{root}$uri")));
+    if let Some(index) = site.server.index.first() {
+        action.insert("index".to_string(), json!(index));
+    }
+    Value::Object(action)
+}
+
+/// `uri` match pattern, `None` for `location /`
+fn uri_pattern(location: &Location) -> Option<String> {
+    let path = &location.path;
+    Some(match location.modifier {
+        LocationModifier::None if path == "/" => return None,
+        LocationModifier::None | LocationModifier::PrefixPriority => format!("{path}*"),
+        LocationModifier::Exact => path.clone(),
+        LocationModifier::Regex => format!("~{path}"),
+        LocationModifier::RegexCaseInsensitive => format!("~(?i){path}"),
+    })
+}
+
+fn action(
+    plan: &Plan<'_>,
+    site: &Site<'_>,
+    location: &Location,
+    upstreams: &mut Map<String, Value>,
+    warnings: &mut Vec<MigrationWarning>,
+) -> Option<Value> {
+    let subject = site.location_subject(location);
+    let line = location_line(site, location);
+    if let Some(proxy_pass) = &location.proxy_pass {
+        let target = plan.proxy_target(proxy_pass, &subject, line, warnings)?;
+        return proxy_action(&target, &subject, line, upstreams, warnings);
+    }
+    let mut warn = |message: String| {
+        warnings.push(MigrationWarning::new(&subject, line, message));
+    };
+
+    let root = site.root_of(location)?.display().to_string();
+    let Some(try_files) = &location.try_files else {
+        return Some(share(site, &root));
+    };
+    let root = root.trim_end_matches('/');
+    let mut action = Map::new();
+    let shares: Vec<String> = try_files
+        .files
+        .iter()
+        .map(|file| format!("{root}{file}"))
+        .collect();
+    action.insert("share".to_string(), json!(shares));
+    match &try_files.fallback {
+        TryFilesFallback::Uri(uri) if !uri.contains(['$', '?']) => {
+            action.insert(
+                "fallback".to_string(),
+                json!({ "share": format!("{root}{uri}") }),
+            );
+        }
+        TryFilesFallback::Code(404) => {}
+        fallback => warn(format!("try_files fallback {fallback} is not converted")),
+    }
+    if let Some(index) = site.server.index.first() {
+        action.insert("index".to_string(), json!(index));
+    }
+    Some(Value::Object(action))
+}
+
+fn proxy_action(
+    target: &ProxyTarget<'_>,
+    subject: &str,
+    line: Option<usize>,
+    upstreams: &mut Map<String, Value>,
+    warnings: &mut Vec<MigrationWarning>,
+) -> Option<Value> {
+    let mut warn = |message: &str| {
+        warnings.push(MigrationWarning::new(subject, line, message));
+    };
+    if target.https {
+        warn("Unit proxies over plain HTTP only; the HTTPS backend is not converted");
+        return None;
+    }
+    if let Some(uri) = &target.uri {
+        warn(&format!(
+            "The URI part {uri} of proxy_pass is not converted"
+        ));
+    }
+
+    let Some(upstream) = target.upstream else {
+        let address = with_port(&target.authority, 80)?;
+        if !is_ip(&address) {
+            warn("Unit proxies to IP addresses only; resolve the host name");
+        }
+        return Some(json!({ "proxy": format!("http://{address}") }));
+    };
+    if !upstreams.contains_key(&upstream.name) {
+        let servers = upstream_servers(upstream, warnings);
+        upstreams.insert(upstream.name.clone(), json!({ "servers": servers }));
+    }
+    Some(json!({ "pass": format!("upstreams/{}", upstream.name) }))
+}
+
+fn upstream_servers(upstream: &Upstream, warnings: &mut Vec<MigrationWarning>) -> Value {
+    let mut warn = |message: String| {
+        warnings.push(MigrationWarning::new(
+            format!("upstream {}", upstream.name),
+            Some(upstream.line),
+            message,
+        ));
+    };
+    if let Some(method) = &upstream.method {
+        warn(format!(
+            "Unit balances with weighted round robin only; {method} is not converted"
+        ));
+    }
+
+    let mut servers = Map::new();
+    for server in &upstream.servers {
+        if server.down {
+            continue;
+        }
+        if server.backup {
+            warn(format!("Backup server {} is not converted", server.address));
+            continue;
+        }
+        let Some(address) = with_port(&server.address, 80).filter(|a| is_ip(a)) else {
+            warn(format!(
+                "Unit needs an IP address and port; server {} is not converted",
+                server.address
+            ));
+            continue;
+        };
+        let options = match server.weight {
+            Some(weight) => json!({ "weight": weight }),
+            None => json!({}),
+        };
+        servers.insert(address, options);
+    }
+    Value::Object(servers)
+}
+
+fn is_ip(address: &str) -> bool {
+    address.parse::<SocketAddr>().is_ok() || address.parse::<IpAddr>().is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::migrate::{migrate, MigrationTarget};
+    use crate::parse;
+
+    #[test]
+    fn test_unit() {
+        let config = parse(
+            r"
+http {
+    upstream app {
+        ip_hash;
+        server 10.0.0.1:8080 weight=2;
+        server backend.internal:8080;
+    }
+    server {
+        listen 443 ssl;
+        server_name example.com;
+        root /srv/www/;
+        location / { try_files $uri /index.html; }
+        location /api/ { proxy_pass http://app; }
+        location = /ping { proxy_pass http://127.0.0.1:9000; }
+    }
+}
+",
+        )
+        .unwrap();
+        let migration = migrate(&config, MigrationTarget::Unit);
+        let json: serde_json::Value = serde_json::from_str(&migration.output).unwrap();
+
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "listeners": {
+                    "*:443": {
+                        "pass": "routes/port_443",
+                        "tls": { "certificate": "example.com" }
+                    }
+                },
+                "routes": {
+                    "port_443": [
+                        {
+                            "match": { "host": ["example.com"], "uri": "/ping" },
+                            "action": { "proxy": "http://127.0.0.1:9000" }
+                        },
+                        {
+                            "match": { "host": ["example.com"], "uri": "/api/*" },
+                            "action": { "pass": "upstreams/app" }
+                        },
+                        {
+                            "match": { "host": ["example.com"] },
+                            "action": {
+                                "share": ["/srv/www$uri"],
+                                "fallback": { "share": "/srv/www/index.html" }
+                            }
+                        }
+                    ]
+                },
+                "upstreams": {
+                    "app": { "servers": { "10.0.0.1:8080": { "weight": 2 } } }
+                }
+            })
+        );
+        let messages: Vec<_> = migration
+            .warnings
+            .iter()
+            .map(|w| w.message.as_str())
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                "Unit balances with weighted round robin only; ip_hash is not converted",
+                "Unit needs an IP address and port; server backend.internal:8080 is not converted",
+                "Upload the certificate and its key to Unit as the bundle \"example.com\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_server_root() {
+        let config = parse(
+            r"
+http {
+    server {
+        listen 80;
+        server_name example.com;
+        root /var/www/html;
+        index index.html;
+        location /api/ { proxy_pass http://10.0.0.1:8080; }
+    }
+}
+",
+        )
+        .unwrap();
+        let migration = migrate(&config, MigrationTarget::Unit);
+        let json: serde_json::Value = serde_json::from_str(&migration.output).unwrap();
+
+        // Requests outside the locations are served from the server root
+        assert_eq!(
+            json["routes"]["port_80"],
+            serde_json::json!([
+                {
+                    "match": { "host": ["example.com"], "uri": "/api/*" },
+                    "action": { "proxy": "http://10.0.0.1:8080" }
+                },
+                {
+                    "match": { "host": ["example.com"] },
+                    "action": { "share": "/var/www/html$uri", "index": "index.html" }
+                }
+            ])
+        );
+        assert!(migration.warnings.is_empty());
+    }
+}
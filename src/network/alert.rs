@@ -0,0 +1,667 @@
+//! Alert notifications for network check failures
+//!
+//! An [`Alerter`] compares each run against the failures it has already
+//! reported and hands only the difference to its [`AlertSink`]s: failures
+//! at or above its severity that are new (or got worse), and earlier
+//! failures that have recovered. Set it in [`NetworkCheckOptions::alerts`]
+//! and [`CheckRegistry::run`](super::CheckRegistry::run) notifies after
+//! every run, or call [`Alerter::notify`] with results of your own.
+//!
+//! [`Webhook`] is the built-in sink and posts JSON to a generic endpoint,
+//! a Slack incoming webhook or a Microsoft Teams connector.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use nginx_discovery::network::{
+//!     check_all, Alerter, CheckSeverity, NetworkCheckOptions, Webhook, WebhookFormat,
+//! };
+//! use std::sync::Arc;
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let config = nginx_discovery::parse("server { listen 80; server_name example.com; }")?;
+//!     let alerter = Alerter::new(CheckSeverity::Error)
+//!         .with_sink(Webhook::new("https://hooks.slack.com/services/T000/B000/XXXX")
+//!             .with_format(WebhookFormat::Slack))
+//!         .with_state_file(Alerter::default_state_path());
+//!     let options = NetworkCheckOptions {
+//!         alerts: Some(Arc::new(alerter)),
+//!         ..Default::default()
+//!     };
+//!
+//!     check_all(&config, options).await?;
+//!     Ok(())
+//! }
+//! ```
+
+use super::cache::cache_dir;
+use super::types::{CheckSeverity, HealthStatus};
+use super::NetworkCheckResult;
+use crate::{Error, Result};
+use futures::future::BoxFuture;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+
+#[cfg(doc)]
+use super::types::NetworkCheckOptions;
+
+/// Failures and recoveries of one run that have not been reported yet
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct AlertNotification {
+    /// New failures, and failures whose severity went up
+    pub failures: Vec<NetworkCheckResult>,
+
+    /// Results of targets that failed earlier and no longer do
+    pub recoveries: Vec<NetworkCheckResult>,
+}
+
+impl AlertNotification {
+    /// Whether there is nothing to report
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.failures.is_empty() && self.recoveries.is_empty()
+    }
+
+    /// One-line summary, e.g. `2 new failures, 1 recovered`
+    #[must_use]
+    pub fn title(&self) -> String {
+        match (self.failures.len(), self.recoveries.len()) {
+            (0, recovered) => format!("{recovered} recovered"),
+            (1, 0) => "1 new failure".to_string(),
+            (failed, 0) => format!("{failed} new failures"),
+            (1, recovered) => format!("1 new failure, {recovered} recovered"),
+            (failed, recovered) => format!("{failed} new failures, {recovered} recovered"),
+        }
+    }
+
+    /// One line per failure and recovery
+    #[must_use]
+    pub fn lines(&self) -> Vec<String> {
+        let failures = self.failures.iter().map(|r| {
+            format!(
+                "[{}] {} {}: {}",
+                r.severity, r.check_type, r.target, r.message
+            )
+        });
+        let recoveries = self
+            .recoveries
+            .iter()
+            .map(|r| format!("[RECOVERED] {} {}: {}", r.check_type, r.target, r.message));
+        failures.chain(recoveries).collect()
+    }
+}
+
+/// Destination of alert notifications
+pub trait AlertSink: Send + Sync {
+    /// Name used in delivery errors
+    fn name(&self) -> &str;
+
+    /// Deliver a notification; only called with non-empty notifications
+    fn send<'a>(&'a self, notification: &'a AlertNotification) -> BoxFuture<'a, Result<()>>;
+}
+
+/// Payload shape of a [`Webhook`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WebhookFormat {
+    /// The [`AlertNotification`] as JSON, with a `source` and `title`
+    #[default]
+    Generic,
+
+    /// Slack incoming webhook message (`text`)
+    Slack,
+
+    /// Microsoft Teams connector `MessageCard`
+    Teams,
+}
+
+impl FromStr for WebhookFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "generic" | "json" => Ok(Self::Generic),
+            "slack" => Ok(Self::Slack),
+            "teams" => Ok(Self::Teams),
+            _ => Err(format!(
+                "Unknown webhook format '{s}' (expected generic, slack or teams)"
+            )),
+        }
+    }
+}
+
+/// Sink that POSTs notifications as JSON to a URL
+#[derive(Debug, Clone)]
+pub struct Webhook {
+    /// Endpoint URL
+    pub url: String,
+
+    /// Payload shape
+    pub format: WebhookFormat,
+
+    /// Request timeout
+    pub timeout: Duration,
+}
+
+impl Webhook {
+    /// Generic JSON webhook posting to `url`
+    #[must_use]
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            format: WebhookFormat::Generic,
+            timeout: Duration::from_secs(10),
+        }
+    }
+
+    /// Set the payload shape
+    #[must_use]
+    pub fn with_format(mut self, format: WebhookFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Set the request timeout
+    #[must_use]
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Request body for a notification
+    #[must_use]
+    pub fn payload(&self, notification: &AlertNotification) -> serde_json::Value {
+        let title = format!("nginx-discovery: {}", notification.title());
+        match self.format {
+            WebhookFormat::Generic => serde_json::json!({
+                "source": "nginx-discovery",
+                "title": title,
+                "failures": notification.failures,
+                "recoveries": notification.recoveries,
+            }),
+            WebhookFormat::Slack => serde_json::json!({
+                "text": format!("*{title}*\n{}", notification.lines().join("\n")),
+            }),
+            WebhookFormat::Teams => serde_json::json!({
+                "@type": "MessageCard",
+                "@context": "https://schema.org/extensions",
+                "summary": title,
+                "title": title,
+                "themeColor": if notification.failures.is_empty() { "2EB886" } else { "D00000" },
+                "text": notification.lines().join("<br>"),
+            }),
+        }
+    }
+}
+
+impl AlertSink for Webhook {
+    fn name(&self) -> &str {
+        &self.url
+    }
+
+    fn send<'a>(&'a self, notification: &'a AlertNotification) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let client = reqwest::Client::builder()
+                .timeout(self.timeout)
+                .build()
+                .map_err(|e| Error::Network(format!("Failed to create client: {e}")))?;
+            let response = client
+                .post(&self.url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(self.payload(notification).to_string())
+                .send()
+                .await
+                .map_err(|e| Error::Network(format!("Webhook {}: {e}", self.url)))?;
+            if !response.status().is_success() {
+                return Err(Error::Network(format!(
+                    "Webhook {} answered {}",
+                    self.url,
+                    response.status()
+                )));
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Reports new failures and recoveries to its sinks, once each
+///
+/// The alerter remembers which targets it reported as failing, and with
+/// which severity. A target is reported again only when its severity goes
+/// up, and once more when it recovers. A run that does not include a
+/// target (the check was skipped) leaves its state alone.
+pub struct Alerter {
+    min_severity: CheckSeverity,
+    sinks: Vec<Box<dyn AlertSink>>,
+    state_file: Option<PathBuf>,
+    alerted: Mutex<BTreeMap<String, CheckSeverity>>,
+}
+
+impl Alerter {
+    /// Alerter for problems at or above `min_severity`, without sinks
+    #[must_use]
+    pub fn new(min_severity: CheckSeverity) -> Self {
+        Self {
+            min_severity,
+            sinks: Vec::new(),
+            state_file: None,
+            alerted: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Add a sink
+    #[must_use]
+    pub fn with_sink(mut self, sink: impl AlertSink + 'static) -> Self {
+        self.sinks.push(Box::new(sink));
+        self
+    }
+
+    /// Keep the reported failures in `path` so that separate runs do not
+    /// repeat them, loading what an earlier run saved there
+    ///
+    /// A missing or unreadable file starts with nothing reported.
+    #[must_use]
+    pub fn with_state_file(mut self, path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let alerted = fs::read_to_string(&path)
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default();
+        self.alerted = Mutex::new(alerted);
+        self.state_file = Some(path);
+        self
+    }
+
+    /// Default state file: `$XDG_CACHE_HOME/nginx-discovery/alerts.json`,
+    /// falling back to `~/.cache` and then the temporary directory
+    #[must_use]
+    pub fn default_state_path() -> PathBuf {
+        cache_dir().join("alerts.json")
+    }
+
+    /// Lowest severity that is reported
+    #[must_use]
+    pub fn min_severity(&self) -> CheckSeverity {
+        self.min_severity
+    }
+
+    /// File the reported failures are kept in
+    #[must_use]
+    pub fn state_file(&self) -> Option<&Path> {
+        self.state_file.as_deref()
+    }
+
+    /// Failures and recoveries in `results` that have not been reported,
+    /// marking them as reported
+    ///
+    /// Use this to deliver notifications yourself; [`Alerter::notify`]
+    /// sends them to the sinks.
+    pub fn evaluate(&self, results: &[NetworkCheckResult]) -> AlertNotification {
+        let (notification, alerted) = self.diff(results);
+        *self.lock() = alerted;
+        notification
+    }
+
+    /// Send the unreported failures and recoveries in `results` to every
+    /// sink and return them
+    ///
+    /// They are marked as reported only if every sink accepted them, so a
+    /// failed delivery is retried with the next run. The state file, if
+    /// any, is written afterwards.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first delivery error, after trying every sink, or an
+    /// error if the state file cannot be written.
+    pub async fn notify(&self, results: &[NetworkCheckResult]) -> Result<AlertNotification> {
+        let (notification, alerted) = self.diff(results);
+        if !notification.is_empty() {
+            let mut first_error = None;
+            for sink in &self.sinks {
+                if let Err(e) = sink.send(&notification).await {
+                    first_error.get_or_insert(e);
+                }
+            }
+            if let Some(e) = first_error {
+                return Err(e);
+            }
+        }
+        *self.lock() = alerted;
+        self.save()?;
+        Ok(notification)
+    }
+
+    fn diff(
+        &self,
+        results: &[NetworkCheckResult],
+    ) -> (AlertNotification, BTreeMap<String, CheckSeverity>) {
+        let mut alerted = self.lock().clone();
+        let mut notification = AlertNotification::default();
+        for result in results {
+            let key = format!("{} {}", result.check_type, result.target);
+            let failing = !matches!(
+                result.status,
+                HealthStatus::Healthy | HealthStatus::NotApplicable
+            ) && result.severity >= self.min_severity;
+
+            match (failing, alerted.get(&key).copied()) {
+                (true, Some(severity)) if result.severity <= severity => {}
+                (true, _) => {
+                    alerted.insert(key, result.severity);
+                    notification.failures.push(result.clone());
+                }
+                (false, Some(_)) => {
+                    alerted.remove(&key);
+                    notification.recoveries.push(result.clone());
+                }
+                (false, None) => {}
+            }
+        }
+        (notification, alerted)
+    }
+
+    fn save(&self) -> Result<()> {
+        let Some(path) = &self.state_file else {
+            return Ok(());
+        };
+        let json = serde_json::to_string(&*self.lock())
+            .map_err(|e| Error::Serialization(e.to_string()))?;
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, json)?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<String, CheckSeverity>> {
+        self.alerted.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl fmt::Debug for Alerter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Alerter")
+            .field("min_severity", &self.min_severity)
+            .field(
+                "sinks",
+                &self.sinks.iter().map(|s| s.name()).collect::<Vec<_>>(),
+            )
+            .field("state_file", &self.state_file)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    fn result(target: &str, status: HealthStatus, severity: CheckSeverity) -> NetworkCheckResult {
+        NetworkCheckResult {
+            check_type: "port".to_string(),
+            target: target.to_string(),
+            status,
+            message: format!("{status}"),
+            severity,
+            details: None,
+            latency: None,
+            cached: None,
+        }
+    }
+
+    #[test]
+    fn test_alerter_dedup_and_recovery() {
+        let alerter = Alerter::new(CheckSeverity::Error);
+        let down = result("a:80", HealthStatus::Unhealthy, CheckSeverity::Error);
+        let slow = result("b:80", HealthStatus::Degraded, CheckSeverity::Warning);
+
+        let first = alerter.evaluate(&[down.clone(), slow.clone()]);
+        assert_eq!(first.failures.len(), 1);
+        assert_eq!(first.title(), "1 new failure");
+
+        assert!(alerter.evaluate(&[down, slow]).is_empty());
+
+        let worse = result("a:80", HealthStatus::Unhealthy, CheckSeverity::Critical);
+        assert_eq!(alerter.evaluate(&[worse]).failures.len(), 1);
+
+        // Missing from the run: nothing changes
+        assert!(alerter.evaluate(&[]).is_empty());
+
+        let up = result("a:80", HealthStatus::Healthy, CheckSeverity::Info);
+        let recovered = alerter.evaluate(std::slice::from_ref(&up));
+        assert_eq!(recovered.recoveries.len(), 1);
+        assert_eq!(recovered.lines(), ["[RECOVERED] port a:80: HEALTHY"]);
+        assert!(alerter.evaluate(&[up]).is_empty());
+
+        assert_eq!("Teams".parse::<WebhookFormat>(), Ok(WebhookFormat::Teams));
+        assert!("pager".parse::<WebhookFormat>().is_err());
+        let slack = Webhook::new("http://localhost").with_format(WebhookFormat::Slack);
+        assert_eq!(
+            slack.payload(&recovered)["text"],
+            "*nginx-discovery: 1 recovered*\n[RECOVERED] port a:80: HEALTHY"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_webhook_notify() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            loop {
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+                let text = String::from_utf8_lossy(&request);
+                if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                    let length = head
+                        .lines()
+                        .find_map(|l| {
+                            l.to_ascii_lowercase()
+                                .strip_prefix("content-length: ")
+                                .map(str::to_string)
+                        })
+                        .and_then(|l| l.trim().parse::<usize>().ok())
+                        .unwrap_or(0);
+                    if body.len() >= length {
+                        break;
+                    }
+                }
+            }
+            stream
+                .write_all(b"HTTP/1.1 204 No Content\r\ncontent-length: 0\r\n\r\n")
+                .await
+                .unwrap();
+            String::from_utf8(request).unwrap()
+        });
+
+        let path = std::env::temp_dir().join(format!(
+            "nginx-discovery-alerts-{}.json",
+            std::process::id()
+        ));
+        let alerter = Alerter::new(CheckSeverity::Warning)
+            .with_sink(Webhook::new(url))
+            .with_state_file(&path);
+        let down = result("a:80", HealthStatus::Unhealthy, CheckSeverity::Error);
+
+        let sent = alerter.notify(std::slice::from_ref(&down)).await.unwrap();
+        assert_eq!(sent.failures.len(), 1);
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST /hook HTTP/1.1"));
+        assert!(request.contains("\"title\":\"nginx-discovery: 1 new failure\""));
+
+        // The state file keeps a second alerter from repeating the failure
+        let again = Alerter::new(CheckSeverity::Warning)
+            .with_sink(Webhook::new("http://127.0.0.1:1/unreachable"))
+            .with_state_file(&path);
+        assert!(again.notify(&[down]).await.unwrap().is_empty());
+
+        // Failed deliveries are not marked as reported
+        let up = result("a:80", HealthStatus::Healthy, CheckSeverity::Info);
+        assert!(again.notify(std::slice::from_ref(&up)).await.is_err());
+        assert_eq!(again.evaluate(&[up]).recoveries.len(), 1);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    /// Sink recording the titles it receives, failing if `fail` is set
+    struct Recorder {
+        name: &'static str,
+        fail: bool,
+        sent: std::sync::Arc<Mutex<Vec<String>>>,
+    }
+
+    impl AlertSink for Recorder {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn send<'a>(&'a self, notification: &'a AlertNotification) -> BoxFuture<'a, Result<()>> {
+            Box::pin(async move {
+                self.sent
+                    .lock()
+                    .unwrap()
+                    .push(format!("{}: {}", self.name, notification.title()));
+                if self.fail {
+                    return Err(Error::Network(format!("{} is down", self.name)));
+                }
+                Ok(())
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_notify_sink_errors() {
+        let sent = std::sync::Arc::new(Mutex::new(Vec::new()));
+        let sink = |name, fail| Recorder {
+            name,
+            fail,
+            sent: std::sync::Arc::clone(&sent),
+        };
+        let alerter = Alerter::new(CheckSeverity::Error)
+            .with_sink(sink("first", true))
+            .with_sink(sink("second", true))
+            .with_sink(sink("third", false));
+        assert_eq!(
+            format!("{alerter:?}"),
+            "Alerter { min_severity: Error, sinks: [\"first\", \"second\", \"third\"], \
+             state_file: None, .. }"
+        );
+
+        // Nothing to report: no sink is called
+        let skipped = result("a:80", HealthStatus::NotApplicable, CheckSeverity::Critical);
+        assert!(alerter.notify(&[skipped]).await.unwrap().is_empty());
+        assert!(sent.lock().unwrap().is_empty());
+
+        // Every sink is tried and the first error returned
+        let down = result("a:80", HealthStatus::Unhealthy, CheckSeverity::Error);
+        let err = alerter
+            .notify(std::slice::from_ref(&down))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("first is down"), "{err}");
+        assert_eq!(
+            *sent.lock().unwrap(),
+            [
+                "first: 1 new failure",
+                "second: 1 new failure",
+                "third: 1 new failure"
+            ]
+        );
+
+        // Not marked as reported, so the next run sends it again
+        assert_eq!(alerter.evaluate(&[down]).failures.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_webhook_errors_and_state_files() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 4096];
+            let _ = stream.read(&mut buf).await.unwrap();
+            stream
+                .write_all(b"HTTP/1.1 500 Internal Server Error\r\ncontent-length: 0\r\n\r\n")
+                .await
+                .unwrap();
+        });
+
+        let down = result("a:80", HealthStatus::Unhealthy, CheckSeverity::Error);
+        let err = Alerter::new(CheckSeverity::Error)
+            .with_sink(Webhook::new(&url).with_timeout(Duration::from_secs(5)))
+            .notify(std::slice::from_ref(&down))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("answered 500"), "{err}");
+        server.await.unwrap();
+
+        // A damaged state file starts with nothing reported; a state file
+        // that cannot be written fails the notification
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("alerts.json");
+        fs::write(&path, "[1, 2").unwrap();
+        let alerter = Alerter::new(CheckSeverity::Error).with_state_file(&path);
+        assert_eq!(alerter.state_file(), Some(path.as_path()));
+        assert_eq!(
+            alerter
+                .notify(std::slice::from_ref(&down))
+                .await
+                .unwrap()
+                .failures
+                .len(),
+            1
+        );
+        assert!(fs::read_to_string(&path).unwrap().contains("port a:80"));
+
+        let blocked = Alerter::new(CheckSeverity::Error).with_state_file(path.join("alerts.json"));
+        assert!(blocked.notify(&[down]).await.is_err());
+    }
+
+    #[test]
+    fn test_notification_payloads() {
+        let down = result("a:80", HealthStatus::Unhealthy, CheckSeverity::Error);
+        let up = result("b:80", HealthStatus::Healthy, CheckSeverity::Info);
+        let notification = |failures: usize, recoveries: usize| AlertNotification {
+            failures: vec![down.clone(); failures],
+            recoveries: vec![up.clone(); recoveries],
+        };
+        let titles: Vec<_> = [(1, 0), (2, 0), (1, 2), (3, 1), (0, 2)]
+            .into_iter()
+            .map(|(f, r)| notification(f, r).title())
+            .collect();
+        assert_eq!(
+            titles,
+            [
+                "1 new failure",
+                "2 new failures",
+                "1 new failure, 2 recovered",
+                "3 new failures, 1 recovered",
+                "2 recovered"
+            ]
+        );
+
+        let teams = Webhook::new("http://localhost").with_format(WebhookFormat::Teams);
+        let payload = teams.payload(&notification(1, 1));
+        assert_eq!(payload["themeColor"], "D00000");
+        assert_eq!(
+            payload["text"],
+            "[ERROR] port a:80: UNHEALTHY<br>[RECOVERED] port b:80: HEALTHY"
+        );
+        assert_eq!(teams.payload(&notification(0, 1))["themeColor"], "2EB886");
+
+        let generic = Webhook::new("http://localhost").payload(&notification(1, 0));
+        assert_eq!(generic["title"], "nginx-discovery: 1 new failure");
+        assert_eq!(generic["failures"][0]["target"], "a:80");
+        assert_eq!("JSON".parse::<WebhookFormat>(), Ok(WebhookFormat::Generic));
+    }
+}
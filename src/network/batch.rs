@@ -0,0 +1,619 @@
+//! Network checks across a host inventory
+//!
+//! [`check_inventory`] runs the checks of a [`CheckRegistry`] against every
+//! target of an [`Inventory`] — configuration files collected from a fleet,
+//! or bare host names — with at most [`BatchOptions::concurrency`] targets
+//! in flight, and aggregates the results into one [`BatchReport`]: a
+//! [`HostReport`] with timing per target, and the problems grouped by
+//! check and message so a failure shared by many hosts shows up once.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use nginx_discovery::network::{check_inventory, BatchOptions, CheckRegistry, Inventory};
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let inventory: Inventory = "
+//!         web-01 /srv/fleet/web-01/nginx.conf
+//!         web-02 /srv/fleet/web-02/nginx.conf
+//!         cdn.example.com
+//!     "
+//!     .parse()?;
+//!
+//!     let registry = CheckRegistry::with_builtin_checks();
+//!     let report = check_inventory(&inventory, &registry, &BatchOptions::default()).await;
+//!     for group in &report.failures {
+//!         println!("{} hosts: {}", group.hosts.len(), group.message);
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+use super::registry::CheckRegistry;
+use super::severity::exit_code;
+use super::summary::NetworkCheckSummary;
+use super::types::{CheckSeverity, HealthStatus, NetworkCheckOptions};
+use super::NetworkCheckResult;
+use crate::ast::Config;
+use crate::parser::{read_source, EncodingWarning, SourceEncoding};
+use futures::stream::{self, StreamExt};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+/// Where the configuration of an inventory target comes from
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum InventorySource {
+    /// A configuration file, e.g. collected with `nginx -T`
+    File(PathBuf),
+
+    /// Configuration text
+    Text(String),
+
+    /// A host without a configuration: its name is resolved and ports 80
+    /// and 443 are probed
+    Host(String),
+}
+
+impl InventorySource {
+    /// Configuration to check for this source, with the bytes of a file
+    /// that did not decode as UTF-8
+    fn config(&self) -> crate::Result<(Config, Vec<EncodingWarning>)> {
+        match self {
+            Self::File(path) => {
+                let source = read_source(path, SourceEncoding::Utf8)?;
+                Ok((crate::parse(&source.text)?, source.warnings))
+            }
+            Self::Text(text) => Ok((crate::parse(text)?, Vec::new())),
+            Self::Host(host) => Ok((
+                crate::parse(&format!(
+                    "http {{ server {{ listen {host}:80; listen {host}:443 ssl; server_name {host}; }} }}"
+                ))?,
+                Vec::new(),
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for InventorySource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::File(path) => write!(f, "{}", path.display()),
+            Self::Text(_) => f.write_str("<text>"),
+            Self::Host(host) => write!(f, "host {host}"),
+        }
+    }
+}
+
+/// One target of an [`Inventory`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InventoryTarget {
+    /// Name the results are reported under
+    pub name: String,
+
+    /// Configuration to check
+    pub source: InventorySource,
+}
+
+/// Targets to check, in report order
+///
+/// The text form has one target per line, `NAME SOURCE` or just `SOURCE`
+/// (named after itself). A source containing `/` or ending in `.conf` is a
+/// configuration file, anything else a host name. Blank lines and `#`
+/// comments are ignored.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Inventory {
+    /// Targets in configuration order
+    pub targets: Vec<InventoryTarget>,
+}
+
+impl Inventory {
+    /// Create an empty inventory
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a target
+    #[must_use]
+    pub fn with_target(mut self, name: impl Into<String>, source: InventorySource) -> Self {
+        self.targets.push(InventoryTarget {
+            name: name.into(),
+            source,
+        });
+        self
+    }
+
+    /// Number of targets
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.targets.len()
+    }
+
+    /// Whether the inventory has no targets
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.targets.is_empty()
+    }
+}
+
+impl FromStr for Inventory {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut inventory = Self::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let (name, source) = match words.as_slice() {
+                [] => continue,
+                [source] => (*source, *source),
+                [name, source] => (*name, *source),
+                _ => {
+                    return Err(format!(
+                        "line {}: expected \"NAME SOURCE\" or \"SOURCE\", got \"{line}\"",
+                        i + 1
+                    ))
+                }
+            };
+            let is_file = source.contains('/')
+                || std::path::Path::new(source)
+                    .extension()
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("conf"));
+            let source = if is_file {
+                InventorySource::File(PathBuf::from(source))
+            } else {
+                InventorySource::Host(source.to_string())
+            };
+            inventory = inventory.with_target(name, source);
+        }
+        Ok(inventory)
+    }
+}
+
+/// Options of [`check_inventory`]
+#[derive(Debug, Clone)]
+pub struct BatchOptions {
+    /// Maximum number of targets checked at the same time
+    pub concurrency: usize,
+
+    /// Options passed to the checks of every target
+    pub checks: NetworkCheckOptions,
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: 4,
+            checks: NetworkCheckOptions {
+                continue_on_error: true,
+                ..NetworkCheckOptions::default()
+            },
+        }
+    }
+}
+
+/// Results of one inventory target
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HostReport {
+    /// Target name
+    pub name: String,
+
+    /// Where its configuration came from
+    pub source: String,
+
+    /// Check results, in registry order
+    pub results: Vec<NetworkCheckResult>,
+
+    /// Aggregate of the results
+    pub summary: NetworkCheckSummary,
+
+    /// Why the target could not be checked (unreadable or invalid
+    /// configuration, failing check)
+    pub error: Option<String>,
+
+    /// Wall-clock time spent on the target
+    pub duration: Duration,
+}
+
+impl HostReport {
+    /// Whether the target has a problem: an error, or a result that is
+    /// neither healthy nor skipped
+    #[must_use]
+    pub fn has_problems(&self) -> bool {
+        self.error.is_some() || self.summary.by_status.problems() > 0
+    }
+}
+
+/// Problems shared by several targets: same check, status and message once
+/// the target is taken out
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FailureGroup {
+    /// Check type (`port`, `dns`, ...), `config` for targets whose
+    /// configuration could not be loaded
+    pub check_type: String,
+
+    /// Status of the results
+    pub status: HealthStatus,
+
+    /// Highest severity among the results
+    pub severity: CheckSeverity,
+
+    /// Message with the check target replaced by `<target>`
+    pub message: String,
+
+    /// Inventory targets with this problem, in inventory order
+    pub hosts: Vec<String>,
+
+    /// Number of results in the group
+    pub count: usize,
+}
+
+/// Result of [`check_inventory`]
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BatchReport {
+    /// One report per target, in inventory order
+    pub hosts: Vec<HostReport>,
+
+    /// Problems grouped across targets, most widespread first
+    pub failures: Vec<FailureGroup>,
+
+    /// Aggregate of the results of all targets
+    pub summary: NetworkCheckSummary,
+
+    /// Wall-clock time of the whole run
+    pub duration: Duration,
+}
+
+impl BatchReport {
+    /// Targets with at least one problem
+    pub fn failed_hosts(&self) -> impl Iterator<Item = &HostReport> {
+        self.hosts.iter().filter(|h| h.has_problems())
+    }
+
+    /// Process exit code for the run, like
+    /// [`exit_code`] over all results; a target that
+    /// could not be checked counts as an error
+    #[must_use]
+    pub fn exit_code(&self, fail_on: CheckSeverity) -> i32 {
+        self.hosts
+            .iter()
+            .map(|host| {
+                let code = exit_code(&host.results, fail_on);
+                if host.error.is_some() && fail_on <= CheckSeverity::Error {
+                    code.max(2)
+                } else {
+                    code
+                }
+            })
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// Run the checks of `registry` against every target of `inventory`.
+///
+/// At most `options.concurrency` targets are checked at the same time;
+/// checks of one target run in registry order. A target whose
+/// configuration cannot be loaded, or whose checks fail with
+/// [`NetworkCheckOptions::continue_on_error`] unset, gets an `error`
+/// instead of results.
+pub async fn check_inventory(
+    inventory: &Inventory,
+    registry: &CheckRegistry,
+    options: &BatchOptions,
+) -> BatchReport {
+    let start = Instant::now();
+
+    let hosts: Vec<HostReport> = stream::iter(&inventory.targets)
+        .map(|target| check_target(target, registry, &options.checks))
+        .buffered(options.concurrency.max(1))
+        .collect()
+        .await;
+
+    let all: Vec<NetworkCheckResult> = hosts
+        .iter()
+        .flat_map(|h| h.results.iter().cloned())
+        .collect();
+
+    BatchReport {
+        failures: group_failures(&hosts),
+        summary: NetworkCheckSummary::from(all.as_slice()),
+        hosts,
+        duration: start.elapsed(),
+    }
+}
+
+async fn check_target(
+    target: &InventoryTarget,
+    registry: &CheckRegistry,
+    options: &NetworkCheckOptions,
+) -> HostReport {
+    let start = Instant::now();
+    let outcome = match target.source.config() {
+        Ok((config, warnings)) => registry.run(&config, options).await.map(|results| {
+            let source = target.source.to_string();
+            warnings
+                .iter()
+                .map(|warning| encoding_result(&source, warning))
+                .chain(results)
+                .collect()
+        }),
+        Err(e) => Err(e),
+    };
+    let (results, error) = match outcome {
+        Ok(results) => (results, None),
+        Err(e) => (Vec::new(), Some(e.to_string())),
+    };
+
+    HostReport {
+        name: target.name.clone(),
+        source: target.source.to_string(),
+        summary: NetworkCheckSummary::from(results.as_slice()),
+        results,
+        error,
+        duration: start.elapsed(),
+    }
+}
+
+/// An encoding warning as a degraded `encoding` result
+fn encoding_result(source: &str, warning: &EncodingWarning) -> NetworkCheckResult {
+    NetworkCheckResult {
+        check_type: "encoding".to_string(),
+        target: source.to_string(),
+        status: HealthStatus::Degraded,
+        message: format!("{source}: {warning}"),
+        severity: CheckSeverity::Warning,
+        details: None,
+        latency: None,
+        cached: None,
+    }
+}
+
+fn group_failures(hosts: &[HostReport]) -> Vec<FailureGroup> {
+    let mut groups: Vec<FailureGroup> = Vec::new();
+    let mut add = |host: &str,
+                   check_type: &str,
+                   status: HealthStatus,
+                   severity: CheckSeverity,
+                   message: String| {
+        match groups
+            .iter_mut()
+            .find(|g| g.check_type == check_type && g.status == status && g.message == message)
+        {
+            Some(group) => {
+                group.count += 1;
+                group.severity = group.severity.max(severity);
+                if !group.hosts.iter().any(|h| h == host) {
+                    group.hosts.push(host.to_string());
+                }
+            }
+            None => groups.push(FailureGroup {
+                check_type: check_type.to_string(),
+                status,
+                severity,
+                message,
+                hosts: vec![host.to_string()],
+                count: 1,
+            }),
+        }
+    };
+
+    for host in hosts {
+        if let Some(error) = &host.error {
+            add(
+                &host.name,
+                "config",
+                HealthStatus::Error,
+                CheckSeverity::Error,
+                error.clone(),
+            );
+        }
+        for result in &host.results {
+            if matches!(
+                result.status,
+                HealthStatus::Healthy | HealthStatus::NotApplicable
+            ) {
+                continue;
+            }
+            let message = if result.target.is_empty() {
+                result.message.clone()
+            } else {
+                result.message.replace(&result.target, "<target>")
+            };
+            add(
+                &host.name,
+                &result.check_type,
+                result.status,
+                result.severity,
+                message,
+            );
+        }
+    }
+
+    // Most widespread first; ties keep first-seen order
+    groups.sort_by_key(|g| std::cmp::Reverse(g.hosts.len()));
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::Check;
+    use futures::future::BoxFuture;
+
+    /// Reports the first `listen` port as closed
+    struct ListenCheck;
+
+    impl Check for ListenCheck {
+        fn name(&self) -> &'static str {
+            "port"
+        }
+
+        fn description(&self) -> &'static str {
+            "First listen port"
+        }
+
+        fn run<'a>(
+            &'a self,
+            config: &'a Config,
+            _options: &'a NetworkCheckOptions,
+        ) -> BoxFuture<'a, crate::Result<Vec<NetworkCheckResult>>> {
+            Box::pin(async move {
+                let port = config
+                    .find_directives_recursive("listen")
+                    .first()
+                    .and_then(|d| d.first_arg())
+                    .unwrap_or_default();
+                let open = port == "8080";
+                Ok(vec![NetworkCheckResult {
+                    check_type: "port".to_string(),
+                    target: port.clone(),
+                    status: if open {
+                        HealthStatus::Healthy
+                    } else {
+                        HealthStatus::Unhealthy
+                    },
+                    message: format!("Port {port} is closed"),
+                    severity: if open {
+                        CheckSeverity::Info
+                    } else {
+                        CheckSeverity::Error
+                    },
+                    details: None,
+                    latency: None,
+                    cached: None,
+                }])
+            })
+        }
+    }
+
+    #[test]
+    fn test_parse_inventory() {
+        let inventory: Inventory = "
+            # fleet
+            web-01 /srv/fleet/web-01/nginx.conf
+            edge.conf
+            cdn.example.com   # bare host
+        "
+        .parse()
+        .unwrap();
+
+        let targets: Vec<_> = inventory
+            .targets
+            .iter()
+            .map(|t| (t.name.as_str(), t.source.clone()))
+            .collect();
+        assert_eq!(
+            targets,
+            vec![
+                (
+                    "web-01",
+                    InventorySource::File("/srv/fleet/web-01/nginx.conf".into())
+                ),
+                ("edge.conf", InventorySource::File("edge.conf".into())),
+                (
+                    "cdn.example.com",
+                    InventorySource::Host("cdn.example.com".to_string())
+                ),
+            ]
+        );
+
+        let error = "a b c".parse::<Inventory>().unwrap_err();
+        assert!(error.starts_with("line 1:"));
+    }
+
+    #[tokio::test]
+    async fn test_check_inventory() {
+        let inventory = Inventory::new()
+            .with_target(
+                "web-01",
+                InventorySource::Text("server { listen 80; }".into()),
+            )
+            .with_target(
+                "web-02",
+                InventorySource::Text("server { listen 8080; }".into()),
+            )
+            .with_target(
+                "web-03",
+                InventorySource::Text("server { listen 81; }".into()),
+            )
+            .with_target(
+                "web-04",
+                InventorySource::Text("server { listen 80;".into()),
+            )
+            .with_target(
+                "web-05",
+                InventorySource::Text("server { listen 80; }".into()),
+            );
+        let registry = CheckRegistry::new().with_check(ListenCheck);
+        let options = BatchOptions {
+            concurrency: 2,
+            ..BatchOptions::default()
+        };
+        let report = check_inventory(&inventory, &registry, &options).await;
+
+        let names: Vec<_> = report.hosts.iter().map(|h| h.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["web-01", "web-02", "web-03", "web-04", "web-05"]
+        );
+        assert!(report.hosts[3].error.is_some());
+        assert_eq!(report.summary.total, 4);
+
+        let failed: Vec<_> = report.failed_hosts().map(|h| h.name.as_str()).collect();
+        assert_eq!(failed, vec!["web-01", "web-03", "web-04", "web-05"]);
+
+        // The same closed port on two hosts is one group
+        let groups: Vec<_> = report
+            .failures
+            .iter()
+            .map(|g| (g.check_type.as_str(), g.message.as_str(), g.hosts.len()))
+            .collect();
+        assert_eq!(groups[0], ("port", "Port <target> is closed", 3));
+        assert_eq!(groups[1].0, "config");
+        assert_eq!(report.failures[0].count, 3);
+
+        assert_eq!(report.exit_code(CheckSeverity::Error), 2);
+        assert_eq!(report.exit_code(CheckSeverity::Critical), 0);
+    }
+
+    #[tokio::test]
+    async fn test_encoding_warnings_are_results() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nginx.conf");
+        std::fs::write(&path, b"# caf\xE9\nserver { listen 8080; }\n").unwrap();
+        let inventory = Inventory::new()
+            .with_target("web-01", InventorySource::File(path.clone()))
+            .with_target(
+                "web-02",
+                InventorySource::File(dir.path().join("missing.conf")),
+            );
+        let registry = CheckRegistry::new().with_check(ListenCheck);
+        let report = check_inventory(&inventory, &registry, &BatchOptions::default()).await;
+
+        let host = &report.hosts[0];
+        assert!(host.error.is_none());
+        assert_eq!(host.results.len(), 2);
+        assert_eq!(host.results[0].check_type, "encoding");
+        assert_eq!(host.results[0].status, HealthStatus::Degraded);
+        assert_eq!(host.results[0].severity, CheckSeverity::Warning);
+        assert_eq!(host.results[0].target, path.display().to_string());
+        assert!(host.results[0].message.contains("line 1"));
+        assert_eq!(host.results[1].status, HealthStatus::Healthy);
+        assert!(host.has_problems());
+
+        // An unreadable file is an error, not an encoding problem
+        assert!(report.hosts[1].error.is_some());
+        assert!(report.hosts[1].results.is_empty());
+        assert_eq!(report.exit_code(CheckSeverity::Warning), 2);
+    }
+}
@@ -0,0 +1,170 @@
+//! Latency budgets for network check results
+//!
+//! A resolver answering in two seconds or a backend taking half a second to
+//! accept a connection is a problem during an incident even though the
+//! check succeeded. [`apply_latency_budgets`] marks healthy results slower
+//! than the matching [`LatencyBudgets`] entry from
+//! [`NetworkCheckOptions`] as [`HealthStatus::Degraded`]. A
+//! [`CheckRegistry`](super::CheckRegistry) applies it to every run, before
+//! severity overrides.
+
+use super::types::{CheckSeverity, HealthStatus, NetworkCheckOptions};
+use super::NetworkCheckResult;
+
+#[cfg(doc)]
+use super::types::LatencyBudgets;
+
+/// Degrade healthy results slower than their latency budget, in place.
+///
+/// UDP port results (target ending in `/udp`) measure a datagram round
+/// trip rather than a connect and are left alone, as are results without a
+/// measured latency.
+pub fn apply_latency_budgets(results: &mut [NetworkCheckResult], options: &NetworkCheckOptions) {
+    for result in results {
+        if result.status != HealthStatus::Healthy || result.target.ends_with("/udp") {
+            continue;
+        }
+        let (Some(latency), Some(budget)) = (
+            result.latency,
+            options.latency_budgets.for_check(&result.check_type),
+        ) else {
+            continue;
+        };
+        if latency <= budget {
+            continue;
+        }
+
+        result.status = HealthStatus::Degraded;
+        result.severity = result.severity.max(CheckSeverity::Warning);
+        let note = format!("Took {latency:?}, over the {budget:?} budget");
+        result.details = Some(match result.details.take() {
+            Some(details) => format!("{details}; {note}"),
+            None => note,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::LatencyBudgets;
+    use std::time::Duration;
+
+    fn result(check_type: &str, target: &str, ms: u64) -> NetworkCheckResult {
+        NetworkCheckResult {
+            check_type: check_type.to_string(),
+            target: target.to_string(),
+            status: HealthStatus::Healthy,
+            message: "OK".to_string(),
+            severity: CheckSeverity::Info,
+            details: None,
+            latency: Some(Duration::from_millis(ms)),
+            cached: None,
+        }
+    }
+
+    #[test]
+    fn test_apply_latency_budgets() {
+        let mut results = vec![
+            result("dns", "example.com", 250),
+            result("dns", "fast.example.com", 20),
+            result("port", "10.0.0.1:80", 80),
+            result("port", "10.0.0.1:443/udp", 80),
+            result("ssl", "example.com:443", 120),
+            result("host_routing", "example.com", 900),
+        ];
+        let mut options = NetworkCheckOptions::default();
+        apply_latency_budgets(&mut results, &options);
+
+        let statuses: Vec<_> = results.iter().map(|r| r.status).collect();
+        assert_eq!(
+            statuses,
+            [
+                HealthStatus::Degraded,
+                HealthStatus::Healthy,
+                HealthStatus::Degraded,
+                HealthStatus::Healthy,
+                HealthStatus::Healthy,
+                HealthStatus::Healthy,
+            ]
+        );
+        assert_eq!(results[0].severity, CheckSeverity::Warning);
+        assert_eq!(
+            results[0].details.as_deref(),
+            Some("Took 250ms, over the 100ms budget")
+        );
+
+        let mut results = vec![result("dns", "example.com", 250)];
+        options.latency_budgets = LatencyBudgets::none();
+        apply_latency_budgets(&mut results, &options);
+        assert_eq!(results[0].status, HealthStatus::Healthy);
+
+        options
+            .latency_budgets
+            .set("tls", Some(Duration::from_millis(50)))
+            .unwrap();
+        assert_eq!(
+            options.latency_budgets.for_check("ssl"),
+            Some(Duration::from_millis(50))
+        );
+        assert!(options.latency_budgets.set("icmp", None).is_err());
+    }
+
+    #[test]
+    fn test_latency_budget_edge_cases() {
+        let mut options = NetworkCheckOptions {
+            latency_budgets: LatencyBudgets::none(),
+            ..Default::default()
+        };
+        options
+            .latency_budgets
+            .set("dns", Some(Duration::from_millis(100)))
+            .unwrap();
+
+        let mut unmeasured = result("dns", "b.example.com", 0);
+        unmeasured.latency = None;
+        let mut unhealthy = result("dns", "c.example.com", 500);
+        unhealthy.status = HealthStatus::Unhealthy;
+        let mut critical = result("dns", "d.example.com", 101);
+        critical.severity = CheckSeverity::Critical;
+        critical.details = Some("2 addresses".to_string());
+        let mut results = vec![
+            result("dns", "a.example.com", 100),
+            unmeasured,
+            unhealthy,
+            critical,
+        ];
+
+        let outcome = |results: &[NetworkCheckResult]| -> Vec<_> {
+            results
+                .iter()
+                .map(|r| (r.status, r.severity, r.details.clone()))
+                .collect()
+        };
+        apply_latency_budgets(&mut results, &options);
+
+        // Only healthy results over the budget change; the severity is never
+        // lowered and earlier details are kept
+        let expected = vec![
+            (HealthStatus::Healthy, CheckSeverity::Info, None),
+            (HealthStatus::Healthy, CheckSeverity::Info, None),
+            (HealthStatus::Unhealthy, CheckSeverity::Info, None),
+            (
+                HealthStatus::Degraded,
+                CheckSeverity::Critical,
+                Some("2 addresses; Took 101ms, over the 100ms budget".to_string()),
+            ),
+        ];
+        assert_eq!(outcome(&results), expected);
+
+        // Applying the budgets again leaves degraded results alone
+        apply_latency_budgets(&mut results, &options);
+        assert_eq!(outcome(&results), expected);
+
+        let err = options.latency_budgets.set("DNS", None).unwrap_err();
+        assert_eq!(
+            err,
+            "Unknown latency budget 'DNS' (expected dns, tcp or tls)"
+        );
+    }
+}
@@ -0,0 +1,342 @@
+//! On-disk cache of network check results
+//!
+//! Repeated runs during an incident re-resolve the same names and re-probe
+//! the same ports. A [`ResultCache`] set in
+//! [`NetworkCheckOptions::cache`] answers DNS, SSL and port checks from
+//! earlier runs while their results are younger than the matching
+//! [`CacheTtls`](super::CacheTtls) entry; served results carry their age in
+//! [`NetworkCheckResult::cached`].
+//!
+//! Entries are keyed by check type and target. Results with
+//! [`HealthStatus::Error`] (the check itself could not run) are never
+//! stored.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use nginx_discovery::network::{check_all, NetworkCheckOptions, ResultCache};
+//! use std::sync::Arc;
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let config = nginx_discovery::parse("server { listen 80; server_name example.com; }")?;
+//!     let cache = Arc::new(ResultCache::load(ResultCache::default_path()));
+//!     let options = NetworkCheckOptions {
+//!         cache: Some(Arc::clone(&cache)),
+//!         ..Default::default()
+//!     };
+//!
+//!     let results = check_all(&config, options).await?;
+//!     let cached = results.iter().filter(|r| r.cached.is_some()).count();
+//!     println!("{cached} of {} results from cache", results.len());
+//!     cache.save()?;
+//!     Ok(())
+//! }
+//! ```
+
+use super::types::{HealthStatus, NetworkCheckOptions};
+use super::NetworkCheckResult;
+use crate::{Error, Result};
+use std::collections::BTreeMap;
+use std::fs;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// A stored result
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct CacheEntry {
+    /// Seconds since the Unix epoch when the result was checked
+    stored_at: u64,
+    result: NetworkCheckResult,
+}
+
+/// Results of earlier checks, persisted as JSON
+#[derive(Debug)]
+pub struct ResultCache {
+    path: PathBuf,
+    entries: Mutex<BTreeMap<String, CacheEntry>>,
+}
+
+impl ResultCache {
+    /// Create an empty cache that saves to `path`
+    #[must_use]
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            entries: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Load the cache saved at `path`
+    ///
+    /// A missing or unreadable file starts an empty cache, so a damaged
+    /// cache never stands in the way of a check run.
+    #[must_use]
+    pub fn load(path: impl Into<PathBuf>) -> Self {
+        let cache = Self::new(path);
+        let entries = fs::read_to_string(&cache.path)
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default();
+        Self {
+            entries: Mutex::new(entries),
+            ..cache
+        }
+    }
+
+    /// Default cache file: `$XDG_CACHE_HOME/nginx-discovery/network.json`,
+    /// falling back to `~/.cache` and then the temporary directory
+    #[must_use]
+    pub fn default_path() -> PathBuf {
+        cache_dir().join("network.json")
+    }
+
+    /// File the cache saves to
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Number of stored results, fresh or not
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Whether no results are stored
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Stored result for a target if it is younger than `ttl`
+    ///
+    /// The returned result has [`NetworkCheckResult::cached`] set to its
+    /// age.
+    #[must_use]
+    pub fn get(&self, check_type: &str, target: &str, ttl: Duration) -> Option<NetworkCheckResult> {
+        let entries = self.lock();
+        let entry = entries.get(&key(check_type, target))?;
+        let age = Duration::from_secs(now().saturating_sub(entry.stored_at));
+        (age < ttl).then(|| NetworkCheckResult {
+            cached: Some(age),
+            ..entry.result.clone()
+        })
+    }
+
+    /// Store a live result, replacing any earlier one for its target
+    ///
+    /// Results that were themselves served from a cache, and results with
+    /// [`HealthStatus::Error`], are ignored.
+    pub fn insert(&self, result: &NetworkCheckResult) {
+        if result.cached.is_some() || result.status == HealthStatus::Error {
+            return;
+        }
+        self.lock().insert(
+            key(&result.check_type, &result.target),
+            CacheEntry {
+                stored_at: now(),
+                result: result.clone(),
+            },
+        );
+    }
+
+    /// Drop results older than `max_age`
+    pub fn prune(&self, max_age: Duration) {
+        let now = now();
+        self.lock()
+            .retain(|_, entry| Duration::from_secs(now.saturating_sub(entry.stored_at)) < max_age);
+    }
+
+    /// Write the cache to its file, creating parent directories
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written.
+    pub fn save(&self) -> Result<()> {
+        let json = serde_json::to_string(&*self.lock())
+            .map_err(|e| Error::Serialization(e.to_string()))?;
+        if let Some(parent) = self.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        // Write next to the target and rename, so concurrent runs never
+        // read a half-written file
+        let tmp = self.path.with_extension("json.tmp");
+        fs::write(&tmp, json)?;
+        fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BTreeMap<String, CacheEntry>> {
+        self.entries.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Answer a check from the cache in `options`, or run it and store the
+/// result
+pub(crate) async fn cached<F>(
+    options: &NetworkCheckOptions,
+    check_type: &str,
+    target: &str,
+    check: F,
+) -> NetworkCheckResult
+where
+    F: Future<Output = NetworkCheckResult>,
+{
+    let Some(cache) = &options.cache else {
+        return check.await;
+    };
+    let Some(ttl) = options.cache_ttls.for_check(check_type) else {
+        return check.await;
+    };
+    if let Some(hit) = cache.get(check_type, target, ttl) {
+        return hit;
+    }
+    let result = check.await;
+    cache.insert(&result);
+    result
+}
+
+/// `$XDG_CACHE_HOME/nginx-discovery`, falling back to `~/.cache` and then
+/// the temporary directory
+pub(crate) fn cache_dir() -> PathBuf {
+    std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))
+        .unwrap_or_else(std::env::temp_dir)
+        .join("nginx-discovery")
+}
+
+fn key(check_type: &str, target: &str) -> String {
+    format!("{check_type} {target}")
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::CheckSeverity;
+    use std::sync::Arc;
+
+    fn result(target: &str, status: HealthStatus) -> NetworkCheckResult {
+        NetworkCheckResult {
+            check_type: "dns".to_string(),
+            target: target.to_string(),
+            status,
+            message: "resolved".to_string(),
+            severity: CheckSeverity::Info,
+            details: None,
+            latency: Some(Duration::from_millis(12)),
+            cached: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_result_cache() {
+        let dir =
+            std::env::temp_dir().join(format!("nginx-discovery-cache-{}", std::process::id()));
+        let path = dir.join("network.json");
+        let cache = Arc::new(ResultCache::new(&path));
+        let options = NetworkCheckOptions {
+            cache: Some(Arc::clone(&cache)),
+            ..Default::default()
+        };
+
+        let live = cached(&options, "dns", "example.com", async {
+            result("example.com", HealthStatus::Healthy)
+        })
+        .await;
+        assert_eq!(live.cached, None);
+
+        let hit = cached(&options, "dns", "example.com", async {
+            unreachable!("served from cache")
+        })
+        .await;
+        assert!(hit.cached.is_some());
+        assert_eq!(hit.message, "resolved");
+
+        // Errors and uncached check types are not stored
+        cached(&options, "dns", "broken.example", async {
+            result("broken.example", HealthStatus::Error)
+        })
+        .await;
+        cached(&options, "upstream", "10.0.0.1:80", async {
+            result("10.0.0.1:80", HealthStatus::Healthy)
+        })
+        .await;
+        assert_eq!(cache.len(), 1);
+        assert!(cache.get("dns", "example.com", Duration::ZERO).is_none());
+
+        cache.save().unwrap();
+        let reloaded = ResultCache::load(&path);
+        assert_eq!(reloaded.len(), 1);
+        reloaded.prune(Duration::ZERO);
+        assert!(reloaded.is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(ResultCache::load(&path).is_empty());
+    }
+
+    #[test]
+    fn test_damaged_or_unwritable_cache() {
+        let dir = tempfile::tempdir().unwrap();
+
+        // A damaged file starts an empty cache, which saves over it
+        let path = dir.path().join("network.json");
+        fs::write(&path, "{not json").unwrap();
+        let cache = ResultCache::load(&path);
+        assert!(cache.is_empty());
+        cache.insert(&result("example.com", HealthStatus::Healthy));
+        cache.save().unwrap();
+        assert_eq!(ResultCache::load(&path).len(), 1);
+        assert!(!path.with_extension("json.tmp").exists());
+
+        // A parent that is a file cannot hold the cache
+        let blocked = ResultCache::new(path.join("network.json"));
+        blocked.insert(&result("example.com", HealthStatus::Healthy));
+        assert!(blocked.save().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_cache_insert_rules() {
+        let cache = ResultCache::new("unused.json");
+
+        // Results served from a cache are not stored again
+        let mut served = result("example.com", HealthStatus::Healthy);
+        served.cached = Some(Duration::from_secs(30));
+        cache.insert(&served);
+        assert!(cache.is_empty());
+
+        // A newer result replaces the earlier one for the same target
+        cache.insert(&result("example.com", HealthStatus::Healthy));
+        let mut degraded = result("example.com", HealthStatus::Degraded);
+        degraded.message = "slow".to_string();
+        cache.insert(&degraded);
+        assert_eq!(cache.len(), 1);
+        let hit = cache
+            .get("dns", "example.com", Duration::from_secs(60))
+            .unwrap();
+        assert_eq!(hit.message, "slow");
+        assert!(cache
+            .get("ssl", "example.com", Duration::from_secs(60))
+            .is_none());
+
+        // Without a cache in the options every check runs
+        let live = cached(
+            &NetworkCheckOptions::default(),
+            "dns",
+            "example.com",
+            async { result("example.com", HealthStatus::Unhealthy) },
+        )
+        .await;
+        assert_eq!(live.status, HealthStatus::Unhealthy);
+        assert_eq!(live.cached, None);
+    }
+}
@@ -5,6 +5,10 @@
 //! hostnames configured in NGINX. It supports parallel resolution and validation
 //! with timeouts.
 //!
+//! Resolution uses the system resolver unless name servers are given with
+//! [`DnsResolverOptions`], such as those of nginx's own `resolver`
+//! directive ([`config_nameservers`]).
+//!
 //! # Examples
 //!
 //! ```no_run
@@ -18,8 +22,13 @@
 //! }
 //! ```
 
-use super::types::{CheckSeverity, DnsCheckResult, DnsValidationResult, HealthStatus};
+use super::types::{
+    CheckSeverity, DnsCheckResult, DnsResolverOptions, DnsValidationResult, HealthStatus,
+};
+use crate::ast::Config;
+use crate::lint::walk;
 use crate::Result;
+use std::net::{IpAddr, SocketAddr};
 use std::time::{Duration, Instant};
 
 /// Resolves a hostname to IP addresses with timeout.
@@ -64,6 +73,92 @@ use std::time::{Duration, Instant};
 /// }
 /// ```
 pub async fn resolve_hostname(hostname: &str) -> Result<DnsCheckResult> {
+    resolve_hostname_using(hostname, None).await
+}
+
+/// Resolves a hostname with the given name servers instead of the system
+/// resolver.
+///
+/// With no name servers this is [`resolve_hostname`]. Use it to check what
+/// nginx sees through its own `resolver`, or an internal resolver.
+///
+/// # Errors
+///
+/// Returns an error if:
+/// - The `network` feature is not enabled
+///
+/// # Examples
+///
+/// ```no_run
+/// use nginx_discovery::network::{resolve_hostname_with, DnsResolverOptions};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let resolver = DnsResolverOptions::default().with_nameserver("10.0.0.2:53".parse()?);
+///     let result = resolve_hostname_with("api.internal", &resolver).await?;
+///     println!("Resolved to: {:?}", result.addresses);
+///     Ok(())
+/// }
+/// ```
+pub async fn resolve_hostname_with(
+    hostname: &str,
+    resolver: &DnsResolverOptions,
+) -> Result<DnsCheckResult> {
+    let resolver = build_resolver(&resolver.nameservers);
+    resolve_hostname_using(hostname, resolver.as_ref()).await
+}
+
+/// Resolver querying `nameservers` over UDP, then TCP; `None` for the
+/// system resolver
+pub(crate) fn build_resolver(nameservers: &[SocketAddr]) -> Option<NameServers> {
+    #[cfg(feature = "network")]
+    {
+        use trust_dns_resolver::config::{
+            NameServerConfig, Protocol, ResolverConfig, ResolverOpts,
+        };
+        use trust_dns_resolver::TokioAsyncResolver;
+
+        if nameservers.is_empty() {
+            return None;
+        }
+        let configs: Vec<NameServerConfig> = [Protocol::Udp, Protocol::Tcp]
+            .into_iter()
+            .flat_map(|protocol| {
+                nameservers
+                    .iter()
+                    .map(move |addr| NameServerConfig::new(*addr, protocol))
+            })
+            .collect();
+        let mut opts = ResolverOpts::default();
+        opts.timeout = Duration::from_secs(2);
+        opts.attempts = 1;
+        let resolver =
+            TokioAsyncResolver::tokio(ResolverConfig::from_parts(None, Vec::new(), configs), opts);
+        Some(NameServers {
+            resolver,
+            addresses: nameservers.to_vec(),
+        })
+    }
+
+    #[cfg(not(feature = "network"))]
+    {
+        let _ = nameservers;
+        None
+    }
+}
+
+/// A resolver for explicit name servers
+pub(crate) struct NameServers {
+    #[cfg(feature = "network")]
+    resolver: trust_dns_resolver::TokioAsyncResolver,
+    addresses: Vec<SocketAddr>,
+}
+
+/// Resolves a hostname with `resolver`, or the system resolver when `None`.
+pub(crate) async fn resolve_hostname_using(
+    hostname: &str,
+    resolver: Option<&NameServers>,
+) -> Result<DnsCheckResult> {
     #[cfg(feature = "network")]
     {
         use tokio::net::lookup_host;
@@ -72,21 +167,39 @@ pub async fn resolve_hostname(hostname: &str) -> Result<DnsCheckResult> {
         let start = Instant::now();
 
         // Try to resolve with timeout
-        let resolve_result =
-            timeout(Duration::from_secs(5), lookup_host(format!("{hostname}:0"))).await;
+        let lookup = async {
+            match resolver {
+                None => lookup_host(format!("{hostname}:0"))
+                    .await
+                    .map(|addrs| addrs.map(|addr| addr.ip().to_string()).collect::<Vec<_>>())
+                    .map_err(|e| e.to_string()),
+                Some(custom) => custom
+                    .resolver
+                    .lookup_ip(hostname)
+                    .await
+                    .map(|lookup| lookup.iter().map(|ip| ip.to_string()).collect())
+                    .map_err(|e| e.to_string()),
+            }
+        };
+        let resolve_result = timeout(Duration::from_secs(5), lookup).await;
 
         let resolution_time = start.elapsed();
+        let via = resolver
+            .map(|custom| {
+                let servers: Vec<String> =
+                    custom.addresses.iter().map(ToString::to_string).collect();
+                format!(" (name servers: {})", servers.join(", "))
+            })
+            .unwrap_or_default();
 
         match resolve_result {
-            Ok(Ok(addrs)) => {
-                let addresses: Vec<String> = addrs.map(|addr| addr.ip().to_string()).collect();
-
+            Ok(Ok(addresses)) => {
                 if addresses.is_empty() {
                     Ok(DnsCheckResult {
                         status: HealthStatus::Unhealthy,
                         message: format!("No addresses found for {hostname}"),
                         severity: CheckSeverity::Warning,
-                        details: None,
+                        details: (!via.is_empty()).then(|| via.trim().to_string()),
                         hostname: hostname.to_string(),
                         addresses,
                         resolution_time: Some(resolution_time),
@@ -96,7 +209,7 @@ pub async fn resolve_hostname(hostname: &str) -> Result<DnsCheckResult> {
                         status: HealthStatus::Healthy,
                         message: format!("Resolved {hostname} to {} address(es)", addresses.len()),
                         severity: CheckSeverity::Info,
-                        details: Some(format!("Addresses: {}", addresses.join(", "))),
+                        details: Some(format!("Addresses: {}{via}", addresses.join(", "))),
                         hostname: hostname.to_string(),
                         addresses,
                         resolution_time: Some(resolution_time),
@@ -107,7 +220,7 @@ pub async fn resolve_hostname(hostname: &str) -> Result<DnsCheckResult> {
                 status: HealthStatus::Error,
                 message: format!("Failed to resolve {hostname}"),
                 severity: CheckSeverity::Error,
-                details: Some(format!("Error: {e}")),
+                details: Some(format!("Error: {e}{via}")),
                 hostname: hostname.to_string(),
                 addresses: vec![],
                 resolution_time: Some(resolution_time),
@@ -116,7 +229,7 @@ pub async fn resolve_hostname(hostname: &str) -> Result<DnsCheckResult> {
                 status: HealthStatus::Error,
                 message: format!("Timeout resolving {hostname}"),
                 severity: CheckSeverity::Warning,
-                details: Some("Resolution timed out after 5 seconds".to_string()),
+                details: Some(format!("Resolution timed out after 5 seconds{via}")),
                 hostname: hostname.to_string(),
                 addresses: vec![],
                 resolution_time: Some(resolution_time),
@@ -126,12 +239,68 @@ pub async fn resolve_hostname(hostname: &str) -> Result<DnsCheckResult> {
 
     #[cfg(not(feature = "network"))]
     {
-        let _ = hostname;
+        let _ = (hostname, resolver);
         use crate::Error;
         Err(Error::FeatureNotEnabled("network".to_string()))
     }
 }
 
+/// Name servers of the configuration's `resolver` directive.
+///
+/// The `http`-level directive wins over those in `server` and `location`
+/// blocks; otherwise the first one is used. Host names and parameters
+/// (`valid=`, `ipv6=`, ...) are skipped.
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::network::config_nameservers;
+///
+/// let config = nginx_discovery::parse("http { resolver 10.0.0.2 [::1]:5353 valid=30s; }")?;
+/// let servers: Vec<String> = config_nameservers(&config).iter().map(ToString::to_string).collect();
+/// assert_eq!(servers, ["10.0.0.2:53", "[::1]:5353"]);
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+#[must_use]
+pub fn config_nameservers(config: &Config) -> Vec<SocketAddr> {
+    let mut first = None;
+    let mut http_level = None;
+    walk(config, &mut |directive, parents| {
+        if directive.name() != "resolver" {
+            return;
+        }
+        let nameservers: Vec<SocketAddr> = directive
+            .args_as_strings()
+            .iter()
+            .filter_map(|arg| parse_nameserver(arg))
+            .collect();
+        if parents
+            .iter()
+            .all(|p| p.name() != "server" && p.name() != "location")
+        {
+            http_level.get_or_insert_with(|| nameservers.clone());
+        }
+        first.get_or_insert(nameservers);
+    });
+    http_level.or(first).unwrap_or_default()
+}
+
+/// Parse a name server as nginx's `resolver` accepts it: `10.0.0.2`,
+/// `10.0.0.2:5353`, `[::1]` or `[::1]:5353`, port 53 unless given.
+///
+/// Returns `None` for host names and parameters.
+#[must_use]
+pub fn parse_nameserver(arg: &str) -> Option<SocketAddr> {
+    if let Ok(addr) = arg.parse::<SocketAddr>() {
+        return Some(addr);
+    }
+    arg.trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse::<IpAddr>()
+        .ok()
+        .map(|ip| SocketAddr::new(ip, 53))
+}
+
 /// Resolves multiple hostnames in parallel.
 ///
 /// This function performs concurrent DNS resolution for multiple hostnames,
@@ -351,4 +520,63 @@ mod tests {
         let validation = result.unwrap();
         assert!(validation.is_valid);
     }
+
+    #[test]
+    fn test_config_nameservers() {
+        let config = crate::parse(
+            r"
+http {
+    server {
+        resolver 10.9.9.9;
+    }
+    resolver 10.0.0.2 10.0.0.3:5353 [::1] kube-dns.local valid=30s ipv6=off;
+}
+",
+        )
+        .unwrap();
+        let servers: Vec<String> = config_nameservers(&config)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(servers, ["10.0.0.2:53", "10.0.0.3:5353", "[::1]:53"]);
+
+        let config = crate::parse("server { resolver 10.9.9.9; }").unwrap();
+        assert_eq!(config_nameservers(&config).len(), 1);
+        assert_eq!(parse_nameserver("valid=30s"), None);
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "network")]
+    async fn test_resolve_with_nameserver() {
+        // Name server answering every A query with 10.1.2.3
+        let socket = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = socket.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 512];
+            loop {
+                let (n, peer) = socket.recv_from(&mut buf).await.unwrap();
+                let mut reply = buf[..n].to_vec();
+                let question_end = 12 + reply[12..].iter().position(|&b| b == 0).unwrap() + 5;
+                reply.truncate(question_end);
+                reply[2] = 0x81;
+                reply[3] = 0x80;
+                reply[6..12].copy_from_slice(&[0, 0, 0, 0, 0, 0]);
+                let qtype = u16::from_be_bytes([reply[question_end - 4], reply[question_end - 3]]);
+                if qtype == 1 {
+                    reply[7] = 1;
+                    reply
+                        .extend_from_slice(&[0xc0, 12, 0, 1, 0, 1, 0, 0, 0, 60, 0, 4, 10, 1, 2, 3]);
+                }
+                socket.send_to(&reply, peer).await.unwrap();
+            }
+        });
+
+        let resolver = DnsResolverOptions::default().with_nameserver(addr);
+        let check = resolve_hostname_with("api.internal", &resolver)
+            .await
+            .unwrap();
+        assert_eq!(check.status, HealthStatus::Healthy);
+        assert_eq!(check.addresses, ["10.1.2.3"]);
+        assert!(check.details.unwrap().contains(&addr.to_string()));
+    }
 }
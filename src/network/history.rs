@@ -0,0 +1,499 @@
+//! Historical trend tracking for network check results
+//!
+//! A single run shows a backend as healthy or not; only a history shows a
+//! backend that flaps between the two, or a resolver that gets slower every
+//! day. [`CheckHistory`] appends timestamped results to a JSON Lines file,
+//! one [`HistoryRecord`] per line, and [`TargetHistory`] reads back the
+//! status changes and latency trend of one target.
+//!
+//! Results served from a [`ResultCache`](super::ResultCache) are not new
+//! observations and are not recorded.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use nginx_discovery::network::{check_all, CheckHistory, NetworkCheckOptions};
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let config = nginx_discovery::parse("server { listen 80; server_name example.com; }")?;
+//!     let history = CheckHistory::new(CheckHistory::default_path());
+//!
+//!     let results = check_all(&config, NetworkCheckOptions::default()).await?;
+//!     history.record(&results)?;
+//!
+//!     for target in history.target("example.com")? {
+//!         println!("{} {}: {} status changes", target.check_type, target.target, target.changes.len());
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+use super::cache::cache_dir;
+use super::types::HealthStatus;
+use super::NetworkCheckResult;
+use crate::{Error, Result};
+use chrono::{DateTime, Utc};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Status changes within this many recent samples that make a target
+/// flapping
+const FLAP_WINDOW: usize = 10;
+
+/// Status changes within [`FLAP_WINDOW`] samples at which a target is
+/// flapping
+const FLAP_CHANGES: usize = 3;
+
+/// A result with the time it was checked
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct HistoryRecord {
+    /// When the check ran
+    pub timestamp: DateTime<Utc>,
+
+    /// The result
+    #[serde(flatten)]
+    pub result: NetworkCheckResult,
+}
+
+/// A change of status between two consecutive samples
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct StatusChange {
+    /// Time of the first sample with the new status
+    pub at: DateTime<Utc>,
+
+    /// Previous status
+    pub from: HealthStatus,
+
+    /// New status
+    pub to: HealthStatus,
+
+    /// Message of the first sample with the new status
+    pub message: String,
+}
+
+/// Latency of a target over its history
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct LatencyTrend {
+    /// Samples with a measured latency
+    pub samples: usize,
+
+    /// Fastest sample
+    pub min: Duration,
+
+    /// Slowest sample
+    pub max: Duration,
+
+    /// Mean of all samples
+    pub mean: Duration,
+
+    /// Mean of the older half of the samples
+    pub earlier: Duration,
+
+    /// Mean of the newer half of the samples
+    pub recent: Duration,
+}
+
+impl LatencyTrend {
+    /// Change from the older to the newer half, in percent (positive when
+    /// slower)
+    #[must_use]
+    pub fn change_percent(&self) -> f64 {
+        if self.earlier.is_zero() {
+            return 0.0;
+        }
+        (self.recent.as_secs_f64() / self.earlier.as_secs_f64() - 1.0) * 100.0
+    }
+}
+
+/// History of one check type against one target
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct TargetHistory {
+    /// Check type (`dns`, `port`, ...)
+    pub check_type: String,
+
+    /// Target checked
+    pub target: String,
+
+    /// Samples, oldest first
+    pub samples: Vec<HistoryRecord>,
+
+    /// Status changes, oldest first
+    pub changes: Vec<StatusChange>,
+}
+
+impl TargetHistory {
+    /// Histories of `target` in `records`, one per check type and target
+    /// in first-seen order
+    ///
+    /// `target` matches a record's target exactly, or as its host:
+    /// `example.com` also matches `example.com:443`.
+    #[must_use]
+    pub fn from_records(records: &[HistoryRecord], target: &str) -> Vec<Self> {
+        let mut histories: Vec<Self> = Vec::new();
+        for record in records {
+            let result = &record.result;
+            let matches = result.target == target
+                || result
+                    .target
+                    .strip_prefix(target)
+                    .is_some_and(|rest| rest.starts_with(':'));
+            if !matches {
+                continue;
+            }
+
+            let index = histories
+                .iter()
+                .position(|h| h.check_type == result.check_type && h.target == result.target)
+                .unwrap_or_else(|| {
+                    histories.push(Self {
+                        check_type: result.check_type.clone(),
+                        target: result.target.clone(),
+                        samples: Vec::new(),
+                        changes: Vec::new(),
+                    });
+                    histories.len() - 1
+                });
+            let history = &mut histories[index];
+            if let Some(previous) = history.samples.last() {
+                if previous.result.status != result.status {
+                    history.changes.push(StatusChange {
+                        at: record.timestamp,
+                        from: previous.result.status,
+                        to: result.status,
+                        message: result.message.clone(),
+                    });
+                }
+            }
+            history.samples.push(record.clone());
+        }
+        histories
+    }
+
+    /// Most recent sample
+    #[must_use]
+    pub fn current(&self) -> Option<&HistoryRecord> {
+        self.samples.last()
+    }
+
+    /// Whether the status changed at least three times within the last
+    /// ten samples
+    #[must_use]
+    pub fn is_flapping(&self) -> bool {
+        let Some(window_start) = self
+            .samples
+            .len()
+            .checked_sub(FLAP_WINDOW)
+            .and_then(|i| self.samples.get(i + 1))
+            .or(self.samples.first())
+        else {
+            return false;
+        };
+        self.changes
+            .iter()
+            .filter(|c| c.at >= window_start.timestamp)
+            .count()
+            >= FLAP_CHANGES
+    }
+
+    /// Latency statistics, `None` without measured samples
+    #[must_use]
+    pub fn latency(&self) -> Option<LatencyTrend> {
+        let latencies: Vec<Duration> = self
+            .samples
+            .iter()
+            .filter_map(|s| s.result.latency)
+            .collect();
+        let (&min, &max) = (latencies.iter().min()?, latencies.iter().max()?);
+        let half = (latencies.len() / 2).max(1);
+        Some(LatencyTrend {
+            samples: latencies.len(),
+            min,
+            max,
+            mean: mean(&latencies),
+            earlier: mean(&latencies[..half]),
+            recent: mean(&latencies[latencies.len() - half..]),
+        })
+    }
+}
+
+fn mean(latencies: &[Duration]) -> Duration {
+    let count = u32::try_from(latencies.len()).unwrap_or(u32::MAX).max(1);
+    latencies.iter().sum::<Duration>() / count
+}
+
+/// Check results persisted as JSON Lines
+#[derive(Debug, Clone)]
+pub struct CheckHistory {
+    path: PathBuf,
+}
+
+impl CheckHistory {
+    /// History stored in `path`
+    #[must_use]
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Default history file:
+    /// `$XDG_CACHE_HOME/nginx-discovery/history.jsonl`, falling back to
+    /// `~/.cache` and then the temporary directory
+    #[must_use]
+    pub fn default_path() -> PathBuf {
+        cache_dir().join("history.jsonl")
+    }
+
+    /// File the history is stored in
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append live results, timestamped now
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written.
+    pub fn record(&self, results: &[NetworkCheckResult]) -> Result<()> {
+        self.record_at(results, Utc::now())
+    }
+
+    /// Append live results with the given timestamp, creating the file and
+    /// its parent directories
+    ///
+    /// Results served from a cache are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written.
+    pub fn record_at(
+        &self,
+        results: &[NetworkCheckResult],
+        timestamp: DateTime<Utc>,
+    ) -> Result<()> {
+        let mut lines = String::new();
+        for result in results.iter().filter(|r| r.cached.is_none()) {
+            let record = HistoryRecord {
+                timestamp,
+                result: result.clone(),
+            };
+            let line =
+                serde_json::to_string(&record).map_err(|e| Error::Serialization(e.to_string()))?;
+            lines.push_str(&line);
+            lines.push('\n');
+        }
+        if lines.is_empty() {
+            return Ok(());
+        }
+
+        if let Some(parent) = self.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        file.write_all(lines.as_bytes())?;
+        Ok(())
+    }
+
+    /// All records, oldest first
+    ///
+    /// A missing file is an empty history; lines that are not valid
+    /// records (for example one cut short by a crash) are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file exists but cannot be read.
+    pub fn records(&self) -> Result<Vec<HistoryRecord>> {
+        let text = match fs::read_to_string(&self.path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut records: Vec<HistoryRecord> = text
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect();
+        records.sort_by_key(|r| r.timestamp);
+        Ok(records)
+    }
+
+    /// Histories of `target`, see [`TargetHistory::from_records`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file exists but cannot be read.
+    pub fn target(&self, target: &str) -> Result<Vec<TargetHistory>> {
+        Ok(TargetHistory::from_records(&self.records()?, target))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::CheckSeverity;
+
+    fn result(check_type: &str, target: &str, status: HealthStatus, ms: u64) -> NetworkCheckResult {
+        NetworkCheckResult {
+            check_type: check_type.to_string(),
+            target: target.to_string(),
+            status,
+            message: format!("{status}"),
+            severity: CheckSeverity::Info,
+            details: None,
+            latency: Some(Duration::from_millis(ms)),
+            cached: None,
+        }
+    }
+
+    #[test]
+    fn test_check_history() {
+        let dir =
+            std::env::temp_dir().join(format!("nginx-discovery-history-{}", std::process::id()));
+        let history = CheckHistory::new(dir.join("history.jsonl"));
+        let start = Utc::now() - chrono::Duration::hours(1);
+
+        let statuses = [
+            HealthStatus::Healthy,
+            HealthStatus::Unhealthy,
+            HealthStatus::Healthy,
+            HealthStatus::Healthy,
+            HealthStatus::Unhealthy,
+        ];
+        for (i, status) in (0..).zip(statuses) {
+            let ms = 10 * (i + 1);
+            let mut cached = result("port", "10.0.0.1:80", HealthStatus::Healthy, 1);
+            cached.cached = Some(Duration::from_secs(1));
+            history
+                .record_at(
+                    &[
+                        result("port", "10.0.0.1:80", status, ms),
+                        result("dns", "example.com", HealthStatus::Healthy, 5),
+                        cached,
+                    ],
+                    start + chrono::Duration::minutes(i64::try_from(i).unwrap()),
+                )
+                .unwrap();
+        }
+        fs::write(
+            history.path(),
+            format!(
+                "{}{{\"truncated\n",
+                fs::read_to_string(history.path()).unwrap()
+            ),
+        )
+        .unwrap();
+
+        assert_eq!(history.records().unwrap().len(), 10);
+
+        let port = history.target("10.0.0.1").unwrap();
+        assert_eq!(port.len(), 1);
+        let port = &port[0];
+        assert_eq!(port.samples.len(), 5);
+        assert_eq!(port.changes.len(), 3);
+        assert_eq!(port.changes[0].from, HealthStatus::Healthy);
+        assert_eq!(port.changes[0].to, HealthStatus::Unhealthy);
+        assert!(port.is_flapping());
+        assert_eq!(
+            port.current().unwrap().result.status,
+            HealthStatus::Unhealthy
+        );
+
+        let latency = port.latency().unwrap();
+        assert_eq!(latency.min, Duration::from_millis(10));
+        assert_eq!(latency.max, Duration::from_millis(50));
+        assert_eq!(latency.earlier, Duration::from_millis(15));
+        assert_eq!(latency.recent, Duration::from_millis(45));
+        assert!((latency.change_percent() - 200.0).abs() < 1e-9);
+
+        let dns = history.target("example.com").unwrap();
+        assert!(dns[0].changes.is_empty());
+        assert!(!dns[0].is_flapping());
+        assert!(history.target("example").unwrap().is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(history.records().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_history_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let now = Utc::now();
+
+        // Only cached results: nothing to write, no file created
+        let history = CheckHistory::new(dir.path().join("nested").join("history.jsonl"));
+        let mut cached = result("dns", "example.com", HealthStatus::Healthy, 5);
+        cached.cached = Some(Duration::from_secs(1));
+        history.record_at(&[cached], now).unwrap();
+        assert!(!history.path().exists());
+
+        // Records appended out of order come back oldest first
+        let live = [result("dns", "example.com", HealthStatus::Healthy, 5)];
+        history.record_at(&live, now).unwrap();
+        history
+            .record_at(&live, now - chrono::Duration::minutes(5))
+            .unwrap();
+        let times: Vec<_> = history
+            .records()
+            .unwrap()
+            .iter()
+            .map(|r| r.timestamp)
+            .collect();
+        assert_eq!(times, vec![now - chrono::Duration::minutes(5), now]);
+
+        // A directory cannot be read or appended to
+        let unreadable = CheckHistory::new(dir.path());
+        assert!(unreadable.records().is_err());
+        assert!(unreadable.target("example.com").is_err());
+        assert!(unreadable.record_at(&live, now).is_err());
+    }
+
+    #[test]
+    fn test_target_history_edge_cases() {
+        let start = Utc::now();
+        let record = |minute: i64, status: HealthStatus, latency: Option<u64>| {
+            let mut result = result("port", "10.0.0.1:80", status, 0);
+            result.latency = latency.map(Duration::from_millis);
+            HistoryRecord {
+                timestamp: start + chrono::Duration::minutes(minute),
+                result,
+            }
+        };
+
+        // Three changes followed by ten stable samples: no longer flapping
+        let mut records: Vec<_> = (0..4)
+            .map(|i| {
+                let status = if i % 2 == 0 {
+                    HealthStatus::Healthy
+                } else {
+                    HealthStatus::Unhealthy
+                };
+                record(i, status, None)
+            })
+            .collect();
+        assert!(TargetHistory::from_records(&records, "10.0.0.1")[0].is_flapping());
+        records.extend((4..14).map(|i| record(i, HealthStatus::Unhealthy, None)));
+        let history = &TargetHistory::from_records(&records, "10.0.0.1:80")[0];
+        assert_eq!(history.changes.len(), 3);
+        assert!(!history.is_flapping());
+
+        // No measured latency, then a single sample
+        assert_eq!(history.latency(), None);
+        let single =
+            &TargetHistory::from_records(&[record(0, HealthStatus::Healthy, Some(0))], "10.0.0.1")
+                [0];
+        let latency = single.latency().unwrap();
+        assert_eq!(
+            (latency.samples, latency.earlier, latency.recent),
+            (1, Duration::ZERO, Duration::ZERO)
+        );
+        assert!(latency.change_percent().abs() < f64::EPSILON);
+
+        // A prefix of the host is not the host
+        assert!(TargetHistory::from_records(&records, "10.0.0.").is_empty());
+        assert!(TargetHistory::from_records(&[], "10.0.0.1").is_empty());
+    }
+}
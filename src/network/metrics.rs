@@ -0,0 +1,296 @@
+//! Prometheus exposition of network check results
+//!
+//! [`prometheus_metrics`] renders results in the Prometheus text format
+//! (version 0.0.4), for scraping from a long-running checker such as
+//! `nginx-discover daemon` or for the node exporter's textfile collector.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use nginx_discovery::network::{check_all, prometheus_metrics, NetworkCheckOptions};
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let config = nginx_discovery::parse("server { listen 80; server_name example.com; }")?;
+//!     let results = check_all(&config, NetworkCheckOptions::default()).await?;
+//!     std::fs::write("/var/lib/node_exporter/nginx.prom", prometheus_metrics(&results))?;
+//!     Ok(())
+//! }
+//! ```
+
+use super::types::{CheckSeverity, HealthStatus};
+use super::NetworkCheckResult;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::time::Duration;
+
+/// Metric name prefix
+const PREFIX: &str = "nginx_discovery";
+
+/// Worst outcome of the results for one check and target
+struct TargetMetrics {
+    healthy: bool,
+    severity: CheckSeverity,
+    latency: Option<Duration>,
+}
+
+/// Render results as Prometheus metrics
+///
+/// Per check and target:
+///
+/// - `nginx_discovery_check_up`: 1 when healthy, 0 otherwise
+/// - `nginx_discovery_check_severity`: 0 (info) to 3 (critical)
+/// - `nginx_discovery_check_latency_seconds`: when measured
+///
+/// and `nginx_discovery_check_results` with the number of results per
+/// status. Targets whose checks do not apply are left out of the
+/// per-target metrics; several results for one check and target are
+/// merged into the worst of them.
+#[must_use]
+pub fn prometheus_metrics(results: &[NetworkCheckResult]) -> String {
+    let mut targets: BTreeMap<(&str, &str), TargetMetrics> = BTreeMap::new();
+    let mut statuses: BTreeMap<&str, usize> = BTreeMap::new();
+    for result in results {
+        let status = match result.status {
+            HealthStatus::Healthy => "healthy",
+            HealthStatus::Degraded => "degraded",
+            HealthStatus::Unhealthy => "unhealthy",
+            HealthStatus::Error => "error",
+            HealthStatus::NotApplicable => "not_applicable",
+        };
+        *statuses.entry(status).or_default() += 1;
+        if result.status == HealthStatus::NotApplicable {
+            continue;
+        }
+        let healthy = result.status == HealthStatus::Healthy;
+        targets
+            .entry((result.check_type.as_str(), result.target.as_str()))
+            .and_modify(|t| {
+                t.healthy &= healthy;
+                t.severity = t.severity.max(result.severity);
+                t.latency = t.latency.max(result.latency);
+            })
+            .or_insert(TargetMetrics {
+                healthy,
+                severity: result.severity,
+                latency: result.latency,
+            });
+    }
+
+    let mut out = String::new();
+    header(
+        &mut out,
+        "check_up",
+        "Whether the check target is healthy (1) or not (0)",
+    );
+    for ((check, target), metrics) in &targets {
+        let _ = writeln!(
+            out,
+            "{PREFIX}_check_up{} {}",
+            labels(check, target),
+            u8::from(metrics.healthy)
+        );
+    }
+
+    header(
+        &mut out,
+        "check_severity",
+        "Severity of the check result: 0 info, 1 warning, 2 error, 3 critical",
+    );
+    for ((check, target), metrics) in &targets {
+        let level = match metrics.severity {
+            CheckSeverity::Info => 0,
+            CheckSeverity::Warning => 1,
+            CheckSeverity::Error => 2,
+            CheckSeverity::Critical => 3,
+        };
+        let _ = writeln!(
+            out,
+            "{PREFIX}_check_severity{} {level}",
+            labels(check, target)
+        );
+    }
+
+    header(
+        &mut out,
+        "check_latency_seconds",
+        "Time the check took to answer",
+    );
+    for ((check, target), metrics) in &targets {
+        if let Some(latency) = metrics.latency {
+            let _ = writeln!(
+                out,
+                "{PREFIX}_check_latency_seconds{} {}",
+                labels(check, target),
+                latency.as_secs_f64()
+            );
+        }
+    }
+
+    header(
+        &mut out,
+        "check_results",
+        "Number of check results per status",
+    );
+    for (status, count) in &statuses {
+        let _ = writeln!(out, "{PREFIX}_check_results{{status=\"{status}\"}} {count}");
+    }
+    out
+}
+
+fn header(out: &mut String, name: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {PREFIX}_{name} {help}");
+    let _ = writeln!(out, "# TYPE {PREFIX}_{name} gauge");
+}
+
+fn labels(check: &str, target: &str) -> String {
+    format!(
+        "{{check=\"{}\",target=\"{}\"}}",
+        escape(check),
+        escape(target)
+    )
+}
+
+/// Escape a label value: backslash, double quote and line feed
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(
+        check_type: &str,
+        target: &str,
+        status: HealthStatus,
+        severity: CheckSeverity,
+        ms: Option<u64>,
+    ) -> NetworkCheckResult {
+        NetworkCheckResult {
+            check_type: check_type.to_string(),
+            target: target.to_string(),
+            status,
+            message: String::new(),
+            severity,
+            details: None,
+            latency: ms.map(Duration::from_millis),
+            cached: None,
+        }
+    }
+
+    #[test]
+    fn test_prometheus_metrics() {
+        let metrics = prometheus_metrics(&[
+            result(
+                "port",
+                "10.0.0.1:80",
+                HealthStatus::Healthy,
+                CheckSeverity::Info,
+                Some(250),
+            ),
+            result(
+                "port",
+                "10.0.0.1:80",
+                HealthStatus::Unhealthy,
+                CheckSeverity::Error,
+                Some(500),
+            ),
+            result(
+                "dns",
+                "we\"ird",
+                HealthStatus::Healthy,
+                CheckSeverity::Info,
+                None,
+            ),
+            result(
+                "port",
+                "10.0.0.1:443/udp",
+                HealthStatus::NotApplicable,
+                CheckSeverity::Info,
+                None,
+            ),
+        ]);
+
+        assert!(metrics.contains("# TYPE nginx_discovery_check_up gauge\n"));
+        assert!(
+            metrics.contains("nginx_discovery_check_up{check=\"port\",target=\"10.0.0.1:80\"} 0\n")
+        );
+        assert!(
+            metrics.contains("nginx_discovery_check_up{check=\"dns\",target=\"we\\\"ird\"} 1\n")
+        );
+        assert!(metrics
+            .contains("nginx_discovery_check_severity{check=\"port\",target=\"10.0.0.1:80\"} 2\n"));
+        assert!(metrics.contains(
+            "nginx_discovery_check_latency_seconds{check=\"port\",target=\"10.0.0.1:80\"} 0.5\n"
+        ));
+        assert!(!metrics.contains("udp"));
+        assert!(metrics.contains("nginx_discovery_check_results{status=\"healthy\"} 2\n"));
+        assert!(metrics.contains("nginx_discovery_check_results{status=\"not_applicable\"} 1\n"));
+    }
+
+    #[test]
+    fn test_prometheus_metrics_edge_cases() {
+        // No results: the headers alone
+        let empty = prometheus_metrics(&[]);
+        assert_eq!(
+            empty.lines().filter(|l| !l.starts_with('#')).count(),
+            0,
+            "{empty}"
+        );
+        assert_eq!(empty.matches("# TYPE ").count(), 4);
+
+        let metrics = prometheus_metrics(&[
+            result(
+                "ssl",
+                "a\\b\nc",
+                HealthStatus::Degraded,
+                CheckSeverity::Warning,
+                Some(120),
+            ),
+            result(
+                "ssl",
+                "a\\b\nc",
+                HealthStatus::Healthy,
+                CheckSeverity::Info,
+                None,
+            ),
+            result(
+                "upstream",
+                "10.0.0.2:80",
+                HealthStatus::Error,
+                CheckSeverity::Critical,
+                None,
+            ),
+        ]);
+
+        // Merged into the worst result, keeping the measured latency; label
+        // values escape backslashes and line feeds
+        let labels = "{check=\"ssl\",target=\"a\\\\b\\nc\"}";
+        assert!(metrics.contains(&format!("nginx_discovery_check_up{labels} 0\n")));
+        assert!(metrics.contains(&format!("nginx_discovery_check_severity{labels} 1\n")));
+        assert!(metrics.contains(&format!(
+            "nginx_discovery_check_latency_seconds{labels} 0.12\n"
+        )));
+        assert!(metrics.contains(
+            "nginx_discovery_check_severity{check=\"upstream\",target=\"10.0.0.2:80\"} 3\n"
+        ));
+        assert!(!metrics.contains("nginx_discovery_check_latency_seconds{check=\"upstream\""));
+
+        let statuses: Vec<_> = metrics
+            .lines()
+            .filter(|l| l.starts_with("nginx_discovery_check_results"))
+            .collect();
+        assert_eq!(
+            statuses,
+            [
+                "nginx_discovery_check_results{status=\"degraded\"} 1",
+                "nginx_discovery_check_results{status=\"error\"} 1",
+                "nginx_discovery_check_results{status=\"healthy\"} 1",
+            ]
+        );
+    }
+}
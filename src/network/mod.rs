@@ -2,7 +2,7 @@
 //! Network health checking and validation.
 //!
 //! This module orchestrates all network-related checks (DNS, ports, SSL,
-//! upstreams) across a parsed NGINX configuration.
+//! upstreams, host routing) across a parsed NGINX configuration.
 //!
 //! Individual checks live in submodules. This file is responsible for
 //! *running them across the whole config* and normalizing results.
@@ -27,42 +27,85 @@
 // Submodules
 // -----------------------------------------------------------------------------
 
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+pub mod alert;
+pub mod batch;
+pub mod budget;
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+pub mod cache;
 pub mod dns;
+
+#[cfg(feature = "network-history")]
+#[cfg_attr(docsrs, doc(cfg(feature = "network-history")))]
+pub mod history;
+
+pub mod metrics;
 pub mod port;
+pub mod proxy;
+pub mod registry;
+pub mod severity;
 pub mod ssl;
+pub mod summary;
 pub mod types;
 pub mod upstream;
+pub mod vhost;
 
 // -----------------------------------------------------------------------------
 // Public re-exports (stable API)
 // -----------------------------------------------------------------------------
 
+#[cfg(feature = "serde")]
+pub use alert::{AlertNotification, AlertSink, Alerter, Webhook, WebhookFormat};
+pub use batch::{
+    check_inventory, BatchOptions, BatchReport, FailureGroup, HostReport, Inventory,
+    InventorySource, InventoryTarget,
+};
+pub use budget::apply_latency_budgets;
+#[cfg(feature = "serde")]
+pub use cache::ResultCache;
+#[cfg(feature = "network-history")]
+pub use history::{CheckHistory, HistoryRecord, LatencyTrend, StatusChange, TargetHistory};
+pub use metrics::prometheus_metrics;
+pub use proxy::{Proxy, ProxyKind};
+pub use registry::{Check, CheckRegistry};
+pub use severity::{apply_severity_overrides, exit_code};
+pub use summary::{NetworkCheckSummary, SeverityCounts, StatusCounts};
+
 pub use types::{
-    CheckSeverity, DnsCheckResult, HealthCheckResult, HealthStatus, NetworkCheckOptions,
-    PortCheckResult, SslCheckResult,
+    CacheTtls, CheckSeverity, DnsCheckResult, DnsResolverOptions, HealthCheckResult, HealthStatus,
+    HostRoutingOptions, LatencyBudgets, NetworkCheckOptions, PortCheckResult, SeverityOverride,
+    SslCheckResult,
 };
 
 #[cfg(feature = "network")]
-pub use dns::resolve_hostname;
+pub use dns::{config_nameservers, parse_nameserver, resolve_hostname, resolve_hostname_with};
 
 #[cfg(feature = "network")]
 pub use dns::resolve_hostnames;
 
 #[cfg(feature = "network")]
-pub use port::check_port;
+pub use port::{check_port, check_port_via};
 
 #[cfg(feature = "network")]
-pub use ssl::check_ssl_certificate;
+pub use port::{check_udp_port, UdpProbe};
 
 #[cfg(feature = "network")]
-pub use upstream::check_upstream_backend;
+pub use ssl::{check_ssl_certificate, check_ssl_endpoint};
 
 #[cfg(feature = "network")]
-pub use upstream::check_upstream_http;
+pub use upstream::{check_upstream_backend, check_upstream_backend_via};
+
+#[cfg(feature = "network")]
+pub use upstream::{check_upstream_http, check_upstream_http_via};
 
 #[cfg(feature = "network")]
 pub use upstream::UpstreamBackend;
 
+#[cfg(feature = "network")]
+pub use vhost::check_host_routing;
+
 // -----------------------------------------------------------------------------
 // Imports
 // -----------------------------------------------------------------------------
@@ -71,6 +114,7 @@ pub use crate::network::dns::reverse_dns_lookup;
 pub use crate::network::dns::validate_dns_config;
 pub use crate::network::ssl::check_ssl_url;
 use crate::{ast::Config, Result};
+use std::time::Duration;
 
 // -----------------------------------------------------------------------------
 // Unified result type
@@ -81,6 +125,7 @@ use crate::{ast::Config, Result};
 /// All concrete network checks are normalized into this structure so
 /// callers (CLI, API, CI) do not need to understand submodules.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NetworkCheckResult {
     /// Category of check (dns, port, ssl, upstream)
     pub check_type: String,
@@ -99,6 +144,14 @@ pub struct NetworkCheckResult {
 
     /// Optional extra details
     pub details: Option<String>,
+
+    /// Time the check took, if measured
+    pub latency: Option<Duration>,
+
+    /// Age of the result when it was served from a `ResultCache` instead
+    /// of being checked live
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub cached: Option<Duration>,
 }
 
 // -----------------------------------------------------------------------------
@@ -108,8 +161,10 @@ pub struct NetworkCheckResult {
 /// Runs all enabled network checks against the configuration.
 ///
 /// This is the **primary public entry point** for network validation.
-/// It orchestrates DNS, port, SSL, and upstream checks based on the
-/// provided options and returns normalized results.
+/// It runs the built-in checks of [`CheckRegistry::with_builtin_checks`]
+/// that are enabled by the provided options and returns normalized results.
+/// Use a [`CheckRegistry`] directly to add custom checks or select checks
+/// by name.
 ///
 /// # Arguments
 ///
@@ -119,13 +174,12 @@ pub struct NetworkCheckResult {
 /// # Returns
 ///
 /// Returns a vector of `NetworkCheckResult` containing all check results.
-/// The order of results is: ports, DNS, upstreams, SSL.
+/// The order of results is: ports, DNS, upstreams, SSL, host routing.
 ///
 /// # Errors
 ///
-/// Returns an error if:
-/// - Server extraction from config fails
-/// - A check encounters a fatal error
+/// Returns an error if a check fails and
+/// [`NetworkCheckOptions::continue_on_error`] is not set.
 ///
 /// Note: Individual check failures are returned as results with error status,
 /// not as `Err`.
@@ -156,26 +210,9 @@ pub async fn check_all(
     config: &Config,
     options: NetworkCheckOptions,
 ) -> Result<Vec<NetworkCheckResult>> {
-    let mut results = Vec::new();
-
-    if options.check_ports {
-        results.extend(check_all_ports(config).await?);
-    }
-
-    if options.check_dns {
-        results.extend(check_all_dns(config).await?);
-    }
-
-    // These are intentionally no-ops for now
-    if options.check_upstreams {
-        results.extend(check_all_upstreams(config).await?);
-    }
-
-    if options.check_ssl {
-        results.extend(check_all_ssl(config).await?);
-    }
-
-    Ok(results)
+    CheckRegistry::with_builtin_checks()
+        .run(config, &options)
+        .await
 }
 
 // -----------------------------------------------------------------------------
@@ -186,8 +223,9 @@ pub async fn check_all(
 ///
 /// # Implementation Note
 ///
-/// Upstream extraction is not implemented yet. This function intentionally
-/// returns an empty result set until `extract::upstreams` exists.
+/// Backend connectivity checks are not wired up yet. This function
+/// intentionally returns an empty result set; the blocks to check are
+/// available from [`extract::upstreams`](crate::extract::upstreams).
 ///
 /// # Future Behavior
 ///
@@ -198,7 +236,7 @@ pub async fn check_all(
 #[allow(clippy::unused_async)]
 async fn check_all_upstreams(_config: &Config) -> Result<Vec<NetworkCheckResult>> {
     // TODO:
-    // Implement once upstream blocks are extracted from AST
+    // Check the servers from extract::upstreams
     Ok(Vec::new())
 }
 
@@ -234,11 +272,18 @@ async fn check_all_ssl(_config: &Config) -> Result<Vec<NetworkCheckResult>> {
 /// Checks all listen directives for port availability.
 ///
 /// Extracts all `listen` directives from server blocks and attempts
-/// to connect to each port to verify it's accessible.
-async fn check_all_ports(config: &Config) -> Result<Vec<NetworkCheckResult>> {
+/// to connect to each port to verify it's accessible. QUIC and `udp`
+/// listeners are checked with [`check_udp_port`]; their target ends in
+/// `/udp`. TCP probes go through [`NetworkCheckOptions::proxy`] when one is
+/// set; UDP probes are then not applicable. Results younger than the port TTL are served from
+/// [`NetworkCheckOptions::cache`].
+async fn check_all_ports(
+    config: &Config,
+    options: &NetworkCheckOptions,
+) -> Result<Vec<NetworkCheckResult>> {
     #[cfg(not(feature = "network"))]
     {
-        let _ = config;
+        let _ = (config, options);
         Ok(Vec::new())
     }
 
@@ -251,24 +296,63 @@ async fn check_all_ports(config: &Config) -> Result<Vec<NetworkCheckResult>> {
 
         for server in servers {
             for listen in &server.listen {
-                match check_port(&listen.address, listen.port).await {
-                    Ok(check) => results.push(NetworkCheckResult {
-                        check_type: "port".to_string(),
-                        target: format!("{}:{}", listen.address, listen.port),
-                        status: check.status,
-                        message: check.message,
-                        severity: check.severity,
-                        details: check.details,
-                    }),
-                    Err(e) => results.push(NetworkCheckResult {
-                        check_type: "port".to_string(),
-                        target: format!("{}:{}", listen.address, listen.port),
-                        status: HealthStatus::Error,
-                        message: format!("Port check failed: {e}"),
-                        severity: CheckSeverity::Error,
-                        details: None,
-                    }),
-                }
+                let target = if listen.is_udp() {
+                    format!("{}:{}/udp", listen.address, listen.port)
+                } else {
+                    format!("{}:{}", listen.address, listen.port)
+                };
+                let check = async {
+                    if listen.is_udp() {
+                        if let Some(proxy) = &options.proxy {
+                            return NetworkCheckResult {
+                                check_type: "port".to_string(),
+                                target: target.clone(),
+                                status: HealthStatus::NotApplicable,
+                                message: "UDP probes cannot go through a proxy".to_string(),
+                                severity: CheckSeverity::Info,
+                                details: Some(format!("Proxy: {proxy}")),
+                                latency: None,
+                                cached: None,
+                            };
+                        }
+                    }
+                    let check = if listen.is_udp() {
+                        let probe = if listen.is_quic() {
+                            UdpProbe::Quic
+                        } else {
+                            UdpProbe::Empty
+                        };
+                        check_udp_port(&listen.address, listen.port, probe).await
+                    } else {
+                        check_port_via(&listen.address, listen.port, options.proxy.as_ref()).await
+                    };
+                    match check {
+                        Ok(check) => NetworkCheckResult {
+                            check_type: "port".to_string(),
+                            target: target.clone(),
+                            status: check.status,
+                            message: check.message,
+                            severity: check.severity,
+                            details: check.details,
+                            latency: check.latency,
+                            cached: None,
+                        },
+                        Err(e) => NetworkCheckResult {
+                            check_type: "port".to_string(),
+                            target: target.clone(),
+                            status: HealthStatus::Error,
+                            message: format!("Port check failed: {e}"),
+                            severity: CheckSeverity::Error,
+                            details: None,
+                            latency: None,
+                            cached: None,
+                        },
+                    }
+                };
+                #[cfg(feature = "serde")]
+                results.push(cache::cached(options, "port", &target, check).await);
+                #[cfg(not(feature = "serde"))]
+                results.push(check.await);
             }
         }
 
@@ -284,10 +368,16 @@ async fn check_all_ports(config: &Config) -> Result<Vec<NetworkCheckResult>> {
 ///
 /// Extracts all `server_name` directives and performs DNS resolution
 /// for each hostname. Skips wildcards and special values like "_".
-async fn check_all_dns(config: &Config) -> Result<Vec<NetworkCheckResult>> {
+/// Names are resolved with the name servers of
+/// [`NetworkCheckOptions::dns`], or the system resolver. Results younger than the DNS TTL are served from
+/// [`NetworkCheckOptions::cache`].
+async fn check_all_dns(
+    config: &Config,
+    options: &NetworkCheckOptions,
+) -> Result<Vec<NetworkCheckResult>> {
     #[cfg(not(feature = "network"))]
     {
-        let _ = config;
+        let _ = (config, options);
         Ok(Vec::new())
     }
 
@@ -298,6 +388,15 @@ async fn check_all_dns(config: &Config) -> Result<Vec<NetworkCheckResult>> {
         let mut results = Vec::new();
         let servers = servers(config)?;
 
+        let mut nameservers = Vec::new();
+        if options.dns.use_config_resolver {
+            nameservers = dns::config_nameservers(config);
+        }
+        if nameservers.is_empty() {
+            nameservers.clone_from(&options.dns.nameservers);
+        }
+        let resolver = dns::build_resolver(&nameservers);
+
         for server in servers {
             for name in &server.server_names {
                 // Skip wildcards and internal placeholders
@@ -305,24 +404,34 @@ async fn check_all_dns(config: &Config) -> Result<Vec<NetworkCheckResult>> {
                     continue;
                 }
 
-                match resolve_hostname(name).await {
-                    Ok(check) => results.push(NetworkCheckResult {
-                        check_type: "dns".to_string(),
-                        target: name.clone(),
-                        status: check.status,
-                        message: check.message,
-                        severity: check.severity,
-                        details: check.details,
-                    }),
-                    Err(e) => results.push(NetworkCheckResult {
-                        check_type: "dns".to_string(),
-                        target: name.clone(),
-                        status: HealthStatus::Error,
-                        message: format!("DNS resolution failed: {e}"),
-                        severity: CheckSeverity::Warning,
-                        details: None,
-                    }),
-                }
+                let check = async {
+                    match dns::resolve_hostname_using(name, resolver.as_ref()).await {
+                        Ok(check) => NetworkCheckResult {
+                            check_type: "dns".to_string(),
+                            target: name.clone(),
+                            status: check.status,
+                            message: check.message,
+                            severity: check.severity,
+                            details: check.details,
+                            latency: check.resolution_time,
+                            cached: None,
+                        },
+                        Err(e) => NetworkCheckResult {
+                            check_type: "dns".to_string(),
+                            target: name.clone(),
+                            status: HealthStatus::Error,
+                            message: format!("DNS resolution failed: {e}"),
+                            severity: CheckSeverity::Warning,
+                            details: None,
+                            latency: None,
+                            cached: None,
+                        },
+                    }
+                };
+                #[cfg(feature = "serde")]
+                results.push(cache::cached(options, "dns", name, check).await);
+                #[cfg(not(feature = "serde"))]
+                results.push(check.await);
             }
         }
 
@@ -345,6 +454,7 @@ mod tests {
         assert!(options.check_dns);
         assert!(options.check_ssl);
         assert!(options.check_upstreams);
+        assert!(!options.check_host_routing);
     }
 
     #[test]
@@ -356,6 +466,8 @@ mod tests {
             message: "OK".to_string(),
             severity: CheckSeverity::Info,
             details: None,
+            latency: None,
+            cached: None,
         };
 
         assert_eq!(result.check_type, "test");
@@ -1,8 +1,15 @@
 //! Port availability checking
 //!
-//! Provides utilities for checking whether TCP ports are listening,
+//! Provides utilities for checking whether TCP and UDP ports are listening,
 //! scanning multiple ports concurrently, and finding available ports.
+//!
+//! UDP has no handshake, so [`check_udp_port`] combines two best-effort
+//! methods: on the nginx host, a port that cannot be bound because it is in
+//! use is listening; otherwise a datagram is sent and a reply means open,
+//! an ICMP port unreachable means closed, and silence is inconclusive
+//! ([`HealthStatus::Degraded`]).
 
+use super::proxy::{dial, Proxy};
 use super::types::{CheckSeverity, HealthStatus, PortCheckResult};
 use crate::Result;
 use std::time::{Duration, Instant};
@@ -15,44 +22,59 @@ use std::time::{Duration, Instant};
 ///
 /// Returns an error if the `network` feature is disabled.
 pub async fn check_port(address: &str, port: u16) -> Result<PortCheckResult> {
+    check_port_via(address, port, None).await
+}
+
+/// Check if a port is available (listening), connecting through `proxy`.
+///
+/// Without a proxy this is [`check_port`]. Through a proxy, a refused
+/// tunnel means the proxy could not reach the port.
+///
+/// # Errors
+///
+/// Returns an error if the `network` feature is disabled.
+pub async fn check_port_via(
+    address: &str,
+    port: u16,
+    proxy: Option<&Proxy>,
+) -> Result<PortCheckResult> {
     #[cfg(feature = "network")]
     {
-        use tokio::net::TcpStream;
-        use tokio::time::timeout;
+        use std::io::ErrorKind;
 
-        let target = format!("{address}:{port}");
         let start = Instant::now();
 
-        let connect_result = timeout(Duration::from_secs(5), TcpStream::connect(&target)).await;
+        let connect_result = dial(address, port, proxy, Duration::from_secs(5)).await;
 
         let latency = start.elapsed();
+        let via = proxy.map(|p| format!(" via {p}")).unwrap_or_default();
 
         match connect_result {
-            Ok(Ok(_)) => Ok(PortCheckResult {
+            Ok(_) => Ok(PortCheckResult {
                 status: HealthStatus::Healthy,
                 message: format!("Port {port} is listening on {address}"),
                 severity: CheckSeverity::Info,
-                details: Some(format!("Connection established in {latency:?}")),
+                details: Some(format!("Connection established{via} in {latency:?}")),
                 port,
                 address: address.to_string(),
                 is_listening: true,
                 latency: Some(latency),
             }),
-            Ok(Err(e)) => Ok(PortCheckResult {
-                status: HealthStatus::Unhealthy,
-                message: format!("Port {port} is not listening on {address}"),
-                severity: CheckSeverity::Error,
-                details: Some(format!("Connection failed: {e}")),
+            Err(e) if e.kind() == ErrorKind::TimedOut => Ok(PortCheckResult {
+                status: HealthStatus::Error,
+                message: format!("Timeout checking port {port} on {address}"),
+                severity: CheckSeverity::Warning,
+                details: Some(format!("Connection{via} timed out after 5 seconds")),
                 port,
                 address: address.to_string(),
                 is_listening: false,
                 latency: Some(latency),
             }),
-            Err(_) => Ok(PortCheckResult {
-                status: HealthStatus::Error,
-                message: format!("Timeout checking port {port} on {address}"),
-                severity: CheckSeverity::Warning,
-                details: Some("Connection timed out after 5 seconds".to_string()),
+            Err(e) => Ok(PortCheckResult {
+                status: HealthStatus::Unhealthy,
+                message: format!("Port {port} is not listening on {address}"),
+                severity: CheckSeverity::Error,
+                details: Some(format!("Connection{via} failed: {e}")),
                 port,
                 address: address.to_string(),
                 is_listening: false,
@@ -64,10 +86,247 @@ pub async fn check_port(address: &str, port: u16) -> Result<PortCheckResult> {
     #[cfg(not(feature = "network"))]
     {
         use crate::Error;
+        let _ = (address, port, proxy);
+        Err(Error::FeatureNotEnabled("network".to_string()))
+    }
+}
+
+/// Datagram sent by [`check_udp_port`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UdpProbe {
+    /// An empty datagram; only an ICMP port unreachable reply tells
+    /// anything
+    #[default]
+    Empty,
+
+    /// A QUIC Initial-sized packet with a reserved version, which QUIC
+    /// servers answer with a Version Negotiation packet (RFC 9000, 6.1)
+    Quic,
+}
+
+/// Check if a UDP port is listening.
+///
+/// First tries to bind the address and port locally: when running on the
+/// nginx host, "address in use" proves a listener. When the address is
+/// not local (or binding is not permitted), `probe` is sent and the
+/// result is:
+///
+/// - [`HealthStatus::Healthy`] when a reply arrives
+/// - [`HealthStatus::Unhealthy`] on ICMP port unreachable
+/// - [`HealthStatus::Degraded`] without an answer: the port is open or
+///   filtered, which UDP cannot tell apart
+///
+/// Wildcard addresses (`*`, `0.0.0.0`, `[::]`) are probed on loopback, and
+/// `unix:` sockets are [`HealthStatus::NotApplicable`].
+///
+/// # Errors
+///
+/// Returns an error if the `network` feature is disabled.
+///
+/// # Examples
+///
+/// ```no_run
+/// use nginx_discovery::network::{check_udp_port, HealthStatus, UdpProbe};
+///
+/// # async fn example() -> nginx_discovery::Result<()> {
+/// let check = check_udp_port("*", 443, UdpProbe::Quic).await?;
+/// if check.status == HealthStatus::Degraded {
+///     println!("undetermined: {}", check.message);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub async fn check_udp_port(address: &str, port: u16, probe: UdpProbe) -> Result<PortCheckResult> {
+    #[cfg(feature = "network")]
+    {
+        let result =
+            |status, severity, is_listening, message: String, details: String| PortCheckResult {
+                status,
+                message,
+                severity,
+                details: Some(details),
+                port,
+                address: address.to_string(),
+                is_listening,
+                latency: None,
+            };
+
+        if address.starts_with("unix:") {
+            return Ok(result(
+                HealthStatus::NotApplicable,
+                CheckSeverity::Info,
+                false,
+                format!("{address} is a unix socket"),
+                "Only IP listeners can be checked".to_string(),
+            ));
+        }
+
+        match udp_bound_locally(address, port) {
+            Some(true) => {
+                return Ok(result(
+                    HealthStatus::Healthy,
+                    CheckSeverity::Info,
+                    true,
+                    format!("UDP port {port} is bound on {address}"),
+                    "The port is in use on this host".to_string(),
+                ))
+            }
+            Some(false) => {
+                return Ok(result(
+                    HealthStatus::Unhealthy,
+                    CheckSeverity::Error,
+                    false,
+                    format!("UDP port {port} is not bound on {address}"),
+                    "Nothing on this host listens on the port".to_string(),
+                ))
+            }
+            None => {}
+        }
+
+        Ok(probe_udp(address, port, probe).await)
+    }
+
+    #[cfg(not(feature = "network"))]
+    {
+        use crate::Error;
+        let _ = (address, port, probe);
         Err(Error::FeatureNotEnabled("network".to_string()))
     }
 }
 
+/// Host to send probes to: wildcards become loopback
+#[cfg(feature = "network")]
+fn probe_host(address: &str) -> &str {
+    match address {
+        "*" | "0.0.0.0" => "127.0.0.1",
+        "::" | "[::]" => "::1",
+        _ => address,
+    }
+}
+
+/// Whether binding `address:port` fails because the port is in use.
+///
+/// `None` when binding is not possible for another reason: the address
+/// is not local, or privileged ports without permission.
+#[cfg(feature = "network")]
+fn udp_bound_locally(address: &str, port: u16) -> Option<bool> {
+    use std::io::ErrorKind;
+    use std::net::{IpAddr, UdpSocket};
+
+    let ip: IpAddr = match address {
+        "*" => IpAddr::from([0, 0, 0, 0]),
+        "localhost" => IpAddr::from([127, 0, 0, 1]),
+        _ => address.trim_matches(['[', ']']).parse().ok()?,
+    };
+
+    match UdpSocket::bind((ip, port)) {
+        Ok(_) => Some(false),
+        Err(e) if e.kind() == ErrorKind::AddrInUse => Some(true),
+        Err(_) => None,
+    }
+}
+
+#[cfg(feature = "network")]
+async fn probe_udp(address: &str, port: u16, probe: UdpProbe) -> PortCheckResult {
+    use tokio::net::UdpSocket;
+    use tokio::time::timeout;
+
+    let host = probe_host(address).trim_matches(['[', ']']);
+    let target = if host.contains(':') {
+        format!("[{host}]:{port}")
+    } else {
+        format!("{host}:{port}")
+    };
+    let local = if host.contains(':') {
+        "[::]:0"
+    } else {
+        "0.0.0.0:0"
+    };
+    let payload = match probe {
+        UdpProbe::Empty => Vec::new(),
+        UdpProbe::Quic => quic_version_probe(),
+    };
+
+    let start = Instant::now();
+    let exchange = async {
+        let socket = UdpSocket::bind(local).await?;
+        socket.connect(&target).await?;
+        socket.send(&payload).await?;
+        let mut buf = vec![0u8; 1500];
+        let len = socket.recv(&mut buf).await?;
+        buf.truncate(len);
+        Ok::<_, std::io::Error>(buf)
+    };
+    let reply = timeout(Duration::from_secs(2), exchange).await;
+    let latency = start.elapsed();
+
+    let (status, severity, is_listening, message, details) = match reply {
+        Ok(Ok(reply)) => (
+            HealthStatus::Healthy,
+            CheckSeverity::Info,
+            true,
+            format!("UDP port {port} is listening on {address}"),
+            if is_version_negotiation(&reply) {
+                "QUIC Version Negotiation received".to_string()
+            } else {
+                format!("{} byte reply received", reply.len())
+            },
+        ),
+        Ok(Err(e)) if e.kind() == std::io::ErrorKind::ConnectionRefused => (
+            HealthStatus::Unhealthy,
+            CheckSeverity::Error,
+            false,
+            format!("UDP port {port} is not listening on {address}"),
+            "ICMP port unreachable received".to_string(),
+        ),
+        Ok(Err(e)) => (
+            HealthStatus::Error,
+            CheckSeverity::Warning,
+            false,
+            format!("UDP probe of port {port} on {address} failed"),
+            e.to_string(),
+        ),
+        Err(_) => (
+            HealthStatus::Degraded,
+            CheckSeverity::Warning,
+            false,
+            format!("No reply from UDP port {port} on {address}"),
+            "The port is open or filtered; UDP cannot tell without a reply".to_string(),
+        ),
+    };
+
+    PortCheckResult {
+        status,
+        message,
+        severity,
+        details: Some(details),
+        port,
+        address: address.to_string(),
+        is_listening,
+        latency: Some(latency),
+    }
+}
+
+/// Long-header packet with the reserved version `0x1a2a3a4a`, padded to
+/// the 1200 bytes a server requires before answering
+#[cfg(feature = "network")]
+fn quic_version_probe() -> Vec<u8> {
+    let mut packet = vec![0xc0, 0x1a, 0x2a, 0x3a, 0x4a];
+    // Destination and source connection IDs, 8 bytes each
+    packet.push(8);
+    packet.extend_from_slice(b"ngxdscvr");
+    packet.push(8);
+    packet.extend_from_slice(b"ngxprobe");
+    packet.resize(1200, 0);
+    packet
+}
+
+/// Long header with version 0
+#[cfg(feature = "network")]
+fn is_version_negotiation(packet: &[u8]) -> bool {
+    packet.len() >= 5 && packet[0] & 0x80 != 0 && packet[1..5] == [0, 0, 0, 0]
+}
+
 /// Check multiple ports concurrently.
 ///
 /// # Errors
@@ -133,3 +392,34 @@ pub async fn find_available_port(start: u16, end: u16) -> Result<Option<u16>> {
         Err(Error::FeatureNotEnabled("network".to_string()))
     }
 }
+
+#[cfg(all(test, feature = "network"))]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_probe_udp() {
+        let socket = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        let port = socket.local_addr().unwrap().port();
+        std::thread::spawn(move || {
+            let mut buf = [0u8; 1500];
+            let (_, peer) = socket.recv_from(&mut buf).unwrap();
+            socket.send_to(&[0x80, 0, 0, 0, 0, 1], peer).unwrap();
+        });
+
+        let check = probe_udp("*", port, UdpProbe::Quic).await;
+        assert_eq!(check.status, HealthStatus::Healthy);
+        assert_eq!(
+            check.details.as_deref(),
+            Some("QUIC Version Negotiation received")
+        );
+    }
+
+    #[test]
+    fn test_quic_version_probe() {
+        let packet = quic_version_probe();
+        assert_eq!(packet.len(), 1200);
+        assert!(!is_version_negotiation(&packet));
+        assert!(is_version_negotiation(&[0xc0, 0, 0, 0, 0]));
+    }
+}
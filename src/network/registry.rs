@@ -0,0 +1,560 @@
+//! Pluggable registry of network checks
+//!
+//! Every network check is a [`Check`] trait object with a name, an
+//! applicability predicate over the parsed [`Config`], and an async `run`.
+//! [`CheckRegistry::with_builtin_checks`] provides the checks behind
+//! [`check_all`](super::check_all); downstream crates can register their own
+//! checks alongside them and callers can select checks by name.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use futures::future::BoxFuture;
+//! use nginx_discovery::ast::Config;
+//! use nginx_discovery::network::{Check, CheckRegistry, NetworkCheckOptions, NetworkCheckResult};
+//!
+//! struct MeshCheck;
+//!
+//! impl Check for MeshCheck {
+//!     fn name(&self) -> &str {
+//!         "mesh"
+//!     }
+//!
+//!     fn description(&self) -> &str {
+//!         "Verify sidecar registration"
+//!     }
+//!
+//!     fn run<'a>(
+//!         &'a self,
+//!         _config: &'a Config,
+//!         _options: &'a NetworkCheckOptions,
+//!     ) -> BoxFuture<'a, nginx_discovery::Result<Vec<NetworkCheckResult>>> {
+//!         Box::pin(async { Ok(Vec::new()) })
+//!     }
+//! }
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let config = nginx_discovery::parse("server { listen 80; }")?;
+//!     let registry = CheckRegistry::with_builtin_checks()
+//!         .with_check(MeshCheck)
+//!         .only(["port", "mesh"]);
+//!
+//!     let results = registry.run(&config, &NetworkCheckOptions::default()).await?;
+//!     println!("Performed {} checks", results.len());
+//!     Ok(())
+//! }
+//! ```
+
+use super::budget::apply_latency_budgets;
+use super::severity::apply_severity_overrides;
+use super::types::{CheckSeverity, HealthStatus, NetworkCheckOptions};
+use super::NetworkCheckResult;
+use crate::ast::Config;
+use crate::Result;
+use futures::future::BoxFuture;
+
+/// A network check that can be registered in a [`CheckRegistry`].
+pub trait Check: Send + Sync {
+    /// Unique name used for selection (`--only`/`--skip`) and as the
+    /// `check_type` of the results it produces.
+    fn name(&self) -> &str;
+
+    /// One-line description shown by `--list-checks`.
+    fn description(&self) -> &str;
+
+    /// Whether the check has anything to do for this configuration.
+    ///
+    /// Checks that do not apply are skipped without producing results.
+    fn applies_to(&self, _config: &Config) -> bool {
+        true
+    }
+
+    /// Whether the check is switched on by the given options.
+    ///
+    /// Built-in checks honour their `check_*` flag; custom checks are
+    /// enabled unless they override this.
+    fn is_enabled(&self, _options: &NetworkCheckOptions) -> bool {
+        true
+    }
+
+    /// Run the check against the configuration.
+    fn run<'a>(
+        &'a self,
+        config: &'a Config,
+        options: &'a NetworkCheckOptions,
+    ) -> BoxFuture<'a, Result<Vec<NetworkCheckResult>>>;
+}
+
+/// Ordered collection of [`Check`]s.
+///
+/// Checks run in registration order, one after another, so result order is
+/// stable between runs.
+#[derive(Default)]
+pub struct CheckRegistry {
+    checks: Vec<Box<dyn Check>>,
+}
+
+impl std::fmt::Debug for CheckRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CheckRegistry")
+            .field("checks", &self.names())
+            .finish()
+    }
+}
+
+impl CheckRegistry {
+    /// Create an empty registry.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a registry with the built-in checks: `port`, `dns`,
+    /// `upstream`, `ssl` and `host_routing`.
+    #[must_use]
+    pub fn with_builtin_checks() -> Self {
+        Self::new()
+            .with_check(PortCheck)
+            .with_check(DnsCheck)
+            .with_check(UpstreamCheck)
+            .with_check(SslCheck)
+            .with_check(HostRoutingCheck)
+    }
+
+    /// Add a check, returning the registry.
+    #[must_use]
+    pub fn with_check(mut self, check: impl Check + 'static) -> Self {
+        self.register(Box::new(check));
+        self
+    }
+
+    /// Add a check.
+    ///
+    /// A check with the same name as an existing one replaces it in place.
+    pub fn register(&mut self, check: Box<dyn Check>) {
+        if let Some(existing) = self.checks.iter_mut().find(|c| c.name() == check.name()) {
+            *existing = check;
+        } else {
+            self.checks.push(check);
+        }
+    }
+
+    /// Keep only the checks with the given names.
+    #[must_use]
+    pub fn only<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let names: Vec<S> = names.into_iter().collect();
+        self.checks
+            .retain(|c| names.iter().any(|n| n.as_ref() == c.name()));
+        self
+    }
+
+    /// Remove the checks with the given names.
+    #[must_use]
+    pub fn skip<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let names: Vec<S> = names.into_iter().collect();
+        self.checks
+            .retain(|c| !names.iter().any(|n| n.as_ref() == c.name()));
+        self
+    }
+
+    /// Look up a check by name.
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&dyn Check> {
+        self.checks
+            .iter()
+            .find(|c| c.name() == name)
+            .map(AsRef::as_ref)
+    }
+
+    /// Whether a check with this name is registered.
+    #[must_use]
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// Names of all registered checks, in run order.
+    #[must_use]
+    pub fn names(&self) -> Vec<&str> {
+        self.checks.iter().map(|c| c.name()).collect()
+    }
+
+    /// Iterate over the registered checks.
+    pub fn iter(&self) -> impl Iterator<Item = &dyn Check> {
+        self.checks.iter().map(AsRef::as_ref)
+    }
+
+    /// Number of registered checks.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.checks.len()
+    }
+
+    /// Whether no checks are registered.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.checks.is_empty()
+    }
+
+    /// Run every enabled and applicable check.
+    ///
+    /// The latency budgets, then the severity overrides, in `options` are
+    /// applied to the results, which are then passed to the
+    /// `NetworkCheckOptions::alerts` alerter, if any.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error from a check or alert delivery unless
+    /// [`NetworkCheckOptions::continue_on_error`] is set, in which case the
+    /// error is recorded as a result with [`HealthStatus::Error`].
+    pub async fn run(
+        &self,
+        config: &Config,
+        options: &NetworkCheckOptions,
+    ) -> Result<Vec<NetworkCheckResult>> {
+        let mut results = Vec::new();
+
+        for check in &self.checks {
+            if !check.is_enabled(options) || !check.applies_to(config) {
+                continue;
+            }
+
+            match check.run(config, options).await {
+                Ok(check_results) => results.extend(check_results),
+                Err(e) if options.continue_on_error => results.push(NetworkCheckResult {
+                    check_type: check.name().to_string(),
+                    target: String::new(),
+                    status: HealthStatus::Error,
+                    message: format!("Check '{}' failed: {e}", check.name()),
+                    severity: CheckSeverity::Error,
+                    details: None,
+                    latency: None,
+                    cached: None,
+                }),
+                Err(e) => return Err(e),
+            }
+        }
+
+        apply_latency_budgets(&mut results, options);
+        apply_severity_overrides(&mut results, options);
+
+        #[cfg(feature = "serde")]
+        if let Some(alerter) = &options.alerts {
+            match alerter.notify(&results).await {
+                Ok(_) => {}
+                Err(e) if options.continue_on_error => results.push(NetworkCheckResult {
+                    check_type: "alert".to_string(),
+                    target: String::new(),
+                    status: HealthStatus::Error,
+                    message: e.to_string(),
+                    severity: CheckSeverity::Warning,
+                    details: None,
+                    latency: None,
+                    cached: None,
+                }),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(results)
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Built-in checks
+// -----------------------------------------------------------------------------
+
+/// TCP (or UDP for `quic` and `udp`) reachability of every `listen`
+/// address.
+struct PortCheck;
+
+impl Check for PortCheck {
+    fn name(&self) -> &'static str {
+        "port"
+    }
+
+    fn description(&self) -> &'static str {
+        "Connect to every listen address and port"
+    }
+
+    fn applies_to(&self, config: &Config) -> bool {
+        !config.find_directives_recursive("listen").is_empty()
+    }
+
+    fn is_enabled(&self, options: &NetworkCheckOptions) -> bool {
+        options.check_ports
+    }
+
+    fn run<'a>(
+        &'a self,
+        config: &'a Config,
+        options: &'a NetworkCheckOptions,
+    ) -> BoxFuture<'a, Result<Vec<NetworkCheckResult>>> {
+        Box::pin(super::check_all_ports(config, options))
+    }
+}
+
+/// DNS resolution of every concrete `server_name`.
+struct DnsCheck;
+
+impl Check for DnsCheck {
+    fn name(&self) -> &'static str {
+        "dns"
+    }
+
+    fn description(&self) -> &'static str {
+        "Resolve every server_name"
+    }
+
+    fn applies_to(&self, config: &Config) -> bool {
+        !config.find_directives_recursive("server_name").is_empty()
+    }
+
+    fn is_enabled(&self, options: &NetworkCheckOptions) -> bool {
+        options.check_dns
+    }
+
+    fn run<'a>(
+        &'a self,
+        config: &'a Config,
+        options: &'a NetworkCheckOptions,
+    ) -> BoxFuture<'a, Result<Vec<NetworkCheckResult>>> {
+        Box::pin(super::check_all_dns(config, options))
+    }
+}
+
+/// Reachability of upstream backends.
+struct UpstreamCheck;
+
+impl Check for UpstreamCheck {
+    fn name(&self) -> &'static str {
+        "upstream"
+    }
+
+    fn description(&self) -> &'static str {
+        "Connect to every upstream backend"
+    }
+
+    fn applies_to(&self, config: &Config) -> bool {
+        !config.find_directives_recursive("upstream").is_empty()
+    }
+
+    fn is_enabled(&self, options: &NetworkCheckOptions) -> bool {
+        options.check_upstreams
+    }
+
+    fn run<'a>(
+        &'a self,
+        config: &'a Config,
+        _options: &'a NetworkCheckOptions,
+    ) -> BoxFuture<'a, Result<Vec<NetworkCheckResult>>> {
+        Box::pin(super::check_all_upstreams(config))
+    }
+}
+
+/// Validity of referenced SSL certificates.
+struct SslCheck;
+
+impl Check for SslCheck {
+    fn name(&self) -> &'static str {
+        "ssl"
+    }
+
+    fn description(&self) -> &'static str {
+        "Validate referenced SSL certificates"
+    }
+
+    fn applies_to(&self, config: &Config) -> bool {
+        !config
+            .find_directives_recursive("ssl_certificate")
+            .is_empty()
+    }
+
+    fn is_enabled(&self, options: &NetworkCheckOptions) -> bool {
+        options.check_ssl
+    }
+
+    fn run<'a>(
+        &'a self,
+        config: &'a Config,
+        _options: &'a NetworkCheckOptions,
+    ) -> BoxFuture<'a, Result<Vec<NetworkCheckResult>>> {
+        Box::pin(super::check_all_ssl(config))
+    }
+}
+
+/// Live `Host` header routing of every listener and `server_name`.
+struct HostRoutingCheck;
+
+impl Check for HostRoutingCheck {
+    fn name(&self) -> &'static str {
+        "host_routing"
+    }
+
+    fn description(&self) -> &'static str {
+        "Request each server_name on each listener and confirm the answering server block"
+    }
+
+    fn applies_to(&self, config: &Config) -> bool {
+        !config.find_directives_recursive("server_name").is_empty()
+    }
+
+    fn is_enabled(&self, options: &NetworkCheckOptions) -> bool {
+        options.check_host_routing
+    }
+
+    fn run<'a>(
+        &'a self,
+        config: &'a Config,
+        options: &'a NetworkCheckOptions,
+    ) -> BoxFuture<'a, Result<Vec<NetworkCheckResult>>> {
+        Box::pin(super::vhost::check_host_routing(config, options))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    struct FixedCheck(&'static str);
+
+    impl Check for FixedCheck {
+        fn name(&self) -> &'static str {
+            self.0
+        }
+
+        fn description(&self) -> &'static str {
+            "Always healthy"
+        }
+
+        fn run<'a>(
+            &'a self,
+            _config: &'a Config,
+            _options: &'a NetworkCheckOptions,
+        ) -> BoxFuture<'a, Result<Vec<NetworkCheckResult>>> {
+            Box::pin(async move {
+                Ok(vec![NetworkCheckResult {
+                    check_type: self.0.to_string(),
+                    target: "test".to_string(),
+                    status: HealthStatus::Healthy,
+                    message: "OK".to_string(),
+                    severity: CheckSeverity::Info,
+                    details: None,
+                    latency: None,
+                    cached: None,
+                }])
+            })
+        }
+    }
+
+    struct FailingCheck;
+
+    impl Check for FailingCheck {
+        fn name(&self) -> &'static str {
+            "failing"
+        }
+
+        fn description(&self) -> &'static str {
+            "Always fails"
+        }
+
+        fn run<'a>(
+            &'a self,
+            _config: &'a Config,
+            _options: &'a NetworkCheckOptions,
+        ) -> BoxFuture<'a, Result<Vec<NetworkCheckResult>>> {
+            Box::pin(async { Err(crate::Error::Network("boom".to_string())) })
+        }
+    }
+
+    #[test]
+    fn test_builtin_names() {
+        let registry = CheckRegistry::with_builtin_checks();
+        assert_eq!(
+            registry.names(),
+            vec!["port", "dns", "upstream", "ssl", "host_routing"]
+        );
+    }
+
+    #[test]
+    fn test_only_and_skip() {
+        let registry = CheckRegistry::with_builtin_checks().only(["dns", "ssl"]);
+        assert_eq!(registry.names(), vec!["dns", "ssl"]);
+
+        let registry = CheckRegistry::with_builtin_checks().skip(["dns"]);
+        assert!(!registry.contains("dns"));
+        assert_eq!(registry.len(), 4);
+    }
+
+    #[test]
+    fn test_register_replaces_same_name() {
+        let registry = CheckRegistry::with_builtin_checks().with_check(FixedCheck("dns"));
+        assert_eq!(registry.len(), 5);
+        assert_eq!(registry.get("dns").unwrap().description(), "Always healthy");
+    }
+
+    #[test]
+    fn test_applicability() {
+        let config = parse("server { listen 80; }").unwrap();
+        let registry = CheckRegistry::with_builtin_checks();
+
+        assert!(registry.get("port").unwrap().applies_to(&config));
+        assert!(!registry.get("dns").unwrap().applies_to(&config));
+        assert!(!registry.get("ssl").unwrap().applies_to(&config));
+    }
+
+    #[tokio::test]
+    async fn test_run_custom_check() {
+        let config = Config::new();
+        let registry = CheckRegistry::new().with_check(FixedCheck("custom"));
+
+        let results = registry
+            .run(&config, &NetworkCheckOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].check_type, "custom");
+    }
+
+    #[tokio::test]
+    async fn test_run_continue_on_error() {
+        let config = Config::new();
+        let registry = CheckRegistry::new()
+            .with_check(FailingCheck)
+            .with_check(FixedCheck("custom"));
+
+        let results = registry
+            .run(&config, &NetworkCheckOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].status, HealthStatus::Error);
+
+        let options = NetworkCheckOptions {
+            continue_on_error: false,
+            ..Default::default()
+        };
+        assert!(registry.run(&config, &options).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_run_applies_severity_overrides() {
+        let config = Config::new();
+        let registry = CheckRegistry::new().with_check(FailingCheck);
+        let options = NetworkCheckOptions {
+            severity_overrides: vec!["failing=info".parse().unwrap()],
+            ..Default::default()
+        };
+
+        let results = registry.run(&config, &options).await.unwrap();
+        assert_eq!(results[0].severity, CheckSeverity::Info);
+    }
+}
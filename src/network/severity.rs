@@ -0,0 +1,240 @@
+//! Severity remapping and exit codes for network check results
+//!
+//! [`SeverityOverride`] rules from [`NetworkCheckOptions`] are applied to
+//! every result a [`CheckRegistry`](super::CheckRegistry) produces, and
+//! [`exit_code`] turns the remapped results into a process exit status for
+//! CI pipelines.
+
+use super::types::{CheckSeverity, HealthStatus, NetworkCheckOptions, SeverityOverride};
+use super::NetworkCheckResult;
+
+impl SeverityOverride {
+    /// Whether the rule applies to this result.
+    ///
+    /// Only problem results (degraded, unhealthy, error) are remapped; a
+    /// healthy result stays informational whatever the rule says.
+    #[must_use]
+    pub fn matches(&self, result: &NetworkCheckResult) -> bool {
+        if matches!(
+            result.status,
+            HealthStatus::Healthy | HealthStatus::NotApplicable
+        ) {
+            return false;
+        }
+
+        if self.check_type != "*" && self.check_type != result.check_type {
+            return false;
+        }
+
+        self.target
+            .as_deref()
+            .map_or(true, |pattern| wildcard_match(pattern, &result.target))
+    }
+}
+
+impl std::str::FromStr for SeverityOverride {
+    type Err = String;
+
+    /// Parses `<check_type>[:<target>]=<severity>`.
+    ///
+    /// # Examples
+    ///
+    /// - `dns=info`
+    /// - `dns:*.internal=info`
+    /// - `port:127.0.0.1:*=critical`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error message if the `=` separator, the check type or the
+    /// severity is missing or invalid.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (selector, severity) = s
+            .rsplit_once('=')
+            .ok_or_else(|| format!("Invalid severity override '{s}': expected CHECK=SEVERITY"))?;
+        let severity: CheckSeverity = severity.trim().parse()?;
+
+        let (check_type, target) = match selector.split_once(':') {
+            Some((check_type, target)) => (check_type, Some(target)),
+            None => (selector, None),
+        };
+
+        let check_type = check_type.trim();
+        if check_type.is_empty() {
+            return Err(format!(
+                "Invalid severity override '{s}': missing check type"
+            ));
+        }
+
+        let mut rule = Self::new(check_type, severity);
+        if let Some(target) = target {
+            rule = rule.with_target(target.trim());
+        }
+        Ok(rule)
+    }
+}
+
+/// Apply the severity overrides from `options` to results in place.
+///
+/// Rules are applied in order, so the last matching rule wins.
+pub fn apply_severity_overrides(results: &mut [NetworkCheckResult], options: &NetworkCheckOptions) {
+    for result in results {
+        if let Some(rule) = options
+            .severity_overrides
+            .iter()
+            .rev()
+            .find(|rule| rule.matches(result))
+        {
+            result.severity = rule.severity;
+        }
+    }
+}
+
+/// Process exit code for a set of results.
+///
+/// Problem results (degraded, unhealthy, error) with a severity at or above
+/// `fail_on` count as failures. The exit code reflects the highest failing
+/// severity:
+///
+/// | Highest failure | Exit code |
+/// |-----------------|-----------|
+/// | none            | 0         |
+/// | info / warning  | 1         |
+/// | error           | 2         |
+/// | critical        | 3         |
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::network::{exit_code, CheckSeverity};
+///
+/// assert_eq!(exit_code(&[], CheckSeverity::Warning), 0);
+/// ```
+#[must_use]
+pub fn exit_code(results: &[NetworkCheckResult], fail_on: CheckSeverity) -> i32 {
+    let worst = results
+        .iter()
+        .filter(|r| {
+            !matches!(
+                r.status,
+                HealthStatus::Healthy | HealthStatus::NotApplicable
+            ) && r.severity >= fail_on
+        })
+        .map(|r| r.severity)
+        .max();
+
+    match worst {
+        None => 0,
+        Some(CheckSeverity::Info | CheckSeverity::Warning) => 1,
+        Some(CheckSeverity::Error) => 2,
+        Some(CheckSeverity::Critical) => 3,
+    }
+}
+
+/// Match `text` against a pattern where `*` matches any run of characters.
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let Some((prefix, rest)) = pattern.split_once('*') else {
+        return pattern.eq_ignore_ascii_case(text);
+    };
+
+    if !text.is_char_boundary(prefix.len()) || !text[..prefix.len()].eq_ignore_ascii_case(prefix) {
+        return false;
+    }
+
+    let text = &text[prefix.len()..];
+    (0..=text.len())
+        .filter(|&i| text.is_char_boundary(i))
+        .any(|i| wildcard_match(rest, &text[i..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(check_type: &str, target: &str, status: HealthStatus) -> NetworkCheckResult {
+        NetworkCheckResult {
+            check_type: check_type.to_string(),
+            target: target.to_string(),
+            status,
+            message: String::new(),
+            severity: CheckSeverity::Error,
+            details: None,
+            latency: None,
+            cached: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_override() {
+        let rule: SeverityOverride = "dns=info".parse().unwrap();
+        assert_eq!(rule, SeverityOverride::new("dns", CheckSeverity::Info));
+
+        let rule: SeverityOverride = "port:127.0.0.1:*=critical".parse().unwrap();
+        assert_eq!(rule.check_type, "port");
+        assert_eq!(rule.target.as_deref(), Some("127.0.0.1:*"));
+        assert_eq!(rule.severity, CheckSeverity::Critical);
+
+        assert!("dns".parse::<SeverityOverride>().is_err());
+        assert!("=info".parse::<SeverityOverride>().is_err());
+        assert!("dns=loud".parse::<SeverityOverride>().is_err());
+    }
+
+    #[test]
+    fn test_override_matching() {
+        let rule = SeverityOverride::new("dns", CheckSeverity::Info).with_target("*.internal");
+
+        assert!(rule.matches(&result("dns", "db.internal", HealthStatus::Unhealthy)));
+        assert!(!rule.matches(&result("dns", "example.com", HealthStatus::Unhealthy)));
+        assert!(!rule.matches(&result("port", "db.internal", HealthStatus::Unhealthy)));
+        assert!(!rule.matches(&result("dns", "db.internal", HealthStatus::Healthy)));
+
+        let any = SeverityOverride::new("*", CheckSeverity::Warning);
+        assert!(any.matches(&result("ssl", "cert.pem", HealthStatus::Error)));
+    }
+
+    #[test]
+    fn test_apply_last_rule_wins() {
+        let options = NetworkCheckOptions {
+            severity_overrides: vec![
+                SeverityOverride::new("dns", CheckSeverity::Info),
+                SeverityOverride::new("dns", CheckSeverity::Critical).with_target("api.*"),
+            ],
+            ..Default::default()
+        };
+
+        let mut results = vec![
+            result("dns", "db.internal", HealthStatus::Unhealthy),
+            result("dns", "api.example.com", HealthStatus::Unhealthy),
+            result("port", "127.0.0.1:80", HealthStatus::Unhealthy),
+        ];
+        apply_severity_overrides(&mut results, &options);
+
+        assert_eq!(results[0].severity, CheckSeverity::Info);
+        assert_eq!(results[1].severity, CheckSeverity::Critical);
+        assert_eq!(results[2].severity, CheckSeverity::Error);
+    }
+
+    #[test]
+    fn test_exit_code_thresholds() {
+        let mut results = vec![result("dns", "a", HealthStatus::Degraded)];
+        results[0].severity = CheckSeverity::Warning;
+
+        assert_eq!(exit_code(&results, CheckSeverity::Error), 0);
+        assert_eq!(exit_code(&results, CheckSeverity::Warning), 1);
+
+        results.push(result("port", "b", HealthStatus::Unhealthy));
+        assert_eq!(exit_code(&results, CheckSeverity::Warning), 2);
+
+        // Healthy results never fail, whatever their severity
+        let healthy = vec![result("port", "c", HealthStatus::Healthy)];
+        assert_eq!(exit_code(&healthy, CheckSeverity::Info), 0);
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("*.internal", "db.internal"));
+        assert!(wildcard_match("api.*.com", "api.example.com"));
+        assert!(wildcard_match("*", ""));
+        assert!(!wildcard_match("*.internal", "internal"));
+        assert!(wildcard_match("Example.COM", "example.com"));
+    }
+}
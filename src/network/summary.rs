@@ -0,0 +1,309 @@
+//! Aggregation of network check results
+//!
+//! [`NetworkCheckSummary`] condenses a slice of [`NetworkCheckResult`]s into
+//! counts per status and severity, the worst outcome, the total time spent,
+//! and per-check-type and per-target breakdowns, so callers (CLI, CI
+//! gates, dashboards) do not each reimplement the same bookkeeping.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use nginx_discovery::{parse, network::{check_all, NetworkCheckOptions, NetworkCheckSummary}};
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let config = parse("server { listen 80; server_name example.com; }")?;
+//!     let results = check_all(&config, NetworkCheckOptions::default()).await?;
+//!
+//!     let summary = NetworkCheckSummary::from(results.as_slice());
+//!     println!("{summary}");
+//!     Ok(())
+//! }
+//! ```
+
+use super::types::{CheckSeverity, HealthStatus};
+use super::NetworkCheckResult;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::Duration;
+
+/// Number of results per [`HealthStatus`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StatusCounts {
+    /// Results with [`HealthStatus::Healthy`].
+    pub healthy: usize,
+
+    /// Results with [`HealthStatus::Degraded`].
+    pub degraded: usize,
+
+    /// Results with [`HealthStatus::Unhealthy`].
+    pub unhealthy: usize,
+
+    /// Results with [`HealthStatus::Error`].
+    pub error: usize,
+
+    /// Results with [`HealthStatus::NotApplicable`].
+    pub not_applicable: usize,
+}
+
+impl StatusCounts {
+    /// Count one result with the given status.
+    pub fn add(&mut self, status: HealthStatus) {
+        match status {
+            HealthStatus::Healthy => self.healthy += 1,
+            HealthStatus::Degraded => self.degraded += 1,
+            HealthStatus::Unhealthy => self.unhealthy += 1,
+            HealthStatus::Error => self.error += 1,
+            HealthStatus::NotApplicable => self.not_applicable += 1,
+        }
+    }
+
+    /// Total number of results counted.
+    #[must_use]
+    pub fn total(&self) -> usize {
+        self.healthy + self.degraded + self.unhealthy + self.error + self.not_applicable
+    }
+
+    /// Number of results that are neither healthy nor skipped.
+    #[must_use]
+    pub fn problems(&self) -> usize {
+        self.degraded + self.unhealthy + self.error
+    }
+}
+
+impl fmt::Display for StatusCounts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} healthy, {} degraded, {} unhealthy, {} errors",
+            self.healthy, self.degraded, self.unhealthy, self.error
+        )?;
+        if self.not_applicable > 0 {
+            write!(f, ", {} skipped", self.not_applicable)?;
+        }
+        Ok(())
+    }
+}
+
+/// Number of results per [`CheckSeverity`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SeverityCounts {
+    /// Results with [`CheckSeverity::Info`].
+    pub info: usize,
+
+    /// Results with [`CheckSeverity::Warning`].
+    pub warning: usize,
+
+    /// Results with [`CheckSeverity::Error`].
+    pub error: usize,
+
+    /// Results with [`CheckSeverity::Critical`].
+    pub critical: usize,
+}
+
+impl SeverityCounts {
+    /// Count one result with the given severity.
+    pub fn add(&mut self, severity: CheckSeverity) {
+        match severity {
+            CheckSeverity::Info => self.info += 1,
+            CheckSeverity::Warning => self.warning += 1,
+            CheckSeverity::Error => self.error += 1,
+            CheckSeverity::Critical => self.critical += 1,
+        }
+    }
+}
+
+/// Aggregate view of a set of network check results.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NetworkCheckSummary {
+    /// Total number of results.
+    pub total: usize,
+
+    /// Counts per health status.
+    pub by_status: StatusCounts,
+
+    /// Counts per severity.
+    pub by_severity: SeverityCounts,
+
+    /// Worst health status seen, `None` when there are no results.
+    ///
+    /// From best to worst: not applicable, healthy, degraded, unhealthy,
+    /// error.
+    pub worst_status: Option<HealthStatus>,
+
+    /// Highest severity seen, `None` when there are no results.
+    pub max_severity: Option<CheckSeverity>,
+
+    /// Sum of the measured latencies of all results.
+    pub total_duration: Duration,
+
+    /// Status counts per check type, sorted by check type.
+    pub by_check_type: BTreeMap<String, StatusCounts>,
+
+    /// Status counts per target, sorted by target.
+    pub by_target: BTreeMap<String, StatusCounts>,
+}
+
+impl NetworkCheckSummary {
+    /// Whether every result is healthy or not applicable.
+    #[must_use]
+    pub fn is_healthy(&self) -> bool {
+        self.by_status.problems() == 0
+    }
+}
+
+impl From<&[NetworkCheckResult]> for NetworkCheckSummary {
+    fn from(results: &[NetworkCheckResult]) -> Self {
+        let mut summary = Self {
+            total: results.len(),
+            ..Self::default()
+        };
+
+        for result in results {
+            summary.by_status.add(result.status);
+            summary.by_severity.add(result.severity);
+
+            if summary.worst_status.map_or(true, |worst| {
+                status_rank(result.status) > status_rank(worst)
+            }) {
+                summary.worst_status = Some(result.status);
+            }
+            summary.max_severity = summary.max_severity.max(Some(result.severity));
+
+            if let Some(latency) = result.latency {
+                summary.total_duration += latency;
+            }
+
+            summary
+                .by_check_type
+                .entry(result.check_type.clone())
+                .or_default()
+                .add(result.status);
+            summary
+                .by_target
+                .entry(result.target.clone())
+                .or_default()
+                .add(result.status);
+        }
+
+        summary
+    }
+}
+
+impl From<&Vec<NetworkCheckResult>> for NetworkCheckSummary {
+    fn from(results: &Vec<NetworkCheckResult>) -> Self {
+        Self::from(results.as_slice())
+    }
+}
+
+impl fmt::Display for NetworkCheckSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} checks: {}", self.total, self.by_status)?;
+        if let Some(worst) = self.worst_status {
+            write!(f, " (worst: {worst}, {:.2?})", self.total_duration)?;
+        }
+
+        for (check_type, counts) in &self.by_check_type {
+            write!(f, "\n  {check_type}: {counts}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Ordering used for [`NetworkCheckSummary::worst_status`].
+fn status_rank(status: HealthStatus) -> u8 {
+    match status {
+        HealthStatus::NotApplicable => 0,
+        HealthStatus::Healthy => 1,
+        HealthStatus::Degraded => 2,
+        HealthStatus::Unhealthy => 3,
+        HealthStatus::Error => 4,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(check_type: &str, target: &str, status: HealthStatus, ms: u64) -> NetworkCheckResult {
+        NetworkCheckResult {
+            check_type: check_type.to_string(),
+            target: target.to_string(),
+            status,
+            message: String::new(),
+            severity: match status {
+                HealthStatus::Healthy | HealthStatus::NotApplicable => CheckSeverity::Info,
+                HealthStatus::Degraded => CheckSeverity::Warning,
+                HealthStatus::Unhealthy | HealthStatus::Error => CheckSeverity::Error,
+            },
+            details: None,
+            latency: Some(Duration::from_millis(ms)),
+            cached: None,
+        }
+    }
+
+    #[test]
+    fn test_summary_counts() {
+        let results = vec![
+            result("port", "127.0.0.1:80", HealthStatus::Healthy, 10),
+            result("port", "127.0.0.1:443", HealthStatus::Unhealthy, 20),
+            result("dns", "example.com", HealthStatus::Degraded, 30),
+            result("dns", "example.org", HealthStatus::Healthy, 40),
+        ];
+
+        let summary = NetworkCheckSummary::from(&results);
+
+        assert_eq!(summary.total, 4);
+        assert_eq!(summary.by_status.healthy, 2);
+        assert_eq!(summary.by_status.problems(), 2);
+        assert_eq!(summary.by_severity.error, 1);
+        assert_eq!(summary.worst_status, Some(HealthStatus::Unhealthy));
+        assert_eq!(summary.max_severity, Some(CheckSeverity::Error));
+        assert_eq!(summary.total_duration, Duration::from_millis(100));
+        assert_eq!(summary.by_check_type["port"].total(), 2);
+        assert_eq!(summary.by_target["example.com"].degraded, 1);
+        assert!(!summary.is_healthy());
+    }
+
+    #[test]
+    fn test_summary_empty() {
+        let summary = NetworkCheckSummary::from(&[][..]);
+
+        assert_eq!(summary.total, 0);
+        assert_eq!(summary.worst_status, None);
+        assert!(summary.is_healthy());
+        assert_eq!(
+            summary.to_string(),
+            "0 checks: 0 healthy, 0 degraded, 0 unhealthy, 0 errors"
+        );
+    }
+
+    #[test]
+    fn test_worst_status_ignores_not_applicable() {
+        let results = vec![
+            result("ssl", "a", HealthStatus::NotApplicable, 0),
+            result("ssl", "b", HealthStatus::Healthy, 0),
+        ];
+
+        let summary = NetworkCheckSummary::from(&results);
+        assert_eq!(summary.worst_status, Some(HealthStatus::Healthy));
+        assert!(summary.is_healthy());
+    }
+
+    #[test]
+    fn test_summary_display() {
+        let results = vec![
+            result("port", "a", HealthStatus::Healthy, 5),
+            result("dns", "b", HealthStatus::Error, 5),
+        ];
+
+        let text = NetworkCheckSummary::from(&results).to_string();
+        assert!(text.starts_with("2 checks: 1 healthy, 0 degraded, 0 unhealthy, 1 errors"));
+        assert!(text.contains("worst: ERROR"));
+        assert!(text.contains("\n  dns: 0 healthy"));
+    }
+}
@@ -21,6 +21,12 @@
 //! This mirrors how large frameworks (e.g. Kubernetes, Django system checks)
 //! separate **evaluation** from **representation**.
 
+use super::proxy::Proxy;
+#[cfg(feature = "serde")]
+use super::{alert::Alerter, cache::ResultCache};
+use std::net::{IpAddr, SocketAddr};
+#[cfg(feature = "serde")]
+use std::sync::Arc;
 use std::time::Duration;
 
 /* ============================================================
@@ -94,6 +100,29 @@ impl std::fmt::Display for CheckSeverity {
     }
 }
 
+impl std::str::FromStr for CheckSeverity {
+    type Err = String;
+
+    /// Parses a severity name (`info`, `warning`, `error`, `critical`).
+    ///
+    /// Matching is case-insensitive and accepts `warn` for `warning`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error message if the name is not a known severity.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "info" => Ok(Self::Info),
+            "warning" | "warn" => Ok(Self::Warning),
+            "error" => Ok(Self::Error),
+            "critical" => Ok(Self::Critical),
+            _ => Err(format!(
+                "Unknown severity: {s}. Expected one of: info, warning, error, critical"
+            )),
+        }
+    }
+}
+
 /* ============================================================
  * Generic health check
  * ============================================================
@@ -218,6 +247,9 @@ pub struct SslCheckResult {
 
     /// Certificate subject (distinguished name).
     pub subject: Option<String>,
+
+    /// TLS handshake time, for live endpoint checks.
+    pub latency: Option<Duration>,
 }
 
 /* ============================================================
@@ -309,6 +341,15 @@ pub struct NetworkCheckOptions {
     /// Whether to perform DNS resolution.
     pub check_dns: bool,
 
+    /// Whether to probe each listener with every `server_name` as the
+    /// `Host` header and confirm the expected server block answers.
+    ///
+    /// Disabled by default because it sends live HTTP requests.
+    pub check_host_routing: bool,
+
+    /// Settings for the host routing check.
+    pub host_routing: HostRoutingOptions,
+
     /// Per-check timeout.
     pub timeout: Duration,
 
@@ -320,6 +361,47 @@ pub struct NetworkCheckOptions {
 
     /// Continue executing checks after failures.
     pub continue_on_error: bool,
+
+    /// Rules that raise or lower the severity of matching results.
+    ///
+    /// Rules are applied in order and the last matching rule wins.
+    pub severity_overrides: Vec<SeverityOverride>,
+
+    /// Lowest severity of a problem result that counts as a failure for
+    /// [`exit_code`](super::exit_code).
+    pub fail_on: CheckSeverity,
+
+    /// Cache consulted before, and filled after, each DNS, SSL and port
+    /// check.
+    ///
+    /// Disabled by default. The cache is shared by clones of the options;
+    /// call [`ResultCache::save`] to persist it. Requires the `serde`
+    /// feature.
+    #[cfg(feature = "serde")]
+    pub cache: Option<Arc<ResultCache>>,
+
+    /// How long cached results stay fresh, per check type.
+    pub cache_ttls: CacheTtls,
+
+    /// Name servers used by the DNS check.
+    pub dns: DnsResolverOptions,
+
+    /// Latency above which a successful result is reported as degraded.
+    pub latency_budgets: LatencyBudgets,
+
+    /// Proxy that port, upstream and SSL probes connect through.
+    ///
+    /// UDP probes cannot be proxied and report
+    /// [`HealthStatus::NotApplicable`] when this is set.
+    pub proxy: Option<Proxy>,
+
+    /// Alerter notified with the results of every
+    /// [`CheckRegistry::run`](super::CheckRegistry::run).
+    ///
+    /// Disabled by default. Shared by clones of the options, so batch runs
+    /// report through one alerter. Requires the `serde` feature.
+    #[cfg(feature = "serde")]
+    pub alerts: Option<Arc<Alerter>>,
 }
 
 impl Default for NetworkCheckOptions {
@@ -329,10 +411,22 @@ impl Default for NetworkCheckOptions {
             check_ssl: true,
             check_ports: true,
             check_dns: true,
+            check_host_routing: false,
+            host_routing: HostRoutingOptions::default(),
             timeout: Duration::from_secs(5),
             retries: 3,
             parallel: true,
             continue_on_error: true,
+            severity_overrides: Vec::new(),
+            fail_on: CheckSeverity::Error,
+            #[cfg(feature = "serde")]
+            cache: None,
+            cache_ttls: CacheTtls::default(),
+            dns: DnsResolverOptions::default(),
+            latency_budgets: LatencyBudgets::default(),
+            proxy: None,
+            #[cfg(feature = "serde")]
+            alerts: None,
         }
     }
 }
@@ -387,6 +481,260 @@ impl NetworkCheckOptions {
     }
 }
 
+/// Rewrites the severity of results from one check type.
+///
+/// Lets one noisy class of checks be toned down (or escalated) without
+/// hiding the rest, e.g. treating DNS failures for internal-only names as
+/// informational:
+///
+/// ```
+/// use nginx_discovery::network::{CheckSeverity, SeverityOverride};
+///
+/// let rule = SeverityOverride::new("dns", CheckSeverity::Info).with_target("*.internal");
+/// let parsed: SeverityOverride = "dns:*.internal=info".parse().unwrap();
+/// assert_eq!(rule, parsed);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SeverityOverride {
+    /// Check type the rule applies to (`dns`, `port`, ...), or `*` for all.
+    pub check_type: String,
+
+    /// Optional target pattern; `*` matches any run of characters.
+    pub target: Option<String>,
+
+    /// Severity assigned to matching results.
+    pub severity: CheckSeverity,
+}
+
+impl SeverityOverride {
+    /// Create a rule for every result of `check_type`.
+    #[must_use]
+    pub fn new(check_type: impl Into<String>, severity: CheckSeverity) -> Self {
+        Self {
+            check_type: check_type.into(),
+            target: None,
+            severity,
+        }
+    }
+
+    /// Restrict the rule to targets matching `pattern`.
+    #[must_use]
+    pub fn with_target(mut self, pattern: impl Into<String>) -> Self {
+        self.target = Some(pattern.into());
+        self
+    }
+}
+
+/// Settings for the live `Host` header routing check.
+///
+/// The server block that answered a request is identified by a marker
+/// response header (for example `add_header X-Served-By api.example.com;`
+/// in every server block) or, on TLS listeners, by the certificate nginx
+/// presents for the requested name.
+#[derive(Debug, Clone)]
+pub struct HostRoutingOptions {
+    /// Address to send every request to instead of the listen address.
+    ///
+    /// When `None`, wildcard listeners (`*`, `0.0.0.0`, `[::]`) are probed
+    /// over loopback.
+    pub target: Option<IpAddr>,
+
+    /// Response header naming the server block that answered.
+    ///
+    /// The expected value comes from an `add_header` directive with the
+    /// same name in the server block, falling back to its first
+    /// `server_name`.
+    pub marker_header: Option<String>,
+
+    /// Request path to probe.
+    pub path: String,
+}
+
+impl Default for HostRoutingOptions {
+    fn default() -> Self {
+        Self {
+            target: None,
+            marker_header: None,
+            path: "/".to_string(),
+        }
+    }
+}
+
+impl HostRoutingOptions {
+    /// Send all requests to `target` instead of the listen address.
+    #[must_use]
+    pub fn with_target(mut self, target: IpAddr) -> Self {
+        self.target = Some(target);
+        self
+    }
+
+    /// Identify the answering server block by this response header.
+    #[must_use]
+    pub fn with_marker_header(mut self, header: impl Into<String>) -> Self {
+        self.marker_header = Some(header.into());
+        self
+    }
+
+    /// Probe `path` instead of `/`.
+    #[must_use]
+    pub fn with_path(mut self, path: impl Into<String>) -> Self {
+        self.path = path.into();
+        self
+    }
+}
+
+/// Freshness of cached results, per check type.
+///
+/// A `None` TTL turns caching off for that check type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheTtls {
+    /// DNS resolution results.
+    pub dns: Option<Duration>,
+
+    /// SSL certificate results.
+    pub ssl: Option<Duration>,
+
+    /// Port reachability results.
+    pub port: Option<Duration>,
+}
+
+impl Default for CacheTtls {
+    fn default() -> Self {
+        Self {
+            dns: Some(Duration::from_secs(300)),
+            ssl: Some(Duration::from_secs(3600)),
+            port: Some(Duration::from_secs(60)),
+        }
+    }
+}
+
+impl CacheTtls {
+    /// Use the same TTL for every check type.
+    #[must_use]
+    pub fn uniform(ttl: Duration) -> Self {
+        Self {
+            dns: Some(ttl),
+            ssl: Some(ttl),
+            port: Some(ttl),
+        }
+    }
+
+    /// TTL for a `check_type`, `None` when its results are not cached.
+    #[must_use]
+    pub fn for_check(&self, check_type: &str) -> Option<Duration> {
+        match check_type {
+            "dns" => self.dns,
+            "ssl" => self.ssl,
+            "port" => self.port,
+            _ => None,
+        }
+    }
+}
+
+/// Latency thresholds for successful results, per kind of operation.
+///
+/// A healthy result slower than its budget becomes
+/// [`HealthStatus::Degraded`] with [`CheckSeverity::Warning`]. A `None`
+/// budget is not enforced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LatencyBudgets {
+    /// DNS resolution (`dns` results).
+    pub dns: Option<Duration>,
+
+    /// TCP connect (`port` and `upstream` results).
+    pub tcp_connect: Option<Duration>,
+
+    /// TLS handshake (`ssl` results).
+    pub tls_handshake: Option<Duration>,
+}
+
+impl Default for LatencyBudgets {
+    fn default() -> Self {
+        Self {
+            dns: Some(Duration::from_millis(100)),
+            tcp_connect: Some(Duration::from_millis(50)),
+            tls_handshake: Some(Duration::from_millis(300)),
+        }
+    }
+}
+
+impl LatencyBudgets {
+    /// No budgets enforced.
+    #[must_use]
+    pub fn none() -> Self {
+        Self {
+            dns: None,
+            tcp_connect: None,
+            tls_handshake: None,
+        }
+    }
+
+    /// Budget for results of `check_type`, `None` when not enforced.
+    #[must_use]
+    pub fn for_check(&self, check_type: &str) -> Option<Duration> {
+        match check_type {
+            "dns" => self.dns,
+            "port" | "upstream" => self.tcp_connect,
+            "ssl" => self.tls_handshake,
+            _ => None,
+        }
+    }
+
+    /// Set the budget named `dns`, `tcp` or `tls`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error message for any other name.
+    pub fn set(&mut self, name: &str, budget: Option<Duration>) -> Result<(), String> {
+        match name {
+            "dns" => self.dns = budget,
+            "tcp" => self.tcp_connect = budget,
+            "tls" => self.tls_handshake = budget,
+            _ => {
+                return Err(format!(
+                    "Unknown latency budget '{name}' (expected dns, tcp or tls)"
+                ))
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Name servers that DNS checks query.
+///
+/// By default the system resolver answers, which may differ from what
+/// nginx sees when it is configured with its own `resolver`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DnsResolverOptions {
+    /// Name servers to query; empty uses the system resolver.
+    pub nameservers: Vec<SocketAddr>,
+
+    /// Query the name servers of the configuration's `resolver` directive
+    /// (the `http`-level one when there are several), falling back to
+    /// `nameservers` when it has none.
+    pub use_config_resolver: bool,
+}
+
+impl DnsResolverOptions {
+    /// Query `nameserver` (port 53 unless given) instead of the system
+    /// resolver.
+    #[must_use]
+    pub fn with_nameserver(mut self, nameserver: SocketAddr) -> Self {
+        self.nameservers.push(nameserver);
+        self
+    }
+
+    /// Follow the configuration's `resolver` directive.
+    #[must_use]
+    pub fn from_config_resolver() -> Self {
+        Self {
+            nameservers: Vec::new(),
+            use_config_resolver: true,
+        }
+    }
+}
+
 /* ============================================================
  * DNS validation
  * ============================================================
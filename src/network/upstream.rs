@@ -4,6 +4,7 @@
 //! (TCP and HTTP), aggregating results across backend groups, and calculating
 //! overall upstream health percentages.
 
+use super::proxy::{dial, Proxy};
 use super::types::{CheckSeverity, HealthCheckResult, HealthStatus};
 use crate::{Error, Result};
 use std::time::{Duration, Instant};
@@ -35,20 +36,33 @@ pub struct UpstreamBackend {
 ///
 /// Returns an error if the `network` feature is disabled.
 pub async fn check_upstream_backend(backend: &UpstreamBackend) -> Result<HealthCheckResult> {
+    check_upstream_backend_via(backend, None).await
+}
+
+/// Perform a TCP health check against a single upstream backend,
+/// connecting through `proxy`.
+///
+/// # Errors
+///
+/// Returns an error if the `network` feature is disabled.
+pub async fn check_upstream_backend_via(
+    backend: &UpstreamBackend,
+    proxy: Option<&Proxy>,
+) -> Result<HealthCheckResult> {
     #[cfg(feature = "network")]
     {
-        use tokio::net::TcpStream;
-        use tokio::time::timeout;
+        use std::io::ErrorKind;
 
         let target = format!("{host}:{port}", host = backend.host, port = backend.port);
         let start = Instant::now();
 
-        let connect_result = timeout(Duration::from_secs(5), TcpStream::connect(&target)).await;
+        let connect_result = dial(&backend.host, backend.port, proxy, Duration::from_secs(5)).await;
 
         let latency = start.elapsed();
+        let via = proxy.map(|p| format!(" via {p}")).unwrap_or_default();
 
         match connect_result {
-            Ok(Ok(_)) => {
+            Ok(_) => {
                 let (status, severity) = if latency > Duration::from_secs(2) {
                     (HealthStatus::Degraded, CheckSeverity::Warning)
                 } else {
@@ -59,22 +73,22 @@ pub async fn check_upstream_backend(backend: &UpstreamBackend) -> Result<HealthC
                     status,
                     message: format!("Backend {target} is reachable"),
                     severity,
-                    details: Some(format!("Response time: {latency:?}")),
+                    details: Some(format!("Response time{via}: {latency:?}")),
                     latency: Some(latency),
                 })
             }
-            Ok(Err(e)) => Ok(HealthCheckResult {
-                status: HealthStatus::Unhealthy,
-                message: format!("Backend {target} is unreachable"),
-                severity: CheckSeverity::Error,
-                details: Some(format!("Connection failed: {e}")),
-                latency: Some(latency),
-            }),
-            Err(_) => Ok(HealthCheckResult {
+            Err(e) if e.kind() == ErrorKind::TimedOut => Ok(HealthCheckResult {
                 status: HealthStatus::Error,
                 message: format!("Backend {target} timed out"),
                 severity: CheckSeverity::Critical,
-                details: Some("Connection timed out after 5 seconds".to_string()),
+                details: Some(format!("Connection{via} timed out after 5 seconds")),
+                latency: Some(latency),
+            }),
+            Err(e) => Ok(HealthCheckResult {
+                status: HealthStatus::Unhealthy,
+                message: format!("Backend {target} is unreachable"),
+                severity: CheckSeverity::Error,
+                details: Some(format!("Connection{via} failed: {e}")),
                 latency: Some(latency),
             }),
         }
@@ -82,6 +96,7 @@ pub async fn check_upstream_backend(backend: &UpstreamBackend) -> Result<HealthC
 
     #[cfg(not(feature = "network"))]
     {
+        let _ = (backend, proxy);
         Err(Error::FeatureNotEnabled("network".to_string()))
     }
 }
@@ -96,9 +111,31 @@ pub async fn check_upstream_backend(backend: &UpstreamBackend) -> Result<HealthC
 pub async fn check_upstream_http(
     backend: &UpstreamBackend,
     health_check_path: &str,
+) -> Result<HealthCheckResult> {
+    check_upstream_http_via(backend, health_check_path, None).await
+}
+
+/// Perform an HTTP health check against an upstream backend, connecting
+/// through `proxy`.
+///
+/// Through a proxy, a plain HTTP/1.1 `GET` is sent over the tunnel.
+///
+/// # Errors
+///
+/// Returns an error if:
+/// - The `network` feature is disabled
+/// - The HTTP client cannot be constructed
+pub async fn check_upstream_http_via(
+    backend: &UpstreamBackend,
+    health_check_path: &str,
+    proxy: Option<&Proxy>,
 ) -> Result<HealthCheckResult> {
     #[cfg(feature = "network")]
     {
+        if let Some(proxy) = proxy {
+            return Ok(http_get_via(backend, health_check_path, proxy).await);
+        }
+
         let url = format!(
             "http://{host}:{port}{path}",
             host = backend.host,
@@ -118,13 +155,7 @@ pub async fn check_upstream_http(
                 let latency = start.elapsed();
                 let status_code = response.status();
 
-                let (status, severity) = if status_code.is_success() {
-                    (HealthStatus::Healthy, CheckSeverity::Info)
-                } else if status_code.is_server_error() {
-                    (HealthStatus::Unhealthy, CheckSeverity::Error)
-                } else {
-                    (HealthStatus::Degraded, CheckSeverity::Warning)
-                };
+                let (status, severity) = classify_status(status_code.as_u16());
 
                 Ok(HealthCheckResult {
                     status,
@@ -149,10 +180,92 @@ pub async fn check_upstream_http(
 
     #[cfg(not(feature = "network"))]
     {
+        let _ = (backend, health_check_path, proxy);
         Err(Error::FeatureNotEnabled("network".to_string()))
     }
 }
 
+/// Health of an HTTP status code
+fn classify_status(code: u16) -> (HealthStatus, CheckSeverity) {
+    match code {
+        200..=299 => (HealthStatus::Healthy, CheckSeverity::Info),
+        500..=599 => (HealthStatus::Unhealthy, CheckSeverity::Error),
+        _ => (HealthStatus::Degraded, CheckSeverity::Warning),
+    }
+}
+
+/// Send a `GET` over a proxy tunnel and read the status line
+#[cfg(feature = "network")]
+async fn http_get_via(
+    backend: &UpstreamBackend,
+    health_check_path: &str,
+    proxy: &Proxy,
+) -> HealthCheckResult {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let start = Instant::now();
+    let host = &backend.host;
+    let request =
+        format!("GET {health_check_path} HTTP/1.1\r\nHost: {host}\r\nConnection: close\r\n\r\n");
+
+    let exchange = async {
+        let mut stream = dial(host, backend.port, Some(proxy), Duration::from_secs(5)).await?;
+        stream.write_all(request.as_bytes()).await?;
+        let mut status_line = String::new();
+        BufReader::new(stream).read_line(&mut status_line).await?;
+        Ok::<_, std::io::Error>(status_line)
+    };
+    let response = tokio::time::timeout(Duration::from_secs(5), exchange).await;
+    let latency = start.elapsed();
+
+    let status_line = match response {
+        Ok(Ok(line)) => line,
+        Ok(Err(e)) => {
+            return HealthCheckResult {
+                status: HealthStatus::Unhealthy,
+                message: format!("HTTP check failed for {host}:{}", backend.port),
+                severity: CheckSeverity::Error,
+                details: Some(format!("Error via {proxy}: {e}")),
+                latency: Some(latency),
+            }
+        }
+        Err(_) => {
+            return HealthCheckResult {
+                status: HealthStatus::Unhealthy,
+                message: format!("HTTP check failed for {host}:{}", backend.port),
+                severity: CheckSeverity::Error,
+                details: Some(format!("Request via {proxy} timed out after 5 seconds")),
+                latency: Some(latency),
+            }
+        }
+    };
+
+    let Some(code) = status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse::<u16>().ok())
+    else {
+        return HealthCheckResult {
+            status: HealthStatus::Unhealthy,
+            message: format!("Backend {host}:{} did not answer HTTP", backend.port),
+            severity: CheckSeverity::Error,
+            details: Some(format!("Response via {proxy}: {}", status_line.trim())),
+            latency: Some(latency),
+        };
+    };
+
+    let (status, severity) = classify_status(code);
+    HealthCheckResult {
+        status,
+        message: format!("Backend returned status {code}"),
+        severity,
+        details: Some(format!(
+            "Response time via {proxy}: {latency:?}, Status: {code}"
+        )),
+        latency: Some(latency),
+    }
+}
+
 /// Perform health checks for all backends in an upstream group.
 ///
 /// # Errors
@@ -214,6 +327,6 @@ mod tests {
     #[test]
     fn test_empty_group_health() {
         let results = vec![];
-        assert_eq!(calculate_group_health(&results), 0.0);
+        assert!(calculate_group_health(&results).abs() < f64::EPSILON);
     }
 }
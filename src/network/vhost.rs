@@ -0,0 +1,445 @@
+//! Live `Host` header routing checks
+//!
+//! Static analysis can say which server block *should* answer for a name,
+//! but a misplaced `default_server` or a `server_name` typo only shows up
+//! when real requests arrive. This module sends one request per listener
+//! and configured `server_name`, using the name as the `Host` header (and
+//! TLS SNI), and confirms nginx answered from the expected server block.
+//!
+//! The answering block is identified by a marker response header (see
+//! [`HostRoutingOptions::marker_header`]) or, on TLS listeners without a
+//! marker, by checking that the presented certificate covers the name.
+//!
+//! Regex names (`~...`), wildcard names (`*.example.com`) and the catch-all
+//! `_` cannot be turned into a concrete `Host` header and are skipped.
+
+use super::types::{HealthCheckResult, HostRoutingOptions, NetworkCheckOptions};
+use super::NetworkCheckResult;
+use crate::ast::{Config, Directive};
+use crate::extract;
+use crate::types::ListenDirective;
+use crate::Result;
+#[cfg(feature = "network")]
+use std::time::Instant;
+
+/// A single request planned by the host routing check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HostProbe {
+    /// Name sent as the `Host` header and TLS SNI.
+    pub host: String,
+
+    /// Address the request is sent to.
+    pub address: String,
+
+    /// Port the request is sent to.
+    pub port: u16,
+
+    /// Whether the listener speaks TLS.
+    pub tls: bool,
+
+    /// Marker header value the expected server block responds with.
+    pub expected_marker: Option<String>,
+}
+
+impl HostProbe {
+    /// The `address:port` pair being probed.
+    #[must_use]
+    pub fn endpoint(&self) -> String {
+        if self.address.contains(':') {
+            format!("[{}]:{}", self.address, self.port)
+        } else {
+            format!("{}:{}", self.address, self.port)
+        }
+    }
+}
+
+/// Plan one probe per listener and concrete `server_name`.
+///
+/// No requests are sent; this is what [`check_host_routing`] will do.
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::{parse, network::{vhost::plan_host_probes, HostRoutingOptions}};
+///
+/// let config = parse("server { listen 8080; server_name a.example b.example; }")?;
+/// let probes = plan_host_probes(&config, &HostRoutingOptions::default());
+///
+/// assert_eq!(probes.len(), 2);
+/// assert_eq!(probes[0].endpoint(), "127.0.0.1:8080");
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+#[must_use]
+pub fn plan_host_probes(config: &Config, options: &HostRoutingOptions) -> Vec<HostProbe> {
+    let mut probes = Vec::new();
+
+    for server in config.find_directives_recursive("server") {
+        let Some(children) = server.children() else {
+            continue;
+        };
+
+        let names: Vec<String> = children
+            .iter()
+            .filter(|d| d.name() == "server_name")
+            .flat_map(Directive::args_as_strings)
+            .collect();
+
+        let expected_marker = options
+            .marker_header
+            .as_ref()
+            .and_then(|header| marker_value(children, header).or_else(|| names.first().cloned()));
+
+        for listen in children.iter().filter(|d| d.name() == "listen") {
+            let Some(listen) = extract::listen(listen) else {
+                continue;
+            };
+            let Some(address) = probe_address(&listen, options) else {
+                continue;
+            };
+
+            for name in &names {
+                let Some(host) = concrete_host(name) else {
+                    continue;
+                };
+
+                probes.push(HostProbe {
+                    host,
+                    address: address.clone(),
+                    port: listen.port,
+                    tls: listen.ssl,
+                    expected_marker: expected_marker.clone(),
+                });
+            }
+        }
+    }
+
+    probes
+}
+
+/// Probe every listener with each configured `server_name` as `Host`.
+///
+/// Each probe produces one result with check type `host_routing`:
+///
+/// - [`HealthStatus::Healthy`](super::HealthStatus::Healthy) when the marker header (or certificate)
+///   confirms the expected server block answered
+/// - [`HealthStatus::Unhealthy`](super::HealthStatus::Unhealthy) when another block answered, which usually
+///   means the request fell through to a `default_server`
+/// - [`HealthStatus::Degraded`](super::HealthStatus::Degraded) when the response could not be attributed
+///   to a block (no marker configured or returned)
+///
+/// # Errors
+///
+/// Returns an error if the `network` feature is disabled. Failed requests
+/// are reported as results, not errors.
+///
+/// # Examples
+///
+/// ```no_run
+/// use nginx_discovery::{parse, network::{check_host_routing, HostRoutingOptions, NetworkCheckOptions}};
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let config = parse("server { listen 80; server_name example.com; }")?;
+///     let options = NetworkCheckOptions {
+///         host_routing: HostRoutingOptions::default().with_marker_header("X-Served-By"),
+///         ..Default::default()
+///     };
+///
+///     for result in check_host_routing(&config, &options).await? {
+///         println!("{} {}", result.status, result.message);
+///     }
+///     Ok(())
+/// }
+/// ```
+pub async fn check_host_routing(
+    config: &Config,
+    options: &NetworkCheckOptions,
+) -> Result<Vec<NetworkCheckResult>> {
+    #[cfg(feature = "network")]
+    {
+        let probes = plan_host_probes(config, &options.host_routing);
+
+        if options.parallel {
+            let futures = probes.iter().map(|probe| run_probe(probe, options));
+            Ok(futures::future::join_all(futures).await)
+        } else {
+            let mut results = Vec::with_capacity(probes.len());
+            for probe in &probes {
+                results.push(run_probe(probe, options).await);
+            }
+            Ok(results)
+        }
+    }
+
+    #[cfg(not(feature = "network"))]
+    {
+        let _ = (config, options);
+        Err(crate::Error::FeatureNotEnabled("network".to_string()))
+    }
+}
+
+/// Send a single probe and classify the response.
+#[cfg(feature = "network")]
+async fn run_probe(probe: &HostProbe, options: &NetworkCheckOptions) -> NetworkCheckResult {
+    let start = Instant::now();
+    let check = match send_probe(probe, options).await {
+        Ok(response) => classify_response(probe, options, &response),
+        Err(check) => check,
+    }
+    .with_latency(start.elapsed());
+
+    NetworkCheckResult {
+        check_type: "host_routing".to_string(),
+        target: format!("{} -> {}", probe.host, probe.endpoint()),
+        status: check.status,
+        message: check.message,
+        severity: check.severity,
+        details: check.details,
+        latency: check.latency,
+        cached: None,
+    }
+}
+
+/// Send the request for a probe, connecting to the probed endpoint.
+#[cfg(feature = "network")]
+async fn send_probe(
+    probe: &HostProbe,
+    options: &NetworkCheckOptions,
+) -> std::result::Result<reqwest::Response, HealthCheckResult> {
+    let addr = tokio::net::lookup_host(probe.endpoint())
+        .await
+        .ok()
+        .and_then(|mut addrs| addrs.next())
+        .ok_or_else(|| {
+            HealthCheckResult::unhealthy(format!(
+                "Could not resolve listener address {}",
+                probe.endpoint()
+            ))
+        })?;
+
+    // Resolving the server name to the listener keeps Host and SNI equal to
+    // the name under test while connecting to the chosen address.
+    let client = reqwest::Client::builder()
+        .resolve(&probe.host, addr)
+        .timeout(options.timeout)
+        .redirect(reqwest::redirect::Policy::none())
+        .danger_accept_invalid_certs(true)
+        .tls_info(true)
+        .build()
+        .map_err(|e| HealthCheckResult::error(format!("Failed to create HTTP client: {e}")))?;
+
+    let scheme = if probe.tls { "https" } else { "http" };
+    let url = format!(
+        "{scheme}://{}:{}{}",
+        probe.host, probe.port, options.host_routing.path
+    );
+
+    client.get(&url).send().await.map_err(|e| {
+        HealthCheckResult::unhealthy(format!("Request for {} failed", probe.host))
+            .with_details(e.to_string())
+    })
+}
+
+/// Decide which server block answered a probe.
+#[cfg(feature = "network")]
+fn classify_response(
+    probe: &HostProbe,
+    options: &NetworkCheckOptions,
+    response: &reqwest::Response,
+) -> HealthCheckResult {
+    if let (Some(header), Some(expected)) = (
+        options.host_routing.marker_header.as_deref(),
+        probe.expected_marker.as_deref(),
+    ) {
+        if let Some(actual) = response.headers().get(header) {
+            let actual = actual.to_str().unwrap_or_default();
+            return if actual == expected {
+                HealthCheckResult::healthy(format!(
+                    "{} is served by the expected server block",
+                    probe.host
+                ))
+                .with_details(format!("{header}: {actual}"))
+            } else {
+                HealthCheckResult::unhealthy(format!(
+                    "{} was served by '{actual}' instead of '{expected}'",
+                    probe.host
+                ))
+                .with_details("Check server_name spelling and default_server placement")
+            };
+        }
+    }
+
+    if probe.tls {
+        let names = response
+            .extensions()
+            .get::<reqwest::tls::TlsInfo>()
+            .and_then(reqwest::tls::TlsInfo::peer_certificate)
+            .map(certificate_names)
+            .unwrap_or_default();
+
+        if !names.is_empty() {
+            let details = format!("Certificate names: {}", names.join(", "));
+            return if names.iter().any(|name| name_matches(name, &probe.host)) {
+                HealthCheckResult::healthy(format!(
+                    "{} is served with a matching certificate",
+                    probe.host
+                ))
+                .with_details(details)
+            } else {
+                HealthCheckResult::unhealthy(format!(
+                    "{} is served with a certificate for another name",
+                    probe.host
+                ))
+                .with_details(details)
+            };
+        }
+    }
+
+    HealthCheckResult::degraded(format!(
+        "{} answered with {} but the server block could not be identified",
+        probe.host,
+        response.status()
+    ))
+    .with_details("Configure a marker header to confirm routing")
+}
+
+/// DNS names a DER-encoded certificate is valid for.
+#[cfg(feature = "network")]
+fn certificate_names(der: &[u8]) -> Vec<String> {
+    use x509_parser::extensions::GeneralName;
+
+    let Ok((_, cert)) = x509_parser::parse_x509_certificate(der) else {
+        return Vec::new();
+    };
+
+    let mut names: Vec<String> = match cert.subject_alternative_name() {
+        Ok(Some(san)) => san
+            .value
+            .general_names
+            .iter()
+            .filter_map(|name| match name {
+                GeneralName::DNSName(dns) => Some((*dns).to_string()),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    if names.is_empty() {
+        names.extend(
+            cert.subject()
+                .iter_common_name()
+                .filter_map(|cn| cn.as_str().ok())
+                .map(str::to_string),
+        );
+    }
+
+    names
+}
+
+/// Whether a certificate name (possibly `*.`-wildcarded) covers `host`.
+fn name_matches(pattern: &str, host: &str) -> bool {
+    if let Some(suffix) = pattern.strip_prefix("*.") {
+        host.split_once('.')
+            .is_some_and(|(label, rest)| !label.is_empty() && rest.eq_ignore_ascii_case(suffix))
+    } else {
+        pattern.eq_ignore_ascii_case(host)
+    }
+}
+
+/// Value of `add_header <header> <value>` in a server block.
+fn marker_value(children: &[Directive], header: &str) -> Option<String> {
+    children
+        .iter()
+        .filter(|d| d.name() == "add_header")
+        .map(Directive::args_as_strings)
+        .find(|args| {
+            args.first()
+                .is_some_and(|name| name.eq_ignore_ascii_case(header))
+        })
+        .and_then(|args| args.get(1).cloned())
+}
+
+/// Address to connect to for a listener, or `None` if it cannot be probed.
+fn probe_address(listen: &ListenDirective, options: &HostRoutingOptions) -> Option<String> {
+    if listen.address.starts_with("unix:") {
+        return None;
+    }
+
+    if let Some(target) = options.target {
+        return Some(target.to_string());
+    }
+
+    Some(match listen.address.as_str() {
+        "*" | "0.0.0.0" => "127.0.0.1".to_string(),
+        "::" => "::1".to_string(),
+        address => address.to_string(),
+    })
+}
+
+/// Turn a `server_name` entry into a concrete host, if it is one.
+fn concrete_host(name: &str) -> Option<String> {
+    if name.is_empty() || name == "_" || name.starts_with('~') || name.contains('*') {
+        return None;
+    }
+
+    // ".example.com" also matches "example.com" itself
+    Some(name.strip_prefix('.').unwrap_or(name).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_plan_skips_non_concrete_names() {
+        let config = parse(
+            "server { listen 80; server_name _ *.example.com .example.org api.example.com; }",
+        )
+        .unwrap();
+        let probes = plan_host_probes(&config, &HostRoutingOptions::default());
+
+        let hosts: Vec<_> = probes.iter().map(|p| p.host.as_str()).collect();
+        assert_eq!(hosts, vec!["example.org", "api.example.com"]);
+    }
+
+    #[test]
+    fn test_plan_uses_target_and_tls() {
+        let config = parse("server { listen 443 ssl; server_name example.com; }").unwrap();
+        let options = HostRoutingOptions::default().with_target("10.0.0.5".parse().unwrap());
+        let probes = plan_host_probes(&config, &options);
+
+        assert_eq!(probes.len(), 1);
+        assert_eq!(probes[0].endpoint(), "10.0.0.5:443");
+        assert!(probes[0].tls);
+    }
+
+    #[test]
+    fn test_plan_expected_marker() {
+        let config = parse(
+            r"
+            server { listen 80; server_name a.example; add_header X-Served-By block-a; }
+            server { listen 80; server_name b.example; }
+            ",
+        )
+        .unwrap();
+
+        let probes = plan_host_probes(&config, &HostRoutingOptions::default());
+        assert!(probes.iter().all(|p| p.expected_marker.is_none()));
+
+        let options = HostRoutingOptions::default().with_marker_header("x-served-by");
+        let probes = plan_host_probes(&config, &options);
+        assert_eq!(probes[0].expected_marker.as_deref(), Some("block-a"));
+        assert_eq!(probes[1].expected_marker.as_deref(), Some("b.example"));
+    }
+
+    #[test]
+    fn test_name_matches() {
+        assert!(name_matches("example.com", "EXAMPLE.com"));
+        assert!(name_matches("*.example.com", "api.example.com"));
+        assert!(!name_matches("*.example.com", "example.com"));
+        assert!(!name_matches("*.example.com", "a.b.example.com"));
+        assert!(!name_matches("example.com", "api.example.com"));
+    }
+}
@@ -0,0 +1,185 @@
+//! Parsing many files at once
+//!
+//! [`parse_files`] reads and parses files on a pool of scoped threads, one
+//! per available CPU, for scanning a `conf.d` directory or a repository of
+//! vhost files. Each file is parsed on its own; `include` directives are
+//! not followed.
+
+use super::parse;
+use crate::ast::Config;
+use crate::error::{Error, Result};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Read and parse one file
+///
+/// The file is decoded as UTF-8 with invalid bytes replaced; see
+/// [`decode`](super::decode).
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be read or does not parse.
+pub fn parse_file(path: impl AsRef<Path>) -> Result<Config> {
+    parse(&super::read_source(path, super::SourceEncoding::Utf8)?.text)
+}
+
+/// Read and parse files in parallel
+///
+/// Results are returned in the order of `paths`, each with the path it
+/// belongs to. A file that cannot be read or parsed does not stop the
+/// others; use [`collect_configs`] to turn the results into one error
+/// listing every failure.
+///
+/// # Examples
+///
+/// ```no_run
+/// use nginx_discovery::parse_files;
+///
+/// let paths = std::fs::read_dir("/etc/nginx/conf.d")?
+///     .map(|entry| entry.map(|e| e.path()))
+///     .collect::<Result<Vec<_>, _>>()?;
+///
+/// for (path, result) in parse_files(&paths) {
+///     match result {
+///         Ok(config) => println!("{}: {} directives", path.display(), config.count_directives()),
+///         Err(e) => eprintln!("{}: {e}", path.display()),
+///     }
+/// }
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+pub fn parse_files<I, P>(paths: I) -> Vec<(PathBuf, Result<Config>)>
+where
+    I: IntoIterator<Item = P>,
+    P: AsRef<Path>,
+{
+    let paths: Vec<PathBuf> = paths
+        .into_iter()
+        .map(|p| p.as_ref().to_path_buf())
+        .collect();
+    let workers = std::thread::available_parallelism()
+        .map_or(1, std::num::NonZeroUsize::get)
+        .min(paths.len());
+    let next = AtomicUsize::new(0);
+
+    let mut parsed: Vec<(usize, Result<Config>)> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(path) = paths.get(i) else {
+                            break;
+                        };
+                        done.push((i, parse_file(path)));
+                    }
+                    done
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+            })
+            .collect()
+    });
+    parsed.sort_by_key(|(i, _)| *i);
+
+    paths
+        .into_iter()
+        .zip(parsed)
+        .map(|(path, (_, result))| (path, result))
+        .collect()
+}
+
+/// The configurations of a batch, or one error listing every file that
+/// failed
+///
+/// # Errors
+///
+/// Returns [`Error::Custom`] naming each failed file with its error when
+/// any file failed.
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::parser::collect_configs;
+/// use std::path::PathBuf;
+///
+/// let results = vec![
+///     (PathBuf::from("a.conf"), nginx_discovery::parse("user nginx;")),
+///     (PathBuf::from("b.conf"), nginx_discovery::parse("user")),
+/// ];
+/// let err = collect_configs(results).unwrap_err();
+/// assert!(err.to_string().starts_with("1 of 2 files failed to parse:\n  b.conf: "));
+/// ```
+pub fn collect_configs(results: Vec<(PathBuf, Result<Config>)>) -> Result<Vec<(PathBuf, Config)>> {
+    let total = results.len();
+    let mut configs = Vec::with_capacity(total);
+    let mut failures = Vec::new();
+    for (path, result) in results {
+        match result {
+            Ok(config) => configs.push((path, config)),
+            Err(error) => failures.push((path, error)),
+        }
+    }
+    if failures.is_empty() {
+        return Ok(configs);
+    }
+
+    let mut message = format!("{} of {total} files failed to parse:", failures.len());
+    for (path, error) in &failures {
+        let _ = write!(message, "\n  {}: {error}", path.display());
+    }
+    Err(Error::Custom(message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut paths = Vec::new();
+        for i in 0..20 {
+            let path = dir.path().join(format!("site{i}.conf"));
+            let text = if i == 7 {
+                "server {".to_string()
+            } else {
+                format!("server {{ listen {}; }}", 8000 + i)
+            };
+            std::fs::write(&path, text).unwrap();
+            paths.push(path);
+        }
+        paths.push(dir.path().join("missing.conf"));
+
+        let results = parse_files(&paths);
+        assert_eq!(results.len(), 21);
+        for (i, (path, result)) in results.iter().enumerate() {
+            assert_eq!(path, &paths[i]);
+            match i {
+                7 | 20 => assert!(result.is_err()),
+                _ => {
+                    let config = result.as_ref().unwrap();
+                    let port = 8000 + i;
+                    assert_eq!(
+                        config.directives[0].children().unwrap()[0].first_arg(),
+                        Some(port.to_string())
+                    );
+                }
+            }
+        }
+
+        let err = collect_configs(results).unwrap_err().to_string();
+        assert!(err.starts_with("2 of 21 files failed to parse:"));
+        assert!(err.contains("site7.conf: "));
+        assert!(err.contains("missing.conf: IO error"));
+
+        assert!(parse_files(Vec::<PathBuf>::new()).is_empty());
+    }
+}
@@ -0,0 +1,136 @@
+//! Block context at a cursor position
+//!
+//! Editors ask for completions while the text is incomplete, so the
+//! context is tracked from the token stream up to the cursor instead of
+//! from a parsed [`Config`](crate::ast::Config).
+
+use super::{Lexer, TokenKind};
+
+/// Where a cursor sits in a configuration
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CursorContext {
+    /// Names of the enclosing blocks, outermost first; empty in the main
+    /// context
+    pub blocks: Vec<String>,
+
+    /// Directive whose arguments the cursor is in, `None` where a new
+    /// directive starts
+    pub directive: Option<String>,
+
+    /// Number of complete arguments of [`directive`](Self::directive)
+    /// before the cursor
+    pub arg_index: usize,
+
+    /// Partial word ending at the cursor
+    pub prefix: String,
+
+    /// Byte offset where [`prefix`](Self::prefix) starts
+    pub prefix_start: usize,
+
+    /// Whether the cursor is inside a comment
+    pub in_comment: bool,
+}
+
+impl CursorContext {
+    /// Name of the innermost enclosing block, `main` at the top level
+    #[must_use]
+    pub fn block(&self) -> &str {
+        self.blocks.last().map_or("main", String::as_str)
+    }
+}
+
+/// Context of the cursor at byte `offset` of `input`
+///
+/// `offset` is clamped to the input and moved back to a character
+/// boundary. Text after the cursor is ignored, and so is an unterminated
+/// string or Lua block before it.
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::parser::context_at;
+///
+/// let text = "http {\n  server {\n    listen 80;\n    prox";
+/// let context = context_at(text, text.len());
+///
+/// assert_eq!(context.blocks, vec!["http", "server"]);
+/// assert_eq!(context.directive, None);
+/// assert_eq!(context.prefix, "prox");
+/// ```
+#[must_use]
+pub fn context_at(input: &str, offset: usize) -> CursorContext {
+    let mut offset = offset.min(input.len());
+    while !input.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    let input = &input[..offset];
+
+    let mut context = CursorContext {
+        prefix_start: offset,
+        ..CursorContext::default()
+    };
+    let mut lexer = Lexer::new(input);
+    while let Ok(token) = lexer.next_token() {
+        let at_cursor = token.span.end == offset;
+        match token.kind {
+            TokenKind::Eof => break,
+            TokenKind::Comment(_) => context.in_comment = at_cursor,
+            _ if at_cursor && token.kind.as_string().is_some() => {
+                context.prefix = input[token.span.start..].to_string();
+                context.prefix_start = token.span.start;
+            }
+            TokenKind::LeftBrace => {
+                context
+                    .blocks
+                    .push(context.directive.take().unwrap_or_default());
+            }
+            TokenKind::RightBrace => {
+                context.blocks.pop();
+                context.directive = None;
+            }
+            TokenKind::Semicolon | TokenKind::RawBlock(_) => context.directive = None,
+            _ => match &context.directive {
+                None => {
+                    context.directive = token.kind.as_string().map(str::to_string);
+                    context.arg_index = 0;
+                }
+                Some(_) => context.arg_index += 1,
+            },
+        }
+    }
+    context
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_context_at() {
+        let text = "events {}\nhttp {\n  server {\n    listen 80 ssl; # note\n    location / { proxy_pass h";
+        let context = context_at(text, text.len());
+        assert_eq!(context.blocks, vec!["http", "server", "location"]);
+        assert_eq!(context.block(), "location");
+        assert_eq!(context.directive.as_deref(), Some("proxy_pass"));
+        assert_eq!(context.arg_index, 0);
+        assert_eq!(context.prefix, "h");
+        assert_eq!(context.prefix_start, text.len() - 1);
+
+        // After a space the next argument starts
+        let context = context_at("listen 80 ", 10);
+        assert_eq!(context.directive.as_deref(), Some("listen"));
+        assert_eq!(context.arg_index, 1);
+        assert_eq!(context.prefix, "");
+
+        let at_comment = text.find("note").unwrap() + 2;
+        assert!(context_at(text, at_comment).in_comment);
+
+        let context = context_at(text, text.find("listen").unwrap());
+        assert_eq!(context.blocks, vec!["http", "server"]);
+        assert_eq!(context.directive, None);
+        assert!(!context.in_comment);
+
+        assert_eq!(context_at("http { }", 8).block(), "main");
+    }
+}
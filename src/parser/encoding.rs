@@ -0,0 +1,377 @@
+//! Decoding configuration files
+//!
+//! nginx reads its configuration as bytes and only cares that the syntax
+//! is ASCII, so old files often carry Latin-1 comments or a byte order
+//! mark left by an editor. [`decode`] turns such files into text instead
+//! of failing:
+//!
+//! - a UTF-8 byte order mark is stripped; a UTF-16 one selects UTF-16
+//! - with [`SourceEncoding::Utf8`], invalid bytes are replaced with
+//!   U+FFFD and reported in an [`EncodingWarning`]
+//! - [`SourceEncoding::Latin1`] and [`SourceEncoding::Windows1252`]
+//!   decode every byte
+//!
+//! Spans of the parsed configuration are offsets into the decoded text.
+
+use crate::error::Result;
+use std::fmt;
+use std::path::Path;
+
+/// Encoding of a configuration file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum SourceEncoding {
+    /// UTF-8, replacing invalid bytes
+    #[default]
+    Utf8,
+    /// ISO 8859-1: every byte is the code point of the same value
+    Latin1,
+    /// Windows code page 1252, Latin-1 with printable characters in
+    /// 0x80–0x9F
+    Windows1252,
+    /// UTF-16, little endian
+    Utf16Le,
+    /// UTF-16, big endian
+    Utf16Be,
+}
+
+impl fmt::Display for SourceEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Utf8 => "utf-8",
+            Self::Latin1 => "latin1",
+            Self::Windows1252 => "windows-1252",
+            Self::Utf16Le => "utf-16le",
+            Self::Utf16Be => "utf-16be",
+        })
+    }
+}
+
+impl std::str::FromStr for SourceEncoding {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().replace('_', "-").as_str() {
+            "utf-8" | "utf8" => Ok(Self::Utf8),
+            "latin1" | "latin-1" | "iso-8859-1" | "iso8859-1" => Ok(Self::Latin1),
+            "windows-1252" | "cp1252" => Ok(Self::Windows1252),
+            "utf-16le" | "utf16le" => Ok(Self::Utf16Le),
+            "utf-16be" | "utf16be" => Ok(Self::Utf16Be),
+            _ => Err(format!(
+                "Unknown encoding: {s}. Available: utf-8, latin1, windows-1252, utf-16le, utf-16be"
+            )),
+        }
+    }
+}
+
+/// Bytes that did not decode cleanly
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EncodingWarning {
+    /// First line with invalid bytes
+    pub line: usize,
+
+    /// What was replaced
+    pub message: String,
+}
+
+impl fmt::Display for EncodingWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// A decoded configuration file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedSource {
+    /// The text, without byte order mark
+    pub text: String,
+
+    /// Encoding the text was decoded from
+    pub encoding: SourceEncoding,
+
+    /// Whether the file started with a byte order mark
+    pub bom: bool,
+
+    /// Invalid bytes that were replaced
+    pub warnings: Vec<EncodingWarning>,
+}
+
+/// Decode a configuration file read as bytes
+///
+/// A byte order mark takes precedence over `encoding`.
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::parser::{decode, SourceEncoding};
+///
+/// let bytes = b"\xEF\xBB\xBFuser nginx; # caf\xE9\n";
+/// let decoded = decode(bytes, SourceEncoding::Utf8);
+/// assert!(decoded.bom);
+/// assert_eq!(decoded.text, "user nginx; # caf\u{FFFD}\n");
+/// assert_eq!(decoded.warnings[0].line, 1);
+///
+/// let decoded = decode(b"# caf\xE9\n", SourceEncoding::Latin1);
+/// assert_eq!(decoded.text, "# café\n");
+/// ```
+#[must_use]
+pub fn decode(bytes: &[u8], encoding: SourceEncoding) -> DecodedSource {
+    let (bytes, encoding, bom) = match bytes {
+        [0xEF, 0xBB, 0xBF, rest @ ..] => (rest, SourceEncoding::Utf8, true),
+        [0xFF, 0xFE, rest @ ..] => (rest, SourceEncoding::Utf16Le, true),
+        [0xFE, 0xFF, rest @ ..] => (rest, SourceEncoding::Utf16Be, true),
+        _ => (bytes, encoding, false),
+    };
+    let mut warnings = Vec::new();
+    let text = match encoding {
+        SourceEncoding::Utf8 => decode_utf8(bytes, &mut warnings),
+        SourceEncoding::Latin1 => bytes.iter().map(|&b| char::from(b)).collect(),
+        SourceEncoding::Windows1252 => bytes.iter().map(|&b| windows_1252(b)).collect(),
+        SourceEncoding::Utf16Le | SourceEncoding::Utf16Be => {
+            decode_utf16(bytes, encoding == SourceEncoding::Utf16Le, &mut warnings)
+        }
+    };
+    DecodedSource {
+        text,
+        encoding,
+        bom,
+        warnings,
+    }
+}
+
+/// Read and decode a configuration file
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be read.
+pub fn read_source(path: impl AsRef<Path>, encoding: SourceEncoding) -> Result<DecodedSource> {
+    Ok(decode(&std::fs::read(path)?, encoding))
+}
+
+fn decode_utf8(mut bytes: &[u8], warnings: &mut Vec<EncodingWarning>) -> String {
+    let mut text = String::with_capacity(bytes.len());
+    let mut first_line = None;
+    let mut lines = 0;
+    let mut line = 1;
+    loop {
+        match std::str::from_utf8(bytes) {
+            Ok(valid) => {
+                text.push_str(valid);
+                break;
+            }
+            Err(e) => {
+                let (valid, rest) = bytes.split_at(e.valid_up_to());
+                // Valid up to the error by construction
+                let valid = std::str::from_utf8(valid).unwrap_or_default();
+                text.push_str(valid);
+                text.push(char::REPLACEMENT_CHARACTER);
+
+                let error_line = line + valid.matches('\n').count();
+                if first_line.is_none() {
+                    first_line = Some(error_line);
+                }
+                if error_line != line || lines == 0 {
+                    lines += 1;
+                }
+                line = error_line;
+                bytes = &rest[e.error_len().unwrap_or(rest.len())..];
+            }
+        }
+    }
+    if let Some(first_line) = first_line {
+        warnings.push(EncodingWarning {
+            line: first_line,
+            message: format!(
+                "invalid UTF-8 replaced with U+FFFD on {lines} line{}; set the file's encoding, such as latin1, to keep those characters",
+                if lines == 1 { "" } else { "s" }
+            ),
+        });
+    }
+    text
+}
+
+fn decode_utf16(bytes: &[u8], little_endian: bool, warnings: &mut Vec<EncodingWarning>) -> String {
+    let units = bytes.chunks(2).map(|pair| match (pair, little_endian) {
+        ([lo, hi], true) | ([hi, lo], false) => u16::from_le_bytes([*lo, *hi]),
+        // A trailing odd byte
+        _ => 0xFFFD,
+    });
+    let mut text = String::with_capacity(bytes.len() / 2);
+    let mut first_line = None;
+    for c in char::decode_utf16(units) {
+        let c = c.unwrap_or_else(|_| {
+            first_line.get_or_insert(text.matches('\n').count() + 1);
+            char::REPLACEMENT_CHARACTER
+        });
+        text.push(c);
+    }
+    if bytes.len() % 2 == 1 {
+        first_line.get_or_insert(text.matches('\n').count() + 1);
+    }
+    if let Some(line) = first_line {
+        warnings.push(EncodingWarning {
+            line,
+            message: "invalid UTF-16 replaced with U+FFFD".to_string(),
+        });
+    }
+    text
+}
+
+/// Windows-1252 differs from Latin-1 in 0x80–0x9F; the five undefined
+/// bytes there map to the C1 controls, as browsers do
+fn windows_1252(byte: u8) -> char {
+    const HIGH: [char; 32] = [
+        '€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8D}', 'Ž',
+        '\u{8F}', '\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9D}',
+        'ž', 'Ÿ',
+    ];
+    match byte {
+        0x80..=0x9F => HIGH[usize::from(byte - 0x80)],
+        _ => char::from(byte),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        let decoded = decode(
+            b"# caf\xE9\nuser nginx;\n# na\xEFve \xFF\n",
+            SourceEncoding::Utf8,
+        );
+        assert!(!decoded.bom);
+        assert_eq!(
+            decoded.text,
+            "# caf\u{FFFD}\nuser nginx;\n# na\u{FFFD}ve \u{FFFD}\n"
+        );
+        assert_eq!(decoded.warnings.len(), 1);
+        assert_eq!(decoded.warnings[0].line, 1);
+        assert!(decoded.warnings[0].message.contains("on 2 lines"));
+        assert!(crate::parse(&decoded.text).is_ok());
+
+        let decoded = decode(b"# \x80 \x93x\x94\n", SourceEncoding::Windows1252);
+        assert_eq!(decoded.text, "# € “x”\n");
+        assert!(decoded.warnings.is_empty());
+
+        let utf16: Vec<u8> = [0xFF, 0xFE]
+            .into_iter()
+            .chain("user nginx;".encode_utf16().flat_map(u16::to_le_bytes))
+            .collect();
+        let decoded = decode(&utf16, SourceEncoding::Latin1);
+        assert_eq!(decoded.encoding, SourceEncoding::Utf16Le);
+        assert_eq!(decoded.text, "user nginx;");
+
+        assert_eq!("ISO-8859-1".parse(), Ok(SourceEncoding::Latin1));
+        assert!("ebcdic".parse::<SourceEncoding>().is_err());
+    }
+
+    #[test]
+    fn test_decode_utf8_edge_cases() {
+        // Several bad bytes on one line count once
+        let decoded = decode(b"user nginx;\n\n# \xE9\xE8 \xFF\n", SourceEncoding::Utf8);
+        assert_eq!(decoded.warnings.len(), 1);
+        assert_eq!(decoded.warnings[0].line, 3);
+        assert!(decoded.warnings[0].message.contains("on 1 line;"));
+        assert_eq!(decoded.text.matches('\u{FFFD}').count(), 3);
+
+        // A sequence cut off at the end is replaced once
+        let decoded = decode(b"# \xE2\x82", SourceEncoding::Utf8);
+        assert_eq!(decoded.text, "# \u{FFFD}");
+        assert_eq!(
+            decoded.warnings[0].to_string().split(':').next(),
+            Some("line 1")
+        );
+
+        // A byte order mark wins over the requested encoding, and spans
+        // start after it
+        let decoded = decode(b"\xEF\xBB\xBFuser caf\xC3\xA9;", SourceEncoding::Latin1);
+        assert!(decoded.bom);
+        assert_eq!(decoded.encoding, SourceEncoding::Utf8);
+        assert_eq!(decoded.text, "user café;");
+        let config = crate::parse(&decoded.text).unwrap();
+        assert_eq!(config.directives[0].span.start, 0);
+
+        let decoded = decode(b"\xEF\xBB\xBF", SourceEncoding::Utf8);
+        assert!(decoded.bom && decoded.text.is_empty() && decoded.warnings.is_empty());
+        let decoded = decode(b"", SourceEncoding::Utf16Be);
+        assert!(!decoded.bom && decoded.text.is_empty() && decoded.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_decode_single_byte_and_utf16_edge_cases() {
+        // Latin-1 keeps 0x80-0x9F as C1 controls; Windows-1252 only its
+        // undefined bytes
+        let bytes = b"\x80\x81\x9F\xA0\xFF";
+        assert_eq!(
+            decode(bytes, SourceEncoding::Latin1).text,
+            "\u{80}\u{81}\u{9F}\u{A0}\u{FF}"
+        );
+        assert_eq!(
+            decode(bytes, SourceEncoding::Windows1252).text,
+            "€\u{81}Ÿ\u{A0}ÿ"
+        );
+
+        let be =
+            |text: &str| -> Vec<u8> { text.encode_utf16().flat_map(u16::to_be_bytes).collect() };
+        let decoded = decode(&be("user nginx;"), SourceEncoding::Utf16Be);
+        assert!(!decoded.bom);
+        assert_eq!(decoded.text, "user nginx;");
+        let mut bom = vec![0xFE, 0xFF];
+        bom.extend(be("a\nb"));
+        let decoded = decode(&bom, SourceEncoding::Utf8);
+        assert_eq!(
+            (decoded.encoding, decoded.text.as_str()),
+            (SourceEncoding::Utf16Be, "a\nb")
+        );
+
+        // An unpaired surrogate on line 2
+        let mut bytes = be("a\n");
+        bytes.extend([0xD8, 0x00]);
+        bytes.extend(be("b"));
+        let decoded = decode(&bytes, SourceEncoding::Utf16Be);
+        assert_eq!(decoded.text, "a\n\u{FFFD}b");
+        assert_eq!(decoded.warnings[0].line, 2);
+        assert_eq!(
+            decoded.warnings[0].message,
+            "invalid UTF-16 replaced with U+FFFD"
+        );
+
+        // A trailing odd byte
+        let decoded = decode(b"a\0\n\0b", SourceEncoding::Utf16Le);
+        assert_eq!(decoded.text, "a\n\u{FFFD}");
+        assert_eq!(decoded.warnings.len(), 1);
+        assert_eq!(decoded.warnings[0].line, 2);
+    }
+
+    #[test]
+    fn test_encoding_names_and_read_source() {
+        for encoding in [
+            SourceEncoding::Utf8,
+            SourceEncoding::Latin1,
+            SourceEncoding::Windows1252,
+            SourceEncoding::Utf16Le,
+            SourceEncoding::Utf16Be,
+        ] {
+            assert_eq!(encoding.to_string().parse(), Ok(encoding));
+        }
+        assert_eq!("CP1252".parse(), Ok(SourceEncoding::Windows1252));
+        assert_eq!("utf_16le".parse(), Ok(SourceEncoding::Utf16Le));
+        assert_eq!("UTF8".parse(), Ok(SourceEncoding::Utf8));
+        let err = "".parse::<SourceEncoding>().unwrap_err();
+        assert!(err.contains("Available: utf-8"), "{err}");
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nginx.conf");
+        std::fs::write(&path, b"# caf\xE9\n").unwrap();
+        assert_eq!(
+            read_source(&path, SourceEncoding::Latin1).unwrap().text,
+            "# café\n"
+        );
+        let err = read_source(dir.path().join("missing.conf"), SourceEncoding::Utf8).unwrap_err();
+        assert!(matches!(err, crate::Error::Io(e) if e.kind() == std::io::ErrorKind::NotFound));
+    }
+}
@@ -1,7 +1,7 @@
 //! Lexer for NGINX configuration files
 use crate::ast::Span;
 use crate::error::{Error, Result};
-use crate::parser::{Token, TokenKind};
+use crate::parser::{ClassifiedToken, Token, TokenClass, TokenKind};
 
 /// Lexer for tokenizing NGINX configuration
 pub struct Lexer<'a> {
@@ -13,6 +13,11 @@ pub struct Lexer<'a> {
     line: usize,
     /// Current column number (1-indexed)
     col: usize,
+    /// Whether the next token starts a directive
+    at_statement_start: bool,
+    /// Whether the current directive's block holds Lua code
+    /// (`content_by_lua_block { ... }`)
+    lua_block: bool,
 }
 
 impl<'a> Lexer<'a> {
@@ -24,6 +29,8 @@ impl<'a> Lexer<'a> {
             pos: 0,
             line: 1,
             col: 1,
+            at_statement_start: true,
+            lua_block: false,
         }
     }
 
@@ -32,9 +39,9 @@ impl<'a> Lexer<'a> {
     /// # Errors
     ///
     /// Returns an error if:
-    /// - An unexpected character is encountered
     /// - A string literal is unterminated
-    /// - A variable reference is malformed
+    /// - A `${name}` variable reference is not closed
+    /// - A `*_by_lua_block` body is not closed
     pub fn next_token(&mut self) -> Result<Token> {
         // Skip whitespace
         self.skip_whitespace();
@@ -55,6 +62,10 @@ impl<'a> Lexer<'a> {
             '#' => self.lex_comment(),
 
             // Braces
+            '{' if self.lua_block => {
+                self.advance();
+                self.lex_lua_block()?
+            }
             '{' => {
                 self.advance();
                 TokenKind::LeftBrace
@@ -70,34 +81,31 @@ impl<'a> Lexer<'a> {
                 TokenKind::Semicolon
             }
 
-            // Equals (for options like buffer=32k)
-            '=' => {
-                self.advance();
-                TokenKind::Word("=".to_string()) // Treat = as a word token
-            }
-
             // Strings
             '"' => self.lex_string('"')?,
             '\'' => self.lex_string('\'')?,
 
-            // Variables
-            '$' => self.lex_variable()?,
-
-            // Numbers or words
-            _ if ch.is_ascii_digit() => self.lex_number(),
-            _ if is_word_start(ch) => self.lex_word(),
-
-            _ => {
-                return Err(Error::syntax(
-                    format!("unexpected character '{ch}'"),
-                    self.line,
-                    self.col,
-                    Some("valid token".to_string()),
-                    Some(format!("'{ch}'")),
-                ));
-            }
+            // Everything else is a bare word: numbers, variables, paths,
+            // addresses, regexes and parameters like `buffer=32k`
+            _ => self.lex_word()?,
         };
 
+        match &kind {
+            TokenKind::Comment(_) => {}
+            TokenKind::LeftBrace
+            | TokenKind::RightBrace
+            | TokenKind::Semicolon
+            | TokenKind::RawBlock(_) => {
+                self.at_statement_start = true;
+                self.lua_block = false;
+            }
+            TokenKind::Word(name) if self.at_statement_start => {
+                self.at_statement_start = false;
+                self.lua_block = name.ends_with("_by_lua_block");
+            }
+            _ => self.at_statement_start = false,
+        }
+
         let span = Span::new(start_pos, self.pos, start_line, start_col);
         Ok(Token::new(kind, span))
     }
@@ -124,18 +132,75 @@ impl<'a> Lexer<'a> {
         Ok(tokens)
     }
 
+    /// Tokenize the entire input into classified slices for highlighting
+    ///
+    /// A bare word that starts a statement is a
+    /// [`DirectiveName`](TokenClass::DirectiveName). Variable references
+    /// inside bare-word arguments get their own
+    /// [`Variable`](TokenClass::Variable) slices, so `http://$host/` yields
+    /// an argument, a variable and an argument. Quoted strings are one
+    /// [`String`](TokenClass::String) slice. The end-of-file token is
+    /// omitted.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any token cannot be lexed, see
+    /// [`next_token`](Self::next_token).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nginx_discovery::parser::{Lexer, TokenClass};
+    ///
+    /// let tokens = Lexer::new("return 301 https://$host;").tokenize_classified()?;
+    /// let classes: Vec<TokenClass> = tokens.iter().map(|t| t.class).collect();
+    /// assert_eq!(
+    ///     classes,
+    ///     vec![
+    ///         TokenClass::DirectiveName,
+    ///         TokenClass::Argument,
+    ///         TokenClass::Argument,
+    ///         TokenClass::Variable,
+    ///         TokenClass::Semicolon,
+    ///     ]
+    /// );
+    /// # Ok::<(), nginx_discovery::Error>(())
+    /// ```
+    pub fn tokenize_classified(&mut self) -> Result<Vec<ClassifiedToken>> {
+        let mut tokens = Vec::new();
+
+        loop {
+            let statement_start = self.at_statement_start;
+            let token = self.next_token()?;
+            let class = match &token.kind {
+                TokenKind::Eof => break,
+                TokenKind::Word(_) | TokenKind::Number(_) if statement_start => {
+                    TokenClass::DirectiveName
+                }
+                TokenKind::Word(word) => {
+                    split_variables(word, token.span, &mut tokens);
+                    continue;
+                }
+                TokenKind::Number(_) => TokenClass::Argument,
+                TokenKind::Variable(_) => TokenClass::Variable,
+                TokenKind::String(_) | TokenKind::DoubleQuotedString(_) => TokenClass::String,
+                TokenKind::Comment(_) => TokenClass::Comment,
+                TokenKind::LeftBrace | TokenKind::RightBrace => TokenClass::Brace,
+                TokenKind::Semicolon => TokenClass::Semicolon,
+                TokenKind::RawBlock(_) => TokenClass::Lua,
+            };
+            tokens.push(ClassifiedToken::new(class, token.span));
+        }
+
+        Ok(tokens)
+    }
+
     /// Skip whitespace characters
     fn skip_whitespace(&mut self) {
         while !self.is_eof() {
             let ch = self.current_char();
             if ch.is_whitespace() {
-                if ch == '\n' {
-                    self.line += 1;
-                    self.col = 1;
-                    self.pos += 1;
-                } else {
-                    self.advance();
-                }
+                self.bump();
             } else {
                 break;
             }
@@ -156,6 +221,11 @@ impl<'a> Lexer<'a> {
     }
 
     /// Lex a quoted string
+    ///
+    /// A backslash escapes the next character, as in nginx. The one
+    /// exception is a backslash before a quote that is followed by `;` and
+    /// the end of the line, as in `root "C:\www\";` on Windows: there
+    /// the backslash belongs to the path and the quote closes the string.
     fn lex_string(&mut self, quote: char) -> Result<TokenKind> {
         self.advance(); // Skip opening quote
 
@@ -167,6 +237,9 @@ impl<'a> Lexer<'a> {
 
             if escaped {
                 escaped = false;
+                if ch == quote && ends_statement(&self.input[self.pos + 1..]) {
+                    return Ok(self.finish_string(start, quote));
+                }
                 self.advance();
                 continue;
             }
@@ -178,94 +251,131 @@ impl<'a> Lexer<'a> {
             }
 
             if ch == quote {
-                let value = self.input[start..self.pos].to_string();
-                self.advance(); // Skip closing quote
-                return Ok(TokenKind::String(value));
-            }
-
-            if ch == '\n' {
-                return Err(Error::syntax(
-                    "unterminated string literal",
-                    self.line,
-                    self.col,
-                    Some("closing quote".to_string()),
-                    Some("newline".to_string()),
-                ));
+                return Ok(self.finish_string(start, quote));
             }
 
-            self.advance();
+            // Like nginx, quoted strings may span lines (`perl_set` subs)
+            self.bump();
         }
 
         Err(Error::unexpected_eof("closing quote", self.line))
     }
 
-    /// Lex a variable ($name)
-    fn lex_variable(&mut self) -> Result<TokenKind> {
-        self.advance(); // Skip '$'
+    /// Take the string from `start` up to its closing quote at the
+    /// current position
+    fn finish_string(&mut self, start: usize, quote: char) -> TokenKind {
+        let value = self.input[start..self.pos].to_string();
+        self.advance(); // Skip closing quote
+        if quote == '"' {
+            TokenKind::DoubleQuotedString(value)
+        } else {
+            TokenKind::String(value)
+        }
+    }
 
+    /// Lex the Lua body of a `*_by_lua_block` directive, after its `{`
+    ///
+    /// Lua strings, long brackets (`[[...]]`, `[==[...]==]`) and comments
+    /// may contain unbalanced braces and quotes, so they are skipped as a
+    /// whole while looking for the closing `}`.
+    fn lex_lua_block(&mut self) -> Result<TokenKind> {
         let start = self.pos;
+        let mut depth = 1;
 
-        // Variable name can be in braces: ${var_name}
-        if !self.is_eof() && self.current_char() == '{' {
-            self.advance(); // Skip '{'
-            let name_start = self.pos;
-
-            while !self.is_eof() && self.current_char() != '}' {
-                self.advance();
-            }
-
-            if self.is_eof() {
-                return Err(Error::unexpected_eof("'}'", self.line));
+        while !self.is_eof() {
+            let rest = &self.input[self.pos..];
+            match self.current_char() {
+                '{' => depth += 1,
+                '}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        let code = self.input[start..self.pos].trim().to_string();
+                        self.advance(); // Skip '}'
+                        return Ok(TokenKind::RawBlock(code));
+                    }
+                }
+                quote @ ('"' | '\'') => {
+                    self.advance();
+                    while !self.is_eof() && self.current_char() != quote {
+                        if self.current_char() == '\\' {
+                            self.advance();
+                        }
+                        self.bump();
+                    }
+                }
+                '[' if long_bracket_level(rest).is_some() => self.skip_long_bracket(),
+                '-' if rest.starts_with("--") => {
+                    self.advance();
+                    self.advance();
+                    if long_bracket_level(&self.input[self.pos..]).is_some() {
+                        self.skip_long_bracket();
+                        continue;
+                    }
+                    while !self.is_eof() && self.current_char() != '\n' {
+                        self.advance();
+                    }
+                    continue;
+                }
+                _ => {}
             }
-
-            let name = self.input[name_start..self.pos].to_string();
-            self.advance(); // Skip '}'
-            return Ok(TokenKind::Variable(name));
+            self.bump();
         }
 
-        // Regular variable: $name
-        while !self.is_eof() && is_word_char(self.current_char()) {
+        Err(Error::unexpected_eof(
+            "'}' closing the Lua block",
+            self.line,
+        ))
+    }
+
+    /// Skip a Lua long bracket starting at the current `[`, up to and
+    /// excluding its last `]`
+    fn skip_long_bracket(&mut self) {
+        let level = long_bracket_level(&self.input[self.pos..]).unwrap_or(0);
+        let close = format!("]{}]", "=".repeat(level));
+        for _ in 0..level + 2 {
             self.advance();
         }
-
-        let name = self.input[start..self.pos].to_string();
-
-        if name.is_empty() {
-            return Err(Error::syntax(
-                "expected variable name after '$'",
-                self.line,
-                self.col,
-                Some("variable name".to_string()),
-                None,
-            ));
+        while !self.is_eof() && !self.input[self.pos..].starts_with(&close) {
+            self.bump();
+        }
+        for _ in 0..=level {
+            self.advance();
         }
-
-        Ok(TokenKind::Variable(name))
     }
 
-    /// Lex a number
-    fn lex_number(&mut self) -> TokenKind {
+    /// Lex a bare word and classify it
+    ///
+    /// Like nginx, a bare word runs until whitespace, `;`, `{` or `}`. A
+    /// `${name}` reference may contain braces. A word that is exactly one
+    /// variable reference becomes [`TokenKind::Variable`], a word made of
+    /// digits becomes [`TokenKind::Number`], anything else is a
+    /// [`TokenKind::Word`].
+    fn lex_word(&mut self) -> Result<TokenKind> {
         let start = self.pos;
 
-        while !self.is_eof() && (self.current_char().is_ascii_digit() || self.current_char() == '.')
-        {
-            self.advance();
-        }
+        while !self.is_eof() {
+            let ch = self.current_char();
 
-        let number = self.input[start..self.pos].to_string();
-        TokenKind::Number(number)
-    }
+            if ch == '$' && self.input[self.pos..].starts_with("${") {
+                while !self.is_eof() && self.current_char() != '}' {
+                    self.advance();
+                }
+                if self.is_eof() {
+                    return Err(Error::unexpected_eof("'}'", self.line));
+                }
+                self.advance(); // Skip '}'
+                continue;
+            }
 
-    /// Lex a word (identifier)
-    fn lex_word(&mut self) -> TokenKind {
-        let start = self.pos;
+            if ch.is_whitespace() || matches!(ch, ';' | '{' | '}') {
+                break;
+            }
 
-        while !self.is_eof() && is_word_char(self.current_char()) {
             self.advance();
         }
 
-        let word = self.input[start..self.pos].to_string();
-        TokenKind::Word(word)
+        let word = &self.input[start..self.pos];
+        Ok(classify_word(word))
     }
 
     /// Make a token at current position
@@ -283,6 +393,17 @@ impl<'a> Lexer<'a> {
         self.pos >= self.input.len()
     }
 
+    /// Advance to next character, counting lines
+    fn bump(&mut self) {
+        if self.current_char() == '\n' {
+            self.line += 1;
+            self.col = 1;
+            self.pos += 1;
+        } else {
+            self.advance();
+        }
+    }
+
     /// Advance to next character
     fn advance(&mut self) {
         if !self.is_eof() {
@@ -295,32 +416,82 @@ impl<'a> Lexer<'a> {
     }
 }
 
-/// Check if character can start a word
-fn is_word_start(ch: char) -> bool {
-    ch.is_ascii_alphabetic()
-        || ch == '_'
-        || ch == '/'
-        || ch == '.'
-        || ch == '*'
-        || ch == '^'
-        || ch == '~'
-        || ch == '\\'
+/// Whether `rest` is `;` and the end of the line, with optional blanks
+/// around the `;` and an optional trailing comment
+fn ends_statement(rest: &str) -> bool {
+    rest.trim_start_matches([' ', '\t'])
+        .strip_prefix(';')
+        .map(|rest| rest.trim_start_matches([' ', '\t']))
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with(['\n', '\r', '#']))
 }
 
-/// Check if character can be part of a word
-fn is_word_char(ch: char) -> bool {
-    ch.is_ascii_alphanumeric()
-        || ch == '_'
-        || ch == '-'
-        || ch == '/'
-        || ch == '.'
-        || ch == ':'
-        || ch == '='
-        || ch == '*'
-        || ch == '^'
-        || ch == '~'
-        || ch == '\\'
-        || ch == '$' // Add $ too for regex patterns like $
+/// Classify a bare word as a variable, number or plain word
+fn classify_word(word: &str) -> TokenKind {
+    if let Some(name) = word.strip_prefix('$') {
+        let name = name
+            .strip_prefix('{')
+            .and_then(|n| n.strip_suffix('}'))
+            .unwrap_or(name);
+        if !name.is_empty() && name.chars().all(is_variable_char) {
+            return TokenKind::Variable(name.to_string());
+        }
+    }
+
+    if word.starts_with(|c: char| c.is_ascii_digit())
+        && word.chars().all(|c| c.is_ascii_digit() || c == '.')
+    {
+        return TokenKind::Number(word.to_string());
+    }
+
+    TokenKind::Word(word.to_string())
+}
+
+/// Push the argument and variable slices of a bare word at `span`
+///
+/// Bare words hold no newlines, so columns advance by characters.
+fn split_variables(word: &str, span: Span, tokens: &mut Vec<ClassifiedToken>) {
+    let mut push = |class, start: usize, end: usize| {
+        if start < end {
+            let col = span.col + word[..start].chars().count();
+            tokens.push(ClassifiedToken::new(
+                class,
+                Span::new(span.start + start, span.start + end, span.line, col),
+            ));
+        }
+    };
+
+    let mut plain = 0;
+    let mut i = 0;
+    while let Some(offset) = word[i..].find('$') {
+        let dollar = i + offset;
+        let rest = &word[dollar + 1..];
+        let len = if rest.starts_with('{') {
+            rest.find('}').map_or(0, |end| end + 1)
+        } else {
+            rest.find(|c| !is_variable_char(c)).unwrap_or(rest.len())
+        };
+        if len == 0 {
+            i = dollar + 1;
+            continue;
+        }
+        push(TokenClass::Argument, plain, dollar);
+        push(TokenClass::Variable, dollar, dollar + 1 + len);
+        plain = dollar + 1 + len;
+        i = plain;
+    }
+    push(TokenClass::Argument, plain, word.len());
+}
+
+/// Level of the Lua long bracket opening `s` (`[[` is 0, `[==[` is 2)
+fn long_bracket_level(s: &str) -> Option<usize> {
+    let rest = s.strip_prefix('[')?;
+    let level = rest.chars().take_while(|&c| c == '=').count();
+    rest[level..].starts_with('[').then_some(level)
+}
+
+/// Check if character can be part of a variable name
+fn is_variable_char(ch: char) -> bool {
+    ch.is_ascii_alphanumeric() || ch == '_'
 }
 
 #[cfg(test)]
@@ -358,8 +529,14 @@ mod tests {
         let tokens = lexer.tokenize().unwrap();
 
         assert_eq!(tokens[0].kind, TokenKind::Word("root".to_string()));
-        assert_eq!(tokens[1].kind, TokenKind::String("/var/www".to_string()));
+        assert_eq!(
+            tokens[1].kind,
+            TokenKind::DoubleQuotedString("/var/www".to_string())
+        );
         assert_eq!(tokens[2].kind, TokenKind::Semicolon);
+
+        let tokens = Lexer::new("return 200 'ok';").tokenize().unwrap();
+        assert_eq!(tokens[2].kind, TokenKind::String("ok".to_string()));
     }
 
     #[test]
@@ -402,4 +579,170 @@ mod tests {
 
         assert!(result.is_err());
     }
+
+    fn kinds(input: &str) -> Vec<TokenKind> {
+        Lexer::new(input)
+            .tokenize()
+            .unwrap()
+            .into_iter()
+            .map(|t| t.kind)
+            .collect()
+    }
+
+    #[test]
+    fn test_lex_nginx_words() {
+        let word = |s: &str| TokenKind::Word(s.to_string());
+
+        assert_eq!(
+            kinds("listen 127.0.0.1:8080 ssl;")[1],
+            word("127.0.0.1:8080")
+        );
+        assert_eq!(kinds("listen [::]:443;")[1], word("[::]:443"));
+        assert_eq!(kinds("client_max_body_size 10m;")[1], word("10m"));
+        assert_eq!(kinds("error_page 404 =200 /x;")[2], word("=200"));
+        assert_eq!(
+            kinds(r"location ~* \.(png|jpg)$ {")[2],
+            word(r"\.(png|jpg)$")
+        );
+        assert_eq!(kinds("try_files $uri $uri/ =404;")[2], word("$uri/"));
+        assert_eq!(
+            kinds("access_log /var/log/$host.log;")[1],
+            word("/var/log/$host.log")
+        );
+        assert_eq!(kinds("add_header X-Id a#b;")[2], word("a#b"));
+    }
+
+    #[test]
+    fn test_lex_variables_and_numbers() {
+        assert_eq!(
+            kinds("set ${name};")[1],
+            TokenKind::Variable("name".to_string())
+        );
+        assert_eq!(
+            kinds("return ${scheme}://x;")[1],
+            TokenKind::Word("${scheme}://x".to_string())
+        );
+        assert_eq!(
+            kinds("worker_processes 4;")[1],
+            TokenKind::Number("4".to_string())
+        );
+        assert!(Lexer::new("set ${name;").tokenize().is_err());
+    }
+
+    #[test]
+    fn test_lex_lua_block() {
+        assert_eq!(
+            kinds("content_by_lua_block { ngx.say('}') } x;")[1],
+            TokenKind::RawBlock("ngx.say('}')".to_string())
+        );
+        assert_eq!(
+            kinds("access_by_lua_block { --[[ { ]] if a then b() end }")[1],
+            TokenKind::RawBlock("--[[ { ]] if a then b() end".to_string())
+        );
+        // Only the directive name decides
+        assert_eq!(
+            kinds("location /x_by_lua_block { }")[2],
+            TokenKind::LeftBrace
+        );
+        assert!(Lexer::new("content_by_lua_block { ngx.say(1)")
+            .tokenize()
+            .is_err());
+    }
+
+    #[test]
+    fn test_lex_windows_paths() {
+        assert_eq!(
+            kinds("root \"C:\\www\\\";\nerror_log C:\\nginx\\logs\\error.log;"),
+            [
+                TokenKind::Word("root".to_string()),
+                TokenKind::DoubleQuotedString("C:\\www\\".to_string()),
+                TokenKind::Semicolon,
+                TokenKind::Word("error_log".to_string()),
+                TokenKind::Word("C:\\nginx\\logs\\error.log".to_string()),
+                TokenKind::Semicolon,
+                TokenKind::Eof,
+            ]
+        );
+        // An escaped quote elsewhere stays escaped
+        assert_eq!(
+            kinds(r"return 200 'it\'s; fine';")[2],
+            TokenKind::String(r"it\'s; fine".to_string())
+        );
+    }
+
+    #[test]
+    fn test_lex_windows_paths_edge_cases() {
+        // Blanks, a comment or CRLF may follow the closing `;`
+        for input in [
+            "root \"C:\\www\\\" ;  # web root\n",
+            "root \"C:\\www\\\";\r\n",
+            "root 'C:\\www\\'\t;",
+        ] {
+            let kinds = kinds(input);
+            assert!(
+                matches!(&kinds[1], TokenKind::DoubleQuotedString(s) | TokenKind::String(s) if s == "C:\\www\\"),
+                "{input:?}: {kinds:?}"
+            );
+            assert_eq!(kinds[2], TokenKind::Semicolon, "{input:?}");
+        }
+
+        // More on the line after the `;` keeps the quote escaped
+        assert_eq!(
+            kinds("set $a \"x\\\"; y\";")[2],
+            TokenKind::DoubleQuotedString("x\\\"; y".to_string())
+        );
+        // So does a quote escaped before something other than `;`
+        assert!(Lexer::new("root \"C:\\www\\\"\n").tokenize().is_err());
+    }
+
+    #[test]
+    fn test_multiline_string() {
+        let tokens = Lexer::new("perl_set $x 'sub {\n  1;\n}';\nuser nginx;")
+            .tokenize()
+            .unwrap();
+        assert_eq!(
+            tokens[2].kind,
+            TokenKind::String("sub {\n  1;\n}".to_string())
+        );
+        assert_eq!(tokens[4].span.line, 4);
+    }
+
+    #[test]
+    fn test_tokenize_classified() {
+        use TokenClass::*;
+
+        let input = "# main\nlocation ~ ^/(.*)$ {\n  proxy_pass http://$host:${port}/$1;\n  set $a 'x';\n  content_by_lua_block { ngx.say(1) }\n}";
+        let tokens = Lexer::new(input).tokenize_classified().unwrap();
+        let classified: Vec<(TokenClass, &str)> = tokens
+            .iter()
+            .map(|t| (t.class, t.span.slice(input).unwrap()))
+            .collect();
+        assert_eq!(
+            classified,
+            vec![
+                (Comment, "# main"),
+                (DirectiveName, "location"),
+                (Argument, "~"),
+                (Argument, "^/(.*)$"),
+                (Brace, "{"),
+                (DirectiveName, "proxy_pass"),
+                (Argument, "http://"),
+                (Variable, "$host"),
+                (Argument, ":"),
+                (Variable, "${port}"),
+                (Argument, "/"),
+                (Variable, "$1"),
+                (Semicolon, ";"),
+                (DirectiveName, "set"),
+                (Variable, "$a"),
+                (String, "'x'"),
+                (Semicolon, ";"),
+                (DirectiveName, "content_by_lua_block"),
+                (Lua, "{ ngx.say(1) }"),
+                (Brace, "}"),
+            ]
+        );
+        assert_eq!(tokens[7].span.line, 3);
+        assert_eq!(tokens[7].span.col, 21);
+    }
 }
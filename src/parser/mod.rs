@@ -1,14 +1,20 @@
 //! NGINX configuration parser
 
+mod batch;
+mod context;
+mod encoding;
 mod lexer;
 mod parse;
 mod token;
 
+pub use batch::{collect_configs, parse_file, parse_files};
+pub use context::{context_at, CursorContext};
+pub use encoding::{decode, read_source, DecodedSource, EncodingWarning, SourceEncoding};
 pub use lexer::Lexer;
 pub use parse::Parser;
-pub use token::{Token, TokenKind};
+pub use token::{ClassifiedToken, Token, TokenClass, TokenKind};
 
-use crate::ast::Config;
+use crate::ast::{Config, Directive};
 use crate::error::Result;
 
 /// Parse NGINX configuration from text
@@ -35,3 +41,82 @@ pub fn parse(input: &str) -> Result<Config> {
     let mut parser = Parser::new(input)?;
     parser.parse()
 }
+
+/// Parse a snippet holding exactly one directive, such as a `location`
+/// block from a form, without wrapping it in a full configuration
+///
+/// # Errors
+///
+/// Returns an error if the snippet has syntax errors, holds no directive,
+/// or holds more than one.
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::parse_directive;
+///
+/// let location = parse_directive("location /api {\n    proxy_pass http://api;\n}")?;
+/// assert_eq!(location.name(), "location");
+/// assert_eq!(location.children().unwrap()[0].name(), "proxy_pass");
+///
+/// assert!(parse_directive("listen 80; listen 443;").is_err());
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+pub fn parse_directive(input: &str) -> Result<Directive> {
+    let mut parser = Parser::new(input)?;
+    parser.parse_single()
+}
+
+/// Parse the body of a block: any number of directives, as found between
+/// the braces of a `server` or `location`
+///
+/// Unlike [`parse`], the result is the directive list itself, ready to be
+/// spliced into a block of an existing configuration.
+///
+/// # Errors
+///
+/// Returns an error if the snippet has syntax errors, including an
+/// unmatched `}`.
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::parse_block_body;
+///
+/// let body = parse_block_body("listen 443 ssl;\nserver_name example.com;")?;
+/// assert_eq!(body.len(), 2);
+/// assert_eq!(body[1].first_arg().as_deref(), Some("example.com"));
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+pub fn parse_block_body(input: &str) -> Result<Vec<Directive>> {
+    Ok(parse(input)?.directives)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_directive() {
+        let directive =
+            parse_directive("# upstream\nupstream app { server 10.0.0.1; } # end").unwrap();
+        assert_eq!(directive.name(), "upstream");
+        assert_eq!(directive.span.line, 2);
+
+        let err = parse_directive("# nothing").unwrap_err();
+        assert!(err.message().contains("expected a directive"));
+        let err = parse_directive("root /a; root /b;").unwrap_err();
+        assert_eq!(err.location(), Some((1, 10)));
+        assert!(parse_directive("location / {").is_err());
+    }
+
+    #[test]
+    fn test_parse_block_body() {
+        assert!(parse_block_body("").unwrap().is_empty());
+        assert_eq!(
+            parse_block_body("root /a; location / { }").unwrap().len(),
+            2
+        );
+        assert!(parse_block_body("root /a; }").is_err());
+    }
+}
@@ -1,5 +1,5 @@
 //! Parser for NGINX configuration files
-use crate::ast::{Config, Directive, Value};
+use crate::ast::{Config, Directive, Span, Value};
 use crate::error::{Error, Result};
 // use crate::prelude::ErrorBuilder;
 use crate::parser::{Lexer, Token, TokenKind};
@@ -50,9 +50,46 @@ impl Parser {
         Ok(Config::with_directives(directives))
     }
 
+    /// Parse input holding exactly one directive, simple or block
+    ///
+    /// Comments around the directive are allowed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the input holds no directive, more than one, or
+    /// the directive is malformed.
+    pub fn parse_single(&mut self) -> Result<Directive> {
+        self.skip_comments();
+        if self.is_eof() {
+            let token = self.current();
+            return Err(Error::syntax(
+                "expected a directive",
+                token.span.line,
+                token.span.col,
+                Some("directive".to_string()),
+                Some(format!("{}", token.kind)),
+            ));
+        }
+
+        let directive = self.parse_directive()?;
+
+        self.skip_comments();
+        if !self.is_eof() {
+            let token = self.current();
+            return Err(Error::syntax(
+                "unexpected input after the directive",
+                token.span.line,
+                token.span.col,
+                Some(format!("{}", TokenKind::Eof)),
+                Some(format!("{}", token.kind)),
+            ));
+        }
+        Ok(directive)
+    }
+
     /// Parse a single directive (simple or block)
     fn parse_directive(&mut self) -> Result<Directive> {
-        let _start_token = self.current();
+        let start = self.current().span;
         let name = self.expect_word()?;
 
         let mut args = Vec::new();
@@ -60,6 +97,7 @@ impl Parser {
         // Collect arguments until we hit ; or {
         while !self.check(&TokenKind::Semicolon)
             && !self.check(&TokenKind::LeftBrace)
+            && !self.check_raw_block()
             && !self.is_eof()
         {
             if self.check_comment() {
@@ -71,6 +109,17 @@ impl Parser {
             args.push(arg);
         }
 
+        // Lua code is kept verbatim as the last argument of an empty block
+        if let TokenKind::RawBlock(code) = &self.current().kind {
+            args.push(Value::single_quoted(code.clone()));
+            let end = self.current().span;
+            self.advance();
+
+            let mut directive = Directive::block_with_values(name, args, Vec::new());
+            directive.span = Span::new(start.start, end.end, start.line, start.col);
+            return Ok(directive);
+        }
+
         // Check if it's a block or simple directive
         if self.check(&TokenKind::LeftBrace) {
             // Block directive
@@ -78,14 +127,18 @@ impl Parser {
 
             let children = self.parse_block_contents()?;
 
-            self.expect(&TokenKind::RightBrace)?;
+            let end = self.expect(&TokenKind::RightBrace)?.span;
 
-            Ok(Directive::block_with_values(name, args, children))
+            let mut directive = Directive::block_with_values(name, args, children);
+            directive.span = Span::new(start.start, end.end, start.line, start.col);
+            Ok(directive)
         } else {
             // Simple directive
-            self.expect(&TokenKind::Semicolon)?;
+            let end = self.expect(&TokenKind::Semicolon)?.span;
 
-            Ok(Directive::simple_with_values(name, args))
+            let mut directive = Directive::simple_with_values(name, args);
+            directive.span = Span::new(start.start, end.end, start.line, start.col);
+            Ok(directive)
         }
     }
 
@@ -112,6 +165,7 @@ impl Parser {
 
         let value = match &token.kind {
             TokenKind::String(s) => Value::single_quoted(s.clone()),
+            TokenKind::DoubleQuotedString(s) => Value::double_quoted(s.clone()),
             TokenKind::Word(s) | TokenKind::Number(s) => Value::literal(s.clone()),
             TokenKind::Variable(s) => Value::variable(s.clone()),
             _ => {
@@ -151,10 +205,21 @@ impl Parser {
     fn expect_word(&mut self) -> Result<String> {
         let token = self.current();
 
-        if let TokenKind::Word(name) = &token.kind {
+        // Entries inside `map`, `geo` and `types` blocks may start with a
+        // number or a quoted string, entries of `geoip2` blocks with a
+        // variable
+        if let TokenKind::Word(name)
+        | TokenKind::Number(name)
+        | TokenKind::String(name)
+        | TokenKind::DoubleQuotedString(name) = &token.kind
+        {
             let result = name.clone();
             self.advance();
             Ok(result)
+        } else if let TokenKind::Variable(name) = &token.kind {
+            let result = format!("${name}");
+            self.advance();
+            Ok(result)
         } else {
             Err(Error::syntax(
                 "expected directive name",
@@ -181,11 +246,23 @@ impl Parser {
         std::mem::discriminant(&self.current().kind) == std::mem::discriminant(kind)
     }
 
+    /// Check if current token is a Lua block body
+    fn check_raw_block(&self) -> bool {
+        matches!(self.current().kind, TokenKind::RawBlock(_))
+    }
+
     /// Check if current token is a comment
     fn check_comment(&self) -> bool {
         matches!(self.current().kind, TokenKind::Comment(_))
     }
 
+    /// Skip comment tokens
+    fn skip_comments(&mut self) {
+        while self.check_comment() {
+            self.advance();
+        }
+    }
+
     /// Check if at end of tokens
     fn is_eof(&self) -> bool {
         matches!(self.current().kind, TokenKind::Eof)
@@ -295,4 +372,74 @@ user nginx;  # Run as nginx
         assert_eq!(config.directives.len(), 1);
         assert!(config.directives[0].args()[0].is_variable());
     }
+
+    #[test]
+    fn test_parse_records_spans() {
+        let input = "user nginx;\nhttp {\n    server { listen 80; }\n}\n";
+        let mut parser = Parser::new(input).unwrap();
+        let config = parser.parse().unwrap();
+
+        let user = &config.directives[0];
+        assert_eq!((user.span.line, user.span.col), (1, 1));
+        assert_eq!(&input[user.span.start..user.span.end], "user nginx;");
+
+        let http = &config.directives[1];
+        assert_eq!((http.span.line, http.span.col), (2, 1));
+        assert!(input[http.span.start..http.span.end].ends_with('}'));
+
+        let listen = &http.children().unwrap()[0].children().unwrap()[0];
+        assert_eq!((listen.span.line, listen.span.col), (3, 14));
+        assert_eq!(&input[listen.span.start..listen.span.end], "listen 80;");
+    }
+
+    #[test]
+    fn test_parse_lua_and_perl() {
+        let input = r#"
+http {
+    perl_set $msie6 'sub {
+        my $r = shift;
+        return "";
+    }';
+    server {
+        location /lua {
+            set_by_lua_block $res { return ngx.var.arg_x or "{" }
+            content_by_lua_block {
+                -- unbalanced } in a comment
+                local s = [==[ "] } ]==]
+                ngx.say("hello ", s)
+            }
+        }
+        location /after { return 204; }
+    }
+}
+"#;
+        let config = Parser::new(input).unwrap().parse().unwrap();
+        let http = &config.directives[0];
+        let perl_set = &http.children().unwrap()[0];
+        assert!(perl_set.args()[1].as_str().contains("my $r = shift;"));
+
+        let server = &http.children().unwrap()[1];
+        let locations = server.children().unwrap();
+        assert_eq!(locations.len(), 2);
+
+        let lua = locations[0].children().unwrap();
+        assert_eq!(lua[0].name(), "set_by_lua_block");
+        assert_eq!(lua[0].args()[0].as_str(), "res");
+        assert_eq!(lua[0].args()[1].as_str(), r#"return ngx.var.arg_x or "{""#);
+        assert!(lua[1].args()[0]
+            .as_str()
+            .ends_with(r#"ngx.say("hello ", s)"#));
+        assert_eq!(lua[1].children(), Some(&[][..]));
+        assert_eq!(locations[1].span.line, 16);
+    }
+
+    #[test]
+    fn test_parse_variable_entries() {
+        let input = "geoip2 /etc/country.mmdb { $country_code country iso_code; }";
+        let config = Parser::new(input).unwrap().parse().unwrap();
+
+        let entry = &config.directives[0].children().unwrap()[0];
+        assert_eq!(entry.name(), "$country_code");
+        assert_eq!(entry.args().len(), 2);
+    }
 }
@@ -20,14 +20,20 @@ impl Token {
 }
 
 /// Token types in NGINX configuration
+///
+/// New token kinds may be added, so matches need a wildcard arm.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum TokenKind {
     /// Word/identifier: `server`, `listen`, etc.
     Word(String),
 
-    /// String literal: `"value"` or `'value'`
+    /// Single-quoted string literal: `'value'`
     String(String),
 
+    /// Double-quoted string literal: `"value"`
+    DoubleQuotedString(String),
+
     /// Number: `80`, `443`, etc.
     Number(String),
 
@@ -46,6 +52,10 @@ pub enum TokenKind {
     /// Comment: `# comment text`
     Comment(String),
 
+    /// Verbatim Lua code of a `*_by_lua_block { ... }` body, braces
+    /// excluded
+    RawBlock(String),
+
     /// End of file
     Eof,
 }
@@ -60,7 +70,7 @@ impl TokenKind {
     /// Check if this token is a string
     #[must_use]
     pub fn is_string(&self) -> bool {
-        matches!(self, Self::String(_))
+        matches!(self, Self::String(_) | Self::DoubleQuotedString(_))
     }
 
     /// Check if this token is a number
@@ -79,23 +89,87 @@ impl TokenKind {
     #[must_use]
     pub fn as_string(&self) -> Option<&str> {
         match self {
-            Self::Word(s) | Self::String(s) | Self::Variable(s) | Self::Number(s) => Some(s),
+            Self::Word(s)
+            | Self::String(s)
+            | Self::DoubleQuotedString(s)
+            | Self::Variable(s)
+            | Self::Number(s) => Some(s),
             _ => None,
         }
     }
 }
 
+/// Semantic class of a token, for syntax highlighting
+///
+/// Unlike [`TokenKind`], the class tells a directive name from an argument.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum TokenClass {
+    /// Bare word naming a directive: `listen`, `location`
+    DirectiveName,
+    /// Bare word or number argument: `80`, `/var/www`, `http://`
+    Argument,
+    /// Variable reference, alone or inside an argument: `$host`, `${uri}`
+    Variable,
+    /// Quoted string, quotes included
+    String,
+    /// Comment, `#` included
+    Comment,
+    /// `{` or `}`
+    Brace,
+    /// `;`
+    Semicolon,
+    /// Body of a `*_by_lua_block`, braces included
+    Lua,
+}
+
+impl std::fmt::Display for TokenClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::DirectiveName => "directive-name",
+            Self::Argument => "argument",
+            Self::Variable => "variable",
+            Self::String => "string",
+            Self::Comment => "comment",
+            Self::Brace => "brace",
+            Self::Semicolon => "semicolon",
+            Self::Lua => "lua",
+        };
+        write!(f, "{s}")
+    }
+}
+
+/// A classified slice of the source
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClassifiedToken {
+    /// What the slice is
+    pub class: TokenClass,
+    /// Where it is
+    pub span: Span,
+}
+
+impl ClassifiedToken {
+    /// Create a classified token
+    #[must_use]
+    pub fn new(class: TokenClass, span: Span) -> Self {
+        Self { class, span }
+    }
+}
+
 impl std::fmt::Display for TokenKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::Word(s) => write!(f, "word '{s}'"),
-            Self::String(s) => write!(f, "string \"{s}\""),
+            Self::String(s) | Self::DoubleQuotedString(s) => write!(f, "string \"{s}\""),
             Self::Number(s) => write!(f, "number '{s}'"),
             Self::Variable(s) => write!(f, "variable '${s}'"),
             Self::LeftBrace => write!(f, "'{{'"), // Changed: double {{ to escape
             Self::RightBrace => write!(f, "'}}'"), // Changed: double }} to escape
             Self::Semicolon => write!(f, "';'"),
             Self::Comment(s) => write!(f, "comment '# {s}'"),
+            Self::RawBlock(_) => write!(f, "Lua block"),
             Self::Eof => write!(f, "end of file"),
         }
     }
@@ -0,0 +1,87 @@
+//! Paths written in configurations
+//!
+//! Configurations of nginx for Windows name files as `C:\nginx\logs\access.log`,
+//! `C:/nginx/html` or `\\server\share\site`. [`std::path::Path`] only
+//! treats these as absolute on Windows, so elsewhere joining them onto the
+//! configuration directory would give paths like
+//! `/etc/nginx/C:\nginx\logs\access.log`. [`resolve`] recognizes them on
+//! every platform.
+
+use std::path::{Path, PathBuf};
+
+/// Whether `path` is absolute on Unix or on Windows
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::paths::is_absolute;
+///
+/// assert!(is_absolute("/var/log/nginx/access.log"));
+/// assert!(is_absolute(r"C:\nginx\logs\access.log"));
+/// assert!(is_absolute("C:/nginx/html"));
+/// assert!(is_absolute(r"\\fileserver\www"));
+/// assert!(!is_absolute("logs/access.log"));
+/// assert!(!is_absolute("C:relative"));
+/// ```
+#[must_use]
+pub fn is_absolute(path: &str) -> bool {
+    path.starts_with(['/', '\\']) || is_drive_absolute(path) || Path::new(path).is_absolute()
+}
+
+/// Whether `path` starts with a drive letter and a separator, `C:\` or `C:/`
+#[must_use]
+pub fn is_drive_absolute(path: &str) -> bool {
+    matches!(
+        path.as_bytes(),
+        [drive, b':', b'\\' | b'/', ..] if drive.is_ascii_alphabetic()
+    )
+}
+
+/// Resolve `path` from a configuration against `base`, the directory
+/// relative paths are relative to
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::paths::resolve;
+/// use std::path::{Path, PathBuf};
+///
+/// let base = Path::new("/etc/nginx");
+/// assert_eq!(resolve(base, "mime.types"), PathBuf::from("/etc/nginx/mime.types"));
+/// assert_eq!(resolve(base, r"C:\nginx\conf\mime.types"), PathBuf::from(r"C:\nginx\conf\mime.types"));
+/// ```
+#[must_use]
+pub fn resolve(base: &Path, path: &str) -> PathBuf {
+    if is_absolute(path) {
+        PathBuf::from(path)
+    } else {
+        base.join(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_forms() {
+        assert!(is_absolute("/"));
+        assert!(is_absolute(r"\nginx\html"));
+        assert!(is_absolute("z:/"));
+        assert!(!is_absolute(""));
+        assert!(!is_absolute("C:"));
+        assert!(!is_absolute("1:/nginx"));
+        assert!(!is_absolute("CC:/nginx"));
+        assert!(!is_absolute("./C:/nginx"));
+        assert!(!is_drive_absolute("/nginx"));
+
+        let base = Path::new("/etc/nginx");
+        assert_eq!(resolve(base, ""), base.join(""));
+        assert_eq!(resolve(base, "C:conf"), base.join("C:conf"));
+        assert_eq!(resolve(base, r"\\srv\share"), PathBuf::from(r"\\srv\share"));
+        assert_eq!(
+            resolve(base, "../logs"),
+            PathBuf::from("/etc/nginx/../logs")
+        );
+    }
+}
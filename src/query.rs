@@ -0,0 +1,401 @@
+//! Directive search with composable predicates
+//!
+//! A [`Query`] combines conditions on a directive: its name, its arguments
+//! (regular expression or exact value), the blocks it sits in and the
+//! children it has, or the [`ConfigPath`] it sits under.
+//! [`Config::query`] returns every matching directive with its context, in
+//! file order.
+//!
+//! The same queries back the `query` CLI command and custom lint rules
+//! ([`QueryRule`](crate::lint::QueryRule)).
+//!
+//! # Examples
+//!
+//! ```
+//! use nginx_discovery::{parse, query::Query};
+//!
+//! let config = parse(r"
+//! http {
+//!     server {
+//!         server_name example.com;
+//!         location /api { proxy_pass http://api; }
+//!         location /static { root /srv; }
+//!     }
+//! }
+//! ")?;
+//!
+//! let query = Query::name("location")
+//!     .inside(["http", "server"])
+//!     .has_child(Query::name("proxy_pass"));
+//! let matches = config.query(&query);
+//!
+//! assert_eq!(matches.len(), 1);
+//! assert_eq!(matches[0].to_string(), "http > server > location /api");
+//! assert_eq!(matches[0].path.to_string(), "http/server/location");
+//! # Ok::<(), nginx_discovery::Error>(())
+//! ```
+
+use crate::ast::{Config, ConfigIndex, ConfigPath, Directive, NodeId, Value};
+use crate::route::regex::Regex;
+use crate::{Error, Result};
+use std::fmt;
+
+#[derive(Debug, Clone)]
+enum Predicate {
+    Name(String),
+    ArgRegex { pattern: String, regex: Regex },
+    Arg(String),
+    Inside(Vec<String>),
+    Under(ConfigPath),
+    HasChild(Query),
+    Any(Vec<Query>),
+    Not(Query),
+}
+
+/// A set of conditions a directive must all satisfy
+///
+/// An empty query matches every directive.
+#[derive(Debug, Clone, Default)]
+pub struct Query {
+    predicates: Vec<Predicate>,
+}
+
+impl Query {
+    /// Create a query matching every directive
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a query matching directives named `name`
+    #[must_use]
+    pub fn name(name: impl Into<String>) -> Self {
+        Self::new().with_name(name)
+    }
+
+    /// Create a query matching directives that satisfy any of `queries`
+    #[must_use]
+    pub fn any(queries: impl IntoIterator<Item = Query>) -> Self {
+        Self {
+            predicates: vec![Predicate::Any(queries.into_iter().collect())],
+        }
+    }
+
+    /// Require the directive name
+    #[must_use]
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.predicates.push(Predicate::Name(name.into()));
+        self
+    }
+
+    /// Require an argument matching a regular expression (PCRE subset,
+    /// `(?i)` for case-insensitive matching)
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidInput`] if the pattern uses unsupported
+    /// syntax (backreferences, lookaround).
+    pub fn arg_regex(mut self, pattern: &str) -> Result<Self> {
+        let regex = Regex::new(pattern, false).ok_or_else(|| {
+            Error::InvalidInput(format!("unsupported regular expression: {pattern}"))
+        })?;
+        self.predicates.push(Predicate::ArgRegex {
+            pattern: pattern.to_string(),
+            regex,
+        });
+        Ok(self)
+    }
+
+    /// Require an argument equal to `value` (quotes removed, variables
+    /// written with `$`)
+    #[must_use]
+    pub fn arg(mut self, value: impl Into<String>) -> Self {
+        self.predicates.push(Predicate::Arg(value.into()));
+        self
+    }
+
+    /// Require enclosing blocks with these names, outermost first
+    ///
+    /// The blocks need not be direct parents: `["http", "location"]`
+    /// matches a directive in `http > server > location`.
+    #[must_use]
+    pub fn inside<I, S>(mut self, blocks: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.predicates.push(Predicate::Inside(
+            blocks.into_iter().map(Into::into).collect(),
+        ));
+        self
+    }
+
+    /// Require the directive to be the one `path` addresses or to lie
+    /// inside it
+    #[must_use]
+    pub fn under(mut self, path: ConfigPath) -> Self {
+        self.predicates.push(Predicate::Under(path));
+        self
+    }
+
+    /// Require a direct child matching `child`
+    #[must_use]
+    pub fn has_child(mut self, child: Query) -> Self {
+        self.predicates.push(Predicate::HasChild(child));
+        self
+    }
+
+    /// Exclude directives matching `other`
+    #[must_use]
+    pub fn without(mut self, other: Query) -> Self {
+        self.predicates.push(Predicate::Not(other));
+        self
+    }
+
+    /// Match directives satisfying this query or `other`
+    #[must_use]
+    pub fn or(self, other: Query) -> Self {
+        Self::any([self, other])
+    }
+
+    /// Check the directive `id` of `index` against the query
+    #[must_use]
+    pub fn matches(&self, index: &ConfigIndex<'_>, id: NodeId) -> bool {
+        self.predicates.iter().all(|predicate| {
+            let directive = index.get(id);
+            match predicate {
+                Predicate::Name(name) => directive.name() == name,
+                Predicate::ArgRegex { regex, .. } => {
+                    args(directive).any(|arg| regex.is_match(&arg))
+                }
+                Predicate::Arg(value) => args(directive).any(|arg| arg == *value),
+                Predicate::Inside(blocks) => {
+                    let path = index.path(id);
+                    let mut ancestors = path[..path.len() - 1].iter();
+                    blocks
+                        .iter()
+                        .all(|block| ancestors.any(|name| name == block))
+                }
+                Predicate::Under(path) => index.config_path(id).starts_with(path),
+                Predicate::HasChild(child) => {
+                    index.children(id).iter().any(|&c| child.matches(index, c))
+                }
+                Predicate::Any(queries) => queries.iter().any(|q| q.matches(index, id)),
+                Predicate::Not(query) => !query.matches(index, id),
+            }
+        })
+    }
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.predicates.is_empty() {
+            return write!(f, "*");
+        }
+        for (i, predicate) in self.predicates.iter().enumerate() {
+            if i > 0 {
+                write!(f, " and ")?;
+            }
+            match predicate {
+                Predicate::Name(name) => write!(f, "name = {name}")?,
+                Predicate::ArgRegex { pattern, .. } => write!(f, "arg ~ {pattern}")?,
+                Predicate::Arg(value) => write!(f, "arg = {value}")?,
+                Predicate::Inside(blocks) => write!(f, "inside {}", blocks.join(" > "))?,
+                Predicate::Under(path) => write!(f, "under {path}")?,
+                Predicate::HasChild(child) => write!(f, "has child ({child})")?,
+                Predicate::Any(queries) => {
+                    let parts: Vec<String> = queries.iter().map(|q| format!("({q})")).collect();
+                    write!(f, "{}", parts.join(" or "))?;
+                }
+                Predicate::Not(query) => write!(f, "not ({query})")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+fn args(directive: &Directive) -> impl Iterator<Item = String> + '_ {
+    directive.args().iter().map(Value::unquoted)
+}
+
+/// A directive found by [`Config::query`]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct QueryMatch<'a> {
+    /// Id of the directive in the configuration's [`ConfigIndex`]
+    pub id: NodeId,
+
+    /// The matching directive
+    pub directive: &'a Directive,
+
+    /// Enclosing blocks and the directive itself, outermost first, each as
+    /// its name followed by its arguments (`server`, `location /api`)
+    pub context: Vec<String>,
+
+    /// Context path of the directive (`http/server[2]/location`)
+    pub path: ConfigPath,
+}
+
+impl QueryMatch<'_> {
+    /// Line of the directive
+    #[must_use]
+    pub fn line(&self) -> usize {
+        self.directive.span.line
+    }
+}
+
+impl fmt::Display for QueryMatch<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.context.join(" > "))
+    }
+}
+
+impl Config {
+    /// Find the directives matching `query`, in file order
+    ///
+    /// See the [`query`](crate::query) module. To run many queries against
+    /// the same configuration, build a [`ConfigIndex`] once and use
+    /// [`ConfigIndex::query`].
+    #[must_use]
+    pub fn query(&self, query: &Query) -> Vec<QueryMatch<'_>> {
+        self.index().query(query)
+    }
+}
+
+impl<'a> ConfigIndex<'a> {
+    /// Find the indexed directives matching `query`, in file order
+    #[must_use]
+    pub fn query(&self, query: &Query) -> Vec<QueryMatch<'a>> {
+        // A name condition narrows the candidates to one lookup
+        let candidates: Vec<NodeId> = match query.predicates.first() {
+            Some(Predicate::Name(name)) => self.find(name),
+            _ => self.ids().collect(),
+        };
+        candidates
+            .into_iter()
+            .filter(|&id| query.matches(self, id))
+            .map(|id| {
+                let mut chain: Vec<NodeId> = self.ancestors(id).collect();
+                chain.reverse();
+                chain.push(id);
+                QueryMatch {
+                    id,
+                    directive: self.get(id),
+                    context: chain.into_iter().map(|n| label(self.get(n))).collect(),
+                    path: self.config_path(id),
+                }
+            })
+            .collect()
+    }
+}
+
+fn label(directive: &Directive) -> String {
+    std::iter::once(directive.name().to_string())
+        .chain(args(directive))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    const CONFIG: &str = r"
+http {
+    server {
+        server_name example.com;
+        location /api {
+            proxy_pass http://api;
+            proxy_set_header Host $host;
+        }
+        location ~* \.php$ { fastcgi_pass unix:/run/php.sock; }
+        location /static { root /srv; }
+    }
+}
+stream {
+    server { proxy_pass db:5432; }
+}
+";
+
+    fn lines(config: &Config, query: &Query) -> Vec<usize> {
+        config.query(query).iter().map(QueryMatch::line).collect()
+    }
+
+    #[test]
+    fn test_query_predicates() {
+        let config = parse(CONFIG).unwrap();
+
+        assert_eq!(lines(&config, &Query::name("proxy_pass")), vec![6, 14]);
+        assert_eq!(
+            lines(&config, &Query::name("proxy_pass").inside(["http"])),
+            vec![6]
+        );
+        assert_eq!(
+            lines(&config, &Query::name("location").inside(["server", "http"])),
+            Vec::<usize>::new()
+        );
+        assert_eq!(
+            lines(&config, &Query::new().arg_regex(r"\$$").unwrap()),
+            vec![9]
+        );
+        assert_eq!(lines(&config, &Query::new().arg("$host")), vec![7]);
+        assert_eq!(
+            lines(
+                &config,
+                &Query::name("location").without(Query::new().has_child(Query::name("proxy_pass")))
+            ),
+            vec![9, 10]
+        );
+        assert_eq!(
+            lines(
+                &config,
+                &Query::name("root").or(Query::name("fastcgi_pass"))
+            ),
+            vec![9, 10]
+        );
+        assert!(Query::new().arg_regex("(a)\\1").is_err());
+
+        let third = ConfigPath::parse("http/server/location[3]").unwrap();
+        assert_eq!(
+            lines(&config, &Query::new().under(third.clone())),
+            vec![10, 10]
+        );
+        assert_eq!(lines(&config, &Query::name("root").under(third)), vec![10]);
+        assert_eq!(
+            Query::name("proxy_pass")
+                .under("stream".parse().unwrap())
+                .to_string(),
+            "name = proxy_pass and under stream"
+        );
+    }
+
+    #[test]
+    fn test_query_match_context() {
+        let config = parse(CONFIG).unwrap();
+        let query = Query::name("proxy_set_header");
+        let matches = config.query(&query);
+
+        assert_eq!(
+            matches[0].context,
+            vec![
+                "http",
+                "server",
+                "location /api",
+                "proxy_set_header Host $host"
+            ]
+        );
+        assert_eq!(matches[0].id, config.index().find("proxy_set_header")[0]);
+        assert_eq!(
+            matches[0].path.to_string(),
+            "http/server/location/proxy_set_header"
+        );
+        assert_eq!(
+            Query::name("location")
+                .inside(["http"])
+                .has_child(Query::name("root"))
+                .to_string(),
+            "name = location and inside http and has child (name = root)"
+        );
+    }
+}
@@ -0,0 +1,1607 @@
+//! Request routing
+//!
+//! [`route`] follows a URL through the configuration the way nginx does:
+//!
+//! 1. picks the `http` server listening on the port by `server_name`
+//!    (exact name, longest leading wildcard, longest trailing wildcard,
+//!    first matching regex, then the `default_server` or first server);
+//! 2. runs the server's `rewrite`/`return` directives;
+//! 3. finds the location (exact match, longest prefix, `^~`, then regex
+//!    locations in order, including nested locations) and runs its
+//!    `rewrite`/`return` directives, searching again after
+//!    `rewrite ... last`;
+//! 4. resolves the directives inherited by that location and what handles
+//!    the request: a proxied or `FastCGI` backend, a static file or a
+//!    `return`.
+//!
+//! Listen addresses are not compared (the address a name resolves to is
+//! unknown) and `if` blocks are not evaluated; both are noted in the
+//! [`Route::steps`]. Regular expressions are evaluated with a built-in
+//! matcher for the PCRE subset configurations use.
+//!
+//! [`replay`] routes a batch of requests, read from a HAR file or access
+//! log lines, into a decision table.
+//!
+//! # Examples
+//!
+//! ```
+//! use nginx_discovery::{parse, route::{route, Outcome}};
+//!
+//! let config = parse(r"
+//! http {
+//!     upstream api { server 10.0.0.1:8080; }
+//!     server {
+//!         listen 80;
+//!         server_name example.com;
+//!         root /var/www;
+//!         location /api/ { proxy_pass http://api/v2/; }
+//!     }
+//! }
+//! ")?;
+//! let route = route(&config, "http://example.com/api/users")?;
+//!
+//! assert_eq!(route.locations[0].pattern, "/api/");
+//! match route.outcome {
+//!     Outcome::Proxy { uri, servers, .. } => {
+//!         assert_eq!(uri.as_deref(), Some("/v2/users"));
+//!         assert_eq!(servers, vec!["10.0.0.1:8080"]);
+//!     }
+//!     other => panic!("unexpected {other:?}"),
+//! }
+//! # Ok::<(), nginx_discovery::Error>(())
+//! ```
+
+pub(crate) mod regex;
+mod replay;
+
+use crate::ast::{Config, ConfigIndex, ConfigPath, Directive, Value};
+use crate::error::{Error, Result};
+use crate::extract;
+use crate::lint::walk;
+use crate::types::{self, ListenDirective, LocationModifier};
+use regex::Regex;
+#[cfg(feature = "serde")]
+pub use replay::requests_from_har;
+pub use replay::{replay, requests_from_log, RouteDecision};
+use std::fmt;
+
+/// nginx stops after this many URI changes (`rewrite ... last` cycles)
+const MAX_CYCLES: usize = 10;
+
+/// Handlers that are not inherited by nested blocks
+const CONTENT_HANDLERS: &[&str] = &[
+    "proxy_pass",
+    "fastcgi_pass",
+    "grpc_pass",
+    "uwsgi_pass",
+    "scgi_pass",
+    "memcached_pass",
+];
+
+/// Directives reported as steps or as the matched server
+const NOT_APPLIED: &[&str] = &[
+    "rewrite",
+    "return",
+    "set",
+    "break",
+    "if",
+    "listen",
+    "server_name",
+];
+
+/// Directives that apply only in the block handling the request
+const LOCAL: &[&str] = &["try_files", "alias", "internal"];
+
+/// A request URL split into the parts routing looks at
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RequestUrl {
+    /// `http` or `https`
+    pub scheme: String,
+
+    /// Host name, lowercase
+    pub host: String,
+
+    /// Port, defaulting to 80 or 443 by scheme
+    pub port: u16,
+
+    /// Decoded path with merged slashes and `.` and `..` segments resolved
+    pub path: String,
+
+    /// Query string without the `?`
+    pub query: Option<String>,
+}
+
+impl std::str::FromStr for RequestUrl {
+    type Err = Error;
+
+    fn from_str(url: &str) -> Result<Self> {
+        let invalid = |reason: &str| Error::InvalidInput(format!("Invalid URL '{url}': {reason}"));
+
+        let (scheme, rest) = url.split_once("://").unwrap_or(("http", url));
+        let scheme = scheme.to_ascii_lowercase();
+        let default_port = match scheme.as_str() {
+            "http" => 80,
+            "https" => 443,
+            _ => return Err(invalid("expected an http or https URL")),
+        };
+
+        let (authority, target) = match rest.find(['/', '?']) {
+            Some(i) => rest.split_at(i),
+            None => (rest, "/"),
+        };
+        let authority = authority.rsplit('@').next().unwrap_or_default();
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) if !port.contains(']') => {
+                (host, port.parse().map_err(|_| invalid("invalid port"))?)
+            }
+            _ => (authority, default_port),
+        };
+        if host.is_empty() {
+            return Err(invalid("missing host"));
+        }
+
+        let target = target.split('#').next().unwrap_or_default();
+        let (path, query) = match target.split_once('?') {
+            Some((path, query)) => (path, Some(query.to_string())),
+            None => (target, None),
+        };
+        let path = if path.is_empty() { "/" } else { path };
+
+        Ok(Self {
+            scheme,
+            host: host.trim_end_matches('.').to_ascii_lowercase(),
+            port,
+            path: normalize_path(path).ok_or_else(|| invalid("the path climbs above \"/\""))?,
+            query,
+        })
+    }
+}
+
+impl fmt::Display for RequestUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}://{}:{}{}",
+            self.scheme, self.host, self.port, self.path
+        )?;
+        if let Some(query) = &self.query {
+            write!(f, "?{query}")?;
+        }
+        Ok(())
+    }
+}
+
+/// A request to route, given by host and path
+///
+/// Parses from a URL (`https://example.com:8443/api?x=1`) or from a host
+/// and path without scheme (`example.com/api`).
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::route::SimulatedRequest;
+///
+/// let request: SimulatedRequest = "https://example.com/api".parse()?;
+/// assert_eq!(request, SimulatedRequest::new("example.com", "/api").https());
+/// assert_eq!(request.url(), "https://example.com/api");
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SimulatedRequest {
+    /// Host, with the port when it is not the scheme's default
+    pub host: String,
+
+    /// Path, with the query string if any
+    pub path: String,
+
+    /// Whether the request is made over https
+    pub https: bool,
+}
+
+impl SimulatedRequest {
+    /// An http request for `path` on `host`
+    #[must_use]
+    pub fn new(host: impl Into<String>, path: impl Into<String>) -> Self {
+        Self {
+            host: host.into(),
+            path: path.into(),
+            https: false,
+        }
+    }
+
+    /// Make the request over https
+    #[must_use]
+    pub fn https(mut self) -> Self {
+        self.https = true;
+        self
+    }
+
+    /// The request as a URL
+    #[must_use]
+    pub fn url(&self) -> String {
+        let scheme = if self.https { "https" } else { "http" };
+        let slash = if self.path.starts_with('/') { "" } else { "/" };
+        format!("{scheme}://{}{slash}{}", self.host, self.path)
+    }
+}
+
+impl std::str::FromStr for SimulatedRequest {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let url: RequestUrl = s.trim().parse()?;
+        let https = url.scheme == "https";
+        let default_port = if https { 443 } else { 80 };
+        let host = if url.port == default_port {
+            url.host
+        } else {
+            format!("{}:{}", url.host, url.port)
+        };
+        let path = match url.query {
+            Some(query) => format!("{}?{query}", url.path),
+            None => url.path,
+        };
+        Ok(Self { host, path, https })
+    }
+}
+
+/// The server chosen for a request
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MatchedServer {
+    /// First `server_name` of the server (`_` if none)
+    pub name: String,
+
+    /// Line of the `server` directive
+    pub line: usize,
+
+    /// Why the server was chosen, e.g. `wildcard name *.example.com`
+    pub matched_by: String,
+}
+
+/// A location on the path to the one handling the request
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MatchedLocation {
+    /// Modifier and path as written, e.g. `~ \.php$`
+    pub pattern: String,
+
+    /// Line of the `location` directive
+    pub line: usize,
+}
+
+/// A directive that applies to the request
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AppliedDirective {
+    /// Directive name
+    pub name: String,
+
+    /// Arguments
+    pub args: Vec<String>,
+
+    /// Line of the directive
+    pub line: usize,
+
+    /// Path of the directive, in `http`, the `server` or a `location`
+    pub path: ConfigPath,
+}
+
+/// A routing decision, in the order nginx makes them
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RouteStep {
+    /// Line of the directive behind the step, if any
+    pub line: Option<usize>,
+
+    /// What happened
+    pub message: String,
+}
+
+/// When a header directive takes effect
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum HeaderPhase {
+    /// Set on the request passed to the backend (`proxy_set_header`,
+    /// `grpc_set_header`)
+    Request,
+    /// Added to the response sent to the client (`add_header`,
+    /// `add_trailer`)
+    Response,
+}
+
+/// A header directive in effect for a request
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HeaderRule {
+    /// Header name as written
+    pub name: String,
+
+    /// Value, with variables kept as `$name`
+    pub value: String,
+
+    /// When the header is set
+    pub phase: HeaderPhase,
+
+    /// `always`: the response header is also sent with error responses
+    pub always: bool,
+
+    /// Directive setting it
+    pub directive: String,
+
+    /// Line of the directive
+    pub line: usize,
+
+    /// Path of the directive, in `http`, the `server` or a `location`
+    pub path: ConfigPath,
+}
+
+impl HeaderRule {
+    /// Whether the header is sent with a response of this status
+    ///
+    /// Without `always`, nginx adds response headers only to 200, 201,
+    /// 204, 206, 301, 302, 303, 304, 307 and 308 responses.
+    #[must_use]
+    pub fn applies_to_status(&self, status: u16) -> bool {
+        self.phase == HeaderPhase::Request
+            || self.always
+            || matches!(status, 200 | 201 | 204 | 206 | 301..=304 | 307 | 308)
+    }
+}
+
+/// What finally handles the request
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "lowercase"))]
+pub enum Outcome {
+    /// No server listens on the port
+    NoServer,
+
+    /// Passed to a backend
+    Proxy {
+        /// Handler directive (`proxy_pass`, `fastcgi_pass`, ...)
+        handler: String,
+        /// Handler argument
+        target: String,
+        /// Upstream block the target names, if any
+        upstream: Option<String>,
+        /// Servers of that upstream
+        servers: Vec<String>,
+        /// URI sent to the backend, `None` when it depends on variables
+        uri: Option<String>,
+    },
+
+    /// Served from the filesystem
+    Static {
+        /// Effective `root` or `alias`
+        root: String,
+        /// File the URI maps to
+        path: String,
+        /// `try_files` arguments, which make the result depend on the
+        /// filesystem
+        try_files: Option<Vec<String>>,
+    },
+
+    /// Answered by `return` with a status and optional body
+    Return {
+        /// Status code
+        code: u16,
+        /// Response body
+        body: Option<String>,
+    },
+
+    /// Answered by a redirect (`return 301 ...`, `rewrite ... permanent`)
+    Redirect {
+        /// Status code
+        code: u16,
+        /// Redirect target
+        location: String,
+    },
+
+    /// Routing could not be completed
+    Unresolved {
+        /// Why
+        reason: String,
+    },
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoServer => write!(f, "no server listens on the port"),
+            Self::Proxy {
+                handler,
+                target,
+                uri,
+                ..
+            } => {
+                write!(f, "{handler} {target}")?;
+                if let Some(uri) = uri {
+                    write!(f, " (URI {uri})")?;
+                }
+                Ok(())
+            }
+            Self::Static { path, .. } => write!(f, "file {path}"),
+            Self::Return { code, body } => match body {
+                Some(body) => write!(f, "return {code} \"{body}\""),
+                None => write!(f, "return {code}"),
+            },
+            Self::Redirect { code, location } => write!(f, "redirect {code} to {location}"),
+            Self::Unresolved { reason } => write!(f, "unresolved: {reason}"),
+        }
+    }
+}
+
+/// How a request is handled
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Route {
+    /// The request
+    pub url: RequestUrl,
+
+    /// Server handling it
+    pub server: Option<MatchedServer>,
+
+    /// Matched locations, outermost first; empty when no location matched
+    pub locations: Vec<MatchedLocation>,
+
+    /// URI after rewrites
+    pub uri: String,
+
+    /// Query string after rewrites
+    pub args: Option<String>,
+
+    /// Routing decisions
+    pub steps: Vec<RouteStep>,
+
+    /// Directives in effect, outermost block first
+    pub directives: Vec<AppliedDirective>,
+
+    /// What handles the request
+    pub outcome: Outcome,
+}
+
+impl Route {
+    /// Effective directives with this name
+    pub fn directives_named<'a>(
+        &'a self,
+        name: &'a str,
+    ) -> impl Iterator<Item = &'a AppliedDirective> + 'a {
+        self.directives.iter().filter(move |d| d.name == name)
+    }
+
+    /// Response headers added with `add_header`, as name and value
+    #[must_use]
+    pub fn response_headers(&self) -> Vec<(String, String)> {
+        self.header_pairs("add_header")
+    }
+
+    /// Request headers set for the backend with `proxy_set_header`
+    #[must_use]
+    pub fn upstream_headers(&self) -> Vec<(String, String)> {
+        if !matches!(&self.outcome, Outcome::Proxy { handler, .. } if handler == "proxy_pass") {
+            return Vec::new();
+        }
+        self.header_pairs("proxy_set_header")
+    }
+
+    /// Header directives in effect, with the phase they apply in
+    ///
+    /// Request headers are set when the request is passed to the backend,
+    /// after rewrites, so their values see rewritten variables. Response
+    /// headers are added to the response, and without `always` only to
+    /// successful and redirect responses.
+    #[must_use]
+    pub fn header_rules(&self) -> Vec<HeaderRule> {
+        self.directives
+            .iter()
+            .filter_map(|d| {
+                let phase = match d.name.as_str() {
+                    "proxy_set_header" | "grpc_set_header" => HeaderPhase::Request,
+                    "add_header" | "add_trailer" => HeaderPhase::Response,
+                    _ => return None,
+                };
+                Some(HeaderRule {
+                    name: d.args.first()?.clone(),
+                    value: d.args.get(1)?.clone(),
+                    phase,
+                    always: d.args.get(2).is_some_and(|a| a == "always"),
+                    directive: d.name.clone(),
+                    line: d.line,
+                    path: d.path.clone(),
+                })
+            })
+            .collect()
+    }
+
+    fn header_pairs(&self, name: &str) -> Vec<(String, String)> {
+        self.directives_named(name)
+            .filter_map(|d| Some((d.args.first()?.clone(), d.args.get(1)?.clone())))
+            .collect()
+    }
+
+    fn step(&mut self, directive: Option<&Directive>, message: impl Into<String>) {
+        self.steps.push(RouteStep {
+            line: directive.map(|d| d.span.line),
+            message: message.into(),
+        });
+    }
+}
+
+/// Follow `url` through the configuration.
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidInput`] if `url` is not an http or https URL,
+/// or if its path climbs above `/` (`/../etc/passwd`), which nginx rejects
+/// with 400 Bad Request.
+pub fn route(config: &Config, url: &str) -> Result<Route> {
+    let url: RequestUrl = url.parse()?;
+    let mut route = Route {
+        uri: url.path.clone(),
+        args: url.query.clone(),
+        url,
+        server: None,
+        locations: Vec::new(),
+        steps: Vec::new(),
+        directives: Vec::new(),
+        outcome: Outcome::NoServer,
+    };
+
+    let servers = http_servers(config);
+    let mut warnings = Vec::new();
+    for server in &servers {
+        for listen in server.directive.find_children("listen") {
+            extract::parse_listen(listen, &mut warnings);
+        }
+    }
+    for warning in warnings {
+        route.steps.push(RouteStep {
+            line: Some(warning.span.line),
+            message: format!("listen: {}", warning.message),
+        });
+    }
+    let Some((server, matched_by)) = select_server(&servers, &route.url) else {
+        route.step(
+            None,
+            format!("no server listens on port {}", route.url.port),
+        );
+        return Ok(route);
+    };
+    route.server = Some(MatchedServer {
+        name: types::Server::primary_name_of(server.directive),
+        line: server.directive.span.line,
+        matched_by: matched_by.clone(),
+    });
+    route.step(
+        Some(server.directive),
+        format!("server selected by {matched_by} (listen addresses are not compared)"),
+    );
+
+    let mut chain: Vec<&Directive> = Vec::new();
+    let mut outcome = match rewrites(server.directive, &mut route) {
+        Rewrite::Done(outcome) => Some(outcome),
+        _ => None,
+    };
+
+    let mut cycles = 0;
+    while outcome.is_none() {
+        chain = find_location(server.directive, &route.uri);
+        match chain.last() {
+            Some(location) => route.step(
+                Some(location),
+                format!(
+                    "location {} matches {}",
+                    text_args(location).join(" "),
+                    route.uri
+                ),
+            ),
+            None => route.step(None, format!("no location matches {}", route.uri)),
+        }
+
+        let Some(location) = chain.last() else { break };
+        match rewrites(location, &mut route) {
+            Rewrite::Done(done) => outcome = Some(done),
+            Rewrite::Last if cycles < MAX_CYCLES => cycles += 1,
+            Rewrite::Last => {
+                outcome = Some(Outcome::Unresolved {
+                    reason: "rewrite or internal redirection cycle".to_string(),
+                });
+            }
+            Rewrite::Continue | Rewrite::Break => break,
+        }
+    }
+
+    route.locations = chain
+        .iter()
+        .map(|location| MatchedLocation {
+            pattern: text_args(location).join(" "),
+            line: location.span.line,
+        })
+        .collect();
+
+    // Innermost block first
+    let blocks: Vec<&Directive> = chain
+        .iter()
+        .rev()
+        .copied()
+        .chain(std::iter::once(server.directive))
+        .chain(server.parents.iter().rev().copied())
+        .collect();
+    route.directives = applied_directives(&config.index(), &blocks);
+    if let Some((header, outer, names)) = dropped_headers(&blocks) {
+        route.step(
+            Some(header),
+            format!(
+                "add_header replaces the headers set in {outer} instead of adding to them: {}",
+                names.join(", ")
+            ),
+        );
+    }
+    route.outcome = match outcome {
+        Some(outcome) => outcome,
+        None => handler(&blocks, chain.last().copied(), &route, config),
+    };
+    Ok(route)
+}
+
+/// Route a [`SimulatedRequest`]; see [`route`].
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidInput`] if the host or port is invalid.
+pub fn simulate(config: &Config, request: &SimulatedRequest) -> Result<Route> {
+    route(config, &request.url())
+}
+
+/// An `http` server and its enclosing blocks, outermost first
+struct Server<'a> {
+    directive: &'a Directive,
+    parents: Vec<&'a Directive>,
+}
+
+fn http_servers(config: &Config) -> Vec<Server<'_>> {
+    let mut servers = Vec::new();
+    walk(config, &mut |directive, parents| {
+        if directive.name() == "server"
+            && directive.is_block()
+            && parents.last().map(|p| p.name()) == Some("http")
+        {
+            servers.push(Server {
+                directive,
+                parents: parents.to_vec(),
+            });
+        }
+    });
+    servers
+}
+
+/// TCP listeners of a server; a server without `listen` gets port 80
+fn listeners(server: &Directive) -> Vec<ListenDirective> {
+    if server.find_children("listen").is_empty() {
+        return vec![ListenDirective::new("*", 80)];
+    }
+    extract::listens(server)
+        .into_iter()
+        .filter(|listen| !listen.is_udp())
+        .collect()
+}
+
+/// Pick the server for the request, with the reason
+fn select_server<'s, 'a>(
+    servers: &'s [Server<'a>],
+    url: &RequestUrl,
+) -> Option<(&'s Server<'a>, String)> {
+    let on_port: Vec<&Server> = servers
+        .iter()
+        .filter(|s| listeners(s.directive).iter().any(|l| l.port == url.port))
+        .collect();
+    let names = |server: &Server<'a>| -> Vec<String> {
+        server
+            .directive
+            .find_children("server_name")
+            .iter()
+            .flat_map(|d| text_args(d))
+            .collect()
+    };
+    let host = url.host.as_str();
+
+    // Exact names, then the longest leading and trailing wildcards
+    for server in &on_port {
+        if names(server).iter().any(|n| n.eq_ignore_ascii_case(host)) {
+            return Some((server, format!("exact name {host}")));
+        }
+    }
+    let wildcard = |leading: bool| {
+        on_port
+            .iter()
+            .flat_map(|server| names(server).into_iter().map(move |name| (*server, name)))
+            .filter(|(_, name)| wildcard_matches(name, host, leading))
+            .max_by_key(|(_, name)| name.len())
+    };
+    if let Some((server, name)) = wildcard(true).or_else(|| wildcard(false)) {
+        return Some((server, format!("wildcard name {name}")));
+    }
+
+    for server in &on_port {
+        for name in names(server) {
+            let Some(pattern) = name.strip_prefix('~') else {
+                continue;
+            };
+            if Regex::new(pattern, false).is_some_and(|regex| regex.is_match(host)) {
+                return Some((server, format!("regex name {name}")));
+            }
+        }
+    }
+
+    let port = url.port;
+    if let Some(server) = on_port.iter().find(|s| {
+        listeners(s.directive)
+            .iter()
+            .any(|l| l.port == port && l.default_server)
+    }) {
+        return Some((server, format!("default_server on port {port}")));
+    }
+    on_port
+        .first()
+        .map(|server| (*server, format!("first server on port {port}")))
+}
+
+/// Whether `*.example.com`/`.example.com` (leading) or `www.example.*`
+/// (trailing) matches `host`
+fn wildcard_matches(name: &str, host: &str, leading: bool) -> bool {
+    let name = name.to_ascii_lowercase();
+    if leading {
+        if let Some(suffix) = name.strip_prefix('*') {
+            return host.ends_with(suffix);
+        }
+        if let Some(domain) = name.strip_prefix('.') {
+            return host == domain || host.ends_with(&name);
+        }
+        false
+    } else {
+        name.strip_suffix('*')
+            .is_some_and(|prefix| prefix.ends_with('.') && host.starts_with(prefix))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Found {
+    None,
+    Exact,
+    Prefix,
+    Regex,
+}
+
+/// Locations handling `uri` inside `block`, outermost first
+pub(crate) fn find_location<'a>(block: &'a Directive, uri: &str) -> Vec<&'a Directive> {
+    let mut chain = Vec::new();
+    find_in(block, uri, &mut chain);
+    chain
+}
+
+fn find_in<'a>(block: &'a Directive, uri: &str, chain: &mut Vec<&'a Directive>) -> Found {
+    let start = chain.len();
+    let locations: Vec<(&Directive, LocationModifier, String)> = block
+        .find_children("location")
+        .into_iter()
+        .map(|location| {
+            let (modifier, path) = LocationModifier::from_args(&text_args(location));
+            (location, modifier, path)
+        })
+        .filter(|(_, _, path)| !path.starts_with('@'))
+        .collect();
+
+    if let Some((location, ..)) = locations
+        .iter()
+        .find(|(_, modifier, path)| *modifier == LocationModifier::Exact && path == uri)
+    {
+        chain.push(location);
+        return Found::Exact;
+    }
+
+    let prefix = locations
+        .iter()
+        .filter(|(_, modifier, path)| {
+            matches!(
+                modifier,
+                LocationModifier::None | LocationModifier::PrefixPriority
+            ) && uri.starts_with(path.as_str())
+        })
+        .max_by_key(|(_, _, path)| path.len());
+
+    let mut found = Found::None;
+    let mut regex_allowed = true;
+    if let Some((location, modifier, _)) = prefix {
+        chain.push(location);
+        found = Found::Prefix;
+        regex_allowed = *modifier != LocationModifier::PrefixPriority;
+        if let nested @ (Found::Exact | Found::Regex) = find_in(location, uri, chain) {
+            return nested;
+        }
+    }
+
+    if regex_allowed {
+        for (location, modifier, pattern) in &locations {
+            let case_insensitive = match modifier {
+                LocationModifier::Regex => false,
+                LocationModifier::RegexCaseInsensitive => true,
+                _ => continue,
+            };
+            if Regex::new(pattern, case_insensitive).is_some_and(|regex| regex.is_match(uri)) {
+                chain.truncate(start);
+                chain.push(location);
+                find_in(location, uri, chain);
+                return Found::Regex;
+            }
+        }
+    }
+
+    found
+}
+
+enum Rewrite {
+    /// No rewrite stopped processing
+    Continue,
+    /// `rewrite ... last`: search the location again
+    Last,
+    /// `rewrite ... break` or `break`: stay in the location
+    Break,
+    /// Answered by `return` or a redirect
+    Done(Outcome),
+}
+
+/// Run the rewrite module directives of `block` in order
+fn rewrites(block: &Directive, route: &mut Route) -> Rewrite {
+    for directive in block.children().unwrap_or_default() {
+        match directive.name() {
+            "rewrite" => {
+                if let Some(result) = rewrite(directive, route) {
+                    return result;
+                }
+            }
+            "return" => {
+                let outcome = return_outcome(directive, route);
+                route.step(Some(directive), format!("return: {outcome}"));
+                return Rewrite::Done(outcome);
+            }
+            "break" => {
+                route.step(Some(directive), "break stops the rewrite directives");
+                return Rewrite::Break;
+            }
+            "if" => route.step(
+                Some(directive),
+                format!("if ({}) is not evaluated", text_args(directive).join(" ")),
+            ),
+            _ => {}
+        }
+    }
+    Rewrite::Continue
+}
+
+/// Apply one `rewrite`; `None` when processing continues
+fn rewrite(directive: &Directive, route: &mut Route) -> Option<Rewrite> {
+    let args = text_args(directive);
+    let (pattern, replacement) = (args.first()?, args.get(1)?);
+    let flag = args.get(2).map(String::as_str);
+
+    let Some(regex) = Regex::new(pattern, false) else {
+        route.step(
+            Some(directive),
+            format!("rewrite {pattern} uses regex syntax that cannot be evaluated"),
+        );
+        return None;
+    };
+    let captures = regex.captures(&route.uri)?;
+
+    let expanded = expand(replacement, &regex, &captures, route);
+    let redirect = ["http://", "https://", "$scheme"]
+        .iter()
+        .any(|scheme| replacement.starts_with(scheme));
+    if redirect || matches!(flag, Some("redirect" | "permanent")) {
+        let code = if flag == Some("permanent") { 301 } else { 302 };
+        route.step(
+            Some(directive),
+            format!("rewrite redirects {} to {expanded}", route.uri),
+        );
+        return Some(Rewrite::Done(Outcome::Redirect {
+            code,
+            location: expanded,
+        }));
+    }
+
+    let (uri, args) = match expanded.split_once('?') {
+        Some((uri, "")) => (uri.to_string(), None),
+        Some((uri, new_args)) => {
+            let args = match (&route.args, replacement.ends_with('?')) {
+                (Some(old), false) => format!("{new_args}&{old}"),
+                _ => new_args.to_string(),
+            };
+            (uri.to_string(), Some(args))
+        }
+        None => (expanded, route.args.clone()),
+    };
+    route.step(
+        Some(directive),
+        format!(
+            "rewrite {} to {uri}{}",
+            route.uri,
+            flag.map(|f| format!(" ({f})")).unwrap_or_default()
+        ),
+    );
+    route.uri = uri;
+    route.args = args;
+
+    match flag {
+        Some("last") => Some(Rewrite::Last),
+        Some("break") => Some(Rewrite::Break),
+        _ => None,
+    }
+}
+
+/// Substitute captures and the request variables routing knows
+fn expand(template: &str, regex: &Regex, captures: &[Option<String>], route: &Route) -> String {
+    let mut out = String::new();
+    let mut rest = template;
+    while let Some(i) = rest.find('$') {
+        out.push_str(&rest[..i]);
+        rest = &rest[i + 1..];
+        let braced = rest.starts_with('{');
+        let body = if braced { &rest[1..] } else { rest };
+        let len = body
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(body.len());
+        let name = &body[..len];
+
+        let value = if let Ok(index) = name.parse::<usize>() {
+            Some(captures.get(index).cloned().flatten().unwrap_or_default())
+        } else if let Some(index) = regex.group_index(name) {
+            Some(captures.get(index).cloned().flatten().unwrap_or_default())
+        } else {
+            variable(name, route)
+        };
+
+        let consumed = if braced && body[len..].starts_with('}') {
+            len + 2
+        } else {
+            len
+        };
+        match value {
+            Some(value) if len > 0 => out.push_str(&value),
+            _ => {
+                out.push('$');
+                out.push_str(&rest[..consumed]);
+            }
+        }
+        rest = &rest[consumed..];
+    }
+    out.push_str(rest);
+    out
+}
+
+fn variable(name: &str, route: &Route) -> Option<String> {
+    let args = route.args.clone().unwrap_or_default();
+    Some(match name {
+        "uri" | "document_uri" => route.uri.clone(),
+        "request_uri" => {
+            let mut uri = route.url.path.clone();
+            if let Some(query) = &route.url.query {
+                uri = format!("{uri}?{query}");
+            }
+            uri
+        }
+        "args" | "query_string" => args,
+        "is_args" => if args.is_empty() { "" } else { "?" }.to_string(),
+        "host" | "server_name" => route.url.host.clone(),
+        "scheme" => route.url.scheme.clone(),
+        "server_port" => route.url.port.to_string(),
+        _ => return None,
+    })
+}
+
+fn return_outcome(directive: &Directive, route: &Route) -> Outcome {
+    let args = text_args(directive);
+    let empty = Regex::new("", false).expect("empty pattern compiles");
+    let text = |i: usize| args.get(i).map(|a| expand(a, &empty, &[], route));
+
+    match args.first().map(|a| a.parse::<u16>()) {
+        Some(Ok(code)) if matches!(code, 301 | 302 | 303 | 307 | 308) => Outcome::Redirect {
+            code,
+            location: text(1).unwrap_or_default(),
+        },
+        Some(Ok(code)) => Outcome::Return {
+            code,
+            body: text(1),
+        },
+        // return URL;
+        _ => Outcome::Redirect {
+            code: 302,
+            location: text(0).unwrap_or_default(),
+        },
+    }
+}
+
+/// Directives in effect, given the blocks innermost first
+fn applied_directives(index: &ConfigIndex<'_>, blocks: &[&Directive]) -> Vec<AppliedDirective> {
+    let mut applied = Vec::new();
+    let mut seen: Vec<&str> = Vec::new();
+
+    for (depth, block) in blocks.iter().enumerate() {
+        let own: Vec<&Directive> = block
+            .children()
+            .unwrap_or_default()
+            .iter()
+            .filter(|d| !d.is_block())
+            .collect();
+
+        let mut names: Vec<&str> = Vec::new();
+        for directive in own {
+            let name = directive.name();
+            let local = LOCAL.contains(&name) || CONTENT_HANDLERS.contains(&name);
+            if seen.contains(&name) || NOT_APPLIED.contains(&name) || (local && depth > 0) {
+                continue;
+            }
+            if !names.contains(&name) {
+                names.push(name);
+            }
+            applied.push(AppliedDirective {
+                name: name.to_string(),
+                args: text_args(directive),
+                line: directive.span.line,
+                path: index
+                    .id_of(directive)
+                    .map(|id| index.config_path(id))
+                    .unwrap_or_default(),
+            });
+        }
+        seen.extend(names);
+    }
+
+    applied.sort_by_key(|d| d.line);
+    applied
+}
+
+/// The `add_header` of the innermost block that has any, the next block
+/// out with headers of its own and the names it no longer sends
+///
+/// nginx inherits `add_header` only into blocks that define none.
+fn dropped_headers<'a>(blocks: &[&'a Directive]) -> Option<(&'a Directive, String, Vec<String>)> {
+    let mut with_headers = blocks
+        .iter()
+        .map(|block| (*block, block.find_children("add_header")))
+        .filter(|(_, headers)| !headers.is_empty());
+    let (_, own) = with_headers.next()?;
+    let (outer, inherited) = with_headers.next()?;
+
+    let own_names: Vec<String> = own
+        .iter()
+        .filter_map(|h| first_text(h).map(|n| n.to_ascii_lowercase()))
+        .collect();
+    let mut names: Vec<String> = Vec::new();
+    for name in inherited.iter().filter_map(|h| first_text(h)) {
+        if !own_names.contains(&name.to_ascii_lowercase()) && !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    if names.is_empty() {
+        return None;
+    }
+    let context = match outer.name() {
+        "location" => format!("location {}", text_args(outer).join(" ")),
+        name => name.to_string(),
+    };
+    Some((own[0], context, names))
+}
+
+/// What handles a request that no `return` answered
+fn handler(
+    blocks: &[&Directive],
+    location: Option<&Directive>,
+    route: &Route,
+    config: &Config,
+) -> Outcome {
+    let handling = blocks[0];
+    if let Some(pass) = CONTENT_HANDLERS
+        .iter()
+        .find_map(|name| handling.find_children(name).last().copied())
+    {
+        return proxy(pass, location, route, config);
+    }
+
+    let effective = |name: &str| {
+        blocks
+            .iter()
+            .find_map(|block| block.find_children(name).last().copied())
+            .and_then(first_text)
+    };
+    let try_files = handling
+        .find_children("try_files")
+        .last()
+        .map(|d| text_args(d));
+
+    let mut path = route.uri.clone();
+    if path.ends_with('/') {
+        let index = effective("index").unwrap_or_else(|| "index.html".to_string());
+        path.push_str(&index);
+    }
+
+    let alias = handling
+        .find_children("alias")
+        .last()
+        .copied()
+        .and_then(first_text);
+    let (root, file) = if let (Some(alias), Some(location)) = (alias, location) {
+        // The alias of a regex location is the whole path
+        let rest = location_prefix(location)
+            .and_then(|prefix| path.strip_prefix(prefix.as_str()).map(str::to_string))
+            .unwrap_or_default();
+        (alias.clone(), format!("{alias}{rest}"))
+    } else {
+        let root = effective("root").unwrap_or_else(|| "html".to_string());
+        let file = format!("{}{path}", root.trim_end_matches('/'));
+        (root, file)
+    };
+
+    Outcome::Static {
+        root,
+        path: file,
+        try_files,
+    }
+}
+
+fn proxy(
+    pass: &Directive,
+    location: Option<&Directive>,
+    route: &Route,
+    config: &Config,
+) -> Outcome {
+    let target = first_text(pass).unwrap_or_default();
+    let without_scheme = target.split_once("://").map_or(target.as_str(), |(_, r)| r);
+    let (authority, uri_part) = match without_scheme.find('/') {
+        Some(i) => without_scheme.split_at(i),
+        None => (without_scheme, ""),
+    };
+    let host = authority.split(':').next().unwrap_or_default();
+
+    let upstream = config
+        .find_directives_recursive("upstream")
+        .into_iter()
+        .find(|u| u.first_arg().as_deref() == Some(host));
+    let servers = upstream
+        .map(|u| {
+            u.find_children("server")
+                .iter()
+                .filter_map(|s| s.first_arg())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    // A URI in proxy_pass replaces the matched prefix of the location
+    let uri = if target.contains('$') || pass.name() != "proxy_pass" {
+        None
+    } else if uri_part.is_empty() {
+        Some(route.uri.clone())
+    } else {
+        let prefix = location.and_then(location_prefix).unwrap_or_default();
+        let rest = route
+            .uri
+            .strip_prefix(prefix.as_str())
+            .unwrap_or(&route.uri);
+        Some(format!("{uri_part}{rest}"))
+    };
+
+    Outcome::Proxy {
+        handler: pass.name().to_string(),
+        target: target.clone(),
+        upstream: upstream.map(|_| host.to_string()),
+        servers,
+        uri: uri.map(|uri| match &route.args {
+            Some(args) => format!("{uri}?{args}"),
+            None => uri,
+        }),
+    }
+}
+
+/// Path of a prefix or exact location, `None` for regex locations
+fn location_prefix(location: &Directive) -> Option<String> {
+    let (modifier, path) = LocationModifier::from_args(&text_args(location));
+    matches!(
+        modifier,
+        LocationModifier::None | LocationModifier::PrefixPriority | LocationModifier::Exact
+    )
+    .then_some(path)
+}
+
+/// Arguments with variables kept as `$name`
+fn text_args(directive: &Directive) -> Vec<String> {
+    directive.args().iter().map(Value::unquoted).collect()
+}
+
+fn first_text(directive: &Directive) -> Option<String> {
+    directive.args().first().map(Value::unquoted)
+}
+
+/// Decode `%XX` escapes, merge repeated slashes and resolve `.` and `..`
+/// segments, as nginx does before matching locations
+///
+/// Segments are resolved after decoding, so `%2e%2e` is `..` as well.
+/// Returns `None` for a path climbing above `/`.
+fn normalize_path(path: &str) -> Option<String> {
+    let hex = |byte: u8| char::from(byte).to_digit(16);
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = match bytes.get(i..i + 3) {
+            Some(&[b'%', high, low]) => hex(high).zip(hex(low)).map(|(h, l)| h << 4 | l),
+            _ => None,
+        };
+        if let Some(byte) = escaped.and_then(|byte| u8::try_from(byte).ok()) {
+            decoded.push(byte);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+
+    let decoded = String::from_utf8_lossy(&decoded);
+    let mut segments: Vec<&str> = Vec::new();
+    let mut directory = false;
+    for segment in decoded.split('/') {
+        directory = matches!(segment, "" | "." | "..");
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop()?;
+            }
+            _ => segments.push(segment),
+        }
+    }
+
+    let mut normalized = format!("/{}", segments.join("/"));
+    if directory && !segments.is_empty() {
+        normalized.push('/');
+    }
+    Some(normalized)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    const CONFIG: &str = r#"
+http {
+    upstream backend { server 10.0.0.1:8080; server 10.0.0.2:8080 backup; }
+    add_header X-Frame-Options DENY;
+    proxy_set_header Host $host;
+    server {
+        listen 80 default_server;
+        return 444;
+    }
+    server {
+        listen 80;
+        server_name example.com *.example.com;
+        root /var/www;
+        rewrite ^/old/(.*)$ /new/$1 permanent;
+        location / {
+            try_files $uri $uri/ /index.html;
+        }
+        location /api/ {
+            add_header X-Api 1;
+            proxy_pass http://backend/v2/;
+            location ~ ^/api/admin { return 403 "forbidden"; }
+        }
+        location ^~ /static/ {
+            alias /srv/static/;
+        }
+        location ~* \.(png|jpg)$ {
+            expires 30d;
+        }
+        location /legacy/ {
+            rewrite ^/legacy/(\w+) /api/$1 last;
+        }
+    }
+}
+"#;
+
+    fn route_of(url: &str) -> Route {
+        route(&parse(CONFIG).unwrap(), url).unwrap()
+    }
+
+    #[test]
+    fn test_request_url() {
+        let url: RequestUrl = "https://Example.COM:8443//a/%62?x=1#top".parse().unwrap();
+        assert_eq!(url.scheme, "https");
+        assert_eq!(url.host, "example.com");
+        assert_eq!(url.port, 8443);
+        assert_eq!(url.path, "/a/b");
+        assert_eq!(url.query.as_deref(), Some("x=1"));
+
+        let url: RequestUrl = "example.com".parse().unwrap();
+        assert_eq!((url.port, url.path.as_str()), (80, "/"));
+        assert!("ftp://example.com/".parse::<RequestUrl>().is_err());
+    }
+
+    #[test]
+    fn test_path_normalization() {
+        let path = |path: &str| {
+            format!("http://example.com{path}")
+                .parse::<RequestUrl>()
+                .map(|url| url.path)
+        };
+        assert_eq!(path("/static/../x.png").unwrap(), "/x.png");
+        assert_eq!(path("/a/./b//%2e%2E/c").unwrap(), "/a/c");
+        assert_eq!(path("/a/b/..").unwrap(), "/a/");
+        assert_eq!(path("/a/.").unwrap(), "/a/");
+        assert_eq!(path("/a/..").unwrap(), "/");
+        assert_eq!(path("/a/%2F..%2Fb").unwrap(), "/b");
+        assert_eq!(path("/..a/b..").unwrap(), "/..a/b..");
+
+        // Only `%` and two hex digits is an escape
+        assert_eq!(path("/%41%2").unwrap(), "/A%2");
+        assert_eq!(path("/%+1%-f%4g").unwrap(), "/%+1%-f%4g");
+
+        // Climbing above the root is a bad request
+        assert!(path("/../x").is_err());
+        assert!(path("/a/../../x").is_err());
+        assert!(path("/a/%2e%2e/%2E%2E/etc/passwd").is_err());
+        assert!(route(&parse(CONFIG).unwrap(), "http://example.com/..").is_err());
+    }
+
+    #[test]
+    fn test_simulated_request() {
+        let parsed = |s: &str| s.parse::<SimulatedRequest>().unwrap();
+
+        // The port is kept only when it is not the scheme's default
+        assert_eq!(parsed("example.com").host, "example.com");
+        assert_eq!(parsed("http://example.com:80/").host, "example.com");
+        assert_eq!(parsed("https://example.com:80/").host, "example.com:80");
+        assert_eq!(parsed("[::1]:8080/").host, "[::1]:8080");
+        assert_eq!(parsed("[::1]/").host, "[::1]");
+
+        let request = parsed("  https://example.com:8443/a?x=1#top ");
+        assert_eq!(request.path, "/a?x=1");
+        assert_eq!(request.url(), "https://example.com:8443/a?x=1");
+        assert_eq!(
+            SimulatedRequest::new("example.com", "api").url(),
+            "http://example.com/api"
+        );
+
+        assert!("ftp://example.com/".parse::<SimulatedRequest>().is_err());
+        assert!("example.com:http/".parse::<SimulatedRequest>().is_err());
+        let config = parse("http { server { listen 80; } }").unwrap();
+        assert!(simulate(&config, &SimulatedRequest::new("", "/")).is_err());
+        let route = simulate(&config, &SimulatedRequest::new("a.example", "/")).unwrap();
+        assert!(route.server.is_some());
+    }
+
+    #[test]
+    fn test_server_selection() {
+        let by = |url: &str| route_of(url).server.unwrap().matched_by;
+        assert_eq!(by("http://example.com/"), "exact name example.com");
+        assert_eq!(by("http://www.example.com/"), "wildcard name *.example.com");
+        assert_eq!(by("http://other.test/"), "default_server on port 80");
+
+        let route = route_of("http://other.test/");
+        assert_eq!(
+            route.outcome,
+            Outcome::Return {
+                code: 444,
+                body: None
+            }
+        );
+        assert_eq!(
+            route_of("http://example.com:8080/").outcome,
+            Outcome::NoServer
+        );
+    }
+
+    #[test]
+    fn test_listen_with_unknown_parameter() {
+        let config = parse(
+            r"http {
+                server { listen 80 default_server; server_name a.example; }
+                server { listen 80 default_server fast; server_name b.example; }
+              }",
+        )
+        .unwrap();
+        let route = route(&config, "http://b.example/").unwrap();
+
+        // The listener is kept without the parameter, not dropped
+        assert_eq!(route.server.unwrap().name, "b.example");
+        assert_eq!(route.steps[0].line, Some(3));
+        assert_eq!(
+            route.steps[0].message,
+            "listen: invalid listen parameter \"fast\"; the parameter is ignored"
+        );
+    }
+
+    #[test]
+    fn test_location_matching() {
+        let pattern = |url: &str| {
+            route_of(url)
+                .locations
+                .iter()
+                .map(|l| l.pattern.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(pattern("http://example.com/about"), vec!["/"]);
+        assert_eq!(
+            pattern("http://example.com/a/logo.PNG"),
+            vec![r"~* \.(png|jpg)$"]
+        );
+        // ^~ stops the regex search
+        assert_eq!(
+            pattern("http://example.com/static/x.png"),
+            vec!["^~ /static/"]
+        );
+        // Dot segments are resolved before matching
+        assert_eq!(
+            pattern("http://example.com/static/../x.png"),
+            vec![r"~* \.(png|jpg)$"]
+        );
+        assert_eq!(
+            pattern("http://example.com/api/%2E%2E/static/x.png"),
+            vec!["^~ /static/"]
+        );
+        assert_eq!(
+            pattern("http://example.com/api/admin/users"),
+            vec!["/api/", "~ ^/api/admin"]
+        );
+    }
+
+    #[test]
+    fn test_proxy_and_inheritance() {
+        let route = route_of("http://example.com/api/users?page=2");
+        assert_eq!(
+            route.outcome,
+            Outcome::Proxy {
+                handler: "proxy_pass".to_string(),
+                target: "http://backend/v2/".to_string(),
+                upstream: Some("backend".to_string()),
+                servers: vec!["10.0.0.1:8080".to_string(), "10.0.0.2:8080".to_string()],
+                uri: Some("/v2/users?page=2".to_string()),
+            }
+        );
+        // add_header in the location replaces the inherited one
+        assert_eq!(
+            route.response_headers(),
+            vec![("X-Api".to_string(), "1".to_string())]
+        );
+        assert_eq!(
+            route.upstream_headers(),
+            vec![("Host".to_string(), "$host".to_string())]
+        );
+        let root: Vec<_> = route.directives_named("root").collect();
+        assert_eq!(
+            (root[0].line, root[0].path.to_string()),
+            (13, "http/server[2]/root".to_string())
+        );
+
+        let rules = route.header_rules();
+        assert_eq!(rules[0].phase, HeaderPhase::Request);
+        assert_eq!(
+            (rules[1].name.as_str(), rules[1].phase),
+            ("X-Api", HeaderPhase::Response)
+        );
+        assert!(rules[1].applies_to_status(200));
+        assert!(!rules[1].applies_to_status(502));
+        assert!(route.steps.iter().any(|s| s.message
+            == "add_header replaces the headers set in http instead of adding to them: \
+                X-Frame-Options"));
+    }
+
+    #[test]
+    fn test_header_rule_phases() {
+        let config = crate::parse(
+            r"
+http {
+    server {
+        listen 80;
+        location / {
+            grpc_pass grpc://backend;
+            grpc_set_header X-Trace $request_id;
+            add_header X-Error 1 always;
+            add_trailer X-Checksum $upstream_trailer_checksum;
+            add_header X-Incomplete;
+        }
+    }
+}
+",
+        )
+        .unwrap();
+        let route = route(&config, "http://example.com/").unwrap();
+        let rules = route.header_rules();
+
+        // add_header without a value is not a rule
+        let names: Vec<_> = rules
+            .iter()
+            .map(|r| (r.directive.as_str(), r.name.as_str(), r.phase))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("grpc_set_header", "X-Trace", HeaderPhase::Request),
+                ("add_header", "X-Error", HeaderPhase::Response),
+                ("add_trailer", "X-Checksum", HeaderPhase::Response),
+            ]
+        );
+        assert!(rules[0].applies_to_status(502));
+        assert!(rules[1].always);
+        assert!(rules[1].applies_to_status(502));
+        assert!(!rules[2].always);
+        for status in [200, 201, 204, 206, 301, 302, 303, 304, 307, 308] {
+            assert!(rules[2].applies_to_status(status), "{status}");
+        }
+        for status in [100, 202, 305, 400, 404, 500] {
+            assert!(!rules[2].applies_to_status(status), "{status}");
+        }
+        assert_eq!(
+            rules[0].path.to_string(),
+            "http/server/location/grpc_set_header"
+        );
+    }
+
+    #[test]
+    fn test_static_files() {
+        let route = route_of("http://example.com/docs/");
+        assert_eq!(
+            route.outcome,
+            Outcome::Static {
+                root: "/var/www".to_string(),
+                path: "/var/www/docs/index.html".to_string(),
+                try_files: Some(vec![
+                    "$uri".to_string(),
+                    "$uri/".to_string(),
+                    "/index.html".to_string()
+                ]),
+            }
+        );
+        assert_eq!(
+            route.response_headers(),
+            vec![("X-Frame-Options".to_string(), "DENY".to_string())]
+        );
+
+        match route_of("http://example.com/static/css/site.css").outcome {
+            Outcome::Static { path, .. } => assert_eq!(path, "/srv/static/css/site.css"),
+            other => panic!("unexpected {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_rewrites() {
+        assert_eq!(
+            route_of("http://example.com/old/page").outcome,
+            Outcome::Redirect {
+                code: 301,
+                location: "/new/page".to_string()
+            }
+        );
+
+        let route = route_of("http://example.com/legacy/users");
+        assert_eq!(route.uri, "/api/users");
+        assert_eq!(route.locations[0].pattern, "/api/");
+        let lines: Vec<_> = route.steps.iter().filter_map(|s| s.line).collect();
+        assert_eq!(lines, vec![10, 29, 30, 18, 19]);
+
+        assert_eq!(
+            route_of("http://example.com/api/admin").outcome,
+            Outcome::Return {
+                code: 403,
+                body: Some("forbidden".to_string())
+            }
+        );
+    }
+
+    #[test]
+    fn test_rewrite_cycle() {
+        let config = parse("http { server { location / { rewrite ^ /x last; } } }").unwrap();
+        let route = route(&config, "http://localhost/").unwrap();
+        assert!(matches!(route.outcome, Outcome::Unresolved { .. }));
+    }
+}
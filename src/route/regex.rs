@@ -0,0 +1,572 @@
+//! Backtracking matcher for the PCRE subset used in configurations
+//!
+//! Supports literals, `.`, character classes (with ranges, negation, POSIX
+//! names and the `\d \w \s` shorthands), anchors, `\b`, capturing,
+//! non-capturing and named groups, alternation, greedy and lazy
+//! quantifiers and a leading `(?i)`. Backreferences and lookaround are not
+//! supported: [`Regex::new`] returns `None` for them.
+
+/// Capture group positions (character offsets), group 0 is the match
+pub(crate) type Captures = Vec<Option<(usize, usize)>>;
+
+#[derive(Debug, Clone)]
+enum Node {
+    Char(char),
+    Any,
+    Class {
+        items: Vec<ClassItem>,
+        negated: bool,
+    },
+    Start,
+    End,
+    WordBoundary(bool),
+    Group {
+        alternatives: Vec<Vec<Node>>,
+        index: Option<usize>,
+    },
+    Repeat {
+        node: Box<Node>,
+        min: usize,
+        max: Option<usize>,
+        greedy: bool,
+    },
+}
+
+#[derive(Debug, Clone)]
+enum ClassItem {
+    Range(char, char),
+    Digit(bool),
+    Word(bool),
+    Space(bool),
+}
+
+/// A compiled pattern
+#[derive(Debug, Clone)]
+pub(crate) struct Regex {
+    alternatives: Vec<Vec<Node>>,
+    groups: usize,
+    names: Vec<(String, usize)>,
+    case_insensitive: bool,
+}
+
+impl Regex {
+    /// Compile `pattern`, `None` if it uses unsupported syntax
+    pub(crate) fn new(pattern: &str, case_insensitive: bool) -> Option<Self> {
+        let mut chars: Vec<char> = pattern.chars().collect();
+        let mut case_insensitive = case_insensitive;
+        if chars.starts_with(&['(', '?', 'i', ')']) {
+            case_insensitive = true;
+            chars.drain(..4);
+        }
+
+        let mut parser = Parser {
+            chars: &chars,
+            pos: 0,
+            groups: 0,
+            names: Vec::new(),
+        };
+        let alternatives = parser.alternatives()?;
+        if parser.pos != chars.len() {
+            return None;
+        }
+        Some(Self {
+            alternatives,
+            groups: parser.groups,
+            names: parser.names,
+            case_insensitive,
+        })
+    }
+
+    /// Capture group of a name
+    pub(crate) fn group_index(&self, name: &str) -> Option<usize> {
+        self.names
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, index)| *index)
+    }
+
+    /// Find the first match in `text`
+    pub(crate) fn captures(&self, text: &str) -> Option<Vec<Option<String>>> {
+        let chars: Vec<char> = text.chars().collect();
+        let matcher = Matcher {
+            text: &chars,
+            case_insensitive: self.case_insensitive,
+        };
+        let root = Node::Group {
+            alternatives: self.alternatives.clone(),
+            index: Some(0),
+        };
+
+        for start in 0..=chars.len() {
+            let mut caps: Captures = vec![None; self.groups + 1];
+            if matcher.match_here(
+                std::slice::from_ref(&root),
+                start,
+                &mut caps,
+                &mut |_, _| true,
+            ) {
+                return Some(
+                    caps.into_iter()
+                        .map(|cap| cap.map(|(s, e)| chars[s..e].iter().collect()))
+                        .collect(),
+                );
+            }
+        }
+        None
+    }
+
+    /// Whether the pattern matches anywhere in `text`
+    pub(crate) fn is_match(&self, text: &str) -> bool {
+        self.captures(text).is_some()
+    }
+}
+
+struct Parser<'a> {
+    chars: &'a [char],
+    pos: usize,
+    groups: usize,
+    names: Vec<(String, usize)>,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn alternatives(&mut self) -> Option<Vec<Vec<Node>>> {
+        let mut alternatives = vec![self.sequence()?];
+        while self.peek() == Some('|') {
+            self.pos += 1;
+            alternatives.push(self.sequence()?);
+        }
+        Some(alternatives)
+    }
+
+    fn sequence(&mut self) -> Option<Vec<Node>> {
+        let mut nodes = Vec::new();
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            let atom = self.atom()?;
+            nodes.push(self.quantified(atom)?);
+        }
+        Some(nodes)
+    }
+
+    fn atom(&mut self) -> Option<Node> {
+        let c = self.peek()?;
+        self.pos += 1;
+        Some(match c {
+            '.' => Node::Any,
+            '^' => Node::Start,
+            '$' => Node::End,
+            '[' => self.class()?,
+            '(' => self.group()?,
+            '\\' => self.escape()?,
+            '*' | '+' | '?' => return None,
+            c => Node::Char(c),
+        })
+    }
+
+    fn group(&mut self) -> Option<Node> {
+        let mut index = None;
+        if self.peek() == Some('?') {
+            let rest: String = self.chars[self.pos..].iter().take(3).collect();
+            if rest.starts_with("?:") {
+                self.pos += 2;
+            } else if rest.starts_with("?<") || rest.starts_with("?P<") || rest.starts_with("?'") {
+                self.pos += if rest.starts_with("?P") { 3 } else { 2 };
+                let close = if rest.starts_with("?'") { '\'' } else { '>' };
+                let end = self.chars[self.pos..].iter().position(|c| *c == close)?;
+                let name: String = self.chars[self.pos..self.pos + end].iter().collect();
+                // (?<= and (?<! are lookbehind
+                if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                    return None;
+                }
+                self.pos += end + 1;
+                self.groups += 1;
+                self.names.push((name, self.groups));
+                index = Some(self.groups);
+            } else {
+                return None;
+            }
+        } else {
+            self.groups += 1;
+            index = Some(self.groups);
+        }
+
+        let alternatives = self.alternatives()?;
+        if self.peek() != Some(')') {
+            return None;
+        }
+        self.pos += 1;
+        Some(Node::Group {
+            alternatives,
+            index,
+        })
+    }
+
+    fn escape(&mut self) -> Option<Node> {
+        let c = self.peek()?;
+        self.pos += 1;
+        Some(match c {
+            'b' => Node::WordBoundary(true),
+            'B' => Node::WordBoundary(false),
+            'A' => Node::Start,
+            'z' | 'Z' => Node::End,
+            c => match shorthand(c) {
+                Some(item) => Node::Class {
+                    items: vec![item],
+                    negated: false,
+                },
+                None => Node::Char(escaped(c)?),
+            },
+        })
+    }
+
+    fn class(&mut self) -> Option<Node> {
+        let mut items = Vec::new();
+        let negated = self.peek() == Some('^');
+        if negated {
+            self.pos += 1;
+        }
+
+        let mut first = true;
+        loop {
+            let c = self.peek()?;
+            self.pos += 1;
+            let start = match c {
+                ']' if !first => break,
+                '[' if self.peek() == Some(':') => {
+                    let end = self.chars[self.pos..].iter().position(|c| *c == ']')?;
+                    let name: String = self.chars[self.pos + 1..self.pos + end - 1]
+                        .iter()
+                        .collect();
+                    self.pos += end + 1;
+                    items.extend(posix_class(&name)?);
+                    first = false;
+                    continue;
+                }
+                '\\' => {
+                    let e = self.peek()?;
+                    self.pos += 1;
+                    if let Some(item) = shorthand(e) {
+                        items.push(item);
+                        first = false;
+                        continue;
+                    }
+                    escaped(e)?
+                }
+                c => c,
+            };
+            first = false;
+
+            if self.peek() == Some('-') && self.chars.get(self.pos + 1).is_some_and(|c| *c != ']') {
+                self.pos += 1;
+                let mut end = self.peek()?;
+                self.pos += 1;
+                if end == '\\' {
+                    end = escaped(self.peek()?)?;
+                    self.pos += 1;
+                }
+                if end < start {
+                    return None;
+                }
+                items.push(ClassItem::Range(start, end));
+            } else {
+                items.push(ClassItem::Range(start, start));
+            }
+        }
+        Some(Node::Class { items, negated })
+    }
+
+    fn quantified(&mut self, node: Node) -> Option<Node> {
+        let (min, max) = match self.peek() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => match self.repeat_counts() {
+                Some(counts) => counts,
+                // A brace that does not start a repeat is a literal
+                None => return Some(node),
+            },
+            _ => return Some(node),
+        };
+        if matches!(node, Node::Start | Node::End | Node::WordBoundary(_)) {
+            return None;
+        }
+        self.pos += 1;
+
+        let mut greedy = true;
+        match self.peek() {
+            Some('?') => {
+                greedy = false;
+                self.pos += 1;
+            }
+            // Possessive quantifiers only differ in backtracking
+            Some('+') => self.pos += 1,
+            _ => {}
+        }
+        Some(Node::Repeat {
+            node: Box::new(node),
+            min,
+            max,
+            greedy,
+        })
+    }
+
+    /// Parse `{n}`, `{n,}` or `{n,m}`, leaving the position on the `}`
+    fn repeat_counts(&mut self) -> Option<(usize, Option<usize>)> {
+        let end = self.chars[self.pos..].iter().position(|c| *c == '}')?;
+        let body: String = self.chars[self.pos + 1..self.pos + end].iter().collect();
+        let (min, max) = match body.split_once(',') {
+            Some((min, "")) => (min.parse().ok()?, None),
+            Some((min, max)) => (min.parse().ok()?, Some(max.parse().ok()?)),
+            None => {
+                let n = body.parse().ok()?;
+                (n, Some(n))
+            }
+        };
+        self.pos += end;
+        Some((min, max))
+    }
+}
+
+fn shorthand(c: char) -> Option<ClassItem> {
+    Some(match c {
+        'd' => ClassItem::Digit(true),
+        'D' => ClassItem::Digit(false),
+        'w' => ClassItem::Word(true),
+        'W' => ClassItem::Word(false),
+        's' => ClassItem::Space(true),
+        'S' => ClassItem::Space(false),
+        _ => return None,
+    })
+}
+
+/// Character an escape stands for, `None` for unsupported escapes such
+/// as backreferences
+fn escaped(c: char) -> Option<char> {
+    match c {
+        'n' => Some('\n'),
+        'r' => Some('\r'),
+        't' => Some('\t'),
+        c if c.is_ascii_alphanumeric() => None,
+        c => Some(c),
+    }
+}
+
+fn posix_class(name: &str) -> Option<Vec<ClassItem>> {
+    Some(match name {
+        "digit" => vec![ClassItem::Digit(true)],
+        "alpha" => vec![ClassItem::Range('a', 'z'), ClassItem::Range('A', 'Z')],
+        "alnum" => vec![
+            ClassItem::Range('a', 'z'),
+            ClassItem::Range('A', 'Z'),
+            ClassItem::Range('0', '9'),
+        ],
+        "lower" => vec![ClassItem::Range('a', 'z')],
+        "upper" => vec![ClassItem::Range('A', 'Z')],
+        "space" => vec![ClassItem::Space(true)],
+        "word" => vec![ClassItem::Word(true)],
+        "xdigit" => vec![
+            ClassItem::Range('0', '9'),
+            ClassItem::Range('a', 'f'),
+            ClassItem::Range('A', 'F'),
+        ],
+        _ => return None,
+    })
+}
+
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+struct Matcher<'a> {
+    text: &'a [char],
+    case_insensitive: bool,
+}
+
+impl Matcher<'_> {
+    fn match_here(
+        &self,
+        nodes: &[Node],
+        pos: usize,
+        caps: &mut Captures,
+        k: &mut dyn FnMut(usize, &mut Captures) -> bool,
+    ) -> bool {
+        let Some((node, rest)) = nodes.split_first() else {
+            return k(pos, caps);
+        };
+
+        match node {
+            Node::Group {
+                alternatives,
+                index,
+            } => alternatives.iter().any(|alternative| {
+                let saved = caps.clone();
+                let matched = self.match_here(alternative, pos, caps, &mut |end, caps| {
+                    let previous = index.map(|i| caps[i].replace((pos, end)));
+                    if self.match_here(rest, end, caps, k) {
+                        return true;
+                    }
+                    if let (Some(i), Some(previous)) = (index, previous) {
+                        caps[*i] = previous;
+                    }
+                    false
+                });
+                if !matched {
+                    *caps = saved;
+                }
+                matched
+            }),
+            Node::Repeat {
+                node,
+                min,
+                max,
+                greedy,
+            } => self.repeat(node, (*min, *max, *greedy), rest, pos, 0, caps, k),
+            _ => self
+                .step(node, pos)
+                .is_some_and(|next| self.match_here(rest, next, caps, k)),
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn repeat(
+        &self,
+        node: &Node,
+        bounds: (usize, Option<usize>, bool),
+        rest: &[Node],
+        pos: usize,
+        count: usize,
+        caps: &mut Captures,
+        k: &mut dyn FnMut(usize, &mut Captures) -> bool,
+    ) -> bool {
+        let (min, max, greedy) = bounds;
+        let can_repeat = max.map_or(true, |max| count < max);
+
+        let more = |caps: &mut Captures, k: &mut dyn FnMut(usize, &mut Captures) -> bool| {
+            can_repeat
+                && self.match_here(std::slice::from_ref(node), pos, caps, &mut |next, caps| {
+                    // An empty iteration cannot make progress
+                    (next != pos || count < min)
+                        && self.repeat(node, bounds, rest, next, count + 1, caps, k)
+                })
+        };
+
+        if count < min {
+            return more(caps, k);
+        }
+        if greedy {
+            return more(caps, k) || self.match_here(rest, pos, caps, k);
+        }
+        self.match_here(rest, pos, caps, k) || more(caps, k)
+    }
+
+    /// Position after matching a single-width node at `pos`
+    fn step(&self, node: &Node, pos: usize) -> Option<usize> {
+        let current = self.text.get(pos).copied();
+        match node {
+            Node::Start => (pos == 0).then_some(pos),
+            Node::End => (pos == self.text.len()).then_some(pos),
+            Node::WordBoundary(expected) => {
+                let before = pos > 0 && is_word(self.text[pos - 1]);
+                let after = current.is_some_and(is_word);
+                ((before != after) == *expected).then_some(pos)
+            }
+            Node::Any => current.filter(|c| *c != '\n').map(|_| pos + 1),
+            Node::Char(expected) => current
+                .filter(|c| self.same(*c, *expected))
+                .map(|_| pos + 1),
+            Node::Class { items, negated } => current
+                .filter(|c| items.iter().any(|item| self.in_class(item, *c)) != *negated)
+                .map(|_| pos + 1),
+            Node::Group { .. } | Node::Repeat { .. } => None,
+        }
+    }
+
+    fn same(&self, a: char, b: char) -> bool {
+        a == b || (self.case_insensitive && a.to_lowercase().eq(b.to_lowercase()))
+    }
+
+    fn in_class(&self, item: &ClassItem, c: char) -> bool {
+        match item {
+            ClassItem::Range(start, end) => {
+                (*start..=*end).contains(&c)
+                    || (self.case_insensitive
+                        && (c.to_lowercase().any(|l| (*start..=*end).contains(&l))
+                            || c.to_uppercase().any(|u| (*start..=*end).contains(&u))))
+            }
+            ClassItem::Digit(positive) => c.is_ascii_digit() == *positive,
+            ClassItem::Word(positive) => is_word(c) == *positive,
+            ClassItem::Space(positive) => c.is_whitespace() == *positive,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn captures(pattern: &str, text: &str) -> Option<Vec<Option<String>>> {
+        Regex::new(pattern, false).unwrap().captures(text)
+    }
+
+    #[test]
+    fn test_matching() {
+        assert!(Regex::new(r"\.(png|jpe?g)$", false)
+            .unwrap()
+            .is_match("/img/a.jpeg"));
+        assert!(!Regex::new(r"^/api/v[0-9]+/", false)
+            .unwrap()
+            .is_match("/api/vx/"));
+        assert!(Regex::new(r"\.PHP$", true).unwrap().is_match("/index.php"));
+        assert!(Regex::new(r"(?i)^/admin", false)
+            .unwrap()
+            .is_match("/ADMIN/x"));
+        assert!(Regex::new(r"^/a{2,3}b$", false).unwrap().is_match("/aaab"));
+        assert!(!Regex::new(r"^/a{2,3}b$", false).unwrap().is_match("/ab"));
+        assert!(Regex::new(r"/\.", false).unwrap().is_match("/.git/config"));
+        assert!(Regex::new(r"[[:digit:]]\b", false)
+            .unwrap()
+            .is_match("v1 x"));
+    }
+
+    #[test]
+    fn test_captures() {
+        assert_eq!(
+            captures(r"^/users/(\d+)/(.*)$", "/users/42/posts/7"),
+            Some(vec![
+                Some("/users/42/posts/7".to_string()),
+                Some("42".to_string()),
+                Some("posts/7".to_string()),
+            ])
+        );
+        assert_eq!(
+            captures(r"^/(a+?)(a*)$", "/aaa"),
+            Some(vec![
+                Some("/aaa".to_string()),
+                Some("a".to_string()),
+                Some("aa".to_string()),
+            ])
+        );
+        assert_eq!(
+            captures("(x)|y", "y"),
+            Some(vec![Some("y".to_string()), None])
+        );
+
+        let regex = Regex::new(r"^(?<version>v\d)/(?:.*)$", false).unwrap();
+        assert_eq!(regex.group_index("version"), Some(1));
+    }
+
+    #[test]
+    fn test_unsupported_syntax() {
+        assert!(Regex::new(r"(a)\1", false).is_none());
+        assert!(Regex::new(r"(?=a)", false).is_none());
+        assert!(Regex::new(r"(?<!a)b", false).is_none());
+        assert!(Regex::new(r"(a", false).is_none());
+        assert!(Regex::new(r"*a", false).is_none());
+    }
+}
@@ -0,0 +1,332 @@
+//! Routing recorded traffic
+//!
+//! [`replay`] routes many requests at once and returns one
+//! [`RouteDecision`] per distinct request, so yesterday's traffic can be
+//! checked against a proposed configuration. Requests come from a browser
+//! HAR export ([`requests_from_har`]) or from access log lines
+//! ([`requests_from_log`]).
+
+use super::{simulate, Route, SimulatedRequest};
+use crate::ast::Config;
+use crate::logs::{LogParser, LogRecord};
+use std::collections::HashMap;
+
+/// How one request is routed
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RouteDecision {
+    /// The request as a URL
+    pub request: String,
+
+    /// Times the request occurs in the input
+    pub count: usize,
+
+    /// First `server_name` of the server handling it
+    pub server: Option<String>,
+
+    /// Innermost location handling it
+    pub location: Option<String>,
+
+    /// What handles it, e.g. `proxy_pass http://api` or `return 404`
+    pub outcome: String,
+}
+
+impl RouteDecision {
+    fn new(request: &SimulatedRequest, route: crate::Result<Route>) -> Self {
+        let mut decision = Self {
+            request: request.url(),
+            count: 1,
+            server: None,
+            location: None,
+            outcome: String::new(),
+        };
+        match route {
+            Ok(route) => {
+                decision.server = route.server.map(|s| s.name);
+                decision.location = route.locations.last().map(|l| l.pattern.clone());
+                decision.outcome = route.outcome.to_string();
+            }
+            Err(e) => decision.outcome = format!("invalid request: {e}"),
+        }
+        decision
+    }
+
+    /// Whether `other` picks the same server and location and has the
+    /// same outcome
+    #[must_use]
+    pub fn same_route(&self, other: &Self) -> bool {
+        self.server == other.server
+            && self.location == other.location
+            && self.outcome == other.outcome
+    }
+}
+
+/// Route every request, merging identical ones
+///
+/// Decisions are in the order requests first occur.
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::{parse, route::{replay, SimulatedRequest}};
+///
+/// let config = parse(r"
+/// http {
+///     server {
+///         listen 80;
+///         server_name example.com;
+///         location /api/ { proxy_pass http://127.0.0.1:8080; }
+///     }
+/// }
+/// ")?;
+/// let requests = [
+///     SimulatedRequest::new("example.com", "/api/users"),
+///     SimulatedRequest::new("example.com", "/api/users"),
+/// ];
+/// let decisions = replay(&config, &requests);
+///
+/// assert_eq!(decisions.len(), 1);
+/// assert_eq!(decisions[0].count, 2);
+/// assert_eq!(decisions[0].location.as_deref(), Some("/api/"));
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+#[must_use]
+pub fn replay(config: &Config, requests: &[SimulatedRequest]) -> Vec<RouteDecision> {
+    let mut decisions: Vec<RouteDecision> = Vec::new();
+    let mut seen: HashMap<&SimulatedRequest, usize> = HashMap::new();
+    for request in requests {
+        if let Some(&index) = seen.get(request) {
+            decisions[index].count += 1;
+            continue;
+        }
+        seen.insert(request, decisions.len());
+        decisions.push(RouteDecision::new(request, simulate(config, request)));
+    }
+    decisions
+}
+
+/// Requests of access log lines
+///
+/// The host comes from `$host`, `$http_host` or `$server_name`, falling
+/// back to `default_host` for formats that log none of them (such as
+/// `combined`); `$server_port` is added when it is not the default. The
+/// scheme comes from `$scheme` or `$https`, the path and query from
+/// `$request_uri`, `$request` or `$uri` and `$args`. Lines that do not
+/// match the format or log no path are skipped.
+#[must_use]
+pub fn requests_from_log<'a>(
+    lines: impl IntoIterator<Item = &'a str>,
+    parser: &LogParser,
+    default_host: &str,
+) -> Vec<SimulatedRequest> {
+    lines
+        .into_iter()
+        .filter_map(|line| parser.parse(line))
+        .filter_map(|record| logged_request(&record, default_host))
+        .collect()
+}
+
+fn logged_request(record: &LogRecord, default_host: &str) -> Option<SimulatedRequest> {
+    let logged = |name: &str| record.get(name).filter(|v| !v.is_empty() && *v != "-");
+    let path = logged("request_uri")
+        .or_else(|| logged("request")?.split(' ').nth(1))
+        .map(str::to_string)
+        .or_else(|| {
+            let uri = logged("uri")?;
+            Some(match logged("args") {
+                Some(args) => format!("{uri}?{args}"),
+                None => uri.to_string(),
+            })
+        })?;
+    let https = logged("scheme") == Some("https") || logged("https") == Some("on");
+
+    let mut host = ["host", "http_host", "server_name"]
+        .iter()
+        .find_map(|name| logged(name).filter(|h| *h != "_"))
+        .unwrap_or(default_host)
+        .to_string();
+    let default_port = if https { "443" } else { "80" };
+    if let Some(port) = logged("server_port") {
+        if !host.contains(':') && port != default_port {
+            host = format!("{host}:{port}");
+        }
+    }
+
+    let request = SimulatedRequest::new(host, path);
+    Some(if https { request.https() } else { request })
+}
+
+/// Requests of a HAR (HTTP Archive) file, as browsers export them
+///
+/// Entries whose URL is not http or https (`data:`, `blob:`, `ws:`) are
+/// skipped.
+///
+/// # Errors
+///
+/// Returns an error if `text` is not JSON or has no `log.entries` array.
+#[cfg(feature = "serde")]
+pub fn requests_from_har(text: &str) -> crate::Result<Vec<SimulatedRequest>> {
+    let har: serde_json::Value = serde_json::from_str(text)?;
+    let entries = har["log"]["entries"].as_array().ok_or_else(|| {
+        crate::Error::InvalidInput("Not a HAR file: no log.entries array".to_string())
+    })?;
+    Ok(entries
+        .iter()
+        .filter_map(|entry| entry["request"]["url"].as_str())
+        .filter(|url| url.starts_with("http://") || url.starts_with("https://"))
+        .filter_map(|url| url.parse().ok())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_requests_from_log() {
+        let parser = LogParser::new("$remote_addr $host $server_port $scheme \"$request\" $status");
+        let lines = [
+            "10.0.0.1 example.com 80 http \"GET /api/users?page=2 HTTP/1.1\" 200",
+            "10.0.0.1 example.com 8443 https \"GET / HTTP/2.0\" 200",
+            "10.0.0.1 _ 80 http \"GET /health HTTP/1.1\" 200",
+            "not a log line",
+        ];
+
+        let requests = requests_from_log(lines, &parser, "default.example");
+
+        assert_eq!(
+            requests,
+            vec![
+                SimulatedRequest::new("example.com", "/api/users?page=2"),
+                SimulatedRequest::new("example.com:8443", "/").https(),
+                SimulatedRequest::new("default.example", "/health"),
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_replay() {
+        let config = parse(
+            r"http {
+                server {
+                  listen 80;
+                  server_name example.com;
+                  location / { root /var/www; }
+                  location /api/ { proxy_pass http://127.0.0.1:8080; }
+                }
+              }",
+        )
+        .unwrap();
+        let har = r#"{"log": {"entries": [
+            {"request": {"method": "GET", "url": "http://example.com/api/users"}},
+            {"request": {"method": "GET", "url": "data:image/png;base64,AAAA"}},
+            {"request": {"method": "GET", "url": "http://example.com/index.html"}},
+            {"request": {"method": "GET", "url": "http://example.com/api/users"}}
+        ]}}"#;
+
+        let requests = requests_from_har(har).unwrap();
+        let decisions = replay(&config, &requests);
+
+        let table: Vec<_> = decisions
+            .iter()
+            .map(|d| (d.request.as_str(), d.count, d.location.as_deref()))
+            .collect();
+        assert_eq!(
+            table,
+            vec![
+                ("http://example.com/api/users", 2, Some("/api/")),
+                ("http://example.com/index.html", 1, Some("/")),
+            ]
+        );
+        assert_eq!(
+            decisions[0].outcome,
+            "proxy_pass http://127.0.0.1:8080 (URI /api/users)"
+        );
+        assert!(requests_from_har("{}").is_err());
+    }
+
+    #[test]
+    fn test_requests_from_log_edge_cases() {
+        // `combined` logs no host; lines without a request are skipped
+        let lines = [
+            "10.0.0.1 - - [10/Oct/2026:13:55:36 +0000] \"GET /a HTTP/1.1\" 200 12 \"-\" \"curl\"",
+            "10.0.0.1 - - [10/Oct/2026:13:55:36 +0000] \"-\" 400 0 \"-\" \"-\"",
+        ];
+        assert_eq!(
+            requests_from_log(lines, &LogParser::combined(), "default.example"),
+            vec![SimulatedRequest::new("default.example", "/a")]
+        );
+
+        let parser = LogParser::new("$http_host $server_name $server_port $https $uri $args");
+        let lines = [
+            "- api.example 8080 on /x a=1",
+            "h.example:81 _ 81 - /y -",
+            "- _ 443 on /z -",
+            "- _ 80 - - -",
+        ];
+        assert_eq!(
+            requests_from_log(lines, &parser, "default.example"),
+            vec![
+                SimulatedRequest::new("api.example:8080", "/x?a=1").https(),
+                SimulatedRequest::new("h.example:81", "/y"),
+                SimulatedRequest::new("default.example", "/z").https(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_replay_edge_cases() {
+        let config = parse("http { server { listen 80; } }").unwrap();
+        assert!(replay(&config, &[]).is_empty());
+
+        let requests = [
+            SimulatedRequest::new("example.com:8080", "/"),
+            SimulatedRequest::new("example.com", "/").https(),
+            SimulatedRequest::new("example.com:http", "/"),
+            SimulatedRequest::new("example.com:8080", "/"),
+        ];
+        let decisions = replay(&config, &requests);
+
+        // http and https requests for the same path stay apart
+        let counts: Vec<_> = decisions.iter().map(|d| d.count).collect();
+        assert_eq!(counts, vec![2, 1, 1]);
+        assert_eq!(decisions[0].server, None);
+        assert_eq!(decisions[0].location, None);
+        assert_eq!(decisions[0].outcome, "no server listens on the port");
+        assert!(decisions[2]
+            .outcome
+            .starts_with("invalid request: Invalid input: Invalid URL"));
+        assert!(decisions[0].same_route(&decisions[1]));
+        assert!(!decisions[0].same_route(&decisions[2]));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_requests_from_har_edge_cases() {
+        assert!(matches!(
+            requests_from_har("not json"),
+            Err(crate::Error::Serialization(_))
+        ));
+        assert!(matches!(
+            requests_from_har(r#"{"log": {"entries": {}}}"#),
+            Err(crate::Error::InvalidInput(_))
+        ));
+        assert!(requests_from_har(r#"{"log": {"entries": []}}"#)
+            .unwrap()
+            .is_empty());
+
+        let har = r#"{"log": {"entries": [
+            {"request": {"method": "GET"}},
+            {"response": {"status": 200}},
+            {"request": {"url": "ws://example.com/socket"}},
+            {"request": {"url": "http://:80/"}},
+            {"request": {"url": "https://example.com:8443/a?b=1"}}
+        ]}}"#;
+        assert_eq!(
+            requests_from_har(har).unwrap(),
+            vec![SimulatedRequest::new("example.com:8443", "/a?b=1").https()]
+        );
+    }
+}
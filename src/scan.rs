@@ -0,0 +1,316 @@
+//! Scan a directory of configuration files
+//!
+//! Many setups keep each virtual host in its own file (`conf.d`,
+//! `sites-available`, or a git repository of vhosts) without a master
+//! `nginx.conf` tying them together. [`directory`] finds the configuration
+//! files below a directory, parses each on its own and aggregates servers,
+//! ports and log files across them, reporting files that fail to parse
+//! instead of stopping at the first one.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use nginx_discovery::scan::{self, ScanOptions};
+//!
+//! let report = scan::directory("/etc/nginx/conf.d", &ScanOptions::default())?;
+//! println!("{} files, ports {:?}", report.files.len(), report.ports);
+//! for file in report.failed() {
+//!     eprintln!("{}: {}", file.path.display(), file.error.as_deref().unwrap_or_default());
+//! }
+//! # Ok::<(), nginx_discovery::Error>(())
+//! ```
+
+use crate::ast::Config;
+use crate::error::{Error, Result};
+use crate::extract;
+use crate::parser::parse_files;
+use crate::types::{AccessLog, LogTarget, Server};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// Selects which files a scan reads
+#[derive(Debug, Clone)]
+pub struct ScanOptions {
+    /// File extensions to read, without the dot
+    pub extensions: Vec<String>,
+
+    /// Descend into subdirectories
+    pub recursive: bool,
+
+    /// Follow symbolic links to files and directories, as in
+    /// `sites-enabled`
+    pub follow_symlinks: bool,
+
+    /// Read files and directories whose name starts with a dot
+    pub include_hidden: bool,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        Self {
+            extensions: vec!["conf".to_string()],
+            recursive: true,
+            follow_symlinks: true,
+            include_hidden: false,
+        }
+    }
+}
+
+/// One scanned file
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScannedFile {
+    /// Path of the file
+    pub path: PathBuf,
+
+    /// Server blocks in the file
+    pub servers: Vec<Server>,
+
+    /// Files written by `access_log` and `error_log` directives, sorted
+    pub log_files: Vec<PathBuf>,
+
+    /// Why the file could not be read or parsed
+    pub error: Option<String>,
+}
+
+impl ScannedFile {
+    fn parsed(path: PathBuf, config: &Config) -> Self {
+        let mut log_files: Vec<PathBuf> = extract::access_logs(config)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(AccessLog::is_file)
+            .map(|log| log.path)
+            .collect();
+        log_files.extend(
+            config
+                .find_directives_recursive("error_log")
+                .iter()
+                .filter_map(|d| d.first_arg())
+                .filter_map(|arg| match LogTarget::parse(&arg) {
+                    LogTarget::File(path) => Some(path),
+                    _ => None,
+                }),
+        );
+        log_files.sort();
+        log_files.dedup();
+
+        Self {
+            path,
+            servers: extract::servers(config).unwrap_or_default(),
+            log_files,
+            error: None,
+        }
+    }
+}
+
+/// Results of a directory scan
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScanReport {
+    /// The scanned directory
+    pub root: PathBuf,
+
+    /// Every file read, sorted by path
+    pub files: Vec<ScannedFile>,
+
+    /// Distinct listening ports across files, sorted
+    pub ports: Vec<u16>,
+
+    /// Distinct server names across files, sorted
+    pub server_names: Vec<String>,
+
+    /// Distinct log files across files, sorted
+    pub log_files: Vec<PathBuf>,
+}
+
+impl ScanReport {
+    /// Files that could not be read or parsed
+    pub fn failed(&self) -> impl Iterator<Item = &ScannedFile> {
+        self.files.iter().filter(|f| f.error.is_some())
+    }
+
+    /// Every server block with the file it is in
+    pub fn servers(&self) -> impl Iterator<Item = (&Path, &Server)> {
+        self.files
+            .iter()
+            .flat_map(|f| f.servers.iter().map(move |s| (f.path.as_path(), s)))
+    }
+}
+
+/// Scan `root` for configuration files and parse each of them
+///
+/// Files are parsed in parallel with [`parse_files`]; `include`
+/// directives are not followed.
+///
+/// # Errors
+///
+/// Returns an error if `root` is not a readable directory. Files that fail
+/// to parse are reported in [`ScannedFile::error`].
+pub fn directory(root: impl AsRef<Path>, options: &ScanOptions) -> Result<ScanReport> {
+    let root = root.as_ref();
+    if !root.is_dir() {
+        return Err(Error::InvalidInput(format!(
+            "{} is not a directory",
+            root.display()
+        )));
+    }
+
+    let mut paths = Vec::new();
+    let mut visited = HashSet::new();
+    find_files(root, options, &mut visited, &mut paths)?;
+    paths.sort();
+
+    let files: Vec<ScannedFile> = parse_files(&paths)
+        .into_iter()
+        .map(|(path, result)| match result {
+            Ok(config) => ScannedFile::parsed(path, &config),
+            Err(error) => ScannedFile {
+                path,
+                servers: Vec::new(),
+                log_files: Vec::new(),
+                error: Some(error.to_string()),
+            },
+        })
+        .collect();
+
+    let mut ports: Vec<u16> = files
+        .iter()
+        .flat_map(|f| &f.servers)
+        .flat_map(|s| s.listen.iter().map(|l| l.port))
+        .collect();
+    ports.sort_unstable();
+    ports.dedup();
+
+    let mut server_names: Vec<String> = files
+        .iter()
+        .flat_map(|f| &f.servers)
+        .flat_map(|s| s.server_names.iter().cloned())
+        .collect();
+    server_names.sort();
+    server_names.dedup();
+
+    let mut log_files: Vec<PathBuf> = files
+        .iter()
+        .flat_map(|f| f.log_files.iter().cloned())
+        .collect();
+    log_files.sort();
+    log_files.dedup();
+
+    Ok(ScanReport {
+        root: root.to_path_buf(),
+        files,
+        ports,
+        server_names,
+        log_files,
+    })
+}
+
+fn find_files(
+    dir: &Path,
+    options: &ScanOptions,
+    visited: &mut HashSet<PathBuf>,
+    paths: &mut Vec<PathBuf>,
+) -> Result<()> {
+    // Symlinked directories may form cycles
+    if !visited.insert(dir.canonicalize()?) {
+        return Ok(());
+    }
+
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let hidden = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| n.starts_with('.'));
+        if hidden && !options.include_hidden {
+            continue;
+        }
+
+        let metadata = if options.follow_symlinks {
+            std::fs::metadata(&path)
+        } else {
+            std::fs::symlink_metadata(&path)
+        };
+        // Dangling links are skipped
+        let Ok(metadata) = metadata else {
+            continue;
+        };
+
+        if metadata.is_dir() {
+            if options.recursive {
+                find_files(&path, options, visited, paths)?;
+            }
+        } else if metadata.is_file()
+            && path
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| options.extensions.iter().any(|x| x == e))
+        {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_scan_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("sites/extra")).unwrap();
+        fs::create_dir(root.join(".git")).unwrap();
+        fs::write(
+            root.join("sites/a.conf"),
+            "server {\n  listen 80;\n  server_name a.example.com;\n  access_log /var/log/nginx/a.log;\n}\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("sites/extra/b.conf"),
+            "server { listen 443 ssl; listen 80; server_name b.example.com; error_log /var/log/nginx/b.err; access_log syslog:server=10.0.0.1; }",
+        )
+        .unwrap();
+        fs::write(root.join("broken.conf"), "server {").unwrap();
+        fs::write(root.join("README.md"), "not nginx {").unwrap();
+        fs::write(root.join(".git/x.conf"), "not nginx {").unwrap();
+
+        let report = directory(root, &ScanOptions::default()).unwrap();
+        let names: Vec<_> = report
+            .files
+            .iter()
+            .map(|f| f.path.strip_prefix(root).unwrap().to_path_buf())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                PathBuf::from("broken.conf"),
+                PathBuf::from("sites/a.conf"),
+                PathBuf::from("sites/extra/b.conf"),
+            ]
+        );
+        assert_eq!(report.ports, vec![80, 443]);
+        assert_eq!(report.server_names, vec!["a.example.com", "b.example.com"]);
+        assert_eq!(
+            report.log_files,
+            vec![
+                PathBuf::from("/var/log/nginx/a.log"),
+                PathBuf::from("/var/log/nginx/b.err")
+            ]
+        );
+        assert_eq!(report.servers().count(), 2);
+
+        let failed: Vec<_> = report.failed().collect();
+        assert_eq!(failed.len(), 1);
+        assert!(failed[0].path.ends_with("broken.conf"));
+
+        let shallow = ScanOptions {
+            recursive: false,
+            ..ScanOptions::default()
+        };
+        assert_eq!(directory(root, &shallow).unwrap().files.len(), 1);
+        assert!(directory(root.join("broken.conf"), &shallow).is_err());
+    }
+}
@@ -0,0 +1,264 @@
+//! Thread-safe discovery with live reload
+//!
+//! [`SharedDiscovery`] holds an [`NginxDiscovery`] for long-running
+//! services. Readers take a [`snapshot`](SharedDiscovery::snapshot), an
+//! `Arc` of the configuration current at that moment; a
+//! [`reload`](SharedDiscovery::reload) parses the new configuration first
+//! and then swaps it in under the write lock, so readers see either the old
+//! or the new configuration, never a mix, and a failed reload leaves the
+//! current one in place.
+//!
+//! Subscribers receive a [`ReloadEvent`] with the semantic diff after each
+//! successful reload. Reloads are applied one at a time: the diff is
+//! computed before taking the write lock, so readers are not held up by
+//! it, and events arrive in generation order.
+//!
+//! # Examples
+//!
+//! ```
+//! use nginx_discovery::SharedDiscovery;
+//!
+//! let shared = SharedDiscovery::from_config_text(
+//!     "http { server { listen 80; server_name a.example.com; } }",
+//! )?;
+//! let events = shared.subscribe();
+//!
+//! let reader = shared.clone();
+//! std::thread::spawn(move || reader.snapshot().server_names())
+//!     .join()
+//!     .unwrap();
+//!
+//! shared.reload_from_text("http { server { listen 80; server_name b.example.com; } }")?;
+//!
+//! let event = events.recv().unwrap();
+//! assert_eq!(event.generation, 1);
+//! assert!(!event.diff.is_empty());
+//! assert_eq!(shared.snapshot().server_names(), vec!["b.example.com"]);
+//! # Ok::<(), nginx_discovery::Error>(())
+//! ```
+
+use crate::diff::{self, SemanticDiff};
+use crate::{Error, NginxDiscovery, Result};
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
+
+/// Notification sent to subscribers after a reload
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReloadEvent {
+    /// Number of reloads so far (1 for the first reload)
+    pub generation: u64,
+
+    /// Changes from the previous configuration (empty when equivalent)
+    pub diff: SemanticDiff,
+}
+
+#[derive(Debug)]
+struct State {
+    current: Arc<NginxDiscovery>,
+    generation: u64,
+}
+
+/// A shared, reloadable [`NginxDiscovery`]
+///
+/// Clones share the same configuration and subscribers.
+#[derive(Debug, Clone)]
+pub struct SharedDiscovery {
+    state: Arc<RwLock<State>>,
+    subscribers: Arc<Mutex<Vec<Sender<ReloadEvent>>>>,
+    /// Held from diffing to notifying, so reloads apply and notify in order
+    reloading: Arc<Mutex<()>>,
+}
+
+impl SharedDiscovery {
+    /// Share an existing discovery instance
+    #[must_use]
+    pub fn new(discovery: NginxDiscovery) -> Self {
+        Self {
+            state: Arc::new(RwLock::new(State {
+                current: Arc::new(discovery),
+                generation: 0,
+            })),
+            subscribers: Arc::new(Mutex::new(Vec::new())),
+            reloading: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// Parse configuration text
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if parsing fails.
+    pub fn from_config_text(text: &str) -> Result<Self> {
+        NginxDiscovery::from_config_text(text).map(Self::new)
+    }
+
+    /// Load a configuration file; [`reload`](Self::reload) re-reads it
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or parsed.
+    pub fn from_config_file(path: impl AsRef<Path>) -> Result<Self> {
+        NginxDiscovery::from_config_file(path).map(Self::new)
+    }
+
+    /// The current configuration
+    ///
+    /// The snapshot stays valid and unchanged across later reloads.
+    #[must_use]
+    pub fn snapshot(&self) -> Arc<NginxDiscovery> {
+        Arc::clone(&self.read().current)
+    }
+
+    /// Number of successful reloads
+    #[must_use]
+    pub fn generation(&self) -> u64 {
+        self.read().generation
+    }
+
+    /// Re-read and re-parse the configuration file and swap it in
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the configuration was not loaded from a file, or
+    /// the file cannot be read or parsed. The current configuration is
+    /// kept in that case.
+    pub fn reload(&self) -> Result<ReloadEvent> {
+        let path = self
+            .snapshot()
+            .config_path()
+            .map(Path::to_path_buf)
+            .ok_or_else(|| {
+                Error::InvalidInput("configuration was not loaded from a file".to_string())
+            })?;
+        Ok(self.replace(NginxDiscovery::from_config_file(path)?))
+    }
+
+    /// Parse `text` and swap it in
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if parsing fails; the current configuration is kept.
+    pub fn reload_from_text(&self, text: &str) -> Result<ReloadEvent> {
+        Ok(self.replace(NginxDiscovery::from_config_text(text)?))
+    }
+
+    /// Swap in an already parsed configuration and notify subscribers
+    pub fn replace(&self, discovery: NginxDiscovery) -> ReloadEvent {
+        let _reloading = self
+            .reloading
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        // Only reloads change the state, so the snapshot stays current
+        // until the swap below
+        let next = Arc::new(discovery);
+        let diff = diff::semantic_compare(self.snapshot().config(), next.config());
+        let event = {
+            let mut state = self.state.write().unwrap_or_else(PoisonError::into_inner);
+            state.current = next;
+            state.generation += 1;
+            ReloadEvent {
+                generation: state.generation,
+                diff,
+            }
+        };
+
+        let mut subscribers = self
+            .subscribers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        subscribers.retain(|subscriber| subscriber.send(event.clone()).is_ok());
+        event
+    }
+
+    /// Receive a [`ReloadEvent`] after every later reload
+    ///
+    /// Dropping the receiver unsubscribes.
+    #[must_use]
+    pub fn subscribe(&self) -> Receiver<ReloadEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(sender);
+        receiver
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, State> {
+        // The state is only ever replaced whole, so a poisoned lock still
+        // holds a consistent configuration
+        self.state.read().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl From<NginxDiscovery> for SharedDiscovery {
+    fn from(discovery: NginxDiscovery) -> Self {
+        Self::new(discovery)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reload_from_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nginx.conf");
+        std::fs::write(&path, "http { upstream app { server 10.0.0.1; } }").unwrap();
+
+        let shared = SharedDiscovery::from_config_file(&path).unwrap();
+        let before = shared.snapshot();
+        let events = shared.subscribe();
+        let dropped = shared.subscribe();
+        drop(dropped);
+
+        std::fs::write(&path, "http { upstream app { server 10.0.0.2; } }").unwrap();
+        let event = shared.reload().unwrap();
+        assert_eq!(event.generation, 1);
+        assert_eq!(events.try_recv().unwrap(), event);
+        assert_eq!(shared.subscribers.lock().unwrap().len(), 1);
+        assert!(!event.diff.is_empty());
+
+        // Old snapshots are unaffected
+        assert_ne!(before.config(), shared.snapshot().config());
+
+        // A failed reload keeps the current configuration
+        std::fs::write(&path, "http {").unwrap();
+        assert!(shared.reload().is_err());
+        assert_eq!(shared.generation(), 1);
+        assert!(events.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_reload_without_file() {
+        let shared = SharedDiscovery::from_config_text("user nginx;").unwrap();
+        assert!(matches!(shared.reload(), Err(Error::InvalidInput(_))));
+
+        let event = shared.reload_from_text("user nginx;").unwrap();
+        assert!(event.diff.is_empty());
+    }
+
+    #[test]
+    fn test_concurrent_reloads_notify_in_order() {
+        let shared = SharedDiscovery::from_config_text("worker_processes 0;").unwrap();
+        let events = shared.subscribe();
+
+        std::thread::scope(|scope| {
+            for thread in 1..=4 {
+                let shared = shared.clone();
+                scope.spawn(move || {
+                    for i in 0..10 {
+                        let text = format!("worker_processes {};", thread * 100 + i);
+                        shared.reload_from_text(&text).unwrap();
+                    }
+                });
+            }
+        });
+
+        let generations: Vec<u64> = events.try_iter().map(|e| e.generation).collect();
+        assert_eq!(generations, (1..=40).collect::<Vec<_>>());
+        assert_eq!(shared.generation(), 40);
+    }
+}
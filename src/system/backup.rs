@@ -0,0 +1,658 @@
+//! Backup and restore of the running configuration
+//!
+//! [`backup_config`] captures `nginx -T` output into a timestamped tar
+//! archive. The dump holds every configuration file nginx loaded, so the
+//! include tree is stored file by file under `files/`, next to the dump
+//! itself (`nginx-T.conf`) and a `manifest.txt`. Certificates, keys and
+//! `auth_basic_user_file` files the configuration references are recorded
+//! by path, and stored too with [`BackupOptions::include_contents`].
+//!
+//! [`restore`] compares an archive with the files on disk and, unless
+//! [`RestoreOptions::dry_run`] is set, writes back the files that differ.
+//! Files that are not in the archive are left alone.
+
+use crate::analyze::{check_paths, PathKind, PathStatus};
+use crate::error::{Error, Result};
+use crate::parse;
+use std::fmt::Write as _;
+use std::path::{Component, Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Marker nginx -T prints before each file
+const FILE_MARKER: &str = "# configuration file ";
+
+/// Archive member holding the raw dump
+const DUMP_NAME: &str = "nginx-T.conf";
+
+/// Archive member listing what was captured
+const MANIFEST_NAME: &str = "manifest.txt";
+
+/// Directory of the archive holding files by absolute path
+const FILES_DIR: &str = "files/";
+
+/// Mode bits a restored file may keep: no setuid, setgid, sticky or
+/// group/other write
+const RESTORE_MODE_MASK: u32 = 0o755;
+
+/// Directives whose files are backed up with mode 0600
+const SECRET_DIRECTIVES: &[&str] = &[
+    "ssl_certificate_key",
+    "proxy_ssl_certificate_key",
+    "grpc_ssl_certificate_key",
+    "ssl_session_ticket_key",
+    "ssl_password_file",
+    "proxy_ssl_password_file",
+    "auth_basic_user_file",
+];
+
+/// What a backup captures
+#[derive(Debug, Clone, Default)]
+pub struct BackupOptions {
+    /// Store the contents of referenced certificates, keys and password
+    /// files; by default only their paths are recorded in the manifest
+    pub include_contents: bool,
+}
+
+/// A backup written by [`backup_config`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Backup {
+    /// The archive
+    pub path: PathBuf,
+
+    /// Configuration files in the dump, main file first
+    pub config_files: Vec<PathBuf>,
+
+    /// Certificates, keys and password files the configuration references
+    pub referenced: Vec<PathBuf>,
+
+    /// Referenced files whose contents are in the archive
+    pub copied: Vec<PathBuf>,
+}
+
+/// Capture the running configuration into a tar archive in `dest`
+///
+/// The archive is named `nginx-backup-<UTC timestamp>.tar`; `dest` is
+/// created if needed.
+///
+/// # Errors
+///
+/// Returns an error if `nginx -T` fails or the archive cannot be written.
+/// Referenced files that cannot be read are recorded by path only.
+///
+/// # Examples
+///
+/// ```no_run
+/// use nginx_discovery::system::{backup_config, BackupOptions};
+///
+/// let backup = backup_config("/var/backups/nginx", &BackupOptions::default())?;
+/// println!("{} files in {}", backup.config_files.len(), backup.path.display());
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+pub fn backup_config(dest: impl AsRef<Path>, options: &BackupOptions) -> Result<Backup> {
+    backup_from_dump(&super::dump_config()?, dest, options)
+}
+
+/// Write a backup from `nginx -T` output captured earlier
+///
+/// # Errors
+///
+/// Returns [`Error::InvalidInput`] if `dump` is not `nginx -T` output, or
+/// an I/O error if the archive cannot be written.
+pub fn backup_from_dump(
+    dump: &str,
+    dest: impl AsRef<Path>,
+    options: &BackupOptions,
+) -> Result<Backup> {
+    let files = split_dump(dump)?;
+    let config_files: Vec<PathBuf> = files.iter().map(|(path, _)| path.clone()).collect();
+    let referenced = referenced_files(&files);
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    let mut archive = Vec::new();
+    let mut manifest = format!(
+        "# nginx-discovery backup\ncreated {}\n",
+        timestamp(now, true)
+    );
+    append_entry(&mut archive, DUMP_NAME, dump.as_bytes(), 0o644, now)?;
+
+    for (path, text) in &files {
+        let _ = writeln!(manifest, "config {}", path.display());
+        append_entry(
+            &mut archive,
+            &member_name(path),
+            text.as_bytes(),
+            0o644,
+            now,
+        )?;
+    }
+
+    let mut copied = Vec::new();
+    for (path, secret) in &referenced {
+        let contents = if options.include_contents {
+            std::fs::read(path).ok()
+        } else {
+            None
+        };
+        match contents {
+            Some(contents) => {
+                let mode = if *secret { 0o600 } else { 0o644 };
+                append_entry(&mut archive, &member_name(path), &contents, mode, now)?;
+                let _ = writeln!(manifest, "file {}", path.display());
+                copied.push(path.clone());
+            }
+            None => {
+                let _ = writeln!(manifest, "path {}", path.display());
+            }
+        }
+    }
+    append_entry(&mut archive, MANIFEST_NAME, manifest.as_bytes(), 0o644, now)?;
+    // Two empty blocks end a tar archive
+    archive.resize(archive.len() + 1024, 0);
+
+    let dest = dest.as_ref();
+    std::fs::create_dir_all(dest)?;
+    let path = dest.join(format!("nginx-backup-{}.tar", timestamp(now, false)));
+    std::fs::write(&path, archive)?;
+
+    Ok(Backup {
+        path,
+        config_files,
+        referenced: referenced.into_iter().map(|(path, _)| path).collect(),
+        copied,
+    })
+}
+
+/// How [`restore`] applies an archive
+#[derive(Debug, Clone)]
+pub struct RestoreOptions {
+    /// Directory absolute paths in the archive are relative to, `/` by
+    /// default
+    pub root: PathBuf,
+
+    /// Only compare the archive with the files on disk
+    pub dry_run: bool,
+}
+
+impl Default for RestoreOptions {
+    fn default() -> Self {
+        Self {
+            root: PathBuf::from("/"),
+            dry_run: true,
+        }
+    }
+}
+
+/// How a file on disk differs from the archive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum FileChange {
+    /// Same contents
+    Unchanged,
+    /// Different contents
+    Modified,
+    /// Not on disk
+    Created,
+}
+
+/// One file of a [`RestoreReport`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RestoredFile {
+    /// Where the file is restored to
+    pub path: PathBuf,
+
+    /// How the file on disk differs from the archive
+    pub change: FileChange,
+
+    /// Lines of the file on disk (`-`) and in the archive (`+`) that
+    /// differ; empty unless modified
+    pub diff: String,
+}
+
+/// Result of [`restore`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RestoreReport {
+    /// Every file in the archive, in archive order
+    pub files: Vec<RestoredFile>,
+
+    /// Whether changed files were written
+    pub applied: bool,
+}
+
+impl RestoreReport {
+    /// Files that differ from the archive
+    pub fn changed(&self) -> impl Iterator<Item = &RestoredFile> {
+        self.files
+            .iter()
+            .filter(|f| f.change != FileChange::Unchanged)
+    }
+}
+
+/// Compare a backup archive with the files on disk and restore it
+///
+/// Restored files get the mode stored in the archive on Unix, without
+/// setuid, setgid, sticky and group or other write bits.
+///
+/// # Errors
+///
+/// Returns an error if the archive cannot be read, is not a tar archive,
+/// holds a path leaving [`RestoreOptions::root`], or a file cannot be
+/// written.
+///
+/// # Examples
+///
+/// ```no_run
+/// use nginx_discovery::system::{restore, RestoreOptions};
+///
+/// let report = restore("nginx-backup-20240101T000000Z.tar", &RestoreOptions::default())?;
+/// for file in report.changed() {
+///     println!("{:?} {}\n{}", file.change, file.path.display(), file.diff);
+/// }
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+pub fn restore(archive: impl AsRef<Path>, options: &RestoreOptions) -> Result<RestoreReport> {
+    let data = std::fs::read(archive)?;
+    let mut files = Vec::new();
+    let mut writes = Vec::new();
+
+    for (name, mode, contents) in read_entries(&data)? {
+        let Some(relative) = name.strip_prefix(FILES_DIR) else {
+            continue;
+        };
+        let relative = Path::new(relative);
+        if relative
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_)))
+        {
+            return Err(Error::InvalidInput(format!(
+                "archive member {name} leaves the restore root"
+            )));
+        }
+        let path = options.root.join(relative);
+
+        let (change, diff) = match std::fs::read(&path) {
+            Ok(current) if current == contents => (FileChange::Unchanged, String::new()),
+            Ok(current) => (FileChange::Modified, line_diff(&current, &contents)),
+            Err(_) => (FileChange::Created, String::new()),
+        };
+        if change != FileChange::Unchanged {
+            writes.push((path.clone(), mode, contents));
+        }
+        files.push(RestoredFile { path, change, diff });
+    }
+
+    if !options.dry_run {
+        for (path, mode, contents) in writes {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&path, contents)?;
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                std::fs::set_permissions(
+                    &path,
+                    std::fs::Permissions::from_mode(mode & RESTORE_MODE_MASK),
+                )?;
+            }
+            #[cfg(not(unix))]
+            let _ = mode;
+        }
+    }
+
+    Ok(RestoreReport {
+        files,
+        applied: !options.dry_run,
+    })
+}
+
+/// Files of a dump with their text, main file first
+fn split_dump(dump: &str) -> Result<Vec<(PathBuf, String)>> {
+    let mut files: Vec<(PathBuf, String)> = Vec::new();
+    for line in dump.split_inclusive('\n') {
+        let header = line
+            .strip_prefix(FILE_MARKER)
+            .and_then(|rest| rest.trim_end().strip_suffix(':'));
+        match (header, files.last_mut()) {
+            (Some(path), _) => files.push((PathBuf::from(path), String::new())),
+            (None, Some((_, text))) => text.push_str(line),
+            (None, None) => {}
+        }
+    }
+    if files.is_empty() {
+        return Err(Error::InvalidInput(
+            "no \"# configuration file\" sections, not nginx -T output".to_string(),
+        ));
+    }
+    // nginx -T adds a line break after each file
+    for (_, text) in &mut files {
+        if text.ends_with('\n') {
+            text.pop();
+        }
+    }
+    Ok(files)
+}
+
+/// Certificates, keys and password files referenced anywhere in the dump,
+/// with whether they hold secrets
+fn referenced_files(files: &[(PathBuf, String)]) -> Vec<(PathBuf, bool)> {
+    let base_dir = files[0]
+        .0
+        .parent()
+        .map_or_else(|| PathBuf::from("/"), Path::to_path_buf);
+    let mut referenced: Vec<(PathBuf, bool)> = Vec::new();
+    for (_, text) in files {
+        let Ok(config) = parse(text) else {
+            continue;
+        };
+        for path in check_paths(&config, &base_dir).paths {
+            let wanted =
+                path.directive.contains("ssl_") || path.directive == "auth_basic_user_file";
+            if wanted
+                && path.kind == PathKind::File
+                && path.status != PathStatus::Unchecked
+                && !referenced.iter().any(|(p, _)| *p == path.resolved)
+            {
+                let secret = SECRET_DIRECTIVES.contains(&path.directive.as_str());
+                referenced.push((path.resolved, secret));
+            }
+        }
+    }
+    referenced
+}
+
+/// Archive member of a file, by its absolute path below `files/`
+fn member_name(path: &Path) -> String {
+    let relative: PathBuf = path
+        .components()
+        .filter(|c| matches!(c, Component::Normal(_)))
+        .collect();
+    format!("{FILES_DIR}{}", relative.display())
+}
+
+/// `20240102T030405Z`, or `2024-01-02T03:04:05Z` when `separators`
+fn timestamp(secs: u64, separators: bool) -> String {
+    // Days to civil date, after Howard Hinnant's `civil_from_days`
+    let days = i64::try_from(secs / 86_400).unwrap_or(0) + 719_468;
+    let era = days / 146_097;
+    let doe = days - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    let secs = secs % 86_400;
+    let (hour, minute, second) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if separators {
+        format!("{year:04}-{month:02}-{day:02}T{hour:02}:{minute:02}:{second:02}Z")
+    } else {
+        format!("{year:04}{month:02}{day:02}T{hour:02}{minute:02}{second:02}Z")
+    }
+}
+
+/// Append a regular file to a ustar archive
+fn append_entry(
+    archive: &mut Vec<u8>,
+    name: &str,
+    contents: &[u8],
+    mode: u32,
+    mtime: u64,
+) -> Result<()> {
+    let (prefix, name) = if name.len() <= 100 {
+        ("", name)
+    } else {
+        name.char_indices()
+            .filter(|&(i, c)| c == '/' && i <= 155 && name.len() - i - 1 <= 100)
+            .map(|(i, _)| (&name[..i], &name[i + 1..]))
+            .next()
+            .ok_or_else(|| Error::InvalidInput(format!("path too long for tar: {name}")))?
+    };
+
+    let mut header = [0u8; 512];
+    let mut field = |offset: usize, value: &[u8]| {
+        header[offset..offset + value.len()].copy_from_slice(value);
+    };
+    field(0, name.as_bytes());
+    field(100, format!("{mode:07o}\0").as_bytes());
+    field(108, b"0000000\0");
+    field(116, b"0000000\0");
+    field(124, format!("{:011o}\0", contents.len()).as_bytes());
+    field(136, format!("{mtime:011o}\0").as_bytes());
+    field(148, b"        ");
+    field(156, b"0");
+    field(257, b"ustar\x0000");
+    field(345, prefix.as_bytes());
+    let checksum: u32 = header.iter().map(|&b| u32::from(b)).sum();
+    header[148..156].copy_from_slice(format!("{checksum:06o}\0 ").as_bytes());
+
+    archive.extend_from_slice(&header);
+    archive.extend_from_slice(contents);
+    archive.resize(blocks(archive.len()), 0);
+    Ok(())
+}
+
+/// `len` rounded up to whole 512-byte tar blocks
+fn blocks(len: usize) -> usize {
+    (len + 511) / 512 * 512
+}
+
+/// Regular files of a ustar archive: name, mode and contents
+fn read_entries(data: &[u8]) -> Result<Vec<(String, u32, Vec<u8>)>> {
+    let invalid = || Error::InvalidInput("not a tar archive".to_string());
+    let text = |bytes: &[u8]| {
+        let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+        String::from_utf8_lossy(&bytes[..end]).trim().to_string()
+    };
+    let octal = |bytes: &[u8]| usize::from_str_radix(&text(bytes), 8).map_err(|_| invalid());
+
+    if data.is_empty() || data.len() % 512 != 0 {
+        return Err(invalid());
+    }
+    let mut entries = Vec::new();
+    let mut offset = 0;
+    while offset + 512 <= data.len() {
+        let header = &data[offset..offset + 512];
+        if header.iter().all(|&b| b == 0) {
+            break;
+        }
+        if &header[257..262] != b"ustar" {
+            return Err(invalid());
+        }
+        let size = octal(&header[124..136])?;
+        let mode = u32::try_from(octal(&header[100..108])?).map_err(|_| invalid())?;
+        let start = offset + 512;
+        let contents = data.get(start..start + size).ok_or_else(invalid)?;
+
+        if matches!(header[156], b'0' | 0) {
+            let prefix = text(&header[345..500]);
+            let name = text(&header[..100]);
+            let name = if prefix.is_empty() {
+                name
+            } else {
+                format!("{prefix}/{name}")
+            };
+            entries.push((name, mode, contents.to_vec()));
+        }
+        offset = start + blocks(size);
+    }
+    Ok(entries)
+}
+
+/// Lines only in `old` prefixed with `-`, lines only in `new` with `+`
+fn line_diff(old: &[u8], new: &[u8]) -> String {
+    let (Ok(old), Ok(new)) = (std::str::from_utf8(old), std::str::from_utf8(new)) else {
+        return "binary files differ\n".to_string();
+    };
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    // Longest common subsequence, from the end
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut diff = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            i += 1;
+            j += 1;
+        } else if j == new.len() || i < old.len() && lcs[i + 1][j] >= lcs[i][j + 1] {
+            let _ = writeln!(diff, "-{}", old[i]);
+            i += 1;
+        } else {
+            let _ = writeln!(diff, "+{}", new[j]);
+            j += 1;
+        }
+    }
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_backup_and_restore() {
+        let dir = tempfile::tempdir().unwrap();
+        let etc = dir.path().join("etc");
+        fs::create_dir_all(&etc).unwrap();
+        fs::write(etc.join("site.key"), "KEY").unwrap();
+        let dump = format!(
+            "# configuration file /etc/nginx/nginx.conf:\n\
+             http {{\n    include conf.d/*.conf;\n}}\n\n\
+             # configuration file /etc/nginx/conf.d/site.conf:\n\
+             server {{\n    listen 443 ssl;\n    ssl_certificate_key {};\n    ssl_certificate /no/such/cert.pem;\n}}\n\n",
+            etc.join("site.key").display()
+        );
+
+        let options = BackupOptions {
+            include_contents: true,
+        };
+        let backup = backup_from_dump(&dump, dir.path().join("backups"), &options).unwrap();
+        let name = backup.path.file_name().unwrap().to_str().unwrap();
+        assert!(name.starts_with("nginx-backup-") && name.ends_with("Z.tar"));
+        assert_eq!(
+            backup.config_files,
+            vec![
+                PathBuf::from("/etc/nginx/nginx.conf"),
+                PathBuf::from("/etc/nginx/conf.d/site.conf")
+            ]
+        );
+        assert_eq!(
+            backup.referenced,
+            vec![etc.join("site.key"), PathBuf::from("/no/such/cert.pem")]
+        );
+        assert_eq!(backup.copied, vec![etc.join("site.key")]);
+
+        // Nothing on disk yet below the restore root
+        let root = dir.path().join("root");
+        let dry_run = RestoreOptions {
+            root: root.clone(),
+            dry_run: true,
+        };
+        let report = restore(&backup.path, &dry_run).unwrap();
+        assert_eq!(report.files.len(), 3);
+        assert!(report.files.iter().all(|f| f.change == FileChange::Created));
+        assert!(!root.exists());
+
+        let apply = RestoreOptions {
+            dry_run: false,
+            ..dry_run.clone()
+        };
+        assert!(restore(&backup.path, &apply).unwrap().applied);
+        let main = root.join("etc/nginx/nginx.conf");
+        assert_eq!(
+            fs::read_to_string(&main).unwrap(),
+            "http {\n    include conf.d/*.conf;\n}\n"
+        );
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let key = root.join(etc.strip_prefix("/").unwrap()).join("site.key");
+            let mode = fs::metadata(key).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        fs::write(&main, "http {\n    include sites/*.conf;\n}\n").unwrap();
+        let report = restore(&backup.path, &dry_run).unwrap();
+        let changed: Vec<_> = report.changed().collect();
+        assert_eq!(changed.len(), 1);
+        assert_eq!(changed[0].path, main);
+        assert_eq!(changed[0].change, FileChange::Modified);
+        assert_eq!(
+            changed[0].diff,
+            "-    include sites/*.conf;\n+    include conf.d/*.conf;\n"
+        );
+
+        assert!(backup_from_dump("user nginx;", dir.path(), &options).is_err());
+        assert!(restore(etc.join("site.key"), &dry_run).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_restore_masks_mode() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut archive = Vec::new();
+        append_entry(
+            &mut archive,
+            "files/etc/nginx/run.sh",
+            b"#!/bin/sh\n",
+            0o7777,
+            0,
+        )
+        .unwrap();
+        archive.resize(archive.len() + 1024, 0);
+        let path = dir.path().join("crafted.tar");
+        fs::write(&path, archive).unwrap();
+
+        let root = dir.path().join("root");
+        let options = RestoreOptions {
+            root: root.clone(),
+            dry_run: false,
+        };
+        restore(&path, &options).unwrap();
+        let mode = fs::metadata(root.join("etc/nginx/run.sh"))
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o7777, 0o755);
+    }
+
+    #[test]
+    fn test_timestamp() {
+        assert_eq!(timestamp(0, false), "19700101T000000Z");
+        assert_eq!(timestamp(1_709_251_199, true), "2024-02-29T23:59:59Z");
+
+        // Year, leap day and century boundaries
+        for (secs, expected) in [
+            (946_684_799, "1999-12-31T23:59:59Z"),
+            (951_825_600, "2000-02-29T12:00:00Z"),
+            (951_868_800, "2000-03-01T00:00:00Z"),
+            (1_704_067_199, "2023-12-31T23:59:59Z"),
+            (1_704_067_200, "2024-01-01T00:00:00Z"),
+            (4_107_542_399, "2100-02-28T23:59:59Z"),
+            (4_107_542_400, "2100-03-01T00:00:00Z"),
+            (13_574_563_200, "2400-02-29T00:00:00Z"),
+        ] {
+            assert_eq!(timestamp(secs, true), expected);
+        }
+    }
+}
@@ -0,0 +1,378 @@
+//! `auth_basic_user_file` password files
+//!
+//! Reads the htpasswd files a configuration references and classifies each
+//! user's hash by its prefix; the hashes themselves are neither kept nor
+//! checked. nginx accepts whatever the system `crypt()` understands plus
+//! `{PLAIN}`, `{SHA}` and `{SSHA}`, so weak schemes keep working unnoticed:
+//!
+//! - `htpasswd_weak_hash`: users hashed with plain text, DES `crypt`, MD5
+//!   (`$apr1$`, `$1$`) or SHA-1 (`{SHA}`, `{SSHA}`)
+//! - `htpasswd_empty`: a file without users, where every login fails
+
+use super::permissions::normalize;
+use crate::ast::{Config, Directive};
+use crate::lint::{walk, Finding, Severity};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Hash scheme of a password file entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum HashAlgorithm {
+    /// `$2y$`, `$2a$`, `$2b$`
+    Bcrypt,
+    /// `$y$` (yescrypt)
+    Yescrypt,
+    /// `$6$` (SHA-512 crypt)
+    Sha512Crypt,
+    /// `$5$` (SHA-256 crypt)
+    Sha256Crypt,
+    /// `$apr1$` (Apache MD5)
+    Apr1,
+    /// `$1$` (MD5 crypt)
+    Md5Crypt,
+    /// `{SSHA}` (salted SHA-1)
+    SaltedSha1,
+    /// `{SHA}` (unsalted SHA-1)
+    Sha1,
+    /// 13-character traditional DES `crypt`
+    Crypt,
+    /// `{PLAIN}`
+    Plain,
+    /// Anything else, most likely a plain-text password nginx cannot match
+    Unknown,
+}
+
+impl HashAlgorithm {
+    /// Classify a hash by its prefix
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nginx_discovery::system::HashAlgorithm;
+    ///
+    /// assert_eq!(HashAlgorithm::detect("$apr1$salt$hash"), HashAlgorithm::Apr1);
+    /// assert_eq!(HashAlgorithm::detect("{PLAIN}secret"), HashAlgorithm::Plain);
+    /// assert!(HashAlgorithm::detect("$2y$10$abc").is_strong());
+    /// ```
+    #[must_use]
+    pub fn detect(hash: &str) -> Self {
+        let crypt_chars = |c: char| c.is_ascii_alphanumeric() || matches!(c, '.' | '/');
+        if ["$2y$", "$2a$", "$2b$", "$2x$"]
+            .iter()
+            .any(|p| hash.starts_with(p))
+        {
+            Self::Bcrypt
+        } else if hash.starts_with("$y$") {
+            Self::Yescrypt
+        } else if hash.starts_with("$6$") {
+            Self::Sha512Crypt
+        } else if hash.starts_with("$5$") {
+            Self::Sha256Crypt
+        } else if hash.starts_with("$apr1$") {
+            Self::Apr1
+        } else if hash.starts_with("$1$") {
+            Self::Md5Crypt
+        } else if hash.starts_with("{SSHA}") {
+            Self::SaltedSha1
+        } else if hash.starts_with("{SHA}") {
+            Self::Sha1
+        } else if hash.starts_with("{PLAIN}") {
+            Self::Plain
+        } else if hash.len() == 13 && hash.chars().all(crypt_chars) {
+            Self::Crypt
+        } else {
+            Self::Unknown
+        }
+    }
+
+    /// Whether the scheme resists offline cracking: bcrypt, yescrypt and
+    /// the SHA-2 crypt schemes
+    #[must_use]
+    pub fn is_strong(self) -> bool {
+        matches!(
+            self,
+            Self::Bcrypt | Self::Yescrypt | Self::Sha512Crypt | Self::Sha256Crypt
+        )
+    }
+}
+
+impl fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Bcrypt => "bcrypt",
+            Self::Yescrypt => "yescrypt",
+            Self::Sha512Crypt => "SHA-512 crypt",
+            Self::Sha256Crypt => "SHA-256 crypt",
+            Self::Apr1 => "MD5 (apr1)",
+            Self::Md5Crypt => "MD5 crypt",
+            Self::SaltedSha1 => "salted SHA-1",
+            Self::Sha1 => "SHA-1",
+            Self::Crypt => "DES crypt",
+            Self::Plain => "plain text",
+            Self::Unknown => "unrecognized (plain text?)",
+        })
+    }
+}
+
+/// A user of a password file
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HtpasswdUser {
+    /// User name
+    pub name: String,
+
+    /// Hash scheme
+    pub algorithm: HashAlgorithm,
+
+    /// Line in the password file
+    pub line: usize,
+}
+
+/// Parse the users of a password file: `user:hash[:comment]` lines,
+/// skipping blank lines and `#` comments
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::system::{parse_htpasswd, HashAlgorithm};
+///
+/// let users = parse_htpasswd("# staff\nalice:$2y$10$abcdefghij\nbob:{SHA}W6ph5Mm5Pz8GgiULbPgzG37mj9g=\n");
+///
+/// assert_eq!(users[0].name, "alice");
+/// assert_eq!(users[1].algorithm, HashAlgorithm::Sha1);
+/// assert_eq!(users[1].line, 3);
+/// ```
+#[must_use]
+pub fn parse_htpasswd(text: &str) -> Vec<HtpasswdUser> {
+    text.lines()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                return None;
+            }
+            let (name, rest) = line.split_once(':')?;
+            let hash = rest.split(':').next().unwrap_or_default();
+            Some(HtpasswdUser {
+                name: name.to_string(),
+                algorithm: HashAlgorithm::detect(hash),
+                line: i + 1,
+            })
+        })
+        .collect()
+}
+
+/// A password file referenced by `auth_basic_user_file`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PasswordFile {
+    /// Resolved path
+    pub path: PathBuf,
+
+    /// Line of the first `auth_basic_user_file` referencing it
+    pub line: usize,
+
+    /// Whether the file could be read
+    pub readable: bool,
+
+    /// Users, in file order
+    pub users: Vec<HtpasswdUser>,
+}
+
+impl PasswordFile {
+    /// Users whose hash scheme is weak
+    pub fn weak_users(&self) -> impl Iterator<Item = &HtpasswdUser> {
+        self.users.iter().filter(|u| !u.algorithm.is_strong())
+    }
+}
+
+/// Result of [`audit_htpasswd`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HtpasswdReport {
+    /// Every referenced password file, once
+    pub files: Vec<PasswordFile>,
+
+    /// Problems found (`htpasswd_weak_hash`, `htpasswd_empty`)
+    pub findings: Vec<Finding>,
+}
+
+/// Read the password files a configuration references and report weak
+/// hashes and empty files.
+///
+/// Relative paths are resolved against `base_dir`. Files that cannot be
+/// read are listed with `readable: false`; missing files are reported by
+/// [`check_paths`](crate::analyze::check_paths).
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::{parse, system::audit_htpasswd};
+///
+/// let dir = tempfile::tempdir()?;
+/// std::fs::write(dir.path().join("users"), "alice:$apr1$x$y\n")?;
+/// let config = parse("http { auth_basic_user_file users; }")?;
+///
+/// let report = audit_htpasswd(&config, dir.path());
+/// assert_eq!(report.files[0].users[0].name, "alice");
+/// assert_eq!(report.findings[0].rule, "htpasswd_weak_hash");
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[must_use]
+pub fn audit_htpasswd(config: &Config, base_dir: impl AsRef<Path>) -> HtpasswdReport {
+    let base_dir = base_dir.as_ref();
+    let mut report = HtpasswdReport::default();
+
+    walk(config, &mut |directive, _| {
+        if directive.name() != "auth_basic_user_file" {
+            return;
+        }
+        let Some(raw) = directive.args().first().filter(|v| !v.is_variable()) else {
+            return;
+        };
+        if raw.as_str().contains('$') {
+            return;
+        }
+        let path = normalize(&base_dir.join(raw.as_str()));
+        if report.files.iter().any(|f| f.path == path) {
+            return;
+        }
+
+        let text = std::fs::read_to_string(&path).ok();
+        let file = PasswordFile {
+            readable: text.is_some(),
+            users: text.as_deref().map(parse_htpasswd).unwrap_or_default(),
+            line: directive.span.line,
+            path,
+        };
+        if file.readable {
+            report.findings.extend(check_file(directive, &file));
+        }
+        report.files.push(file);
+    });
+
+    report
+}
+
+fn check_file(directive: &Directive, file: &PasswordFile) -> Option<Finding> {
+    let path = file.path.display();
+    if file.users.is_empty() {
+        return Some(
+            Finding::new(
+                "htpasswd_empty",
+                Severity::Error,
+                format!("password file {path} has no users: every login fails"),
+            )
+            .at(directive)
+            .with_help(format!("Add users with htpasswd -B {path} <user>")),
+        );
+    }
+
+    let weak: Vec<String> = file
+        .weak_users()
+        .map(|u| format!("{} ({})", u.name, u.algorithm))
+        .collect();
+    if weak.is_empty() {
+        return None;
+    }
+    Some(
+        Finding::new(
+            "htpasswd_weak_hash",
+            Severity::Warning,
+            format!(
+                "password file {path} has {} of {} users with weak hashes: {}",
+                weak.len(),
+                file.users.len(),
+                weak.join(", ")
+            ),
+        )
+        .at(directive)
+        .with_help(format!(
+            "Reset these passwords with bcrypt: htpasswd -B {path} <user>"
+        )),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_detect() {
+        let detected: Vec<_> = [
+            "$2b$12$abcdefghijklmnopqrstuv",
+            "$y$j9T$salt$hash",
+            "$6$salt$hash",
+            "$5$salt$hash",
+            "$apr1$salt$hash",
+            "$1$salt$hash",
+            "{SSHA}abcdef",
+            "{SHA}abcdef",
+            "rl.3StKT.4T8M",
+            "{PLAIN}secret",
+            "secret",
+        ]
+        .iter()
+        .map(|hash| HashAlgorithm::detect(hash))
+        .collect();
+        assert_eq!(
+            detected,
+            vec![
+                HashAlgorithm::Bcrypt,
+                HashAlgorithm::Yescrypt,
+                HashAlgorithm::Sha512Crypt,
+                HashAlgorithm::Sha256Crypt,
+                HashAlgorithm::Apr1,
+                HashAlgorithm::Md5Crypt,
+                HashAlgorithm::SaltedSha1,
+                HashAlgorithm::Sha1,
+                HashAlgorithm::Crypt,
+                HashAlgorithm::Plain,
+                HashAlgorithm::Unknown,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_audit_htpasswd() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("staff"),
+            "alice:$2y$10$abcdefghij\nbob:$apr1$salt$hash:Bob\n\ncarol:{PLAIN}secret\n",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("empty"), "# no users yet\n").unwrap();
+
+        let config = parse(
+            r"
+http {
+    server {
+        location /a { auth_basic_user_file staff; }
+        location /b { auth_basic_user_file ./staff; }
+        location /c { auth_basic_user_file empty; }
+        location /d { auth_basic_user_file missing; }
+    }
+}
+",
+        )
+        .unwrap();
+        let report = audit_htpasswd(&config, dir.path());
+
+        let files: Vec<_> = report
+            .files
+            .iter()
+            .map(|f| (f.line, f.readable, f.users.len()))
+            .collect();
+        assert_eq!(files, vec![(4, true, 3), (6, true, 0), (7, false, 0)]);
+        assert_eq!(report.files[0].users[2].line, 4);
+
+        let rules: Vec<_> = report.findings.iter().map(|f| f.rule.as_str()).collect();
+        assert_eq!(rules, vec!["htpasswd_weak_hash", "htpasswd_empty"]);
+        assert!(report.findings[0]
+            .message
+            .ends_with("2 of 3 users with weak hashes: bob (MD5 (apr1)), carol (plain text)"));
+    }
+}
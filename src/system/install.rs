@@ -0,0 +1,289 @@
+//! Locating the nginx installation
+//!
+//! `nginx` is not always on `PATH`: packages install it in `sbin`, which
+//! is usually only on root's `PATH`, and nginx for Windows is unpacked
+//! wherever the user likes, most often `C:\nginx`. [`find_nginx`] also
+//! looks in the common install locations.
+//!
+//! The prefix matters for relative paths: nginx resolves
+//! `conf/nginx.conf`, `logs/error.log` and the like against it. Unix builds
+//! compile in an absolute `--prefix`; nginx for Windows is built with an
+//! empty one and uses the current directory, so [`NginxInstall::command`]
+//! runs it from its install directory with explicit `-p` and `-c`.
+
+use crate::error::{Error, Result};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Prefix of Unix builds configured without `--prefix`
+const DEFAULT_PREFIX: &str = "/usr/local/nginx";
+
+/// An nginx binary and the paths it was built with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NginxInstall {
+    /// The nginx binary
+    pub binary: PathBuf,
+
+    /// Directory relative paths in the configuration are relative to
+    pub prefix: PathBuf,
+
+    /// Main configuration file
+    pub conf_path: PathBuf,
+
+    /// Whether nginx takes its prefix from the current directory, as
+    /// nginx for Windows does
+    pub prefix_from_cwd: bool,
+}
+
+impl NginxInstall {
+    /// Read the prefix and configuration path from `nginx -V` output
+    ///
+    /// An empty `--prefix=`, or none for a `.exe`, means the prefix is the
+    /// binary's directory, where nginx for Windows is run from.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nginx_discovery::system::NginxInstall;
+    /// use std::path::Path;
+    ///
+    /// let install = NginxInstall::from_build_output(
+    ///     "/usr/sbin/nginx",
+    ///     "configure arguments: --prefix=/etc/nginx --conf-path=/etc/nginx/nginx.conf",
+    /// );
+    /// assert_eq!(install.conf_path, Path::new("/etc/nginx/nginx.conf"));
+    /// assert!(!install.prefix_from_cwd);
+    /// ```
+    #[must_use]
+    pub fn from_build_output(binary: impl Into<PathBuf>, output: &str) -> Self {
+        let binary = binary.into();
+        let arguments: Vec<&str> = output
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("configure arguments:"))
+            .flat_map(str::split_whitespace)
+            .collect();
+        let argument = |name: &str| {
+            arguments
+                .iter()
+                .find_map(|arg| arg.strip_prefix(name)?.strip_prefix('='))
+        };
+
+        let is_exe = binary
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("exe"));
+        let (prefix, prefix_from_cwd) = match argument("--prefix") {
+            Some(prefix) if !prefix.is_empty() => (PathBuf::from(prefix), false),
+            None if !is_exe => (PathBuf::from(DEFAULT_PREFIX), false),
+            // `nginx.exe` alone has an empty parent
+            _ => (
+                binary
+                    .parent()
+                    .filter(|dir| !dir.as_os_str().is_empty())
+                    .unwrap_or(Path::new("."))
+                    .to_path_buf(),
+                true,
+            ),
+        };
+        let conf_path = crate::paths::resolve(
+            &prefix,
+            argument("--conf-path").unwrap_or("conf/nginx.conf"),
+        );
+
+        Self {
+            binary,
+            prefix,
+            conf_path,
+            prefix_from_cwd,
+        }
+    }
+
+    /// A command running this nginx
+    ///
+    /// When nginx takes its prefix from the current directory, the command
+    /// runs in [`prefix`](Self::prefix) and passes `-p` and `-c`, so
+    /// relative paths resolve wherever the caller runs from.
+    #[must_use]
+    pub fn command(&self) -> Command {
+        let mut command = Command::new(&self.binary);
+        if self.prefix_from_cwd {
+            // nginx appends file names to -p as given
+            let mut prefix = self.prefix.clone().into_os_string();
+            prefix.push(std::path::MAIN_SEPARATOR_STR);
+            command
+                .current_dir(&self.prefix)
+                .arg("-p")
+                .arg(prefix)
+                .arg("-c")
+                .arg(&self.conf_path);
+        }
+        command
+    }
+}
+
+/// Find the nginx binary on the system
+///
+/// Searches `PATH` for `nginx` (`nginx.exe` on Windows), then the usual
+/// install locations: `sbin` directories and Homebrew on Unix,
+/// `C:\nginx`, `Program Files`, Chocolatey and Scoop on Windows.
+///
+/// # Errors
+///
+/// Returns an error if nginx is neither in `PATH` nor in a usual install
+/// location.
+///
+/// # Examples
+///
+/// ```no_run
+/// use nginx_discovery::system::find_nginx;
+///
+/// let nginx_path = find_nginx()?;
+/// println!("Found nginx at: {}", nginx_path.display());
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+pub fn find_nginx() -> Result<PathBuf> {
+    which::which("nginx").or_else(|e| {
+        candidates()
+            .into_iter()
+            .find(|path| path.is_file())
+            .ok_or_else(|| {
+                Error::System(format!(
+                    "nginx binary not found in PATH: {e}. \
+                     Please ensure nginx is installed and accessible."
+                ))
+            })
+    })
+}
+
+/// Find nginx and read the paths it was built with from `nginx -V`
+///
+/// # Errors
+///
+/// Returns an error if nginx cannot be found or `nginx -V` fails to
+/// execute.
+///
+/// # Examples
+///
+/// ```no_run
+/// use nginx_discovery::system::nginx_install;
+///
+/// let install = nginx_install()?;
+/// println!("Configuration: {}", install.conf_path.display());
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+pub fn nginx_install() -> Result<NginxInstall> {
+    let nginx = find_nginx()?;
+    let output = Command::new(&nginx)
+        .arg("-V")
+        .output()
+        .map_err(|e| Error::System(format!("Failed to execute nginx -V: {e}")))?;
+    Ok(NginxInstall::from_build_output(
+        nginx,
+        &String::from_utf8_lossy(&output.stderr),
+    ))
+}
+
+#[cfg(not(windows))]
+fn candidates() -> Vec<PathBuf> {
+    [
+        "/usr/sbin/nginx",
+        "/usr/local/sbin/nginx",
+        "/usr/local/nginx/sbin/nginx",
+        "/opt/homebrew/bin/nginx",
+        "/usr/local/bin/nginx",
+    ]
+    .into_iter()
+    .map(PathBuf::from)
+    .collect()
+}
+
+#[cfg(windows)]
+fn candidates() -> Vec<PathBuf> {
+    let env = |name: &str| std::env::var_os(name).map(PathBuf::from);
+    let mut candidates = vec![PathBuf::from(r"C:\nginx\nginx.exe")];
+    if let Some(dir) = env("ProgramFiles") {
+        candidates.push(dir.join(r"nginx\nginx.exe"));
+    }
+    if let Some(dir) = env("USERPROFILE") {
+        candidates.push(dir.join(r"scoop\apps\nginx\current\nginx.exe"));
+    }
+    // Chocolatey unpacks into a versioned directory, C:\tools\nginx-1.25.3
+    let tools = env("ChocolateyToolsLocation").unwrap_or_else(|| PathBuf::from(r"C:\tools"));
+    if let Ok(entries) = std::fs::read_dir(tools) {
+        let mut versions: Vec<PathBuf> = entries
+            .filter_map(std::result::Result::ok)
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with("nginx"))
+            .map(|entry| entry.path().join("nginx.exe"))
+            .collect();
+        versions.sort();
+        candidates.extend(versions.into_iter().rev());
+    }
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_windows_install() {
+        let install = NginxInstall::from_build_output(
+            "C:/nginx/nginx.exe",
+            "nginx version: nginx/1.25.3\n\
+             built by cl 16.00.40219.01 for 80x86\n\
+             configure arguments: --with-cc=cl --builddir=objs.msvc8 --prefix= \
+             --conf-path=conf/nginx.conf --pid-path=logs/nginx.pid",
+        );
+        assert!(install.prefix_from_cwd);
+        assert_eq!(install.conf_path, Path::new("C:/nginx/conf/nginx.conf"));
+
+        let install = NginxInstall::from_build_output("/opt/nginx/sbin/nginx", "");
+        assert_eq!(install.prefix, Path::new("/usr/local/nginx"));
+        assert_eq!(
+            install.conf_path,
+            Path::new("/usr/local/nginx/conf/nginx.conf")
+        );
+        assert_eq!(install.command().get_args().count(), 0);
+    }
+
+    #[test]
+    fn test_install_edge_cases() {
+        // The extension is matched without case, and a bare file name runs
+        // from the current directory
+        let install = NginxInstall::from_build_output("nginx.EXE", "nginx version: nginx/1.25.3");
+        assert!(install.prefix_from_cwd);
+        assert_eq!(install.prefix, Path::new("."));
+        assert_eq!(install.conf_path, Path::new("./conf/nginx.conf"));
+
+        let command = install.command();
+        assert_eq!(command.get_current_dir(), Some(Path::new(".")));
+        let args: Vec<_> = command.get_args().collect();
+        assert_eq!(args.len(), 4);
+        assert_eq!(args[0], "-p");
+        assert_eq!(
+            args[1],
+            format!(".{}", std::path::MAIN_SEPARATOR_STR).as_str()
+        );
+        assert_eq!(
+            (args[2], args[3]),
+            ("-c".as_ref(), "./conf/nginx.conf".as_ref())
+        );
+
+        // Absolute configuration paths are kept, Windows ones included;
+        // options that only start like `--prefix` are not it
+        let install = NginxInstall::from_build_output(
+            "/usr/sbin/nginx",
+            "configure arguments: --prefix-extra=/x --prefix=/etc/nginx \
+             --conf-path=C:\\nginx\\conf\\nginx.conf",
+        );
+        assert_eq!(install.prefix, Path::new("/etc/nginx"));
+        assert_eq!(install.conf_path, Path::new(r"C:\nginx\conf\nginx.conf"));
+        assert!(!install.prefix_from_cwd);
+
+        let install = NginxInstall::from_build_output(
+            "/usr/sbin/nginx",
+            "built with OpenSSL 3.0.2\n  configure arguments: --conf-path=/etc/nginx/nginx.conf\n",
+        );
+        assert_eq!(install.prefix, Path::new(DEFAULT_PREFIX));
+        assert_eq!(install.conf_path, Path::new("/etc/nginx/nginx.conf"));
+    }
+}
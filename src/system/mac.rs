@@ -0,0 +1,448 @@
+//! `SELinux` and `AppArmor` hints
+//!
+//! With a mandatory access control module enforcing, nginx fails with
+//! "Permission denied" even where file modes allow access, because the
+//! policy only lets it read content, logs and certificates in the standard
+//! locations. [`audit_mac`] detects `SELinux` or an `AppArmor` profile for
+//! nginx and flags configured paths the stock policies likely block:
+//!
+//! - `selinux_context` / `apparmor_profile`: document roots, log
+//!   directories and certificates outside the locations the policy labels
+//!   for nginx, such as a `root` under `/home`
+//! - `selinux_network`: proxying to a network address, which needs the
+//!   `httpd_can_network_connect` boolean
+//! - `selinux_port`: listening on a port not labelled `http_port_t`
+//!
+//! Findings are hints: local policy changes are not inspected.
+
+use super::permissions::normalize;
+use crate::ast::{Config, Directive};
+use crate::extract;
+use crate::lint::{walk, Finding, Severity};
+use crate::types::LogTarget;
+use std::fmt;
+use std::path::Path;
+
+/// Document root prefixes labelled for the web server
+const CONTENT_DIRS: &[&str] = &["/var/www", "/usr/share/nginx", "/srv/www"];
+
+/// Log directory prefixes labelled for the web server
+const LOG_DIRS: &[&str] = &["/var/log/nginx", "/var/log/httpd"];
+
+/// Certificate prefixes readable by the web server
+const CERT_DIRS: &[&str] = &["/etc/pki", "/etc/ssl", "/etc/nginx", "/etc/letsencrypt"];
+
+/// Ports labelled `http_port_t` in the targeted `SELinux` policy
+const HTTP_PORTS: &[u16] = &[80, 81, 443, 488, 8008, 8009, 8443, 9000];
+
+/// Directives whose file the policy must let nginx read
+const CERT_DIRECTIVES: &[&str] = &[
+    "ssl_certificate",
+    "ssl_certificate_key",
+    "ssl_trusted_certificate",
+    "ssl_client_certificate",
+    "ssl_dhparam",
+];
+
+/// Directives connecting to a backend
+const PASS_DIRECTIVES: &[&str] = &[
+    "proxy_pass",
+    "fastcgi_pass",
+    "uwsgi_pass",
+    "scgi_pass",
+    "grpc_pass",
+    "memcached_pass",
+];
+
+/// A Linux security module confining nginx
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum SecurityModule {
+    /// `SELinux`
+    SeLinux,
+    /// `AppArmor` with a profile for nginx
+    AppArmor,
+}
+
+impl fmt::Display for SecurityModule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::SeLinux => "SELinux",
+            Self::AppArmor => "AppArmor",
+        })
+    }
+}
+
+/// Which security module confines nginx, and whether it enforces
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MacStatus {
+    /// The module, `None` when nginx is not confined
+    pub module: Option<SecurityModule>,
+
+    /// Whether denials are enforced rather than only logged (`SELinux`
+    /// permissive mode, `AppArmor` complain mode)
+    pub enforcing: bool,
+}
+
+impl MacStatus {
+    /// Detect the security module on this system
+    ///
+    /// Reads `/sys/fs/selinux/enforce` and the `AppArmor` profile list.
+    /// When the profile list is not readable (it needs root), an nginx
+    /// profile in `/etc/apparmor.d` is assumed to enforce. Always
+    /// unconfined outside Linux.
+    #[must_use]
+    pub fn detect() -> Self {
+        if !cfg!(target_os = "linux") {
+            return Self::default();
+        }
+        let read = |path: &str| std::fs::read_to_string(path).ok();
+
+        let status = Self::from_files(
+            read("/sys/fs/selinux/enforce").as_deref(),
+            read("/sys/kernel/security/apparmor/profiles").as_deref(),
+        );
+        if status.module.is_some() {
+            return status;
+        }
+
+        let apparmor_enabled = read("/sys/module/apparmor/parameters/enabled")
+            .is_some_and(|enabled| enabled.trim() == "Y");
+        let nginx_profile = std::fs::read_dir("/etc/apparmor.d").is_ok_and(|entries| {
+            entries
+                .filter_map(std::result::Result::ok)
+                .any(|e| e.file_name().to_string_lossy().contains("nginx"))
+        });
+        if apparmor_enabled && nginx_profile {
+            Self {
+                module: Some(SecurityModule::AppArmor),
+                enforcing: true,
+            }
+        } else {
+            Self::default()
+        }
+    }
+
+    /// Status from the contents of `/sys/fs/selinux/enforce` and
+    /// `/sys/kernel/security/apparmor/profiles`, `None` for files that do
+    /// not exist
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nginx_discovery::system::{MacStatus, SecurityModule};
+    ///
+    /// let status = MacStatus::from_files(None, Some("/usr/sbin/nginx (enforce)\n"));
+    /// assert_eq!(status.module, Some(SecurityModule::AppArmor));
+    /// assert!(status.enforcing);
+    /// ```
+    #[must_use]
+    pub fn from_files(selinux_enforce: Option<&str>, apparmor_profiles: Option<&str>) -> Self {
+        if let Some(enforce) = selinux_enforce {
+            return Self {
+                module: Some(SecurityModule::SeLinux),
+                enforcing: enforce.trim() == "1",
+            };
+        }
+        apparmor_profiles
+            .and_then(|profiles| profiles.lines().find(|line| line.contains("nginx")))
+            .map_or_else(Self::default, |profile| Self {
+                module: Some(SecurityModule::AppArmor),
+                enforcing: profile.trim_end().ends_with("(enforce)"),
+            })
+    }
+}
+
+/// Result of [`audit_mac`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MacReport {
+    /// The security module the findings assume
+    pub status: MacStatus,
+
+    /// Number of paths and ports checked
+    pub checked: usize,
+
+    /// Paths and settings the policy likely blocks
+    pub findings: Vec<Finding>,
+}
+
+/// Flag paths and ports the `SELinux` or `AppArmor` policy of this system
+/// likely blocks.
+///
+/// Relative paths are resolved against `base_dir`. Nothing is flagged
+/// unless a module enforces; see [`audit_mac_with`] to check against a
+/// given status.
+#[must_use]
+pub fn audit_mac(config: &Config, base_dir: impl AsRef<Path>) -> MacReport {
+    audit_mac_with(config, base_dir, MacStatus::detect())
+}
+
+/// [`audit_mac`] against a given status, e.g. to check a configuration
+/// before deploying it to an `SELinux` host
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::parse;
+/// use nginx_discovery::system::{audit_mac_with, MacStatus};
+///
+/// let config = parse("server { listen 80; root /home/alice/site; }")?;
+/// let selinux = MacStatus::from_files(Some("1"), None);
+///
+/// let report = audit_mac_with(&config, "/etc/nginx", selinux);
+/// assert_eq!(report.findings[0].rule, "selinux_context");
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+#[must_use]
+pub fn audit_mac_with(config: &Config, base_dir: impl AsRef<Path>, status: MacStatus) -> MacReport {
+    let base_dir = base_dir.as_ref();
+    let mut report = MacReport {
+        status,
+        ..MacReport::default()
+    };
+    let Some(module) = status.module.filter(|_| status.enforcing) else {
+        return report;
+    };
+    let selinux = module == SecurityModule::SeLinux;
+    let mut network: Vec<&Directive> = Vec::new();
+
+    walk(config, &mut |directive, _| {
+        let Some(raw) = directive.first_arg() else {
+            return;
+        };
+        if raw.contains('$') {
+            return;
+        }
+        let resolve = |path: &Path| normalize(&base_dir.join(path));
+        let name = directive.name();
+
+        let (path, allowed, what) = match name {
+            "root" | "alias" => (resolve(Path::new(&raw)), CONTENT_DIRS, "document root"),
+            "access_log" | "error_log" => {
+                let target = LogTarget::parse(&raw);
+                let Some(dir) = target.file_path().and_then(Path::parent) else {
+                    return;
+                };
+                (resolve(dir), LOG_DIRS, "log directory")
+            }
+            _ if CERT_DIRECTIVES.contains(&name) => {
+                if raw.starts_with("data:") || raw.starts_with("engine:") {
+                    return;
+                }
+                (resolve(Path::new(&raw)), CERT_DIRS, "certificate")
+            }
+            "listen" if selinux => {
+                report.checked += 1;
+                report.findings.extend(port_finding(directive));
+                return;
+            }
+            _ if selinux && PASS_DIRECTIVES.contains(&name) => {
+                if !raw.contains("unix:") {
+                    network.push(directive);
+                }
+                return;
+            }
+            _ => return,
+        };
+
+        report.checked += 1;
+        if allowed.iter().any(|dir| path.starts_with(dir)) {
+            return;
+        }
+        report
+            .findings
+            .push(path_finding(module, &path, what).at(directive));
+    });
+
+    if let Some(first) = network.first() {
+        report.checked += 1;
+        report.findings.push(
+            Finding::new(
+                "selinux_network",
+                Severity::Warning,
+                format!(
+                    "{} directive(s) connect to backends over the network, which SELinux denies unless httpd_can_network_connect is on (502 Bad Gateway)",
+                    network.len()
+                ),
+            )
+            .at(first)
+            .with_help("setsebool -P httpd_can_network_connect 1"),
+        );
+    }
+
+    report
+        .findings
+        .sort_by_key(|f| f.span.map(|s| (s.line, s.col)));
+    report
+}
+
+/// `selinux_port` for a `listen` outside `http_port_t`
+fn port_finding(directive: &Directive) -> Option<Finding> {
+    let listen = extract::listen(directive)?;
+    if listen.address.starts_with("unix:") || HTTP_PORTS.contains(&listen.port) {
+        return None;
+    }
+    Some(
+        Finding::new(
+            "selinux_port",
+            Severity::Warning,
+            format!(
+                "port {} is not labelled http_port_t; SELinux will refuse the bind",
+                listen.port
+            ),
+        )
+        .at(directive)
+        .with_help(format!(
+            "semanage port -a -t http_port_t -p tcp {}",
+            listen.port
+        )),
+    )
+}
+
+fn path_finding(module: SecurityModule, path: &Path, what: &str) -> Finding {
+    match module {
+        SecurityModule::SeLinux => Finding::new(
+            "selinux_context",
+            Severity::Warning,
+            format!(
+                "{what} {} is outside the locations SELinux labels for nginx",
+                path.display()
+            ),
+        )
+        .with_help(selinux_help(path, what)),
+        SecurityModule::AppArmor => Finding::new(
+            "apparmor_profile",
+            Severity::Warning,
+            format!(
+                "{what} {} is outside the locations the nginx AppArmor profile usually allows",
+                path.display()
+            ),
+        )
+        .with_help(format!(
+            "Allow {} in the nginx profile under /etc/apparmor.d and reload it with apparmor_parser -r",
+            path.display()
+        )),
+    }
+}
+
+fn selinux_help(path: &Path, what: &str) -> String {
+    if what == "document root" && path.starts_with("/home") {
+        return format!(
+            "setsebool -P httpd_enable_homedirs 1 && chcon -R -t httpd_user_content_t {}",
+            path.display()
+        );
+    }
+    let label = match what {
+        "log directory" => "httpd_log_t",
+        "certificate" => "cert_t",
+        _ => "httpd_sys_content_t",
+    };
+    let pattern = if what == "certificate" { "" } else { "(/.*)?" };
+    format!(
+        "semanage fcontext -a -t {label} '{}{pattern}' && restorecon -Rv {}",
+        path.display(),
+        path.display()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    const CONFIG: &str = r"
+http {
+    access_log /var/log/nginx/access.log;
+    error_log /data/logs/error.log;
+    server {
+        listen 443 ssl;
+        listen 8081;
+        root /home/alice/site;
+        ssl_certificate /etc/ssl/site.pem;
+        ssl_certificate_key /opt/keys/site.key;
+        location /static { alias /var/www/static/; }
+        location /api { proxy_pass http://127.0.0.1:3000; }
+        location /php { fastcgi_pass unix:/run/php-fpm.sock; }
+    }
+}
+";
+
+    #[test]
+    fn test_mac_status_from_files() {
+        let status = MacStatus::from_files(Some("0\n"), None);
+        assert_eq!(status.module, Some(SecurityModule::SeLinux));
+        assert!(!status.enforcing);
+
+        let profiles = "/usr/bin/man (enforce)\nnginx (complain)\n";
+        let status = MacStatus::from_files(None, Some(profiles));
+        assert_eq!(status.module, Some(SecurityModule::AppArmor));
+        assert!(!status.enforcing);
+
+        assert_eq!(
+            MacStatus::from_files(None, Some("/usr/bin/man (enforce)\n")),
+            MacStatus::default()
+        );
+    }
+
+    #[test]
+    fn test_audit_selinux() {
+        let config = parse(CONFIG).unwrap();
+        let report = audit_mac_with(
+            &config,
+            "/etc/nginx",
+            MacStatus::from_files(Some("1"), None),
+        );
+
+        let rules: Vec<_> = report
+            .findings
+            .iter()
+            .map(|f| (f.rule.as_str(), f.line().unwrap()))
+            .collect();
+        assert_eq!(
+            rules,
+            vec![
+                ("selinux_context", 4),
+                ("selinux_port", 7),
+                ("selinux_context", 8),
+                ("selinux_context", 10),
+                ("selinux_network", 12),
+            ]
+        );
+        assert_eq!(report.checked, 9);
+        assert!(report.findings[2]
+            .help
+            .as_deref()
+            .unwrap()
+            .starts_with("setsebool -P httpd_enable_homedirs 1"));
+        assert_eq!(
+            report.findings[0].help.as_deref(),
+            Some("semanage fcontext -a -t httpd_log_t '/data/logs(/.*)?' && restorecon -Rv /data/logs")
+        );
+        assert!(report.findings[4].message.starts_with("1 directive(s)"));
+
+        // Permissive mode only logs denials
+        let permissive = audit_mac_with(
+            &config,
+            "/etc/nginx",
+            MacStatus::from_files(Some("0"), None),
+        );
+        assert!(permissive.findings.is_empty());
+    }
+
+    #[test]
+    fn test_audit_apparmor() {
+        let config = parse(CONFIG).unwrap();
+        let status = MacStatus {
+            module: Some(SecurityModule::AppArmor),
+            enforcing: true,
+        };
+        let report = audit_mac_with(&config, "/etc/nginx", status);
+
+        assert_eq!(report.findings.len(), 3);
+        assert!(report.findings.iter().all(|f| f.rule == "apparmor_profile"));
+        assert!(report.findings[1].message.contains("/home/alice/site"));
+    }
+}
@@ -1,43 +1,46 @@
 //! System interaction utilities for NGINX discovery
 //!
 //! This module provides functions to interact with the system to:
-//! - Find the nginx binary
+//! - Find the nginx binary and the paths, TLS library and modules it was
+//!   built with
 //! - Execute nginx commands
 //! - Parse running configurations
+//! - Audit permissions of the files a configuration references
+//! - Classify the password hashes of `auth_basic_user_file` files
+//! - Back up and restore the running configuration
+//! - Flag paths and ports `SELinux` or `AppArmor` likely block
+//! - Find the processes holding configured listen ports
+//! - Cross-check the nginx systemd unit against the configuration
 
+mod backup;
+mod htpasswd;
+mod install;
+mod mac;
+mod permissions;
+mod sockets;
+mod systemd;
+
+pub use backup::{
+    backup_config, backup_from_dump, restore, Backup, BackupOptions, FileChange, RestoreOptions,
+    RestoreReport, RestoredFile,
+};
+pub use htpasswd::{
+    audit_htpasswd, parse_htpasswd, HashAlgorithm, HtpasswdReport, HtpasswdUser, PasswordFile,
+};
+pub use install::{find_nginx, nginx_install, NginxInstall};
+pub use mac::{audit_mac, audit_mac_with, MacReport, MacStatus, SecurityModule};
+pub use permissions::{audit_permissions, Account, PermissionReport};
+pub use sockets::{
+    check_listen_sockets, listen_socket_report, listening_sockets, parse_proc_net, ListeningSocket,
+    PortState, PortUse, Protocol, SocketOwner, SocketReport,
+};
+pub use systemd::{audit_systemd, audit_systemd_with, SystemdReport, SystemdUnit};
+
+use crate::analyze::{BuildInfo, CompiledModules, NginxVersion};
 use crate::discovery::NginxDiscovery;
 use crate::error::{Error, Result};
-use std::path::PathBuf;
 use std::process::Command;
 
-/// Find the nginx binary on the system
-///
-/// Searches for the `nginx` binary in the system PATH.
-///
-/// # Errors
-///
-/// Returns an error if:
-/// - The nginx binary cannot be found in PATH
-/// - Insufficient permissions to access the binary
-///
-/// # Examples
-///
-/// ```no_run
-/// use nginx_discovery::system::find_nginx;
-///
-/// let nginx_path = find_nginx()?;
-/// println!("Found nginx at: {}", nginx_path.display());
-/// # Ok::<(), nginx_discovery::Error>(())
-/// ```
-pub fn find_nginx() -> Result<PathBuf> {
-    which::which("nginx").map_err(|e| {
-        Error::System(format!(
-            "nginx binary not found in PATH: {e}. \
-             Please ensure nginx is installed and accessible."
-        ))
-    })
-}
-
 /// Get the nginx version
 ///
 /// Executes `nginx -v` to retrieve the version information.
@@ -74,6 +77,84 @@ pub fn nginx_version() -> Result<String> {
     Ok(version)
 }
 
+/// Get the installed nginx version as a comparable [`NginxVersion`]
+///
+/// # Errors
+///
+/// Returns an error if `nginx -v` cannot be run or its output does not
+/// contain a version number.
+///
+/// # Examples
+///
+/// ```no_run
+/// use nginx_discovery::system::installed_version;
+///
+/// let version = installed_version()?;
+/// println!("NGINX {version}");
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+pub fn installed_version() -> Result<NginxVersion> {
+    let output = nginx_version()?;
+    NginxVersion::from_version_output(&output)
+        .ok_or_else(|| Error::System(format!("Cannot parse nginx version from '{output}'")))
+}
+
+/// Get the version, TLS library and modules of the installed nginx
+///
+/// Runs `nginx -V` and parses its output.
+///
+/// # Errors
+///
+/// Returns an error if nginx cannot be found or `nginx -V` fails to
+/// execute.
+///
+/// # Examples
+///
+/// ```no_run
+/// use nginx_discovery::system::nginx_build_info;
+///
+/// let info = nginx_build_info()?;
+/// if let Some(tls) = &info.tls_library {
+///     println!("Built with {tls}, TLSv1.3: {}", tls.supports_tls13());
+/// }
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+pub fn nginx_build_info() -> Result<BuildInfo> {
+    let nginx = find_nginx()?;
+
+    let output = Command::new(nginx)
+        .arg("-V")
+        .output()
+        .map_err(|e| Error::System(format!("Failed to execute nginx -V: {e}")))?;
+
+    // nginx -V outputs to stderr
+    Ok(BuildInfo::from_build_output(&String::from_utf8_lossy(
+        &output.stderr,
+    )))
+}
+
+/// Get the modules compiled into the installed nginx
+///
+/// Runs `nginx -V` and parses its configure arguments.
+///
+/// # Errors
+///
+/// Returns an error if nginx cannot be found or `nginx -V` fails to
+/// execute.
+///
+/// # Examples
+///
+/// ```no_run
+/// use nginx_discovery::system::compiled_modules;
+///
+/// let compiled = compiled_modules()?;
+/// println!("Lua support: {}", compiled.has("lua"));
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+pub fn compiled_modules() -> Result<CompiledModules> {
+    nginx_build_info().map(|info| info.modules)
+}
+
 /// Dump the current nginx configuration
 ///
 /// Executes `nginx -T` to dump the complete running configuration,
@@ -97,9 +178,7 @@ pub fn nginx_version() -> Result<String> {
 /// # Ok::<(), nginx_discovery::Error>(())
 /// ```
 pub fn dump_config() -> Result<String> {
-    let nginx = find_nginx()?;
-
-    let output = Command::new(nginx).arg("-T").output().map_err(|e| {
+    let output = nginx_install()?.command().arg("-T").output().map_err(|e| {
         Error::System(format!(
             "Failed to execute nginx -T: {e}. \
                  You may need to run with sudo or as root."
@@ -145,9 +224,8 @@ pub fn dump_config() -> Result<String> {
 /// # Ok::<(), nginx_discovery::Error>(())
 /// ```
 pub fn test_config() -> Result<String> {
-    let nginx = find_nginx()?;
-
-    let output = Command::new(nginx)
+    let output = nginx_install()?
+        .command()
         .arg("-t")
         .output()
         .map_err(|e| Error::System(format!("Failed to execute nginx -t: {e}")))?;
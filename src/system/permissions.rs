@@ -0,0 +1,434 @@
+//! Ownership and permission audit of security-critical files
+//!
+//! Checks the files a configuration references against the account nginx
+//! workers run as (the `user` directive, `nobody` by default):
+//!
+//! - `key_permissions`: private keys, session ticket keys and password
+//!   files must not be readable or writable by everyone
+//! - `auth_file_served`: `auth_basic_user_file` files must not live under a
+//!   `root` or `alias` directory, where they could be downloaded
+//! - `log_dir_writable`: log directories must be writable by the worker
+//!   user, or log rotation (`nginx -s reopen`) fails
+//!
+//! Mode and ownership checks need Unix; elsewhere only the
+//! `auth_file_served` check runs and [`PermissionReport::supported`] is
+//! `false`.
+
+use crate::ast::{Config, Directive};
+use crate::lint::{walk, Finding, Severity};
+use crate::types::LogTarget;
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+
+/// Files that hold secrets: `(directive, description)`
+const SECRET_FILES: &[(&str, &str)] = &[
+    ("ssl_certificate_key", "private key"),
+    ("proxy_ssl_certificate_key", "private key"),
+    ("grpc_ssl_certificate_key", "private key"),
+    ("ssl_session_ticket_key", "session ticket key"),
+    ("ssl_password_file", "key password file"),
+    ("proxy_ssl_password_file", "key password file"),
+];
+
+/// Worker user when the configuration has no `user` directive
+const DEFAULT_USER: &str = "nobody";
+
+/// A system account resolved from `/etc/passwd` and `/etc/group`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Account {
+    /// User name
+    pub name: String,
+    /// User id
+    pub uid: u32,
+    /// Primary group id
+    pub gid: u32,
+    /// Supplementary group ids
+    pub groups: Vec<u32>,
+}
+
+impl Account {
+    /// Look up a user on this system
+    ///
+    /// Returns `None` when the user does not exist or the platform has no
+    /// `/etc/passwd`.
+    #[must_use]
+    pub fn lookup(name: &str) -> Option<Self> {
+        if !cfg!(unix) {
+            return None;
+        }
+        let passwd = std::fs::read_to_string("/etc/passwd").ok()?;
+        let group = std::fs::read_to_string("/etc/group").unwrap_or_default();
+        Self::from_files(name, &passwd, &group)
+    }
+
+    /// Resolve a user from the contents of `passwd` and `group` files
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nginx_discovery::system::Account;
+    ///
+    /// let passwd = "root:x:0:0::/root:/bin/sh\nwww-data:x:33:33::/var/www:/usr/sbin/nologin\n";
+    /// let group = "www-data:x:33:\nadm:x:4:syslog,www-data\n";
+    ///
+    /// let account = Account::from_files("www-data", passwd, group).unwrap();
+    /// assert_eq!((account.uid, account.gid), (33, 33));
+    /// assert_eq!(account.groups, vec![4]);
+    /// ```
+    #[must_use]
+    pub fn from_files(name: &str, passwd: &str, group: &str) -> Option<Self> {
+        let (uid, gid) = passwd.lines().find_map(|line| {
+            let fields: Vec<&str> = line.split(':').collect();
+            if fields.len() < 4 || fields[0] != name {
+                return None;
+            }
+            Some((fields[2].parse().ok()?, fields[3].parse().ok()?))
+        })?;
+
+        let groups = group
+            .lines()
+            .filter_map(|line| {
+                let fields: Vec<&str> = line.split(':').collect();
+                let members = fields.get(3)?;
+                let id: u32 = fields.get(2)?.parse().ok()?;
+                (id != gid && members.split(',').any(|m| m.trim() == name)).then_some(id)
+            })
+            .collect();
+
+        Some(Self {
+            name: name.to_string(),
+            uid,
+            gid,
+            groups,
+        })
+    }
+
+    /// Whether this account can write to a file with the given owning user,
+    /// owning group and mode
+    #[must_use]
+    pub fn can_write(&self, owner: u32, group: u32, mode: u32) -> bool {
+        if self.uid == 0 {
+            return true;
+        }
+        if owner == self.uid {
+            return mode & 0o200 != 0;
+        }
+        if group == self.gid || self.groups.contains(&group) {
+            return mode & 0o020 != 0;
+        }
+        mode & 0o002 != 0
+    }
+}
+
+/// Result of [`audit_permissions`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PermissionReport {
+    /// Worker user the log directories were checked against
+    pub user: String,
+
+    /// Whether mode and ownership checks ran (Unix only)
+    pub supported: bool,
+
+    /// Number of files and directories checked
+    pub checked: usize,
+
+    /// Problems found
+    pub findings: Vec<Finding>,
+}
+
+/// Audit ownership and permissions of the files a configuration references.
+///
+/// Relative paths are resolved against `base_dir`.
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::{parse, system::audit_permissions};
+///
+/// let config = parse(r"
+/// http {
+///     server {
+///         root /srv/www;
+///         location /admin { auth_basic_user_file /srv/www/.htpasswd; }
+///     }
+/// }
+/// ")?;
+///
+/// let report = audit_permissions(&config, "/etc/nginx");
+/// assert_eq!(report.findings[0].rule, "auth_file_served");
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+#[must_use]
+pub fn audit_permissions(config: &Config, base_dir: impl AsRef<Path>) -> PermissionReport {
+    audit(config, base_dir.as_ref(), Account::lookup)
+}
+
+fn audit(
+    config: &Config,
+    base_dir: &Path,
+    lookup: impl Fn(&str) -> Option<Account>,
+) -> PermissionReport {
+    let user = config
+        .find_directives("user")
+        .first()
+        .and_then(|d| d.first_arg())
+        .unwrap_or_else(|| DEFAULT_USER.to_string());
+
+    let mut report = PermissionReport {
+        user: user.clone(),
+        supported: cfg!(unix),
+        ..PermissionReport::default()
+    };
+
+    let mut secret_files: Vec<(&Directive, PathBuf, &str)> = Vec::new();
+    let mut auth_files: Vec<(&Directive, PathBuf)> = Vec::new();
+    let mut document_roots: Vec<(&Directive, PathBuf)> = Vec::new();
+    let mut log_dirs: BTreeMap<PathBuf, &Directive> = BTreeMap::new();
+
+    walk(config, &mut |directive, _| {
+        let Some(arg) = directive.args().first().filter(|v| !v.is_variable()) else {
+            return;
+        };
+        let raw = arg.as_str();
+        if raw.contains('$') || raw.starts_with("data:") || raw.starts_with("engine:") {
+            return;
+        }
+        let path = normalize(&crate::paths::resolve(base_dir, raw));
+
+        match directive.name() {
+            "auth_basic_user_file" => auth_files.push((directive, path)),
+            "root" | "alias" => document_roots.push((directive, path)),
+            "access_log" | "error_log" => {
+                let target = LogTarget::parse(raw);
+                if let Some(dir) = target.file_path().and_then(Path::parent) {
+                    log_dirs
+                        .entry(normalize(&crate::paths::resolve(
+                            base_dir,
+                            &dir.to_string_lossy(),
+                        )))
+                        .or_insert(directive);
+                }
+            }
+            name => {
+                if let Some((_, what)) = SECRET_FILES.iter().find(|(n, _)| *n == name) {
+                    secret_files.push((directive, path, what));
+                }
+            }
+        }
+    });
+
+    for (directive, path) in &auth_files {
+        report.checked += 1;
+        if let Some((root, root_path)) = document_roots.iter().find(|(_, r)| path.starts_with(r)) {
+            report.findings.push(
+                Finding::new(
+                    "auth_file_served",
+                    Severity::Error,
+                    format!(
+                        "{} is inside the document root {} (line {}) and may be downloadable",
+                        path.display(),
+                        root_path.display(),
+                        root.span.line
+                    ),
+                )
+                .at(directive)
+                .with_help("Move the password file outside every root and alias directory"),
+            );
+        }
+    }
+
+    #[cfg(unix)]
+    {
+        for (directive, path, what) in &secret_files {
+            if let Some(finding) = secret_file_finding(directive, path, what) {
+                report.findings.push(finding);
+            }
+            report.checked += 1;
+        }
+
+        let account = lookup(&user);
+        for (dir, directive) in &log_dirs {
+            report.checked += 1;
+            if let Some(finding) = log_dir_finding(directive, dir, &user, account.as_ref()) {
+                report.findings.push(finding);
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = (secret_files, log_dirs, lookup);
+
+    report
+        .findings
+        .sort_by_key(|f| f.span.map(|s| (s.line, s.col)));
+    report
+}
+
+#[cfg(unix)]
+fn secret_file_finding(directive: &Directive, path: &Path, what: &str) -> Option<Finding> {
+    use std::os::unix::fs::MetadataExt;
+
+    let mode = std::fs::metadata(path).ok()?.mode();
+    let access = match (mode & 0o004 != 0, mode & 0o002 != 0) {
+        (false, false) => return None,
+        (true, false) => "readable",
+        (false, true) => "writable",
+        (true, true) => "readable and writable",
+    };
+
+    Some(
+        Finding::new(
+            "key_permissions",
+            Severity::Error,
+            format!(
+                "{what} {} is world-{access} (mode {:o})",
+                path.display(),
+                mode & 0o777
+            ),
+        )
+        .at(directive)
+        .with_help(format!("chmod 600 {}", path.display())),
+    )
+}
+
+#[cfg(unix)]
+fn log_dir_finding(
+    directive: &Directive,
+    dir: &Path,
+    user: &str,
+    account: Option<&Account>,
+) -> Option<Finding> {
+    use std::os::unix::fs::MetadataExt;
+
+    let metadata = std::fs::metadata(dir).ok()?;
+    let Some(account) = account else {
+        return Some(
+            Finding::new(
+                "log_dir_writable",
+                Severity::Warning,
+                format!("worker user \"{user}\" does not exist on this system"),
+            )
+            .at(directive)
+            .with_help("Create the user or change the \"user\" directive"),
+        );
+    };
+
+    if account.can_write(metadata.uid(), metadata.gid(), metadata.mode()) {
+        return None;
+    }
+    Some(
+        Finding::new(
+            "log_dir_writable",
+            Severity::Warning,
+            format!(
+                "log directory {} is not writable by worker user \"{user}\"",
+                dir.display()
+            ),
+        )
+        .at(directive)
+        .with_help(format!("chown {user} {}", dir.display())),
+    )
+}
+
+/// Resolve `.` and `..` without touching the file system
+pub(super) fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_account_can_write() {
+        let account = Account {
+            name: "nginx".to_string(),
+            uid: 101,
+            gid: 101,
+            groups: vec![4],
+        };
+        assert!(account.can_write(101, 0, 0o755));
+        assert!(!account.can_write(0, 0, 0o755));
+        assert!(account.can_write(0, 4, 0o775));
+        assert!(!account.can_write(0, 4, 0o755));
+        assert!(account.can_write(0, 0, 0o777));
+        assert!(!account.can_write(101, 0, 0o555));
+    }
+
+    #[test]
+    fn test_auth_file_under_root() {
+        let config = parse(
+            r"
+http {
+    server {
+        root /srv/www;
+        location /a { auth_basic_user_file /srv/www/../www/admin/.htpasswd; }
+        location /b { auth_basic_user_file /etc/nginx/.htpasswd; }
+        location /c { alias /opt/static/; auth_basic_user_file /opt/static/users; }
+    }
+}
+",
+        )
+        .unwrap();
+        let report = audit(&config, Path::new("/etc/nginx"), |_| None);
+
+        assert_eq!(report.user, "nobody");
+        assert_eq!(report.checked, 3);
+        assert_eq!(report.findings.len(), 2);
+        assert_eq!(report.findings[0].line(), Some(5));
+        assert!(report.findings[0]
+            .message
+            .contains("document root /srv/www (line 4)"));
+        assert_eq!(report.findings[1].line(), Some(7));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_key_and_log_dir_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let key = dir.path().join("server.key");
+        std::fs::write(&key, "").unwrap();
+        std::fs::set_permissions(&key, std::fs::Permissions::from_mode(0o644)).unwrap();
+        let logs = dir.path().join("logs");
+        std::fs::create_dir(&logs).unwrap();
+        std::fs::set_permissions(&logs, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let config = parse(
+            "user www;\nhttp {\n  access_log logs/access.log;\n  ssl_certificate_key server.key;\n}\n",
+        )
+        .unwrap();
+        let other_user = |name: &str| {
+            Some(Account {
+                name: name.to_string(),
+                uid: 65_000,
+                gid: 65_000,
+                groups: Vec::new(),
+            })
+        };
+        let report = audit(&config, dir.path(), other_user);
+
+        let rules: Vec<_> = report.findings.iter().map(|f| f.rule.as_str()).collect();
+        assert_eq!(rules, vec!["log_dir_writable", "key_permissions"]);
+        assert!(report.findings[1]
+            .message
+            .contains("world-readable (mode 644)"));
+
+        std::fs::set_permissions(&key, std::fs::Permissions::from_mode(0o600)).unwrap();
+        std::fs::set_permissions(&logs, std::fs::Permissions::from_mode(0o777)).unwrap();
+        assert!(audit(&config, dir.path(), other_user).findings.is_empty());
+    }
+}
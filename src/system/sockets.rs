@@ -0,0 +1,456 @@
+//! Listening sockets and the processes holding them
+//!
+//! A port check that fails only says nothing answers, or something else
+//! does. [`check_listen_sockets`] reads the kernel socket tables
+//! (`/proc/net/tcp`, `tcp6`, `udp`, `udp6`) and the file descriptors of
+//! running processes to tell whether each configured `listen` port is
+//! free (nginx is not running), held by nginx, or taken by another
+//! service — naming it and its PID.
+//!
+//! Linux only; elsewhere [`SocketReport::supported`] is `false`. Sockets
+//! of processes owned by other users are only attributed when running as
+//! root.
+
+use crate::ast::Config;
+use crate::extract;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::path::Path;
+
+/// Transport protocol of a socket
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Protocol {
+    /// TCP
+    Tcp,
+    /// UDP (QUIC)
+    Udp,
+}
+
+impl fmt::Display for Protocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Tcp => "tcp",
+            Self::Udp => "udp",
+        })
+    }
+}
+
+/// A process holding a socket
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SocketOwner {
+    /// Process id
+    pub pid: u32,
+
+    /// Process name (`/proc/<pid>/comm`)
+    pub name: String,
+}
+
+impl SocketOwner {
+    /// Whether the process is nginx
+    #[must_use]
+    pub fn is_nginx(&self) -> bool {
+        self.name == "nginx" || self.name.starts_with("nginx:")
+    }
+}
+
+impl fmt::Display for SocketOwner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (pid {})", self.name, self.pid)
+    }
+}
+
+/// A bound TCP listener or UDP socket
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ListeningSocket {
+    /// TCP or UDP
+    pub protocol: Protocol,
+
+    /// Local address, unspecified for wildcard binds
+    pub address: IpAddr,
+
+    /// Local port
+    pub port: u16,
+
+    /// Socket inode, linking it to the file descriptors of its process
+    pub inode: u64,
+
+    /// Process holding the socket, when visible
+    pub owner: Option<SocketOwner>,
+}
+
+/// Who holds a configured listen port
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case", tag = "state"))]
+pub enum PortState {
+    /// Nothing is bound
+    Free,
+    /// nginx holds it
+    Nginx {
+        /// PID of the first nginx process found with the socket
+        pid: u32,
+    },
+    /// Another process holds it
+    Other(SocketOwner),
+    /// Something is bound but its process is not visible
+    Unknown,
+}
+
+/// A configured `listen` with the state of its port
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PortUse {
+    /// Address as configured, `*` for all addresses
+    pub address: String,
+
+    /// Configured port
+    pub port: u16,
+
+    /// TCP, or UDP for `quic` and `udp` listeners
+    pub protocol: Protocol,
+
+    /// Who holds the port
+    pub state: PortState,
+}
+
+/// Result of [`check_listen_sockets`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SocketReport {
+    /// Whether socket tables could be read (Linux only)
+    pub supported: bool,
+
+    /// Each distinct configured listen address, port and protocol
+    pub ports: Vec<PortUse>,
+}
+
+impl SocketReport {
+    /// Ports held by a process other than nginx
+    pub fn conflicts(&self) -> impl Iterator<Item = &PortUse> {
+        self.ports
+            .iter()
+            .filter(|p| matches!(p.state, PortState::Other(_)))
+    }
+
+    /// Ports nothing is bound to
+    pub fn free(&self) -> impl Iterator<Item = &PortUse> {
+        self.ports.iter().filter(|p| p.state == PortState::Free)
+    }
+
+    /// Whether nginx holds at least one configured port
+    #[must_use]
+    pub fn nginx_running(&self) -> bool {
+        self.ports
+            .iter()
+            .any(|p| matches!(p.state, PortState::Nginx { .. }))
+    }
+}
+
+/// Bound sockets on this system with the processes holding them
+///
+/// Empty outside Linux.
+#[must_use]
+pub fn listening_sockets() -> Vec<ListeningSocket> {
+    let tables = [
+        ("/proc/net/tcp", Protocol::Tcp),
+        ("/proc/net/tcp6", Protocol::Tcp),
+        ("/proc/net/udp", Protocol::Udp),
+        ("/proc/net/udp6", Protocol::Udp),
+    ];
+    let mut sockets: Vec<ListeningSocket> = tables
+        .iter()
+        .filter_map(|(path, protocol)| {
+            let text = std::fs::read_to_string(path).ok()?;
+            Some(parse_proc_net(&text, *protocol))
+        })
+        .flatten()
+        .collect();
+    attach_owners(Path::new("/proc"), &mut sockets);
+    sockets
+}
+
+/// Listening TCP and bound UDP sockets of a `/proc/net/{tcp,udp}[6]`
+/// table, without owners
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::system::{parse_proc_net, Protocol};
+///
+/// let table = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode\n\
+///    0: 00000000:0050 00000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 4242 1 0 100 0 0 10 0\n";
+/// let sockets = parse_proc_net(table, Protocol::Tcp);
+/// assert_eq!(sockets[0].port, 80);
+/// assert_eq!(sockets[0].inode, 4242);
+/// ```
+#[must_use]
+pub fn parse_proc_net(text: &str, protocol: Protocol) -> Vec<ListeningSocket> {
+    // TCP_LISTEN; unconnected UDP sockets show TCP_CLOSE
+    let bound_state = match protocol {
+        Protocol::Tcp => "0A",
+        Protocol::Udp => "07",
+    };
+    text.lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.get(3) != Some(&bound_state) {
+                return None;
+            }
+            let (address, port) = fields.get(1)?.split_once(':')?;
+            Some(ListeningSocket {
+                protocol,
+                address: parse_hex_address(address)?,
+                port: u16::from_str_radix(port, 16).ok()?,
+                inode: fields.get(9)?.parse().ok()?,
+                owner: None,
+            })
+        })
+        .collect()
+}
+
+/// Addresses are written as 32-bit words in host byte order
+fn parse_hex_address(hex: &str) -> Option<IpAddr> {
+    let words: Option<Vec<u32>> = (0..hex.len() / 8)
+        .map(|i| u32::from_str_radix(hex.get(i * 8..i * 8 + 8)?, 16).ok())
+        .collect();
+    let bytes: Vec<u8> = words?
+        .into_iter()
+        .flat_map(|w| {
+            if cfg!(target_endian = "little") {
+                w.to_le_bytes()
+            } else {
+                w.to_be_bytes()
+            }
+        })
+        .collect();
+    match bytes.len() {
+        4 => Some(IpAddr::V4(Ipv4Addr::new(
+            bytes[0], bytes[1], bytes[2], bytes[3],
+        ))),
+        16 => {
+            let octets: [u8; 16] = bytes.try_into().ok()?;
+            Some(IpAddr::V6(Ipv6Addr::from(octets)))
+        }
+        _ => None,
+    }
+}
+
+/// Find the process of each socket through `<proc>/<pid>/fd` links
+fn attach_owners(proc: &Path, sockets: &mut [ListeningSocket]) {
+    let Ok(entries) = std::fs::read_dir(proc) else {
+        return;
+    };
+    for entry in entries.filter_map(std::result::Result::ok) {
+        let Some(pid) = entry.file_name().to_str().and_then(|s| s.parse().ok()) else {
+            continue;
+        };
+        // Processes of other users are unreadable without root
+        let Ok(fds) = std::fs::read_dir(entry.path().join("fd")) else {
+            continue;
+        };
+        for fd in fds.filter_map(std::result::Result::ok) {
+            let Ok(target) = std::fs::read_link(fd.path()) else {
+                continue;
+            };
+            let Some(inode) = target
+                .to_str()
+                .and_then(|t| t.strip_prefix("socket:["))
+                .and_then(|t| t.strip_suffix(']'))
+                .and_then(|t| t.parse::<u64>().ok())
+            else {
+                continue;
+            };
+            for socket in sockets
+                .iter_mut()
+                .filter(|s| s.inode == inode && s.owner.is_none())
+            {
+                let name = std::fs::read_to_string(entry.path().join("comm"))
+                    .map(|n| n.trim().to_string())
+                    .unwrap_or_default();
+                socket.owner = Some(SocketOwner { pid, name });
+            }
+        }
+    }
+}
+
+/// Check who holds each port the configuration listens on.
+///
+/// # Examples
+///
+/// ```no_run
+/// use nginx_discovery::{parse, system::check_listen_sockets};
+///
+/// let config = parse("http { server { listen 80; } }")?;
+/// let report = check_listen_sockets(&config);
+/// for port in report.conflicts() {
+///     println!("port {} is taken: {:?}", port.port, port.state);
+/// }
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+#[must_use]
+pub fn check_listen_sockets(config: &Config) -> SocketReport {
+    let mut report = listen_socket_report(config, &listening_sockets());
+    report.supported = cfg!(target_os = "linux") && Path::new("/proc/net/tcp").exists();
+    report
+}
+
+/// [`check_listen_sockets`] against a given socket list
+#[must_use]
+pub fn listen_socket_report(config: &Config, sockets: &[ListeningSocket]) -> SocketReport {
+    let mut report = SocketReport {
+        supported: true,
+        ports: Vec::new(),
+    };
+    let listens = extract::servers(config)
+        .unwrap_or_default()
+        .into_iter()
+        .flat_map(|s| s.listen);
+
+    for listen in listens {
+        if listen.address.starts_with("unix:") {
+            continue;
+        }
+        let protocol = if listen.is_udp() {
+            Protocol::Udp
+        } else {
+            Protocol::Tcp
+        };
+        if report
+            .ports
+            .iter()
+            .any(|p| p.address == listen.address && p.port == listen.port && p.protocol == protocol)
+        {
+            continue;
+        }
+
+        let configured: Option<IpAddr> = listen.address.parse().ok();
+        let bound: Vec<&ListeningSocket> = sockets
+            .iter()
+            .filter(|s| s.protocol == protocol && s.port == listen.port)
+            .filter(|s| match configured {
+                // Wildcard binds on either side collide
+                Some(ip) => ip.is_unspecified() || s.address.is_unspecified() || s.address == ip,
+                None => true,
+            })
+            .collect();
+
+        let owners = || bound.iter().filter_map(|s| s.owner.as_ref());
+        let state = if bound.is_empty() {
+            PortState::Free
+        } else if let Some(other) = owners().find(|o| !o.is_nginx()) {
+            PortState::Other(other.clone())
+        } else if let Some(nginx) = owners().next() {
+            PortState::Nginx { pid: nginx.pid }
+        } else {
+            PortState::Unknown
+        };
+
+        report.ports.push(PortUse {
+            address: listen.address.clone(),
+            port: listen.port,
+            protocol,
+            state,
+        });
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    const TCP6: &str = "  sl  local_address                         remote_address                        st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 00000000000000000000000000000000:1F90 00000000000000000000000000000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 777 1 0000000000000000 100 0 0 10 0
+   1: 00000000000000000000000001000000:0016 00000000000000000000000001000000:9C40 01 00000000:00000000 00:00000000 00000000     0        0 778 1 0000000000000000 100 0 0 10 0
+";
+
+    #[test]
+    fn test_parse_proc_net() {
+        let tcp = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 0100007F:0CEA 00000000:0000 0A 00000000:00000000 00:00000000 00000000   999        0 1001 1 0 100 0 0 10 0
+   1: 0100007F:0CEA 0100007F:D431 01 00000000:00000000 00:00000000 00000000   999        0 1002 1 0 100 0 0 10 0
+";
+        let sockets = parse_proc_net(tcp, Protocol::Tcp);
+        assert_eq!(sockets.len(), 1);
+        assert_eq!(sockets[0].address, IpAddr::V4(Ipv4Addr::LOCALHOST));
+        assert_eq!(sockets[0].port, 3306);
+        assert_eq!(sockets[0].inode, 1001);
+
+        let sockets = parse_proc_net(TCP6, Protocol::Tcp);
+        assert_eq!(sockets.len(), 1);
+        assert_eq!(sockets[0].address, IpAddr::V6(Ipv6Addr::UNSPECIFIED));
+        assert_eq!(sockets[0].port, 8080);
+    }
+
+    #[test]
+    fn test_listen_socket_report() {
+        let socket = |address: &str, port, owner: Option<(u32, &str)>| ListeningSocket {
+            protocol: Protocol::Tcp,
+            address: address.parse().unwrap(),
+            port,
+            inode: 0,
+            owner: owner.map(|(pid, name)| SocketOwner {
+                pid,
+                name: name.to_string(),
+            }),
+        };
+        let sockets = [
+            socket("0.0.0.0", 80, Some((100, "nginx"))),
+            socket("127.0.0.1", 8080, Some((200, "apache2"))),
+            socket("0.0.0.0", 9000, None),
+        ];
+        let config = parse(
+            "http {
+               server { listen 80; listen 443 ssl; listen 443 quic; }
+               server { listen 80; listen 0.0.0.0:8080; listen 10.0.0.1:9000; listen unix:/run/a.sock; }
+             }",
+        )
+        .unwrap();
+
+        let report = listen_socket_report(&config, &sockets);
+        let states: Vec<_> = report
+            .ports
+            .iter()
+            .map(|p| (p.port, p.protocol, p.state.clone()))
+            .collect();
+        assert_eq!(
+            states,
+            vec![
+                (80, Protocol::Tcp, PortState::Nginx { pid: 100 }),
+                (443, Protocol::Tcp, PortState::Free),
+                (443, Protocol::Udp, PortState::Free),
+                (
+                    8080,
+                    Protocol::Tcp,
+                    PortState::Other(SocketOwner {
+                        pid: 200,
+                        name: "apache2".to_string()
+                    })
+                ),
+                (9000, Protocol::Tcp, PortState::Unknown),
+            ]
+        );
+        assert!(report.nginx_running());
+        assert_eq!(report.conflicts().count(), 1);
+        assert_eq!(report.free().count(), 2);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_attach_owners() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let socket = listening_sockets()
+            .into_iter()
+            .find(|s| s.protocol == Protocol::Tcp && s.port == port)
+            .unwrap();
+        assert_eq!(socket.owner.unwrap().pid, std::process::id());
+    }
+}
@@ -0,0 +1,369 @@
+//! Cross-check of the nginx systemd unit
+//!
+//! The limits nginx runs with come from its service unit as much as from
+//! the configuration. [`audit_systemd`] reads `nginx.service` with its
+//! drop-ins and compares it with the configuration:
+//!
+//! - `systemd_nofile`: `LimitNOFILE` (1024 when unset) is below what
+//!   `worker_connections` needs and `worker_rlimit_nofile` does not raise
+//!   it
+//! - `systemd_config_test`: no `ExecStartPre` runs `nginx -t`, so a broken
+//!   configuration is only noticed when the service fails to start
+//! - `systemd_restart`: no `Restart` policy, so a crashed nginx stays down
+
+use crate::ast::{Config, Directive};
+use crate::lint::{Finding, Severity};
+use std::path::{Path, PathBuf};
+
+/// Directories searched for `nginx.service`, most specific first
+const UNIT_DIRS: &[&str] = &[
+    "/etc/systemd/system",
+    "/run/systemd/system",
+    "/usr/lib/systemd/system",
+    "/lib/systemd/system",
+];
+
+/// Soft file descriptor limit of services without `LimitNOFILE`
+const DEFAULT_NOFILE: u64 = 1024;
+
+/// Settings of a service unit that matter to nginx
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SystemdUnit {
+    /// Unit file, followed by the drop-ins applied to it
+    pub files: Vec<PathBuf>,
+
+    /// Soft `LimitNOFILE`, `u64::MAX` for `infinity`
+    pub limit_nofile: Option<u64>,
+
+    /// `Restart` policy
+    pub restart: Option<String>,
+
+    /// `ExecStartPre` commands, in order
+    pub exec_start_pre: Vec<String>,
+
+    /// `ExecStart` command
+    pub exec_start: Option<String>,
+}
+
+impl SystemdUnit {
+    /// Find `nginx.service` and its drop-ins on this system
+    ///
+    /// Returns `None` when no unit file exists.
+    #[must_use]
+    pub fn find() -> Option<Self> {
+        let path = UNIT_DIRS
+            .iter()
+            .map(|dir| Path::new(dir).join("nginx.service"))
+            .find(|path| path.is_file())?;
+        let mut unit = Self::default();
+        unit.load(&path).ok()?;
+
+        // Drop-ins apply in name order across directories
+        let mut drop_ins: Vec<PathBuf> = UNIT_DIRS
+            .iter()
+            .filter_map(|dir| std::fs::read_dir(Path::new(dir).join("nginx.service.d")).ok())
+            .flatten()
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().is_some_and(|e| e == "conf"))
+            .collect();
+        drop_ins.sort_by(|a, b| a.file_name().cmp(&b.file_name()));
+        for drop_in in drop_ins {
+            // An unreadable drop-in leaves the settings before it
+            let _ = unit.load(&drop_in);
+        }
+        Some(unit)
+    }
+
+    /// Read a unit file or drop-in over the settings parsed so far
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read.
+    pub fn load(&mut self, path: impl AsRef<Path>) -> crate::Result<()> {
+        let path = path.as_ref();
+        self.apply(&std::fs::read_to_string(path)?);
+        self.files.push(path.to_path_buf());
+        Ok(())
+    }
+
+    /// Parse the text of a unit file
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nginx_discovery::system::SystemdUnit;
+    ///
+    /// let unit = SystemdUnit::parse(
+    ///     "[Service]\nExecStartPre=/usr/sbin/nginx -t -q\nExecStart=/usr/sbin/nginx\nLimitNOFILE=65536:524288\n",
+    /// );
+    /// assert_eq!(unit.limit_nofile, Some(65536));
+    /// assert!(unit.tests_config());
+    /// ```
+    #[must_use]
+    pub fn parse(text: &str) -> Self {
+        let mut unit = Self::default();
+        unit.apply(text);
+        unit
+    }
+
+    /// Whether an `ExecStartPre` command runs `nginx -t`
+    #[must_use]
+    pub fn tests_config(&self) -> bool {
+        self.exec_start_pre.iter().any(|command| {
+            let mut words = command.split_whitespace();
+            // Leading `-`, `@`, `+`, `!` and `:` are systemd prefixes
+            let runs_nginx = words.next().is_some_and(|program| {
+                program
+                    .trim_start_matches(['-', '@', '+', '!', ':'])
+                    .ends_with("nginx")
+            });
+            runs_nginx && words.any(is_test_flag)
+        })
+    }
+
+    fn apply(&mut self, text: &str) {
+        let mut in_service = false;
+        let mut lines = text.lines();
+        while let Some(line) = lines.next() {
+            let mut line = line.trim().to_string();
+            // Continuation lines
+            while line.ends_with('\\') {
+                line.pop();
+                line.truncate(line.trim_end().len());
+                line.push(' ');
+                line.push_str(lines.next().unwrap_or_default().trim());
+            }
+            if line.is_empty() || line.starts_with(['#', ';']) {
+                continue;
+            }
+            if line.starts_with('[') {
+                in_service = line == "[Service]";
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            if !in_service {
+                continue;
+            }
+            let value = value.trim();
+            match key.trim() {
+                "LimitNOFILE" => {
+                    let soft = value.split(':').next().unwrap_or_default();
+                    self.limit_nofile = if value.is_empty() {
+                        None
+                    } else if soft == "infinity" {
+                        Some(u64::MAX)
+                    } else {
+                        soft.parse().ok()
+                    };
+                }
+                "Restart" => self.restart = Some(value.to_string()).filter(|v| !v.is_empty()),
+                // An empty assignment clears the list
+                "ExecStartPre" if value.is_empty() => self.exec_start_pre.clear(),
+                "ExecStartPre" => self.exec_start_pre.push(value.to_string()),
+                "ExecStart" => self.exec_start = Some(value.to_string()).filter(|v| !v.is_empty()),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Whether a command line word is `-t`, `-T` or a bundle such as `-qt`
+fn is_test_flag(word: &str) -> bool {
+    word.strip_prefix('-').is_some_and(|flags| {
+        !flags.is_empty()
+            && flags.chars().all(|c| c.is_ascii_alphabetic())
+            && flags.contains(['t', 'T'])
+    })
+}
+
+/// Result of [`audit_systemd`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SystemdReport {
+    /// The unit, `None` when nginx has no systemd unit here
+    pub unit: Option<SystemdUnit>,
+
+    /// File descriptors the configuration needs: twice
+    /// `worker_connections`, for proxied connections
+    pub needed_nofile: u64,
+
+    /// Problems found
+    pub findings: Vec<Finding>,
+}
+
+/// Compare the nginx systemd unit of this system with the configuration.
+#[must_use]
+pub fn audit_systemd(config: &Config) -> SystemdReport {
+    audit_systemd_with(config, SystemdUnit::find())
+}
+
+/// [`audit_systemd`] against a given unit
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::parse;
+/// use nginx_discovery::system::{audit_systemd_with, SystemdUnit};
+///
+/// let config = parse("events { worker_connections 4096; }")?;
+/// let unit = SystemdUnit::parse("[Service]\nExecStart=/usr/sbin/nginx\nRestart=on-failure\n");
+///
+/// let report = audit_systemd_with(&config, Some(unit));
+/// let rules: Vec<_> = report.findings.iter().map(|f| f.rule.as_str()).collect();
+/// assert_eq!(rules, vec!["systemd_nofile", "systemd_config_test"]);
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+#[must_use]
+pub fn audit_systemd_with(config: &Config, unit: Option<SystemdUnit>) -> SystemdReport {
+    let events: Option<&Directive> = config.find_directives("events").into_iter().next();
+    let worker_connections =
+        events.and_then(|e| e.find_children("worker_connections").into_iter().next());
+    let connections = worker_connections
+        .and_then(Directive::first_arg)
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(512);
+    let needed_nofile = connections * 2;
+
+    let mut report = SystemdReport {
+        unit: None,
+        needed_nofile,
+        findings: Vec::new(),
+    };
+    let Some(unit) = unit else {
+        return report;
+    };
+    let with_file = |finding: Finding| match unit.files.first() {
+        Some(file) => finding.with_file(file),
+        None => finding,
+    };
+
+    let rlimit = config
+        .find_directives("worker_rlimit_nofile")
+        .into_iter()
+        .next();
+    let limit = unit.limit_nofile.unwrap_or(DEFAULT_NOFILE);
+    if rlimit.is_none() && limit < needed_nofile {
+        let mut finding = Finding::new(
+            "systemd_nofile",
+            Severity::Warning,
+            format!(
+                "LimitNOFILE of the nginx service is {}{}, but {connections} worker_connections need up to {needed_nofile} file descriptors",
+                limit,
+                if unit.limit_nofile.is_none() { " (default)" } else { "" }
+            ),
+        )
+        .with_help(format!(
+            "Set LimitNOFILE={needed_nofile} in a drop-in (systemctl edit nginx) or worker_rlimit_nofile {needed_nofile}; in nginx.conf"
+        ));
+        if let Some(directive) = worker_connections {
+            finding = finding.at(directive);
+        } else {
+            finding = with_file(finding);
+        }
+        report.findings.push(finding);
+    }
+
+    if !unit.tests_config() {
+        report.findings.push(with_file(
+            Finding::new(
+                "systemd_config_test",
+                Severity::Warning,
+                "the nginx service does not run `nginx -t` in ExecStartPre; a broken configuration is only noticed when the service fails to start",
+            )
+            .with_help("Add ExecStartPre=/usr/sbin/nginx -t -q -g 'daemon on; master_process on;'"),
+        ));
+    }
+
+    if unit.restart.as_deref().map_or(true, |r| r == "no") {
+        report.findings.push(with_file(
+            Finding::new(
+                "systemd_restart",
+                Severity::Info,
+                "the nginx service has no Restart policy; a crashed master process stays down",
+            )
+            .with_help("Add Restart=on-failure to the [Service] section"),
+        ));
+    }
+
+    report.unit = Some(unit);
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    const DEBIAN: &str = "\
+[Unit]
+Description=A high performance web server
+After=network.target
+
+[Service]
+Type=forking
+PIDFile=/run/nginx.pid
+ExecStartPre=/usr/sbin/nginx -t -q -g 'daemon on; master_process on;'
+ExecStart=/usr/sbin/nginx -g 'daemon on; \\
+    master_process on;'
+ExecReload=/usr/sbin/nginx -g 'daemon on; master_process on;' -s reload
+TimeoutStopSec=5
+KillMode=mixed
+
+[Install]
+WantedBy=multi-user.target
+";
+
+    #[test]
+    fn test_parse_unit() {
+        let mut unit = SystemdUnit::parse(DEBIAN);
+        assert!(unit.tests_config());
+        assert_eq!(unit.limit_nofile, None);
+        assert_eq!(unit.restart, None);
+        assert_eq!(
+            unit.exec_start.as_deref(),
+            Some("/usr/sbin/nginx -g 'daemon on; master_process on;'")
+        );
+
+        // A drop-in resetting ExecStartPre
+        unit.apply("[Service]\nExecStartPre=\nExecStartPre=/bin/true\nLimitNOFILE=infinity\nRestart=always\n");
+        assert!(!unit.tests_config());
+        assert_eq!(unit.limit_nofile, Some(u64::MAX));
+        assert_eq!(unit.restart.as_deref(), Some("always"));
+
+        assert!(
+            SystemdUnit::parse("[Service]\nExecStartPre=-/usr/sbin/nginx -qt\n").tests_config()
+        );
+        assert!(!SystemdUnit::parse("[Unit]\nExecStartPre=/usr/sbin/nginx -t\n").tests_config());
+    }
+
+    #[test]
+    fn test_audit_systemd() {
+        let config = parse("events {\n    worker_connections 1024;\n}\n").unwrap();
+        let mut unit = SystemdUnit::parse(DEBIAN);
+        unit.files
+            .push(PathBuf::from("/lib/systemd/system/nginx.service"));
+
+        let report = audit_systemd_with(&config, Some(unit.clone()));
+        assert_eq!(report.needed_nofile, 2048);
+        let rules: Vec<_> = report.findings.iter().map(|f| f.rule.as_str()).collect();
+        assert_eq!(rules, vec!["systemd_nofile", "systemd_restart"]);
+        assert_eq!(report.findings[0].line(), Some(2));
+        assert!(report.findings[0].message.contains("is 1024 (default)"));
+        assert_eq!(
+            report.findings[1].file.as_deref(),
+            Some(Path::new("/lib/systemd/system/nginx.service"))
+        );
+
+        // worker_rlimit_nofile raises the limit itself
+        let raised =
+            parse("worker_rlimit_nofile 4096;\nevents { worker_connections 1024; }").unwrap();
+        unit.restart = Some("on-failure".to_string());
+        assert!(audit_systemd_with(&raised, Some(unit)).findings.is_empty());
+
+        let report = audit_systemd_with(&config, None);
+        assert!(report.unit.is_none() && report.findings.is_empty());
+    }
+}
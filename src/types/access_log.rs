@@ -1,5 +1,7 @@
 //! Access log type
 
+use crate::ast::{split_variables, Segment};
+use crate::types::LogTarget;
 use std::collections::HashMap;
 use std::path::PathBuf;
 
@@ -7,9 +9,12 @@ use std::path::PathBuf;
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AccessLog {
-    /// Path to the log file
+    /// Path to the log file, as written in the directive
     pub path: PathBuf,
 
+    /// Where the log is written (file, syslog, ...)
+    pub target: LogTarget,
+
     /// Format name (e.g., "combined", "main")
     pub format_name: Option<String>,
 
@@ -18,6 +23,11 @@ pub struct AccessLog {
 
     /// Context where this log was defined
     pub context: LogContext,
+
+    /// Names of the enclosing server block, used to expand `$host` and
+    /// `$server_name` in templated paths
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub server_names: Vec<String>,
 }
 
 /// Context where a log directive appears
@@ -34,13 +44,19 @@ pub enum LogContext {
 
 impl AccessLog {
     /// Create a new access log
+    ///
+    /// The target is classified from the path, so
+    /// `AccessLog::new("syslog:server=10.0.0.1")` is a syslog log.
     #[must_use]
     pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
         Self {
-            path: path.into(),
+            target: LogTarget::parse(&path.to_string_lossy()),
+            path,
             format_name: None,
             options: HashMap::new(),
             context: LogContext::Main,
+            server_names: Vec::new(),
         }
     }
 
@@ -64,4 +80,174 @@ impl AccessLog {
         self.options.insert(key.into(), value.into());
         self
     }
+
+    /// Set the names of the enclosing server block
+    #[must_use]
+    pub fn with_server_names<S: Into<String>>(
+        mut self,
+        names: impl IntoIterator<Item = S>,
+    ) -> Self {
+        self.server_names = names.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Whether the log is written to a file (not syslog, stderr, ...)
+    #[must_use]
+    pub fn is_file(&self) -> bool {
+        self.target.is_file()
+    }
+
+    /// Whether the path contains variables (e.g. `/var/log/nginx/$host.log`)
+    ///
+    /// nginx opens such logs per request, so the literal path never exists
+    /// on disk.
+    #[must_use]
+    pub fn is_templated(&self) -> bool {
+        self.is_file() && !self.variables().is_empty()
+    }
+
+    /// Variable names used in the path, without the `$`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nginx_discovery::types::AccessLog;
+    ///
+    /// let log = AccessLog::new("/var/log/nginx/${host}_$status.log");
+    /// assert_eq!(log.variables(), vec!["host", "status"]);
+    /// ```
+    #[must_use]
+    pub fn variables(&self) -> Vec<String> {
+        split_variables(&self.path.to_string_lossy())
+            .into_iter()
+            .filter_map(|segment| match segment {
+                Segment::Variable(name) => Some(name.to_string()),
+                Segment::Literal(_) => None,
+            })
+            .collect()
+    }
+
+    /// Concrete file paths this log can be written to
+    ///
+    /// Non-file targets yield an empty list and a literal path is returned
+    /// as is. In a templated path, `$host`,
+    /// `$server_name` and `$http_host` are replaced with each concrete name
+    /// of the enclosing server (wildcard, regex and catch-all names are
+    /// skipped). Paths using any other variable cannot be resolved
+    /// statically and yield an empty list.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nginx_discovery::types::AccessLog;
+    ///
+    /// let log = AccessLog::new("/var/log/nginx/$host.access.log")
+    ///     .with_server_names(["example.com", "www.example.com", "_"]);
+    ///
+    /// let paths = log.expanded_paths();
+    /// assert_eq!(paths.len(), 2);
+    /// assert_eq!(paths[0].to_str(), Some("/var/log/nginx/example.com.access.log"));
+    /// ```
+    #[must_use]
+    pub fn expanded_paths(&self) -> Vec<PathBuf> {
+        if !self.is_file() {
+            return Vec::new();
+        }
+
+        let path = self.path.to_string_lossy();
+        let segments = split_variables(&path);
+
+        let resolvable = segments.iter().all(|segment| match segment {
+            Segment::Variable(name) => HOST_VARIABLES.contains(name),
+            Segment::Literal(_) => true,
+        });
+        if !resolvable {
+            return Vec::new();
+        }
+        if segments.iter().all(|s| matches!(s, Segment::Literal(_))) {
+            return vec![self.path.clone()];
+        }
+
+        let mut paths: Vec<PathBuf> = Vec::new();
+        for name in self.server_names.iter().filter(|n| is_concrete_name(n)) {
+            let expanded: String = segments
+                .iter()
+                .map(|segment| match segment {
+                    Segment::Literal(text) => *text,
+                    Segment::Variable(_) => name.trim_start_matches('.'),
+                })
+                .collect();
+            let expanded = PathBuf::from(expanded);
+            if !paths.contains(&expanded) {
+                paths.push(expanded);
+            }
+        }
+        paths
+    }
+}
+
+/// How templated log paths are treated when listing log files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogPathMode {
+    /// Return paths as written, variables included
+    #[default]
+    Literal,
+    /// Expand `$host`-style variables against the server's names and drop
+    /// paths that cannot be resolved
+    Expand,
+    /// Drop templated paths
+    Exclude,
+}
+
+/// Variables that evaluate to the requested host name
+const HOST_VARIABLES: &[&str] = &["host", "server_name", "http_host"];
+
+/// Whether a `server_name` names a single host
+fn is_concrete_name(name: &str) -> bool {
+    let name = name.trim_start_matches('.');
+    !(name.is_empty()
+        || name == "_"
+        || name.starts_with('~')
+        || name.contains('*')
+        || name.contains('$'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_variables() {
+        assert!(!AccessLog::new("/var/log/nginx/access.log").is_templated());
+        assert!(!AccessLog::new("/var/log/nginx/a$.log").is_templated());
+
+        let log = AccessLog::new("/var/log/$server_name/${host}-access.log");
+        assert!(log.is_templated());
+        assert_eq!(log.variables(), vec!["server_name", "host"]);
+    }
+
+    #[test]
+    fn test_expanded_paths() {
+        let literal = AccessLog::new("/var/log/nginx/access.log");
+        assert_eq!(literal.expanded_paths(), vec![literal.path.clone()]);
+
+        let log = AccessLog::new("/var/log/$server_name/${host}.log").with_server_names([
+            ".example.com",
+            "*.example.org",
+            "~^api",
+        ]);
+        assert_eq!(
+            log.expanded_paths(),
+            vec![PathBuf::from("/var/log/example.com/example.com.log")]
+        );
+
+        let unresolvable =
+            AccessLog::new("/var/log/nginx/$remote_addr.log").with_server_names(["example.com"]);
+        assert!(unresolvable.expanded_paths().is_empty());
+
+        // No server names to expand against
+        assert!(AccessLog::new("/var/log/$host.log")
+            .expanded_paths()
+            .is_empty());
+    }
 }
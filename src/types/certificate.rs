@@ -0,0 +1,106 @@
+//! `ssl_certificate` and `ssl_certificate_key` pairs
+//!
+//! A server can list several certificates of different key types, usually
+//! one RSA and one ECDSA certificate, and nginx hands each client the one
+//! it supports. The n-th `ssl_certificate_key` belongs to the n-th
+//! `ssl_certificate`.
+
+use std::fmt;
+
+/// Public key algorithm of a certificate or private key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum KeyAlgorithm {
+    /// RSA, including RSA-PSS keys
+    Rsa,
+    /// Elliptic curve keys (`id-ecPublicKey`)
+    Ecdsa,
+    /// Ed25519
+    Ed25519,
+    /// Ed448
+    Ed448,
+}
+
+impl fmt::Display for KeyAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Rsa => "RSA",
+            Self::Ecdsa => "ECDSA",
+            Self::Ed25519 => "Ed25519",
+            Self::Ed448 => "Ed448",
+        })
+    }
+}
+
+/// A certificate and the private key at the same position
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CertificatePair {
+    /// Path (or `data:` value) of the certificate
+    pub certificate: String,
+
+    /// Path of the key; `None` when the block lists fewer keys than
+    /// certificates
+    pub key: Option<String>,
+}
+
+impl CertificatePair {
+    /// Create a pair
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nginx_discovery::types::CertificatePair;
+    ///
+    /// let pair = CertificatePair::new("/etc/ssl/$ssl_server_name.crt")
+    ///     .with_key("/etc/ssl/$ssl_server_name.key");
+    /// assert!(pair.is_dynamic());
+    /// ```
+    #[must_use]
+    pub fn new(certificate: impl Into<String>) -> Self {
+        Self {
+            certificate: certificate.into(),
+            key: None,
+        }
+    }
+
+    /// Set the key
+    #[must_use]
+    pub fn with_key(mut self, key: impl Into<String>) -> Self {
+        self.key = Some(key.into());
+        self
+    }
+
+    /// Whether either file is only known at runtime: built from variables,
+    /// inline `data:` or an `engine:` key
+    #[must_use]
+    pub fn is_dynamic(&self) -> bool {
+        std::iter::once(&self.certificate)
+            .chain(&self.key)
+            .any(|path| is_runtime_path(path))
+    }
+}
+
+/// Whether a certificate or key parameter names something other than a
+/// file that can be read before nginx starts
+pub(crate) fn is_runtime_path(path: &str) -> bool {
+    path.contains('$') || path.starts_with("data:") || path.starts_with("engine:")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_runtime_certificate_paths() {
+        let pair = CertificatePair::new("/etc/ssl/site.crt");
+        assert_eq!(pair.key, None);
+        assert!(!pair.is_dynamic());
+        assert!(!pair.clone().with_key("/etc/ssl/site.key").is_dynamic());
+        assert!(pair.clone().with_key("engine:pkcs11:id_45").is_dynamic());
+        assert!(CertificatePair::new("data:$certificate").is_dynamic());
+        assert!(!is_runtime_path("/etc/ssl/data:cert.pem"));
+        assert_eq!(KeyAlgorithm::Ed448.to_string(), "Ed448");
+    }
+}
@@ -0,0 +1,162 @@
+//! `if` condition type
+//!
+//! ```nginx
+//! if ($http_user_agent ~* "(curl|wget)") { return 403; }
+//! if ($bad_bot) { return 444; }
+//! if (!-f $request_filename) { ... }
+//! ```
+//!
+//! A condition is a variable (false when empty or `0`), a comparison of a
+//! variable with a string (`=`, `!=`) or a regular expression (`~`, `~*`,
+//! `!~`, `!~*`), or a file test (`-f`, `-d`, `-e`, `-x`, optionally
+//! negated with `!`).
+
+/// Parsed condition of an `if` block
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IfCondition {
+    /// Variable or path tested, as written (`$http_user_agent`)
+    pub subject: String,
+
+    /// Comparison or file test operator, `None` for a bare variable
+    pub operator: Option<String>,
+
+    /// String or regular expression compared against, quotes removed
+    pub operand: Option<String>,
+}
+
+impl IfCondition {
+    /// Parse the arguments of an `if` directive, parentheses included
+    ///
+    /// Returns `None` when the arguments are not a condition nginx accepts.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nginx_discovery::types::IfCondition;
+    ///
+    /// let condition = IfCondition::from_args(&["($http_user_agent", "~*", "(curl|wget)", ")"])
+    ///     .unwrap();
+    /// assert_eq!(condition.variable(), Some("http_user_agent"));
+    /// assert!(condition.is_regex());
+    ///
+    /// let test = IfCondition::from_args(&["(!-f", "$request_filename)"]).unwrap();
+    /// assert_eq!(test.operator.as_deref(), Some("!-f"));
+    /// ```
+    #[must_use]
+    pub fn from_args<S: AsRef<str>>(args: &[S]) -> Option<Self> {
+        let text = args.iter().map(AsRef::as_ref).collect::<Vec<_>>().join(" ");
+        let inner = text.trim().strip_prefix('(')?.strip_suffix(')')?.trim();
+
+        // The operand may contain spaces, so only split off the first two
+        // words
+        let mut words = inner.splitn(2, char::is_whitespace);
+        let first = words.next()?;
+        let rest = words.next().map(str::trim_start);
+
+        if matches!(
+            first,
+            "-f" | "!-f" | "-d" | "!-d" | "-e" | "!-e" | "-x" | "!-x"
+        ) {
+            return Some(Self {
+                subject: rest?.to_string(),
+                operator: Some(first.to_string()),
+                operand: None,
+            });
+        }
+        if !first.starts_with('$') {
+            return None;
+        }
+        let Some(rest) = rest else {
+            return Some(Self {
+                subject: first.to_string(),
+                operator: None,
+                operand: None,
+            });
+        };
+        let (operator, operand) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        if !matches!(operator, "=" | "!=" | "~" | "~*" | "!~" | "!~*") {
+            return None;
+        }
+        Some(Self {
+            subject: first.to_string(),
+            operator: Some(operator.to_string()),
+            operand: Some(unquote(operand.trim()).to_string()),
+        })
+    }
+
+    /// Name of the tested variable, without the `$`; `None` for file
+    /// tests
+    #[must_use]
+    pub fn variable(&self) -> Option<&str> {
+        if self.is_file_test() {
+            return None;
+        }
+        self.subject.strip_prefix('$')
+    }
+
+    /// Whether the operand is a regular expression
+    #[must_use]
+    pub fn is_regex(&self) -> bool {
+        self.operator.as_deref().is_some_and(|op| op.contains('~'))
+    }
+
+    /// Whether the condition holds when the comparison does not match
+    /// (`!=`, `!~`, `!~*`, `!-f`, ...)
+    #[must_use]
+    pub fn is_negated(&self) -> bool {
+        self.operator
+            .as_deref()
+            .is_some_and(|op| op.starts_with('!'))
+    }
+
+    /// Whether the condition tests a file (`-f`, `-d`, `-e`, `-x`)
+    #[must_use]
+    pub fn is_file_test(&self) -> bool {
+        self.operator
+            .as_deref()
+            .is_some_and(|op| op.trim_start_matches('!').starts_with('-'))
+    }
+}
+
+fn unquote(text: &str) -> &str {
+    ['"', '\'']
+        .iter()
+        .find_map(|q| text.strip_prefix(*q).and_then(|t| t.strip_suffix(*q)))
+        .unwrap_or(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_if_condition() {
+        let bare = IfCondition::from_args(&["($bad_bot)"]).unwrap();
+        assert_eq!(bare.variable(), Some("bad_bot"));
+        assert_eq!(bare.operator, None);
+
+        let empty = IfCondition::from_args(&["($http_user_agent", "=", "", ")"]).unwrap();
+        assert_eq!(empty.operand.as_deref(), Some(""));
+        assert!(!empty.is_regex());
+
+        let regex =
+            IfCondition::from_args(&["($http_user_agent", "!~*", "^(Mozilla|Opera) x", ")"])
+                .unwrap();
+        assert_eq!(regex.operand.as_deref(), Some("^(Mozilla|Opera) x"));
+        assert!(regex.is_regex());
+        assert!(regex.is_negated());
+
+        let quoted = IfCondition::from_args(&["($host", "=", "'a b')"]).unwrap();
+        assert_eq!(quoted.operand.as_deref(), Some("a b"));
+
+        let file = IfCondition::from_args(&["(-d", "$request_filename)"]).unwrap();
+        assert!(file.is_file_test());
+        assert_eq!(file.variable(), None);
+        assert_eq!(file.subject, "$request_filename");
+
+        assert_eq!(IfCondition::from_args(&["$x"]), None);
+        assert_eq!(IfCondition::from_args(&["(foo", "=", "bar)"]), None);
+        assert_eq!(IfCondition::from_args(&["($x", "<", "1)"]), None);
+    }
+}
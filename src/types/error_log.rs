@@ -1,5 +1,5 @@
 // src/types/error_log.rs
-use crate::types::LogContext;
+use crate::types::{LogContext, LogTarget};
 use std::path::PathBuf;
 use std::str::FromStr;
 
@@ -7,8 +7,10 @@ use std::str::FromStr;
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct ErrorLog {
-    /// Path to the error log file
+    /// Path to the error log file, as written in the directive
     pub path: PathBuf,
+    /// Where the log is written (file, syslog, memory, stderr)
+    pub target: LogTarget,
     /// Log level
     pub level: ErrorLogLevel,
     /// Context where this log was defined
@@ -18,8 +20,10 @@ pub struct ErrorLog {
 impl ErrorLog {
     /// Create a new error log
     pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
         Self {
-            path: path.into(),
+            target: LogTarget::parse(&path.to_string_lossy()),
+            path,
             level: ErrorLogLevel::Error,
             context: LogContext::Main,
         }
@@ -38,6 +42,12 @@ impl ErrorLog {
         self.context = context;
         self
     }
+
+    /// Whether the log is written to a file (not syslog, memory, stderr)
+    #[must_use]
+    pub fn is_file(&self) -> bool {
+        self.target.is_file()
+    }
 }
 
 /// Error log levels
@@ -0,0 +1,142 @@
+//! `limit_except` and access rule types
+//!
+//! `limit_except GET { deny all; }` applies the `allow`, `deny` and
+//! authentication directives inside the block to every method *except* the
+//! listed ones. Allowing `GET` also allows `HEAD`.
+
+/// Whether an access rule allows or denies
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum AccessAction {
+    /// `allow`
+    Allow,
+    /// `deny`
+    Deny,
+}
+
+/// An `allow` or `deny` directive
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AccessRule {
+    /// `allow` or `deny`
+    pub action: AccessAction,
+
+    /// Address, CIDR range, `unix:` or `all`
+    pub address: String,
+}
+
+impl AccessRule {
+    /// Parse an `allow` or `deny` directive
+    ///
+    /// Returns `None` for other directives or a missing address.
+    #[must_use]
+    pub fn from_directive(name: &str, args: &[String]) -> Option<Self> {
+        let action = match name {
+            "allow" => AccessAction::Allow,
+            "deny" => AccessAction::Deny,
+            _ => return None,
+        };
+        Some(Self {
+            action,
+            address: args.first()?.clone(),
+        })
+    }
+}
+
+/// A parsed `limit_except` block
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LimitExcept {
+    /// Methods exempt from the restrictions, upper case
+    pub methods: Vec<String>,
+
+    /// `allow` and `deny` rules inside the block, in order
+    pub rules: Vec<AccessRule>,
+
+    /// Whether the block requires authentication (`auth_basic` or
+    /// `auth_request`)
+    pub auth: bool,
+}
+
+impl LimitExcept {
+    /// Create a block exempting `methods`
+    #[must_use]
+    pub fn new<S: AsRef<str>>(methods: &[S]) -> Self {
+        Self {
+            methods: methods
+                .iter()
+                .map(|m| m.as_ref().to_ascii_uppercase())
+                .collect(),
+            ..Self::default()
+        }
+    }
+
+    /// Whether `method` is exempt from the restrictions
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nginx_discovery::types::LimitExcept;
+    ///
+    /// let limit = LimitExcept::new(&["GET"]);
+    /// assert!(limit.exempts("head"));
+    /// assert!(!limit.exempts("POST"));
+    /// ```
+    #[must_use]
+    pub fn exempts(&self, method: &str) -> bool {
+        let method = method.to_ascii_uppercase();
+        self.methods.contains(&method) || (method == "HEAD" && self.exempts("GET"))
+    }
+
+    /// Whether the other methods are actually restricted: the block denies
+    /// some address or requires authentication
+    ///
+    /// An empty `limit_except GET { }` restricts nothing.
+    #[must_use]
+    pub fn is_restricted(&self) -> bool {
+        self.auth || self.rules.iter().any(|r| r.action == AccessAction::Deny)
+    }
+
+    /// Whether every other method is refused outright (`deny all;` with no
+    /// `allow` before it)
+    #[must_use]
+    pub fn denies_all(&self) -> bool {
+        self.rules
+            .first()
+            .is_some_and(|r| r.action == AccessAction::Deny && r.address == "all")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(name: &str, address: &str) -> AccessRule {
+        AccessRule::from_directive(name, &[address.to_string()]).unwrap()
+    }
+
+    #[test]
+    fn test_limit_except() {
+        let mut limit = LimitExcept::new(&["get", "POST"]);
+        assert_eq!(limit.methods, vec!["GET", "POST"]);
+        assert!(limit.exempts("HEAD"));
+        assert!(!limit.exempts("DELETE"));
+        assert!(!limit.is_restricted());
+
+        limit.rules.push(rule("allow", "10.0.0.0/8"));
+        limit.rules.push(rule("deny", "all"));
+        assert!(limit.is_restricted());
+        assert!(!limit.denies_all());
+
+        limit.rules.remove(0);
+        assert!(limit.denies_all());
+    }
+
+    #[test]
+    fn test_access_rule() {
+        assert_eq!(rule("deny", "all").action, AccessAction::Deny);
+        assert!(AccessRule::from_directive("allow", &[]).is_none());
+        assert!(AccessRule::from_directive("root", &["/".to_string()]).is_none());
+    }
+}
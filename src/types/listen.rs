@@ -2,7 +2,69 @@
 //!
 //! This module provides types for representing NGINX `listen` directives,
 //! including address, port, SSL configuration, and various options like
-//! HTTP/2, HTTP/3, `default_server`, and reuseport.
+//! HTTP/2, QUIC, `default_server`, and reuseport.
+//!
+//! [`ListenDirective::from_args`] follows nginx: a port out of range, an
+//! unknown parameter or a malformed parameter value is an error rather than
+//! a guess.
+
+use crate::ast::{parse_duration, parse_size};
+use crate::error::{Error, Result};
+use std::time::Duration;
+
+/// TCP keepalive setting of a listening socket (`so_keepalive=`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum SoKeepalive {
+    /// `so_keepalive=on`: keepalive with the system defaults
+    On,
+
+    /// `so_keepalive=off`
+    Off,
+
+    /// `so_keepalive=[keepidle]:[keepintvl]:[keepcnt]`, empty parts keep
+    /// the system default
+    Tuned {
+        /// Idle time before the first probe (`TCP_KEEPIDLE`)
+        idle: Option<Duration>,
+        /// Interval between probes (`TCP_KEEPINTVL`)
+        interval: Option<Duration>,
+        /// Number of probes (`TCP_KEEPCNT`)
+        count: Option<u32>,
+    },
+}
+
+impl SoKeepalive {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "on" => return Some(Self::On),
+            "off" => return Some(Self::Off),
+            _ => {}
+        }
+        let mut parts = value.split(':');
+        let (idle, interval, count) = (parts.next()?, parts.next()?, parts.next()?);
+        if parts.next().is_some() {
+            return None;
+        }
+        let time = |part: &str| -> Option<Option<Duration>> {
+            if part.is_empty() {
+                Some(None)
+            } else {
+                parse_duration(part).map(Some)
+            }
+        };
+        Some(Self::Tuned {
+            idle: time(idle)?,
+            interval: time(interval)?,
+            count: if count.is_empty() {
+                None
+            } else {
+                Some(count.parse().ok()?)
+            },
+        })
+    }
+}
 
 // src/types/listen.rs
 /// Represents an NGINX listen directive
@@ -22,9 +84,19 @@ pub struct ListenDirective {
     /// HTTP/2 enabled
     pub http2: bool,
 
-    /// HTTP/3 enabled (QUIC)
+    /// `http3` parameter of the nginx-quic preview branch; released nginx
+    /// (1.25.0+) uses `quic` instead
     pub http3: bool,
 
+    /// QUIC listener (`quic`): HTTP/3 over UDP
+    pub quic: bool,
+
+    /// UDP listener of a `stream` server (`udp`)
+    pub udp: bool,
+
+    /// Connections start with a PROXY protocol header (`proxy_protocol`)
+    pub proxy_protocol: bool,
+
     /// Default server
     pub default_server: bool,
 
@@ -33,6 +105,34 @@ pub struct ListenDirective {
 
     /// Backlog size
     pub backlog: Option<u32>,
+
+    /// TCP keepalive of accepted connections (`so_keepalive=`)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub so_keepalive: Option<SoKeepalive>,
+
+    /// Receive buffer size in bytes (`rcvbuf=`)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub rcvbuf: Option<u64>,
+
+    /// Send buffer size in bytes (`sndbuf=`)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub sndbuf: Option<u64>,
+
+    /// TCP Fast Open queue length (`fastopen=`)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub fastopen: Option<u32>,
+
+    /// Accept only once data arrives (`deferred`, `TCP_DEFER_ACCEPT`)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub deferred: bool,
+
+    /// Separate `bind()` for this address:port (`bind`)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub bind: bool,
+
+    /// `IPV6_V6ONLY` of a wildcard IPv6 socket (`ipv6only=on|off`)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub ipv6only: Option<bool>,
 }
 
 impl ListenDirective {
@@ -44,83 +144,163 @@ impl ListenDirective {
             ssl: false,
             http2: false,
             http3: false,
+            quic: false,
+            udp: false,
+            proxy_protocol: false,
             default_server: false,
             reuseport: false,
             backlog: None,
+            so_keepalive: None,
+            rcvbuf: None,
+            sndbuf: None,
+            fastopen: None,
+            deferred: false,
+            bind: false,
+            ipv6only: None,
         }
     }
 
     /// Parse from NGINX listen directive arguments
-    #[must_use]
-    pub fn from_args(args: &[String]) -> Option<Self> {
-        if args.is_empty() {
-            return None;
-        }
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::InvalidInput`] for a missing address, a port out
+    /// of range or not a number (`80o`), an unknown parameter, or a
+    /// parameter with an invalid value (`backlog=many`).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nginx_discovery::types::ListenDirective;
+    ///
+    /// let args = ["443".to_string(), "ssl".to_string(), "rcvbuf=64k".to_string()];
+    /// let listen = ListenDirective::from_args(&args)?;
+    /// assert_eq!(listen.rcvbuf, Some(65536));
+    ///
+    /// assert!(ListenDirective::from_args(&["80o".to_string()]).is_err());
+    /// assert!(ListenDirective::from_args(&["70000".to_string()]).is_err());
+    /// # Ok::<(), nginx_discovery::Error>(())
+    /// ```
+    pub fn from_args(args: &[String]) -> Result<Self> {
+        let Some(first) = args.first() else {
+            return Err(Error::InvalidInput(
+                "listen needs an address or port".to_string(),
+            ));
+        };
 
         // Parse first argument (address:port or just port)
-        let (address, port) = parse_listen_address(&args[0])?;
+        let (address, port) = parse_listen_address(first)?;
 
         let mut directive = Self::new(address, port);
 
         // Parse options
         for arg in &args[1..] {
-            match arg.as_str() {
-                "ssl" => directive.ssl = true,
-                "http2" => directive.http2 = true,
-                "http3" => directive.http3 = true,
-                "default_server" | "default" => directive.default_server = true,
-                "reuseport" => directive.reuseport = true,
-                _ if arg.starts_with("backlog=") => {
-                    if let Some(val) = arg.strip_prefix("backlog=") {
-                        directive.backlog = val.parse().ok();
-                    }
+            let (name, value) = match arg.split_once('=') {
+                Some((name, value)) => (name, Some(value)),
+                None => (arg.as_str(), None),
+            };
+            let invalid = || Error::InvalidInput(format!("invalid listen parameter \"{arg}\""));
+            match (name, value) {
+                ("ssl", None) => directive.ssl = true,
+                ("http2", None) => directive.http2 = true,
+                ("http3", None) => directive.http3 = true,
+                ("quic", None) => directive.quic = true,
+                ("udp", None) => directive.udp = true,
+                ("proxy_protocol", None) => directive.proxy_protocol = true,
+                ("default_server" | "default", None) => directive.default_server = true,
+                ("reuseport", None) => directive.reuseport = true,
+                ("deferred", None) => directive.deferred = true,
+                ("bind", None) => directive.bind = true,
+                // Recognized but not modelled
+                ("spdy", None) | ("setfib" | "accept_filter", Some(_)) => {}
+                ("backlog", Some(v)) => directive.backlog = Some(v.parse().map_err(|_| invalid())?),
+                ("fastopen", Some(v)) => {
+                    directive.fastopen = Some(v.parse().map_err(|_| invalid())?);
                 }
-                _ => {} // Ignore unknown options
+                ("rcvbuf", Some(v)) => directive.rcvbuf = Some(parse_size(v).ok_or_else(invalid)?),
+                ("sndbuf", Some(v)) => directive.sndbuf = Some(parse_size(v).ok_or_else(invalid)?),
+                ("ipv6only", Some("on")) => directive.ipv6only = Some(true),
+                ("ipv6only", Some("off")) => directive.ipv6only = Some(false),
+                ("so_keepalive", Some(v)) => {
+                    directive.so_keepalive = Some(SoKeepalive::parse(v).ok_or_else(invalid)?);
+                }
+                _ => return Err(invalid()),
             }
         }
 
-        Some(directive)
+        Ok(directive)
+    }
+
+    /// Whether this is a UDP listener for HTTP/3 (`quic`, or `http3` of
+    /// the preview branch)
+    #[must_use]
+    pub fn is_quic(&self) -> bool {
+        self.quic || self.http3
+    }
+
+    /// Whether the socket is UDP: a QUIC or `stream` UDP listener
+    #[must_use]
+    pub fn is_udp(&self) -> bool {
+        self.udp || self.is_quic()
+    }
+}
+
+/// Parse a port, rejecting values outside 1-65535
+fn parse_port(text: &str, addr: &str) -> Result<u16> {
+    if text.is_empty() || !text.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(Error::InvalidInput(format!(
+            "invalid port \"{text}\" in listen {addr}"
+        )));
+    }
+    match text.parse::<u32>() {
+        Ok(port @ 1..=65535) => Ok(u16::try_from(port).unwrap_or_default()),
+        _ => Err(Error::InvalidInput(format!(
+            "port {text} out of range (1-65535) in listen {addr}"
+        ))),
     }
 }
 
 /// Parse listen address and port
-#[allow(clippy::unnecessary_wraps)]
-fn parse_listen_address(addr: &str) -> Option<(String, u16)> {
+fn parse_listen_address(addr: &str) -> Result<(String, u16)> {
     // Examples:
     // "80" -> ("*", 80)
     // "0.0.0.0:80" -> ("0.0.0.0", 80)
     // "localhost:8080" -> ("localhost", 8080)
     // "[::]:80" -> ("::", 80)
+    // "unix:/run/nginx.sock" -> ("unix:/run/nginx.sock", 80)
 
-    if let Ok(port) = addr.parse::<u16>() {
-        // Just a port number
-        return Some(("*".to_string(), port));
+    if addr.starts_with("unix:") {
+        return Ok((addr.to_string(), 80));
     }
 
     // Check for [IPv6]:port format
-    if addr.starts_with('[') {
-        if let Some(bracket_end) = addr.find(']') {
-            let ipv6 = &addr[1..bracket_end];
-            let port_part = &addr[bracket_end + 1..];
-            if let Some(port_str) = port_part.strip_prefix(':') {
-                if let Ok(port) = port_str.parse() {
-                    return Some((ipv6.to_string(), port));
-                }
-            }
-        }
+    if let Some(rest) = addr.strip_prefix('[') {
+        let Some((ipv6, port_part)) = rest.split_once(']') else {
+            return Err(Error::InvalidInput(format!(
+                "missing \"]\" in listen {addr}"
+            )));
+        };
+        return match port_part.strip_prefix(':') {
+            Some(port) => Ok((ipv6.to_string(), parse_port(port, addr)?)),
+            None if port_part.is_empty() => Ok((ipv6.to_string(), 80)),
+            None => Err(Error::InvalidInput(format!(
+                "unexpected \"{port_part}\" after the address in listen {addr}"
+            ))),
+        };
+    }
+
+    // Just a port number, or digits that look like a mistyped one (`80o`)
+    if addr.starts_with(|c: char| c.is_ascii_digit()) && !addr.contains(['.', ':']) {
+        return Ok(("*".to_string(), parse_port(addr, addr)?));
     }
 
     // Check for address:port format
-    if let Some(colon_pos) = addr.rfind(':') {
-        let address = &addr[..colon_pos];
-        let port_str = &addr[colon_pos + 1..];
-        if let Ok(port) = port_str.parse() {
-            return Some((address.to_string(), port));
-        }
+    if let Some((address, port)) = addr.rsplit_once(':') {
+        return Ok((address.to_string(), parse_port(port, addr)?));
     }
 
     // Default port 80 if no port specified
-    Some((addr.to_string(), 80))
+    Ok((addr.to_string(), 80))
 }
 
 #[cfg(test)]
@@ -135,6 +315,9 @@ mod tests {
         assert!(!listen.ssl);
         assert!(!listen.http2);
         assert!(!listen.http3);
+        assert!(!listen.quic);
+        assert!(!listen.udp);
+        assert!(!listen.proxy_protocol);
         assert!(!listen.default_server);
         assert!(!listen.reuseport);
         assert_eq!(listen.backlog, None);
@@ -183,6 +366,48 @@ mod tests {
         let listen = ListenDirective::from_args(&args).unwrap();
 
         assert!(listen.http3);
+        assert!(!listen.quic);
+        assert!(listen.is_quic());
+    }
+
+    #[test]
+    fn test_from_args_with_quic() {
+        let args = vec![
+            "[::]:443".to_string(),
+            "quic".to_string(),
+            "reuseport".to_string(),
+        ];
+        let listen = ListenDirective::from_args(&args).unwrap();
+
+        assert!(listen.quic);
+        assert!(!listen.http3);
+        assert!(!listen.ssl);
+        assert!(listen.reuseport);
+        assert!(listen.is_quic());
+        assert!(listen.is_udp());
+    }
+
+    #[test]
+    fn test_from_args_with_udp() {
+        let args = vec!["53".to_string(), "udp".to_string()];
+        let listen = ListenDirective::from_args(&args).unwrap();
+
+        assert!(listen.udp);
+        assert!(listen.is_udp());
+        assert!(!listen.is_quic());
+    }
+
+    #[test]
+    fn test_from_args_with_proxy_protocol() {
+        let args = vec![
+            "443".to_string(),
+            "ssl".to_string(),
+            "proxy_protocol".to_string(),
+        ];
+        let listen = ListenDirective::from_args(&args).unwrap();
+
+        assert!(listen.ssl);
+        assert!(listen.proxy_protocol);
     }
 
     #[test]
@@ -242,7 +467,75 @@ mod tests {
         let args: Vec<String> = vec![];
         let listen = ListenDirective::from_args(&args);
 
-        assert!(listen.is_none());
+        assert!(listen.is_err());
+    }
+
+    #[test]
+    fn test_from_args_rejects_invalid() {
+        let parse = |args: &[&str]| {
+            ListenDirective::from_args(&args.iter().map(ToString::to_string).collect::<Vec<_>>())
+        };
+
+        for args in [
+            &["80o"][..],
+            &["0"],
+            &["65536"],
+            &["127.0.0.1:http"],
+            &["[::1"],
+            &["[::1]8080"],
+            &["80", "sslv3"],
+            &["80", "backlog=many"],
+            &["80", "ipv6only=yes"],
+            &["80", "so_keepalive=1:2"],
+            &["80", "ssl=on"],
+        ] {
+            assert!(parse(args).is_err(), "{args:?}");
+        }
+
+        let err = parse(&["80o"]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid input: invalid port \"80o\" in listen 80o"
+        );
+        assert!(parse(&["65535"]).is_ok());
+    }
+
+    #[test]
+    fn test_from_args_socket_options() {
+        let args: Vec<String> = [
+            "[::]:443",
+            "ssl",
+            "deferred",
+            "bind",
+            "ipv6only=on",
+            "fastopen=256",
+            "rcvbuf=64k",
+            "sndbuf=1m",
+            "so_keepalive=30m::10",
+            "setfib=1",
+        ]
+        .iter()
+        .map(ToString::to_string)
+        .collect();
+        let listen = ListenDirective::from_args(&args).unwrap();
+
+        assert!(listen.deferred);
+        assert!(listen.bind);
+        assert_eq!(listen.ipv6only, Some(true));
+        assert_eq!(listen.fastopen, Some(256));
+        assert_eq!(listen.rcvbuf, Some(64 * 1024));
+        assert_eq!(listen.sndbuf, Some(1024 * 1024));
+        assert_eq!(
+            listen.so_keepalive,
+            Some(SoKeepalive::Tuned {
+                idle: Some(Duration::from_secs(1800)),
+                interval: None,
+                count: Some(10),
+            })
+        );
+
+        let off = ListenDirective::from_args(&["80".to_string(), "so_keepalive=off".to_string()]);
+        assert_eq!(off.unwrap().so_keepalive, Some(SoKeepalive::Off));
     }
 
     #[test]
@@ -1,5 +1,5 @@
 // src/types/location.rs
-use crate::types::AccessLog;
+use crate::types::{AccessLog, LimitExcept, TryFiles};
 use std::path::PathBuf;
 /// Represents an NGINX location block
 #[derive(Debug, Clone, PartialEq)]
@@ -14,9 +14,27 @@ pub struct Location {
     /// Root directory (if specified)
     pub root: Option<PathBuf>,
 
+    /// `alias` directory (if specified)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub alias: Option<PathBuf>,
+
     /// Proxy pass upstream (if specified)
     pub proxy_pass: Option<String>,
 
+    /// `try_files` files and fallback (if specified)
+    pub try_files: Option<TryFiles>,
+
+    /// Method restrictions from `limit_except` (if specified)
+    pub limit_except: Option<LimitExcept>,
+
+    /// Whether requests need authentication (`auth_basic` or `auth_request`
+    /// in the location or inherited from the server)
+    pub requires_auth: bool,
+
+    /// Only reachable through internal redirects (`internal`)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub internal: bool,
+
     /// Access logs for this location
     pub access_logs: Vec<AccessLog>,
 }
@@ -28,7 +46,12 @@ impl Location {
             path: path.into(),
             modifier,
             root: None,
+            alias: None,
             proxy_pass: None,
+            try_files: None,
+            limit_except: None,
+            requires_auth: false,
+            internal: false,
             access_logs: Vec::new(),
         }
     }
@@ -39,10 +62,21 @@ impl Location {
         self.proxy_pass.is_some()
     }
 
+    /// Check if every HTTP method reaches this location unrestricted: no
+    /// authentication and no `limit_except` that restricts anything
+    #[must_use]
+    pub fn allows_all_methods(&self) -> bool {
+        !self.requires_auth
+            && self
+                .limit_except
+                .as_ref()
+                .map_or(true, |limit| !limit.is_restricted())
+    }
+
     /// Check if this serves static files
     #[must_use]
     pub fn is_static(&self) -> bool {
-        self.root.is_some() && self.proxy_pass.is_none()
+        (self.root.is_some() || self.alias.is_some()) && self.proxy_pass.is_none()
     }
 }
 
@@ -48,7 +48,7 @@ impl LogFormat {
 }
 
 /// Extract variable names from a log format pattern
-fn extract_variables(pattern: &str) -> Vec<String> {
+pub(crate) fn extract_variables(pattern: &str) -> Vec<String> {
     let mut variables = Vec::new();
     let mut chars = pattern.chars().peekable();
 
@@ -0,0 +1,182 @@
+//! Log destination type
+//!
+//! The first argument of `access_log` and `error_log` is not always a file:
+//! nginx also accepts `syslog:...`, `memory:SIZE` (error log only),
+//! `stderr` and `off`.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Where an `access_log` or `error_log` directive writes to
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LogTarget {
+    /// A file on disk
+    File(PathBuf),
+    /// A syslog server (`syslog:server=10.0.0.1:514,tag=nginx`)
+    Syslog {
+        /// Server address (`host[:port]` or `unix:/path`)
+        server: String,
+        /// Syslog facility (`local7` by default in nginx)
+        facility: Option<String>,
+        /// Message tag (`nginx` by default in nginx)
+        tag: Option<String>,
+        /// Severity of access log messages (`info` by default in nginx)
+        severity: Option<String>,
+    },
+    /// The cyclic memory buffer used for debugging (`memory:32m`)
+    Memory {
+        /// Buffer size as written (e.g. `32m`)
+        size: String,
+    },
+    /// The standard error stream
+    Stderr,
+    /// Logging disabled (`access_log off`)
+    Off,
+}
+
+impl LogTarget {
+    /// Classify the first argument of a log directive
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nginx_discovery::types::LogTarget;
+    ///
+    /// let target = LogTarget::parse("syslog:server=10.0.0.1:514,tag=nginx");
+    /// assert!(matches!(
+    ///     target,
+    ///     LogTarget::Syslog { ref server, ref tag, .. }
+    ///         if server == "10.0.0.1:514" && tag.as_deref() == Some("nginx")
+    /// ));
+    ///
+    /// assert!(LogTarget::parse("/var/log/nginx/access.log").is_file());
+    /// ```
+    #[must_use]
+    pub fn parse(value: &str) -> Self {
+        if let Some(params) = value.strip_prefix("syslog:") {
+            let mut server = String::new();
+            let mut facility = None;
+            let mut tag = None;
+            let mut severity = None;
+
+            for param in params.split(',') {
+                let (key, val) = param.split_once('=').unwrap_or((param, ""));
+                match key {
+                    "server" => server = val.to_string(),
+                    "facility" => facility = Some(val.to_string()),
+                    "tag" => tag = Some(val.to_string()),
+                    "severity" => severity = Some(val.to_string()),
+                    _ => {}
+                }
+            }
+
+            return Self::Syslog {
+                server,
+                facility,
+                tag,
+                severity,
+            };
+        }
+
+        if let Some(size) = value.strip_prefix("memory:") {
+            return Self::Memory {
+                size: size.to_string(),
+            };
+        }
+
+        match value {
+            "stderr" => Self::Stderr,
+            "off" => Self::Off,
+            path => Self::File(PathBuf::from(path)),
+        }
+    }
+
+    /// Whether this target is a file on disk
+    #[must_use]
+    pub fn is_file(&self) -> bool {
+        matches!(self, Self::File(_))
+    }
+
+    /// The file path, if this target is a file
+    #[must_use]
+    pub fn file_path(&self) -> Option<&Path> {
+        match self {
+            Self::File(path) => Some(path),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for LogTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::File(path) => write!(f, "{}", path.display()),
+            Self::Syslog {
+                server,
+                facility,
+                tag,
+                severity,
+            } => {
+                write!(f, "syslog:server={server}")?;
+                for (key, value) in [("facility", facility), ("tag", tag), ("severity", severity)] {
+                    if let Some(value) = value {
+                        write!(f, ",{key}={value}")?;
+                    }
+                }
+                Ok(())
+            }
+            Self::Memory { size } => write!(f, "memory:{size}"),
+            Self::Stderr => write!(f, "stderr"),
+            Self::Off => write!(f, "off"),
+        }
+    }
+}
+
+impl From<&str> for LogTarget {
+    fn from(value: &str) -> Self {
+        Self::parse(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_targets() {
+        assert_eq!(
+            LogTarget::parse("/var/log/nginx/error.log"),
+            LogTarget::File(PathBuf::from("/var/log/nginx/error.log"))
+        );
+        assert_eq!(LogTarget::parse("stderr"), LogTarget::Stderr);
+        assert_eq!(LogTarget::parse("off"), LogTarget::Off);
+        assert_eq!(
+            LogTarget::parse("memory:32m"),
+            LogTarget::Memory {
+                size: "32m".to_string()
+            }
+        );
+        assert_eq!(
+            LogTarget::parse("syslog:server=unix:/dev/log,facility=local7,nohostname"),
+            LogTarget::Syslog {
+                server: "unix:/dev/log".to_string(),
+                facility: Some("local7".to_string()),
+                tag: None,
+                severity: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        for value in [
+            "/var/log/nginx/access.log",
+            "syslog:server=10.0.0.1:514,tag=nginx",
+            "memory:32m",
+            "stderr",
+        ] {
+            assert_eq!(LogTarget::parse(value).to_string(), value);
+        }
+    }
+}
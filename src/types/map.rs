@@ -0,0 +1,195 @@
+//! `map` type
+//!
+//! ```nginx
+//! map $http_user_agent $bad_bot {
+//!     default              0;
+//!     ~*(ahrefs|semrush)   1;
+//!     ""                   1;
+//! }
+//! ```
+//!
+//! nginx compares the source string against the keys (exact strings first,
+//! then regular expressions in order) and sets the variable to the value
+//! of the first match, or to the default.
+
+use crate::route::regex::Regex;
+use crate::types::log_format::extract_variables;
+
+/// One key/value line of a `map` block
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MapEntry {
+    /// Key as written, `~` or `~*` prefix included for regular expressions
+    pub key: String,
+
+    /// Value the variable is set to
+    pub value: String,
+
+    /// Line of the entry
+    pub line: usize,
+}
+
+impl MapEntry {
+    /// Whether the key is a regular expression
+    #[must_use]
+    pub fn is_regex(&self) -> bool {
+        self.key.starts_with('~')
+    }
+
+    /// Whether the key is a case-insensitive regular expression (`~*`)
+    #[must_use]
+    pub fn is_case_insensitive(&self) -> bool {
+        self.key.starts_with("~*")
+    }
+
+    /// Key without its regular expression prefix
+    #[must_use]
+    pub fn pattern(&self) -> &str {
+        self.key
+            .strip_prefix("~*")
+            .or_else(|| self.key.strip_prefix('~'))
+            .unwrap_or(&self.key)
+    }
+}
+
+/// A `map` block
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Map {
+    /// String compared against the keys (`$http_user_agent`)
+    pub source: String,
+
+    /// Variable the block defines, without the `$`
+    pub variable: String,
+
+    /// `default` value, `None` when not set (the empty string)
+    pub default: Option<String>,
+
+    /// Whether keys are host names with wildcards (`hostnames`)
+    pub hostnames: bool,
+
+    /// Whether the variable is not cached (`volatile`)
+    pub volatile: bool,
+
+    /// Entries in configuration order, parameters and `default` excluded
+    pub entries: Vec<MapEntry>,
+
+    /// Whether the block is in the `stream` context
+    pub stream: bool,
+
+    /// Line of the `map` directive
+    pub line: usize,
+}
+
+impl Map {
+    /// Create a block without entries
+    #[must_use]
+    pub fn new(source: impl Into<String>, variable: impl Into<String>) -> Self {
+        let variable = variable.into();
+        Self {
+            source: source.into(),
+            variable: variable.trim_start_matches('$').to_string(),
+            default: None,
+            hostnames: false,
+            volatile: false,
+            entries: Vec::new(),
+            stream: false,
+            line: 0,
+        }
+    }
+
+    /// Add an entry, or set the parameter it names (`default`,
+    /// `hostnames`, `volatile`)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nginx_discovery::types::Map;
+    ///
+    /// let map = Map::new("$http_user_agent", "$bad_bot")
+    ///     .with_entry("default", "0", 2)
+    ///     .with_entry("~*curl", "1", 3);
+    ///
+    /// assert_eq!(map.default.as_deref(), Some("0"));
+    /// assert_eq!(map.entries[0].pattern(), "curl");
+    /// assert_eq!(map.value_for("curl/8.0"), "1");
+    /// ```
+    #[must_use]
+    pub fn with_entry(mut self, key: &str, value: impl Into<String>, line: usize) -> Self {
+        let value = value.into();
+        match key {
+            "default" => self.default = Some(value),
+            "hostnames" if value.is_empty() => self.hostnames = true,
+            "volatile" if value.is_empty() => self.volatile = true,
+            _ => self.entries.push(MapEntry {
+                key: key.to_string(),
+                value,
+                line,
+            }),
+        }
+        self
+    }
+
+    /// Variables the source string is built from
+    #[must_use]
+    pub fn source_variables(&self) -> Vec<String> {
+        extract_variables(&self.source)
+    }
+
+    /// Value the variable takes for `input`
+    ///
+    /// Exact keys win over regular expressions, which are tried in order;
+    /// those that cannot be evaluated never match. Host name wildcards are
+    /// not expanded.
+    #[must_use]
+    pub fn value_for(&self, input: &str) -> &str {
+        let exact = self
+            .entries
+            .iter()
+            .filter(|e| !e.is_regex())
+            .find(|e| e.key == input);
+        let regex = || {
+            self.entries.iter().filter(|e| e.is_regex()).find(|e| {
+                Regex::new(e.pattern(), e.is_case_insensitive())
+                    .is_some_and(|regex| regex.is_match(input))
+            })
+        };
+        exact
+            .or_else(regex)
+            .map(|e| e.value.as_str())
+            .or(self.default.as_deref())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map() {
+        let map = Map::new("$http_user_agent", "$bad_bot")
+            .with_entry("default", "0", 2)
+            .with_entry("volatile", "", 3)
+            .with_entry("~*(AhrefsBot|SemrushBot)", "1", 4)
+            .with_entry("~^Mozilla", "0", 5)
+            .with_entry("", "1", 6);
+
+        assert_eq!(map.variable, "bad_bot");
+        assert!(map.volatile);
+        assert!(!map.hostnames);
+        assert_eq!(map.entries.len(), 3);
+        assert!(map.entries[0].is_case_insensitive());
+        assert_eq!(map.entries[0].pattern(), "(AhrefsBot|SemrushBot)");
+        assert!(!map.entries[2].is_regex());
+        assert_eq!(map.source_variables(), vec!["http_user_agent"]);
+
+        assert_eq!(
+            map.value_for("Mozilla/5.0 (compatible; ahrefsbot/7.0)"),
+            "1"
+        );
+        assert_eq!(map.value_for("Mozilla/5.0 (X11; Linux x86_64)"), "0");
+        assert_eq!(map.value_for(""), "1");
+        assert_eq!(map.value_for("curl/8.0"), "0");
+    }
+}
@@ -1,15 +1,39 @@
 //! High-level types for NGINX configuration elements
 
 mod access_log;
+mod certificate;
+mod condition;
 mod error_log;
+mod limit_except;
 mod listen;
 mod location;
 mod log_format;
+mod log_target;
+mod map;
+mod monitoring;
 mod server;
+mod split_clients;
+mod summary;
+mod try_files;
+mod upstream;
 
-pub use access_log::{AccessLog, LogContext};
+pub use access_log::{AccessLog, LogContext, LogPathMode};
+pub(crate) use certificate::is_runtime_path;
+pub use certificate::{CertificatePair, KeyAlgorithm};
+pub use condition::IfCondition;
 pub use error_log::{ErrorLog, ErrorLogLevel};
-pub use listen::ListenDirective;
+pub use limit_except::{AccessAction, AccessRule, LimitExcept};
+pub use listen::{ListenDirective, SoKeepalive};
 pub use location::{Location, LocationModifier};
 pub use log_format::LogFormat;
+pub use log_target::LogTarget;
+pub use map::{Map, MapEntry};
+pub use monitoring::{
+    blackbox_targets, uptime_kuma_monitors, BlackboxTargetGroup, MonitoringTarget,
+    UptimeKumaImport, UptimeKumaMonitor,
+};
 pub use server::Server;
+pub use split_clients::{SplitBucket, SplitClients};
+pub use summary::{ConfigSummary, SslSummary};
+pub use try_files::{TryFiles, TryFilesFallback};
+pub use upstream::{AffinityKind, SessionAffinity, Upstream, UpstreamServer};
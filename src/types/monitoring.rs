@@ -0,0 +1,250 @@
+//! External monitoring targets derived from server blocks
+//!
+//! Each concrete `server_name` is paired with the schemes and ports its
+//! server listens on, producing URLs that can be fed to blackbox-exporter
+//! (Prometheus `file_sd` target groups) or imported into Uptime Kuma.
+
+use crate::types::Server;
+use std::collections::BTreeMap;
+
+/// A URL to probe from outside, derived from one `server_name` and one
+/// `listen` directive.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MonitoringTarget {
+    /// Host name taken from `server_name`
+    pub server_name: String,
+
+    /// `http` or `https`
+    pub scheme: String,
+
+    /// Port the server listens on
+    pub port: u16,
+
+    /// Full URL to probe (default ports are omitted)
+    pub url: String,
+}
+
+impl MonitoringTarget {
+    /// Create a target for `scheme://host:port/`
+    #[must_use]
+    pub fn new(server_name: impl Into<String>, scheme: impl Into<String>, port: u16) -> Self {
+        let server_name = server_name.into();
+        let scheme = scheme.into();
+        let default_port = matches!((scheme.as_str(), port), ("http", 80) | ("https", 443));
+        let url = if default_port {
+            format!("{scheme}://{server_name}/")
+        } else {
+            format!("{scheme}://{server_name}:{port}/")
+        };
+
+        Self {
+            server_name,
+            scheme,
+            port,
+            url,
+        }
+    }
+
+    /// Targets for every concrete name and listener of a server.
+    ///
+    /// Catch-all (`_`, empty), wildcard and regex names are skipped; a
+    /// leading-dot name (`.example.com`) yields the bare domain. Servers
+    /// without `listen` are assumed to listen on port 80, and UNIX socket
+    /// and UDP (`quic`) listeners are ignored.
+    #[must_use]
+    pub fn from_server(server: &Server) -> Vec<Self> {
+        let endpoints: Vec<(&str, u16)> = if server.listen.is_empty() {
+            vec![("http", 80)]
+        } else {
+            server
+                .listen
+                .iter()
+                .filter(|l| !l.address.starts_with("unix:") && !l.is_udp())
+                .map(|l| (if l.ssl { "https" } else { "http" }, l.port))
+                .collect()
+        };
+
+        let mut targets = Vec::new();
+        for name in server.server_names.iter().filter_map(|n| concrete_name(n)) {
+            for &(scheme, port) in &endpoints {
+                let target = Self::new(name, scheme, port);
+                if !targets.contains(&target) {
+                    targets.push(target);
+                }
+            }
+        }
+        targets
+    }
+}
+
+/// The probeable form of a `server_name`, `None` for names that do not
+/// identify a single host.
+fn concrete_name(name: &str) -> Option<&str> {
+    let name = name.strip_prefix('.').unwrap_or(name);
+    if name.is_empty()
+        || name == "_"
+        || name.starts_with('~')
+        || name.contains('*')
+        || name.contains('$')
+    {
+        None
+    } else {
+        Some(name)
+    }
+}
+
+/// A Prometheus `file_sd` target group for blackbox-exporter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BlackboxTargetGroup {
+    /// URLs to probe
+    pub targets: Vec<String>,
+
+    /// Labels attached to the probes (`module`, `server_name`, `scheme`)
+    pub labels: BTreeMap<String, String>,
+}
+
+/// Group targets into blackbox-exporter `file_sd` groups, one per target,
+/// labelled with the prober `module` to use.
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::types::{blackbox_targets, MonitoringTarget};
+///
+/// let groups = blackbox_targets(&[MonitoringTarget::new("example.com", "https", 443)], "http_2xx");
+/// assert_eq!(groups[0].targets, vec!["https://example.com/"]);
+/// assert_eq!(groups[0].labels["module"], "http_2xx");
+/// ```
+#[must_use]
+pub fn blackbox_targets(targets: &[MonitoringTarget], module: &str) -> Vec<BlackboxTargetGroup> {
+    targets
+        .iter()
+        .map(|t| BlackboxTargetGroup {
+            targets: vec![t.url.clone()],
+            labels: BTreeMap::from([
+                ("module".to_string(), module.to_string()),
+                ("server_name".to_string(), t.server_name.clone()),
+                ("scheme".to_string(), t.scheme.clone()),
+            ]),
+        })
+        .collect()
+}
+
+/// An HTTP monitor in Uptime Kuma's backup/import format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UptimeKumaMonitor {
+    /// Display name
+    pub name: String,
+
+    /// Monitor type, always `http`
+    #[cfg_attr(feature = "serde", serde(rename = "type"))]
+    pub kind: String,
+
+    /// URL to probe
+    pub url: String,
+
+    /// HTTP method
+    pub method: String,
+
+    /// Check interval in seconds
+    pub interval: u32,
+
+    /// Status codes counted as up
+    #[cfg_attr(feature = "serde", serde(rename = "accepted_statuscodes"))]
+    pub accepted_status_codes: Vec<String>,
+}
+
+/// Uptime Kuma import document (`monitorList`).
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UptimeKumaImport {
+    /// Monitors to create
+    #[cfg_attr(feature = "serde", serde(rename = "monitorList"))]
+    pub monitor_list: Vec<UptimeKumaMonitor>,
+}
+
+/// Build an Uptime Kuma import document with one HTTP monitor per target.
+#[must_use]
+pub fn uptime_kuma_monitors(targets: &[MonitoringTarget], interval: u32) -> UptimeKumaImport {
+    UptimeKumaImport {
+        monitor_list: targets
+            .iter()
+            .map(|t| UptimeKumaMonitor {
+                name: t.url.trim_end_matches('/').to_string(),
+                kind: "http".to_string(),
+                url: t.url.clone(),
+                method: "GET".to_string(),
+                interval,
+                accepted_status_codes: vec!["200-299".to_string()],
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ListenDirective;
+
+    #[test]
+    fn test_target_url() {
+        assert_eq!(
+            MonitoringTarget::new("a.example", "https", 443).url,
+            "https://a.example/"
+        );
+        assert_eq!(
+            MonitoringTarget::new("a.example", "http", 8080).url,
+            "http://a.example:8080/"
+        );
+    }
+
+    #[test]
+    fn test_from_server() {
+        let mut tls = ListenDirective::new("*", 443);
+        tls.ssl = true;
+        let mut quic = ListenDirective::new("*", 8443);
+        quic.quic = true;
+        let server = Server::new()
+            .with_server_name("example.com")
+            .with_server_name(".example.org")
+            .with_server_name("*.example.com")
+            .with_server_name("~^api\\d+")
+            .with_server_name("_")
+            .with_listen(ListenDirective::new("*", 80))
+            .with_listen(tls)
+            .with_listen(quic)
+            .with_listen(ListenDirective::new("[::]", 80));
+
+        let urls: Vec<String> = MonitoringTarget::from_server(&server)
+            .into_iter()
+            .map(|t| t.url)
+            .collect();
+        assert_eq!(
+            urls,
+            vec![
+                "http://example.com/",
+                "https://example.com/",
+                "http://example.org/",
+                "https://example.org/",
+            ]
+        );
+
+        // A QUIC-only server has nothing to probe over TCP
+        let mut quic = ListenDirective::new("*", 443);
+        quic.quic = true;
+        let server = Server::new()
+            .with_server_name("example.com")
+            .with_listen(quic);
+        assert!(MonitoringTarget::from_server(&server).is_empty());
+    }
+
+    #[test]
+    fn test_uptime_kuma() {
+        let import = uptime_kuma_monitors(&[MonitoringTarget::new("a.example", "http", 80)], 60);
+        assert_eq!(import.monitor_list[0].name, "http://a.example");
+        assert_eq!(import.monitor_list[0].interval, 60);
+    }
+}
@@ -4,7 +4,8 @@
 //! including listen directives, server names, locations, and associated logs.
 
 // src/types/server.rs
-use crate::types::{AccessLog, ErrorLog, ListenDirective, Location};
+use crate::ast::Directive;
+use crate::types::{AccessLog, CertificatePair, ErrorLog, ListenDirective, Location};
 use std::path::PathBuf;
 // ... rest of file
 
@@ -32,6 +33,10 @@ pub struct Server {
 
     /// Index files
     pub index: Vec<String>,
+
+    /// Certificate and key pairs (from `ssl_certificate` and
+    /// `ssl_certificate_key`), in order
+    pub certificates: Vec<CertificatePair>,
 }
 
 impl Default for Server {
@@ -52,6 +57,7 @@ impl Server {
             access_logs: Vec::new(),
             error_logs: Vec::new(),
             index: Vec::new(),
+            certificates: Vec::new(),
         }
     }
 
@@ -94,6 +100,16 @@ impl Server {
         self.server_names.first().map(String::as_str)
     }
 
+    /// Primary name of a `server` block without extracting it: its first
+    /// `server_name`, `_` without one or without a block
+    pub(crate) fn primary_name_of<'a>(server: impl Into<Option<&'a Directive>>) -> String {
+        server
+            .into()
+            .and_then(|server| server.find_children("server_name").first().copied())
+            .and_then(Directive::first_arg)
+            .unwrap_or_else(|| "_".to_string())
+    }
+
     /// Add an index file
     #[must_use]
     pub fn with_index(mut self, index: impl Into<String>) -> Self {
@@ -107,6 +123,13 @@ impl Server {
         self.locations.push(location);
         self
     }
+
+    /// Add a certificate and key pair
+    #[must_use]
+    pub fn with_certificate(mut self, pair: CertificatePair) -> Self {
+        self.certificates.push(pair);
+        self
+    }
 }
 
 #[cfg(test)]
@@ -124,6 +147,7 @@ mod tests {
         assert!(server.access_logs.is_empty());
         assert!(server.error_logs.is_empty());
         assert!(server.index.is_empty());
+        assert!(server.certificates.is_empty());
     }
 
     #[test]
@@ -144,6 +168,19 @@ mod tests {
         assert_eq!(server.server_names[1], "www.example.com");
     }
 
+    #[test]
+    fn test_primary_name_of() {
+        let config = crate::parse(
+            "server { server_name a.example b.example; server_name c.example; }\nserver { listen 80; }\n",
+        )
+        .unwrap();
+        let servers = config.find_directives_recursive("server");
+
+        assert_eq!(Server::primary_name_of(servers[0]), "a.example");
+        assert_eq!(Server::primary_name_of(servers[1]), "_");
+        assert_eq!(Server::primary_name_of(None), "_");
+    }
+
     #[test]
     fn test_with_listen() {
         let listen = ListenDirective::new("0.0.0.0", 80);
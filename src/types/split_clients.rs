@@ -0,0 +1,146 @@
+//! `split_clients` type
+//!
+//! ```nginx
+//! split_clients "${remote_addr}AAA" $variant {
+//!     0.5%  .one;
+//!     2.0%  .two;
+//!     *     "";
+//! }
+//! ```
+//!
+//! nginx hashes the source string with `MurmurHash2` and sets the variable to
+//! the value of the bucket the hash falls into. `*` takes whatever
+//! percentage is left.
+
+use crate::types::log_format::extract_variables;
+
+/// One bucket of a `split_clients` block
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SplitBucket {
+    /// Share of clients in percent, `None` for the `*` remainder bucket
+    pub percent: Option<f64>,
+
+    /// Value the variable is set to
+    pub value: String,
+}
+
+/// A `split_clients` block
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SplitClients {
+    /// String hashed to pick a bucket (`${remote_addr}AAA`)
+    pub source: String,
+
+    /// Variable the block defines, without the `$`
+    pub variable: String,
+
+    /// Buckets in configuration order
+    pub buckets: Vec<SplitBucket>,
+}
+
+impl SplitClients {
+    /// Create a block without buckets
+    #[must_use]
+    pub fn new(source: impl Into<String>, variable: impl Into<String>) -> Self {
+        let variable = variable.into();
+        Self {
+            source: source.into(),
+            variable: variable.trim_start_matches('$').to_string(),
+            buckets: Vec::new(),
+        }
+    }
+
+    /// Add a bucket from its key (`"12.5%"` or `"*"`) and value
+    ///
+    /// Keys that are neither a percentage nor `*` are ignored.
+    #[must_use]
+    pub fn with_bucket(mut self, key: &str, value: impl Into<String>) -> Self {
+        let percent = if key == "*" {
+            None
+        } else {
+            match key.strip_suffix('%').and_then(|p| p.parse::<f64>().ok()) {
+                Some(percent) => Some(percent),
+                None => return self,
+            }
+        };
+        self.buckets.push(SplitBucket {
+            percent,
+            value: value.into(),
+        });
+        self
+    }
+
+    /// Variables the source string is built from
+    #[must_use]
+    pub fn source_variables(&self) -> Vec<String> {
+        extract_variables(&self.source)
+    }
+
+    /// Sum of the explicit percentages
+    #[must_use]
+    pub fn total_percent(&self) -> f64 {
+        self.buckets.iter().filter_map(|b| b.percent).sum()
+    }
+
+    /// Effective share of each bucket, with `*` resolved to the remainder
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nginx_discovery::types::SplitClients;
+    ///
+    /// let split = SplitClients::new("${remote_addr}", "$variant")
+    ///     .with_bucket("10%", "a")
+    ///     .with_bucket("30%", "b")
+    ///     .with_bucket("*", "control");
+    ///
+    /// let shares: Vec<f64> = split.shares().into_iter().map(|(_, p)| p).collect();
+    /// assert_eq!(shares, vec![10.0, 30.0, 60.0]);
+    /// ```
+    #[must_use]
+    pub fn shares(&self) -> Vec<(&str, f64)> {
+        let remainder = (100.0 - self.total_percent()).max(0.0);
+        self.buckets
+            .iter()
+            .map(|b| (b.value.as_str(), b.percent.unwrap_or(remainder)))
+            .collect()
+    }
+
+    /// Whether the explicit percentages add up to more than 100, which
+    /// nginx rejects
+    #[must_use]
+    pub fn is_over_allocated(&self) -> bool {
+        self.total_percent() > 100.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_clients() {
+        let split = SplitClients::new("${remote_addr}AAA$http_user_agent", "variant")
+            .with_bucket("0.5%", ".one")
+            .with_bucket("2.0%", ".two")
+            .with_bucket("bogus", "x")
+            .with_bucket("*", "");
+
+        assert_eq!(split.variable, "variant");
+        assert_eq!(split.buckets.len(), 3);
+        assert!((split.total_percent() - 2.5).abs() < f64::EPSILON);
+        assert_eq!(split.shares()[2], ("", 97.5));
+        assert_eq!(
+            split.source_variables(),
+            vec!["remote_addr", "http_user_agent"]
+        );
+        assert!(!split.is_over_allocated());
+
+        let over = SplitClients::new("$uri", "$v")
+            .with_bucket("60%", "a")
+            .with_bucket("50%", "b");
+        assert!(over.is_over_allocated());
+        assert_eq!(over.shares()[0], ("a", 60.0));
+    }
+}
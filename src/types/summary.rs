@@ -0,0 +1,221 @@
+//! Structured configuration summary
+//!
+//! [`ConfigSummary`] holds the figures behind
+//! [`NginxDiscovery::summary`](crate::NginxDiscovery::summary) as data, so
+//! dashboards and API consumers can read them without parsing prose.
+
+use crate::ast::Config;
+use crate::extract::{self, ExtractWarning};
+use std::fmt;
+
+/// TLS figures of a configuration
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SslSummary {
+    /// Servers with at least one `ssl` listener
+    pub servers: usize,
+
+    /// `listen` directives with the `ssl` parameter
+    pub listeners: usize,
+
+    /// Distinct `ssl_certificate` paths, sorted
+    pub certificates: Vec<String>,
+
+    /// Distinct protocols named by `ssl_protocols`, sorted
+    pub protocols: Vec<String>,
+}
+
+/// Counts and key facts of a configuration
+///
+/// # Examples
+///
+/// ```
+/// use nginx_discovery::{parse, types::ConfigSummary};
+///
+/// let config = parse(r"
+/// events {}
+/// http {
+///     upstream app { server 10.0.0.1; }
+///     server {
+///         listen 443 ssl;
+///         ssl_certificate /etc/ssl/a.pem;
+///         location / { proxy_pass http://app; }
+///     }
+/// }
+/// ")?;
+/// let summary = ConfigSummary::from_config(&config);
+///
+/// assert_eq!(summary.servers, 1);
+/// assert_eq!(summary.ports, vec![443]);
+/// assert_eq!(summary.ssl.certificates, vec!["/etc/ssl/a.pem"]);
+/// assert_eq!(summary.contexts, vec!["events", "http"]);
+/// # Ok::<(), nginx_discovery::Error>(())
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConfigSummary {
+    /// Total number of directives at any depth
+    pub directives: usize,
+
+    /// `server` blocks
+    pub servers: usize,
+
+    /// `location` blocks directly inside server blocks
+    pub locations: usize,
+
+    /// `upstream` blocks
+    pub upstreams: usize,
+
+    /// `access_log` directives
+    pub access_logs: usize,
+
+    /// `log_format` directives
+    pub log_formats: usize,
+
+    /// Distinct listening ports, sorted
+    pub ports: Vec<u16>,
+
+    /// TLS figures
+    pub ssl: SslSummary,
+
+    /// Top-level blocks present (`events`, `http`, `stream`, `mail`), in
+    /// file order
+    pub contexts: Vec<String>,
+
+    /// `include` patterns, in file order. They are listed as written; the
+    /// summary covers the parsed text only.
+    pub includes: Vec<String>,
+
+    /// Directives the extractors skipped or defaulted
+    pub warnings: Vec<ExtractWarning>,
+}
+
+impl ConfigSummary {
+    /// Summarize a parsed configuration
+    #[must_use]
+    pub fn from_config(config: &Config) -> Self {
+        let servers = extract::servers(config).unwrap_or_default();
+
+        let mut ports: Vec<u16> = servers
+            .iter()
+            .flat_map(|s| s.listen.iter().map(|l| l.port))
+            .collect();
+        ports.sort_unstable();
+        ports.dedup();
+
+        let mut contexts: Vec<String> = Vec::new();
+        for directive in config.directives.iter().filter(|d| d.is_block()) {
+            if !contexts.iter().any(|c| c == directive.name()) {
+                contexts.push(directive.name().to_string());
+            }
+        }
+
+        Self {
+            directives: config.count_directives(),
+            servers: servers.len(),
+            locations: servers.iter().map(|s| s.locations.len()).sum(),
+            upstreams: extract::upstreams(config).map_or(0, |u| u.len()),
+            access_logs: config.find_directives_recursive("access_log").len(),
+            log_formats: config.find_directives_recursive("log_format").len(),
+            ports,
+            ssl: SslSummary {
+                servers: servers.iter().filter(|s| s.has_ssl()).count(),
+                listeners: servers
+                    .iter()
+                    .flat_map(|s| &s.listen)
+                    .filter(|l| l.ssl)
+                    .count(),
+                certificates: distinct_args(config, "ssl_certificate", Some(1)),
+                protocols: distinct_args(config, "ssl_protocols", None),
+            },
+            contexts,
+            includes: config
+                .find_directives_recursive("include")
+                .iter()
+                .filter_map(|d| d.first_arg())
+                .collect(),
+            warnings: extract::warnings(config),
+        }
+    }
+}
+
+/// Sorted distinct arguments of every `name` directive, the first `limit`
+/// of each
+fn distinct_args(config: &Config, name: &str, limit: Option<usize>) -> Vec<String> {
+    let mut values: Vec<String> = config
+        .find_directives_recursive(name)
+        .iter()
+        .flat_map(|d| {
+            let args = d.args_as_strings();
+            let take = limit.unwrap_or(args.len());
+            args.into_iter().take(take)
+        })
+        .collect();
+    values.sort();
+    values.dedup();
+    values
+}
+
+impl fmt::Display for ConfigSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "NGINX Configuration Summary:\n\
+            - Total directives: {}\n\
+            - Server blocks: {}\n\
+            - Access logs: {}\n\
+            - Log formats: {}",
+            self.directives, self.servers, self.access_logs, self.log_formats
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_config_summary() {
+        let config = parse(
+            r"
+include /etc/nginx/modules-enabled/*.conf;
+events {}
+http {
+    include mime.types;
+    ssl_protocols TLSv1.2 TLSv1.3;
+    upstream app { server 10.0.0.1; server 10.0.0.2; }
+    server {
+        listen 80;
+        listen 443 ssl;
+        listen [::]:443 ssl;
+        ssl_certificate /etc/ssl/a.pem;
+        ssl_protocols TLSv1.3;
+        location / { location /nested {} }
+    }
+    server { listen 80; access_log off; }
+}
+stream { server { listen 5432o; } }
+",
+        )
+        .unwrap();
+        let summary = ConfigSummary::from_config(&config);
+
+        // Upstream `server` entries are not server blocks
+        assert_eq!(summary.servers, 3);
+        assert_eq!(summary.locations, 1);
+        assert_eq!(summary.upstreams, 1);
+        assert_eq!(summary.access_logs, 1);
+        assert_eq!(summary.ports, vec![80, 443]);
+        assert_eq!(summary.ssl.servers, 1);
+        assert_eq!(summary.ssl.listeners, 2);
+        assert_eq!(summary.ssl.protocols, vec!["TLSv1.2", "TLSv1.3"]);
+        assert_eq!(summary.contexts, vec!["events", "http", "stream"]);
+        assert_eq!(
+            summary.includes,
+            vec!["/etc/nginx/modules-enabled/*.conf", "mime.types"]
+        );
+        assert_eq!(summary.warnings.len(), 1);
+        assert!(summary.to_string().contains("Server blocks: 3"));
+    }
+}
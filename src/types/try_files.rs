@@ -0,0 +1,142 @@
+//! `try_files` directive type
+//!
+//! `try_files file ... fallback;` checks the files in order and serves the
+//! first one that exists. When none does, nginx uses the last parameter: an
+//! internal redirect to a URI, a named location (`@backend`) or a status
+//! code (`=404`).
+
+use std::fmt;
+
+/// What `try_files` does when none of the files exist
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TryFilesFallback {
+    /// Internal redirect to a URI (`/index.php?$args`)
+    Uri(String),
+    /// Internal redirect to a named location, without the `@`
+    NamedLocation(String),
+    /// Return a status code (`=404`)
+    Code(u16),
+    /// A `=` parameter that is not a valid status code; nginx rejects the
+    /// configuration
+    InvalidCode(String),
+}
+
+impl TryFilesFallback {
+    /// Classify the last parameter of `try_files`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nginx_discovery::types::TryFilesFallback;
+    ///
+    /// assert_eq!(TryFilesFallback::parse("=404"), TryFilesFallback::Code(404));
+    /// assert_eq!(
+    ///     TryFilesFallback::parse("@app"),
+    ///     TryFilesFallback::NamedLocation("app".to_string())
+    /// );
+    /// ```
+    #[must_use]
+    pub fn parse(value: &str) -> Self {
+        if let Some(code) = value.strip_prefix('=') {
+            // nginx accepts any three-digit code here
+            return match code.parse::<u16>() {
+                Ok(code) if code <= 999 => Self::Code(code),
+                _ => Self::InvalidCode(value.to_string()),
+            };
+        }
+        match value.strip_prefix('@') {
+            Some(name) => Self::NamedLocation(name.to_string()),
+            None => Self::Uri(value.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for TryFilesFallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Uri(uri) | Self::InvalidCode(uri) => write!(f, "{uri}"),
+            Self::NamedLocation(name) => write!(f, "@{name}"),
+            Self::Code(code) => write!(f, "={code}"),
+        }
+    }
+}
+
+/// A parsed `try_files` directive
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TryFiles {
+    /// Files and directories (ending in `/`) checked in order
+    pub files: Vec<String>,
+
+    /// What happens when none of them exist
+    pub fallback: TryFilesFallback,
+}
+
+impl TryFiles {
+    /// Parse `try_files` arguments
+    ///
+    /// Returns `None` with fewer than two arguments, which nginx rejects.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nginx_discovery::types::{TryFiles, TryFilesFallback};
+    ///
+    /// let args: Vec<String> = ["$uri", "$uri/", "/index.php?$args"]
+    ///     .iter()
+    ///     .map(ToString::to_string)
+    ///     .collect();
+    /// let try_files = TryFiles::from_args(&args).unwrap();
+    ///
+    /// assert_eq!(try_files.files, vec!["$uri", "$uri/"]);
+    /// assert_eq!(
+    ///     try_files.fallback,
+    ///     TryFilesFallback::Uri("/index.php?$args".to_string())
+    /// );
+    /// ```
+    #[must_use]
+    pub fn from_args(args: &[String]) -> Option<Self> {
+        let (last, files) = args.split_last()?;
+        if files.is_empty() {
+            return None;
+        }
+        Some(Self {
+            files: files.to_vec(),
+            fallback: TryFilesFallback::parse(last),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fallback_parse() {
+        assert_eq!(TryFilesFallback::parse("=200"), TryFilesFallback::Code(200));
+        assert_eq!(
+            TryFilesFallback::parse("=abc"),
+            TryFilesFallback::InvalidCode("=abc".to_string())
+        );
+        assert_eq!(
+            TryFilesFallback::parse("=1000"),
+            TryFilesFallback::InvalidCode("=1000".to_string())
+        );
+        assert_eq!(
+            TryFilesFallback::parse("$uri/"),
+            TryFilesFallback::Uri("$uri/".to_string())
+        );
+        assert_eq!(TryFilesFallback::parse("@php").to_string(), "@php");
+    }
+
+    #[test]
+    fn test_from_args() {
+        assert!(TryFiles::from_args(&["$uri".to_string()]).is_none());
+        assert!(TryFiles::from_args(&[]).is_none());
+
+        let try_files = TryFiles::from_args(&["$uri".to_string(), "=404".to_string()]).unwrap();
+        assert_eq!(try_files.files, vec!["$uri"]);
+        assert_eq!(try_files.fallback, TryFilesFallback::Code(404));
+    }
+}
@@ -0,0 +1,365 @@
+//! `upstream` block type
+//!
+//! ```nginx
+//! upstream backend {
+//!     least_conn;
+//!     server 10.0.0.1:8080 weight=3 max_fails=2 fail_timeout=10s;
+//!     server 10.0.0.2:8080 backup;
+//!     keepalive 32;
+//! }
+//! ```
+
+/// A `server` entry of an upstream block
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UpstreamServer {
+    /// Address: `host[:port]` or `unix:/path`
+    pub address: String,
+
+    /// `weight=` (default 1)
+    pub weight: Option<u32>,
+
+    /// `max_fails=` (default 1)
+    pub max_fails: Option<u32>,
+
+    /// `fail_timeout=` as written (default `10s`)
+    pub fail_timeout: Option<String>,
+
+    /// Only used when the primary servers are unavailable
+    pub backup: bool,
+
+    /// Marked permanently unavailable
+    pub down: bool,
+}
+
+impl UpstreamServer {
+    /// Parse the arguments of an upstream `server` directive
+    ///
+    /// Returns `None` without an address. Unknown parameters are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nginx_discovery::types::UpstreamServer;
+    ///
+    /// let args: Vec<String> = ["10.0.0.1:8080", "weight=3", "backup"]
+    ///     .iter()
+    ///     .map(ToString::to_string)
+    ///     .collect();
+    /// let server = UpstreamServer::from_args(&args).unwrap();
+    ///
+    /// assert_eq!(server.address, "10.0.0.1:8080");
+    /// assert_eq!(server.weight, Some(3));
+    /// assert!(server.backup);
+    /// ```
+    #[must_use]
+    pub fn from_args(args: &[String]) -> Option<Self> {
+        let (address, params) = args.split_first()?;
+        let mut server = Self {
+            address: address.clone(),
+            ..Self::default()
+        };
+
+        for param in params {
+            match param.split_once('=') {
+                Some(("weight", v)) => server.weight = v.parse().ok(),
+                Some(("max_fails", v)) => server.max_fails = v.parse().ok(),
+                Some(("fail_timeout", v)) => server.fail_timeout = Some(v.to_string()),
+                None if param == "backup" => server.backup = true,
+                None if param == "down" => server.down = true,
+                _ => {}
+            }
+        }
+
+        Some(server)
+    }
+}
+
+/// Variables that identify a client or its session in a `hash` key
+const CLIENT_KEYS: &[&str] = &[
+    "$cookie_",
+    "$remote_addr",
+    "$binary_remote_addr",
+    "$http_",
+    "$arg_",
+    "$jwt_",
+];
+
+/// How an upstream pins a client to one server
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum AffinityKind {
+    /// `ip_hash`: by client address
+    IpHash,
+    /// `hash $key`, with a key taken from the client (cookie, header,
+    /// address)
+    Hash,
+    /// `sticky cookie`: nginx Plus issues a route cookie
+    StickyCookie,
+    /// `sticky route`: the route is taken from a variable
+    StickyRoute,
+    /// `sticky learn`: the session cookie of the backend is learned
+    StickyLearn,
+}
+
+/// Session affinity of an upstream
+///
+/// ```nginx
+/// upstream app {
+///     hash $cookie_session consistent;
+///     server 10.0.0.1:8080;
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SessionAffinity {
+    /// Method
+    pub kind: AffinityKind,
+
+    /// Hash key, cookie name (`sticky cookie`/`sticky learn`) or route
+    /// variables (`sticky route`)
+    pub key: Option<String>,
+
+    /// `consistent` hashing: adding or removing a server moves few clients
+    pub consistent: bool,
+}
+
+impl SessionAffinity {
+    /// Session affinity set by a load-balancing or `sticky` directive
+    ///
+    /// Returns `None` for other directives and for a `hash` whose key does
+    /// not come from the client, e.g. `hash $request_uri` (cache locality).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nginx_discovery::types::{AffinityKind, SessionAffinity};
+    ///
+    /// let args = vec!["$cookie_sid".to_string(), "consistent".to_string()];
+    /// let affinity = SessionAffinity::from_directive("hash", &args).unwrap();
+    ///
+    /// assert_eq!(affinity.kind, AffinityKind::Hash);
+    /// assert_eq!(affinity.key.as_deref(), Some("$cookie_sid"));
+    /// assert!(affinity.consistent);
+    /// assert!(SessionAffinity::from_directive("hash", &["$request_uri".to_string()]).is_none());
+    /// ```
+    #[must_use]
+    pub fn from_directive(name: &str, args: &[String]) -> Option<Self> {
+        let (kind, key) = match (name, args) {
+            ("ip_hash", _) => (AffinityKind::IpHash, None),
+            ("hash", [key, ..]) if CLIENT_KEYS.iter().any(|v| key.contains(v)) => {
+                (AffinityKind::Hash, Some(key.clone()))
+            }
+            ("sticky", [method, rest @ ..]) => {
+                let kind = match method.as_str() {
+                    "cookie" => AffinityKind::StickyCookie,
+                    "route" => AffinityKind::StickyRoute,
+                    "learn" => AffinityKind::StickyLearn,
+                    _ => return None,
+                };
+                let key = if kind == AffinityKind::StickyRoute {
+                    (!rest.is_empty()).then(|| rest.join(" "))
+                } else if kind == AffinityKind::StickyLearn {
+                    rest.iter()
+                        .find_map(|a| a.strip_prefix("lookup="))
+                        .map(ToString::to_string)
+                } else {
+                    rest.first().cloned()
+                };
+                (kind, key)
+            }
+            _ => return None,
+        };
+        Some(Self {
+            kind,
+            key,
+            consistent: name == "hash" && args.iter().any(|a| a == "consistent"),
+        })
+    }
+
+    /// Variable the affinity is derived from, e.g. `$cookie_sid` for
+    /// `hash $cookie_sid` or `sticky cookie sid`
+    #[must_use]
+    pub fn variable(&self) -> Option<String> {
+        match self.kind {
+            AffinityKind::IpHash => Some("$remote_addr".to_string()),
+            AffinityKind::StickyCookie => self.key.as_ref().map(|c| format!("$cookie_{c}")),
+            AffinityKind::Hash | AffinityKind::StickyRoute | AffinityKind::StickyLearn => {
+                self.key.clone()
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for SessionAffinity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let method = match self.kind {
+            AffinityKind::IpHash => return f.write_str("ip_hash"),
+            AffinityKind::Hash => "hash",
+            AffinityKind::StickyCookie => "sticky cookie",
+            AffinityKind::StickyRoute => "sticky route",
+            AffinityKind::StickyLearn => "sticky learn",
+        };
+        f.write_str(method)?;
+        if let Some(key) = &self.key {
+            write!(f, " {key}")?;
+        }
+        if self.consistent {
+            f.write_str(" consistent")?;
+        }
+        Ok(())
+    }
+}
+
+/// An `upstream` block
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Upstream {
+    /// Upstream name, as used in `proxy_pass http://name`
+    pub name: String,
+
+    /// Backend servers in configuration order
+    pub servers: Vec<UpstreamServer>,
+
+    /// Load-balancing method (`least_conn`, `ip_hash`, `hash $key`, ...),
+    /// `None` for round robin
+    pub method: Option<String>,
+
+    /// Idle connections kept open per worker (`keepalive`)
+    pub keepalive: Option<u32>,
+
+    /// `keepalive_timeout` as written
+    pub keepalive_timeout: Option<String>,
+
+    /// `keepalive_requests`
+    pub keepalive_requests: Option<u32>,
+
+    /// Session affinity (`ip_hash`, `hash` on a client key, `sticky`)
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub affinity: Option<SessionAffinity>,
+
+    /// Whether the block is inside `stream` rather than `http`
+    pub stream: bool,
+
+    /// Line of the `upstream` directive
+    pub line: usize,
+}
+
+impl Upstream {
+    /// Create an empty upstream
+    #[must_use]
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            servers: Vec::new(),
+            method: None,
+            keepalive: None,
+            keepalive_timeout: None,
+            keepalive_requests: None,
+            affinity: None,
+            stream: false,
+            line: 0,
+        }
+    }
+
+    /// Servers that take traffic while all of them are up: not `backup`,
+    /// not `down`
+    pub fn primary_servers(&self) -> impl Iterator<Item = &UpstreamServer> {
+        self.servers.iter().filter(|s| !s.backup && !s.down)
+    }
+
+    /// Whether idle connections to the servers are kept for reuse
+    #[must_use]
+    pub fn has_keepalive(&self) -> bool {
+        self.keepalive.is_some_and(|n| n > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn test_upstream_server() {
+        let server = UpstreamServer::from_args(&args(
+            "unix:/run/app.sock max_fails=3 fail_timeout=30s down",
+        ))
+        .unwrap();
+        assert_eq!(server.address, "unix:/run/app.sock");
+        assert_eq!(server.max_fails, Some(3));
+        assert_eq!(server.fail_timeout.as_deref(), Some("30s"));
+        assert!(server.down);
+        assert_eq!(server.weight, None);
+        assert!(UpstreamServer::from_args(&[]).is_none());
+    }
+
+    #[test]
+    fn test_upstream() {
+        let mut upstream = Upstream::new("backend");
+        assert!(!upstream.has_keepalive());
+
+        upstream
+            .servers
+            .push(UpstreamServer::from_args(&args("a:80")).unwrap());
+        upstream
+            .servers
+            .push(UpstreamServer::from_args(&args("b:80 backup")).unwrap());
+        upstream.keepalive = Some(16);
+
+        assert!(upstream.has_keepalive());
+        assert_eq!(upstream.primary_servers().count(), 1);
+    }
+
+    #[test]
+    fn test_session_affinity_edge_cases() {
+        let parse = |name: &str, line: &str| SessionAffinity::from_directive(name, &args(line));
+
+        // Keys that do not identify the client, methods without affinity
+        assert!(parse("hash", "").is_none());
+        assert!(parse("hash", "$request_uri consistent").is_none());
+        assert!(parse("least_conn", "").is_none());
+        assert!(parse("sticky", "").is_none());
+        assert!(parse("sticky", "unknown srv_id").is_none());
+
+        // `consistent` only applies to `hash`
+        let ip_hash = parse("ip_hash", "consistent").unwrap();
+        assert!(!ip_hash.consistent);
+        assert_eq!(ip_hash.variable().as_deref(), Some("$remote_addr"));
+        assert_eq!(ip_hash.to_string(), "ip_hash");
+
+        let hash = parse("hash", "$binary_remote_addr$http_x_tenant").unwrap();
+        assert!(!hash.consistent);
+        assert_eq!(hash.to_string(), "hash $binary_remote_addr$http_x_tenant");
+
+        let route = parse("sticky", "route $route_cookie $route_uri").unwrap();
+        assert_eq!(route.kind, AffinityKind::StickyRoute);
+        assert_eq!(
+            route.variable().as_deref(),
+            Some("$route_cookie $route_uri")
+        );
+        assert_eq!(parse("sticky", "route").unwrap().key, None);
+
+        let learn = parse(
+            "sticky",
+            "learn create=$upstream_cookie_sid lookup=$cookie_sid zone=client_sessions:1m",
+        )
+        .unwrap();
+        assert_eq!(learn.variable().as_deref(), Some("$cookie_sid"));
+        assert_eq!(
+            parse("sticky", "learn create=$upstream_cookie_sid")
+                .unwrap()
+                .key,
+            None
+        );
+
+        // A `sticky cookie` without a name has no variable to key a cache on
+        let cookie = parse("sticky", "cookie").unwrap();
+        assert_eq!(cookie.variable(), None);
+        assert_eq!(cookie.to_string(), "sticky cookie");
+    }
+}
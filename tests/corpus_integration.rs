@@ -0,0 +1,267 @@
+//! Golden corpus and round-trip tests
+//!
+//! The files under `tests/fixtures/corpus` are real-world configurations:
+//! the nginx.org default, the Debian/Ubuntu package default, a site after
+//! `certbot --nginx`, an ingress-nginx generated configuration and OpenResty
+//! samples. Each must parse, render back to text and parse again to the
+//! same AST, as must random ASTs generated with `proptest`.
+
+use nginx_discovery::ast::{Config, Directive, DirectiveItem, Span, Value};
+use nginx_discovery::{parse, NginxDiscovery};
+use proptest::prelude::*;
+use std::path::PathBuf;
+
+const CORPUS: &[&str] = &[
+    "nginx-default.conf",
+    "debian-nginx.conf",
+    "certbot.conf",
+    "ingress-nginx.conf",
+    "openresty.conf",
+];
+
+fn corpus(name: &str) -> String {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests/fixtures/corpus", name]
+        .iter()
+        .collect();
+    std::fs::read_to_string(path).unwrap()
+}
+
+/// Reset every span, so that ASTs parsed from different text compare equal
+fn without_spans(config: &Config) -> Config {
+    fn strip(directive: &Directive) -> Directive {
+        let item = match &directive.item {
+            DirectiveItem::Simple { name, args } => DirectiveItem::Simple {
+                name: name.clone(),
+                args: args.clone(),
+            },
+            DirectiveItem::Block {
+                name,
+                args,
+                children,
+            } => DirectiveItem::Block {
+                name: name.clone(),
+                args: args.clone(),
+                children: children.iter().map(strip).collect(),
+            },
+        };
+        Directive {
+            item,
+            span: Span::default(),
+        }
+    }
+    Config::with_directives(config.directives.iter().map(strip).collect())
+}
+
+fn assert_round_trip(config: &Config) {
+    let rendered = config.to_config_string();
+    let reparsed = parse(&rendered)
+        .unwrap_or_else(|e| panic!("rendered config does not parse: {e}\n{rendered}"));
+    assert_eq!(
+        without_spans(&reparsed),
+        without_spans(config),
+        "round trip changed the AST:\n{rendered}"
+    );
+    assert_eq!(reparsed.to_config_string(), rendered);
+}
+
+#[test]
+fn test_corpus_round_trip() {
+    for name in CORPUS {
+        let config = parse(&corpus(name)).unwrap_or_else(|e| panic!("{name}: {e}"));
+        assert!(!config.is_empty(), "{name}");
+        assert_round_trip(&config);
+    }
+}
+
+#[test]
+fn test_corpus_nginx_default() {
+    let discovery = NginxDiscovery::from_config_text(&corpus("nginx-default.conf")).unwrap();
+    let config = discovery.config();
+
+    assert_eq!(config.count_directives(), 17);
+    assert_eq!(discovery.server_names(), vec!["localhost"]);
+    assert_eq!(discovery.listening_ports(), vec![80]);
+    assert!(discovery.access_logs().is_empty());
+}
+
+#[test]
+fn test_corpus_debian() {
+    let discovery = NginxDiscovery::from_config_text(&corpus("debian-nginx.conf")).unwrap();
+    let config = discovery.config();
+
+    assert_eq!(config.find_directives("include").len(), 1);
+    assert_eq!(config.find_directives_recursive("include").len(), 3);
+    assert_eq!(discovery.server_names(), vec!["_"]);
+    assert_eq!(discovery.listening_ports(), vec![80]);
+
+    let try_files = config.find_directives_recursive("try_files");
+    let args: Vec<String> = try_files[0].args().iter().map(Value::unquoted).collect();
+    assert_eq!(args, vec!["$uri", "$uri/", "=404"]);
+    let logs = discovery.access_logs();
+    assert_eq!(logs.len(), 1);
+    assert_eq!(logs[0].path.to_str(), Some("/var/log/nginx/access.log"));
+}
+
+#[test]
+fn test_corpus_certbot() {
+    let discovery = NginxDiscovery::from_config_text(&corpus("certbot.conf")).unwrap();
+    let config = discovery.config();
+
+    assert_eq!(discovery.servers().len(), 2);
+    assert_eq!(discovery.listening_ports(), vec![80, 443]);
+    assert_eq!(config.find_directives_recursive("if").len(), 2);
+    assert_eq!(
+        config.find_directives_recursive("ssl_certificate")[0].args_as_strings(),
+        vec!["/etc/letsencrypt/live/example.com/fullchain.pem"]
+    );
+}
+
+#[test]
+fn test_corpus_ingress_nginx() {
+    let config = parse(&corpus("ingress-nginx.conf")).unwrap();
+
+    let maps = config.find_directives_recursive("map");
+    assert_eq!(maps.len(), 3);
+    // `'' '';` maps the empty string to the empty string
+    let upgrade = maps[1].children().unwrap();
+    assert_eq!(upgrade[1].name(), "");
+    assert_eq!(upgrade[1].args(), &[Value::single_quoted("")]);
+
+    let lua = config.find_directives_recursive("log_by_lua_block");
+    assert_eq!(lua.len(), 1);
+    assert!(lua[0].args()[0].as_str().contains("monitor.call()"));
+
+    let log_format = &config.find_directives_recursive("log_format")[0];
+    assert_eq!(log_format.args().len(), 2);
+    assert_eq!(config.find_directives("stream").len(), 1);
+}
+
+#[test]
+fn test_corpus_openresty() {
+    let config = parse(&corpus("openresty.conf")).unwrap();
+
+    let content = config.find_directives_recursive("content_by_lua_block");
+    assert_eq!(content.len(), 3);
+    let lookup = content[1].args()[0].as_str();
+    assert!(lookup.contains(r#"note = "}""#));
+    assert!(lookup.contains("[[ { not a block ]]"));
+
+    let set = &config.find_directives_recursive("set_by_lua_block")[0];
+    assert_eq!(set.args()[0], Value::variable("key"));
+    assert_eq!(
+        set.args()[1].as_str(),
+        r#"return ngx.var.arg_key or "default""#
+    );
+}
+
+const NAMES: &[&str] = &[
+    "listen",
+    "server_name",
+    "root",
+    "proxy_pass",
+    "add_header",
+    "return",
+    "rewrite",
+    "set",
+    "log_format",
+    "try_files",
+];
+const BLOCKS: &[&str] = &["http", "server", "location", "upstream", "if", "map"];
+const WORDS: &[&str] = &[
+    "on",
+    "off",
+    "80",
+    "443",
+    "ssl",
+    "/var/www/html",
+    "http://127.0.0.1:8080",
+    "[::]:443",
+    "~*",
+    "\\.php$",
+    "^/(.*)$",
+    "=404",
+    "10m",
+    "unix:/run/app.sock",
+];
+const QUOTED: &[&str] = &[
+    "",
+    "hello world",
+    "a;b",
+    "{ braces }",
+    "it's",
+    "say \"hi\"",
+    "both \\' and \"",
+    "$remote_addr - $request",
+    "# not a comment",
+    "back\\slash",
+];
+const VARIABLES: &[&str] = &["host", "uri", "remote_addr", "http_user_agent"];
+
+fn value() -> impl Strategy<Value = Value> {
+    let quoted = || prop::sample::select(QUOTED);
+    prop_oneof![
+        1 => quoted().prop_map(Value::single_quoted),
+        1 => quoted().prop_map(Value::double_quoted),
+        1 => prop::sample::select(VARIABLES).prop_map(Value::variable),
+        // Unquoted text with spaces or separators must be quoted on output
+        1 => quoted().prop_map(Value::literal),
+        2 => prop::sample::select(WORDS).prop_map(Value::literal),
+    ]
+}
+
+fn args() -> impl Strategy<Value = Vec<Value>> {
+    prop::collection::vec(value(), 0..4)
+}
+
+/// Directives nested up to three blocks deep
+fn directive() -> impl Strategy<Value = Directive> {
+    let simple = (prop::sample::select(NAMES), args())
+        .prop_map(|(name, args)| Directive::simple_with_values(name, args));
+    simple.prop_recursive(3, 64, 4, |children| {
+        (
+            prop::sample::select(BLOCKS),
+            args(),
+            prop::collection::vec(children, 0..4),
+        )
+            .prop_map(|(name, args, children)| Directive::block_with_values(name, args, children))
+    })
+}
+
+fn config() -> impl Strategy<Value = Config> {
+    prop::collection::vec(directive(), 1..=5).prop_map(Config::with_directives)
+}
+
+/// Parse and compare what the parser would produce for a value, since
+/// rendering may legitimately change how a value is spelled (a literal
+/// with spaces becomes a quoted string)
+fn normalized(config: &Config) -> Config {
+    let rendered = config.to_config_string();
+    parse(&rendered).unwrap_or_else(|e| panic!("rendered config does not parse: {e}\n{rendered}"))
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(500))]
+
+    #[test]
+    fn test_random_round_trip(config in config()) {
+        let once = normalized(&config);
+        prop_assert_eq!(
+            once.count_directives(),
+            config.count_directives(),
+            "{:?}",
+            config
+        );
+        assert_round_trip(&once);
+    }
+
+    #[test]
+    fn test_random_values_keep_their_text(value in value()) {
+        let config = Config::with_directives(vec![Directive::simple_with_values(
+            "set",
+            vec![Value::variable("x"), value.clone()],
+        )]);
+        let reparsed = normalized(&config);
+        let arg = &reparsed.directives[0].args()[1];
+        prop_assert_eq!(arg.as_str(), value.as_str(), "{:?} became {:?}", value, arg);
+    }
+}
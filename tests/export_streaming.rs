@@ -0,0 +1,211 @@
+//! Memory use of exporting a large configuration
+//!
+//! Exporters stream into the writer they are given instead of building the
+//! whole document first, also with source annotations and in deterministic
+//! mode. A counting allocator checks that exporting a synthetic 100 MB
+//! configuration as JSON, YAML or Markdown allocates little beyond the
+//! configuration itself. The Markdown report leaves argument values out,
+//! so its configuration is large in location paths instead.
+
+#![cfg(feature = "serde")]
+
+use nginx_discovery::ast::{Config, Directive};
+use nginx_discovery::export::{export, ExportFormat, ExportOptions};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+struct Counting;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let current = CURRENT.fetch_add(layout.size(), Ordering::SeqCst) + layout.size();
+            PEAK.fetch_max(current, Ordering::SeqCst);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        CURRENT.fetch_sub(layout.size(), Ordering::SeqCst);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// Discards output, counting the bytes
+struct Sink(usize);
+
+impl Write for Sink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// About 100 MB of directive arguments in 1000 server blocks
+fn large_config() -> Config {
+    let value = "x".repeat(100_000);
+    let servers = (0..1000)
+        .map(|i| {
+            Directive::block(
+                "server",
+                vec![],
+                vec![
+                    Directive::simple("server_name", vec![format!("site{i}.example.com")]),
+                    Directive::simple("set", vec!["$blob".to_string(), value.clone()]),
+                ],
+            )
+        })
+        .collect();
+    Config::with_directives(vec![Directive::block("http", vec![], servers)])
+}
+
+/// Peak bytes allocated while exporting, beyond what was allocated before,
+/// and the bytes written
+fn measure(config: &Config, options: &ExportOptions) -> (usize, usize) {
+    let mut sink = Sink(0);
+    let before = CURRENT.load(Ordering::SeqCst);
+    PEAK.store(before, Ordering::SeqCst);
+    export(config, &mut sink, options).unwrap();
+    (PEAK.load(Ordering::SeqCst) - before, sink.0)
+}
+
+#[test]
+fn test_export_streams_large_config() {
+    let config = large_config();
+
+    for format in [ExportFormat::Json, ExportFormat::Yaml] {
+        // Default, source-annotated and deterministic output
+        for (include_source, deterministic) in [(false, false), (true, false), (false, true)] {
+            let options = ExportOptions::builder()
+                .format(format)
+                .pretty(false)
+                .include_source(include_source)
+                .source_file("/etc/nginx/nginx.conf")
+                .deterministic(deterministic)
+                .build();
+            let mode =
+                format!("{format} (source: {include_source}, deterministic: {deterministic})");
+
+            let (extra, written) = measure(&config, &options);
+
+            assert!(written > 100_000_000, "{mode}: wrote {written} bytes");
+            assert!(
+                extra < 8 * 1024 * 1024,
+                "{mode}: export allocated {extra} bytes"
+            );
+        }
+    }
+}
+
+/// About 100 MB of location paths in 1000 server blocks, which the
+/// Markdown report lists with their source lines
+#[cfg(feature = "export-markdown")]
+fn large_locations_config() -> Config {
+    let path = format!("/{}", "x".repeat(100_000));
+    let servers = (0..1000)
+        .map(|i| {
+            Directive::block(
+                "server",
+                vec![],
+                vec![
+                    Directive::simple("server_name", vec![format!("site{i}.example.com")]),
+                    Directive::block("location", vec![path.clone()], vec![]),
+                ],
+            )
+        })
+        .collect();
+    Config::with_directives(vec![Directive::block("http", vec![], servers)])
+}
+
+#[cfg(feature = "export-markdown")]
+#[test]
+fn test_markdown_export_streams_large_config() {
+    let config = large_locations_config();
+
+    for deterministic in [false, true] {
+        let options = ExportOptions::builder()
+            .format(ExportFormat::Markdown)
+            .include_source(true)
+            .source_file("/etc/nginx/nginx.conf")
+            .deterministic(deterministic)
+            .build();
+        let mode = format!("markdown (deterministic: {deterministic})");
+
+        let (extra, written) = measure(&config, &options);
+
+        assert!(written > 100_000_000, "{mode}: wrote {written} bytes");
+        assert!(
+            extra < 8 * 1024 * 1024,
+            "{mode}: export allocated {extra} bytes"
+        );
+    }
+}
+
+/// Accepts `limit` bytes, then fails every write
+struct Failing {
+    limit: usize,
+    written: usize,
+}
+
+impl Write for Failing {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.written + buf.len() > self.limit {
+            return Err(io::Error::new(io::ErrorKind::BrokenPipe, "pipe closed"));
+        }
+        self.written += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_failing_writer_is_an_io_error() {
+    let config = large_config();
+    #[allow(unused_mut)]
+    let mut formats = vec![ExportFormat::Json, ExportFormat::Yaml];
+    #[cfg(feature = "export-toml")]
+    formats.push(ExportFormat::Toml);
+    #[cfg(feature = "export-markdown")]
+    formats.push(ExportFormat::Markdown);
+    #[cfg(feature = "export-xml")]
+    formats.push(ExportFormat::Xml);
+    #[cfg(feature = "export-binary")]
+    formats.extend([ExportFormat::MessagePack, ExportFormat::Cbor]);
+
+    for format in formats {
+        for deterministic in [false, true] {
+            let options = ExportOptions::builder()
+                .format(format)
+                .include_source(deterministic)
+                .deterministic(deterministic)
+                .build();
+            let mut writer = Failing {
+                limit: 1000,
+                written: 0,
+            };
+            let err = export(&config, &mut writer, &options).unwrap_err();
+
+            // The export stops at the first failed write
+            assert!(
+                matches!(&err, nginx_discovery::Error::Io(e) if e.kind() == io::ErrorKind::BrokenPipe),
+                "{format}: {err:?}"
+            );
+            assert!(writer.written <= 1000, "{format}");
+        }
+    }
+}
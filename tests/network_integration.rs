@@ -7,11 +7,13 @@
 
 use nginx_discovery::{
     network::{
-        check_all, check_port, check_ssl_certificate, resolve_hostname, HealthStatus,
-        NetworkCheckOptions,
+        check_all, check_host_routing, check_port, check_ssl_certificate, check_udp_port,
+        resolve_hostname, HealthStatus, HostRoutingOptions, NetworkCheckOptions, UdpProbe,
     },
     parse,
 };
+use std::io::{Read, Write};
+use std::net::TcpListener;
 use std::path::Path;
 
 const SAMPLE_CONFIG: &str = r#"
@@ -119,6 +121,72 @@ async fn test_check_all_upstream_indirectly() {
     // We don't assert success/failure — only that upstreams were evaluated
 }
 
+/// Serve every request from the same "server block", like nginx does when
+/// all names fall through to `default_server`.
+fn spawn_fixed_vhost_server(served_by: &'static str) -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind failed");
+    let port = listener.local_addr().unwrap().port();
+
+    std::thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            let mut buf = [0u8; 4096];
+            let _ = stream.read(&mut buf);
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nX-Served-By: {served_by}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+            );
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+
+    port
+}
+
+#[tokio::test]
+async fn test_check_host_routing_detects_misrouting() {
+    let port = spawn_fixed_vhost_server("a.example");
+    let config = parse(&format!(
+        r"
+        server {{ listen {port}; server_name a.example; }}
+        server {{ listen {port}; server_name b.example; add_header X-Served-By b.example; }}
+        "
+    ))
+    .expect("config parse failed");
+
+    let options = NetworkCheckOptions {
+        host_routing: HostRoutingOptions::default().with_marker_header("X-Served-By"),
+        ..Default::default()
+    };
+
+    let checks = check_host_routing(&config, &options).await.unwrap();
+    assert_eq!(checks.len(), 2);
+    assert!(checks.iter().all(|c| c.check_type == "host_routing"));
+    assert_eq!(checks[0].status, HealthStatus::Healthy);
+    assert_eq!(checks[1].status, HealthStatus::Unhealthy);
+    assert!(checks[1].message.contains("a.example"));
+}
+
+#[tokio::test]
+async fn test_check_host_routing_without_marker_is_degraded() {
+    let port = spawn_fixed_vhost_server("a.example");
+    let config = parse(&format!(
+        "server {{ listen {port}; server_name a.example; }}"
+    ))
+    .expect("config parse failed");
+
+    let options = NetworkCheckOptions {
+        check_upstreams: false,
+        check_ports: false,
+        check_ssl: false,
+        check_dns: false,
+        check_host_routing: true,
+        ..Default::default()
+    };
+
+    let checks = check_all(&config, options).await.unwrap();
+    assert_eq!(checks.len(), 1);
+    assert_eq!(checks[0].status, HealthStatus::Degraded);
+}
+
 #[test]
 fn test_health_status_display() {
     assert_eq!(HealthStatus::Healthy.to_string(), "HEALTHY");
@@ -126,3 +194,30 @@ fn test_health_status_display() {
     assert_eq!(HealthStatus::Degraded.to_string(), "DEGRADED");
     assert_eq!(HealthStatus::Error.to_string(), "ERROR");
 }
+
+#[tokio::test]
+async fn test_check_udp_port_bound_locally() {
+    let socket = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+    let port = socket.local_addr().unwrap().port();
+
+    let check = check_udp_port("127.0.0.1", port, UdpProbe::Quic)
+        .await
+        .unwrap();
+    assert_eq!(check.status, HealthStatus::Healthy);
+    assert!(check.is_listening);
+
+    drop(socket);
+    let check = check_udp_port("127.0.0.1", port, UdpProbe::Empty)
+        .await
+        .unwrap();
+    assert_eq!(check.status, HealthStatus::Unhealthy);
+    assert!(!check.is_listening);
+}
+
+#[tokio::test]
+async fn test_check_udp_port_unix_socket() {
+    let check = check_udp_port("unix:/run/dns.sock", 80, UdpProbe::Empty)
+        .await
+        .unwrap();
+    assert_eq!(check.status, HealthStatus::NotApplicable);
+}
@@ -284,7 +284,7 @@ fn test_complex_server_config() {
     // Test second server
     assert_eq!(servers[1].server_names[0], "secure.example.com");
     assert!(servers[1].has_ssl());
-    assert_eq!(servers[1].listen[0].http2, true);
+    assert!(servers[1].listen[0].http2);
     assert_eq!(servers[1].locations.len(), 2);
 
     // Test SSL servers